@@ -0,0 +1,312 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Authentication of the users of the pgwire and HTTP servers.
+//!
+//! Both servers delegate the decision of who a client is to an
+//! [`Authenticator`], which is selected when the process starts. This crate
+//! provides authenticators that trust clients, that check passwords against a
+//! password file or against the passwords of roles in the catalog, and that
+//! validate Frontegg API tokens and JWTs. Deployments that authenticate users
+//! some other way can provide their own implementation of the trait.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+use openssl::memcmp;
+use openssl::sha::sha256;
+
+use mz_frontegg_auth::FronteggAuthentication;
+
+pub mod scram;
+
+/// The credentials that a client presents to authenticate.
+#[derive(Clone)]
+pub enum Credentials {
+    /// No credentials.
+    None,
+    /// A password, as presented in a pgwire password message or in HTTP basic
+    /// authentication.
+    Password(String),
+    /// A token, as presented in HTTP bearer authentication.
+    Token(String),
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Credentials::None => f.write_str("None"),
+            Credentials::Password(_) => f.write_str("Password(<redacted>)"),
+            Credentials::Token(_) => f.write_str("Token(<redacted>)"),
+        }
+    }
+}
+
+/// A successfully authenticated client.
+pub struct Authenticated {
+    /// The user as whom the client is authenticated.
+    pub user: String,
+    /// A future that resolves when the authentication expires, if it can
+    /// expire.
+    ///
+    /// Servers close a client's connection when the future resolves.
+    pub expired: Option<BoxFuture<'static, ()>>,
+}
+
+impl fmt::Debug for Authenticated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Authenticated")
+            .field("user", &self.user)
+            .field("expires", &self.expired.is_some())
+            .finish()
+    }
+}
+
+/// Authenticates the clients of the pgwire and HTTP servers.
+///
+/// A single authenticator is shared by every connection to both servers.
+#[async_trait]
+pub trait Authenticator: fmt::Debug + Send + Sync {
+    /// Reports whether clients must present credentials to authenticate.
+    ///
+    /// If so, the pgwire server asks clients for a password before
+    /// authenticating them. If not, clients are authenticated without
+    /// credentials.
+    fn requires_credentials(&self) -> bool;
+
+    /// Reports whether users that authenticate successfully, but that do not
+    /// yet exist, are created when they first connect.
+    fn creates_users(&self) -> bool {
+        false
+    }
+
+    /// Reports whether clients authenticate with the passwords of their roles
+    /// in the catalog.
+    ///
+    /// If so, the servers check those passwords themselves, with
+    /// SCRAM-SHA-256 in the case of the pgwire server, as only they can look
+    /// up the roles. `authenticate` is not called.
+    fn checks_role_passwords(&self) -> bool {
+        false
+    }
+
+    /// Authenticates a client that presents `credentials`.
+    ///
+    /// `user` is the user that the client claims to be, if it named one. If
+    /// present, the returned user must be `user`.
+    async fn authenticate(
+        &self,
+        user: Option<&str>,
+        credentials: Credentials,
+    ) -> Result<Authenticated, anyhow::Error>;
+}
+
+/// An authenticator that trusts that clients are the user they claim to be.
+#[derive(Debug, Clone, Default)]
+pub struct TrustAuthenticator;
+
+#[async_trait]
+impl Authenticator for TrustAuthenticator {
+    fn requires_credentials(&self) -> bool {
+        false
+    }
+
+    async fn authenticate(
+        &self,
+        user: Option<&str>,
+        _: Credentials,
+    ) -> Result<Authenticated, anyhow::Error> {
+        match user {
+            Some(user) => Ok(Authenticated {
+                user: user.into(),
+                expired: None,
+            }),
+            None => bail!("no user specified"),
+        }
+    }
+}
+
+/// An authenticator that checks the passwords that clients present against a
+/// fixed set of users.
+///
+/// Only the SHA-256 digests of the passwords are retained in memory.
+#[derive(Clone)]
+pub struct PasswordAuthenticator {
+    users: BTreeMap<String, [u8; 32]>,
+}
+
+impl PasswordAuthenticator {
+    /// Creates an authenticator for the given map from users to passwords.
+    pub fn new(users: BTreeMap<String, String>) -> PasswordAuthenticator {
+        PasswordAuthenticator {
+            users: users
+                .into_iter()
+                .map(|(user, password)| (user, sha256(password.as_bytes())))
+                .collect(),
+        }
+    }
+
+    /// Creates an authenticator for the users in the password file at `path`.
+    ///
+    /// Each line of the file names a user and its password, separated by a
+    /// colon, as in `user:password`. Passwords may contain colons; user names
+    /// may not. Blank lines and lines that begin with `#` are ignored.
+    pub fn load(path: &Path) -> Result<PasswordAuthenticator, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("reading password file {}", path.display()))?;
+        let mut users = BTreeMap::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(':') {
+                Some((user, password)) if !user.is_empty() => {
+                    users.insert(user.into(), password.into());
+                }
+                _ => bail!(
+                    "invalid password file {}: line {} is not of the form user:password",
+                    path.display(),
+                    i + 1
+                ),
+            }
+        }
+        Ok(PasswordAuthenticator::new(users))
+    }
+}
+
+impl fmt::Debug for PasswordAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PasswordAuthenticator")
+            .field("users", &self.users.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Authenticator for PasswordAuthenticator {
+    fn requires_credentials(&self) -> bool {
+        true
+    }
+
+    async fn authenticate(
+        &self,
+        user: Option<&str>,
+        credentials: Credentials,
+    ) -> Result<Authenticated, anyhow::Error> {
+        let user = match user {
+            Some(user) => user,
+            None => bail!("no user specified"),
+        };
+        let password = match credentials {
+            Credentials::Password(password) => password,
+            Credentials::None => bail!("password required"),
+            Credentials::Token(_) => bail!("token authentication is not supported"),
+        };
+        match self.users.get(user) {
+            // Compare digests, which are of a fixed length, in constant time
+            // to avoid leaking information about the expected password.
+            Some(digest) if memcmp::eq(digest, &sha256(password.as_bytes())) => Ok(Authenticated {
+                user: user.into(),
+                expired: None,
+            }),
+            _ => bail!("invalid password"),
+        }
+    }
+}
+
+/// An authenticator that checks the passwords that clients present against the
+/// passwords set with `CREATE ROLE ... PASSWORD`.
+///
+/// The catalog is only accessible to the servers, which check the passwords
+/// themselves; see [`Authenticator::checks_role_passwords`].
+#[derive(Debug, Clone, Default)]
+pub struct CatalogAuthenticator;
+
+#[async_trait]
+impl Authenticator for CatalogAuthenticator {
+    fn requires_credentials(&self) -> bool {
+        true
+    }
+
+    fn checks_role_passwords(&self) -> bool {
+        true
+    }
+
+    async fn authenticate(
+        &self,
+        _: Option<&str>,
+        _: Credentials,
+    ) -> Result<Authenticated, anyhow::Error> {
+        bail!("passwords of roles must be checked against the catalog")
+    }
+}
+
+/// An authenticator that validates Frontegg credentials.
+///
+/// Clients may present either a Frontegg API token as a password, in which
+/// case the authentication expires when the token can no longer be refreshed,
+/// or a Frontegg JWT as a token, in which case the user is the email address
+/// that the JWT asserts.
+#[derive(Debug, Clone)]
+pub struct FronteggAuthenticator {
+    frontegg: FronteggAuthentication,
+}
+
+impl FronteggAuthenticator {
+    /// Creates an authenticator that validates credentials with `frontegg`.
+    pub fn new(frontegg: FronteggAuthentication) -> FronteggAuthenticator {
+        FronteggAuthenticator { frontegg }
+    }
+}
+
+#[async_trait]
+impl Authenticator for FronteggAuthenticator {
+    fn requires_credentials(&self) -> bool {
+        true
+    }
+
+    fn creates_users(&self) -> bool {
+        true
+    }
+
+    async fn authenticate(
+        &self,
+        user: Option<&str>,
+        credentials: Credentials,
+    ) -> Result<Authenticated, anyhow::Error> {
+        match credentials {
+            Credentials::Password(password) => {
+                let user = match user {
+                    Some(user) => user,
+                    None => bail!("no user specified"),
+                };
+                let token = self.frontegg.exchange_password_for_token(&password).await?;
+                let expired = self.frontegg.check_expiry(token, user.into())?;
+                Ok(Authenticated {
+                    user: user.into(),
+                    expired: Some(expired.boxed()),
+                })
+            }
+            Credentials::Token(token) => {
+                let claims = self.frontegg.validate_access_token(&token, user)?;
+                Ok(Authenticated {
+                    user: claims.email,
+                    expired: None,
+                })
+            }
+            Credentials::None => bail!("credentials required"),
+        }
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The server side of SCRAM-SHA-256 authentication.
+//!
+//! SCRAM is specified in [RFC 5802], and its SHA-256 variant in [RFC 7677].
+//! Passwords are stored as verifiers in the same format as PostgreSQL, so that
+//! verifiers can be copied from PostgreSQL with `CREATE ROLE ... PASSWORD`.
+//!
+//! Unlike PostgreSQL, passwords are not normalized with SASLprep. Clients
+//! normalize passwords before hashing them, so passwords that contain
+//! non-ASCII characters that SASLprep alters cannot be used.
+//!
+//! [RFC 5802]: https://datatracker.ietf.org/doc/html/rfc5802
+//! [RFC 7677]: https://datatracker.ietf.org/doc/html/rfc7677
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use openssl::base64;
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+
+/// The name of the SASL mechanism.
+pub const MECHANISM: &str = "SCRAM-SHA-256";
+
+/// The number of iterations with which new passwords are hashed, which is
+/// PostgreSQL's default.
+const ITERATIONS: u32 = 4096;
+
+/// The range of iteration counts that verifiers may specify.
+///
+/// The minimum is the count that RFC 7677 requires. The maximum bounds the
+/// time that the server spends hashing a password that a client sends in
+/// cleartext, as verifiers can be supplied by roles that are not superusers.
+const ITERATIONS_RANGE: RangeInclusive<u32> = 4096..=10_000_000;
+
+/// The length in bytes of the salt of new passwords.
+const SALT_LEN: usize = 16;
+
+/// The length in bytes of the server's part of the nonce.
+const NONCE_LEN: usize = 18;
+
+/// The stored form of a password, from which the password cannot be
+/// recovered.
+///
+/// A verifier is formatted as
+/// `SCRAM-SHA-256$<iterations>:<salt>$<stored key>:<server key>`, where the
+/// salt and keys are encoded in base64.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Verifier {
+    iterations: u32,
+    salt: Vec<u8>,
+    stored_key: [u8; 32],
+    server_key: [u8; 32],
+}
+
+impl Verifier {
+    /// Hashes `password` with a random salt.
+    pub fn new(password: &str) -> Verifier {
+        let mut salt = vec![0; SALT_LEN];
+        rand_bytes(&mut salt).expect("generating random bytes cannot fail");
+        Verifier::derive(password, salt, ITERATIONS)
+    }
+
+    /// Returns a verifier with a random salt that no password matches.
+    ///
+    /// Exchanges for roles that do not exist or that have no password use a
+    /// mock verifier, so that clients cannot tell them apart from exchanges
+    /// in which the password is wrong.
+    pub fn mock() -> Verifier {
+        let mut verifier = Verifier::derive("", vec![0; SALT_LEN], ITERATIONS);
+        rand_bytes(&mut verifier.salt).expect("generating random bytes cannot fail");
+        rand_bytes(&mut verifier.stored_key).expect("generating random bytes cannot fail");
+        verifier
+    }
+
+    fn derive(password: &str, salt: Vec<u8>, iterations: u32) -> Verifier {
+        let salted_password = salted_password(password, &salt, iterations);
+        let client_key = hmac(&salted_password, b"Client Key");
+        Verifier {
+            iterations,
+            salt,
+            stored_key: sha256(&client_key),
+            server_key: hmac(&salted_password, b"Server Key"),
+        }
+    }
+
+    /// Reports whether `password` is the password from which the verifier was
+    /// derived.
+    pub fn verify_password(&self, password: &str) -> bool {
+        let salted_password = salted_password(password, &self.salt, self.iterations);
+        let stored_key = sha256(&hmac(&salted_password, b"Client Key"));
+        memcmp::eq(&stored_key, &self.stored_key)
+    }
+}
+
+impl FromStr for Verifier {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Verifier, anyhow::Error> {
+        let invalid = || anyhow!("invalid {} verifier", MECHANISM);
+        let (mechanism, rest) = s.split_once('$').ok_or_else(invalid)?;
+        let (iterations_salt, keys) = rest.split_once('$').ok_or_else(invalid)?;
+        let (iterations, salt) = iterations_salt.split_once(':').ok_or_else(invalid)?;
+        let (stored_key, server_key) = keys.split_once(':').ok_or_else(invalid)?;
+        if mechanism != MECHANISM {
+            return Err(invalid());
+        }
+        let decode_key = |key: &str| -> Result<[u8; 32], anyhow::Error> {
+            base64::decode_block(key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .ok_or_else(invalid)
+        };
+        Ok(Verifier {
+            iterations: iterations
+                .parse::<u32>()
+                .ok()
+                .filter(|i| ITERATIONS_RANGE.contains(i))
+                .ok_or_else(invalid)?,
+            salt: base64::decode_block(salt).map_err(|_| invalid())?,
+            stored_key: decode_key(stored_key)?,
+            server_key: decode_key(server_key)?,
+        })
+    }
+}
+
+impl fmt::Display for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}${}:{}${}:{}",
+            MECHANISM,
+            self.iterations,
+            base64::encode_block(&self.salt),
+            base64::encode_block(&self.stored_key),
+            base64::encode_block(&self.server_key)
+        )
+    }
+}
+
+impl fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Verifier")
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The server's state between the first and the final messages of an
+/// exchange.
+#[derive(Debug)]
+pub struct ServerExchange {
+    verifier: Verifier,
+    gs2_header: String,
+    client_first_bare: String,
+    server_first: String,
+    nonce: String,
+}
+
+impl ServerExchange {
+    /// Begins an exchange in which the client proves that it knows the
+    /// password from which `verifier` was derived.
+    ///
+    /// `client_first` is the client's first message. Returns the exchange and
+    /// the server's first message.
+    pub fn start(
+        verifier: Verifier,
+        client_first: &str,
+    ) -> Result<(ServerExchange, String), anyhow::Error> {
+        let mut nonce = [0; NONCE_LEN];
+        rand_bytes(&mut nonce).expect("generating random bytes cannot fail");
+        ServerExchange::start_with_nonce(verifier, client_first, &base64::encode_block(&nonce))
+    }
+
+    fn start_with_nonce(
+        verifier: Verifier,
+        client_first: &str,
+        server_nonce: &str,
+    ) -> Result<(ServerExchange, String), anyhow::Error> {
+        // The GS2 header is a channel binding flag and an optional
+        // authorization identity, each followed by a comma.
+        let (cbind_flag, rest) = client_first
+            .split_once(',')
+            .ok_or_else(|| anyhow!("malformed client-first-message"))?;
+        let (authzid, client_first_bare) = rest
+            .split_once(',')
+            .ok_or_else(|| anyhow!("malformed client-first-message"))?;
+        match cbind_flag {
+            "n" | "y" => (),
+            _ if cbind_flag.starts_with("p=") => bail!("channel binding is not supported"),
+            _ => bail!("malformed client-first-message"),
+        }
+        if !authzid.is_empty() {
+            bail!("authorization identities are not supported");
+        }
+
+        // The user name is ignored in favor of the user named in the startup
+        // message, as in PostgreSQL.
+        let mut client_nonce = None;
+        for attr in client_first_bare.split(',') {
+            match attr.split_once('=') {
+                Some(("r", value)) => client_nonce = Some(value),
+                Some(("m", _)) => bail!("mandatory extensions are not supported"),
+                _ => (),
+            }
+        }
+        let nonce = match client_nonce {
+            Some(client_nonce) if !client_nonce.is_empty() => {
+                format!("{}{}", client_nonce, server_nonce)
+            }
+            _ => bail!("client-first-message does not contain a nonce"),
+        };
+
+        let server_first = format!(
+            "r={},s={},i={}",
+            nonce,
+            base64::encode_block(&verifier.salt),
+            verifier.iterations
+        );
+        let exchange = ServerExchange {
+            verifier,
+            gs2_header: format!("{},{},", cbind_flag, authzid),
+            client_first_bare: client_first_bare.into(),
+            server_first: server_first.clone(),
+            nonce,
+        };
+        Ok((exchange, server_first))
+    }
+
+    /// Completes the exchange with the client's final message.
+    ///
+    /// Returns the server's final message if the client's proof is valid.
+    pub fn finish(self, client_final: &str) -> Result<String, anyhow::Error> {
+        let (client_final_without_proof, proof) = client_final
+            .rsplit_once(",p=")
+            .ok_or_else(|| anyhow!("client-final-message does not contain a proof"))?;
+        let mut channel_binding = None;
+        let mut nonce = None;
+        for attr in client_final_without_proof.split(',') {
+            match attr.split_once('=') {
+                Some(("c", value)) => channel_binding = Some(value),
+                Some(("r", value)) => nonce = Some(value),
+                _ => (),
+            }
+        }
+        if channel_binding != Some(base64::encode_block(self.gs2_header.as_bytes()).as_str()) {
+            bail!("channel binding does not match");
+        }
+        if nonce != Some(self.nonce.as_str()) {
+            bail!("nonce does not match");
+        }
+        let proof: [u8; 32] = base64::decode_block(proof)
+            .ok()
+            .and_then(|proof| proof.try_into().ok())
+            .ok_or_else(|| anyhow!("malformed proof"))?;
+
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, self.server_first, client_final_without_proof
+        );
+        let client_signature = hmac(&self.verifier.stored_key, auth_message.as_bytes());
+        let mut client_key = proof;
+        for (k, s) in client_key.iter_mut().zip(client_signature) {
+            *k ^= s;
+        }
+        if !memcmp::eq(&sha256(&client_key), &self.verifier.stored_key) {
+            bail!("invalid proof");
+        }
+        let server_signature = hmac(&self.verifier.server_key, auth_message.as_bytes());
+        Ok(format!("v={}", base64::encode_block(&server_signature)))
+    }
+}
+
+fn salted_password(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut salted_password = [0; 32];
+    pbkdf2_hmac(
+        password.as_bytes(),
+        salt,
+        usize::try_from(iterations).expect("u32 fits in usize"),
+        MessageDigest::sha256(),
+        &mut salted_password,
+    )
+    .expect("PBKDF2 cannot fail");
+    salted_password
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let key = PKey::hmac(key).expect("creating an HMAC key cannot fail");
+    let mut signer =
+        Signer::new(MessageDigest::sha256(), &key).expect("creating a signer cannot fail");
+    signer.update(data).expect("signing cannot fail");
+    signer
+        .sign_to_vec()
+        .expect("signing cannot fail")
+        .try_into()
+        .expect("SHA-256 HMACs are 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ServerExchange, Verifier};
+
+    // The example exchange from RFC 7677, section 3.
+    const PASSWORD: &str = "pencil";
+    const SALT: &str = "W22ZaJ0SNY7soEsUEjb6gQ==";
+    const CLIENT_FIRST: &str = "n,,n=user,r=rOprNGfwEbeRWgbNEkqO";
+    const SERVER_NONCE: &str = "%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+    const SERVER_FIRST: &str =
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+    const CLIENT_FINAL: &str = "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+        p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+    const SERVER_FINAL: &str = "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+
+    fn rfc_verifier() -> Verifier {
+        let salt = openssl::base64::decode_block(SALT).unwrap();
+        Verifier::derive(PASSWORD, salt, 4096)
+    }
+
+    #[test]
+    fn test_exchange() {
+        let (exchange, server_first) =
+            ServerExchange::start_with_nonce(rfc_verifier(), CLIENT_FIRST, SERVER_NONCE).unwrap();
+        assert_eq!(server_first, SERVER_FIRST);
+        assert_eq!(exchange.finish(CLIENT_FINAL).unwrap(), SERVER_FINAL);
+    }
+
+    #[test]
+    fn test_exchange_wrong_password() {
+        let salt = openssl::base64::decode_block(SALT).unwrap();
+        let verifier = Verifier::derive("pencils", salt, 4096);
+        let (exchange, _) =
+            ServerExchange::start_with_nonce(verifier, CLIENT_FIRST, SERVER_NONCE).unwrap();
+        assert!(exchange.finish(CLIENT_FINAL).is_err());
+
+        let (exchange, _) =
+            ServerExchange::start_with_nonce(Verifier::mock(), CLIENT_FIRST, SERVER_NONCE).unwrap();
+        assert!(exchange.finish(CLIENT_FINAL).is_err());
+    }
+
+    #[test]
+    fn test_exchange_malformed() {
+        for client_first in [
+            "",
+            "n,,",
+            "p=tls-server-end-point,,n=user,r=a",
+            "n,a,n=user,r=a",
+        ] {
+            assert!(
+                ServerExchange::start_with_nonce(rfc_verifier(), client_first, SERVER_NONCE)
+                    .is_err(),
+                "{:?}",
+                client_first
+            );
+        }
+        for client_final in [
+            "",
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0",
+            "c=eSws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+        ] {
+            let (exchange, _) =
+                ServerExchange::start_with_nonce(rfc_verifier(), CLIENT_FIRST, SERVER_NONCE)
+                    .unwrap();
+            assert!(exchange.finish(client_final).is_err(), "{:?}", client_final);
+        }
+    }
+
+    #[test]
+    fn test_verifier() {
+        let verifier = Verifier::new("hunter2");
+        assert!(verifier.verify_password("hunter2"));
+        assert!(!verifier.verify_password("hunter3"));
+        assert!(!Verifier::mock().verify_password(""));
+
+        let s = verifier.to_string();
+        assert!(s.starts_with("SCRAM-SHA-256$4096:"));
+        assert_eq!(s.parse::<Verifier>().unwrap(), verifier);
+
+        let s = rfc_verifier().to_string();
+        assert_eq!(s.parse::<Verifier>().unwrap(), rfc_verifier());
+        for invalid in [
+            "",
+            "hunter2",
+            "md5a3556571e93b0d20722ba62be61e8c2d",
+            "SCRAM-SHA-256$0:W22ZaJ0SNY7soEsUEjb6gQ==$a:b",
+            "SCRAM-SHA-1$4096:W22ZaJ0SNY7soEsUEjb6gQ==$a:b",
+        ] {
+            assert!(invalid.parse::<Verifier>().is_err(), "{:?}", invalid);
+        }
+
+        // Iteration counts outside of the allowed range are refused.
+        let mut verifier = rfc_verifier();
+        for (iterations, valid) in [(4095, false), (10_000_000, true), (10_000_001, false)] {
+            verifier.iterations = iterations;
+            let s = verifier.to_string();
+            assert_eq!(s.parse::<Verifier>().is_ok(), valid, "{:?}", s);
+        }
+    }
+}
@@ -20,14 +20,26 @@ pub mod config;
 #[cfg(feature = "kinesis")]
 pub mod kinesis;
 
+#[cfg_attr(nightly_doc_features, doc(cfg(feature = "kms")))]
+#[cfg(feature = "kms")]
+pub mod kms;
+
 #[cfg_attr(nightly_doc_features, doc(cfg(feature = "s3")))]
 #[cfg(feature = "s3")]
 pub mod s3;
 
+#[cfg_attr(nightly_doc_features, doc(cfg(feature = "secretsmanager")))]
+#[cfg(feature = "secretsmanager")]
+pub mod secretsmanager;
+
 #[cfg_attr(nightly_doc_features, doc(cfg(feature = "sqs")))]
 #[cfg(feature = "sqs")]
 pub mod sqs;
 
+#[cfg_attr(nightly_doc_features, doc(cfg(feature = "ssm")))]
+#[cfg(feature = "ssm")]
+pub mod ssm;
+
 #[cfg_attr(nightly_doc_features, doc(cfg(feature = "sts")))]
 #[cfg(feature = "sts")]
 pub mod sts;
@@ -0,0 +1,25 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! AWS Systems Manager client and utilities.
+
+use aws_sdk_ssm::Client;
+
+use crate::config::AwsConfig;
+use crate::util;
+
+/// Constructs a new AWS Systems Manager client that respects the
+/// [system proxy configuration](mz_http_proxy#system-proxy-configuration).
+pub fn client(config: &AwsConfig) -> Client {
+    let mut builder = aws_sdk_ssm::config::Builder::from(config.inner());
+    if let Some(endpoint) = config.endpoint() {
+        builder = builder.endpoint_resolver(endpoint.clone());
+    }
+    Client::from_conf_conn(builder.build(), util::connector())
+}
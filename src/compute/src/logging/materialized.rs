@@ -93,6 +93,13 @@ pub fn construct<A: Allocate>(
     activator: RcActivator,
 ) -> std::collections::HashMap<LogVariant, KeysValsHandle> {
     let granularity_ms = std::cmp::max(1, config.granularity_ns / 1_000_000) as Timestamp;
+    // Time-partitioned logs retract their events once they fall out of the
+    // retention window, rounded up to the logging granularity so that the
+    // retractions land on the same timestamps as the other log updates.
+    let retention_ms = config.retention_ns.map(|retention_ns| {
+        let retention_ms = (retention_ns / 1_000_000) as Timestamp;
+        ((retention_ms + granularity_ms - 1) / granularity_ms) * granularity_ms
+    });
 
     let traces = worker.dataflow_named("Dataflow: mz logging", move |scope| {
         let compute_logs = Some(compute).mz_replay(
@@ -119,6 +126,7 @@ pub fn construct<A: Allocate>(
         let (mut frontier_out, frontier) = demux.new_output();
         let (mut peek_out, peek) = demux.new_output();
         let (mut peek_duration_out, peek_duration) = demux.new_output();
+        let (mut peek_duration_per_minute_out, peek_duration_per_minute) = demux.new_output();
 
         let mut demux_buffer = Vec::new();
         demux.build(move |_capability| {
@@ -130,6 +138,7 @@ pub fn construct<A: Allocate>(
                 let mut frontier = frontier_out.activate();
                 let mut peek = peek_out.activate();
                 let mut peek_duration = peek_duration_out.activate();
+                let mut peek_duration_per_minute = peek_duration_per_minute_out.activate();
 
                 input.for_each(|time, data| {
                     data.swap(&mut demux_buffer);
@@ -139,6 +148,8 @@ pub fn construct<A: Allocate>(
                     let mut frontier_session = frontier.session(&time);
                     let mut peek_session = peek.session(&time);
                     let mut peek_duration_session = peek_duration.session(&time);
+                    let mut peek_duration_per_minute_session =
+                        peek_duration_per_minute.session(&time);
 
                     for (time, worker, datum) in demux_buffer.drain(..) {
                         let time_ms = (((time.as_millis() as Timestamp / granularity_ms) + 1)
@@ -218,11 +229,39 @@ pub fn construct<A: Allocate>(
                                     peek_session.give(((peek, worker), time_ms, -1));
                                     if let Some(start) = peek_stash.remove(&key) {
                                         let elapsed_ns = time.as_nanos() - start;
+                                        let pow = elapsed_ns.next_power_of_two();
+                                        let minute_start =
+                                            ((time.as_millis() / 60_000) * 60_000) as Timestamp;
                                         peek_duration_session.give((
-                                            (key.0, elapsed_ns.next_power_of_two()),
+                                            (key.0, pow),
                                             time_ms,
                                             Diff::from(1),
                                         ));
+                                        peek_duration_per_minute_session.give((
+                                            (key.0, minute_start, pow),
+                                            time_ms,
+                                            Diff::from(1),
+                                        ));
+                                        if let Some(retention_ms) = retention_ms {
+                                            peek_duration_session.give((
+                                                (key.0, pow),
+                                                time_ms + retention_ms,
+                                                Diff::from(-1),
+                                            ));
+                                            // Rollups are retained for the full
+                                            // retention window after their
+                                            // minute closes.
+                                            let minute_end =
+                                                (((minute_start + 60_000 + granularity_ms - 1)
+                                                    / granularity_ms)
+                                                    * granularity_ms)
+                                                    .max(time_ms);
+                                            peek_duration_per_minute_session.give((
+                                                (key.0, minute_start, pow),
+                                                minute_end + retention_ms,
+                                                Diff::from(-1),
+                                            ));
+                                        }
                                     } else {
                                         error!(
                                             "peek not yet registered: \
@@ -245,12 +284,16 @@ pub fn construct<A: Allocate>(
         let mut input = demux.new_input(&storage_logs, Pipeline);
         let (mut kafka_source_statistics_out, kafka_source_statistics) = demux.new_output();
         let (mut source_info_out, source_info) = demux.new_output();
+        let (mut source_schema_changes_out, source_schema_changes) = demux.new_output();
+        let (mut upsert_statistics_out, upsert_statistics) = demux.new_output();
 
         let mut demux_buffer = Vec::new();
         demux.build(move |_capability| {
             move |_frontiers| {
                 let mut kafka_source_statistics = kafka_source_statistics_out.activate();
                 let mut source_info = source_info_out.activate();
+                let mut source_schema_changes = source_schema_changes_out.activate();
+                let mut upsert_statistics = upsert_statistics_out.activate();
 
                 input.for_each(|time, data| {
                     data.swap(&mut demux_buffer);
@@ -258,6 +301,8 @@ pub fn construct<A: Allocate>(
                     let mut kafka_source_statistics_session =
                         kafka_source_statistics.session(&time);
                     let mut source_info_session = source_info.session(&time);
+                    let mut source_schema_changes_session = source_schema_changes.session(&time);
+                    let mut upsert_statistics_session = upsert_statistics.session(&time);
 
                     for (time, worker, datum) in demux_buffer.drain(..) {
                         let time_ms = (((time.as_millis() as Timestamp / granularity_ms) + 1)
@@ -297,6 +342,30 @@ pub fn construct<A: Allocate>(
                                     (offset, timestamp),
                                 ));
                             }
+                            StorageEvent::SourceSchemaChange {
+                                source_id,
+                                object,
+                                change,
+                                action,
+                            } => {
+                                source_schema_changes_session.give((
+                                    (source_id, worker, object, change, action),
+                                    time_ms,
+                                    1,
+                                ));
+                            }
+                            StorageEvent::UpsertStatistics {
+                                source_id,
+                                duplicate_keys,
+                                tombstones,
+                                late_retractions,
+                            } => {
+                                upsert_statistics_session.give((
+                                    (source_id, worker),
+                                    time_ms,
+                                    ((duplicate_keys, tombstones), late_retractions),
+                                ));
+                            }
                         }
                     }
                 });
@@ -359,6 +428,31 @@ pub fn construct<A: Allocate>(
             }
         });
 
+        let source_schema_changes_current = source_schema_changes.as_collection().map({
+            move |(source_id, worker, object, change, action)| {
+                Row::pack_slice(&[
+                    Datum::String(&source_id.source_id.to_string()),
+                    Datum::Int64(worker as i64),
+                    Datum::String(&object),
+                    Datum::String(&change),
+                    Datum::String(&action),
+                ])
+            }
+        });
+
+        let upsert_statistics_current = upsert_statistics.as_collection().count_core().map({
+            move |((id, worker), ((duplicate_keys, tombstones), late_retractions))| {
+                Row::pack_slice(&[
+                    Datum::String(&id.source_id.to_string()),
+                    Datum::Int64(id.dataflow_id as i64),
+                    Datum::Int64(worker as i64),
+                    Datum::Int64(duplicate_keys),
+                    Datum::Int64(tombstones),
+                    Datum::Int64(late_retractions),
+                ])
+            }
+        });
+
         // Duration statistics derive from the non-rounded event times.
         let peek_duration = peek_duration.as_collection().count_total_core().map({
             move |((worker, pow), count)| {
@@ -370,6 +464,20 @@ pub fn construct<A: Allocate>(
             }
         });
 
+        let peek_duration_per_minute = peek_duration_per_minute
+            .as_collection()
+            .count_total_core()
+            .map({
+                move |((worker, minute_start, pow), count)| {
+                    Row::pack_slice(&[
+                        Datum::Int64(worker as i64),
+                        Datum::Int64(minute_start as i64),
+                        Datum::Int64(pow as i64),
+                        Datum::Int64(count as i64),
+                    ])
+                }
+            });
+
         let logs = vec![
             (
                 LogVariant::Materialized(MaterializedLog::DataflowCurrent),
@@ -395,10 +503,22 @@ pub fn construct<A: Allocate>(
                 LogVariant::Materialized(MaterializedLog::PeekDuration),
                 peek_duration,
             ),
+            (
+                LogVariant::Materialized(MaterializedLog::PeekDurationPerMinute),
+                peek_duration_per_minute,
+            ),
             (
                 LogVariant::Materialized(MaterializedLog::SourceInfo),
                 source_info_current,
             ),
+            (
+                LogVariant::Materialized(MaterializedLog::SourceSchemaChanges),
+                source_schema_changes_current,
+            ),
+            (
+                LogVariant::Materialized(MaterializedLog::UpsertStatistics),
+                upsert_statistics_current,
+            ),
         ];
 
         let mut result = std::collections::HashMap::new();
@@ -9,7 +9,7 @@
 
 //! Persistent metadata storage for the coordinator.
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
@@ -19,10 +19,13 @@ use chrono::{DateTime, TimeZone, Utc};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
 use tracing::{info, trace};
 
-use mz_build_info::DUMMY_BUILD_INFO;
+use mz_build_info::{BuildInfo, DUMMY_BUILD_INFO};
 use mz_dataflow_types::client::{ComputeInstanceId, InstanceConfig};
 use mz_dataflow_types::logging::LoggingConfig as DataflowLoggingConfig;
 use mz_dataflow_types::sinks::{SinkConnector, SinkConnectorBuilder, SinkEnvelope};
@@ -34,10 +37,12 @@ use mz_expr::{ExprHumanizer, GlobalId, MirScalarExpr, OptimizedMirRelationExpr};
 use mz_ore::collections::CollectionExt;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::{to_datetime, EpochMillis, NowFn};
+use mz_ore::str::StrExt;
 use mz_pgrepr::oid::FIRST_USER_OID;
 use mz_repr::{RelationDesc, ScalarType};
+use mz_secrets::SecretVersion;
 use mz_sql::ast::display::AstDisplay;
-use mz_sql::ast::Expr;
+use mz_sql::ast::{Expr, Privilege, Raw, Statement};
 use mz_sql::catalog::{
     CatalogDatabase, CatalogError as SqlCatalogError, CatalogItem as SqlCatalogItem,
     CatalogItemType as SqlCatalogItemType, CatalogSchema, CatalogType, CatalogTypeDetails,
@@ -49,9 +54,10 @@ use mz_sql::names::{
     SchemaSpecifier,
 };
 use mz_sql::plan::{
-    ComputeInstanceConfig, ComputeInstanceIntrospectionConfig, CreateIndexPlan, CreateSecretPlan,
-    CreateSinkPlan, CreateSourcePlan, CreateTablePlan, CreateTypePlan, CreateViewPlan, Params,
-    Plan, PlanContext, StatementDesc,
+    ComputeInstanceConfig, ComputeInstanceIntrospectionConfig, CreateIndexPlan,
+    CreatePublicationPlan, CreateSecretPlan, CreateSinkPlan, CreateSourcePlan, CreateTablePlan,
+    CreateTaskPlan, CreateTypePlan, CreateViewPlan, IndexOption, Params, Plan, PlanContext,
+    RoleRestrictions, StatementDesc,
 };
 use mz_sql::DEFAULT_SCHEMA;
 use mz_transform::Optimizer;
@@ -59,8 +65,10 @@ use uuid::Uuid;
 
 use crate::catalog::builtin::{
     Builtin, BuiltinLog, BuiltinTable, BuiltinType, Fingerprint, BUILTINS, BUILTIN_ROLES,
-    INFORMATION_SCHEMA, MZ_CATALOG_SCHEMA, MZ_INTERNAL_SCHEMA, MZ_TEMP_SCHEMA, PG_CATALOG_SCHEMA,
+    INFORMATION_SCHEMA, MZ_CATALOG_SCHEMA, MZ_INTERNAL_SCHEMA, MZ_SYSTEM, MZ_TEMP_SCHEMA,
+    PG_CATALOG_SCHEMA,
 };
+use crate::catalog::storage::OwnedObjectId;
 use crate::persistcfg::PersistConfig;
 use crate::session::{PreparedStatement, Session, DEFAULT_DATABASE_NAME};
 use crate::CoordError;
@@ -80,7 +88,11 @@ pub use crate::catalog::error::Error;
 pub use crate::catalog::error::ErrorKind;
 
 pub const SYSTEM_CONN_ID: u32 = 0;
-const SYSTEM_USER: &str = "mz_system";
+pub const SYSTEM_USER: &str = "mz_system";
+
+/// The number of most recent operations on secrets storage that are retained
+/// in `mz_secret_audit_events`.
+pub const SECRET_AUDIT_EVENTS_RETAINED: u64 = 10_000;
 
 /// A `Catalog` keeps track of the SQL objects known to the planner.
 ///
@@ -123,7 +135,29 @@ pub struct CatalogState {
     compute_instances_by_id: HashMap<ComputeInstanceId, ComputeInstance>,
     compute_instances_by_name: HashMap<String, ComputeInstanceId>,
     roles: HashMap<String, Role>,
+    /// The comments on each item, keyed by the 1-based position of the
+    /// commented column, or by `None` for the comment on the item itself.
+    comments: BTreeMap<GlobalId, BTreeMap<Option<usize>, String>>,
+    /// The session defaults for each role, keyed by the name of the session
+    /// variable. Defaults for every role are keyed by a role ID of `None`.
+    role_settings: BTreeMap<Option<i64>, BTreeMap<String, String>>,
+    /// The privileges that roles hold on objects that they do not own.
+    /// Privileges granted to every role have a role ID of `None`.
+    privileges: BTreeMap<OwnedObjectId, BTreeSet<(Option<i64>, Privilege)>>,
+    /// The dropped items whose definitions have not yet been purged.
+    dropped_items: BTreeMap<GlobalId, DroppedItem>,
+    /// How long the definitions of dropped items are retained.
+    dropped_item_retention: Duration,
+    /// The current version of each secret in secrets storage, as last
+    /// reported by the secrets controller. The versions are not durable: the
+    /// coordinator reads them from secrets storage when it starts.
+    secret_versions: HashMap<GlobalId, SecretVersion>,
+    /// The versions of the secrets with which each source and sink was last
+    /// configured. Like the versions of secrets, these are not durable: the
+    /// coordinator reads the secrets again when it starts.
+    secret_uses: HashMap<GlobalId, BTreeMap<GlobalId, SecretVersion>>,
     config: mz_sql::catalog::CatalogConfig,
+    config_reload_tx: Option<mpsc::UnboundedSender<()>>,
     oid_counter: u32,
 }
 
@@ -195,7 +229,9 @@ impl CatalogState {
             | CatalogItem::Func(_)
             | CatalogItem::Sink(_)
             | CatalogItem::Type(_)
-            | CatalogItem::Secret(_) => false,
+            | CatalogItem::Secret(_)
+            | CatalogItem::Publication(_)
+            | CatalogItem::Task(_) => false,
         }
     }
 
@@ -296,6 +332,8 @@ impl CatalogState {
         oid: u32,
         name: QualifiedObjectName,
         item: CatalogItem,
+        owner_id: i64,
+        timestamps: Option<ObjectTimestamps>,
     ) {
         if !id.is_system() && !item.is_placeholder() {
             info!("create {} {} ({})", item.typ(), name, id);
@@ -323,6 +361,8 @@ impl CatalogState {
             id,
             oid,
             used_by: Vec::new(),
+            owner_id,
+            timestamps,
         };
         for u in entry.uses() {
             match self.entry_by_id.get_mut(&u) {
@@ -361,6 +401,8 @@ impl CatalogState {
         config: ComputeInstanceConfig,
         local_compute_introspection: Option<ComputeInstanceIntrospectionConfig>,
         introspection_sources: Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        timestamps: ObjectTimestamps,
     ) {
         let (config, introspection) = match config {
             ComputeInstanceConfig::Local => (InstanceConfig::Local, local_compute_introspection),
@@ -426,12 +468,16 @@ impl CatalogState {
                             depends_on: vec![log_id],
                             enabled: true,
                             compute_instance: id,
+                            logical_compaction_window: None,
                         }),
+                        MZ_SYSTEM.id,
+                        None,
                     );
                     active_logs.insert(log.variant.clone(), index_id);
                 }
                 Some(DataflowLoggingConfig {
                     granularity_ns: introspection.granularity.as_nanos(),
+                    retention_ns: introspection.retention.map(|r| r.as_nanos()),
                     log_logging: introspection.debugging,
                     active_logs,
                 })
@@ -445,6 +491,8 @@ impl CatalogState {
                 id,
                 indexes: HashSet::new(),
                 logging,
+                owner_id,
+                timestamps,
             },
         );
         self.compute_instances_by_name.insert(name, id);
@@ -604,12 +652,25 @@ impl CatalogState {
             CatalogItem::Type(_) => Unknown,
             CatalogItem::Func(_) => Unknown,
             CatalogItem::Secret(_) => Nonvolatile,
+            CatalogItem::Publication(_) => Unknown,
+            CatalogItem::Task(_) => Unknown,
         }
     }
 
     pub fn config(&self) -> &mz_sql::catalog::CatalogConfig {
         &self.config
     }
+
+    /// Requests that the server reload its runtime configuration.
+    ///
+    /// Returns whether the request was delivered. The reload itself happens
+    /// asynchronously.
+    pub fn request_config_reload(&self) -> bool {
+        match &self.config_reload_tx {
+            Some(tx) => tx.send(()).is_ok(),
+            None => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -629,6 +690,120 @@ impl ConnCatalog<'_> {
     }
 }
 
+/// When a catalog object was created and last modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ObjectTimestamps {
+    /// When the object was created, in milliseconds since the Unix epoch.
+    pub created_at: EpochMillis,
+    /// When the object was last modified, in milliseconds since the Unix
+    /// epoch.
+    pub updated_at: EpochMillis,
+}
+
+impl ObjectTimestamps {
+    /// Returns the timestamps of an object created at `now`.
+    pub fn created(now: EpochMillis) -> ObjectTimestamps {
+        ObjectTimestamps {
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Returns these timestamps, updated to reflect a modification at `now`.
+    pub fn updated(self, now: EpochMillis) -> ObjectTimestamps {
+        ObjectTimestamps {
+            created_at: self.created_at,
+            updated_at: now,
+        }
+    }
+}
+
+/// A record of an operation on secrets storage.
+///
+/// Events record who changed which secret and when, but never the contents of
+/// the secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretAuditEvent {
+    /// The ID of the event, which increases with each recorded event.
+    pub id: u64,
+    /// The ID of the secret that the operation applied to.
+    pub secret_id: GlobalId,
+    /// The operation that was applied.
+    pub operation: SecretAuditOperation,
+    /// The version of the secret that the operation created or deleted, or
+    /// `None` if the operation deleted every version of the secret.
+    pub version: Option<SecretVersion>,
+    /// The user who applied the operation.
+    pub user: String,
+    /// When the operation was applied, in milliseconds since the Unix epoch.
+    pub occurred_at: EpochMillis,
+    /// The error with which the operation, or an operation applied along
+    /// with it, failed, or `None` if it was applied. Operations that failed
+    /// may or may not have been applied.
+    pub error: Option<String>,
+}
+
+/// The kind of operation recorded in a [`SecretAuditEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretAuditOperation {
+    /// The contents of the secret were created or updated.
+    Ensure,
+    /// The secret, or one of its versions, was deleted.
+    Delete,
+}
+
+impl SecretAuditOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretAuditOperation::Ensure => "ensure",
+            SecretAuditOperation::Delete => "delete",
+        }
+    }
+}
+
+/// A record of a change to a database, schema, role, cluster, or item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    /// The ID of the event, which increases with each recorded event.
+    pub id: u64,
+    /// The kind of change.
+    pub event_type: AuditEventType,
+    /// The type of the changed object, like `database` or `view`.
+    pub object_type: String,
+    /// The ID of the changed object.
+    pub object_id: String,
+    /// The name of the changed object, fully qualified for schemas and items.
+    pub name: String,
+    /// The user who made the change.
+    pub user: String,
+    /// When the change was made, in milliseconds since the Unix epoch.
+    pub occurred_at: EpochMillis,
+    /// Additional details about the change, which vary by event and object
+    /// type.
+    pub details: serde_json::Value,
+}
+
+/// The kind of change recorded in an [`AuditEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventType {
+    /// The object was created.
+    Create,
+    /// The object was dropped.
+    Drop,
+    /// The object was renamed or its definition was changed.
+    Alter,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventType::Create => "create",
+            AuditEventType::Drop => "drop",
+            AuditEventType::Alter => "alter",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Database {
     pub name: String,
@@ -637,6 +812,8 @@ pub struct Database {
     pub oid: u32,
     pub schemas_by_id: BTreeMap<SchemaId, Schema>,
     pub schemas_by_name: BTreeMap<String, SchemaId>,
+    pub owner_id: i64,
+    pub timestamps: ObjectTimestamps,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -647,6 +824,22 @@ pub struct Schema {
     pub oid: u32,
     pub items: BTreeMap<String, GlobalId>,
     pub functions: BTreeMap<String, GlobalId>,
+    // `mz_system` for ambient and temporary schemas
+    pub owner_id: i64,
+    // `None` for ambient and temporary schemas
+    pub timestamps: Option<ObjectTimestamps>,
+}
+
+/// An item that was dropped, and that can be restored with `UNDROP` until its
+/// definition is purged.
+#[derive(Debug, Clone)]
+pub struct DroppedItem {
+    pub id: GlobalId,
+    /// The fully qualified name of the item when it was dropped.
+    pub name: String,
+    pub item_type: String,
+    pub create_sql: String,
+    pub dropped_at: EpochMillis,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -655,6 +848,12 @@ pub struct Role {
     pub id: i64,
     #[serde(skip)]
     pub oid: u32,
+    pub restrictions: RoleRestrictions,
+    /// The SCRAM-SHA-256 verifier of the role's password, if it has one.
+    #[serde(skip)]
+    pub password: Option<String>,
+    // `None` for builtin roles
+    pub timestamps: Option<ObjectTimestamps>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -665,6 +864,8 @@ pub struct ComputeInstance {
     pub logging: Option<DataflowLoggingConfig>,
     // does not include introspection source indexes
     pub indexes: HashSet<GlobalId>,
+    pub owner_id: i64,
+    pub timestamps: ObjectTimestamps,
 }
 
 #[derive(Clone, Debug)]
@@ -674,6 +875,8 @@ pub struct CatalogEntry {
     id: GlobalId,
     oid: u32,
     name: QualifiedObjectName,
+    owner_id: i64,
+    timestamps: Option<ObjectTimestamps>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -686,6 +889,8 @@ pub enum CatalogItem {
     Type(Type),
     Func(Func),
     Secret(Secret),
+    Publication(Publication),
+    Task(Task),
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -713,6 +918,7 @@ pub struct Source {
     pub connector: SourceConnector,
     pub persist_details: Option<SerializedSourcePersistDetails>,
     pub desc: RelationDesc,
+    pub depends_on: Vec<GlobalId>,
 }
 
 impl Source {
@@ -763,6 +969,23 @@ pub struct Index {
     // does not exist in the catalog.
     pub enabled: bool,
     pub compute_instance: ComputeInstanceId,
+    /// The logical compaction window that the index was created or altered
+    /// with, if it overrides the server's default. A window of `None` disables
+    /// logical compaction.
+    pub logical_compaction_window: Option<Option<Duration>>,
+}
+
+impl Index {
+    /// Returns the logical compaction window set by `options`, the options of
+    /// a `CREATE INDEX` statement, if any.
+    pub fn logical_compaction_window(options: &[IndexOption]) -> Option<Option<Duration>> {
+        options
+            .iter()
+            .map(|option| match option {
+                IndexOption::LogicalCompactionWindow(window) => *window,
+            })
+            .last()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -784,6 +1007,20 @@ pub struct Secret {
     pub create_sql: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct Publication {
+    pub create_sql: String,
+    pub views: Vec<GlobalId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Task {
+    pub create_sql: String,
+    /// The interval at which the task runs.
+    pub schedule: Duration,
+    pub depends_on: Vec<GlobalId>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum Volatility {
     Volatile,
@@ -813,6 +1050,8 @@ impl CatalogItem {
             CatalogItem::Type(_) => mz_sql::catalog::CatalogItemType::Type,
             CatalogItem::Func(_) => mz_sql::catalog::CatalogItemType::Func,
             CatalogItem::Secret(_) => mz_sql::catalog::CatalogItemType::Secret,
+            CatalogItem::Publication(_) => mz_sql::catalog::CatalogItemType::Publication,
+            CatalogItem::Task(_) => mz_sql::catalog::CatalogItemType::Task,
         }
     }
 
@@ -825,7 +1064,9 @@ impl CatalogItem {
             | CatalogItem::Index(_)
             | CatalogItem::Sink(_)
             | CatalogItem::Type(_)
-            | CatalogItem::Secret(_) => Err(SqlCatalogError::InvalidDependency {
+            | CatalogItem::Secret(_)
+            | CatalogItem::Publication(_)
+            | CatalogItem::Task(_) => Err(SqlCatalogError::InvalidDependency {
                 name: name.to_string(),
                 typ: self.typ(),
             }),
@@ -859,11 +1100,13 @@ impl CatalogItem {
             CatalogItem::Func(_) => &[],
             CatalogItem::Index(idx) => &idx.depends_on,
             CatalogItem::Sink(sink) => &sink.depends_on,
-            CatalogItem::Source(_) => &[],
+            CatalogItem::Source(source) => &source.depends_on,
             CatalogItem::Table(table) => &table.depends_on,
             CatalogItem::Type(typ) => &typ.depends_on,
             CatalogItem::View(view) => &view.depends_on,
             CatalogItem::Secret(_) => &[],
+            CatalogItem::Publication(publication) => &publication.views,
+            CatalogItem::Task(task) => &task.depends_on,
         }
     }
 
@@ -877,7 +1120,9 @@ impl CatalogItem {
             | CatalogItem::Table(_)
             | CatalogItem::Type(_)
             | CatalogItem::View(_)
-            | CatalogItem::Secret(_) => false,
+            | CatalogItem::Secret(_)
+            | CatalogItem::Publication(_)
+            | CatalogItem::Task(_) => false,
             CatalogItem::Sink(s) => match s.connector {
                 SinkConnectorState::Pending(_) => true,
                 SinkConnectorState::Ready(_) => false,
@@ -895,6 +1140,8 @@ impl CatalogItem {
             CatalogItem::Source(_) => None,
             CatalogItem::Sink(_) => None,
             CatalogItem::Secret(_) => None,
+            CatalogItem::Publication(_) => None,
+            CatalogItem::Task(_) => None,
             CatalogItem::Type(_) => None,
             CatalogItem::Func(_) => None,
         }
@@ -955,12 +1202,51 @@ impl CatalogItem {
                 i.create_sql = do_rewrite(i.create_sql)?;
                 Ok(CatalogItem::Secret(i))
             }
+            CatalogItem::Publication(i) => {
+                let mut i = i.clone();
+                i.create_sql = do_rewrite(i.create_sql)?;
+                Ok(CatalogItem::Publication(i))
+            }
+            CatalogItem::Task(i) => {
+                let mut i = i.clone();
+                i.create_sql = do_rewrite(i.create_sql)?;
+                Ok(CatalogItem::Task(i))
+            }
             CatalogItem::Func(_) | CatalogItem::Type(_) => {
                 unreachable!("{}s cannot be renamed", self.typ())
             }
         }
     }
 
+    /// Returns a copy of the item whose `create_sql` has been rewritten by
+    /// `f`, or `None` if `f` leaves the statement unchanged.
+    fn rewrite_create_sql<F>(&self, f: F) -> Result<Option<CatalogItem>, String>
+    where
+        F: FnOnce(&mut Statement<Raw>) -> Result<(), String>,
+    {
+        let mut item = self.clone();
+        let create_sql = match &mut item {
+            CatalogItem::Table(Table { create_sql, .. })
+            | CatalogItem::Source(Source { create_sql, .. })
+            | CatalogItem::Sink(Sink { create_sql, .. })
+            | CatalogItem::View(View { create_sql, .. })
+            | CatalogItem::Index(Index { create_sql, .. })
+            | CatalogItem::Type(Type { create_sql, .. })
+            | CatalogItem::Secret(Secret { create_sql, .. })
+            | CatalogItem::Publication(Publication { create_sql, .. })
+            | CatalogItem::Task(Task { create_sql, .. }) => create_sql,
+            CatalogItem::Func(_) => unreachable!("funcs have no create_sql"),
+        };
+        let mut create_stmt = mz_sql::parse::parse(create_sql).unwrap().into_element();
+        f(&mut create_stmt)?;
+        let new_create_sql = create_stmt.to_ast_string_stable();
+        if new_create_sql == *create_sql {
+            return Ok(None);
+        }
+        *create_sql = new_create_sql;
+        Ok(Some(item))
+    }
+
     pub fn requires_single_materialization(&self) -> bool {
         if let CatalogItem::Source(Source {
             connector: SourceConnector::External { ref connector, .. },
@@ -1017,6 +1303,23 @@ impl CatalogEntry {
         }
     }
 
+    /// Returns the inner [`Publication`] if this entry is a publication, else
+    /// `None`.
+    pub fn publication(&self) -> Option<&Publication> {
+        match self.item() {
+            CatalogItem::Publication(publication) => Some(publication),
+            _ => None,
+        }
+    }
+
+    /// Returns the inner [`Task`] if this entry is a task, else `None`.
+    pub fn task(&self) -> Option<&Task> {
+        match self.item() {
+            CatalogItem::Task(task) => Some(task),
+            _ => None,
+        }
+    }
+
     /// Returns the [`mz_dataflow_types::sources::SourceConnector`] associated with
     /// this `CatalogEntry`.
     pub fn source_connector(&self) -> Result<&SourceConnector, SqlCatalogError> {
@@ -1059,6 +1362,18 @@ impl CatalogEntry {
         &self.used_by
     }
 
+    /// Returns the ID of the role that owns this catalog entry. Builtin items
+    /// are owned by the `mz_system` role.
+    pub fn owner_id(&self) -> i64 {
+        self.owner_id
+    }
+
+    /// Returns when this catalog entry was created and last modified, or
+    /// `None` if this is a builtin or temporary item.
+    pub fn timestamps(&self) -> Option<ObjectTimestamps> {
+        self.timestamps
+    }
+
     /// Returns the connection ID that this item belongs to, if this item is
     /// temporary.
     pub fn conn_id(&self) -> Option<u32> {
@@ -1069,7 +1384,6 @@ impl CatalogEntry {
 struct AllocatedBuiltinSystemIds<T> {
     all_builtins: Vec<(T, GlobalId)>,
     new_builtins: Vec<(T, GlobalId)>,
-    migrated_builtins: Vec<(T, GlobalId)>,
 }
 
 impl Catalog {
@@ -1090,12 +1404,19 @@ impl Catalog {
                 compute_instances_by_id: HashMap::new(),
                 compute_instances_by_name: HashMap::new(),
                 roles: HashMap::new(),
+                comments: BTreeMap::new(),
+                role_settings: BTreeMap::new(),
+                privileges: BTreeMap::new(),
+                dropped_items: BTreeMap::new(),
+                dropped_item_retention: config.dropped_item_retention,
+                secret_versions: HashMap::new(),
+                secret_uses: HashMap::new(),
                 config: mz_sql::catalog::CatalogConfig {
                     start_time: to_datetime((config.now)()),
                     start_instant: Instant::now(),
                     nonce: rand::random(),
                     experimental_mode: config.storage.experimental_mode(),
-                    safe_mode: config.safe_mode,
+                    safe_mode: config.storage.safe_mode(),
                     cluster_id: config.storage.cluster_id(),
                     session_id: Uuid::new_v4(),
                     build_info: config.build_info,
@@ -1104,6 +1425,7 @@ impl Catalog {
                     now: config.now.clone(),
                     disable_user_indexes: config.disable_user_indexes,
                 },
+                config_reload_tx: config.config_reload_tx,
                 oid_counter: FIRST_USER_OID,
             },
             transient_revision: 0,
@@ -1113,7 +1435,7 @@ impl Catalog {
         catalog.create_temporary_schema(SYSTEM_CONN_ID)?;
 
         let databases = catalog.storage().load_databases()?;
-        for (id, name) in databases {
+        for (id, name, owner_id, timestamps) in databases {
             let oid = catalog.allocate_oid()?;
             catalog.state.database_by_id.insert(
                 id.clone(),
@@ -1123,6 +1445,8 @@ impl Catalog {
                     oid,
                     schemas_by_id: BTreeMap::new(),
                     schemas_by_name: BTreeMap::new(),
+                    owner_id,
+                    timestamps,
                 },
             );
             catalog
@@ -1132,9 +1456,9 @@ impl Catalog {
         }
 
         let schemas = catalog.storage().load_schemas()?;
-        for (schema_id, schema_name, database_id) in schemas {
+        for (schema_id, schema_name, database_id, owner_id, timestamps) in schemas {
             let oid = catalog.allocate_oid()?;
-            let (schemas_by_id, schemas_by_name, database_spec) = match &database_id {
+            let (schemas_by_id, schemas_by_name, database_spec, timestamps) = match &database_id {
                 Some(database_id) => {
                     let db = catalog
                         .state
@@ -1145,12 +1469,14 @@ impl Catalog {
                         &mut db.schemas_by_id,
                         &mut db.schemas_by_name,
                         ResolvedDatabaseSpecifier::Id(*database_id),
+                        Some(timestamps),
                     )
                 }
                 None => (
                     &mut catalog.state.ambient_schemas_by_id,
                     &mut catalog.state.ambient_schemas_by_name,
                     ResolvedDatabaseSpecifier::Ambient,
+                    None,
                 ),
             };
             schemas_by_id.insert(
@@ -1164,14 +1490,29 @@ impl Catalog {
                     oid,
                     items: BTreeMap::new(),
                     functions: BTreeMap::new(),
+                    owner_id,
+                    timestamps,
                 },
             );
             schemas_by_name.insert(schema_name.clone(), schema_id);
         }
 
         let roles = catalog.storage().load_roles()?;
-        let builtin_roles = BUILTIN_ROLES.iter().map(|b| (b.id, b.name.to_owned()));
-        for (id, name) in roles.into_iter().chain(builtin_roles) {
+        let roles = roles
+            .into_iter()
+            .map(|(id, name, restrictions, password, timestamps)| {
+                (id, name, restrictions, password, Some(timestamps))
+            });
+        let builtin_roles = BUILTIN_ROLES.iter().map(|b| {
+            (
+                b.id,
+                b.name.to_owned(),
+                RoleRestrictions::default(),
+                None,
+                None,
+            )
+        });
+        for (id, name, restrictions, password, timestamps) in roles.chain(builtin_roles) {
             let oid = catalog.allocate_oid()?;
             catalog.state.roles.insert(
                 name.clone(),
@@ -1179,28 +1520,62 @@ impl Catalog {
                     name: name.clone(),
                     id,
                     oid,
+                    restrictions,
+                    password,
+                    timestamps,
                 },
             );
         }
 
         catalog.load_builtin_types()?;
 
+        let builtins: Vec<_> = BUILTINS
+            .iter()
+            .filter(|builtin| !matches!(builtin, Builtin::Type(_)))
+            .collect();
         let persisted_builtin_ids = catalog.storage().load_system_gids()?;
+        // Builtins whose definitions have changed since their IDs were stored
+        // are re-created under new IDs, so that no state derived from their
+        // old definitions, like persisted table data, is reused. Builtins
+        // whose stored fingerprints predate stable fingerprints keep their
+        // IDs, and their current fingerprints are recorded.
+        let stale_builtin_ids = catalog.storage().load_stale_system_gids(
+            builtins
+                .iter()
+                .map(|builtin| (builtin.schema(), builtin.name(), builtin.fingerprint())),
+        )?;
+        if !stale_builtin_ids.is_empty() {
+            info!("re-creating {} changed builtins", stale_builtin_ids.len());
+        }
         let AllocatedBuiltinSystemIds {
             all_builtins,
             new_builtins,
-            migrated_builtins,
-        } = catalog.allocate_system_ids(
-            BUILTINS
-                .iter()
-                .filter(|builtin| !matches!(builtin, Builtin::Type(_)))
-                .collect(),
-            |builtin| {
-                persisted_builtin_ids
-                    .get(&(builtin.schema().to_string(), builtin.name().to_string()))
-                    .cloned()
-            },
-        )?;
+        } = catalog.allocate_system_ids(builtins, |builtin| {
+            let key = (builtin.schema().to_string(), builtin.name().to_string());
+            if stale_builtin_ids.contains_key(&key) {
+                return None;
+            }
+            persisted_builtin_ids.get(&key).map(|(id, _)| *id)
+        })?;
+        let unfingerprinted_builtins: Vec<_> = all_builtins
+            .iter()
+            .filter(|(builtin, _)| {
+                let key = (builtin.schema().to_string(), builtin.name().to_string());
+                matches!(persisted_builtin_ids.get(&key), Some((_, None)))
+            })
+            .copied()
+            .collect();
+        // The comments, privileges, and dependencies that refer to re-created
+        // builtins are carried over to their new IDs. Items that depend on
+        // them refer to them by name, and are planned against the new
+        // definitions when they are loaded.
+        let replaced_builtin_ids = new_builtins
+            .iter()
+            .filter_map(|(builtin, id)| {
+                let key = (builtin.schema().to_string(), builtin.name().to_string());
+                stale_builtin_ids.get(&key).map(|old_id| (*old_id, *id))
+            })
+            .collect();
 
         for (builtin, id) in all_builtins {
             let schema_id = catalog.state.ambient_schemas_by_name[builtin.schema()];
@@ -1231,7 +1606,10 @@ impl Catalog {
                             },
                             persist_details: None,
                             desc: log.variant.desc(),
+                            depends_on: vec![],
                         }),
+                        MZ_SYSTEM.id,
+                        None,
                     );
                 }
 
@@ -1256,6 +1634,8 @@ impl Catalog {
                             depends_on: vec![],
                             persist_name,
                         }),
+                        MZ_SYSTEM.id,
+                        None,
                     );
                 }
 
@@ -1281,7 +1661,9 @@ impl Catalog {
                             )
                         });
                     let oid = catalog.allocate_oid()?;
-                    catalog.state.insert_item(id, oid, name, item);
+                    catalog
+                        .state
+                        .insert_item(id, oid, name, item, MZ_SYSTEM.id, None);
                 }
 
                 Builtin::Type(_) => unreachable!("loaded separately"),
@@ -1293,27 +1675,24 @@ impl Catalog {
                         oid,
                         name.clone(),
                         CatalogItem::Func(Func { inner: func.inner }),
+                        MZ_SYSTEM.id,
+                        None,
                     );
                 }
             }
         }
         let new_system_id_mappings = new_builtins
             .iter()
+            .chain(&unfingerprinted_builtins)
             .map(|(builtin, id)| (builtin.schema(), builtin.name(), *id, builtin.fingerprint()))
             .collect();
         catalog.storage().set_system_gids(new_system_id_mappings)?;
-
-        // TODO(jkosh44) actually migrate builtins
-        let migrated_system_id_mappings = migrated_builtins
-            .iter()
-            .map(|(builtin, id)| (builtin.schema(), builtin.name(), *id, builtin.fingerprint()))
-            .collect();
         catalog
             .storage()
-            .set_system_gids(migrated_system_id_mappings)?;
+            .replace_system_gids(replaced_builtin_ids)?;
 
         let compute_instances = catalog.storage().load_compute_instances()?;
-        for (id, name, conf) in compute_instances {
+        for (id, name, conf, owner_id, timestamps) in compute_instances {
             // Only one virtual compute instance can configure logging or
             // else the virtual compute host will panic. We arbitrarily
             // choose to attach the virtual compute host's logging to the
@@ -1334,11 +1713,7 @@ impl Catalog {
                     new_builtins: new_indexes,
                     ..
                 } = catalog.allocate_system_ids(BUILTINS.logs().collect(), |log| {
-                    introspection_source_index_gids
-                        .get(log.name)
-                        .cloned()
-                        // We don't migrate indexes so we can hardcode the fingerprint as 0
-                        .map(|id| (id, 0))
+                    introspection_source_index_gids.get(log.name).cloned()
                 })?;
 
                 catalog.storage().set_introspection_source_index_gids(
@@ -1358,6 +1733,8 @@ impl Catalog {
                 conf,
                 local_logging,
                 introspection_sources,
+                owner_id,
+                timestamps,
             );
         }
 
@@ -1377,8 +1754,80 @@ impl Catalog {
 
         let mut storage = catalog.storage();
         let mut tx = storage.transaction()?;
-        let catalog = Self::load_catalog_items(&mut tx, &catalog)?;
+        let mut catalog = Self::load_catalog_items(&mut tx, &catalog)?;
+        // The items were loaded in the order of their stored dependencies.
+        // Items written before their dependencies were stored, imported from a
+        // dump, or rewritten by a migration may have missing or outdated
+        // dependencies, which are corrected from the items' plans.
+        let stored_dependencies = tx.load_item_dependencies()?;
+        let stale_dependencies: Vec<_> = catalog
+            .state
+            .entry_by_id
+            .iter()
+            .filter(|(id, _)| id.is_user())
+            .filter_map(|(id, entry)| {
+                let uses: BTreeSet<_> = entry.uses().iter().copied().collect();
+                let stale = match stored_dependencies.get(id) {
+                    None => !uses.is_empty(),
+                    Some(stored) => *stored != uses,
+                };
+                stale.then(|| (*id, uses.into_iter().collect()))
+            })
+            .collect();
+        tx.set_item_dependencies(&stale_dependencies)?;
+        tx.commit()?;
+
+        for (id, column_position, comment) in storage.load_comments()? {
+            catalog
+                .state
+                .comments
+                .entry(id)
+                .or_default()
+                .insert(column_position, comment);
+        }
+
+        for (role_id, name, value) in storage.load_role_settings()? {
+            catalog
+                .state
+                .role_settings
+                .entry(role_id)
+                .or_default()
+                .insert(name, value);
+        }
+
+        for (object, role_id, privilege) in storage.load_privileges()? {
+            catalog
+                .state
+                .privileges
+                .entry(object)
+                .or_default()
+                .insert((role_id, privilege));
+        }
+
+        let mut tx = storage.transaction()?;
+        tx.purge_dropped_items(catalog.dropped_item_cutoff((catalog.config().now)()))?;
         tx.commit()?;
+        for (id, name, item_type, definition, dropped_at) in storage.load_dropped_items()? {
+            let SerializedCatalogItem::V1 { create_sql, .. } = serde_json::from_slice(&definition)
+                .map_err(|e| {
+                    Error::new(ErrorKind::Corruption {
+                        detail: format!(
+                            "failed to deserialize dropped item {} ({}): {}",
+                            id, name, e
+                        ),
+                    })
+                })?;
+            catalog.state.dropped_items.insert(
+                id,
+                DroppedItem {
+                    id,
+                    name,
+                    item_type,
+                    create_sql,
+                    dropped_at,
+                },
+            );
+        }
 
         let mut builtin_table_updates = vec![];
         for (schema_id, schema) in &catalog.state.ambient_schemas_by_id {
@@ -1409,8 +1858,38 @@ impl Catalog {
             builtin_table_updates.push(catalog.state.pack_role_update(role_name, 1));
         }
         for (name, _id) in &catalog.state.compute_instances_by_name {
-            builtin_table_updates.push(catalog.state.pack_compute_instance_update(name, 1));
+            builtin_table_updates.extend(catalog.state.pack_compute_instance_update(name, 1));
+        }
+        for event in storage.load_secret_audit_events()? {
+            builtin_table_updates.push(catalog.state.pack_secret_audit_event_update(&event, 1));
+        }
+        for event in storage.load_audit_events()? {
+            builtin_table_updates.push(catalog.state.pack_audit_event_update(&event, 1));
+        }
+        for (id, comments) in &catalog.state.comments {
+            for (column_position, comment) in comments {
+                builtin_table_updates.push(catalog.state.pack_comment_update(
+                    *id,
+                    *column_position,
+                    comment,
+                    1,
+                ));
+            }
         }
+        for (object, privileges) in &catalog.state.privileges {
+            for (role_id, privilege) in privileges {
+                builtin_table_updates.push(
+                    catalog
+                        .state
+                        .pack_privilege_update(*object, *role_id, *privilege, 1),
+                );
+            }
+        }
+        for item in catalog.state.dropped_items.values() {
+            builtin_table_updates.push(catalog.state.pack_dropped_item_update(item, 1));
+        }
+        let versions = storage.load_versions()?;
+        builtin_table_updates.push(catalog.state.pack_catalog_versions_update(&versions, 1));
 
         Ok((catalog, builtin_table_updates))
     }
@@ -1431,7 +1910,7 @@ impl Catalog {
         } = self.allocate_system_ids(BUILTINS.types().collect(), |typ| {
             persisted_builtin_ids
                 .get(&(typ.schema.to_string(), typ.name.to_string()))
-                .cloned()
+                .map(|(id, _)| *id)
         })?;
         let name_to_id_map: HashMap<&str, GlobalId> = all_builtins
             .into_iter()
@@ -1479,6 +1958,8 @@ impl Catalog {
                     details: typ.details.clone(),
                     depends_on: vec![],
                 }),
+                MZ_SYSTEM.id,
+                None,
             );
         }
 
@@ -1576,8 +2057,9 @@ impl Catalog {
         c: &Catalog,
     ) -> Result<Catalog, Error> {
         let mut c = c.clone();
-        let items = tx.load_items()?;
-        for (id, name, def) in items {
+        let dependencies = tx.load_item_dependencies()?;
+        let items = order_by_dependencies(tx.load_items()?, &dependencies);
+        for (id, name, def, owner_id, timestamps) in items {
             // TODO(benesch): a better way of detecting when a view has depended
             // upon a non-existent logging view. This is fine for now because
             // the only goal is to produce a nicer error message; we'll bail out
@@ -1600,7 +2082,8 @@ impl Catalog {
                 }
             };
             let oid = c.allocate_oid()?;
-            c.state.insert_item(id, oid, name, item);
+            c.state
+                .insert_item(id, oid, name, item, owner_id, Some(timestamps));
         }
         c.transient_revision = 1;
         Ok(c)
@@ -1617,17 +2100,35 @@ impl Catalog {
     /// [`Catalog::open`] with appropriately set configuration parameters
     /// instead.
     pub async fn open_debug(data_dir_path: &Path, now: NowFn) -> Result<Catalog, anyhow::Error> {
+        Self::open_debug_with_dropped_item_retention(data_dir_path, now, Duration::MAX).await
+    }
+
+    /// Like [`Catalog::open_debug`], but retains the definitions of dropped
+    /// items for only `dropped_item_retention`.
+    async fn open_debug_with_dropped_item_retention(
+        data_dir_path: &Path,
+        now: NowFn,
+        dropped_item_retention: Duration,
+    ) -> Result<Catalog, anyhow::Error> {
         let experimental_mode = None;
+        let safe_mode = None;
         let metrics_registry = &MetricsRegistry::new();
-        let storage = storage::Connection::open(data_dir_path, experimental_mode)?;
+        let storage = storage::Connection::open(
+            data_dir_path,
+            storage::SqliteSynchronous::default(),
+            None,
+            experimental_mode,
+            safe_mode,
+        )?;
         let (catalog, _) = Self::open(Config {
             storage,
             local_compute_introspection: Some(ComputeInstanceIntrospectionConfig {
                 granularity: Duration::from_secs(1),
                 debugging: false,
+                retention: None,
             }),
             experimental_mode,
-            safe_mode: false,
+            safe_mode,
             build_info: &DUMMY_BUILD_INFO,
             aws_external_id: AwsExternalId::NotProvided,
             timestamp_frequency: Duration::from_secs(1),
@@ -1638,11 +2139,111 @@ impl Catalog {
             persister: &PersistConfig::disabled()
                 .init(Uuid::new_v4(), DUMMY_BUILD_INFO, metrics_registry)
                 .await?,
+            config_reload_tx: None,
+            dropped_item_retention,
         })
         .await?;
         Ok(catalog)
     }
 
+    /// Applies any unapplied migrations to a copy of the catalog stored in the
+    /// data directory at `data_dir_path`, as if the catalog were opened by
+    /// this version of Materialize.
+    ///
+    /// The catalog in the data directory is not modified, even if the
+    /// migrations fail.
+    pub async fn check_migrations(
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+        build_info: &'static BuildInfo,
+        now: NowFn,
+    ) -> Result<(), anyhow::Error> {
+        let scratch = tempfile::tempdir()?;
+        storage::Connection::copy(data_dir_path, scratch.path(), encryption_key)?;
+        let experimental_mode = None;
+        let safe_mode = None;
+        let metrics_registry = &MetricsRegistry::new();
+        // The copy is discarded, so there is no need to wait for its commits
+        // to be durable.
+        let storage = storage::Connection::open(
+            scratch.path(),
+            storage::SqliteSynchronous::Off,
+            encryption_key,
+            experimental_mode,
+            safe_mode,
+        )?;
+        Self::open(Config {
+            storage,
+            local_compute_introspection: Some(ComputeInstanceIntrospectionConfig {
+                granularity: Duration::from_secs(1),
+                debugging: false,
+                retention: None,
+            }),
+            experimental_mode,
+            safe_mode,
+            build_info,
+            aws_external_id: AwsExternalId::NotProvided,
+            timestamp_frequency: Duration::from_secs(1),
+            now,
+            skip_migrations: false,
+            metrics_registry,
+            disable_user_indexes: false,
+            persister: &PersistConfig::disabled()
+                .init(Uuid::new_v4(), build_info.clone(), metrics_registry)
+                .await?,
+            config_reload_tx: None,
+            dropped_item_retention: Duration::MAX,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Checks the referential integrity of a copy of the catalog stored in the
+    /// data directory at `data_dir_path`, returning every violation found.
+    ///
+    /// The copy is migrated as if it were opened by this version of
+    /// Materialize, but the catalog in the data directory is not modified.
+    pub fn check_stored_consistency(
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<Vec<storage::Inconsistency>, anyhow::Error> {
+        let scratch = tempfile::tempdir()?;
+        storage::Connection::copy(data_dir_path, scratch.path(), encryption_key)?;
+        let experimental_mode = None;
+        let safe_mode = None;
+        let storage = storage::Connection::open(
+            scratch.path(),
+            storage::SqliteSynchronous::Off,
+            encryption_key,
+            experimental_mode,
+            safe_mode,
+        )?;
+        Ok(storage.check_consistency()?)
+    }
+
+    /// Describes the contents of a copy of the catalog stored in the data
+    /// directory at `data_dir_path`, as by [`storage::Connection::dump`],
+    /// which omits the contents of secrets.
+    ///
+    /// The catalog in the data directory is not modified.
+    pub fn dump_stored(
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<storage::CatalogDump, anyhow::Error> {
+        let scratch = tempfile::tempdir()?;
+        storage::Connection::copy(data_dir_path, scratch.path(), encryption_key)?;
+        let experimental_mode = None;
+        let safe_mode = None;
+        let mut storage = storage::Connection::open(
+            scratch.path(),
+            storage::SqliteSynchronous::Off,
+            encryption_key,
+            experimental_mode,
+            safe_mode,
+        )?;
+        Ok(storage.dump()?)
+    }
+
     pub fn for_session<'a>(&'a self, session: &'a Session) -> ConnCatalog<'a> {
         let database = self
             .state
@@ -1701,8 +2302,8 @@ impl Catalog {
         builtin_lookup: F,
     ) -> Result<AllocatedBuiltinSystemIds<T>, Error>
     where
-        T: Copy + Fingerprint,
-        F: Fn(&T) -> Option<(GlobalId, u64)>,
+        T: Copy,
+        F: Fn(&T) -> Option<GlobalId>,
     {
         let new_builtin_amount = builtins
             .iter()
@@ -1720,15 +2321,9 @@ impl Catalog {
 
         let mut all_builtins = Vec::new();
         let mut new_builtins = Vec::new();
-        let mut migrated_builtins = Vec::new();
         for builtin in &builtins {
             match builtin_lookup(builtin) {
-                Some((id, fingerprint)) => {
-                    all_builtins.push((*builtin, id));
-                    if fingerprint != builtin.fingerprint() {
-                        migrated_builtins.push((*builtin, id));
-                    }
-                }
+                Some(id) => all_builtins.push((*builtin, id)),
                 None => {
                     let id = global_ids.next().expect("not enough global IDs");
                     all_builtins.push((*builtin, id));
@@ -1740,7 +2335,6 @@ impl Catalog {
         Ok(AllocatedBuiltinSystemIds {
             all_builtins,
             new_builtins,
-            migrated_builtins,
         })
     }
 
@@ -1748,6 +2342,138 @@ impl Catalog {
         self.storage().allocate_user_id()
     }
 
+    /// Writes a consistent snapshot of the catalog's storage to a new file at
+    /// `path`.
+    pub fn backup(&self, path: &Path) -> Result<(), Error> {
+        self.storage().backup(path)
+    }
+
+    /// Returns the values of the system variables that have been set with
+    /// `ALTER SYSTEM`, by name.
+    pub fn load_system_settings(&self) -> Result<BTreeMap<String, String>, Error> {
+        self.storage()
+            .load_settings(storage::SettingNamespace::System)
+    }
+
+    /// Durably sets the value of the system variable `name`, or removes its
+    /// value if `value` is `None`.
+    pub fn set_system_setting(&self, name: &str, value: Option<&str>) -> Result<(), Error> {
+        let mut storage = self.storage();
+        match value {
+            Some(value) => storage.set_setting(storage::SettingNamespace::System, name, &value),
+            None => storage.remove_setting(storage::SettingNamespace::System, name),
+        }
+    }
+
+    /// Returns the replication slots that are not temporary, by name.
+    pub fn load_replication_slots<T: DeserializeOwned>(
+        &self,
+    ) -> Result<BTreeMap<String, T>, Error> {
+        self.storage()
+            .load_settings(storage::SettingNamespace::ReplicationSlot)
+    }
+
+    /// Durably records the state of the replication slot `name`, or removes
+    /// the slot if `slot` is `None`.
+    pub fn set_replication_slot<T: Serialize>(
+        &self,
+        name: &str,
+        slot: Option<&T>,
+    ) -> Result<(), Error> {
+        let mut storage = self.storage();
+        match slot {
+            Some(slot) => {
+                storage.set_setting(storage::SettingNamespace::ReplicationSlot, name, slot)
+            }
+            None => storage.remove_setting(storage::SettingNamespace::ReplicationSlot, name),
+        }
+    }
+
+    /// Returns the statistics about user indexes, by index ID.
+    pub fn load_statistics<T: DeserializeOwned>(&self) -> Result<BTreeMap<String, T>, Error> {
+        self.storage()
+            .load_settings(storage::SettingNamespace::Statistics)
+    }
+
+    /// Durably records the statistics about the index `id`, or removes them
+    /// if `stats` is `None`.
+    pub fn set_statistics<T: Serialize>(
+        &self,
+        id: GlobalId,
+        stats: Option<&T>,
+    ) -> Result<(), Error> {
+        let mut storage = self.storage();
+        let name = id.to_string();
+        match stats {
+            Some(stats) => storage.set_setting(storage::SettingNamespace::Statistics, &name, stats),
+            None => storage.remove_setting(storage::SettingNamespace::Statistics, &name),
+        }
+    }
+
+    /// Checks the referential integrity of the catalog's storage, returning
+    /// every violation found.
+    pub fn check_consistency(&self) -> Result<Vec<storage::Inconsistency>, Error> {
+        self.storage().check_consistency()
+    }
+
+    /// Records that the current version of the secret `id` in secrets
+    /// storage is `version`, or that it is unknown if `version` is `None`.
+    ///
+    /// Returns the updates that reflect the new version in `mz_secrets`, if
+    /// the secret exists in the catalog. The version of a secret that is
+    /// about to be created may be recorded before the secret is created.
+    pub fn set_secret_version(
+        &mut self,
+        id: GlobalId,
+        version: Option<SecretVersion>,
+    ) -> Vec<BuiltinTableUpdate> {
+        let exists = self.state.entry_by_id.contains_key(&id);
+        let mut updates = vec![];
+        if exists {
+            updates.extend(self.state.pack_item_update(id, -1));
+        }
+        match version {
+            Some(version) => self.state.secret_versions.insert(id, version),
+            None => self.state.secret_versions.remove(&id),
+        };
+        if exists {
+            updates.extend(self.state.pack_item_update(id, 1));
+        }
+        updates
+    }
+
+    /// Returns the versions of the secrets with which the source or sink `id`
+    /// was last configured.
+    pub fn secret_uses(&self, id: GlobalId) -> BTreeMap<GlobalId, SecretVersion> {
+        self.state.secret_uses.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Records that the source or sink `id` was configured with the given
+    /// versions of the secrets that it uses.
+    ///
+    /// Returns the updates that reflect the versions in `mz_secret_uses`, if
+    /// the source or sink exists in the catalog.
+    pub fn set_secret_uses(
+        &mut self,
+        id: GlobalId,
+        versions: BTreeMap<GlobalId, SecretVersion>,
+    ) -> Vec<BuiltinTableUpdate> {
+        let exists = self.state.entry_by_id.contains_key(&id);
+        let mut updates = vec![];
+        if exists {
+            updates.extend(self.state.pack_item_update(id, -1));
+        }
+        if versions.is_empty() {
+            self.state.secret_uses.remove(&id);
+        } else {
+            self.state.secret_uses.insert(id, versions);
+        }
+        if exists {
+            updates.extend(self.state.pack_item_update(id, 1));
+        }
+        updates
+    }
+
     pub fn allocate_oid(&mut self) -> Result<u32, Error> {
         self.state.allocate_oid()
     }
@@ -1853,25 +2579,84 @@ impl Catalog {
         )
     }
 
-    pub fn resolve_compute_instance(
-        &self,
-        name: &str,
-    ) -> Result<&ComputeInstance, SqlCatalogError> {
-        let id = self
-            .state
-            .compute_instances_by_name
-            .get(name)
-            .ok_or_else(|| SqlCatalogError::UnknownComputeInstance(name.to_string()))?;
-        Ok(&self.state.compute_instances_by_id[id])
-    }
-
-    /// Resolves [`PartialObjectName`] into a [`CatalogEntry`].
+    /// Resolves `name` to a dropped item whose definition has not yet expired.
     ///
-    /// If `name` does not specify a database, the `current_database` is used.
-    /// If `name` does not specify a schema, then the schemas in `search_path`
-    /// are searched in order.
-    #[allow(clippy::useless_let_if_seq)]
-    pub fn resolve(
+    /// The schemas are searched as in [`Catalog::resolve`]. If a schema
+    /// contains several dropped items with the name, the most recently dropped
+    /// item is chosen.
+    pub fn resolve_dropped_item(
+        &self,
+        current_database: Option<&DatabaseId>,
+        search_path: &Vec<(ResolvedDatabaseSpecifier, SchemaSpecifier)>,
+        name: &PartialObjectName,
+        conn_id: u32,
+    ) -> Result<&DroppedItem, SqlCatalogError> {
+        let schemas = match &name.schema {
+            Some(schema_name) => {
+                let schema = self.resolve_schema(
+                    current_database,
+                    name.database.as_deref(),
+                    schema_name,
+                    conn_id,
+                )?;
+                vec![(schema.name.database.clone(), schema.id.clone())]
+            }
+            None => search_path.to_vec(),
+        };
+
+        let cutoff = self.dropped_item_cutoff((self.config().now)());
+        for (database_spec, schema_spec) in schemas {
+            let full_name = self
+                .resolve_full_name(
+                    &QualifiedObjectName {
+                        qualifiers: ObjectQualifiers {
+                            database_spec,
+                            schema_spec,
+                        },
+                        item: name.item.clone(),
+                    },
+                    Some(conn_id),
+                )
+                .to_string();
+            let item = self
+                .state
+                .dropped_items
+                .values()
+                .filter(|item| item.name == full_name && item.dropped_at >= cutoff)
+                .max_by_key(|item| item.dropped_at);
+            if let Some(item) = item {
+                return Ok(item);
+            }
+        }
+        Err(SqlCatalogError::UnknownItem(name.to_string()))
+    }
+
+    /// Returns the time before which items must have been dropped for their
+    /// definitions to have expired at time `now`.
+    fn dropped_item_cutoff(&self, now: EpochMillis) -> EpochMillis {
+        let retention = self.state.dropped_item_retention.as_millis();
+        now.saturating_sub(u64::try_from(retention).unwrap_or(u64::MAX))
+    }
+
+    pub fn resolve_compute_instance(
+        &self,
+        name: &str,
+    ) -> Result<&ComputeInstance, SqlCatalogError> {
+        let id = self
+            .state
+            .compute_instances_by_name
+            .get(name)
+            .ok_or_else(|| SqlCatalogError::UnknownComputeInstance(name.to_string()))?;
+        Ok(&self.state.compute_instances_by_id[id])
+    }
+
+    /// Resolves [`PartialObjectName`] into a [`CatalogEntry`].
+    ///
+    /// If `name` does not specify a database, the `current_database` is used.
+    /// If `name` does not specify a schema, then the schemas in `search_path`
+    /// are searched in order.
+    #[allow(clippy::useless_let_if_seq)]
+    pub fn resolve(
         &self,
         get_schema_entries: fn(&Schema) -> &BTreeMap<String, GlobalId>,
         current_database: Option<&DatabaseId>,
@@ -1956,6 +2741,123 @@ impl Catalog {
         self.state.get_entry(id)
     }
 
+    pub fn try_get_role(&self, name: &str) -> Option<&Role> {
+        self.state.roles.get(name)
+    }
+
+    pub fn try_get_role_by_id(&self, id: i64) -> Option<&Role> {
+        self.state.roles.values().find(|role| role.id == id)
+    }
+
+    /// Returns the session defaults for the role with ID `role_id`, keyed by
+    /// the name of the session variable. Defaults set for the role itself take
+    /// precedence over those set for every role.
+    pub fn role_settings(&self, role_id: i64) -> BTreeMap<&str, &str> {
+        [None, Some(role_id)]
+            .iter()
+            .filter_map(|role_id| self.state.role_settings.get(role_id))
+            .flatten()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    /// Returns the ID of the role that owns `object`, or `None` if the object
+    /// does not exist.
+    pub fn owner_id(&self, object: OwnedObjectId) -> Option<i64> {
+        match object {
+            OwnedObjectId::Database(id) => self.state.database_by_id.get(&id).map(|db| db.owner_id),
+            OwnedObjectId::Schema(id) => self
+                .state
+                .database_by_id
+                .values()
+                .find_map(|db| db.schemas_by_id.get(&id))
+                .map(|schema| schema.owner_id),
+            OwnedObjectId::ComputeInstance(id) => self
+                .state
+                .compute_instances_by_id
+                .get(&id)
+                .map(|instance| instance.owner_id),
+            OwnedObjectId::Item(id) => self.try_get_entry(&id).map(|entry| entry.owner_id()),
+        }
+    }
+
+    /// Returns the name of `object`, fully qualified if it is a schema or an
+    /// item.
+    pub fn object_name(&self, object: OwnedObjectId) -> String {
+        match object {
+            OwnedObjectId::Database(id) => self.get_database(&id).name.clone(),
+            OwnedObjectId::Schema(id) => {
+                let db = self
+                    .state
+                    .database_by_id
+                    .values()
+                    .find(|db| db.schemas_by_id.contains_key(&id))
+                    .expect("schema must exist");
+                format!("{}.{}", db.name, db.schemas_by_id[&id].name.schema)
+            }
+            OwnedObjectId::ComputeInstance(id) => self.state.get_compute_instance(id).name.clone(),
+            OwnedObjectId::Item(id) => {
+                let entry = self.get_entry(&id);
+                self.resolve_full_name(entry.name(), entry.conn_id())
+                    .to_string()
+            }
+        }
+    }
+
+    /// Returns the objects that the role `role_id` owns, each described by
+    /// its type and name.
+    fn owned_objects(&self, role_id: i64) -> Vec<String> {
+        let mut objects = vec![];
+        for db in self.state.database_by_id.values() {
+            if db.owner_id == role_id {
+                objects.push(format!("database {}", db.name.quoted()));
+            }
+            for schema in db.schemas_by_id.values() {
+                if schema.owner_id == role_id {
+                    let name = format!("{}.{}", db.name, schema.name.schema);
+                    objects.push(format!("schema {}", name.quoted()));
+                }
+            }
+        }
+        for instance in self.state.compute_instances_by_id.values() {
+            if instance.owner_id == role_id {
+                objects.push(format!("cluster {}", instance.name.quoted()));
+            }
+        }
+        for entry in self.state.entry_by_id.values() {
+            if entry.owner_id == role_id {
+                let name = self.resolve_full_name(entry.name(), entry.conn_id());
+                objects.push(format!(
+                    "{} {}",
+                    entry.item_type(),
+                    name.to_string().quoted()
+                ));
+            }
+        }
+        objects
+    }
+
+    /// Reports whether `role` owns `object`. Superusers are treated as owning
+    /// every object.
+    pub fn is_owner(&self, role: &Role, object: OwnedObjectId) -> bool {
+        role.restrictions.superuser || self.owner_id(object) == Some(role.id)
+    }
+
+    /// Reports whether `role` holds `privilege` on `object`, either because it
+    /// owns the object or because the privilege was granted to it or to every
+    /// role.
+    pub fn has_privilege(&self, role: &Role, object: OwnedObjectId, privilege: Privilege) -> bool {
+        self.is_owner(role, object)
+            || self
+                .state
+                .privileges
+                .get(&object)
+                .map_or(false, |privileges| {
+                    privileges.contains(&(None, privilege))
+                        || privileges.contains(&(Some(role.id), privilege))
+                })
+    }
+
     pub fn get_entry_by_oid(&self, oid: &u32) -> &CatalogEntry {
         self.state.get_entry_by_oid(oid)
     }
@@ -2000,6 +2902,8 @@ impl Catalog {
                 oid,
                 items: BTreeMap::new(),
                 functions: BTreeMap::new(),
+                owner_id: MZ_SYSTEM.id,
+                timestamps: None,
             },
         );
         Ok(())
@@ -2128,8 +3032,12 @@ impl Catalog {
         Ok(temporary_ids)
     }
 
+    /// Applies `ops` to the catalog atomically, recording the changes they
+    /// make in `mz_audit_events` on behalf of the user of `session`, or of
+    /// the system user if no session is provided.
     pub fn transact<F, T>(
         &mut self,
+        session: Option<&Session>,
         ops: Vec<Op>,
         f: F,
     ) -> Result<(Vec<BuiltinTableUpdate>, T), CoordError>
@@ -2155,6 +3063,13 @@ impl Catalog {
                 id: i64,
                 oid: u32,
                 name: String,
+                restrictions: RoleRestrictions,
+                password: Option<String>,
+            },
+            UpdateRole {
+                name: String,
+                restrictions: RoleRestrictions,
+                password: Option<String>,
             },
             CreateComputeInstance {
                 id: ComputeInstanceId,
@@ -2192,6 +3107,33 @@ impl Catalog {
                 id: ComputeInstanceId,
                 config: InstanceConfig,
             },
+            RenameDatabase {
+                id: DatabaseId,
+                to_name: String,
+            },
+            RenameSchema {
+                database_id: DatabaseId,
+                schema_id: SchemaId,
+                to_name: String,
+            },
+            Comment {
+                id: GlobalId,
+                column_position: Option<usize>,
+                comment: Option<String>,
+            },
+            SetRoleSetting {
+                role_id: Option<i64>,
+                name: String,
+                value: Option<String>,
+            },
+            UpdatePrivilege {
+                object: OwnedObjectId,
+                role_id: Option<i64>,
+                privilege: Privilege,
+                granted: bool,
+            },
+            AddDroppedItem(DroppedItem),
+            RemoveDroppedItems(Vec<GlobalId>),
         }
 
         let drop_ids: HashSet<_> = ops
@@ -2212,18 +3154,77 @@ impl Catalog {
             })
             .collect();
         let temporary_ids = self.temporary_ids(&ops, temporary_drops)?;
+        let now = (self.config().now)();
         let mut builtin_table_updates = vec![];
         let mut actions = Vec::with_capacity(ops.len());
         let mut storage = self.storage();
         let mut tx = storage.transaction()?;
+        let user = session.map_or(SYSTEM_USER, |session| session.user());
+        let owner_id = self
+            .state
+            .roles
+            .get(user)
+            .map_or(MZ_SYSTEM.id, |role| role.id);
+        let mut pending_item_writes = PendingItemWrites::new(owner_id, now);
+
+        // Definitions of dropped items that have outlived the retention period
+        // are purged with the next write to the catalog.
+        let dropped_item_cutoff = self.dropped_item_cutoff(now);
+        let expired_dropped_items: Vec<_> = self
+            .state
+            .dropped_items
+            .values()
+            .filter(|item| item.dropped_at < dropped_item_cutoff)
+            .collect();
+        if !expired_dropped_items.is_empty() {
+            tx.purge_dropped_items(dropped_item_cutoff)?;
+            for item in &expired_dropped_items {
+                builtin_table_updates.push(self.state.pack_dropped_item_update(item, -1));
+            }
+            actions.push(Action::RemoveDroppedItems(
+                expired_dropped_items.iter().map(|item| item.id).collect(),
+            ));
+        }
+
+        let mut audit_events = vec![];
+        let mut secret_audit_events = vec![];
+        let mut audit =
+            |event_type, object_type: String, object_id: String, name: String, details| {
+                audit_events.push(AuditEvent {
+                    id: 0,
+                    event_type,
+                    object_type,
+                    object_id,
+                    name,
+                    user: user.into(),
+                    occurred_at: now,
+                    details,
+                })
+            };
         for op in ops {
+            match op {
+                Op::CreateItem { .. }
+                | Op::DropItem(_)
+                | Op::RenameItem { .. }
+                | Op::UpdateItem { .. } => (),
+                // Other operations may depend on the item writes before them,
+                // e.g., a schema can only be removed once its items are.
+                _ => pending_item_writes.flush(&tx)?,
+            }
             actions.extend(match op {
                 Op::CreateDatabase {
                     name,
                     oid,
                     public_schema_oid,
                 } => {
-                    let database_id = tx.insert_database(&name)?;
+                    let database_id = tx.insert_database(&name, owner_id, now)?;
+                    audit(
+                        AuditEventType::Create,
+                        "database".into(),
+                        database_id.to_string(),
+                        name.clone(),
+                        json!({}),
+                    );
                     vec![
                         Action::CreateDatabase {
                             id: database_id,
@@ -2231,7 +3232,7 @@ impl Catalog {
                             name,
                         },
                         Action::CreateSchema {
-                            id: tx.insert_schema(database_id, DEFAULT_SCHEMA)?,
+                            id: tx.insert_schema(database_id, DEFAULT_SCHEMA, owner_id, now)?,
                             oid: public_schema_oid,
                             database_id,
                             schema_name: DEFAULT_SCHEMA.to_string(),
@@ -2256,23 +3257,70 @@ impl Catalog {
                             )));
                         }
                     };
+                    let id = tx.insert_schema(database_id, &schema_name, owner_id, now)?;
+                    audit(
+                        AuditEventType::Create,
+                        "schema".into(),
+                        id.to_string(),
+                        format!("{}.{}", self.get_database(&database_id).name, schema_name),
+                        json!({}),
+                    );
                     vec![Action::CreateSchema {
-                        id: tx.insert_schema(database_id, &schema_name)?,
+                        id,
                         oid,
                         database_id,
                         schema_name,
                     }]
                 }
-                Op::CreateRole { name, oid } => {
+                Op::CreateRole {
+                    name,
+                    oid,
+                    restrictions,
+                    password,
+                } => {
                     if is_reserved_name(&name) {
                         return Err(CoordError::Catalog(Error::new(
                             ErrorKind::ReservedRoleName(name),
                         )));
                     }
+                    let id = tx.insert_role(&name, &restrictions, password.as_deref(), now)?;
+                    audit(
+                        AuditEventType::Create,
+                        "role".into(),
+                        id.to_string(),
+                        name.clone(),
+                        json!({}),
+                    );
                     vec![Action::CreateRole {
-                        id: tx.insert_role(&name)?,
+                        id,
                         oid,
                         name,
+                        restrictions,
+                        password,
+                    }]
+                }
+                Op::UpdateRole {
+                    name,
+                    restrictions,
+                    password,
+                } => {
+                    let role = &self.state.roles[&name];
+                    tx.update_role(role.id, &restrictions, password.as_deref(), now)?;
+                    audit(
+                        AuditEventType::Alter,
+                        "role".into(),
+                        role.id.to_string(),
+                        name.clone(),
+                        json!({
+                            "superuser": restrictions.superuser,
+                            "login": restrictions.login,
+                            "password_changed": password != role.password,
+                        }),
+                    );
+                    vec![Action::UpdateRole {
+                        name,
+                        restrictions,
+                        password,
                     }]
                 }
                 Op::CreateComputeInstance {
@@ -2285,8 +3333,22 @@ impl Catalog {
                             ErrorKind::ReservedClusterName(name),
                         )));
                     }
+                    let id = tx.insert_compute_instance(
+                        &name,
+                        &config,
+                        &introspection_sources,
+                        owner_id,
+                        now,
+                    )?;
+                    audit(
+                        AuditEventType::Create,
+                        "cluster".into(),
+                        id.to_string(),
+                        name.clone(),
+                        json!({ "config": config }),
+                    );
                     vec![Action::CreateComputeInstance {
-                        id: tx.insert_compute_instance(&name, &config, &introspection_sources)?,
+                        id,
                         name,
                         config,
                         introspection_sources,
@@ -2327,7 +3389,18 @@ impl Catalog {
                         }
                         let schema_id = name.qualifiers.schema_spec.clone().into();
                         let serialized_item = self.serialize_item(&item);
-                        tx.insert_item(id, schema_id, &name.item, &serialized_item)?;
+                        pending_item_writes.insert(
+                            &tx,
+                            (id, schema_id, name.item.clone(), serialized_item),
+                            item.uses(),
+                        )?;
+                        audit(
+                            AuditEventType::Create,
+                            item.typ().to_string(),
+                            id.to_string(),
+                            self.resolve_full_name(&name, None).to_string(),
+                            json!({}),
+                        );
                     }
 
                     vec![Action::CreateItem {
@@ -2339,7 +3412,18 @@ impl Catalog {
                 }
                 Op::DropDatabase { id } => {
                     tx.remove_database(&id)?;
+                    audit(
+                        AuditEventType::Drop,
+                        "database".into(),
+                        id.to_string(),
+                        self.get_database(&id).name.clone(),
+                        json!({}),
+                    );
                     builtin_table_updates.push(self.state.pack_database_update(&id, -1));
+                    builtin_table_updates.extend(
+                        self.state
+                            .pack_object_privileges_update(OwnedObjectId::Database(id), -1),
+                    );
                     vec![Action::DropDatabase { id }]
                 }
                 Op::DropSchema {
@@ -2347,19 +3431,67 @@ impl Catalog {
                     schema_id,
                 } => {
                     tx.remove_schema(&database_id, &schema_id)?;
+                    let schema = self.get_schema(
+                        &ResolvedDatabaseSpecifier::Id(database_id.clone()),
+                        &SchemaSpecifier::Id(schema_id.clone()),
+                        SYSTEM_CONN_ID,
+                    );
+                    audit(
+                        AuditEventType::Drop,
+                        "schema".into(),
+                        schema_id.to_string(),
+                        format!(
+                            "{}.{}",
+                            self.get_database(&database_id).name,
+                            schema.name.schema
+                        ),
+                        json!({}),
+                    );
                     builtin_table_updates.push(self.state.pack_schema_update(
                         &ResolvedDatabaseSpecifier::Id(database_id.clone()),
                         &schema_id,
                         -1,
                     ));
+                    builtin_table_updates.extend(
+                        self.state
+                            .pack_object_privileges_update(OwnedObjectId::Schema(schema_id), -1),
+                    );
                     vec![Action::DropSchema {
                         database_id,
                         schema_id,
                     }]
                 }
                 Op::DropRole { name } => {
+                    // Objects cannot outlive their owner, so they must be
+                    // dropped first.
+                    if let Some(role) = self.state.roles.get(&name) {
+                        let objects = self.owned_objects(role.id);
+                        if !objects.is_empty() {
+                            return Err(Error::new(ErrorKind::RoleOwnsObjects {
+                                role: name,
+                                objects,
+                            })
+                            .into());
+                        }
+                    }
                     tx.remove_role(&name)?;
+                    audit(
+                        AuditEventType::Drop,
+                        "role".into(),
+                        self.state.roles[&name].id.to_string(),
+                        name.clone(),
+                        json!({}),
+                    );
                     builtin_table_updates.push(self.state.pack_role_update(&name, -1));
+                    let role_id = Some(self.state.roles[&name].id);
+                    for (object, privileges) in &self.state.privileges {
+                        for (_, privilege) in privileges.iter().filter(|(r, _)| *r == role_id) {
+                            builtin_table_updates.push(
+                                self.state
+                                    .pack_privilege_update(*object, role_id, *privilege, -1),
+                            );
+                        }
+                    }
                     vec![Action::DropRole { name }]
                 }
                 Op::DropComputeInstance { name } => {
@@ -2367,15 +3499,57 @@ impl Catalog {
                         coord_bail!("cannot drop the default cluster");
                     }
                     tx.remove_compute_instance(&name)?;
-                    builtin_table_updates.push(self.state.pack_compute_instance_update(&name, -1));
+                    audit(
+                        AuditEventType::Drop,
+                        "cluster".into(),
+                        self.state.compute_instances_by_name[&name].to_string(),
+                        name.clone(),
+                        json!({}),
+                    );
+                    builtin_table_updates
+                        .extend(self.state.pack_compute_instance_update(&name, -1));
                     vec![Action::DropComputeInstance { name }]
                 }
                 Op::DropItem(id) => {
-                    if !self.get_entry(&id).item().is_temporary() {
-                        tx.remove_item(id)?;
+                    let mut actions = vec![Action::DropItem(id)];
+                    let entry = self.get_entry(&id);
+                    if !entry.item().is_temporary() {
+                        let name = self
+                            .resolve_full_name(entry.name(), entry.conn_id())
+                            .to_string();
+                        let item_type = entry.item().typ().to_string();
+                        pending_item_writes.remove(&tx, (id, name.clone(), item_type.clone()))?;
+                        audit(
+                            AuditEventType::Drop,
+                            item_type.clone(),
+                            id.to_string(),
+                            name.clone(),
+                            json!({}),
+                        );
+                        actions.push(Action::AddDroppedItem(DroppedItem {
+                            id,
+                            name,
+                            item_type,
+                            create_sql: entry.create_sql().to_owned(),
+                            dropped_at: now,
+                        }));
                     }
                     builtin_table_updates.extend(self.state.pack_item_update(id, -1));
-                    vec![Action::DropItem(id)]
+                    for (column_position, comment) in
+                        self.state.comments.get(&id).into_iter().flatten()
+                    {
+                        builtin_table_updates.push(self.state.pack_comment_update(
+                            id,
+                            *column_position,
+                            comment,
+                            -1,
+                        ));
+                    }
+                    builtin_table_updates.extend(
+                        self.state
+                            .pack_object_privileges_update(OwnedObjectId::Item(id), -1),
+                    );
+                    actions
                 }
                 Op::RenameItem {
                     id,
@@ -2444,7 +3618,11 @@ impl Catalog {
 
                         if !item.is_temporary() {
                             let serialized_item = self.serialize_item(&to_item);
-                            tx.update_item(*id, &dependent_item.name().item, &serialized_item)?;
+                            pending_item_writes.update(
+                                &tx,
+                                (*id, dependent_item.name().item.clone(), serialized_item),
+                                to_item.uses(),
+                            )?;
                         }
                         builtin_table_updates.extend(self.state.pack_item_update(*id, -1));
 
@@ -2455,7 +3633,18 @@ impl Catalog {
                         });
                     }
                     if !item.is_temporary() {
-                        tx.update_item(id, &to_full_name.item, &serialized_item)?;
+                        pending_item_writes.update(
+                            &tx,
+                            (id, to_full_name.item.clone(), serialized_item),
+                            item.uses(),
+                        )?;
+                        audit(
+                            AuditEventType::Alter,
+                            item.typ().to_string(),
+                            id.to_string(),
+                            to_full_name.to_string(),
+                            json!({ "previous_name": current_full_name.to_string() }),
+                        );
                     }
                     builtin_table_updates.extend(self.state.pack_item_update(id, -1));
                     actions.push(Action::UpdateItem {
@@ -2470,7 +3659,19 @@ impl Catalog {
 
                     if !to_item.is_temporary() {
                         let serialized_item = self.serialize_item(&to_item);
-                        tx.update_item(id, &entry.name().item, &serialized_item)?;
+                        pending_item_writes.update(
+                            &tx,
+                            (id, entry.name().item.clone(), serialized_item),
+                            to_item.uses(),
+                        )?;
+                        audit(
+                            AuditEventType::Alter,
+                            to_item.typ().to_string(),
+                            id.to_string(),
+                            self.resolve_full_name(entry.name(), entry.conn_id())
+                                .to_string(),
+                            json!({}),
+                        );
                     }
 
                     builtin_table_updates.extend(self.state.pack_item_update(id, -1));
@@ -2482,7 +3683,14 @@ impl Catalog {
                     }]
                 }
                 Op::UpdateComputeInstanceConfig { id, config } => {
-                    tx.update_compute_instance_config(id, &config)?;
+                    tx.update_compute_instance_config(id, &config, now)?;
+                    audit(
+                        AuditEventType::Alter,
+                        "cluster".into(),
+                        id.to_string(),
+                        self.state.compute_instances_by_id[&id].name.clone(),
+                        json!({ "config": config }),
+                    );
                     let config = match config {
                         ComputeInstanceConfig::Local => InstanceConfig::Local,
                         ComputeInstanceConfig::Remote {
@@ -2508,11 +3716,287 @@ impl Catalog {
                             InstanceConfig::Managed { size }
                         }
                     };
+                    let name = &self.state.compute_instances_by_id[&id].name;
+                    builtin_table_updates.extend(self.state.pack_compute_instance_update(name, -1));
                     vec![Action::UpdateComputeInstanceConfig { id, config }]
                 }
+                Op::RenameDatabase { id, to_name } => {
+                    let from_name = self.get_database(&id).name.clone();
+                    tx.rename_database(&id, &to_name, now)?;
+                    audit(
+                        AuditEventType::Alter,
+                        "database".into(),
+                        id.to_string(),
+                        to_name.clone(),
+                        json!({ "previous_name": from_name }),
+                    );
+                    builtin_table_updates.push(self.state.pack_database_update(&id, -1));
+
+                    // Items refer to one another by fully qualified names,
+                    // so every reference to the database must follow it.
+                    let mut actions = vec![];
+                    for (item_id, to_item) in
+                        self.rewrite_user_items(&from_name, |create_stmt| {
+                            mz_sql::ast::transform::create_stmt_rename_database_refs(
+                                create_stmt,
+                                &from_name,
+                                &to_name,
+                            )
+                        })?
+                    {
+                        let entry = self.get_entry(&item_id);
+                        if !to_item.is_temporary() {
+                            let serialized_item = self.serialize_item(&to_item);
+                            pending_item_writes.update(
+                                &tx,
+                                (item_id, entry.name().item.clone(), serialized_item),
+                                to_item.uses(),
+                            )?;
+                        }
+                        builtin_table_updates.extend(self.state.pack_item_update(item_id, -1));
+                        actions.push(Action::UpdateItem {
+                            id: item_id,
+                            to_name: entry.name().clone(),
+                            to_item,
+                        });
+                    }
+                    actions.push(Action::RenameDatabase { id, to_name });
+                    actions
+                }
+                Op::RenameSchema {
+                    database_id,
+                    schema_id,
+                    to_name,
+                } => {
+                    if is_reserved_name(&to_name) {
+                        return Err(CoordError::Catalog(Error::new(
+                            ErrorKind::ReservedSchemaName(to_name),
+                        )));
+                    }
+                    let database_name = self.get_database(&database_id).name.clone();
+                    let from_name = self
+                        .get_schema(
+                            &ResolvedDatabaseSpecifier::Id(database_id.clone()),
+                            &SchemaSpecifier::Id(schema_id.clone()),
+                            SYSTEM_CONN_ID,
+                        )
+                        .name
+                        .schema
+                        .clone();
+                    tx.rename_schema(&database_id, &schema_id, &to_name, now)?;
+                    audit(
+                        AuditEventType::Alter,
+                        "schema".into(),
+                        schema_id.to_string(),
+                        format!("{}.{}", database_name, to_name),
+                        json!({
+                            "previous_name": format!("{}.{}", database_name, from_name),
+                        }),
+                    );
+                    builtin_table_updates.push(self.state.pack_schema_update(
+                        &ResolvedDatabaseSpecifier::Id(database_id.clone()),
+                        &schema_id,
+                        -1,
+                    ));
+
+                    let mut actions = vec![];
+                    let renamed = format!("{}.{}", database_name, from_name);
+                    for (item_id, to_item) in self.rewrite_user_items(&renamed, |create_stmt| {
+                        mz_sql::ast::transform::create_stmt_rename_schema_refs(
+                            create_stmt,
+                            &database_name,
+                            &from_name,
+                            &to_name,
+                        )
+                    })? {
+                        let entry = self.get_entry(&item_id);
+                        if !to_item.is_temporary() {
+                            let serialized_item = self.serialize_item(&to_item);
+                            pending_item_writes.update(
+                                &tx,
+                                (item_id, entry.name().item.clone(), serialized_item),
+                                to_item.uses(),
+                            )?;
+                        }
+                        builtin_table_updates.extend(self.state.pack_item_update(item_id, -1));
+                        actions.push(Action::UpdateItem {
+                            id: item_id,
+                            to_name: entry.name().clone(),
+                            to_item,
+                        });
+                    }
+                    actions.push(Action::RenameSchema {
+                        database_id,
+                        schema_id,
+                        to_name,
+                    });
+                    actions
+                }
+                Op::Comment {
+                    id,
+                    column_position,
+                    comment,
+                } => {
+                    let entry = self.get_entry(&id);
+                    let existing = self
+                        .state
+                        .comments
+                        .get(&id)
+                        .and_then(|comments| comments.get(&column_position));
+                    // Comments on temporary items are never persisted, like
+                    // the items themselves.
+                    if !entry.item().is_temporary() {
+                        match (existing, &comment) {
+                            (None, Some(comment)) => {
+                                tx.insert_comment(id, column_position, comment)?
+                            }
+                            (Some(_), Some(comment)) => {
+                                tx.update_comment(id, column_position, comment)?
+                            }
+                            (Some(_), None) => tx.delete_comment(id, column_position)?,
+                            (None, None) => (),
+                        }
+                        audit(
+                            AuditEventType::Alter,
+                            entry.item().typ().to_string(),
+                            id.to_string(),
+                            self.resolve_full_name(entry.name(), entry.conn_id())
+                                .to_string(),
+                            json!({
+                                "column_position": column_position,
+                                "comment": comment,
+                            }),
+                        );
+                    }
+                    if let Some(existing) = existing {
+                        builtin_table_updates.push(self.state.pack_comment_update(
+                            id,
+                            column_position,
+                            existing,
+                            -1,
+                        ));
+                    }
+                    vec![Action::Comment {
+                        id,
+                        column_position,
+                        comment,
+                    }]
+                }
+                Op::SetRoleSetting {
+                    role_id,
+                    name,
+                    value,
+                } => {
+                    tx.set_role_setting(role_id, &name, value.as_deref())?;
+                    // Defaults for every role are not attached to any one
+                    // object, so only changes to a single role are audited.
+                    if let Some(role_id) = role_id {
+                        if let Some(role) = self.state.roles.values().find(|r| r.id == role_id) {
+                            audit(
+                                AuditEventType::Alter,
+                                "role".into(),
+                                role_id.to_string(),
+                                role.name.clone(),
+                                json!({
+                                    "setting": name,
+                                    "value": value,
+                                }),
+                            );
+                        }
+                    }
+                    vec![Action::SetRoleSetting {
+                        role_id,
+                        name,
+                        value,
+                    }]
+                }
+                Op::UpdatePrivilege {
+                    object,
+                    role_id,
+                    privilege,
+                    granted,
+                } => {
+                    let held = self
+                        .state
+                        .privileges
+                        .get(&object)
+                        .map_or(false, |privileges| {
+                            privileges.contains(&(role_id, privilege))
+                        });
+                    if granted == held {
+                        // Granting a held privilege or revoking one that is not
+                        // held is not an error, as in PostgreSQL.
+                        vec![]
+                    } else {
+                        if granted {
+                            tx.grant_privilege(object, role_id, privilege)?;
+                        } else {
+                            tx.revoke_privilege(object, role_id, privilege)?;
+                        }
+                        let (object_type, object_id) = object.stored_id();
+                        let role = match role_id {
+                            Some(role_id) => {
+                                self.try_get_role_by_id(role_id).map(|r| r.name.clone())
+                            }
+                            None => Some("PUBLIC".into()),
+                        };
+                        audit(
+                            AuditEventType::Alter,
+                            object_type.into(),
+                            object_id,
+                            self.object_name(object),
+                            json!({
+                                "privilege": privilege.to_string(),
+                                "role": role,
+                                "granted": granted,
+                            }),
+                        );
+                        builtin_table_updates.push(self.state.pack_privilege_update(
+                            object,
+                            role_id,
+                            privilege,
+                            if granted { 1 } else { -1 },
+                        ));
+                        vec![Action::UpdatePrivilege {
+                            object,
+                            role_id,
+                            privilege,
+                            granted,
+                        }]
+                    }
+                }
+                Op::RemoveDroppedItem(id) => match self.state.dropped_items.get(&id) {
+                    // An expired definition has already been purged above.
+                    Some(item) if item.dropped_at >= dropped_item_cutoff => {
+                        tx.remove_dropped_item(id)?;
+                        builtin_table_updates.push(self.state.pack_dropped_item_update(item, -1));
+                        vec![Action::RemoveDroppedItems(vec![id])]
+                    }
+                    _ => vec![],
+                },
+                Op::RecordSecretOps(events) => {
+                    secret_audit_events.extend(events);
+                    vec![]
+                }
             });
         }
 
+        pending_item_writes.flush(&tx)?;
+
+        for event in &mut audit_events {
+            tx.insert_audit_event(event)?;
+            builtin_table_updates.push(self.state.pack_audit_event_update(event, 1));
+        }
+        if !secret_audit_events.is_empty() {
+            tx.insert_secret_audit_events(&mut secret_audit_events)?;
+            for event in &secret_audit_events {
+                builtin_table_updates.push(self.state.pack_secret_audit_event_update(event, 1));
+            }
+            for event in tx.purge_secret_audit_events(SECRET_AUDIT_EVENTS_RETAINED)? {
+                builtin_table_updates.push(self.state.pack_secret_audit_event_update(&event, -1));
+            }
+        }
+
         // Prepare a candidate catalog state.
         let mut state = self.state.clone();
 
@@ -2528,6 +4012,8 @@ impl Catalog {
                             oid,
                             schemas_by_id: BTreeMap::new(),
                             schemas_by_name: BTreeMap::new(),
+                            owner_id,
+                            timestamps: ObjectTimestamps::created(now),
                         },
                     );
                     state.database_by_name.insert(name.clone(), id.clone());
@@ -2553,6 +4039,8 @@ impl Catalog {
                             oid,
                             items: BTreeMap::new(),
                             functions: BTreeMap::new(),
+                            owner_id,
+                            timestamps: Some(ObjectTimestamps::created(now)),
                         },
                     );
                     db.schemas_by_name.insert(schema_name.clone(), id.clone());
@@ -2563,7 +4051,13 @@ impl Catalog {
                     ));
                 }
 
-                Action::CreateRole { id, oid, name } => {
+                Action::CreateRole {
+                    id,
+                    oid,
+                    name,
+                    restrictions,
+                    password,
+                } => {
                     info!("create role {}", name);
                     state.roles.insert(
                         name.clone(),
@@ -2571,11 +4065,27 @@ impl Catalog {
                             name: name.clone(),
                             id,
                             oid,
+                            restrictions,
+                            password,
+                            timestamps: Some(ObjectTimestamps::created(now)),
                         },
                     );
                     builtin_table_updates.push(state.pack_role_update(&name, 1));
                 }
 
+                Action::UpdateRole {
+                    name,
+                    restrictions,
+                    password,
+                } => {
+                    builtin_table_updates.push(state.pack_role_update(&name, -1));
+                    let role = state.roles.get_mut(&name).expect("role known to exist");
+                    role.restrictions = restrictions;
+                    role.password = password;
+                    role.timestamps = role.timestamps.map(|t| t.updated(now));
+                    builtin_table_updates.push(state.pack_role_update(&name, 1));
+                }
+
                 Action::CreateComputeInstance {
                     id,
                     name,
@@ -2589,8 +4099,10 @@ impl Catalog {
                         config,
                         None,
                         introspection_sources,
+                        owner_id,
+                        ObjectTimestamps::created(now),
                     );
-                    builtin_table_updates.push(state.pack_compute_instance_update(&name, 1));
+                    builtin_table_updates.extend(state.pack_compute_instance_update(&name, 1));
                 }
 
                 Action::CreateItem {
@@ -2599,11 +4111,13 @@ impl Catalog {
                     name,
                     item,
                 } => {
-                    state.insert_item(id, oid, name, item);
+                    let timestamps = ObjectTimestamps::created(now);
+                    state.insert_item(id, oid, name, item, owner_id, Some(timestamps));
                     builtin_table_updates.extend(state.pack_item_update(id, 1));
                 }
 
                 Action::DropDatabase { id } => {
+                    state.privileges.remove(&OwnedObjectId::Database(id));
                     let db = state.database_by_id.get(&id).unwrap();
                     state.database_by_name.remove(db.name());
                     state.database_by_id.remove(&id);
@@ -2613,6 +4127,7 @@ impl Catalog {
                     database_id,
                     schema_id,
                 } => {
+                    state.privileges.remove(&OwnedObjectId::Schema(schema_id));
                     let db = state.database_by_id.get_mut(&database_id).unwrap();
                     let schema = db.schemas_by_id.get(&schema_id).unwrap();
                     db.schemas_by_name.remove(&schema.name.schema);
@@ -2620,7 +4135,14 @@ impl Catalog {
                 }
 
                 Action::DropRole { name } => {
-                    if state.roles.remove(&name).is_some() {
+                    if let Some(role) = state.roles.remove(&name) {
+                        state.role_settings.remove(&Some(role.id));
+                        for privileges in state.privileges.values_mut() {
+                            privileges.retain(|(role_id, _)| *role_id != Some(role.id));
+                        }
+                        state
+                            .privileges
+                            .retain(|_, privileges| !privileges.is_empty());
                         info!("drop role {}", name);
                     }
                 }
@@ -2643,6 +4165,10 @@ impl Catalog {
                 }
 
                 Action::DropItem(id) => {
+                    state.comments.remove(&id);
+                    state.secret_versions.remove(&id);
+                    state.secret_uses.remove(&id);
+                    state.privileges.remove(&OwnedObjectId::Item(id));
                     let metadata = state.entry_by_id.remove(&id).unwrap();
                     if !metadata.item.is_placeholder() {
                         info!(
@@ -2711,30 +4237,180 @@ impl Catalog {
                     let mut new_entry = old_entry.clone();
                     new_entry.name = to_name;
                     new_entry.item = to_item;
+                    new_entry.timestamps = old_entry.timestamps.map(|t| t.updated(now));
                     schema.items.insert(new_entry.name().item.clone(), id);
                     state.entry_by_id.insert(id, new_entry.clone());
                     builtin_table_updates.extend(state.pack_item_update(id, 1));
                 }
 
                 Action::UpdateComputeInstanceConfig { id, config } => {
-                    state.compute_instances_by_id.get_mut(&id).unwrap().config = config;
+                    let instance = state.compute_instances_by_id.get_mut(&id).unwrap();
+                    instance.config = config;
+                    instance.timestamps = instance.timestamps.updated(now);
+                    let name = instance.name.clone();
+                    builtin_table_updates.extend(state.pack_compute_instance_update(&name, 1));
                 }
-            }
-        }
 
-        let result = f(&state)?;
+                Action::RenameDatabase { id, to_name } => {
+                    let db = state.database_by_id.get_mut(&id).unwrap();
+                    info!("rename database {} to {}", db.name, to_name);
+                    let from_name = std::mem::replace(&mut db.name, to_name.clone());
+                    db.timestamps = db.timestamps.updated(now);
+                    state.database_by_name.remove(&from_name);
+                    state.database_by_name.insert(to_name, id.clone());
+                    builtin_table_updates.push(state.pack_database_update(&id, 1));
+                }
 
-        // The user closure was successful, apply the updates.
-        tx.commit().map_err(|err| CoordError::Catalog(err.into()))?;
-        // Dropping here keeps the mutable borrow on self, preventing us accidentally
-        // mutating anything until after f is executed.
-        drop(storage);
-        self.state = state;
-        self.transient_revision += 1;
+                Action::RenameSchema {
+                    database_id,
+                    schema_id,
+                    to_name,
+                } => {
+                    let db = state.database_by_id.get_mut(&database_id).unwrap();
+                    let schema = db.schemas_by_id.get_mut(&schema_id).unwrap();
+                    info!(
+                        "rename schema {}.{} to {}",
+                        db.name, schema.name.schema, to_name
+                    );
+                    let from_name = std::mem::replace(&mut schema.name.schema, to_name.clone());
+                    schema.timestamps = schema.timestamps.map(|t| t.updated(now));
+                    db.schemas_by_name.remove(&from_name);
+                    db.schemas_by_name.insert(to_name, schema_id.clone());
+                    builtin_table_updates.push(state.pack_schema_update(
+                        &ResolvedDatabaseSpecifier::Id(database_id),
+                        &schema_id,
+                        1,
+                    ));
+                }
+
+                Action::Comment {
+                    id,
+                    column_position,
+                    comment,
+                } => match comment {
+                    Some(comment) => {
+                        builtin_table_updates.push(state.pack_comment_update(
+                            id,
+                            column_position,
+                            &comment,
+                            1,
+                        ));
+                        state
+                            .comments
+                            .entry(id)
+                            .or_default()
+                            .insert(column_position, comment);
+                    }
+                    None => {
+                        if let Some(comments) = state.comments.get_mut(&id) {
+                            comments.remove(&column_position);
+                            if comments.is_empty() {
+                                state.comments.remove(&id);
+                            }
+                        }
+                    }
+                },
+
+                Action::SetRoleSetting {
+                    role_id,
+                    name,
+                    value,
+                } => match value {
+                    Some(value) => {
+                        state
+                            .role_settings
+                            .entry(role_id)
+                            .or_default()
+                            .insert(name, value);
+                    }
+                    None => {
+                        if let Some(settings) = state.role_settings.get_mut(&role_id) {
+                            settings.remove(&name);
+                            if settings.is_empty() {
+                                state.role_settings.remove(&role_id);
+                            }
+                        }
+                    }
+                },
+
+                Action::UpdatePrivilege {
+                    object,
+                    role_id,
+                    privilege,
+                    granted,
+                } => {
+                    if granted {
+                        state
+                            .privileges
+                            .entry(object)
+                            .or_default()
+                            .insert((role_id, privilege));
+                    } else if let Some(privileges) = state.privileges.get_mut(&object) {
+                        privileges.remove(&(role_id, privilege));
+                        if privileges.is_empty() {
+                            state.privileges.remove(&object);
+                        }
+                    }
+                }
+
+                Action::AddDroppedItem(item) => {
+                    builtin_table_updates.push(state.pack_dropped_item_update(&item, 1));
+                    state.dropped_items.insert(item.id, item);
+                }
+
+                Action::RemoveDroppedItems(ids) => {
+                    for id in ids {
+                        state.dropped_items.remove(&id);
+                    }
+                }
+            }
+        }
+
+        let result = f(&state)?;
+
+        // The user closure was successful, apply the updates.
+        tx.commit().map_err(CoordError::Catalog)?;
+        // Dropping here keeps the mutable borrow on self, preventing us accidentally
+        // mutating anything until after f is executed.
+        drop(storage);
+        self.state = state;
+        self.transient_revision += 1;
 
         Ok((builtin_table_updates, result))
     }
 
+    /// Rewrites the definitions of all user items with `rewrite`, which renames
+    /// references to the database or schema named `renamed`. Returns the IDs
+    /// and new definitions of the items whose definitions changed.
+    fn rewrite_user_items<F>(
+        &self,
+        renamed: &str,
+        rewrite: F,
+    ) -> Result<Vec<(GlobalId, CatalogItem)>, Error>
+    where
+        F: Fn(&mut Statement<Raw>) -> Result<(), String>,
+    {
+        let mut items = vec![];
+        for entry in self.entries() {
+            if !entry.id().is_user() {
+                continue;
+            }
+            let item = entry.item().rewrite_create_sql(&rewrite).map_err(|e| {
+                Error::new(ErrorKind::from(AmbiguousRename {
+                    depender: self
+                        .resolve_full_name(entry.name(), entry.conn_id())
+                        .to_string(),
+                    dependee: renamed.to_string(),
+                    message: e,
+                }))
+            })?;
+            if let Some(item) = item {
+                items.push((entry.id(), item));
+            }
+        }
+        Ok(items)
+    }
+
     fn serialize_item(&self, item: &CatalogItem) -> Vec<u8> {
         let item = match item {
             CatalogItem::Table(table) => SerializedCatalogItem::V1 {
@@ -2779,6 +4455,18 @@ impl Catalog {
                 table_persist_name: None,
                 source_persist_details: None,
             },
+            CatalogItem::Publication(publication) => SerializedCatalogItem::V1 {
+                create_sql: publication.create_sql.clone(),
+                eval_env: None,
+                table_persist_name: None,
+                source_persist_details: None,
+            },
+            CatalogItem::Task(task) => SerializedCatalogItem::V1 {
+                create_sql: task.create_sql.clone(),
+                eval_env: None,
+                table_persist_name: None,
+                source_persist_details: None,
+            },
             CatalogItem::Func(_) => unreachable!("cannot serialize functions yet"),
         };
         serde_json::to_vec(&item).expect("catalog serialization cannot fail")
@@ -2839,6 +4527,7 @@ impl Catalog {
                     connector: source.connector,
                     persist_details: source_persist_details,
                     desc: source.desc,
+                    depends_on: source.depends_on,
                 })
             }
             Plan::CreateView(CreateViewPlan { view, .. }) => {
@@ -2853,15 +4542,18 @@ impl Catalog {
                     depends_on: view.depends_on,
                 })
             }
-            Plan::CreateIndex(CreateIndexPlan { index, .. }) => CatalogItem::Index(Index {
-                create_sql: index.create_sql,
-                on: index.on,
-                keys: index.keys,
-                conn_id: None,
-                depends_on: index.depends_on,
-                enabled: self.index_enabled_by_default(&id),
-                compute_instance: index.compute_instance,
-            }),
+            Plan::CreateIndex(CreateIndexPlan { index, options, .. }) => {
+                CatalogItem::Index(Index {
+                    create_sql: index.create_sql,
+                    on: index.on,
+                    keys: index.keys,
+                    conn_id: None,
+                    depends_on: index.depends_on,
+                    enabled: self.index_enabled_by_default(&id),
+                    compute_instance: index.compute_instance,
+                    logical_compaction_window: Index::logical_compaction_window(&options),
+                })
+            }
             Plan::CreateSink(CreateSinkPlan {
                 sink,
                 with_snapshot,
@@ -2886,6 +4578,17 @@ impl Catalog {
             Plan::CreateSecret(CreateSecretPlan { secret, .. }) => CatalogItem::Secret(Secret {
                 create_sql: secret.create_sql,
             }),
+            Plan::CreatePublication(CreatePublicationPlan { publication, .. }) => {
+                CatalogItem::Publication(Publication {
+                    create_sql: publication.create_sql,
+                    views: publication.views,
+                })
+            }
+            Plan::CreateTask(CreateTaskPlan { task, .. }) => CatalogItem::Task(Task {
+                create_sql: task.create_sql,
+                schedule: task.schedule,
+                depends_on: task.depends_on,
+            }),
             _ => bail!("catalog entry generated inappropriate plan"),
         })
     }
@@ -2951,7 +4654,7 @@ impl Catalog {
     }
 }
 
-fn is_reserved_name(name: &str) -> bool {
+pub(crate) fn is_reserved_name(name: &str) -> bool {
     name.starts_with("mz_") || name.starts_with("pg_")
 }
 
@@ -2970,6 +4673,15 @@ pub enum Op {
     CreateRole {
         name: String,
         oid: u32,
+        restrictions: RoleRestrictions,
+        /// The SCRAM-SHA-256 verifier of the role's password, if it has one.
+        password: Option<String>,
+    },
+    /// Replaces the restrictions and the password verifier of the named role.
+    UpdateRole {
+        name: String,
+        restrictions: RoleRestrictions,
+        password: Option<String>,
     },
     CreateComputeInstance {
         name: String,
@@ -3012,6 +4724,137 @@ pub enum Op {
         id: ComputeInstanceId,
         config: ComputeInstanceConfig,
     },
+    RenameDatabase {
+        id: DatabaseId,
+        to_name: String,
+    },
+    RenameSchema {
+        database_id: DatabaseId,
+        schema_id: SchemaId,
+        to_name: String,
+    },
+    /// Sets the comment on an item, or on one of its columns if
+    /// `column_position` is specified. A `comment` of `None` removes the
+    /// existing comment, if any.
+    Comment {
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: Option<String>,
+    },
+    /// Sets the default value of a session variable for the identified role,
+    /// or for every role if `role_id` is `None`. A `value` of `None` removes
+    /// the existing default, if any.
+    SetRoleSetting {
+        role_id: Option<i64>,
+        name: String,
+        value: Option<String>,
+    },
+    /// Grants `privilege` on `object` to the identified role, or to every role
+    /// if `role_id` is `None`, or revokes it if `granted` is false.
+    UpdatePrivilege {
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+        granted: bool,
+    },
+    /// Forgets the definition of the identified dropped item, e.g., once the
+    /// item has been restored.
+    RemoveDroppedItem(GlobalId),
+    /// Records operations on secrets storage in `mz_secret_audit_events`,
+    /// forgetting the operations that precede the most recent
+    /// [`SECRET_AUDIT_EVENTS_RETAINED`].
+    RecordSecretOps(Vec<SecretAuditEvent>),
+}
+
+/// Writes to items in a catalog transaction that have not yet been applied to
+/// storage.
+///
+/// Consecutive writes of the same kind are buffered so that they can be applied
+/// with one batch operation, e.g., when dropping a schema that contains many
+/// views. Writes are still applied in the order in which they were requested,
+/// as an item can, e.g., only be created with the name of another item once
+/// that item has been removed.
+///
+/// The dependencies of inserted and updated items are written along with
+/// them.
+#[derive(Debug)]
+struct PendingItemWrites {
+    owner_id: i64,
+    now: EpochMillis,
+    inserts: Vec<storage::NewItem>,
+    removes: Vec<storage::RemovedItem>,
+    updates: Vec<storage::ItemUpdate>,
+    dependencies: Vec<storage::ItemDependencies>,
+}
+
+impl PendingItemWrites {
+    fn new(owner_id: i64, now: EpochMillis) -> PendingItemWrites {
+        PendingItemWrites {
+            owner_id,
+            now,
+            inserts: vec![],
+            removes: vec![],
+            updates: vec![],
+            dependencies: vec![],
+        }
+    }
+
+    fn insert(
+        &mut self,
+        tx: &storage::Transaction,
+        item: storage::NewItem,
+        uses: &[GlobalId],
+    ) -> Result<(), Error> {
+        if !self.removes.is_empty() || !self.updates.is_empty() {
+            self.flush(tx)?;
+        }
+        self.dependencies.push((item.0, uses.to_vec()));
+        self.inserts.push(item);
+        Ok(())
+    }
+
+    fn remove(
+        &mut self,
+        tx: &storage::Transaction,
+        item: storage::RemovedItem,
+    ) -> Result<(), Error> {
+        if !self.inserts.is_empty() || !self.updates.is_empty() {
+            self.flush(tx)?;
+        }
+        self.removes.push(item);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        tx: &storage::Transaction,
+        update: storage::ItemUpdate,
+        uses: &[GlobalId],
+    ) -> Result<(), Error> {
+        // A batch can update each item at most once.
+        if !self.inserts.is_empty()
+            || !self.removes.is_empty()
+            || self.updates.iter().any(|(id, ..)| *id == update.0)
+        {
+            self.flush(tx)?;
+        }
+        self.dependencies.push((update.0, uses.to_vec()));
+        self.updates.push(update);
+        Ok(())
+    }
+
+    /// Applies all buffered writes to `tx`.
+    fn flush(&mut self, tx: &storage::Transaction) -> Result<(), Error> {
+        tx.insert_items(&self.inserts, self.owner_id, self.now)?;
+        tx.remove_items(&self.removes, self.now)?;
+        tx.update_items(&self.updates, self.now)?;
+        tx.set_item_dependencies(&self.dependencies)?;
+        self.inserts.clear();
+        self.removes.clear();
+        self.updates.clear();
+        self.dependencies.clear();
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -3328,6 +5171,18 @@ impl SessionCatalog for ConnCatalog<'_> {
         )?)
     }
 
+    fn resolve_dropped_item(
+        &self,
+        name: &PartialObjectName,
+    ) -> Result<&dyn mz_sql::catalog::CatalogDroppedItem, SqlCatalogError> {
+        Ok(self.catalog.resolve_dropped_item(
+            self.database.as_ref(),
+            &self.search_path,
+            name,
+            self.conn_id,
+        )?)
+    }
+
     fn try_get_item(&self, id: &GlobalId) -> Option<&dyn mz_sql::catalog::CatalogItem> {
         self.catalog
             .try_get_entry(id)
@@ -3406,6 +5261,20 @@ impl mz_sql::catalog::CatalogRole for Role {
     }
 }
 
+impl mz_sql::catalog::CatalogDroppedItem for DroppedItem {
+    fn id(&self) -> GlobalId {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn create_sql(&self) -> &str {
+        &self.create_sql
+    }
+}
+
 impl mz_sql::catalog::CatalogComputeInstance for ComputeInstance {
     fn name(&self) -> &str {
         &self.name
@@ -3454,6 +5323,8 @@ impl mz_sql::catalog::CatalogItem for CatalogEntry {
             CatalogItem::Index(Index { create_sql, .. }) => create_sql,
             CatalogItem::Type(Type { create_sql, .. }) => create_sql,
             CatalogItem::Secret(Secret { create_sql, .. }) => create_sql,
+            CatalogItem::Publication(Publication { create_sql, .. }) => create_sql,
+            CatalogItem::Task(Task { create_sql, .. }) => create_sql,
             CatalogItem::Func(_) => "TODO",
         }
     }
@@ -3495,17 +5366,62 @@ impl mz_sql::catalog::CatalogItem for CatalogEntry {
     }
 }
 
+/// Orders `items` so that every item follows the items on which it depends,
+/// according to the stored `dependencies`, and otherwise by ID.
+///
+/// An item's definition refers to the items on which it depends, so those
+/// items must be loaded first, and IDs need not be allocated in that order.
+/// Items whose dependencies form a cycle are ordered by ID, and fail to load
+/// if their definitions cannot be planned in that order.
+fn order_by_dependencies(
+    items: Vec<storage::StoredItem>,
+    dependencies: &BTreeMap<GlobalId, BTreeSet<GlobalId>>,
+) -> Vec<storage::StoredItem> {
+    let mut pending: BTreeMap<_, _> = items.into_iter().map(|item| (item.0, item)).collect();
+    let ids: Vec<_> = pending.keys().copied().collect();
+    let mut ordered = Vec::with_capacity(ids.len());
+    let mut visited = HashSet::new();
+    for id in ids {
+        // Each item is pushed once to visit its dependencies, and again to be
+        // emitted after them. An explicit stack avoids recursing through long
+        // chains of views.
+        let mut stack = vec![(id, false)];
+        while let Some((id, emit)) = stack.pop() {
+            if emit {
+                ordered.extend(pending.remove(&id));
+            } else if pending.contains_key(&id) && visited.insert(id) {
+                stack.push((id, true));
+                for dependency in dependencies.get(&id).into_iter().flatten().rev() {
+                    stack.push((*dependency, false));
+                }
+            }
+        }
+    }
+    ordered
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
     use tempfile::TempDir;
 
-    use mz_ore::now::NOW_ZERO;
+    use mz_expr::GlobalId;
+    use mz_ore::now::{NowFn, NOW_ZERO};
     use mz_sql::names::{
         ObjectQualifiers, PartialObjectName, QualifiedObjectName, ResolvedDatabaseSpecifier,
         SchemaSpecifier,
     };
+    use mz_sql::plan::{ComputeInstanceReplicaConfig, RoleRestrictions};
 
-    use crate::catalog::{Catalog, Op, MZ_CATALOG_SCHEMA, PG_CATALOG_SCHEMA};
+    use crate::catalog::storage::{self, Inconsistency, SettingNamespace};
+    use crate::catalog::{
+        migrate, Catalog, ObjectTimestamps, Op, SerializedCatalogItem, MZ_CATALOG_SCHEMA,
+        PG_CATALOG_SCHEMA, SYSTEM_CONN_ID,
+    };
     use crate::session::Session;
 
     /// System sessions have an empty `search_path` so it's necessary to
@@ -3594,6 +5510,7 @@ mod tests {
         assert_eq!(catalog.transient_revision(), 1);
         catalog
             .transact(
+                None,
                 vec![Op::CreateDatabase {
                     name: "test".to_string(),
                     oid: 1,
@@ -3610,4 +5527,836 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_drop_role_that_owns_objects() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        catalog
+            .transact(
+                None,
+                vec![Op::CreateRole {
+                    name: "alice".into(),
+                    oid: 1,
+                    restrictions: RoleRestrictions::default(),
+                    password: None,
+                }],
+                |_| Ok(()),
+            )
+            .unwrap();
+        let session = Session::new(1, "alice".into());
+        catalog
+            .transact(
+                Some(&session),
+                vec![Op::CreateDatabase {
+                    name: "d".into(),
+                    oid: 2,
+                    public_schema_oid: 3,
+                }],
+                |_| Ok(()),
+            )
+            .unwrap();
+
+        let drop_role = || {
+            vec![Op::DropRole {
+                name: "alice".into(),
+            }]
+        };
+        let err = match catalog.transact(None, drop_role(), |_| Ok(())) {
+            Ok(_) => panic!("unexpected success"),
+            Err(e) => e,
+        };
+        assert_eq!(
+            err.to_string(),
+            "role \"alice\" cannot be dropped because it owns objects"
+        );
+        assert_eq!(
+            err.detail().as_deref(),
+            Some("owner of database \"d\"\nowner of schema \"d.public\"")
+        );
+        assert!(catalog.state().roles.contains_key("alice"));
+
+        // Once its objects have been dropped, the role can be dropped too.
+        let id = catalog.resolve_database("d")?.id.clone();
+        let ops = catalog.drop_database_ops(Some(id));
+        catalog.transact(None, ops, |_| Ok(())).unwrap();
+        catalog.transact(None, drop_role(), |_| Ok(())).unwrap();
+        assert!(!catalog.state().roles.contains_key("alice"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_consistency() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        assert_eq!(catalog.check_consistency()?, vec![]);
+        drop(catalog);
+
+        // Corrupt the catalog in ways that SQLite does not prevent.
+        let conn = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        let schema_id: i64 = conn.query_row(
+            "UPDATE schemas SET database_id = 999 WHERE name = 'public' RETURNING id",
+            [],
+            |row| row.get(0),
+        )?;
+        let id: i64 = conn.query_row(
+            "INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint)
+            SELECT 'mz_catalog', 'mz_bogus', id, 0 FROM system_gid_mapping
+            WHERE schema_name = 'mz_catalog' AND object_name = 'mz_views'
+            RETURNING id",
+            [],
+            |row| row.get(0),
+        )?;
+        drop(conn);
+
+        let inconsistencies = Catalog::check_stored_consistency(data_dir.path(), None)?;
+        assert_eq!(
+            inconsistencies,
+            vec![
+                Inconsistency::DanglingDatabaseReference {
+                    schema_id,
+                    database_id: 999,
+                },
+                Inconsistency::DuplicateGlobalId {
+                    id: GlobalId::System(id as u64),
+                    objects: vec![
+                        "builtin mz_catalog.mz_bogus".into(),
+                        "builtin mz_catalog.mz_views".into(),
+                    ],
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_item_consistency() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        let schema_id = catalog.resolve_database("materialize")?.schemas_by_name["public"];
+        let (a, b, c) = {
+            let mut storage = catalog.storage();
+            let a = storage.allocate_user_id()?;
+            let b = storage.allocate_user_id()?;
+            let c = GlobalId::User(1 << 40);
+            let tx = storage.transaction()?;
+            for (id, name) in [(a, "a"), (b, "b"), (c, "c")] {
+                tx.insert_item(id, schema_id, name, &[], 1, 0)?;
+            }
+            tx.commit()?;
+            (a, b, c)
+        };
+        // Only the item whose ID was never allocated is inconsistent so far.
+        assert_eq!(
+            catalog.check_consistency()?,
+            vec![Inconsistency::UnallocatedGlobalId {
+                id: c,
+                object: "item c".into(),
+            }]
+        );
+        drop(catalog);
+
+        // Detach items from their schemas, and give an introspection source
+        // index the ID of a builtin.
+        let conn = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        conn.execute("UPDATE items SET schema_id = 999 WHERE name = 'a'", [])?;
+        conn.execute("UPDATE items SET schema_id = NULL WHERE name = 'b'", [])?;
+        let builtin_id: i64 = conn.query_row(
+            "SELECT id FROM system_gid_mapping
+            WHERE schema_name = 'mz_catalog' AND object_name = 'mz_views'",
+            [],
+            |row| row.get(0),
+        )?;
+        let (compute_id, index_name): (i64, String) = conn.query_row(
+            "UPDATE compute_introspection_source_indexes SET index_id = ?
+            WHERE rowid = (SELECT min(rowid) FROM compute_introspection_source_indexes)
+            RETURNING compute_id, name",
+            [builtin_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        drop(conn);
+
+        let inconsistencies = Catalog::check_stored_consistency(data_dir.path(), None)?;
+        assert_eq!(
+            inconsistencies,
+            vec![
+                Inconsistency::DanglingSchemaReference {
+                    item: a,
+                    schema_id: Some(999),
+                },
+                Inconsistency::DanglingSchemaReference {
+                    item: b,
+                    schema_id: None,
+                },
+                Inconsistency::DuplicateGlobalId {
+                    id: GlobalId::System(builtin_id as u64),
+                    objects: vec![
+                        "builtin mz_catalog.mz_views".into(),
+                        format!(
+                            "introspection source index {} of cluster {}",
+                            index_name, compute_id
+                        ),
+                    ],
+                },
+                Inconsistency::UnallocatedGlobalId {
+                    id: c,
+                    object: "item c".into(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_open_read_only() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+
+        // The catalog can be read while it is open for writing, and the reader
+        // sees the writer's changes.
+        let mut storage = storage::Connection::open_read_only(data_dir.path(), None)?;
+        catalog.transact(
+            None,
+            vec![Op::CreateDatabase {
+                name: "test".to_string(),
+                oid: 1,
+                public_schema_oid: 2,
+            }],
+            |_catalog| Ok(()),
+        )?;
+        let databases: Vec<_> = storage
+            .load_databases()?
+            .into_iter()
+            .map(|(_, name, _, _)| name)
+            .collect();
+        assert_eq!(databases, vec!["materialize", "test"]);
+        assert_eq!(storage.cluster_id(), catalog.config().cluster_id);
+
+        // The catalog cannot be modified.
+        let mut tx = storage.transaction()?;
+        assert!(tx.insert_database("bogus", 1, 0).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migration_checksums() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let open = || {
+            storage::Connection::open(
+                data_dir.path(),
+                storage::SqliteSynchronous::Off,
+                None,
+                None,
+                None,
+            )
+        };
+        let count_migrations = |sqlite: &rusqlite::Connection| -> Result<i64, rusqlite::Error> {
+            sqlite.query_row(
+                "SELECT count(*) FROM migrations",
+                rusqlite::params![],
+                |row| row.get(0),
+            )
+        };
+        drop(open()?);
+        let sqlite = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        let applied = count_migrations(&sqlite)?;
+        let unrecorded: i64 = sqlite.query_row(
+            "SELECT count(*) FROM migrations WHERE applied_at IS NULL",
+            rusqlite::params![],
+            |row| row.get(0),
+        )?;
+        assert_eq!(unrecorded, 0);
+
+        // Migrations applied before checksums were recorded are recorded when
+        // the catalog is next opened.
+        sqlite.execute_batch("DELETE FROM migrations")?;
+        drop(open()?);
+        assert_eq!(count_migrations(&sqlite)?, applied);
+
+        // The catalog is not opened if an applied migration has changed.
+        sqlite.execute_batch("UPDATE migrations SET checksum = 'bogus' WHERE version = 0")?;
+        assert_eq!(
+            open().unwrap_err().to_string(),
+            "catalog migration 0 has been modified since it was applied"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_unplannable_items() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        let schema_id = catalog.resolve_database("materialize")?.schemas_by_name["public"];
+        let items = [
+            (
+                1,
+                "a",
+                "CREATE VIEW materialize.public.a AS SELECT * FROM materialize.public.t",
+            ),
+            (
+                2,
+                "b",
+                "CREATE VIEW materialize.public.b AS SELECT * FROM materialize.public.u",
+            ),
+            (3, "c", "CREATE VIEW materialize.public.c AS SELECT 1"),
+        ];
+        let serialize = |create_sql: &str| {
+            serde_json::to_vec(&SerializedCatalogItem::V1 {
+                create_sql: create_sql.into(),
+                eval_env: None,
+                table_persist_name: None,
+                source_persist_details: None,
+            })
+            .unwrap()
+        };
+        {
+            let mut storage = catalog.storage();
+            let tx = storage.transaction()?;
+            for (id, name, create_sql) in items {
+                tx.insert_item(
+                    GlobalId::User(id),
+                    schema_id,
+                    name,
+                    &serialize(create_sql),
+                    1,
+                    0,
+                )?;
+            }
+            tx.commit()?;
+        }
+
+        // Every item that cannot be planned is reported, rather than just the
+        // first, and no definition is rewritten.
+        let err = migrate::migrate(&mut catalog).unwrap_err().to_string();
+        assert!(
+            err.starts_with("2 catalog items cannot be planned by this version:"),
+            "{}",
+            err
+        );
+        assert!(err.contains("materialize.public.a (u1)"), "{}", err);
+        assert!(err.contains("materialize.public.b (u2)"), "{}", err);
+        assert!(!err.contains("(u3)"), "{}", err);
+        let stored: Vec<_> = catalog
+            .storage()
+            .load_items()?
+            .into_iter()
+            .map(|(_, _, def, _, _)| def)
+            .collect();
+        assert_eq!(
+            stored,
+            items
+                .iter()
+                .map(|(_, _, create_sql)| serialize(create_sql))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_settings() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut storage = storage::Connection::open(
+            data_dir.path(),
+            storage::SqliteSynchronous::Off,
+            None,
+            None,
+            None,
+        )?;
+        let system = SettingNamespace::System;
+
+        assert_eq!(storage.get_setting::<bool>(system, "enable_foo")?, None);
+        storage.set_setting(system, "enable_foo", &true)?;
+        storage.set_setting(system, "max_bar", &10)?;
+        assert_eq!(storage.get_setting(system, "enable_foo")?, Some(true));
+        assert_eq!(storage.get_setting(system, "max_bar")?, Some(10));
+        assert!(storage.get_setting::<bool>(system, "max_bar").is_err());
+        assert_eq!(
+            storage.load_settings::<serde_json::Value>(system)?,
+            [
+                ("enable_foo".to_string(), serde_json::json!(true)),
+                ("max_bar".to_string(), serde_json::json!(10)),
+            ]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+        );
+
+        storage.remove_setting(system, "enable_foo")?;
+        assert_eq!(storage.get_setting::<bool>(system, "enable_foo")?, None);
+        assert_eq!(storage.load_settings::<i64>(system)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_item_dependencies() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut storage = storage::Connection::open(
+            data_dir.path(),
+            storage::SqliteSynchronous::Off,
+            None,
+            None,
+            None,
+        )?;
+        let schema_id = storage
+            .load_schemas()?
+            .into_iter()
+            .find(|(_, name, database_id, ..)| name == "public" && database_id.is_some())
+            .unwrap()
+            .0;
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+
+        let tx = storage.transaction()?;
+        tx.insert_items(
+            &[
+                (a, schema_id, "a".into(), vec![]),
+                (b, schema_id, "b".into(), vec![]),
+            ],
+            1,
+            0,
+        )?;
+        tx.set_item_dependencies(&[(a, vec![GlobalId::System(1)]), (b, vec![a, a])])?;
+        assert_eq!(
+            tx.load_item_dependencies()?,
+            BTreeMap::from([
+                (a, BTreeSet::from([GlobalId::System(1)])),
+                (b, BTreeSet::from([a])),
+            ])
+        );
+
+        // Setting an item's dependencies replaces them, and removing an item
+        // removes them.
+        tx.set_item_dependencies(&[(a, vec![])])?;
+        assert_eq!(
+            tx.load_item_dependencies()?,
+            BTreeMap::from([(b, BTreeSet::from([a]))])
+        );
+        tx.remove_items(&[(b, "materialize.public.b".into(), "view".into())], 0)?;
+        assert_eq!(tx.load_item_dependencies()?, BTreeMap::new());
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_order_by_dependencies() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut storage = storage::Connection::open(
+            data_dir.path(),
+            storage::SqliteSynchronous::Off,
+            None,
+            None,
+            None,
+        )?;
+        let schema_id = storage
+            .load_schemas()?
+            .into_iter()
+            .find(|(_, name, database_id, ..)| name == "public" && database_id.is_some())
+            .unwrap()
+            .0;
+        let [a, b, c, d, e] = [1, 2, 3, 4, 5].map(GlobalId::User);
+
+        // `a` depends on `c`, which was allocated a later ID, and `d` and `e`
+        // depend on each other.
+        let tx = storage.transaction()?;
+        tx.insert_items(
+            &[a, b, c, d, e].map(|id| (id, schema_id, id.to_string(), vec![])),
+            1,
+            0,
+        )?;
+        tx.set_item_dependencies(&[
+            (a, vec![GlobalId::System(1), c]),
+            (b, vec![a]),
+            (d, vec![e]),
+            (e, vec![d]),
+        ])?;
+        let ordered: Vec<_> =
+            order_by_dependencies(tx.load_items()?, &tx.load_item_dependencies()?)
+                .into_iter()
+                .map(|item| item.0)
+                .collect();
+        assert_eq!(ordered, [c, a, b, e, d]);
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dropped_item_retention() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let now = Arc::new(AtomicU64::new(0));
+        let now_fn = {
+            let now = Arc::clone(&now);
+            NowFn::from(move || now.load(Ordering::SeqCst))
+        };
+        let catalog = Catalog::open_debug(data_dir.path(), now_fn.clone()).await?;
+        let schema_id = catalog.resolve_database("materialize")?.schemas_by_name["public"];
+        {
+            let mut storage = catalog.storage();
+            let tx = storage.transaction()?;
+            for (id, name) in [(1, "a"), (2, "b")] {
+                let definition = serde_json::to_vec(&SerializedCatalogItem::V1 {
+                    create_sql: format!("CREATE VIEW materialize.public.{} AS SELECT 1", name),
+                    eval_env: None,
+                    table_persist_name: None,
+                    source_persist_details: None,
+                })?;
+                tx.insert_item(GlobalId::User(id), schema_id, name, &definition, 1, 0)?;
+            }
+            tx.commit()?;
+        }
+        drop(catalog);
+
+        let retention = Duration::from_millis(1000);
+        let mut catalog = Catalog::open_debug_with_dropped_item_retention(
+            data_dir.path(),
+            now_fn.clone(),
+            retention,
+        )
+        .await?;
+        let stored_dropped_items = |catalog: &Catalog| -> Result<Vec<GlobalId>, anyhow::Error> {
+            Ok(catalog
+                .storage()
+                .load_dropped_items()?
+                .into_iter()
+                .map(|(id, ..)| id)
+                .collect())
+        };
+        let resolve_dropped_item = |catalog: &Catalog, name: &str| {
+            catalog
+                .resolve_dropped_item(
+                    None,
+                    &vec![],
+                    &PartialObjectName {
+                        database: Some("materialize".into()),
+                        schema: Some("public".into()),
+                        item: name.into(),
+                    },
+                    SYSTEM_CONN_ID,
+                )
+                .map(|item| item.id)
+        };
+
+        now.store(100, Ordering::SeqCst);
+        catalog
+            .transact(None, vec![Op::DropItem(GlobalId::User(1))], |_| Ok(()))
+            .unwrap();
+        now.store(600, Ordering::SeqCst);
+        catalog
+            .transact(None, vec![Op::DropItem(GlobalId::User(2))], |_| Ok(()))
+            .unwrap();
+        assert_eq!(
+            stored_dropped_items(&catalog)?,
+            vec![GlobalId::User(1), GlobalId::User(2)]
+        );
+        assert_eq!(resolve_dropped_item(&catalog, "a")?, GlobalId::User(1));
+
+        // Once the retention period has passed, the item can no longer be
+        // restored, and its definition is purged with the next write.
+        now.store(1200, Ordering::SeqCst);
+        assert!(resolve_dropped_item(&catalog, "a").is_err());
+        assert_eq!(resolve_dropped_item(&catalog, "b")?, GlobalId::User(2));
+        assert_eq!(
+            stored_dropped_items(&catalog)?,
+            vec![GlobalId::User(1), GlobalId::User(2)]
+        );
+        catalog
+            .transact(
+                None,
+                vec![Op::CreateDatabase {
+                    name: "d".into(),
+                    oid: 1,
+                    public_schema_oid: 2,
+                }],
+                |_| Ok(()),
+            )
+            .unwrap();
+        assert_eq!(stored_dropped_items(&catalog)?, vec![GlobalId::User(2)]);
+        assert_eq!(
+            catalog.state.dropped_items.keys().collect::<Vec<_>>(),
+            vec![&GlobalId::User(2)]
+        );
+
+        // Expired definitions are also purged when the catalog is opened.
+        drop(catalog);
+        now.store(1700, Ordering::SeqCst);
+        let catalog =
+            Catalog::open_debug_with_dropped_item_retention(data_dir.path(), now_fn, retention)
+                .await?;
+        assert_eq!(stored_dropped_items(&catalog)?, vec![]);
+        assert!(catalog.state.dropped_items.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_builtins() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        let schema_id = catalog.resolve_database("materialize")?.schemas_by_name["public"];
+        let (v, w) = (GlobalId::User(1), GlobalId::User(2));
+        {
+            let mut storage = catalog.storage();
+            let tx = storage.transaction()?;
+            for (id, name, create_sql) in [
+                (
+                    v,
+                    "v",
+                    "CREATE VIEW materialize.public.v AS SELECT * FROM mz_catalog.mz_views",
+                ),
+                (
+                    w,
+                    "w",
+                    "CREATE VIEW materialize.public.w AS SELECT * FROM pg_catalog.pg_namespace",
+                ),
+            ] {
+                let definition = serde_json::to_vec(&SerializedCatalogItem::V1 {
+                    create_sql: create_sql.into(),
+                    eval_env: None,
+                    table_persist_name: None,
+                    source_persist_details: None,
+                })?;
+                tx.insert_item(id, schema_id, name, &definition, 1, 0)?;
+            }
+            tx.commit()?;
+        }
+        drop(catalog);
+        // Records the views' dependencies.
+        let catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        {
+            let mut storage = catalog.storage();
+            let tx = storage.transaction()?;
+            tx.insert_comment(catalog.get_entry(&w).uses()[0], None, "namespaces")?;
+            tx.commit()?;
+        }
+        drop(catalog);
+
+        let load_gids = || -> Result<BTreeMap<String, (i64, Option<i64>)>, anyhow::Error> {
+            let sqlite = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+            let mut stmt = sqlite.prepare(
+                "SELECT schema_name || '.' || object_name, id, fingerprint FROM system_gid_mapping",
+            )?;
+            let gids = stmt
+                .query_map(rusqlite::params![], |row| {
+                    Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+                })?
+                .collect::<Result<_, _>>()?;
+            Ok(gids)
+        };
+        let before = load_gids()?;
+        assert!(before["pg_catalog.pg_namespace"].1.is_some());
+
+        // Pretend that one builtin's definition has changed, and that
+        // another's fingerprint predates stable fingerprints.
+        let sqlite = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        sqlite.execute(
+            "UPDATE system_gid_mapping SET fingerprint = fingerprint + 1
+            WHERE object_name = 'pg_namespace'",
+            rusqlite::params![],
+        )?;
+        sqlite.execute(
+            "UPDATE system_gid_mapping SET fingerprint = NULL WHERE object_name = 'mz_views'",
+            rusqlite::params![],
+        )?;
+        drop(sqlite);
+
+        let storage = storage::Connection::open(
+            data_dir.path(),
+            storage::SqliteSynchronous::Off,
+            None,
+            None,
+            None,
+        )?;
+        let fingerprints = [
+            (
+                "pg_catalog",
+                "pg_namespace",
+                before["pg_catalog.pg_namespace"].1.unwrap() as u64,
+            ),
+            (
+                "mz_catalog",
+                "mz_views",
+                before["mz_catalog.mz_views"].1.unwrap() as u64,
+            ),
+            (
+                "pg_catalog",
+                "pg_class",
+                before["pg_catalog.pg_class"].1.unwrap() as u64,
+            ),
+            ("mz_catalog", "mz_bogus", 0),
+        ];
+        let stale: Vec<_> = storage
+            .load_stale_system_gids(fingerprints)?
+            .into_keys()
+            .collect();
+        assert_eq!(
+            stale,
+            vec![("pg_catalog".to_string(), "pg_namespace".to_string())]
+        );
+        drop(storage);
+
+        // Only the stale builtin is re-created under a new ID, and the
+        // unknown fingerprint is filled in.
+        let catalog = Catalog::open_debug(data_dir.path(), NOW_ZERO.clone()).await?;
+        let after = load_gids()?;
+        for (name, (id, fingerprint)) in &after {
+            if name == "pg_catalog.pg_namespace" {
+                assert_ne!(*id, before[name].0);
+            } else {
+                assert_eq!(*id, before[name].0);
+            }
+            assert_eq!(*fingerprint, before[name].1);
+        }
+
+        // The views depend on the builtins' current IDs, and the comment and
+        // stored dependencies that referred to the re-created builtin were
+        // carried over to its new ID.
+        let mz_views = GlobalId::System(after["mz_catalog.mz_views"].0 as u64);
+        let pg_namespace = GlobalId::System(after["pg_catalog.pg_namespace"].0 as u64);
+        assert_eq!(catalog.get_entry(&v).uses(), [mz_views]);
+        assert_eq!(catalog.get_entry(&w).uses(), [pg_namespace]);
+        assert_eq!(
+            catalog.storage().transaction()?.load_item_dependencies()?,
+            BTreeMap::from([
+                (v, BTreeSet::from([mz_views])),
+                (w, BTreeSet::from([pg_namespace])),
+            ])
+        );
+        assert_eq!(
+            catalog.storage().load_comments()?,
+            vec![(pg_namespace, None, "namespaces".to_string())]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gid_blocks() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let open = || {
+            storage::Connection::open(
+                data_dir.path(),
+                storage::SqliteSynchronous::Off,
+                None,
+                None,
+                None,
+            )
+        };
+        let next_user_gid = || -> Result<i64, anyhow::Error> {
+            let sqlite = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+            Ok(sqlite.query_row(
+                "SELECT next_gid FROM user_gid_alloc",
+                rusqlite::params![],
+                |row| row.get(0),
+            )?)
+        };
+
+        // IDs are handed out from a block, which is allocated in storage all
+        // at once.
+        let mut storage = open()?;
+        storage.set_user_gid_block_size(10);
+        for i in 1..=3 {
+            assert_eq!(storage.allocate_user_id()?, GlobalId::User(i));
+        }
+        assert_eq!(next_user_gid()?, 11);
+
+        // Allocators are separate per namespace.
+        storage.allocate_system_ids(5)?;
+        assert_eq!(storage.allocate_user_id()?, GlobalId::User(4));
+        assert_eq!(next_user_gid()?, 11);
+
+        // The rest of the block is skipped after reopening.
+        drop(storage);
+        let mut storage = open()?;
+        assert_eq!(storage.allocate_user_id()?, GlobalId::User(11));
+        assert_eq!(next_user_gid()?, 12);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gid_wraparound() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let open = || {
+            storage::Connection::open(
+                data_dir.path(),
+                storage::SqliteSynchronous::Off,
+                None,
+                None,
+                None,
+            )
+        };
+        drop(open()?);
+        let sqlite = rusqlite::Connection::open(data_dir.path().join("catalog"))?;
+        sqlite.execute(
+            "UPDATE user_gid_alloc SET next_gid = ?",
+            rusqlite::params![i64::MAX - 1],
+        )?;
+
+        // A block that would extend past the largest ID is refused, without
+        // consuming any IDs.
+        let mut storage = open()?;
+        storage.set_user_gid_block_size(2);
+        assert!(storage.allocate_user_id().is_err());
+        storage.set_user_gid_block_size(1);
+        let id = u64::try_from(i64::MAX - 1)?;
+        assert_eq!(storage.allocate_user_id()?, GlobalId::User(id));
+        assert!(storage.allocate_user_id().is_err());
+
+        // Amounts that cannot be stored at all are refused too.
+        assert!(storage.allocate_system_ids(u64::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compute_replicas() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut storage = storage::Connection::open(
+            data_dir.path(),
+            storage::SqliteSynchronous::Off,
+            None,
+            None,
+            None,
+        )?;
+        let compute_id = storage.load_compute_instances()?[0].0;
+
+        let config = ComputeInstanceReplicaConfig::Managed { size: "1".into() };
+        let mut tx = storage.transaction()?;
+        let id = tx.insert_compute_replica(compute_id, "r1", &config, 1)?;
+        assert!(tx
+            .insert_compute_replica(compute_id, "r1", &config, 1)
+            .is_err());
+        let config = ComputeInstanceReplicaConfig::Managed { size: "2".into() };
+        tx.update_compute_replica_config(id, &config, 2)?;
+        tx.commit()?;
+
+        assert_eq!(
+            storage.load_compute_replicas()?,
+            vec![(
+                id,
+                compute_id,
+                "r1".to_string(),
+                config,
+                ObjectTimestamps {
+                    created_at: 1,
+                    updated_at: 2,
+                },
+            )]
+        );
+
+        // Dropping a compute instance drops its replicas.
+        let tx = storage.transaction()?;
+        assert!(tx.remove_compute_replica(compute_id, "r2").is_err());
+        tx.remove_compute_instance("default")?;
+        tx.commit()?;
+        assert!(storage.load_compute_replicas()?.is_empty());
+
+        Ok(())
+    }
 }
@@ -22,9 +22,8 @@
 //! More information about builtin system tables and types can be found in
 //! <https://materialize.com/docs/sql/system-tables/>.
 
-use differential_dataflow::Hashable;
 use lazy_static::lazy_static;
-use std::hash::Hash;
+use sha2::{Digest, Sha256};
 
 use mz_dataflow_types::logging::{DifferentialLog, LogVariant, MaterializedLog, TimelyLog};
 use mz_repr::{RelationDesc, ScalarType};
@@ -106,13 +105,39 @@ pub struct BuiltinRole {
     pub id: i64,
 }
 
+/// A hash of a builtin's definition, which is stored alongside its ID so that
+/// changes to the definition can be detected across versions.
 pub trait Fingerprint {
     fn fingerprint(&self) -> u64;
 }
 
-impl<T: Hash> Fingerprint for &T {
+/// Returns the first 64 bits of the SHA-256 digest of `definition`.
+///
+/// Unlike [`std::hash::Hash`], the result does not depend on the layout of
+/// any Rust type, so it is stable across versions of Materialize.
+fn stable_fingerprint(definition: &[u8]) -> u64 {
+    let digest = Sha256::digest(definition);
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digest has 32 bytes"))
+}
+
+impl Fingerprint for &BuiltinLog {
+    fn fingerprint(&self) -> u64 {
+        let desc = serde_json::to_vec(&self.variant.desc()).expect("desc is serializable");
+        stable_fingerprint(&desc)
+    }
+}
+
+impl Fingerprint for &BuiltinTable {
     fn fingerprint(&self) -> u64 {
-        self.hashed()
+        let definition =
+            serde_json::to_vec(&(&self.desc, self.persistent)).expect("desc is serializable");
+        stable_fingerprint(&definition)
+    }
+}
+
+impl Fingerprint for &BuiltinView {
+    fn fingerprint(&self) -> u64 {
+        stable_fingerprint(self.sql.as_bytes())
     }
 }
 
@@ -897,12 +922,30 @@ pub const MZ_PEEK_DURATIONS: BuiltinLog = BuiltinLog {
     variant: LogVariant::Materialized(MaterializedLog::PeekDuration),
 };
 
+pub const MZ_PEEK_DURATIONS_PER_MINUTE_INTERNAL: BuiltinLog = BuiltinLog {
+    name: "mz_peek_durations_per_minute_internal",
+    schema: MZ_CATALOG_SCHEMA,
+    variant: LogVariant::Materialized(MaterializedLog::PeekDurationPerMinute),
+};
+
 pub const MZ_SOURCE_INFO: BuiltinLog = BuiltinLog {
     name: "mz_source_info",
     schema: MZ_CATALOG_SCHEMA,
     variant: LogVariant::Materialized(MaterializedLog::SourceInfo),
 };
 
+pub const MZ_SOURCE_SCHEMA_CHANGES: BuiltinLog = BuiltinLog {
+    name: "mz_source_schema_changes",
+    schema: MZ_CATALOG_SCHEMA,
+    variant: LogVariant::Materialized(MaterializedLog::SourceSchemaChanges),
+};
+
+pub const MZ_SOURCE_UPSERT_STATISTICS: BuiltinLog = BuiltinLog {
+    name: "mz_source_upsert_statistics",
+    schema: MZ_CATALOG_SCHEMA,
+    variant: LogVariant::Materialized(MaterializedLog::UpsertStatistics),
+};
+
 pub const MZ_MESSAGE_COUNTS_RECEIVED_INTERNAL: BuiltinLog = BuiltinLog {
     name: "mz_message_counts_received_internal",
     schema: MZ_CATALOG_SCHEMA,
@@ -980,7 +1023,10 @@ lazy_static! {
         desc: RelationDesc::empty()
             .with_column("id", ScalarType::Int64.nullable(false))
             .with_column("oid", ScalarType::Oid.nullable(false))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_SCHEMAS: BuiltinTable = BuiltinTable {
@@ -990,7 +1036,10 @@ lazy_static! {
             .with_column("id", ScalarType::Int64.nullable(false))
             .with_column("oid", ScalarType::Oid.nullable(false))
             .with_column("database_id", ScalarType::Int64.nullable(true))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_COLUMNS: BuiltinTable = BuiltinTable {
@@ -1016,7 +1065,10 @@ lazy_static! {
             .with_column("on_id", ScalarType::String.nullable(false))
             .with_column("volatility", ScalarType::String.nullable(false))
             .with_column("enabled", ScalarType::Bool.nullable(false))
-            .with_column("cluster_id", ScalarType::Int64.nullable(false)),
+            .with_column("cluster_id", ScalarType::Int64.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_INDEX_COLUMNS: BuiltinTable = BuiltinTable {
@@ -1038,7 +1090,10 @@ lazy_static! {
             .with_column("oid", ScalarType::Oid.nullable(false))
             .with_column("schema_id", ScalarType::Int64.nullable(false))
             .with_column("name", ScalarType::String.nullable(false))
-            .with_column("persisted_name", ScalarType::String.nullable(true)),
+            .with_column("persisted_name", ScalarType::String.nullable(true))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_SOURCES: BuiltinTable = BuiltinTable {
@@ -1051,7 +1106,10 @@ lazy_static! {
             .with_column("name", ScalarType::String.nullable(false))
             .with_column("connector_type", ScalarType::String.nullable(false))
             .with_column("volatility", ScalarType::String.nullable(false))
-            .with_column("persisted_name", ScalarType::String.nullable(true)),
+            .with_column("persisted_name", ScalarType::String.nullable(true))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_SINKS: BuiltinTable = BuiltinTable {
@@ -1064,7 +1122,10 @@ lazy_static! {
             .with_column("name", ScalarType::String.nullable(false))
             .with_column("connector_type", ScalarType::String.nullable(false))
             .with_column("volatility", ScalarType::String.nullable(false))
-            .with_column("cluster_id", ScalarType::Int64.nullable(false)),
+            .with_column("cluster_id", ScalarType::Int64.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_VIEWS: BuiltinTable = BuiltinTable {
@@ -1076,7 +1137,10 @@ lazy_static! {
             .with_column("schema_id", ScalarType::Int64.nullable(false))
             .with_column("name", ScalarType::String.nullable(false))
             .with_column("volatility", ScalarType::String.nullable(false))
-            .with_column("definition", ScalarType::String.nullable(false)),
+            .with_column("definition", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_TYPES: BuiltinTable = BuiltinTable {
@@ -1086,7 +1150,10 @@ lazy_static! {
             .with_column("id", ScalarType::String.nullable(false))
             .with_column("oid", ScalarType::Oid.nullable(false))
             .with_column("schema_id", ScalarType::Int64.nullable(false))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_ARRAY_TYPES: BuiltinTable = BuiltinTable {
@@ -1127,7 +1194,9 @@ lazy_static! {
         desc: RelationDesc::empty()
             .with_column("id", ScalarType::Int64.nullable(false))
             .with_column("oid", ScalarType::Oid.nullable(false))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_PSEUDO_TYPES: BuiltinTable = BuiltinTable {
@@ -1199,7 +1268,34 @@ lazy_static! {
         schema: MZ_CATALOG_SCHEMA,
         desc: RelationDesc::empty()
             .with_column("id", ScalarType::Int64.nullable(false))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_CLUSTER_REPLICAS: BuiltinTable = BuiltinTable {
+        name: "mz_cluster_replicas",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("cluster_id", ScalarType::Int64.nullable(false))
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_CLUSTER_REPLICA_FRONTIERS: BuiltinTable = BuiltinTable {
+        name: "mz_cluster_replica_frontiers",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("cluster_id", ScalarType::Int64.nullable(false))
+            .with_column("replica", ScalarType::String.nullable(true))
+            .with_column("hydrated", ScalarType::Bool.nullable(false))
+            .with_column("serving", ScalarType::Bool.nullable(false))
+            .with_column("time", ScalarType::Int64.nullable(true))
+            .with_column("input_time", ScalarType::Int64.nullable(true)),
         persistent: false,
     };
     pub static ref MZ_SECRETS: BuiltinTable = BuiltinTable {
@@ -1207,8 +1303,205 @@ lazy_static! {
         schema: MZ_CATALOG_SCHEMA,
         desc: RelationDesc::empty()
             .with_column("id", ScalarType::String.nullable(false))
+            .with_column("oid", ScalarType::Oid.nullable(false))
+            .with_column("schema_id", ScalarType::Int64.nullable(false))
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("version", ScalarType::Int64.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_PUBLICATIONS: BuiltinTable = BuiltinTable {
+        name: "mz_publications",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::String.nullable(false))
+            .with_column("oid", ScalarType::Oid.nullable(false))
+            .with_column("schema_id", ScalarType::Int64.nullable(false))
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_PUBLICATION_VIEWS: BuiltinTable = BuiltinTable {
+        name: "mz_publication_views",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("publication_id", ScalarType::String.nullable(false))
+            .with_column("view_id", ScalarType::String.nullable(false))
+            .with_column("position", ScalarType::Int64.nullable(false)),
+        persistent: false,
+    };
+    pub static ref MZ_SCHEDULED_TASKS: BuiltinTable = BuiltinTable {
+        name: "mz_scheduled_tasks",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::String.nullable(false))
+            .with_column("oid", ScalarType::Oid.nullable(false))
             .with_column("schema_id", ScalarType::Int64.nullable(false))
-            .with_column("name", ScalarType::String.nullable(false)),
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("owner_id", ScalarType::Int64.nullable(false))
+            .with_column("schedule", ScalarType::Interval.nullable(false))
+            .with_column("statement", ScalarType::String.nullable(false))
+            .with_column("created_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("updated_at", ScalarType::TimestampTz.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_SCHEDULED_TASK_RUNS: BuiltinTable = BuiltinTable {
+        name: "mz_scheduled_task_runs",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("task_id", ScalarType::String.nullable(false))
+            .with_column("started_at", ScalarType::TimestampTz.nullable(false))
+            .with_column("finished_at", ScalarType::TimestampTz.nullable(false))
+            .with_column("status", ScalarType::String.nullable(false))
+            .with_column("error", ScalarType::String.nullable(true)),
+        persistent: false,
+    };
+    pub static ref MZ_SYSTEM_CONFIG: BuiltinTable = BuiltinTable {
+        name: "mz_system_config",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("value", ScalarType::String.nullable(true))
+            .with_column("source", ScalarType::String.nullable(false))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_INDEX_USAGE: BuiltinTable = BuiltinTable {
+        name: "mz_index_usage",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("index_id", ScalarType::String.nullable(false))
+            .with_column("dataflow_name", ScalarType::String.nullable(true))
+            .with_column("dataflow_imports", ScalarType::Int64.nullable(false))
+            .with_column("last_fast_path_peek", ScalarType::TimestampTz.nullable(true))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_ALL_DATAFLOW_STATEMENTS: BuiltinTable = BuiltinTable {
+        name: "mz_all_dataflow_statements",
+        schema: MZ_INTERNAL_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("export_id", ScalarType::String.nullable(false))
+            .with_column("dataflow_name", ScalarType::String.nullable(false))
+            .with_column("statement_id", ScalarType::Int64.nullable(false))
+            .with_column("session_id", ScalarType::Int32.nullable(true))
+            .with_column("user", ScalarType::String.nullable(true))
+            .with_column("sql", ScalarType::String.nullable(true))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_DATAFLOW_MEMORY: BuiltinTable = BuiltinTable {
+        name: "mz_dataflow_memory",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("cluster_id", ScalarType::Int64.nullable(false))
+            .with_column("dataflow_name", ScalarType::String.nullable(false))
+            .with_column("records", ScalarType::Int64.nullable(false))
+            .with_column("size", ScalarType::Int64.nullable(false))
+            .with_key(vec![0, 1]),
+        persistent: false,
+    };
+    pub static ref MZ_STATISTICS: BuiltinTable = BuiltinTable {
+        name: "mz_statistics",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("index_id", ScalarType::String.nullable(false))
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("row_count", ScalarType::Int64.nullable(false))
+            .with_column("distinct_keys", ScalarType::Int64.nullable(false))
+            .with_column("collected_at", ScalarType::TimestampTz.nullable(false))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_SECRET_AUDIT_EVENTS: BuiltinTable = BuiltinTable {
+        name: "mz_secret_audit_events",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::Int64.nullable(false))
+            .with_column("secret_id", ScalarType::String.nullable(false))
+            .with_column("operation", ScalarType::String.nullable(false))
+            .with_column("version", ScalarType::Int64.nullable(true))
+            .with_column("user", ScalarType::String.nullable(false))
+            .with_column("occurred_at", ScalarType::TimestampTz.nullable(false))
+            .with_column("error", ScalarType::String.nullable(true))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_SECRET_USES: BuiltinTable = BuiltinTable {
+        name: "mz_secret_uses",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("secret_id", ScalarType::String.nullable(false))
+            .with_column("version", ScalarType::Int64.nullable(false))
+            .with_key(vec![0, 1]),
+        persistent: false,
+    };
+    pub static ref MZ_OBJECT_DEPENDENCIES: BuiltinTable = BuiltinTable {
+        name: "mz_object_dependencies",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("referenced_object_id", ScalarType::String.nullable(false))
+            .with_key(vec![0, 1]),
+        persistent: false,
+    };
+    pub static ref MZ_AUDIT_EVENTS: BuiltinTable = BuiltinTable {
+        name: "mz_audit_events",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::Int64.nullable(false))
+            .with_column("event_type", ScalarType::String.nullable(false))
+            .with_column("object_type", ScalarType::String.nullable(false))
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("user", ScalarType::String.nullable(false))
+            .with_column("occurred_at", ScalarType::TimestampTz.nullable(false))
+            .with_column("details", ScalarType::Jsonb.nullable(false))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_COMMENTS: BuiltinTable = BuiltinTable {
+        name: "mz_comments",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::String.nullable(false))
+            .with_column("column_position", ScalarType::Int64.nullable(true))
+            .with_column("comment", ScalarType::String.nullable(false)),
+        persistent: false,
+    };
+    pub static ref MZ_PRIVILEGES: BuiltinTable = BuiltinTable {
+        name: "mz_privileges",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("object_type", ScalarType::String.nullable(false))
+            .with_column("object_id", ScalarType::String.nullable(false))
+            .with_column("role_id", ScalarType::Int64.nullable(true))
+            .with_column("privilege", ScalarType::String.nullable(false)),
+        persistent: false,
+    };
+    pub static ref MZ_DROPPED_OBJECTS: BuiltinTable = BuiltinTable {
+        name: "mz_dropped_objects",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("id", ScalarType::String.nullable(false))
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("type", ScalarType::String.nullable(false))
+            .with_column("dropped_at", ScalarType::TimestampTz.nullable(false))
+            .with_key(vec![0]),
+        persistent: false,
+    };
+    pub static ref MZ_CATALOG_VERSIONS: BuiltinTable = BuiltinTable {
+        name: "mz_catalog_versions",
+        schema: MZ_CATALOG_SCHEMA,
+        desc: RelationDesc::empty()
+            .with_column("schema_version", ScalarType::Int64.nullable(true))
+            .with_column("last_migration_at", ScalarType::TimestampTz.nullable(true))
+            .with_column("catalog_content_version", ScalarType::String.nullable(false)),
         persistent: false,
     };
 
@@ -1217,23 +1510,30 @@ lazy_static! {
 pub const MZ_RELATIONS: BuiltinView = BuiltinView {
     name: "mz_relations",
     schema: MZ_CATALOG_SCHEMA,
-    sql: "CREATE VIEW mz_catalog.mz_relations (id, oid, schema_id, name, type) AS
-      SELECT id, oid, schema_id, name, 'table' FROM mz_catalog.mz_tables
-UNION SELECT id, oid, schema_id, name, 'source' FROM mz_catalog.mz_sources
-UNION SELECT id, oid, schema_id, name, 'view' FROM mz_catalog.mz_views",
+    sql: "CREATE VIEW mz_catalog.mz_relations (id, oid, schema_id, name, type, owner_id, created_at, updated_at) AS
+      SELECT id, oid, schema_id, name, 'table', owner_id, created_at, updated_at FROM mz_catalog.mz_tables
+UNION SELECT id, oid, schema_id, name, 'source', owner_id, created_at, updated_at FROM mz_catalog.mz_sources
+UNION SELECT id, oid, schema_id, name, 'view', owner_id, created_at, updated_at FROM mz_catalog.mz_views",
 };
 
 pub const MZ_OBJECTS: BuiltinView = BuiltinView {
     name: "mz_objects",
     schema: MZ_CATALOG_SCHEMA,
-    sql: "CREATE VIEW mz_catalog.mz_objects (id, oid, schema_id, name, type) AS
-    SELECT id, oid, schema_id, name, type FROM mz_catalog.mz_relations
+    sql: "CREATE VIEW mz_catalog.mz_objects (id, oid, schema_id, name, type, owner_id, created_at, updated_at) AS
+    SELECT id, oid, schema_id, name, type, owner_id, created_at, updated_at FROM mz_catalog.mz_relations
 UNION
-    SELECT id, oid, schema_id, name, 'sink' FROM mz_catalog.mz_sinks
+    SELECT id, oid, schema_id, name, 'sink', owner_id, created_at, updated_at FROM mz_catalog.mz_sinks
 UNION
-    SELECT mz_indexes.id, mz_indexes.oid, schema_id, mz_indexes.name, 'index'
+    SELECT mz_indexes.id, mz_indexes.oid, schema_id, mz_indexes.name, 'index',
+        mz_indexes.owner_id, mz_indexes.created_at, mz_indexes.updated_at
     FROM mz_catalog.mz_indexes
-    JOIN mz_catalog.mz_relations ON mz_indexes.on_id = mz_relations.id",
+    JOIN mz_catalog.mz_relations ON mz_indexes.on_id = mz_relations.id
+UNION
+    SELECT id, oid, schema_id, name, 'secret', owner_id, created_at, updated_at FROM mz_catalog.mz_secrets
+UNION
+    SELECT id, oid, schema_id, name, 'publication', owner_id, created_at, updated_at FROM mz_catalog.mz_publications
+UNION
+    SELECT id, oid, schema_id, name, 'task', owner_id, created_at, updated_at FROM mz_catalog.mz_scheduled_tasks",
 };
 
 // For historical reasons, this view does not properly escape identifiers. For
@@ -1365,6 +1665,72 @@ GROUP BY
     mz_records_per_dataflow.name",
 };
 
+pub const MZ_DATAFLOW_STATEMENTS: BuiltinView = BuiltinView {
+    name: "mz_dataflow_statements",
+    schema: MZ_CATALOG_SCHEMA,
+    sql: "CREATE VIEW mz_catalog.mz_dataflow_statements AS
+SELECT
+    export_id,
+    dataflow_name,
+    statement_id,
+    session_id,
+    CASE WHEN \"user\" = pg_catalog.current_user() THEN sql END AS sql
+FROM mz_internal.mz_all_dataflow_statements",
+};
+
+pub const MZ_STATEMENT_RESOURCE_USAGE: BuiltinView = BuiltinView {
+    name: "mz_statement_resource_usage",
+    schema: MZ_CATALOG_SCHEMA,
+    sql: "CREATE VIEW mz_catalog.mz_statement_resource_usage AS
+WITH unique_names AS (
+    SELECT dataflow_name
+    FROM mz_catalog.mz_dataflow_statements
+    GROUP BY dataflow_name
+    HAVING pg_catalog.count(*) = 1
+),
+elapsed_cte AS (
+    SELECT
+        mz_dataflow_operator_dataflows.dataflow_name,
+        pg_catalog.SUM(mz_scheduling_elapsed.elapsed_ns) AS elapsed_ns
+    FROM
+        mz_catalog.mz_scheduling_elapsed,
+        mz_catalog.mz_dataflow_operator_dataflows
+    WHERE
+        mz_scheduling_elapsed.id = mz_dataflow_operator_dataflows.id AND
+        mz_scheduling_elapsed.worker = mz_dataflow_operator_dataflows.worker
+    GROUP BY
+        mz_dataflow_operator_dataflows.dataflow_name
+),
+records_cte AS (
+    SELECT
+        mz_records_per_dataflow_global.name AS dataflow_name,
+        pg_catalog.SUM(mz_records_per_dataflow_global.records) AS records
+    FROM
+        mz_catalog.mz_records_per_dataflow_global
+    GROUP BY
+        mz_records_per_dataflow_global.name
+)
+SELECT
+    mz_dataflow_statements.statement_id,
+    mz_dataflow_statements.session_id,
+    mz_dataflow_statements.sql,
+    pg_catalog.count(*) AS dataflows,
+    pg_catalog.SUM(elapsed_cte.elapsed_ns) AS elapsed_ns,
+    pg_catalog.SUM(records_cte.records) AS records
+FROM
+    mz_catalog.mz_dataflow_statements
+    LEFT JOIN unique_names
+        ON mz_dataflow_statements.dataflow_name = unique_names.dataflow_name
+    LEFT JOIN elapsed_cte
+        ON unique_names.dataflow_name = elapsed_cte.dataflow_name
+    LEFT JOIN records_cte
+        ON unique_names.dataflow_name = records_cte.dataflow_name
+GROUP BY
+    mz_dataflow_statements.statement_id,
+    mz_dataflow_statements.session_id,
+    mz_dataflow_statements.sql",
+};
+
 pub const MZ_PERF_ARRANGEMENT_RECORDS: BuiltinView = BuiltinView {
     name: "mz_perf_arrangement_records",
     schema: MZ_CATALOG_SCHEMA,
@@ -1421,6 +1787,17 @@ FROM mz_catalog.mz_peek_durations lpd
 GROUP BY worker",
 };
 
+pub const MZ_PEEK_DURATIONS_PER_MINUTE: BuiltinView = BuiltinView {
+    name: "mz_peek_durations_per_minute",
+    schema: MZ_CATALOG_SCHEMA,
+    sql: "CREATE VIEW mz_catalog.mz_peek_durations_per_minute AS SELECT
+    pg_catalog.to_timestamp((pdm.minute_start / 1000)::pg_catalog.float8) AS minute_start,
+    pdm.duration_ns,
+    pg_catalog.sum(pdm.count) AS count
+FROM mz_catalog.mz_peek_durations_per_minute_internal pdm
+GROUP BY pdm.minute_start, pdm.duration_ns",
+};
+
 pub const MZ_PERF_DEPENDENCY_FRONTIERS: BuiltinView = BuiltinView {
     name: "mz_perf_dependency_frontiers",
     schema: MZ_CATALOG_SCHEMA,
@@ -1445,6 +1822,28 @@ JOIN mz_catalog.mz_catalog_names mcn ON mcn.global_id = index_deps.dataflow
 JOIN mz_catalog.mz_catalog_names mcn_source ON mcn_source.global_id = source_info.source_id",
 };
 
+pub const MZ_HYDRATION_STATUSES: BuiltinView = BuiltinView {
+    name: "mz_hydration_statuses",
+    schema: MZ_CATALOG_SCHEMA,
+    sql: "CREATE VIEW mz_catalog.mz_hydration_statuses AS SELECT
+    f.object_id,
+    o.name AS object_name,
+    o.type AS object_type,
+    c.name AS cluster,
+    f.replica,
+    f.hydrated,
+    f.serving,
+    CASE
+        WHEN f.time IS NULL THEN 0
+        WHEN f.input_time IS NULL THEN NULL
+        WHEN f.input_time < f.time THEN 0
+        ELSE f.input_time - f.time
+    END AS lag_ms
+FROM mz_catalog.mz_cluster_replica_frontiers f
+JOIN mz_catalog.mz_objects o ON o.id = f.object_id
+JOIN mz_catalog.mz_clusters c ON c.id = f.cluster_id",
+};
+
 pub const PG_NAMESPACE: BuiltinView = BuiltinView {
     name: "pg_namespace",
     schema: PG_CATALOG_SCHEMA,
@@ -1557,12 +1956,15 @@ pub const PG_DESCRIPTION: BuiltinView = BuiltinView {
     name: "pg_description",
     schema: PG_CATALOG_SCHEMA,
     sql: "CREATE VIEW pg_catalog.pg_description AS SELECT
-    c.oid as objoid,
+    mz_objects.oid as objoid,
     NULL::pg_catalog.oid as classoid,
-    0::pg_catalog.int4 as objsubid,
-    NULL::pg_catalog.text as description
-FROM pg_catalog.pg_class c
-JOIN mz_catalog.mz_databases d ON (d.id IS NULL OR d.name = pg_catalog.current_database())",
+    COALESCE(mz_comments.column_position, 0)::pg_catalog.int4 as objsubid,
+    mz_comments.comment as description
+FROM mz_catalog.mz_comments
+JOIN mz_catalog.mz_objects ON mz_objects.id = mz_comments.id
+JOIN mz_catalog.mz_schemas ON mz_schemas.id = mz_objects.schema_id
+LEFT JOIN mz_catalog.mz_databases d ON d.id = mz_schemas.database_id
+WHERE mz_schemas.database_id IS NULL OR d.name = pg_catalog.current_database()",
 };
 
 pub const PG_TYPE: BuiltinView = BuiltinView {
@@ -1693,10 +2095,11 @@ pub const PG_SETTINGS: BuiltinView = BuiltinView {
     name: "pg_settings",
     schema: PG_CATALOG_SCHEMA,
     sql: "CREATE VIEW pg_catalog.pg_settings AS SELECT
-    *
-FROM (VALUES
-    ('max_index_keys'::pg_catalog.text, '1000'::pg_catalog.text)
-) AS _ (name, setting)",
+    var->>'name' AS name,
+    var->>'setting' AS setting,
+    var->>'short_desc' AS short_desc,
+    var->>'vartype' AS vartype
+FROM pg_catalog.jsonb_array_elements(mz_internal.mz_session_settings()) AS vars (var)",
 };
 
 pub const MZ_SCHEDULING_ELAPSED: BuiltinView = BuiltinView {
@@ -1940,9 +2343,10 @@ pub const INFORMATION_SCHEMA_TABLES: BuiltinView = BuiltinView {
         WHEN 'table' THEN 'BASE TABLE'
         ELSE pg_catalog.upper(r.type)
     END AS table_type
-FROM mz_catalog.mz_relations r
+FROM mz_catalog.mz_objects r
 JOIN mz_catalog.mz_schemas s ON s.id = r.schema_id
-JOIN mz_catalog.mz_databases d on s.database_id = d.id",
+JOIN mz_catalog.mz_databases d on s.database_id = d.id
+WHERE r.type <> 'index'",
 };
 
 // MZ doesn't support COLLATE so the table is filled with NULLs and made empty. pg_database hard
@@ -1995,6 +2399,23 @@ AS SELECT
 WHERE false",
 };
 
+pub const PG_PUBLICATION_TABLES: BuiltinView = BuiltinView {
+    name: "pg_publication_tables",
+    schema: PG_CATALOG_SCHEMA,
+    sql: "CREATE VIEW pg_catalog.pg_publication_tables
+AS SELECT
+    p.name AS pubname,
+    s.name AS schemaname,
+    v.name AS tablename
+FROM mz_catalog.mz_publications p
+JOIN mz_catalog.mz_publication_views pv ON pv.publication_id = p.id
+JOIN mz_catalog.mz_views v ON v.id = pv.view_id
+JOIN mz_catalog.mz_schemas s ON s.id = v.schema_id
+JOIN mz_catalog.mz_schemas ps ON ps.id = p.schema_id
+JOIN mz_catalog.mz_databases d ON d.id = ps.database_id
+WHERE d.name = pg_catalog.current_database()",
+};
+
 pub const MZ_SYSTEM: BuiltinRole = BuiltinRole {
     name: "mz_system",
     id: -1,
@@ -2097,10 +2518,13 @@ lazy_static! {
             Builtin::Log(&MZ_MESSAGE_COUNTS_SENT_INTERNAL),
             Builtin::Log(&MZ_PEEK_ACTIVE),
             Builtin::Log(&MZ_PEEK_DURATIONS),
+            Builtin::Log(&MZ_PEEK_DURATIONS_PER_MINUTE_INTERNAL),
             Builtin::Log(&MZ_SCHEDULING_ELAPSED_INTERNAL),
             Builtin::Log(&MZ_SCHEDULING_HISTOGRAM_INTERNAL),
             Builtin::Log(&MZ_SCHEDULING_PARKS_INTERNAL),
             Builtin::Log(&MZ_SOURCE_INFO),
+            Builtin::Log(&MZ_SOURCE_SCHEMA_CHANGES),
+            Builtin::Log(&MZ_SOURCE_UPSERT_STATISTICS),
             Builtin::Log(&MZ_WORKER_MATERIALIZATION_FRONTIERS),
             Builtin::Table(&MZ_VIEW_KEYS),
             Builtin::Table(&MZ_VIEW_FOREIGN_KEYS),
@@ -2127,7 +2551,26 @@ lazy_static! {
             Builtin::Table(&MZ_PROMETHEUS_HISTOGRAMS),
             Builtin::Table(&MZ_PROMETHEUS_METRICS),
             Builtin::Table(&MZ_CLUSTERS),
+            Builtin::Table(&MZ_CLUSTER_REPLICAS),
+            Builtin::Table(&MZ_CLUSTER_REPLICA_FRONTIERS),
             Builtin::Table(&MZ_SECRETS),
+            Builtin::Table(&MZ_PUBLICATIONS),
+            Builtin::Table(&MZ_PUBLICATION_VIEWS),
+            Builtin::Table(&MZ_SCHEDULED_TASKS),
+            Builtin::Table(&MZ_SCHEDULED_TASK_RUNS),
+            Builtin::Table(&MZ_SYSTEM_CONFIG),
+            Builtin::Table(&MZ_INDEX_USAGE),
+            Builtin::Table(&MZ_ALL_DATAFLOW_STATEMENTS),
+            Builtin::Table(&MZ_DATAFLOW_MEMORY),
+            Builtin::Table(&MZ_STATISTICS),
+            Builtin::Table(&MZ_SECRET_AUDIT_EVENTS),
+            Builtin::Table(&MZ_AUDIT_EVENTS),
+            Builtin::Table(&MZ_COMMENTS),
+            Builtin::Table(&MZ_PRIVILEGES),
+            Builtin::Table(&MZ_DROPPED_OBJECTS),
+            Builtin::Table(&MZ_CATALOG_VERSIONS),
+            Builtin::Table(&MZ_SECRET_USES),
+            Builtin::Table(&MZ_OBJECT_DEPENDENCIES),
             Builtin::View(&MZ_RELATIONS),
             Builtin::View(&MZ_OBJECTS),
             Builtin::View(&MZ_CATALOG_NAMES),
@@ -2136,8 +2579,10 @@ lazy_static! {
             Builtin::View(&MZ_DATAFLOW_NAMES),
             Builtin::View(&MZ_DATAFLOW_OPERATOR_DATAFLOWS),
             Builtin::View(&MZ_DATAFLOW_OPERATOR_REACHABILITY),
+            Builtin::View(&MZ_HYDRATION_STATUSES),
             Builtin::View(&MZ_MATERIALIZATION_FRONTIERS),
             Builtin::View(&MZ_MESSAGE_COUNTS),
+            Builtin::View(&MZ_PEEK_DURATIONS_PER_MINUTE),
             Builtin::View(&MZ_PERF_ARRANGEMENT_RECORDS),
             Builtin::View(&MZ_PERF_DEPENDENCY_FRONTIERS),
             Builtin::View(&MZ_PERF_PEEK_DURATIONS_AGGREGATES),
@@ -2149,6 +2594,8 @@ lazy_static! {
             Builtin::View(&MZ_SCHEDULING_ELAPSED),
             Builtin::View(&MZ_SCHEDULING_HISTOGRAM),
             Builtin::View(&MZ_SCHEDULING_PARKS),
+            Builtin::View(&MZ_DATAFLOW_STATEMENTS),
+            Builtin::View(&MZ_STATEMENT_RESOURCE_USAGE),
             Builtin::View(&PG_NAMESPACE),
             Builtin::View(&PG_CLASS),
             Builtin::View(&PG_DATABASE),
@@ -2169,6 +2616,7 @@ lazy_static! {
             Builtin::View(&PG_COLLATION),
             Builtin::View(&PG_POLICY),
             Builtin::View(&PG_INHERITS),
+            Builtin::View(&PG_PUBLICATION_TABLES),
             Builtin::View(&INFORMATION_SCHEMA_COLUMNS),
             Builtin::View(&INFORMATION_SCHEMA_TABLES),
         ]);
@@ -7,27 +7,37 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::BTreeSet;
 use std::os::unix::ffi::OsStringExt;
 
+use mz_dataflow_types::client::controller::DEFAULT_REPLICA;
+use mz_dataflow_types::client::InstanceConfig;
 use mz_dataflow_types::sinks::{AvroOcfSinkConnector, KafkaSinkConnector};
 use mz_expr::{GlobalId, MirScalarExpr};
 use mz_ore::collections::CollectionExt;
+use mz_ore::now::to_datetime;
 use mz_repr::adt::array::ArrayDimension;
+use mz_repr::adt::interval::Interval;
+use mz_repr::adt::jsonb::JsonbPacker;
 use mz_repr::{Datum, Diff, Row};
-use mz_sql::ast::{CreateIndexStatement, Statement};
+use mz_sql::ast::{CreateIndexStatement, Privilege, Statement};
 use mz_sql::catalog::{CatalogDatabase, CatalogType};
 use mz_sql::names::{DatabaseId, ResolvedDatabaseSpecifier, SchemaId, SchemaSpecifier};
 use mz_sql_parser::ast::display::AstDisplay;
 
 use crate::catalog::builtin::{
-    MZ_ARRAY_TYPES, MZ_AVRO_OCF_SINKS, MZ_BASE_TYPES, MZ_CLUSTERS, MZ_COLUMNS, MZ_DATABASES,
+    MZ_ARRAY_TYPES, MZ_AUDIT_EVENTS, MZ_AVRO_OCF_SINKS, MZ_BASE_TYPES, MZ_CATALOG_VERSIONS,
+    MZ_CLUSTERS, MZ_CLUSTER_REPLICAS, MZ_COLUMNS, MZ_COMMENTS, MZ_DATABASES, MZ_DROPPED_OBJECTS,
     MZ_FUNCTIONS, MZ_INDEXES, MZ_INDEX_COLUMNS, MZ_KAFKA_SINKS, MZ_LIST_TYPES, MZ_MAP_TYPES,
-    MZ_PSEUDO_TYPES, MZ_ROLES, MZ_SCHEMAS, MZ_SECRETS, MZ_SINKS, MZ_SOURCES, MZ_TABLES, MZ_TYPES,
-    MZ_VIEWS,
+    MZ_OBJECT_DEPENDENCIES, MZ_PRIVILEGES, MZ_PSEUDO_TYPES, MZ_PUBLICATIONS, MZ_PUBLICATION_VIEWS,
+    MZ_ROLES, MZ_SCHEDULED_TASKS, MZ_SCHEMAS, MZ_SECRETS, MZ_SECRET_AUDIT_EVENTS, MZ_SECRET_USES,
+    MZ_SINKS, MZ_SOURCES, MZ_TABLES, MZ_TYPES, MZ_VIEWS,
 };
+use crate::catalog::storage::{CatalogVersions, OwnedObjectId};
 use crate::catalog::{
-    CatalogItem, CatalogState, Func, Index, Sink, SinkConnector, SinkConnectorState, Source, Table,
-    Type, View, SYSTEM_CONN_ID,
+    AuditEvent, CatalogItem, CatalogState, DroppedItem, Func, Index, ObjectTimestamps, Publication,
+    SecretAuditEvent, Sink, SinkConnector, SinkConnectorState, Source, Table, Task, Type, View,
+    SYSTEM_CONN_ID,
 };
 
 /// An update to a built-in table.
@@ -44,12 +54,16 @@ pub struct BuiltinTableUpdate {
 impl CatalogState {
     pub(super) fn pack_database_update(&self, id: &DatabaseId, diff: Diff) -> BuiltinTableUpdate {
         let database = &self.database_by_id[id];
+        let [created_at, updated_at] = timestamp_datums(Some(database.timestamps));
         BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_DATABASES),
             row: Row::pack_slice(&[
                 Datum::Int64(id.0),
                 Datum::UInt32(database.oid),
                 Datum::String(database.name()),
+                Datum::Int64(database.owner_id),
+                created_at,
+                updated_at,
             ]),
             diff,
         }
@@ -68,6 +82,7 @@ impl CatalogState {
                 &self.database_by_id[id].schemas_by_id[schema_id],
             ),
         };
+        let [created_at, updated_at] = timestamp_datums(schema.timestamps);
         BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_SCHEMAS),
             row: Row::pack_slice(&[
@@ -75,6 +90,9 @@ impl CatalogState {
                 Datum::UInt32(schema.oid),
                 Datum::from(database_id),
                 Datum::String(&schema.name.schema),
+                Datum::Int64(schema.owner_id),
+                created_at,
+                updated_at,
             ]),
             diff,
         }
@@ -82,12 +100,15 @@ impl CatalogState {
 
     pub(super) fn pack_role_update(&self, name: &str, diff: Diff) -> BuiltinTableUpdate {
         let role = &self.roles[name];
+        let [created_at, updated_at] = timestamp_datums(role.timestamps);
         BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_ROLES),
             row: Row::pack_slice(&[
                 Datum::Int64(role.id),
                 Datum::UInt32(role.oid),
                 Datum::String(&name),
+                created_at,
+                updated_at,
             ]),
             diff,
         }
@@ -97,13 +118,44 @@ impl CatalogState {
         &self,
         name: &str,
         diff: Diff,
-    ) -> BuiltinTableUpdate {
-        let compute_instance_id = &self.compute_instances_by_name[name];
-        BuiltinTableUpdate {
+    ) -> Vec<BuiltinTableUpdate> {
+        let compute_instance_id = self.compute_instances_by_name[name];
+        let compute_instance = &self.compute_instances_by_id[&compute_instance_id];
+        let [created_at, updated_at] = timestamp_datums(Some(compute_instance.timestamps));
+        let mut updates = vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_CLUSTERS),
-            row: Row::pack_slice(&[Datum::Int64(*compute_instance_id), Datum::String(&name)]),
+            row: Row::pack_slice(&[
+                Datum::Int64(compute_instance_id),
+                Datum::String(&name),
+                Datum::Int64(compute_instance.owner_id),
+                created_at,
+                updated_at,
+            ]),
             diff,
+        }];
+        // Local clusters run within this process and have no replicas, while
+        // managed clusters have a single replica named `default`.
+        let replicas = match &compute_instance.config {
+            InstanceConfig::Local => vec![],
+            InstanceConfig::Remote { replicas } => replicas.keys().map(|r| r.as_str()).collect(),
+            InstanceConfig::Managed { .. } => vec![DEFAULT_REPLICA],
+        };
+        // Replicas are configured along with their cluster, so they share its
+        // owner and timestamps.
+        for replica in replicas {
+            updates.push(BuiltinTableUpdate {
+                id: self.resolve_builtin_table(&MZ_CLUSTER_REPLICAS),
+                row: Row::pack_slice(&[
+                    Datum::Int64(compute_instance_id),
+                    Datum::String(replica),
+                    Datum::Int64(compute_instance.owner_id),
+                    created_at,
+                    updated_at,
+                ]),
+                diff,
+            });
         }
+        updates
     }
 
     pub(super) fn pack_item_update(&self, id: GlobalId, diff: Diff) -> Vec<BuiltinTableUpdate> {
@@ -131,9 +183,39 @@ impl CatalogState {
             CatalogItem::Sink(sink) => self.pack_sink_update(id, oid, schema_id, name, sink, diff),
             CatalogItem::Type(ty) => self.pack_type_update(id, oid, schema_id, name, ty, diff),
             CatalogItem::Func(func) => self.pack_func_update(id, schema_id, name, func, diff),
-            CatalogItem::Secret(_) => self.pack_secret_update(id, schema_id, name, diff),
+            CatalogItem::Secret(_) => self.pack_secret_update(id, oid, schema_id, name, diff),
+            CatalogItem::Publication(publication) => {
+                self.pack_publication_update(id, oid, schema_id, name, publication, diff)
+            }
+            CatalogItem::Task(task) => self.pack_task_update(id, oid, schema_id, name, task, diff),
         };
 
+        // The uses of user items are the dependencies stored in the catalog,
+        // which are checked against the items' definitions when the catalog is
+        // opened.
+        for referenced_id in entry.uses().iter().collect::<BTreeSet<_>>() {
+            updates.push(BuiltinTableUpdate {
+                id: self.resolve_builtin_table(&MZ_OBJECT_DEPENDENCIES),
+                row: Row::pack_slice(&[
+                    Datum::String(&id.to_string()),
+                    Datum::String(&referenced_id.to_string()),
+                ]),
+                diff,
+            });
+        }
+
+        for (secret_id, version) in self.secret_uses.get(&id).into_iter().flatten() {
+            updates.push(BuiltinTableUpdate {
+                id: self.resolve_builtin_table(&MZ_SECRET_USES),
+                row: Row::pack_slice(&[
+                    Datum::String(&id.to_string()),
+                    Datum::String(&secret_id.to_string()),
+                    Datum::Int64(version.0 as i64),
+                ]),
+                diff,
+            });
+        }
+
         if let Ok(desc) = entry.desc(&self.resolve_full_name(entry.name(), entry.conn_id())) {
             let defaults = match entry.item() {
                 CatalogItem::Table(table) => Some(&table.defaults),
@@ -174,6 +256,7 @@ impl CatalogState {
         table: &Table,
         diff: Diff,
     ) -> Vec<BuiltinTableUpdate> {
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_TABLES),
             row: Row::pack_slice(&[
@@ -182,6 +265,9 @@ impl CatalogState {
                 Datum::Int64(schema_id.into()),
                 Datum::String(name),
                 Datum::from(table.persist_name.as_deref()),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
             ]),
             diff,
         }]
@@ -200,6 +286,7 @@ impl CatalogState {
             .persist_details
             .as_ref()
             .map(|persist| &*persist.primary_stream);
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_SOURCES),
             row: Row::pack_slice(&[
@@ -210,6 +297,9 @@ impl CatalogState {
                 Datum::String(source.connector.name()),
                 Datum::String(self.is_volatile(id).as_str()),
                 Datum::from(persist_name),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
             ]),
             diff,
         }]
@@ -237,6 +327,7 @@ impl CatalogState {
         // do the same for compatibility's sake.
         query_string.push(';');
 
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_VIEWS),
             row: Row::pack_slice(&[
@@ -246,6 +337,9 @@ impl CatalogState {
                 Datum::String(name),
                 Datum::String(self.is_volatile(id).as_str()),
                 Datum::String(&query_string),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
             ]),
             diff,
         }]
@@ -297,6 +391,7 @@ impl CatalogState {
                 }
                 _ => (),
             }
+            let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
             updates.push(BuiltinTableUpdate {
                 id: self.resolve_builtin_table(&MZ_SINKS),
                 row: Row::pack_slice(&[
@@ -307,6 +402,9 @@ impl CatalogState {
                     Datum::String(connector.name()),
                     Datum::String(self.is_volatile(id).as_str()),
                     Datum::Int64(sink.compute_instance),
+                    Datum::Int64(self.get_entry(&id).owner_id()),
+                    created_at,
+                    updated_at,
                 ]),
                 diff,
             });
@@ -332,6 +430,7 @@ impl CatalogState {
             _ => unreachable!(),
         };
 
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         updates.push(BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_INDEXES),
             row: Row::pack_slice(&[
@@ -342,6 +441,9 @@ impl CatalogState {
                 Datum::String(self.is_volatile(id).as_str()),
                 Datum::from(index.enabled),
                 Datum::Int64(index.compute_instance),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
             ]),
             diff,
         });
@@ -393,6 +495,7 @@ impl CatalogState {
         typ: &Type,
         diff: Diff,
     ) -> Vec<BuiltinTableUpdate> {
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         let generic_update = BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_TYPES),
             row: Row::pack_slice(&[
@@ -400,6 +503,9 @@ impl CatalogState {
                 Datum::UInt32(oid),
                 Datum::Int64(schema_id.into()),
                 Datum::String(name),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
             ]),
             diff,
         };
@@ -498,18 +604,263 @@ impl CatalogState {
     fn pack_secret_update(
         &self,
         id: GlobalId,
+        oid: u32,
         schema_id: &SchemaSpecifier,
         name: &str,
         diff: Diff,
     ) -> Vec<BuiltinTableUpdate> {
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
         vec![BuiltinTableUpdate {
             id: self.resolve_builtin_table(&MZ_SECRETS),
             row: Row::pack_slice(&[
                 Datum::String(&id.to_string()),
+                Datum::UInt32(oid),
+                Datum::Int64(schema_id.into()),
+                Datum::String(name),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
+                match self.secret_versions.get(&id) {
+                    Some(version) => Datum::Int64(version.0 as i64),
+                    None => Datum::Null,
+                },
+            ]),
+            diff,
+        }]
+    }
+
+    pub(super) fn pack_secret_audit_event_update(
+        &self,
+        event: &SecretAuditEvent,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_SECRET_AUDIT_EVENTS),
+            row: Row::pack_slice(&[
+                Datum::Int64(event.id as i64),
+                Datum::String(&event.secret_id.to_string()),
+                Datum::String(event.operation.as_str()),
+                match event.version {
+                    Some(version) => Datum::Int64(version.0 as i64),
+                    None => Datum::Null,
+                },
+                Datum::String(&event.user),
+                Datum::TimestampTz(to_datetime(event.occurred_at)),
+                match &event.error {
+                    Some(error) => Datum::String(error),
+                    None => Datum::Null,
+                },
+            ]),
+            diff,
+        }
+    }
+
+    pub(super) fn pack_audit_event_update(
+        &self,
+        event: &AuditEvent,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        let mut row = Row::default();
+        let mut packer = row.packer();
+        packer.extend([
+            Datum::Int64(event.id as i64),
+            Datum::String(event.event_type.as_str()),
+            Datum::String(&event.object_type),
+            Datum::String(&event.object_id),
+            Datum::String(&event.name),
+            Datum::String(&event.user),
+            Datum::TimestampTz(to_datetime(event.occurred_at)),
+        ]);
+        JsonbPacker::new(&mut packer)
+            .pack_serde_json(event.details.clone())
+            .expect("audit event details must be representable as jsonb");
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_AUDIT_EVENTS),
+            row,
+            diff,
+        }
+    }
+
+    pub(super) fn pack_comment_update(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_COMMENTS),
+            row: Row::pack_slice(&[
+                Datum::String(&id.to_string()),
+                Datum::from(column_position.map(|p| p as i64)),
+                Datum::String(comment),
+            ]),
+            diff,
+        }
+    }
+
+    pub(super) fn pack_privilege_update(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        let (object_type, object_id) = object.stored_id();
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_PRIVILEGES),
+            row: Row::pack_slice(&[
+                Datum::String(object_type),
+                Datum::String(&object_id),
+                Datum::from(role_id),
+                Datum::String(&privilege.to_string()),
+            ]),
+            diff,
+        }
+    }
+
+    /// Packs an update for each privilege that is held on `object`.
+    pub(super) fn pack_object_privileges_update(
+        &self,
+        object: OwnedObjectId,
+        diff: Diff,
+    ) -> Vec<BuiltinTableUpdate> {
+        self.privileges
+            .get(&object)
+            .into_iter()
+            .flatten()
+            .map(|(role_id, privilege)| {
+                self.pack_privilege_update(object, *role_id, *privilege, diff)
+            })
+            .collect()
+    }
+
+    pub(super) fn pack_dropped_item_update(
+        &self,
+        item: &DroppedItem,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_DROPPED_OBJECTS),
+            row: Row::pack_slice(&[
+                Datum::String(&item.id.to_string()),
+                Datum::String(&item.name),
+                Datum::String(&item.item_type),
+                Datum::TimestampTz(to_datetime(item.dropped_at)),
+            ]),
+            diff,
+        }
+    }
+
+    pub(super) fn pack_catalog_versions_update(
+        &self,
+        versions: &CatalogVersions,
+        diff: Diff,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_CATALOG_VERSIONS),
+            row: Row::pack_slice(&[
+                Datum::from(versions.schema_version.map(|v| v as i64)),
+                match versions.last_migration_at {
+                    Some(last_migration_at) => Datum::TimestampTz(to_datetime(last_migration_at)),
+                    None => Datum::Null,
+                },
+                Datum::String(&versions.catalog_content_version),
+            ]),
+            diff,
+        }
+    }
+
+    fn pack_publication_update(
+        &self,
+        id: GlobalId,
+        oid: u32,
+        schema_id: &SchemaSpecifier,
+        name: &str,
+        publication: &Publication,
+        diff: Diff,
+    ) -> Vec<BuiltinTableUpdate> {
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
+        let mut updates = vec![BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_PUBLICATIONS),
+            row: Row::pack_slice(&[
+                Datum::String(&id.to_string()),
+                Datum::UInt32(oid),
                 Datum::Int64(schema_id.into()),
                 Datum::String(name),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                created_at,
+                updated_at,
+            ]),
+            diff,
+        }];
+        for (i, view_id) in publication.views.iter().enumerate() {
+            updates.push(BuiltinTableUpdate {
+                id: self.resolve_builtin_table(&MZ_PUBLICATION_VIEWS),
+                row: Row::pack_slice(&[
+                    Datum::String(&id.to_string()),
+                    Datum::String(&view_id.to_string()),
+                    Datum::Int64(i as i64 + 1),
+                ]),
+                diff,
+            });
+        }
+        updates
+    }
+
+    fn pack_task_update(
+        &self,
+        id: GlobalId,
+        oid: u32,
+        schema_id: &SchemaSpecifier,
+        name: &str,
+        task: &Task,
+        diff: Diff,
+    ) -> Vec<BuiltinTableUpdate> {
+        let statement = match mz_sql::parse::parse(&task.create_sql)
+            .expect("create_sql cannot be invalid")
+            .into_element()
+        {
+            Statement::CreateTask(stmt) => stmt.statement.to_ast_string_stable(),
+            _ => unreachable!(),
+        };
+        let schedule = Interval::new(
+            0,
+            0,
+            i64::try_from(task.schedule.as_micros()).expect("task schedule fits in an interval"),
+        )
+        .expect("task schedule fits in an interval");
+
+        let [created_at, updated_at] = timestamp_datums(self.get_entry(&id).timestamps());
+        vec![BuiltinTableUpdate {
+            id: self.resolve_builtin_table(&MZ_SCHEDULED_TASKS),
+            row: Row::pack_slice(&[
+                Datum::String(&id.to_string()),
+                Datum::UInt32(oid),
+                Datum::Int64(schema_id.into()),
+                Datum::String(name),
+                Datum::Int64(self.get_entry(&id).owner_id()),
+                Datum::Interval(schedule),
+                Datum::String(&statement),
+                created_at,
+                updated_at,
             ]),
             diff,
         }]
     }
 }
+
+/// Returns the datums that report `timestamps` in the `created_at` and
+/// `updated_at` columns of the catalog's builtin tables.
+fn timestamp_datums(timestamps: Option<ObjectTimestamps>) -> [Datum<'static>; 2] {
+    match timestamps {
+        Some(ObjectTimestamps {
+            created_at,
+            updated_at,
+        }) => [
+            Datum::TimestampTz(to_datetime(created_at)),
+            Datum::TimestampTz(to_datetime(updated_at)),
+        ],
+        None => [Datum::Null, Datum::Null],
+    }
+}
@@ -9,6 +9,8 @@
 
 use std::time::Duration;
 
+use tokio::sync::mpsc;
+
 use mz_build_info::BuildInfo;
 use mz_dataflow_types::sources::AwsExternalId;
 use mz_ore::metrics::MetricsRegistry;
@@ -25,7 +27,7 @@ pub struct Config<'a> {
     /// Whether to enable experimental mode.
     pub experimental_mode: Option<bool>,
     /// Whether to enable safe mode.
-    pub safe_mode: bool,
+    pub safe_mode: Option<bool>,
     /// Whether to enable introspection for the local compute instance.
     pub local_compute_introspection: Option<ComputeInstanceIntrospectionConfig>,
     /// Information about this build of Materialize.
@@ -46,4 +48,12 @@ pub struct Config<'a> {
     pub disable_user_indexes: bool,
     /// A runtime for the `persist` crate alongside its configuration.
     pub persister: &'a PersisterWithConfig,
+    /// A channel on which to request a reload of the server's runtime
+    /// configuration, e.g., via `pg_reload_conf()`.
+    ///
+    /// If absent, configuration reloading is not supported.
+    pub config_reload_tx: Option<mpsc::UnboundedSender<()>>,
+    /// How long the definitions of dropped items are retained, so that the
+    /// items can be restored.
+    pub dropped_item_retention: Duration,
 }
@@ -37,6 +37,8 @@ pub enum ErrorKind {
     RoleAlreadyExists(String),
     #[error("cluster '{0}' already exists")]
     ClusterAlreadyExists(String),
+    #[error("cluster replica '{0}' already exists")]
+    ClusterReplicaAlreadyExists(String),
     #[error("catalog item '{0}' already exists")]
     ItemAlreadyExists(String),
     #[error("unacceptable schema name '{0}'")]
@@ -51,6 +53,8 @@ pub enum ErrorKind {
     ReadOnlyItem(String),
     #[error("cannot drop non-empty schema '{0}'")]
     SchemaNotEmpty(String),
+    #[error("role {} cannot be dropped because it owns objects", .role.quoted())]
+    RoleOwnsObjects { role: String, objects: Vec<String> },
     #[error("non-temporary items cannot depend on temporary item '{0}'")]
     InvalidTemporaryDependency(String),
     #[error("cannot create temporary item in non-temporary schema")]
@@ -59,6 +63,26 @@ pub enum ErrorKind {
     UnsatisfiableLoggingDependency { depender_name: String },
     #[error("sqlite error: {0}")]
     Storage(#[from] rusqlite::Error),
+    #[error("postgres error: {0}")]
+    PostgresStorage(#[from] tokio_postgres::Error),
+    #[error(transparent)]
+    Stash(#[from] mz_stash::StashError),
+    #[error("invalid catalog storage configuration: {0}")]
+    InvalidStorageConfig(String),
+    #[error(
+        "catalog has been opened by another process since this process opened it \
+         (epoch {}, expected {epoch})",
+        .current.map_or("unknown".into(), |current| current.to_string())
+    )]
+    Fenced { epoch: i64, current: Option<i64> },
+    #[error("cannot back up catalog: {0}")]
+    Backup(String),
+    #[error("cannot restore catalog from {path}: {detail}")]
+    InvalidBackup { path: String, detail: String },
+    #[error("cannot import catalog: {0}")]
+    Import(String),
+    #[error("cannot encrypt catalog: {0}")]
+    Encryption(String),
     #[error("persistence error: {0}")]
     Persistence(#[from] mz_persist::error::Error),
     #[error(transparent)]
@@ -77,6 +101,18 @@ https://materialize.com/docs/cli#experimental-mode"#
 more details, see https://materialize.com/docs/cli#experimental-mode"#
     )]
     ExperimentalModeUnavailable,
+    #[error(
+        r#"Materialize previously started with --safe to disable
+features that are unsafe for multi-tenant deployments, so now must be
+started in safe mode."#
+    )]
+    SafeModeRequired,
+    #[error("Safe mode is only available on new nodes.")]
+    SafeModeUnavailable,
+    #[error("Experimental mode is not available in safe mode.")]
+    ExperimentalModeInSafeMode,
+    #[error("catalog migration {version} has been modified since it was applied")]
+    ModifiedMigration { version: usize },
     #[error("cannot migrate from catalog version {last_seen_version} to version {this_version} (earlier versions might still work): {cause}")]
     FailedMigration {
         last_seen_version: String,
@@ -104,6 +140,18 @@ impl Error {
             ErrorKind::ReservedClusterName(_) => {
                 Some("The prefixes \"mz_\" and \"pg_\" are reserved for system clusters.".into())
             }
+            ErrorKind::RoleOwnsObjects { objects, .. } => Some(
+                objects
+                    .iter()
+                    .map(|object| format!("owner of {}", object))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            ErrorKind::ModifiedMigration { .. } => Some(
+                "Migrations that have shipped in a release must never be edited, \
+                only patched by later migrations."
+                    .into(),
+            ),
             _ => None,
         }
     }
@@ -120,6 +168,18 @@ impl From<rusqlite::Error> for Error {
     }
 }
 
+impl From<tokio_postgres::Error> for Error {
+    fn from(e: tokio_postgres::Error) -> Error {
+        Error::new(ErrorKind::from(e))
+    }
+}
+
+impl From<mz_stash::StashError> for Error {
+    fn from(e: mz_stash::StashError) -> Error {
+        Error::new(ErrorKind::from(e))
+    }
+}
+
 impl From<SqlCatalogError> for Error {
     fn from(e: SqlCatalogError) -> Error {
         Error::new(ErrorKind::from(e))
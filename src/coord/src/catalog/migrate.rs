@@ -34,8 +34,9 @@ use mz_sql::ast::{
     ProtobufSchema, Raw, RawIdent, RawObjectName, SqlOption, Statement, TableFunction,
     UnresolvedDataType, UnresolvedObjectName, Value, ViewDefinition, WithOption, WithOptionValue,
 };
+use mz_sql::catalog::CatalogItem as SqlCatalogItem;
 use mz_sql::names::resolve_names_stmt;
-use mz_sql::plan::StatementContext;
+use mz_sql::plan::{PlanContext, StatementContext};
 use mz_sql_parser::ast::CreateTypeAs;
 
 use crate::catalog::storage::Transaction;
@@ -47,7 +48,7 @@ where
     F: FnMut(&mut mz_sql::ast::Statement<Raw>) -> Result<(), anyhow::Error>,
 {
     let items = tx.load_items()?;
-    for (id, name, def) in items {
+    for (id, name, def, _owner_id, timestamps) in items {
         let SerializedCatalogItem::V1 {
             create_sql,
             eval_env,
@@ -67,7 +68,7 @@ where
 
         let serialized_item =
             serde_json::to_vec(&serialized_item).expect("catalog serialization cannot fail");
-        tx.update_item(id, &name.item, &serialized_item)?;
+        tx.update_item(id, &name.item, &serialized_item, timestamps.updated_at)?;
     }
     Ok(())
 }
@@ -115,11 +116,18 @@ pub(crate) fn migrate(catalog: &mut Catalog) -> Result<(), anyhow::Error> {
         Ok(())
     })?;
 
-    // Then, load up a temporary catalog with the rewritten items, and perform
-    // some transformations that require introspecting the catalog. These
-    // migrations are *weird*: they're rewriting the catalog while looking at
-    // it. You probably should be adding a basic AST migration above, unless
-    // you are really certain you want one of these crazy migrations.
+    // Then, re-plan every item with this version, and store the definitions
+    // that this version produces. This must happen before the items are
+    // loaded below, as loading stops at the first item that fails to plan,
+    // whereas re-planning reports every such item.
+    replan_items(&tx, &catalog)?;
+
+    // Finally, load up a temporary catalog with the rewritten items, and
+    // perform some transformations that require introspecting the catalog.
+    // These migrations are *weird*: they're rewriting the catalog while
+    // looking at it. You probably should be adding a basic AST migration
+    // above, unless you are really certain you want one of these crazy
+    // migrations.
     let cat = Catalog::load_catalog_items(&mut tx, &catalog)?;
     let conn_cat = cat.for_system_session();
     rewrite_items(&tx, |item| {
@@ -146,6 +154,86 @@ pub(crate) fn migrate(catalog: &mut Catalog) -> Result<(), anyhow::Error> {
 //
 // Please include @benesch on any code reviews that add or edit migrations.
 
+// ****************************************************************************
+// Re-planning -- Rewrites every item to the definition that this version plans
+// ****************************************************************************
+
+/// Re-plans the definition of every item with this version, and stores the
+/// definition that the planner produces in place of the old one.
+///
+/// Definitions are stored as SQL, so a definition written by a past version
+/// may no longer parse or plan, e.g. because syntax that it relied upon has
+/// been removed, which used to surface only when the catalog failed to load.
+/// Re-planning every item on upgrade catches these definitions up front, and
+/// keeps stored definitions in the form that the current version writes, so
+/// that no definition is more than one upgrade behind.
+///
+/// Every item is planned before any definition is rewritten, as a dry run. If
+/// any item fails to plan, the returned error reports all of the items that
+/// failed, rather than just the first, and no definition is rewritten.
+fn replan_items(tx: &Transaction, catalog: &Catalog) -> Result<(), anyhow::Error> {
+    let mut c = catalog.clone();
+    let mut rewrites = vec![];
+    let mut failures = vec![];
+    for (id, name, def, owner_id, timestamps) in tx.load_items()? {
+        let SerializedCatalogItem::V1 {
+            create_sql,
+            eval_env,
+            table_persist_name,
+            source_persist_details,
+        } = serde_json::from_slice(&def)?;
+        let item = c.parse_item(
+            id,
+            create_sql.clone(),
+            Some(&PlanContext::zero()),
+            table_persist_name.clone(),
+            source_persist_details.clone(),
+        );
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                // Items that depend on this item fail too, as they are planned
+                // after it, and are reported with it.
+                failures.push(format!(
+                    "{} ({}): {}",
+                    c.resolve_full_name(&name, None),
+                    id,
+                    e
+                ));
+                continue;
+            }
+        };
+        let oid = c.allocate_oid()?;
+        c.state
+            .insert_item(id, oid, name.clone(), item, owner_id, Some(timestamps));
+
+        let new_create_sql = c.get_entry(&id).create_sql();
+        if new_create_sql != create_sql {
+            let serialized_item = SerializedCatalogItem::V1 {
+                create_sql: new_create_sql.into(),
+                eval_env,
+                table_persist_name,
+                source_persist_details,
+            };
+            let serialized_item =
+                serde_json::to_vec(&serialized_item).expect("catalog serialization cannot fail");
+            rewrites.push((id, name.item, serialized_item, timestamps.updated_at));
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} catalog items cannot be planned by this version:\n    {}",
+            failures.len(),
+            failures.join("\n    ")
+        );
+    }
+    for (id, item_name, serialized_item, updated_at) in rewrites {
+        tx.update_item(id, &item_name, &serialized_item, updated_at)?;
+    }
+    Ok(())
+}
+
 // ****************************************************************************
 // AST migrations -- Basic AST -> AST transformations
 // ****************************************************************************
@@ -7,483 +7,853 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::{BTreeMap, HashMap};
+//! Durable storage for the catalog.
+//!
+//! The catalog is stored by a [`Backend`]. By default, the catalog is stored
+//! in a SQLite database in the data directory. It can instead be stored in a
+//! PostgreSQL database, so that the catalog outlives the machine on which
+//! `materialized` runs, or in typed collections of the STORAGE stash in the
+//! data directory, so that the data directory has a single store of metadata.
+//!
+//! Each backend manages its own schema and applies its own migrations when it
+//! is opened. The settings that describe the catalog as a whole, like its
+//! cluster ID and whether it was created in experimental mode, are managed by
+//! [`Connection`] in terms of the backend's settings. Other settings, like
+//! feature flags, are stored as JSON under a [`SettingNamespace`], so that
+//! adding one requires neither a migration nor a bespoke accessor.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::ops::Range;
 use std::path::Path;
+use std::str::FromStr;
 
-use rusqlite::params;
-use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, Value, ValueRef};
-use rusqlite::OptionalExtension;
-use serde::{Deserialize, Serialize};
-use timely::progress::Antichain;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-use crate::catalog::builtin::BuiltinLog;
 use mz_dataflow_types::client::ComputeInstanceId;
-use mz_dataflow_types::sources::MzOffset;
-use mz_expr::{GlobalId, PartitionId};
-use mz_ore::cast::CastFrom;
-use mz_ore::collections::CollectionExt;
+use mz_expr::GlobalId;
+use mz_ore::now::EpochMillis;
+use mz_sql::ast::Privilege;
 use mz_sql::catalog::CatalogError as SqlCatalogError;
-use mz_sql::names::{
-    DatabaseId, ObjectQualifiers, QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaId,
-    SchemaSpecifier,
-};
-use mz_sql::plan::ComputeInstanceConfig;
-use mz_stash::Stash;
-use uuid::Uuid;
+use mz_sql::names::{DatabaseId, QualifiedObjectName, SchemaId};
+use mz_sql::plan::{ComputeInstanceConfig, ComputeInstanceReplicaConfig, RoleRestrictions};
 
+use crate::catalog::builtin::BuiltinLog;
 use crate::catalog::error::{Error, ErrorKind};
+use crate::catalog::{AuditEvent, ObjectTimestamps, SecretAuditEvent};
+
+mod dump;
+mod postgres;
+mod sqlite;
+mod stash;
+
+pub use self::dump::{
+    CatalogDump, DumpedCluster, DumpedDatabase, DumpedItem, DumpedRole, DumpedSchema, DUMP_VERSION,
+};
+pub use self::postgres::PostgresBackend;
+pub use self::sqlite::{SqliteBackend, SqliteSynchronous};
+pub use self::stash::StashBackend;
 
+/// Identifies a catalog created by `materialized`, as opposed to some other
+/// database that happens to be at the configured location.
 const APPLICATION_ID: i32 = 0x1854_47dc;
 
-/// A catalog migration
-trait Migration {
-    /// Applies a catalog migration given the top level data directory and an active transaction to
-    /// the catalog's SQLite database.
-    fn apply(&self, path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error>;
+/// A namespace of global IDs, each of which has its own allocator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GidNamespace {
+    /// IDs of builtin objects, like [`GlobalId::System`].
+    System,
+    /// IDs of user objects, like [`GlobalId::User`].
+    User,
 }
 
-impl<'a> Migration for &'a str {
-    fn apply(&self, _path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error> {
-        tx.execute_batch(self)?;
-        Ok(())
+impl GidNamespace {
+    /// Returns the name of the table that stores the next ID to allocate.
+    fn alloc_table(&self) -> &'static str {
+        match self {
+            GidNamespace::System => "system_gid_alloc",
+            GidNamespace::User => "user_gid_alloc",
+        }
+    }
+}
+
+/// Returns the ID that follows a block of `amount` IDs starting at `next_id`.
+///
+/// # Errors
+///
+/// - If the block would extend past `i64::MAX`, the largest ID that can be
+///   stored.
+fn advance_gid(next_id: i64, amount: u64) -> Result<i64, Error> {
+    i64::try_from(amount)
+        .ok()
+        .and_then(|amount| next_id.checked_add(amount))
+        .ok_or_else(|| Error::new(ErrorKind::IdExhaustion))
+}
+
+/// A namespace of settings stored in the catalog.
+///
+/// A namespaced setting is stored under the name `<namespace>.<name>`, which
+/// cannot collide with the settings that describe the catalog itself, as none
+/// of their names contain a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingNamespace {
+    /// Settings that configure the system as a whole, like feature flags and
+    /// tuning knobs.
+    System,
+    /// The replication slots from which subscribers stream publications, by
+    /// slot name.
+    ReplicationSlot,
+    /// The statistics about user indexes that the optimizer consults, by
+    /// index ID.
+    Statistics,
+}
+
+impl SettingNamespace {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SettingNamespace::System => "system",
+            SettingNamespace::ReplicationSlot => "replication_slot",
+            SettingNamespace::Statistics => "statistics",
+        }
+    }
+
+    /// Returns the name under which the setting `name` is stored.
+    fn qualify(&self, name: &str) -> String {
+        format!("{}.{}", self.as_str(), name)
     }
 }
 
-impl<F: Fn(&Path, &rusqlite::Transaction) -> Result<(), Error>> Migration for F {
-    fn apply(&self, path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error> {
-        (self)(path, tx)
+impl fmt::Display for SettingNamespace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
-/// Schema migrations for the on-disk state.
-const MIGRATIONS: &[&dyn Migration] = &[
-    // Creates initial schema.
-    //
-    // Introduced for v0.1.0.
-    &"CREATE TABLE gid_alloc (
-         next_gid integer NOT NULL
-     );
-
-     CREATE TABLE databases (
-         id   integer PRIMARY KEY,
-         name text NOT NULL UNIQUE
-     );
-
-     CREATE TABLE schemas (
-         id          integer PRIMARY KEY,
-         database_id integer REFERENCES databases,
-         name        text NOT NULL,
-         UNIQUE (database_id, name)
-     );
-
-     CREATE TABLE items (
-         gid        blob PRIMARY KEY,
-         schema_id  integer REFERENCES schemas,
-         name       text NOT NULL,
-         definition blob NOT NULL,
-         UNIQUE (schema_id, name)
-     );
-
-     CREATE TABLE timestamps (
-         sid blob NOT NULL,
-         vid blob NOT NULL,
-         timestamp integer NOT NULL,
-         offset blob NOT NULL,
-         PRIMARY KEY (sid, vid, timestamp)
-     );
-
-     INSERT INTO gid_alloc VALUES (1);
-     INSERT INTO databases VALUES (1, 'materialize');
-     INSERT INTO schemas VALUES
-         (1, NULL, 'mz_catalog'),
-         (2, NULL, 'pg_catalog'),
-         (3, 1, 'public');",
-    // Adjusts timestamp table to support multi-partition Kafka topics.
-    //
-    // Introduced for v0.1.4.
-    //
-    // ATTENTION: this migration blows away data and must not be used as a model
-    // for future migrations! It is only acceptable now because we have not yet
-    // made any consistency promises to users.
-    &"DROP TABLE timestamps;
-     CREATE TABLE timestamps (
-        sid blob NOT NULL,
-        vid blob NOT NULL,
-        pcount blob NOT NULL,
-        pid blob NOT NULL,
-        timestamp integer NOT NULL,
-        offset blob NOT NULL,
-        PRIMARY KEY (sid, vid, pid, timestamp)
-    );",
-    // Introduces settings table to support persistent node settings.
-    //
-    // Introduced in v0.4.0.
-    &"CREATE TABLE settings (
-        name TEXT PRIMARY KEY,
-        value TEXT
-    );",
-    // Creates the roles table and a default "materialize" user.
-    //
-    // Introduced in v0.7.0.
-    &"CREATE TABLE roles (
-        id   integer PRIMARY KEY,
-        name text NOT NULL UNIQUE
-    );
-    INSERT INTO roles VALUES (1, 'materialize');",
-    // Makes the mz_internal schema literal so it can store functions.
-    //
-    // Introduced in v0.7.0.
-    &"INSERT INTO schemas (database_id, name) VALUES
-        (NULL, 'mz_internal');",
-    // Adjusts timestamp table to support replayable source timestamp bindings.
-    //
-    // Introduced for v0.7.4
-    //
-    // ATTENTION: this migration blows away data and must not be used as a model
-    // for future migrations! It is only acceptable now because we have not yet
-    // made any consistency promises to users.
-    &"DROP TABLE timestamps;
-     CREATE TABLE timestamps (
-        sid blob NOT NULL,
-        pid blob NOT NULL,
-        timestamp integer NOT NULL,
-        offset blob NOT NULL,
-        PRIMARY KEY (sid, pid, timestamp, offset)
-    );",
-    // Makes the information_schema schema literal so it can store functions.
-    //
-    // Introduced in v0.9.12.
-    &"INSERT INTO schemas (database_id, name) VALUES
-        (NULL, 'information_schema');",
-    // Adds index to timestamp table to more efficiently compact timestamps.
-    //
-    // Introduced in v0.12.0.
-    &"CREATE INDEX timestamps_sid_timestamp ON timestamps (sid, timestamp)",
-    // Adds table to track users' compute instances.
-    //
-    // Introduced in v0.22.0.
-    &"CREATE TABLE compute_instances (
-        id   integer PRIMARY KEY,
-        name text NOT NULL UNIQUE
-    );
-    INSERT INTO compute_instances VALUES (1, 'default');",
-    // Introduced in v0.24.0.
-    &"ALTER TABLE compute_instances ADD COLUMN config text",
-    // Migrates timestamp bindings from the coordinator's catalog to STORAGE's internal state
-    // Introduced in v0.26.0.
-    &|data_dir_path: &Path, tx: &rusqlite::Transaction| {
-        let source_ids = tx
-            .prepare("SELECT DISTINCT sid FROM timestamps")?
-            .query_and_then([], |row| Ok(row.get::<_, SqlVal<GlobalId>>(0)?.0))?
-            .collect::<Result<Vec<_>, Error>>()?;
-
-        let stash = mz_stash::Sqlite::open(&data_dir_path.join("storage"))
-            .expect("unable to open STORAGE stash");
-
-        let mut statement = tx.prepare(
-            "SELECT pid, timestamp, offset FROM timestamps WHERE sid = ? ORDER BY pid, timestamp",
-        )?;
-        for source_id in source_ids {
-            let bindings = statement
-                .query_and_then(params![SqlVal(&source_id)], |row| {
-                    let partition: PartitionId = row
-                        .get::<_, String>(0)
-                        .unwrap()
-                        .parse()
-                        .expect("parsing partition id from string cannot fail");
-                    let timestamp: i64 = row.get(1)?;
-                    let offset = MzOffset {
-                        offset: row.get(2)?,
-                    };
-
-                    Ok((partition, timestamp, offset))
-                })?
-                .collect::<Result<Vec<_>, Error>>()?;
-
-            let ts_binding_stash = stash
-                .collection::<PartitionId, ()>(&format!("timestamp-bindings-{source_id}"))
-                .expect("failed to read timestamp bindings");
-
-            // See
-            // [mz_dataflow_types::client::controller::StorageControllerMut::persist_timestamp_bindings]
-            // for an explanation of the logic
-            let mut last_reported_ts_bindings: HashMap<_, MzOffset> = HashMap::new();
-            let seal_ts = bindings.iter().map(|(_, ts, _)| *ts).max();
-            stash
-                .update_many(
-                    ts_binding_stash,
-                    bindings.into_iter().map(|(pid, ts, offset)| {
-                        let prev_offset = last_reported_ts_bindings.entry(pid.clone()).or_default();
-                        let update = ((pid, ()), ts, offset.offset - prev_offset.offset);
-                        prev_offset.offset = offset.offset;
-                        update
-                    }),
-                )
-                .expect("failed to write timestamp bindings");
-
-            stash
-                .seal(ts_binding_stash, Antichain::from_iter(seal_ts).borrow())
-                .expect("failed to write timestamp bindings");
+/// Whether commits to the catalog wait for their changes to be synced to
+/// durable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogDurability {
+    /// Commits never wait for their changes to be synced, whatever the
+    /// backend is otherwise configured to do. Recent catalog changes can be
+    /// lost if the machine crashes, or, for a catalog stored in PostgreSQL,
+    /// if the database server crashes.
+    ///
+    /// This is unsafe, and intended only for throwaway test environments.
+    None,
+    /// Commits are synced as the backend is configured to sync them.
+    Fsync,
+}
+
+impl CatalogDurability {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CatalogDurability::None => "none",
+            CatalogDurability::Fsync => "fsync",
         }
+    }
+}
 
-        tx.execute_batch("DROP TABLE timestamps;")?;
+impl Default for CatalogDurability {
+    fn default() -> CatalogDurability {
+        CatalogDurability::Fsync
+    }
+}
 
-        Ok(())
+impl fmt::Display for CatalogDurability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CatalogDurability {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<CatalogDurability, anyhow::Error> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(CatalogDurability::None),
+            "fsync" => Ok(CatalogDurability::Fsync),
+            _ => anyhow::bail!("invalid catalog durability {}", s),
+        }
+    }
+}
+
+/// An item as stored in the catalog: its ID, name, serialized definition,
+/// owner's role ID, and timestamps.
+pub type StoredItem = (
+    GlobalId,
+    QualifiedObjectName,
+    Vec<u8>,
+    i64,
+    ObjectTimestamps,
+);
+
+/// An item to insert with [`Transaction::insert_items`]: its ID, the ID of its
+/// schema, its name, and its serialized definition.
+pub type NewItem = (GlobalId, SchemaId, String, Vec<u8>);
+
+/// An item to remove with [`Transaction::remove_items`]: its ID, and its fully
+/// qualified name and type, which are recorded alongside its definition among
+/// the dropped items.
+pub type RemovedItem = (GlobalId, String, String);
+
+/// A dropped item as stored in the catalog: its ID, fully qualified name,
+/// type, serialized definition, and the time at which it was dropped.
+pub type StoredDroppedItem = (GlobalId, String, String, Vec<u8>, EpochMillis);
+
+/// A new name and serialized definition for an existing item, to apply with
+/// [`Transaction::update_items`].
+pub type ItemUpdate = (GlobalId, String, Vec<u8>);
+
+/// The IDs of the objects on which an item depends, to store with
+/// [`Transaction::set_item_dependencies`].
+pub type ItemDependencies = (GlobalId, Vec<GlobalId>);
+
+/// A comment as stored in the catalog: the ID of the commented item, the
+/// 1-based position of the commented column if the comment applies to a
+/// column, and the comment itself.
+pub type StoredComment = (GlobalId, Option<usize>, String);
+
+/// A role as stored in the catalog: its ID, name, restrictions, the
+/// SCRAM-SHA-256 verifier of its password, if it has one, and timestamps.
+pub type StoredRole = (
+    i64,
+    String,
+    RoleRestrictions,
+    Option<String>,
+    ObjectTimestamps,
+);
+
+/// A session default as stored in the catalog: the ID of the role to which the
+/// default applies, or `None` if it applies to every role, and the name and
+/// value of the session variable.
+pub type StoredRoleSetting = (Option<i64>, String, String);
+
+/// A privilege as stored in the catalog: the object to which it applies, the
+/// ID of the role to which it was granted, or `None` if it was granted to every
+/// role, and the privilege itself.
+pub type StoredPrivilege = (OwnedObjectId, Option<i64>, Privilege);
+
+/// A replica of a compute instance as stored in the catalog: the replica's ID,
+/// the ID of its compute instance, and its name, configuration, and creation
+/// and modification times.
+pub type StoredComputeReplica = (
+    i64,
+    ComputeInstanceId,
+    String,
+    ComputeInstanceReplicaConfig,
+    ObjectTimestamps,
+);
+
+/// The versions of the catalog's schema and contents, as recorded in the
+/// settings table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogVersions {
+    /// The index of the last migration applied to the backend's schema, or
+    /// `None` if the catalog was last migrated by a version of materialized
+    /// that did not record it.
+    pub schema_version: Option<u64>,
+    /// When the last migration was applied, or `None` if this is not known.
+    pub last_migration_at: Option<EpochMillis>,
+    /// The version of materialized that last migrated the catalog's contents.
+    pub catalog_content_version: String,
+}
+
+/// The references between catalog objects exactly as stored, without the joins
+/// that the loaders use to resolve them, for [`Connection::check_consistency`].
+#[derive(Debug)]
+pub struct StoredReferences {
+    /// The ID of every database.
+    databases: Vec<i64>,
+    /// The ID of every schema, and the ID of its database, if any.
+    schemas: Vec<(i64, Option<i64>)>,
+    /// The ID, name, and schema ID of every item.
+    items: Vec<(GlobalId, String, Option<i64>)>,
+    /// The schema name, object name, and ID of every builtin object.
+    system_gids: Vec<(String, String, u64)>,
+    /// The compute instance ID, log name, and index ID of every introspection
+    /// source index.
+    introspection_source_indexes: Vec<(i64, String, u64)>,
+    /// The next system ID to be allocated.
+    next_system_gid: u64,
+    /// The next user ID to be allocated.
+    next_user_gid: u64,
+}
+
+/// A violation of the catalog's referential integrity, as reported by
+/// [`Connection::check_consistency`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// An item refers to a schema that does not exist.
+    DanglingSchemaReference {
+        item: GlobalId,
+        schema_id: Option<i64>,
     },
-    // Allows us to dynamically assign system IDs to all objects but funcs. Also allows us to
-    // track built-in object name to ID mapping.
-    //
-    // Introduced in v0.26.0
-    &"ALTER TABLE gid_alloc RENAME TO user_gid_alloc;
-
-    CREATE TABLE system_gid_alloc (
-        next_gid integer NOT NULL
-    );
-
-    -- Higher than all previous statically assigned IDs
-    INSERT INTO system_gid_alloc VALUES (5044);
-
-    CREATE TABLE system_gid_mapping (
-        schema_name text NOT NULL,
-        object_name text NOT NULL,
-        id integer NOT NULL,
-        fingerprint integer NOT NULL,
-        PRIMARY KEY (schema_name, object_name)
-    );
-
-    -- We need to insert previous static IDs in the mapping so user can successfully upgrade
-    INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint) VALUES
-        -- Types
-        ('pg_catalog', 'bool', 1000, 0),
-        ('pg_catalog', 'bytea', 1001, 0),
-        ('pg_catalog', 'int8', 1002, 0),
-        ('pg_catalog', 'int4', 1003, 0),
-        ('pg_catalog', 'text', 1004, 0),
-        ('pg_catalog', 'oid', 1005, 0),
-        ('pg_catalog', 'float4', 1006, 0),
-        ('pg_catalog', 'float8', 1007, 0),
-        ('pg_catalog', '_bool', 1008, 0),
-        ('pg_catalog', '_bytea', 1009, 0),
-        ('pg_catalog', '_int4', 1010, 0),
-        ('pg_catalog', '_text', 1011, 0),
-        ('pg_catalog', '_int8', 1012, 0),
-        ('pg_catalog', '_float4', 1013, 0),
-        ('pg_catalog', '_float8', 1014, 0),
-        ('pg_catalog', '_oid', 1015, 0),
-        ('pg_catalog', 'date', 1016, 0),
-        ('pg_catalog', 'time', 1017, 0),
-        ('pg_catalog', 'timestamp', 1018, 0),
-        ('pg_catalog', '_timestamp', 1019, 0),
-        ('pg_catalog', '_date', 1020, 0),
-        ('pg_catalog', '_time', 1021, 0),
-        ('pg_catalog', 'timestamptz', 1022, 0),
-        ('pg_catalog', '_timestamptz', 1023, 0),
-        ('pg_catalog', 'interval', 1024, 0),
-        ('pg_catalog', '_interval', 1025, 0),
-        ('pg_catalog', 'numeric', 1026, 0),
-        ('pg_catalog', '_numeric', 1027, 0),
-        ('pg_catalog', 'record', 1028, 0),
-        ('pg_catalog', '_record', 1029, 0),
-        ('pg_catalog', 'uuid', 1030, 0),
-        ('pg_catalog', '_uuid', 1031, 0),
-        ('pg_catalog', 'jsonb', 1032, 0),
-        ('pg_catalog', '_jsonb', 1033, 0),
-        ('pg_catalog', 'any', 1034, 0),
-        ('pg_catalog', 'anyarray', 1035, 0),
-        ('pg_catalog', 'anyelement', 1036, 0),
-        ('pg_catalog', 'anynonarray', 1037, 0),
-        ('pg_catalog', 'char', 1038, 0),
-        ('pg_catalog', 'varchar', 1039, 0),
-        ('pg_catalog', 'int2', 1040, 0),
-        ('pg_catalog', '_int2', 1041, 0),
-        ('pg_catalog', 'bpchar', 1042, 0),
-        ('pg_catalog', '_char', 1043, 0),
-        ('pg_catalog', '_varchar', 1044, 0),
-        ('pg_catalog', '_bpchar', 1045, 0),
-        ('pg_catalog', 'regproc', 1046, 0),
-        ('pg_catalog', '_regproc', 1047, 0),
-        ('pg_catalog', 'regtype', 1048, 0),
-        ('pg_catalog', '_regtype', 1049, 0),
-        ('pg_catalog', 'regclass', 1050, 0),
-        ('pg_catalog', '_regclass', 1051, 0),
-        ('pg_catalog', 'int2vector', 1052, 0),
-        ('pg_catalog', '_int2vector', 1053, 0),
-        ('pg_catalog', 'anycompatible', 1054, 0),
-        ('pg_catalog', 'anycompatiblearray', 1055, 0),
-        ('pg_catalog', 'anycompatiblenonarray', 1056, 0),
-        ('pg_catalog', 'list', 1998, 0),
-        ('pg_catalog', 'map', 1999, 0),
-        ('pg_catalog', 'anycompatiblelist', 1997, 0),
-        ('pg_catalog', 'anycompatiblemap', 1996, 0),
-        -- Logs
-        ('mz_catalog', 'mz_dataflow_operators', 3000, 0),
-        ('mz_catalog', 'mz_dataflow_operator_addresses', 3002, 0),
-        ('mz_catalog', 'mz_dataflow_channels', 3004, 0),
-        ('mz_catalog', 'mz_scheduling_elapsed_internal', 3006, 0),
-        ('mz_catalog', 'mz_scheduling_histogram_internal', 3008, 0),
-        ('mz_catalog', 'mz_scheduling_parks_internal', 3010, 0),
-        ('mz_catalog', 'mz_arrangement_batches_internal', 3012, 0),
-        ('mz_catalog', 'mz_arrangement_sharing_internal', 3014, 0),
-        ('mz_catalog', 'mz_materializations', 3016, 0),
-        ('mz_catalog', 'mz_materialization_dependencies', 3018, 0),
-        ('mz_catalog', 'mz_worker_materialization_frontiers', 3020, 0),
-        ('mz_catalog', 'mz_peek_active', 3022, 0),
-        ('mz_catalog', 'mz_peek_durations', 3024, 0),
-        ('mz_catalog', 'mz_source_info', 3026, 0),
-        ('mz_catalog', 'mz_message_counts_received_internal', 3028, 0),
-        ('mz_catalog', 'mz_message_counts_sent_internal', 3036, 0),
-        ('mz_catalog', 'mz_dataflow_operator_reachability_internal', 3034, 0),
-        ('mz_catalog', 'mz_arrangement_records_internal', 3038, 0),
-        ('mz_catalog', 'mz_kafka_source_statistics', 3040, 0),
-         -- Tables
-        ('mz_catalog', 'mz_view_keys', 4001, 0),
-        ('mz_catalog', 'mz_view_foreign_keys', 4003, 0),
-        ('mz_catalog', 'mz_kafka_sinks', 4005, 0),
-        ('mz_catalog', 'mz_avro_ocf_sinks', 4007, 0),
-        ('mz_catalog', 'mz_databases', 4009, 0),
-        ('mz_catalog', 'mz_schemas', 4011, 0),
-        ('mz_catalog', 'mz_columns', 4013, 0),
-        ('mz_catalog', 'mz_indexes', 4015, 0),
-        ('mz_catalog', 'mz_index_columns', 4017, 0),
-        ('mz_catalog', 'mz_tables', 4019, 0),
-        ('mz_catalog', 'mz_sources', 4021, 0),
-        ('mz_catalog', 'mz_sinks', 4023, 0),
-        ('mz_catalog', 'mz_views', 4025, 0),
-        ('mz_catalog', 'mz_types', 4027, 0),
-        ('mz_catalog', 'mz_array_types', 4029, 0),
-        ('mz_catalog', 'mz_base_types', 4031, 0),
-        ('mz_catalog', 'mz_list_types', 4033, 0),
-        ('mz_catalog', 'mz_map_types', 4035, 0),
-        ('mz_catalog', 'mz_roles', 4037, 0),
-        ('mz_catalog', 'mz_pseudo_types', 4039, 0),
-        ('mz_catalog', 'mz_functions', 4041, 0),
-        ('mz_catalog', 'mz_metrics', 4043, 0),
-        ('mz_catalog', 'mz_metrics_meta', 4045, 0),
-        ('mz_catalog', 'mz_metric_histograms', 4047, 0),
-        ('mz_catalog', 'mz_clusters', 4049, 0),
-        ('mz_catalog', 'mz_secrets', 4050, 0),
-        -- Views
-        ('mz_catalog', 'mz_relations', 5000, 0),
-        ('mz_catalog', 'mz_objects', 5001, 0),
-        ('mz_catalog', 'mz_catalog_names', 5002, 0),
-        ('mz_catalog', 'mz_dataflow_names', 5003, 0),
-        ('mz_catalog', 'mz_dataflow_operator_dataflows', 5004, 0),
-        ('mz_catalog', 'mz_materialization_frontiers', 5005, 0),
-        ('mz_catalog', 'mz_records_per_dataflow_operator', 5006, 0),
-        ('mz_catalog', 'mz_records_per_dataflow', 5007, 0),
-        ('mz_catalog', 'mz_records_per_dataflow_global', 5008, 0),
-        ('mz_catalog', 'mz_perf_arrangement_records', 5009, 0),
-        ('mz_catalog', 'mz_perf_peek_durations_core', 5010, 0),
-        ('mz_catalog', 'mz_perf_peek_durations_bucket', 5011, 0),
-        ('mz_catalog', 'mz_perf_peek_durations_aggregates', 5012, 0),
-        ('mz_catalog', 'mz_perf_dependency_frontiers', 5013, 0),
-        ('pg_catalog', 'pg_namespace', 5014, 0),
-        ('pg_catalog', 'pg_class', 5015, 0),
-        ('pg_catalog', 'pg_database', 5016, 0),
-        ('pg_catalog', 'pg_index', 5017, 0),
-        ('pg_catalog', 'pg_description', 5018, 0),
-        ('pg_catalog', 'pg_type', 5019, 0),
-        ('pg_catalog', 'pg_attribute', 5020, 0),
-        ('pg_catalog', 'pg_proc', 5021, 0),
-        ('pg_catalog', 'pg_range', 5022, 0),
-        ('pg_catalog', 'pg_enum', 5023, 0),
-        ('pg_catalog', 'pg_attrdef', 5025, 0),
-        ('pg_catalog', 'pg_settings', 5026, 0),
-        ('mz_catalog', 'mz_scheduling_elapsed', 5027, 0),
-        ('mz_catalog', 'mz_scheduling_histogram', 5028, 0),
-        ('mz_catalog', 'mz_scheduling_parks', 5029, 0),
-        ('mz_catalog', 'mz_message_counts', 5030, 0),
-        ('mz_catalog', 'mz_dataflow_operator_reachability', 5031, 0),
-        ('mz_catalog', 'mz_arrangement_sizes', 5032, 0),
-        ('mz_catalog', 'mz_arrangement_sharing', 5033, 0),
-        ('pg_catalog', 'pg_constraint', 5034, 0),
-        ('pg_catalog', 'pg_tables', 5035, 0),
-        ('pg_catalog', 'pg_am', 5036, 0),
-        ('pg_catalog', 'pg_roles', 5037, 0),
-        ('pg_catalog', 'pg_views', 5038, 0),
-        ('information_schema', 'columns', 5039, 0),
-        ('information_schema', 'tables', 5040, 0),
-        ('pg_catalog', 'pg_collation', 5041, 0),
-        ('pg_catalog', 'pg_policy', 5042, 0),
-        ('pg_catalog', 'pg_inherits', 5043, 0);
-
-    CREATE TABLE compute_introspection_source_indexes (
-        compute_id integer NOT NULL,
-        name text NOT NULL,
-        index_id integer NOT NULL,
-        PRIMARY KEY (compute_id, name)
-    );
-    CREATE INDEX compute_introspection_source_indexes_ind
-        ON compute_introspection_source_indexes(compute_id);",
-    // Add new migrations here.
-    //
-    // Migrations should be preceded with a comment of the following form:
-    //
-    //     > Short summary of migration's purpose.
-    //     >
-    //     > Introduced in <VERSION>.
-    //     >
-    //     > Optional additional commentary about safety or approach.
-    //
-    // Please include @benesch on any code reviews that add or edit migrations.
-    // Migrations must preserve backwards compatibility with all past releases
-    // of materialized. Migrations can be edited up until they ship in a
-    // release, after which they must never be removed, only patched by future
-    // migrations.
-];
+    /// A schema refers to a database that does not exist.
+    DanglingDatabaseReference { schema_id: i64, database_id: i64 },
+    /// Several objects have the same ID.
+    DuplicateGlobalId { id: GlobalId, objects: Vec<String> },
+    /// An object has an ID that was never allocated to it.
+    UnallocatedGlobalId { id: GlobalId, object: String },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Inconsistency::DanglingSchemaReference {
+                item,
+                schema_id: Some(schema_id),
+            } => write!(f, "item {} refers to unknown schema {}", item, schema_id),
+            Inconsistency::DanglingSchemaReference {
+                item,
+                schema_id: None,
+            } => write!(f, "item {} has no schema", item),
+            Inconsistency::DanglingDatabaseReference {
+                schema_id,
+                database_id,
+            } => write!(
+                f,
+                "schema {} refers to unknown database {}",
+                schema_id, database_id
+            ),
+            Inconsistency::DuplicateGlobalId { id, objects } => {
+                write!(f, "ID {} is used by {}", id, objects.join(", "))
+            }
+            Inconsistency::UnallocatedGlobalId { id, object } => {
+                write!(f, "ID {} of {} was never allocated", id, object)
+            }
+        }
+    }
+}
+
+/// Identifies a catalog object that has an owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OwnedObjectId {
+    Database(DatabaseId),
+    Schema(SchemaId),
+    ComputeInstance(ComputeInstanceId),
+    Item(GlobalId),
+}
+
+impl OwnedObjectId {
+    /// Returns the error that reports that the object does not exist.
+    fn unknown(&self) -> Error {
+        match self {
+            OwnedObjectId::Database(id) => SqlCatalogError::UnknownDatabase(id.to_string()),
+            OwnedObjectId::Schema(id) => SqlCatalogError::UnknownSchema(id.0.to_string()),
+            OwnedObjectId::ComputeInstance(id) => {
+                SqlCatalogError::UnknownComputeInstance(id.to_string())
+            }
+            OwnedObjectId::Item(id) => SqlCatalogError::UnknownItem(id.to_string()),
+        }
+        .into()
+    }
+
+    /// Returns the type and ID with which privileges on the object are stored.
+    pub fn stored_id(&self) -> (&'static str, String) {
+        match self {
+            OwnedObjectId::Database(id) => ("database", id.0.to_string()),
+            OwnedObjectId::Schema(id) => ("schema", id.0.to_string()),
+            OwnedObjectId::ComputeInstance(id) => ("cluster", id.to_string()),
+            OwnedObjectId::Item(id) => ("item", id.to_string()),
+        }
+    }
+
+    /// The inverse of [`OwnedObjectId::stored_id`].
+    fn parse_stored_id(object_type: &str, id: &str) -> Result<OwnedObjectId, Error> {
+        let object = match object_type {
+            "database" => id
+                .parse()
+                .ok()
+                .map(|id| OwnedObjectId::Database(DatabaseId(id))),
+            "schema" => id
+                .parse()
+                .ok()
+                .map(|id| OwnedObjectId::Schema(SchemaId(id))),
+            "cluster" => id.parse().ok().map(OwnedObjectId::ComputeInstance),
+            "item" => id.parse().ok().map(OwnedObjectId::Item),
+            _ => None,
+        };
+        object.ok_or_else(|| {
+            Error::new(ErrorKind::Corruption {
+                detail: format!("invalid privilege object {} {}", object_type, id),
+            })
+        })
+    }
+}
+
+/// Stores the catalog durably.
+///
+/// The methods that modify the catalog outside of a [`BackendTransaction`]
+/// must apply their modifications atomically.
+pub trait Backend: fmt::Debug + Send {
+    /// Returns the value of the named setting, if it is set.
+    fn setting(&mut self, name: &str) -> Result<Option<String>, Error>;
+
+    /// Sets the value of the named setting, replacing any existing value.
+    fn set_setting(&mut self, name: &str, value: &str) -> Result<(), Error>;
+
+    /// Removes the named setting, if it is set.
+    fn remove_setting(&mut self, name: &str) -> Result<(), Error>;
+
+    /// Returns the names and values of every setting.
+    fn load_settings(&mut self) -> Result<Vec<(String, String)>, Error>;
+
+    fn load_databases(&self) -> Result<Vec<(DatabaseId, String, i64, ObjectTimestamps)>, Error>;
+
+    fn load_schemas(
+        &self,
+    ) -> Result<Vec<(SchemaId, String, Option<DatabaseId>, i64, ObjectTimestamps)>, Error>;
+
+    fn load_roles(&self) -> Result<Vec<StoredRole>, Error>;
+
+    fn load_compute_instances(
+        &self,
+    ) -> Result<Vec<(i64, String, ComputeInstanceConfig, i64, ObjectTimestamps)>, Error>;
+
+    fn load_compute_replicas(&self) -> Result<Vec<StoredComputeReplica>, Error>;
+
+    /// Load the persisted mapping of system object to global ID and
+    /// fingerprint. Key is (schema-name, object-name). The fingerprint is
+    /// `None` if it was recorded before fingerprints were stable.
+    fn load_system_gids(
+        &self,
+    ) -> Result<BTreeMap<(String, String), (GlobalId, Option<u64>)>, Error>;
+
+    fn load_introspection_source_index_gids(
+        &self,
+        compute_id: i64,
+    ) -> Result<BTreeMap<String, GlobalId>, Error>;
+
+    /// Persist mapping from system objects to global IDs. Each element of `mappings` should be
+    /// (schema-name, object-name, global-id).
+    ///
+    /// Panics if provided id is not a system id
+    fn set_system_gids(&mut self, mappings: Vec<(&str, &str, GlobalId, u64)>) -> Result<(), Error>;
+
+    /// Replaces the IDs of re-created system objects in the comments,
+    /// privileges, and item dependencies that refer to them. Each element of
+    /// `replacements` should be (old-global-id, new-global-id).
+    fn replace_system_gids(&mut self, replacements: Vec<(GlobalId, GlobalId)>)
+        -> Result<(), Error>;
+
+    /// Panics if provided id is not a system id
+    fn set_introspection_source_index_gids(
+        &mut self,
+        mappings: Vec<(i64, &str, GlobalId)>,
+    ) -> Result<(), Error>;
+
+    /// Loads every recorded operation on secrets storage, in the order in
+    /// which the operations were recorded.
+    fn load_secret_audit_events(&self) -> Result<Vec<SecretAuditEvent>, Error>;
+
+    /// Loads every recorded change to catalog objects, in the order in which
+    /// the changes were recorded.
+    fn load_audit_events(&self) -> Result<Vec<AuditEvent>, Error>;
+
+    /// Loads the comments on every item and column.
+    fn load_comments(&self) -> Result<Vec<StoredComment>, Error>;
+
+    /// Loads the session defaults for every role.
+    fn load_role_settings(&self) -> Result<Vec<StoredRoleSetting>, Error>;
+
+    /// Loads every privilege granted on catalog objects.
+    fn load_privileges(&self) -> Result<Vec<StoredPrivilege>, Error>;
+
+    /// Loads every dropped item that has not yet been purged, in the order in
+    /// which the items were dropped.
+    fn load_dropped_items(&self) -> Result<Vec<StoredDroppedItem>, Error>;
+
+    /// Loads the references between catalog objects from a consistent
+    /// snapshot of the catalog.
+    fn load_references(&self) -> Result<StoredReferences, Error>;
+
+    /// Allocates `amount` consecutive IDs in `namespace`, returning the first
+    /// ID of the block.
+    fn allocate_global_id(&mut self, namespace: GidNamespace, amount: u64) -> Result<u64, Error>;
+
+    /// Starts a transaction, whose modifications are applied atomically when
+    /// it is committed and discarded if it is dropped without being committed.
+    fn transaction(&mut self) -> Result<Box<dyn BackendTransaction + '_>, Error>;
+
+    /// Writes a consistent snapshot of the catalog to a new file at `path`,
+    /// from which the catalog can be restored with [`Connection::restore`].
+    fn backup(&mut self, path: &Path) -> Result<(), Error>;
+}
+
+/// A transaction against a [`Backend`].
+pub trait BackendTransaction {
+    fn load_items(&self) -> Result<Vec<StoredItem>, Error>;
+
+    /// Loads the IDs of the objects on which each item depends. Items that
+    /// depend on nothing are omitted.
+    fn load_item_dependencies(&self) -> Result<BTreeMap<GlobalId, BTreeSet<GlobalId>>, Error>;
+
+    fn insert_database(
+        &mut self,
+        database_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<DatabaseId, Error>;
+
+    fn insert_schema(
+        &mut self,
+        database_id: DatabaseId,
+        schema_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<SchemaId, Error>;
+
+    fn insert_role(
+        &mut self,
+        role_name: &str,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        now: EpochMillis,
+    ) -> Result<i64, Error>;
+
+    /// Replaces the restrictions and the password verifier of the role with
+    /// ID `id`.
+    fn update_role(
+        &mut self,
+        id: i64,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Panics if any introspection source id is not a system id
+    fn insert_compute_instance(
+        &mut self,
+        cluster_name: &str,
+        config: &ComputeInstanceConfig,
+        introspection_sources: &Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<i64, Error>;
+
+    fn update_compute_instance_config(
+        &mut self,
+        id: ComputeInstanceId,
+        config: &ComputeInstanceConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    fn insert_compute_replica(
+        &mut self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+        config: &ComputeInstanceReplicaConfig,
+        now: EpochMillis,
+    ) -> Result<i64, Error>;
+
+    fn update_compute_replica_config(
+        &mut self,
+        id: i64,
+        config: &ComputeInstanceReplicaConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    fn remove_compute_replica(
+        &self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+    ) -> Result<(), Error>;
+
+    fn insert_item(
+        &self,
+        id: GlobalId,
+        schema_id: SchemaId,
+        item_name: &str,
+        item: &[u8],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Inserts each of `items`, as if by `insert_item`, with as few
+    /// statements as the backend allows.
+    fn insert_items(&self, items: &[NewItem], owner_id: i64, now: EpochMillis)
+        -> Result<(), Error>;
+
+    /// Removes the database with the given ID, along with the privileges
+    /// granted on it.
+    fn remove_database(&self, id: &DatabaseId) -> Result<(), Error>;
+
+    /// Removes the schema with the given ID, along with the privileges granted
+    /// on it.
+    fn remove_schema(&self, database_id: &DatabaseId, schema_id: &SchemaId) -> Result<(), Error>;
+
+    /// Removes the named role, along with its session defaults and the
+    /// privileges granted to it.
+    fn remove_role(&self, name: &str) -> Result<(), Error>;
+
+    /// Removes the named compute instance, along with its replicas.
+    fn remove_compute_instance(&self, name: &str) -> Result<(), Error>;
+
+    /// Removes the item with the given ID, along with any comments on it or
+    /// its columns, its dependencies, and the privileges granted on it, and
+    /// records its definition among the dropped items.
+    fn remove_item(&self, item: &RemovedItem, dropped_at: EpochMillis) -> Result<(), Error>;
+
+    /// Removes each of `items`, as if by `remove_item`, with as few
+    /// statements as the backend allows.
+    fn remove_items(&self, items: &[RemovedItem], dropped_at: EpochMillis) -> Result<(), Error>;
+
+    /// Forgets the dropped item with the given ID, e.g., once it has been
+    /// restored.
+    fn remove_dropped_item(&self, id: GlobalId) -> Result<(), Error>;
+
+    /// Forgets every item dropped before `dropped_before`.
+    fn purge_dropped_items(&self, dropped_before: EpochMillis) -> Result<(), Error>;
+
+    fn update_item(
+        &self,
+        id: GlobalId,
+        item_name: &str,
+        item: &[u8],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Applies each of `updates`, as if by `update_item`, with as few
+    /// statements as the backend allows. Each item may be updated at most
+    /// once.
+    fn update_items(&self, updates: &[ItemUpdate], updated_at: EpochMillis) -> Result<(), Error>;
+
+    /// Replaces the stored dependencies of each of the items in
+    /// `dependencies`.
+    fn set_item_dependencies(&self, dependencies: &[ItemDependencies]) -> Result<(), Error>;
+
+    /// Renames the database with the given ID. Errors if a database with the
+    /// new name already exists.
+    fn rename_database(
+        &self,
+        id: &DatabaseId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Renames the schema with the given ID in the given database. Errors if
+    /// the database already contains a schema with the new name.
+    fn rename_schema(
+        &self,
+        database_id: &DatabaseId,
+        schema_id: &SchemaId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Transfers ownership of `object` to the role with ID `owner_id`.
+    fn update_owner(
+        &self,
+        object: OwnedObjectId,
+        owner_id: i64,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error>;
+
+    /// Records a change to a catalog object, assigning the event the next
+    /// event ID.
+    fn insert_audit_event(&mut self, event: &mut AuditEvent) -> Result<(), Error>;
+
+    /// Records operations on secrets storage, assigning each event the next
+    /// event ID.
+    fn insert_secret_audit_events(&mut self, events: &mut [SecretAuditEvent]) -> Result<(), Error>;
+
+    /// Forgets every recorded operation on secrets storage but the most
+    /// recent `retained`, returning the forgotten events.
+    fn purge_secret_audit_events(&self, retained: u64) -> Result<Vec<SecretAuditEvent>, Error>;
+
+    /// Records a comment on an item, or on one of its columns if
+    /// `column_position` is specified. Errors if such a comment already
+    /// exists.
+    fn insert_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error>;
+
+    /// Replaces an existing comment on an item or one of its columns.
+    fn update_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error>;
+
+    /// Removes an existing comment on an item or one of its columns.
+    fn delete_comment(&self, id: GlobalId, column_position: Option<usize>) -> Result<(), Error>;
+
+    /// Sets the default value of the named session variable for the role with
+    /// ID `role_id`, or for every role if `role_id` is `None`. A `value` of
+    /// `None` removes the default, if any.
+    fn set_role_setting(
+        &self,
+        role_id: Option<i64>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), Error>;
+
+    /// Grants `privilege` on `object` to the role with ID `role_id`, or to
+    /// every role if `role_id` is `None`. Granting a privilege that was already
+    /// granted has no effect.
+    fn grant_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error>;
+
+    /// Revokes `privilege` on `object` from the role with ID `role_id`, or from
+    /// every role if `role_id` is `None`. Revoking a privilege that was not
+    /// granted has no effect.
+    fn revoke_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error>;
+
+    fn commit(self: Box<Self>) -> Result<(), Error>;
+}
 
 #[derive(Debug)]
 pub struct Connection {
-    inner: rusqlite::Connection,
+    inner: Box<dyn Backend>,
     experimental_mode: bool,
+    safe_mode: bool,
     cluster_id: Uuid,
+    /// User IDs that have been allocated in storage but not yet handed out.
+    user_gid_block: Range<u64>,
+    /// How many user IDs to allocate in storage at once.
+    user_gid_block_size: u64,
 }
 
 impl Connection {
+    /// Opens the catalog stored in a SQLite database in the data directory at
+    /// `data_dir_path`, whose commits are made as durable as `synchronous`
+    /// requires, and which is encrypted with `encryption_key`, if specified.
+    ///
+    /// A catalog that was migrated into the STORAGE stash can only be opened
+    /// with [`Connection::open_stash`].
     pub fn open(
         data_dir_path: &Path,
+        synchronous: SqliteSynchronous,
+        encryption_key: Option<&str>,
         experimental_mode: Option<bool>,
+        safe_mode: Option<bool>,
     ) -> Result<Connection, Error> {
-        let mut sqlite = rusqlite::Connection::open(&data_dir_path.join("catalog"))?;
-
-        // Validate application ID.
-        let tx = sqlite.transaction()?;
-        let app_id: i32 = tx.query_row("PRAGMA application_id", params![], |row| row.get(0))?;
-        if app_id == 0 {
-            // Fresh catalog, so install the correct ID. We also apply the
-            // zeroth migration for historical reasons: the default
-            // `user_version` of zero indicates that the zeroth migration has
-            // been applied.
-            tx.execute_batch(&format!("PRAGMA application_id = {}", APPLICATION_ID))?;
-            MIGRATIONS[0].apply(data_dir_path, &tx)?;
-        } else if app_id != APPLICATION_ID {
-            return Err(Error::new(ErrorKind::Corruption {
-                detail: "catalog file has incorrect application_id".into(),
-            }));
-        };
-        tx.commit()?;
-
-        // Run unapplied migrations. The `user_version` field stores the index
-        // of the last migration that was run.
-        let version: u32 = sqlite.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
-        for (i, migration) in MIGRATIONS
-            .iter()
-            .enumerate()
-            .skip(usize::cast_from(version) + 1)
-        {
-            let tx = sqlite.transaction()?;
-            migration.apply(data_dir_path, &tx)?;
-            tx.execute_batch(&format!("PRAGMA user_version = {}", i))?;
-            tx.commit()?;
+        Self::check_modes(experimental_mode, safe_mode)?;
+        if data_dir_path.join(stash::MIGRATED_SQLITE_CATALOG).exists() {
+            return Err(Error::new(ErrorKind::InvalidStorageConfig(
+                "the catalog was migrated into the STORAGE stash, and must be opened \
+                 with --catalog-stash"
+                    .into(),
+            )));
         }
+        let backend = SqliteBackend::open(data_dir_path, synchronous, encryption_key)?;
+        Self::from_backend(Box::new(backend), experimental_mode, safe_mode)
+    }
 
+    /// Opens the catalog stored in a SQLite database in the data directory at
+    /// `data_dir_path` for reading only, as by
+    /// [`SqliteBackend::open_read_only`].
+    ///
+    /// The catalog can be inspected while `materialized` is running, and is
+    /// neither migrated nor otherwise modified.
+    pub fn open_read_only(
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<Connection, Error> {
+        let backend = SqliteBackend::open_read_only(data_dir_path, encryption_key)?;
+        Self::from_backend(Box::new(backend), None, None)
+    }
+
+    /// Opens the catalog stored in the PostgreSQL database at `url`.
+    pub fn open_postgres(
+        url: &str,
+        durability: CatalogDurability,
+        experimental_mode: Option<bool>,
+        safe_mode: Option<bool>,
+    ) -> Result<Connection, Error> {
+        Self::check_modes(experimental_mode, safe_mode)?;
+        let backend = PostgresBackend::open(url, durability)?;
+        Self::from_backend(Box::new(backend), experimental_mode, safe_mode)
+    }
+
+    /// Opens the catalog stored in the STORAGE stash in the data directory at
+    /// `data_dir_path`, migrating the SQLite catalog in the data directory into
+    /// the stash if it has not been migrated yet, as by [`StashBackend::open`].
+    pub fn open_stash(
+        data_dir_path: &Path,
+        experimental_mode: Option<bool>,
+        safe_mode: Option<bool>,
+    ) -> Result<Connection, Error> {
+        Self::check_modes(experimental_mode, safe_mode)?;
+        let backend = StashBackend::open(data_dir_path)?;
+        Self::from_backend(Box::new(backend), experimental_mode, safe_mode)
+    }
+
+    /// Replaces the catalog stored in the data directory at `data_dir_path`
+    /// with the backup at `backup_path`, as written by [`Connection::backup`].
+    ///
+    /// The backup is validated before the catalog is replaced. A backup
+    /// written by an older version of Materialize is migrated when the catalog
+    /// is next opened. A data directory is restored only once, until the
+    /// marker that records the restore is removed.
+    pub fn restore(
+        backup_path: &Path,
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<(), Error> {
+        SqliteBackend::restore(backup_path, data_dir_path, encryption_key)
+    }
+
+    /// Copies the catalog stored in the data directory at `data_dir_path`, and
+    /// any other state that its migrations depend upon, into the directory at
+    /// `to_path`, so that the copy can be opened without affecting the
+    /// original.
+    pub fn copy(
+        data_dir_path: &Path,
+        to_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<(), Error> {
+        SqliteBackend::copy(data_dir_path, to_path, encryption_key)
+    }
+
+    fn check_modes(experimental_mode: Option<bool>, safe_mode: Option<bool>) -> Result<(), Error> {
+        if experimental_mode == Some(true) && safe_mode == Some(true) {
+            return Err(Error::new(ErrorKind::ExperimentalModeInSafeMode));
+        }
+        Ok(())
+    }
+
+    fn from_backend(
+        mut inner: Box<dyn Backend>,
+        experimental_mode: Option<bool>,
+        safe_mode: Option<bool>,
+    ) -> Result<Connection, Error> {
         Ok(Connection {
-            experimental_mode: Self::set_or_get_experimental_mode(&mut sqlite, experimental_mode)?,
-            cluster_id: Self::set_or_get_cluster_id(&mut sqlite)?,
-            inner: sqlite,
+            experimental_mode: Self::set_or_get_experimental_mode(&mut *inner, experimental_mode)?,
+            safe_mode: Self::set_or_get_safe_mode(&mut *inner, safe_mode)?,
+            cluster_id: Self::set_or_get_cluster_id(&mut *inner)?,
+            inner,
+            user_gid_block: 0..0,
+            user_gid_block_size: 1,
         })
     }
 
+    /// Sets how many user IDs to allocate in storage at once, so that
+    /// allocating many IDs does not require a write to storage for each.
+    ///
+    /// The IDs of a block that have not been handed out when the connection
+    /// is closed are never used, leaving a gap in the sequence of user IDs.
+    pub fn set_user_gid_block_size(&mut self, block_size: u64) {
+        assert!(block_size > 0, "user gid block size must be positive");
+        self.user_gid_block_size = block_size;
+    }
+
     /// Sets catalog's `experimental_mode` setting on initialization or gets
     /// that value.
     ///
@@ -503,25 +873,14 @@ impl Connection {
     ///
     /// - If server has not been initialized and `experimental_mode.is_none()`.
     fn set_or_get_experimental_mode(
-        sqlite: &mut rusqlite::Connection,
+        backend: &mut dyn Backend,
         experimental_mode: Option<bool>,
     ) -> Result<bool, Error> {
-        let tx = sqlite.transaction()?;
-        let current_setting: Option<String> = tx
-            .query_row(
-                "SELECT value FROM settings WHERE name = 'experimental_mode';",
-                params![],
-                |row| row.get(0),
-            )
-            .optional()?;
-
-        let res = match (current_setting, experimental_mode) {
+        let current_setting = backend.setting("experimental_mode")?;
+        match (current_setting, experimental_mode) {
             // Server init
             (None, Some(experimental_mode)) => {
-                tx.execute(
-                    "INSERT INTO settings VALUES ('experimental_mode', ?);",
-                    params![experimental_mode],
-                )?;
+                backend.set_setting("experimental_mode", bool_setting(experimental_mode))?;
                 Ok(experimental_mode)
             }
             // Server reboot
@@ -541,50 +900,79 @@ impl Connection {
             (Some(cs), None) => Ok(cs.parse::<usize>().unwrap() != 0),
             // Test code that doesn't care. Just disable experimental mode.
             (None, None) => Ok(false),
-        };
-        tx.commit()?;
-        res
+        }
+    }
+
+    /// Sets catalog's `safe_mode` setting on initialization or gets that value.
+    ///
+    /// Note that using `None` for `safe_mode` is appropriate when reading the
+    /// catalog outside the context of starting the server.
+    ///
+    /// # Errors
+    ///
+    /// - If server was initialized and `safe_mode.unwrap()` does not match the
+    ///   initialized value.
+    ///
+    ///   This means that safe mode:
+    ///   - Can only be enabled on initialization, as objects that are not
+    ///     permitted in safe mode may already exist
+    ///   - Cannot be disabled once enabled
+    fn set_or_get_safe_mode(
+        backend: &mut dyn Backend,
+        safe_mode: Option<bool>,
+    ) -> Result<bool, Error> {
+        let current_setting = backend.setting("safe_mode")?;
+        match (current_setting, safe_mode) {
+            // Server init
+            (None, Some(safe_mode)) => {
+                backend.set_setting("safe_mode", bool_setting(safe_mode))?;
+                Ok(safe_mode)
+            }
+            // Server reboot
+            (Some(cs), Some(safe_mode)) => {
+                let current_setting = cs.parse::<usize>().unwrap() != 0;
+                if current_setting && !safe_mode {
+                    // Setting is true but was not given `--safe` flag.
+                    Err(Error::new(ErrorKind::SafeModeRequired))
+                } else if !current_setting && safe_mode {
+                    // Setting is false but was given `--safe` flag.
+                    Err(Error::new(ErrorKind::SafeModeUnavailable))
+                } else {
+                    Ok(safe_mode)
+                }
+            }
+            // Reading existing catalog
+            (Some(cs), None) => Ok(cs.parse::<usize>().unwrap() != 0),
+            // Test code that doesn't care. Just disable safe mode.
+            (None, None) => Ok(false),
+        }
     }
 
     /// Sets catalog's `cluster_id` setting on initialization or gets that value.
-    fn set_or_get_cluster_id(sqlite: &mut rusqlite::Connection) -> Result<Uuid, Error> {
-        let tx = sqlite.transaction()?;
-        let current_setting: Option<SqlVal<Uuid>> = tx
-            .query_row(
-                "SELECT value FROM settings WHERE name = 'cluster_id';",
-                params![],
-                |row| row.get(0),
-            )
-            .optional()?;
-
-        let res = match current_setting {
+    ///
+    /// The cluster ID is stored as a JSON string, as it always has been.
+    fn set_or_get_cluster_id(backend: &mut dyn Backend) -> Result<Uuid, Error> {
+        match backend.setting("cluster_id")? {
             // Server init
             None => {
                 // Generate a new version 4 UUID. These are generated from random input.
                 let cluster_id = Uuid::new_v4();
-                tx.execute(
-                    "INSERT INTO settings VALUES ('cluster_id', ?);",
-                    params![SqlVal(cluster_id)],
-                )?;
+                let value =
+                    serde_json::to_string(&cluster_id).expect("uuid serialization cannot fail");
+                backend.set_setting("cluster_id", &value)?;
                 Ok(cluster_id)
             }
             // Server reboot
-            Some(cs) => Ok(cs.0),
-        };
-        tx.commit()?;
-        res
+            Some(cs) => serde_json::from_str(&cs).map_err(|e| {
+                Error::new(ErrorKind::Corruption {
+                    detail: format!("invalid cluster_id setting: {}", e),
+                })
+            }),
+        }
     }
 
     pub fn get_catalog_content_version(&mut self) -> Result<String, Error> {
-        let tx = self.inner.transaction()?;
-        let current_setting: Option<String> = tx
-            .query_row(
-                "SELECT value FROM settings WHERE name = 'catalog_content_version';",
-                params![],
-                |row| row.get(0),
-            )
-            .optional()?;
-        let version = match current_setting {
+        let version = match self.inner.setting("catalog_content_version")? {
             Some(v) => match v.parse::<u32>() {
                 // Prior to v0.8.4 catalog content versions was stored as a u32
                 Ok(_) => "pre-v0.8.4".to_string(),
@@ -592,109 +980,160 @@ impl Connection {
             },
             None => "new".to_string(),
         };
-        tx.commit()?;
         Ok(version)
     }
 
     pub fn set_catalog_content_version(&mut self, new_version: &str) -> Result<(), Error> {
-        let tx = self.inner.transaction()?;
-        tx.execute(
-            "INSERT INTO settings (name, value) VALUES ('catalog_content_version', ?)
-                    ON CONFLICT (name) DO UPDATE SET value=excluded.value;",
-            params![new_version],
-        )?;
-        tx.commit()?;
-        Ok(())
+        self.inner
+            .set_setting("catalog_content_version", new_version)
     }
 
-    pub fn load_databases(&self) -> Result<Vec<(DatabaseId, String)>, Error> {
-        self.inner
-            .prepare("SELECT id, name FROM databases")?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let id: i64 = row.get(0)?;
-                let name: String = row.get(1)?;
-                Ok((DatabaseId(id), name))
-            })?
-            .collect()
+    /// Loads the versions of the catalog's schema and contents.
+    pub fn load_versions(&mut self) -> Result<CatalogVersions, Error> {
+        let mut parse_setting = |name: &str| -> Result<Option<u64>, Error> {
+            match self.inner.setting(name)? {
+                None => Ok(None),
+                Some(value) => value.parse().map(Some).map_err(|e| {
+                    Error::new(ErrorKind::Corruption {
+                        detail: format!("invalid {} setting {:?}: {}", name, value, e),
+                    })
+                }),
+            }
+        };
+        let schema_version = parse_setting("schema_version")?;
+        let last_migration_at = parse_setting("last_migration_at")?;
+        Ok(CatalogVersions {
+            schema_version,
+            last_migration_at,
+            catalog_content_version: self.get_catalog_content_version()?,
+        })
     }
 
-    pub fn load_schemas(&self) -> Result<Vec<(SchemaId, String, Option<DatabaseId>)>, Error> {
-        self.inner
-            .prepare(
-                "SELECT schemas.id, schemas.name, databases.id
-                FROM schemas
-                LEFT JOIN databases ON schemas.database_id = databases.id",
-            )?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let id: i64 = row.get(0)?;
-                let schema_name: String = row.get(1)?;
-                let database_id: Option<i64> = row.get(2)?;
-                Ok((SchemaId(id), schema_name, database_id.map(DatabaseId)))
-            })?
-            .collect()
-    }
-
-    pub fn load_roles(&self) -> Result<Vec<(i64, String)>, Error> {
-        self.inner
-            .prepare("SELECT id, name FROM roles")?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let id: i64 = row.get(0)?;
-                let name: String = row.get(1)?;
-                Ok((id, name))
-            })?
-            .collect()
+    /// Returns the value of the setting `name` in `namespace`, if it is set.
+    ///
+    /// # Errors
+    ///
+    /// - If the stored value cannot be deserialized as a `T`.
+    pub fn get_setting<T: DeserializeOwned>(
+        &mut self,
+        namespace: SettingNamespace,
+        name: &str,
+    ) -> Result<Option<T>, Error> {
+        let name = namespace.qualify(name);
+        match self.inner.setting(&name)? {
+            None => Ok(None),
+            Some(value) => parse_json_setting(&name, &value).map(Some),
+        }
+    }
+
+    /// Sets the value of the setting `name` in `namespace`, replacing any
+    /// existing value.
+    ///
+    /// # Panics
+    ///
+    /// - If `value` cannot be serialized to JSON.
+    pub fn set_setting<T: Serialize>(
+        &mut self,
+        namespace: SettingNamespace,
+        name: &str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let value = serde_json::to_string(value).expect("setting serialization cannot fail");
+        self.inner.set_setting(&namespace.qualify(name), &value)
+    }
+
+    /// Removes the setting `name` in `namespace`, if it is set.
+    pub fn remove_setting(&mut self, namespace: SettingNamespace, name: &str) -> Result<(), Error> {
+        self.inner.remove_setting(&namespace.qualify(name))
+    }
+
+    /// Returns the names and values of every setting in `namespace`.
+    pub fn load_settings<T: DeserializeOwned>(
+        &mut self,
+        namespace: SettingNamespace,
+    ) -> Result<BTreeMap<String, T>, Error> {
+        let prefix = namespace.qualify("");
+        let mut settings = BTreeMap::new();
+        for (name, value) in self.inner.load_settings()? {
+            if let Some(unqualified) = name.strip_prefix(&prefix) {
+                let value = parse_json_setting(&name, &value)?;
+                settings.insert(unqualified.to_owned(), value);
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn load_databases(
+        &self,
+    ) -> Result<Vec<(DatabaseId, String, i64, ObjectTimestamps)>, Error> {
+        self.inner.load_databases()
+    }
+
+    pub fn load_schemas(
+        &self,
+    ) -> Result<Vec<(SchemaId, String, Option<DatabaseId>, i64, ObjectTimestamps)>, Error> {
+        self.inner.load_schemas()
+    }
+
+    pub fn load_roles(&self) -> Result<Vec<StoredRole>, Error> {
+        self.inner.load_roles()
     }
 
     pub fn load_compute_instances(
         &self,
-    ) -> Result<Vec<(i64, String, ComputeInstanceConfig)>, Error> {
-        self.inner
-            .prepare("SELECT id, name, config FROM compute_instances")?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let id: i64 = row.get(0)?;
-                let name: String = row.get(1)?;
-                let config: Option<String> = row.get(2)?;
-                let config: ComputeInstanceConfig = match config {
-                    None => ComputeInstanceConfig::Local,
-                    Some(config) => serde_json::from_str(&config)
-                        .map_err(|err| rusqlite::Error::from(FromSqlError::Other(Box::new(err))))?,
-                };
-                Ok((id, name, config))
-            })?
-            .collect()
-    }
-
-    /// Load the persisted mapping of system object to global ID. Key is (schema-name, object-name).
-    pub fn load_system_gids(&self) -> Result<BTreeMap<(String, String), (GlobalId, u64)>, Error> {
-        self.inner
-            .prepare("SELECT schema_name, object_name, id, fingerprint FROM system_gid_mapping")?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let schema_name: String = row.get(0)?;
-                let object_name: String = row.get(1)?;
-                let id: i64 = row.get(2)?;
-                let fingerprint: i64 = row.get(3)?;
-                let id = id as u64;
-                let fingerprint = fingerprint as u64;
-                Ok((
-                    (schema_name, object_name),
-                    (GlobalId::System(id), fingerprint),
-                ))
-            })?
-            .collect()
+    ) -> Result<Vec<(i64, String, ComputeInstanceConfig, i64, ObjectTimestamps)>, Error> {
+        self.inner.load_compute_instances()
+    }
+
+    /// Loads the replicas of every compute instance.
+    pub fn load_compute_replicas(&self) -> Result<Vec<StoredComputeReplica>, Error> {
+        self.inner.load_compute_replicas()
+    }
+
+    /// Load the persisted mapping of system object to global ID and
+    /// fingerprint. Key is (schema-name, object-name).
+    pub fn load_system_gids(
+        &self,
+    ) -> Result<BTreeMap<(String, String), (GlobalId, Option<u64>)>, Error> {
+        self.inner.load_system_gids()
+    }
+
+    /// Returns the IDs of the system objects in `fingerprints` whose stored
+    /// fingerprint differs from the given one. Each element of `fingerprints`
+    /// should be (schema-name, object-name, fingerprint).
+    ///
+    /// System objects that have no stored ID are new rather than stale, and
+    /// are not returned. Nor are system objects whose stored fingerprint is
+    /// unknown, as it was recorded before fingerprints were stable: whether
+    /// their definitions have changed cannot be told, and they keep their IDs,
+    /// as they did before fingerprints were compared.
+    pub fn load_stale_system_gids<'a, I>(
+        &self,
+        fingerprints: I,
+    ) -> Result<BTreeMap<(String, String), GlobalId>, Error>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str, u64)>,
+    {
+        let mut stored = self.inner.load_system_gids()?;
+        Ok(fingerprints
+            .into_iter()
+            .filter_map(|(schema_name, object_name, fingerprint)| {
+                let key = (schema_name.to_string(), object_name.to_string());
+                match stored.remove(&key) {
+                    Some((id, Some(stored_fingerprint))) if stored_fingerprint != fingerprint => {
+                        Some((key, id))
+                    }
+                    _ => None,
+                }
+            })
+            .collect())
     }
 
     pub fn load_introspection_source_index_gids(
         &self,
         compute_id: i64,
     ) -> Result<BTreeMap<String, GlobalId>, Error> {
-        self.inner
-            .prepare("SELECT name, index_id FROM compute_introspection_source_indexes WHERE compute_id = ?")?
-            .query_and_then(params![compute_id], |row| -> Result<_, Error> {
-                let name: String = row.get(0)?;
-                let index_id: i64 = row.get(1)?;
-                Ok((name, GlobalId::System(index_id as u64)))
-            })?
-            .collect()
+        self.inner.load_introspection_source_index_gids(compute_id)
     }
 
     /// Persist mapping from system objects to global IDs. Each element of `mappings` should be
@@ -708,22 +1147,20 @@ impl Connection {
         if mappings.is_empty() {
             return Ok(());
         }
+        self.inner.set_system_gids(mappings)
+    }
 
-        let tx = self.inner.transaction()?;
-        for (schema_name, object_name, id, fingerprint) in mappings {
-            let id = if let GlobalId::System(id) = id {
-                id
-            } else {
-                panic!("non-system id provided")
-            };
-            tx.execute(
-                "INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint) VALUES (?, ?, ?, ?)
-                        ON CONFLICT (schema_name, object_name) DO UPDATE SET id=excluded.id, fingerprint=excluded.fingerprint;",
-                params![schema_name, object_name, id as i64, fingerprint as i64],
-            )?;
+    /// Replaces the IDs of re-created system objects in the comments,
+    /// privileges, and item dependencies that refer to them. Each element of
+    /// `replacements` should be (old-global-id, new-global-id).
+    pub fn replace_system_gids(
+        &mut self,
+        replacements: Vec<(GlobalId, GlobalId)>,
+    ) -> Result<(), Error> {
+        if replacements.is_empty() {
+            return Ok(());
         }
-        tx.commit()?;
-        Ok(())
+        self.inner.replace_system_gids(replacements)
     }
 
     /// Panics if provided id is not a system id
@@ -734,54 +1171,136 @@ impl Connection {
         if mappings.is_empty() {
             return Ok(());
         }
+        self.inner.set_introspection_source_index_gids(mappings)
+    }
+
+    /// Loads every recorded operation on secrets storage, in the order in
+    /// which the operations were recorded.
+    pub fn load_secret_audit_events(&self) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.inner.load_secret_audit_events()
+    }
+
+    /// Loads every recorded change to catalog objects, in the order in which
+    /// the changes were recorded.
+    pub fn load_audit_events(&self) -> Result<Vec<AuditEvent>, Error> {
+        self.inner.load_audit_events()
+    }
 
-        let tx = self.inner.transaction()?;
-        for (compute_id, name, index_id) in mappings {
-            let index_id = if let GlobalId::System(id) = index_id {
-                id
-            } else {
-                panic!("non-system id provided")
+    /// Loads the comments on every item and column.
+    pub fn load_comments(&self) -> Result<Vec<StoredComment>, Error> {
+        self.inner.load_comments()
+    }
+
+    /// Loads the session defaults for every role.
+    pub fn load_role_settings(&self) -> Result<Vec<StoredRoleSetting>, Error> {
+        self.inner.load_role_settings()
+    }
+
+    /// Loads every privilege granted on catalog objects.
+    pub fn load_privileges(&self) -> Result<Vec<StoredPrivilege>, Error> {
+        self.inner.load_privileges()
+    }
+
+    /// Loads every dropped item that has not yet been purged, in the order in
+    /// which the items were dropped.
+    pub fn load_dropped_items(&self) -> Result<Vec<StoredDroppedItem>, Error> {
+        self.inner.load_dropped_items()
+    }
+
+    /// Validates the references between catalog objects that the backend does
+    /// not enforce, returning every violation found.
+    ///
+    /// Every item must be in an existing schema, every schema must be in an
+    /// existing database, if any, and every item, builtin object, and
+    /// introspection source index must have a distinct ID that was allocated
+    /// from the appropriate namespace.
+    pub fn check_consistency(&self) -> Result<Vec<Inconsistency>, Error> {
+        let refs = self.inner.load_references()?;
+        let mut inconsistencies = vec![];
+
+        let databases: HashSet<_> = refs.databases.into_iter().collect();
+        let schemas: HashSet<_> = refs.schemas.iter().map(|(id, _)| *id).collect();
+        for (schema_id, database_id) in refs.schemas {
+            match database_id {
+                Some(database_id) if !databases.contains(&database_id) => {
+                    inconsistencies.push(Inconsistency::DanglingDatabaseReference {
+                        schema_id,
+                        database_id,
+                    })
+                }
+                _ => (),
+            }
+        }
+
+        let mut objects: BTreeMap<GlobalId, Vec<String>> = BTreeMap::new();
+        for (id, name, schema_id) in refs.items {
+            if !schema_id.map_or(false, |schema_id| schemas.contains(&schema_id)) {
+                inconsistencies.push(Inconsistency::DanglingSchemaReference {
+                    item: id,
+                    schema_id,
+                });
+            }
+            objects
+                .entry(id)
+                .or_default()
+                .push(format!("item {}", name));
+        }
+        for (schema_name, object_name, id) in refs.system_gids {
+            objects
+                .entry(GlobalId::System(id))
+                .or_default()
+                .push(format!("builtin {}.{}", schema_name, object_name));
+        }
+        for (compute_id, name, id) in refs.introspection_source_indexes {
+            objects
+                .entry(GlobalId::System(id))
+                .or_default()
+                .push(format!(
+                    "introspection source index {} of cluster {}",
+                    name, compute_id
+                ));
+        }
+        for (id, mut objects) in objects {
+            let allocated = match id {
+                GlobalId::System(id) => id < refs.next_system_gid,
+                GlobalId::User(id) => id < refs.next_user_gid,
+                // Transient IDs are never stored.
+                GlobalId::Transient(_) | GlobalId::Explain => false,
             };
-            tx.execute(
-                "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id) VALUES (?, ?, ?)",
-                params![compute_id, name, index_id as i64],
-            )?;
+            if !allocated {
+                for object in &objects {
+                    inconsistencies.push(Inconsistency::UnallocatedGlobalId {
+                        id,
+                        object: object.clone(),
+                    });
+                }
+            }
+            if objects.len() > 1 {
+                objects.sort();
+                inconsistencies.push(Inconsistency::DuplicateGlobalId { id, objects });
+            }
         }
-        tx.commit()?;
-        Ok(())
+
+        Ok(inconsistencies)
     }
 
     pub fn allocate_system_ids(&mut self, amount: u64) -> Result<Vec<GlobalId>, Error> {
-        let id = self.allocate_global_id("system", amount)?;
-
-        Ok(id.into_iter().map(GlobalId::System).collect())
+        let id = self
+            .inner
+            .allocate_global_id(GidNamespace::System, amount)?;
+        Ok((id..id + amount).map(GlobalId::System).collect())
     }
 
+    /// Allocates a user ID, from the current block of user IDs if it is not
+    /// exhausted, or else from a newly allocated block.
     pub fn allocate_user_id(&mut self) -> Result<GlobalId, Error> {
-        let id = self.allocate_global_id("user", 1)?;
-        let id = id.into_element();
-        Ok(GlobalId::User(id))
-    }
-
-    fn allocate_global_id(&mut self, id_type: &str, amount: u64) -> Result<Vec<u64>, Error> {
-        let tx = self.inner.transaction()?;
-        // SQLite doesn't support u64s, so we constrain ourselves to the more
-        // limited range of positive i64s.
-        let id: i64 = tx.query_row(
-            format!("SELECT next_gid FROM {id_type}_gid_alloc").as_str(),
-            params![],
-            |row| row.get(0),
-        )?;
-        if id == i64::MAX {
-            return Err(Error::new(ErrorKind::IdExhaustion));
+        if self.user_gid_block.is_empty() {
+            let size = self.user_gid_block_size;
+            let id = self.inner.allocate_global_id(GidNamespace::User, size)?;
+            self.user_gid_block = id..id + size;
         }
-        let id = id as u64;
-        tx.execute(
-            format!("UPDATE {id_type}_gid_alloc SET next_gid = ?").as_str(),
-            params![(id + amount) as i64],
-        )?;
-        tx.commit()?;
-        Ok((id..id + amount).collect())
+        let id = self.user_gid_block.next().expect("block is not empty");
+        Ok(GlobalId::User(id))
     }
 
     pub fn transaction(&mut self) -> Result<Transaction, Error> {
@@ -790,6 +1309,14 @@ impl Connection {
         })
     }
 
+    /// Writes a consistent snapshot of the catalog to a new file at `path`.
+    ///
+    /// The snapshot is taken while the catalog is in use, so unlike a copy of
+    /// the catalog's files, it never reflects a partially applied change.
+    pub fn backup(&mut self, path: &Path) -> Result<(), Error> {
+        self.inner.backup(path)
+    }
+
     pub fn cluster_id(&self) -> Uuid {
         self.cluster_id
     }
@@ -797,88 +1324,69 @@ impl Connection {
     pub fn experimental_mode(&self) -> bool {
         self.experimental_mode
     }
+
+    pub fn safe_mode(&self) -> bool {
+        self.safe_mode
+    }
 }
 
 pub struct Transaction<'a> {
-    inner: rusqlite::Transaction<'a>,
+    inner: Box<dyn BackendTransaction + 'a>,
 }
 
 impl Transaction<'_> {
-    pub fn load_items(&self) -> Result<Vec<(GlobalId, QualifiedObjectName, Vec<u8>)>, Error> {
-        // Order user views by their GlobalId
-        self.inner
-            .prepare(
-                "SELECT items.gid, databases.id, schemas.id, items.name, items.definition
-                FROM items
-                JOIN schemas ON items.schema_id = schemas.id
-                JOIN databases ON schemas.database_id = databases.id
-                ORDER BY json_extract(items.gid, '$.User')",
-            )?
-            .query_and_then(params![], |row| -> Result<_, Error> {
-                let id: SqlVal<GlobalId> = row.get(0)?;
-                let database: i64 = row.get(1)?;
-                let schema: i64 = row.get(2)?;
-                let item: String = row.get(3)?;
-                let definition: Vec<u8> = row.get(4)?;
-                Ok((
-                    id.0,
-                    QualifiedObjectName {
-                        qualifiers: ObjectQualifiers {
-                            database_spec: ResolvedDatabaseSpecifier::from(database),
-                            schema_spec: SchemaSpecifier::from(schema),
-                        },
-                        item,
-                    },
-                    definition,
-                ))
-            })?
-            .collect()
-    }
-
-    pub fn insert_database(&mut self, database_name: &str) -> Result<DatabaseId, Error> {
-        match self
-            .inner
-            .prepare_cached("INSERT INTO databases (name) VALUES (?)")?
-            .execute(params![database_name])
-        {
-            Ok(_) => Ok(DatabaseId(self.inner.last_insert_rowid())),
-            Err(err) if is_constraint_violation(&err) => Err(Error::new(
-                ErrorKind::DatabaseAlreadyExists(database_name.to_owned()),
-            )),
-            Err(err) => Err(err.into()),
-        }
+    pub fn load_items(&self) -> Result<Vec<StoredItem>, Error> {
+        self.inner.load_items()
+    }
+
+    /// Loads the IDs of the objects on which each item depends. Items that
+    /// depend on nothing are omitted.
+    pub fn load_item_dependencies(&self) -> Result<BTreeMap<GlobalId, BTreeSet<GlobalId>>, Error> {
+        self.inner.load_item_dependencies()
+    }
+
+    pub fn insert_database(
+        &mut self,
+        database_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<DatabaseId, Error> {
+        self.inner.insert_database(database_name, owner_id, now)
     }
 
     pub fn insert_schema(
         &mut self,
         database_id: DatabaseId,
         schema_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
     ) -> Result<SchemaId, Error> {
-        match self
-            .inner
-            .prepare_cached("INSERT INTO schemas (database_id, name) VALUES (?, ?)")?
-            .execute(params![database_id.0, schema_name])
-        {
-            Ok(_) => Ok(SchemaId(self.inner.last_insert_rowid())),
-            Err(err) if is_constraint_violation(&err) => Err(Error::new(
-                ErrorKind::SchemaAlreadyExists(schema_name.to_owned()),
-            )),
-            Err(err) => Err(err.into()),
-        }
+        self.inner
+            .insert_schema(database_id, schema_name, owner_id, now)
     }
 
-    pub fn insert_role(&mut self, role_name: &str) -> Result<i64, Error> {
-        match self
-            .inner
-            .prepare_cached("INSERT INTO roles (name) VALUES (?)")?
-            .execute(params![role_name])
-        {
-            Ok(_) => Ok(self.inner.last_insert_rowid()),
-            Err(err) if is_constraint_violation(&err) => Err(Error::new(
-                ErrorKind::RoleAlreadyExists(role_name.to_owned()),
-            )),
-            Err(err) => Err(err.into()),
-        }
+    pub fn insert_role(
+        &mut self,
+        role_name: &str,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        self.inner
+            .insert_role(role_name, restrictions, password, now)
+    }
+
+    /// Replaces the restrictions and the password verifier of the role with
+    /// ID `id`.
+    pub fn update_role(
+        &mut self,
+        id: i64,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner
+            .update_role(id, restrictions, password, updated_at)
     }
 
     /// Panics if any introspection source id is not a system id
@@ -887,54 +1395,57 @@ impl Transaction<'_> {
         cluster_name: &str,
         config: &ComputeInstanceConfig,
         introspection_sources: &Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        now: EpochMillis,
     ) -> Result<i64, Error> {
-        let config = serde_json::to_string(config)
-            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
-        let id = match self
-            .inner
-            .prepare_cached("INSERT INTO compute_instances (name, config) VALUES (?, ?)")?
-            .execute(params![cluster_name, config])
-        {
-            Ok(_) => self.inner.last_insert_rowid(),
-            Err(err) if is_constraint_violation(&err) => {
-                return Err(Error::new(ErrorKind::ClusterAlreadyExists(
-                    cluster_name.to_owned(),
-                )))
-            }
-            Err(err) => return Err(err.into()),
-        };
-
-        for (builtin, index_id) in introspection_sources {
-            let index_id = if let GlobalId::System(id) = index_id {
-                *id
-            } else {
-                panic!("non-system id provided")
-            };
-            self
-                .inner
-                .prepare_cached(
-                "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id) VALUES (?, ?, ?)")?
-                .execute(params![id, builtin.name, index_id as i64])?;
-        }
-
-        Ok(id)
+        self.inner.insert_compute_instance(
+            cluster_name,
+            config,
+            introspection_sources,
+            owner_id,
+            now,
+        )
     }
 
     pub fn update_compute_instance_config(
         &mut self,
         id: ComputeInstanceId,
         config: &ComputeInstanceConfig,
+        updated_at: EpochMillis,
     ) -> Result<(), Error> {
-        let config = serde_json::to_string(config)
-            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
-        match self
-            .inner
-            .prepare_cached("UPDATE compute_instances SET config = ? WHERE id = ?")?
-            .execute(params![config, id])
-        {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err.into()),
-        }
+        self.inner
+            .update_compute_instance_config(id, config, updated_at)
+    }
+
+    /// Adds a replica named `replica_name` to the compute instance with ID
+    /// `compute_id`, returning the replica's ID.
+    pub fn insert_compute_replica(
+        &mut self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+        config: &ComputeInstanceReplicaConfig,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        self.inner
+            .insert_compute_replica(compute_id, replica_name, config, now)
+    }
+
+    pub fn update_compute_replica_config(
+        &mut self,
+        id: i64,
+        config: &ComputeInstanceReplicaConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner
+            .update_compute_replica_config(id, config, updated_at)
+    }
+
+    pub fn remove_compute_replica(
+        &self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+    ) -> Result<(), Error> {
+        self.inner.remove_compute_replica(compute_id, replica_name)
     }
 
     pub fn insert_item(
@@ -943,33 +1454,29 @@ impl Transaction<'_> {
         schema_id: SchemaId,
         item_name: &str,
         item: &[u8],
+        owner_id: i64,
+        now: EpochMillis,
     ) -> Result<(), Error> {
-        match self
-            .inner
-            .prepare_cached(
-                "INSERT INTO items (gid, schema_id, name, definition) VALUES (?, ?, ?, ?)",
-            )?
-            .execute(params![SqlVal(&id), schema_id.0, item_name, item])
-        {
-            Ok(_) => Ok(()),
-            Err(err) if is_constraint_violation(&err) => Err(Error::new(
-                ErrorKind::ItemAlreadyExists(item_name.to_owned()),
-            )),
-            Err(err) => Err(err.into()),
+        self.inner
+            .insert_item(id, schema_id, item_name, item, owner_id, now)
+    }
+
+    /// Inserts each of `items`, as if by [`Transaction::insert_item`], with as
+    /// few statements as the backend allows.
+    pub fn insert_items(
+        &self,
+        items: &[NewItem],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        if items.is_empty() {
+            return Ok(());
         }
+        self.inner.insert_items(items, owner_id, now)
     }
 
     pub fn remove_database(&self, id: &DatabaseId) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("DELETE FROM databases WHERE id = ?")?
-            .execute(params![id.0])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownDatabase(id.to_string()).into())
-        }
+        self.inner.remove_database(id)
     }
 
     pub fn remove_schema(
@@ -977,108 +1484,291 @@ impl Transaction<'_> {
         database_id: &DatabaseId,
         schema_id: &SchemaId,
     ) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("DELETE FROM schemas WHERE database_id = ? AND id = ?")?
-            .execute(params![database_id.0, schema_id.0])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownSchema(format!("{}.{}", database_id.0, schema_id.0)).into())
-        }
+        self.inner.remove_schema(database_id, schema_id)
     }
 
+    /// Removes the named role, along with its session defaults.
     pub fn remove_role(&self, name: &str) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("DELETE FROM roles WHERE name = ?")?
-            .execute(params![name])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownRole(name.to_owned()).into())
-        }
+        self.inner.remove_role(name)
     }
 
+    /// Removes the named compute instance, along with its replicas.
     pub fn remove_compute_instance(&self, name: &str) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("DELETE FROM compute_instances WHERE name = ?")?
-            .execute(params![name])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownComputeInstance(name.to_owned()).into())
+        self.inner.remove_compute_instance(name)
+    }
+
+    /// Removes the item with the given ID, along with any comments on it or
+    /// its columns and its dependencies, and records its definition among the
+    /// dropped items, from which it can be restored until it is purged.
+    pub fn remove_item(&self, item: &RemovedItem, dropped_at: EpochMillis) -> Result<(), Error> {
+        self.inner.remove_item(item, dropped_at)
+    }
+
+    /// Removes each of `items`, as if by [`Transaction::remove_item`], with as
+    /// few statements as the backend allows.
+    pub fn remove_items(
+        &self,
+        items: &[RemovedItem],
+        dropped_at: EpochMillis,
+    ) -> Result<(), Error> {
+        if items.is_empty() {
+            return Ok(());
         }
+        self.inner.remove_items(items, dropped_at)
     }
 
-    pub fn remove_item(&self, id: GlobalId) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("DELETE FROM items WHERE gid = ?")?
-            .execute(params![SqlVal(id)])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+    /// Forgets the dropped item with the given ID, e.g., once it has been
+    /// restored.
+    pub fn remove_dropped_item(&self, id: GlobalId) -> Result<(), Error> {
+        self.inner.remove_dropped_item(id)
+    }
+
+    /// Forgets every item dropped before `dropped_before`.
+    pub fn purge_dropped_items(&self, dropped_before: EpochMillis) -> Result<(), Error> {
+        self.inner.purge_dropped_items(dropped_before)
+    }
+
+    pub fn update_item(
+        &self,
+        id: GlobalId,
+        item_name: &str,
+        item: &[u8],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner.update_item(id, item_name, item, updated_at)
+    }
+
+    /// Applies each of `updates`, as if by [`Transaction::update_item`], with
+    /// as few statements as the backend allows. Each item may be updated at
+    /// most once.
+    pub fn update_items(
+        &self,
+        updates: &[ItemUpdate],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        if updates.is_empty() {
+            return Ok(());
         }
+        self.inner.update_items(updates, updated_at)
     }
 
-    pub fn update_item(&self, id: GlobalId, item_name: &str, item: &[u8]) -> Result<(), Error> {
-        let n = self
-            .inner
-            .prepare_cached("UPDATE items SET name = ?, definition = ? WHERE gid = ?")?
-            .execute(params![item_name, item, SqlVal(id)])?;
-        assert!(n <= 1);
-        if n == 1 {
-            Ok(())
-        } else {
-            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+    /// Replaces the stored dependencies of each of the items in
+    /// `dependencies`, so that the dependency graph can be read without
+    /// planning the items' definitions.
+    pub fn set_item_dependencies(&self, dependencies: &[ItemDependencies]) -> Result<(), Error> {
+        if dependencies.is_empty() {
+            return Ok(());
         }
+        self.inner.set_item_dependencies(dependencies)
+    }
+
+    /// Renames the database with the given ID. Errors if a database with the
+    /// new name already exists.
+    pub fn rename_database(
+        &self,
+        id: &DatabaseId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner.rename_database(id, to_name, updated_at)
+    }
+
+    /// Renames the schema with the given ID in the given database. Errors if
+    /// the database already contains a schema with the new name.
+    pub fn rename_schema(
+        &self,
+        database_id: &DatabaseId,
+        schema_id: &SchemaId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner
+            .rename_schema(database_id, schema_id, to_name, updated_at)
+    }
+
+    /// Transfers ownership of `object` to the role with ID `owner_id`.
+    pub fn update_owner(
+        &self,
+        object: OwnedObjectId,
+        owner_id: i64,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.inner.update_owner(object, owner_id, updated_at)
+    }
+
+    /// Records a change to a catalog object, assigning the event the next
+    /// event ID. The event is discarded if the transaction is not committed.
+    pub fn insert_audit_event(&mut self, event: &mut AuditEvent) -> Result<(), Error> {
+        self.inner.insert_audit_event(event)
+    }
+
+    /// Records operations on secrets storage, assigning each event the next
+    /// event ID. The events are discarded if the transaction is not
+    /// committed.
+    pub fn insert_secret_audit_events(
+        &mut self,
+        events: &mut [SecretAuditEvent],
+    ) -> Result<(), Error> {
+        self.inner.insert_secret_audit_events(events)
+    }
+
+    /// Forgets every recorded operation on secrets storage but the most
+    /// recent `retained`, returning the forgotten events.
+    pub fn purge_secret_audit_events(&self, retained: u64) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.inner.purge_secret_audit_events(retained)
+    }
+
+    /// Records a comment on an item, or on one of its columns if
+    /// `column_position` is specified. Errors if such a comment already
+    /// exists.
+    pub fn insert_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        self.inner.insert_comment(id, column_position, comment)
+    }
+
+    /// Replaces an existing comment on an item or one of its columns.
+    pub fn update_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        self.inner.update_comment(id, column_position, comment)
+    }
+
+    /// Removes an existing comment on an item or one of its columns.
+    pub fn delete_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+    ) -> Result<(), Error> {
+        self.inner.delete_comment(id, column_position)
+    }
+
+    /// Sets the default value of the named session variable for the role with
+    /// ID `role_id`, or for every role if `role_id` is `None`. A `value` of
+    /// `None` removes the default, if any.
+    pub fn set_role_setting(
+        &self,
+        role_id: Option<i64>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), Error> {
+        self.inner.set_role_setting(role_id, name, value)
     }
 
-    pub fn commit(self) -> Result<(), rusqlite::Error> {
+    /// Grants `privilege` on `object` to the role with ID `role_id`, or to
+    /// every role if `role_id` is `None`. Granting a privilege that was already
+    /// granted has no effect.
+    pub fn grant_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        self.inner.grant_privilege(object, role_id, privilege)
+    }
+
+    /// Revokes `privilege` on `object` from the role with ID `role_id`, or from
+    /// every role if `role_id` is `None`. Revoking a privilege that was not
+    /// granted has no effect.
+    pub fn revoke_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        self.inner.revoke_privilege(object, role_id, privilege)
+    }
+
+    pub fn commit(self) -> Result<(), Error> {
         self.inner.commit()
     }
 }
 
-fn is_constraint_violation(err: &rusqlite::Error) -> bool {
-    match err {
-        rusqlite::Error::SqliteFailure(err, _) => {
-            err.code == rusqlite::ErrorCode::ConstraintViolation
-        }
-        _ => false,
+/// Returns the checksum with which an applied migration is recorded, so that
+/// later edits to the migration's text can be detected.
+fn migration_checksum(migration: &str) -> String {
+    hex::encode(Sha256::digest(migration.as_bytes()))
+}
+
+/// Returns the stored column position of a comment. As with `objsubid` in
+/// PostgreSQL's `pg_description`, comments on the item itself are stored with
+/// a column position of zero.
+fn comment_column_position(column_position: Option<usize>) -> i64 {
+    column_position.map_or(0, |p| {
+        i64::try_from(p).expect("column position fits in i64")
+    })
+}
+
+/// The inverse of [`comment_column_position`].
+fn parse_comment_column_position(column_position: i64) -> Result<Option<usize>, Error> {
+    match column_position {
+        0 => Ok(None),
+        p => match usize::try_from(p) {
+            Ok(p) => Ok(Some(p)),
+            Err(_) => Err(Error::new(ErrorKind::Corruption {
+                detail: format!("invalid comment column position {}", p),
+            })),
+        },
     }
 }
 
-pub struct SqlVal<T>(pub T);
+/// Returns the error for an attempt to modify a comment that does not exist.
+fn unknown_comment(id: GlobalId, column_position: Option<usize>) -> Error {
+    let detail = match column_position {
+        None => format!("no comment on {}", id),
+        Some(p) => format!("no comment on column {} of {}", p, id),
+    };
+    Error::new(ErrorKind::Corruption { detail })
+}
+
+/// Returns the stored role ID of a session default or privilege. Defaults and
+/// privileges that apply to every role are stored with a role ID of zero,
+/// which no role has.
+fn stored_role_id(role_id: Option<i64>) -> i64 {
+    role_id.unwrap_or(0)
+}
+
+/// The inverse of [`stored_role_id`].
+fn parse_stored_role_id(role_id: i64) -> Option<i64> {
+    match role_id {
+        0 => None,
+        id => Some(id),
+    }
+}
 
-impl<T> ToSql for SqlVal<T>
-where
-    T: Serialize,
-{
-    fn to_sql(&self) -> Result<ToSqlOutput, rusqlite::Error> {
-        let bytes = serde_json::to_vec(&self.0)
-            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
-        Ok(ToSqlOutput::Owned(Value::Blob(bytes)))
+/// Parses a stored privilege, which is stored as its SQL keyword.
+fn parse_privilege(privilege: &str) -> Result<Privilege, Error> {
+    match privilege {
+        "SELECT" => Ok(Privilege::Select),
+        "INSERT" => Ok(Privilege::Insert),
+        "UPDATE" => Ok(Privilege::Update),
+        "DELETE" => Ok(Privilege::Delete),
+        "CREATE" => Ok(Privilege::Create),
+        _ => Err(Error::new(ErrorKind::Corruption {
+            detail: format!("invalid privilege {}", privilege),
+        })),
     }
 }
 
-impl<T> FromSql for SqlVal<T>
-where
-    T: for<'de> Deserialize<'de>,
-{
-    fn column_result(val: ValueRef) -> Result<Self, FromSqlError> {
-        let bytes = match val {
-            ValueRef::Blob(bytes) => bytes,
-            _ => return Err(FromSqlError::InvalidType),
-        };
-        Ok(SqlVal(
-            serde_json::from_slice(bytes).map_err(|err| FromSqlError::Other(Box::new(err)))?,
-        ))
+/// Parses the JSON value of the setting `name`.
+fn parse_json_setting<T: DeserializeOwned>(name: &str, value: &str) -> Result<T, Error> {
+    serde_json::from_str(value).map_err(|e| {
+        Error::new(ErrorKind::Corruption {
+            detail: format!("invalid {} setting {:?}: {}", name, value, e),
+        })
+    })
+}
+
+/// Returns the representation of a boolean setting.
+fn bool_setting(value: bool) -> &'static str {
+    match value {
+        false => "0",
+        true => "1",
     }
 }
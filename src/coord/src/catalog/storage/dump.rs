@@ -0,0 +1,542 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Export and import of the catalog as a JSON document.
+//!
+//! A dump describes the catalog's objects by name rather than by the IDs that
+//! a particular backend assigned to them, so that it can be reviewed, compared
+//! against another dump, and imported into a fresh catalog of either backend.
+
+use std::cmp;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use mz_expr::GlobalId;
+use mz_ore::now::EpochMillis;
+use mz_sql::names::{DatabaseId, SchemaId, SchemaSpecifier};
+use mz_sql::plan::{ComputeInstanceConfig, RoleRestrictions};
+
+use crate::catalog::error::{Error, ErrorKind};
+use crate::catalog::storage::{
+    parse_privilege, Connection, GidNamespace, OwnedObjectId, Transaction,
+};
+use crate::catalog::SerializedCatalogItem;
+
+/// The version of the document format written by [`Connection::dump`].
+pub const DUMP_VERSION: u64 = 1;
+
+/// The settings that a dump records. The cluster ID is omitted, as it
+/// identifies the environment rather than describing its contents.
+const DUMPED_SETTINGS: &[&str] = &["catalog_content_version", "experimental_mode", "safe_mode"];
+
+/// The contents of a catalog, as written by [`Connection::dump`].
+///
+/// Objects are listed in a deterministic order, so that dumps of catalogs with
+/// the same contents are identical.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogDump {
+    /// The version of the document format.
+    pub version: u64,
+    pub settings: BTreeMap<String, String>,
+    /// The session defaults for every role, keyed by the name of the session
+    /// variable.
+    #[serde(default)]
+    pub role_defaults: BTreeMap<String, String>,
+    /// Sorted by name.
+    pub roles: Vec<DumpedRole>,
+    /// Sorted by name.
+    pub clusters: Vec<DumpedCluster>,
+    /// Sorted by name.
+    pub databases: Vec<DumpedDatabase>,
+    /// Sorted by database name, then by name.
+    pub schemas: Vec<DumpedSchema>,
+    /// Sorted by ID, so that every item follows the items on which it depends.
+    pub items: Vec<DumpedItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedRole {
+    pub name: String,
+    pub restrictions: RoleRestrictions,
+    /// The SCRAM-SHA-256 verifier of the role's password, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// The session defaults for this role, keyed by the name of the session
+    /// variable.
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedCluster {
+    pub name: String,
+    pub owner: String,
+    pub config: ComputeInstanceConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedDatabase {
+    pub name: String,
+    pub owner: String,
+    #[serde(default)]
+    pub privileges: Vec<DumpedPrivilege>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedSchema {
+    pub database: String,
+    pub name: String,
+    pub owner: String,
+    #[serde(default)]
+    pub privileges: Vec<DumpedPrivilege>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedItem {
+    /// The item's ID, which the item keeps when it is imported.
+    pub id: GlobalId,
+    pub database: String,
+    pub schema: String,
+    pub name: String,
+    pub owner: String,
+    pub create_sql: String,
+    #[serde(default)]
+    pub privileges: Vec<DumpedPrivilege>,
+    /// The hex-encoded contents of a secret. The catalog does not store the
+    /// contents of secrets, so [`Connection::dump`] leaves this unset, and
+    /// the caller fills it in from secrets storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_contents: Option<String>,
+}
+
+impl DumpedItem {
+    /// Reports whether the item is a secret.
+    pub fn is_secret(&self) -> bool {
+        self.create_sql.starts_with("CREATE SECRET ")
+    }
+}
+
+impl CatalogDump {
+    /// Returns the ID and the contents of every secret in the dump.
+    ///
+    /// # Errors
+    ///
+    /// - If the dump does not include the contents of a secret, or includes
+    ///   contents that are not valid hex.
+    pub fn secrets(&self) -> Result<Vec<(GlobalId, Vec<u8>)>, Error> {
+        let mut secrets = vec![];
+        for item in self.items.iter().filter(|item| item.is_secret()) {
+            let name = format!("{}.{}.{}", item.database, item.schema, item.name);
+            let contents = item.secret_contents.as_ref().ok_or_else(|| {
+                import_error(format!(
+                    "dump does not include the contents of secret {}",
+                    name
+                ))
+            })?;
+            let contents = hex::decode(contents)
+                .map_err(|e| import_error(format!("invalid contents of secret {}: {}", name, e)))?;
+            secrets.push((item.id, contents));
+        }
+        Ok(secrets)
+    }
+}
+
+/// A privilege that a role holds on a dumped object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedPrivilege {
+    /// The name of the role, or `None` if the privilege is granted to every
+    /// role.
+    pub role: Option<String>,
+    /// The privilege, by its SQL keyword.
+    pub privilege: String,
+}
+
+impl Connection {
+    /// Describes the contents of the catalog as a [`CatalogDump`].
+    ///
+    /// Only the objects that users can create are described; the builtin
+    /// schemas and objects are the same in every catalog. Timestamps and the
+    /// persistence details of tables and sources are omitted.
+    pub fn dump(&mut self) -> Result<CatalogDump, Error> {
+        let mut settings = BTreeMap::new();
+        for name in DUMPED_SETTINGS {
+            if let Some(value) = self.inner.setting(name)? {
+                settings.insert(name.to_string(), value);
+            }
+        }
+
+        let role_names: BTreeMap<_, _> = self
+            .load_roles()?
+            .into_iter()
+            .map(|(id, name, restrictions, password, _)| (id, (name, restrictions, password)))
+            .collect();
+        let owner = |owner_id: i64| match role_names.get(&owner_id) {
+            Some((name, _, _)) => Ok(name.clone()),
+            None => Err(Error::new(ErrorKind::Corruption {
+                detail: format!("unknown owner {}", owner_id),
+            })),
+        };
+        let mut role_defaults = BTreeMap::new();
+        let mut defaults_by_role: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for (role_id, name, value) in self.load_role_settings()? {
+            match role_id {
+                None => role_defaults.insert(name, value),
+                Some(role_id) => defaults_by_role
+                    .entry(role_id)
+                    .or_default()
+                    .insert(name, value),
+            };
+        }
+        let mut roles: Vec<_> = role_names
+            .iter()
+            .map(|(id, (name, restrictions, password))| DumpedRole {
+                name: name.clone(),
+                restrictions: restrictions.clone(),
+                password: password.clone(),
+                defaults: defaults_by_role.remove(id).unwrap_or_default(),
+            })
+            .collect();
+        roles.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut privileges: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (object, role_id, privilege) in self.load_privileges()? {
+            let role = match role_id {
+                Some(role_id) => Some(owner(role_id)?),
+                None => None,
+            };
+            privileges.entry(object).or_default().push(DumpedPrivilege {
+                role,
+                privilege: privilege.to_string(),
+            });
+        }
+        let mut object_privileges = |object: OwnedObjectId| {
+            let mut object_privileges = privileges.remove(&object).unwrap_or_default();
+            object_privileges.sort_by(|a, b| (&a.role, &a.privilege).cmp(&(&b.role, &b.privilege)));
+            object_privileges
+        };
+
+        let mut clusters = vec![];
+        for (_, name, config, owner_id, _) in self.load_compute_instances()? {
+            clusters.push(DumpedCluster {
+                name,
+                owner: owner(owner_id)?,
+                config,
+            });
+        }
+        clusters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut database_names = BTreeMap::new();
+        let mut databases = vec![];
+        for (id, name, owner_id, _) in self.load_databases()? {
+            database_names.insert(id, name.clone());
+            databases.push(DumpedDatabase {
+                name,
+                owner: owner(owner_id)?,
+                privileges: object_privileges(OwnedObjectId::Database(id)),
+            });
+        }
+        databases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut schema_names = BTreeMap::new();
+        let mut schemas = vec![];
+        for (id, name, database_id, owner_id, _) in self.load_schemas()? {
+            // Schemas outside of any database are builtin.
+            let database_id = match database_id {
+                Some(database_id) => database_id,
+                None => continue,
+            };
+            let database = database_names
+                .get(&database_id)
+                .ok_or_else(|| unknown_database(database_id))?
+                .clone();
+            schema_names.insert(id, (database.clone(), name.clone()));
+            schemas.push(DumpedSchema {
+                database,
+                name,
+                owner: owner(owner_id)?,
+                privileges: object_privileges(OwnedObjectId::Schema(id)),
+            });
+        }
+        schemas.sort_by(|a, b| (&a.database, &a.name).cmp(&(&b.database, &b.name)));
+
+        let mut items = vec![];
+        for (id, name, definition, owner_id, _) in self.transaction()?.load_items()? {
+            let schema_id = match name.qualifiers.schema_spec {
+                SchemaSpecifier::Id(schema_id) => schema_id,
+                SchemaSpecifier::Temporary => unreachable!("temporary items are not stored"),
+            };
+            let (database, schema) = schema_names
+                .get(&schema_id)
+                .ok_or_else(|| unknown_schema(schema_id))?
+                .clone();
+            let SerializedCatalogItem::V1 { create_sql, .. } = serde_json::from_slice(&definition)
+                .map_err(|e| {
+                    Error::new(ErrorKind::Corruption {
+                        detail: format!("invalid definition of item {}: {}", id, e),
+                    })
+                })?;
+            items.push(DumpedItem {
+                id,
+                database,
+                schema,
+                name: name.item,
+                owner: owner(owner_id)?,
+                create_sql,
+                privileges: object_privileges(OwnedObjectId::Item(id)),
+                secret_contents: None,
+            });
+        }
+        items.sort_by_key(|item| item.id);
+
+        Ok(CatalogDump {
+            version: DUMP_VERSION,
+            settings,
+            role_defaults,
+            roles,
+            clusters,
+            databases,
+            schemas,
+            items,
+        })
+    }
+
+    /// Replays a [`CatalogDump`] on a fresh catalog, which must not yet contain
+    /// any items.
+    ///
+    /// Roles, clusters, databases, and schemas that already exist, like the
+    /// `materialize` database, are reused, and the owners, cluster
+    /// configurations, role defaults, and privileges recorded in the dump are
+    /// applied to them. Items keep their IDs, and the allocator of user IDs is
+    /// advanced past them. The items' definitions are migrated from the catalog
+    /// content version recorded in the dump when the catalog is next opened.
+    ///
+    /// The contents of secrets are not stored in the catalog, so the caller
+    /// must write them to secrets storage, as returned by
+    /// [`CatalogDump::secrets`]. The import fails before modifying the
+    /// catalog if the dump does not include them.
+    pub fn import(&mut self, dump: &CatalogDump, now: EpochMillis) -> Result<(), Error> {
+        if dump.version != DUMP_VERSION {
+            return Err(import_error(format!(
+                "unsupported dump version {}",
+                dump.version
+            )));
+        }
+        if !self.transaction()?.load_items()?.is_empty() {
+            return Err(import_error("catalog already contains items".into()));
+        }
+        let mut content_version = None;
+        for (name, value) in &dump.settings {
+            match name.as_str() {
+                "catalog_content_version" => content_version = Some(value),
+                // These modes cannot be changed once the catalog is created.
+                "experimental_mode" | "safe_mode" => {
+                    if self.inner.setting(name)?.as_ref() != Some(value) {
+                        return Err(import_error(format!(
+                            "dump has {} = {}, but the catalog does not",
+                            name, value
+                        )));
+                    }
+                }
+                _ => return Err(import_error(format!("unknown setting {}", name))),
+            }
+        }
+
+        dump.secrets()?;
+
+        // The catalog contains no items, so none of the IDs allocated so far
+        // are in use, and the items' IDs are free to reuse.
+        let mut next_id = 0;
+        for item in &dump.items {
+            match item.id {
+                GlobalId::User(id) => next_id = cmp::max(next_id, id + 1),
+                id => return Err(import_error(format!("item {} has a non-user ID", id))),
+            }
+        }
+        let allocated = self.inner.allocate_global_id(GidNamespace::User, 0)?;
+        if next_id > allocated {
+            self.inner
+                .allocate_global_id(GidNamespace::User, next_id - allocated)?;
+        }
+        // Discard any block of user IDs that was allocated before the import.
+        self.user_gid_block = 0..0;
+        // The content version is set before the objects are inserted, so that
+        // a failed import leaves at worst a catalog of the builtin objects
+        // with an older content version, whose migrations are harmless.
+        if let Some(content_version) = content_version {
+            self.set_catalog_content_version(content_version)?;
+        }
+
+        let roles: BTreeMap<_, _> = self
+            .load_roles()?
+            .into_iter()
+            .map(|(id, name, _, _, _)| (name, id))
+            .collect();
+        let clusters: BTreeMap<_, _> = self
+            .load_compute_instances()?
+            .into_iter()
+            .map(|(id, name, _, _, _)| (name, id))
+            .collect();
+        let databases: BTreeMap<_, _> = self
+            .load_databases()?
+            .into_iter()
+            .map(|(id, name, _, _)| (name, id))
+            .collect();
+        let schemas: BTreeMap<_, _> = self
+            .load_schemas()?
+            .into_iter()
+            .filter_map(|(id, name, database_id, _, _)| Some(((database_id?, name), id)))
+            .collect();
+
+        let mut tx = self.transaction()?;
+
+        for (name, value) in &dump.role_defaults {
+            tx.set_role_setting(None, name, Some(value))?;
+        }
+        let mut role_ids = roles;
+        for role in &dump.roles {
+            let id = match role_ids.get(&role.name) {
+                Some(id) => *id,
+                None => {
+                    let id = tx.insert_role(
+                        &role.name,
+                        &role.restrictions,
+                        role.password.as_deref(),
+                        now,
+                    )?;
+                    role_ids.insert(role.name.clone(), id);
+                    id
+                }
+            };
+            for (name, value) in &role.defaults {
+                tx.set_role_setting(Some(id), name, Some(value))?;
+            }
+        }
+        let role_id = |owner: &str| {
+            role_ids
+                .get(owner)
+                .copied()
+                .ok_or_else(|| import_error(format!("unknown owner {}", owner)))
+        };
+        let grant = |tx: &Transaction, object, privileges: &[DumpedPrivilege]| {
+            for privilege in privileges {
+                let role_id = match &privilege.role {
+                    Some(role) => Some(role_id(role)?),
+                    None => None,
+                };
+                tx.grant_privilege(object, role_id, parse_privilege(&privilege.privilege)?)?;
+            }
+            Ok::<_, Error>(())
+        };
+
+        for cluster in &dump.clusters {
+            let owner_id = role_id(&cluster.owner)?;
+            match clusters.get(&cluster.name) {
+                Some(id) => {
+                    tx.update_compute_instance_config(*id, &cluster.config, now)?;
+                    tx.update_owner(OwnedObjectId::ComputeInstance(*id), owner_id, now)?;
+                }
+                None => {
+                    // The introspection source indexes are allocated when the
+                    // catalog is next opened.
+                    tx.insert_compute_instance(
+                        &cluster.name,
+                        &cluster.config,
+                        &vec![],
+                        owner_id,
+                        now,
+                    )?;
+                }
+            }
+        }
+
+        let mut database_ids = databases;
+        for database in &dump.databases {
+            let owner_id = role_id(&database.owner)?;
+            let id = match database_ids.get(&database.name) {
+                Some(id) => {
+                    tx.update_owner(OwnedObjectId::Database(*id), owner_id, now)?;
+                    *id
+                }
+                None => {
+                    let id = tx.insert_database(&database.name, owner_id, now)?;
+                    database_ids.insert(database.name.clone(), id);
+                    id
+                }
+            };
+            grant(&tx, OwnedObjectId::Database(id), &database.privileges)?;
+        }
+
+        let mut schema_ids = schemas;
+        for schema in &dump.schemas {
+            let owner_id = role_id(&schema.owner)?;
+            let database_id = *database_ids
+                .get(&schema.database)
+                .ok_or_else(|| import_error(format!("unknown database {}", schema.database)))?;
+            let id = match schema_ids.get(&(database_id, schema.name.clone())) {
+                Some(id) => {
+                    tx.update_owner(OwnedObjectId::Schema(*id), owner_id, now)?;
+                    *id
+                }
+                None => {
+                    let id = tx.insert_schema(database_id, &schema.name, owner_id, now)?;
+                    schema_ids.insert((database_id, schema.name.clone()), id);
+                    id
+                }
+            };
+            grant(&tx, OwnedObjectId::Schema(id), &schema.privileges)?;
+        }
+
+        for item in &dump.items {
+            let database_id = database_ids
+                .get(&item.database)
+                .ok_or_else(|| import_error(format!("unknown database {}", item.database)))?;
+            let schema_id = schema_ids
+                .get(&(*database_id, item.schema.clone()))
+                .ok_or_else(|| {
+                    import_error(format!("unknown schema {}.{}", item.database, item.schema))
+                })?;
+            let definition = SerializedCatalogItem::V1 {
+                create_sql: item.create_sql.clone(),
+                eval_env: None,
+                table_persist_name: None,
+                source_persist_details: None,
+            };
+            let definition =
+                serde_json::to_vec(&definition).expect("catalog serialization cannot fail");
+            tx.insert_item(
+                item.id,
+                *schema_id,
+                &item.name,
+                &definition,
+                role_id(&item.owner)?,
+                now,
+            )?;
+            grant(&tx, OwnedObjectId::Item(item.id), &item.privileges)?;
+        }
+
+        tx.commit()
+    }
+}
+
+fn import_error(detail: String) -> Error {
+    Error::new(ErrorKind::Import(detail))
+}
+
+fn unknown_database(id: DatabaseId) -> Error {
+    Error::new(ErrorKind::Corruption {
+        detail: format!("unknown database {}", id),
+    })
+}
+
+fn unknown_schema(id: SchemaId) -> Error {
+    Error::new(ErrorKind::Corruption {
+        detail: format!("unknown schema {}", id.0),
+    })
+}
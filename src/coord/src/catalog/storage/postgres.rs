@@ -0,0 +1,2272 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Catalog storage in a PostgreSQL database.
+//!
+//! The catalog's storage is accessed synchronously, but the synchronous
+//! `postgres` client must not be used on a thread that drives asynchronous
+//! tasks, like the coordinator's. So the client lives on a dedicated thread,
+//! to which the backend sends each operation to run.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+use postgres::error::SqlState;
+use postgres::{Client, GenericClient, IsolationLevel};
+use tokio_postgres::Config;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_expr::GlobalId;
+use mz_ore::now::EpochMillis;
+use mz_secrets::SecretVersion;
+use mz_sql::ast::Privilege;
+use mz_sql::catalog::CatalogError as SqlCatalogError;
+use mz_sql::names::{
+    DatabaseId, ObjectQualifiers, QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaId,
+    SchemaSpecifier,
+};
+use mz_sql::plan::{ComputeInstanceConfig, ComputeInstanceReplicaConfig, RoleRestrictions};
+
+use crate::catalog::builtin::BuiltinLog;
+use crate::catalog::error::{Error, ErrorKind};
+use crate::catalog::storage::{
+    advance_gid, comment_column_position, migration_checksum, parse_comment_column_position,
+    parse_privilege, parse_stored_role_id, stored_role_id, unknown_comment, Backend,
+    BackendTransaction, CatalogDurability, GidNamespace, ItemDependencies, ItemUpdate, NewItem,
+    OwnedObjectId, RemovedItem, StoredComment, StoredComputeReplica, StoredDroppedItem, StoredItem,
+    StoredPrivilege, StoredReferences, StoredRole, StoredRoleSetting, APPLICATION_ID,
+};
+use crate::catalog::{
+    AuditEvent, AuditEventType, ObjectTimestamps, SecretAuditEvent, SecretAuditOperation,
+};
+
+/// Schema migrations for the catalog's database.
+///
+/// Catalogs were first stored in PostgreSQL in v0.26.1, so the first migration
+/// creates the schema that SQLite catalogs had at that point, rather than
+/// replaying their history. Later migrations must be added here as well as to
+/// the SQLite backend's migrations.
+///
+/// Foreign keys are not declared, as the SQLite backend does not enforce them
+/// and the catalog removes rows in whatever order is convenient.
+const MIGRATIONS: &[&str] = &[
+    // Creates initial schema.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE settings (
+        name text PRIMARY KEY,
+        value text
+    );
+
+    CREATE TABLE user_gid_alloc (
+        next_gid bigint NOT NULL
+    );
+
+    CREATE TABLE system_gid_alloc (
+        next_gid bigint NOT NULL
+    );
+
+    CREATE TABLE databases (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        name text NOT NULL UNIQUE,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL
+    );
+
+    CREATE TABLE schemas (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        database_id bigint,
+        name text NOT NULL,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL,
+        UNIQUE (database_id, name)
+    );
+
+    CREATE TABLE roles (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        name text NOT NULL UNIQUE,
+        restrictions text,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL
+    );
+
+    CREATE TABLE compute_instances (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        name text NOT NULL UNIQUE,
+        config text,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL
+    );
+
+    CREATE TABLE items (
+        gid text PRIMARY KEY,
+        schema_id bigint,
+        name text NOT NULL,
+        definition bytea NOT NULL,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL,
+        UNIQUE (schema_id, name)
+    );
+
+    CREATE TABLE system_gid_mapping (
+        schema_name text NOT NULL,
+        object_name text NOT NULL,
+        id bigint NOT NULL,
+        fingerprint bigint NOT NULL,
+        PRIMARY KEY (schema_name, object_name)
+    );
+
+    CREATE TABLE compute_introspection_source_indexes (
+        compute_id bigint NOT NULL,
+        name text NOT NULL,
+        index_id bigint NOT NULL,
+        PRIMARY KEY (compute_id, name)
+    );
+
+    CREATE TABLE secret_audit_events (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        secret_id text NOT NULL,
+        operation text NOT NULL,
+        version bigint,
+        \"user\" text NOT NULL,
+        occurred_at bigint NOT NULL
+    );
+
+    -- NOW_MS is the current time in milliseconds since the Unix epoch.
+    INSERT INTO user_gid_alloc VALUES (1);
+    INSERT INTO system_gid_alloc VALUES (1);
+    INSERT INTO databases VALUES
+        (1, 'materialize', NOW_MS, NOW_MS);
+    INSERT INTO schemas VALUES
+        (1, NULL, 'mz_catalog', NOW_MS, NOW_MS),
+        (2, NULL, 'pg_catalog', NOW_MS, NOW_MS),
+        (3, 1, 'public', NOW_MS, NOW_MS),
+        (4, NULL, 'mz_internal', NOW_MS, NOW_MS),
+        (5, NULL, 'information_schema', NOW_MS, NOW_MS);
+    INSERT INTO roles VALUES
+        (1, 'materialize', NULL, NOW_MS, NOW_MS);
+    INSERT INTO compute_instances VALUES
+        (1, 'default', NULL, NOW_MS, NOW_MS);
+    SELECT setval(pg_get_serial_sequence('databases', 'id'), 1);
+    SELECT setval(pg_get_serial_sequence('schemas', 'id'), 5);
+    SELECT setval(pg_get_serial_sequence('roles', 'id'), 1);
+    SELECT setval(pg_get_serial_sequence('compute_instances', 'id'), 1);",
+    // Records every change to databases, schemas, roles, clusters, and
+    // items, for auditing.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE audit_events (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        event_type text NOT NULL,
+        object_type text NOT NULL,
+        object_id text NOT NULL,
+        name text NOT NULL,
+        \"user\" text NOT NULL,
+        occurred_at bigint NOT NULL,
+        details text NOT NULL
+    );",
+    // Records the role that owns each database, schema, compute instance, and
+    // item.
+    //
+    // Introduced in v0.26.1.
+    //
+    // See the corresponding SQLite migration for how existing objects are
+    // assigned owners.
+    "ALTER TABLE databases ADD COLUMN owner_id bigint NOT NULL DEFAULT -1;
+    ALTER TABLE schemas ADD COLUMN owner_id bigint NOT NULL DEFAULT -1;
+    ALTER TABLE compute_instances ADD COLUMN owner_id bigint NOT NULL DEFAULT -1;
+    ALTER TABLE items ADD COLUMN owner_id bigint NOT NULL DEFAULT -1;
+    UPDATE databases SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);
+    UPDATE schemas SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1)
+        WHERE database_id IS NOT NULL;
+    UPDATE compute_instances SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);
+    UPDATE items SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);",
+    // Records the comments set by `COMMENT ON`.
+    //
+    // Introduced in v0.26.1.
+    //
+    // As with `objsubid` in PostgreSQL's `pg_description`, a column position
+    // of zero indicates a comment on the item itself.
+    "CREATE TABLE comments (
+        object_id text NOT NULL,
+        column_position bigint NOT NULL,
+        comment text NOT NULL,
+        PRIMARY KEY (object_id, column_position)
+    );",
+    // Records the session defaults set by `ALTER ROLE ... SET`.
+    //
+    // Introduced in v0.26.1.
+    //
+    // A role ID of zero indicates a default for every role.
+    "CREATE TABLE role_settings (
+        role_id bigint NOT NULL,
+        name text NOT NULL,
+        value text NOT NULL,
+        PRIMARY KEY (role_id, name)
+    );",
+    // Records the definitions of dropped items, from which the items can be
+    // restored with `UNDROP` until they are purged.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE dropped_items (
+        gid text PRIMARY KEY,
+        name text NOT NULL,
+        type text NOT NULL,
+        definition bytea NOT NULL,
+        dropped_at bigint NOT NULL
+    );
+    CREATE INDEX dropped_items_dropped_at ON dropped_items (dropped_at);",
+    // Records the replicas of each compute instance, separately from the
+    // compute instance's own configuration.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE compute_replicas (
+        id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        compute_id bigint NOT NULL,
+        name text NOT NULL,
+        config text NOT NULL,
+        created_at bigint NOT NULL,
+        updated_at bigint NOT NULL,
+        UNIQUE (compute_id, name)
+    );",
+    // Records the objects on which each item depends, so that the dependency
+    // graph can be read without planning every item. Existing items'
+    // dependencies are filled in when the catalog is next loaded.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE item_dependencies (
+        gid text NOT NULL,
+        dependency text NOT NULL,
+        PRIMARY KEY (gid, dependency)
+    );
+    CREATE INDEX item_dependencies_dependency ON item_dependencies (dependency);",
+    // Forgets the fingerprints of builtins, which were either placeholders or
+    // hashes that were not stable across versions. Builtins whose fingerprint
+    // is unknown keep their IDs, and their fingerprints are filled in when the
+    // catalog is next loaded.
+    //
+    // Introduced in v0.26.1.
+    "ALTER TABLE system_gid_mapping ALTER COLUMN fingerprint DROP NOT NULL;
+    UPDATE system_gid_mapping SET fingerprint = NULL;",
+    // Records the privileges that roles hold on objects that they do not own.
+    // The object is identified by its type, one of `database`, `schema`,
+    // `cluster`, or `item`, and its ID, and the privilege by its SQL keyword.
+    //
+    // A role ID of zero indicates a privilege granted to every role.
+    //
+    // Introduced in v0.26.1.
+    "CREATE TABLE privileges (
+        object_type text NOT NULL,
+        object_id text NOT NULL,
+        role_id bigint NOT NULL,
+        privilege text NOT NULL,
+        PRIMARY KEY (object_type, object_id, role_id, privilege)
+    );
+    CREATE INDEX privileges_role_id ON privileges (role_id);",
+    // Records the SCRAM-SHA-256 verifier of each role's password, or NULL if
+    // the role has no password.
+    //
+    // Introduced in v0.26.1.
+    "ALTER TABLE roles ADD COLUMN password text",
+    // Records the error with which each operation on secrets storage failed,
+    // or NULL if it was applied.
+    //
+    // Introduced in v0.26.1.
+    "ALTER TABLE secret_audit_events ADD COLUMN error text",
+    // Add new migrations here.
+    //
+    // Migrations should be preceded with a comment of the following form:
+    //
+    //     > Short summary of migration's purpose.
+    //     >
+    //     > Introduced in <VERSION>.
+    //     >
+    //     > Optional additional commentary about safety or approach.
+    //
+    // Migrations must preserve backwards compatibility with all past releases
+    // of materialized. Migrations can be edited up until they ship in a
+    // release, after which they must never be removed, only patched by future
+    // migrations.
+];
+
+/// The current time in milliseconds since the Unix epoch, for use in
+/// migrations.
+const NOW_MS: &str = "(extract(epoch FROM now()) * 1000)::bigint";
+
+/// The name of the setting that counts the times that the catalog has been
+/// opened, with which a process detects that another has since opened it.
+const EPOCH_SETTING: &str = "epoch";
+
+/// An operation to run against the database on the client's thread.
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Catalog storage in a PostgreSQL database.
+///
+/// Only one process at a time can write to the catalog. Opening the catalog
+/// fences out any process that opened it before, whose subsequent writes fail
+/// with [`ErrorKind::Fenced`].
+///
+/// Writes run at the `READ COMMITTED` isolation level, under the same advisory
+/// lock that migrations take, rather than at the `SERIALIZABLE` isolation
+/// level. Writers therefore run one after the other, and cannot fail with
+/// serialization errors that would need to be retried.
+#[derive(Debug)]
+pub struct PostgresBackend {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl PostgresBackend {
+    /// Connects to the database at `url` and applies any unapplied
+    /// migrations, creating the catalog if the database does not contain
+    /// one.
+    ///
+    /// With [`CatalogDurability::None`], commits do not wait for the server to
+    /// flush them to its write-ahead log.
+    pub fn open(url: &str, durability: CatalogDurability) -> Result<PostgresBackend, Error> {
+        let config: Config = url.parse()?;
+        let tls = mz_postgres_util::make_tls(&config)
+            .map_err(|e| Error::new(ErrorKind::InvalidStorageConfig(format!("{:#}", e))))?;
+        let config = postgres::Config::from(config);
+        let connect = move || -> Result<Client, Error> {
+            let mut client = config.connect(tls.clone())?;
+            if durability == CatalogDurability::None {
+                client.batch_execute("SET synchronous_commit = off")?;
+            }
+            Ok(client)
+        };
+        let (jobs_tx, jobs_rx) = mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("catalog-postgres".to_string())
+            .spawn(move || {
+                let mut conn = match Connection::open(Box::new(connect)) {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                for job in jobs_rx {
+                    job(&mut conn);
+                }
+            })
+            .expect("failed to spawn catalog storage thread");
+        ready_rx.recv().expect("catalog storage thread exited")?;
+        Ok(PostgresBackend { jobs: jobs_tx })
+    }
+
+    /// Runs `f` against the connection and waits for its result.
+    fn run_on<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut Connection) -> Result<R, Error> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        self.jobs
+            .send(Box::new(move |conn| {
+                let _ = tx.send(f(conn));
+            }))
+            .expect("catalog storage thread exited");
+        rx.recv().expect("catalog storage thread exited")
+    }
+
+    /// Runs `f` against the database and waits for its result.
+    fn run<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut Client) -> Result<R, Error> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.run_on(|conn| f(conn.client()?))
+    }
+
+    /// Runs `f` in a transaction of its own, as a writer, and commits its
+    /// changes.
+    ///
+    /// Fails with [`ErrorKind::Fenced`] if another process has since opened the
+    /// catalog.
+    fn write<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut postgres::Transaction) -> Result<R, Error> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.run_on(|conn| {
+            let epoch = conn.epoch;
+            let mut tx = conn.client()?.transaction()?;
+            lock_and_check_epoch(&mut tx, epoch)?;
+            let result = f(&mut tx)?;
+            tx.commit()?;
+            Ok(result)
+        })
+    }
+}
+
+/// The client's connection to the database, on the client's thread.
+///
+/// A connection that is lost between transactions is reestablished the next
+/// time that it is used. A connection that is lost during a transaction is
+/// reestablished only once the transaction has ended, as the transaction's
+/// changes are lost with it, so the transaction's remaining operations fail.
+struct Connection {
+    connect: Box<dyn FnMut() -> Result<Client, Error> + Send>,
+    client: Client,
+    /// The epoch at which this process opened the catalog.
+    epoch: i64,
+    /// Whether a [`PostgresTransaction`] is open on the connection.
+    in_transaction: bool,
+}
+
+impl Connection {
+    /// Connects to the database, applies any unapplied migrations, and fences
+    /// out any process that opened the catalog before.
+    fn open(
+        mut connect: Box<dyn FnMut() -> Result<Client, Error> + Send>,
+    ) -> Result<Connection, Error> {
+        let mut client = connect()?;
+        let epoch = migrate(&mut client)?;
+        Ok(Connection {
+            connect,
+            client,
+            epoch,
+            in_transaction: false,
+        })
+    }
+
+    /// Returns the client, first reconnecting if the connection was lost
+    /// outside of a transaction.
+    fn client(&mut self) -> Result<&mut Client, Error> {
+        if self.client.is_closed() && !self.in_transaction {
+            self.client = (self.connect)()?;
+        }
+        Ok(&mut self.client)
+    }
+
+    /// Begins a writer's transaction, as by [`PostgresBackend::write`].
+    ///
+    /// The connection can be lost without the client noticing until it is
+    /// next used, so beginning the transaction is retried once on a new
+    /// connection if it fails because the connection was lost.
+    fn begin(&mut self) -> Result<(), Error> {
+        let mut result = self.try_begin();
+        if result.is_err() && self.client.is_closed() {
+            result = self.try_begin();
+        }
+        if result.is_ok() {
+            self.in_transaction = true;
+        }
+        result
+    }
+
+    fn try_begin(&mut self) -> Result<(), Error> {
+        let epoch = self.epoch;
+        let client = self.client()?;
+        client.batch_execute("BEGIN ISOLATION LEVEL READ COMMITTED")?;
+        if let Err(e) = lock_and_check_epoch(client, epoch) {
+            let _ = client.batch_execute("ROLLBACK");
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Ends the transaction that is open on the connection by running `stmt`,
+    /// either `COMMIT` or `ROLLBACK`.
+    fn end(&mut self, stmt: &str) -> Result<(), Error> {
+        let result = self.client.batch_execute(stmt);
+        self.in_transaction = false;
+        Ok(result?)
+    }
+}
+
+/// Takes the catalog's advisory lock for the rest of the transaction open on
+/// `client`, and then checks that no other process has opened the catalog
+/// since this process opened it at `epoch`.
+fn lock_and_check_epoch(client: &mut impl GenericClient, epoch: i64) -> Result<(), Error> {
+    client.execute(
+        "SELECT pg_advisory_xact_lock($1)",
+        &[&i64::from(APPLICATION_ID)],
+    )?;
+    let current: Option<String> = client
+        .query_opt(
+            "SELECT value FROM settings WHERE name = $1",
+            &[&EPOCH_SETTING],
+        )?
+        .and_then(|row| row.get(0));
+    let current = current.and_then(|current| current.parse().ok());
+    if current != Some(epoch) {
+        return Err(Error::new(ErrorKind::Fenced { epoch, current }));
+    }
+    Ok(())
+}
+
+/// Applies the migrations that have not yet been applied to the database, and
+/// advances the catalog's epoch, which it returns.
+///
+/// The migrations are applied in one transaction, under a lock, so that
+/// processes that open the same catalog at once do not race to migrate it.
+///
+/// Each applied migration is recorded in the `migrations` table along with
+/// the checksum of its text and when it was applied. The catalog is not opened
+/// if the text of an applied migration has since changed.
+fn migrate(client: &mut Client) -> Result<i64, Error> {
+    let mut tx = client.transaction()?;
+    tx.execute(
+        "SELECT pg_advisory_xact_lock($1)",
+        &[&i64::from(APPLICATION_ID)],
+    )?;
+    // Catalogs created before checksums were recorded store a single row,
+    // holding the index of the last migration that was run, without a
+    // checksum or application time. That row is treated like any other
+    // migration that was applied before checksums were recorded.
+    tx.batch_execute(
+        "CREATE TABLE IF NOT EXISTS migrations (
+            application_id integer NOT NULL,
+            version bigint NOT NULL
+        );
+        ALTER TABLE migrations
+            ADD COLUMN IF NOT EXISTS checksum text,
+            ADD COLUMN IF NOT EXISTS applied_at bigint;",
+    )?;
+    let mut recorded = BTreeMap::new();
+    for row in tx.query(
+        "SELECT application_id, version, checksum FROM migrations",
+        &[],
+    )? {
+        let app_id: i32 = row.get(0);
+        if app_id != APPLICATION_ID {
+            return Err(Error::new(ErrorKind::Corruption {
+                detail: "catalog database has incorrect application_id".into(),
+            }));
+        }
+        let version: i64 = row.get(1);
+        let checksum: Option<String> = row.get(2);
+        recorded.insert(version, checksum);
+    }
+    let next = match recorded.keys().next_back() {
+        None => 0,
+        Some(version) => usize::try_from(version + 1).expect("version is at least -1"),
+    };
+    // A catalog from a later version of materialized can have applied
+    // migrations that this version does not know about.
+    for (i, migration) in MIGRATIONS.iter().enumerate().take(next) {
+        let checksum = migration_checksum(migration);
+        let version = i as i64;
+        match recorded.get(&version) {
+            None => {
+                tx.execute(
+                    "INSERT INTO migrations (application_id, version, checksum)
+                    VALUES ($1, $2, $3)",
+                    &[&APPLICATION_ID, &version, &checksum],
+                )?;
+            }
+            Some(None) => {
+                tx.execute(
+                    "UPDATE migrations SET checksum = $1 WHERE version = $2",
+                    &[&checksum, &version],
+                )?;
+            }
+            Some(Some(stored)) if *stored != checksum => {
+                return Err(Error::new(ErrorKind::ModifiedMigration { version: i }));
+            }
+            Some(Some(_)) => (),
+        }
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(next) {
+        tx.batch_execute(&migration.replace("NOW_MS", NOW_MS))?;
+        let query = "INSERT INTO migrations (application_id, version, checksum, applied_at)
+            VALUES ($1, $2, $3, NOW_MS)"
+            .replace("NOW_MS", NOW_MS);
+        tx.execute(
+            query.as_str(),
+            &[&APPLICATION_ID, &(i as i64), &migration_checksum(migration)],
+        )?;
+    }
+    if next < MIGRATIONS.len() {
+        let version = (MIGRATIONS.len() - 1).to_string();
+        let query = "INSERT INTO settings (name, value)
+            VALUES ('schema_version', $1), ('last_migration_at', NOW_MS::text)
+            ON CONFLICT (name) DO UPDATE SET value = excluded.value"
+            .replace("NOW_MS", NOW_MS);
+        tx.execute(query.as_str(), &[&version])?;
+    }
+    let epoch: String = tx
+        .query_one(
+            "INSERT INTO settings (name, value) VALUES ($1, '1')
+            ON CONFLICT (name) DO UPDATE SET value = (settings.value::bigint + 1)::text
+            RETURNING value",
+            &[&EPOCH_SETTING],
+        )?
+        .get(0);
+    tx.commit()?;
+    epoch.parse().map_err(|_| {
+        Error::new(ErrorKind::Corruption {
+            detail: format!("invalid catalog epoch {}", epoch),
+        })
+    })
+}
+
+impl Backend for PostgresBackend {
+    fn setting(&mut self, name: &str) -> Result<Option<String>, Error> {
+        let name = name.to_owned();
+        self.run(move |client| {
+            let row = client.query_opt("SELECT value FROM settings WHERE name = $1", &[&name])?;
+            Ok(row.and_then(|row| row.get(0)))
+        })
+    }
+
+    fn set_setting(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let name = name.to_owned();
+        let value = value.to_owned();
+        self.write(move |tx| {
+            tx.execute(
+                "INSERT INTO settings (name, value) VALUES ($1, $2)
+                ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+                &[&name, &value],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn remove_setting(&mut self, name: &str) -> Result<(), Error> {
+        let name = name.to_owned();
+        self.write(move |tx| {
+            tx.execute("DELETE FROM settings WHERE name = $1", &[&name])?;
+            Ok(())
+        })
+    }
+
+    fn load_settings(&mut self) -> Result<Vec<(String, String)>, Error> {
+        self.run(|client| {
+            Ok(client
+                .query(
+                    "SELECT name, value FROM settings WHERE value IS NOT NULL",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect())
+        })
+    }
+
+    fn load_databases(&self) -> Result<Vec<(DatabaseId, String, i64, ObjectTimestamps)>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, name, owner_id, created_at, updated_at FROM databases",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let name: String = row.get(1);
+                    let owner_id: i64 = row.get(2);
+                    Ok((DatabaseId(id), name, owner_id, get_timestamps(&row, 3)))
+                })
+                .collect()
+        })
+    }
+
+    fn load_schemas(
+        &self,
+    ) -> Result<Vec<(SchemaId, String, Option<DatabaseId>, i64, ObjectTimestamps)>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT schemas.id, schemas.name, databases.id, schemas.owner_id,
+                        schemas.created_at, schemas.updated_at
+                    FROM schemas
+                    LEFT JOIN databases ON schemas.database_id = databases.id",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let schema_name: String = row.get(1);
+                    let database_id: Option<i64> = row.get(2);
+                    let owner_id: i64 = row.get(3);
+                    Ok((
+                        SchemaId(id),
+                        schema_name,
+                        database_id.map(DatabaseId),
+                        owner_id,
+                        get_timestamps(&row, 4),
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_roles(&self) -> Result<Vec<StoredRole>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, name, restrictions, password, created_at, updated_at FROM roles",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let name: String = row.get(1);
+                    let restrictions: Option<String> = row.get(2);
+                    let restrictions = match restrictions {
+                        None => RoleRestrictions::default(),
+                        Some(restrictions) => from_json(&restrictions)?,
+                    };
+                    let password: Option<String> = row.get(3);
+                    Ok((id, name, restrictions, password, get_timestamps(&row, 4)))
+                })
+                .collect()
+        })
+    }
+
+    fn load_compute_instances(
+        &self,
+    ) -> Result<Vec<(i64, String, ComputeInstanceConfig, i64, ObjectTimestamps)>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, name, config, owner_id, created_at, updated_at
+                    FROM compute_instances",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let name: String = row.get(1);
+                    let config: Option<String> = row.get(2);
+                    let config = match config {
+                        None => ComputeInstanceConfig::Local,
+                        Some(config) => from_json(&config)?,
+                    };
+                    let owner_id: i64 = row.get(3);
+                    Ok((id, name, config, owner_id, get_timestamps(&row, 4)))
+                })
+                .collect()
+        })
+    }
+
+    fn load_compute_replicas(&self) -> Result<Vec<StoredComputeReplica>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, compute_id, name, config, created_at, updated_at
+                    FROM compute_replicas",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let compute_id: i64 = row.get(1);
+                    let name: String = row.get(2);
+                    let config: String = row.get(3);
+                    let config = from_json(&config)?;
+                    Ok((id, compute_id, name, config, get_timestamps(&row, 4)))
+                })
+                .collect()
+        })
+    }
+
+    fn load_system_gids(
+        &self,
+    ) -> Result<BTreeMap<(String, String), (GlobalId, Option<u64>)>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT schema_name, object_name, id, fingerprint FROM system_gid_mapping",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let schema_name: String = row.get(0);
+                    let object_name: String = row.get(1);
+                    let id: i64 = row.get(2);
+                    let fingerprint: Option<i64> = row.get(3);
+                    Ok((
+                        (schema_name, object_name),
+                        (
+                            GlobalId::System(id as u64),
+                            fingerprint.map(|fingerprint| fingerprint as u64),
+                        ),
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_introspection_source_index_gids(
+        &self,
+        compute_id: i64,
+    ) -> Result<BTreeMap<String, GlobalId>, Error> {
+        self.run(move |client| {
+            client
+                .query(
+                    "SELECT name, index_id FROM compute_introspection_source_indexes
+                    WHERE compute_id = $1",
+                    &[&compute_id],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let name: String = row.get(0);
+                    let index_id: i64 = row.get(1);
+                    Ok((name, GlobalId::System(index_id as u64)))
+                })
+                .collect()
+        })
+    }
+
+    fn set_system_gids(&mut self, mappings: Vec<(&str, &str, GlobalId, u64)>) -> Result<(), Error> {
+        let mappings: Vec<_> = mappings
+            .into_iter()
+            .map(|(schema_name, object_name, id, fingerprint)| {
+                (
+                    schema_name.to_owned(),
+                    object_name.to_owned(),
+                    system_id(id),
+                    fingerprint as i64,
+                )
+            })
+            .collect();
+        self.write(move |tx| {
+            for (schema_name, object_name, id, fingerprint) in mappings {
+                tx.execute(
+                    "INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (schema_name, object_name)
+                    DO UPDATE SET id = excluded.id, fingerprint = excluded.fingerprint",
+                    &[&schema_name, &object_name, &id, &fingerprint],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    fn replace_system_gids(
+        &mut self,
+        replacements: Vec<(GlobalId, GlobalId)>,
+    ) -> Result<(), Error> {
+        self.write(move |tx| {
+            for (old, new) in replacements {
+                tx.execute(
+                    "UPDATE comments SET object_id = $1 WHERE object_id = $2",
+                    &[&to_json(&new), &to_json(&old)],
+                )?;
+                tx.execute(
+                    "UPDATE privileges SET object_id = $1
+                    WHERE object_type = 'item' AND object_id = $2",
+                    &[&new.to_string(), &old.to_string()],
+                )?;
+                tx.execute(
+                    "UPDATE item_dependencies SET dependency = $1 WHERE dependency = $2",
+                    &[&to_json(&new), &to_json(&old)],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    fn set_introspection_source_index_gids(
+        &mut self,
+        mappings: Vec<(i64, &str, GlobalId)>,
+    ) -> Result<(), Error> {
+        let mappings: Vec<_> = mappings
+            .into_iter()
+            .map(|(compute_id, name, index_id)| (compute_id, name.to_owned(), system_id(index_id)))
+            .collect();
+        self.write(move |tx| {
+            for (compute_id, name, index_id) in mappings {
+                tx.execute(
+                    "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id)
+                    VALUES ($1, $2, $3)",
+                    &[&compute_id, &name, &index_id],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_secret_audit_events(&self) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, secret_id, operation, version, \"user\", occurred_at, error
+                    FROM secret_audit_events
+                    ORDER BY id",
+                    &[],
+                )?
+                .iter()
+                .map(secret_audit_event_from_row)
+                .collect()
+        })
+    }
+
+    fn load_audit_events(&self) -> Result<Vec<AuditEvent>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT id, event_type, object_type, object_id, name, \"user\", occurred_at,
+                        details
+                    FROM audit_events
+                    ORDER BY id",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(0);
+                    let event_type: String = row.get(1);
+                    let event_type = match event_type.as_str() {
+                        "create" => AuditEventType::Create,
+                        "drop" => AuditEventType::Drop,
+                        "alter" => AuditEventType::Alter,
+                        _ => {
+                            return Err(Error::new(ErrorKind::Corruption {
+                                detail: format!("unknown audit event type {}", event_type),
+                            }))
+                        }
+                    };
+                    let occurred_at: i64 = row.get(6);
+                    let details: String = row.get(7);
+                    Ok(AuditEvent {
+                        id: id as u64,
+                        event_type,
+                        object_type: row.get(2),
+                        object_id: row.get(3),
+                        name: row.get(4),
+                        user: row.get(5),
+                        occurred_at: occurred_at as EpochMillis,
+                        details: from_json(&details)?,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn load_comments(&self) -> Result<Vec<StoredComment>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT object_id, column_position, comment FROM comments",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: String = row.get(0);
+                    let column_position: i64 = row.get(1);
+                    Ok((
+                        from_json(&id)?,
+                        parse_comment_column_position(column_position)?,
+                        row.get(2),
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_role_settings(&self) -> Result<Vec<StoredRoleSetting>, Error> {
+        self.run(|client| {
+            Ok(client
+                .query("SELECT role_id, name, value FROM role_settings", &[])?
+                .into_iter()
+                .map(|row| {
+                    let role_id: i64 = row.get(0);
+                    (parse_stored_role_id(role_id), row.get(1), row.get(2))
+                })
+                .collect())
+        })
+    }
+
+    fn load_privileges(&self) -> Result<Vec<StoredPrivilege>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT object_type, object_id, role_id, privilege FROM privileges",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let object_type: String = row.get(0);
+                    let object_id: String = row.get(1);
+                    let role_id: i64 = row.get(2);
+                    let privilege: String = row.get(3);
+                    Ok((
+                        OwnedObjectId::parse_stored_id(&object_type, &object_id)?,
+                        parse_stored_role_id(role_id),
+                        parse_privilege(&privilege)?,
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_dropped_items(&self) -> Result<Vec<StoredDroppedItem>, Error> {
+        self.run(|client| {
+            client
+                .query(
+                    "SELECT gid, name, type, definition, dropped_at
+                    FROM dropped_items
+                    ORDER BY dropped_at",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: String = row.get(0);
+                    let dropped_at: i64 = row.get(4);
+                    Ok((
+                        from_json(&id)?,
+                        row.get(1),
+                        row.get(2),
+                        row.get(3),
+                        dropped_at as EpochMillis,
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_references(&self) -> Result<StoredReferences, Error> {
+        self.run(|client| {
+            // A repeatable read transaction gives every query the same
+            // snapshot.
+            let mut tx = client
+                .build_transaction()
+                .isolation_level(IsolationLevel::RepeatableRead)
+                .read_only(true)
+                .start()?;
+            let databases = tx
+                .query("SELECT id FROM databases", &[])?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect();
+            let schemas = tx
+                .query("SELECT id, database_id FROM schemas", &[])?
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect();
+            let items = tx
+                .query("SELECT gid, name, schema_id FROM items", &[])?
+                .into_iter()
+                .map(|row| {
+                    let id: String = row.get(0);
+                    Ok((from_json(&id)?, row.get(1), row.get(2)))
+                })
+                .collect::<Result<_, Error>>()?;
+            let system_gids = tx
+                .query(
+                    "SELECT schema_name, object_name, id FROM system_gid_mapping",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: i64 = row.get(2);
+                    (row.get(0), row.get(1), id as u64)
+                })
+                .collect();
+            let introspection_source_indexes = tx
+                .query(
+                    "SELECT compute_id, name, index_id FROM compute_introspection_source_indexes",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let index_id: i64 = row.get(2);
+                    (row.get(0), row.get(1), index_id as u64)
+                })
+                .collect();
+            let next_system_gid: i64 = tx
+                .query_one("SELECT next_gid FROM system_gid_alloc", &[])?
+                .get(0);
+            let next_user_gid: i64 = tx
+                .query_one("SELECT next_gid FROM user_gid_alloc", &[])?
+                .get(0);
+            tx.commit()?;
+            Ok(StoredReferences {
+                databases,
+                schemas,
+                items,
+                system_gids,
+                introspection_source_indexes,
+                next_system_gid: next_system_gid as u64,
+                next_user_gid: next_user_gid as u64,
+            })
+        })
+    }
+
+    fn allocate_global_id(&mut self, namespace: GidNamespace, amount: u64) -> Result<u64, Error> {
+        let table = namespace.alloc_table();
+        self.write(move |tx| {
+            let id: i64 = tx
+                .query_one(format!("SELECT next_gid FROM {table}").as_str(), &[])?
+                .get(0);
+            let next_id = advance_gid(id, amount)?;
+            tx.execute(
+                format!("UPDATE {table} SET next_gid = $1").as_str(),
+                &[&next_id],
+            )?;
+            Ok(id as u64)
+        })
+    }
+
+    fn transaction(&mut self) -> Result<Box<dyn BackendTransaction + '_>, Error> {
+        self.run_on(|conn| conn.begin())?;
+        Ok(Box::new(PostgresTransaction {
+            backend: self,
+            committed: false,
+        }))
+    }
+
+    fn backup(&mut self, _path: &Path) -> Result<(), Error> {
+        Err(Error::new(ErrorKind::Backup(
+            "catalogs stored in PostgreSQL must be backed up using PostgreSQL's own tools, \
+             like pg_dump"
+                .into(),
+        )))
+    }
+}
+
+/// A writer's transaction that is open on the client's connection, as begun
+/// by [`Connection::begin`].
+///
+/// Nothing else runs on the connection while the transaction is open, as the
+/// transaction holds the backend's only reference.
+struct PostgresTransaction<'a> {
+    backend: &'a PostgresBackend,
+    committed: bool,
+}
+
+impl BackendTransaction for PostgresTransaction<'_> {
+    fn load_items(&self) -> Result<Vec<StoredItem>, Error> {
+        // Order user views by their GlobalId
+        self.backend.run(|client| {
+            client
+                .query(
+                    "SELECT items.gid, databases.id, schemas.id, items.name, items.definition,
+                        items.owner_id, items.created_at, items.updated_at
+                    FROM items
+                    JOIN schemas ON items.schema_id = schemas.id
+                    JOIN databases ON schemas.database_id = databases.id
+                    ORDER BY (items.gid::jsonb ->> 'User')::bigint NULLS FIRST",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| {
+                    let id: String = row.get(0);
+                    let database: i64 = row.get(1);
+                    let schema: i64 = row.get(2);
+                    let item: String = row.get(3);
+                    let definition: Vec<u8> = row.get(4);
+                    let owner_id: i64 = row.get(5);
+                    Ok((
+                        from_json(&id)?,
+                        QualifiedObjectName {
+                            qualifiers: ObjectQualifiers {
+                                database_spec: ResolvedDatabaseSpecifier::from(database),
+                                schema_spec: SchemaSpecifier::from(schema),
+                            },
+                            item,
+                        },
+                        definition,
+                        owner_id,
+                        get_timestamps(&row, 6),
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    fn load_item_dependencies(&self) -> Result<BTreeMap<GlobalId, BTreeSet<GlobalId>>, Error> {
+        let rows = self.backend.run(|client| {
+            Ok(client.query("SELECT gid, dependency FROM item_dependencies", &[])?)
+        })?;
+        let mut dependencies = BTreeMap::<_, BTreeSet<_>>::new();
+        for row in rows {
+            let id: String = row.get(0);
+            let dependency: String = row.get(1);
+            dependencies
+                .entry(from_json(&id)?)
+                .or_default()
+                .insert(from_json(&dependency)?);
+        }
+        Ok(dependencies)
+    }
+
+    fn insert_database(
+        &mut self,
+        database_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<DatabaseId, Error> {
+        let name = database_name.to_owned();
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            let row = client.query_one(
+                "INSERT INTO databases (name, owner_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $3)
+                RETURNING id",
+                &[&name, &owner_id, &now],
+            )?;
+            Ok(row.get(0))
+        });
+        match result {
+            Ok(id) => Ok(DatabaseId(id)),
+            Err(err) if is_unique_violation(&err) => Err(Error::new(
+                ErrorKind::DatabaseAlreadyExists(database_name.to_owned()),
+            )),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn insert_schema(
+        &mut self,
+        database_id: DatabaseId,
+        schema_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<SchemaId, Error> {
+        let name = schema_name.to_owned();
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            let row = client.query_one(
+                "INSERT INTO schemas (database_id, name, owner_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $4)
+                RETURNING id",
+                &[&database_id.0, &name, &owner_id, &now],
+            )?;
+            Ok(row.get(0))
+        });
+        match result {
+            Ok(id) => Ok(SchemaId(id)),
+            Err(err) if is_unique_violation(&err) => Err(Error::new(
+                ErrorKind::SchemaAlreadyExists(schema_name.to_owned()),
+            )),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn insert_role(
+        &mut self,
+        role_name: &str,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let restrictions = serialize_restrictions(restrictions);
+        let name = role_name.to_owned();
+        let password = password.map(|password| password.to_owned());
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            let row = client.query_one(
+                "INSERT INTO roles (name, restrictions, password, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $4)
+                RETURNING id",
+                &[&name, &restrictions, &password, &now],
+            )?;
+            Ok(row.get(0))
+        });
+        match result {
+            Err(err) if is_unique_violation(&err) => Err(Error::new(ErrorKind::RoleAlreadyExists(
+                role_name.to_owned(),
+            ))),
+            result => result,
+        }
+    }
+
+    fn update_role(
+        &mut self,
+        id: i64,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let restrictions = serialize_restrictions(restrictions);
+        let password = password.map(|password| password.to_owned());
+        let updated_at = updated_at as i64;
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE roles SET restrictions = $1, password = $2, updated_at = $3
+                WHERE id = $4",
+                &[&restrictions, &password, &updated_at, &id],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownRole(id.to_string()).into())
+        }
+    }
+
+    fn insert_compute_instance(
+        &mut self,
+        cluster_name: &str,
+        config: &ComputeInstanceConfig,
+        introspection_sources: &Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let config = to_json(config);
+        let introspection_sources: Vec<_> = introspection_sources
+            .iter()
+            .map(|(builtin, index_id)| (builtin.name, system_id(*index_id)))
+            .collect();
+        let name = cluster_name.to_owned();
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            let id: i64 = client
+                .query_one(
+                    "INSERT INTO compute_instances (name, config, owner_id, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $4)
+                    RETURNING id",
+                    &[&name, &config, &owner_id, &now],
+                )?
+                .get(0);
+            for (name, index_id) in introspection_sources {
+                client.execute(
+                    "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id)
+                    VALUES ($1, $2, $3)",
+                    &[&id, &name, &index_id],
+                )?;
+            }
+            Ok(id)
+        });
+        match result {
+            Err(err) if is_unique_violation(&err) => Err(Error::new(
+                ErrorKind::ClusterAlreadyExists(cluster_name.to_owned()),
+            )),
+            result => result,
+        }
+    }
+
+    fn update_compute_instance_config(
+        &mut self,
+        id: ComputeInstanceId,
+        config: &ComputeInstanceConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let config = to_json(config);
+        let updated_at = updated_at as i64;
+        self.backend.run(move |client| {
+            client.execute(
+                "UPDATE compute_instances SET config = $1, updated_at = $2 WHERE id = $3",
+                &[&config, &updated_at, &id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn insert_compute_replica(
+        &mut self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+        config: &ComputeInstanceReplicaConfig,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let config = to_json(config);
+        let name = replica_name.to_owned();
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            Ok(client
+                .query_one(
+                    "INSERT INTO compute_replicas (compute_id, name, config, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $4)
+                    RETURNING id",
+                    &[&compute_id, &name, &config, &now],
+                )?
+                .get(0))
+        });
+        match result {
+            Err(err) if is_unique_violation(&err) => Err(Error::new(
+                ErrorKind::ClusterReplicaAlreadyExists(replica_name.to_owned()),
+            )),
+            result => result,
+        }
+    }
+
+    fn update_compute_replica_config(
+        &mut self,
+        id: i64,
+        config: &ComputeInstanceReplicaConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let config = to_json(config);
+        let updated_at = updated_at as i64;
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE compute_replicas SET config = $1, updated_at = $2 WHERE id = $3",
+                &[&config, &updated_at, &id],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstanceReplica(id.to_string()).into())
+        }
+    }
+
+    fn remove_compute_replica(
+        &self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+    ) -> Result<(), Error> {
+        let name = replica_name.to_owned();
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "DELETE FROM compute_replicas WHERE compute_id = $1 AND name = $2",
+                &[&compute_id, &name],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstanceReplica(replica_name.to_owned()).into())
+        }
+    }
+
+    fn insert_item(
+        &self,
+        id: GlobalId,
+        schema_id: SchemaId,
+        item_name: &str,
+        item: &[u8],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        let gid = to_json(&id);
+        let name = item_name.to_owned();
+        let item = item.to_vec();
+        let now = now as i64;
+        let result = self.backend.run(move |client| {
+            client.execute(
+                "INSERT INTO items
+                    (gid, schema_id, name, definition, owner_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $6)",
+                &[&gid, &schema_id.0, &name, &item, &owner_id, &now],
+            )?;
+            Ok(())
+        });
+        match result {
+            Err(err) if is_unique_violation(&err) => Err(Error::new(ErrorKind::ItemAlreadyExists(
+                item_name.to_owned(),
+            ))),
+            result => result,
+        }
+    }
+
+    fn insert_items(
+        &self,
+        items: &[NewItem],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        let gids: Vec<String> = items.iter().map(|(id, ..)| to_json(id)).collect();
+        let schema_ids: Vec<i64> = items.iter().map(|(_, schema_id, ..)| schema_id.0).collect();
+        let names: Vec<String> = items.iter().map(|(_, _, name, _)| name.clone()).collect();
+        let definitions: Vec<Vec<u8>> = items.iter().map(|(.., item)| item.clone()).collect();
+        let now = now as i64;
+        // Conflicting items are skipped rather than aborting the transaction,
+        // so that the error can name the first conflicting item.
+        let inserted: HashSet<String> = self.backend.run(move |client| {
+            Ok(client
+                .query(
+                    "INSERT INTO items
+                        (gid, schema_id, name, definition, owner_id, created_at, updated_at)
+                    SELECT gid, schema_id, name, definition, $5, $6, $6
+                    FROM unnest($1::text[], $2::bigint[], $3::text[], $4::bytea[])
+                        AS new_items (gid, schema_id, name, definition)
+                    ON CONFLICT DO NOTHING
+                    RETURNING gid",
+                    &[&gids, &schema_ids, &names, &definitions, &owner_id, &now],
+                )?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect())
+        })?;
+        match items
+            .iter()
+            .find(|(id, ..)| !inserted.contains(&to_json(id)))
+        {
+            Some((_, _, item_name, _)) => {
+                Err(Error::new(ErrorKind::ItemAlreadyExists(item_name.clone())))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn remove_database(&self, id: &DatabaseId) -> Result<(), Error> {
+        let db_id = id.0;
+        let n = self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM privileges WHERE object_type = 'database' AND object_id = $1",
+                &[&db_id.to_string()],
+            )?;
+            Ok(client.execute("DELETE FROM databases WHERE id = $1", &[&db_id])?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownDatabase(id.to_string()).into())
+        }
+    }
+
+    fn remove_schema(&self, database_id: &DatabaseId, schema_id: &SchemaId) -> Result<(), Error> {
+        let (db_id, id) = (database_id.0, schema_id.0);
+        let n = self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM privileges WHERE object_type = 'schema' AND object_id = $1",
+                &[&id.to_string()],
+            )?;
+            Ok(client.execute(
+                "DELETE FROM schemas WHERE database_id = $1 AND id = $2",
+                &[&db_id, &id],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownSchema(format!("{}.{}", database_id.0, schema_id.0)).into())
+        }
+    }
+
+    fn remove_role(&self, name: &str) -> Result<(), Error> {
+        let role_name = name.to_owned();
+        let n = self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM role_settings WHERE role_id = (SELECT id FROM roles WHERE name = $1)",
+                &[&role_name],
+            )?;
+            client.execute(
+                "DELETE FROM privileges WHERE role_id = (SELECT id FROM roles WHERE name = $1)",
+                &[&role_name],
+            )?;
+            Ok(client.execute("DELETE FROM roles WHERE name = $1", &[&role_name])?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownRole(name.to_owned()).into())
+        }
+    }
+
+    fn remove_compute_instance(&self, name: &str) -> Result<(), Error> {
+        let cluster_name = name.to_owned();
+        let n = self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM compute_replicas
+                WHERE compute_id IN (SELECT id FROM compute_instances WHERE name = $1)",
+                &[&cluster_name],
+            )?;
+            Ok(client.execute(
+                "DELETE FROM compute_instances WHERE name = $1",
+                &[&cluster_name],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstance(name.to_owned()).into())
+        }
+    }
+
+    fn remove_item(&self, item: &RemovedItem, dropped_at: EpochMillis) -> Result<(), Error> {
+        self.remove_items(std::slice::from_ref(item), dropped_at)
+    }
+
+    fn remove_items(&self, items: &[RemovedItem], dropped_at: EpochMillis) -> Result<(), Error> {
+        let ids: Vec<GlobalId> = items.iter().map(|(id, _, _)| *id).collect();
+        let gids: Vec<String> = ids.iter().map(to_json).collect();
+        let object_ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let names: Vec<String> = items.iter().map(|(_, name, _)| name.clone()).collect();
+        let types: Vec<String> = items.iter().map(|(_, _, typ)| typ.clone()).collect();
+        let dropped_at = dropped_at as i64;
+        let removed: HashSet<String> = self.backend.run(move |client| {
+            client.execute(
+                "INSERT INTO dropped_items (gid, name, type, definition, dropped_at)
+                SELECT items.gid, d.name, d.type, items.definition, $4
+                FROM items
+                JOIN unnest($1::text[], $2::text[], $3::text[]) AS d (gid, name, type)
+                    ON items.gid = d.gid",
+                &[&gids, &names, &types, &dropped_at],
+            )?;
+            let removed = client
+                .query(
+                    "DELETE FROM items WHERE gid = ANY($1) RETURNING gid",
+                    &[&gids],
+                )?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect();
+            client.execute("DELETE FROM comments WHERE object_id = ANY($1)", &[&gids])?;
+            client.execute(
+                "DELETE FROM item_dependencies WHERE gid = ANY($1)",
+                &[&gids],
+            )?;
+            client.execute(
+                "DELETE FROM privileges WHERE object_type = 'item' AND object_id = ANY($1)",
+                &[&object_ids],
+            )?;
+            Ok(removed)
+        })?;
+        match ids.iter().find(|id| !removed.contains(&to_json(id))) {
+            Some(id) => Err(SqlCatalogError::UnknownItem(id.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn remove_dropped_item(&self, id: GlobalId) -> Result<(), Error> {
+        let gid = to_json(&id);
+        let n = self.backend.run(move |client| {
+            Ok(client.execute("DELETE FROM dropped_items WHERE gid = $1", &[&gid])?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+        }
+    }
+
+    fn purge_dropped_items(&self, dropped_before: EpochMillis) -> Result<(), Error> {
+        let dropped_before = dropped_before as i64;
+        self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM dropped_items WHERE dropped_at < $1",
+                &[&dropped_before],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn update_item(
+        &self,
+        id: GlobalId,
+        item_name: &str,
+        item: &[u8],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let gid = to_json(&id);
+        let name = item_name.to_owned();
+        let item = item.to_vec();
+        let updated_at = updated_at as i64;
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE items SET name = $1, definition = $2, updated_at = $3 WHERE gid = $4",
+                &[&name, &item, &updated_at, &gid],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+        }
+    }
+
+    fn update_items(&self, updates: &[ItemUpdate], updated_at: EpochMillis) -> Result<(), Error> {
+        let gids: Vec<String> = updates.iter().map(|(id, ..)| to_json(id)).collect();
+        let names: Vec<String> = updates.iter().map(|(_, name, _)| name.clone()).collect();
+        let definitions: Vec<Vec<u8>> = updates.iter().map(|(.., item)| item.clone()).collect();
+        let updated_at = updated_at as i64;
+        let updated: HashSet<String> = self.backend.run(move |client| {
+            Ok(client
+                .query(
+                    "UPDATE items
+                    SET name = updates.name, definition = updates.definition, updated_at = $4
+                    FROM unnest($1::text[], $2::text[], $3::bytea[])
+                        AS updates (gid, name, definition)
+                    WHERE items.gid = updates.gid
+                    RETURNING items.gid",
+                    &[&gids, &names, &definitions, &updated_at],
+                )?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect())
+        })?;
+        match updates
+            .iter()
+            .find(|(id, ..)| !updated.contains(&to_json(id)))
+        {
+            Some((id, ..)) => Err(SqlCatalogError::UnknownItem(id.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn set_item_dependencies(&self, dependencies: &[ItemDependencies]) -> Result<(), Error> {
+        let gids: Vec<String> = dependencies.iter().map(|(id, _)| to_json(id)).collect();
+        let mut edge_gids = vec![];
+        let mut edge_dependencies = vec![];
+        for (id, uses) in dependencies {
+            for dependency in uses {
+                edge_gids.push(to_json(id));
+                edge_dependencies.push(to_json(dependency));
+            }
+        }
+        self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM item_dependencies WHERE gid = ANY($1)",
+                &[&gids],
+            )?;
+            client.execute(
+                "INSERT INTO item_dependencies (gid, dependency)
+                SELECT * FROM unnest($1::text[], $2::text[])
+                ON CONFLICT DO NOTHING",
+                &[&edge_gids, &edge_dependencies],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn rename_database(
+        &self,
+        id: &DatabaseId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let (db_id, name, updated_at) = (id.0, to_name.to_owned(), updated_at as i64);
+        let result = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE databases SET name = $1, updated_at = $2 WHERE id = $3",
+                &[&name, &updated_at, &db_id],
+            )?)
+        });
+        let n = match result {
+            Ok(n) => n,
+            Err(err) if is_unique_violation(&err) => {
+                return Err(Error::new(ErrorKind::DatabaseAlreadyExists(
+                    to_name.to_owned(),
+                )))
+            }
+            Err(err) => return Err(err),
+        };
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownDatabase(id.to_string()).into())
+        }
+    }
+
+    fn rename_schema(
+        &self,
+        database_id: &DatabaseId,
+        schema_id: &SchemaId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let (db_id, id) = (database_id.0, schema_id.0);
+        let (name, updated_at) = (to_name.to_owned(), updated_at as i64);
+        let result = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE schemas SET name = $1, updated_at = $2 WHERE database_id = $3 AND id = $4",
+                &[&name, &updated_at, &db_id, &id],
+            )?)
+        });
+        let n = match result {
+            Ok(n) => n,
+            Err(err) if is_unique_violation(&err) => {
+                return Err(Error::new(ErrorKind::SchemaAlreadyExists(
+                    to_name.to_owned(),
+                )))
+            }
+            Err(err) => return Err(err),
+        };
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownSchema(format!("{}.{}", database_id.0, schema_id.0)).into())
+        }
+    }
+
+    fn update_owner(
+        &self,
+        object: OwnedObjectId,
+        owner_id: i64,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let updated_at = updated_at as i64;
+        let n = self.backend.run(move |client| {
+            let n = match object {
+                OwnedObjectId::Database(id) => client.execute(
+                    "UPDATE databases SET owner_id = $1, updated_at = $2 WHERE id = $3",
+                    &[&owner_id, &updated_at, &id.0],
+                )?,
+                OwnedObjectId::Schema(id) => client.execute(
+                    "UPDATE schemas SET owner_id = $1, updated_at = $2 WHERE id = $3",
+                    &[&owner_id, &updated_at, &id.0],
+                )?,
+                OwnedObjectId::ComputeInstance(id) => client.execute(
+                    "UPDATE compute_instances SET owner_id = $1, updated_at = $2 WHERE id = $3",
+                    &[&owner_id, &updated_at, &id],
+                )?,
+                OwnedObjectId::Item(id) => client.execute(
+                    "UPDATE items SET owner_id = $1, updated_at = $2 WHERE gid = $3",
+                    &[&owner_id, &updated_at, &to_json(&id)],
+                )?,
+            };
+            Ok(n)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(object.unknown())
+        }
+    }
+
+    fn insert_audit_event(&mut self, event: &mut AuditEvent) -> Result<(), Error> {
+        let event_type = event.event_type.as_str();
+        let object_type = event.object_type.clone();
+        let object_id = event.object_id.clone();
+        let name = event.name.clone();
+        let user = event.user.clone();
+        let occurred_at = event.occurred_at as i64;
+        let details = to_json(&event.details);
+        let id = self.backend.run(move |client| {
+            let row = client.query_one(
+                "INSERT INTO audit_events
+                    (event_type, object_type, object_id, name, \"user\", occurred_at, details)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id",
+                &[
+                    &event_type,
+                    &object_type,
+                    &object_id,
+                    &name,
+                    &user,
+                    &occurred_at,
+                    &details,
+                ],
+            )?;
+            Ok(row.get::<_, i64>(0))
+        })?;
+        event.id = id as u64;
+        Ok(())
+    }
+
+    fn insert_secret_audit_events(&mut self, events: &mut [SecretAuditEvent]) -> Result<(), Error> {
+        let rows: Vec<_> = events
+            .iter()
+            .map(|event| {
+                (
+                    to_json(&event.secret_id),
+                    event.operation.as_str(),
+                    event.version.map(|v| v.0 as i64),
+                    event.user.clone(),
+                    event.occurred_at as i64,
+                    event.error.clone(),
+                )
+            })
+            .collect();
+        let ids = self.backend.run(move |client| {
+            let mut ids = vec![];
+            for (secret_id, operation, version, user, occurred_at, error) in rows {
+                let row = client.query_one(
+                    "INSERT INTO secret_audit_events
+                        (secret_id, operation, version, \"user\", occurred_at, error)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    RETURNING id",
+                    &[
+                        &secret_id,
+                        &operation,
+                        &version,
+                        &user,
+                        &occurred_at,
+                        &error,
+                    ],
+                )?;
+                ids.push(row.get::<_, i64>(0));
+            }
+            Ok(ids)
+        })?;
+        for (event, id) in events.iter_mut().zip(ids) {
+            event.id = id as u64;
+        }
+        Ok(())
+    }
+
+    fn purge_secret_audit_events(&self, retained: u64) -> Result<Vec<SecretAuditEvent>, Error> {
+        let retained = retained as i64;
+        self.backend.run(move |client| {
+            // Events are forgotten in order of their IDs, which increase with
+            // each recorded event.
+            client
+                .query(
+                    "DELETE FROM secret_audit_events
+                    WHERE id <= (
+                        SELECT id FROM secret_audit_events
+                        ORDER BY id DESC
+                        LIMIT 1 OFFSET $1
+                    )
+                    RETURNING id, secret_id, operation, version, \"user\", occurred_at, error",
+                    &[&retained],
+                )?
+                .iter()
+                .map(secret_audit_event_from_row)
+                .collect()
+        })
+    }
+
+    fn insert_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        let object_id = to_json(&id);
+        let column_position = comment_column_position(column_position);
+        let comment = comment.to_owned();
+        self.backend.run(move |client| {
+            client.execute(
+                "INSERT INTO comments (object_id, column_position, comment) VALUES ($1, $2, $3)",
+                &[&object_id, &column_position, &comment],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn update_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        let object_id = to_json(&id);
+        let position = comment_column_position(column_position);
+        let comment = comment.to_owned();
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "UPDATE comments SET comment = $1 WHERE object_id = $2 AND column_position = $3",
+                &[&comment, &object_id, &position],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(unknown_comment(id, column_position))
+        }
+    }
+
+    fn delete_comment(&self, id: GlobalId, column_position: Option<usize>) -> Result<(), Error> {
+        let object_id = to_json(&id);
+        let position = comment_column_position(column_position);
+        let n = self.backend.run(move |client| {
+            Ok(client.execute(
+                "DELETE FROM comments WHERE object_id = $1 AND column_position = $2",
+                &[&object_id, &position],
+            )?)
+        })?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(unknown_comment(id, column_position))
+        }
+    }
+
+    fn set_role_setting(
+        &self,
+        role_id: Option<i64>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), Error> {
+        let role_id = stored_role_id(role_id);
+        let name = name.to_owned();
+        let value = value.map(|v| v.to_owned());
+        self.backend.run(move |client| {
+            match value {
+                Some(value) => client.execute(
+                    "INSERT INTO role_settings (role_id, name, value) VALUES ($1, $2, $3)
+                    ON CONFLICT (role_id, name) DO UPDATE SET value = excluded.value",
+                    &[&role_id, &name, &value],
+                )?,
+                None => client.execute(
+                    "DELETE FROM role_settings WHERE role_id = $1 AND name = $2",
+                    &[&role_id, &name],
+                )?,
+            };
+            Ok(())
+        })
+    }
+
+    fn grant_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        let (object_type, object_id) = object.stored_id();
+        let role_id = stored_role_id(role_id);
+        let privilege = privilege.to_string();
+        self.backend.run(move |client| {
+            client.execute(
+                "INSERT INTO privileges (object_type, object_id, role_id, privilege)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING",
+                &[&object_type, &object_id, &role_id, &privilege],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn revoke_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        let (object_type, object_id) = object.stored_id();
+        let role_id = stored_role_id(role_id);
+        let privilege = privilege.to_string();
+        self.backend.run(move |client| {
+            client.execute(
+                "DELETE FROM privileges
+                WHERE object_type = $1 AND object_id = $2 AND role_id = $3 AND privilege = $4",
+                &[&object_type, &object_id, &role_id, &privilege],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn commit(mut self: Box<Self>) -> Result<(), Error> {
+        self.committed = true;
+        self.backend.run_on(|conn| conn.end("COMMIT"))
+    }
+}
+
+impl Drop for PostgresTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            // If rolling back fails, the connection is unusable anyway.
+            let _ = self.backend.run_on(|conn| conn.end("ROLLBACK"));
+        }
+    }
+}
+
+/// Decodes a row of `secret_audit_events` whose columns are selected in the
+/// order in which they are declared.
+fn secret_audit_event_from_row(row: &postgres::Row) -> Result<SecretAuditEvent, Error> {
+    let id: i64 = row.get(0);
+    let secret_id: String = row.get(1);
+    let operation: String = row.get(2);
+    let operation = match operation.as_str() {
+        "ensure" => SecretAuditOperation::Ensure,
+        "delete" => SecretAuditOperation::Delete,
+        _ => {
+            return Err(Error::new(ErrorKind::Corruption {
+                detail: format!("unknown secret audit operation {}", operation),
+            }))
+        }
+    };
+    let version: Option<i64> = row.get(3);
+    let occurred_at: i64 = row.get(5);
+    Ok(SecretAuditEvent {
+        id: id as u64,
+        secret_id: from_json(&secret_id)?,
+        operation,
+        version: version.map(|v| SecretVersion(v as u64)),
+        user: row.get(4),
+        occurred_at: occurred_at as EpochMillis,
+        error: row.get(6),
+    })
+}
+
+/// Reports whether `err` was caused by a violation of a uniqueness constraint.
+fn is_unique_violation(err: &Error) -> bool {
+    match &err.kind {
+        ErrorKind::PostgresStorage(err) => err.code() == Some(&SqlState::UNIQUE_VIOLATION),
+        _ => false,
+    }
+}
+
+/// Serializes restrictions for the `restrictions` column of the `roles` table,
+/// in which the default restrictions are stored as NULL.
+fn serialize_restrictions(restrictions: &RoleRestrictions) -> Option<String> {
+    if *restrictions == RoleRestrictions::default() {
+        None
+    } else {
+        Some(to_json(restrictions))
+    }
+}
+
+/// Reads the creation and modification times stored in the columns of `row`
+/// at `idx` and `idx + 1`.
+fn get_timestamps(row: &postgres::Row, idx: usize) -> ObjectTimestamps {
+    let created_at: i64 = row.get(idx);
+    let updated_at: i64 = row.get(idx + 1);
+    ObjectTimestamps {
+        created_at: created_at as EpochMillis,
+        updated_at: updated_at as EpochMillis,
+    }
+}
+
+/// Returns the ID of a system object, as stored in the database.
+///
+/// Panics if `id` is not a system ID.
+fn system_id(id: GlobalId) -> i64 {
+    match id {
+        GlobalId::System(id) => id as i64,
+        _ => panic!("non-system id provided"),
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("catalog serialization cannot fail")
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, Error> {
+    serde_json::from_str(value).map_err(|e| {
+        Error::new(ErrorKind::Corruption {
+            detail: e.to_string(),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use postgres::NoTls;
+
+    use super::*;
+
+    /// Returns the connection string of the PostgreSQL server in which the
+    /// tests create their databases, which must not name a database.
+    fn server_url() -> String {
+        env::var("POSTGRES_URL").unwrap_or_else(|_| "host=localhost user=postgres".into())
+    }
+
+    /// Creates an empty database named `name`, replacing any database of that
+    /// name, and returns its connection string.
+    fn create_database(name: &str) -> Result<String, anyhow::Error> {
+        let mut client = Client::connect(&server_url(), NoTls)?;
+        client.batch_execute(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", name))?;
+        client.batch_execute(&format!("CREATE DATABASE {}", name))?;
+        let url = server_url();
+        if url.contains("://") {
+            Ok(format!("{}/{}", url.trim_end_matches('/'), name))
+        } else {
+            Ok(format!("{} dbname={}", url, name))
+        }
+    }
+
+    /// Terminates every connection to the database `name`.
+    fn terminate_connections(name: &str) -> Result<(), anyhow::Error> {
+        let mut client = Client::connect(&server_url(), NoTls)?;
+        client.execute(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+            WHERE datname = $1 AND pid <> pg_backend_pid()",
+            &[&name],
+        )?;
+        Ok(())
+    }
+
+    fn is_fenced<T>(res: Result<T, Error>) -> bool {
+        matches!(
+            res,
+            Err(Error {
+                kind: ErrorKind::Fenced { .. }
+            })
+        )
+    }
+
+    #[test]
+    fn test_postgres_backend_reopen() -> Result<(), anyhow::Error> {
+        let url = create_database("catalog_reopen")?;
+
+        let mut backend = PostgresBackend::open(&url, CatalogDurability::None)?;
+        backend.set_setting("test", "\"a\"")?;
+        let id = backend.allocate_global_id(GidNamespace::User, 2)?;
+        let mut tx = backend.transaction()?;
+        let database_id = tx.insert_database("committed", 1, 0)?;
+        tx.commit()?;
+        let mut tx = backend.transaction()?;
+        tx.insert_database("rolled_back", 1, 0)?;
+        drop(tx);
+        drop(backend);
+
+        let mut backend = PostgresBackend::open(&url, CatalogDurability::None)?;
+        assert_eq!(backend.setting("test")?.as_deref(), Some("\"a\""));
+        assert_eq!(backend.allocate_global_id(GidNamespace::User, 1)?, id + 2);
+        let databases: Vec<_> = backend
+            .load_databases()?
+            .into_iter()
+            .map(|(id, name, _, _)| (id, name))
+            .filter(|(_, name)| name != "materialize")
+            .collect();
+        assert_eq!(databases, vec![(database_id, "committed".into())]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_postgres_backend_fencing() -> Result<(), anyhow::Error> {
+        let url = create_database("catalog_fencing")?;
+
+        let mut first = PostgresBackend::open(&url, CatalogDurability::None)?;
+        first.set_setting("test", "\"first\"")?;
+        let mut second = PostgresBackend::open(&url, CatalogDurability::None)?;
+
+        // Every kind of write by the first process is refused, but reads are
+        // not.
+        assert!(is_fenced(first.set_setting("test", "\"fenced\"")));
+        assert!(is_fenced(first.remove_setting("test")));
+        assert!(is_fenced(first.allocate_global_id(GidNamespace::User, 1)));
+        assert!(is_fenced(first.transaction().map(|_| ())));
+        assert_eq!(first.setting("test")?.as_deref(), Some("\"first\""));
+
+        second.set_setting("test", "\"second\"")?;
+        let mut tx = second.transaction()?;
+        tx.insert_database("second", 1, 0)?;
+        tx.commit()?;
+        assert_eq!(first.setting("test")?.as_deref(), Some("\"second\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_postgres_backend_reconnect() -> Result<(), anyhow::Error> {
+        let url = create_database("catalog_reconnect")?;
+        let mut backend = PostgresBackend::open(&url, CatalogDurability::None)?;
+
+        // A connection that is lost between transactions is reestablished.
+        terminate_connections("catalog_reconnect")?;
+        let mut tx = backend.transaction()?;
+        tx.insert_database("before", 1, 0)?;
+        tx.commit()?;
+        backend.set_setting("test", "\"a\"")?;
+        assert_eq!(backend.setting("test")?.as_deref(), Some("\"a\""));
+
+        // A connection that is lost during a transaction fails the
+        // transaction, but not the transactions that follow it.
+        let mut tx = backend.transaction()?;
+        tx.insert_database("lost", 1, 0)?;
+        terminate_connections("catalog_reconnect")?;
+        assert!(tx.commit().is_err());
+        let mut tx = backend.transaction()?;
+        tx.insert_database("after", 1, 0)?;
+        tx.commit()?;
+
+        let mut names: Vec<_> = backend
+            .load_databases()?
+            .into_iter()
+            .map(|(_, name, _, _)| name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["after", "before", "materialize"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,2602 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Catalog storage in a SQLite database in the data directory.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::bail;
+use rusqlite::backup::Backup;
+use rusqlite::params;
+use rusqlite::types::{FromSql, FromSqlError, ToSql, ToSqlOutput, Value, ValueRef};
+use rusqlite::{ErrorCode, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use timely::progress::Antichain;
+use tracing::{info, warn};
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::sources::MzOffset;
+use mz_expr::{GlobalId, PartitionId};
+use mz_ore::cast::CastFrom;
+use mz_ore::now::{EpochMillis, SYSTEM_TIME};
+use mz_secrets::SecretVersion;
+use mz_sql::ast::Privilege;
+use mz_sql::catalog::CatalogError as SqlCatalogError;
+use mz_sql::names::{
+    DatabaseId, ObjectQualifiers, QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaId,
+    SchemaSpecifier,
+};
+use mz_sql::plan::{ComputeInstanceConfig, ComputeInstanceReplicaConfig, RoleRestrictions};
+use mz_stash::Stash;
+
+use crate::catalog::builtin::BuiltinLog;
+use crate::catalog::error::{Error, ErrorKind};
+use crate::catalog::storage::{
+    advance_gid, comment_column_position, migration_checksum, parse_comment_column_position,
+    parse_privilege, parse_stored_role_id, stored_role_id, unknown_comment, Backend,
+    BackendTransaction, GidNamespace, ItemDependencies, ItemUpdate, NewItem, OwnedObjectId,
+    RemovedItem, StoredComment, StoredComputeReplica, StoredDroppedItem, StoredItem,
+    StoredPrivilege, StoredReferences, StoredRole, StoredRoleSetting, APPLICATION_ID,
+};
+use crate::catalog::{
+    AuditEvent, AuditEventType, ObjectTimestamps, SecretAuditEvent, SecretAuditOperation,
+};
+
+/// A catalog migration
+trait Migration {
+    /// Applies a catalog migration given the top level data directory and an active transaction to
+    /// the catalog's SQLite database.
+    fn apply(&self, path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error>;
+
+    /// Returns the checksum of the migration's text, if it has any. Migrations
+    /// written in Rust have no text, so edits to them cannot be detected.
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<'a> Migration for &'a str {
+    fn apply(&self, _path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error> {
+        tx.execute_batch(self)?;
+        Ok(())
+    }
+
+    fn checksum(&self) -> Option<String> {
+        Some(migration_checksum(self))
+    }
+}
+
+impl<F: Fn(&Path, &rusqlite::Transaction) -> Result<(), Error>> Migration for F {
+    fn apply(&self, path: &Path, tx: &rusqlite::Transaction) -> Result<(), Error> {
+        (self)(path, tx)
+    }
+}
+
+/// Schema migrations for the on-disk state.
+const MIGRATIONS: &[&dyn Migration] = &[
+    // Creates initial schema.
+    //
+    // Introduced for v0.1.0.
+    &"CREATE TABLE gid_alloc (
+         next_gid integer NOT NULL
+     );
+
+     CREATE TABLE databases (
+         id   integer PRIMARY KEY,
+         name text NOT NULL UNIQUE
+     );
+
+     CREATE TABLE schemas (
+         id          integer PRIMARY KEY,
+         database_id integer REFERENCES databases,
+         name        text NOT NULL,
+         UNIQUE (database_id, name)
+     );
+
+     CREATE TABLE items (
+         gid        blob PRIMARY KEY,
+         schema_id  integer REFERENCES schemas,
+         name       text NOT NULL,
+         definition blob NOT NULL,
+         UNIQUE (schema_id, name)
+     );
+
+     CREATE TABLE timestamps (
+         sid blob NOT NULL,
+         vid blob NOT NULL,
+         timestamp integer NOT NULL,
+         offset blob NOT NULL,
+         PRIMARY KEY (sid, vid, timestamp)
+     );
+
+     INSERT INTO gid_alloc VALUES (1);
+     INSERT INTO databases VALUES (1, 'materialize');
+     INSERT INTO schemas VALUES
+         (1, NULL, 'mz_catalog'),
+         (2, NULL, 'pg_catalog'),
+         (3, 1, 'public');",
+    // Adjusts timestamp table to support multi-partition Kafka topics.
+    //
+    // Introduced for v0.1.4.
+    //
+    // ATTENTION: this migration blows away data and must not be used as a model
+    // for future migrations! It is only acceptable now because we have not yet
+    // made any consistency promises to users.
+    &"DROP TABLE timestamps;
+     CREATE TABLE timestamps (
+        sid blob NOT NULL,
+        vid blob NOT NULL,
+        pcount blob NOT NULL,
+        pid blob NOT NULL,
+        timestamp integer NOT NULL,
+        offset blob NOT NULL,
+        PRIMARY KEY (sid, vid, pid, timestamp)
+    );",
+    // Introduces settings table to support persistent node settings.
+    //
+    // Introduced in v0.4.0.
+    &"CREATE TABLE settings (
+        name TEXT PRIMARY KEY,
+        value TEXT
+    );",
+    // Creates the roles table and a default "materialize" user.
+    //
+    // Introduced in v0.7.0.
+    &"CREATE TABLE roles (
+        id   integer PRIMARY KEY,
+        name text NOT NULL UNIQUE
+    );
+    INSERT INTO roles VALUES (1, 'materialize');",
+    // Makes the mz_internal schema literal so it can store functions.
+    //
+    // Introduced in v0.7.0.
+    &"INSERT INTO schemas (database_id, name) VALUES
+        (NULL, 'mz_internal');",
+    // Adjusts timestamp table to support replayable source timestamp bindings.
+    //
+    // Introduced for v0.7.4
+    //
+    // ATTENTION: this migration blows away data and must not be used as a model
+    // for future migrations! It is only acceptable now because we have not yet
+    // made any consistency promises to users.
+    &"DROP TABLE timestamps;
+     CREATE TABLE timestamps (
+        sid blob NOT NULL,
+        pid blob NOT NULL,
+        timestamp integer NOT NULL,
+        offset blob NOT NULL,
+        PRIMARY KEY (sid, pid, timestamp, offset)
+    );",
+    // Makes the information_schema schema literal so it can store functions.
+    //
+    // Introduced in v0.9.12.
+    &"INSERT INTO schemas (database_id, name) VALUES
+        (NULL, 'information_schema');",
+    // Adds index to timestamp table to more efficiently compact timestamps.
+    //
+    // Introduced in v0.12.0.
+    &"CREATE INDEX timestamps_sid_timestamp ON timestamps (sid, timestamp)",
+    // Adds table to track users' compute instances.
+    //
+    // Introduced in v0.22.0.
+    &"CREATE TABLE compute_instances (
+        id   integer PRIMARY KEY,
+        name text NOT NULL UNIQUE
+    );
+    INSERT INTO compute_instances VALUES (1, 'default');",
+    // Introduced in v0.24.0.
+    &"ALTER TABLE compute_instances ADD COLUMN config text",
+    // Migrates timestamp bindings from the coordinator's catalog to STORAGE's internal state
+    // Introduced in v0.26.0.
+    &|data_dir_path: &Path, tx: &rusqlite::Transaction| {
+        let source_ids = tx
+            .prepare("SELECT DISTINCT sid FROM timestamps")?
+            .query_and_then([], |row| Ok(row.get::<_, SqlVal<GlobalId>>(0)?.0))?
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let stash = mz_stash::Sqlite::open(&data_dir_path.join("storage"))
+            .expect("unable to open STORAGE stash");
+
+        let mut statement = tx.prepare(
+            "SELECT pid, timestamp, offset FROM timestamps WHERE sid = ? ORDER BY pid, timestamp",
+        )?;
+        for source_id in source_ids {
+            let bindings = statement
+                .query_and_then(params![SqlVal(&source_id)], |row| {
+                    let partition: PartitionId = row
+                        .get::<_, String>(0)
+                        .unwrap()
+                        .parse()
+                        .expect("parsing partition id from string cannot fail");
+                    let timestamp: i64 = row.get(1)?;
+                    let offset = MzOffset {
+                        offset: row.get(2)?,
+                    };
+
+                    Ok((partition, timestamp, offset))
+                })?
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let ts_binding_stash = stash
+                .collection::<PartitionId, ()>(&format!("timestamp-bindings-{source_id}"))
+                .expect("failed to read timestamp bindings");
+
+            // See
+            // [mz_dataflow_types::client::controller::StorageControllerMut::persist_timestamp_bindings]
+            // for an explanation of the logic
+            let mut last_reported_ts_bindings: HashMap<_, MzOffset> = HashMap::new();
+            let seal_ts = bindings.iter().map(|(_, ts, _)| *ts).max();
+            stash
+                .update_many(
+                    ts_binding_stash,
+                    bindings.into_iter().map(|(pid, ts, offset)| {
+                        let prev_offset = last_reported_ts_bindings.entry(pid.clone()).or_default();
+                        let update = ((pid, ()), ts, offset.offset - prev_offset.offset);
+                        prev_offset.offset = offset.offset;
+                        update
+                    }),
+                )
+                .expect("failed to write timestamp bindings");
+
+            stash
+                .seal(ts_binding_stash, Antichain::from_iter(seal_ts).borrow())
+                .expect("failed to write timestamp bindings");
+        }
+
+        tx.execute_batch("DROP TABLE timestamps;")?;
+
+        Ok(())
+    },
+    // Allows us to dynamically assign system IDs to all objects but funcs. Also allows us to
+    // track built-in object name to ID mapping.
+    //
+    // Introduced in v0.26.0
+    &"ALTER TABLE gid_alloc RENAME TO user_gid_alloc;
+
+    CREATE TABLE system_gid_alloc (
+        next_gid integer NOT NULL
+    );
+
+    -- Higher than all previous statically assigned IDs
+    INSERT INTO system_gid_alloc VALUES (5044);
+
+    CREATE TABLE system_gid_mapping (
+        schema_name text NOT NULL,
+        object_name text NOT NULL,
+        id integer NOT NULL,
+        fingerprint integer NOT NULL,
+        PRIMARY KEY (schema_name, object_name)
+    );
+
+    -- We need to insert previous static IDs in the mapping so user can successfully upgrade
+    INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint) VALUES
+        -- Types
+        ('pg_catalog', 'bool', 1000, 0),
+        ('pg_catalog', 'bytea', 1001, 0),
+        ('pg_catalog', 'int8', 1002, 0),
+        ('pg_catalog', 'int4', 1003, 0),
+        ('pg_catalog', 'text', 1004, 0),
+        ('pg_catalog', 'oid', 1005, 0),
+        ('pg_catalog', 'float4', 1006, 0),
+        ('pg_catalog', 'float8', 1007, 0),
+        ('pg_catalog', '_bool', 1008, 0),
+        ('pg_catalog', '_bytea', 1009, 0),
+        ('pg_catalog', '_int4', 1010, 0),
+        ('pg_catalog', '_text', 1011, 0),
+        ('pg_catalog', '_int8', 1012, 0),
+        ('pg_catalog', '_float4', 1013, 0),
+        ('pg_catalog', '_float8', 1014, 0),
+        ('pg_catalog', '_oid', 1015, 0),
+        ('pg_catalog', 'date', 1016, 0),
+        ('pg_catalog', 'time', 1017, 0),
+        ('pg_catalog', 'timestamp', 1018, 0),
+        ('pg_catalog', '_timestamp', 1019, 0),
+        ('pg_catalog', '_date', 1020, 0),
+        ('pg_catalog', '_time', 1021, 0),
+        ('pg_catalog', 'timestamptz', 1022, 0),
+        ('pg_catalog', '_timestamptz', 1023, 0),
+        ('pg_catalog', 'interval', 1024, 0),
+        ('pg_catalog', '_interval', 1025, 0),
+        ('pg_catalog', 'numeric', 1026, 0),
+        ('pg_catalog', '_numeric', 1027, 0),
+        ('pg_catalog', 'record', 1028, 0),
+        ('pg_catalog', '_record', 1029, 0),
+        ('pg_catalog', 'uuid', 1030, 0),
+        ('pg_catalog', '_uuid', 1031, 0),
+        ('pg_catalog', 'jsonb', 1032, 0),
+        ('pg_catalog', '_jsonb', 1033, 0),
+        ('pg_catalog', 'any', 1034, 0),
+        ('pg_catalog', 'anyarray', 1035, 0),
+        ('pg_catalog', 'anyelement', 1036, 0),
+        ('pg_catalog', 'anynonarray', 1037, 0),
+        ('pg_catalog', 'char', 1038, 0),
+        ('pg_catalog', 'varchar', 1039, 0),
+        ('pg_catalog', 'int2', 1040, 0),
+        ('pg_catalog', '_int2', 1041, 0),
+        ('pg_catalog', 'bpchar', 1042, 0),
+        ('pg_catalog', '_char', 1043, 0),
+        ('pg_catalog', '_varchar', 1044, 0),
+        ('pg_catalog', '_bpchar', 1045, 0),
+        ('pg_catalog', 'regproc', 1046, 0),
+        ('pg_catalog', '_regproc', 1047, 0),
+        ('pg_catalog', 'regtype', 1048, 0),
+        ('pg_catalog', '_regtype', 1049, 0),
+        ('pg_catalog', 'regclass', 1050, 0),
+        ('pg_catalog', '_regclass', 1051, 0),
+        ('pg_catalog', 'int2vector', 1052, 0),
+        ('pg_catalog', '_int2vector', 1053, 0),
+        ('pg_catalog', 'anycompatible', 1054, 0),
+        ('pg_catalog', 'anycompatiblearray', 1055, 0),
+        ('pg_catalog', 'anycompatiblenonarray', 1056, 0),
+        ('pg_catalog', 'list', 1998, 0),
+        ('pg_catalog', 'map', 1999, 0),
+        ('pg_catalog', 'anycompatiblelist', 1997, 0),
+        ('pg_catalog', 'anycompatiblemap', 1996, 0),
+        -- Logs
+        ('mz_catalog', 'mz_dataflow_operators', 3000, 0),
+        ('mz_catalog', 'mz_dataflow_operator_addresses', 3002, 0),
+        ('mz_catalog', 'mz_dataflow_channels', 3004, 0),
+        ('mz_catalog', 'mz_scheduling_elapsed_internal', 3006, 0),
+        ('mz_catalog', 'mz_scheduling_histogram_internal', 3008, 0),
+        ('mz_catalog', 'mz_scheduling_parks_internal', 3010, 0),
+        ('mz_catalog', 'mz_arrangement_batches_internal', 3012, 0),
+        ('mz_catalog', 'mz_arrangement_sharing_internal', 3014, 0),
+        ('mz_catalog', 'mz_materializations', 3016, 0),
+        ('mz_catalog', 'mz_materialization_dependencies', 3018, 0),
+        ('mz_catalog', 'mz_worker_materialization_frontiers', 3020, 0),
+        ('mz_catalog', 'mz_peek_active', 3022, 0),
+        ('mz_catalog', 'mz_peek_durations', 3024, 0),
+        ('mz_catalog', 'mz_source_info', 3026, 0),
+        ('mz_catalog', 'mz_message_counts_received_internal', 3028, 0),
+        ('mz_catalog', 'mz_message_counts_sent_internal', 3036, 0),
+        ('mz_catalog', 'mz_dataflow_operator_reachability_internal', 3034, 0),
+        ('mz_catalog', 'mz_arrangement_records_internal', 3038, 0),
+        ('mz_catalog', 'mz_kafka_source_statistics', 3040, 0),
+         -- Tables
+        ('mz_catalog', 'mz_view_keys', 4001, 0),
+        ('mz_catalog', 'mz_view_foreign_keys', 4003, 0),
+        ('mz_catalog', 'mz_kafka_sinks', 4005, 0),
+        ('mz_catalog', 'mz_avro_ocf_sinks', 4007, 0),
+        ('mz_catalog', 'mz_databases', 4009, 0),
+        ('mz_catalog', 'mz_schemas', 4011, 0),
+        ('mz_catalog', 'mz_columns', 4013, 0),
+        ('mz_catalog', 'mz_indexes', 4015, 0),
+        ('mz_catalog', 'mz_index_columns', 4017, 0),
+        ('mz_catalog', 'mz_tables', 4019, 0),
+        ('mz_catalog', 'mz_sources', 4021, 0),
+        ('mz_catalog', 'mz_sinks', 4023, 0),
+        ('mz_catalog', 'mz_views', 4025, 0),
+        ('mz_catalog', 'mz_types', 4027, 0),
+        ('mz_catalog', 'mz_array_types', 4029, 0),
+        ('mz_catalog', 'mz_base_types', 4031, 0),
+        ('mz_catalog', 'mz_list_types', 4033, 0),
+        ('mz_catalog', 'mz_map_types', 4035, 0),
+        ('mz_catalog', 'mz_roles', 4037, 0),
+        ('mz_catalog', 'mz_pseudo_types', 4039, 0),
+        ('mz_catalog', 'mz_functions', 4041, 0),
+        ('mz_catalog', 'mz_metrics', 4043, 0),
+        ('mz_catalog', 'mz_metrics_meta', 4045, 0),
+        ('mz_catalog', 'mz_metric_histograms', 4047, 0),
+        ('mz_catalog', 'mz_clusters', 4049, 0),
+        ('mz_catalog', 'mz_secrets', 4050, 0),
+        -- Views
+        ('mz_catalog', 'mz_relations', 5000, 0),
+        ('mz_catalog', 'mz_objects', 5001, 0),
+        ('mz_catalog', 'mz_catalog_names', 5002, 0),
+        ('mz_catalog', 'mz_dataflow_names', 5003, 0),
+        ('mz_catalog', 'mz_dataflow_operator_dataflows', 5004, 0),
+        ('mz_catalog', 'mz_materialization_frontiers', 5005, 0),
+        ('mz_catalog', 'mz_records_per_dataflow_operator', 5006, 0),
+        ('mz_catalog', 'mz_records_per_dataflow', 5007, 0),
+        ('mz_catalog', 'mz_records_per_dataflow_global', 5008, 0),
+        ('mz_catalog', 'mz_perf_arrangement_records', 5009, 0),
+        ('mz_catalog', 'mz_perf_peek_durations_core', 5010, 0),
+        ('mz_catalog', 'mz_perf_peek_durations_bucket', 5011, 0),
+        ('mz_catalog', 'mz_perf_peek_durations_aggregates', 5012, 0),
+        ('mz_catalog', 'mz_perf_dependency_frontiers', 5013, 0),
+        ('pg_catalog', 'pg_namespace', 5014, 0),
+        ('pg_catalog', 'pg_class', 5015, 0),
+        ('pg_catalog', 'pg_database', 5016, 0),
+        ('pg_catalog', 'pg_index', 5017, 0),
+        ('pg_catalog', 'pg_description', 5018, 0),
+        ('pg_catalog', 'pg_type', 5019, 0),
+        ('pg_catalog', 'pg_attribute', 5020, 0),
+        ('pg_catalog', 'pg_proc', 5021, 0),
+        ('pg_catalog', 'pg_range', 5022, 0),
+        ('pg_catalog', 'pg_enum', 5023, 0),
+        ('pg_catalog', 'pg_attrdef', 5025, 0),
+        ('pg_catalog', 'pg_settings', 5026, 0),
+        ('mz_catalog', 'mz_scheduling_elapsed', 5027, 0),
+        ('mz_catalog', 'mz_scheduling_histogram', 5028, 0),
+        ('mz_catalog', 'mz_scheduling_parks', 5029, 0),
+        ('mz_catalog', 'mz_message_counts', 5030, 0),
+        ('mz_catalog', 'mz_dataflow_operator_reachability', 5031, 0),
+        ('mz_catalog', 'mz_arrangement_sizes', 5032, 0),
+        ('mz_catalog', 'mz_arrangement_sharing', 5033, 0),
+        ('pg_catalog', 'pg_constraint', 5034, 0),
+        ('pg_catalog', 'pg_tables', 5035, 0),
+        ('pg_catalog', 'pg_am', 5036, 0),
+        ('pg_catalog', 'pg_roles', 5037, 0),
+        ('pg_catalog', 'pg_views', 5038, 0),
+        ('information_schema', 'columns', 5039, 0),
+        ('information_schema', 'tables', 5040, 0),
+        ('pg_catalog', 'pg_collation', 5041, 0),
+        ('pg_catalog', 'pg_policy', 5042, 0),
+        ('pg_catalog', 'pg_inherits', 5043, 0);
+
+    CREATE TABLE compute_introspection_source_indexes (
+        compute_id integer NOT NULL,
+        name text NOT NULL,
+        index_id integer NOT NULL,
+        PRIMARY KEY (compute_id, name)
+    );
+    CREATE INDEX compute_introspection_source_indexes_ind
+        ON compute_introspection_source_indexes(compute_id);",
+    // Adds per-role connection restrictions.
+    //
+    // Introduced in v0.26.1.
+    //
+    // A NULL value means the role has no restrictions.
+    &"ALTER TABLE roles ADD COLUMN restrictions text",
+    // Records when each database, schema, role, compute instance, and item was
+    // created and last modified, in milliseconds since the Unix epoch.
+    //
+    // Introduced in v0.26.1.
+    //
+    // The true creation times of existing objects are unknown, so they are
+    // backfilled with the time of the migration.
+    &"ALTER TABLE databases ADD COLUMN created_at integer;
+    ALTER TABLE databases ADD COLUMN updated_at integer;
+    ALTER TABLE schemas ADD COLUMN created_at integer;
+    ALTER TABLE schemas ADD COLUMN updated_at integer;
+    ALTER TABLE roles ADD COLUMN created_at integer;
+    ALTER TABLE roles ADD COLUMN updated_at integer;
+    ALTER TABLE compute_instances ADD COLUMN created_at integer;
+    ALTER TABLE compute_instances ADD COLUMN updated_at integer;
+    ALTER TABLE items ADD COLUMN created_at integer;
+    ALTER TABLE items ADD COLUMN updated_at integer;
+    UPDATE databases SET
+        created_at = CAST(strftime('%s', 'now') AS integer) * 1000,
+        updated_at = CAST(strftime('%s', 'now') AS integer) * 1000;
+    UPDATE schemas SET
+        created_at = CAST(strftime('%s', 'now') AS integer) * 1000,
+        updated_at = CAST(strftime('%s', 'now') AS integer) * 1000;
+    UPDATE roles SET
+        created_at = CAST(strftime('%s', 'now') AS integer) * 1000,
+        updated_at = CAST(strftime('%s', 'now') AS integer) * 1000;
+    UPDATE compute_instances SET
+        created_at = CAST(strftime('%s', 'now') AS integer) * 1000,
+        updated_at = CAST(strftime('%s', 'now') AS integer) * 1000;
+    UPDATE items SET
+        created_at = CAST(strftime('%s', 'now') AS integer) * 1000,
+        updated_at = CAST(strftime('%s', 'now') AS integer) * 1000;",
+    // Records every operation on secrets storage, for auditing.
+    //
+    // Introduced in v0.26.1.
+    //
+    // The contents of secrets are never recorded.
+    &"CREATE TABLE secret_audit_events (
+        id integer PRIMARY KEY,
+        secret_id blob NOT NULL,
+        operation text NOT NULL,
+        version integer,
+        user text NOT NULL,
+        occurred_at integer NOT NULL
+    );",
+    // Records every change to databases, schemas, roles, clusters, and
+    // items, for auditing.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE audit_events (
+        id integer PRIMARY KEY,
+        event_type text NOT NULL,
+        object_type text NOT NULL,
+        object_id text NOT NULL,
+        name text NOT NULL,
+        user text NOT NULL,
+        occurred_at integer NOT NULL,
+        details text NOT NULL
+    );",
+    // Records the role that owns each database, schema, compute instance, and
+    // item.
+    //
+    // Introduced in v0.26.1.
+    //
+    // Ambient schemas belong to the builtin `mz_system` role, whose ID is -1.
+    // Every other existing object is assigned to the default `materialize`
+    // role, which is the role that created it unless other roles have been
+    // created, or to `mz_system` if the `materialize` role has been dropped.
+    &"ALTER TABLE databases ADD COLUMN owner_id integer NOT NULL DEFAULT -1;
+    ALTER TABLE schemas ADD COLUMN owner_id integer NOT NULL DEFAULT -1;
+    ALTER TABLE compute_instances ADD COLUMN owner_id integer NOT NULL DEFAULT -1;
+    ALTER TABLE items ADD COLUMN owner_id integer NOT NULL DEFAULT -1;
+    UPDATE databases SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);
+    UPDATE schemas SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1)
+        WHERE database_id IS NOT NULL;
+    UPDATE compute_instances SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);
+    UPDATE items SET
+        owner_id = coalesce((SELECT id FROM roles WHERE name = 'materialize'), -1);",
+    // Records the comments set by `COMMENT ON`.
+    //
+    // Introduced in v0.26.1.
+    //
+    // As with `objsubid` in PostgreSQL's `pg_description`, a column position
+    // of zero indicates a comment on the item itself.
+    &"CREATE TABLE comments (
+        object_id blob NOT NULL,
+        column_position integer NOT NULL,
+        comment text NOT NULL,
+        PRIMARY KEY (object_id, column_position)
+    );",
+    // Records the session defaults set by `ALTER ROLE ... SET`.
+    //
+    // Introduced in v0.26.1.
+    //
+    // A role ID of zero indicates a default for every role.
+    &"CREATE TABLE role_settings (
+        role_id integer NOT NULL,
+        name text NOT NULL,
+        value text NOT NULL,
+        PRIMARY KEY (role_id, name)
+    );",
+    // Records the definitions of dropped items, from which the items can be
+    // restored with `UNDROP` until they are purged.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE dropped_items (
+        gid blob PRIMARY KEY,
+        name text NOT NULL,
+        type text NOT NULL,
+        definition blob NOT NULL,
+        dropped_at integer NOT NULL
+    );
+    CREATE INDEX dropped_items_dropped_at ON dropped_items (dropped_at);",
+    // Records the replicas of each compute instance, separately from the
+    // compute instance's own configuration.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE compute_replicas (
+        id integer PRIMARY KEY,
+        compute_id integer NOT NULL,
+        name text NOT NULL,
+        config text NOT NULL,
+        created_at integer NOT NULL,
+        updated_at integer NOT NULL,
+        UNIQUE (compute_id, name)
+    );",
+    // Records the objects on which each item depends, so that the dependency
+    // graph can be read without planning every item. Existing items'
+    // dependencies are filled in when the catalog is next loaded.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE item_dependencies (
+        gid blob NOT NULL,
+        dependency blob NOT NULL,
+        PRIMARY KEY (gid, dependency)
+    );
+    CREATE INDEX item_dependencies_dependency ON item_dependencies (dependency);",
+    // Forgets the fingerprints of builtins, which were either placeholders or
+    // hashes that were not stable across versions. Builtins whose fingerprint
+    // is unknown keep their IDs, and their fingerprints are filled in when the
+    // catalog is next loaded.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE system_gid_mapping_new (
+        schema_name text NOT NULL,
+        object_name text NOT NULL,
+        id integer NOT NULL,
+        fingerprint integer,
+        PRIMARY KEY (schema_name, object_name)
+    );
+    INSERT INTO system_gid_mapping_new (schema_name, object_name, id)
+        SELECT schema_name, object_name, id FROM system_gid_mapping;
+    DROP TABLE system_gid_mapping;
+    ALTER TABLE system_gid_mapping_new RENAME TO system_gid_mapping;",
+    // Records the privileges that roles hold on objects that they do not own.
+    // The object is identified by its type, one of `database`, `schema`,
+    // `cluster`, or `item`, and its ID, and the privilege by its SQL keyword.
+    //
+    // A role ID of zero indicates a privilege granted to every role.
+    //
+    // Introduced in v0.26.1.
+    &"CREATE TABLE privileges (
+        object_type text NOT NULL,
+        object_id text NOT NULL,
+        role_id integer NOT NULL,
+        privilege text NOT NULL,
+        PRIMARY KEY (object_type, object_id, role_id, privilege)
+    );
+    CREATE INDEX privileges_role_id ON privileges (role_id);",
+    // Records the SCRAM-SHA-256 verifier of each role's password, or NULL if
+    // the role has no password.
+    //
+    // Introduced in v0.26.1.
+    &"ALTER TABLE roles ADD COLUMN password text",
+    // Records the error with which each operation on secrets storage failed,
+    // or NULL if it was applied. Only operations that were applied were
+    // recorded before.
+    //
+    // Introduced in v0.26.1.
+    &"ALTER TABLE secret_audit_events ADD COLUMN error text",
+    // Add new migrations here.
+    //
+    // Migrations should be preceded with a comment of the following form:
+    //
+    //     > Short summary of migration's purpose.
+    //     >
+    //     > Introduced in <VERSION>.
+    //     >
+    //     > Optional additional commentary about safety or approach.
+    //
+    // Please include @benesch on any code reviews that add or edit migrations.
+    // Migrations must preserve backwards compatibility with all past releases
+    // of materialized. Migrations can be edited up until they ship in a
+    // release, after which they must never be removed, only patched by future
+    // migrations.
+];
+
+/// How long to wait for a lock on the catalog held by another connection,
+/// like one copying the catalog, before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The name of the file in the data directory that records the backup from
+/// which the catalog was restored.
+const RESTORE_MARKER: &str = "catalog.restored";
+
+/// How thoroughly SQLite ensures that commits to the catalog are durable.
+///
+/// See <https://www.sqlite.org/pragma.html#pragma_synchronous>. As the catalog
+/// is written in WAL mode, no setting can corrupt the catalog if
+/// `materialized` crashes, but only [`SqliteSynchronous::Full`] guarantees
+/// that commits survive a crash of the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqliteSynchronous {
+    /// Never wait for writes to reach durable storage.
+    Off,
+    /// Wait for writes to reach durable storage only when checkpointing the
+    /// write-ahead log.
+    Normal,
+    /// Wait for writes to reach durable storage on every commit.
+    Full,
+}
+
+impl SqliteSynchronous {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SqliteSynchronous::Off => "off",
+            SqliteSynchronous::Normal => "normal",
+            SqliteSynchronous::Full => "full",
+        }
+    }
+}
+
+impl Default for SqliteSynchronous {
+    fn default() -> SqliteSynchronous {
+        SqliteSynchronous::Normal
+    }
+}
+
+impl fmt::Display for SqliteSynchronous {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SqliteSynchronous {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<SqliteSynchronous, anyhow::Error> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(SqliteSynchronous::Off),
+            "normal" => Ok(SqliteSynchronous::Normal),
+            "full" => Ok(SqliteSynchronous::Full),
+            _ => bail!("invalid synchronous setting {}", s),
+        }
+    }
+}
+
+/// Records that the migration with index `version` has been applied, along
+/// with the checksum of its current text.
+fn record_migration(
+    tx: &rusqlite::Transaction,
+    version: usize,
+    applied_at: Option<EpochMillis>,
+) -> Result<(), Error> {
+    tx.execute(
+        "INSERT INTO migrations (version, checksum, applied_at) VALUES (?, ?, ?)",
+        params![
+            i64::try_from(version).expect("migration index fits in i64"),
+            MIGRATIONS[version].checksum(),
+            applied_at.map(|t| t as i64),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Verifies that none of the migrations up to and including `version` have
+/// been modified since they were applied.
+///
+/// Migrations that were applied before checksums were recorded, and so have
+/// no row in the migrations table, are recorded with the checksum of their
+/// current text and no application time.
+fn verify_migrations(tx: &rusqlite::Transaction, version: usize) -> Result<(), Error> {
+    let recorded: BTreeMap<i64, Option<String>> = tx
+        .prepare("SELECT version, checksum FROM migrations")?
+        .query_and_then(params![], |row| -> Result<_, Error> {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<Result<_, _>>()?;
+    // A catalog from a later version of materialized can have applied
+    // migrations that this version does not know about.
+    for (i, migration) in MIGRATIONS.iter().enumerate().take(version + 1) {
+        let index = i64::try_from(i).expect("migration index fits in i64");
+        match recorded.get(&index) {
+            None => record_migration(tx, i, None)?,
+            Some(Some(checksum)) if Some(checksum) != migration.checksum().as_ref() => {
+                return Err(Error::new(ErrorKind::ModifiedMigration { version: i }));
+            }
+            Some(_) => (),
+        }
+    }
+    Ok(())
+}
+
+/// The header with which every unencrypted SQLite database begins.
+const PLAINTEXT_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Catalog storage in a SQLite database.
+pub struct SqliteBackend {
+    inner: rusqlite::Connection,
+    /// The key with which the database is encrypted, if any.
+    encryption_key: Option<String>,
+}
+
+impl fmt::Debug for SqliteBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SqliteBackend")
+            .field("inner", &self.inner)
+            .field("encrypted", &self.encryption_key.is_some())
+            .finish()
+    }
+}
+
+impl SqliteBackend {
+    /// Opens the catalog in the data directory at `data_dir_path`, creating
+    /// it if it does not exist, and applies any unapplied migrations.
+    ///
+    /// Before migrating an existing catalog, a snapshot of it is written to
+    /// `catalog.v<VERSION>.bak` in the data directory, from which it can be
+    /// restored if the migration fails partway through.
+    ///
+    /// The catalog is written in WAL mode, with the durability of its commits
+    /// determined by `synchronous`, and with its foreign keys enforced.
+    ///
+    /// If `encryption_key` is specified, the catalog is encrypted with it using
+    /// SQLCipher. An existing unencrypted catalog, and any unencrypted
+    /// snapshots of it, are encrypted before it is opened.
+    pub fn open(
+        data_dir_path: &Path,
+        synchronous: SqliteSynchronous,
+        encryption_key: Option<&str>,
+    ) -> Result<SqliteBackend, Error> {
+        let path = data_dir_path.join("catalog");
+        if let Some(key) = encryption_key {
+            if is_plaintext(&path)? {
+                encrypt_database(&path, key)?;
+                info!("encrypted catalog {}", path.display());
+            }
+            // The snapshots taken before migrations are full copies of the
+            // catalog. They are encrypted even if the catalog already is, in
+            // case a previous attempt was interrupted partway through.
+            for snapshot_path in snapshot_paths(data_dir_path)? {
+                if is_plaintext(&snapshot_path)? {
+                    encrypt_database(&snapshot_path, key)?;
+                    info!("encrypted catalog snapshot {}", snapshot_path.display());
+                }
+            }
+        }
+        let mut sqlite = rusqlite::Connection::open(&path)?;
+        set_key(&sqlite, encryption_key)?;
+        sqlite.busy_timeout(BUSY_TIMEOUT)?;
+        // In WAL mode, a commit appends to the write-ahead log rather than
+        // syncing both a rollback journal and the database itself. The mode is
+        // recorded in the catalog, so it need only be set once, but setting it
+        // again is harmless.
+        //
+        // This is the first statement to read the catalog, so it is the one
+        // that fails if the catalog cannot be decrypted.
+        let journal_mode: String = sqlite
+            .query_row("PRAGMA journal_mode = WAL", params![], |row| row.get(0))
+            .map_err(|e| read_error(e, encryption_key))?;
+        if journal_mode != "wal" {
+            return Err(Error::new(ErrorKind::InvalidStorageConfig(format!(
+                "unable to enable WAL mode; journal mode is {}",
+                journal_mode
+            ))));
+        }
+        sqlite.execute_batch(&format!("PRAGMA synchronous = {}", synchronous.as_str()))?;
+
+        // Validate application ID.
+        let tx = sqlite.transaction()?;
+        let app_id: i32 = tx.query_row("PRAGMA application_id", params![], |row| row.get(0))?;
+        let fresh = app_id == 0;
+        if fresh {
+            // Fresh catalog, so install the correct ID. We also apply the
+            // zeroth migration for historical reasons: the default
+            // `user_version` of zero indicates that the zeroth migration has
+            // been applied.
+            tx.execute_batch(&format!("PRAGMA application_id = {}", APPLICATION_ID))?;
+            MIGRATIONS[0].apply(data_dir_path, &tx)?;
+        } else if app_id != APPLICATION_ID {
+            return Err(Error::new(ErrorKind::Corruption {
+                detail: "catalog file has incorrect application_id".into(),
+            }));
+        };
+        // The migrations table records when each migration was applied and
+        // the checksum of its text. It lives outside of the migrations, as it
+        // must exist before any of them are recorded.
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS migrations (
+                version integer PRIMARY KEY,
+                checksum text,
+                applied_at integer
+            )",
+        )?;
+        if fresh {
+            record_migration(&tx, 0, Some(SYSTEM_TIME()))?;
+        }
+        tx.commit()?;
+
+        // Run unapplied migrations. The `user_version` field stores the index
+        // of the last migration that was run.
+        let version: u32 = sqlite.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+        let tx = sqlite.transaction()?;
+        verify_migrations(&tx, usize::cast_from(version))?;
+        tx.commit()?;
+        if !fresh && usize::cast_from(version) < MIGRATIONS.len() - 1 {
+            // Each migration commits separately, so a failed migration can
+            // leave the catalog between versions. Snapshot it first so that
+            // the previous version of materialized can still be started.
+            let snapshot_path = data_dir_path.join(format!("catalog.v{}.bak", version));
+            copy_database(&sqlite, &snapshot_path, encryption_key)?;
+            info!(
+                "snapshotted catalog version {} to {} before migrating",
+                version,
+                snapshot_path.display()
+            );
+        }
+        for (i, migration) in MIGRATIONS
+            .iter()
+            .enumerate()
+            .skip(usize::cast_from(version) + 1)
+        {
+            let tx = sqlite.transaction()?;
+            migration.apply(data_dir_path, &tx)?;
+            tx.execute_batch(&format!("PRAGMA user_version = {}", i))?;
+            record_migration(&tx, i, Some(SYSTEM_TIME()))?;
+            // The final version is also recorded in the settings table, where
+            // it can be read the same way as in the PostgreSQL backend. The
+            // settings table does not exist before the third migration.
+            if i == MIGRATIONS.len() - 1 {
+                tx.execute(
+                    "INSERT INTO settings (name, value)
+                    VALUES ('schema_version', ?), ('last_migration_at', ?)
+                    ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+                    params![i.to_string(), SYSTEM_TIME().to_string()],
+                )?;
+            }
+            tx.commit()?;
+        }
+
+        // Foreign keys are only enforced once the migrations have run, as
+        // migrations may rebuild referenced tables. Earlier versions did not
+        // enforce foreign keys at all, so the catalog may already contain
+        // dangling references. These are harmless, as the referencing rows
+        // are never loaded, so they are reported rather than rejected.
+        let violations: i64 = sqlite.query_row(
+            "SELECT count(*) FROM pragma_foreign_key_check",
+            params![],
+            |row| row.get(0),
+        )?;
+        if violations > 0 {
+            warn!(
+                "catalog contains {} rows with dangling foreign key references",
+                violations
+            );
+        }
+        sqlite.execute_batch("PRAGMA foreign_keys = ON")?;
+
+        Ok(SqliteBackend {
+            inner: sqlite,
+            encryption_key: encryption_key.map(|key| key.to_owned()),
+        })
+    }
+
+    /// Opens the catalog in the data directory at `data_dir_path` for reading
+    /// only, without applying any migrations.
+    ///
+    /// The catalog may be open for writing by another process at the same
+    /// time, and so it is not opened in SQLite's immutable mode, which would
+    /// ignore that process's changes. As the catalog is not migrated, it must
+    /// already be at the version that this version of Materialize expects.
+    pub fn open_read_only(
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<SqliteBackend, Error> {
+        let path = data_dir_path.join("catalog");
+        // An unencrypted catalog is only encrypted once it is opened for
+        // writing.
+        let encryption_key = match is_plaintext(&path)? {
+            true => None,
+            false => encryption_key,
+        };
+        let sqlite = rusqlite::Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        set_key(&sqlite, encryption_key)?;
+        sqlite.busy_timeout(BUSY_TIMEOUT)?;
+
+        let app_id: i32 = sqlite
+            .query_row("PRAGMA application_id", params![], |row| row.get(0))
+            .map_err(|e| read_error(e, encryption_key))?;
+        if app_id != APPLICATION_ID {
+            return Err(Error::new(ErrorKind::Corruption {
+                detail: "catalog file has incorrect application_id".into(),
+            }));
+        }
+        let version: u32 = sqlite.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+        let latest = MIGRATIONS.len() - 1;
+        if usize::cast_from(version) != latest {
+            return Err(Error::new(ErrorKind::InvalidStorageConfig(format!(
+                "catalog has version {}, but this version of Materialize can only read \
+                 catalog version {} without migrating it",
+                version, latest
+            ))));
+        }
+
+        Ok(SqliteBackend {
+            inner: sqlite,
+            encryption_key: encryption_key.map(|key| key.to_owned()),
+        })
+    }
+
+    /// Replaces the catalog in the data directory at `data_dir_path` with the
+    /// backup at `backup_path`.
+    ///
+    /// A backup of an encrypted catalog is encrypted with the same key, which
+    /// must be specified as `encryption_key`. An unencrypted backup can only
+    /// replace an unencrypted catalog, but the restored catalog is encrypted
+    /// when it is next opened with an encryption key.
+    ///
+    /// A catalog is restored only once: restoring records the backup in a
+    /// marker file in the data directory, and later restores are refused
+    /// until the marker is removed, so that restarting a process that was
+    /// configured to restore a backup does not discard the changes made
+    /// since.
+    pub fn restore(
+        backup_path: &Path,
+        data_dir_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<(), Error> {
+        let invalid = |detail: String| {
+            Error::new(ErrorKind::InvalidBackup {
+                path: backup_path.display().to_string(),
+                detail,
+            })
+        };
+        let marker_path = data_dir_path.join(RESTORE_MARKER);
+        match fs::read_to_string(&marker_path) {
+            Ok(restored) => {
+                return Err(invalid(format!(
+                    "the catalog was already restored from {}; remove {} to restore it again",
+                    restored.trim(),
+                    marker_path.display()
+                )))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(invalid(format!("{}: {}", marker_path.display(), e))),
+        }
+        // Opening a nonexistent file read-only fails, rather than creating an
+        // empty database.
+        let backup = rusqlite::Connection::open_with_flags(
+            backup_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|e| invalid(e.to_string()))?;
+        let encryption_key = match is_plaintext(backup_path)? {
+            true => None,
+            false => encryption_key,
+        };
+        set_key(&backup, encryption_key)?;
+
+        let app_id: i32 = backup
+            .query_row("PRAGMA application_id", params![], |row| row.get(0))
+            .map_err(|e| invalid(e.to_string()))?;
+        if app_id != APPLICATION_ID {
+            return Err(invalid("file is not a Materialize catalog".into()));
+        }
+        let version: u32 = backup.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+        let latest = MIGRATIONS.len() - 1;
+        if usize::cast_from(version) > latest {
+            return Err(invalid(format!(
+                "backup has catalog version {}, but this version of Materialize \
+                 supports catalog versions up to {}",
+                version, latest
+            )));
+        }
+        let check: String = backup.query_row("PRAGMA quick_check", params![], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(invalid(format!("integrity check failed: {}", check)));
+        }
+
+        copy_database(&backup, &data_dir_path.join("catalog"), encryption_key)?;
+        fs::write(&marker_path, backup_path.display().to_string())
+            .map_err(|e| invalid(format!("{}: {}", marker_path.display(), e)))?;
+        Ok(())
+    }
+
+    /// Copies the catalog in the data directory at `data_dir_path`, along with
+    /// the STORAGE stash that its migrations may write to, into the directory
+    /// at `to_path`.
+    ///
+    /// Files that do not exist in the data directory are not copied. An
+    /// encrypted catalog is copied with its encryption, and so `encryption_key`
+    /// must be its key.
+    pub fn copy(
+        data_dir_path: &Path,
+        to_path: &Path,
+        encryption_key: Option<&str>,
+    ) -> Result<(), Error> {
+        for name in ["catalog", "storage"] {
+            let from_path = data_dir_path.join(name);
+            if !from_path.exists() {
+                continue;
+            }
+            // Only the catalog is encrypted, and only once it has been opened
+            // with an encryption key.
+            let encryption_key = match name == "catalog" && !is_plaintext(&from_path)? {
+                true => encryption_key,
+                false => None,
+            };
+            let from = rusqlite::Connection::open_with_flags(
+                &from_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            set_key(&from, encryption_key)?;
+            copy_database(&from, &to_path.join(name), encryption_key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Supplies the key with which a newly opened database is encrypted, if any.
+///
+/// SQLCipher does not check the key until the database is first read.
+fn set_key(sqlite: &rusqlite::Connection, encryption_key: Option<&str>) -> Result<(), Error> {
+    if let Some(key) = encryption_key {
+        sqlite.pragma_update(None, "key", key)?;
+    }
+    Ok(())
+}
+
+/// Describes the failure of the first read of a database that was opened with
+/// `encryption_key`, which is when SQLCipher detects a missing or incorrect
+/// key.
+fn read_error(e: rusqlite::Error, encryption_key: Option<&str>) -> Error {
+    match e {
+        rusqlite::Error::SqliteFailure(e, _) if e.code == ErrorCode::NotADatabase => {
+            Error::new(ErrorKind::InvalidStorageConfig(match encryption_key {
+                Some(_) => "unable to decrypt catalog with the specified encryption key".into(),
+                None => "catalog is encrypted or is not a SQLite database".into(),
+            }))
+        }
+        e => e.into(),
+    }
+}
+
+/// Reports whether the file at `path` is an unencrypted SQLite database.
+///
+/// A database that does not yet exist, or that is empty, is not considered
+/// unencrypted, as it is created encrypted if it is opened with a key.
+fn is_plaintext(path: &Path) -> Result<bool, Error> {
+    let mut header = [0; 16];
+    match File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => Ok(&header == PLAINTEXT_HEADER),
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::UnexpectedEof
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(encryption_error(path, e)),
+    }
+}
+
+/// Returns the paths of the snapshots of the catalog that were taken before
+/// migrations, which are named `catalog.v<VERSION>.bak`, in the data directory
+/// at `data_dir_path`.
+fn snapshot_paths(data_dir_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries = match fs::read_dir(data_dir_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(encryption_error(data_dir_path, e)),
+    };
+    let mut paths = vec![];
+    for entry in entries {
+        let entry = entry.map_err(|e| encryption_error(data_dir_path, e))?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("catalog.v") && name.ends_with(".bak") {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Replaces the unencrypted database at `path` with a copy encrypted with
+/// `encryption_key`.
+///
+/// The encrypted copy is written alongside the database and then renamed over
+/// it, so that if the process crashes partway through, the database is left
+/// unencrypted and is encrypted again when it is next opened.
+fn encrypt_database(path: &Path, encryption_key: &str) -> Result<(), Error> {
+    let encrypted_path = path.with_extension("encrypted");
+    if encrypted_path.exists() {
+        fs::remove_file(&encrypted_path).map_err(|e| encryption_error(&encrypted_path, e))?;
+    }
+    let plaintext = rusqlite::Connection::open(path)?;
+    plaintext.execute(
+        "ATTACH DATABASE ? AS encrypted KEY ?",
+        params![encrypted_path.display().to_string(), encryption_key],
+    )?;
+    plaintext.query_row(
+        "SELECT sqlcipher_export('encrypted')",
+        params![],
+        |_| Ok(()),
+    )?;
+    // `sqlcipher_export` copies the schema and contents of the database, but
+    // not the fields of its header.
+    let app_id: i32 = plaintext.query_row("PRAGMA application_id", params![], |row| row.get(0))?;
+    let version: u32 = plaintext.query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+    plaintext.execute_batch(&format!(
+        "PRAGMA encrypted.application_id = {};
+        PRAGMA encrypted.user_version = {};
+        DETACH DATABASE encrypted;",
+        app_id, version
+    ))?;
+    // Closing the last connection to the database checkpoints and removes its
+    // write-ahead log, which would otherwise be applied to the encrypted copy.
+    plaintext.close().map_err(|(_, e)| e)?;
+    fs::rename(&encrypted_path, path).map_err(|e| encryption_error(path, e))?;
+    Ok(())
+}
+
+fn encryption_error(path: &Path, e: io::Error) -> Error {
+    Error::new(ErrorKind::Encryption(format!("{}: {}", path.display(), e)))
+}
+
+/// Replaces the contents of the database at `path`, creating it if it does
+/// not exist, with a consistent snapshot of the database `from`.
+///
+/// The database at `path` is encrypted with `encryption_key`, which must be
+/// the key with which `from` is encrypted, if any.
+fn copy_database(
+    from: &rusqlite::Connection,
+    path: &Path,
+    encryption_key: Option<&str>,
+) -> Result<(), Error> {
+    let mut to = rusqlite::Connection::open(path)?;
+    set_key(&to, encryption_key)?;
+    to.busy_timeout(BUSY_TIMEOUT)?;
+    // Copying every page in a single step prevents concurrent writers from
+    // forcing the copy to restart, and the catalog is small enough that the
+    // step is quick.
+    Backup::new(from, &mut to)?.run_to_completion(-1, Duration::ZERO, None)?;
+    Ok(())
+}
+
+impl Backend for SqliteBackend {
+    fn setting(&mut self, name: &str) -> Result<Option<String>, Error> {
+        let value: Option<Value> = self
+            .inner
+            .query_row(
+                "SELECT value FROM settings WHERE name = ?",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // Older versions of materialized stored some settings as integers or
+        // blobs rather than as text.
+        Ok(value.and_then(|value| match value {
+            Value::Null => None,
+            Value::Integer(i) => Some(i.to_string()),
+            Value::Real(f) => Some(f.to_string()),
+            Value::Text(s) => Some(s),
+            Value::Blob(b) => Some(String::from_utf8_lossy(&b).into_owned()),
+        }))
+    }
+
+    fn set_setting(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        self.inner.execute(
+            "INSERT INTO settings (name, value) VALUES (?, ?)
+                    ON CONFLICT (name) DO UPDATE SET value=excluded.value;",
+            params![name, value],
+        )?;
+        Ok(())
+    }
+
+    fn remove_setting(&mut self, name: &str) -> Result<(), Error> {
+        self.inner
+            .execute("DELETE FROM settings WHERE name = ?", params![name])?;
+        Ok(())
+    }
+
+    fn load_settings(&mut self) -> Result<Vec<(String, String)>, Error> {
+        self.inner
+            .prepare("SELECT name, value FROM settings")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let name: String = row.get(0)?;
+                // As in `setting`, older settings may not be stored as text.
+                let value = match row.get::<_, Value>(1)? {
+                    Value::Null => return Ok(None),
+                    Value::Integer(i) => i.to_string(),
+                    Value::Real(f) => f.to_string(),
+                    Value::Text(s) => s,
+                    Value::Blob(b) => String::from_utf8_lossy(&b).into_owned(),
+                };
+                Ok(Some((name, value)))
+            })?
+            .filter_map(Result::transpose)
+            .collect()
+    }
+
+    fn load_databases(&self) -> Result<Vec<(DatabaseId, String, i64, ObjectTimestamps)>, Error> {
+        self.inner
+            .prepare("SELECT id, name, owner_id, created_at, updated_at FROM databases")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let name: String = row.get(1)?;
+                let owner_id: i64 = row.get(2)?;
+                let timestamps = get_timestamps(row, 3)?;
+                Ok((DatabaseId(id), name, owner_id, timestamps))
+            })?
+            .collect()
+    }
+
+    fn load_schemas(
+        &self,
+    ) -> Result<Vec<(SchemaId, String, Option<DatabaseId>, i64, ObjectTimestamps)>, Error> {
+        self.inner
+            .prepare(
+                "SELECT schemas.id, schemas.name, databases.id, schemas.owner_id,
+                    schemas.created_at, schemas.updated_at
+                FROM schemas
+                LEFT JOIN databases ON schemas.database_id = databases.id",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let schema_name: String = row.get(1)?;
+                let database_id: Option<i64> = row.get(2)?;
+                let owner_id: i64 = row.get(3)?;
+                let timestamps = get_timestamps(row, 4)?;
+                Ok((
+                    SchemaId(id),
+                    schema_name,
+                    database_id.map(DatabaseId),
+                    owner_id,
+                    timestamps,
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_roles(&self) -> Result<Vec<StoredRole>, Error> {
+        self.inner
+            .prepare("SELECT id, name, restrictions, password, created_at, updated_at FROM roles")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let name: String = row.get(1)?;
+                let restrictions: Option<String> = row.get(2)?;
+                let restrictions: RoleRestrictions = match restrictions {
+                    None => RoleRestrictions::default(),
+                    Some(restrictions) => serde_json::from_str(&restrictions)
+                        .map_err(|err| rusqlite::Error::from(FromSqlError::Other(Box::new(err))))?,
+                };
+                let password: Option<String> = row.get(3)?;
+                let timestamps = get_timestamps(row, 4)?;
+                Ok((id, name, restrictions, password, timestamps))
+            })?
+            .collect()
+    }
+
+    fn load_compute_instances(
+        &self,
+    ) -> Result<Vec<(i64, String, ComputeInstanceConfig, i64, ObjectTimestamps)>, Error> {
+        self.inner
+            .prepare(
+                "SELECT id, name, config, owner_id, created_at, updated_at FROM compute_instances",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let name: String = row.get(1)?;
+                let config: Option<String> = row.get(2)?;
+                let config: ComputeInstanceConfig = match config {
+                    None => ComputeInstanceConfig::Local,
+                    Some(config) => serde_json::from_str(&config)
+                        .map_err(|err| rusqlite::Error::from(FromSqlError::Other(Box::new(err))))?,
+                };
+                let owner_id: i64 = row.get(3)?;
+                let timestamps = get_timestamps(row, 4)?;
+                Ok((id, name, config, owner_id, timestamps))
+            })?
+            .collect()
+    }
+
+    fn load_compute_replicas(&self) -> Result<Vec<StoredComputeReplica>, Error> {
+        self.inner
+            .prepare(
+                "SELECT id, compute_id, name, config, created_at, updated_at
+                FROM compute_replicas",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let compute_id: i64 = row.get(1)?;
+                let name: String = row.get(2)?;
+                let config: String = row.get(3)?;
+                let config: ComputeInstanceReplicaConfig = serde_json::from_str(&config)
+                    .map_err(|err| rusqlite::Error::from(FromSqlError::Other(Box::new(err))))?;
+                let timestamps = get_timestamps(row, 4)?;
+                Ok((id, compute_id, name, config, timestamps))
+            })?
+            .collect()
+    }
+
+    fn load_system_gids(
+        &self,
+    ) -> Result<BTreeMap<(String, String), (GlobalId, Option<u64>)>, Error> {
+        self.inner
+            .prepare("SELECT schema_name, object_name, id, fingerprint FROM system_gid_mapping")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let schema_name: String = row.get(0)?;
+                let object_name: String = row.get(1)?;
+                let id: i64 = row.get(2)?;
+                let fingerprint: Option<i64> = row.get(3)?;
+                let id = id as u64;
+                let fingerprint = fingerprint.map(|fingerprint| fingerprint as u64);
+                Ok((
+                    (schema_name, object_name),
+                    (GlobalId::System(id), fingerprint),
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_introspection_source_index_gids(
+        &self,
+        compute_id: i64,
+    ) -> Result<BTreeMap<String, GlobalId>, Error> {
+        self.inner
+            .prepare("SELECT name, index_id FROM compute_introspection_source_indexes WHERE compute_id = ?")?
+            .query_and_then(params![compute_id], |row| -> Result<_, Error> {
+                let name: String = row.get(0)?;
+                let index_id: i64 = row.get(1)?;
+                Ok((name, GlobalId::System(index_id as u64)))
+            })?
+            .collect()
+    }
+
+    /// Persist mapping from system objects to global IDs. Each element of `mappings` should be
+    /// (schema-name, object-name, global-id).
+    ///
+    /// Panics if provided id is not a system id
+    fn set_system_gids(&mut self, mappings: Vec<(&str, &str, GlobalId, u64)>) -> Result<(), Error> {
+        let tx = self.inner.transaction()?;
+        for (schema_name, object_name, id, fingerprint) in mappings {
+            let id = if let GlobalId::System(id) = id {
+                id
+            } else {
+                panic!("non-system id provided")
+            };
+            tx.execute(
+                "INSERT INTO system_gid_mapping (schema_name, object_name, id, fingerprint) VALUES (?, ?, ?, ?)
+                        ON CONFLICT (schema_name, object_name) DO UPDATE SET id=excluded.id, fingerprint=excluded.fingerprint;",
+                params![schema_name, object_name, id as i64, fingerprint as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn replace_system_gids(
+        &mut self,
+        replacements: Vec<(GlobalId, GlobalId)>,
+    ) -> Result<(), Error> {
+        let tx = self.inner.transaction()?;
+        for (old, new) in replacements {
+            tx.execute(
+                "UPDATE comments SET object_id = ? WHERE object_id = ?",
+                params![SqlVal(new), SqlVal(old)],
+            )?;
+            tx.execute(
+                "UPDATE privileges SET object_id = ? WHERE object_type = 'item' AND object_id = ?",
+                params![new.to_string(), old.to_string()],
+            )?;
+            tx.execute(
+                "UPDATE item_dependencies SET dependency = ? WHERE dependency = ?",
+                params![SqlVal(new), SqlVal(old)],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Panics if provided id is not a system id
+    fn set_introspection_source_index_gids(
+        &mut self,
+        mappings: Vec<(i64, &str, GlobalId)>,
+    ) -> Result<(), Error> {
+        let tx = self.inner.transaction()?;
+        for (compute_id, name, index_id) in mappings {
+            let index_id = if let GlobalId::System(id) = index_id {
+                id
+            } else {
+                panic!("non-system id provided")
+            };
+            tx.execute(
+                "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id) VALUES (?, ?, ?)",
+                params![compute_id, name, index_id as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Loads every recorded operation on secrets storage, in the order in
+    /// which the operations were recorded.
+    fn load_secret_audit_events(&self) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.inner
+            .prepare(
+                "SELECT id, secret_id, operation, version, user, occurred_at, error
+                FROM secret_audit_events
+                ORDER BY id",
+            )?
+            .query_and_then(params![], secret_audit_event_from_row)?
+            .collect()
+    }
+
+    fn load_audit_events(&self) -> Result<Vec<AuditEvent>, Error> {
+        self.inner
+            .prepare(
+                "SELECT id, event_type, object_type, object_id, name, user, occurred_at, details
+                FROM audit_events
+                ORDER BY id",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(0)?;
+                let event_type: String = row.get(1)?;
+                let event_type = match event_type.as_str() {
+                    "create" => AuditEventType::Create,
+                    "drop" => AuditEventType::Drop,
+                    "alter" => AuditEventType::Alter,
+                    _ => {
+                        return Err(Error::new(ErrorKind::Corruption {
+                            detail: format!("unknown audit event type {}", event_type),
+                        }))
+                    }
+                };
+                let occurred_at: i64 = row.get(6)?;
+                let details: String = row.get(7)?;
+                let details = serde_json::from_str(&details)
+                    .map_err(|err| rusqlite::Error::from(FromSqlError::Other(Box::new(err))))?;
+                Ok(AuditEvent {
+                    id: id as u64,
+                    event_type,
+                    object_type: row.get(2)?,
+                    object_id: row.get(3)?,
+                    name: row.get(4)?,
+                    user: row.get(5)?,
+                    occurred_at: occurred_at as EpochMillis,
+                    details,
+                })
+            })?
+            .collect()
+    }
+
+    fn load_comments(&self) -> Result<Vec<StoredComment>, Error> {
+        self.inner
+            .prepare("SELECT object_id, column_position, comment FROM comments")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                let column_position: i64 = row.get(1)?;
+                Ok((
+                    id.0,
+                    parse_comment_column_position(column_position)?,
+                    row.get(2)?,
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_role_settings(&self) -> Result<Vec<StoredRoleSetting>, Error> {
+        self.inner
+            .prepare("SELECT role_id, name, value FROM role_settings")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let role_id: i64 = row.get(0)?;
+                Ok((parse_stored_role_id(role_id), row.get(1)?, row.get(2)?))
+            })?
+            .collect()
+    }
+
+    fn load_privileges(&self) -> Result<Vec<StoredPrivilege>, Error> {
+        self.inner
+            .prepare("SELECT object_type, object_id, role_id, privilege FROM privileges")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let object_type: String = row.get(0)?;
+                let object_id: String = row.get(1)?;
+                let role_id: i64 = row.get(2)?;
+                let privilege: String = row.get(3)?;
+                Ok((
+                    OwnedObjectId::parse_stored_id(&object_type, &object_id)?,
+                    parse_stored_role_id(role_id),
+                    parse_privilege(&privilege)?,
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_dropped_items(&self) -> Result<Vec<StoredDroppedItem>, Error> {
+        self.inner
+            .prepare(
+                "SELECT gid, name, type, definition, dropped_at
+                FROM dropped_items
+                ORDER BY dropped_at",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                let dropped_at: i64 = row.get(4)?;
+                Ok((
+                    id.0,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    dropped_at as EpochMillis,
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_references(&self) -> Result<StoredReferences, Error> {
+        // Reading within one transaction gives every query the same snapshot.
+        let tx = self.inner.unchecked_transaction()?;
+        let databases = tx
+            .prepare("SELECT id FROM databases")?
+            .query_and_then(params![], |row| -> Result<_, Error> { Ok(row.get(0)?) })?
+            .collect::<Result<_, _>>()?;
+        let schemas = tx
+            .prepare("SELECT id, database_id FROM schemas")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let items = tx
+            .prepare("SELECT gid, name, schema_id FROM items")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                Ok((id.0, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let system_gids = tx
+            .prepare("SELECT schema_name, object_name, id FROM system_gid_mapping")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: i64 = row.get(2)?;
+                Ok((row.get(0)?, row.get(1)?, id as u64))
+            })?
+            .collect::<Result<_, _>>()?;
+        let introspection_source_indexes = tx
+            .prepare("SELECT compute_id, name, index_id FROM compute_introspection_source_indexes")?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let index_id: i64 = row.get(2)?;
+                Ok((row.get(0)?, row.get(1)?, index_id as u64))
+            })?
+            .collect::<Result<_, _>>()?;
+        let next_system_gid: i64 =
+            tx.query_row("SELECT next_gid FROM system_gid_alloc", params![], |row| {
+                row.get(0)
+            })?;
+        let next_user_gid: i64 =
+            tx.query_row("SELECT next_gid FROM user_gid_alloc", params![], |row| {
+                row.get(0)
+            })?;
+        Ok(StoredReferences {
+            databases,
+            schemas,
+            items,
+            system_gids,
+            introspection_source_indexes,
+            next_system_gid: next_system_gid as u64,
+            next_user_gid: next_user_gid as u64,
+        })
+    }
+
+    fn allocate_global_id(&mut self, namespace: GidNamespace, amount: u64) -> Result<u64, Error> {
+        let table = namespace.alloc_table();
+        let tx = self.inner.transaction()?;
+        // SQLite doesn't support u64s, so we constrain ourselves to the more
+        // limited range of positive i64s.
+        let id: i64 = tx.query_row(
+            format!("SELECT next_gid FROM {table}").as_str(),
+            params![],
+            |row| row.get(0),
+        )?;
+        let next_id = advance_gid(id, amount)?;
+        tx.execute(
+            format!("UPDATE {table} SET next_gid = ?").as_str(),
+            params![next_id],
+        )?;
+        tx.commit()?;
+        Ok(id as u64)
+    }
+
+    fn transaction(&mut self) -> Result<Box<dyn BackendTransaction + '_>, Error> {
+        Ok(Box::new(SqliteTransaction {
+            inner: self.inner.transaction()?,
+        }))
+    }
+
+    fn backup(&mut self, path: &Path) -> Result<(), Error> {
+        // Refuse to overwrite an existing file, which might be the catalog
+        // itself or an earlier backup.
+        if path.exists() {
+            return Err(Error::new(ErrorKind::Backup(format!(
+                "{} already exists",
+                path.display()
+            ))));
+        }
+        copy_database(&self.inner, path, self.encryption_key.as_deref())
+    }
+}
+
+struct SqliteTransaction<'a> {
+    inner: rusqlite::Transaction<'a>,
+}
+
+impl BackendTransaction for SqliteTransaction<'_> {
+    fn load_items(&self) -> Result<Vec<StoredItem>, Error> {
+        // Order user views by their GlobalId
+        self.inner
+            .prepare(
+                "SELECT items.gid, databases.id, schemas.id, items.name, items.definition,
+                    items.owner_id, items.created_at, items.updated_at
+                FROM items
+                JOIN schemas ON items.schema_id = schemas.id
+                JOIN databases ON schemas.database_id = databases.id
+                ORDER BY json_extract(items.gid, '$.User')",
+            )?
+            .query_and_then(params![], |row| -> Result<_, Error> {
+                let id: SqlVal<GlobalId> = row.get(0)?;
+                let database: i64 = row.get(1)?;
+                let schema: i64 = row.get(2)?;
+                let item: String = row.get(3)?;
+                let definition: Vec<u8> = row.get(4)?;
+                let owner_id: i64 = row.get(5)?;
+                let timestamps = get_timestamps(row, 6)?;
+                Ok((
+                    id.0,
+                    QualifiedObjectName {
+                        qualifiers: ObjectQualifiers {
+                            database_spec: ResolvedDatabaseSpecifier::from(database),
+                            schema_spec: SchemaSpecifier::from(schema),
+                        },
+                        item,
+                    },
+                    definition,
+                    owner_id,
+                    timestamps,
+                ))
+            })?
+            .collect()
+    }
+
+    fn load_item_dependencies(&self) -> Result<BTreeMap<GlobalId, BTreeSet<GlobalId>>, Error> {
+        let mut dependencies = BTreeMap::<_, BTreeSet<_>>::new();
+        let mut stmt = self
+            .inner
+            .prepare("SELECT gid, dependency FROM item_dependencies")?;
+        let mut rows = stmt.query(params![])?;
+        while let Some(row) = rows.next()? {
+            let id: SqlVal<GlobalId> = row.get(0)?;
+            let dependency: SqlVal<GlobalId> = row.get(1)?;
+            dependencies.entry(id.0).or_default().insert(dependency.0);
+        }
+        Ok(dependencies)
+    }
+
+    fn insert_database(
+        &mut self,
+        database_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<DatabaseId, Error> {
+        match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO databases (name, owner_id, created_at, updated_at)
+                VALUES (?, ?, ?, ?)",
+            )?
+            .execute(params![database_name, owner_id, now as i64, now as i64])
+        {
+            Ok(_) => Ok(DatabaseId(self.inner.last_insert_rowid())),
+            Err(err) if is_constraint_violation(&err) => Err(Error::new(
+                ErrorKind::DatabaseAlreadyExists(database_name.to_owned()),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn insert_schema(
+        &mut self,
+        database_id: DatabaseId,
+        schema_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<SchemaId, Error> {
+        match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO schemas (database_id, name, owner_id, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                database_id.0,
+                schema_name,
+                owner_id,
+                now as i64,
+                now as i64
+            ]) {
+            Ok(_) => Ok(SchemaId(self.inner.last_insert_rowid())),
+            Err(err) if is_constraint_violation(&err) => Err(Error::new(
+                ErrorKind::SchemaAlreadyExists(schema_name.to_owned()),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn insert_role(
+        &mut self,
+        role_name: &str,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let restrictions = serialize_restrictions(restrictions)?;
+        match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO roles (name, restrictions, password, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                role_name,
+                restrictions,
+                password,
+                now as i64,
+                now as i64
+            ]) {
+            Ok(_) => Ok(self.inner.last_insert_rowid()),
+            Err(err) if is_constraint_violation(&err) => Err(Error::new(
+                ErrorKind::RoleAlreadyExists(role_name.to_owned()),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn update_role(
+        &mut self,
+        id: i64,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let restrictions = serialize_restrictions(restrictions)?;
+        let n = self
+            .inner
+            .prepare_cached(
+                "UPDATE roles SET restrictions = ?, password = ?, updated_at = ? WHERE id = ?",
+            )?
+            .execute(params![restrictions, password, updated_at as i64, id])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownRole(id.to_string()).into())
+        }
+    }
+
+    /// Panics if any introspection source id is not a system id
+    fn insert_compute_instance(
+        &mut self,
+        cluster_name: &str,
+        config: &ComputeInstanceConfig,
+        introspection_sources: &Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let config = serde_json::to_string(config)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        let id = match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO compute_instances (name, config, owner_id, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                cluster_name,
+                config,
+                owner_id,
+                now as i64,
+                now as i64
+            ]) {
+            Ok(_) => self.inner.last_insert_rowid(),
+            Err(err) if is_constraint_violation(&err) => {
+                return Err(Error::new(ErrorKind::ClusterAlreadyExists(
+                    cluster_name.to_owned(),
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for (builtin, index_id) in introspection_sources {
+            let index_id = if let GlobalId::System(id) = index_id {
+                *id
+            } else {
+                panic!("non-system id provided")
+            };
+            self
+                .inner
+                .prepare_cached(
+                "INSERT INTO compute_introspection_source_indexes (compute_id, name, index_id) VALUES (?, ?, ?)")?
+                .execute(params![id, builtin.name, index_id as i64])?;
+        }
+
+        Ok(id)
+    }
+
+    fn update_compute_instance_config(
+        &mut self,
+        id: ComputeInstanceId,
+        config: &ComputeInstanceConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let config = serde_json::to_string(config)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        match self
+            .inner
+            .prepare_cached("UPDATE compute_instances SET config = ?, updated_at = ? WHERE id = ?")?
+            .execute(params![config, updated_at as i64, id])
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn insert_compute_replica(
+        &mut self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+        config: &ComputeInstanceReplicaConfig,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        let config = serde_json::to_string(config)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO compute_replicas (compute_id, name, config, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                compute_id,
+                replica_name,
+                config,
+                now as i64,
+                now as i64
+            ]) {
+            Ok(_) => Ok(self.inner.last_insert_rowid()),
+            Err(err) if is_constraint_violation(&err) => Err(Error::new(
+                ErrorKind::ClusterReplicaAlreadyExists(replica_name.to_owned()),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn update_compute_replica_config(
+        &mut self,
+        id: i64,
+        config: &ComputeInstanceReplicaConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let config = serde_json::to_string(config)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        let n = self
+            .inner
+            .prepare_cached("UPDATE compute_replicas SET config = ?, updated_at = ? WHERE id = ?")?
+            .execute(params![config, updated_at as i64, id])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstanceReplica(id.to_string()).into())
+        }
+    }
+
+    fn remove_compute_replica(
+        &self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+    ) -> Result<(), Error> {
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM compute_replicas WHERE compute_id = ? AND name = ?")?
+            .execute(params![compute_id, replica_name])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstanceReplica(replica_name.to_owned()).into())
+        }
+    }
+
+    fn insert_item(
+        &self,
+        id: GlobalId,
+        schema_id: SchemaId,
+        item_name: &str,
+        item: &[u8],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        let now = now as i64;
+        match self
+            .inner
+            .prepare_cached(
+                "INSERT INTO items
+                    (gid, schema_id, name, definition, owner_id, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                SqlVal(&id),
+                schema_id.0,
+                item_name,
+                item,
+                owner_id,
+                now,
+                now
+            ]) {
+            Ok(_) => Ok(()),
+            Err(err) if is_constraint_violation(&err) => Err(Error::new(
+                ErrorKind::ItemAlreadyExists(item_name.to_owned()),
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn insert_items(
+        &self,
+        items: &[NewItem],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        let now = now as i64;
+        let mut stmt = self.inner.prepare_cached(
+            "INSERT INTO items
+                (gid, schema_id, name, definition, owner_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for (id, schema_id, item_name, item) in items {
+            match stmt.execute(params![
+                SqlVal(id),
+                schema_id.0,
+                item_name,
+                item,
+                owner_id,
+                now,
+                now
+            ]) {
+                Ok(_) => (),
+                Err(err) if is_constraint_violation(&err) => {
+                    return Err(Error::new(ErrorKind::ItemAlreadyExists(item_name.clone())))
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_database(&self, id: &DatabaseId) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "DELETE FROM privileges WHERE object_type = 'database' AND object_id = ?",
+            )?
+            .execute(params![id.0.to_string()])?;
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM databases WHERE id = ?")?
+            .execute(params![id.0])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownDatabase(id.to_string()).into())
+        }
+    }
+
+    fn remove_schema(&self, database_id: &DatabaseId, schema_id: &SchemaId) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "DELETE FROM privileges WHERE object_type = 'schema' AND object_id = ?",
+            )?
+            .execute(params![schema_id.0.to_string()])?;
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM schemas WHERE database_id = ? AND id = ?")?
+            .execute(params![database_id.0, schema_id.0])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownSchema(format!("{}.{}", database_id.0, schema_id.0)).into())
+        }
+    }
+
+    fn remove_role(&self, name: &str) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "DELETE FROM role_settings WHERE role_id = (SELECT id FROM roles WHERE name = ?)",
+            )?
+            .execute(params![name])?;
+        self.inner
+            .prepare_cached(
+                "DELETE FROM privileges WHERE role_id = (SELECT id FROM roles WHERE name = ?)",
+            )?
+            .execute(params![name])?;
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM roles WHERE name = ?")?
+            .execute(params![name])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownRole(name.to_owned()).into())
+        }
+    }
+
+    fn remove_compute_instance(&self, name: &str) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "DELETE FROM compute_replicas
+                WHERE compute_id IN (SELECT id FROM compute_instances WHERE name = ?)",
+            )?
+            .execute(params![name])?;
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM compute_instances WHERE name = ?")?
+            .execute(params![name])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownComputeInstance(name.to_owned()).into())
+        }
+    }
+
+    fn remove_item(&self, item: &RemovedItem, dropped_at: EpochMillis) -> Result<(), Error> {
+        self.remove_items(std::slice::from_ref(item), dropped_at)
+    }
+
+    fn remove_items(&self, items: &[RemovedItem], dropped_at: EpochMillis) -> Result<(), Error> {
+        let mut drop_item = self.inner.prepare_cached(
+            "INSERT INTO dropped_items (gid, name, type, definition, dropped_at)
+            SELECT gid, ?, ?, definition, ? FROM items WHERE gid = ?",
+        )?;
+        let mut remove_item = self
+            .inner
+            .prepare_cached("DELETE FROM items WHERE gid = ?")?;
+        let mut remove_comments = self
+            .inner
+            .prepare_cached("DELETE FROM comments WHERE object_id = ?")?;
+        let mut remove_dependencies = self
+            .inner
+            .prepare_cached("DELETE FROM item_dependencies WHERE gid = ?")?;
+        let mut remove_privileges = self.inner.prepare_cached(
+            "DELETE FROM privileges WHERE object_type = 'item' AND object_id = ?",
+        )?;
+        for (id, name, item_type) in items {
+            drop_item.execute(params![name, item_type, dropped_at as i64, SqlVal(id)])?;
+            let n = remove_item.execute(params![SqlVal(id)])?;
+            assert!(n <= 1);
+            if n == 0 {
+                return Err(SqlCatalogError::UnknownItem(id.to_string()).into());
+            }
+            remove_comments.execute(params![SqlVal(id)])?;
+            remove_dependencies.execute(params![SqlVal(id)])?;
+            remove_privileges.execute(params![id.to_string()])?;
+        }
+        Ok(())
+    }
+
+    fn remove_dropped_item(&self, id: GlobalId) -> Result<(), Error> {
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM dropped_items WHERE gid = ?")?
+            .execute(params![SqlVal(id)])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+        }
+    }
+
+    fn purge_dropped_items(&self, dropped_before: EpochMillis) -> Result<(), Error> {
+        self.inner
+            .prepare_cached("DELETE FROM dropped_items WHERE dropped_at < ?")?
+            .execute(params![dropped_before as i64])?;
+        Ok(())
+    }
+
+    fn update_item(
+        &self,
+        id: GlobalId,
+        item_name: &str,
+        item: &[u8],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let n = self
+            .inner
+            .prepare_cached(
+                "UPDATE items SET name = ?, definition = ?, updated_at = ? WHERE gid = ?",
+            )?
+            .execute(params![item_name, item, updated_at as i64, SqlVal(id)])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownItem(id.to_string()).into())
+        }
+    }
+
+    fn update_items(&self, updates: &[ItemUpdate], updated_at: EpochMillis) -> Result<(), Error> {
+        let updated_at = updated_at as i64;
+        let mut stmt = self.inner.prepare_cached(
+            "UPDATE items SET name = ?, definition = ?, updated_at = ? WHERE gid = ?",
+        )?;
+        for (id, item_name, item) in updates {
+            let n = stmt.execute(params![item_name, item, updated_at, SqlVal(id)])?;
+            assert!(n <= 1);
+            if n == 0 {
+                return Err(SqlCatalogError::UnknownItem(id.to_string()).into());
+            }
+        }
+        Ok(())
+    }
+
+    fn set_item_dependencies(&self, dependencies: &[ItemDependencies]) -> Result<(), Error> {
+        let mut remove = self
+            .inner
+            .prepare_cached("DELETE FROM item_dependencies WHERE gid = ?")?;
+        let mut insert = self.inner.prepare_cached(
+            "INSERT INTO item_dependencies (gid, dependency) VALUES (?, ?)
+            ON CONFLICT DO NOTHING",
+        )?;
+        for (id, uses) in dependencies {
+            remove.execute(params![SqlVal(id)])?;
+            for dependency in uses {
+                insert.execute(params![SqlVal(id), SqlVal(dependency)])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rename_database(
+        &self,
+        id: &DatabaseId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let n = match self
+            .inner
+            .prepare_cached("UPDATE databases SET name = ?, updated_at = ? WHERE id = ?")?
+            .execute(params![to_name, updated_at as i64, id.0])
+        {
+            Ok(n) => n,
+            Err(err) if is_constraint_violation(&err) => {
+                return Err(Error::new(ErrorKind::DatabaseAlreadyExists(
+                    to_name.to_owned(),
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownDatabase(id.to_string()).into())
+        }
+    }
+
+    fn rename_schema(
+        &self,
+        database_id: &DatabaseId,
+        schema_id: &SchemaId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let n = match self
+            .inner
+            .prepare_cached(
+                "UPDATE schemas SET name = ?, updated_at = ? WHERE database_id = ? AND id = ?",
+            )?
+            .execute(params![
+                to_name,
+                updated_at as i64,
+                database_id.0,
+                schema_id.0
+            ]) {
+            Ok(n) => n,
+            Err(err) if is_constraint_violation(&err) => {
+                return Err(Error::new(ErrorKind::SchemaAlreadyExists(
+                    to_name.to_owned(),
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(SqlCatalogError::UnknownSchema(format!("{}.{}", database_id.0, schema_id.0)).into())
+        }
+    }
+
+    fn update_owner(
+        &self,
+        object: OwnedObjectId,
+        owner_id: i64,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        let updated_at = updated_at as i64;
+        let n = match object {
+            OwnedObjectId::Database(id) => self
+                .inner
+                .prepare_cached("UPDATE databases SET owner_id = ?, updated_at = ? WHERE id = ?")?
+                .execute(params![owner_id, updated_at, id.0])?,
+            OwnedObjectId::Schema(id) => self
+                .inner
+                .prepare_cached("UPDATE schemas SET owner_id = ?, updated_at = ? WHERE id = ?")?
+                .execute(params![owner_id, updated_at, id.0])?,
+            OwnedObjectId::ComputeInstance(id) => self
+                .inner
+                .prepare_cached(
+                    "UPDATE compute_instances SET owner_id = ?, updated_at = ? WHERE id = ?",
+                )?
+                .execute(params![owner_id, updated_at, id])?,
+            OwnedObjectId::Item(id) => self
+                .inner
+                .prepare_cached("UPDATE items SET owner_id = ?, updated_at = ? WHERE gid = ?")?
+                .execute(params![owner_id, updated_at, SqlVal(id)])?,
+        };
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(object.unknown())
+        }
+    }
+
+    fn insert_audit_event(&mut self, event: &mut AuditEvent) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "INSERT INTO audit_events
+                    (event_type, object_type, object_id, name, user, occurred_at, details)
+                VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                event.event_type.as_str(),
+                event.object_type,
+                event.object_id,
+                event.name,
+                event.user,
+                event.occurred_at as i64,
+                event.details.to_string(),
+            ])?;
+        event.id = self.inner.last_insert_rowid() as u64;
+        Ok(())
+    }
+
+    fn insert_secret_audit_events(&mut self, events: &mut [SecretAuditEvent]) -> Result<(), Error> {
+        let mut stmt = self.inner.prepare_cached(
+            "INSERT INTO secret_audit_events
+                (secret_id, operation, version, user, occurred_at, error)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )?;
+        for event in events.iter_mut() {
+            stmt.execute(params![
+                SqlVal(&event.secret_id),
+                event.operation.as_str(),
+                event.version.map(|v| v.0 as i64),
+                event.user,
+                event.occurred_at as i64,
+                event.error,
+            ])?;
+            event.id = self.inner.last_insert_rowid() as u64;
+        }
+        Ok(())
+    }
+
+    fn purge_secret_audit_events(&self, retained: u64) -> Result<Vec<SecretAuditEvent>, Error> {
+        // Events are forgotten in order of their IDs, which increase with
+        // each recorded event.
+        let cutoff: Option<i64> = self
+            .inner
+            .prepare_cached("SELECT id FROM secret_audit_events ORDER BY id DESC LIMIT 1 OFFSET ?")?
+            .query_row(params![retained as i64], |row| row.get(0))
+            .optional()?;
+        let cutoff = match cutoff {
+            Some(cutoff) => cutoff,
+            None => return Ok(vec![]),
+        };
+        let events = self
+            .inner
+            .prepare_cached(
+                "SELECT id, secret_id, operation, version, user, occurred_at, error
+                FROM secret_audit_events
+                WHERE id <= ?
+                ORDER BY id",
+            )?
+            .query_and_then(params![cutoff], secret_audit_event_from_row)?
+            .collect::<Result<_, _>>()?;
+        self.inner
+            .prepare_cached("DELETE FROM secret_audit_events WHERE id <= ?")?
+            .execute(params![cutoff])?;
+        Ok(events)
+    }
+
+    fn insert_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        self.inner
+            .prepare_cached(
+                "INSERT INTO comments (object_id, column_position, comment) VALUES (?, ?, ?)",
+            )?
+            .execute(params![
+                SqlVal(id),
+                comment_column_position(column_position),
+                comment
+            ])?;
+        Ok(())
+    }
+
+    fn update_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        let n = self
+            .inner
+            .prepare_cached(
+                "UPDATE comments SET comment = ? WHERE object_id = ? AND column_position = ?",
+            )?
+            .execute(params![
+                comment,
+                SqlVal(id),
+                comment_column_position(column_position)
+            ])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(unknown_comment(id, column_position))
+        }
+    }
+
+    fn delete_comment(&self, id: GlobalId, column_position: Option<usize>) -> Result<(), Error> {
+        let n = self
+            .inner
+            .prepare_cached("DELETE FROM comments WHERE object_id = ? AND column_position = ?")?
+            .execute(params![
+                SqlVal(id),
+                comment_column_position(column_position)
+            ])?;
+        assert!(n <= 1);
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(unknown_comment(id, column_position))
+        }
+    }
+
+    fn set_role_setting(
+        &self,
+        role_id: Option<i64>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), Error> {
+        let role_id = stored_role_id(role_id);
+        match value {
+            Some(value) => {
+                self.inner
+                    .prepare_cached(
+                        "INSERT INTO role_settings (role_id, name, value) VALUES (?, ?, ?)
+                        ON CONFLICT (role_id, name) DO UPDATE SET value = excluded.value",
+                    )?
+                    .execute(params![role_id, name, value])?;
+            }
+            None => {
+                self.inner
+                    .prepare_cached("DELETE FROM role_settings WHERE role_id = ? AND name = ?")?
+                    .execute(params![role_id, name])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn grant_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        let (object_type, object_id) = object.stored_id();
+        self.inner
+            .prepare_cached(
+                "INSERT INTO privileges (object_type, object_id, role_id, privilege)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT DO NOTHING",
+            )?
+            .execute(params![
+                object_type,
+                object_id,
+                stored_role_id(role_id),
+                privilege.to_string()
+            ])?;
+        Ok(())
+    }
+
+    fn revoke_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        let (object_type, object_id) = object.stored_id();
+        self.inner
+            .prepare_cached(
+                "DELETE FROM privileges
+                WHERE object_type = ? AND object_id = ? AND role_id = ? AND privilege = ?",
+            )?
+            .execute(params![
+                object_type,
+                object_id,
+                stored_role_id(role_id),
+                privilege.to_string()
+            ])?;
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), Error> {
+        self.inner.commit()?;
+        Ok(())
+    }
+}
+
+/// Decodes a row of `secret_audit_events` whose columns are selected in the
+/// order in which they are declared.
+fn secret_audit_event_from_row(row: &rusqlite::Row) -> Result<SecretAuditEvent, Error> {
+    let id: i64 = row.get(0)?;
+    let secret_id: SqlVal<GlobalId> = row.get(1)?;
+    let operation: String = row.get(2)?;
+    let operation = match operation.as_str() {
+        "ensure" => SecretAuditOperation::Ensure,
+        "delete" => SecretAuditOperation::Delete,
+        _ => {
+            return Err(Error::new(ErrorKind::Corruption {
+                detail: format!("unknown secret audit operation {}", operation),
+            }))
+        }
+    };
+    let version: Option<i64> = row.get(3)?;
+    let occurred_at: i64 = row.get(5)?;
+    Ok(SecretAuditEvent {
+        id: id as u64,
+        secret_id: secret_id.0,
+        operation,
+        version: version.map(|v| SecretVersion(v as u64)),
+        user: row.get(4)?,
+        occurred_at: occurred_at as EpochMillis,
+        error: row.get(6)?,
+    })
+}
+
+fn is_constraint_violation(err: &rusqlite::Error) -> bool {
+    match err {
+        rusqlite::Error::SqliteFailure(err, _) => {
+            err.code == rusqlite::ErrorCode::ConstraintViolation
+        }
+        _ => false,
+    }
+}
+
+/// Serializes restrictions for the `restrictions` column of the `roles` table,
+/// in which the default restrictions are stored as NULL.
+fn serialize_restrictions(restrictions: &RoleRestrictions) -> Result<Option<String>, Error> {
+    if *restrictions == RoleRestrictions::default() {
+        Ok(None)
+    } else {
+        let restrictions = serde_json::to_string(restrictions)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        Ok(Some(restrictions))
+    }
+}
+
+/// Reads the creation and modification times stored in the columns of `row`
+/// at `idx` and `idx + 1`.
+fn get_timestamps(row: &rusqlite::Row, idx: usize) -> Result<ObjectTimestamps, rusqlite::Error> {
+    let created_at: i64 = row.get(idx)?;
+    let updated_at: i64 = row.get(idx + 1)?;
+    Ok(ObjectTimestamps {
+        created_at: created_at as EpochMillis,
+        updated_at: updated_at as EpochMillis,
+    })
+}
+
+pub struct SqlVal<T>(pub T);
+
+impl<T> ToSql for SqlVal<T>
+where
+    T: Serialize,
+{
+    fn to_sql(&self) -> Result<ToSqlOutput, rusqlite::Error> {
+        let bytes = serde_json::to_vec(&self.0)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        Ok(ToSqlOutput::Owned(Value::Blob(bytes)))
+    }
+}
+
+impl<T> FromSql for SqlVal<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    fn column_result(val: ValueRef) -> Result<Self, FromSqlError> {
+        let bytes = match val {
+            ValueRef::Blob(bytes) => bytes,
+            _ => return Err(FromSqlError::InvalidType),
+        };
+        Ok(SqlVal(
+            serde_json::from_slice(bytes).map_err(|err| FromSqlError::Other(Box::new(err)))?,
+        ))
+    }
+}
@@ -0,0 +1,2481 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Catalog storage in typed collections of the STORAGE stash.
+//!
+//! Each kind of catalog object is stored in its own [`StashCollection`], keyed
+//! by the object's ID, so that the data directory has a single store of
+//! metadata. The contents of every collection are loaded into memory when the
+//! catalog is opened, and a transaction's changes are written to the stash in
+//! a single [`Stash::append`] when it commits.
+//!
+//! The constraints that the SQLite backend's schema enforces, like the
+//! uniqueness of names, are checked against the contents in memory. This is
+//! sound only because a single process writes to the catalog, which the data
+//! directory's PID file guarantees.
+//!
+//! When it is first opened, the backend migrates the SQLite catalog in the
+//! data directory, if any, into the stash, and then renames it to
+//! `catalog.migrated`, so that it is neither migrated again nor opened by a
+//! process that does not use the stash.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bytes::BufMut;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use timely::progress::Antichain;
+use tracing::info;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_expr::GlobalId;
+use mz_ore::now::EpochMillis;
+use mz_persist_types::Codec;
+use mz_secrets::SecretVersion;
+use mz_sql::ast::Privilege;
+use mz_sql::catalog::CatalogError as SqlCatalogError;
+use mz_sql::names::{
+    DatabaseId, ObjectQualifiers, QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaId,
+    SchemaSpecifier,
+};
+use mz_sql::plan::{ComputeInstanceConfig, ComputeInstanceReplicaConfig, RoleRestrictions};
+use mz_stash::{AppendBatch, Stash, StashCollection, Timestamp};
+
+use crate::catalog::builtin::BuiltinLog;
+use crate::catalog::error::{Error, ErrorKind};
+use crate::catalog::storage::{
+    advance_gid, comment_column_position, parse_comment_column_position, parse_privilege,
+    parse_stored_role_id, stored_role_id, unknown_comment, Backend, BackendTransaction,
+    GidNamespace, ItemDependencies, ItemUpdate, NewItem, OwnedObjectId, RemovedItem, SqliteBackend,
+    SqliteSynchronous, StoredComment, StoredComputeReplica, StoredDroppedItem, StoredItem,
+    StoredPrivilege, StoredReferences, StoredRole, StoredRoleSetting,
+};
+use crate::catalog::{
+    AuditEvent, AuditEventType, ObjectTimestamps, SecretAuditEvent, SecretAuditOperation,
+};
+
+/// The name of the file in the data directory to which the SQLite catalog is
+/// renamed once it has been migrated into the stash.
+pub(super) const MIGRATED_SQLITE_CATALOG: &str = "catalog.migrated";
+
+/// Implements [`Codec`] for types that are stored in the stash as JSON.
+macro_rules! json_codec {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl Codec for $ty {
+                fn codec_name() -> String {
+                    concat!("json[", stringify!($ty), "]").into()
+                }
+
+                fn encode<B: BufMut>(&self, buf: &mut B) {
+                    let bytes = serde_json::to_vec(self).expect("catalog serialization cannot fail");
+                    buf.put_slice(&bytes);
+                }
+
+                fn decode<'a>(buf: &'a [u8]) -> Result<Self, String> {
+                    serde_json::from_slice(buf).map_err(|e| e.to_string())
+                }
+            }
+        )*
+    };
+}
+
+/// The key of an object that is identified by an integer ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct IdKey {
+    id: i64,
+}
+
+/// The key of an item, or of a dropped item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct GidKey {
+    gid: GlobalId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct IdAllocValue {
+    next_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct DatabaseValue {
+    name: String,
+    owner_id: i64,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct SchemaValue {
+    database_id: Option<i64>,
+    name: String,
+    owner_id: i64,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ItemValue {
+    schema_id: i64,
+    name: String,
+    definition: Vec<u8>,
+    owner_id: i64,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ItemDependencyKey {
+    gid: GlobalId,
+    dependency: GlobalId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct RoleValue {
+    name: String,
+    /// The role's restrictions as JSON, or `None` if they are the defaults.
+    restrictions: Option<String>,
+    password: Option<String>,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+/// The key of a session default. The role ID is stored as by
+/// [`stored_role_id`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct RoleSettingKey {
+    role_id: i64,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ComputeInstanceValue {
+    name: String,
+    /// The configuration as JSON, or `None` for a local compute instance.
+    config: Option<String>,
+    owner_id: i64,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ComputeReplicaValue {
+    compute_id: ComputeInstanceId,
+    name: String,
+    /// The configuration as JSON.
+    config: String,
+    created_at: EpochMillis,
+    updated_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct IntrospectionSourceIndexKey {
+    compute_id: ComputeInstanceId,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct IntrospectionSourceIndexValue {
+    index_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct SystemGidKey {
+    schema_name: String,
+    object_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct SystemGidValue {
+    id: u64,
+    fingerprint: Option<u64>,
+}
+
+/// The key of a comment. The column position is stored as by
+/// [`comment_column_position`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct CommentKey {
+    object_id: GlobalId,
+    column_position: i64,
+}
+
+/// A privilege, stored as its object's [`OwnedObjectId::stored_id`], the
+/// [`stored_role_id`] of its grantee, and its SQL keyword.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct PrivilegeKey {
+    object_type: String,
+    object_id: String,
+    role_id: i64,
+    privilege: String,
+}
+
+impl PrivilegeKey {
+    fn new(object: OwnedObjectId, role_id: Option<i64>, privilege: Privilege) -> PrivilegeKey {
+        let (object_type, object_id) = object.stored_id();
+        PrivilegeKey {
+            object_type: object_type.into(),
+            object_id,
+            role_id: stored_role_id(role_id),
+            privilege: privilege.to_string(),
+        }
+    }
+
+    fn is_on(&self, object: OwnedObjectId) -> bool {
+        let (object_type, object_id) = object.stored_id();
+        self.object_type == object_type && self.object_id == object_id
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct DroppedItemValue {
+    name: String,
+    item_type: String,
+    definition: Vec<u8>,
+    dropped_at: EpochMillis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct AuditEventValue {
+    event_type: String,
+    object_type: String,
+    object_id: String,
+    name: String,
+    user: String,
+    occurred_at: EpochMillis,
+    /// The details as JSON.
+    details: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct SecretAuditEventValue {
+    secret_id: GlobalId,
+    operation: String,
+    version: Option<u64>,
+    user: String,
+    occurred_at: EpochMillis,
+    error: Option<String>,
+}
+
+json_codec!(
+    IdKey,
+    GidKey,
+    IdAllocValue,
+    DatabaseValue,
+    SchemaValue,
+    ItemValue,
+    ItemDependencyKey,
+    RoleValue,
+    RoleSettingKey,
+    ComputeInstanceValue,
+    ComputeReplicaValue,
+    IntrospectionSourceIndexKey,
+    IntrospectionSourceIndexValue,
+    SystemGidKey,
+    SystemGidValue,
+    CommentKey,
+    PrivilegeKey,
+    DroppedItemValue,
+    AuditEventValue,
+    SecretAuditEventValue,
+);
+
+/// Declares the collections in which the catalog is stored, each of which is
+/// named `catalog-<field>` in the stash, along with the structs that hold
+/// their contents and the changes to them.
+macro_rules! collections {
+    ($($field:ident: $key:ty => $value:ty,)*) => {
+        /// The collections in which the catalog is stored.
+        #[derive(Clone, Copy)]
+        struct Collections {
+            $($field: StashCollection<$key, $value>,)*
+        }
+
+        impl Collections {
+            fn open(stash: &mz_stash::Sqlite) -> Result<Collections, Error> {
+                Ok(Collections {
+                    $($field: stash.collection(concat!("catalog-", stringify!($field)))?,)*
+                })
+            }
+
+            /// Returns the furthest upper frontier of any of the collections,
+            /// at which the next change to the catalog can be written.
+            fn upper(&self, stash: &mz_stash::Sqlite) -> Result<Timestamp, Error> {
+                let mut upper = 0;
+                $(
+                    let collection_upper = stash.upper(self.$field)?.into_option().ok_or_else(|| {
+                        corruption(format!("catalog-{} is sealed", stringify!($field)))
+                    })?;
+                    upper = cmp::max(upper, collection_upper);
+                )*
+                Ok(upper)
+            }
+        }
+
+        /// The committed contents of the collections.
+        #[derive(Debug, Default)]
+        struct Contents {
+            $($field: BTreeMap<$key, $value>,)*
+        }
+
+        impl Contents {
+            /// Consolidates and then loads every collection.
+            fn load(stash: &mz_stash::Sqlite, collections: &Collections) -> Result<Contents, Error> {
+                Ok(Contents {
+                    $($field: load_collection(stash, collections.$field, stringify!($field))?,)*
+                })
+            }
+
+            /// Returns the changes that insert these contents into empty
+            /// collections.
+            fn into_changes(self) -> Changes {
+                Changes {
+                    $($field: self.$field.into_iter().map(|(k, v)| (k, Some(v))).collect(),)*
+                }
+            }
+        }
+
+        /// Changes to the contents of the collections that have not yet been
+        /// committed, by key. A value of `None` removes the key.
+        #[derive(Debug, Default)]
+        struct Changes {
+            $($field: BTreeMap<$key, Option<$value>>,)*
+        }
+
+        impl Changes {
+            /// Returns the batches that write the changes to the stash at
+            /// `timestamp`.
+            fn batches(
+                &self,
+                collections: &Collections,
+                contents: &Contents,
+                timestamp: Timestamp,
+            ) -> Vec<AppendBatch> {
+                let mut batches = vec![];
+                $(
+                    if let Some(batch) = make_batch(
+                        collections.$field,
+                        &contents.$field,
+                        &self.$field,
+                        timestamp,
+                    ) {
+                        batches.push(batch);
+                    }
+                )*
+                batches
+            }
+
+            /// Applies the changes to `contents`.
+            fn apply(self, contents: &mut Contents) {
+                $(apply_changes(&mut contents.$field, self.$field);)*
+            }
+        }
+    };
+}
+
+collections! {
+    settings: String => String,
+    id_alloc: String => IdAllocValue,
+    databases: IdKey => DatabaseValue,
+    schemas: IdKey => SchemaValue,
+    items: GidKey => ItemValue,
+    item_dependencies: ItemDependencyKey => (),
+    roles: IdKey => RoleValue,
+    role_settings: RoleSettingKey => String,
+    compute_instances: IdKey => ComputeInstanceValue,
+    compute_replicas: IdKey => ComputeReplicaValue,
+    introspection_source_indexes: IntrospectionSourceIndexKey => IntrospectionSourceIndexValue,
+    system_gids: SystemGidKey => SystemGidValue,
+    comments: CommentKey => String,
+    privileges: PrivilegeKey => (),
+    dropped_items: GidKey => DroppedItemValue,
+    audit_events: IdKey => AuditEventValue,
+    secret_audit_events: IdKey => SecretAuditEventValue,
+}
+
+/// Returns a [`Table`] over the collection `$field`, as modified by
+/// `$changes`.
+macro_rules! table {
+    ($contents:expr, $changes:expr, $field:ident) => {
+        Table {
+            committed: &$contents.$field,
+            changes: &mut $changes.$field,
+        }
+    };
+}
+
+/// The names under which the next ID of each kind of object is stored in the
+/// `id_alloc` collection. Global IDs are allocated under the names of the
+/// SQLite backend's allocator tables.
+const DATABASE_ID_ALLOC: &str = "databases";
+const SCHEMA_ID_ALLOC: &str = "schemas";
+const ROLE_ID_ALLOC: &str = "roles";
+const COMPUTE_ID_ALLOC: &str = "compute_instances";
+const REPLICA_ID_ALLOC: &str = "compute_replicas";
+const AUDIT_EVENT_ID_ALLOC: &str = "audit_events";
+const SECRET_AUDIT_EVENT_ID_ALLOC: &str = "secret_audit_events";
+
+/// Catalog storage in typed collections of the STORAGE stash.
+pub struct StashBackend {
+    stash: mz_stash::Sqlite,
+    path: PathBuf,
+    collections: Collections,
+    contents: Contents,
+    /// The time at which the next change to the catalog is written.
+    timestamp: Timestamp,
+}
+
+impl fmt::Debug for StashBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StashBackend")
+            .field("path", &self.path)
+            .field("timestamp", &self.timestamp)
+            .finish()
+    }
+}
+
+impl StashBackend {
+    /// Opens the catalog stored in the STORAGE stash in the data directory at
+    /// `data_dir_path`.
+    ///
+    /// If the stash does not yet contain a catalog, the SQLite catalog in the
+    /// data directory is migrated to the latest version and copied into the
+    /// stash, preserving the IDs of every object. The copy is written
+    /// atomically, and only then is the SQLite catalog renamed to
+    /// `catalog.migrated`, so a migration that is interrupted before the copy
+    /// is retried, and one that is interrupted after it only renames the
+    /// SQLite catalog. A data directory without a SQLite catalog starts with
+    /// the contents of a new SQLite catalog.
+    pub fn open(data_dir_path: &Path) -> Result<StashBackend, Error> {
+        let path = data_dir_path.join("storage");
+        let stash = mz_stash::Sqlite::open(&path)?;
+        let collections = Collections::open(&stash)?;
+        let contents = Contents::load(&stash, &collections)?;
+        let timestamp = collections.upper(&stash)?;
+        let mut backend = StashBackend {
+            stash,
+            path,
+            collections,
+            contents,
+            timestamp,
+        };
+
+        let sqlite_path = data_dir_path.join("catalog");
+        let migrated_path = data_dir_path.join(MIGRATED_SQLITE_CATALOG);
+        // Every ID allocator is written along with the rest of the catalog.
+        if backend.contents.id_alloc.is_empty() {
+            let contents = if sqlite_path.exists() {
+                let mut sqlite = SqliteBackend::open(data_dir_path, SqliteSynchronous::Full, None)?;
+                read_catalog(&mut sqlite)?
+            } else if migrated_path.exists() {
+                return Err(Error::new(ErrorKind::InvalidStorageConfig(format!(
+                    "the catalog was migrated into {}, which no longer contains it",
+                    backend.path.display()
+                ))));
+            } else {
+                let dir = tempfile::tempdir()
+                    .map_err(|e| Error::new(ErrorKind::Import(e.to_string())))?;
+                let mut sqlite = SqliteBackend::open(dir.path(), SqliteSynchronous::Off, None)?;
+                read_catalog(&mut sqlite)?
+            };
+            backend.commit_changes(contents.into_changes())?;
+        }
+        if sqlite_path.exists() {
+            fs::rename(&sqlite_path, &migrated_path).map_err(|e| {
+                Error::new(ErrorKind::Import(format!(
+                    "{}: {}",
+                    sqlite_path.display(),
+                    e
+                )))
+            })?;
+            info!(
+                "migrated catalog {} into {}",
+                sqlite_path.display(),
+                backend.path.display()
+            );
+        }
+
+        Ok(backend)
+    }
+
+    /// Atomically writes `changes` to the stash, and then applies them to the
+    /// contents in memory.
+    fn commit_changes(&mut self, changes: Changes) -> Result<(), Error> {
+        let batches = changes.batches(&self.collections, &self.contents, self.timestamp);
+        if batches.is_empty() {
+            return Ok(());
+        }
+        self.stash.append(batches)?;
+        changes.apply(&mut self.contents);
+        self.timestamp += 1;
+        Ok(())
+    }
+
+    /// Applies the changes made by `f` atomically.
+    fn modify<F, R>(&mut self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Contents, &mut Changes) -> Result<R, Error>,
+    {
+        let mut changes = Changes::default();
+        let result = f(&self.contents, &mut changes)?;
+        self.commit_changes(changes)?;
+        Ok(result)
+    }
+}
+
+impl Backend for StashBackend {
+    fn setting(&mut self, name: &str) -> Result<Option<String>, Error> {
+        Ok(self.contents.settings.get(name).cloned())
+    }
+
+    fn set_setting(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, settings).insert(name.into(), value.into());
+            Ok(())
+        })
+    }
+
+    fn remove_setting(&mut self, name: &str) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, settings).remove(&name.to_owned());
+            Ok(())
+        })
+    }
+
+    fn load_settings(&mut self) -> Result<Vec<(String, String)>, Error> {
+        Ok(self
+            .contents
+            .settings
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect())
+    }
+
+    fn load_databases(&self) -> Result<Vec<(DatabaseId, String, i64, ObjectTimestamps)>, Error> {
+        Ok(self
+            .contents
+            .databases
+            .iter()
+            .map(|(key, database)| {
+                (
+                    DatabaseId(key.id),
+                    database.name.clone(),
+                    database.owner_id,
+                    timestamps(database.created_at, database.updated_at),
+                )
+            })
+            .collect())
+    }
+
+    fn load_schemas(
+        &self,
+    ) -> Result<Vec<(SchemaId, String, Option<DatabaseId>, i64, ObjectTimestamps)>, Error> {
+        Ok(self
+            .contents
+            .schemas
+            .iter()
+            .map(|(key, schema)| {
+                // As with the SQLite backend's join, a schema whose database
+                // does not exist has no database.
+                let database_id = schema
+                    .database_id
+                    .filter(|id| self.contents.databases.contains_key(&IdKey { id: *id }));
+                (
+                    SchemaId(key.id),
+                    schema.name.clone(),
+                    database_id.map(DatabaseId),
+                    schema.owner_id,
+                    timestamps(schema.created_at, schema.updated_at),
+                )
+            })
+            .collect())
+    }
+
+    fn load_roles(&self) -> Result<Vec<StoredRole>, Error> {
+        self.contents
+            .roles
+            .iter()
+            .map(|(key, role)| -> Result<_, Error> {
+                let restrictions = match &role.restrictions {
+                    None => RoleRestrictions::default(),
+                    Some(restrictions) => parse_json("role restrictions", restrictions)?,
+                };
+                Ok((
+                    key.id,
+                    role.name.clone(),
+                    restrictions,
+                    role.password.clone(),
+                    timestamps(role.created_at, role.updated_at),
+                ))
+            })
+            .collect()
+    }
+
+    fn load_compute_instances(
+        &self,
+    ) -> Result<Vec<(i64, String, ComputeInstanceConfig, i64, ObjectTimestamps)>, Error> {
+        self.contents
+            .compute_instances
+            .iter()
+            .map(|(key, instance)| -> Result<_, Error> {
+                let config = match &instance.config {
+                    None => ComputeInstanceConfig::Local,
+                    Some(config) => parse_json("compute instance config", config)?,
+                };
+                Ok((
+                    key.id,
+                    instance.name.clone(),
+                    config,
+                    instance.owner_id,
+                    timestamps(instance.created_at, instance.updated_at),
+                ))
+            })
+            .collect()
+    }
+
+    fn load_compute_replicas(&self) -> Result<Vec<StoredComputeReplica>, Error> {
+        self.contents
+            .compute_replicas
+            .iter()
+            .map(|(key, replica)| -> Result<_, Error> {
+                Ok((
+                    key.id,
+                    replica.compute_id,
+                    replica.name.clone(),
+                    parse_json("compute replica config", &replica.config)?,
+                    timestamps(replica.created_at, replica.updated_at),
+                ))
+            })
+            .collect()
+    }
+
+    fn load_system_gids(
+        &self,
+    ) -> Result<BTreeMap<(String, String), (GlobalId, Option<u64>)>, Error> {
+        Ok(self
+            .contents
+            .system_gids
+            .iter()
+            .map(|(key, value)| {
+                (
+                    (key.schema_name.clone(), key.object_name.clone()),
+                    (GlobalId::System(value.id), value.fingerprint),
+                )
+            })
+            .collect())
+    }
+
+    fn load_introspection_source_index_gids(
+        &self,
+        compute_id: i64,
+    ) -> Result<BTreeMap<String, GlobalId>, Error> {
+        Ok(self
+            .contents
+            .introspection_source_indexes
+            .iter()
+            .filter(|(key, _)| key.compute_id == compute_id)
+            .map(|(key, value)| (key.name.clone(), GlobalId::System(value.index_id)))
+            .collect())
+    }
+
+    fn set_system_gids(&mut self, mappings: Vec<(&str, &str, GlobalId, u64)>) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut system_gids = table!(contents, changes, system_gids);
+            for (schema_name, object_name, id, fingerprint) in mappings {
+                system_gids.insert(
+                    SystemGidKey {
+                        schema_name: schema_name.into(),
+                        object_name: object_name.into(),
+                    },
+                    SystemGidValue {
+                        id: system_id(id),
+                        fingerprint: Some(fingerprint),
+                    },
+                );
+            }
+            Ok(())
+        })
+    }
+
+    fn replace_system_gids(
+        &mut self,
+        replacements: Vec<(GlobalId, GlobalId)>,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut comments = table!(contents, changes, comments);
+            let mut privileges = table!(contents, changes, privileges);
+            let mut item_dependencies = table!(contents, changes, item_dependencies);
+            for (old, new) in replacements {
+                comments.rekey(
+                    |key| key.object_id == old,
+                    |key| CommentKey {
+                        object_id: new,
+                        ..key
+                    },
+                );
+                privileges.rekey(
+                    |key| key.is_on(OwnedObjectId::Item(old)),
+                    |key| PrivilegeKey {
+                        object_id: new.to_string(),
+                        ..key
+                    },
+                );
+                item_dependencies.rekey(
+                    |key| key.dependency == old,
+                    |key| ItemDependencyKey {
+                        dependency: new,
+                        ..key
+                    },
+                );
+            }
+            Ok(())
+        })
+    }
+
+    fn set_introspection_source_index_gids(
+        &mut self,
+        mappings: Vec<(i64, &str, GlobalId)>,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut indexes = table!(contents, changes, introspection_source_indexes);
+            for (compute_id, name, index_id) in mappings {
+                indexes.insert(
+                    IntrospectionSourceIndexKey {
+                        compute_id,
+                        name: name.into(),
+                    },
+                    IntrospectionSourceIndexValue {
+                        index_id: system_id(index_id),
+                    },
+                );
+            }
+            Ok(())
+        })
+    }
+
+    fn load_secret_audit_events(&self) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.contents
+            .secret_audit_events
+            .iter()
+            .map(|(key, event)| secret_audit_event(key, event))
+            .collect()
+    }
+
+    fn load_audit_events(&self) -> Result<Vec<AuditEvent>, Error> {
+        self.contents
+            .audit_events
+            .iter()
+            .map(|(key, event)| -> Result<_, Error> {
+                let event_type = match event.event_type.as_str() {
+                    "create" => AuditEventType::Create,
+                    "drop" => AuditEventType::Drop,
+                    "alter" => AuditEventType::Alter,
+                    _ => {
+                        return Err(corruption(format!(
+                            "unknown audit event type {}",
+                            event.event_type
+                        )))
+                    }
+                };
+                Ok(AuditEvent {
+                    id: key.id as u64,
+                    event_type,
+                    object_type: event.object_type.clone(),
+                    object_id: event.object_id.clone(),
+                    name: event.name.clone(),
+                    user: event.user.clone(),
+                    occurred_at: event.occurred_at,
+                    details: parse_json("audit event details", &event.details)?,
+                })
+            })
+            .collect()
+    }
+
+    fn load_comments(&self) -> Result<Vec<StoredComment>, Error> {
+        self.contents
+            .comments
+            .iter()
+            .map(|(key, comment)| -> Result<_, Error> {
+                Ok((
+                    key.object_id,
+                    parse_comment_column_position(key.column_position)?,
+                    comment.clone(),
+                ))
+            })
+            .collect()
+    }
+
+    fn load_role_settings(&self) -> Result<Vec<StoredRoleSetting>, Error> {
+        Ok(self
+            .contents
+            .role_settings
+            .iter()
+            .map(|(key, value)| {
+                (
+                    parse_stored_role_id(key.role_id),
+                    key.name.clone(),
+                    value.clone(),
+                )
+            })
+            .collect())
+    }
+
+    fn load_privileges(&self) -> Result<Vec<StoredPrivilege>, Error> {
+        self.contents
+            .privileges
+            .keys()
+            .map(|key| -> Result<_, Error> {
+                Ok((
+                    OwnedObjectId::parse_stored_id(&key.object_type, &key.object_id)?,
+                    parse_stored_role_id(key.role_id),
+                    parse_privilege(&key.privilege)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn load_dropped_items(&self) -> Result<Vec<StoredDroppedItem>, Error> {
+        let mut dropped_items: Vec<_> = self
+            .contents
+            .dropped_items
+            .iter()
+            .map(|(key, item)| {
+                (
+                    key.gid,
+                    item.name.clone(),
+                    item.item_type.clone(),
+                    item.definition.clone(),
+                    item.dropped_at,
+                )
+            })
+            .collect();
+        dropped_items.sort_by_key(|(_, _, _, _, dropped_at)| *dropped_at);
+        Ok(dropped_items)
+    }
+
+    fn load_references(&self) -> Result<StoredReferences, Error> {
+        let contents = &self.contents;
+        let next_gid = |namespace: GidNamespace| -> Result<u64, Error> {
+            let name = namespace.alloc_table();
+            let next_id = contents
+                .id_alloc
+                .get(name)
+                .ok_or_else(|| corruption(format!("missing {} allocator", name)))?;
+            Ok(next_id.next_id as u64)
+        };
+        Ok(StoredReferences {
+            databases: contents.databases.keys().map(|key| key.id).collect(),
+            schemas: contents
+                .schemas
+                .iter()
+                .map(|(key, schema)| (key.id, schema.database_id))
+                .collect(),
+            items: contents
+                .items
+                .iter()
+                .map(|(key, item)| (key.gid, item.name.clone(), Some(item.schema_id)))
+                .collect(),
+            system_gids: contents
+                .system_gids
+                .iter()
+                .map(|(key, value)| (key.schema_name.clone(), key.object_name.clone(), value.id))
+                .collect(),
+            introspection_source_indexes: contents
+                .introspection_source_indexes
+                .iter()
+                .map(|(key, value)| (key.compute_id, key.name.clone(), value.index_id))
+                .collect(),
+            next_system_gid: next_gid(GidNamespace::System)?,
+            next_user_gid: next_gid(GidNamespace::User)?,
+        })
+    }
+
+    fn allocate_global_id(&mut self, namespace: GidNamespace, amount: u64) -> Result<u64, Error> {
+        self.modify(|contents, changes| {
+            let id = allocate_ids(contents, changes, namespace.alloc_table(), amount)?;
+            Ok(id as u64)
+        })
+    }
+
+    fn transaction(&mut self) -> Result<Box<dyn BackendTransaction + '_>, Error> {
+        Ok(Box::new(StashTransaction {
+            backend: self,
+            changes: RefCell::new(Changes::default()),
+        }))
+    }
+
+    fn backup(&mut self, _path: &Path) -> Result<(), Error> {
+        Err(Error::new(ErrorKind::Backup(format!(
+            "catalogs stored in the STORAGE stash must be backed up by copying {} \
+             while materialized is stopped",
+            self.path.display()
+        ))))
+    }
+}
+
+/// A transaction against a [`StashBackend`], whose changes are held in memory
+/// until it commits.
+struct StashTransaction<'a> {
+    backend: &'a mut StashBackend,
+    changes: RefCell<Changes>,
+}
+
+impl StashTransaction<'_> {
+    /// Makes the changes made by `f` part of the transaction.
+    fn modify<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Contents, &mut Changes) -> Result<R, Error>,
+    {
+        f(&self.backend.contents, &mut self.changes.borrow_mut())
+    }
+
+    /// Removes the items with the given IDs, along with their comments,
+    /// dependencies, and privileges, and records them among the dropped items.
+    fn remove_items_inner(
+        &self,
+        items: &[RemovedItem],
+        dropped_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut removed = HashSet::new();
+            {
+                let mut table = table!(contents, changes, items);
+                let mut dropped_items = Vec::with_capacity(items.len());
+                for (id, name, item_type) in items {
+                    let item = table
+                        .remove(&GidKey { gid: *id })
+                        .ok_or_else(|| SqlCatalogError::UnknownItem(id.to_string()))?;
+                    dropped_items.push((
+                        GidKey { gid: *id },
+                        DroppedItemValue {
+                            name: name.clone(),
+                            item_type: item_type.clone(),
+                            definition: item.definition,
+                            dropped_at,
+                        },
+                    ));
+                    removed.insert(*id);
+                }
+                let mut table = table!(contents, changes, dropped_items);
+                for (key, value) in dropped_items {
+                    table.insert(key, value);
+                }
+            }
+            table!(contents, changes, comments)
+                .remove_where(|key, _| removed.contains(&key.object_id));
+            table!(contents, changes, item_dependencies)
+                .remove_where(|key, _| removed.contains(&key.gid));
+            table!(contents, changes, privileges).remove_where(|key, _| {
+                key.object_type == "item"
+                    && removed.iter().any(|id| key.is_on(OwnedObjectId::Item(*id)))
+            });
+            Ok(())
+        })
+    }
+}
+
+impl BackendTransaction for StashTransaction<'_> {
+    fn load_items(&self) -> Result<Vec<StoredItem>, Error> {
+        self.modify(|contents, changes| {
+            let databases = table!(contents, changes, databases);
+            let schemas = table!(contents, changes, schemas);
+            let items = table!(contents, changes, items);
+            let mut loaded = vec![];
+            for (key, item) in items.iter() {
+                // As with the SQLite backend's joins, items whose schema or
+                // database does not exist are not loaded.
+                let database_id = match schemas.get(&IdKey { id: item.schema_id }) {
+                    Some(SchemaValue {
+                        database_id: Some(database_id),
+                        ..
+                    }) if databases.get(&IdKey { id: *database_id }).is_some() => *database_id,
+                    _ => continue,
+                };
+                loaded.push((
+                    key.gid,
+                    QualifiedObjectName {
+                        qualifiers: ObjectQualifiers {
+                            database_spec: ResolvedDatabaseSpecifier::from(database_id),
+                            schema_spec: SchemaSpecifier::from(item.schema_id),
+                        },
+                        item: item.name.clone(),
+                    },
+                    item.definition.clone(),
+                    item.owner_id,
+                    timestamps(item.created_at, item.updated_at),
+                ));
+            }
+            loaded.sort_by_key(|(id, _, _, _, _)| *id);
+            Ok(loaded)
+        })
+    }
+
+    fn load_item_dependencies(&self) -> Result<BTreeMap<GlobalId, BTreeSet<GlobalId>>, Error> {
+        self.modify(|contents, changes| {
+            let mut dependencies = BTreeMap::<_, BTreeSet<_>>::new();
+            for (key, _) in table!(contents, changes, item_dependencies).iter() {
+                dependencies
+                    .entry(key.gid)
+                    .or_default()
+                    .insert(key.dependency);
+            }
+            Ok(dependencies)
+        })
+    }
+
+    fn insert_database(
+        &mut self,
+        database_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<DatabaseId, Error> {
+        self.modify(|contents, changes| {
+            let databases = table!(contents, changes, databases);
+            if databases
+                .iter()
+                .any(|(_, database)| database.name == database_name)
+            {
+                return Err(Error::new(ErrorKind::DatabaseAlreadyExists(
+                    database_name.to_owned(),
+                )));
+            }
+            let id = allocate_ids(contents, changes, DATABASE_ID_ALLOC, 1)?;
+            table!(contents, changes, databases).insert(
+                IdKey { id },
+                DatabaseValue {
+                    name: database_name.into(),
+                    owner_id,
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            Ok(DatabaseId(id))
+        })
+    }
+
+    fn insert_schema(
+        &mut self,
+        database_id: DatabaseId,
+        schema_name: &str,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<SchemaId, Error> {
+        self.modify(|contents, changes| {
+            let schemas = table!(contents, changes, schemas);
+            if schemas.iter().any(|(_, schema)| {
+                schema.database_id == Some(database_id.0) && schema.name == schema_name
+            }) {
+                return Err(Error::new(ErrorKind::SchemaAlreadyExists(
+                    schema_name.to_owned(),
+                )));
+            }
+            let id = allocate_ids(contents, changes, SCHEMA_ID_ALLOC, 1)?;
+            table!(contents, changes, schemas).insert(
+                IdKey { id },
+                SchemaValue {
+                    database_id: Some(database_id.0),
+                    name: schema_name.into(),
+                    owner_id,
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            Ok(SchemaId(id))
+        })
+    }
+
+    fn insert_role(
+        &mut self,
+        role_name: &str,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        self.modify(|contents, changes| {
+            let roles = table!(contents, changes, roles);
+            if roles.iter().any(|(_, role)| role.name == role_name) {
+                return Err(Error::new(ErrorKind::RoleAlreadyExists(
+                    role_name.to_owned(),
+                )));
+            }
+            let id = allocate_ids(contents, changes, ROLE_ID_ALLOC, 1)?;
+            table!(contents, changes, roles).insert(
+                IdKey { id },
+                RoleValue {
+                    name: role_name.into(),
+                    restrictions: serialize_restrictions(restrictions),
+                    password: password.map(|password| password.into()),
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            Ok(id)
+        })
+    }
+
+    fn update_role(
+        &mut self,
+        id: i64,
+        restrictions: &RoleRestrictions,
+        password: Option<&str>,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut roles = table!(contents, changes, roles);
+            let mut role = roles
+                .get(&IdKey { id })
+                .cloned()
+                .ok_or_else(|| SqlCatalogError::UnknownRole(id.to_string()))?;
+            role.restrictions = serialize_restrictions(restrictions);
+            role.password = password.map(|password| password.into());
+            role.updated_at = updated_at;
+            roles.insert(IdKey { id }, role);
+            Ok(())
+        })
+    }
+
+    fn insert_compute_instance(
+        &mut self,
+        cluster_name: &str,
+        config: &ComputeInstanceConfig,
+        introspection_sources: &Vec<(&'static BuiltinLog, GlobalId)>,
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        self.modify(|contents, changes| {
+            let instances = table!(contents, changes, compute_instances);
+            if instances
+                .iter()
+                .any(|(_, instance)| instance.name == cluster_name)
+            {
+                return Err(Error::new(ErrorKind::ClusterAlreadyExists(
+                    cluster_name.to_owned(),
+                )));
+            }
+            let id = allocate_ids(contents, changes, COMPUTE_ID_ALLOC, 1)?;
+            table!(contents, changes, compute_instances).insert(
+                IdKey { id },
+                ComputeInstanceValue {
+                    name: cluster_name.into(),
+                    config: Some(to_json(config)),
+                    owner_id,
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            let mut indexes = table!(contents, changes, introspection_source_indexes);
+            for (builtin, index_id) in introspection_sources {
+                indexes.insert(
+                    IntrospectionSourceIndexKey {
+                        compute_id: id,
+                        name: builtin.name.into(),
+                    },
+                    IntrospectionSourceIndexValue {
+                        index_id: system_id(*index_id),
+                    },
+                );
+            }
+            Ok(id)
+        })
+    }
+
+    fn update_compute_instance_config(
+        &mut self,
+        id: ComputeInstanceId,
+        config: &ComputeInstanceConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut instances = table!(contents, changes, compute_instances);
+            // As in the SQLite backend, updating a compute instance that does
+            // not exist has no effect.
+            if let Some(mut instance) = instances.get(&IdKey { id }).cloned() {
+                instance.config = Some(to_json(config));
+                instance.updated_at = updated_at;
+                instances.insert(IdKey { id }, instance);
+            }
+            Ok(())
+        })
+    }
+
+    fn insert_compute_replica(
+        &mut self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+        config: &ComputeInstanceReplicaConfig,
+        now: EpochMillis,
+    ) -> Result<i64, Error> {
+        self.modify(|contents, changes| {
+            let replicas = table!(contents, changes, compute_replicas);
+            if replicas.iter().any(|(_, replica)| {
+                replica.compute_id == compute_id && replica.name == replica_name
+            }) {
+                return Err(Error::new(ErrorKind::ClusterReplicaAlreadyExists(
+                    replica_name.to_owned(),
+                )));
+            }
+            let id = allocate_ids(contents, changes, REPLICA_ID_ALLOC, 1)?;
+            table!(contents, changes, compute_replicas).insert(
+                IdKey { id },
+                ComputeReplicaValue {
+                    compute_id,
+                    name: replica_name.into(),
+                    config: to_json(config),
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+            Ok(id)
+        })
+    }
+
+    fn update_compute_replica_config(
+        &mut self,
+        id: i64,
+        config: &ComputeInstanceReplicaConfig,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut replicas = table!(contents, changes, compute_replicas);
+            let mut replica = replicas
+                .get(&IdKey { id })
+                .cloned()
+                .ok_or_else(|| SqlCatalogError::UnknownComputeInstanceReplica(id.to_string()))?;
+            replica.config = to_json(config);
+            replica.updated_at = updated_at;
+            replicas.insert(IdKey { id }, replica);
+            Ok(())
+        })
+    }
+
+    fn remove_compute_replica(
+        &self,
+        compute_id: ComputeInstanceId,
+        replica_name: &str,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut replicas = table!(contents, changes, compute_replicas);
+            let key = replicas
+                .find(|_, replica| replica.compute_id == compute_id && replica.name == replica_name)
+                .ok_or_else(|| {
+                    SqlCatalogError::UnknownComputeInstanceReplica(replica_name.to_owned())
+                })?;
+            replicas.remove(&key);
+            Ok(())
+        })
+    }
+
+    fn insert_item(
+        &self,
+        id: GlobalId,
+        schema_id: SchemaId,
+        item_name: &str,
+        item: &[u8],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        self.insert_items(
+            &[(id, schema_id, item_name.to_owned(), item.to_vec())],
+            owner_id,
+            now,
+        )
+    }
+
+    fn insert_items(
+        &self,
+        items: &[NewItem],
+        owner_id: i64,
+        now: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut table = table!(contents, changes, items);
+            let mut names: HashSet<_> = table
+                .iter()
+                .map(|(_, item)| (item.schema_id, item.name.clone()))
+                .collect();
+            for (id, schema_id, item_name, item) in items {
+                let key = GidKey { gid: *id };
+                if table.get(&key).is_some() || !names.insert((schema_id.0, item_name.clone())) {
+                    return Err(Error::new(ErrorKind::ItemAlreadyExists(item_name.clone())));
+                }
+                table.insert(
+                    key,
+                    ItemValue {
+                        schema_id: schema_id.0,
+                        name: item_name.clone(),
+                        definition: item.clone(),
+                        owner_id,
+                        created_at: now,
+                        updated_at: now,
+                    },
+                );
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_database(&self, id: &DatabaseId) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, databases)
+                .remove(&IdKey { id: id.0 })
+                .ok_or_else(|| SqlCatalogError::UnknownDatabase(id.to_string()))?;
+            let object = OwnedObjectId::Database(*id);
+            table!(contents, changes, privileges).remove_where(|key, _| key.is_on(object));
+            Ok(())
+        })
+    }
+
+    fn remove_schema(&self, database_id: &DatabaseId, schema_id: &SchemaId) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut schemas = table!(contents, changes, schemas);
+            let key = IdKey { id: schema_id.0 };
+            match schemas.get(&key) {
+                Some(schema) if schema.database_id == Some(database_id.0) => {
+                    schemas.remove(&key);
+                }
+                _ => {
+                    return Err(SqlCatalogError::UnknownSchema(format!(
+                        "{}.{}",
+                        database_id.0, schema_id.0
+                    ))
+                    .into())
+                }
+            }
+            let object = OwnedObjectId::Schema(*schema_id);
+            table!(contents, changes, privileges).remove_where(|key, _| key.is_on(object));
+            Ok(())
+        })
+    }
+
+    fn remove_role(&self, name: &str) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut roles = table!(contents, changes, roles);
+            let key = roles
+                .find(|_, role| role.name == name)
+                .ok_or_else(|| SqlCatalogError::UnknownRole(name.to_owned()))?;
+            roles.remove(&key);
+            table!(contents, changes, role_settings)
+                .remove_where(|setting, _| setting.role_id == key.id);
+            table!(contents, changes, privileges)
+                .remove_where(|privilege, _| privilege.role_id == key.id);
+            Ok(())
+        })
+    }
+
+    fn remove_compute_instance(&self, name: &str) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut instances = table!(contents, changes, compute_instances);
+            let key = instances
+                .find(|_, instance| instance.name == name)
+                .ok_or_else(|| SqlCatalogError::UnknownComputeInstance(name.to_owned()))?;
+            instances.remove(&key);
+            table!(contents, changes, compute_replicas)
+                .remove_where(|_, replica| replica.compute_id == key.id);
+            Ok(())
+        })
+    }
+
+    fn remove_item(&self, item: &RemovedItem, dropped_at: EpochMillis) -> Result<(), Error> {
+        self.remove_items_inner(std::slice::from_ref(item), dropped_at)
+    }
+
+    fn remove_items(&self, items: &[RemovedItem], dropped_at: EpochMillis) -> Result<(), Error> {
+        self.remove_items_inner(items, dropped_at)
+    }
+
+    fn remove_dropped_item(&self, id: GlobalId) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, dropped_items)
+                .remove(&GidKey { gid: id })
+                .ok_or_else(|| SqlCatalogError::UnknownItem(id.to_string()))?;
+            Ok(())
+        })
+    }
+
+    fn purge_dropped_items(&self, dropped_before: EpochMillis) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, dropped_items)
+                .remove_where(|_, item| item.dropped_at < dropped_before);
+            Ok(())
+        })
+    }
+
+    fn update_item(
+        &self,
+        id: GlobalId,
+        item_name: &str,
+        item: &[u8],
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.update_items(&[(id, item_name.to_owned(), item.to_vec())], updated_at)
+    }
+
+    fn update_items(&self, updates: &[ItemUpdate], updated_at: EpochMillis) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut items = table!(contents, changes, items);
+            for (id, item_name, definition) in updates {
+                let key = GidKey { gid: *id };
+                let mut item = items
+                    .get(&key)
+                    .cloned()
+                    .ok_or_else(|| SqlCatalogError::UnknownItem(id.to_string()))?;
+                if item.name != *item_name
+                    && items.iter().any(|(_, other)| {
+                        other.schema_id == item.schema_id && other.name == *item_name
+                    })
+                {
+                    return Err(Error::new(ErrorKind::ItemAlreadyExists(item_name.clone())));
+                }
+                item.name = item_name.clone();
+                item.definition = definition.clone();
+                item.updated_at = updated_at;
+                items.insert(key, item);
+            }
+            Ok(())
+        })
+    }
+
+    fn set_item_dependencies(&self, dependencies: &[ItemDependencies]) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut table = table!(contents, changes, item_dependencies);
+            let ids: HashSet<_> = dependencies.iter().map(|(id, _)| *id).collect();
+            table.remove_where(|key, _| ids.contains(&key.gid));
+            for (id, uses) in dependencies {
+                for dependency in uses {
+                    table.insert(
+                        ItemDependencyKey {
+                            gid: *id,
+                            dependency: *dependency,
+                        },
+                        (),
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn rename_database(
+        &self,
+        id: &DatabaseId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut databases = table!(contents, changes, databases);
+            let key = IdKey { id: id.0 };
+            let mut database = databases
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| SqlCatalogError::UnknownDatabase(id.to_string()))?;
+            if databases
+                .iter()
+                .any(|(other, database)| *other != key && database.name == to_name)
+            {
+                return Err(Error::new(ErrorKind::DatabaseAlreadyExists(
+                    to_name.to_owned(),
+                )));
+            }
+            database.name = to_name.into();
+            database.updated_at = updated_at;
+            databases.insert(key, database);
+            Ok(())
+        })
+    }
+
+    fn rename_schema(
+        &self,
+        database_id: &DatabaseId,
+        schema_id: &SchemaId,
+        to_name: &str,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut schemas = table!(contents, changes, schemas);
+            let key = IdKey { id: schema_id.0 };
+            let mut schema = match schemas.get(&key) {
+                Some(schema) if schema.database_id == Some(database_id.0) => schema.clone(),
+                _ => {
+                    return Err(SqlCatalogError::UnknownSchema(format!(
+                        "{}.{}",
+                        database_id.0, schema_id.0
+                    ))
+                    .into())
+                }
+            };
+            if schemas.iter().any(|(other, schema)| {
+                *other != key && schema.database_id == Some(database_id.0) && schema.name == to_name
+            }) {
+                return Err(Error::new(ErrorKind::SchemaAlreadyExists(
+                    to_name.to_owned(),
+                )));
+            }
+            schema.name = to_name.into();
+            schema.updated_at = updated_at;
+            schemas.insert(key, schema);
+            Ok(())
+        })
+    }
+
+    fn update_owner(
+        &self,
+        object: OwnedObjectId,
+        owner_id: i64,
+        updated_at: EpochMillis,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let updated = match object {
+                OwnedObjectId::Database(id) => {
+                    table!(contents, changes, databases).update(&IdKey { id: id.0 }, |database| {
+                        database.owner_id = owner_id;
+                        database.updated_at = updated_at;
+                    })
+                }
+                OwnedObjectId::Schema(id) => {
+                    table!(contents, changes, schemas).update(&IdKey { id: id.0 }, |schema| {
+                        schema.owner_id = owner_id;
+                        schema.updated_at = updated_at;
+                    })
+                }
+                OwnedObjectId::ComputeInstance(id) => table!(contents, changes, compute_instances)
+                    .update(&IdKey { id }, |instance| {
+                        instance.owner_id = owner_id;
+                        instance.updated_at = updated_at;
+                    }),
+                OwnedObjectId::Item(id) => {
+                    table!(contents, changes, items).update(&GidKey { gid: id }, |item| {
+                        item.owner_id = owner_id;
+                        item.updated_at = updated_at;
+                    })
+                }
+            };
+            if updated {
+                Ok(())
+            } else {
+                Err(object.unknown())
+            }
+        })
+    }
+
+    fn insert_audit_event(&mut self, event: &mut AuditEvent) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let id = allocate_ids(contents, changes, AUDIT_EVENT_ID_ALLOC, 1)?;
+            table!(contents, changes, audit_events).insert(
+                IdKey { id },
+                AuditEventValue {
+                    event_type: event.event_type.as_str().into(),
+                    object_type: event.object_type.clone(),
+                    object_id: event.object_id.clone(),
+                    name: event.name.clone(),
+                    user: event.user.clone(),
+                    occurred_at: event.occurred_at,
+                    details: event.details.to_string(),
+                },
+            );
+            event.id = id as u64;
+            Ok(())
+        })
+    }
+
+    fn insert_secret_audit_events(&mut self, events: &mut [SecretAuditEvent]) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            for event in events.iter_mut() {
+                let id = allocate_ids(contents, changes, SECRET_AUDIT_EVENT_ID_ALLOC, 1)?;
+                table!(contents, changes, secret_audit_events)
+                    .insert(IdKey { id }, secret_audit_event_value(event));
+                event.id = id as u64;
+            }
+            Ok(())
+        })
+    }
+
+    fn purge_secret_audit_events(&self, retained: u64) -> Result<Vec<SecretAuditEvent>, Error> {
+        self.modify(|contents, changes| {
+            let mut table = table!(contents, changes, secret_audit_events);
+            // Events are forgotten in order of their IDs, which increase with
+            // each recorded event.
+            let mut events: Vec<_> = table
+                .iter()
+                .map(|(key, event)| (*key, event.clone()))
+                .collect();
+            events.sort_by_key(|(key, _)| *key);
+            let retained = usize::try_from(retained).unwrap_or(usize::MAX);
+            let purged = events.len().saturating_sub(retained);
+            events
+                .into_iter()
+                .take(purged)
+                .map(|(key, event)| {
+                    table.remove(&key);
+                    secret_audit_event(&key, &event)
+                })
+                .collect()
+        })
+    }
+
+    fn insert_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut comments = table!(contents, changes, comments);
+            let key = CommentKey {
+                object_id: id,
+                column_position: comment_column_position(column_position),
+            };
+            if comments.get(&key).is_some() {
+                let detail = match column_position {
+                    None => format!("duplicate comment on {}", id),
+                    Some(p) => format!("duplicate comment on column {} of {}", p, id),
+                };
+                return Err(corruption(detail));
+            }
+            comments.insert(key, comment.into());
+            Ok(())
+        })
+    }
+
+    fn update_comment(
+        &self,
+        id: GlobalId,
+        column_position: Option<usize>,
+        comment: &str,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let key = CommentKey {
+                object_id: id,
+                column_position: comment_column_position(column_position),
+            };
+            let updated = table!(contents, changes, comments).update(&key, |existing| {
+                *existing = comment.into();
+            });
+            if updated {
+                Ok(())
+            } else {
+                Err(unknown_comment(id, column_position))
+            }
+        })
+    }
+
+    fn delete_comment(&self, id: GlobalId, column_position: Option<usize>) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let key = CommentKey {
+                object_id: id,
+                column_position: comment_column_position(column_position),
+            };
+            match table!(contents, changes, comments).remove(&key) {
+                Some(_) => Ok(()),
+                None => Err(unknown_comment(id, column_position)),
+            }
+        })
+    }
+
+    fn set_role_setting(
+        &self,
+        role_id: Option<i64>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            let mut role_settings = table!(contents, changes, role_settings);
+            let key = RoleSettingKey {
+                role_id: stored_role_id(role_id),
+                name: name.into(),
+            };
+            match value {
+                Some(value) => role_settings.insert(key, value.into()),
+                None => {
+                    role_settings.remove(&key);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn grant_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, privileges)
+                .insert(PrivilegeKey::new(object, role_id, privilege), ());
+            Ok(())
+        })
+    }
+
+    fn revoke_privilege(
+        &self,
+        object: OwnedObjectId,
+        role_id: Option<i64>,
+        privilege: Privilege,
+    ) -> Result<(), Error> {
+        self.modify(|contents, changes| {
+            table!(contents, changes, privileges)
+                .remove(&PrivilegeKey::new(object, role_id, privilege));
+            Ok(())
+        })
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), Error> {
+        let StashTransaction { backend, changes } = *self;
+        backend.commit_changes(changes.into_inner())
+    }
+}
+
+/// A view of a collection's committed contents as modified by uncommitted
+/// changes.
+struct Table<'a, K, V> {
+    committed: &'a BTreeMap<K, V>,
+    changes: &'a mut BTreeMap<K, Option<V>>,
+}
+
+impl<K, V> Table<'_, K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn get(&self, key: &K) -> Option<&V> {
+        match self.changes.get(key) {
+            Some(value) => value.as_ref(),
+            None => self.committed.get(key),
+        }
+    }
+
+    /// Iterates over the entries in no particular order.
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let committed: &BTreeMap<K, V> = self.committed;
+        let changes = &*self.changes;
+        committed
+            .iter()
+            .filter(move |(key, _)| !changes.contains_key(key))
+            .chain(
+                changes
+                    .iter()
+                    .filter_map(|(key, value)| value.as_ref().map(|value| (key, value))),
+            )
+    }
+
+    /// Returns the key of an entry for which `f` returns true, if any.
+    fn find<F>(&self, f: F) -> Option<K>
+    where
+        F: Fn(&K, &V) -> bool,
+    {
+        self.iter()
+            .find(|(key, value)| f(key, value))
+            .map(|(key, _)| key.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.changes.insert(key, Some(value));
+    }
+
+    /// Removes the entry for `key`, returning its value, if any.
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.get(key).cloned();
+        if value.is_some() {
+            self.changes.insert(key.clone(), None);
+        }
+        value
+    }
+
+    /// Removes every entry for which `f` returns true.
+    fn remove_where<F>(&mut self, f: F)
+    where
+        F: Fn(&K, &V) -> bool,
+    {
+        let keys: Vec<_> = self
+            .iter()
+            .filter(|(key, value)| f(key, value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            self.changes.insert(key, None);
+        }
+    }
+
+    /// Applies `f` to the value of the entry for `key`, returning whether
+    /// there is such an entry.
+    fn update<F>(&mut self, key: &K, f: F) -> bool
+    where
+        F: FnOnce(&mut V),
+    {
+        match self.get(key).cloned() {
+            Some(mut value) => {
+                f(&mut value);
+                self.insert(key.clone(), value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the key of every entry for which `matches` returns true with
+    /// the key returned by `rekey`.
+    fn rekey<F, G>(&mut self, matches: F, rekey: G)
+    where
+        F: Fn(&K) -> bool,
+        G: Fn(K) -> K,
+    {
+        let entries: Vec<_> = self
+            .iter()
+            .filter(|(key, _)| matches(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        for (key, _) in &entries {
+            self.changes.insert(key.clone(), None);
+        }
+        for (key, value) in entries {
+            self.insert(rekey(key), value);
+        }
+    }
+}
+
+/// Loads the consolidated contents of the collection `name`, each of whose
+/// keys must have exactly one value.
+fn load_collection<K, V>(
+    stash: &mz_stash::Sqlite,
+    collection: StashCollection<K, V>,
+    name: &str,
+) -> Result<BTreeMap<K, V>, Error>
+where
+    K: Codec + Ord,
+    V: Codec + Ord,
+{
+    stash.consolidate(collection)?;
+    let mut contents = BTreeMap::new();
+    for ((key, value), _time, diff) in stash.iter(collection)? {
+        if diff != 1 || contents.insert(key, value).is_some() {
+            return Err(corruption(format!(
+                "catalog-{} has a key with other than one value",
+                name
+            )));
+        }
+    }
+    Ok(contents)
+}
+
+/// Returns the batch that writes `changes` to `collection`, whose committed
+/// contents are `committed`, at `timestamp`, and then advances the
+/// collection's frontiers past `timestamp`. Returns `None` if there are no
+/// changes.
+fn make_batch<K, V>(
+    collection: StashCollection<K, V>,
+    committed: &BTreeMap<K, V>,
+    changes: &BTreeMap<K, Option<V>>,
+    timestamp: Timestamp,
+) -> Option<AppendBatch>
+where
+    K: Codec + Ord,
+    V: Codec + PartialEq,
+{
+    if changes.is_empty() {
+        return None;
+    }
+    let frontier = Antichain::from_elem(timestamp + 1);
+    let mut batch = collection.make_batch(frontier.clone(), frontier);
+    for (key, value) in changes {
+        let old = committed.get(key);
+        if old == value.as_ref() {
+            continue;
+        }
+        if let Some(old) = old {
+            collection.append_to_batch(&mut batch, key, old, timestamp, -1);
+        }
+        if let Some(value) = value {
+            collection.append_to_batch(&mut batch, key, value, timestamp, 1);
+        }
+    }
+    Some(batch)
+}
+
+fn apply_changes<K: Ord, V>(committed: &mut BTreeMap<K, V>, changes: BTreeMap<K, Option<V>>) {
+    for (key, value) in changes {
+        match value {
+            Some(value) => {
+                committed.insert(key, value);
+            }
+            None => {
+                committed.remove(&key);
+            }
+        }
+    }
+}
+
+/// Allocates `amount` consecutive IDs from the allocator `name`, returning
+/// the first ID of the block.
+fn allocate_ids(
+    contents: &Contents,
+    changes: &mut Changes,
+    name: &str,
+    amount: u64,
+) -> Result<i64, Error> {
+    let mut id_alloc = table!(contents, changes, id_alloc);
+    let name = name.to_owned();
+    let id = id_alloc
+        .get(&name)
+        .ok_or_else(|| corruption(format!("missing {} allocator", name)))?
+        .next_id;
+    id_alloc.insert(
+        name,
+        IdAllocValue {
+            next_id: advance_gid(id, amount)?,
+        },
+    );
+    Ok(id)
+}
+
+/// Reads the contents of the catalog stored by `backend`, as they are stored
+/// in the stash.
+///
+/// The ID allocators are set to allocate the ID after the greatest ID of each
+/// kind of object, or, for global IDs, the next ID that `backend` would have
+/// allocated.
+fn read_catalog(backend: &mut dyn Backend) -> Result<Contents, Error> {
+    let mut contents = Contents::default();
+
+    // The items are only read within a transaction, which is rolled back.
+    {
+        let tx = backend.transaction()?;
+        for (id, name, definition, owner_id, timestamps) in tx.load_items()? {
+            contents.items.insert(
+                GidKey { gid: id },
+                ItemValue {
+                    schema_id: SchemaId::from(&name.qualifiers.schema_spec).0,
+                    name: name.item,
+                    definition,
+                    owner_id,
+                    created_at: timestamps.created_at,
+                    updated_at: timestamps.updated_at,
+                },
+            );
+        }
+        for (id, dependencies) in tx.load_item_dependencies()? {
+            for dependency in dependencies {
+                contents.item_dependencies.insert(
+                    ItemDependencyKey {
+                        gid: id,
+                        dependency,
+                    },
+                    (),
+                );
+            }
+        }
+    }
+
+    contents.settings = backend.load_settings()?.into_iter().collect();
+    for (id, name, owner_id, timestamps) in backend.load_databases()? {
+        contents.databases.insert(
+            IdKey { id: id.0 },
+            DatabaseValue {
+                name,
+                owner_id,
+                created_at: timestamps.created_at,
+                updated_at: timestamps.updated_at,
+            },
+        );
+    }
+    for (id, name, database_id, owner_id, timestamps) in backend.load_schemas()? {
+        contents.schemas.insert(
+            IdKey { id: id.0 },
+            SchemaValue {
+                database_id: database_id.map(|id| id.0),
+                name,
+                owner_id,
+                created_at: timestamps.created_at,
+                updated_at: timestamps.updated_at,
+            },
+        );
+    }
+    for (id, name, restrictions, password, timestamps) in backend.load_roles()? {
+        contents.roles.insert(
+            IdKey { id },
+            RoleValue {
+                name,
+                restrictions: serialize_restrictions(&restrictions),
+                password,
+                created_at: timestamps.created_at,
+                updated_at: timestamps.updated_at,
+            },
+        );
+    }
+    for (id, name, config, owner_id, timestamps) in backend.load_compute_instances()? {
+        contents.compute_instances.insert(
+            IdKey { id },
+            ComputeInstanceValue {
+                name,
+                config: Some(to_json(&config)),
+                owner_id,
+                created_at: timestamps.created_at,
+                updated_at: timestamps.updated_at,
+            },
+        );
+    }
+    for (id, compute_id, name, config, timestamps) in backend.load_compute_replicas()? {
+        contents.compute_replicas.insert(
+            IdKey { id },
+            ComputeReplicaValue {
+                compute_id,
+                name,
+                config: to_json(&config),
+                created_at: timestamps.created_at,
+                updated_at: timestamps.updated_at,
+            },
+        );
+    }
+    for ((schema_name, object_name), (id, fingerprint)) in backend.load_system_gids()? {
+        contents.system_gids.insert(
+            SystemGidKey {
+                schema_name,
+                object_name,
+            },
+            SystemGidValue {
+                id: system_id(id),
+                fingerprint,
+            },
+        );
+    }
+    for (id, column_position, comment) in backend.load_comments()? {
+        contents.comments.insert(
+            CommentKey {
+                object_id: id,
+                column_position: comment_column_position(column_position),
+            },
+            comment,
+        );
+    }
+    for (role_id, name, value) in backend.load_role_settings()? {
+        contents.role_settings.insert(
+            RoleSettingKey {
+                role_id: stored_role_id(role_id),
+                name,
+            },
+            value,
+        );
+    }
+    for (object, role_id, privilege) in backend.load_privileges()? {
+        contents
+            .privileges
+            .insert(PrivilegeKey::new(object, role_id, privilege), ());
+    }
+    for (id, name, item_type, definition, dropped_at) in backend.load_dropped_items()? {
+        contents.dropped_items.insert(
+            GidKey { gid: id },
+            DroppedItemValue {
+                name,
+                item_type,
+                definition,
+                dropped_at,
+            },
+        );
+    }
+    for event in backend.load_audit_events()? {
+        contents.audit_events.insert(
+            IdKey {
+                id: event.id as i64,
+            },
+            AuditEventValue {
+                event_type: event.event_type.as_str().into(),
+                object_type: event.object_type,
+                object_id: event.object_id,
+                name: event.name,
+                user: event.user,
+                occurred_at: event.occurred_at,
+                details: event.details.to_string(),
+            },
+        );
+    }
+    for event in backend.load_secret_audit_events()? {
+        contents.secret_audit_events.insert(
+            IdKey {
+                id: event.id as i64,
+            },
+            secret_audit_event_value(&event),
+        );
+    }
+
+    let references = backend.load_references()?;
+    for (compute_id, name, index_id) in references.introspection_source_indexes {
+        contents.introspection_source_indexes.insert(
+            IntrospectionSourceIndexKey { compute_id, name },
+            IntrospectionSourceIndexValue { index_id },
+        );
+    }
+
+    fn next_object_id<V>(objects: &BTreeMap<IdKey, V>) -> i64 {
+        objects.keys().next_back().map_or(1, |key| key.id + 1)
+    }
+    let next_ids = [
+        (DATABASE_ID_ALLOC, next_object_id(&contents.databases)),
+        (SCHEMA_ID_ALLOC, next_object_id(&contents.schemas)),
+        (ROLE_ID_ALLOC, next_object_id(&contents.roles)),
+        (
+            COMPUTE_ID_ALLOC,
+            next_object_id(&contents.compute_instances),
+        ),
+        (REPLICA_ID_ALLOC, next_object_id(&contents.compute_replicas)),
+        (AUDIT_EVENT_ID_ALLOC, next_object_id(&contents.audit_events)),
+        (
+            SECRET_AUDIT_EVENT_ID_ALLOC,
+            next_object_id(&contents.secret_audit_events),
+        ),
+        (
+            GidNamespace::System.alloc_table(),
+            references.next_system_gid as i64,
+        ),
+        (
+            GidNamespace::User.alloc_table(),
+            references.next_user_gid as i64,
+        ),
+    ];
+    for (name, next_id) in next_ids {
+        contents
+            .id_alloc
+            .insert(name.into(), IdAllocValue { next_id });
+    }
+
+    Ok(contents)
+}
+
+fn timestamps(created_at: EpochMillis, updated_at: EpochMillis) -> ObjectTimestamps {
+    ObjectTimestamps {
+        created_at,
+        updated_at,
+    }
+}
+
+/// Returns the ID of a system object.
+///
+/// Panics if `id` is not a system ID.
+fn system_id(id: GlobalId) -> u64 {
+    match id {
+        GlobalId::System(id) => id,
+        _ => panic!("non-system id provided"),
+    }
+}
+
+/// Serializes restrictions as JSON, or as `None` if they are the defaults, as
+/// in the SQLite backend's `roles` table.
+fn serialize_restrictions(restrictions: &RoleRestrictions) -> Option<String> {
+    if *restrictions == RoleRestrictions::default() {
+        None
+    } else {
+        Some(to_json(restrictions))
+    }
+}
+
+fn secret_audit_event_value(event: &SecretAuditEvent) -> SecretAuditEventValue {
+    SecretAuditEventValue {
+        secret_id: event.secret_id,
+        operation: event.operation.as_str().into(),
+        version: event.version.map(|v| v.0),
+        user: event.user.clone(),
+        occurred_at: event.occurred_at,
+        error: event.error.clone(),
+    }
+}
+
+fn secret_audit_event(
+    key: &IdKey,
+    event: &SecretAuditEventValue,
+) -> Result<SecretAuditEvent, Error> {
+    let operation = match event.operation.as_str() {
+        "ensure" => SecretAuditOperation::Ensure,
+        "delete" => SecretAuditOperation::Delete,
+        _ => {
+            return Err(corruption(format!(
+                "unknown secret audit operation {}",
+                event.operation
+            )))
+        }
+    };
+    Ok(SecretAuditEvent {
+        id: key.id as u64,
+        secret_id: event.secret_id,
+        operation,
+        version: event.version.map(SecretVersion),
+        user: event.user.clone(),
+        occurred_at: event.occurred_at,
+        error: event.error.clone(),
+    })
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("catalog serialization cannot fail")
+}
+
+/// Parses the stored JSON representation of `what`.
+fn parse_json<T: DeserializeOwned>(what: &str, value: &str) -> Result<T, Error> {
+    serde_json::from_str(value).map_err(|e| corruption(format!("invalid {}: {}", what, e)))
+}
+
+fn corruption(detail: String) -> Error {
+    Error::new(ErrorKind::Corruption { detail })
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::catalog::storage::Connection;
+
+    use super::*;
+
+    fn is_kind<T>(res: Result<T, Error>, f: impl FnOnce(&ErrorKind) -> bool) -> bool {
+        match res {
+            Err(e) => f(&e.kind),
+            Ok(_) => false,
+        }
+    }
+
+    /// Returns the ID of the `public` schema of the `materialize` database.
+    fn public_schema(backend: &dyn Backend) -> Result<(DatabaseId, SchemaId), Error> {
+        let (database_id, ..) = backend
+            .load_databases()?
+            .into_iter()
+            .find(|(_, name, _, _)| name == "materialize")
+            .expect("materialize database exists");
+        let (schema_id, ..) = backend
+            .load_schemas()?
+            .into_iter()
+            .find(|(_, name, id, _, _)| name == "public" && *id == Some(database_id))
+            .expect("public schema exists");
+        Ok((database_id, schema_id))
+    }
+
+    /// Returns the ID that follows the user ID `id`.
+    fn id_after(id: GlobalId) -> u64 {
+        match id {
+            GlobalId::User(id) => id + 1,
+            _ => panic!("not a user id"),
+        }
+    }
+
+    /// Returns everything that `backend` loads, each object formatted on its
+    /// own line, in a deterministic order.
+    fn snapshot(backend: &mut dyn Backend) -> Result<Vec<String>, Error> {
+        fn lines<T: fmt::Debug>(kind: &str, objects: Vec<T>) -> Vec<String> {
+            let mut lines: Vec<_> = objects
+                .iter()
+                .map(|o| format!("{}: {:?}", kind, o))
+                .collect();
+            lines.sort();
+            lines
+        }
+
+        let mut snapshot = vec![];
+        {
+            let tx = backend.transaction()?;
+            snapshot.extend(lines("item", tx.load_items()?));
+            snapshot.extend(lines(
+                "dependencies",
+                tx.load_item_dependencies()?.into_iter().collect(),
+            ));
+        }
+        snapshot.extend(lines("setting", backend.load_settings()?));
+        snapshot.extend(lines("database", backend.load_databases()?));
+        snapshot.extend(lines("schema", backend.load_schemas()?));
+        snapshot.extend(lines("role", backend.load_roles()?));
+        let instances = backend.load_compute_instances()?;
+        for (id, ..) in &instances {
+            snapshot.extend(lines(
+                "introspection source index",
+                backend
+                    .load_introspection_source_index_gids(*id)?
+                    .into_iter()
+                    .collect(),
+            ));
+        }
+        snapshot.extend(lines("compute instance", instances));
+        snapshot.extend(lines("replica", backend.load_compute_replicas()?));
+        snapshot.extend(lines(
+            "system gid",
+            backend.load_system_gids()?.into_iter().collect(),
+        ));
+        snapshot.extend(lines("comment", backend.load_comments()?));
+        snapshot.extend(lines("role setting", backend.load_role_settings()?));
+        snapshot.extend(lines("privilege", backend.load_privileges()?));
+        snapshot.extend(lines("dropped item", backend.load_dropped_items()?));
+        snapshot.extend(lines("audit event", backend.load_audit_events()?));
+        snapshot.extend(lines(
+            "secret audit event",
+            backend.load_secret_audit_events()?,
+        ));
+        let references = backend.load_references()?;
+        snapshot.push(format!(
+            "next gids: {} {}",
+            references.next_system_gid, references.next_user_gid
+        ));
+        Ok(snapshot)
+    }
+
+    #[test]
+    fn test_stash_backend_reopen() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+
+        let mut backend = StashBackend::open(data_dir.path())?;
+        backend.set_setting("test", "\"a\"")?;
+        let id = backend.allocate_global_id(GidNamespace::User, 2)?;
+        let mut tx = backend.transaction()?;
+        let database_id = tx.insert_database("committed", 1, 0)?;
+        tx.commit()?;
+        let mut tx = backend.transaction()?;
+        tx.insert_database("rolled_back", 1, 0)?;
+        drop(tx);
+        drop(backend);
+
+        let mut backend = StashBackend::open(data_dir.path())?;
+        assert_eq!(backend.setting("test")?.as_deref(), Some("\"a\""));
+        assert_eq!(backend.allocate_global_id(GidNamespace::User, 1)?, id + 2);
+        let databases: Vec<_> = backend
+            .load_databases()?
+            .into_iter()
+            .map(|(id, name, _, _)| (id, name))
+            .filter(|(_, name)| name != "materialize")
+            .collect();
+        assert_eq!(databases, vec![(database_id, "committed".into())]);
+
+        // A new catalog is not the result of a migration.
+        assert!(!data_dir.path().join("catalog").exists());
+        assert!(!data_dir.path().join(MIGRATED_SQLITE_CATALOG).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_backend_constraints() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut backend = StashBackend::open(data_dir.path())?;
+        let (database_id, schema_id) = public_schema(&backend)?;
+        let id = GlobalId::User(backend.allocate_global_id(GidNamespace::User, 1)?);
+
+        let mut tx = backend.transaction()?;
+        assert!(is_kind(tx.insert_database("materialize", 1, 0), |k| {
+            matches!(k, ErrorKind::DatabaseAlreadyExists(_))
+        }));
+        assert!(is_kind(
+            tx.insert_schema(database_id, "public", 1, 0),
+            |k| { matches!(k, ErrorKind::SchemaAlreadyExists(_)) }
+        ));
+        let role_id = tx.insert_role("r", &RoleRestrictions::default(), None, 0)?;
+        tx.insert_item(id, schema_id, "t", b"definition", role_id, 0)?;
+        assert!(is_kind(
+            tx.insert_item(GlobalId::User(id_after(id)), schema_id, "t", b"", 1, 0),
+            |k| matches!(k, ErrorKind::ItemAlreadyExists(_))
+        ));
+        tx.insert_comment(id, None, "comment")?;
+        tx.set_item_dependencies(&[(id, vec![GlobalId::System(1)])])?;
+        tx.grant_privilege(OwnedObjectId::Item(id), Some(role_id), Privilege::Select)?;
+        tx.set_role_setting(Some(role_id), "cluster", Some("default"))?;
+        tx.commit()?;
+
+        // Dropping an item removes its comments, dependencies, and privileges,
+        // and records its definition among the dropped items.
+        let mut tx = backend.transaction()?;
+        tx.remove_items(&[(id, "materialize.public.t".into(), "table".into())], 7)?;
+        assert!(tx.load_items()?.is_empty());
+        assert!(tx.load_item_dependencies()?.is_empty());
+        assert!(is_kind(
+            tx.remove_item(&(id, "materialize.public.t".into(), "table".into()), 8),
+            |k| matches!(k, ErrorKind::Sql(SqlCatalogError::UnknownItem(_)))
+        ));
+        tx.commit()?;
+        assert!(backend.load_comments()?.is_empty());
+        assert!(backend.load_privileges()?.is_empty());
+        assert_eq!(
+            backend.load_dropped_items()?,
+            vec![(
+                id,
+                "materialize.public.t".into(),
+                "table".into(),
+                b"definition".to_vec(),
+                7
+            )]
+        );
+
+        // Dropping a role removes its session defaults.
+        let mut tx = backend.transaction()?;
+        tx.remove_role("r")?;
+        tx.commit()?;
+        assert!(backend.load_role_settings()?.is_empty());
+        assert!(backend
+            .load_roles()?
+            .iter()
+            .all(|(_, name, ..)| name != "r"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_backend_migration() -> Result<(), anyhow::Error> {
+        let data_dir = TempDir::new()?;
+        let mut sqlite = SqliteBackend::open(data_dir.path(), SqliteSynchronous::Off, None)?;
+        let (_, schema_id) = public_schema(&sqlite)?;
+        let id = GlobalId::User(sqlite.allocate_global_id(GidNamespace::User, 1)?);
+        sqlite.set_setting("test", "\"a\"")?;
+        let mut tx = sqlite.transaction()?;
+        let database_id = tx.insert_database("db", 1, 10)?;
+        tx.insert_schema(database_id, "s", 1, 11)?;
+        let role_id = tx.insert_role("r", &RoleRestrictions::default(), Some("verifier"), 12)?;
+        tx.insert_item(id, schema_id, "t", b"definition", role_id, 13)?;
+        tx.set_item_dependencies(&[(id, vec![GlobalId::System(1)])])?;
+        tx.insert_comment(id, Some(1), "comment")?;
+        tx.grant_privilege(
+            OwnedObjectId::Database(database_id),
+            None,
+            Privilege::Create,
+        )?;
+        tx.set_role_setting(Some(role_id), "cluster", Some("default"))?;
+        tx.insert_audit_event(&mut AuditEvent {
+            id: 0,
+            event_type: AuditEventType::Create,
+            object_type: "database".into(),
+            object_id: database_id.to_string(),
+            name: "db".into(),
+            user: "mz_system".into(),
+            occurred_at: 10,
+            details: serde_json::json!({ "owner": "r" }),
+        })?;
+        tx.commit()?;
+        let expected = snapshot(&mut sqlite)?;
+        drop(sqlite);
+
+        // The stash holds exactly what the SQLite catalog held, and the SQLite
+        // catalog is set aside so that it cannot be opened again by mistake.
+        let mut backend = StashBackend::open(data_dir.path())?;
+        assert_eq!(snapshot(&mut backend)?, expected);
+        assert!(!data_dir.path().join("catalog").exists());
+        assert!(data_dir.path().join(MIGRATED_SQLITE_CATALOG).exists());
+        let id_after_migration = backend.allocate_global_id(GidNamespace::User, 1)?;
+        assert_eq!(id_after_migration, id_after(id));
+        let mut tx = backend.transaction()?;
+        assert!(is_kind(
+            tx.insert_role("r", &RoleRestrictions::default(), None, 0),
+            |k| { matches!(k, ErrorKind::RoleAlreadyExists(_)) }
+        ));
+        drop(tx);
+        drop(backend);
+
+        assert!(is_kind(
+            Connection::open(data_dir.path(), SqliteSynchronous::Off, None, None, None),
+            |k| matches!(k, ErrorKind::InvalidStorageConfig(_))
+        ));
+
+        // Reopening the stash does not migrate the catalog again.
+        let mut backend = StashBackend::open(data_dir.path())?;
+        let mut after_reopen = snapshot(&mut backend)?;
+        after_reopen.pop();
+        assert_eq!(after_reopen, expected[..expected.len() - 1]);
+        Ok(())
+    }
+}
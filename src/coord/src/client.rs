@@ -23,8 +23,9 @@ use mz_repr::{Datum, Row, ScalarType};
 use mz_sql::ast::{Raw, Statement};
 
 use crate::command::{
-    Canceled, Command, ExecuteResponse, Response, SimpleExecuteResponse, SimpleResult,
-    StartupResponse,
+    Canceled, ClusterSpec, ClusterSpecChanges, Command, ExecuteResponse, ReplicationStream,
+    Response, RuntimeConfig, SimpleExecuteResponse, SimpleResult, StartupResponse,
+    SystemConfigEntry,
 };
 use crate::error::CoordError;
 use crate::session::{EndTransactionAction, PreparedStatement, Session};
@@ -33,7 +34,8 @@ use crate::session::{EndTransactionAction, PreparedStatement, Session};
 ///
 /// The coordinator runs on its own thread. Dropping the handle will wait for
 /// the coordinator's thread to exit, which will only occur after all
-/// outstanding [`Client`]s for the coordinator have dropped.
+/// outstanding [`Client`]s for the coordinator have dropped, or after a client
+/// calls [`Client::shutdown`].
 pub struct Handle {
     pub(crate) cluster_id: Uuid,
     pub(crate) session_id: Uuid,
@@ -113,6 +115,46 @@ impl Client {
         let response = self.system_execute(stmt).await?;
         Ok(response.results.into_element())
     }
+
+    /// Reports the active runtime configuration of the server, replacing the
+    /// contents of `mz_system_config`.
+    pub fn report_system_config(&self, entries: Vec<SystemConfigEntry>) {
+        self.cmd_tx
+            .send(Command::ReportSystemConfig { entries })
+            .expect("coordinator unexpectedly gone");
+    }
+
+    /// Applies new values of the coordinator's reloadable options.
+    pub fn reload_runtime_config(&self, config: RuntimeConfig) {
+        self.cmd_tx
+            .send(Command::ReloadRuntimeConfig { config })
+            .expect("coordinator unexpectedly gone");
+    }
+
+    /// Shuts down the coordinator.
+    ///
+    /// The coordinator processes the commands sent before the shutdown, stops
+    /// the services that the orchestrator launched, and then exits, closing
+    /// the catalog, the STORAGE stash, and the persistence runtime. Commands sent afterwards never receive a response, so callers
+    /// should first ensure that no connections remain open.
+    pub fn shutdown(&self) {
+        // If sending fails, the coordinator has already shut down.
+        let _ = self.cmd_tx.send(Command::Shutdown);
+    }
+
+    /// Returns the SCRAM-SHA-256 verifier of the password of the role named
+    /// `user`, or `None` if the role does not exist, has no password, or may
+    /// not log in.
+    pub async fn role_password(&self, user: &str) -> Option<String> {
+        let (tx, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(Command::RolePassword {
+                user: user.into(),
+                tx,
+            })
+            .expect("coordinator unexpectedly gone");
+        rx.await.expect("coordinator unexpectedly canceled request")
+    }
 }
 
 /// A coordinator client that is bound to a connection.
@@ -180,6 +222,11 @@ impl ConnClient {
         }
     }
 
+    /// Returns the password verifier of a role, as in [`Client::role_password`].
+    pub async fn role_password(&self, user: &str) -> Option<String> {
+        self.inner.role_password(user).await
+    }
+
     /// Cancels the query currently running on another connection.
     pub async fn cancel_request(&mut self, conn_id: u32, secret_key: u32) {
         self.inner
@@ -354,6 +401,30 @@ impl SessionClient {
             .await
     }
 
+    /// Exports the configuration of every cluster as a [`ClusterSpec`].
+    pub async fn export_cluster_spec(&mut self) -> Result<ClusterSpec, CoordError> {
+        self.send(|tx, session| Command::ExportClusterSpec { session, tx })
+            .await
+    }
+
+    /// Creates, alters, and drops clusters to match `spec`.
+    ///
+    /// The spec is validated in full before any cluster is changed. If
+    /// `dry_run` is set, the changes are reported but not made.
+    pub async fn apply_cluster_spec(
+        &mut self,
+        spec: ClusterSpec,
+        dry_run: bool,
+    ) -> Result<ClusterSpecChanges, CoordError> {
+        self.send(|tx, session| Command::ApplyClusterSpec {
+            spec,
+            dry_run,
+            session,
+            tx,
+        })
+        .await
+    }
+
     /// Inserts a set of rows into the given table.
     ///
     /// The rows only contain the columns positions in `columns`, so they
@@ -375,6 +446,49 @@ impl SessionClient {
         .await
     }
 
+    /// Creates a replication slot named `name`.
+    ///
+    /// A temporary slot is dropped when the session terminates.
+    pub async fn create_replication_slot(
+        &mut self,
+        name: String,
+        temporary: bool,
+    ) -> Result<(), CoordError> {
+        self.send(|tx, session| Command::CreateReplicationSlot {
+            name,
+            temporary,
+            session,
+            tx,
+        })
+        .await
+    }
+
+    /// Drops the replication slot named `name`.
+    pub async fn drop_replication_slot(&mut self, name: String) -> Result<(), CoordError> {
+        self.send(|tx, session| Command::DropReplicationSlot { name, session, tx })
+            .await
+    }
+
+    /// Starts streaming the named publications from the replication slot
+    /// `slot`, skipping any updates before `start_lsn`.
+    ///
+    /// The stream's tails are dropped when the session's transaction ends.
+    pub async fn start_replication(
+        &mut self,
+        slot: String,
+        publications: Vec<String>,
+        start_lsn: u64,
+    ) -> Result<ReplicationStream, CoordError> {
+        self.send(|tx, session| Command::StartReplication {
+            slot,
+            publications,
+            start_lsn,
+            session,
+            tx,
+        })
+        .await
+    }
+
     /// Executes SQL statements using a simple protocol that does not involve
     /// portals.
     ///
@@ -462,6 +576,7 @@ impl SessionClient {
                 PeekResponseUnary::Rows(rows) => rows,
                 PeekResponseUnary::Error(e) => coord_bail!("{}", e),
                 PeekResponseUnary::Canceled => coord_bail!("execution canceled"),
+                PeekResponseUnary::TimedOut => return Err(CoordError::StatementTimeout),
             };
             let mut sql_rows: Vec<Vec<serde_json::Value>> = vec![];
             let col_names = match desc.relation_desc {
@@ -7,21 +7,23 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use derivative::Derivative;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tokio::sync::oneshot;
 
+use mz_dataflow_types::client::controller::ClusterReplicaSize;
 use mz_dataflow_types::PeekResponseUnary;
 use mz_expr::GlobalId;
 use mz_ore::str::StrExt;
-use mz_repr::{Row, ScalarType};
+use mz_repr::{RelationDesc, Row, ScalarType};
 use mz_sql::ast::{FetchDirection, NoticeSeverity, ObjectType, Raw, Statement};
-use mz_sql::plan::ExecuteTimeout;
+use mz_sql::plan::{ComputeInstanceConfig, ExecuteTimeout};
 use tokio::sync::watch;
 
 use crate::error::CoordError;
@@ -86,6 +88,18 @@ pub enum Command {
         tx: oneshot::Sender<Response<String>>,
     },
 
+    ExportClusterSpec {
+        session: Session,
+        tx: oneshot::Sender<Response<ClusterSpec>>,
+    },
+
+    ApplyClusterSpec {
+        spec: ClusterSpec,
+        dry_run: bool,
+        session: Session,
+        tx: oneshot::Sender<Response<ClusterSpecChanges>>,
+    },
+
     CopyRows {
         id: GlobalId,
         columns: Vec<usize>,
@@ -94,9 +108,45 @@ pub enum Command {
         tx: oneshot::Sender<Response<ExecuteResponse>>,
     },
 
+    CreateReplicationSlot {
+        name: String,
+        temporary: bool,
+        session: Session,
+        tx: oneshot::Sender<Response<()>>,
+    },
+
+    DropReplicationSlot {
+        name: String,
+        session: Session,
+        tx: oneshot::Sender<Response<()>>,
+    },
+
+    StartReplication {
+        slot: String,
+        publications: Vec<String>,
+        start_lsn: u64,
+        session: Session,
+        tx: oneshot::Sender<Response<ReplicationStream>>,
+    },
+
     Terminate {
         session: Session,
     },
+
+    ReportSystemConfig {
+        entries: Vec<SystemConfigEntry>,
+    },
+
+    ReloadRuntimeConfig {
+        config: RuntimeConfig,
+    },
+
+    RolePassword {
+        user: String,
+        tx: oneshot::Sender<Option<String>>,
+    },
+
+    Shutdown,
 }
 
 #[derive(Debug)]
@@ -116,6 +166,82 @@ pub struct StartupResponse {
     pub messages: Vec<StartupMessage>,
 }
 
+/// The response to
+/// [`SessionClient::start_replication`](crate::SessionClient::start_replication).
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ReplicationStream {
+    /// The views of the publications, in the order the publications and their
+    /// views were named.
+    pub relations: Vec<ReplicatedRelation>,
+    /// Reports that the subscriber has durably applied all updates at times
+    /// before the given log sequence number, so that the replication slot
+    /// need not retain them.
+    #[derivative(Debug = "ignore")]
+    pub confirm: Box<dyn Fn(u64) + Send + Sync>,
+}
+
+/// A view streamed by a [`ReplicationStream`].
+#[derive(Debug)]
+pub struct ReplicatedRelation {
+    /// The OID of the view.
+    pub oid: u32,
+    /// The name of the view's schema.
+    pub schema: String,
+    /// The name of the view.
+    pub name: String,
+    /// The columns of the view.
+    pub desc: RelationDesc,
+    /// The tail of the view, which includes progress updates.
+    pub rx: RowBatchStream,
+}
+
+/// A declarative description of the clusters in a Materialize instance.
+///
+/// A spec describes every cluster, so applying it creates the clusters it
+/// names that do not exist, alters those whose configuration differs, and
+/// drops those it does not name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterSpec {
+    /// The configuration of each cluster, by name.
+    pub clusters: BTreeMap<String, ComputeInstanceConfig>,
+}
+
+/// The changes made, or that would be made, by applying a [`ClusterSpec`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ClusterSpecChanges {
+    /// The names of the clusters that were created.
+    pub created: Vec<String>,
+    /// The names of the clusters whose configuration was altered.
+    pub altered: Vec<String>,
+    /// The names of the clusters that were dropped.
+    pub dropped: Vec<String>,
+}
+
+/// The active value of a runtime configuration parameter, as reported to
+/// [`Client::report_system_config`](crate::Client::report_system_config).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemConfigEntry {
+    /// The name of the parameter, e.g., `log-filter`.
+    pub name: String,
+    /// The active value of the parameter, if it is set.
+    pub value: Option<String>,
+    /// Where the active value came from, e.g., `environment`.
+    pub source: String,
+}
+
+/// The options of the coordinator that can change while it runs, as applied
+/// by [`Client::reload_runtime_config`](crate::Client::reload_runtime_config).
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeConfig {
+    /// The maximum number of concurrent client connections, if any.
+    pub max_connections: Option<usize>,
+    /// The resources to allocate to the replica of a managed cluster of each
+    /// size, by the name of the size.
+    pub cluster_replica_sizes: HashMap<String, ClusterReplicaSize>,
+}
+
 /// Messages in a [`StartupResponse`].
 #[derive(Debug)]
 pub enum StartupMessage {
@@ -165,10 +291,18 @@ pub enum ExecuteResponse {
     AlteredObject(ObjectType),
     // The index was altered.
     AlteredIndexLogicalCompaction,
+    /// The requested database was altered.
+    AlteredDatabase,
+    /// The requested schema was altered.
+    AlteredSchema,
+    /// The system configuration was altered.
+    AlteredSystemConfiguration,
     // The query was canceled.
     Canceled,
     /// The requested cursor was closed.
     ClosedCursor,
+    /// The comment on the requested object was set.
+    Commented,
     CopyTo {
         format: mz_sql::plan::CopyFormat,
         resp: Box<ExecuteResponse>,
@@ -196,10 +330,18 @@ pub enum ExecuteResponse {
     CreatedIndex {
         existed: bool,
     },
+    /// The requested publication was created.
+    CreatedPublication {
+        existed: bool,
+    },
     /// The requested secret was created.
     CreatedSecret {
         existed: bool,
     },
+    /// The requested task was created.
+    CreatedTask {
+        existed: bool,
+    },
     /// The requested sink was created.
     CreatedSink {
         existed: bool,
@@ -254,6 +396,10 @@ pub enum ExecuteResponse {
     DroppedType,
     /// The requested secret was dropped.
     DroppedSecret,
+    /// The requested publication was dropped.
+    DroppedPublication,
+    /// The requested task was dropped.
+    DroppedTask,
     /// The provided query was empty.
     EmptyQuery,
     /// Fetch results from a cursor.
@@ -265,16 +411,25 @@ pub enum ExecuteResponse {
         /// How long to wait for results to arrive.
         timeout: ExecuteTimeout,
     },
+    /// The requested privileges were granted.
+    GrantedPrivilege,
     /// The specified number of rows were inserted into the requested table.
     Inserted(usize),
     /// The specified prepared statement was created.
     Prepare,
+    /// The requested privileges were revoked.
+    RevokedPrivilege,
     /// Rows will be delivered via the specified future.
     SendingRows(#[derivative(Debug = "ignore")] RowsFuture),
     /// The specified variable was set to a new value.
     SetVariable {
         name: String,
+        /// Whether the variable was restored to its default value with `RESET`,
+        /// rather than set with `SET`.
+        reset: bool,
     },
+    /// All variables were restored to their default values.
+    ResetAllVariables,
     /// A new transaction was started.
     StartedTransaction {
         duplicated: bool, // true if a transaction is in progress
@@ -284,6 +439,8 @@ pub enum ExecuteResponse {
     Tailing {
         rx: RowBatchStream,
     },
+    /// The requested dropped object was restored.
+    Undropped,
     /// The specified number of rows were updated in the requested table.
     Updated(usize),
     /// Raise a warning.
@@ -68,15 +68,19 @@
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::future::Future;
+use std::iter;
+use std::mem;
+use std::net::IpAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use derivative::Derivative;
 use differential_dataflow::lattice::Lattice;
-use futures::future::{FutureExt, TryFutureExt};
+use futures::future::{self, FutureExt, TryFutureExt};
 use futures::stream::StreamExt;
 use itertools::Itertools;
 use rand::Rng;
@@ -86,9 +90,10 @@ use timely::progress::{Antichain, Timestamp as _};
 use tokio::runtime::Handle as TokioHandle;
 use tokio::select;
 use tokio::sync::{mpsc, oneshot, watch};
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use mz_authenticator::scram::{self, Verifier};
 use mz_build_info::BuildInfo;
 use mz_dataflow_types::client::controller::ReadPolicy;
 use mz_dataflow_types::client::{
@@ -96,49 +101,60 @@ use mz_dataflow_types::client::{
     Response as DataflowResponse, StorageResponse, TimestampBindingFeedback,
     DEFAULT_COMPUTE_INSTANCE_ID,
 };
-use mz_dataflow_types::sinks::{SinkAsOf, SinkConnector, SinkDesc, TailSinkConnector};
+use mz_dataflow_types::sinks::{
+    SinkAsOf, SinkConnector, SinkConnectorBuilder, SinkDesc, TailSinkConnector,
+};
 use mz_dataflow_types::sources::{
-    AwsExternalId, ExternalSourceConnector, PostgresSourceConnector, SourceConnector, Timeline,
+    AwsExternalId, ExternalSourceConnector, PostgresSourceConnector, SourceConnector, SourceDesc,
+    SourceEnvelope, Timeline,
 };
 use mz_dataflow_types::{
     BuildDesc, DataflowDesc, DataflowDescription, IndexDesc, PeekResponse, PeekResponseUnary,
     Update,
 };
+use mz_expr::analysis::Analysis;
 use mz_expr::{
-    permutation_for_arrangement, CollectionPlan, ExprHumanizer, GlobalId, MirRelationExpr,
-    MirScalarExpr, OptimizedMirRelationExpr, RowSetFinishing,
+    permutation_for_arrangement, CollectionPlan, ExprHumanizer, GlobalId, Id, JoinInputMapper,
+    MirRelationExpr, MirScalarExpr, OptimizedMirRelationExpr, RowSetFinishing,
 };
+use mz_ore::id_gen::IdAllocator;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::{to_datetime, EpochMillis, NowFn};
 use mz_ore::retry::Retry;
 use mz_ore::soft_assert_eq;
+use mz_ore::str::StrExt;
 use mz_ore::task;
 use mz_ore::thread::JoinHandleExt;
 use mz_repr::adt::interval::Interval;
 use mz_repr::adt::numeric::{Numeric, NumericMaxScale};
 use mz_repr::{Datum, Diff, RelationDesc, RelationType, Row, RowArena, ScalarType, Timestamp};
-use mz_secrets::{SecretOp, SecretsController};
+use mz_secrets::{SecretFormat, SecretOp, SecretVersion, SecretsController};
 use mz_sql::ast::display::AstDisplay;
 use mz_sql::ast::{
-    CreateIndexStatement, CreateSinkStatement, CreateSourceStatement, ExplainStage, FetchStatement,
-    Ident, InsertSource, ObjectType, Query, Raw, RawIdent, SetExpr, SourceConnectorType, Statement,
+    AvroSchema, CreateIndexStatement, CreateSinkStatement, CreateSourceConnector,
+    CreateSourceFormat, CreateSourceStatement, ExplainFormat, ExplainStage, FetchStatement, Format,
+    Ident, ObjectType, ProtobufSchema, Raw, RawIdent, Schema, SourceConnectorType, Statement,
 };
 use mz_sql::catalog::{
-    CatalogComputeInstance, CatalogError, CatalogTypeDetails, SessionCatalog as _,
+    CatalogComputeInstance, CatalogError, CatalogItem as _, CatalogTypeDetails, SessionCatalog as _,
 };
 use mz_sql::names::{
     FullObjectName, QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaSpecifier,
 };
 use mz_sql::plan::{
-    AlterComputeInstancePlan, AlterIndexEnablePlan, AlterIndexResetOptionsPlan,
-    AlterIndexSetOptionsPlan, AlterItemRenamePlan, ComputeInstanceIntrospectionConfig,
-    CreateComputeInstancePlan, CreateDatabasePlan, CreateIndexPlan, CreateRolePlan,
-    CreateSchemaPlan, CreateSecretPlan, CreateSinkPlan, CreateSourcePlan, CreateTablePlan,
-    CreateTypePlan, CreateViewPlan, CreateViewsPlan, DropComputeInstancesPlan, DropDatabasePlan,
-    DropItemsPlan, DropRolesPlan, DropSchemaPlan, ExecutePlan, ExplainPlan, FetchPlan,
-    HirRelationExpr, IndexOption, IndexOptionName, InsertPlan, MutationKind, OptimizerConfig,
-    Params, PeekPlan, Plan, QueryWhen, RaisePlan, ReadThenWritePlan, SendDiffsPlan,
-    SetVariablePlan, ShowVariablePlan, StatementDesc, TailFrom, TailPlan, View,
+    AdminOperation, AdminPlan, AlterComputeInstancePlan, AlterDatabaseRenamePlan,
+    AlterIndexEnablePlan, AlterIndexResetOptionsPlan, AlterIndexSetOptionsPlan,
+    AlterItemRenamePlan, AlterRolePlan, AlterRoleSetPlan, AlterSchemaRenamePlan, AlterSecretPlan,
+    AlterSystemSetPlan, CommentPlan, ComputeInstanceConfig, ComputeInstanceIntrospectionConfig,
+    CreateComputeInstancePlan, CreateDatabasePlan, CreateIndexPlan, CreatePublicationPlan,
+    CreateRolePlan, CreateSchemaPlan, CreateSecretPlan, CreateSinkPlan, CreateSourcePlan,
+    CreateTablePlan, CreateTaskPlan, CreateTypePlan, CreateViewPlan, CreateViewsPlan,
+    DropComputeInstancesPlan, DropDatabasePlan, DropItemsPlan, DropRolesPlan, DropSchemaPlan,
+    ExecutePlan, ExplainDropPlan, ExplainPlan, FetchPlan, HirRelationExpr, IndexOption,
+    IndexOptionName, InsertPlan, MutationKind, OptimizerConfig, Params, PeekPlan, Plan, QueryWhen,
+    RaisePlan, ReadThenWritePlan, ResetVariablePlan, RoleRestrictions, SendDiffsPlan,
+    SetVariablePlan, ShowVariablePlan, StatementDesc, TailFrom, TailPlan, UndropPlan,
+    UpdatePrivilegesPlan, View,
 };
 use mz_sql_parser::ast::RawObjectName;
 use mz_transform::Optimizer;
@@ -146,32 +162,58 @@ use mz_transform::Optimizer;
 use self::prometheus::Scraper;
 use crate::catalog::builtin::{
     BUILTINS, MZ_PROMETHEUS_HISTOGRAMS, MZ_PROMETHEUS_METRICS, MZ_PROMETHEUS_READINGS,
-    MZ_VIEW_FOREIGN_KEYS, MZ_VIEW_KEYS,
+    MZ_SYSTEM_CONFIG, MZ_VIEW_FOREIGN_KEYS, MZ_VIEW_KEYS,
 };
 use crate::catalog::{
     self, storage, BuiltinTableUpdate, Catalog, CatalogItem, CatalogState, SinkConnectorState,
 };
 use crate::client::{Client, Handle};
 use crate::command::{
-    Canceled, Command, ExecuteResponse, Response, StartupMessage, StartupResponse,
+    Canceled, Command, ExecuteResponse, Response, RuntimeConfig, StartupMessage, StartupResponse,
+    SystemConfigEntry,
 };
 use crate::coord::dataflow_builder::{prep_relation_expr, prep_scalar_expr, ExprPrepStyle};
+use crate::coord::dataflow_statements::{ActiveStatement, AttributedDataflow, ReportedDataflow};
+use crate::coord::explain_analyze::{ExplainAnalyzeExecuted, ExplainAnalyzeFinished, PeekStrategy};
+use crate::coord::hydration::{ReplicaFrontier, ReplicaFrontierKey};
 use crate::coord::id_bundle::CollectionIdBundle;
+use crate::coord::index_usage::IndexUsage;
+use crate::coord::memory::{MemoryCollected, MemoryUsage};
+use crate::coord::plan_cache::{PlanCache, PlanCacheKey};
+use crate::coord::replication::ReplicationSlot;
+use crate::coord::secrets::{SecretOpsApplied, SecretOpsFollowUp};
+use crate::coord::statistics::{Statistics, StatisticsCollected};
+use crate::coord::tasks::{ScheduledTask, ScheduledTaskFinished};
+use crate::coord::view_cache::ViewCache;
 use crate::error::CoordError;
+use crate::finishing::SpillingFinisher;
 use crate::persistcfg::PersisterWithConfig;
 use crate::session::{
-    EndTransactionAction, PreparedStatement, Session, Transaction, TransactionOps,
-    TransactionStatus, WriteOp,
+    EndTransactionAction, IsolationLevel, PreparedStatement, RowBatchStream, Session, SystemVars,
+    Transaction, TransactionOps, TransactionStatus, Vars, WriteOp,
 };
 use crate::sink_connector;
-use crate::tail::PendingTail;
+use crate::tail::{PendingTail, TailFlow, TAIL_PROGRESS_INTERVAL};
 use crate::util::ClientTransmitter;
 
 pub mod id_bundle;
 
+mod cluster_spec;
 mod dataflow_builder;
+mod dataflow_statements;
+mod explain_analyze;
+mod hydration;
+mod index_usage;
 mod indexes;
+mod memory;
+mod plan_cache;
+mod privileges;
 mod prometheus;
+mod replication;
+mod secrets;
+mod statistics;
+mod tasks;
+mod view_cache;
 
 #[derive(Debug)]
 pub enum Message {
@@ -183,6 +225,20 @@ pub enum Message {
     SendDiffs(SendDiffs),
     WriteLockGrant(tokio::sync::OwnedMutexGuard<()>),
     AdvanceLocalInputs,
+    PeekQueueTimeout(ComputeInstanceId, Uuid),
+    PeekTimedOut(Uuid),
+    TailAcked(GlobalId),
+    TailTimedOut(ComputeInstanceId, GlobalId),
+    TailHeartbeat(GlobalId),
+    ReplicationSlotConfirmed(String, Timestamp),
+    ExplainAnalyzeExecuted(ExplainAnalyzeExecuted),
+    ExplainAnalyzeFinished(ExplainAnalyzeFinished),
+    CollectStatistics,
+    StatisticsCollected(StatisticsCollected),
+    MemoryCollected(MemoryCollected),
+    ScheduledTaskDue(GlobalId),
+    ScheduledTaskFinished(ScheduledTaskFinished),
+    SecretOpsApplied(SecretOpsApplied),
 }
 
 #[derive(Derivative)]
@@ -204,6 +260,8 @@ pub struct CreateSourceStatementReady {
     pub tx: ClientTransmitter<ExecuteResponse>,
     pub result: Result<CreateSourceStatement<Raw>, CoordError>,
     pub params: Params,
+    /// The statement to which the source's dataflows are attributed.
+    pub statement: Option<ActiveStatement>,
 }
 
 /// This is the struct meant to be paired with [`Message::WriteLockGrant`], but
@@ -215,6 +273,39 @@ pub struct DeferredPlan {
     pub tx: ClientTransmitter<ExecuteResponse>,
     pub session: Session,
     pub plan: Plan,
+    /// The statement to which the plan's dataflows are attributed.
+    pub statement: Option<ActiveStatement>,
+}
+
+/// The maximum number of peeks that may execute concurrently on each compute
+/// instance.
+#[derive(Debug, Clone, Default)]
+pub struct PeekConcurrencyLimits {
+    /// The limit of compute instances that have no limit of their own, if
+    /// any.
+    pub default: Option<usize>,
+    /// The limits of individual compute instances, by name.
+    pub clusters: HashMap<String, usize>,
+}
+
+impl PeekConcurrencyLimits {
+    /// Returns the limit of the compute instance named `cluster`, if any.
+    pub fn get(&self, cluster: &str) -> Option<usize> {
+        self.clusters.get(cluster).copied().or(self.default)
+    }
+}
+
+/// A peek that is waiting for its compute instance to have a free peek slot.
+///
+/// See [`Config::peek_concurrency_limits`].
+pub struct DeferredPeek {
+    /// Identifies this peek among all deferred peeks.
+    pub id: Uuid,
+    pub tx: ClientTransmitter<ExecuteResponse>,
+    pub session: Session,
+    pub plan: PeekPlan,
+    /// The statement to which the peek's dataflow is attributed.
+    pub statement: Option<ActiveStatement>,
 }
 
 #[derive(Derivative)]
@@ -227,6 +318,8 @@ pub struct SinkConnectorReady {
     pub oid: u32,
     pub result: Result<SinkConnector, CoordError>,
     pub compute_instance: ComputeInstanceId,
+    /// The statement to which the sink's dataflow is attributed.
+    pub statement: Option<ActiveStatement>,
 }
 
 #[derive(Debug)]
@@ -240,6 +333,7 @@ pub struct TimestampedUpdate {
 pub struct LoggingConfig {
     pub granularity: Duration,
     pub log_logging: bool,
+    pub retention: Option<Duration>,
     pub retain_readings_for: Duration,
     pub metrics_scraping_interval: Option<Duration>,
 }
@@ -260,11 +354,62 @@ pub struct Config {
     pub persister: PersisterWithConfig,
     pub now: NowFn,
     pub secrets_controller: Box<dyn SecretsController>,
+    /// Whether to recreate secrets that the catalog knows about but that are
+    /// missing from secrets storage as empty placeholders, rather than
+    /// refusing to start.
+    pub recreate_missing_secrets: bool,
+    /// The maximum size of the contents of a secret, in bytes.
+    pub max_secret_size: usize,
+    /// The maximum number of peeks that may execute concurrently on each
+    /// compute instance. Additional peeks are queued until a running peek
+    /// completes.
+    pub peek_concurrency_limits: PeekConcurrencyLimits,
+    /// How long a peek may be queued before it fails.
+    pub peek_queue_timeout: Duration,
+    /// A channel on which `pg_reload_conf()` requests a reload of the
+    /// server's runtime configuration, if reloading is supported.
+    pub config_reload_tx: Option<mpsc::UnboundedSender<()>>,
+    /// The maximum number of concurrent client connections, if any.
+    pub max_connections: Option<usize>,
+    /// The number of bytes of rows that finishing a peek's result may hold in
+    /// memory before spilling sorted runs to temporary files, if any.
+    pub result_sort_memory_limit: Option<usize>,
+    /// The interval at which to collect statistics about indexed collections,
+    /// if statistics are collected automatically.
+    pub statistics_interval: Option<Duration>,
+    /// How long the definitions of dropped objects are kept for `UNDROP`.
+    pub dropped_object_retention: Duration,
+    /// How far the subscriber of a replication slot may fall behind before
+    /// the slot is invalidated, if there is a limit.
+    pub max_replication_slot_lag: Option<Duration>,
 }
 
 struct PendingPeek {
     sender: mpsc::UnboundedSender<PeekResponse>,
     conn_id: u32,
+    compute_instance: ComputeInstanceId,
+    /// Whether the peek exceeded its statement timeout. The client of such a
+    /// peek has stopped listening for its response.
+    timed_out: bool,
+}
+
+/// Limits on a peek, as configured by the session that issued it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeekLimits {
+    /// How long the peek may run before it is canceled, if limited.
+    pub statement_timeout: Option<Duration>,
+    /// The maximum number of rows in the peek's result, if limited.
+    pub max_result_rows: Option<usize>,
+}
+
+impl PeekLimits {
+    /// Returns the limits configured by `session`.
+    fn from_session(session: &Session) -> PeekLimits {
+        PeekLimits {
+            statement_timeout: session.vars().statement_timeout(),
+            max_result_rows: session.vars().max_result_rows(),
+        }
+    }
 }
 
 /// State provided to a catalog transaction closure.
@@ -272,6 +417,8 @@ pub struct CatalogTxn<'a, T> {
     dataflow_client: &'a mz_dataflow_types::client::Controller<T>,
     catalog: &'a CatalogState,
     persister: &'a PersisterWithConfig,
+    view_cache: &'a ViewCache,
+    statistics: &'a Statistics,
 }
 
 /// Glues the external world to the Timely workers.
@@ -324,8 +471,25 @@ pub struct Coordinator {
     pending_peeks: HashMap<Uuid, PendingPeek>,
     /// A map from client connection ids to a set of all pending peeks for that client
     client_pending_peeks: HashMap<u32, BTreeSet<Uuid>>,
+    /// The maximum number of pending peeks on each compute instance.
+    peek_concurrency_limits: PeekConcurrencyLimits,
+    /// The memory budget for finishing a peek's result before it spills to
+    /// disk, if any.
+    result_sort_memory_limit: Option<usize>,
+    /// For each compute instance, peeks deferred due to the peek concurrency
+    /// limit, in the order they arrived.
+    peek_wait_groups: HashMap<ComputeInstanceId, VecDeque<DeferredPeek>>,
     /// A map from pending tails to the tail description.
     pending_tails: HashMap<GlobalId, PendingTail>,
+    /// A map from the sink of each running tail dataflow to the pending tail
+    /// it serves. The two differ for flow-controlled tails whose dataflows
+    /// have been recreated.
+    tail_sinks: HashMap<GlobalId, GlobalId>,
+    /// The replication slots, by name. Replication slots are not durable.
+    replication_slots: HashMap<String, ReplicationSlot>,
+    /// How far the subscriber of a replication slot may fall behind before
+    /// the slot is invalidated, if there is a limit.
+    max_replication_slot_lag: Option<Duration>,
 
     /// Serializes accesses to write critical sections.
     write_lock: Arc<tokio::sync::Mutex<()>>,
@@ -334,7 +498,66 @@ pub struct Coordinator {
 
     /// Handle to secret manager that can create and delete secrets from
     /// an arbitrary secret storage engine.
-    secrets_controller: Box<dyn SecretsController>,
+    secrets_controller: Arc<dyn SecretsController>,
+    /// Serializes the application of operations to secrets storage.
+    secrets_write_lock: Arc<tokio::sync::Mutex<()>>,
+    /// The maximum size of the contents of a secret, in bytes.
+    max_secret_size: usize,
+
+    /// The runtime configuration most recently reported by the server, as
+    /// reflected in `mz_system_config`.
+    system_config: Vec<SystemConfigEntry>,
+    /// The maximum number of concurrent client connections, if any.
+    max_connections: Option<usize>,
+    /// The values of the system variables, as set with `ALTER SYSTEM`.
+    system_vars: SystemVars,
+
+    /// The current usage of each index.
+    index_usage: HashMap<GlobalId, IndexUsage>,
+    /// The usage of each index as most recently reflected in
+    /// `mz_index_usage`.
+    reported_index_usage: HashMap<GlobalId, IndexUsage>,
+    /// The indexes imported by the dataflow of each dataflow export.
+    dataflow_index_imports: HashMap<GlobalId, Vec<GlobalId>>,
+    /// The progress of each index and sink on each replica as most recently
+    /// reflected in `mz_cluster_replica_frontiers`.
+    reported_replica_frontiers: HashMap<ReplicaFrontierKey, ReplicaFrontier>,
+
+    /// The statement being sequenced, to which new dataflows are attributed.
+    active_statement: Option<ActiveStatement>,
+    /// The ID to assign to the next statement to which a dataflow is
+    /// attributed.
+    next_statement_id: i64,
+    /// The statement to which each dataflow is attributed, by the dataflow's
+    /// first export.
+    dataflow_statements: HashMap<GlobalId, AttributedDataflow>,
+    /// The statement to which each dataflow is attributed as most recently
+    /// reflected in `mz_all_dataflow_statements`.
+    reported_dataflow_statements: HashMap<GlobalId, ReportedDataflow>,
+
+    /// The imports of views into dataflows, as of the last time each view was
+    /// imported.
+    view_cache: ViewCache,
+    /// The optimized plans of recently executed statements.
+    plan_cache: PlanCache,
+    /// The key under which the plan of the statement being sequenced is
+    /// cached, if its plan is cacheable.
+    plan_cache_key: Option<PlanCacheKey>,
+
+    /// Statistics about indexed collections, as reflected in `mz_statistics`.
+    statistics: Statistics,
+    /// The interval at which statistics are collected, if they are collected
+    /// automatically.
+    statistics_interval: Option<Duration>,
+    /// The estimated memory that the dataflows on each cluster use, as
+    /// reflected in `mz_dataflow_memory`.
+    memory_usage: MemoryUsage,
+
+    /// The state of each scheduled task.
+    scheduled_tasks: HashMap<GlobalId, ScheduledTask>,
+    /// Allocates the IDs of the connections in which scheduled tasks run,
+    /// which are disjoint from those of client connections.
+    task_conn_ids: IdAllocator<u32>,
 }
 
 /// Metadata about an active connection.
@@ -351,6 +574,8 @@ struct ConnMeta {
     /// requests are required to authenticate with the secret of the connection
     /// that they are targeting.
     secret_key: u32,
+    /// The name of the role that the connection authenticated as.
+    user: String,
 }
 
 struct TxnReads {
@@ -472,8 +697,17 @@ impl Coordinator {
     /// `Coordinator::serve` method.
     async fn bootstrap(
         &mut self,
-        builtin_table_updates: Vec<BuiltinTableUpdate>,
+        mut builtin_table_updates: Vec<BuiltinTableUpdate>,
+        recreate_missing_secrets: bool,
     ) -> Result<(), CoordError> {
+        // Sources and sinks may read secrets as they are recreated, so secrets
+        // storage must agree with the catalog first.
+        builtin_table_updates.extend(self.reconcile_secrets(recreate_missing_secrets).await?);
+
+        // Indexes must be planned with the statistics they were planned with
+        // before the restart.
+        builtin_table_updates.extend(self.restore_statistics()?);
+
         for instance in self.catalog.compute_instances() {
             self.dataflow_client
                 .create_instance(
@@ -508,12 +742,20 @@ impl Coordinator {
                         .map(|p| p.since_ts)
                         .unwrap_or_else(Timestamp::minimum);
 
-                    // Re-announce the source description.
-                    let source_description = self
+                    // Re-announce the source description, configured with
+                    // the current contents of its secrets. A source whose
+                    // secrets cannot be read is created without them, and
+                    // reports the resulting errors when it runs.
+                    let mut source_description = self
                         .catalog
                         .state()
                         .source_description_for(entry.id())
                         .unwrap();
+                    match self.read_source_secrets(&mut source_description).await {
+                        Ok(versions) => builtin_table_updates
+                            .extend(self.catalog.set_secret_uses(entry.id(), versions)),
+                        Err(e) => error!("source {} cannot read its secrets: {}", entry.name(), e),
+                    }
 
                     self.dataflow_client
                         .storage_mut()
@@ -594,10 +836,17 @@ impl Coordinator {
                             panic!("sink already initialized during catalog boot")
                         }
                     };
-                    let connector = sink_connector::build(builder.clone(), entry.id())
+                    let mut builder = builder.clone();
+                    match self.read_sink_secrets(&mut builder).await {
+                        Ok(versions) => builtin_table_updates
+                            .extend(self.catalog.set_secret_uses(entry.id(), versions)),
+                        Err(e) => error!("sink {} cannot read its secrets: {}", entry.name(), e),
+                    }
+                    let connector = sink_connector::build(builder, entry.id())
                         .await
                         .with_context(|| format!("recreating sink {}", entry.name()))?;
                     self.handle_sink_connector_ready(
+                        None,
                         entry.id(),
                         entry.oid(),
                         connector,
@@ -606,10 +855,14 @@ impl Coordinator {
                     )
                     .await?;
                 }
+                CatalogItem::Task(_) => self.install_scheduled_task(entry.id()),
                 _ => (), // Handled in prior loop.
             }
         }
 
+        // The inputs of the publications' views are now readable.
+        self.restore_replication_slots().await?;
+
         self.send_builtin_table_updates(builtin_table_updates).await;
 
         // Announce primary and foreign key relationships.
@@ -706,7 +959,22 @@ impl Coordinator {
             });
         }
 
-        let mut metric_scraper_stream = self.metric_scraper.tick_stream();
+        if let Some(statistics_interval) = self.statistics_interval {
+            let internal_cmd_tx = self.internal_cmd_tx.clone();
+            task::spawn(|| "coordinator_collect_statistics", async move {
+                let mut interval = tokio::time::interval(statistics_interval);
+                loop {
+                    interval.tick().await;
+                    // If sending fails, the main thread has shutdown.
+                    if internal_cmd_tx.send(Message::CollectStatistics).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let mut metrics_scraping_interval = self.system_vars.metrics_scraping_interval();
+        let mut metric_scraper_stream = self.metric_scraper.tick_stream(metrics_scraping_interval);
 
         loop {
             let msg = select! {
@@ -729,6 +997,12 @@ impl Coordinator {
             };
 
             match msg {
+                Message::Command(Command::Shutdown) => {
+                    if let Err(e) = self.dataflow_client.shutdown().await {
+                        warn!("failed to stop orchestrated services: {:#}", e);
+                    }
+                    break;
+                }
                 Message::Command(cmd) => self.message_command(cmd).await,
                 Message::Worker(worker) => self.message_worker(worker).await,
                 Message::CreateSourceStatementReady(ready) => {
@@ -741,9 +1015,14 @@ impl Coordinator {
                     // It's possible to have more incoming write lock grants
                     // than pending writes because of cancellations.
                     if let Some(mut ready) = self.write_lock_wait_group.pop_front() {
-                        ready.session.grant_write_lock(write_lock_guard);
+                        ready.session.grant_write_lock(
+                            write_lock_guard,
+                            self.system_vars.write_lock_timeout(),
+                        );
+                        let previous = self.resume_statement(ready.statement);
                         self.sequence_plan(ready.tx, ready.session, ready.plan)
                             .await;
+                        self.restore_statement(previous);
                     }
                     // N.B. if no deferred plans, write lock is released by drop
                     // here.
@@ -754,12 +1033,56 @@ impl Coordinator {
                     // Convince the coordinator it needs to open a new timestamp
                     // and advance inputs.
                     self.global_timeline.fast_forward(self.now());
+                    self.report_index_usage().await;
+                    self.report_dataflow_statements().await;
+                    self.report_replica_frontiers().await;
+                    self.collect_memory_usage().await;
+                    self.invalidate_lagging_replication_slots().await;
+                }
+                Message::PeekQueueTimeout(compute_instance, id) => {
+                    self.message_peek_queue_timeout(compute_instance, id).await
+                }
+                Message::PeekTimedOut(uuid) => self.message_peek_timed_out(uuid).await,
+                Message::TailAcked(tail_id) => self.message_tail_acked(tail_id).await,
+                Message::TailTimedOut(compute_instance, tail_id) => {
+                    self.message_tail_timed_out(compute_instance, tail_id).await
+                }
+                Message::TailHeartbeat(tail_id) => self.message_tail_heartbeat(tail_id),
+                Message::ReplicationSlotConfirmed(slot, lsn) => {
+                    self.confirm_replication_slot(&slot, lsn).await
+                }
+                Message::ExplainAnalyzeExecuted(executed) => {
+                    self.message_explain_analyze_executed(executed).await
+                }
+                Message::ExplainAnalyzeFinished(finished) => {
+                    self.message_explain_analyze_finished(finished).await
+                }
+                Message::CollectStatistics => self.collect_oldest_statistics().await,
+                Message::StatisticsCollected(collected) => {
+                    self.message_statistics_collected(collected).await
+                }
+                Message::MemoryCollected(collected) => {
+                    self.message_memory_collected(collected).await
+                }
+                Message::ScheduledTaskDue(id) => self.message_scheduled_task_due(id).await,
+                Message::ScheduledTaskFinished(finished) => {
+                    self.message_scheduled_task_finished(finished).await
+                }
+                Message::SecretOpsApplied(applied) => {
+                    self.message_secret_ops_applied(applied).await
                 }
             }
 
             if let Some(timestamp) = self.global_timeline.should_advance_to() {
                 self.advance_local_inputs(timestamp).await;
             }
+
+            // `ALTER SYSTEM` may have changed the scraping interval, which
+            // takes effect at the next tick.
+            if self.system_vars.metrics_scraping_interval() != metrics_scraping_interval {
+                metrics_scraping_interval = self.system_vars.metrics_scraping_interval();
+                metric_scraper_stream = self.metric_scraper.tick_stream(metrics_scraping_interval);
+            }
         }
     }
 
@@ -831,11 +1154,17 @@ impl Coordinator {
                 if let Some(PendingPeek {
                     sender: rows_tx,
                     conn_id,
+                    compute_instance,
+                    timed_out,
                 }) = self.pending_peeks.remove(&uuid)
                 {
-                    rows_tx
-                        .send(response)
-                        .expect("Peek endpoint terminated prematurely");
+                    // The client of a peek that timed out has already been
+                    // told so.
+                    if !timed_out {
+                        rows_tx
+                            .send(response)
+                            .expect("Peek endpoint terminated prematurely");
+                    }
                     let uuids = self
                         .client_pending_peeks
                         .get_mut(&conn_id)
@@ -844,6 +1173,9 @@ impl Coordinator {
                     if uuids.is_empty() {
                         self.client_pending_peeks.remove(&conn_id);
                     }
+                    // The completed peek may have freed up a slot for a
+                    // deferred peek.
+                    self.sequence_deferred_peeks(compute_instance).await;
                 } else {
                     warn!("Received a PeekResponse without a pending peek: {uuid}");
                 }
@@ -851,11 +1183,28 @@ impl Coordinator {
             DataflowResponse::Compute(ComputeResponse::TailResponse(sink_id, response)) => {
                 // We use an `if let` here because the peek could have been canceled already.
                 // We can also potentially receive multiple `Complete` responses, followed by
-                // a `Dropped` response.
-                if let Some(pending_tail) = self.pending_tails.get_mut(&sink_id) {
+                // a `Dropped` response, and responses from the dataflow of a paused tail.
+                if let Some(tail_id) = self.tail_sinks.get(&sink_id).copied() {
+                    let pending_tail = self
+                        .pending_tails
+                        .get_mut(&tail_id)
+                        .expect("tail sink without pending tail");
                     let remove = pending_tail.process_response(response);
                     if remove {
-                        self.pending_tails.remove(&sink_id);
+                        self.tail_sinks.remove(&sink_id);
+                        let pending_tail = self.pending_tails.remove(&tail_id).unwrap();
+                        // A tail that reached its `UP TO` timestamp ends while
+                        // its dataflow is still running.
+                        self.drop_compute_sinks(vec![(pending_tail.compute_instance, sink_id)])
+                            .await;
+                        self.release_tail(pending_tail).await;
+                    } else if pending_tail.is_paused() {
+                        // The client has fallen behind. Drop the tail's
+                        // dataflow until it catches up.
+                        let compute_instance = pending_tail.flow.as_ref().unwrap().compute_instance;
+                        self.tail_sinks.remove(&sink_id);
+                        self.drop_compute_sinks(vec![(compute_instance, sink_id)])
+                            .await;
                     }
                 }
             }
@@ -902,11 +1251,25 @@ impl Coordinator {
     async fn message_create_source_statement_ready(
         &mut self,
         CreateSourceStatementReady {
-            mut session,
+            session,
             tx,
             result,
             params,
+            statement,
         }: CreateSourceStatementReady,
+    ) {
+        let previous = self.resume_statement(statement);
+        self.sequence_create_source_statement(session, tx, result, params)
+            .await;
+        self.restore_statement(previous);
+    }
+
+    async fn sequence_create_source_statement(
+        &mut self,
+        mut session: Session,
+        tx: ClientTransmitter<ExecuteResponse>,
+        result: Result<CreateSourceStatement<Raw>, CoordError>,
+        params: Params,
     ) {
         let stmt = match result {
             Ok(stmt) => stmt,
@@ -921,6 +1284,12 @@ impl Coordinator {
             Ok(_) => unreachable!("planning CREATE SOURCE must result in a Plan::CreateSource"),
             Err(e) => return tx.send(Err(e), session),
         };
+        if let Err(e) = self
+            .check_create_privileges(&session, &plan.name)
+            .and_then(|()| self.check_secret_use(&session, plan.source.depends_on.iter().copied()))
+        {
+            return tx.send(Err(e), session);
+        }
 
         let result = self.sequence_create_source(&mut session, plan).await;
         tx.send(result, session);
@@ -935,8 +1304,10 @@ impl Coordinator {
             oid,
             result,
             compute_instance,
+            statement,
         }: SinkConnectorReady,
     ) {
+        let previous = self.resume_statement(statement);
         match result {
             Ok(connector) => {
                 // NOTE: we must not fail from here on out. We have a
@@ -948,9 +1319,15 @@ impl Coordinator {
                     // no better solution presents itself. Possibly sinks should
                     // have an error bit, and an error here would set the error
                     // bit on the sink.
-                    self.handle_sink_connector_ready(id, oid, connector, compute_instance)
-                        .await
-                        .expect("sinks should be validated by sequence_create_sink");
+                    self.handle_sink_connector_ready(
+                        Some(&session),
+                        id,
+                        oid,
+                        connector,
+                        compute_instance,
+                    )
+                    .await
+                    .expect("sinks should be validated by sequence_create_sink");
                 } else {
                     // Another session dropped the sink while we were
                     // creating the connector. Report to the client that
@@ -963,7 +1340,8 @@ impl Coordinator {
             Err(e) => {
                 // Drop the placeholder sink if still present.
                 if self.catalog.try_get_entry(&id).is_some() {
-                    self.catalog_transact(vec![catalog::Op::DropItem(id)], |_| Ok(()))
+                    let ops = vec![catalog::Op::DropItem(id)];
+                    self.catalog_transact(Some(&session), ops, |_| Ok(()))
                         .await
                         .expect("deleting placeholder sink cannot fail");
                 } else {
@@ -974,6 +1352,7 @@ impl Coordinator {
                 tx.send(Err(e), session);
             }
         }
+        self.restore_statement(previous);
     }
 
     fn message_send_diffs(
@@ -1006,6 +1385,107 @@ impl Coordinator {
         }
     }
 
+    async fn message_peek_queue_timeout(&mut self, compute_instance: ComputeInstanceId, id: Uuid) {
+        // The peek may have already been sequenced or canceled.
+        if let Some(peeks) = self.peek_wait_groups.get_mut(&compute_instance) {
+            if let Some(idx) = peeks.iter().position(|peek| peek.id == id) {
+                let peek = peeks.remove(idx).unwrap();
+                let cluster = peek.session.vars().cluster().to_string();
+                peek.tx.send(
+                    Err(CoordError::PeekQueueTimeout {
+                        limit: self
+                            .peek_concurrency_limits
+                            .get(&cluster)
+                            .unwrap_or_default(),
+                        cluster,
+                        timeout: self.system_vars.peek_queue_timeout(),
+                    }),
+                    peek.session,
+                );
+                // The peeks that were queued behind the timed out peek may
+                // now fit.
+                self.sequence_deferred_peeks(compute_instance).await;
+            }
+        }
+    }
+
+    async fn message_peek_timed_out(&mut self, uuid: Uuid) {
+        // The peek may have already completed or been canceled.
+        if let Some(peek) = self.pending_peeks.get_mut(&uuid) {
+            if !peek.timed_out {
+                peek.timed_out = true;
+                let compute_instance = peek.compute_instance;
+                self.dataflow_client
+                    .compute_mut(compute_instance)
+                    .unwrap()
+                    .cancel_peeks(&iter::once(uuid).collect())
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    async fn message_tail_timed_out(
+        &mut self,
+        compute_instance: ComputeInstanceId,
+        tail_id: GlobalId,
+    ) {
+        // The tail may have already completed or been dropped.
+        if let Some(pending_tail) = self.pending_tails.get(&tail_id) {
+            pending_tail.send_timed_out();
+            self.drop_sinks(vec![(compute_instance, tail_id)]).await;
+        }
+    }
+
+    fn message_tail_heartbeat(&mut self, tail_id: GlobalId) {
+        // The tail may have already completed or been dropped, in which case
+        // its heartbeats stop.
+        if let Some(pending_tail) = self.pending_tails.get_mut(&tail_id) {
+            pending_tail.heartbeat();
+            self.schedule_tail_heartbeat(tail_id);
+        }
+    }
+
+    /// Arranges for the tail `tail_id` to receive a heartbeat after
+    /// [`TAIL_PROGRESS_INTERVAL`].
+    fn schedule_tail_heartbeat(&self, tail_id: GlobalId) {
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(|| format!("tail_heartbeat:{tail_id}"), async move {
+            tokio::time::sleep(TAIL_PROGRESS_INTERVAL).await;
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::TailHeartbeat(tail_id));
+        });
+    }
+
+    async fn message_tail_acked(&mut self, tail_id: GlobalId) {
+        // The tail may have already completed or been dropped.
+        let pending_tail = match self.pending_tails.get_mut(&tail_id) {
+            Some(pending_tail) => pending_tail,
+            None => return,
+        };
+        let paused = pending_tail.is_paused();
+        let flow = match &mut pending_tail.flow {
+            Some(flow) => flow,
+            None => return,
+        };
+        let time = flow.ack();
+        let should_resume = flow.should_resume(paused);
+        if time > flow.hold {
+            // Allow the tail's inputs to compact up to the updates the client
+            // has consumed.
+            let old_time = mem::replace(&mut flow.hold, time);
+            let id_bundle = flow.id_bundle.clone();
+            let compute_instance = flow.compute_instance;
+            let new_holds = self.tail_read_holds(time, &id_bundle, compute_instance);
+            self.acquire_read_holds(&new_holds).await;
+            let old_holds = self.tail_read_holds(old_time, &id_bundle, compute_instance);
+            self.release_read_hold(old_holds).await;
+        }
+        if should_resume {
+            self.resume_tail(tail_id).await;
+        }
+    }
+
     async fn message_scrape_metrics(&mut self) {
         let scraped_metrics = self.metric_scraper.scrape_once();
         self.send_builtin_table_updates_at_offset(scraped_metrics)
@@ -1015,11 +1495,23 @@ impl Coordinator {
     async fn message_command(&mut self, cmd: Command) {
         match cmd {
             Command::Startup {
-                session,
+                mut session,
                 create_user_if_not_exists,
                 cancel_tx,
                 tx,
             } => {
+                // The system user is exempt from the limit, so that an
+                // operator can always connect to investigate.
+                if let Some(limit) = self.max_connections {
+                    if self.active_conns.len() >= limit && session.user() != catalog::SYSTEM_USER {
+                        let _ = tx.send(Response {
+                            result: Err(CoordError::TooManyConnections { limit }),
+                            session,
+                        });
+                        return;
+                    }
+                }
+
                 if let Err(e) = self.catalog.create_temporary_schema(session.conn_id()) {
                     let _ = tx.send(Response {
                         result: Err(e.into()),
@@ -1043,8 +1535,10 @@ impl Coordinator {
                     }
                     let plan = CreateRolePlan {
                         name: session.user().to_string(),
+                        restrictions: RoleRestrictions::default(),
+                        password: None,
                     };
-                    if let Err(err) = self.sequence_create_role(plan).await {
+                    if let Err(err) = self.sequence_create_role(&session, plan).await {
                         let _ = tx.send(Response {
                             result: Err(err),
                             session,
@@ -1053,6 +1547,27 @@ impl Coordinator {
                     }
                 }
 
+                // Apply the defaults stored for the role. Parameters that the
+                // client specified at startup take precedence.
+                if let Some(role) = self.catalog.try_get_role(session.user()) {
+                    for (name, value) in self.catalog.role_settings(role.id) {
+                        if let Err(e) = session.vars_mut().set_default(name, value) {
+                            warn!(
+                                "ignoring default {} = {} for role {}: {}",
+                                name, value, role.name, e
+                            );
+                        }
+                    }
+                }
+
+                if let Err(e) = self.check_role_restrictions(&session) {
+                    let _ = tx.send(Response {
+                        result: Err(e),
+                        session,
+                    });
+                    return;
+                }
+
                 let mut messages = vec![];
                 let catalog = self.catalog.for_session(&session);
                 if catalog.active_database().is_none() {
@@ -1068,6 +1583,7 @@ impl Coordinator {
                     ConnMeta {
                         cancel_tx,
                         secret_key,
+                        user: session.user().into(),
                     },
                 );
 
@@ -1119,15 +1635,27 @@ impl Coordinator {
             }
 
             Command::DumpCatalog { session, tx } => {
-                // TODO(benesch): when we have RBAC, dumping the catalog should
-                // require superuser permissions.
+                let result = self.check_superuser(&session).map(|()| self.catalog.dump());
+                let _ = tx.send(Response { result, session });
+            }
 
+            Command::ExportClusterSpec { session, tx } => {
                 let _ = tx.send(Response {
-                    result: Ok(self.catalog.dump()),
+                    result: Ok(self.export_cluster_spec()),
                     session,
                 });
             }
 
+            Command::ApplyClusterSpec {
+                spec,
+                dry_run,
+                session,
+                tx,
+            } => {
+                let result = self.sequence_apply_cluster_spec(spec, dry_run).await;
+                let _ = tx.send(Response { result, session });
+            }
+
             Command::CopyRows {
                 id,
                 columns,
@@ -1139,10 +1667,63 @@ impl Coordinator {
                 let _ = tx.send(Response { result, session });
             }
 
+            Command::CreateReplicationSlot {
+                name,
+                temporary,
+                session,
+                tx,
+            } => {
+                let result = self.sequence_create_replication_slot(&session, name, temporary);
+                let _ = tx.send(Response { result, session });
+            }
+
+            Command::DropReplicationSlot { name, session, tx } => {
+                let result = self.sequence_drop_replication_slot(name).await;
+                let _ = tx.send(Response { result, session });
+            }
+
+            Command::StartReplication {
+                slot,
+                publications,
+                start_lsn,
+                mut session,
+                tx,
+            } => {
+                let result = self
+                    .sequence_start_replication(&mut session, slot, publications, start_lsn)
+                    .await;
+                let _ = tx.send(Response { result, session });
+            }
+
             Command::Terminate { mut session } => {
                 self.handle_terminate(&mut session).await;
             }
 
+            Command::ReportSystemConfig { entries } => {
+                self.handle_report_system_config(entries).await;
+            }
+
+            Command::ReloadRuntimeConfig { config } => {
+                let RuntimeConfig {
+                    max_connections,
+                    cluster_replica_sizes,
+                } = config;
+                self.max_connections = max_connections;
+                self.dataflow_client
+                    .set_cluster_replica_sizes(cluster_replica_sizes);
+            }
+
+            Command::Shutdown => unreachable!("shutdown is handled by the serve loop"),
+
+            Command::RolePassword { user, tx } => {
+                let password = self
+                    .catalog
+                    .try_get_role(&user)
+                    .filter(|role| role.restrictions.login)
+                    .and_then(|role| role.password.clone());
+                let _ = tx.send(password);
+            }
+
             Command::StartTransaction {
                 implicit,
                 session,
@@ -1419,6 +2000,7 @@ impl Coordinator {
                     | Statement::Discard(_)
                     | Statement::Execute(_)
                     | Statement::Explain(_)
+                    | Statement::ExplainDrop(_)
                     | Statement::Fetch(_)
                     | Statement::Prepare(_)
                     | Statement::Rollback(_)
@@ -1434,25 +2016,21 @@ impl Coordinator {
                     | Statement::ShowSchemas(_)
                     | Statement::ShowIndexes(_)
                     | Statement::ShowObjects(_)
+                    | Statement::ShowDroppedObjects(_)
                     | Statement::ShowVariable(_)
                     | Statement::SetVariable(_)
+                    | Statement::ResetVariable(_)
                     | Statement::StartTransaction(_)
                     | Statement::Tail(_)
                     | Statement::Raise(_) => {
                         // Always safe.
                     }
 
-                    Statement::Insert(ref insert_statement)
-                        if matches!(
-                            insert_statement.source,
-                            InsertSource::Query(Query {
-                                body: SetExpr::Values(..),
-                                ..
-                            }) | InsertSource::DefaultValues
-                        ) =>
-                    {
-                        // Inserting from default? values statements
-                        // is always safe.
+                    Statement::Insert(_) | Statement::Update(_) | Statement::Delete(_) => {
+                        // Writes are safe. Their reads of tables see the
+                        // transaction's earlier writes, and
+                        // `add_transaction_ops` rejects mixing them with
+                        // other reads.
                     }
 
                     // Statements below must by run singly (in Started).
@@ -1460,10 +2038,18 @@ impl Coordinator {
                     | Statement::AlterSecret(_)
                     | Statement::AlterCluster(_)
                     | Statement::AlterObjectRename(_)
+                    | Statement::AlterDatabaseRename(_)
+                    | Statement::AlterSchemaRename(_)
+                    | Statement::AlterRole(_)
+                    | Statement::AlterRoleSet(_)
+                    | Statement::AlterSystemSet(_)
+                    | Statement::Comment(_)
                     | Statement::CreateDatabase(_)
                     | Statement::CreateIndex(_)
                     | Statement::CreateRole(_)
                     | Statement::CreateCluster(_)
+                    | Statement::CreatePublication(_)
+                    | Statement::CreateTask(_)
                     | Statement::CreateSchema(_)
                     | Statement::CreateSecret(_)
                     | Statement::CreateSink(_)
@@ -1472,14 +2058,14 @@ impl Coordinator {
                     | Statement::CreateType(_)
                     | Statement::CreateView(_)
                     | Statement::CreateViews(_)
-                    | Statement::Delete(_)
                     | Statement::DropDatabase(_)
                     | Statement::DropSchema(_)
                     | Statement::DropObjects(_)
                     | Statement::DropRoles(_)
                     | Statement::DropClusters(_)
-                    | Statement::Insert(_)
-                    | Statement::Update(_) => {
+                    | Statement::GrantPrivileges(_)
+                    | Statement::RevokePrivileges(_)
+                    | Statement::Undrop(_) => {
                         return tx.send(
                             Err(CoordError::OperationProhibitsTransaction(stmt.to_string())),
                             session,
@@ -1504,9 +2090,16 @@ impl Coordinator {
                 let internal_cmd_tx = self.internal_cmd_tx.clone();
                 let conn_id = session.conn_id();
                 let params = portal.parameters.clone();
+                let statement =
+                    self.statement_for(&session, &Statement::CreateSource(stmt.clone()));
+                let secret_contents = match self.read_purification_secrets(&session, &stmt).await {
+                    Ok(secret_contents) => secret_contents,
+                    Err(e) => return tx.send(Err(e), session),
+                };
                 let purify_fut = mz_sql::pure::purify_create_source(
                     self.now(),
                     self.catalog.config().aws_external_id.clone(),
+                    secret_contents,
                     stmt,
                 );
                 task::spawn(|| format!("purify:{conn_id}"), async move {
@@ -1518,6 +2111,7 @@ impl Coordinator {
                                 tx,
                                 result,
                                 params,
+                                statement,
                             },
                         ))
                         .expect("sending to internal_cmd_tx cannot fail");
@@ -1525,10 +2119,25 @@ impl Coordinator {
             }
 
             // All other statements are handled immediately.
-            _ => match self.handle_statement(&mut session, stmt, &params).await {
-                Ok(plan) => self.sequence_plan(tx, session, plan).await,
-                Err(e) => tx.send(Err(e), session),
-            },
+            _ => {
+                self.begin_statement(&session, &stmt);
+                if matches!(
+                    stmt,
+                    Statement::Select(_) | Statement::Tail(_) | Statement::Copy(_)
+                ) {
+                    self.plan_cache_key = Some(PlanCacheKey {
+                        sql: stmt.to_ast_string_stable(),
+                        param_types: params.types.clone(),
+                        params: params.datums.clone(),
+                    });
+                }
+                match self.handle_statement(&mut session, stmt, &params).await {
+                    Ok(plan) => self.sequence_plan(tx, session, plan).await,
+                    Err(e) => tx.send(Err(e), session),
+                }
+                self.plan_cache_key = None;
+                self.end_statement();
+            }
         }
     }
 
@@ -1542,7 +2151,17 @@ impl Coordinator {
             if conn_meta.secret_key != secret_key {
                 return;
             }
+            self.cancel_conn(conn_id).await;
+        }
+    }
 
+    /// Cancels the statement that the connection `conn_id` is running.
+    ///
+    /// Peeks are canceled on whichever compute instance they were issued
+    /// to, which releases the read holds that they retain and retires them
+    /// on the compute instance.
+    async fn cancel_conn(&mut self, conn_id: u32) {
+        if let Some(conn_meta) = self.active_conns.get(&conn_id) {
             // Cancel deferred writes. There is at most one pending write per session.
             if let Some(idx) = self
                 .write_lock_wait_group
@@ -1553,19 +2172,44 @@ impl Coordinator {
                 ready.tx.send(Ok(ExecuteResponse::Canceled), ready.session);
             }
 
+            // Cancel deferred peeks. There is at most one pending peek per session.
+            let mut canceled_instances = vec![];
+            for (compute_instance, peeks) in &mut self.peek_wait_groups {
+                if let Some(idx) = peeks
+                    .iter()
+                    .position(|peek| peek.session.conn_id() == conn_id)
+                {
+                    let peek = peeks.remove(idx).unwrap();
+                    peek.tx.send(Ok(ExecuteResponse::Canceled), peek.session);
+                    canceled_instances.push(*compute_instance);
+                }
+            }
+            for compute_instance in canceled_instances {
+                self.sequence_deferred_peeks(compute_instance).await;
+            }
+
             // Inform the target session (if it asks) about the cancellation.
             let _ = conn_meta.cancel_tx.send(Canceled::Canceled);
 
-            // The peek is present on some specific compute instance.
-            let compute_instance = DEFAULT_COMPUTE_INSTANCE_ID;
-            // Allow dataflow to cancel any pending peeks.
+            // Allow dataflow to cancel any pending peeks, each on the compute
+            // instance that it is present on.
+            let mut uuids_by_instance: BTreeMap<ComputeInstanceId, BTreeSet<Uuid>> =
+                BTreeMap::new();
             if let Some(uuids) = self.client_pending_peeks.get(&conn_id) {
-                self.dataflow_client
-                    .compute_mut(compute_instance)
-                    .unwrap()
-                    .cancel_peeks(uuids)
-                    .await
-                    .unwrap();
+                for uuid in uuids {
+                    let compute_instance = self.pending_peeks[uuid].compute_instance;
+                    uuids_by_instance
+                        .entry(compute_instance)
+                        .or_default()
+                        .insert(*uuid);
+                }
+            }
+            for (compute_instance, uuids) in uuids_by_instance {
+                // The compute instance may have been dropped since the peek
+                // was issued, in which case there is nothing to cancel.
+                if let Some(mut compute) = self.dataflow_client.compute_mut(compute_instance) {
+                    compute.cancel_peeks(&uuids).await.unwrap();
+                }
             }
         }
     }
@@ -1576,13 +2220,36 @@ impl Coordinator {
     async fn handle_terminate(&mut self, session: &mut Session) {
         self.clear_transaction(session).await;
 
-        self.drop_temp_items(session.conn_id()).await;
+        self.drop_temporary_replication_slots(session.conn_id())
+            .await;
+        self.drop_temp_items(session).await;
         self.catalog
             .drop_temporary_schema(session.conn_id())
             .expect("unable to drop temporary schema");
         self.active_conns.remove(&session.conn_id());
     }
 
+    /// Replaces the contents of `mz_system_config` with `entries`.
+    async fn handle_report_system_config(&mut self, entries: Vec<SystemConfigEntry>) {
+        let id = self.catalog.resolve_builtin_table(&MZ_SYSTEM_CONFIG);
+        let old = mem::replace(&mut self.system_config, entries);
+        let updates = old
+            .iter()
+            .map(|entry| (entry, -1))
+            .chain(self.system_config.iter().map(|entry| (entry, 1)))
+            .map(|(entry, diff)| BuiltinTableUpdate {
+                id,
+                row: Row::pack_slice(&[
+                    Datum::String(&entry.name),
+                    Datum::from(entry.value.as_deref()),
+                    Datum::String(&entry.source),
+                ]),
+                diff,
+            })
+            .collect();
+        self.send_builtin_table_updates(updates).await;
+    }
+
     /// Handle removing in-progress transaction state regardless of the end action
     /// of the transaction.
     async fn clear_transaction(
@@ -1591,6 +2258,7 @@ impl Coordinator {
     ) -> TransactionStatus<mz_repr::Timestamp> {
         let (drop_sinks, txn) = session.clear_transaction();
         self.drop_sinks(drop_sinks).await;
+        self.end_replications(session.conn_id());
 
         // Release this transaction's compaction hold on collections.
         if let Some(txn_reads) = self.txn_reads.remove(&session.conn_id()) {
@@ -1599,17 +2267,20 @@ impl Coordinator {
         txn
     }
 
-    /// Removes all temporary items created by the specified connection, though
-    /// not the temporary schema itself.
-    async fn drop_temp_items(&mut self, conn_id: u32) {
-        let ops = self.catalog.drop_temp_item_ops(conn_id);
-        self.catalog_transact(ops, |_| Ok(()))
+    /// Removes all temporary items created by the connection of `session`,
+    /// though not the temporary schema itself.
+    async fn drop_temp_items(&mut self, session: &Session) {
+        let ops = self.catalog.drop_temp_item_ops(session.conn_id());
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
             .await
             .expect("unable to drop temporary items for conn_id");
     }
 
+    /// Replaces the placeholder sink `id` with a sink that writes to
+    /// `connector`, on behalf of the user of `session`, if any.
     async fn handle_sink_connector_ready(
         &mut self,
+        session: Option<&Session>,
         id: GlobalId,
         oid: u32,
         connector: SinkConnector,
@@ -1645,8 +2316,11 @@ impl Coordinator {
                 item: CatalogItem::Sink(sink.clone()),
             },
         ];
+        // Replacing the sink forgets the versions of the secrets that it
+        // uses, so they are recorded again once it is replaced.
+        let secret_versions = self.catalog.secret_uses(id);
         let df = self
-            .catalog_transact(ops, |txn| {
+            .catalog_transact(session, ops, |txn| {
                 let mut builder = txn.dataflow_builder(compute_instance);
                 let from_entry = builder.catalog.get_entry(&sink.from);
                 let sink_description = mz_dataflow_types::sinks::SinkDesc {
@@ -1666,6 +2340,8 @@ impl Coordinator {
                 Ok(builder.build_sink_dataflow(name.to_string(), id, sink_description)?)
             })
             .await?;
+        let updates = self.catalog.set_secret_uses(id, secret_versions);
+        self.send_builtin_table_updates(updates).await;
 
         Ok(self.ship_dataflow(df, compute_instance).await)
     }
@@ -1676,24 +2352,36 @@ impl Coordinator {
         mut session: Session,
         plan: Plan,
     ) {
+        if let Err(e) = self
+            .check_plan_privileges(&session, &plan)
+            .and_then(|()| self.check_plan_cluster(&session, &plan))
+        {
+            return tx.send(Err(e), session);
+        }
         match plan {
             Plan::CreateDatabase(plan) => {
-                tx.send(self.sequence_create_database(plan).await, session);
+                tx.send(self.sequence_create_database(&session, plan).await, session);
             }
             Plan::CreateSchema(plan) => {
-                tx.send(self.sequence_create_schema(plan).await, session);
+                tx.send(self.sequence_create_schema(&session, plan).await, session);
             }
             Plan::CreateRole(plan) => {
-                tx.send(self.sequence_create_role(plan).await, session);
+                tx.send(self.sequence_create_role(&session, plan).await, session);
+            }
+            Plan::AlterRole(plan) => {
+                tx.send(self.sequence_alter_role(&session, plan).await, session);
             }
             Plan::CreateComputeInstance(plan) => {
-                tx.send(self.sequence_create_compute_instance(plan).await, session);
+                tx.send(
+                    self.sequence_create_compute_instance(&session, plan).await,
+                    session,
+                );
             }
             Plan::CreateTable(plan) => {
                 tx.send(self.sequence_create_table(&session, plan).await, session);
             }
             Plan::CreateSecret(plan) => {
-                tx.send(self.sequence_create_secret(&session, plan).await, session);
+                self.sequence_create_secret(tx, session, plan);
             }
             Plan::CreateSource(_) => unreachable!("handled separately"),
             Plan::CreateSink(plan) => {
@@ -1712,22 +2400,37 @@ impl Coordinator {
                 tx.send(self.sequence_create_index(&session, plan).await, session);
             }
             Plan::CreateType(plan) => {
-                tx.send(self.sequence_create_type(plan).await, session);
+                tx.send(self.sequence_create_type(&session, plan).await, session);
+            }
+            Plan::CreatePublication(plan) => {
+                tx.send(
+                    self.sequence_create_publication(&session, plan).await,
+                    session,
+                );
+            }
+            Plan::CreateTask(plan) => {
+                tx.send(self.sequence_create_task(&session, plan).await, session);
             }
             Plan::DropDatabase(plan) => {
-                tx.send(self.sequence_drop_database(plan).await, session);
+                tx.send(self.sequence_drop_database(&session, plan).await, session);
             }
             Plan::DropSchema(plan) => {
-                tx.send(self.sequence_drop_schema(plan).await, session);
+                tx.send(self.sequence_drop_schema(&session, plan).await, session);
             }
             Plan::DropRoles(plan) => {
-                tx.send(self.sequence_drop_roles(plan).await, session);
+                tx.send(self.sequence_drop_roles(&session, plan).await, session);
             }
             Plan::DropComputeInstances(plan) => {
-                tx.send(self.sequence_drop_compute_instances(plan).await, session);
+                tx.send(
+                    self.sequence_drop_compute_instances(&session, plan).await,
+                    session,
+                );
             }
             Plan::DropItems(plan) => {
-                tx.send(self.sequence_drop_items(plan).await, session);
+                tx.send(self.sequence_drop_items(&session, plan).await, session);
+            }
+            Plan::ExplainDrop(plan) => {
+                tx.send(self.sequence_explain_drop(&session, plan), session);
             }
             Plan::EmptyQuery => {
                 tx.send(Ok(ExecuteResponse::EmptyQuery), session);
@@ -1741,6 +2444,13 @@ impl Coordinator {
             Plan::SetVariable(plan) => {
                 tx.send(self.sequence_set_variable(&mut session, plan), session);
             }
+            Plan::ResetVariable(plan) => {
+                tx.send(self.sequence_reset_variable(&mut session, plan), session);
+            }
+            Plan::ResetAllVariables => {
+                session.vars_mut().reset_all();
+                tx.send(Ok(ExecuteResponse::ResetAllVariables), session);
+            }
             Plan::StartTransaction(plan) => {
                 let duplicated =
                     matches!(session.transaction(), TransactionStatus::InTransaction(_));
@@ -1760,6 +2470,23 @@ impl Coordinator {
                 self.sequence_end_transaction(tx, session, action).await;
             }
             Plan::Peek(plan) => {
+                // Reads in a transaction that has written see its writes, and
+                // so must not interleave with other writes.
+                if !session.transaction_writes().is_empty() {
+                    guard_write_critical_section!(self, tx, session, Plan::Peek(plan));
+                }
+                // If the compute instance cannot be resolved, `sequence_peek`
+                // will report the error.
+                if let Ok(compute_instance) = self
+                    .catalog
+                    .resolve_compute_instance(session.vars().cluster())
+                {
+                    let compute_instance = compute_instance.id;
+                    if !self.peek_slot_available(compute_instance) {
+                        self.defer_peek(tx, session, plan, compute_instance);
+                        return;
+                    }
+                }
                 tx.send(self.sequence_peek(&mut session, plan).await, session);
             }
             Plan::Tail(plan) => {
@@ -1779,6 +2506,12 @@ impl Coordinator {
                     session,
                 );
             }
+            Plan::Explain(plan) if plan.stage == ExplainStage::Analyze => {
+                if !session.transaction_writes().is_empty() {
+                    guard_write_critical_section!(self, tx, session, Plan::Explain(plan));
+                }
+                self.sequence_explain_analyze(tx, session, plan).await;
+            }
             Plan::Explain(plan) => {
                 tx.send(self.sequence_explain(&session, plan), session);
             }
@@ -1798,10 +2531,59 @@ impl Coordinator {
                 );
             }
             Plan::AlterComputeInstance(plan) => {
-                tx.send(self.sequence_alter_compute_instance(plan).await, session);
+                tx.send(
+                    self.sequence_alter_compute_instance(&session, plan).await,
+                    session,
+                );
             }
             Plan::AlterItemRename(plan) => {
-                tx.send(self.sequence_alter_item_rename(plan).await, session);
+                tx.send(
+                    self.sequence_alter_item_rename(&session, plan).await,
+                    session,
+                );
+            }
+            Plan::AlterSecret(plan) => {
+                self.sequence_alter_secret(tx, session, plan);
+            }
+            Plan::AlterDatabaseRename(plan) => {
+                tx.send(
+                    self.sequence_alter_database_rename(&session, plan).await,
+                    session,
+                );
+            }
+            Plan::AlterSchemaRename(plan) => {
+                tx.send(
+                    self.sequence_alter_schema_rename(&session, plan).await,
+                    session,
+                );
+            }
+            Plan::AlterRoleSet(plan) => {
+                tx.send(self.sequence_alter_role_set(&session, plan).await, session);
+            }
+            Plan::AlterSystemSet(plan) => {
+                tx.send(self.sequence_alter_system_set(plan), session);
+            }
+            Plan::GrantPrivileges(plan) => {
+                tx.send(
+                    self.sequence_update_privileges(&session, plan, true)
+                        .await
+                        .map(|()| ExecuteResponse::GrantedPrivilege),
+                    session,
+                );
+            }
+            Plan::RevokePrivileges(plan) => {
+                tx.send(
+                    self.sequence_update_privileges(&session, plan, false)
+                        .await
+                        .map(|()| ExecuteResponse::RevokedPrivilege),
+                    session,
+                );
+            }
+            Plan::Comment(plan) => {
+                tx.send(self.sequence_comment(&session, plan).await, session);
+            }
+            Plan::Undrop(plan) => {
+                tx.send(self.sequence_undrop(&mut session, plan).await, session);
             }
             Plan::AlterIndexSetOptions(plan) => {
                 tx.send(
@@ -1817,15 +2599,18 @@ impl Coordinator {
                 );
             }
             Plan::AlterIndexEnable(plan) => {
-                tx.send(self.sequence_alter_index_enable(plan).await, session);
+                tx.send(
+                    self.sequence_alter_index_enable(&session, plan).await,
+                    session,
+                );
             }
             Plan::DiscardTemp => {
-                self.drop_temp_items(session.conn_id()).await;
+                self.drop_temp_items(&session).await;
                 tx.send(Ok(ExecuteResponse::DiscardedTemp), session);
             }
             Plan::DiscardAll => {
                 let ret = if let TransactionStatus::Started(_) = session.transaction() {
-                    self.drop_temp_items(session.conn_id()).await;
+                    self.drop_temp_items(&session).await;
                     let drop_sinks = session.reset();
                     self.drop_sinks(drop_sinks).await;
                     Ok(ExecuteResponse::DiscardedAll)
@@ -1916,14 +2701,326 @@ impl Coordinator {
             Plan::Raise(RaisePlan { severity }) => {
                 tx.send(Ok(ExecuteResponse::Raise { severity }), session);
             }
+            Plan::Admin(plan) => {
+                tx.send(self.sequence_admin(&session, plan).await, session);
+            }
         }
     }
 
-    // Returns the name of the portal to execute.
-    fn sequence_execute(
+    async fn sequence_admin(
         &mut self,
-        session: &mut Session,
-        plan: ExecutePlan,
+        session: &Session,
+        plan: AdminPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        // Administrative operations have effects that cannot be rolled back.
+        if !matches!(session.transaction(), TransactionStatus::Started(_)) {
+            return Err(CoordError::OperationProhibitsTransaction(
+                plan.operation.qualified_function_name(),
+            ));
+        }
+        if let AdminOperation::CpuProfile { duration } = plan.operation {
+            // Profiling exposes the internals of the process and perturbs its
+            // performance, so it is restricted to the system user.
+            if session.user() != catalog::SYSTEM_USER {
+                return Err(CoordError::OperationNotPermitted {
+                    role: session.user().into(),
+                    operation: plan.operation.qualified_function_name(),
+                });
+            }
+            // The remote processes that serve the storage and compute layers
+            // are profiled over the same period as this one, and each stack is
+            // labeled with the process in which it was sampled.
+            let profilers = self.dataflow_client.profilers();
+            info!(
+                "taking {:?} CPU profile of {} processes",
+                duration,
+                profilers.len() + 1
+            );
+            return Ok(ExecuteResponse::SendingRows(Box::pin(async move {
+                let remote = future::try_join_all(profilers.into_iter().map(
+                    |(label, profiler)| async move {
+                        let profile = profiler
+                            .cpu_profile(duration)
+                            .await
+                            .with_context(|| format!("profiling {}", label))?;
+                        Ok::<_, anyhow::Error>((label, profile))
+                    },
+                ));
+                let local = mz_prof::time::cpu_profile_folded(duration);
+                let (local, remote) = match future::try_join(local, remote).await {
+                    Ok(profiles) => profiles,
+                    Err(e) => return PeekResponseUnary::Error(format!("{:#}", e)),
+                };
+                let mut folded = String::new();
+                for (label, profile) in iter::once(("materialized".into(), local)).chain(remote) {
+                    for line in profile.lines() {
+                        folded.push_str(&label);
+                        folded.push(';');
+                        folded.push_str(line);
+                        folded.push('\n');
+                    }
+                }
+                PeekResponseUnary::Rows(vec![Row::pack_slice(&[Datum::String(&folded)])])
+            })));
+        }
+        if let AdminOperation::CheckCatalog = plan.operation {
+            // The report describes each inconsistency on its own line, and is
+            // empty if the catalog is consistent.
+            let inconsistencies = self.catalog.check_consistency()?;
+            for inconsistency in &inconsistencies {
+                warn!("catalog inconsistency: {}", inconsistency);
+            }
+            let report = inconsistencies
+                .iter()
+                .map(|inconsistency| inconsistency.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Ok(send_immediate_rows(vec![Row::pack_slice(&[
+                Datum::String(&report),
+            ])]));
+        }
+        let result = match plan.operation {
+            AdminOperation::RestartReplica {
+                compute_instance,
+                replica,
+            } => {
+                let config = self
+                    .catalog
+                    .state()
+                    .get_compute_instance(compute_instance)
+                    .config
+                    .clone();
+                self.dataflow_client
+                    .restart_replica(compute_instance, &config, &replica)
+                    .await?;
+                Datum::True
+            }
+            AdminOperation::ReplicaDiagnostics {
+                compute_instance,
+                replica,
+            } => {
+                let instance = self.catalog.state().get_compute_instance(compute_instance);
+                let diagnostics = self
+                    .dataflow_client
+                    .replica_diagnostics(compute_instance, &instance.config, &replica)
+                    .await?;
+                match diagnostics {
+                    Some(diagnostics) => {
+                        let summary =
+                            diagnostics.summarize(SystemTime::now(), REPLICA_DIAGNOSTICS_WINDOW);
+                        return Ok(send_immediate_rows(vec![Row::pack_slice(&[
+                            Datum::String(&summary),
+                        ])]));
+                    }
+                    None => coord_bail!(
+                        "replica {} of cluster {} is not managed by an orchestrator",
+                        replica.quoted(),
+                        instance.name.quoted()
+                    ),
+                }
+            }
+            AdminOperation::RecomputeMaterialization { id } => {
+                let entry = self.catalog.get_entry(&id);
+                let indexes: Vec<_> = match entry.index() {
+                    Some(index) => vec![(id, index.compute_instance)],
+                    None => self
+                        .catalog
+                        .state()
+                        .get_indexes_on(id)
+                        .filter(|(_, index)| index.enabled)
+                        .map(|(index_id, index)| (index_id, index.compute_instance))
+                        .collect(),
+                };
+                if indexes.is_empty() {
+                    coord_bail!(
+                        "{} is not materialized",
+                        self.catalog
+                            .resolve_full_name(entry.name(), Some(session.conn_id()))
+                    );
+                }
+                for (index_id, compute_instance) in indexes {
+                    self.recompute_index(index_id, compute_instance).await?;
+                }
+                Datum::True
+            }
+            AdminOperation::RebuildUpsertState { id } => {
+                let entry = self.catalog.get_entry(&id);
+                let name = self
+                    .catalog
+                    .resolve_full_name(entry.name(), Some(session.conn_id()));
+                match entry.item() {
+                    CatalogItem::Source(catalog::Source {
+                        connector:
+                            SourceConnector::External {
+                                envelope: SourceEnvelope::Upsert(_),
+                                ..
+                            },
+                        persist_details,
+                        ..
+                    }) => {
+                        // Persisted upsert state is restored from persistent
+                        // storage, not rebuilt from the source.
+                        if persist_details.is_some() {
+                            coord_bail!("cannot rebuild upsert state of persisted source {}", name);
+                        }
+                    }
+                    _ => coord_bail!("{} is not an upsert source", name),
+                }
+
+                // Every dataflow that reads from the source maintains its own
+                // upsert state, so every index that depends on the source,
+                // directly or through views, must be recomputed.
+                let mut indexes = vec![];
+                let mut seen = HashSet::new();
+                let mut worklist = entry.used_by().to_vec();
+                while let Some(id) = worklist.pop() {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    let entry = self.catalog.get_entry(&id);
+                    match entry.item() {
+                        CatalogItem::Index(index) if index.enabled => {
+                            indexes.push((id, index.compute_instance))
+                        }
+                        _ => worklist.extend(entry.used_by()),
+                    }
+                }
+                if indexes.is_empty() {
+                    coord_bail!("{} is not materialized", name);
+                }
+                let rebuilt = indexes.len();
+                for (index_id, compute_instance) in indexes {
+                    self.recompute_index(index_id, compute_instance).await?;
+                }
+                Datum::Int64(rebuilt.try_into().expect("index count fits in i64"))
+            }
+            AdminOperation::GcSecrets => {
+                if !self.secrets_controller.can_list() {
+                    coord_bail!(
+                        "secrets storage cannot list secrets, so orphaned secrets cannot be found"
+                    );
+                }
+                let ops: Vec<_> = self
+                    .secrets_controller
+                    .list()
+                    .await?
+                    .into_iter()
+                    .filter(|id| {
+                        !matches!(
+                            self.catalog.try_get_entry(id).map(|entry| entry.item()),
+                            Some(CatalogItem::Secret(_))
+                        )
+                    })
+                    .map(|id| SecretOp::Delete { id, version: None })
+                    .collect();
+                if ops.is_empty() {
+                    Datum::Int64(0)
+                } else {
+                    // The orphaned secrets are deleted off the coordinator's
+                    // task, and the count is reported once they have been.
+                    let deleted = i64::try_from(ops.len()).expect("secret count fits in i64");
+                    let (tx, rx) = oneshot::channel();
+                    let follow_up = SecretOpsFollowUp::Report(tx);
+                    self.spawn_secret_ops(session.user(), ops, false, follow_up);
+                    return Ok(ExecuteResponse::SendingRows(Box::pin(async move {
+                        match rx.await {
+                            Ok(Ok(_)) => {
+                                let row = Row::pack_slice(&[Datum::Int64(deleted)]);
+                                PeekResponseUnary::Rows(vec![row])
+                            }
+                            Ok(Err(e)) => PeekResponseUnary::Error(e.to_string()),
+                            Err(_) => PeekResponseUnary::Canceled,
+                        }
+                    })));
+                }
+            }
+            AdminOperation::MigrateSecrets => {
+                // Copying secrets is not bounded by `SECRETS_APPLY_TIMEOUT`,
+                // as a migration may copy many secrets. Copies are idempotent,
+                // so a migration that fails can simply be retried.
+                let copied = self.secrets_controller.migrate().await?;
+                info!("migrated {} secrets to the new secrets backend", copied);
+                Datum::Int64(copied.try_into().expect("secret count fits in i64"))
+            }
+            AdminOperation::CollectStatistics => {
+                // The statistics are reflected in `mz_statistics` once they
+                // have been collected.
+                let started = self.collect_statistics().await;
+                Datum::Int64(started.try_into().expect("index count fits in i64"))
+            }
+            AdminOperation::BackupCatalog { path } => {
+                // Backups are written with the server's file system
+                // privileges, and contain the whole catalog.
+                self.check_superuser(session)?;
+                self.catalog.backup(Path::new(&path))?;
+                info!("backed up catalog to {}", path);
+                Datum::True
+            }
+            AdminOperation::CancelQuery { conn_id } => {
+                // As in PostgreSQL, canceling a connection that does not
+                // exist is not an error.
+                match self.active_conns.get(&conn_id) {
+                    None => Datum::False,
+                    Some(conn_meta) => {
+                        self.check_cancel_privileges(session, &conn_meta.user)?;
+                        self.cancel_conn(conn_id).await;
+                        Datum::True
+                    }
+                }
+            }
+            AdminOperation::ReloadConfig => {
+                // Reloading changes the configuration of the whole server.
+                self.check_superuser(session)?;
+                if !self.catalog.state().request_config_reload() {
+                    coord_bail!("this server does not support reloading its configuration");
+                }
+                Datum::True
+            }
+            AdminOperation::CheckCatalog => unreachable!("handled above"),
+            AdminOperation::CpuProfile { .. } => unreachable!("handled above"),
+        };
+        Ok(send_immediate_rows(vec![Row::pack_slice(&[result])]))
+    }
+
+    /// Drops the dataflow that maintains an index and builds it anew from the
+    /// index's inputs.
+    async fn recompute_index(
+        &mut self,
+        index_id: GlobalId,
+        compute_instance: ComputeInstanceId,
+    ) -> Result<(), CoordError> {
+        // Build the new dataflow before dropping the old one, so that the
+        // index is left intact if the dataflow cannot be built. The index is
+        // detached while building so that the dataflow does not import the
+        // very index that it is to recreate.
+        let state = self
+            .dataflow_client
+            .compute_mut(compute_instance)
+            .unwrap()
+            .detach_index(index_id)
+            .map_err(anyhow::Error::new)?;
+        let df = self
+            .dataflow_builder(compute_instance)
+            .build_index_dataflow(index_id);
+        let mut compute = self.dataflow_client.compute_mut(compute_instance).unwrap();
+        compute.reattach_index(index_id, state);
+        let df = df?;
+        compute
+            .forget_index(index_id)
+            .await
+            .map_err(anyhow::Error::new)?;
+        self.read_capability.remove(&index_id);
+        if let Some(df) = df {
+            self.ship_dataflow(df, compute_instance).await;
+        }
+        Ok(())
+    }
+
+    // Returns the name of the portal to execute.
+    fn sequence_execute(
+        &mut self,
+        session: &mut Session,
+        plan: ExecutePlan,
     ) -> Result<String, CoordError> {
         // Verify the stmt is still valid.
         self.verify_prepared_statement(session, &plan.name)?;
@@ -1938,6 +3035,7 @@ impl Coordinator {
 
     async fn sequence_create_database(
         &mut self,
+        session: &Session,
         plan: CreateDatabasePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let db_oid = self.catalog.allocate_oid()?;
@@ -1947,7 +3045,7 @@ impl Coordinator {
             oid: db_oid,
             public_schema_oid: schema_oid,
         }];
-        match self.catalog_transact(ops, |_| Ok(())).await {
+        match self.catalog_transact(Some(session), ops, |_| Ok(())).await {
             Ok(_) => Ok(ExecuteResponse::CreatedDatabase { existed: false }),
             Err(CoordError::Catalog(catalog::Error {
                 kind: catalog::ErrorKind::DatabaseAlreadyExists(_),
@@ -1959,6 +3057,7 @@ impl Coordinator {
 
     async fn sequence_create_schema(
         &mut self,
+        session: &Session,
         plan: CreateSchemaPlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let oid = self.catalog.allocate_oid()?;
@@ -1967,7 +3066,10 @@ impl Coordinator {
             schema_name: plan.schema_name,
             oid,
         };
-        match self.catalog_transact(vec![op], |_| Ok(())).await {
+        match self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+        {
             Ok(_) => Ok(ExecuteResponse::CreatedSchema { existed: false }),
             Err(CoordError::Catalog(catalog::Error {
                 kind: catalog::ErrorKind::SchemaAlreadyExists(_),
@@ -1979,22 +3081,66 @@ impl Coordinator {
 
     async fn sequence_create_role(
         &mut self,
+        session: &Session,
         plan: CreateRolePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let oid = self.catalog.allocate_oid()?;
         let op = catalog::Op::CreateRole {
             name: plan.name,
             oid,
+            restrictions: plan.restrictions,
+            password: plan
+                .password
+                .map(|password| hash_password(&password))
+                .transpose()?,
         };
-        self.catalog_transact(vec![op], |_| Ok(()))
+        self.catalog_transact(Some(session), vec![op], |_| Ok(()))
             .await
             .map(|_| ExecuteResponse::CreatedRole)
     }
 
+    async fn sequence_alter_role(
+        &mut self,
+        session: &Session,
+        plan: AlterRolePlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let role = self
+            .catalog
+            .try_get_role_by_id(plan.id)
+            .expect("planner resolved role");
+        let mut restrictions = role.restrictions.clone();
+        if let Some(superuser) = plan.superuser {
+            restrictions.superuser = superuser;
+        }
+        if let Some(login) = plan.login {
+            restrictions.login = login;
+        }
+        let password = match plan.password {
+            Some(password) => password
+                .map(|password| hash_password(&password))
+                .transpose()?,
+            None => role.password.clone(),
+        };
+        let op = catalog::Op::UpdateRole {
+            name: plan.name,
+            restrictions,
+            password,
+        };
+        self.catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+            .map(|_| ExecuteResponse::AlteredObject(ObjectType::Role))
+    }
+
     async fn sequence_create_compute_instance(
         &mut self,
+        session: &Session,
         plan: CreateComputeInstancePlan,
     ) -> Result<ExecuteResponse, CoordError> {
+        if let ComputeInstanceConfig::Managed { size, .. } = &plan.config {
+            if self.dataflow_client.cluster_replica_size(size).is_none() {
+                coord_bail!("unknown cluster size '{}'", size);
+            }
+        }
         let introspection_sources = if plan.config.introspection().is_some() {
             self.catalog.allocate_introspection_source_indexes()
         } else {
@@ -2005,21 +3151,37 @@ impl Coordinator {
             config: plan.config.clone(),
             introspection_sources,
         };
-        let r = self.catalog_transact(vec![op], |_| Ok(())).await;
+        let r = self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await;
         match r {
             Ok(()) => {
                 let instance = self
                     .catalog
                     .resolve_compute_instance(&plan.name)
                     .expect("compute instance must exist after creation");
-                self.dataflow_client
+                let res = self
+                    .dataflow_client
                     .create_instance(
                         instance.id,
                         instance.config.clone(),
                         instance.logging.clone(),
                     )
-                    .await
-                    .unwrap();
+                    .await;
+                if let Err(e) = res {
+                    // The controller has already retried launching the
+                    // instance's replicas and cleaned up after them, so all
+                    // that is left is to roll back the catalog.
+                    let ids_to_drop: Vec<GlobalId> = instance.indexes().iter().cloned().collect();
+                    let mut ops = self.catalog.drop_items_ops(&ids_to_drop);
+                    ops.push(catalog::Op::DropComputeInstance { name: plan.name });
+                    self.catalog_transact(Some(session), ops, |_| Ok(()))
+                        .await
+                        .expect("dropping newly created cluster cannot fail");
+                    return Err(CoordError::Unstructured(
+                        e.context("unable to launch cluster replicas"),
+                    ));
+                }
                 Ok(ExecuteResponse::CreatedComputeInstance { existed: false })
             }
             Err(CoordError::Catalog(catalog::Error {
@@ -2034,6 +3196,7 @@ impl Coordinator {
 
     async fn sequence_alter_compute_instance(
         &mut self,
+        session: &Session,
         plan: AlterComputeInstancePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let instance = self.catalog.state().get_compute_instance(plan.id);
@@ -2045,7 +3208,7 @@ impl Coordinator {
         }];
         let mut replicas_to_remove = vec![];
         let mut replicas_to_add = vec![];
-        self.catalog_transact(ops, |tx| {
+        self.catalog_transact(Some(session), ops, |tx| {
             let new_config = &tx.catalog.get_compute_instance(plan.id).config;
             match (old_config, new_config) {
                 (InstanceConfig::Local, InstanceConfig::Local) => Ok(()),
@@ -2101,72 +3264,121 @@ impl Coordinator {
         Ok(ExecuteResponse::AlteredObject(ObjectType::Cluster))
     }
 
-    async fn sequence_create_secret(
+    fn sequence_create_secret(
         &mut self,
-        session: &Session,
+        tx: ClientTransmitter<ExecuteResponse>,
+        session: Session,
         plan: CreateSecretPlan,
-    ) -> Result<ExecuteResponse, CoordError> {
+    ) {
         let CreateSecretPlan {
             name,
             secret,
-            full_name,
+            full_name: _,
             if_not_exists,
         } = plan;
 
+        let payload = match self.evaluate_secret(&session, secret.secret_as, secret.format) {
+            Ok(payload) => payload,
+            Err(e) => return tx.send(Err(e), session),
+        };
+        let id = match self.catalog.allocate_user_id() {
+            Ok(id) => id,
+            Err(e) => return tx.send(Err(e.into()), session),
+        };
+        let oid = match self.catalog.allocate_oid() {
+            Ok(oid) => oid,
+            Err(e) => return tx.send(Err(e.into()), session),
+        };
+        let secret = catalog::Secret {
+            create_sql: secret.create_sql,
+        };
+
+        // Writing a secret is idempotent, and the secrets controller applies
+        // operations atomically, so a failed write can be retried as is. A
+        // write that times out may have been partially applied, but the
+        // secret is not yet in the catalog, so that is harmless: the secret
+        // is either overwritten by the retry or orphaned.
+        let ops = vec![SecretOp::Ensure {
+            id,
+            contents: payload,
+        }];
+        let user = session.user().to_string();
+        let follow_up = SecretOpsFollowUp::CreateSecret {
+            session,
+            tx,
+            id,
+            oid,
+            name,
+            secret,
+            if_not_exists,
+        };
+        self.spawn_secret_ops(&user, ops, true, follow_up);
+    }
+
+    fn sequence_alter_secret(
+        &mut self,
+        tx: ClientTransmitter<ExecuteResponse>,
+        session: Session,
+        AlterSecretPlan {
+            id,
+            secret_as,
+            format,
+        }: AlterSecretPlan,
+    ) {
+        let payload = match self.evaluate_secret(&session, secret_as, format) {
+            Ok(payload) => payload,
+            Err(e) => return tx.send(Err(e), session),
+        };
+
+        // Unlike the write that creates a secret, this write is not retried:
+        // a write that failed may have been applied, and a retry would then
+        // write another version of the secret.
+        let ops = vec![SecretOp::Ensure {
+            id,
+            contents: payload,
+        }];
+        let user = session.user().to_string();
+        let follow_up = SecretOpsFollowUp::AlterSecret { session, tx, id };
+        self.spawn_secret_ops(&user, ops, false, follow_up);
+    }
+
+    /// Evaluates the contents of a secret from `secret_as`, and validates them
+    /// against the secret's `format` and the maximum size of secrets.
+    fn evaluate_secret(
+        &self,
+        session: &Session,
+        mut secret_as: MirScalarExpr,
+        format: SecretFormat,
+    ) -> Result<Vec<u8>, CoordError> {
         let temp_storage = RowArena::new();
         prep_scalar_expr(
             self.catalog.state(),
-            &mut secret.secret_as.clone(),
+            &mut secret_as,
             ExprPrepStyle::OneShot {
                 logical_time: None,
                 session,
             },
         )?;
-        let evaled = secret.secret_as.eval(&[], &temp_storage)?;
+        let evaled = secret_as.eval(&[], &temp_storage)?;
 
         if evaled == Datum::Null {
             coord_bail!("secret value can not be null");
         }
 
         let payload = evaled.unwrap_bytes();
-
-        let id = self.catalog.allocate_user_id()?;
-        let oid = self.catalog.allocate_oid()?;
-        let secret = catalog::Secret {
-            create_sql: format!("CREATE SECRET {} AS '********'", full_name),
-        };
-
-        self.secrets_controller.apply(vec![SecretOp::Ensure {
-            id,
-            contents: Vec::from(payload),
-        }])?;
-
-        let ops = vec![catalog::Op::CreateItem {
-            id,
-            oid,
-            name,
-            item: CatalogItem::Secret(secret.clone()),
-        }];
-
-        match self.catalog_transact(ops, |_| Ok(())).await {
-            Ok(()) => Ok(ExecuteResponse::CreatedSecret { existed: false }),
-            Err(CoordError::Catalog(catalog::Error {
-                kind: catalog::ErrorKind::ItemAlreadyExists(_),
-                ..
-            })) if if_not_exists => Ok(ExecuteResponse::CreatedSecret { existed: true }),
-            Err(err) => {
-                match self.secrets_controller.apply(vec![SecretOp::Delete { id }]) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        warn!(
-                            "Dropping newly created secrets has encountered an error: {}",
-                            e
-                        );
-                    }
-                }
-                Err(err)
-            }
+        if payload.len() > self.max_secret_size {
+            return Err(CoordError::SecretTooLarge {
+                size: payload.len(),
+                limit: self.max_secret_size,
+            });
         }
+        if let Err(e) = format.validate(payload) {
+            return Err(CoordError::InvalidSecret {
+                format,
+                reason: e.to_string(),
+            });
+        }
+        Ok(Vec::from(payload))
     }
 
     async fn sequence_create_table(
@@ -2205,7 +3417,7 @@ impl Coordinator {
             name,
             item: CatalogItem::Table(table.clone()),
         }];
-        match self.catalog_transact(ops, |_| Ok(())).await {
+        match self.catalog_transact(Some(session), ops, |_| Ok(())).await {
             Ok(()) => {
                 // Determine the initial validity for the table.
                 self.persister
@@ -2265,7 +3477,18 @@ impl Coordinator {
             connector: plan.source.connector,
             persist_details,
             desc: plan.source.desc,
+            depends_on: plan.source.depends_on,
+        };
+        // The contents of the secrets that configure the source are only
+        // described to storage, never recorded in the catalog.
+        let mut source_description = SourceDesc {
+            connector: source.connector.clone(),
+            desc: source.desc.clone(),
         };
+        let secret_versions = self.read_source_secrets(&mut source_description).await?;
+        // The source does not exist in the catalog yet, so the versions are
+        // reflected in `mz_secret_uses` when it is created.
+        self.catalog.set_secret_uses(source_id, secret_versions);
         ops.push(catalog::Op::CreateItem {
             id: source_id,
             oid: source_oid,
@@ -2308,8 +3531,8 @@ impl Coordinator {
         } else {
             None
         };
-        match self
-            .catalog_transact(ops, move |txn| {
+        let res = self
+            .catalog_transact(Some(session), ops, move |txn| {
                 if let Some((index_id, compute_instance)) = index {
                     let mut builder = txn.dataflow_builder(compute_instance);
                     Ok(builder
@@ -2319,8 +3542,11 @@ impl Coordinator {
                     Ok(None)
                 }
             })
-            .await
-        {
+            .await;
+        if res.is_err() {
+            self.catalog.set_secret_uses(source_id, BTreeMap::new());
+        }
+        match res {
             Ok(df) => {
                 // Do everything to instantiate the source at the coordinator and
                 // inform the timestamper and dataflow workers of its existence before
@@ -2335,12 +3561,6 @@ impl Coordinator {
                     .map(|p| p.since_ts)
                     .unwrap_or_else(Timestamp::minimum);
 
-                let source_description = self
-                    .catalog
-                    .state()
-                    .source_description_for(source_id)
-                    .unwrap();
-
                 self.dataflow_client
                     .storage_mut()
                     .create_sources(vec![(
@@ -2400,6 +3620,20 @@ impl Coordinator {
             }
         };
 
+        // The contents of the secrets that configure the sink are only given
+        // to the sink connector, never recorded in the catalog.
+        let mut connector_builder = sink.connector_builder.clone();
+        let secret_versions = match self.read_sink_secrets(&mut connector_builder).await {
+            Ok(versions) => versions,
+            Err(e) => {
+                tx.send(Err(e), session);
+                return;
+            }
+        };
+        // The sink does not exist in the catalog yet, so the versions are
+        // reflected in `mz_secret_uses` when it is created.
+        self.catalog.set_secret_uses(id, secret_versions);
+
         // Then try to create a placeholder catalog item with an unknown
         // connector. If that fails, we're done, though if the client specified
         // `if_not_exists` we'll tell the client we succeeded.
@@ -2422,7 +3656,7 @@ impl Coordinator {
         };
 
         let transact_result = self
-            .catalog_transact(vec![op], |txn| -> Result<(), CoordError> {
+            .catalog_transact(Some(&session), vec![op], |txn| -> Result<(), CoordError> {
                 let from_entry = txn.catalog.get_entry(&sink.from);
                 // Insert a dummy dataflow to trigger validation before we try to actually create
                 // the external sink resources (e.g. Kafka Topics)
@@ -2450,6 +3684,9 @@ impl Coordinator {
                     .map(|_ok| ())
             })
             .await;
+        if transact_result.is_err() {
+            self.catalog.set_secret_uses(id, BTreeMap::new());
+        }
         match transact_result {
             Ok(()) => (),
             Err(CoordError::Catalog(catalog::Error {
@@ -2467,8 +3704,8 @@ impl Coordinator {
 
         // Now we're ready to create the sink connector. Arrange to notify the
         // main coordinator thread when the future completes.
-        let connector_builder = sink.connector_builder;
         let internal_cmd_tx = self.internal_cmd_tx.clone();
+        let statement = self.current_statement();
         task::spawn(
             || format!("sink_connector_ready:{}", sink.from),
             async move {
@@ -2480,6 +3717,7 @@ impl Coordinator {
                         oid,
                         result: sink_connector::build(connector_builder, id).await,
                         compute_instance,
+                        statement,
                     }))
                     .expect("sending to internal_cmd_tx cannot fail");
             },
@@ -2504,6 +3742,7 @@ impl Coordinator {
         let view_id = self.catalog.allocate_user_id()?;
         let view_oid = self.catalog.allocate_oid()?;
         let optimized_expr = self.view_optimizer.optimize(view.expr)?;
+        self.check_plan_safety(&optimized_expr)?;
         let desc = RelationDesc::new(optimized_expr.typ(), view.column_names);
         let view = catalog::View {
             create_sql: view.create_sql,
@@ -2576,7 +3815,7 @@ impl Coordinator {
             plan.materialize,
         )?;
         match self
-            .catalog_transact(ops, |txn| {
+            .catalog_transact(Some(session), ops, |txn| {
                 if let Some((index_id, compute_instance)) = index {
                     let mut builder = txn.dataflow_builder(compute_instance);
                     Ok(builder
@@ -2617,7 +3856,7 @@ impl Coordinator {
             indexes.extend(index);
         }
         match self
-            .catalog_transact(ops, |txn| {
+            .catalog_transact(Some(session), ops, |txn| {
                 let mut dfs = HashMap::new();
                 for (index_id, compute_instance) in indexes {
                     let mut builder = txn.dataflow_builder(compute_instance);
@@ -2667,6 +3906,7 @@ impl Coordinator {
             depends_on: index.depends_on,
             enabled: self.catalog.index_enabled_by_default(&id),
             compute_instance,
+            logical_compaction_window: catalog::Index::logical_compaction_window(&options),
         };
         let oid = self.catalog.allocate_oid()?;
         let op = catalog::Op::CreateItem {
@@ -2676,7 +3916,7 @@ impl Coordinator {
             item: CatalogItem::Index(index),
         };
         match self
-            .catalog_transact(vec![op], |txn| {
+            .catalog_transact(Some(session), vec![op], |txn| {
                 let mut builder = txn.dataflow_builder(compute_instance);
                 let df = builder.build_index_dataflow(id)?;
                 Ok(df)
@@ -2686,9 +3926,6 @@ impl Coordinator {
             Ok(df) => {
                 if let Some(df) = df {
                     self.ship_dataflow(df, compute_instance).await;
-                    self.set_index_options(id, options, session)
-                        .await
-                        .expect("index enabled");
                 }
                 Ok(ExecuteResponse::CreatedIndex { existed: false })
             }
@@ -2702,6 +3939,7 @@ impl Coordinator {
 
     async fn sequence_create_type(
         &mut self,
+        session: &Session,
         plan: CreateTypePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let typ = catalog::Type {
@@ -2720,32 +3958,104 @@ impl Coordinator {
             name: plan.name,
             item: CatalogItem::Type(typ),
         };
-        match self.catalog_transact(vec![op], |_| Ok(())).await {
+        match self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+        {
             Ok(()) => Ok(ExecuteResponse::CreatedType),
             Err(err) => Err(err),
         }
     }
 
+    async fn sequence_create_publication(
+        &mut self,
+        session: &Session,
+        plan: CreatePublicationPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let publication = catalog::Publication {
+            create_sql: plan.publication.create_sql,
+            views: plan.publication.views,
+        };
+        let id = self.catalog.allocate_user_id()?;
+        let oid = self.catalog.allocate_oid()?;
+        let op = catalog::Op::CreateItem {
+            id,
+            oid,
+            name: plan.name,
+            item: CatalogItem::Publication(publication),
+        };
+        match self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+        {
+            Ok(()) => Ok(ExecuteResponse::CreatedPublication { existed: false }),
+            Err(CoordError::Catalog(catalog::Error {
+                kind: catalog::ErrorKind::ItemAlreadyExists(_),
+                ..
+            })) if plan.if_not_exists => Ok(ExecuteResponse::CreatedPublication { existed: true }),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn sequence_create_task(
+        &mut self,
+        session: &Session,
+        plan: CreateTaskPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let task = catalog::Task {
+            create_sql: plan.task.create_sql,
+            schedule: plan.task.schedule,
+            depends_on: plan.task.depends_on,
+        };
+        let id = self.catalog.allocate_user_id()?;
+        let oid = self.catalog.allocate_oid()?;
+        let op = catalog::Op::CreateItem {
+            id,
+            oid,
+            name: plan.name,
+            item: CatalogItem::Task(task),
+        };
+        match self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+        {
+            Ok(()) => {
+                self.install_scheduled_task(id);
+                Ok(ExecuteResponse::CreatedTask { existed: false })
+            }
+            Err(CoordError::Catalog(catalog::Error {
+                kind: catalog::ErrorKind::ItemAlreadyExists(_),
+                ..
+            })) if plan.if_not_exists => Ok(ExecuteResponse::CreatedTask { existed: true }),
+            Err(err) => Err(err),
+        }
+    }
+
     async fn sequence_drop_database(
         &mut self,
+        session: &Session,
         plan: DropDatabasePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let ops = self.catalog.drop_database_ops(plan.id);
-        self.catalog_transact(ops, |_| Ok(())).await?;
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
+            .await?;
         Ok(ExecuteResponse::DroppedDatabase)
     }
 
     async fn sequence_drop_schema(
         &mut self,
+        session: &Session,
         plan: DropSchemaPlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let ops = self.catalog.drop_schema_ops(plan.id);
-        self.catalog_transact(ops, |_| Ok(())).await?;
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
+            .await?;
         Ok(ExecuteResponse::DroppedSchema)
     }
 
     async fn sequence_drop_roles(
         &mut self,
+        session: &Session,
         plan: DropRolesPlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let ops = plan
@@ -2753,12 +4063,14 @@ impl Coordinator {
             .into_iter()
             .map(|name| catalog::Op::DropRole { name })
             .collect();
-        self.catalog_transact(ops, |_| Ok(())).await?;
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
+            .await?;
         Ok(ExecuteResponse::DroppedRole)
     }
 
     async fn sequence_drop_compute_instances(
         &mut self,
+        session: &Session,
         plan: DropComputeInstancesPlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let mut ops = Vec::new();
@@ -2771,7 +4083,8 @@ impl Coordinator {
             ops.push(catalog::Op::DropComputeInstance { name });
         }
 
-        self.catalog_transact(ops, |_| Ok(())).await?;
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
+            .await?;
         for id in instance_ids {
             self.dataflow_client.drop_instance(id).await.unwrap();
         }
@@ -2780,10 +4093,12 @@ impl Coordinator {
 
     async fn sequence_drop_items(
         &mut self,
+        session: &Session,
         plan: DropItemsPlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let ops = self.catalog.drop_items_ops(&plan.items);
-        self.catalog_transact(ops, |_| Ok(())).await?;
+        self.catalog_transact(Some(session), ops, |_| Ok(()))
+            .await?;
         Ok(match plan.ty {
             ObjectType::Source => ExecuteResponse::DroppedSource,
             ObjectType::View => ExecuteResponse::DroppedView,
@@ -2792,12 +4107,71 @@ impl Coordinator {
             ObjectType::Index => ExecuteResponse::DroppedIndex,
             ObjectType::Type => ExecuteResponse::DroppedType,
             ObjectType::Secret => ExecuteResponse::DroppedSecret,
+            ObjectType::Publication => ExecuteResponse::DroppedPublication,
+            ObjectType::Task => ExecuteResponse::DroppedTask,
             ObjectType::Role => unreachable!("DROP ROLE is handled elsewhere"),
             ObjectType::Cluster => unreachable!("DROP CLUSTER is handled elsewhere"),
             ObjectType::Object => unreachable!("generic OBJECT cannot be dropped"),
         })
     }
 
+    /// Lists the objects that the `DROP` statement planned as `plan.plan`
+    /// would drop, in the order in which it would drop them, without dropping
+    /// them.
+    fn sequence_explain_drop(
+        &mut self,
+        session: &Session,
+        plan: ExplainDropPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let ops = match *plan.plan {
+            Plan::DropDatabase(plan) => self.catalog.drop_database_ops(plan.id),
+            Plan::DropSchema(plan) => self.catalog.drop_schema_ops(plan.id),
+            Plan::DropItems(plan) => self.catalog.drop_items_ops(&plan.items),
+            Plan::DropComputeInstances(plan) => {
+                let mut ops = Vec::new();
+                for name in plan.names {
+                    let instance = self.catalog.resolve_compute_instance(&name)?;
+                    let ids_to_drop: Vec<GlobalId> = instance.indexes().iter().cloned().collect();
+                    ops.extend(self.catalog.drop_items_ops(&ids_to_drop));
+                    ops.push(catalog::Op::DropComputeInstance { name });
+                }
+                ops
+            }
+            plan => unreachable!("EXPLAIN of unsupported DROP plan: {:?}", plan),
+        };
+        let rows = ops
+            .into_iter()
+            .map(|op| {
+                let (name, typ) = match op {
+                    catalog::Op::DropItem(id) => {
+                        let entry = self.catalog.get_entry(&id);
+                        let name = self
+                            .catalog
+                            .resolve_full_name(entry.name(), Some(session.conn_id()));
+                        (name.to_string(), entry.item_type().to_string())
+                    }
+                    catalog::Op::DropSchema {
+                        database_id,
+                        schema_id,
+                    } => {
+                        let database = self.catalog.get_database(&database_id);
+                        let schema = &database.schemas_by_id[&schema_id];
+                        let name = format!("{}.{}", database.name, schema.name.schema);
+                        (name, "schema".into())
+                    }
+                    catalog::Op::DropDatabase { id } => {
+                        let name = self.catalog.get_database(&id).name.clone();
+                        (name, "database".into())
+                    }
+                    catalog::Op::DropComputeInstance { name } => (name, "cluster".into()),
+                    op => unreachable!("unexpected drop op: {:?}", op),
+                };
+                Row::pack_slice(&[Datum::String(&name), Datum::String(&typ)])
+            })
+            .collect();
+        Ok(send_immediate_rows(rows))
+    }
+
     fn sequence_show_all_variables(
         &mut self,
         session: &Session,
@@ -2806,6 +4180,7 @@ impl Coordinator {
             session
                 .vars()
                 .iter()
+                .chain(self.system_vars.iter())
                 .filter(|v| !v.experimental())
                 .map(|v| {
                     Row::pack_slice(&[
@@ -2823,7 +4198,10 @@ impl Coordinator {
         session: &Session,
         plan: ShowVariablePlan,
     ) -> Result<ExecuteResponse, CoordError> {
-        let variable = session.vars().get(&plan.name)?;
+        let variable = match session.vars().get(&plan.name) {
+            Err(CoordError::UnknownParameter(_)) => self.system_vars.get(&plan.name)?,
+            variable => variable?,
+        };
         let row = Row::pack_slice(&[Datum::String(&variable.value())]);
         Ok(send_immediate_rows(vec![row]))
     }
@@ -2833,10 +4211,122 @@ impl Coordinator {
         session: &mut Session,
         plan: SetVariablePlan,
     ) -> Result<ExecuteResponse, CoordError> {
-        session
-            .vars_mut()
-            .set(&plan.name, &plan.value, plan.local)?;
-        Ok(ExecuteResponse::SetVariable { name: plan.name })
+        match &plan.value {
+            Some(value) => {
+                if plan.name.eq_ignore_ascii_case("cluster") {
+                    self.check_cluster_permitted(session, value)?;
+                }
+                session.vars_mut().set(&plan.name, value, plan.local)?;
+            }
+            None => session.vars_mut().reset(&plan.name, plan.local)?,
+        }
+        Ok(ExecuteResponse::SetVariable {
+            name: plan.name,
+            reset: false,
+        })
+    }
+
+    fn sequence_reset_variable(
+        &self,
+        session: &mut Session,
+        plan: ResetVariablePlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let local = false;
+        session.vars_mut().reset(&plan.name, local)?;
+        Ok(ExecuteResponse::SetVariable {
+            name: plan.name,
+            reset: true,
+        })
+    }
+
+    /// Verifies that the session's role may log in, may connect over the
+    /// session's connection, and may use the session's cluster.
+    fn check_role_restrictions(&self, session: &Session) -> Result<(), CoordError> {
+        let role = match self.catalog.try_get_role(session.user()) {
+            Some(role) => role,
+            None => return Ok(()),
+        };
+        let restrictions = &role.restrictions;
+        if !restrictions.login {
+            return Err(CoordError::LoginNotPermitted(role.name.clone()));
+        }
+        let not_permitted = |reason: String| CoordError::ConnectionNotPermitted {
+            role: role.name.clone(),
+            reason,
+        };
+        let info = session.connection_info();
+        if restrictions.require_tls && !info.map_or(false, |info| info.tls) {
+            return Err(not_permitted("without TLS".into()));
+        }
+        if !restrictions.allowed_addresses.is_empty() {
+            let addr = match info.and_then(|info| info.peer_addr) {
+                // Clients that connect over IPv4 to a dual-stack listener
+                // appear with IPv4-mapped IPv6 addresses.
+                Some(IpAddr::V6(addr)) if addr.segments()[..6] == [0, 0, 0, 0, 0, 0xffff] => {
+                    addr.to_ipv4().map_or(IpAddr::V6(addr), IpAddr::V4)
+                }
+                Some(addr) => addr,
+                None => return Err(not_permitted("from an unknown address".into())),
+            };
+            if !restrictions
+                .allowed_addresses
+                .iter()
+                .any(|net| net.contains(&addr))
+            {
+                return Err(not_permitted(format!("from {}", addr)));
+            }
+        }
+        self.check_cluster_permitted(session, session.vars().cluster())
+    }
+
+    /// Verifies that the session's role may use the cluster on which `plan`
+    /// runs.
+    ///
+    /// The session's cluster is also checked when the session starts and when
+    /// the cluster is set, but some plans name their cluster explicitly with
+    /// `IN CLUSTER`.
+    fn check_plan_cluster(&self, session: &Session, plan: &Plan) -> Result<(), CoordError> {
+        let compute_instance = match plan {
+            Plan::CreateIndex(plan) => plan.index.compute_instance,
+            Plan::CreateSink(plan) => plan.sink.compute_instance,
+            Plan::CreateSource(CreateSourcePlan {
+                materialized: true, ..
+            })
+            | Plan::CreateView(CreateViewPlan {
+                materialize: true, ..
+            })
+            | Plan::CreateViews(CreateViewsPlan {
+                materialize: true, ..
+            })
+            | Plan::Peek(_)
+            | Plan::Tail(_)
+            | Plan::Explain(_) => {
+                return self.check_cluster_permitted(session, session.vars().cluster());
+            }
+            _ => return Ok(()),
+        };
+        let name = &self.catalog.get_compute_instance(compute_instance).name;
+        self.check_cluster_permitted(session, name)
+    }
+
+    /// Verifies that the session's role may use the named cluster.
+    fn check_cluster_permitted(&self, session: &Session, cluster: &str) -> Result<(), CoordError> {
+        let allowed_clusters = match self
+            .catalog
+            .try_get_role(session.user())
+            .and_then(|role| role.restrictions.allowed_clusters.as_ref())
+        {
+            Some(allowed_clusters) => allowed_clusters,
+            None => return Ok(()),
+        };
+        if allowed_clusters.iter().any(|c| c == cluster) {
+            Ok(())
+        } else {
+            Err(CoordError::ClusterNotPermitted {
+                role: session.user().into(),
+                cluster: cluster.into(),
+            })
+        }
     }
 
     async fn sequence_end_transaction(
@@ -3102,6 +4592,22 @@ impl Coordinator {
         session: &mut Session,
         plan: PeekPlan,
     ) -> Result<ExecuteResponse, CoordError> {
+        let (resp, _strategy) = self.sequence_peek_inner(session, plan, false).await?;
+        Ok(resp)
+    }
+
+    /// Like [`Coordinator::sequence_peek`], but additionally reports how the
+    /// peek was implemented.
+    ///
+    /// If `retain_dataflow` is set, the dataflow that is built for the peek,
+    /// if any, is not dropped once the peek is issued, and the caller becomes
+    /// responsible for dropping it.
+    async fn sequence_peek_inner(
+        &mut self,
+        session: &mut Session,
+        plan: PeekPlan,
+        retain_dataflow: bool,
+    ) -> Result<(ExecuteResponse, PeekStrategy), CoordError> {
         // TODO: remove this function when sources are linearizable.
         // See: #11048.
         fn check_no_unmaterialized_sources(
@@ -3153,7 +4659,7 @@ impl Coordinator {
 
         let PeekPlan {
             mut source,
-            when,
+            mut when,
             finishing,
             copy_to,
         } = plan;
@@ -3165,6 +4671,25 @@ impl Coordinator {
 
         let source_ids = source.depends_on();
 
+        // Reads in a transaction that has written may only read tables, and
+        // see the transaction's writes. The session holds the write lock, so
+        // the tables do not change between the transaction's reads.
+        if !session.transaction_writes().is_empty() && when == QueryWhen::Immediately {
+            for id in &source_ids {
+                match self.catalog.try_get_entry(id) {
+                    Some(entry) if id.is_user() && entry.is_table() => (),
+                    _ => return Err(CoordError::WriteOnlyTransaction),
+                }
+            }
+            read_transaction_writes(&mut source, session.transaction_writes());
+            when = QueryWhen::AtTimestamp(MirScalarExpr::literal_ok(
+                Datum::from(Numeric::from(self.get_local_read_ts())),
+                ScalarType::Numeric {
+                    max_scale: Some(NumericMaxScale::ZERO),
+                },
+            ));
+        }
+
         let timeline = self.validate_timeline(source_ids.clone())?;
         let conn_id = session.conn_id();
         let in_transaction = matches!(
@@ -3271,7 +4796,7 @@ impl Coordinator {
             let id_bundle = self
                 .index_oracle(compute_instance)
                 .sufficient_collections(&source_ids);
-            if when == QueryWhen::Immediately {
+            if !matches!(when, QueryWhen::AtTimestamp(_)) {
                 check_no_unmaterialized_sources(&self.catalog, &id_bundle, session)?;
             }
             self.determine_timestamp(session, &id_bundle, when, compute_instance)?
@@ -3283,7 +4808,8 @@ impl Coordinator {
         // ------------------------------
         // after we have the timestamp \/
 
-        let source = self.view_optimizer.optimize(source)?;
+        let source = self.optimize_cached(source)?;
+        self.check_plan_safety(&source)?;
 
         // We create a dataflow and optimize it, to determine if we can avoid building it.
         // This can happen if the result optimizes to a constant, or to a `Get` expression
@@ -3300,7 +4826,12 @@ impl Coordinator {
         let view_id = self.allocate_transient_id()?;
         let index_id = self.allocate_transient_id()?;
         // The assembled dataflow contains a view and an index of that view.
-        let mut dataflow = DataflowDesc::new(format!("temp-view-{}", view_id));
+        let debug_name = if retain_dataflow {
+            format!("explain-analyze-{}", view_id)
+        } else {
+            format!("temp-view-{}", view_id)
+        };
+        let mut dataflow = DataflowDesc::new(debug_name.clone());
         dataflow.set_as_of(Antichain::from_elem(timestamp));
         let mut builder = self.dataflow_builder(compute_instance);
         builder.import_view_into_dataflow(&view_id, &source, &mut dataflow)?;
@@ -3340,6 +4871,23 @@ impl Coordinator {
             thinning.len(),
         )?;
 
+        let strategy = match &fast_path {
+            fast_path_peek::Plan::Constant(_) => PeekStrategy::Constant,
+            fast_path_peek::Plan::PeekExisting(id, _, _) => PeekStrategy::Existing(*id),
+            fast_path_peek::Plan::PeekDataflow(_) => {
+                // Only peeks that build dataflows over user objects are
+                // subject to the memory limit of the cluster.
+                if source_ids.iter().any(|id| id.is_user()) {
+                    self.check_cluster_memory(compute_instance, session.vars().cluster())?;
+                }
+                PeekStrategy::Dataflow {
+                    compute_instance,
+                    index_id,
+                    debug_name,
+                }
+            }
+        };
+
         // Implement the peek, and capture the response.
         let resp = self
             .implement_fast_path_peek(
@@ -3349,16 +4897,19 @@ impl Coordinator {
                 conn_id,
                 source.arity(),
                 compute_instance,
+                retain_dataflow,
+                PeekLimits::from_session(session),
             )
             .await?;
 
-        match copy_to {
-            None => Ok(resp),
-            Some(format) => Ok(ExecuteResponse::CopyTo {
+        let resp = match copy_to {
+            None => resp,
+            Some(format) => ExecuteResponse::CopyTo {
                 format,
                 resp: Box::new(resp),
-            }),
-        }
+            },
+        };
+        Ok((resp, strategy))
     }
 
     async fn sequence_tail(
@@ -3372,6 +4923,9 @@ impl Coordinator {
             when,
             copy_to,
             emit_progress,
+            envelope,
+            max_unacked_batches,
+            up_to,
         } = plan;
 
         let compute_instance = self
@@ -3432,7 +4986,8 @@ impl Coordinator {
                 depends_on,
             } => {
                 let id = self.allocate_transient_id()?;
-                let expr = self.view_optimizer.optimize(expr)?;
+                let expr = self.optimize_cached(expr)?;
+                self.check_plan_safety(&expr)?;
                 let desc = RelationDesc::new(expr.typ(), desc.iter_names());
                 let sink_desc = make_sink_desc(self, id, desc, &depends_on)?;
                 let mut dataflow = DataflowDesc::new(format!("tail-{}", id));
@@ -3444,12 +4999,78 @@ impl Coordinator {
         };
 
         let (sink_id, sink_desc) = dataflow.sink_exports.iter().next().unwrap();
-        session.add_drop_sink(compute_instance, *sink_id);
+        let sink_id = *sink_id;
+        let up_to = match up_to {
+            None => None,
+            Some(up_to) => {
+                let up_to = self.evaluate_timestamp(session, up_to, "UP TO")?;
+                let as_of = *sink_desc.as_of.frontier.as_option().unwrap();
+                if up_to < as_of {
+                    coord_bail!(
+                        "UP TO timestamp {} is before the AS OF timestamp {}",
+                        up_to,
+                        as_of
+                    );
+                }
+                Some(up_to)
+            }
+        };
+        session.add_drop_sink(compute_instance, sink_id);
         let arity = sink_desc.from_desc.arity();
         let (tx, rx) = mpsc::unbounded_channel();
-        self.pending_tails
-            .insert(*sink_id, PendingTail::new(tx, emit_progress, arity));
+        let (flow, rx) = match max_unacked_batches {
+            None => (None, RowBatchStream::new(rx)),
+            Some(max_unacked_batches) => {
+                // Hold back compaction of the tail's inputs so that its
+                // dataflow can be recreated if the client falls behind.
+                let id_bundle = CollectionIdBundle {
+                    storage_ids: dataflow.source_imports.keys().copied().collect(),
+                    compute_ids: dataflow.index_imports.keys().copied().collect(),
+                };
+                let flow = TailFlow::new(
+                    max_unacked_batches,
+                    dataflow.clone(),
+                    compute_instance,
+                    id_bundle,
+                );
+                let holds = self.tail_read_holds(flow.hold, &flow.id_bundle, compute_instance);
+                self.acquire_read_holds(&holds).await;
+                let internal_cmd_tx = self.internal_cmd_tx.clone();
+                let rx = RowBatchStream::with_ack(rx, move || {
+                    // It is not an error for the coordinator to have shut down.
+                    let _ = internal_cmd_tx.send(Message::TailAcked(sink_id));
+                });
+                (Some(flow), rx)
+            }
+        };
+        self.pending_tails.insert(
+            sink_id,
+            PendingTail::new(
+                tx,
+                emit_progress,
+                envelope,
+                arity,
+                sink_id,
+                compute_instance,
+                flow,
+                session.vars().max_result_rows(),
+                up_to,
+            ),
+        );
+        self.tail_sinks.insert(sink_id, sink_id);
         self.ship_dataflow(dataflow, compute_instance).await;
+        if emit_progress {
+            self.schedule_tail_heartbeat(sink_id);
+        }
+
+        if let Some(timeout) = session.vars().statement_timeout() {
+            let internal_cmd_tx = self.internal_cmd_tx.clone();
+            task::spawn(|| format!("tail_timeout:{sink_id}"), async move {
+                tokio::time::sleep(timeout).await;
+                // If sending fails, the main thread has shutdown.
+                let _ = internal_cmd_tx.send(Message::TailTimedOut(compute_instance, sink_id));
+            });
+        }
 
         let resp = ExecuteResponse::Tailing { rx };
         match copy_to {
@@ -3524,6 +5145,76 @@ impl Coordinator {
         since
     }
 
+    /// The largest timestamp that is closed by all the specified collections,
+    /// i.e., the largest timestamp at which they can be read without waiting.
+    fn largest_closed_timestamp(
+        &self,
+        id_bundle: &CollectionIdBundle,
+        instance: ComputeInstanceId,
+    ) -> Timestamp {
+        let upper = self.least_valid_write(id_bundle, instance);
+
+        // We peek at the largest element not in advance of `upper`, which
+        // involves a subtraction. If `upper` contains a zero timestamp there
+        // is no "prior" answer, and we do not want to peek at it as it risks
+        // hanging awaiting the response to data that may never arrive.
+        //
+        // The .get(0) here breaks the antichain abstraction by assuming this antichain
+        // has 0 or 1 elements in it. It happens to work because we use a timestamp
+        // type that meets that assumption, but would break if we used a more general
+        // timestamp.
+        if let Some(candidate) = upper.elements().get(0) {
+            if *candidate > Timestamp::minimum() {
+                candidate.saturating_sub(1)
+            } else {
+                Timestamp::minimum()
+            }
+        } else {
+            // A complete trace can be read in its final form with this time.
+            //
+            // This should only happen for literals that have no sources or sources that
+            // are known to have completed (non-tailed files for example).
+            Timestamp::MAX
+        }
+    }
+
+    /// Evaluates `timestamp`, the expression in the `clause` clause of a
+    /// statement, like `AS OF`, to a timestamp.
+    fn evaluate_timestamp(
+        &self,
+        session: &Session,
+        mut timestamp: MirScalarExpr,
+        clause: &str,
+    ) -> Result<Timestamp, CoordError> {
+        let temp_storage = RowArena::new();
+        prep_scalar_expr(
+            self.catalog.state(),
+            &mut timestamp,
+            ExprPrepStyle::OneShot {
+                logical_time: None,
+                session,
+            },
+        )?;
+        let evaled = timestamp.eval(&[], &temp_storage)?;
+        let ty = timestamp.typ(&RelationType::empty());
+        Ok(match ty.scalar_type {
+            ScalarType::Numeric { .. } => {
+                let n = evaled.unwrap_numeric().0;
+                u64::try_from(n)?
+            }
+            ScalarType::Int16 => evaled.unwrap_int16().try_into()?,
+            ScalarType::Int32 => evaled.unwrap_int32().try_into()?,
+            ScalarType::Int64 => evaled.unwrap_int64().try_into()?,
+            ScalarType::TimestampTz => evaled.unwrap_timestamptz().timestamp_millis().try_into()?,
+            ScalarType::Timestamp => evaled.unwrap_timestamp().timestamp_millis().try_into()?,
+            _ => coord_bail!(
+                "can't use {} as a timestamp for {}",
+                self.catalog.for_session(session).humanize_column_type(&ty),
+                clause
+            ),
+        })
+    }
+
     /// Determines the timestamp for a query.
     ///
     /// Timestamp determination may fail due to the restricted validity of
@@ -3552,55 +5243,52 @@ impl Coordinator {
         // a larger timestamp and block, perhaps the user should intervene).
 
         let since = self.least_valid_read(&id_bundle, compute_instance);
+        let strict_serializable = *session.vars().transaction_isolation()
+            == IsolationLevel::StrictSerializable
+            && !matches!(when, QueryWhen::AtTimestamp(_));
 
         // First determine the candidate timestamp, which is either the explicitly requested
         // timestamp, or the latest timestamp known to be immediately available.
         let timestamp: Timestamp = match when {
             // Explicitly requested timestamps should be respected.
-            QueryWhen::AtTimestamp(mut timestamp) => {
-                let temp_storage = RowArena::new();
-                prep_scalar_expr(
-                    self.catalog.state(),
-                    &mut timestamp,
-                    ExprPrepStyle::OneShot {
-                        logical_time: None,
-                        session,
-                    },
-                )?;
-                let evaled = timestamp.eval(&[], &temp_storage)?;
-                let ty = timestamp.typ(&RelationType::empty());
-                match ty.scalar_type {
-                    ScalarType::Numeric { .. } => {
-                        let n = evaled.unwrap_numeric().0;
-                        u64::try_from(n)?
-                    }
-                    ScalarType::Int16 => evaled.unwrap_int16().try_into()?,
-                    ScalarType::Int32 => evaled.unwrap_int32().try_into()?,
-                    ScalarType::Int64 => evaled.unwrap_int64().try_into()?,
-                    ScalarType::TimestampTz => {
-                        evaled.unwrap_timestamptz().timestamp_millis().try_into()?
-                    }
-                    ScalarType::Timestamp => {
-                        evaled.unwrap_timestamp().timestamp_millis().try_into()?
-                    }
-                    _ => coord_bail!(
-                        "can't use {} as a timestamp for AS OF",
-                        self.catalog.for_session(session).humanize_column_type(&ty)
-                    ),
-                }
+            QueryWhen::AtTimestamp(timestamp) => {
+                self.evaluate_timestamp(session, timestamp, "AS OF")?
             }
 
             // These two strategies vary in terms of which traces drive the
             // timestamp determination process: either the trace itself or the
             // original sources on which they depend.
-            QueryWhen::Immediately => {
+            QueryWhen::Immediately | QueryWhen::AtLeastTimestamp(_) => {
                 // Initialize candidate to the minimum correct time.
                 let mut candidate = Timestamp::minimum();
                 candidate.advance_by(since.borrow());
 
                 // Compute a timestamp to which we should advance the candidate (if it is in
                 // advance).
-                let advance_to = if id_bundle.iter().any(|id| self.catalog.uses_tables(id)) {
+                let max_staleness = session.vars().max_read_staleness();
+                let advance_to = if strict_serializable {
+                    // Strict serializable reads must reflect every write and
+                    // read that completed before they began, in any session,
+                    // so they read at the latest time handed out by the
+                    // timestamp oracle, and wait for all inputs, not just
+                    // tables, to catch up to it. Stale reads are never
+                    // allowed.
+                    self.get_local_read_ts()
+                } else if max_staleness > Duration::ZERO {
+                    // The session tolerates stale reads, so read at the latest
+                    // time that all inputs have already closed, to avoid
+                    // waiting on lagging inputs, unless that time is staler
+                    // than the session allows. In that case read at the
+                    // oldest allowed time, and wait for the inputs to catch up
+                    // to it.
+                    let max_staleness =
+                        u64::try_from(max_staleness.as_millis()).unwrap_or(Timestamp::MAX);
+                    let oldest_allowed = self.now().saturating_sub(max_staleness);
+                    std::cmp::max(
+                        self.largest_closed_timestamp(id_bundle, compute_instance),
+                        oldest_allowed,
+                    )
+                } else if id_bundle.iter().any(|id| self.catalog.uses_tables(id)) {
                     // If the view depends on any tables, we enforce linearizability by choosing
                     // the latest input time.  If the candidate is already advanced past read_ts
                     // due to the since work above (if joined with some other view), a peek will
@@ -3613,32 +5301,19 @@ impl Coordinator {
                     // advanced already.
                     self.get_local_read_ts()
                 } else {
-                    let upper = self.least_valid_write(&id_bundle, compute_instance);
-
-                    // We peek at the largest element not in advance of `upper`, which
-                    // involves a subtraction. If `upper` contains a zero timestamp there
-                    // is no "prior" answer, and we do not want to peek at it as it risks
-                    // hanging awaiting the response to data that may never arrive.
-                    //
-                    // The .get(0) here breaks the antichain abstraction by assuming this antichain
-                    // has 0 or 1 elements in it. It happens to work because we use a timestamp
-                    // type that meets that assumption, but would break if we used a more general
-                    // timestamp.
-                    if let Some(candidate) = upper.elements().get(0) {
-                        if *candidate > Timestamp::minimum() {
-                            candidate.saturating_sub(1)
-                        } else {
-                            Timestamp::minimum()
-                        }
-                    } else {
-                        // A complete trace can be read in its final form with this time.
-                        //
-                        // This should only happen for literals that have no sources or sources that
-                        // are known to have completed (non-tailed files for example).
-                        Timestamp::MAX
-                    }
+                    self.largest_closed_timestamp(id_bundle, compute_instance)
                 };
                 candidate.join_assign(&advance_to);
+                // A lower bound on the timestamp, e.g. one that a client
+                // observed on another connection, delays the query until its
+                // inputs reach it rather than failing the query.
+                if let QueryWhen::AtLeastTimestamp(at_least) = when {
+                    candidate.join_assign(&self.evaluate_timestamp(
+                        session,
+                        at_least,
+                        "AS OF AT LEAST",
+                    )?);
+                }
                 candidate
             }
         };
@@ -3646,6 +5321,12 @@ impl Coordinator {
         // If the timestamp is greater or equal to some element in `since` we are
         // assured that the answer will be correct.
         if since.less_equal(&timestamp) {
+            if strict_serializable && timestamp > self.get_local_read_ts() {
+                // Later strict serializable reads must not read at an earlier
+                // time than this one, and later writes must not be assigned a
+                // time that this read could miss.
+                self.global_timeline.fast_forward(timestamp + 1);
+            }
             Ok(timestamp)
         } else {
             let invalid_indexes = id_bundle
@@ -3710,6 +5391,7 @@ impl Coordinator {
             row_set_finishing,
             stage,
             options,
+            ..
         } = plan;
 
         struct Timings {
@@ -3786,29 +5468,70 @@ impl Coordinator {
                     raw_plan,
                 )?);
                 let catalog = self.catalog.for_session(session);
-                let formatter =
-                    mz_dataflow_types::DataflowGraphFormatter::new(&catalog, options.typed);
-                let mut explanation =
-                    mz_dataflow_types::Explanation::new(&decorrelated_plan, &catalog, &formatter);
-                if let Some(row_set_finishing) = row_set_finishing {
-                    explanation.explain_row_set_finishing(row_set_finishing);
+                match options.format {
+                    ExplainFormat::Text => {
+                        let formatter = mz_dataflow_types::DataflowGraphFormatter::new(
+                            &catalog,
+                            options.typed,
+                            options.attributes,
+                        );
+                        let mut explanation = mz_dataflow_types::Explanation::new(
+                            &decorrelated_plan,
+                            &catalog,
+                            &formatter,
+                        );
+                        if let Some(row_set_finishing) = row_set_finishing {
+                            explanation.explain_row_set_finishing(row_set_finishing);
+                        }
+                        explanation.to_string()
+                    }
+                    ExplainFormat::Json => {
+                        let formatter =
+                            mz_dataflow_types::JsonGraphFormatter::new(options.attributes);
+                        let mut explanation = mz_dataflow_types::Explanation::new(
+                            &decorrelated_plan,
+                            &catalog,
+                            &formatter,
+                        );
+                        if let Some(row_set_finishing) = row_set_finishing {
+                            explanation.explain_row_set_finishing(row_set_finishing);
+                        }
+                        explanation.to_string()
+                    }
                 }
-                explanation.to_string()
             }
             ExplainStage::OptimizedPlan => {
                 let decorrelated_plan = decorrelate(&mut timings, raw_plan)?;
                 self.validate_timeline(decorrelated_plan.depends_on())?;
                 let dataflow = optimize(&mut timings, self, decorrelated_plan)?;
                 let catalog = self.catalog.for_session(session);
-                let formatter =
-                    mz_dataflow_types::DataflowGraphFormatter::new(&catalog, options.typed);
-                let mut explanation = mz_dataflow_types::Explanation::new_from_dataflow(
-                    &dataflow, &catalog, &formatter,
-                );
-                if let Some(row_set_finishing) = row_set_finishing {
-                    explanation.explain_row_set_finishing(row_set_finishing);
+                match options.format {
+                    ExplainFormat::Text => {
+                        let formatter = mz_dataflow_types::DataflowGraphFormatter::new(
+                            &catalog,
+                            options.typed,
+                            options.attributes,
+                        );
+                        let mut explanation = mz_dataflow_types::Explanation::new_from_dataflow(
+                            &dataflow, &catalog, &formatter,
+                        );
+                        if let Some(row_set_finishing) = row_set_finishing {
+                            explanation.explain_row_set_finishing(row_set_finishing);
+                        }
+                        explanation.to_string()
+                    }
+                    ExplainFormat::Json => {
+                        let formatter =
+                            mz_dataflow_types::JsonGraphFormatter::new(options.attributes);
+                        let mut explanation = mz_dataflow_types::Explanation::new_from_dataflow(
+                            &dataflow, &catalog, &formatter,
+                        );
+                        if let Some(row_set_finishing) = row_set_finishing {
+                            explanation.explain_row_set_finishing(row_set_finishing);
+                        }
+                        explanation.to_string()
+                    }
                 }
-                explanation.to_string()
             }
             ExplainStage::PhysicalPlan => {
                 let decorrelated_plan = decorrelate(&mut timings, raw_plan)?;
@@ -3922,6 +5645,9 @@ impl Coordinator {
                 };
                 explanation.to_string()
             }
+            ExplainStage::Analyze => {
+                unreachable!("EXPLAIN ANALYZE is sequenced by sequence_explain_analyze")
+            }
         };
         if options.timing {
             if let Some(decorrelation) = &timings.decorrelation {
@@ -4150,7 +5876,7 @@ impl Coordinator {
         let ReadThenWritePlan {
             id,
             kind,
-            selection,
+            mut selection,
             assignments,
             finishing,
         } = plan;
@@ -4190,6 +5916,10 @@ impl Coordinator {
             }
         }
 
+        // Statements after the first in a transaction see the transaction's
+        // earlier writes.
+        read_transaction_writes(&mut selection, session.transaction_writes());
+
         let ts = self.get_local_read_ts();
         let ts = MirScalarExpr::literal_ok(
             Datum::from(Numeric::from(ts)),
@@ -4266,6 +5996,7 @@ impl Coordinator {
                     PeekResponseUnary::Canceled => {
                         Err(CoordError::Unstructured(anyhow!("execution canceled")))
                     }
+                    PeekResponseUnary::TimedOut => Err(CoordError::StatementTimeout),
                     PeekResponseUnary::Error(e) => Err(CoordError::Unstructured(anyhow!(e))),
                 },
                 _ => Err(CoordError::Unstructured(anyhow!("expected SendingRows"))),
@@ -4284,6 +6015,7 @@ impl Coordinator {
 
     async fn sequence_alter_item_rename(
         &mut self,
+        session: &Session,
         plan: AlterItemRenamePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let op = catalog::Op::RenameItem {
@@ -4291,19 +6023,174 @@ impl Coordinator {
             current_full_name: plan.current_full_name,
             to_name: plan.to_name,
         };
-        match self.catalog_transact(vec![op], |_| Ok(())).await {
+        match self
+            .catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await
+        {
             Ok(()) => Ok(ExecuteResponse::AlteredObject(plan.object_type)),
             Err(err) => Err(err),
         }
     }
 
+    async fn sequence_alter_database_rename(
+        &mut self,
+        session: &Session,
+        plan: AlterDatabaseRenamePlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        if let Some(id) = plan.id {
+            let op = catalog::Op::RenameDatabase {
+                id,
+                to_name: plan.to_name,
+            };
+            self.catalog_transact(Some(session), vec![op], |_| Ok(()))
+                .await?;
+        }
+        Ok(ExecuteResponse::AlteredDatabase)
+    }
+
+    async fn sequence_alter_schema_rename(
+        &mut self,
+        session: &Session,
+        plan: AlterSchemaRenamePlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        if let Some((database_id, schema_id)) = plan.id {
+            let op = catalog::Op::RenameSchema {
+                database_id,
+                schema_id,
+                to_name: plan.to_name,
+            };
+            self.catalog_transact(Some(session), vec![op], |_| Ok(()))
+                .await?;
+        }
+        Ok(ExecuteResponse::AlteredSchema)
+    }
+
+    async fn sequence_comment(
+        &mut self,
+        session: &Session,
+        plan: CommentPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let op = catalog::Op::Comment {
+            id: plan.id,
+            column_position: plan.column_position,
+            comment: plan.comment,
+        };
+        self.catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await?;
+        Ok(ExecuteResponse::Commented)
+    }
+
+    async fn sequence_undrop(
+        &mut self,
+        session: &mut Session,
+        plan: UndropPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let UndropPlan { id, plan } = plan;
+        match *plan {
+            Plan::CreateTable(plan) => self.sequence_create_table(session, plan).await?,
+            Plan::CreateSource(plan) => self.sequence_create_source(session, plan).await?,
+            Plan::CreateView(plan) => self.sequence_create_view(session, plan).await?,
+            Plan::CreateIndex(plan) => self.sequence_create_index(session, plan).await?,
+            Plan::CreateType(plan) => self.sequence_create_type(session, plan).await?,
+            Plan::CreatePublication(plan) => {
+                self.sequence_create_publication(session, plan).await?
+            }
+            Plan::CreateTask(plan) => self.sequence_create_task(session, plan).await?,
+            plan => unreachable!("cannot undrop an item planned as {:?}", plan),
+        };
+        self.catalog_transact(
+            Some(session),
+            vec![catalog::Op::RemoveDroppedItem(id)],
+            |_| Ok(()),
+        )
+        .await?;
+        Ok(ExecuteResponse::Undropped)
+    }
+
+    async fn sequence_alter_role_set(
+        &mut self,
+        session: &Session,
+        plan: AlterRoleSetPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        // Validate the default as it would be applied at session startup, and
+        // store it under the parameter's canonical name.
+        let mut vars = Vars::default();
+        let name = vars.get(&plan.name)?.name().to_string();
+        if let Some(value) = &plan.value {
+            vars.set_default(&name, value)?;
+        }
+        let op = catalog::Op::SetRoleSetting {
+            role_id: plan.role_id,
+            name,
+            value: plan.value,
+        };
+        self.catalog_transact(Some(session), vec![op], |_| Ok(()))
+            .await?;
+        Ok(ExecuteResponse::AlteredObject(ObjectType::Role))
+    }
+
+    fn sequence_alter_system_set(
+        &mut self,
+        plan: AlterSystemSetPlan,
+    ) -> Result<ExecuteResponse, CoordError> {
+        // Validate the new value before it is persisted, and store it under
+        // the variable's canonical name. The new value takes effect as soon
+        // as it is installed, as every use reads it from `system_vars`.
+        let mut system_vars = self.system_vars.clone();
+        let name = system_vars.get(&plan.name)?.name();
+        match &plan.value {
+            Some(value) => system_vars.set(name, value)?,
+            None => system_vars.reset(name)?,
+        }
+        self.catalog
+            .set_system_setting(name, plan.value.as_deref())?;
+        self.system_vars = system_vars;
+        Ok(ExecuteResponse::AlteredSystemConfiguration)
+    }
+
+    async fn sequence_update_privileges(
+        &mut self,
+        session: &Session,
+        plan: UpdatePrivilegesPlan,
+        granted: bool,
+    ) -> Result<(), CoordError> {
+        let object = privileges::privilege_object_id(plan.object);
+        // A statement may name the same privilege or role more than once.
+        let updates: BTreeSet<_> = plan
+            .role_ids
+            .into_iter()
+            .cartesian_product(plan.privileges)
+            .collect();
+        let ops = updates
+            .into_iter()
+            .map(|(role_id, privilege)| catalog::Op::UpdatePrivilege {
+                object,
+                role_id,
+                privilege,
+                granted,
+            })
+            .collect();
+        self.catalog_transact(Some(session), ops, |_| Ok(())).await
+    }
+
     async fn sequence_alter_index_set_options(
         &mut self,
         session: &Session,
         plan: AlterIndexSetOptionsPlan,
     ) -> Result<ExecuteResponse, CoordError> {
+        let mut index = self
+            .catalog
+            .get_entry(&plan.id)
+            .index()
+            .expect("setting options on index")
+            .clone();
+        if let Some(window) = catalog::Index::logical_compaction_window(&plan.options) {
+            index.logical_compaction_window = Some(window);
+        }
+        index.create_sql = plan.create_sql;
         self.set_index_options(plan.id, plan.options, session)
             .await?;
+        self.update_index(session, plan.id, index).await?;
         Ok(ExecuteResponse::AlteredObject(ObjectType::Index))
     }
 
@@ -4312,21 +6199,48 @@ impl Coordinator {
         session: &Session,
         plan: AlterIndexResetOptionsPlan,
     ) -> Result<ExecuteResponse, CoordError> {
+        let mut index = self
+            .catalog
+            .get_entry(&plan.id)
+            .index()
+            .expect("resetting options on index")
+            .clone();
         let options = plan
             .options
             .into_iter()
             .map(|o| match o {
-                IndexOptionName::LogicalCompactionWindow => IndexOption::LogicalCompactionWindow(
-                    self.logical_compaction_window_ms.map(Duration::from_millis),
-                ),
+                IndexOptionName::LogicalCompactionWindow => {
+                    index.logical_compaction_window = None;
+                    IndexOption::LogicalCompactionWindow(
+                        self.logical_compaction_window_ms.map(Duration::from_millis),
+                    )
+                }
             })
             .collect();
+        index.create_sql = plan.create_sql;
         self.set_index_options(plan.id, options, session).await?;
+        self.update_index(session, plan.id, index).await?;
         Ok(ExecuteResponse::AlteredObject(ObjectType::Index))
     }
 
+    /// Records the altered options of the index `id` in the catalog, so that
+    /// they survive restarts.
+    async fn update_index(
+        &mut self,
+        session: &Session,
+        id: GlobalId,
+        index: catalog::Index,
+    ) -> Result<(), CoordError> {
+        let ops = vec![catalog::Op::UpdateItem {
+            id,
+            to_item: CatalogItem::Index(index),
+        }];
+        self.catalog_transact(Some(session), ops, |_| Ok(())).await
+    }
+
     async fn sequence_alter_index_enable(
         &mut self,
+        session: &Session,
         plan: AlterIndexEnablePlan,
     ) -> Result<ExecuteResponse, CoordError> {
         let index = self
@@ -4344,7 +6258,7 @@ impl Coordinator {
                 }),
             }];
             let df = self
-                .catalog_transact(ops, |txn| {
+                .catalog_transact(Some(session), ops, |txn| {
                     let df = txn
                         .dataflow_builder(compute_instance)
                         .build_index_dataflow(plan.id)?
@@ -4365,8 +6279,19 @@ impl Coordinator {
     /// [`DataflowDesc`]s. [`Coordinator::ship_dataflow`] must be called after this
     /// function successfully returns on any built `DataflowDesc`.
     ///
+    /// `session` is the session on whose behalf the transaction is performed,
+    /// if any. Its user is recorded in `mz_audit_events` as the user who made
+    /// the changes, and as the user who deleted any secrets that the
+    /// transaction drops. Only changes that Materialize makes on its own,
+    /// e.g., at startup, should be performed without a session.
+    ///
     /// [`CatalogState`]: crate::catalog::CatalogState
-    async fn catalog_transact<F, R>(&mut self, ops: Vec<catalog::Op>, f: F) -> Result<R, CoordError>
+    async fn catalog_transact<F, R>(
+        &mut self,
+        session: Option<&Session>,
+        ops: Vec<catalog::Op>,
+        f: F,
+    ) -> Result<R, CoordError>
     where
         F: FnOnce(CatalogTxn<Timestamp>) -> Result<R, CoordError>,
     {
@@ -4376,8 +6301,29 @@ impl Coordinator {
         let mut indexes_to_drop = vec![];
         let mut replication_slots_to_drop: HashMap<String, Vec<String>> = HashMap::new();
         let mut secrets_to_drop = vec![];
+        let mut publications_to_drop = vec![];
+        let mut tasks_to_drop = vec![];
+        let mut instances_to_drop = vec![];
 
         for op in &ops {
+            // The imports of views built from changed items may have changed
+            // too. Invalidating them early is harmless if the transaction
+            // fails.
+            match op {
+                catalog::Op::DropItem(id)
+                | catalog::Op::RenameItem { id, .. }
+                | catalog::Op::UpdateItem { id, .. } => {
+                    self.view_cache.invalidate(*id);
+                    self.plan_cache.invalidate(*id);
+                }
+                catalog::Op::DropComputeInstance { name } => {
+                    if let Ok(instance) = self.catalog.resolve_compute_instance(name) {
+                        self.view_cache.invalidate_instance(instance.id);
+                        instances_to_drop.push(instance.id);
+                    }
+                }
+                _ => (),
+            }
             if let catalog::Op::DropItem(id) = op {
                 match self.catalog.get_entry(id).item() {
                     CatalogItem::Table(_) => {
@@ -4416,16 +6362,24 @@ impl Coordinator {
                     CatalogItem::Secret(_) => {
                         secrets_to_drop.push(*id);
                     }
+                    CatalogItem::Publication(_) => {
+                        publications_to_drop.push(*id);
+                    }
+                    CatalogItem::Task(_) => {
+                        tasks_to_drop.push(*id);
+                    }
                     _ => (),
                 }
             }
         }
 
-        let (builtin_table_updates, result) = self.catalog.transact(ops, |catalog| {
+        let (builtin_table_updates, result) = self.catalog.transact(session, ops, |catalog| {
             f(CatalogTxn {
                 dataflow_client: &self.dataflow_client,
                 persister: &self.persister,
                 catalog,
+                view_cache: &self.view_cache,
+                statistics: &self.statistics,
             })
         })?;
 
@@ -4465,7 +6419,20 @@ impl Coordinator {
                 self.drop_indexes(indexes_to_drop).await;
             }
             if !secrets_to_drop.is_empty() {
-                self.drop_secrets(secrets_to_drop).await;
+                let user = session.map_or(catalog::SYSTEM_USER, |session| session.user());
+                self.drop_secrets(user, secrets_to_drop);
+            }
+            if !publications_to_drop.is_empty() {
+                self.drop_publications(publications_to_drop).await;
+            }
+            if !tasks_to_drop.is_empty() {
+                self.drop_scheduled_tasks(tasks_to_drop).await;
+            }
+            // Peeks waiting on a dropped cluster will never see a slot free
+            // up. Sequence them now so they fail promptly instead of waiting
+            // out the peek queue timeout.
+            for id in instances_to_drop {
+                self.sequence_deferred_peeks(id).await;
             }
 
             // We don't want to block the coordinator on an external postgres server, so
@@ -4551,8 +6518,32 @@ impl Coordinator {
     }
 
     async fn drop_sinks(&mut self, sinks: Vec<(ComputeInstanceId, GlobalId)>) {
+        let mut dataflow_sinks = vec![];
+        for (compute_instance, id) in sinks {
+            match self.pending_tails.remove(&id) {
+                None => dataflow_sinks.push((compute_instance, id)),
+                Some(pending_tail) => {
+                    // A flow-controlled tail's dataflow may have been
+                    // recreated under a new sink, or dropped entirely if the
+                    // tail is paused.
+                    if let Some(sink_id) = pending_tail.sink_id {
+                        self.tail_sinks.remove(&sink_id);
+                        dataflow_sinks.push((compute_instance, sink_id));
+                    }
+                    self.release_tail(pending_tail).await;
+                }
+            }
+        }
+        self.drop_compute_sinks(dataflow_sinks).await;
+    }
+
+    /// Drops the dataflows that export the given sinks, without regard for any
+    /// pending tails they serve.
+    async fn drop_compute_sinks(&mut self, sinks: Vec<(ComputeInstanceId, GlobalId)>) {
         let mut by_compute_instance = HashMap::new();
         for (compute_instance, id) in sinks {
+            self.record_dataflow_export_dropped(id);
+            self.forget_dataflow_statement_export(id);
             by_compute_instance
                 .entry(compute_instance)
                 .or_insert(vec![])
@@ -4568,9 +6559,83 @@ impl Coordinator {
         }
     }
 
+    /// Recreates the dataflow of a paused tail from where it left off.
+    async fn resume_tail(&mut self, tail_id: GlobalId) {
+        let pending_tail = &self.pending_tails[&tail_id];
+        let flow = pending_tail
+            .flow
+            .as_ref()
+            .expect("paused tail is flow controlled");
+        let compute_instance = flow.compute_instance;
+        // The tail's inputs may have been dropped while it was paused.
+        let dropped = flow
+            .id_bundle
+            .iter()
+            .any(|id| !self.read_capability.contains_key(&id));
+        let sink_id = if dropped {
+            Err(CoordError::Unstructured(anyhow!(
+                "TAIL cannot be resumed because one of its inputs has been dropped"
+            )))
+        } else {
+            self.allocate_transient_id()
+        };
+        match sink_id {
+            Ok(sink_id) => {
+                let pending_tail = self.pending_tails.get_mut(&tail_id).unwrap();
+                let dataflow = pending_tail.flow.as_ref().unwrap().resume(sink_id);
+                pending_tail.sink_id = Some(sink_id);
+                self.tail_sinks.insert(sink_id, tail_id);
+                self.ship_dataflow(dataflow, compute_instance).await;
+            }
+            Err(e) => {
+                let pending_tail = self.pending_tails.remove(&tail_id).unwrap();
+                pending_tail.send_error(e.to_string());
+                self.release_tail(pending_tail).await;
+            }
+        }
+    }
+
+    /// Releases the read holds of a flow-controlled tail that has been
+    /// removed.
+    async fn release_tail(&mut self, pending_tail: PendingTail) {
+        if let Some(flow) = pending_tail.flow {
+            let holds = self.tail_read_holds(flow.hold, &flow.id_bundle, flow.compute_instance);
+            self.release_read_hold(holds).await;
+        }
+    }
+
+    /// Describes the read holds of a flow-controlled tail at `time`, omitting
+    /// any inputs that have since been dropped.
+    fn tail_read_holds(
+        &self,
+        time: Timestamp,
+        id_bundle: &CollectionIdBundle,
+        compute_instance: ComputeInstanceId,
+    ) -> read_holds::ReadHolds<Timestamp> {
+        let live = |ids: &BTreeSet<GlobalId>| -> BTreeSet<GlobalId> {
+            ids.iter()
+                .filter(|id| self.read_capability.contains_key(id))
+                .copied()
+                .collect()
+        };
+        read_holds::ReadHolds {
+            time,
+            id_bundle: CollectionIdBundle {
+                storage_ids: live(&id_bundle.storage_ids),
+                compute_ids: live(&id_bundle.compute_ids),
+            },
+            compute_instance,
+        }
+    }
+
     async fn drop_indexes(&mut self, indexes: Vec<(ComputeInstanceId, GlobalId)>) {
         let mut by_compute_instance = HashMap::new();
+        let mut statistics_updates = vec![];
         for (compute_instance, id) in indexes {
+            self.view_cache.invalidate(id);
+            self.record_dataflow_export_dropped(id);
+            self.forget_dataflow_statement_export(id);
+            statistics_updates.extend(self.forget_statistics(id));
             if self.read_capability.remove(&id).is_some() {
                 by_compute_instance
                     .entry(compute_instance)
@@ -4588,6 +6653,9 @@ impl Coordinator {
                 .await
                 .unwrap();
         }
+        if !statistics_updates.is_empty() {
+            self.send_builtin_table_updates(statistics_updates).await;
+        }
     }
 
     async fn set_index_options(
@@ -4642,17 +6710,241 @@ impl Coordinator {
         Ok(())
     }
 
-    async fn drop_secrets(&mut self, secrets: Vec<GlobalId>) {
+    /// Makes secrets storage agree with the secrets in the catalog.
+    ///
+    /// Secrets are written to storage before the catalog transaction that
+    /// creates them commits and deleted after the transaction that drops them
+    /// commits, so a crash can leave secrets in storage that the catalog does
+    /// not know about. These orphaned secrets are deleted.
+    ///
+    /// Secrets that the catalog knows about but that are missing from storage
+    /// indicate that storage was modified externally. They are reported, and
+    /// their versions are left unknown, so that reading them fails but they
+    /// can still be altered or dropped. If `recreate_missing` is set, they are
+    /// instead recreated with empty contents.
+    ///
+    /// The current version of each secret is read from storage, rather than
+    /// trusted to the catalog, and reflected in `mz_secrets`.
+    ///
+    /// Secrets storage that cannot list its secrets cannot be reconciled, as
+    /// every secret would appear to be missing from it. The versions of the
+    /// secrets in such storage are left unknown.
+    ///
+    /// Returns the updates that record the operations on secrets storage in
+    /// `mz_secret_audit_events`, and the versions of secrets in `mz_secrets`.
+    async fn reconcile_secrets(
+        &mut self,
+        recreate_missing: bool,
+    ) -> Result<Vec<BuiltinTableUpdate>, CoordError> {
+        if !self.secrets_controller.can_list() {
+            info!("secrets storage cannot list secrets; skipping reconciliation");
+            return Ok(vec![]);
+        }
+        let mut audit_updates = vec![];
+        let stored: HashSet<_> = self.secrets_controller.list().await?.into_iter().collect();
+        let mut known = HashSet::new();
+        let mut missing = vec![];
+        let mut versions = vec![];
+        for entry in self.catalog.entries() {
+            if let CatalogItem::Secret(_) = entry.item() {
+                known.insert(entry.id());
+                let name = self
+                    .catalog
+                    .resolve_full_name(entry.name(), entry.conn_id());
+                if !stored.contains(&entry.id()) {
+                    missing.push((name, entry.id()));
+                    continue;
+                }
+                match self.secrets_controller.current_version(entry.id()).await {
+                    Ok(version) => versions.push((entry.id(), version)),
+                    Err(e) => {
+                        error!("secret {} ({}) cannot be read: {:#}", name, entry.id(), e);
+                        continue;
+                    }
+                }
+                if let Err(e) = self.secrets_controller.read(entry.id()).await {
+                    error!("secret {} ({}) cannot be read: {:#}", name, entry.id(), e);
+                }
+            }
+        }
+
+        let orphaned: Vec<_> = stored.difference(&known).copied().collect();
+        if !orphaned.is_empty() {
+            let ops = orphaned
+                .iter()
+                .map(|id| SecretOp::Delete {
+                    id: *id,
+                    version: None,
+                })
+                .collect();
+            match self
+                .apply_and_audit_secret_ops(catalog::SYSTEM_USER, ops, &mut audit_updates)
+                .await
+            {
+                Ok(_) => {
+                    info!(
+                        "deleted {} secrets that do not exist in the catalog",
+                        orphaned.len()
+                    )
+                }
+                Err(e) => warn!(
+                    "deleting {} secrets that do not exist in the catalog has encountered \
+                     an error; they can be removed with mz_internal.gc_secrets(): {:#}",
+                    orphaned.len(),
+                    e
+                ),
+            }
+        }
+
+        if !missing.is_empty() {
+            let names = missing
+                .iter()
+                .map(|(name, id)| format!("{} ({})", name, id))
+                .join(", ");
+            if recreate_missing {
+                let ops = missing
+                    .iter()
+                    .map(|(_, id)| SecretOp::Ensure {
+                        id: *id,
+                        contents: vec![],
+                    })
+                    .collect();
+                match self
+                    .apply_and_audit_secret_ops(catalog::SYSTEM_USER, ops, &mut audit_updates)
+                    .await
+                {
+                    Ok(recreated) => {
+                        versions.extend(missing.iter().map(|(_, id)| *id).zip(recreated));
+                        warn!(
+                            "recreated secrets missing from secrets storage as empty \
+                             placeholders: {}",
+                            names
+                        );
+                    }
+                    Err(e) => error!(
+                        "recreating secrets missing from secrets storage ({}) has \
+                         encountered an error: {:#}",
+                        names, e
+                    ),
+                }
+            } else {
+                error!(
+                    "secrets are missing from secrets storage and cannot be read until \
+                     they are altered: {}; restart with --recreate-missing-secrets to \
+                     recreate them as empty placeholders",
+                    names
+                );
+            }
+        }
+
+        for (id, version) in versions {
+            audit_updates.extend(self.catalog.set_secret_version(id, Some(version)));
+        }
+        Ok(audit_updates)
+    }
+
+    fn drop_secrets(&mut self, user: &str, secrets: Vec<GlobalId>) {
         let ops = secrets
             .into_iter()
-            .map(|id| SecretOp::Delete { id })
+            .map(|id| SecretOp::Delete { id, version: None })
             .collect_vec();
+        self.spawn_secret_ops(
+            user,
+            ops,
+            false,
+            SecretOpsFollowUp::Log {
+                context: "dropping secrets",
+            },
+        );
+    }
 
-        match self.secrets_controller.apply(ops) {
-            Ok(_) => {}
-            Err(e) => {
-                warn!("Dropping secrets has encountered an error: {}", e);
+    /// Reads the current contents of the secrets in `secret_options` into the
+    /// configuration options that they define in `config_options`.
+    ///
+    /// Returns the versions of the secrets that were read.
+    async fn read_secret_options(
+        &self,
+        secret_options: &BTreeMap<String, GlobalId>,
+        config_options: &mut BTreeMap<String, String>,
+    ) -> Result<BTreeMap<GlobalId, SecretVersion>, CoordError> {
+        let mut versions = BTreeMap::new();
+        for (key, id) in secret_options {
+            // Reading a specific version ensures that the recorded version
+            // is the one whose contents were read.
+            let version = self.secrets_controller.current_version(*id).await?;
+            let contents = self.secrets_controller.read_version(*id, version).await?;
+            let contents = match String::from_utf8(contents) {
+                Ok(contents) => contents,
+                Err(_) => {
+                    let entry = self.catalog.get_entry(id);
+                    coord_bail!(
+                        "secret {} is not valid UTF-8",
+                        self.catalog
+                            .resolve_full_name(entry.name(), entry.conn_id())
+                            .to_string()
+                            .quoted()
+                    )
+                }
+            };
+            config_options.insert(key.clone(), contents);
+            versions.insert(*id, version);
+        }
+        Ok(versions)
+    }
+
+    /// Reads the contents of the secrets that the `WITH` options of `stmt`
+    /// name, which purification needs to connect to the source, keyed by the
+    /// configuration option that each defines.
+    async fn read_purification_secrets(
+        &self,
+        session: &Session,
+        stmt: &CreateSourceStatement<Raw>,
+    ) -> Result<BTreeMap<String, String>, CoordError> {
+        if !matches!(stmt.connector, CreateSourceConnector::Kafka(_)) {
+            return Ok(BTreeMap::new());
+        }
+        let secret_options = mz_sql::kafka_util::extract_secret_config(
+            &self.catalog.for_session(session),
+            &stmt.with_options,
+            &mut mz_sql::normalize::options(&stmt.with_options),
+        )?;
+        self.check_secret_use(session, secret_options.values().copied())?;
+        let mut contents = BTreeMap::new();
+        self.read_secret_options(&secret_options, &mut contents)
+            .await?;
+        Ok(contents)
+    }
+
+    /// Reads the secrets that configure the source described by `desc` into
+    /// its configuration, as with [`Coordinator::read_secret_options`].
+    async fn read_source_secrets(
+        &self,
+        desc: &mut SourceDesc,
+    ) -> Result<BTreeMap<GlobalId, SecretVersion>, CoordError> {
+        match &mut desc.connector {
+            SourceConnector::External {
+                connector: ExternalSourceConnector::Kafka(kafka),
+                ..
+            } => {
+                self.read_secret_options(&kafka.secret_options, &mut kafka.config_options)
+                    .await
+            }
+            _ => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Reads the secrets that configure the sink that `builder` builds into
+    /// its configuration, as with [`Coordinator::read_secret_options`].
+    async fn read_sink_secrets(
+        &self,
+        builder: &mut SinkConnectorBuilder,
+    ) -> Result<BTreeMap<GlobalId, SecretVersion>, CoordError> {
+        match builder {
+            SinkConnectorBuilder::Kafka(kafka) => {
+                self.read_secret_options(&kafka.secret_options, &mut kafka.config_options)
+                    .await
             }
+            SinkConnectorBuilder::AvroOcf(_) => Ok(BTreeMap::new()),
         }
     }
 
@@ -4667,7 +6959,14 @@ impl Coordinator {
         let mut output_ids = Vec::new();
         let mut dataflow_plans = Vec::with_capacity(dataflows.len());
         for dataflow in dataflows.into_iter() {
+            // Views over the indexed items may now be imported via the new
+            // indexes.
+            for (index_desc, _) in dataflow.index_exports.values() {
+                self.view_cache.invalidate(index_desc.on_id);
+            }
             output_ids.extend(dataflow.export_ids());
+            self.record_dataflow_index_usage(&dataflow);
+            self.record_dataflow_statement(&dataflow.debug_name, dataflow.export_ids());
             dataflow_plans.push(self.finalize_dataflow(dataflow, instance));
         }
         self.dataflow_client
@@ -4676,8 +6975,29 @@ impl Coordinator {
             .create_dataflows(dataflow_plans)
             .await
             .unwrap();
+        // Indexes that were configured with a logical compaction window of
+        // their own compact according to it rather than the default.
+        let mut default_ids = vec![];
+        for id in output_ids {
+            let window = self
+                .catalog
+                .try_get_entry(&id)
+                .and_then(|entry| entry.index())
+                .and_then(|index| index.logical_compaction_window);
+            match window {
+                Some(window) => {
+                    self.initialize_compute_read_policies(
+                        vec![id],
+                        instance,
+                        window.map(duration_to_timestamp_millis),
+                    )
+                    .await
+                }
+                None => default_ids.push(id),
+            }
+        }
         self.initialize_compute_read_policies(
-            output_ids,
+            default_ids,
             instance,
             self.logical_compaction_window_ms,
         )
@@ -4748,6 +7068,40 @@ impl Coordinator {
             .expect("Dataflow planning failed; unrecoverable error")
     }
 
+    /// In safe mode, returns an error if `expr` could require an unbounded
+    /// amount of memory to compute.
+    ///
+    /// Only newly planned queries and views are checked, so that objects
+    /// created before a change to the optimizer cannot prevent the catalog
+    /// from loading.
+    fn check_plan_safety(&self, expr: &MirRelationExpr) -> Result<(), CoordError> {
+        if self.catalog.config().safe_mode && has_unbounded_cross_join(expr) {
+            return Err(CoordError::SafeModeViolation(
+                "cross join of unbounded relations".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Optimizes the plan of a query, reusing the optimized plan of an
+    /// earlier execution of the statement being sequenced if one is cached.
+    fn optimize_cached(
+        &mut self,
+        expr: MirRelationExpr,
+    ) -> Result<OptimizedMirRelationExpr, CoordError> {
+        let key = match self.plan_cache_key.take() {
+            Some(key) => key,
+            None => return Ok(self.view_optimizer.optimize(expr)?),
+        };
+        if let Some(optimized) = self.plan_cache.get(&key, &expr) {
+            return Ok(OptimizedMirRelationExpr::declare_optimized(optimized));
+        }
+        let optimized = self.view_optimizer.optimize(expr.clone())?;
+        self.plan_cache
+            .insert(key, expr, optimized.clone().into_inner());
+        Ok(optimized)
+    }
+
     fn allocate_transient_id(&mut self) -> Result<GlobalId, CoordError> {
         let id = self.transient_id_counter;
         if id == u64::max_value() {
@@ -4831,7 +7185,7 @@ impl Coordinator {
         session: &mut Session,
     ) -> Result<(), tokio::sync::TryLockError> {
         Arc::clone(&self.write_lock).try_lock_owned().map(|p| {
-            session.grant_write_lock(p);
+            session.grant_write_lock(p, self.system_vars.write_lock_timeout());
         })
     }
 
@@ -4845,7 +7199,12 @@ impl Coordinator {
         plan: Plan,
     ) {
         let conn_id = session.conn_id();
-        let plan = DeferredPlan { tx, session, plan };
+        let plan = DeferredPlan {
+            tx,
+            session,
+            plan,
+            statement: self.current_statement(),
+        };
         self.write_lock_wait_group.push_back(plan);
 
         let internal_cmd_tx = self.internal_cmd_tx.clone();
@@ -4858,6 +7217,96 @@ impl Coordinator {
                 .expect("sending to internal_cmd_tx cannot fail");
         });
     }
+
+    /// Reports whether a new peek on `compute_instance` may be sequenced
+    /// immediately without exceeding the peek concurrency limit.
+    ///
+    /// Peeks are admitted in order, so no slot is available while earlier
+    /// peeks are still waiting.
+    fn peek_slot_available(&self, compute_instance: ComputeInstanceId) -> bool {
+        let waiting = self
+            .peek_wait_groups
+            .get(&compute_instance)
+            .map_or(false, |peeks| !peeks.is_empty());
+        !waiting && self.peek_below_limit(compute_instance)
+    }
+
+    /// Reports whether fewer than the maximum number of peeks are pending on
+    /// `compute_instance`.
+    fn peek_below_limit(&self, compute_instance: ComputeInstanceId) -> bool {
+        let limit = self
+            .catalog
+            .compute_instances()
+            .find(|instance| instance.id == compute_instance)
+            .and_then(|instance| self.peek_concurrency_limits.get(&instance.name));
+        match limit {
+            None => true,
+            Some(limit) => {
+                let pending = self
+                    .pending_peeks
+                    .values()
+                    .filter(|peek| peek.compute_instance == compute_instance)
+                    .count();
+                pending < limit
+            }
+        }
+    }
+
+    /// Defers executing the provided peek until `compute_instance` has a free
+    /// peek slot, failing the peek if no slot frees up before the peek queue
+    /// timeout elapses.
+    fn defer_peek(
+        &mut self,
+        tx: ClientTransmitter<ExecuteResponse>,
+        session: Session,
+        plan: PeekPlan,
+        compute_instance: ComputeInstanceId,
+    ) {
+        let id = Uuid::new_v4();
+        self.peek_wait_groups
+            .entry(compute_instance)
+            .or_default()
+            .push_back(DeferredPeek {
+                id,
+                tx,
+                session,
+                plan,
+                statement: self.current_statement(),
+            });
+
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        let timeout = self.system_vars.peek_queue_timeout();
+        task::spawn(|| format!("peek_queue_timeout:{id}"), async move {
+            tokio::time::sleep(timeout).await;
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::PeekQueueTimeout(compute_instance, id));
+        });
+    }
+
+    /// Sequences deferred peeks on `compute_instance` for as long as it has
+    /// free peek slots.
+    async fn sequence_deferred_peeks(&mut self, compute_instance: ComputeInstanceId) {
+        while self.peek_below_limit(compute_instance) {
+            let peek = match self
+                .peek_wait_groups
+                .get_mut(&compute_instance)
+                .and_then(|peeks| peeks.pop_front())
+            {
+                Some(peek) => peek,
+                None => break,
+            };
+            let DeferredPeek {
+                id: _,
+                tx,
+                mut session,
+                plan,
+                statement,
+            } = peek;
+            let previous = self.resume_statement(statement);
+            tx.send(self.sequence_peek(&mut session, plan).await, session);
+            self.restore_statement(previous);
+        }
+    }
 }
 
 /// Serves the coordinator based on the provided configuration.
@@ -4883,6 +7332,16 @@ pub async fn serve(
         persister,
         now,
         secrets_controller,
+        recreate_missing_secrets,
+        max_secret_size,
+        peek_concurrency_limits,
+        peek_queue_timeout,
+        config_reload_tx,
+        max_connections,
+        result_sort_memory_limit,
+        statistics_interval,
+        dropped_object_retention,
+        max_replication_slot_lag,
     }: Config,
 ) -> Result<(Handle, Client), CoordError> {
     let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
@@ -4891,11 +7350,12 @@ pub async fn serve(
     let (catalog, builtin_table_updates) = Catalog::open(catalog::Config {
         storage,
         experimental_mode: Some(experimental_mode),
-        safe_mode,
+        safe_mode: Some(safe_mode),
         local_compute_introspection: logging.as_ref().map(|logging| {
             ComputeInstanceIntrospectionConfig {
                 granularity: logging.granularity,
                 debugging: logging.log_logging,
+                retention: logging.retention,
             }
         }),
         build_info,
@@ -4906,8 +7366,25 @@ pub async fn serve(
         metrics_registry: &metrics_registry,
         disable_user_indexes,
         persister: &persister,
+        config_reload_tx,
+        dropped_item_retention: dropped_object_retention,
     })
     .await?;
+    let mut system_vars = SystemVars::default();
+    system_vars.set_default_peek_queue_timeout(peek_queue_timeout);
+    system_vars.set_default_metrics_scraping_interval(
+        logging
+            .as_ref()
+            .and_then(|logging| logging.metrics_scraping_interval)
+            .unwrap_or_default(),
+    );
+    for (name, value) in catalog.load_system_settings()? {
+        // A setting may be unknown, or no longer valid, if it was stored by a
+        // different version.
+        if let Err(e) = system_vars.set(&name, &value) {
+            warn!("ignoring system setting {}: {}", name, e);
+        }
+    }
     let cluster_id = catalog.config().cluster_id;
     let session_id = catalog.config().session_id;
     let start_instant = catalog.config().start_instant;
@@ -4950,12 +7427,40 @@ pub async fn serve(
                 txn_reads: Default::default(),
                 pending_peeks: HashMap::new(),
                 client_pending_peeks: HashMap::new(),
+                peek_concurrency_limits,
+                result_sort_memory_limit,
+                peek_wait_groups: HashMap::new(),
                 pending_tails: HashMap::new(),
+                tail_sinks: HashMap::new(),
+                replication_slots: HashMap::new(),
+                max_replication_slot_lag,
                 write_lock: Arc::new(tokio::sync::Mutex::new(())),
                 write_lock_wait_group: VecDeque::new(),
-                secrets_controller,
+                secrets_controller: Arc::from(secrets_controller),
+                secrets_write_lock: Arc::new(tokio::sync::Mutex::new(())),
+                max_secret_size,
+                system_config: Vec::new(),
+                max_connections,
+                system_vars,
+                index_usage: HashMap::new(),
+                reported_index_usage: HashMap::new(),
+                dataflow_index_imports: HashMap::new(),
+                reported_replica_frontiers: HashMap::new(),
+                active_statement: None,
+                next_statement_id: 1,
+                dataflow_statements: HashMap::new(),
+                reported_dataflow_statements: HashMap::new(),
+                view_cache: ViewCache::new(&metrics_registry),
+                plan_cache: PlanCache::new(&metrics_registry),
+                plan_cache_key: None,
+                statistics: Statistics::default(),
+                statistics_interval,
+                memory_usage: MemoryUsage::default(),
+                scheduled_tasks: HashMap::new(),
+                task_conn_ids: IdAllocator::new((1 << 16) + 1, 1 << 17),
             };
-            let bootstrap = handle.block_on(coord.bootstrap(builtin_table_updates));
+            let bootstrap =
+                handle.block_on(coord.bootstrap(builtin_table_updates, recreate_missing_secrets));
             let ok = bootstrap.is_ok();
             bootstrap_tx.send(bootstrap).unwrap();
             if ok {
@@ -4978,6 +7483,51 @@ pub async fn serve(
     }
 }
 
+/// Converts a password from `CREATE ROLE` or `ALTER ROLE` into the verifier
+/// that the catalog stores.
+///
+/// As in PostgreSQL, a password that is already a SCRAM-SHA-256 verifier is
+/// stored as is, so that roles can be copied between servers without knowing
+/// their passwords. A password that looks like a verifier but is not a valid
+/// one, e.g. because its iteration count is out of range, is refused.
+fn hash_password(password: &str) -> Result<String, CoordError> {
+    match password.parse::<Verifier>() {
+        Ok(verifier) => Ok(verifier.to_string()),
+        Err(e) if password.starts_with(&format!("{}$", scram::MECHANISM)) => {
+            Err(CoordError::Unstructured(e))
+        }
+        Err(_) => Ok(Verifier::new(password).to_string()),
+    }
+}
+
+/// Rewrites `expr` so that its reads of tables include the uncommitted `writes`
+/// of the current transaction.
+fn read_transaction_writes(expr: &mut MirRelationExpr, writes: &[WriteOp]) {
+    let mut pending: HashMap<GlobalId, Vec<(Row, Diff)>> = HashMap::new();
+    for WriteOp { id, rows } in writes {
+        pending.entry(*id).or_default().extend(rows.iter().cloned());
+    }
+    for rows in pending.values_mut() {
+        differential_dataflow::consolidation::consolidate(rows);
+    }
+    expr.visit_mut_post(&mut |expr| {
+        let writes = match expr {
+            MirRelationExpr::Get {
+                id: Id::Global(id),
+                typ,
+            } => match pending.get(id) {
+                Some(rows) if !rows.is_empty() => MirRelationExpr::Constant {
+                    rows: Ok(rows.clone()),
+                    typ: RelationType::new(typ.column_types.clone()),
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+        expr.replace_using(|get| get.union(writes));
+    });
+}
+
 /// Constructs an [`ExecuteResponse`] that that will send some rows to the
 /// client immediately, as opposed to asking the dataflow layer to send along
 /// the rows after some computation.
@@ -4985,6 +7535,43 @@ fn send_immediate_rows(rows: Vec<Row>) -> ExecuteResponse {
     ExecuteResponse::SendingRows(Box::pin(async { PeekResponseUnary::Rows(rows) }))
 }
 
+/// Returns `rows` as the response to a peek, or an error if their total size
+/// exceeds `max_result_size` bytes or there are more than `max_result_rows`
+/// of them.
+fn check_result_size(
+    rows: Vec<Row>,
+    max_result_size: usize,
+    max_result_rows: Option<usize>,
+) -> PeekResponseUnary {
+    let size = rows.iter().map(|row| row.data().len()).sum();
+    match result_size_error(rows.len(), size, max_result_size, max_result_rows) {
+        Some(error) => error,
+        None => PeekResponseUnary::Rows(rows),
+    }
+}
+
+/// Returns the error for a result of `rows` rows totaling `size` bytes, if it
+/// exceeds `max_result_size` bytes or `max_result_rows` rows.
+fn result_size_error(
+    rows: usize,
+    size: usize,
+    max_result_size: usize,
+    max_result_rows: Option<usize>,
+) -> Option<PeekResponseUnary> {
+    if size > max_result_size {
+        return Some(PeekResponseUnary::Error(format!(
+            "result exceeds max size of {} bytes",
+            max_result_size
+        )));
+    }
+    match max_result_rows {
+        Some(max) if rows > max => Some(PeekResponseUnary::Error(
+            CoordError::ResultRowsExceeded(max).to_string(),
+        )),
+        _ => None,
+    }
+}
+
 fn auto_generate_primary_idx(
     index_name: String,
     compute_instance: ComputeInstanceId,
@@ -5013,6 +7600,7 @@ fn auto_generate_primary_idx(
         depends_on,
         enabled,
         compute_instance,
+        logical_compaction_window: None,
     }
 }
 
@@ -5095,20 +7683,144 @@ pub fn describe(
     }
 }
 
+/// Reports whether `expr` contains a join that forms the cross product of two
+/// or more relations of unknown size.
+///
+/// The memory required to maintain such a join grows with the product of the
+/// sizes of the relations. Inputs that are connected by an equivalence are
+/// joined on a key and so are not considered to form a cross product, and
+/// neither are inputs of known cardinality, like constants and global
+/// aggregates.
+fn has_unbounded_cross_join(expr: &MirRelationExpr) -> bool {
+    let analysis = Analysis::new(expr);
+    let mut found = false;
+    expr.visit_post(&mut |e| {
+        if let MirRelationExpr::Join {
+            inputs,
+            equivalences,
+            ..
+        } = e
+        {
+            // Label each input with the smallest input to which it is
+            // connected by some chain of equivalences.
+            let input_mapper = JoinInputMapper::new(inputs);
+            let mut components: Vec<usize> = (0..inputs.len()).collect();
+            for class in equivalences {
+                let members: Vec<usize> = class
+                    .iter()
+                    .flat_map(|expr| input_mapper.lookup_inputs(expr))
+                    .map(|input| components[input])
+                    .collect();
+                if let Some(min) = members.iter().min().copied() {
+                    for component in &mut components {
+                        if members.contains(component) {
+                            *component = min;
+                        }
+                    }
+                }
+            }
+            let unbounded: BTreeSet<usize> = inputs
+                .iter()
+                .zip(&components)
+                .filter(|(input, _)| {
+                    analysis
+                        .attributes(input)
+                        .map_or(true, |attrs| attrs.cardinality.is_none())
+                })
+                .map(|(_, component)| *component)
+                .collect();
+            found |= unbounded.len() > 1;
+        }
+    });
+    found
+}
+
+/// The period over which `mz_internal.replica_diagnostics` counts the recent
+/// exits of a replica's processes.
+const REPLICA_DIAGNOSTICS_WINDOW: Duration = Duration::from_secs(300);
+
+/// How long the coordinator waits for the secrets controller to apply
+/// operations before canceling them.
+const SECRETS_APPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options that name files on the local filesystem.
+///
+/// These are prohibited in safe mode because they allow reading arbitrary
+/// files on disk.
+const SAFE_MODE_PATH_OPTIONS: &[&str] = &[
+    "sasl_kerberos_keytab",
+    "ssl_ca_location",
+    "ssl_certificate_location",
+    "ssl_key_location",
+];
+
 fn check_statement_safety(stmt: &Statement<Raw>) -> Result<(), CoordError> {
-    let (source_or_sink, typ, with_options) = match stmt {
+    let (source_or_sink, typ, with_options, formats) = match stmt {
         Statement::CreateSource(CreateSourceStatement {
             connector,
             with_options,
+            format,
             ..
-        }) => ("source", SourceConnectorType::from(connector), with_options),
+        }) => {
+            let formats = match format {
+                CreateSourceFormat::None => vec![],
+                CreateSourceFormat::Bare(format) => vec![format],
+                CreateSourceFormat::KeyValue { key, value } => vec![key, value],
+            };
+            (
+                "source",
+                SourceConnectorType::from(connector),
+                with_options,
+                formats,
+            )
+        }
         Statement::CreateSink(CreateSinkStatement {
             connector,
             with_options,
+            format,
             ..
-        }) => ("sink", SourceConnectorType::from(connector), with_options),
+        }) => (
+            "sink",
+            SourceConnectorType::from(connector),
+            with_options,
+            format.iter().collect(),
+        ),
         _ => return Ok(()),
     };
+    let mut options: Vec<_> = with_options.iter().collect();
+    for format in formats {
+        match format {
+            Format::Avro(AvroSchema::InlineSchema {
+                schema: Schema::File(_),
+                ..
+            })
+            | Format::Protobuf(ProtobufSchema::InlineSchema {
+                schema: Schema::File(_),
+                ..
+            }) => {
+                return Err(CoordError::SafeModeViolation(format!(
+                    "{} with a schema file",
+                    source_or_sink
+                )));
+            }
+            Format::Avro(AvroSchema::Csr { csr_connector }) => {
+                options.extend(&csr_connector.with_options)
+            }
+            Format::Protobuf(ProtobufSchema::Csr { csr_connector }) => {
+                options.extend(&csr_connector.with_options)
+            }
+            _ => (),
+        }
+    }
+    for option in options {
+        let name = mz_sql::normalize::ident_ref(option.name());
+        if SAFE_MODE_PATH_OPTIONS.contains(&name) {
+            return Err(CoordError::SafeModeViolation(format!(
+                "{} with option {}",
+                source_or_sink, name
+            )));
+        }
+    }
     match typ {
         // File sources and sinks are prohibited in safe mode because they allow
         // reading from and writing to arbitrary files on disk.
@@ -5168,9 +7880,11 @@ pub mod fast_path_peek {
     use mz_dataflow_types::PeekResponseUnary;
     use std::collections::BTreeSet;
     use std::{collections::HashMap, num::NonZeroUsize};
+    use tracing::info;
     use uuid::Uuid;
 
-    use crate::coord::PendingPeek;
+    use crate::coord::{check_result_size, Message, PeekLimits, PendingPeek};
+    use crate::finishing::SpillingFinisher;
     use crate::CoordError;
     use mz_expr::{EvalError, GlobalId, Id, MirScalarExpr};
     use mz_repr::{Diff, Row};
@@ -5273,6 +7987,7 @@ pub mod fast_path_peek {
 
     impl crate::coord::Coordinator {
         /// Implements a peek plan produced by `create_plan` above.
+        #[allow(clippy::too_many_arguments)]
         pub async fn implement_fast_path_peek(
             &mut self,
             fast_path: Plan,
@@ -5281,6 +7996,8 @@ pub mod fast_path_peek {
             conn_id: u32,
             source_arity: usize,
             compute_instance: ComputeInstanceId,
+            retain_dataflow: bool,
+            limits: PeekLimits,
         ) -> Result<crate::ExecuteResponse, CoordError> {
             // If the dataflow optimizes to a constant expression, we can immediately return the result.
             if let Plan::Constant(rows) = fast_path {
@@ -5315,6 +8032,11 @@ pub mod fast_path_peek {
                     }
                 }
                 let results = finishing.finish(results);
+                if let Some(max_result_rows) = limits.max_result_rows {
+                    if results.len() > max_result_rows {
+                        return Err(CoordError::ResultRowsExceeded(max_result_rows));
+                    }
+                }
                 return Ok(crate::coord::send_immediate_rows(results));
             }
 
@@ -5325,10 +8047,13 @@ pub mod fast_path_peek {
 
             // If we must build the view, ship the dataflow.
             let (peek_command, drop_dataflow) = match fast_path {
-                Plan::PeekExisting(id, key, map_filter_project) => (
-                    (id, key, timestamp, finishing.clone(), map_filter_project),
-                    None,
-                ),
+                Plan::PeekExisting(id, key, map_filter_project) => {
+                    self.record_fast_path_peek(id);
+                    (
+                        (id, key, timestamp, finishing.clone(), map_filter_project),
+                        None,
+                    )
+                }
                 Plan::PeekDataflow(PeekDataflowPlan {
                     desc: dataflow,
                     // n.b. this index_id identifies a transient index the
@@ -5340,6 +8065,7 @@ pub mod fast_path_peek {
                     thinned_arity: index_thinned_arity,
                 }) => {
                     let output_ids = dataflow.export_ids().collect();
+                    self.record_dataflow_statement(&dataflow.debug_name, dataflow.export_ids());
 
                     // Very important: actually create the dataflow (here, so we can destructure).
                     self.dataflow_client
@@ -5401,6 +8127,8 @@ pub mod fast_path_peek {
                 PendingPeek {
                     sender: rows_tx,
                     conn_id,
+                    compute_instance,
+                    timed_out: false,
                 },
             );
             self.client_pending_peeks
@@ -5426,34 +8154,88 @@ pub mod fast_path_peek {
             use futures::StreamExt;
             use mz_dataflow_types::PeekResponse;
 
-            // Prepare the receiver to return as a response.
+            // Prepare the receiver to return as a response. Rows are fed to the
+            // finisher as they arrive, so that it can spill to disk if the
+            // result exceeds the memory limit. If the size of the finished
+            // result is known as rows arrive, a result that exceeds the
+            // maximum size fails as soon as it does, rather than once it has
+            // been received in full.
+            let finisher = SpillingFinisher::new(finishing, self.result_sort_memory_limit);
+            let max_result_size = self.system_vars.max_result_size();
+            let max_result_rows = limits.max_result_rows;
             let rows_rx = tokio_stream::wrappers::UnboundedReceiverStream::new(rows_rx)
-                .fold(PeekResponse::Rows(vec![]), |memo, resp| async {
+                .fold(Ok(finisher), move |memo, resp| async move {
                     match (memo, resp) {
-                        (PeekResponse::Rows(mut memo), PeekResponse::Rows(rows)) => {
-                            memo.extend(rows);
-                            PeekResponse::Rows(memo)
-                        }
-                        (PeekResponse::Error(e), _) | (_, PeekResponse::Error(e)) => {
-                            PeekResponse::Error(e)
+                        (Ok(mut finisher), PeekResponse::Rows(rows)) => match finisher.push(rows) {
+                            Ok(()) => match finisher.result_size().and_then(|(rows, size)| {
+                                result_size_error(rows, size, max_result_size, max_result_rows)
+                            }) {
+                                Some(error) => Err(error),
+                                None => Ok(finisher),
+                            },
+                            Err(e) => Err(PeekResponseUnary::Error(format!(
+                                "spilling query result to disk: {}",
+                                e
+                            ))),
+                        },
+                        (Err(PeekResponseUnary::Error(e)), _) | (_, PeekResponse::Error(e)) => {
+                            Err(PeekResponseUnary::Error(e))
                         }
-                        (PeekResponse::Canceled, _) | (_, PeekResponse::Canceled) => {
-                            PeekResponse::Canceled
+                        (Err(PeekResponseUnary::Canceled), _) | (_, PeekResponse::Canceled) => {
+                            Err(PeekResponseUnary::Canceled)
                         }
+                        (Err(PeekResponseUnary::Rows(_)), _) => unreachable!(),
                     }
                 })
                 .map(move |resp| match resp {
-                    PeekResponse::Rows(rows) => PeekResponseUnary::Rows(finishing.finish(rows)),
-                    PeekResponse::Canceled => PeekResponseUnary::Canceled,
-                    PeekResponse::Error(e) => PeekResponseUnary::Error(e),
+                    Ok(finisher) => match finisher.finish() {
+                        Ok((rows, None)) => {
+                            check_result_size(rows, max_result_size, max_result_rows)
+                        }
+                        Ok((rows, Some(stats))) => {
+                            info!(
+                                "query result for connection {} spilled to disk: \
+                                 {} runs, {} merges, {} rows, {} bytes",
+                                conn_id, stats.runs, stats.merges, stats.rows, stats.bytes
+                            );
+                            check_result_size(rows, max_result_size, max_result_rows)
+                        }
+                        Err(e) => PeekResponseUnary::Error(format!(
+                            "reading spilled query result from disk: {}",
+                            e
+                        )),
+                    },
+                    Err(resp) => resp,
                 });
 
-            // If it was created, drop the dataflow once the peek command is sent.
+            // If it was created, drop the dataflow once the peek command is sent,
+            // unless the caller has asked to retain it.
             if let Some(index_id) = drop_dataflow {
-                self.drop_indexes(vec![(compute_instance, index_id)]).await;
+                if !retain_dataflow {
+                    self.drop_indexes(vec![(compute_instance, index_id)]).await;
+                }
             }
 
-            Ok(crate::ExecuteResponse::SendingRows(Box::pin(rows_rx)))
+            // If the peek runs for longer than its statement timeout, give up
+            // on it and cancel it.
+            let rows_rx: crate::command::RowsFuture = match limits.statement_timeout {
+                None => Box::pin(rows_rx),
+                Some(timeout) => {
+                    let internal_cmd_tx = self.internal_cmd_tx.clone();
+                    Box::pin(async move {
+                        match tokio::time::timeout(timeout, rows_rx).await {
+                            Ok(resp) => resp,
+                            Err(_) => {
+                                // If sending fails, the main thread has shutdown.
+                                let _ = internal_cmd_tx.send(Message::PeekTimedOut(uuid));
+                                PeekResponseUnary::TimedOut
+                            }
+                        }
+                    })
+                }
+            };
+
+            Ok(crate::ExecuteResponse::SendingRows(rows_rx))
         }
     }
 }
@@ -0,0 +1,344 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Export and application of declarative cluster specs.
+//!
+//! A [`ClusterSpec`] describes the configuration of every cluster. Applying a
+//! spec plans the changes that make the clusters match it, validates all of
+//! them, and only then makes them, one cluster at a time, through the same
+//! paths as `CREATE CLUSTER`, `ALTER CLUSTER`, and `DROP CLUSTER`. Applying
+//! the spec that was just exported, or applying a spec twice, changes nothing.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use mz_dataflow_types::client::InstanceConfig;
+use mz_ore::str::StrExt;
+use mz_sql::plan::{
+    AlterComputeInstancePlan, ComputeInstanceConfig, ComputeInstanceIntrospectionConfig,
+    CreateComputeInstancePlan, DropComputeInstancesPlan,
+};
+
+use crate::catalog::{self, ComputeInstance};
+use crate::command::{ClusterSpec, ClusterSpecChanges};
+use crate::coord::Coordinator;
+use crate::error::CoordError;
+
+/// A change to one cluster that applying a spec makes.
+#[derive(Debug, PartialEq)]
+enum ClusterChange {
+    Create {
+        name: String,
+        config: ComputeInstanceConfig,
+    },
+    Alter {
+        name: String,
+        config: ComputeInstanceConfig,
+    },
+    Drop {
+        name: String,
+    },
+}
+
+/// The state of an existing cluster that determines how it can be changed.
+struct ExistingCluster {
+    config: ComputeInstanceConfig,
+    /// Whether indexes or sinks are installed on the cluster, which prevents
+    /// it from being dropped.
+    in_use: bool,
+}
+
+impl Coordinator {
+    /// Describes the configuration of every cluster.
+    pub(super) fn export_cluster_spec(&self) -> ClusterSpec {
+        ClusterSpec {
+            clusters: self
+                .catalog
+                .compute_instances()
+                .map(|instance| (instance.name.clone(), instance_config(instance)))
+                .collect(),
+        }
+    }
+
+    /// Creates, alters, and drops clusters to match `spec`, or, if `dry_run`
+    /// is set, only reports the changes that would be made.
+    pub(super) async fn sequence_apply_cluster_spec(
+        &mut self,
+        spec: ClusterSpec,
+        dry_run: bool,
+    ) -> Result<ClusterSpecChanges, CoordError> {
+        // Cluster DDL is only available in experimental mode, and a spec is
+        // no exception.
+        let config = self.catalog.config();
+        if config.safe_mode {
+            coord_bail!("cannot apply a cluster spec in safe mode");
+        }
+        if !config.experimental_mode {
+            coord_bail!(
+                "applying a cluster spec requires experimental mode; see \
+                https://materialize.com/docs/cli/#experimental-mode"
+            );
+        }
+
+        let existing = self
+            .catalog
+            .compute_instances()
+            .map(|instance| {
+                let existing = ExistingCluster {
+                    config: instance_config(instance),
+                    in_use: !instance.indexes.is_empty(),
+                };
+                (instance.name.clone(), existing)
+            })
+            .collect();
+        let changes = plan_changes(&existing, spec)?;
+
+        let mut summary = ClusterSpecChanges::default();
+        for change in changes {
+            match change {
+                ClusterChange::Create { name, config } => {
+                    if !dry_run {
+                        self.sequence_create_compute_instance(CreateComputeInstancePlan {
+                            name: name.clone(),
+                            if_not_exists: false,
+                            config,
+                        })
+                        .await?;
+                    }
+                    summary.created.push(name);
+                }
+                ClusterChange::Alter { name, config } => {
+                    if !dry_run {
+                        let id = self.catalog.resolve_compute_instance(&name)?.id;
+                        self.sequence_alter_compute_instance(AlterComputeInstancePlan {
+                            id,
+                            config,
+                        })
+                        .await?;
+                    }
+                    summary.altered.push(name);
+                }
+                ClusterChange::Drop { name } => {
+                    if !dry_run {
+                        self.sequence_drop_compute_instances(DropComputeInstancesPlan {
+                            names: vec![name.clone()],
+                        })
+                        .await?;
+                    }
+                    summary.dropped.push(name);
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// Reconstructs the configuration with which `instance` was created, as it
+/// would be planned from `CREATE CLUSTER`.
+fn instance_config(instance: &ComputeInstance) -> ComputeInstanceConfig {
+    let nanos = |ns: u128| Duration::from_nanos(u64::try_from(ns).unwrap_or(u64::MAX));
+    let introspection =
+        instance
+            .logging
+            .as_ref()
+            .map(|logging| ComputeInstanceIntrospectionConfig {
+                debugging: logging.log_logging,
+                granularity: nanos(logging.granularity_ns),
+                retention: logging.retention_ns.map(nanos),
+            });
+    match &instance.config {
+        InstanceConfig::Local => ComputeInstanceConfig::Local,
+        InstanceConfig::Remote { replicas } => ComputeInstanceConfig::Remote {
+            replicas: replicas.clone(),
+            introspection,
+        },
+        InstanceConfig::Managed { size } => ComputeInstanceConfig::Managed {
+            size: size.clone(),
+            introspection,
+        },
+    }
+}
+
+/// Plans the changes that make the clusters `existing` match `spec`.
+///
+/// Returns an error if any change is not one that `CREATE CLUSTER`,
+/// `ALTER CLUSTER`, or `DROP CLUSTER` without `CASCADE` could make.
+fn plan_changes(
+    existing: &BTreeMap<String, ExistingCluster>,
+    spec: ClusterSpec,
+) -> Result<Vec<ClusterChange>, CoordError> {
+    let mut changes = vec![];
+    for (name, current) in existing {
+        if !spec.clusters.contains_key(name) {
+            if current.in_use {
+                coord_bail!(
+                    "cannot drop cluster {} with active indexes or sinks",
+                    name.quoted()
+                );
+            }
+            changes.push(ClusterChange::Drop { name: name.clone() });
+        }
+    }
+    for (name, config) in spec.clusters {
+        match (existing.get(&name).map(|c| &c.config), &config) {
+            (None, ComputeInstanceConfig::Local) => {
+                coord_bail!("cannot create local cluster {}", name.quoted())
+            }
+            (None, ComputeInstanceConfig::Remote { replicas, .. }) if replicas.is_empty() => {
+                coord_bail!("cluster {} must have at least one replica", name.quoted())
+            }
+            (None, _) => {
+                if catalog::is_reserved_name(&name) {
+                    return Err(CoordError::Catalog(catalog::Error::new(
+                        catalog::ErrorKind::ReservedClusterName(name),
+                    )));
+                }
+                changes.push(ClusterChange::Create { name, config });
+            }
+            (Some(current), _) if *current == config => (),
+            (Some(current), _) if current.introspection() != config.introspection() => {
+                coord_bail!(
+                    "cannot change introspection options on existing cluster {}",
+                    name.quoted()
+                )
+            }
+            (
+                Some(ComputeInstanceConfig::Remote {
+                    replicas: old_replicas,
+                    ..
+                }),
+                ComputeInstanceConfig::Remote {
+                    replicas: new_replicas,
+                    ..
+                },
+            ) => {
+                if new_replicas.is_empty() {
+                    coord_bail!("cluster {} must have at least one replica", name.quoted());
+                }
+                for (replica, old_hosts) in old_replicas {
+                    if matches!(new_replicas.get(replica), Some(new_hosts) if new_hosts != old_hosts)
+                    {
+                        coord_bail!(
+                            "cannot change definition of existing replica {} of cluster {}",
+                            replica.quoted(),
+                            name.quoted()
+                        );
+                    }
+                }
+                // Introspection options cannot be restated when altering a
+                // cluster, and are unchanged.
+                let config = ComputeInstanceConfig::Remote {
+                    replicas: new_replicas.clone(),
+                    introspection: None,
+                };
+                changes.push(ClusterChange::Alter { name, config });
+            }
+            (
+                Some(ComputeInstanceConfig::Managed { .. }),
+                ComputeInstanceConfig::Managed { .. },
+            ) => {
+                coord_bail!("cannot yet change size of cluster {}", name.quoted())
+            }
+            (Some(_), _) => {
+                coord_bail!("cannot change type of existing cluster {}", name.quoted())
+            }
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn remote(replicas: &[(&str, &str)]) -> ComputeInstanceConfig {
+        ComputeInstanceConfig::Remote {
+            replicas: replicas
+                .iter()
+                .map(|(name, host)| (name.to_string(), BTreeSet::from([host.to_string()])))
+                .collect(),
+            introspection: None,
+        }
+    }
+
+    fn spec(clusters: &[(&str, ComputeInstanceConfig)]) -> ClusterSpec {
+        ClusterSpec {
+            clusters: clusters
+                .iter()
+                .map(|(name, config)| (name.to_string(), config.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_plan_changes() {
+        let existing: BTreeMap<_, _> = [
+            ("default", ComputeInstanceConfig::Local, true),
+            ("c1", remote(&[("r1", "h1:2100")]), false),
+            ("c2", remote(&[("r1", "h2:2100")]), false),
+        ]
+        .into_iter()
+        .map(|(name, config, in_use)| (name.to_string(), ExistingCluster { config, in_use }))
+        .collect();
+
+        // The spec of the existing clusters changes nothing.
+        let unchanged = spec(&[
+            ("default", ComputeInstanceConfig::Local),
+            ("c1", remote(&[("r1", "h1:2100")])),
+            ("c2", remote(&[("r1", "h2:2100")])),
+        ]);
+        assert_eq!(plan_changes(&existing, unchanged).unwrap(), vec![]);
+
+        let changed = spec(&[
+            ("default", ComputeInstanceConfig::Local),
+            ("c1", remote(&[("r1", "h1:2100"), ("r2", "h3:2100")])),
+            ("c3", remote(&[("r1", "h4:2100")])),
+        ]);
+        assert_eq!(
+            plan_changes(&existing, changed).unwrap(),
+            vec![
+                ClusterChange::Drop { name: "c2".into() },
+                ClusterChange::Alter {
+                    name: "c1".into(),
+                    config: remote(&[("r1", "h1:2100"), ("r2", "h3:2100")]),
+                },
+                ClusterChange::Create {
+                    name: "c3".into(),
+                    config: remote(&[("r1", "h4:2100")]),
+                },
+            ]
+        );
+
+        // Clusters that are in use cannot be dropped.
+        let dropped = spec(&[("c1", remote(&[("r1", "h1:2100")]))]);
+        assert!(plan_changes(&existing, dropped).is_err());
+
+        // Existing replicas cannot be redefined.
+        let redefined = spec(&[
+            ("default", ComputeInstanceConfig::Local),
+            ("c1", remote(&[("r1", "h5:2100")])),
+        ]);
+        assert!(plan_changes(&existing, redefined).is_err());
+
+        // The type of a cluster cannot be changed.
+        let retyped = spec(&[
+            ("default", ComputeInstanceConfig::Local),
+            (
+                "c1",
+                ComputeInstanceConfig::Managed {
+                    size: "1".into(),
+                    introspection: None,
+                },
+            ),
+        ]);
+        assert!(plan_changes(&existing, retyped).is_err());
+    }
+}
@@ -14,6 +14,8 @@
 //! and indicate which identifiers have arrangements available. This module
 //! isolates that logic from the rest of the somewhat complicated coordinator.
 
+use std::rc::Rc;
+
 use mz_dataflow_types::client::controller::ComputeController;
 use mz_dataflow_types::client::ComputeInstanceId;
 use mz_dataflow_types::sinks::SinkDesc;
@@ -24,10 +26,13 @@ use mz_expr::{
 };
 use mz_ore::stack::maybe_grow;
 use mz_repr::adt::array::ArrayDimension;
+use mz_repr::adt::jsonb::JsonbPacker;
 use mz_repr::adt::numeric::Numeric;
 use mz_repr::{Datum, Row};
 
 use crate::catalog::{CatalogItem, CatalogState};
+use crate::coord::statistics::Statistics;
+use crate::coord::view_cache::{CachedView, ViewCache};
 use crate::coord::{CatalogTxn, Coordinator};
 use crate::error::RematerializedSourceType;
 use crate::session::{Session, SERVER_MAJOR_VERSION, SERVER_MINOR_VERSION};
@@ -37,6 +42,12 @@ use crate::{CoordError, PersisterWithConfig};
 pub struct DataflowBuilder<'a, T> {
     pub catalog: &'a CatalogState,
     pub persister: &'a PersisterWithConfig,
+    /// The cache of the imports of views into dataflows.
+    pub view_cache: &'a ViewCache,
+    /// Statistics about indexed collections, for cardinality estimates.
+    pub statistics: &'a Statistics,
+    /// The compute instance for which dataflows are built.
+    pub instance: ComputeInstanceId,
     /// A handle to the compute abstraction, which describes indexes by identifier.
     ///
     /// This can also be used to grab a handle to the storage abstraction, through
@@ -67,6 +78,9 @@ impl Coordinator {
         DataflowBuilder {
             catalog: self.catalog.state(),
             persister: &self.persister,
+            view_cache: &self.view_cache,
+            statistics: &self.statistics,
+            instance,
             compute,
         }
     }
@@ -82,6 +96,9 @@ impl CatalogTxn<'_, mz_repr::Timestamp> {
         DataflowBuilder {
             catalog: self.catalog,
             persister: &self.persister,
+            view_cache: self.view_cache,
+            statistics: self.statistics,
+            instance,
             compute,
         }
     }
@@ -175,8 +192,24 @@ impl<'a> DataflowBuilder<'a, mz_repr::Timestamp> {
                         dataflow.import_source(*id, source_description, persist_desc);
                     }
                     CatalogItem::View(view) => {
-                        let expr = view.optimized_expr.clone();
-                        self.import_view_into_dataflow(id, &expr, dataflow)?;
+                        let cached = match self.view_cache.get(self.instance, *id) {
+                            Some(cached) => cached,
+                            None => {
+                                let mut imports = DataflowDesc::new(String::new());
+                                let expr = view.optimized_expr.clone();
+                                self.import_view_into_dataflow(id, &expr, &mut imports)?;
+                                let cached = Rc::new(CachedView::new(imports));
+                                // Whether sources that must only be materialized once
+                                // can be imported depends on more than the items
+                                // consulted, so views over them are not cached.
+                                if !self.imports_single_materialization_source(&cached) {
+                                    self.view_cache
+                                        .insert(self.instance, *id, Rc::clone(&cached));
+                                }
+                                cached
+                            }
+                        };
+                        cached.import_into(dataflow);
                     }
                     _ => unreachable!(),
                 }
@@ -185,6 +218,16 @@ impl<'a> DataflowBuilder<'a, mz_repr::Timestamp> {
         })
     }
 
+    /// Reports whether `view` imports a source that may only be materialized
+    /// once.
+    fn imports_single_materialization_source(&self, view: &CachedView) -> bool {
+        view.source_ids()
+            .any(|id| match self.catalog.get_entry(&id).item() {
+                CatalogItem::Source(source) => source.requires_single_materialization(),
+                _ => false,
+            })
+    }
+
     /// Imports the view with the specified ID and expression into the provided
     /// dataflow description.
     ///
@@ -435,6 +478,26 @@ fn eval_unmaterializable_func(
             Some(logical_time) => pack(Datum::from(Numeric::from(logical_time))),
         },
         UnmaterializableFunc::MzSessionId => pack(Datum::from(state.config().session_id)),
+        UnmaterializableFunc::MzSessionSettings => {
+            let settings = session
+                .vars()
+                .iter()
+                .filter(|v| !v.experimental())
+                .map(|v| {
+                    serde_json::json!({
+                        "name": v.name(),
+                        "setting": v.value(),
+                        "short_desc": v.description(),
+                        "vartype": v.type_name(),
+                    })
+                })
+                .collect();
+            let mut row = Row::default();
+            JsonbPacker::new(&mut row.packer())
+                .pack_serde_json(serde_json::Value::Array(settings))
+                .expect("known to be valid JSON");
+            Ok(MirScalarExpr::Literal(Ok(row), f.output_type()))
+        }
         UnmaterializableFunc::MzUptime => {
             let uptime = state.config().start_instant.elapsed();
             let uptime = chrono::Duration::from_std(uptime).map_or(Datum::Null, Datum::from);
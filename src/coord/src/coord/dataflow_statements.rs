@@ -0,0 +1,267 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Attribution of dataflows to the statements that created them, as reported
+//! in `mz_internal.mz_all_dataflow_statements`.
+//!
+//! Dataflows are identified by their first export, as their names need not
+//! be unique. The name of a dataflow's root operator in the introspection
+//! sources lets `mz_statement_resource_usage` aggregate the resources that
+//! dataflows consume by statement. Like index usage, the table is brought up
+//! to date periodically rather than once per peek.
+//!
+//! The statement that created a dataflow is not necessarily the one that the
+//! coordinator is sequencing when it ships the dataflow: plans that wait for
+//! the write lock, for a free peek slot, or for a sink connector carry their
+//! statement with them and resume it with [`Coordinator::resume_statement`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
+
+use mz_expr::GlobalId;
+use mz_repr::{Datum, Row};
+use mz_sql::ast::display::AstDisplay;
+use mz_sql::ast::{ExplainStage, ExplainStatement, Raw, Statement};
+use mz_sql::catalog::CatalogItem as _;
+
+use crate::catalog::builtin::MZ_ALL_DATAFLOW_STATEMENTS;
+use crate::catalog::BuiltinTableUpdate;
+use crate::coord::Coordinator;
+use crate::session::Session;
+
+/// A statement to which the dataflows it creates are attributed.
+#[derive(Debug, Clone)]
+pub struct ActiveStatement {
+    /// The ID assigned to the statement.
+    statement_id: i64,
+    /// The ID of the connection that issued the statement.
+    conn_id: u32,
+    /// The user that issued the statement.
+    user: String,
+    /// The SQL text of the statement.
+    sql: String,
+}
+
+/// The statement to which a dataflow is attributed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementAttribution {
+    /// An identifier for the execution of the statement, which is unique
+    /// across executions.
+    statement_id: i64,
+    /// The ID of the connection that issued the statement, if the dataflow was
+    /// created directly by a statement of a connection.
+    session_id: Option<u32>,
+    /// The user that issued the statement, if any.
+    user: Option<String>,
+    /// The SQL text of the statement, if known.
+    sql: Option<String>,
+}
+
+/// A dataflow attributed to a statement.
+#[derive(Debug)]
+pub struct AttributedDataflow {
+    /// The name of the dataflow's root operator.
+    name: String,
+    attribution: StatementAttribution,
+    /// The exports of the dataflow that have not yet been dropped.
+    exports: BTreeSet<GlobalId>,
+}
+
+/// A dataflow as most recently reflected in `mz_all_dataflow_statements`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportedDataflow {
+    name: String,
+    attribution: StatementAttribution,
+}
+
+impl Coordinator {
+    /// Returns the statement to which the dataflows that `stmt`, issued in
+    /// `session`, creates are attributed, if `stmt` may create dataflows.
+    pub(super) fn statement_for(
+        &mut self,
+        session: &Session,
+        stmt: &Statement<Raw>,
+    ) -> Option<ActiveStatement> {
+        // Only these statements create dataflows, and the text of other
+        // statements, like large `INSERT`s, is not worth rendering.
+        let creates_dataflows = matches!(
+            stmt,
+            Statement::Select(_)
+                | Statement::Tail(_)
+                | Statement::Copy(_)
+                | Statement::CreateSource(_)
+                | Statement::CreateIndex(_)
+                | Statement::CreateView(_)
+                | Statement::CreateViews(_)
+                | Statement::CreateSink(_)
+                | Statement::Explain(ExplainStatement {
+                    stage: ExplainStage::Analyze,
+                    ..
+                })
+        );
+        if creates_dataflows {
+            Some(ActiveStatement {
+                statement_id: self.allocate_statement_id(),
+                conn_id: session.conn_id(),
+                user: session.user().into(),
+                sql: stmt.to_ast_string_stable(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Attributes the dataflows created until the next call to
+    /// [`Coordinator::end_statement`] to `stmt`, issued in `session`.
+    pub(super) fn begin_statement(&mut self, session: &Session, stmt: &Statement<Raw>) {
+        self.active_statement = self.statement_for(session, stmt);
+    }
+
+    /// Ends the attribution started by [`Coordinator::begin_statement`].
+    pub(super) fn end_statement(&mut self) {
+        self.active_statement = None;
+    }
+
+    /// Returns the statement to which new dataflows are attributed, for work
+    /// that is deferred to resume with it later.
+    pub(super) fn current_statement(&self) -> Option<ActiveStatement> {
+        self.active_statement.clone()
+    }
+
+    /// Attributes new dataflows to `statement`, the statement of deferred
+    /// work that is resuming, until the returned statement is restored with
+    /// [`Coordinator::restore_statement`].
+    pub(super) fn resume_statement(
+        &mut self,
+        statement: Option<ActiveStatement>,
+    ) -> Option<ActiveStatement> {
+        mem::replace(&mut self.active_statement, statement)
+    }
+
+    /// Restores the statement that [`Coordinator::resume_statement`]
+    /// replaced.
+    pub(super) fn restore_statement(&mut self, previous: Option<ActiveStatement>) {
+        self.active_statement = previous;
+    }
+
+    /// Attributes the dataflow named `debug_name` that is being created with
+    /// exports `export_ids` to the active statement.
+    ///
+    /// Dataflows that are created outside of a statement, e.g., when the
+    /// coordinator boots, are attributed to the SQL of the catalog item they
+    /// export, if any.
+    pub(super) fn record_dataflow_statement<I>(&mut self, debug_name: &str, export_ids: I)
+    where
+        I: IntoIterator<Item = GlobalId>,
+    {
+        let exports: BTreeSet<_> = export_ids.into_iter().collect();
+        let id = match exports.iter().next() {
+            Some(id) => *id,
+            None => return,
+        };
+        let attribution = match &self.active_statement {
+            Some(stmt) => StatementAttribution {
+                statement_id: stmt.statement_id,
+                session_id: Some(stmt.conn_id),
+                user: Some(stmt.user.clone()),
+                sql: Some(stmt.sql.clone()),
+            },
+            None => StatementAttribution {
+                statement_id: self.allocate_statement_id(),
+                session_id: None,
+                user: None,
+                sql: exports.iter().find_map(|id| {
+                    self.catalog
+                        .try_get_entry(id)
+                        .map(|entry| entry.create_sql().to_string())
+                }),
+            },
+        };
+        self.dataflow_statements.insert(
+            id,
+            AttributedDataflow {
+                // The name matches the one the compute layer gives the
+                // dataflow's root operator.
+                name: format!("Dataflow: {}", debug_name),
+                attribution,
+                exports,
+            },
+        );
+    }
+
+    fn allocate_statement_id(&mut self) -> i64 {
+        let id = self.next_statement_id;
+        self.next_statement_id += 1;
+        id
+    }
+
+    /// Records that the dataflow export `id` has been dropped.
+    ///
+    /// A dataflow's attribution is forgotten once all of its exports have
+    /// been dropped.
+    pub(super) fn forget_dataflow_statement_export(&mut self, id: GlobalId) {
+        self.dataflow_statements.retain(|_, dataflow| {
+            dataflow.exports.remove(&id);
+            !dataflow.exports.is_empty()
+        });
+    }
+
+    /// Brings `mz_all_dataflow_statements` up to date with the dataflows
+    /// created and dropped since it was last updated.
+    pub(super) async fn report_dataflow_statements(&mut self) {
+        let current: HashMap<_, _> = self
+            .dataflow_statements
+            .iter()
+            .map(|(id, dataflow)| {
+                let reported = ReportedDataflow {
+                    name: dataflow.name.clone(),
+                    attribution: dataflow.attribution.clone(),
+                };
+                (*id, reported)
+            })
+            .collect();
+        if current == self.reported_dataflow_statements {
+            return;
+        }
+        let table_id = self
+            .catalog
+            .resolve_builtin_table(&MZ_ALL_DATAFLOW_STATEMENTS);
+        let pack = |id: &GlobalId, dataflow: &ReportedDataflow, diff| {
+            let attribution = &dataflow.attribution;
+            BuiltinTableUpdate {
+                id: table_id,
+                row: Row::pack_slice(&[
+                    Datum::String(&id.to_string()),
+                    Datum::String(&dataflow.name),
+                    Datum::Int64(attribution.statement_id),
+                    match attribution.session_id {
+                        Some(session_id) => Datum::Int32(session_id as i32),
+                        None => Datum::Null,
+                    },
+                    Datum::from(attribution.user.as_deref()),
+                    Datum::from(attribution.sql.as_deref()),
+                ]),
+                diff,
+            }
+        };
+        let mut updates = vec![];
+        for (id, dataflow) in &self.reported_dataflow_statements {
+            if current.get(id) != Some(dataflow) {
+                updates.push(pack(id, dataflow, -1));
+            }
+        }
+        for (id, dataflow) in &current {
+            if self.reported_dataflow_statements.get(id) != Some(dataflow) {
+                updates.push(pack(id, dataflow, 1));
+            }
+        }
+        self.reported_dataflow_statements = current;
+        self.send_builtin_table_updates(updates).await;
+    }
+}
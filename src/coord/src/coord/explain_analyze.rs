@@ -0,0 +1,633 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! `EXPLAIN ANALYZE`, which executes a query and reports, alongside the
+//! query's optimized plan, how much time each operator of the query's
+//! dataflow spent executing it, how many rows each operator produced, and how
+//! many records each operator's arrangements hold.
+//!
+//! The statistics are read from the compute instance's introspection sources,
+//! which only describe dataflows that are still installed. The dataflow that
+//! computes the query is therefore retained until the statistics have been
+//! read, which happens in three steps, each driven by an internal message so
+//! that the coordinator is not blocked while the query executes:
+//!
+//!   1. The query is peeked like a `SELECT`, except that its dataflow is
+//!      retained once the peek is issued.
+//!   2. Once the query's results arrive, the introspection sources are peeked
+//!      at a time no earlier than any event that the dataflow has logged.
+//!   3. Once the introspection peeks complete, the dataflow is dropped and the
+//!      timed plan is returned to the client.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use derivative::Derivative;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::logging::{DifferentialLog, LogVariant, TimelyLog};
+use mz_dataflow_types::{
+    DataflowDesc, DataflowGraphFormatter, Explanation, PeekResponse, PeekResponseUnary,
+};
+use mz_expr::{
+    permutation_for_arrangement, GlobalId, MapFilterProject, MirRelationExpr, MirScalarExpr,
+    RowSetFinishing,
+};
+use mz_ore::cast::CastFrom;
+use mz_ore::task;
+use mz_repr::adt::interval::Interval;
+use mz_repr::{Datum, Row, Timestamp};
+use mz_sql::plan::{ExplainPlan, OptimizerConfig, PeekPlan, QueryWhen};
+
+use crate::command::ExecuteResponse;
+use crate::coord::{send_immediate_rows, Coordinator, Message, PendingPeek};
+use crate::error::CoordError;
+use crate::session::Session;
+use crate::util::ClientTransmitter;
+
+/// The introspection sources from which `EXPLAIN ANALYZE` reads statistics.
+const LOGS: [LogVariant; 6] = [
+    LogVariant::Timely(TimelyLog::Operates),
+    LogVariant::Timely(TimelyLog::Addresses),
+    LogVariant::Timely(TimelyLog::Elapsed),
+    LogVariant::Timely(TimelyLog::Channels),
+    LogVariant::Timely(TimelyLog::MessagesSent),
+    LogVariant::Differential(DifferentialLog::ArrangementRecords),
+];
+
+/// How a peek produced its results.
+#[derive(Debug)]
+pub enum PeekStrategy {
+    /// The query optimized to a constant.
+    Constant,
+    /// The results were read from the existing index with the given ID.
+    Existing(GlobalId),
+    /// The results were read from a dataflow built for the peek, which
+    /// exports the transient index `index_id`.
+    Dataflow {
+        compute_instance: ComputeInstanceId,
+        index_id: GlobalId,
+        debug_name: String,
+    },
+}
+
+/// A query executed by `EXPLAIN ANALYZE` whose results have arrived.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ExplainAnalyzeExecuted {
+    pub session: Session,
+    #[derivative(Debug = "ignore")]
+    pub tx: ClientTransmitter<ExecuteResponse>,
+    pub strategy: PeekStrategy,
+    /// The query's optimized plan, as `EXPLAIN` renders it.
+    pub plan: String,
+    /// The number of rows that the query returned, or the response that
+    /// reports why it did not return any.
+    #[derivative(Debug = "ignore")]
+    pub outcome: Result<usize, PeekResponseUnary>,
+    /// The time from sequencing the query to the arrival of its results.
+    pub elapsed: Duration,
+}
+
+/// An `EXPLAIN ANALYZE` whose response is ready.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ExplainAnalyzeFinished {
+    pub session: Session,
+    #[derivative(Debug = "ignore")]
+    pub tx: ClientTransmitter<ExecuteResponse>,
+    /// The dataflow to drop before responding, if any.
+    pub dataflow: Option<(ComputeInstanceId, GlobalId)>,
+    #[derivative(Debug = "ignore")]
+    pub result: Result<ExecuteResponse, CoordError>,
+}
+
+impl Coordinator {
+    /// Sequences `EXPLAIN ANALYZE` by executing the explained query.
+    pub(super) async fn sequence_explain_analyze(
+        &mut self,
+        tx: ClientTransmitter<ExecuteResponse>,
+        mut session: Session,
+        plan: ExplainPlan,
+    ) {
+        let start = Instant::now();
+        let (resp, strategy, plan) = match self.explain_analyze_peek(&mut session, plan).await {
+            Ok(res) => res,
+            Err(e) => {
+                tx.send(Err(e), session);
+                return;
+            }
+        };
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        let conn_id = session.conn_id();
+        task::spawn(|| format!("explain_analyze:{conn_id}"), async move {
+            let outcome = match resp {
+                ExecuteResponse::SendingRows(rows) => match rows.await {
+                    PeekResponseUnary::Rows(rows) => Ok(rows.len()),
+                    resp => Err(resp),
+                },
+                _ => Err(PeekResponseUnary::Error("expected SendingRows".into())),
+            };
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::ExplainAnalyzeExecuted(ExplainAnalyzeExecuted {
+                session,
+                tx,
+                strategy,
+                plan,
+                outcome,
+                elapsed: start.elapsed(),
+            }));
+        });
+    }
+
+    /// Peeks the query explained by `plan`, retaining its dataflow.
+    ///
+    /// Returns the query's optimized plan along with the peek.
+    async fn explain_analyze_peek(
+        &mut self,
+        session: &mut Session,
+        plan: ExplainPlan,
+    ) -> Result<(ExecuteResponse, PeekStrategy, String), CoordError> {
+        let compute_instance = self
+            .catalog
+            .resolve_compute_instance(session.vars().cluster())?;
+        if compute_instance.logging.is_none() {
+            return Err(CoordError::Unstructured(anyhow!(
+                "EXPLAIN ANALYZE requires introspection to be enabled on cluster {}",
+                compute_instance.name
+            )));
+        }
+        let compute_instance = compute_instance.id;
+
+        let ExplainPlan {
+            raw_plan,
+            row_set_finishing,
+            ..
+        } = plan;
+        let source = raw_plan.optimize_and_lower(&OptimizerConfig {
+            qgm_optimizations: session.vars().qgm_optimizations(),
+        })?;
+        let explanation = self.explain_optimized_plan(
+            session,
+            compute_instance,
+            source.clone(),
+            row_set_finishing.clone(),
+        )?;
+        let finishing = row_set_finishing.unwrap_or_else(|| RowSetFinishing {
+            order_by: vec![],
+            limit: None,
+            offset: 0,
+            project: (0..source.arity()).collect(),
+        });
+        let plan = PeekPlan {
+            source,
+            when: QueryWhen::Immediately,
+            finishing,
+            copy_to: None,
+        };
+        let (resp, strategy) = self.sequence_peek_inner(session, plan, true).await?;
+        Ok((resp, strategy, explanation))
+    }
+
+    /// Renders the optimized plan of `source` as `EXPLAIN OPTIMIZED PLAN`
+    /// would render it.
+    fn explain_optimized_plan(
+        &mut self,
+        session: &Session,
+        compute_instance: ComputeInstanceId,
+        source: MirRelationExpr,
+        row_set_finishing: Option<RowSetFinishing>,
+    ) -> Result<String, CoordError> {
+        let optimized_plan = self.view_optimizer.optimize(source)?;
+        let mut dataflow = DataflowDesc::new("explanation".into());
+        self.dataflow_builder(compute_instance)
+            .import_view_into_dataflow(&GlobalId::Explain, &optimized_plan, &mut dataflow)?;
+        mz_transform::optimize_dataflow(&mut dataflow, &self.index_oracle(compute_instance))?;
+        let catalog = self.catalog.for_session(session);
+        let formatter = DataflowGraphFormatter::new(&catalog, false, false);
+        let mut explanation = Explanation::new_from_dataflow(&dataflow, &catalog, &formatter);
+        if let Some(row_set_finishing) = row_set_finishing {
+            explanation.explain_row_set_finishing(row_set_finishing);
+        }
+        Ok(explanation.to_string())
+    }
+
+    /// Reads the statistics of the dataflow that computed the query of an
+    /// `EXPLAIN ANALYZE`, if there is one, once the query's results arrive.
+    pub(super) async fn message_explain_analyze_executed(
+        &mut self,
+        ExplainAnalyzeExecuted {
+            session,
+            tx,
+            strategy,
+            plan,
+            outcome,
+            elapsed,
+        }: ExplainAnalyzeExecuted,
+    ) {
+        let (compute_instance, index_id, debug_name) = match strategy {
+            PeekStrategy::Dataflow {
+                compute_instance,
+                index_id,
+                debug_name,
+            } => (compute_instance, index_id, debug_name),
+            strategy => {
+                let resp = match outcome {
+                    Ok(rows) => {
+                        let summary = match strategy {
+                            PeekStrategy::Existing(id) => format!(
+                                "Read from existing index {}; no dataflow was built",
+                                self.catalog.resolve_full_name(
+                                    self.catalog.get_entry(&id).name(),
+                                    Some(session.conn_id())
+                                )
+                            ),
+                            _ => "Constant result; no dataflow was built".into(),
+                        };
+                        render(&plan, vec![summary], rows, elapsed)
+                    }
+                    Err(resp) => ExecuteResponse::SendingRows(Box::pin(async { resp })),
+                };
+                tx.send(Ok(resp), session);
+                return;
+            }
+        };
+        let dataflow = Some((compute_instance, index_id));
+
+        let rows = match outcome {
+            Ok(rows) => rows,
+            Err(resp) => {
+                let result = Ok(ExecuteResponse::SendingRows(Box::pin(async { resp })));
+                self.message_explain_analyze_finished(ExplainAnalyzeFinished {
+                    session,
+                    tx,
+                    dataflow,
+                    result,
+                })
+                .await;
+                return;
+            }
+        };
+
+        // The cluster may have been dropped while the query executed.
+        let logging = match self
+            .catalog
+            .compute_instances()
+            .find(|instance| instance.id == compute_instance)
+        {
+            Some(instance) => instance.logging.clone(),
+            None => {
+                tx.send(
+                    Err(CoordError::Unstructured(anyhow!(
+                        "cluster was dropped while the query executed"
+                    ))),
+                    session,
+                );
+                return;
+            }
+        };
+        let logging = logging.expect("checked when the query was sequenced");
+
+        // Introspection events are stamped with the wall-clock time at which
+        // they occur, rounded up to the next multiple of the logging
+        // granularity, so peeking at the next such multiple observes every
+        // event the dataflow has logged so far.
+        let granularity_ms = std::cmp::max(1, logging.granularity_ns / 1_000_000) as Timestamp;
+        let timestamp = ((self.now() / granularity_ms) + 1) * granularity_ms;
+        let mut receivers = vec![];
+        for variant in LOGS {
+            let id = match logging.active_logs.get(&variant) {
+                Some(id) => *id,
+                None => {
+                    let result = Err(CoordError::Unstructured(anyhow!(
+                        "introspection source for {:?} is not active",
+                        variant
+                    )));
+                    self.message_explain_analyze_finished(ExplainAnalyzeFinished {
+                        session,
+                        tx,
+                        dataflow,
+                        result,
+                    })
+                    .await;
+                    return;
+                }
+            };
+            let rx = self
+                .peek_introspection(id, &variant, timestamp, session.conn_id(), compute_instance)
+                .await;
+            receivers.push(rx);
+        }
+
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(
+            || format!("explain_analyze_introspection:{index_id}"),
+            async move {
+                let mut logs = vec![];
+                let mut result = Ok(());
+                for mut rx in receivers {
+                    match rx.recv().await {
+                        Some(PeekResponse::Rows(rows)) => logs.push(rows),
+                        Some(PeekResponse::Error(e)) => {
+                            result = Err(PeekResponseUnary::Error(e));
+                            break;
+                        }
+                        Some(PeekResponse::Canceled) | None => {
+                            result = Err(PeekResponseUnary::Canceled);
+                            break;
+                        }
+                    }
+                }
+                let result = match result {
+                    Ok(()) => {
+                        let operators = analyze(&debug_name, logs);
+                        Ok(render(&plan, operators, rows, elapsed))
+                    }
+                    Err(resp) => Ok(ExecuteResponse::SendingRows(Box::pin(async { resp }))),
+                };
+                // If sending fails, the main thread has shutdown.
+                let _ =
+                    internal_cmd_tx.send(Message::ExplainAnalyzeFinished(ExplainAnalyzeFinished {
+                        session,
+                        tx,
+                        dataflow,
+                        result,
+                    }));
+            },
+        );
+    }
+
+    /// Drops the dataflow of an `EXPLAIN ANALYZE`, if it built one, and
+    /// responds to the client.
+    pub(super) async fn message_explain_analyze_finished(
+        &mut self,
+        ExplainAnalyzeFinished {
+            session,
+            tx,
+            dataflow,
+            result,
+        }: ExplainAnalyzeFinished,
+    ) {
+        if let Some((compute_instance, index_id)) = dataflow {
+            // The dataflow was dropped along with its cluster, if the cluster
+            // has been dropped.
+            if self.dataflow_client.compute(compute_instance).is_some() {
+                self.drop_indexes(vec![(compute_instance, index_id)]).await;
+            }
+        }
+        tx.send(result, session);
+    }
+
+    /// Peeks the introspection index `id`, which arranges the log `variant`,
+    /// on behalf of the connection `conn_id`.
+    ///
+    /// The peek is registered as a pending peek of the connection, so that
+    /// canceling the connection's queries cancels it.
+    pub(super) async fn peek_introspection(
+        &mut self,
+        id: GlobalId,
+        variant: &LogVariant,
+        timestamp: Timestamp,
+        conn_id: u32,
+        compute_instance: ComputeInstanceId,
+    ) -> mpsc::UnboundedReceiver<PeekResponse> {
+        let arity = variant.desc().arity();
+        let key: Vec<_> = variant
+            .index_by()
+            .into_iter()
+            .map(MirScalarExpr::Column)
+            .collect();
+        let (permutation, thinning) = permutation_for_arrangement(&key, arity);
+        let mut map_filter_project = MapFilterProject::new(arity);
+        map_filter_project.permute(permutation, key.len() + thinning.len());
+        let map_filter_project = map_filter_project
+            .into_plan()
+            .expect("identity plan is valid")
+            .into_nontemporal()
+            .expect("identity plan is not temporal");
+        let finishing = RowSetFinishing {
+            order_by: vec![],
+            limit: None,
+            offset: 0,
+            project: (0..arity).collect(),
+        };
+
+        let (rows_tx, rows_rx) = mpsc::unbounded_channel();
+        let mut uuid = Uuid::new_v4();
+        while self.pending_peeks.contains_key(&uuid) {
+            uuid = Uuid::new_v4();
+        }
+        self.pending_peeks.insert(
+            uuid,
+            PendingPeek {
+                sender: rows_tx,
+                conn_id,
+                compute_instance,
+                timed_out: false,
+            },
+        );
+        self.client_pending_peeks
+            .entry(conn_id)
+            .or_insert_with(BTreeSet::new)
+            .insert(uuid);
+        self.dataflow_client
+            .compute_mut(compute_instance)
+            .unwrap()
+            .peek(id, None, uuid, timestamp, finishing, map_filter_project)
+            .await
+            .unwrap();
+        rows_rx
+    }
+}
+
+/// The statistics of an operator, summed across workers.
+#[derive(Debug, Default)]
+struct OperatorStats {
+    name: String,
+    elapsed: Duration,
+    rows: u64,
+    arranged_records: u64,
+}
+
+/// Reads the statistics of the operators of the dataflow named `debug_name`
+/// out of the contents of the introspection sources in [`LOGS`].
+///
+/// Returns one line per operator, indented by the operator's depth within
+/// the dataflow and in the order of the operators' addresses.
+fn analyze(debug_name: &str, logs: Vec<Vec<(Row, NonZeroUsize)>>) -> Vec<String> {
+    let mut logs = logs.into_iter();
+    let mut next_log = || logs.next().unwrap_or_default();
+    let (operates, addresses, elapsed, channels, messages_sent, arrangement_records) = (
+        next_log(),
+        next_log(),
+        next_log(),
+        next_log(),
+        next_log(),
+        next_log(),
+    );
+
+    // The addresses of operators and of the scopes that contain channels, by
+    // ID and worker.
+    let mut address_by_id = HashMap::new();
+    for (row, _) in &addresses {
+        let datums = row.unpack();
+        let address: Vec<i64> = datums[2]
+            .unwrap_list()
+            .iter()
+            .map(|d| d.unwrap_int64())
+            .collect();
+        address_by_id.insert(
+            (datums[0].unwrap_int64(), datums[1].unwrap_int64()),
+            address,
+        );
+    }
+
+    // Each worker hosts its own instance of the dataflow, whose root operator
+    // is named after the dataflow.
+    let root_name = format!("Dataflow: {}", debug_name);
+    let mut roots = HashMap::new();
+    for (row, _) in &operates {
+        let datums = row.unpack();
+        if datums[2].unwrap_str() == root_name {
+            let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+            if let Some(address) = address_by_id.get(&key) {
+                roots.insert(key.1, address.clone());
+            }
+        }
+    }
+    // Returns the address of the operator or scope with the given address on
+    // `worker` relative to the dataflow's root, if it belongs to the dataflow.
+    let relative = |worker: i64, address: &[i64]| -> Option<Vec<i64>> {
+        let root = roots.get(&worker)?;
+        address.strip_prefix(&root[..]).map(|a| a.to_vec())
+    };
+
+    let mut operators: BTreeMap<Vec<i64>, OperatorStats> = BTreeMap::new();
+    let mut operator_by_id = HashMap::new();
+    for (row, _) in &operates {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        let address = match address_by_id
+            .get(&key)
+            .and_then(|address| relative(key.1, address))
+        {
+            Some(address) => address,
+            None => continue,
+        };
+        operators.entry(address.clone()).or_default().name = datums[2].unwrap_str().into();
+        operator_by_id.insert(key, address);
+    }
+
+    // The elapsed time of an operator is recorded in the multiplicity of its
+    // record, in nanoseconds.
+    for (row, count) in &elapsed {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        if let Some(address) = operator_by_id.get(&key) {
+            let stats = operators.get_mut(address).unwrap();
+            stats.elapsed += Duration::from_nanos(u64::cast_from(count.get()));
+        }
+    }
+
+    // The number of records sent on each channel by each worker, which is
+    // recorded in the multiplicity of the records.
+    let mut sent = HashMap::new();
+    for (row, count) in &messages_sent {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        *sent.entry(key).or_insert(0) += u64::cast_from(count.get());
+    }
+
+    // The rows an operator produces are the records sent on the channels out
+    // of its output ports. A port with several outgoing channels sends each
+    // record on each of them, so count only its busiest channel.
+    let mut port_rows = HashMap::new();
+    for (row, _) in &channels {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        let (source_node, source_port) = (datums[2].unwrap_int64(), datums[3].unwrap_int64());
+        // Node zero is the scope's own inputs, rather than an operator.
+        if source_node == 0 {
+            continue;
+        }
+        let mut address = match address_by_id
+            .get(&key)
+            .and_then(|scope| relative(key.1, scope))
+        {
+            Some(scope) => scope,
+            None => continue,
+        };
+        address.push(source_node);
+        let rows = sent.get(&key).copied().unwrap_or(0);
+        let max_rows = port_rows.entry((key.1, address, source_port)).or_insert(0);
+        *max_rows = std::cmp::max(*max_rows, rows);
+    }
+    for ((_worker, address, _port), rows) in port_rows {
+        if let Some(stats) = operators.get_mut(&address) {
+            stats.rows += rows;
+        }
+    }
+
+    // The number of records in the arrangements that an operator maintains
+    // is recorded in the multiplicity of its records.
+    for (row, count) in &arrangement_records {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        if let Some(address) = operator_by_id.get(&key) {
+            let stats = operators.get_mut(address).unwrap();
+            stats.arranged_records += u64::cast_from(count.get());
+        }
+    }
+
+    operators
+        .into_iter()
+        .map(|(address, stats)| {
+            let mut line = format!(
+                "{:indent$}{} (elapsed: {}, rows: {}",
+                "",
+                stats.name,
+                interval(stats.elapsed),
+                stats.rows,
+                indent = 2 * address.len()
+            );
+            // Most operators do not maintain arrangements.
+            if stats.arranged_records > 0 {
+                line.push_str(&format!(", arranged records: {}", stats.arranged_records));
+            }
+            line.push(')');
+            line
+        })
+        .collect()
+}
+
+/// Renders the response to an `EXPLAIN ANALYZE` whose query, which has the
+/// optimized plan `plan`, returned `rows` rows in `elapsed`, with `lines`
+/// describing how the query was executed.
+fn render(plan: &str, lines: Vec<String>, rows: usize, elapsed: Duration) -> ExecuteResponse {
+    let mut explanation = format!("{}\n\n", plan.trim_end());
+    explanation.push_str(&lines.join("\n"));
+    explanation.push_str(&format!(
+        "\n\nRows returned: {}\nExecution time: {}\n",
+        rows,
+        interval(elapsed)
+    ));
+    send_immediate_rows(vec![Row::pack_slice(&[Datum::from(&*explanation)])])
+}
+
+fn interval(duration: Duration) -> Interval {
+    Interval {
+        months: 0,
+        days: 0,
+        micros: duration.as_micros().try_into().unwrap(),
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Reporting of the progress of indexes and sinks on each replica of each
+//! cluster, as reflected in `mz_cluster_replica_frontiers`.
+//!
+//! The introspection sources of a cluster only describe the dataflows of that
+//! cluster, so the frontiers they report cannot be compared across clusters.
+//! The compute controller, on the other hand, hears from every replica of
+//! every cluster. The coordinator periodically brings
+//! `mz_cluster_replica_frontiers` up to date with the controller's view of
+//! each replica, from which `mz_hydration_statuses` reports whether each
+//! object is hydrated and how far it lags behind its inputs.
+
+use std::collections::HashMap;
+
+use timely::order::PartialOrder;
+use timely::progress::Antichain;
+
+use mz_dataflow_types::client::{ComputeInstanceId, InstanceConfig};
+use mz_expr::GlobalId;
+use mz_repr::{Datum, Row, Timestamp};
+
+use crate::catalog::builtin::MZ_CLUSTER_REPLICA_FRONTIERS;
+use crate::catalog::BuiltinTableUpdate;
+use crate::coord::Coordinator;
+
+/// Identifies an index or sink on a replica of a cluster.
+///
+/// The replica is `None` for the cluster that runs within this process, which
+/// has no replicas.
+pub type ReplicaFrontierKey = (GlobalId, ComputeInstanceId, Option<String>);
+
+/// The progress of an index or sink on a replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaFrontier {
+    /// Whether the replica's write frontier is beyond the object's read
+    /// frontier, i.e., whether the replica can serve reads of the object.
+    hydrated: bool,
+    /// Whether the replica is hydrated and its write frontier is as far along
+    /// as that of any other replica, so that the replica is among those
+    /// serving the object.
+    serving: bool,
+    /// The replica's write frontier, or `None` if the object is complete.
+    time: Option<Timestamp>,
+    /// The least write frontier of the object's inputs, or `None` if the
+    /// object has no inputs or its inputs are complete.
+    input_time: Option<Timestamp>,
+}
+
+impl Coordinator {
+    /// Brings `mz_cluster_replica_frontiers` up to date with the frontiers
+    /// that the replicas have reported since it was last updated.
+    pub(super) async fn report_replica_frontiers(&mut self) {
+        let mut frontiers = HashMap::new();
+        for instance in self.catalog.compute_instances() {
+            let compute = match self.dataflow_client.compute(instance.id) {
+                Some(compute) => compute,
+                None => continue,
+            };
+            for (id, collection) in compute.collections() {
+                // Only report user objects. The dataflows of `TAIL`s and
+                // peeks are not objects at all.
+                if !id.is_user() || self.catalog.try_get_entry(&id).is_none() {
+                    continue;
+                }
+                let mut input_frontier = Antichain::new();
+                for input in &collection.storage_dependencies {
+                    if let Ok(input) = compute.storage().collection(*input) {
+                        input_frontier.extend(input.write_frontier.frontier().iter().copied());
+                    }
+                }
+                for input in &collection.compute_dependencies {
+                    if let Ok(input) = compute.collection(*input) {
+                        input_frontier.extend(input.write_frontier.frontier().iter().copied());
+                    }
+                }
+                let since = collection.read_capabilities.frontier();
+                let upper = collection.write_frontier.frontier();
+                let replica_frontiers = match compute.replica_write_frontiers(id) {
+                    Ok(replica_frontiers) => replica_frontiers,
+                    Err(_) => continue,
+                };
+                for (replica, replica_upper) in replica_frontiers {
+                    let replica = match instance.config {
+                        InstanceConfig::Local => None,
+                        InstanceConfig::Remote { .. } | InstanceConfig::Managed { .. } => {
+                            Some(replica.to_string())
+                        }
+                    };
+                    let hydrated = replica_upper.is_empty()
+                        || !PartialOrder::less_equal(&replica_upper, &since);
+                    frontiers.insert(
+                        (id, instance.id, replica),
+                        ReplicaFrontier {
+                            hydrated,
+                            serving: hydrated && PartialOrder::less_equal(&upper, &replica_upper),
+                            time: replica_upper.get(0).copied(),
+                            input_time: input_frontier.get(0).copied(),
+                        },
+                    );
+                }
+            }
+        }
+        if frontiers == self.reported_replica_frontiers {
+            return;
+        }
+
+        let table_id = self
+            .catalog
+            .resolve_builtin_table(&MZ_CLUSTER_REPLICA_FRONTIERS);
+        let pack = |(id, instance_id, replica): &ReplicaFrontierKey,
+                    frontier: &ReplicaFrontier,
+                    diff| BuiltinTableUpdate {
+            id: table_id,
+            row: Row::pack_slice(&[
+                Datum::String(&id.to_string()),
+                Datum::Int64(*instance_id),
+                Datum::from(replica.as_deref()),
+                Datum::from(frontier.hydrated),
+                Datum::from(frontier.serving),
+                time_datum(frontier.time),
+                time_datum(frontier.input_time),
+            ]),
+            diff,
+        };
+        let mut updates = vec![];
+        for (key, frontier) in &self.reported_replica_frontiers {
+            if frontiers.get(key) != Some(frontier) {
+                updates.push(pack(key, frontier, -1));
+            }
+        }
+        for (key, frontier) in &frontiers {
+            if self.reported_replica_frontiers.get(key) != Some(frontier) {
+                updates.push(pack(key, frontier, 1));
+            }
+        }
+        self.reported_replica_frontiers = frontiers;
+        self.send_builtin_table_updates(updates).await;
+    }
+}
+
+fn time_datum(time: Option<Timestamp>) -> Datum<'static> {
+    Datum::from(time.map(|time| i64::try_from(time).unwrap_or(i64::MAX)))
+}
@@ -0,0 +1,120 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Tracking of how indexes are used, as reported in `mz_index_usage`.
+//!
+//! The coordinator records usage as dataflows are created and dropped and as
+//! peeks are served, and periodically brings `mz_index_usage` up to date, so
+//! that frequent peeks do not each write to the table.
+
+use mz_dataflow_types::DataflowDesc;
+use mz_expr::GlobalId;
+use mz_ore::now::{to_datetime, EpochMillis};
+use mz_repr::{Datum, Row};
+
+use crate::catalog::builtin::MZ_INDEX_USAGE;
+use crate::catalog::BuiltinTableUpdate;
+use crate::coord::Coordinator;
+
+/// How an index is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// The name of the dataflow that maintains the index, if it is
+    /// maintained by a dataflow.
+    dataflow_name: Option<String>,
+    /// The number of dataflow exports whose dataflows import the index.
+    dataflow_imports: i64,
+    /// The time at which the index last served a fast-path peek.
+    last_fast_path_peek: Option<EpochMillis>,
+}
+
+impl Coordinator {
+    /// Records that `dataflow` has been created.
+    pub(super) fn record_dataflow_index_usage(&mut self, dataflow: &DataflowDesc) {
+        for id in dataflow.index_exports.keys() {
+            // The name matches the one the compute layer gives the dataflow's
+            // root operator.
+            self.index_usage.entry(*id).or_default().dataflow_name =
+                Some(format!("Dataflow: {}", dataflow.debug_name));
+        }
+        let imports: Vec<_> = dataflow.index_imports.keys().copied().collect();
+        for export_id in dataflow.export_ids() {
+            // A dataflow may be recreated under the same ID, e.g., when an
+            // index is re-enabled, in which case its previous imports are
+            // superseded.
+            self.forget_dataflow_index_imports(export_id);
+            for id in &imports {
+                self.index_usage.entry(*id).or_default().dataflow_imports += 1;
+            }
+            self.dataflow_index_imports
+                .insert(export_id, imports.clone());
+        }
+    }
+
+    /// Records that the dataflow export `id` has been dropped.
+    ///
+    /// If `id` is an index, its own usage is forgotten as well.
+    pub(super) fn record_dataflow_export_dropped(&mut self, id: GlobalId) {
+        self.forget_dataflow_index_imports(id);
+        self.index_usage.remove(&id);
+    }
+
+    /// Records that the index `id` served a fast-path peek.
+    pub(super) fn record_fast_path_peek(&mut self, id: GlobalId) {
+        let now = self.now();
+        self.index_usage.entry(id).or_default().last_fast_path_peek = Some(now);
+    }
+
+    /// Brings `mz_index_usage` up to date with the usage recorded since it was
+    /// last updated.
+    pub(super) async fn report_index_usage(&mut self) {
+        if self.index_usage == self.reported_index_usage {
+            return;
+        }
+        let table_id = self.catalog.resolve_builtin_table(&MZ_INDEX_USAGE);
+        let pack = |id: &GlobalId, usage: &IndexUsage, diff| BuiltinTableUpdate {
+            id: table_id,
+            row: Row::pack_slice(&[
+                Datum::String(&id.to_string()),
+                Datum::from(usage.dataflow_name.as_deref()),
+                Datum::Int64(usage.dataflow_imports),
+                match usage.last_fast_path_peek {
+                    Some(time) => Datum::TimestampTz(to_datetime(time)),
+                    None => Datum::Null,
+                },
+            ]),
+            diff,
+        };
+        let mut updates = vec![];
+        for (id, usage) in &self.reported_index_usage {
+            if self.index_usage.get(id) != Some(usage) {
+                updates.push(pack(id, usage, -1));
+            }
+        }
+        for (id, usage) in &self.index_usage {
+            if self.reported_index_usage.get(id) != Some(usage) {
+                updates.push(pack(id, usage, 1));
+            }
+        }
+        self.reported_index_usage = self.index_usage.clone();
+        self.send_builtin_table_updates(updates).await;
+    }
+
+    /// Removes the imports of the dataflow export `id` from the usage of the
+    /// indexes it imports.
+    fn forget_dataflow_index_imports(&mut self, id: GlobalId) {
+        if let Some(imports) = self.dataflow_index_imports.remove(&id) {
+            for import in imports {
+                if let Some(usage) = self.index_usage.get_mut(&import) {
+                    usage.dataflow_imports -= 1;
+                }
+            }
+        }
+    }
+}
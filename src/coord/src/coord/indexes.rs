@@ -17,6 +17,7 @@ use mz_transform::IndexOracle;
 
 use crate::catalog::{CatalogItem, CatalogState, Index};
 use crate::coord::dataflow_builder::DataflowBuilder;
+use crate::coord::statistics::Statistics;
 use crate::coord::{CollectionIdBundle, CoordTimestamp, Coordinator};
 
 /// Answers questions about the indexes available on a particular compute
@@ -25,6 +26,7 @@ use crate::coord::{CollectionIdBundle, CoordTimestamp, Coordinator};
 pub struct ComputeInstanceIndexOracle<'a, T> {
     catalog: &'a CatalogState,
     compute: ComputeController<'a, T>,
+    statistics: &'a Statistics,
 }
 
 impl Coordinator {
@@ -36,6 +38,7 @@ impl Coordinator {
         ComputeInstanceIndexOracle {
             catalog: self.catalog.state(),
             compute: self.dataflow_client.compute(instance).unwrap(),
+            statistics: &self.statistics,
         }
     }
 }
@@ -47,6 +50,7 @@ impl<T: Copy> DataflowBuilder<'_, T> {
         ComputeInstanceIndexOracle {
             catalog: self.catalog,
             compute: self.compute,
+            statistics: self.statistics,
         }
     }
 }
@@ -105,4 +109,8 @@ impl<T: CoordTimestamp> IndexOracle for ComputeInstanceIndexOracle<'_, T> {
                 .map(|(_idx_id, idx)| idx.keys.as_slice()),
         )
     }
+
+    fn cardinality(&self, id: GlobalId) -> Option<u64> {
+        self.statistics.cardinality(id)
+    }
 }
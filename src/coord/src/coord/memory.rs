@@ -0,0 +1,339 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Accounting of the memory that the dataflows of each cluster use, as
+//! reported in `mz_dataflow_memory`, and admission control of the queries that
+//! would build new dataflows on a cluster that uses too much of it.
+//!
+//! The coordinator periodically reads, from the introspection sources of each
+//! cluster, how many records the arrangements of each dataflow hold. The
+//! arrangements are where nearly all of the memory of a dataflow goes, but
+//! they report how many records they hold rather than how large the records
+//! are, so the memory of a dataflow is estimated from a nominal record size.
+//!
+//! When the estimated memory of a cluster exceeds `cluster_memory_limit`,
+//! peeks that would build a dataflow over user objects on the cluster are
+//! rejected, rather than risking that the dataflow runs the cluster's replicas
+//! out of memory. Peeks that read from existing indexes, or only from the
+//! system catalog, are still admitted, so that the problem can be diagnosed and
+//! the offending indexes found and dropped.
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+
+use derivative::Derivative;
+use tracing::warn;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::logging::{DifferentialLog, LogVariant, TimelyLog};
+use mz_dataflow_types::PeekResponse;
+use mz_ore::cast::CastFrom;
+use mz_ore::task;
+use mz_repr::{Datum, Row};
+
+use crate::catalog::builtin::MZ_DATAFLOW_MEMORY;
+use crate::catalog::{BuiltinTableUpdate, SYSTEM_CONN_ID};
+use crate::coord::id_bundle::CollectionIdBundle;
+use crate::coord::{Coordinator, Message};
+use crate::error::CoordError;
+
+/// The number of bytes that each arranged record is assumed to occupy.
+const ESTIMATED_RECORD_SIZE: u64 = 64;
+
+/// The introspection sources from which the records arranged by each dataflow
+/// are read.
+const LOGS: [LogVariant; 3] = [
+    LogVariant::Timely(TimelyLog::Operates),
+    LogVariant::Timely(TimelyLog::Addresses),
+    LogVariant::Differential(DifferentialLog::ArrangementRecords),
+];
+
+/// The number of records arranged by each dataflow on each cluster, and the
+/// clusters whose dataflows are being measured.
+#[derive(Debug, Default)]
+pub struct MemoryUsage {
+    /// The number of records arranged by each dataflow, by dataflow name, on
+    /// each cluster, as reflected in `mz_dataflow_memory`.
+    clusters: HashMap<ComputeInstanceId, HashMap<String, u64>>,
+    in_flight: HashSet<ComputeInstanceId>,
+}
+
+impl MemoryUsage {
+    /// Returns the estimated number of bytes that the dataflows on
+    /// `compute_instance` use, as of their most recent measurement.
+    pub fn cluster_size(&self, compute_instance: ComputeInstanceId) -> u64 {
+        self.clusters
+            .get(&compute_instance)
+            .map_or(0, |dataflows| estimated_size(dataflows.values().sum()))
+    }
+}
+
+/// The outcome of measuring the dataflows on a cluster.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct MemoryCollected {
+    compute_instance: ComputeInstanceId,
+    /// The number of records arranged by each dataflow, or the reason they
+    /// could not be measured.
+    #[derivative(Debug = "ignore")]
+    result: Result<HashMap<String, u64>, String>,
+}
+
+impl Coordinator {
+    /// Starts measuring the dataflows on every cluster whose dataflows are not
+    /// already being measured, and forgets the measurements of clusters that
+    /// have been dropped.
+    pub(super) async fn collect_memory_usage(&mut self) {
+        let dropped: Vec<_> = self
+            .memory_usage
+            .clusters
+            .keys()
+            .filter(|id| self.catalog.compute_instances().all(|i| i.id != **id))
+            .copied()
+            .collect();
+        let mut updates = vec![];
+        for compute_instance in dropped {
+            let dataflows = self
+                .memory_usage
+                .clusters
+                .remove(&compute_instance)
+                .unwrap();
+            for (name, records) in &dataflows {
+                updates.push(self.pack_memory_update(compute_instance, name, *records, -1));
+            }
+        }
+        if !updates.is_empty() {
+            self.send_builtin_table_updates(updates).await;
+        }
+
+        // Clusters without introspection sources cannot be measured, and so
+        // are never subject to the memory limit.
+        let clusters: Vec<_> = self
+            .catalog
+            .compute_instances()
+            .filter(|instance| !self.memory_usage.in_flight.contains(&instance.id))
+            .filter_map(|instance| {
+                let logging = instance.logging.as_ref()?;
+                let ids: Option<Vec<_>> = LOGS
+                    .iter()
+                    .map(|variant| logging.active_logs.get(variant).copied())
+                    .collect();
+                Some((instance.id, ids?))
+            })
+            .collect();
+        for (compute_instance, ids) in clusters {
+            if self.dataflow_client.compute(compute_instance).is_none() {
+                continue;
+            }
+            let mut id_bundle = CollectionIdBundle::default();
+            id_bundle.compute_ids.extend(ids.iter().copied());
+            // Read at the latest time at which the introspection sources can
+            // be read without waiting.
+            let since = self.least_valid_read(&id_bundle, compute_instance);
+            let timestamp = since.elements().iter().copied().fold(
+                self.largest_closed_timestamp(&id_bundle, compute_instance),
+                std::cmp::max,
+            );
+            let mut receivers = vec![];
+            for (id, variant) in ids.into_iter().zip(LOGS.iter()) {
+                let rx = self
+                    .peek_introspection(id, variant, timestamp, SYSTEM_CONN_ID, compute_instance)
+                    .await;
+                receivers.push(rx);
+            }
+
+            self.memory_usage.in_flight.insert(compute_instance);
+            let internal_cmd_tx = self.internal_cmd_tx.clone();
+            task::spawn(
+                || format!("collect_memory_usage:{compute_instance}"),
+                async move {
+                    let mut logs = vec![];
+                    let mut result = Ok(());
+                    for mut rx in receivers {
+                        match rx.recv().await {
+                            Some(PeekResponse::Rows(rows)) => logs.push(rows),
+                            Some(PeekResponse::Error(e)) => {
+                                result = Err(e);
+                                break;
+                            }
+                            Some(PeekResponse::Canceled) | None => {
+                                result = Err("canceled".into());
+                                break;
+                            }
+                        }
+                    }
+                    let result = result.map(|()| records_per_dataflow(logs));
+                    // If sending fails, the main thread has shutdown.
+                    let _ = internal_cmd_tx.send(Message::MemoryCollected(MemoryCollected {
+                        compute_instance,
+                        result,
+                    }));
+                },
+            );
+        }
+    }
+
+    /// Records the measurements of the dataflows on a cluster in
+    /// `mz_dataflow_memory`.
+    pub(super) async fn message_memory_collected(
+        &mut self,
+        MemoryCollected {
+            compute_instance,
+            result,
+        }: MemoryCollected,
+    ) {
+        self.memory_usage.in_flight.remove(&compute_instance);
+        // The cluster may have been dropped while its dataflows were
+        // measured.
+        if self
+            .catalog
+            .compute_instances()
+            .all(|instance| instance.id != compute_instance)
+        {
+            return;
+        }
+        let dataflows = match result {
+            Ok(dataflows) => dataflows,
+            Err(e) => {
+                warn!(
+                    "failed to measure the dataflows on cluster {}: {}",
+                    compute_instance, e
+                );
+                return;
+            }
+        };
+        let old = self
+            .memory_usage
+            .clusters
+            .remove(&compute_instance)
+            .unwrap_or_default();
+        let mut updates = vec![];
+        for (name, records) in &old {
+            if dataflows.get(name) != Some(records) {
+                updates.push(self.pack_memory_update(compute_instance, name, *records, -1));
+            }
+        }
+        for (name, records) in &dataflows {
+            if old.get(name) != Some(records) {
+                updates.push(self.pack_memory_update(compute_instance, name, *records, 1));
+            }
+        }
+        self.memory_usage
+            .clusters
+            .insert(compute_instance, dataflows);
+        if !updates.is_empty() {
+            self.send_builtin_table_updates(updates).await;
+        }
+    }
+
+    /// Returns an error if the dataflows on the cluster `compute_instance`,
+    /// named `cluster`, are estimated to use more memory than
+    /// `cluster_memory_limit` permits.
+    pub(super) fn check_cluster_memory(
+        &self,
+        compute_instance: ComputeInstanceId,
+        cluster: &str,
+    ) -> Result<(), CoordError> {
+        let limit = match self.system_vars.cluster_memory_limit() {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let usage = self.memory_usage.cluster_size(compute_instance);
+        if usage > u64::cast_from(limit) {
+            return Err(CoordError::ClusterMemoryExceeded {
+                cluster: cluster.into(),
+                usage,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    fn pack_memory_update(
+        &self,
+        compute_instance: ComputeInstanceId,
+        name: &str,
+        records: u64,
+        diff: i64,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.catalog.resolve_builtin_table(&MZ_DATAFLOW_MEMORY),
+            row: Row::pack_slice(&[
+                Datum::Int64(compute_instance),
+                Datum::String(name),
+                Datum::Int64(i64::try_from(records).unwrap_or(i64::MAX)),
+                Datum::Int64(i64::try_from(estimated_size(records)).unwrap_or(i64::MAX)),
+            ]),
+            diff,
+        }
+    }
+}
+
+/// Returns the estimated number of bytes that `records` arranged records
+/// occupy.
+fn estimated_size(records: u64) -> u64 {
+    records.saturating_mul(ESTIMATED_RECORD_SIZE)
+}
+
+/// Sums the records arranged by the operators of each dataflow, across
+/// workers, out of the contents of the introspection sources in [`LOGS`].
+///
+/// Returns the number of records by dataflow name. Dataflows that arrange no
+/// records are omitted.
+fn records_per_dataflow(logs: Vec<Vec<(Row, NonZeroUsize)>>) -> HashMap<String, u64> {
+    let mut logs = logs.into_iter();
+    let mut next_log = || logs.next().unwrap_or_default();
+    let (operates, addresses, arrangement_records) = (next_log(), next_log(), next_log());
+
+    let mut address_by_id = HashMap::new();
+    for (row, _) in &addresses {
+        let datums = row.unpack();
+        let address: Vec<i64> = datums[2]
+            .unwrap_list()
+            .iter()
+            .map(|d| d.unwrap_int64())
+            .collect();
+        address_by_id.insert(
+            (datums[0].unwrap_int64(), datums[1].unwrap_int64()),
+            address,
+        );
+    }
+
+    // The first component of the address of an operator identifies the
+    // dataflow that contains it on its worker. Each dataflow's root operator
+    // is named after the dataflow.
+    let mut names = HashMap::new();
+    for (row, _) in &operates {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        let name = match datums[2].unwrap_str().strip_prefix("Dataflow: ") {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some([dataflow]) = address_by_id.get(&key).map(|address| &address[..]) {
+            names.insert((key.1, *dataflow), name.to_string());
+        }
+    }
+
+    // The number of records in the arrangements that an operator maintains
+    // is recorded in the multiplicity of its records.
+    let mut records = HashMap::new();
+    for (row, count) in &arrangement_records {
+        let datums = row.unpack();
+        let key = (datums[0].unwrap_int64(), datums[1].unwrap_int64());
+        let name = address_by_id
+            .get(&key)
+            .and_then(|address| address.first())
+            .and_then(|dataflow| names.get(&(key.1, *dataflow)));
+        if let Some(name) = name {
+            *records.entry(name.clone()).or_insert(0) += u64::cast_from(count.get());
+        }
+    }
+    records
+}
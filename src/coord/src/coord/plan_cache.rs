@@ -0,0 +1,297 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A cache of the optimized plans of prepared statements.
+//!
+//! Clients that use the extended protocol, like dashboards that refresh on a
+//! timer, tend to execute the same prepared statement with the same
+//! parameters over and over. Each execution plans the statement anew, which
+//! is cheap, and then optimizes the plan, which is not. The cache remembers
+//! the optimized plan of each statement, so that executing the same
+//! statement again only pays for the planning.
+//!
+//! Entries are keyed by the text of the statement and the types and values of
+//! its parameters. Because the same text can resolve to different items in
+//! different sessions, an entry also records the unoptimized plan it was
+//! built from, and is only used for an identical plan. The coordinator
+//! invalidates the entries that depend on an item when the item is dropped or
+//! altered, and the least recently used entry is evicted when the cache is
+//! full.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use mz_expr::{CollectionPlan, GlobalId, MirRelationExpr};
+use mz_ore::cast::CastFrom;
+use mz_ore::metric;
+use mz_ore::metrics::{IntCounter, MetricsRegistry, UIntGauge};
+use mz_repr::{Row, ScalarType};
+
+/// The number of entries beyond which the least recently used entry is
+/// evicted.
+const PLAN_CACHE_CAPACITY: usize = 1024;
+
+/// The statement whose optimized plan is cached.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlanCacheKey {
+    /// The SQL text of the statement.
+    pub sql: String,
+    /// The types of the statement's parameters.
+    pub param_types: Vec<ScalarType>,
+    /// The values bound to the statement's parameters.
+    pub params: Row,
+}
+
+/// An optimized plan.
+#[derive(Debug)]
+struct CachedPlan {
+    /// The unoptimized plan.
+    expr: MirRelationExpr,
+    /// The optimized plan.
+    optimized: MirRelationExpr,
+    /// The IDs of the items on which the plan depends.
+    depends_on: BTreeSet<GlobalId>,
+    /// The tick at which the entry was last used.
+    last_used: u64,
+}
+
+/// A cache of the optimized plans of statements, by [`PlanCacheKey`].
+#[derive(Debug)]
+pub struct PlanCache {
+    entries: HashMap<Rc<PlanCacheKey>, CachedPlan>,
+    /// The key of each entry, by the tick at which the entry was last used.
+    recency: BTreeMap<u64, Rc<PlanCacheKey>>,
+    /// The keys of the entries that depend on each item.
+    dependents: HashMap<GlobalId, HashSet<Rc<PlanCacheKey>>>,
+    /// The tick to assign to the next use of an entry.
+    tick: u64,
+    capacity: usize,
+    metrics: PlanCacheMetrics,
+}
+
+impl PlanCache {
+    /// Creates an empty cache whose metrics are registered with `registry`.
+    pub fn new(registry: &MetricsRegistry) -> PlanCache {
+        PlanCache::with_capacity(registry, PLAN_CACHE_CAPACITY)
+    }
+
+    fn with_capacity(registry: &MetricsRegistry, capacity: usize) -> PlanCache {
+        PlanCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            dependents: HashMap::new(),
+            tick: 0,
+            capacity,
+            metrics: PlanCacheMetrics::register_with(registry),
+        }
+    }
+
+    /// Returns the optimized plan of the statement `key`, if it is cached for
+    /// the unoptimized plan `expr`.
+    pub fn get(&mut self, key: &PlanCacheKey, expr: &MirRelationExpr) -> Option<MirRelationExpr> {
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            Some(plan) if plan.expr == *expr => {
+                let key = self
+                    .recency
+                    .remove(&plan.last_used)
+                    .expect("cached plans are tracked by recency");
+                self.recency.insert(tick, key);
+                plan.last_used = tick;
+                self.tick += 1;
+                self.metrics.hits.inc();
+                Some(plan.optimized.clone())
+            }
+            _ => {
+                self.metrics.misses.inc();
+                None
+            }
+        }
+    }
+
+    /// Caches `optimized` as the optimized plan of the statement `key`, whose
+    /// unoptimized plan is `expr`.
+    pub fn insert(&mut self, key: PlanCacheKey, expr: MirRelationExpr, optimized: MirRelationExpr) {
+        self.remove(&key);
+        if self.entries.len() >= self.capacity {
+            let oldest = self.recency.values().next().cloned();
+            if let Some(oldest) = oldest {
+                self.remove(&oldest);
+                self.metrics.evictions.inc();
+            }
+        }
+        let key = Rc::new(key);
+        let depends_on = expr.depends_on();
+        for id in &depends_on {
+            self.dependents
+                .entry(*id)
+                .or_default()
+                .insert(Rc::clone(&key));
+        }
+        self.recency.insert(self.tick, Rc::clone(&key));
+        self.entries.insert(
+            key,
+            CachedPlan {
+                expr,
+                optimized,
+                depends_on,
+                last_used: self.tick,
+            },
+        );
+        self.tick += 1;
+        self.metrics.entries.set(u64::cast_from(self.entries.len()));
+    }
+
+    /// Invalidates the entries whose plans depend on the item `id`.
+    pub fn invalidate(&mut self, id: GlobalId) {
+        for key in self.dependents.remove(&id).unwrap_or_default() {
+            self.remove(&key);
+            self.metrics.invalidations.inc();
+        }
+    }
+
+    /// Removes the entry for the statement `key`, if there is one.
+    fn remove(&mut self, key: &PlanCacheKey) {
+        if let Some((key, plan)) = self.entries.remove_entry(key) {
+            self.recency.remove(&plan.last_used);
+            for id in &plan.depends_on {
+                if let Some(dependents) = self.dependents.get_mut(id) {
+                    dependents.remove(&key);
+                    if dependents.is_empty() {
+                        self.dependents.remove(id);
+                    }
+                }
+            }
+            self.metrics.entries.set(u64::cast_from(self.entries.len()));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PlanCacheMetrics {
+    hits: IntCounter,
+    misses: IntCounter,
+    invalidations: IntCounter,
+    evictions: IntCounter,
+    entries: UIntGauge,
+}
+
+impl PlanCacheMetrics {
+    fn register_with(registry: &MetricsRegistry) -> PlanCacheMetrics {
+        PlanCacheMetrics {
+            hits: registry.register(metric!(
+                name: "mz_plan_cache_hits_total",
+                help: "The number of times the optimized plan of a query was found in the plan cache.",
+            )),
+            misses: registry.register(metric!(
+                name: "mz_plan_cache_misses_total",
+                help: "The number of times the optimized plan of a query was not found in the plan cache.",
+            )),
+            invalidations: registry.register(metric!(
+                name: "mz_plan_cache_invalidations_total",
+                help: "The number of entries removed from the plan cache because the items they depend on changed.",
+            )),
+            evictions: registry.register(metric!(
+                name: "mz_plan_cache_evictions_total",
+                help: "The number of entries removed from the plan cache to make room for other entries.",
+            )),
+            entries: registry.register(metric!(
+                name: "mz_plan_cache_entries",
+                help: "The number of entries in the plan cache.",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mz_repr::RelationType;
+
+    use super::*;
+
+    fn key(sql: &str) -> PlanCacheKey {
+        PlanCacheKey {
+            sql: sql.into(),
+            param_types: vec![],
+            params: Row::default(),
+        }
+    }
+
+    fn get(id: u64) -> MirRelationExpr {
+        MirRelationExpr::global_get(GlobalId::User(id), RelationType::empty())
+    }
+
+    fn optimized(id: u64) -> MirRelationExpr {
+        get(id).distinct()
+    }
+
+    #[test]
+    fn test_hit() {
+        let mut cache = PlanCache::new(&MetricsRegistry::new());
+        assert_eq!(cache.get(&key("SELECT 1"), &get(1)), None);
+
+        cache.insert(key("SELECT 1"), get(1), optimized(1));
+        assert_eq!(cache.get(&key("SELECT 1"), &get(1)), Some(optimized(1)));
+        // The same text with different parameters, or that plans differently,
+        // e.g. in a session with another search path, misses.
+        let mut other_params = key("SELECT 1");
+        other_params.param_types = vec![ScalarType::Int32];
+        other_params.params = Row::pack_slice(&[mz_repr::Datum::Int32(1)]);
+        assert_eq!(cache.get(&other_params, &get(1)), None);
+        assert_eq!(cache.get(&key("SELECT 1"), &get(2)), None);
+
+        // Re-inserting the statement replaces its entry.
+        cache.insert(key("SELECT 1"), get(2), optimized(2));
+        assert_eq!(cache.get(&key("SELECT 1"), &get(1)), None);
+        assert_eq!(cache.get(&key("SELECT 1"), &get(2)), Some(optimized(2)));
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.metrics.hits.get(), 2);
+        assert_eq!(cache.metrics.misses.get(), 4);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let mut cache = PlanCache::new(&MetricsRegistry::new());
+        cache.insert(key("a"), get(1), optimized(1));
+        cache.insert(key("b"), get(2), optimized(2));
+        cache.insert(key("c"), get(1).union(get(2)), optimized(3));
+
+        cache.invalidate(GlobalId::User(1));
+        assert_eq!(cache.get(&key("a"), &get(1)), None);
+        assert_eq!(cache.get(&key("b"), &get(2)), Some(optimized(2)));
+        assert_eq!(cache.get(&key("c"), &get(1).union(get(2))), None);
+        assert_eq!(cache.metrics.invalidations.get(), 2);
+
+        // Items that no entry depends on are not tracked.
+        cache.invalidate(GlobalId::User(3));
+        assert_eq!(cache.metrics.invalidations.get(), 2);
+        assert_eq!(
+            cache.dependents.keys().collect::<Vec<_>>(),
+            [&GlobalId::User(2)]
+        );
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut cache = PlanCache::with_capacity(&MetricsRegistry::new(), 2);
+        cache.insert(key("a"), get(1), optimized(1));
+        cache.insert(key("b"), get(2), optimized(2));
+        // Using `a` makes `b` the least recently used entry.
+        assert!(cache.get(&key("a"), &get(1)).is_some());
+
+        cache.insert(key("c"), get(3), optimized(3));
+        assert_eq!(cache.get(&key("b"), &get(2)), None);
+        assert!(cache.get(&key("a"), &get(1)).is_some());
+        assert!(cache.get(&key("c"), &get(3)).is_some());
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.recency.len(), 2);
+        assert!(!cache.dependents.contains_key(&GlobalId::User(2)));
+        assert_eq!(cache.metrics.evictions.get(), 1);
+    }
+}
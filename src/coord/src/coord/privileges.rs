@@ -0,0 +1,378 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Checks of the privileges that roles hold on catalog objects.
+//!
+//! Superusers hold every privilege, and a role holds every privilege on the
+//! objects that it owns. Other privileges are granted with `GRANT`. Items in
+//! the system schemas can be read by every role, except for the relations in
+//! `mz_internal`, which expose the activity of every session and can be read
+//! only by superusers.
+
+use mz_expr::{CollectionPlan, GlobalId};
+use mz_ore::str::StrExt;
+use mz_sql::ast::Privilege;
+use mz_sql::catalog::CatalogItem as _;
+use mz_sql::names::{QualifiedObjectName, ResolvedDatabaseSpecifier, SchemaSpecifier};
+use mz_sql::plan::{AdminOperation, AdminPlan, MutationKind, Plan, PrivilegeObjectId, TailFrom};
+
+use crate::catalog::builtin::MZ_INTERNAL_SCHEMA;
+use crate::catalog::storage::OwnedObjectId;
+use crate::catalog::{CatalogItem, Role};
+use crate::coord::Coordinator;
+use crate::error::CoordError;
+use crate::session::Session;
+
+impl Coordinator {
+    /// Verifies that the session's role holds the privileges that `plan`
+    /// requires.
+    pub(super) fn check_plan_privileges(
+        &self,
+        session: &Session,
+        plan: &Plan,
+    ) -> Result<(), CoordError> {
+        // Sessions whose role does not exist are not restricted, as in
+        // `check_role_restrictions`.
+        let role = match self.catalog.try_get_role(session.user()) {
+            Some(role) if !role.restrictions.superuser => role,
+            _ => return Ok(()),
+        };
+        match plan {
+            // As in PostgreSQL, roles may change their own passwords.
+            Plan::AlterRole(plan)
+                if plan.id == role.id && plan.superuser.is_none() && plan.login.is_none() =>
+            {
+                Ok(())
+            }
+            // Roles may cancel their own queries. Whether the query belongs
+            // to the role is checked when the query is canceled.
+            Plan::Admin(AdminPlan {
+                operation: AdminOperation::CancelQuery { .. },
+                ..
+            }) => Ok(()),
+            Plan::CreateDatabase(_)
+            | Plan::CreateRole(_)
+            | Plan::AlterRole(_)
+            | Plan::CreateComputeInstance(_)
+            | Plan::DropRoles(_)
+            | Plan::DropComputeInstances(_)
+            | Plan::AlterRoleSet(_)
+            | Plan::AlterSystemSet(_)
+            | Plan::Undrop(_)
+            | Plan::Admin(_) => Err(CoordError::PermissionDenied {
+                role: role.name.clone(),
+                privilege: "superuser privileges".into(),
+            }),
+            Plan::CreateSchema(plan) => match &plan.database_spec {
+                ResolvedDatabaseSpecifier::Id(id) => {
+                    self.require_privilege(role, OwnedObjectId::Database(*id), Privilege::Create)
+                }
+                ResolvedDatabaseSpecifier::Ambient => Ok(()),
+            },
+            Plan::CreateSource(plan) => {
+                self.require_create(role, &plan.name)?;
+                self.require_secret_use(role, plan.source.depends_on.iter().copied())
+            }
+            Plan::CreateSecret(plan) => self.require_create(role, &plan.name),
+            Plan::AlterSecret(plan) => self.require_owner(role, OwnedObjectId::Item(plan.id)),
+            Plan::CreateType(plan) => self.require_create(role, &plan.name),
+            Plan::CreateTable(plan) => self.require_create(role, &plan.name),
+            Plan::CreatePublication(plan) => {
+                self.require_create(role, &plan.name)?;
+                self.require_select(role, plan.publication.views.iter().copied())
+            }
+            // The task's statement is checked against the privileges of the
+            // task's owner each time the task runs.
+            Plan::CreateTask(plan) => self.require_create(role, &plan.name),
+            Plan::CreateSink(plan) => {
+                self.require_create(role, &plan.name)?;
+                self.require_select(role, [plan.sink.from])?;
+                self.require_secret_use(role, plan.sink.depends_on.iter().copied())
+            }
+            Plan::CreateView(plan) => {
+                self.require_create(role, &plan.name)?;
+                match plan.replace {
+                    Some(id) => self.require_owner(role, OwnedObjectId::Item(id)),
+                    None => Ok(()),
+                }
+            }
+            Plan::CreateViews(plan) => {
+                for (name, _) in &plan.views {
+                    self.require_create(role, name)?;
+                }
+                Ok(())
+            }
+            Plan::CreateIndex(plan) => {
+                self.require_create(role, &plan.name)?;
+                // As in PostgreSQL, only the owner of a relation may index it.
+                self.require_owner(role, OwnedObjectId::Item(plan.index.on))
+            }
+            Plan::DropDatabase(plan) => match plan.id {
+                Some(id) => self.require_owner(role, OwnedObjectId::Database(id)),
+                None => Ok(()),
+            },
+            Plan::AlterDatabaseRename(plan) => match plan.id {
+                Some(id) => self.require_owner(role, OwnedObjectId::Database(id)),
+                None => Ok(()),
+            },
+            Plan::DropSchema(plan) => match plan.id {
+                Some((_, id)) => self.require_owner(role, OwnedObjectId::Schema(id)),
+                None => Ok(()),
+            },
+            Plan::AlterSchemaRename(plan) => match plan.id {
+                Some((_, id)) => self.require_owner(role, OwnedObjectId::Schema(id)),
+                None => Ok(()),
+            },
+            Plan::DropItems(plan) => {
+                for id in &plan.items {
+                    self.require_owner(role, OwnedObjectId::Item(*id))?;
+                }
+                Ok(())
+            }
+            Plan::AlterComputeInstance(plan) => {
+                self.require_owner(role, OwnedObjectId::ComputeInstance(plan.id))
+            }
+            Plan::AlterIndexSetOptions(plan) => {
+                self.require_owner(role, OwnedObjectId::Item(plan.id))
+            }
+            Plan::AlterIndexResetOptions(plan) => {
+                self.require_owner(role, OwnedObjectId::Item(plan.id))
+            }
+            Plan::AlterIndexEnable(plan) => self.require_owner(role, OwnedObjectId::Item(plan.id)),
+            Plan::AlterItemRename(plan) => self.require_owner(role, OwnedObjectId::Item(plan.id)),
+            Plan::Comment(plan) => self.require_owner(role, OwnedObjectId::Item(plan.id)),
+            Plan::GrantPrivileges(plan) | Plan::RevokePrivileges(plan) => {
+                self.require_owner(role, privilege_object_id(plan.object))
+            }
+            Plan::Peek(plan) => self.require_select(role, plan.source.depends_on()),
+            // As in PostgreSQL, explaining a query requires the privileges
+            // to run it, and `EXPLAIN ANALYZE` does run it.
+            Plan::Explain(plan) => self.require_select(role, plan.depends_on.iter().copied()),
+            Plan::Tail(plan) => match &plan.from {
+                TailFrom::Id(id) => self.require_select(role, [*id]),
+                TailFrom::Query { depends_on, .. } => {
+                    self.require_select(role, depends_on.iter().copied())
+                }
+            },
+            Plan::CopyFrom(plan) => {
+                self.require_privilege(role, OwnedObjectId::Item(plan.id), Privilege::Insert)
+            }
+            Plan::Insert(plan) => {
+                self.require_privilege(role, OwnedObjectId::Item(plan.id), Privilege::Insert)?;
+                self.require_select(
+                    role,
+                    plan.values
+                        .depends_on()
+                        .into_iter()
+                        .filter(|id| *id != plan.id),
+                )
+            }
+            Plan::ReadThenWrite(plan) => {
+                let privilege = match plan.kind {
+                    MutationKind::Insert => Privilege::Insert,
+                    MutationKind::Update => Privilege::Update,
+                    MutationKind::Delete => Privilege::Delete,
+                };
+                self.require_privilege(role, OwnedObjectId::Item(plan.id), privilege)?;
+                self.require_select(
+                    role,
+                    plan.selection
+                        .depends_on()
+                        .into_iter()
+                        .filter(|id| *id != plan.id),
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that the session's role may create an object named `name`.
+    pub(super) fn check_create_privileges(
+        &self,
+        session: &Session,
+        name: &QualifiedObjectName,
+    ) -> Result<(), CoordError> {
+        match self.catalog.try_get_role(session.user()) {
+            Some(role) if !role.restrictions.superuser => self.require_create(role, name),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that the session's role may configure a source or sink with
+    /// the secrets among the items identified by `ids`.
+    pub(super) fn check_secret_use<I>(&self, session: &Session, ids: I) -> Result<(), CoordError>
+    where
+        I: IntoIterator<Item = GlobalId>,
+    {
+        match self.catalog.try_get_role(session.user()) {
+            Some(role) if !role.restrictions.superuser => self.require_secret_use(role, ids),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that the session's role is a superuser.
+    pub(super) fn check_superuser(&self, session: &Session) -> Result<(), CoordError> {
+        match self.catalog.try_get_role(session.user()) {
+            Some(role) if !role.restrictions.superuser => Err(CoordError::PermissionDenied {
+                role: role.name.clone(),
+                privilege: "superuser privileges".into(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that the session's role may cancel the queries of the role
+    /// named `user`.
+    pub(super) fn check_cancel_privileges(
+        &self,
+        session: &Session,
+        user: &str,
+    ) -> Result<(), CoordError> {
+        match self.catalog.try_get_role(session.user()) {
+            Some(role) if !role.restrictions.superuser && role.name != user => {
+                Err(CoordError::PermissionDenied {
+                    role: role.name.clone(),
+                    privilege: format!("permission to cancel queries of role {}", user.quoted()),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that `role` may create an object named `name`.
+    fn require_create(&self, role: &Role, name: &QualifiedObjectName) -> Result<(), CoordError> {
+        match (&name.qualifiers.database_spec, &name.qualifiers.schema_spec) {
+            (ResolvedDatabaseSpecifier::Id(_), SchemaSpecifier::Id(id)) => {
+                self.require_privilege(role, OwnedObjectId::Schema(*id), Privilege::Create)
+            }
+            // Every role may create objects in its temporary schema, and the
+            // planner forbids creating objects in the system schemas.
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies that `role` may read from the relations identified by `ids`.
+    ///
+    /// As in PostgreSQL, the relations that a view reads from are read with
+    /// the privileges of the view's owner.
+    fn require_select<I>(&self, role: &Role, ids: I) -> Result<(), CoordError>
+    where
+        I: IntoIterator<Item = GlobalId>,
+    {
+        for id in ids {
+            if id.is_system() {
+                let entry = self.catalog.get_entry(&id);
+                let name = self.catalog.resolve_full_name(entry.name(), None);
+                if name.schema == MZ_INTERNAL_SCHEMA {
+                    return Err(CoordError::PermissionDenied {
+                        role: role.name.clone(),
+                        privilege: format!(
+                            "superuser privileges to read {}",
+                            name.to_string().quoted()
+                        ),
+                    });
+                }
+                continue;
+            }
+            let entry = self.catalog.get_entry(&id);
+            match entry.item() {
+                CatalogItem::Table(_) | CatalogItem::Source(_) => {
+                    self.require_privilege(role, OwnedObjectId::Item(id), Privilege::Select)?
+                }
+                CatalogItem::View(_) => {
+                    self.require_privilege(role, OwnedObjectId::Item(id), Privilege::Select)?;
+                    match self.catalog.try_get_role_by_id(entry.owner_id()) {
+                        Some(owner) if !owner.restrictions.superuser => {
+                            self.require_select(owner, entry.uses().iter().copied())?
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies that `role` may configure a source or sink with the secrets
+    /// among the items identified by `ids`.
+    ///
+    /// A source or sink sends the contents of its secrets to the external
+    /// system that its creator chose, so only the owner of a secret may use
+    /// it.
+    fn require_secret_use<I>(&self, role: &Role, ids: I) -> Result<(), CoordError>
+    where
+        I: IntoIterator<Item = GlobalId>,
+    {
+        for id in ids {
+            if let CatalogItem::Secret(_) = self.catalog.get_entry(&id).item() {
+                self.require_owner(role, OwnedObjectId::Item(id))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies that `role` holds `privilege` on `object`.
+    fn require_privilege(
+        &self,
+        role: &Role,
+        object: OwnedObjectId,
+        privilege: Privilege,
+    ) -> Result<(), CoordError> {
+        if self.catalog.has_privilege(role, object, privilege) {
+            Ok(())
+        } else {
+            Err(CoordError::PermissionDenied {
+                role: role.name.clone(),
+                privilege: format!(
+                    "{} privilege on {}",
+                    privilege,
+                    self.describe_object(object)
+                ),
+            })
+        }
+    }
+
+    /// Verifies that `role` owns `object`.
+    fn require_owner(&self, role: &Role, object: OwnedObjectId) -> Result<(), CoordError> {
+        if self.catalog.is_owner(role, object) {
+            Ok(())
+        } else {
+            Err(CoordError::PermissionDenied {
+                role: role.name.clone(),
+                privilege: format!("ownership of {}", self.describe_object(object)),
+            })
+        }
+    }
+
+    /// Describes `object` by its type and name, for use in error messages.
+    fn describe_object(&self, object: OwnedObjectId) -> String {
+        let object_type = match object {
+            OwnedObjectId::Database(_) => "database".into(),
+            OwnedObjectId::Schema(_) => "schema".into(),
+            OwnedObjectId::ComputeInstance(_) => "cluster".into(),
+            OwnedObjectId::Item(id) => self.catalog.get_entry(&id).item_type().to_string(),
+        };
+        format!(
+            "{} {}",
+            object_type,
+            self.catalog.object_name(object).quoted()
+        )
+    }
+}
+
+/// Converts the planner's identifier for an object on which privileges can be
+/// granted into the catalog's.
+pub(super) fn privilege_object_id(object: PrivilegeObjectId) -> OwnedObjectId {
+    match object {
+        PrivilegeObjectId::Database(id) => OwnedObjectId::Database(id),
+        PrivilegeObjectId::Schema(id) => OwnedObjectId::Schema(id),
+        PrivilegeObjectId::Item(id) => OwnedObjectId::Item(id),
+    }
+}
@@ -31,7 +31,7 @@ use crate::coord::{LoggingConfig, Message, TimestampedUpdate};
 /// Scrapes the prometheus registry when asked and produces a batch of metric
 /// data that can be inserted into the built-in `mz_metrics` table.
 pub struct Scraper {
-    interval: Option<Duration>,
+    enabled: bool,
     retain_for: u64,
     registry: MetricsRegistry,
     metadata: HashMap<Row, u64>,
@@ -175,8 +175,8 @@ fn add_metadata_update<I: IntoIterator<Item = Row>>(
 impl Scraper {
     /// Constructs a new metrics scraper for the specified registry.
     ///
-    /// The logging configuration specifies what metrics to scrape and how long
-    /// to retain them for. If the logging configuration is none, scraping is
+    /// The logging configuration specifies how long to retain the scraped
+    /// metrics for. If the logging configuration is none, scraping is
     /// disabled.
     pub fn new(
         logging_config: Option<&LoggingConfig>,
@@ -185,17 +185,16 @@ impl Scraper {
         mz_prometheus_histograms_global_id: GlobalId,
         mz_prometheus_readings_global_id: GlobalId,
     ) -> Result<Scraper, anyhow::Error> {
-        let (interval, retain_for) = match logging_config {
+        let (enabled, retain_for) = match logging_config {
             Some(config) => {
-                let interval = config.metrics_scraping_interval;
                 let retain_for = u64::try_from(config.retain_readings_for.as_millis())
                     .map_err(|_| anyhow!("scraper retention duration does not fit in an i64"))?;
-                (interval, retain_for)
+                (true, retain_for)
             }
-            None => (None, 0),
+            None => (false, 0),
         };
         Ok(Scraper {
-            interval,
+            enabled,
             retain_for,
             registry,
             metadata: HashMap::new(),
@@ -205,16 +204,20 @@ impl Scraper {
         })
     }
 
-    /// Produces a stream that yields a `Message::ScrapeMetrics` at the desired
-    /// scrape frequency.
+    /// Produces a stream that yields a `Message::ScrapeMetrics` every
+    /// `interval`.
     ///
-    /// If the scraper is disabled, this stream will yield no items.
-    pub fn tick_stream(&self) -> Pin<Box<dyn Stream<Item = Message> + Send>> {
-        match self.interval {
-            None => stream::empty().boxed(),
-            Some(interval) => IntervalStream::new(time::interval(interval))
+    /// If the scraper or the interval is disabled, this stream will yield no
+    /// items.
+    pub fn tick_stream(
+        &self,
+        interval: Option<Duration>,
+    ) -> Pin<Box<dyn Stream<Item = Message> + Send>> {
+        match interval {
+            Some(interval) if self.enabled => IntervalStream::new(time::interval(interval))
                 .map(|_| Message::ScrapeMetrics)
                 .boxed(),
+            _ => stream::empty().boxed(),
         }
     }
 
@@ -0,0 +1,702 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Replication slots, from which subscribers stream publications over the
+//! PostgreSQL logical replication protocol.
+//!
+//! A slot is created unbound. The first time replication is started from the
+//! slot, the slot is bound to the named publications: the views of the
+//! publications are tailed, starting with a snapshot, and compaction of the
+//! tails' inputs is held back to the point the subscriber has confirmed that
+//! it applied. When replication is later restarted from the slot, e.g. because
+//! the subscriber reconnected, the tails resume from that point, so that the
+//! subscriber neither misses nor duplicates updates.
+//!
+//! Slots that are not temporary are stored in the catalog, along with the
+//! point from which to resume them, and are restored when Materialize
+//! restarts. A restored slot holds back compaction of its inputs to that
+//! point again, if the inputs can still be read there. Otherwise the slot is
+//! invalidated, as it is if its subscriber falls further behind than the
+//! configured maximum lag, and must be recreated.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use timely::progress::Antichain;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::sinks::{SinkAsOf, SinkConnector, SinkDesc, TailSinkConnector};
+use mz_dataflow_types::DataflowDesc;
+use mz_expr::GlobalId;
+use mz_ore::str::StrExt;
+use mz_repr::Timestamp;
+use mz_sql::names::RawDatabaseSpecifier;
+use mz_sql::plan::{QueryWhen, TailEnvelope};
+
+use crate::command::{ReplicatedRelation, ReplicationStream};
+use crate::coord::id_bundle::CollectionIdBundle;
+use crate::coord::{Coordinator, Message};
+use crate::error::CoordError;
+use crate::session::{RowBatchStream, Session};
+use crate::tail::{advance_as_of, resume_dataflow, PendingTail};
+
+/// A replication slot.
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationSlot {
+    /// The connection that created the slot, if the slot is temporary.
+    temporary: Option<u32>,
+    /// The replication that is streaming from the slot, if any.
+    active: Option<ActiveReplication>,
+    /// The publications to which the slot is bound, if replication has been
+    /// started from the slot.
+    binding: Option<SlotBinding>,
+    /// The reason the slot was invalidated, if it was. Replication cannot be
+    /// started from an invalidated slot.
+    invalidated: Option<String>,
+}
+
+/// The state of a replication slot that is stored in the catalog.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredReplicationSlot {
+    binding: Option<StoredSlotBinding>,
+    invalidated: Option<String>,
+}
+
+/// The state of a [`SlotBinding`] that is stored in the catalog, from which
+/// the binding's dataflows are rebuilt.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredSlotBinding {
+    publications: Vec<GlobalId>,
+    compute_instance: ComputeInstanceId,
+    resume_at: SinkAsOf,
+}
+
+/// A replication that is streaming from a slot.
+#[derive(Debug)]
+struct ActiveReplication {
+    /// The connection that started the replication.
+    conn_id: u32,
+    /// The sinks of the replication's tails.
+    sinks: Vec<(ComputeInstanceId, GlobalId)>,
+}
+
+/// The publications to which a replication slot is bound.
+#[derive(Debug)]
+struct SlotBinding {
+    /// The publications, in the order they were named.
+    publications: Vec<GlobalId>,
+    /// The dataflow from which the tail of each of the publications' views is
+    /// created, in the order the views were named.
+    dataflows: Vec<(GlobalId, DataflowDesc)>,
+    /// The compute instance on which the tails run.
+    compute_instance: ComputeInstanceId,
+    /// The inputs of the tails' dataflows.
+    id_bundle: CollectionIdBundle,
+    /// The point from which to resume the tails. All updates not beyond this
+    /// point have been applied by the subscriber.
+    resume_at: SinkAsOf,
+}
+
+impl SlotBinding {
+    /// Returns the time at which read holds on the tails' inputs are held.
+    fn hold(&self) -> Timestamp {
+        *self
+            .resume_at
+            .frontier
+            .as_option()
+            .expect("slot as_of must not be empty")
+    }
+}
+
+impl Coordinator {
+    /// Creates the replication slot `name`.
+    pub(super) fn sequence_create_replication_slot(
+        &mut self,
+        session: &Session,
+        name: String,
+        temporary: bool,
+    ) -> Result<(), CoordError> {
+        // PostgreSQL restricts slot names in the same way.
+        if name.is_empty()
+            || name.len() > 63
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            coord_bail!(
+                "replication slot name {} must contain only lower case letters, numbers, and \
+                 the underscore character, and be at most 63 characters long",
+                name.quoted()
+            );
+        }
+        if self.replication_slots.contains_key(&name) {
+            return Err(CoordError::DuplicateReplicationSlot(name));
+        }
+        let slot = ReplicationSlot {
+            temporary: temporary.then(|| session.conn_id()),
+            ..Default::default()
+        };
+        self.replication_slots.insert(name.clone(), slot);
+        if let Err(e) = self.store_replication_slot(&name) {
+            self.replication_slots.remove(&name);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Drops the replication slot `name`, which must not be active.
+    pub(super) async fn sequence_drop_replication_slot(
+        &mut self,
+        name: String,
+    ) -> Result<(), CoordError> {
+        match self.replication_slots.get(&name) {
+            None => Err(CoordError::UnknownReplicationSlot(name)),
+            Some(slot) if slot.active.is_some() => Err(CoordError::ReplicationSlotActive(name)),
+            Some(_) => {
+                self.drop_replication_slot(&name).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts streaming the publications named `publications` from the
+    /// replication slot `slot_name`.
+    pub(super) async fn sequence_start_replication(
+        &mut self,
+        session: &mut Session,
+        slot_name: String,
+        publications: Vec<String>,
+        start_lsn: u64,
+    ) -> Result<ReplicationStream, CoordError> {
+        let publications = self.resolve_publications(session, &publications)?;
+        let slot = match self.replication_slots.get(&slot_name) {
+            Some(slot) => slot,
+            None => return Err(CoordError::UnknownReplicationSlot(slot_name)),
+        };
+        if slot.active.is_some() {
+            return Err(CoordError::ReplicationSlotActive(slot_name));
+        }
+        if let Some(reason) = &slot.invalidated {
+            coord_bail!(
+                "replication slot {} has been invalidated because {}; drop and recreate it",
+                slot_name.quoted(),
+                reason
+            );
+        }
+        let bound_publications = slot
+            .binding
+            .as_ref()
+            .map(|binding| binding.publications.clone());
+        match bound_publications {
+            None => {
+                let binding = self.bind_replication_slot(session, publications)?;
+                let holds = self.tail_read_holds(
+                    binding.hold(),
+                    &binding.id_bundle,
+                    binding.compute_instance,
+                );
+                self.acquire_read_holds(&holds).await;
+                let slot = self.replication_slots.get_mut(&slot_name).unwrap();
+                slot.binding = Some(binding);
+                if let Err(e) = self.store_replication_slot(&slot_name) {
+                    let slot = self.replication_slots.get_mut(&slot_name).unwrap();
+                    let binding = slot.binding.take().unwrap();
+                    let holds = self.tail_read_holds(
+                        binding.hold(),
+                        &binding.id_bundle,
+                        binding.compute_instance,
+                    );
+                    self.release_read_hold(holds).await;
+                    return Err(e);
+                }
+            }
+            Some(bound_publications) => {
+                if bound_publications != publications {
+                    coord_bail!(
+                        "replication slot {} was started with different publications",
+                        slot_name.quoted()
+                    );
+                }
+                // The subscriber's start position implies that it has applied
+                // all updates before it.
+                self.confirm_replication_slot(&slot_name, start_lsn).await;
+            }
+        }
+
+        let binding = self.replication_slots[&slot_name].binding.as_ref().unwrap();
+        // The slot's inputs may have been dropped since it was bound.
+        if binding
+            .id_bundle
+            .iter()
+            .any(|id| !self.read_capability.contains_key(&id))
+        {
+            coord_bail!(
+                "replication slot {} cannot be resumed because one of its inputs has been dropped",
+                slot_name.quoted()
+            );
+        }
+        let compute_instance = binding.compute_instance;
+        let resume_at = binding.resume_at.clone();
+        let dataflows = binding.dataflows.clone();
+
+        let mut relations = vec![];
+        let mut sinks = vec![];
+        for (view, dataflow) in dataflows {
+            let sink_id = self.allocate_transient_id()?;
+            let dataflow = resume_dataflow(&dataflow, sink_id, &resume_at);
+            let entry = self.catalog.get_entry(&view);
+            let full_name = self
+                .catalog
+                .resolve_full_name(entry.name(), Some(session.conn_id()));
+            let desc = entry.desc(&full_name)?.clone();
+            let oid = entry.oid();
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.pending_tails.insert(
+                sink_id,
+                PendingTail::new(
+                    tx,
+                    true,
+                    TailEnvelope::Updates,
+                    desc.arity(),
+                    sink_id,
+                    compute_instance,
+                    None,
+                    None,
+                    None,
+                ),
+            );
+            self.tail_sinks.insert(sink_id, sink_id);
+            session.add_drop_sink(compute_instance, sink_id);
+            self.ship_dataflow(dataflow, compute_instance).await;
+            sinks.push((compute_instance, sink_id));
+            relations.push(ReplicatedRelation {
+                oid,
+                schema: full_name.schema,
+                name: full_name.item,
+                desc,
+                rx: RowBatchStream::new(rx),
+            });
+        }
+
+        let slot = self.replication_slots.get_mut(&slot_name).unwrap();
+        slot.active = Some(ActiveReplication {
+            conn_id: session.conn_id(),
+            sinks,
+        });
+
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        let confirm = Box::new(move |lsn| {
+            // It is not an error for the coordinator to have shut down.
+            let _ = internal_cmd_tx.send(Message::ReplicationSlotConfirmed(slot_name.clone(), lsn));
+        });
+        Ok(ReplicationStream { relations, confirm })
+    }
+
+    /// Records that the subscriber of the replication slot `slot_name` has
+    /// applied all updates before `lsn`.
+    pub(super) async fn confirm_replication_slot(&mut self, slot_name: &str, lsn: Timestamp) {
+        // The slot may have since been dropped.
+        let binding = match self
+            .replication_slots
+            .get_mut(slot_name)
+            .and_then(|slot| slot.binding.as_mut())
+        {
+            Some(binding) => binding,
+            None => return,
+        };
+        let old_time = binding.hold();
+        advance_as_of(&mut binding.resume_at, &Antichain::from_elem(lsn));
+        let time = binding.hold();
+        if time > old_time {
+            // Allow the slot's inputs to compact up to the updates the
+            // subscriber has applied.
+            let id_bundle = binding.id_bundle.clone();
+            let compute_instance = binding.compute_instance;
+            let new_holds = self.tail_read_holds(time, &id_bundle, compute_instance);
+            self.acquire_read_holds(&new_holds).await;
+            let old_holds = self.tail_read_holds(old_time, &id_bundle, compute_instance);
+            self.release_read_hold(old_holds).await;
+            // If the new position is not stored, the slot resumes from an
+            // earlier position after a restart, which only duplicates updates
+            // that the subscriber has already applied.
+            if let Err(e) = self.store_replication_slot(slot_name) {
+                warn!(
+                    "failed to store the position of replication slot {}: {}",
+                    slot_name, e
+                );
+            }
+        }
+    }
+
+    /// Invalidates the replication slots whose subscribers have fallen
+    /// further behind than the maximum lag, so that they no longer hold back
+    /// compaction of their inputs.
+    pub(super) async fn invalidate_lagging_replication_slots(&mut self) {
+        let max_lag = match self.max_replication_slot_lag {
+            Some(max_lag) => max_lag,
+            None => return,
+        };
+        let max_lag_ms = u64::try_from(max_lag.as_millis()).unwrap_or(u64::MAX);
+        let oldest = self.now().saturating_sub(max_lag_ms);
+        let names: Vec<_> = self
+            .replication_slots
+            .iter()
+            .filter(|(_, slot)| match &slot.binding {
+                Some(binding) => binding.hold() < oldest,
+                None => false,
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            self.invalidate_replication_slot(
+                &name,
+                format!("its subscriber fell more than {:?} behind", max_lag),
+            )
+            .await;
+        }
+    }
+
+    /// Invalidates the replication slot `name` for `reason`, ending the
+    /// replication from the slot, if any, with an error, and releasing the
+    /// slot's read holds.
+    async fn invalidate_replication_slot(&mut self, name: &str, reason: String) {
+        let slot = self.replication_slots.get_mut(name).unwrap();
+        let active = slot.active.take();
+        let binding = slot.binding.take();
+        slot.invalidated = Some(reason.clone());
+        if let Some(active) = active {
+            self.end_replication(
+                active,
+                format!(
+                    "replication slot {} was invalidated because {}",
+                    name.quoted(),
+                    reason
+                ),
+            )
+            .await;
+        }
+        if let Some(binding) = binding {
+            let holds =
+                self.tail_read_holds(binding.hold(), &binding.id_bundle, binding.compute_instance);
+            self.release_read_hold(holds).await;
+        }
+        warn!("invalidated replication slot {}: {}", name, reason);
+        if let Err(e) = self.store_replication_slot(name) {
+            warn!("failed to store replication slot {}: {}", name, e);
+        }
+    }
+
+    /// Restores the replication slots stored in the catalog, holding back
+    /// compaction of the inputs of each bound slot to the point from which it
+    /// resumes.
+    pub(super) async fn restore_replication_slots(&mut self) -> Result<(), CoordError> {
+        let stored_slots = self
+            .catalog
+            .load_replication_slots::<StoredReplicationSlot>()?;
+        for (name, stored) in stored_slots {
+            let mut slot = ReplicationSlot {
+                invalidated: stored.invalidated,
+                ..Default::default()
+            };
+            if let Some(stored) = stored.binding {
+                // Dropping a publication drops the slots bound to it, so a
+                // missing publication means that the slot was being dropped.
+                if stored
+                    .publications
+                    .iter()
+                    .any(|id| self.catalog.try_get_entry(id).is_none())
+                {
+                    self.catalog
+                        .set_replication_slot::<StoredReplicationSlot>(&name, None)?;
+                    continue;
+                }
+                match self.restore_slot_binding(stored) {
+                    Ok(binding) => {
+                        let holds = self.tail_read_holds(
+                            binding.hold(),
+                            &binding.id_bundle,
+                            binding.compute_instance,
+                        );
+                        self.acquire_read_holds(&holds).await;
+                        slot.binding = Some(binding);
+                    }
+                    Err(reason) => {
+                        warn!("invalidated replication slot {}: {}", name, reason);
+                        slot.invalidated = Some(reason);
+                    }
+                }
+            }
+            self.replication_slots.insert(name.clone(), slot);
+            self.store_replication_slot(&name)?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the binding of a restored replication slot, if the slot can
+    /// still be resumed. Otherwise returns the reason that it cannot.
+    fn restore_slot_binding(&mut self, stored: StoredSlotBinding) -> Result<SlotBinding, String> {
+        if !self
+            .catalog
+            .compute_instances()
+            .any(|instance| instance.id == stored.compute_instance)
+        {
+            return Err("the cluster on which it ran was dropped".into());
+        }
+        let binding = self
+            .build_slot_binding(
+                stored.publications,
+                stored.compute_instance,
+                stored.resume_at,
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+        let since = self.least_valid_read(&binding.id_bundle, binding.compute_instance);
+        if !since.less_equal(&binding.hold()) {
+            return Err(
+                "its inputs were compacted past the position that its subscriber confirmed".into(),
+            );
+        }
+        Ok(binding)
+    }
+
+    /// Records the state of the replication slot `name` in the catalog, if the
+    /// slot is not temporary.
+    fn store_replication_slot(&self, name: &str) -> Result<(), CoordError> {
+        let slot = &self.replication_slots[name];
+        if slot.temporary.is_some() {
+            return Ok(());
+        }
+        let stored = StoredReplicationSlot {
+            binding: slot.binding.as_ref().map(|binding| StoredSlotBinding {
+                publications: binding.publications.clone(),
+                compute_instance: binding.compute_instance,
+                resume_at: binding.resume_at.clone(),
+            }),
+            invalidated: slot.invalidated.clone(),
+        };
+        self.catalog.set_replication_slot(name, Some(&stored))?;
+        Ok(())
+    }
+
+    /// Records that the replications started by the connection `conn_id` have
+    /// ended, as their tails have been dropped.
+    pub(super) fn end_replications(&mut self, conn_id: u32) {
+        for slot in self.replication_slots.values_mut() {
+            if matches!(&slot.active, Some(active) if active.conn_id == conn_id) {
+                slot.active = None;
+            }
+        }
+    }
+
+    /// Drops the temporary replication slots created by the connection
+    /// `conn_id`.
+    pub(super) async fn drop_temporary_replication_slots(&mut self, conn_id: u32) {
+        let names: Vec<_> = self
+            .replication_slots
+            .iter()
+            .filter(|(_, slot)| slot.temporary == Some(conn_id))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            self.drop_replication_slot(&name).await;
+        }
+    }
+
+    /// Drops the replication slots bound to any of the publications `ids`,
+    /// which have been dropped, ending any replications from those slots.
+    pub(super) async fn drop_publications(&mut self, ids: Vec<GlobalId>) {
+        let names: Vec<_> = self
+            .replication_slots
+            .iter()
+            .filter(|(_, slot)| match &slot.binding {
+                Some(binding) => binding.publications.iter().any(|id| ids.contains(id)),
+                None => false,
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            self.drop_replication_slot(&name).await;
+        }
+    }
+
+    /// Drops the replication slot `name`, ending the replication from the slot,
+    /// if any, with an error.
+    async fn drop_replication_slot(&mut self, name: &str) {
+        let slot = self.replication_slots.remove(name).unwrap();
+        if slot.temporary.is_none() {
+            if let Err(e) = self
+                .catalog
+                .set_replication_slot::<StoredReplicationSlot>(name, None)
+            {
+                warn!("failed to remove replication slot {}: {}", name, e);
+            }
+        }
+        if let Some(active) = slot.active {
+            self.end_replication(
+                active,
+                format!("replication slot {} was dropped", name.quoted()),
+            )
+            .await;
+        }
+        if let Some(binding) = slot.binding {
+            let holds =
+                self.tail_read_holds(binding.hold(), &binding.id_bundle, binding.compute_instance);
+            self.release_read_hold(holds).await;
+        }
+    }
+
+    /// Ends the replication `active` with the error `message`.
+    async fn end_replication(&mut self, active: ActiveReplication, message: String) {
+        for (_, sink_id) in &active.sinks {
+            if let Some(pending_tail) = self.pending_tails.remove(sink_id) {
+                self.tail_sinks.remove(sink_id);
+                pending_tail.send_error(message.clone());
+            }
+        }
+        self.drop_compute_sinks(active.sinks).await;
+    }
+
+    /// Binds a replication slot to the publications `publications`, as of a
+    /// time at which all of their views can be read.
+    fn bind_replication_slot(
+        &mut self,
+        session: &Session,
+        publications: Vec<GlobalId>,
+    ) -> Result<SlotBinding, CoordError> {
+        let compute_instance = self
+            .catalog
+            .resolve_compute_instance(session.vars().cluster())?
+            .id;
+        let views = self.publication_views(&publications);
+
+        let id_bundle = self
+            .index_oracle(compute_instance)
+            .sufficient_collections(&views);
+        let timestamp = self.determine_timestamp(
+            session,
+            &id_bundle,
+            QueryWhen::Immediately,
+            compute_instance,
+        )?;
+        // The first replication from the slot begins with a snapshot of the
+        // views.
+        let resume_at = SinkAsOf {
+            frontier: Antichain::from_elem(timestamp),
+            strict: false,
+        };
+        self.build_slot_binding(
+            publications,
+            compute_instance,
+            resume_at,
+            Some(session.conn_id()),
+        )
+    }
+
+    /// Builds the binding of a replication slot to the publications
+    /// `publications`, whose tails run on `compute_instance` and resume from
+    /// `resume_at`.
+    fn build_slot_binding(
+        &mut self,
+        publications: Vec<GlobalId>,
+        compute_instance: ComputeInstanceId,
+        resume_at: SinkAsOf,
+        conn_id: Option<u32>,
+    ) -> Result<SlotBinding, CoordError> {
+        let views = self.publication_views(&publications);
+
+        let mut dataflows = vec![];
+        let mut id_bundle = CollectionIdBundle::default();
+        for view in views {
+            let entry = self.catalog.get_entry(&view);
+            let from_desc = entry
+                .desc(&self.catalog.resolve_full_name(entry.name(), conn_id))?
+                .clone();
+            let sink_id = self.allocate_transient_id()?;
+            let sink_desc = SinkDesc {
+                from: view,
+                from_desc,
+                connector: SinkConnector::Tail(TailSinkConnector::default()),
+                envelope: None,
+                as_of: resume_at.clone(),
+            };
+            let dataflow = self
+                .dataflow_builder(compute_instance)
+                .build_sink_dataflow(format!("tail-{}", sink_id), sink_id, sink_desc)?;
+            id_bundle
+                .storage_ids
+                .extend(dataflow.source_imports.keys().copied());
+            id_bundle
+                .compute_ids
+                .extend(dataflow.index_imports.keys().copied());
+            dataflows.push((view, dataflow));
+        }
+
+        Ok(SlotBinding {
+            publications,
+            dataflows,
+            compute_instance,
+            id_bundle,
+            resume_at,
+        })
+    }
+
+    /// Returns the views of the publications `publications`, in the order in
+    /// which they were named, without duplicates.
+    fn publication_views(&self, publications: &[GlobalId]) -> Vec<GlobalId> {
+        let mut views = vec![];
+        for id in publications {
+            let publication = self.catalog.get_entry(id).publication().unwrap();
+            for view in &publication.views {
+                if !views.contains(view) {
+                    views.push(*view);
+                }
+            }
+        }
+        views
+    }
+
+    /// Resolves the names of publications in the session's database, in which
+    /// publication names must be unique, as they are in PostgreSQL.
+    fn resolve_publications(
+        &self,
+        session: &Session,
+        names: &[String],
+    ) -> Result<Vec<GlobalId>, CoordError> {
+        let database = RawDatabaseSpecifier::Name(session.vars().database().into());
+        let mut by_name: HashMap<&str, Vec<GlobalId>> = HashMap::new();
+        for entry in self.catalog.entries() {
+            if entry.publication().is_some()
+                && self.catalog.resolve_full_name(entry.name(), None).database == database
+            {
+                by_name
+                    .entry(entry.name().item.as_str())
+                    .or_default()
+                    .push(entry.id());
+            }
+        }
+        let mut ids = vec![];
+        for name in names {
+            let id = match by_name.get(name.as_str()).map(|ids| ids.as_slice()) {
+                None => return Err(CoordError::UnknownPublication(name.clone())),
+                Some([id]) => *id,
+                Some(_) => coord_bail!(
+                    "publication name {} is ambiguous in database {}",
+                    name.quoted(),
+                    session.vars().database().quoted()
+                ),
+            };
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
@@ -0,0 +1,442 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Applying operations to secrets storage.
+//!
+//! Secrets storage may be a remote service, so the coordinator does not wait
+//! for it to apply operations. It instead spawns a task that applies them and
+//! reports back with [`Message::SecretOpsApplied`], whose
+//! [`SecretOpsFollowUp`] finishes the work that requested the operations,
+//! e.g., by creating a secret in the catalog once its contents are stored.
+//!
+//! The tasks hold a lock while they apply operations and report back, so that
+//! operations are applied, and their follow-ups run, in the order in which
+//! they were requested.
+
+use std::sync::Arc;
+
+use derivative::Derivative;
+use futures::StreamExt;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use mz_expr::GlobalId;
+use mz_ore::now::EpochMillis;
+use mz_ore::task;
+use mz_secrets::{SecretOp, SecretVersion, SecretsController};
+use mz_sql::ast::ObjectType;
+use mz_sql::names::QualifiedObjectName;
+
+use crate::catalog::{
+    self, BuiltinTableUpdate, CatalogItem, SecretAuditEvent, SecretAuditOperation,
+};
+use crate::command::ExecuteResponse;
+use crate::coord::{Coordinator, Message, SECRETS_APPLY_TIMEOUT};
+use crate::error::CoordError;
+use crate::session::Session;
+use crate::util::{ddl_retry, ClientTransmitter};
+
+/// Operations on secrets storage that a spawned task has finished applying.
+#[derive(Debug)]
+pub struct SecretOpsApplied {
+    /// The audit events that record the operations, whose versions, times,
+    /// and errors are filled in once the operations have been applied.
+    pub events: Vec<SecretAuditEvent>,
+    /// The versions that the operations created, or the error with which
+    /// they failed.
+    pub result: Result<Vec<SecretVersion>, CoordError>,
+    pub follow_up: SecretOpsFollowUp,
+}
+
+/// The work that resumes once operations on secrets storage are applied.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub enum SecretOpsFollowUp {
+    /// Creates the secret `id`, whose contents were written, in the catalog.
+    CreateSecret {
+        session: Session,
+        #[derivative(Debug = "ignore")]
+        tx: ClientTransmitter<ExecuteResponse>,
+        id: GlobalId,
+        oid: u32,
+        name: QualifiedObjectName,
+        secret: catalog::Secret,
+        if_not_exists: bool,
+    },
+    /// Finishes altering the secret `id`, whose new contents were written.
+    AlterSecret {
+        session: Session,
+        #[derivative(Debug = "ignore")]
+        tx: ClientTransmitter<ExecuteResponse>,
+        id: GlobalId,
+    },
+    /// Reports the outcome of the operations.
+    Report(#[derivative(Debug = "ignore")] oneshot::Sender<Result<Vec<SecretVersion>, CoordError>>),
+    /// Logs the failure of the operations, which nothing waits for.
+    Log {
+        /// Describes what the operations were doing.
+        context: &'static str,
+    },
+}
+
+impl Coordinator {
+    /// Applies `ops` with the secrets controller on behalf of `user` in a
+    /// spawned task, and runs `follow_up` once they have been applied.
+    ///
+    /// The controller is given [`SECRETS_APPLY_TIMEOUT`] to apply the
+    /// operations. If it times out, any subset of `ops` may have been
+    /// applied, per the contract of [`SecretsController::apply`], so callers
+    /// must only apply operations whose partial application is harmless. If
+    /// `retry` is set, operations that fail or time out are retried a few
+    /// times, which callers must only request of idempotent operations.
+    pub(super) fn spawn_secret_ops(
+        &mut self,
+        user: &str,
+        ops: Vec<SecretOp>,
+        retry: bool,
+        follow_up: SecretOpsFollowUp,
+    ) {
+        let events = audit_events(user, &ops);
+        let controller = Arc::clone(&self.secrets_controller);
+        let lock = Arc::clone(&self.secrets_write_lock);
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(|| "apply_secret_ops", async move {
+            // The guard is held until the follow-up is enqueued, so that
+            // follow-ups run in the order in which operations were applied.
+            let _guard = lock.lock().await;
+            let result = if retry {
+                let retry = ddl_retry().max_tries(3).into_retry_stream();
+                tokio::pin!(retry);
+                loop {
+                    let state = retry
+                        .next()
+                        .await
+                        .expect("retry produces at least one element");
+                    match apply_with_timeout(&*controller, ops.clone()).await {
+                        Err(e) if state.next_backoff.is_some() => {
+                            warn!("retrying secret operations: {}", e)
+                        }
+                        res => break res,
+                    }
+                }
+            } else {
+                apply_with_timeout(&*controller, ops).await
+            };
+            internal_cmd_tx
+                .send(Message::SecretOpsApplied(SecretOpsApplied {
+                    events,
+                    result,
+                    follow_up,
+                }))
+                .expect("sending to internal_cmd_tx cannot fail");
+        });
+    }
+
+    /// Records operations on secrets storage that a task spawned by
+    /// [`Coordinator::spawn_secret_ops`] applied, and runs their follow-up.
+    ///
+    /// The operations are recorded whether or not they succeeded. Operations
+    /// that created or altered a secret are recorded in the same catalog
+    /// transaction as the secret's creation or alteration.
+    pub(super) async fn message_secret_ops_applied(
+        &mut self,
+        SecretOpsApplied {
+            events,
+            result,
+            follow_up,
+        }: SecretOpsApplied,
+    ) {
+        let events = complete_audit_events(events, &result, self.now());
+        match follow_up {
+            SecretOpsFollowUp::CreateSecret {
+                session,
+                tx,
+                id,
+                oid,
+                name,
+                secret,
+                if_not_exists,
+            } => {
+                let result = match result {
+                    Ok(versions) => {
+                        // The secret does not exist in the catalog yet, so
+                        // its version is reflected in `mz_secrets` when it is
+                        // created.
+                        self.catalog.set_secret_version(id, Some(versions[0]));
+                        self.finish_create_secret(
+                            &session,
+                            id,
+                            oid,
+                            name,
+                            secret,
+                            if_not_exists,
+                            events,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        self.record_secret_ops(Some(&session), events).await;
+                        Err(e)
+                    }
+                };
+                tx.send(result, session);
+            }
+            SecretOpsFollowUp::AlterSecret { session, tx, id } => {
+                let result = match result {
+                    Ok(versions) => {
+                        self.finish_alter_secret(&session, id, versions[0], events)
+                            .await
+                    }
+                    Err(e) => {
+                        self.record_secret_ops(Some(&session), events).await;
+                        Err(e)
+                    }
+                };
+                tx.send(result, session);
+            }
+            SecretOpsFollowUp::Report(tx) => {
+                self.record_secret_ops(None, events).await;
+                let _ = tx.send(result);
+            }
+            SecretOpsFollowUp::Log { context } => {
+                self.record_secret_ops(None, events).await;
+                if let Err(e) = result {
+                    warn!("{} has encountered an error: {}", context, e);
+                }
+            }
+        }
+    }
+
+    /// Applies `ops` with the secrets controller on behalf of `user`, waiting
+    /// for them to be applied, for use before the coordinator serves clients.
+    ///
+    /// The operations are recorded in `mz_secret_audit_events` whether or not
+    /// they succeed, and the updates that reflect them are added to
+    /// `updates`, which the caller must send. Returns the versions that the
+    /// operations created.
+    pub(super) async fn apply_and_audit_secret_ops(
+        &mut self,
+        user: &str,
+        ops: Vec<SecretOp>,
+        updates: &mut Vec<BuiltinTableUpdate>,
+    ) -> Result<Vec<SecretVersion>, CoordError> {
+        let events = audit_events(user, &ops);
+        let result = apply_with_timeout(&*self.secrets_controller, ops).await;
+        let events = complete_audit_events(events, &result, self.now());
+        let (audit_updates, ()) =
+            self.catalog
+                .transact(None, vec![catalog::Op::RecordSecretOps(events)], |_| Ok(()))?;
+        updates.extend(audit_updates);
+        result
+    }
+
+    /// Records `events` in `mz_secret_audit_events` on behalf of the user of
+    /// `session`, if any, logging any failure to record them.
+    async fn record_secret_ops(
+        &mut self,
+        session: Option<&Session>,
+        events: Vec<SecretAuditEvent>,
+    ) {
+        let ops = vec![catalog::Op::RecordSecretOps(events)];
+        if let Err(e) = self.catalog_transact(session, ops, |_| Ok(())).await {
+            warn!(
+                "recording operations on secrets storage has encountered an error: {}",
+                e
+            );
+        }
+    }
+
+    /// Creates the secret `id`, whose contents were written by the operations
+    /// that `events` record, in the catalog.
+    async fn finish_create_secret(
+        &mut self,
+        session: &Session,
+        id: GlobalId,
+        oid: u32,
+        name: QualifiedObjectName,
+        secret: catalog::Secret,
+        if_not_exists: bool,
+        events: Vec<SecretAuditEvent>,
+    ) -> Result<ExecuteResponse, CoordError> {
+        let ops = vec![
+            catalog::Op::CreateItem {
+                id,
+                oid,
+                name,
+                item: CatalogItem::Secret(secret),
+            },
+            catalog::Op::RecordSecretOps(events.clone()),
+        ];
+        let res = self.catalog_transact(Some(session), ops, |_| Ok(())).await;
+        if res.is_err() {
+            // The contents were written even though the secret was not
+            // created.
+            self.record_secret_ops(Some(session), events).await;
+            // The written contents belong to no secret.
+            self.catalog.set_secret_version(id, None);
+            self.spawn_secret_ops(
+                session.user(),
+                vec![SecretOp::Delete { id, version: None }],
+                false,
+                SecretOpsFollowUp::Log {
+                    context: "dropping newly created secret",
+                },
+            );
+        }
+        match res {
+            Ok(()) => Ok(ExecuteResponse::CreatedSecret { existed: false }),
+            Err(CoordError::Catalog(catalog::Error {
+                kind: catalog::ErrorKind::ItemAlreadyExists(_),
+                ..
+            })) if if_not_exists => Ok(ExecuteResponse::CreatedSecret { existed: true }),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Finishes altering the secret `id`, whose new contents were written as
+    /// version `version` by the operations that `events` record.
+    async fn finish_alter_secret(
+        &mut self,
+        session: &Session,
+        id: GlobalId,
+        version: SecretVersion,
+        events: Vec<SecretAuditEvent>,
+    ) -> Result<ExecuteResponse, CoordError> {
+        // The secret may have been dropped while its contents were written,
+        // in which case the write recreated it in storage.
+        let item = match self.catalog.try_get_entry(&id).map(|entry| entry.item()) {
+            Some(item @ CatalogItem::Secret(_)) => item.clone(),
+            _ => {
+                self.record_secret_ops(Some(session), events).await;
+                self.spawn_secret_ops(
+                    session.user(),
+                    vec![SecretOp::Delete { id, version: None }],
+                    false,
+                    SecretOpsFollowUp::Log {
+                        context: "dropping altered secret",
+                    },
+                );
+                coord_bail!("secret was dropped while it was altered");
+            }
+        };
+        let updates = self.catalog.set_secret_version(id, Some(version));
+        self.send_builtin_table_updates(updates).await;
+
+        // Record the modification of the secret along with the write of its
+        // contents.
+        let ops = vec![
+            catalog::Op::UpdateItem { id, to_item: item },
+            catalog::Op::RecordSecretOps(events.clone()),
+        ];
+        if let Err(e) = self.catalog_transact(Some(session), ops, |_| Ok(())).await {
+            self.record_secret_ops(Some(session), events).await;
+            return Err(e);
+        }
+
+        // The sources that the secret configures are reconfigured with its new
+        // contents, which the instances of the sources that start afterward
+        // use. Instances that are already running, and sinks, use the new
+        // contents once they restart.
+        let sources: Vec<_> = self
+            .catalog
+            .get_entry(&id)
+            .used_by()
+            .iter()
+            .copied()
+            .filter(|id| matches!(self.catalog.get_entry(id).item(), CatalogItem::Source(_)))
+            .collect();
+        let mut updates = vec![];
+        for source_id in sources {
+            let mut source_description = self
+                .catalog
+                .state()
+                .source_description_for(source_id)
+                .unwrap();
+            match self.read_source_secrets(&mut source_description).await {
+                Ok(versions) => {
+                    self.dataflow_client
+                        .storage_mut()
+                        .alter_sources(vec![(source_id, source_description)])
+                        .await
+                        .unwrap();
+                    updates.extend(self.catalog.set_secret_uses(source_id, versions));
+                }
+                Err(e) => warn!(
+                    "source {} cannot read its secrets after secret {} was altered: {}",
+                    source_id, id, e
+                ),
+            }
+        }
+        self.send_builtin_table_updates(updates).await;
+
+        Ok(ExecuteResponse::AlteredObject(ObjectType::Secret))
+    }
+}
+
+/// Applies `ops` with `controller`, giving up if it does not finish within
+/// [`SECRETS_APPLY_TIMEOUT`].
+async fn apply_with_timeout(
+    controller: &dyn SecretsController,
+    ops: Vec<SecretOp>,
+) -> Result<Vec<SecretVersion>, CoordError> {
+    match tokio::time::timeout(SECRETS_APPLY_TIMEOUT, controller.apply(ops)).await {
+        Ok(res) => Ok(res?),
+        Err(_) => coord_bail!(
+            "secrets storage did not respond within {:?}",
+            SECRETS_APPLY_TIMEOUT
+        ),
+    }
+}
+
+/// Returns the audit events that record `ops`, applied on behalf of `user`,
+/// whose times, created versions, and errors are filled in by
+/// [`complete_audit_events`] and whose IDs are filled in when they are
+/// recorded.
+fn audit_events(user: &str, ops: &[SecretOp]) -> Vec<SecretAuditEvent> {
+    ops.iter()
+        .map(|op| {
+            let (operation, version) = match op {
+                SecretOp::Ensure { .. } => (SecretAuditOperation::Ensure, None),
+                SecretOp::Delete { version, .. } => (SecretAuditOperation::Delete, *version),
+            };
+            SecretAuditEvent {
+                id: 0,
+                secret_id: op.id(),
+                operation,
+                version,
+                user: user.into(),
+                occurred_at: 0,
+                error: None,
+            }
+        })
+        .collect()
+}
+
+/// Completes `events`, which record operations that were applied at `now`
+/// with the given `result`.
+///
+/// Ensure operations are recorded with the versions they created. If the
+/// operations failed, every operation is recorded with the error, as any
+/// subset of them may have been applied.
+fn complete_audit_events(
+    mut events: Vec<SecretAuditEvent>,
+    result: &Result<Vec<SecretVersion>, CoordError>,
+    now: EpochMillis,
+) -> Vec<SecretAuditEvent> {
+    let mut created = result.as_ref().ok().into_iter().flatten();
+    let error = result.as_ref().err().map(|e| e.to_string());
+    for event in &mut events {
+        if event.operation == SecretAuditOperation::Ensure {
+            event.version = created.next().copied();
+        }
+        event.occurred_at = now;
+        event.error = error.clone();
+    }
+    events
+}
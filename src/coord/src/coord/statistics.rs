@@ -0,0 +1,427 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Statistics about indexed collections, as reported in `mz_statistics` and
+//! consulted by the optimizer's cardinality estimates.
+//!
+//! The coordinator collects, for each user index, the number of rows in the
+//! indexed collection and the number of distinct values of the index's key.
+//! Each collection runs a small transient dataflow that reduces the index's
+//! arrangement to those two numbers, so only they are sent back to the
+//! coordinator. At most one collection per index is in flight at a time.
+//!
+//! `mz_internal.collect_statistics()` collects statistics about every index.
+//! If statistics are collected periodically, each period collects statistics
+//! about only the index whose statistics are the oldest, so that the cost of a
+//! period does not grow with the number of indexes.
+//!
+//! Statistics are stored in the catalog, so that plans do not change when the
+//! coordinator restarts. The optimizer only sees the number of rows rounded up
+//! to a power of two, so that plans only change when a collection's size
+//! changes substantially.
+
+use std::collections::{HashMap, HashSet};
+
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use timely::progress::Antichain;
+use tracing::warn;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::{DataflowDesc, IndexDesc, PeekResponseUnary};
+use mz_expr::{
+    permutation_for_arrangement, AggregateExpr, AggregateFunc, GlobalId, MirRelationExpr,
+    MirScalarExpr, OptimizedMirRelationExpr, RowSetFinishing,
+};
+use mz_ore::now::{to_datetime, EpochMillis};
+use mz_ore::task;
+use mz_repr::{Datum, Row, ScalarType, Timestamp};
+
+use crate::catalog::builtin::MZ_STATISTICS;
+use crate::catalog::{BuiltinTableUpdate, CatalogItem, Index, SYSTEM_CONN_ID};
+use crate::command::ExecuteResponse;
+use crate::coord::id_bundle::CollectionIdBundle;
+use crate::coord::{fast_path_peek, Coordinator, Message, PeekLimits};
+use crate::error::CoordError;
+
+/// The statistics about an index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStatistics {
+    /// The ID of the indexed collection.
+    on_id: GlobalId,
+    /// The number of rows in the indexed collection.
+    row_count: u64,
+    /// The number of distinct values of the index's key.
+    distinct_keys: u64,
+    /// The time at which the statistics were collected.
+    collected_at: EpochMillis,
+}
+
+/// The statistics about every index, and the indexes whose statistics are
+/// being collected.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    indexes: HashMap<GlobalId, IndexStatistics>,
+    in_flight: HashSet<GlobalId>,
+}
+
+impl Statistics {
+    /// Returns the number of rows in the collection `id`, as of the most
+    /// recent collection of statistics about any of its indexes, rounded up to
+    /// a power of two.
+    pub fn cardinality(&self, id: GlobalId) -> Option<u64> {
+        self.indexes
+            .values()
+            .filter(|stats| stats.on_id == id)
+            .max_by_key(|stats| stats.collected_at)
+            .map(|stats| {
+                stats
+                    .row_count
+                    .checked_next_power_of_two()
+                    .unwrap_or(u64::MAX)
+            })
+    }
+}
+
+/// The outcome of collecting statistics about an index.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct StatisticsCollected {
+    index_id: GlobalId,
+    /// The number of rows and the number of distinct keys, or the reason
+    /// they could not be collected.
+    #[derivative(Debug = "ignore")]
+    result: Result<(u64, u64), String>,
+}
+
+impl Coordinator {
+    /// Starts collecting statistics about every enabled user index whose
+    /// statistics are not already being collected.
+    ///
+    /// Returns the number of indexes whose statistics are being collected.
+    pub(super) async fn collect_statistics(&mut self) -> usize {
+        let indexes = self.statistics_candidates();
+        self.start_collecting_statistics(indexes).await
+    }
+
+    /// Starts collecting statistics about the enabled user index that has
+    /// gone the longest without them, if its statistics are not already being
+    /// collected.
+    pub(super) async fn collect_oldest_statistics(&mut self) {
+        // Indexes without statistics come first, in order of their IDs.
+        let oldest = self
+            .statistics_candidates()
+            .into_iter()
+            .min_by_key(|(index_id, _)| {
+                let collected_at = self
+                    .statistics
+                    .indexes
+                    .get(index_id)
+                    .map(|stats| stats.collected_at);
+                (collected_at, *index_id)
+            });
+        self.start_collecting_statistics(oldest.into_iter().collect())
+            .await;
+    }
+
+    /// Returns the enabled user indexes whose statistics are not already
+    /// being collected.
+    fn statistics_candidates(&self) -> Vec<(GlobalId, Index)> {
+        self.catalog
+            .entries()
+            .filter_map(|entry| match entry.item() {
+                CatalogItem::Index(index)
+                    if entry.id().is_user()
+                        && index.enabled
+                        && !self.statistics.in_flight.contains(&entry.id()) =>
+                {
+                    Some((entry.id(), index.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Starts collecting statistics about each of `indexes`, returning the
+    /// number of collections started.
+    async fn start_collecting_statistics(&mut self, indexes: Vec<(GlobalId, Index)>) -> usize {
+        let mut started = 0;
+        for (index_id, index) in indexes {
+            match self.collect_index_statistics(index_id, &index).await {
+                Ok(()) => started += 1,
+                Err(e) => warn!(
+                    "failed to start collecting statistics about index {}: {}",
+                    index_id, e
+                ),
+            }
+        }
+        started
+    }
+
+    /// Starts collecting statistics about the index `index_id`.
+    async fn collect_index_statistics(
+        &mut self,
+        index_id: GlobalId,
+        index: &Index,
+    ) -> Result<(), CoordError> {
+        let compute_instance = index.compute_instance;
+        let installed = self
+            .dataflow_client
+            .compute(compute_instance)
+            .map_or(false, |compute| compute.collection(index_id).is_ok());
+        if !installed {
+            return Err(CoordError::Unstructured(anyhow::anyhow!(
+                "index is not installed on its cluster"
+            )));
+        }
+
+        let on = self.catalog.get_entry(&index.on);
+        let typ = on
+            .desc(&self.catalog.resolve_full_name(on.name(), None))?
+            .typ()
+            .clone();
+
+        // Count the rows with each distinct key, and then sum those counts
+        // and count the keys.
+        let count_rows = || AggregateExpr {
+            func: AggregateFunc::Count,
+            expr: MirScalarExpr::literal_ok(Datum::True, ScalarType::Bool),
+            distinct: false,
+        };
+        let per_key = MirRelationExpr::Reduce {
+            input: Box::new(MirRelationExpr::global_get(index.on, typ)),
+            group_key: index.keys.clone(),
+            aggregates: vec![count_rows()],
+            monotonic: false,
+            expected_group_size: None,
+        };
+        let totals = MirRelationExpr::Reduce {
+            input: Box::new(per_key),
+            group_key: vec![],
+            aggregates: vec![
+                AggregateExpr {
+                    func: AggregateFunc::SumInt64,
+                    expr: MirScalarExpr::Column(index.keys.len()),
+                    distinct: false,
+                },
+                count_rows(),
+            ],
+            monotonic: false,
+            expected_group_size: None,
+        };
+
+        let typ = totals.typ();
+        let arity = typ.arity();
+        let key: Vec<_> = typ
+            .default_key()
+            .iter()
+            .map(|k| MirScalarExpr::Column(*k))
+            .collect();
+        let (permutation, thinning) = permutation_for_arrangement(&key, arity);
+        let view_id = self.allocate_transient_id()?;
+        let transient_index_id = self.allocate_transient_id()?;
+        let timestamp = self.statistics_timestamp(index_id, compute_instance);
+        let mut dataflow = DataflowDesc::new(format!("statistics-{}", index_id));
+        dataflow.set_as_of(Antichain::from_elem(timestamp));
+        let mut builder = self.dataflow_builder(compute_instance);
+        builder.import_view_into_dataflow(
+            &view_id,
+            &OptimizedMirRelationExpr::declare_optimized(totals),
+            &mut dataflow,
+        )?;
+        dataflow.export_index(
+            transient_index_id,
+            IndexDesc {
+                on_id: view_id,
+                key: key.clone(),
+            },
+            typ,
+        );
+        mz_transform::optimize_dataflow(&mut dataflow, &builder.index_oracle())?;
+        let dataflow_plan = self.finalize_dataflow(dataflow, compute_instance);
+        let fast_path = fast_path_peek::create_plan(
+            dataflow_plan,
+            view_id,
+            transient_index_id,
+            key,
+            permutation,
+            thinning.len(),
+        )?;
+        let finishing = RowSetFinishing {
+            order_by: vec![],
+            limit: None,
+            offset: 0,
+            project: (0..arity).collect(),
+        };
+        let resp = self
+            .implement_fast_path_peek(
+                fast_path,
+                timestamp,
+                finishing,
+                SYSTEM_CONN_ID,
+                arity,
+                compute_instance,
+                false,
+                PeekLimits::default(),
+            )
+            .await?;
+
+        self.statistics.in_flight.insert(index_id);
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(|| format!("collect_statistics:{index_id}"), async move {
+            let result = match resp {
+                ExecuteResponse::SendingRows(rows) => match rows.await {
+                    PeekResponseUnary::Rows(rows) => Ok(unpack_totals(rows)),
+                    PeekResponseUnary::Error(e) => Err(e),
+                    PeekResponseUnary::Canceled | PeekResponseUnary::TimedOut => {
+                        Err("canceled".into())
+                    }
+                },
+                _ => Err("expected SendingRows".into()),
+            };
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::StatisticsCollected(StatisticsCollected {
+                index_id,
+                result,
+            }));
+        });
+        Ok(())
+    }
+
+    /// Returns the latest time at which the index `index_id` can be read
+    /// without waiting.
+    fn statistics_timestamp(
+        &self,
+        index_id: GlobalId,
+        compute_instance: ComputeInstanceId,
+    ) -> Timestamp {
+        let mut id_bundle = CollectionIdBundle::default();
+        id_bundle.compute_ids.insert(index_id);
+        let since = self.least_valid_read(&id_bundle, compute_instance);
+        let timestamp = self.largest_closed_timestamp(&id_bundle, compute_instance);
+        since
+            .elements()
+            .iter()
+            .copied()
+            .fold(timestamp, std::cmp::max)
+    }
+
+    /// Records the statistics collected about an index in `mz_statistics`.
+    pub(super) async fn message_statistics_collected(
+        &mut self,
+        StatisticsCollected { index_id, result }: StatisticsCollected,
+    ) {
+        self.statistics.in_flight.remove(&index_id);
+        let (row_count, distinct_keys) = match result {
+            Ok(totals) => totals,
+            Err(e) => {
+                warn!(
+                    "failed to collect statistics about index {}: {}",
+                    index_id, e
+                );
+                return;
+            }
+        };
+        // The index may have been dropped while its statistics were collected.
+        let on_id = match self.catalog.try_get_entry(&index_id).map(|e| e.item()) {
+            Some(CatalogItem::Index(index)) => index.on,
+            _ => return,
+        };
+        let stats = IndexStatistics {
+            on_id,
+            row_count,
+            distinct_keys,
+            collected_at: self.now(),
+        };
+        if let Err(e) = self.catalog.set_statistics(index_id, Some(&stats)) {
+            warn!("failed to store statistics about index {}: {}", index_id, e);
+            return;
+        }
+        let mut updates = vec![self.pack_statistics_update(index_id, &stats, 1)];
+        if let Some(old) = self.statistics.indexes.insert(index_id, stats) {
+            updates.push(self.pack_statistics_update(index_id, &old, -1));
+        }
+        self.send_builtin_table_updates(updates).await;
+    }
+
+    /// Loads the statistics stored in the catalog, returning the updates that
+    /// report them in `mz_statistics`.
+    ///
+    /// Must be called before the dataflows of indexes are built, so that they
+    /// are planned as they were before the coordinator restarted.
+    pub(super) fn restore_statistics(&mut self) -> Result<Vec<BuiltinTableUpdate>, CoordError> {
+        let mut updates = vec![];
+        for (name, stats) in self.catalog.load_statistics::<IndexStatistics>()? {
+            let index_id = match name.parse::<GlobalId>() {
+                Ok(index_id) => index_id,
+                Err(_) => {
+                    warn!("ignoring statistics about unknown index {}", name);
+                    continue;
+                }
+            };
+            // The index may have been dropped before its statistics were
+            // removed.
+            match self.catalog.try_get_entry(&index_id).map(|e| e.item()) {
+                Some(CatalogItem::Index(_)) => {
+                    updates.push(self.pack_statistics_update(index_id, &stats, 1));
+                    self.statistics.indexes.insert(index_id, stats);
+                }
+                _ => self
+                    .catalog
+                    .set_statistics::<IndexStatistics>(index_id, None)?,
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Forgets the statistics about the index `id`, which has been dropped,
+    /// returning the update that removes them from `mz_statistics`, if any.
+    pub(super) fn forget_statistics(&mut self, id: GlobalId) -> Option<BuiltinTableUpdate> {
+        let stats = self.statistics.indexes.remove(&id)?;
+        // Statistics that fail to be removed are removed when the coordinator
+        // next starts.
+        if let Err(e) = self.catalog.set_statistics::<IndexStatistics>(id, None) {
+            warn!("failed to remove statistics about index {}: {}", id, e);
+        }
+        Some(self.pack_statistics_update(id, &stats, -1))
+    }
+
+    fn pack_statistics_update(
+        &self,
+        index_id: GlobalId,
+        stats: &IndexStatistics,
+        diff: i64,
+    ) -> BuiltinTableUpdate {
+        BuiltinTableUpdate {
+            id: self.catalog.resolve_builtin_table(&MZ_STATISTICS),
+            row: Row::pack_slice(&[
+                Datum::String(&index_id.to_string()),
+                Datum::String(&stats.on_id.to_string()),
+                Datum::Int64(i64::try_from(stats.row_count).unwrap_or(i64::MAX)),
+                Datum::Int64(i64::try_from(stats.distinct_keys).unwrap_or(i64::MAX)),
+                Datum::TimestampTz(to_datetime(stats.collected_at)),
+            ]),
+            diff,
+        }
+    }
+}
+
+/// Unpacks the number of rows and the number of distinct keys from the result
+/// of a collection, which has no rows if the collection is empty.
+fn unpack_totals(rows: Vec<Row>) -> (u64, u64) {
+    match rows.first() {
+        None => (0, 0),
+        Some(row) => {
+            let datums = row.unpack();
+            let row_count = u64::try_from(datums[0].unwrap_numeric().0).unwrap_or(0);
+            let distinct_keys = u64::try_from(datums[1].unwrap_int64()).unwrap_or(0);
+            (row_count, distinct_keys)
+        }
+    }
+}
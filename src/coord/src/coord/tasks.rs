@@ -0,0 +1,320 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Scheduled tasks, which execute a statement at a fixed interval.
+//!
+//! Each run of a task executes the task's statement in a connection of its
+//! own, opened on behalf of the task's owner. The connection is driven through
+//! the same commands that a client would send, submitted on the coordinator's
+//! internal command channel, so that the statement is planned, checked against
+//! the owner's privileges, and sequenced exactly as if the owner had executed
+//! it. A run that is still in progress when the task is next due is not
+//! overlapped; the task instead skips that run.
+//!
+//! The outcomes of the most recent runs of each task are reported in
+//! `mz_scheduled_task_runs`. Like the schedules themselves, which restart when
+//! Materialize restarts, the outcomes are not durable.
+
+use std::collections::VecDeque;
+use std::iter;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::sync::{mpsc, oneshot, watch};
+
+use mz_dataflow_types::PeekResponseUnary;
+use mz_expr::GlobalId;
+use mz_ore::collections::CollectionExt;
+use mz_ore::now::{to_datetime, EpochMillis};
+use mz_ore::task;
+use mz_repr::{Datum, Row};
+use mz_sql::ast::{Raw, Statement};
+
+use crate::catalog::builtin::MZ_SCHEDULED_TASK_RUNS;
+use crate::catalog::BuiltinTableUpdate;
+use crate::command::{Canceled, Command, ExecuteResponse, Response};
+use crate::coord::{Coordinator, Message};
+use crate::error::CoordError;
+use crate::session::{EndTransactionAction, Session};
+
+/// The number of runs of each task that are reported in
+/// `mz_scheduled_task_runs`.
+const RETAINED_RUNS: usize = 100;
+
+/// The state of a scheduled task.
+#[derive(Debug, Default)]
+pub(crate) struct ScheduledTask {
+    /// Whether a run of the task is in progress.
+    running: bool,
+    /// The task's rows in `mz_scheduled_task_runs`, oldest first.
+    runs: VecDeque<Row>,
+}
+
+/// A run of a scheduled task that has finished.
+#[derive(Debug)]
+pub struct ScheduledTaskFinished {
+    pub id: GlobalId,
+    /// The connection in which the run executed.
+    pub conn_id: u32,
+    pub started_at: EpochMillis,
+    /// The outcome of the run, or the error that the run failed with.
+    pub result: Result<(), String>,
+}
+
+impl Coordinator {
+    /// Starts scheduling runs of the task `id`, the first of which is due one
+    /// interval from now.
+    pub(super) fn install_scheduled_task(&mut self, id: GlobalId) {
+        let schedule = self
+            .catalog
+            .get_entry(&id)
+            .task()
+            .expect("scheduled item is a task")
+            .schedule;
+        self.scheduled_tasks.insert(id, ScheduledTask::default());
+        self.schedule_task_run(id, schedule);
+    }
+
+    /// Arranges for the task `id` to be due after `delay`.
+    fn schedule_task_run(&self, id: GlobalId, delay: Duration) {
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(|| format!("scheduled_task_due:{id}"), async move {
+            tokio::time::sleep(delay).await;
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::ScheduledTaskDue(id));
+        });
+    }
+
+    pub(super) async fn message_scheduled_task_due(&mut self, id: GlobalId) {
+        // The task may have been dropped, in which case it is no longer
+        // scheduled.
+        let running = match self.scheduled_tasks.get(&id) {
+            Some(state) => state.running,
+            None => return,
+        };
+        let entry = self.catalog.get_entry(&id);
+        let task = entry.task().expect("scheduled item is a task");
+        self.schedule_task_run(id, task.schedule);
+        if running {
+            return;
+        }
+
+        let started_at = self.now();
+        let user = match self.catalog.try_get_role_by_id(entry.owner_id()) {
+            Some(role) => role.name.clone(),
+            None => {
+                let err = format!("owner of task {} no longer exists", entry.name().item);
+                self.record_task_run(id, started_at, Err(err)).await;
+                return;
+            }
+        };
+        let conn_id = match self.task_conn_ids.alloc() {
+            Some(conn_id) => conn_id,
+            None => {
+                let err = CoordError::IdExhaustionError.to_string();
+                self.record_task_run(id, started_at, Err(err)).await;
+                return;
+            }
+        };
+        let stmt = match mz_sql::parse::parse(&task.create_sql)
+            .expect("create_sql cannot be invalid")
+            .into_element()
+        {
+            Statement::CreateTask(stmt) => Statement::Insert(stmt.statement),
+            _ => unreachable!(),
+        };
+
+        self.scheduled_tasks.get_mut(&id).unwrap().running = true;
+        let internal_cmd_tx = self.internal_cmd_tx.clone();
+        task::spawn(|| format!("scheduled_task_run:{id}"), async move {
+            let mut conn = TaskConn {
+                internal_cmd_tx: internal_cmd_tx.clone(),
+                session: Some(Session::new(conn_id, user)),
+                started: false,
+            };
+            let result = conn.run(stmt).await.map_err(|e| e.to_string());
+            conn.terminate();
+            // If sending fails, the main thread has shutdown.
+            let _ = internal_cmd_tx.send(Message::ScheduledTaskFinished(ScheduledTaskFinished {
+                id,
+                conn_id,
+                started_at,
+                result,
+            }));
+        });
+    }
+
+    pub(super) async fn message_scheduled_task_finished(
+        &mut self,
+        finished: ScheduledTaskFinished,
+    ) {
+        self.task_conn_ids.free(finished.conn_id);
+        self.record_task_run(finished.id, finished.started_at, finished.result)
+            .await;
+    }
+
+    /// Reports the outcome of a run of the task `id` in
+    /// `mz_scheduled_task_runs`, retracting the task's oldest run if the task
+    /// has more than [`RETAINED_RUNS`] runs.
+    async fn record_task_run(
+        &mut self,
+        id: GlobalId,
+        started_at: EpochMillis,
+        result: Result<(), String>,
+    ) {
+        let finished_at = self.now();
+        // The task may have been dropped during the run.
+        let state = match self.scheduled_tasks.get_mut(&id) {
+            Some(state) => state,
+            None => return,
+        };
+        state.running = false;
+        let (status, error) = match &result {
+            Ok(()) => ("success", None),
+            Err(e) => ("error", Some(e.as_str())),
+        };
+        let row = Row::pack_slice(&[
+            Datum::String(&id.to_string()),
+            Datum::TimestampTz(to_datetime(started_at)),
+            Datum::TimestampTz(to_datetime(finished_at)),
+            Datum::String(status),
+            Datum::from(error),
+        ]);
+        state.runs.push_back(row.clone());
+        let mut retractions = vec![];
+        while state.runs.len() > RETAINED_RUNS {
+            retractions.push(state.runs.pop_front().unwrap());
+        }
+
+        let table = self.catalog.resolve_builtin_table(&MZ_SCHEDULED_TASK_RUNS);
+        let updates = iter::once((row, 1))
+            .chain(retractions.into_iter().map(|row| (row, -1)))
+            .map(|(row, diff)| BuiltinTableUpdate {
+                id: table,
+                row,
+                diff,
+            })
+            .collect();
+        self.send_builtin_table_updates(updates).await;
+    }
+
+    /// Stops scheduling runs of the tasks `ids` and retracts their runs from
+    /// `mz_scheduled_task_runs`. Runs in progress are left to finish.
+    pub(super) async fn drop_scheduled_tasks(&mut self, ids: Vec<GlobalId>) {
+        let table = self.catalog.resolve_builtin_table(&MZ_SCHEDULED_TASK_RUNS);
+        let mut updates = vec![];
+        for id in ids {
+            if let Some(state) = self.scheduled_tasks.remove(&id) {
+                updates.extend(state.runs.into_iter().map(|row| BuiltinTableUpdate {
+                    id: table,
+                    row,
+                    diff: -1,
+                }));
+            }
+        }
+        self.send_builtin_table_updates(updates).await;
+    }
+}
+
+/// A connection in which a run of a scheduled task executes.
+///
+/// This is the counterpart of [`SessionClient`](crate::client::SessionClient)
+/// for commands that the coordinator submits to itself.
+struct TaskConn {
+    internal_cmd_tx: mpsc::UnboundedSender<Message>,
+    /// The connection's session, which is `None` only while a command is
+    /// outstanding or if the coordinator shut down during one.
+    session: Option<Session>,
+    /// Whether the connection was successfully started up, and so must be
+    /// terminated.
+    started: bool,
+}
+
+impl TaskConn {
+    /// Executes `stmt` in an implicit transaction of its own.
+    async fn run(&mut self, stmt: Statement<Raw>) -> Result<(), CoordError> {
+        // Nothing cancels the runs of a task, but startup requires a channel
+        // through which to do so.
+        let (cancel_tx, _cancel_rx) = watch::channel(Canceled::NotCanceled);
+        self.send(|tx, session| Command::Startup {
+            session,
+            create_user_if_not_exists: false,
+            cancel_tx: Arc::new(cancel_tx),
+            tx,
+        })
+        .await?;
+        self.started = true;
+
+        const EMPTY_PORTAL: &str = "";
+        self.send(|tx, session| Command::StartTransaction {
+            implicit: Some(1),
+            session,
+            tx,
+        })
+        .await?;
+        self.send(|tx, session| Command::Declare {
+            name: EMPTY_PORTAL.into(),
+            stmt,
+            param_types: vec![],
+            session,
+            tx,
+        })
+        .await?;
+        let response = self
+            .send(|tx, session| Command::Execute {
+                portal_name: EMPTY_PORTAL.into(),
+                session,
+                tx,
+            })
+            .await?;
+        // An `INSERT` with a `RETURNING` clause returns the inserted rows,
+        // which are discarded once it is known that they were computed.
+        if let ExecuteResponse::SendingRows(rows) = response {
+            match rows.await {
+                PeekResponseUnary::Rows(_) => (),
+                PeekResponseUnary::Error(e) => coord_bail!("{}", e),
+                PeekResponseUnary::Canceled => coord_bail!("execution canceled"),
+                PeekResponseUnary::TimedOut => return Err(CoordError::StatementTimeout),
+            }
+        }
+        self.send(|tx, session| Command::Commit {
+            action: EndTransactionAction::Commit,
+            session,
+            tx,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Terminates the connection, if it was started up.
+    fn terminate(&mut self) {
+        if let (true, Some(session)) = (self.started, self.session.take()) {
+            // If sending fails, the main thread has shutdown.
+            let _ = self
+                .internal_cmd_tx
+                .send(Message::Command(Command::Terminate { session }));
+        }
+    }
+
+    async fn send<T, F>(&mut self, f: F) -> Result<T, CoordError>
+    where
+        F: FnOnce(oneshot::Sender<Response<T>>, Session) -> Command,
+    {
+        let session = self.session.take().expect("session invariant violated");
+        let (tx, rx) = oneshot::channel();
+        let shut_down = || CoordError::Unstructured(anyhow!("coordinator shut down"));
+        self.internal_cmd_tx
+            .send(Message::Command(f(tx, session)))
+            .map_err(|_| shut_down())?;
+        let response = rx.await.map_err(|_| shut_down())?;
+        self.session = Some(response.session);
+        response.result
+    }
+}
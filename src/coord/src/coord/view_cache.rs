@@ -0,0 +1,174 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A cache of the imports that catalog views contribute to dataflows.
+//!
+//! Importing a view into a dataflow walks the chain of views beneath it down
+//! to the nearest indexes and sources, cloning each view's optimized
+//! expression along the way. The cache remembers the result of that walk for
+//! each view and compute instance, so that creating many dataflows over the
+//! same deep stack of views walks it only once.
+//!
+//! An entry remains valid for as long as the items it was built from are
+//! unchanged and the indexes available on them are the same. The coordinator
+//! invalidates entries when items are dropped or altered and when indexes are
+//! created or dropped.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::{BuildDesc, DataflowDesc};
+use mz_expr::GlobalId;
+use mz_ore::cast::CastFrom;
+use mz_ore::metric;
+use mz_ore::metrics::{IntCounter, MetricsRegistry, UIntGauge};
+
+/// The imports of a view into a dataflow.
+#[derive(Debug)]
+pub struct CachedView {
+    /// A dataflow that consists of only the imports of the view, including
+    /// the view itself as the last object to build.
+    imports: DataflowDesc,
+    /// The IDs of the items that were consulted to determine the imports.
+    depends_on: BTreeSet<GlobalId>,
+}
+
+impl CachedView {
+    /// Records the imports in `imports`, which must consist of only the
+    /// imports of a view.
+    pub fn new(imports: DataflowDesc) -> CachedView {
+        let mut depends_on = BTreeSet::new();
+        for (index_id, (desc, _)) in &imports.index_imports {
+            depends_on.insert(*index_id);
+            depends_on.insert(desc.on_id);
+        }
+        depends_on.extend(imports.source_imports.keys().copied());
+        depends_on.extend(imports.objects_to_build.iter().map(|build| build.id));
+        CachedView {
+            imports,
+            depends_on,
+        }
+    }
+
+    /// Returns the IDs of the sources that the view imports.
+    pub fn source_ids(&self) -> impl Iterator<Item = GlobalId> + '_ {
+        self.imports.source_imports.keys().copied()
+    }
+
+    /// Adds the imports to `dataflow`, skipping those that it already has.
+    pub fn import_into(&self, dataflow: &mut DataflowDesc) {
+        for (id, (desc, typ)) in &self.imports.index_imports {
+            dataflow.import_index(*id, desc.clone(), typ.clone());
+        }
+        for (id, source) in &self.imports.source_imports {
+            if !dataflow.is_imported(id) {
+                dataflow.source_imports.insert(*id, source.clone());
+            }
+        }
+        // The objects are in dependency order, and any object that the
+        // dataflow already has was added along with its own dependencies.
+        for BuildDesc { id, plan } in &self.imports.objects_to_build {
+            if !dataflow.is_imported(id) {
+                dataflow.insert_plan(*id, plan.clone());
+            }
+        }
+    }
+}
+
+/// A cache of the imports of views into dataflows, by compute instance and
+/// view ID.
+#[derive(Debug)]
+pub struct ViewCache {
+    entries: RefCell<HashMap<(ComputeInstanceId, GlobalId), Rc<CachedView>>>,
+    metrics: ViewCacheMetrics,
+}
+
+impl ViewCache {
+    /// Creates an empty cache whose metrics are registered with `registry`.
+    pub fn new(registry: &MetricsRegistry) -> ViewCache {
+        ViewCache {
+            entries: RefCell::new(HashMap::new()),
+            metrics: ViewCacheMetrics::register_with(registry),
+        }
+    }
+
+    /// Returns the imports of the view `id` into dataflows on the compute
+    /// instance `instance`, if they are cached.
+    pub fn get(&self, instance: ComputeInstanceId, id: GlobalId) -> Option<Rc<CachedView>> {
+        let entry = self.entries.borrow().get(&(instance, id)).cloned();
+        match entry {
+            Some(_) => self.metrics.hits.inc(),
+            None => self.metrics.misses.inc(),
+        }
+        entry
+    }
+
+    /// Caches the imports of the view `id` into dataflows on the compute
+    /// instance `instance`.
+    pub fn insert(&self, instance: ComputeInstanceId, id: GlobalId, view: Rc<CachedView>) {
+        let mut entries = self.entries.borrow_mut();
+        entries.insert((instance, id), view);
+        self.metrics.entries.set(u64::cast_from(entries.len()));
+    }
+
+    /// Invalidates the entries that were built from the item `id`.
+    pub fn invalidate(&self, id: GlobalId) {
+        let mut entries = self.entries.borrow_mut();
+        let len = entries.len();
+        entries.retain(|_, view| !view.depends_on.contains(&id));
+        self.metrics
+            .invalidations
+            .inc_by(u64::cast_from(len - entries.len()));
+        self.metrics.entries.set(u64::cast_from(entries.len()));
+    }
+
+    /// Invalidates the entries for the compute instance `instance`.
+    pub fn invalidate_instance(&self, instance: ComputeInstanceId) {
+        let mut entries = self.entries.borrow_mut();
+        let len = entries.len();
+        entries.retain(|(entry_instance, _), _| *entry_instance != instance);
+        self.metrics
+            .invalidations
+            .inc_by(u64::cast_from(len - entries.len()));
+        self.metrics.entries.set(u64::cast_from(entries.len()));
+    }
+}
+
+#[derive(Debug)]
+struct ViewCacheMetrics {
+    hits: IntCounter,
+    misses: IntCounter,
+    invalidations: IntCounter,
+    entries: UIntGauge,
+}
+
+impl ViewCacheMetrics {
+    fn register_with(registry: &MetricsRegistry) -> ViewCacheMetrics {
+        ViewCacheMetrics {
+            hits: registry.register(metric!(
+                name: "mz_view_cache_hits_total",
+                help: "The number of times the imports of a view into a dataflow were found in the view cache.",
+            )),
+            misses: registry.register(metric!(
+                name: "mz_view_cache_misses_total",
+                help: "The number of times the imports of a view into a dataflow were not found in the view cache.",
+            )),
+            invalidations: registry.register(metric!(
+                name: "mz_view_cache_invalidations_total",
+                help: "The number of entries removed from the view cache because the items they were built from changed.",
+            )),
+            entries: registry.register(metric!(
+                name: "mz_view_cache_entries",
+                help: "The number of entries in the view cache.",
+            )),
+        }
+    }
+}
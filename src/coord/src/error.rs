@@ -10,6 +10,7 @@
 use std::error::Error;
 use std::fmt;
 use std::num::TryFromIntError;
+use std::time::Duration;
 
 use dec::TryFromDecimalError;
 
@@ -18,6 +19,7 @@ use mz_expr::{EvalError, UnmaterializableFunc};
 use mz_ore::stack::RecursionLimitError;
 use mz_ore::str::StrExt;
 use mz_repr::NotNullViolation;
+use mz_secrets::SecretFormat;
 use mz_sql::query_model::QGMError;
 use mz_transform::TransformError;
 
@@ -38,6 +40,23 @@ pub enum CoordError {
     Catalog(catalog::Error),
     /// The cached plan or descriptor changed.
     ChangedPlan,
+    /// The dataflows of the named cluster are estimated to use more memory
+    /// than `cluster_memory_limit` permits.
+    ClusterMemoryExceeded {
+        cluster: String,
+        usage: u64,
+        limit: usize,
+    },
+    /// The named role is not permitted to use the named cluster.
+    ClusterNotPermitted {
+        role: String,
+        cluster: String,
+    },
+    /// The named role is not permitted to connect over the connection.
+    ConnectionNotPermitted {
+        role: String,
+        reason: String,
+    },
     /// The specified session parameter is constrained to a finite set of values.
     ConstrainedParameter {
         parameter: &'static (dyn Var + Send + Sync),
@@ -46,6 +65,8 @@ pub enum CoordError {
     },
     /// The cursor already exists.
     DuplicateCursor(String),
+    /// The named replication slot already exists.
+    DuplicateReplicationSlot(String),
     /// An error while evaluating an expression.
     Eval(EvalError),
     /// The specified parameter is fixed to a single specific value.
@@ -70,18 +91,41 @@ pub enum CoordError {
         value: String,
         reason: String,
     },
+    /// The contents of a secret do not conform to the secret's format.
+    InvalidSecret {
+        format: SecretFormat,
+        reason: String,
+    },
     /// The selection value for a table mutation operation refers to an invalid object.
     InvalidTableMutationSelection,
     /// Expression violated a column's constraint
     ConstraintViolation(NotNullViolation),
+    /// The named role is not permitted to start sessions.
+    LoginNotPermitted(String),
+    /// The named role is not permitted to run the named operation.
+    OperationNotPermitted {
+        role: String,
+        operation: String,
+    },
     /// The named operation cannot be run in a transaction.
     OperationProhibitsTransaction(String),
     /// The named operation requires an active transaction.
     OperationRequiresTransaction(String),
+    /// The named role does not hold a privilege that the operation requires.
+    PermissionDenied {
+        role: String,
+        privilege: String,
+    },
     /// A persistence-related error.
     Persistence(mz_persist::error::Error),
     /// The named prepared statement already exists.
     PreparedStatementExists(String),
+    /// A peek waited too long for a free slot on its cluster.
+    PeekQueueTimeout {
+        cluster: String,
+        limit: usize,
+        timeout: Duration,
+    },
     /// An error occurred in the QGM stage of the optimizer.
     QGM(QGMError),
     /// The transaction is in read-only mode.
@@ -90,6 +134,11 @@ pub enum CoordError {
     ReadOnlyParameter(&'static (dyn Var + Send + Sync)),
     /// The recursion limit of some operation was exceeded.
     RecursionLimit(RecursionLimitError),
+    /// The named replication slot is in use by another connection.
+    ReplicationSlotActive(String),
+    /// The result of a query has more rows than the session's
+    /// `max_result_rows` allows.
+    ResultRowsExceeded(usize),
     /// A query in a transaction referenced a relation outside the first query's
     /// time domain.
     RelationOutsideTimeDomain {
@@ -98,10 +147,21 @@ pub enum CoordError {
     },
     /// The specified feature is not permitted in safe mode.
     SafeModeViolation(String),
+    /// The contents of a secret exceed the maximum size of a secret.
+    SecretTooLarge {
+        size: usize,
+        limit: usize,
+    },
     /// An error occurred in a SQL catalog operation.
     SqlCatalog(mz_sql::catalog::CatalogError),
+    /// A statement ran for longer than the session's statement timeout.
+    StatementTimeout,
     /// The transaction is in single-tail mode.
     TailOnlyTransaction,
+    /// The server is already serving the maximum number of connections.
+    TooManyConnections {
+        limit: usize,
+    },
     /// An error occurred in the MIR stage of the optimizer.
     Transform(TransformError),
     /// The named cursor does not exist.
@@ -111,6 +171,10 @@ pub enum CoordError {
     /// The named parameter is unknown to the system.
     UnknownParameter(String),
     UnknownPreparedStatement(String),
+    /// The named publication does not exist.
+    UnknownPublication(String),
+    /// The named replication slot does not exist.
+    UnknownReplicationSlot(String),
     /// A generic error occurred.
     //
     // TODO(benesch): convert all those errors to structured errors.
@@ -183,6 +247,22 @@ impl CoordError {
                     ),
                 }
             )),
+            CoordError::ClusterMemoryExceeded {
+                cluster,
+                usage,
+                limit,
+            } => Some(format!(
+                "The arrangements of cluster {} hold an estimated {} bytes, \
+                 which exceeds the limit of {} bytes.",
+                cluster.quoted(),
+                usage,
+                limit
+            )),
+            CoordError::PeekQueueTimeout { cluster, limit, .. } => Some(format!(
+                "Cluster {} runs at most {} queries concurrently.",
+                cluster.quoted(),
+                limit
+            )),
             CoordError::SafeModeViolation(_) => Some(
                 "The Materialize server you are connected to is running in \
                  safe mode, which limits the features that are available."
@@ -247,6 +327,9 @@ impl CoordError {
                 INDEX {} SET ENABLED",
                 idx.quoted()
             )),
+            CoordError::OperationNotPermitted { .. } => {
+                Some("Connect as the \"mz_system\" user to run this operation.".into())
+            }
             CoordError::UnknownLoginRole(_) => {
                 // TODO(benesch): this will be a bad hint when people are used
                 // to creating roles in Materialize, since they might drop the
@@ -257,6 +340,14 @@ impl CoordError {
                 // because that leaks information to unauthenticated clients.)
                 Some("Try connecting as the \"materialize\" user.".into())
             }
+            CoordError::ClusterMemoryExceeded { .. } => Some(
+                "Drop indexes or materialized views on the cluster, run the query \
+                 on a different cluster, or raise cluster_memory_limit."
+                    .into(),
+            ),
+            CoordError::PeekQueueTimeout { .. } => {
+                Some("Retry the query later, or run it on a different cluster.".into())
+            }
             CoordError::InvalidRematerialization { source_type, .. } => {
                 let doc_page = match source_type {
                     RematerializedSourceType::Postgres => "postgres",
@@ -287,6 +378,20 @@ impl fmt::Display for CoordError {
             }
             CoordError::ChangedPlan => f.write_str("cached plan must not change result type"),
             CoordError::Catalog(e) => e.fmt(f),
+            CoordError::ClusterMemoryExceeded { cluster, .. } => write!(
+                f,
+                "cluster {} has exceeded its memory limit",
+                cluster.quoted()
+            ),
+            CoordError::ClusterNotPermitted { role, cluster } => write!(
+                f,
+                "role {} is not permitted to use cluster {}",
+                role.quoted(),
+                cluster.quoted()
+            ),
+            CoordError::ConnectionNotPermitted { role, reason } => {
+                write!(f, "role {} may not connect {}", role.quoted(), reason)
+            }
             CoordError::ConstrainedParameter {
                 parameter, value, ..
             } => write!(
@@ -298,6 +403,9 @@ impl fmt::Display for CoordError {
             CoordError::DuplicateCursor(name) => {
                 write!(f, "cursor {} already exists", name.quoted())
             }
+            CoordError::DuplicateReplicationSlot(name) => {
+                write!(f, "replication slot {} already exists", name.quoted())
+            }
             CoordError::Eval(e) => e.fmt(f),
             CoordError::FixedValueParameter(p) => write!(
                 f,
@@ -334,28 +442,59 @@ impl fmt::Display for CoordError {
                 value.quoted(),
                 reason,
             ),
+            CoordError::InvalidSecret { format, reason } => {
+                write!(f, "secret contents are not valid {}: {}", format, reason)
+            }
             CoordError::InvalidTableMutationSelection => {
                 f.write_str("invalid selection: operation may only refer to user-defined tables")
             }
             CoordError::ConstraintViolation(not_null_violation) => {
                 write!(f, "{}", not_null_violation)
             }
+            CoordError::LoginNotPermitted(role) => {
+                write!(f, "role {} is not permitted to log in", role.quoted())
+            }
+            CoordError::OperationNotPermitted { role, operation } => write!(
+                f,
+                "role {} is not permitted to call {}",
+                role.quoted(),
+                operation
+            ),
             CoordError::OperationProhibitsTransaction(op) => {
                 write!(f, "{} cannot be run inside a transaction block", op)
             }
             CoordError::OperationRequiresTransaction(op) => {
                 write!(f, "{} can only be used in transaction blocks", op)
             }
+            CoordError::PermissionDenied { role, privilege } => {
+                write!(f, "role {} does not have {}", role.quoted(), privilege)
+            }
             CoordError::Persistence(error) => error.fmt(f),
             CoordError::PreparedStatementExists(name) => {
                 write!(f, "prepared statement {} already exists", name.quoted())
             }
+            CoordError::PeekQueueTimeout {
+                cluster, timeout, ..
+            } => write!(
+                f,
+                "query timed out after {:?} waiting to run on cluster {}",
+                timeout,
+                cluster.quoted()
+            ),
             CoordError::QGM(e) => e.fmt(f),
             CoordError::ReadOnlyTransaction => f.write_str("transaction in read-only mode"),
             CoordError::ReadOnlyParameter(p) => {
                 write!(f, "parameter {} cannot be changed", p.name().quoted())
             }
             CoordError::RecursionLimit(e) => e.fmt(f),
+            CoordError::ReplicationSlotActive(name) => write!(
+                f,
+                "replication slot {} is active for another connection",
+                name.quoted()
+            ),
+            CoordError::ResultRowsExceeded(max) => {
+                write!(f, "result exceeds max_result_rows of {}", max)
+            }
             CoordError::RelationOutsideTimeDomain { .. } => {
                 write!(
                     f,
@@ -366,10 +505,21 @@ impl fmt::Display for CoordError {
             CoordError::SafeModeViolation(feature) => {
                 write!(f, "cannot create {} in safe mode", feature)
             }
+            CoordError::SecretTooLarge { size, limit } => write!(
+                f,
+                "secret is too large: {} bytes exceeds the limit of {} bytes",
+                size, limit
+            ),
             CoordError::SqlCatalog(e) => e.fmt(f),
+            CoordError::StatementTimeout => {
+                f.write_str("canceling statement due to statement timeout")
+            }
             CoordError::TailOnlyTransaction => {
                 f.write_str("TAIL in transactions must be the only read statement")
             }
+            CoordError::TooManyConnections { limit } => {
+                write!(f, "too many connections: the limit is {}", limit)
+            }
             CoordError::Transform(e) => e.fmt(f),
             CoordError::UnknownCursor(name) => {
                 write!(f, "cursor {} does not exist", name.quoted())
@@ -377,6 +527,7 @@ impl fmt::Display for CoordError {
             CoordError::UnknownLoginRole(name) => {
                 write!(f, "role {} does not exist", name.quoted())
             }
+
             CoordError::UnknownParameter(name) => {
                 write!(f, "unrecognized configuration parameter {}", name.quoted())
             }
@@ -389,6 +540,12 @@ impl fmt::Display for CoordError {
             CoordError::UnknownPreparedStatement(name) => {
                 write!(f, "prepared statement {} does not exist", name.quoted())
             }
+            CoordError::UnknownPublication(name) => {
+                write!(f, "publication {} does not exist", name.quoted())
+            }
+            CoordError::UnknownReplicationSlot(name) => {
+                write!(f, "replication slot {} does not exist", name.quoted())
+            }
         }
     }
 }
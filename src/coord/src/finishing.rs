@@ -0,0 +1,560 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Result finishing that spills to disk.
+//!
+//! [`RowSetFinishing::finish`] sorts the rows of a query result in memory. A
+//! [`SpillingFinisher`] instead accumulates rows only until they exceed a
+//! memory limit, at which point it sorts them and writes them as a "run" to an
+//! anonymous temporary file. Finishing merges the runs with any rows still in
+//! memory, so only one row per run need be resident at a time.
+//!
+//! At most [`MERGE_WIDTH`] runs are merged at once, which bounds the number of
+//! temporary files that are open. Whenever [`MERGE_WIDTH`] runs of the same
+//! level accumulate, they are merged into a single run of the next level, and
+//! finishing merges runs in as many passes as necessary. Each row is thus
+//! rewritten a number of times logarithmic in the size of the result.
+//!
+//! When the finishing has a limit, a run never needs to contain more than
+//! `offset + limit` rows, as no later rows can make it into the result.
+
+use std::cmp::{self, Ordering};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::mem;
+use std::num::NonZeroUsize;
+
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use mz_expr::{compare_columns, RowSetFinishing};
+use mz_ore::cast::CastFrom;
+use mz_repr::{DatumVec, Row};
+
+/// The maximum number of runs that are merged at once.
+pub const MERGE_WIDTH: usize = 16;
+
+/// The minimum number of rows in a run.
+///
+/// Without a minimum, rows larger than the memory limit would each be spilled
+/// to a run of their own. The memory limit is exceeded instead.
+pub const MIN_RUN_ROWS: usize = 64;
+
+/// Statistics about the temporary files written by a [`SpillingFinisher`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpillStats {
+    /// The number of sorted runs spilled from memory.
+    pub runs: usize,
+    /// The number of times that runs were merged into a larger run.
+    pub merges: usize,
+    /// The number of rows written, not counting duplicates.
+    pub rows: usize,
+    /// The number of bytes written.
+    pub bytes: u64,
+}
+
+/// Applies a [`RowSetFinishing`] to a result that arrives in batches, spilling
+/// to disk if the result exceeds a memory limit.
+#[derive(Debug)]
+pub struct SpillingFinisher {
+    finishing: RowSetFinishing,
+    memory_limit: Option<usize>,
+    rows: Vec<(Row, NonZeroUsize)>,
+    rows_size: usize,
+    /// The number of rows and bytes in the finished result so far, if every
+    /// row pushed is part of the finished result.
+    result_size: Option<(usize, usize)>,
+    row_buf: Row,
+    /// The spilled runs, each with its level. Runs spilled from memory have
+    /// level zero, and a run merged from runs of level `n` has level `n + 1`.
+    runs: Vec<(File, usize)>,
+    stats: SpillStats,
+}
+
+impl SpillingFinisher {
+    /// Constructs a finisher that keeps at most `memory_limit` bytes of rows
+    /// in memory before spilling. If `memory_limit` is `None`, the finisher
+    /// never spills.
+    pub fn new(finishing: RowSetFinishing, memory_limit: Option<usize>) -> SpillingFinisher {
+        SpillingFinisher {
+            memory_limit,
+            // Without a limit or an offset, the finishing drops no rows, so
+            // the size of the result is known as rows arrive.
+            result_size: (finishing.limit.is_none() && finishing.offset == 0).then(|| (0, 0)),
+            row_buf: Row::default(),
+            finishing,
+            rows: vec![],
+            rows_size: 0,
+            runs: vec![],
+            stats: SpillStats::default(),
+        }
+    }
+
+    /// Adds a batch of rows to the result.
+    pub fn push(&mut self, rows: Vec<(Row, NonZeroUsize)>) -> Result<(), io::Error> {
+        let mut datum_vec = DatumVec::new();
+        for (row, count) in rows {
+            if let Some((result_rows, result_bytes)) = &mut self.result_size {
+                let datums = datum_vec.borrow_with(&row);
+                self.row_buf
+                    .packer()
+                    .extend(self.finishing.project.iter().map(|i| &datums[*i]));
+                *result_rows += count.get();
+                *result_bytes += self.row_buf.data().len() * count.get();
+            }
+            self.rows_size += mem::size_of::<(Row, NonZeroUsize)>() + row.data().len();
+            self.rows.push((row, count));
+            if matches!(self.memory_limit, Some(limit) if self.rows_size > limit)
+                && self.rows.len() >= MIN_RUN_ROWS
+            {
+                self.spill()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of rows and bytes that the finished result will
+    /// have at least, given the rows pushed so far, or `None` if that cannot
+    /// be known before the result is finished.
+    ///
+    /// The size is known only if the finishing has neither a limit nor an
+    /// offset, as otherwise the rows that are dropped depend on the order of
+    /// the rows that are yet to arrive. A limit bounds the size of the result
+    /// anyway.
+    pub fn result_size(&self) -> Option<(usize, usize)> {
+        self.result_size
+    }
+
+    /// Applies the finishing to the result.
+    ///
+    /// Returns the finished rows and, if the finisher spilled, statistics
+    /// about the spill.
+    pub fn finish(mut self) -> Result<(Vec<Row>, Option<SpillStats>), io::Error> {
+        if self.runs.is_empty() {
+            return Ok((self.finishing.finish(self.rows), None));
+        }
+        if !self.rows.is_empty() {
+            self.spill()?;
+        }
+        // Merge the smallest runs until few enough remain to merge at once.
+        while self.runs.len() > MERGE_WIDTH {
+            self.runs.sort_by_key(|(_, level)| *level);
+            let level = self.runs[MERGE_WIDTH - 1].1;
+            let runs = self.runs.drain(..MERGE_WIDTH).collect();
+            self.merge(runs, level + 1)?;
+        }
+
+        let runs = self.runs.drain(..).map(|(file, _)| file).collect();
+        let mut merger = Merger::new(&self.finishing, runs)?;
+        let mut datum_vec = DatumVec::new();
+        let mut row_buf = Row::default();
+        let mut offset = self.finishing.offset;
+        let mut remaining = self.finishing.limit.unwrap_or(usize::MAX);
+        let mut out = vec![];
+        while remaining > 0 {
+            let (row, count) = match merger.next()? {
+                None => break,
+                Some(next) => next,
+            };
+            let mut count = count.get();
+            let skip = cmp::min(count, offset);
+            offset -= skip;
+            count = cmp::min(count - skip, remaining);
+            remaining -= count;
+            if count > 0 {
+                let datums = datum_vec.borrow_with(&row);
+                row_buf
+                    .packer()
+                    .extend(self.finishing.project.iter().map(|i| &datums[*i]));
+                for _ in 0..count {
+                    out.push(row_buf.clone());
+                }
+            }
+        }
+        Ok((out, Some(self.stats)))
+    }
+
+    /// Sorts the rows in memory and writes them to a new run.
+    fn spill(&mut self) -> Result<(), io::Error> {
+        let mut left_datum_vec = DatumVec::new();
+        let mut right_datum_vec = DatumVec::new();
+        let finishing = &self.finishing;
+        self.rows.sort_by(|(left, _), (right, _)| {
+            compare(
+                finishing,
+                &mut left_datum_vec,
+                &mut right_datum_vec,
+                left,
+                right,
+            )
+        });
+
+        let mut writer = RunWriter::new(finishing)?;
+        for (row, count) in self.rows.drain(..) {
+            if !writer.write(&row, count, &mut self.stats)? {
+                break;
+            }
+        }
+        self.runs.push((writer.finish()?, 0));
+        self.stats.runs += 1;
+        self.rows.clear();
+        self.rows_size = 0;
+
+        // Merge runs of the same level as soon as there are enough of them,
+        // so that the number of runs grows only logarithmically.
+        let mut level = 0;
+        loop {
+            let (same, other): (Vec<_>, Vec<_>) = mem::take(&mut self.runs)
+                .into_iter()
+                .partition(|(_, l)| *l == level);
+            self.runs = other;
+            if same.len() < MERGE_WIDTH {
+                self.runs.extend(same);
+                return Ok(());
+            }
+            self.merge(same, level + 1)?;
+            level += 1;
+        }
+    }
+
+    /// Merges `runs` into a single new run of level `level`.
+    fn merge(&mut self, runs: Vec<(File, usize)>, level: usize) -> Result<(), io::Error> {
+        let runs = runs.into_iter().map(|(file, _)| file).collect();
+        let mut merger = Merger::new(&self.finishing, runs)?;
+        let mut writer = RunWriter::new(&self.finishing)?;
+        while let Some((row, count)) = merger.next()? {
+            if !writer.write(&row, count, &mut self.stats)? {
+                break;
+            }
+        }
+        self.runs.push((writer.finish()?, level));
+        self.stats.merges += 1;
+        Ok(())
+    }
+}
+
+/// Compares two rows in the order required by `finishing`.
+fn compare(
+    finishing: &RowSetFinishing,
+    left_datum_vec: &mut DatumVec,
+    right_datum_vec: &mut DatumVec,
+    left: &Row,
+    right: &Row,
+) -> Ordering {
+    let left_datums = left_datum_vec.borrow_with(left);
+    let right_datums = right_datum_vec.borrow_with(right);
+    compare_columns(&finishing.order_by, &left_datums, &right_datums, || {
+        left.cmp(right)
+    })
+}
+
+/// Writes sorted rows to a new run, keeping only as many rows as can make it
+/// into the result.
+struct RunWriter {
+    file: BufWriter<File>,
+    remaining: usize,
+}
+
+impl RunWriter {
+    fn new(finishing: &RowSetFinishing) -> Result<RunWriter, io::Error> {
+        let remaining = match finishing.limit {
+            Some(limit) => finishing.offset.saturating_add(limit),
+            None => usize::MAX,
+        };
+        Ok(RunWriter {
+            file: BufWriter::new(tempfile::tempfile()?),
+            remaining,
+        })
+    }
+
+    /// Writes `count` copies of `row` to the run.
+    ///
+    /// Returns whether later rows can still make it into the result.
+    fn write(
+        &mut self,
+        row: &Row,
+        count: NonZeroUsize,
+        stats: &mut SpillStats,
+    ) -> Result<bool, io::Error> {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let count = cmp::min(count.get(), self.remaining);
+        self.remaining -= count;
+        let data = row.data();
+        self.file
+            .write_u64::<NetworkEndian>(u64::cast_from(count))?;
+        self.file
+            .write_u64::<NetworkEndian>(u64::cast_from(data.len()))?;
+        self.file.write_all(data)?;
+        stats.rows += 1;
+        stats.bytes += u64::cast_from(16 + data.len());
+        Ok(self.remaining > 0)
+    }
+
+    /// Returns the run's file, positioned at its start.
+    fn finish(self) -> Result<File, io::Error> {
+        let mut file = self.file.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(file)
+    }
+}
+
+/// Merges sorted runs into a single sorted sequence of rows.
+struct Merger<'a> {
+    finishing: &'a RowSetFinishing,
+    runs: Vec<Run>,
+    left_datum_vec: DatumVec,
+    right_datum_vec: DatumVec,
+}
+
+impl<'a> Merger<'a> {
+    fn new(finishing: &'a RowSetFinishing, files: Vec<File>) -> Result<Merger<'a>, io::Error> {
+        Ok(Merger {
+            finishing,
+            runs: files
+                .into_iter()
+                .map(Run::new)
+                .collect::<Result<Vec<_>, _>>()?,
+            left_datum_vec: DatumVec::new(),
+            right_datum_vec: DatumVec::new(),
+        })
+    }
+
+    /// Returns the least row at the head of any run, if any rows remain.
+    fn next(&mut self) -> Result<Option<(Row, NonZeroUsize)>, io::Error> {
+        // Find the run with the least head. At most `MERGE_WIDTH` runs are
+        // merged at once, so a linear scan is cheap relative to the I/O.
+        let mut min: Option<usize> = None;
+        for (i, run) in self.runs.iter().enumerate() {
+            let row = match &run.head {
+                None => continue,
+                Some((row, _)) => row,
+            };
+            let less = match min {
+                None => true,
+                Some(j) => {
+                    let min_row = &self.runs[j].head.as_ref().unwrap().0;
+                    compare(
+                        self.finishing,
+                        &mut self.left_datum_vec,
+                        &mut self.right_datum_vec,
+                        row,
+                        min_row,
+                    ) == Ordering::Less
+                }
+            };
+            if less {
+                min = Some(i);
+            }
+        }
+        match min {
+            None => Ok(None),
+            Some(i) => Ok(Some(self.runs[i].advance()?)),
+        }
+    }
+}
+
+/// A sorted run that has been spilled to disk.
+struct Run {
+    file: BufReader<File>,
+    head: Option<(Row, NonZeroUsize)>,
+}
+
+impl Run {
+    fn new(file: File) -> Result<Run, io::Error> {
+        let mut run = Run {
+            file: BufReader::new(file),
+            head: None,
+        };
+        run.head = run.read()?;
+        Ok(run)
+    }
+
+    /// Returns the head of the run and reads the next row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the run is exhausted.
+    fn advance(&mut self) -> Result<(Row, NonZeroUsize), io::Error> {
+        let next = self.read()?;
+        Ok(mem::replace(&mut self.head, next).expect("run exhausted"))
+    }
+
+    fn read(&mut self) -> Result<Option<(Row, NonZeroUsize)>, io::Error> {
+        let count = match self.file.read_u64::<NetworkEndian>() {
+            Ok(count) => count,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let len = self.file.read_u64::<NetworkEndian>()?;
+        let mut data = vec![0; usize::cast_from(len)];
+        io::Read::read_exact(&mut self.file, &mut data)?;
+        // SAFETY: `data` was produced by `Row::data` in `RunWriter::write`.
+        let row = unsafe { Row::from_bytes_unchecked(data) };
+        let count = NonZeroUsize::new(usize::cast_from(count)).expect("spilled zero count");
+        Ok(Some((row, count)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use mz_expr::{ColumnOrder, RowSetFinishing};
+    use mz_repr::{Datum, Row};
+
+    use super::{SpillStats, SpillingFinisher, MERGE_WIDTH, MIN_RUN_ROWS};
+
+    fn finish(finishing: &RowSetFinishing, memory_limit: Option<usize>) -> Vec<Row> {
+        let (rows, stats) = finish_n(finishing, memory_limit, 100);
+        assert_eq!(stats.is_some(), memory_limit.is_some());
+        rows
+    }
+
+    fn finish_n(
+        finishing: &RowSetFinishing,
+        memory_limit: Option<usize>,
+        n: i64,
+    ) -> (Vec<Row>, Option<SpillStats>) {
+        let mut finisher = SpillingFinisher::new(finishing.clone(), memory_limit);
+        for batch in (0..n).collect::<Vec<_>>().chunks(7) {
+            let rows = batch
+                .iter()
+                .map(|i| {
+                    let row = Row::pack_slice(&[Datum::Int64((i * 37) % n), Datum::Int64(*i)]);
+                    (
+                        row,
+                        NonZeroUsize::new(usize::try_from(i % 3 + 1).unwrap()).unwrap(),
+                    )
+                })
+                .collect();
+            finisher.push(rows).unwrap();
+            // Runs are merged eagerly, so there are never more than
+            // `MERGE_WIDTH - 1` runs of any one level.
+            let max_level = finisher.runs.iter().map(|(_, l)| *l).max().unwrap_or(0);
+            assert!(finisher.runs.len() < MERGE_WIDTH * (max_level + 1));
+        }
+        finisher.finish().unwrap()
+    }
+
+    #[test]
+    fn test_spilling_matches_in_memory() {
+        let finishings = [
+            RowSetFinishing {
+                order_by: vec![],
+                limit: None,
+                offset: 0,
+                project: vec![0, 1],
+            },
+            RowSetFinishing {
+                order_by: vec![ColumnOrder {
+                    column: 0,
+                    desc: true,
+                }],
+                limit: Some(25),
+                offset: 10,
+                project: vec![1],
+            },
+            RowSetFinishing {
+                order_by: vec![ColumnOrder {
+                    column: 1,
+                    desc: false,
+                }],
+                limit: None,
+                offset: 150,
+                project: vec![0],
+            },
+        ];
+        for finishing in &finishings {
+            let expected = finish(finishing, None);
+            assert_eq!(finish(finishing, Some(512)), expected);
+            assert_eq!(finish(finishing, Some(1)), expected);
+        }
+    }
+
+    #[test]
+    fn test_result_size() {
+        let finishing = |limit, offset| RowSetFinishing {
+            order_by: vec![],
+            limit,
+            offset,
+            project: vec![1],
+        };
+        let mut finisher = SpillingFinisher::new(finishing(None, 0), Some(1));
+        let row = Row::pack_slice(&[Datum::String("dropped"), Datum::Int64(1)]);
+        let rows = vec![
+            (row.clone(), NonZeroUsize::new(2).unwrap()),
+            (row.clone(), NonZeroUsize::new(1).unwrap()),
+        ];
+        finisher.push(rows).unwrap();
+        let result_size = finisher.result_size();
+        let (rows, _) = finisher.finish().unwrap();
+        assert_eq!(
+            result_size,
+            Some((3, rows.iter().map(|row| row.data().len()).sum()))
+        );
+
+        // With a limit or an offset, the size of the result is not known
+        // until it is finished.
+        for finishing in [finishing(Some(1), 0), finishing(None, 1)] {
+            let mut finisher = SpillingFinisher::new(finishing, None);
+            finisher
+                .push(vec![(row.clone(), NonZeroUsize::new(1).unwrap())])
+                .unwrap();
+            assert_eq!(finisher.result_size(), None);
+        }
+    }
+
+    #[test]
+    fn test_spilling_multi_pass_merge() {
+        let finishing = RowSetFinishing {
+            order_by: vec![ColumnOrder {
+                column: 0,
+                desc: false,
+            }],
+            limit: None,
+            offset: 0,
+            project: vec![0, 1],
+        };
+        // Enough rows for more than `MERGE_WIDTH` runs of the second level.
+        let n = i64::try_from(MIN_RUN_ROWS * MERGE_WIDTH * (MERGE_WIDTH + 1)).unwrap();
+        let (expected, _) = finish_n(&finishing, None, n);
+        let (rows, stats) = finish_n(&finishing, Some(1), n);
+        assert_eq!(rows, expected);
+        let stats = stats.unwrap();
+        assert_eq!(stats.runs, MERGE_WIDTH * (MERGE_WIDTH + 1));
+        assert!(stats.merges > MERGE_WIDTH);
+    }
+
+    #[test]
+    fn test_spilling_large_rows() {
+        let finishing = RowSetFinishing {
+            order_by: vec![],
+            limit: None,
+            offset: 0,
+            project: vec![0],
+        };
+        let mut finisher = SpillingFinisher::new(finishing, Some(16));
+        let rows = (0..MIN_RUN_ROWS * 3)
+            .map(|i| {
+                let s = i.to_string().repeat(64);
+                (
+                    Row::pack_slice(&[Datum::String(&s)]),
+                    NonZeroUsize::new(1).unwrap(),
+                )
+            })
+            .collect();
+        finisher.push(rows).unwrap();
+        let (rows, stats) = finisher.finish().unwrap();
+        assert_eq!(rows.len(), MIN_RUN_ROWS * 3);
+        // Each row exceeds the memory limit, but rows are still spilled in
+        // runs of at least `MIN_RUN_ROWS`.
+        assert_eq!(stats.unwrap().runs, 3);
+    }
+}
@@ -36,6 +36,7 @@ mod client;
 mod command;
 mod coord;
 mod error;
+mod finishing;
 mod persistcfg;
 mod sink_connector;
 mod tail;
@@ -45,8 +46,11 @@ pub mod catalog;
 pub mod session;
 
 pub use crate::client::{Client, ConnClient, Handle, SessionClient};
-pub use crate::command::{Canceled, ExecuteResponse, StartupMessage, StartupResponse};
-pub use crate::coord::{serve, Config, LoggingConfig};
+pub use crate::command::{
+    Canceled, ClusterSpec, ClusterSpecChanges, ExecuteResponse, ReplicatedRelation,
+    ReplicationStream, RuntimeConfig, StartupMessage, StartupResponse, SystemConfigEntry,
+};
+pub use crate::coord::{serve, Config, LoggingConfig, PeekConcurrencyLimits};
 pub use crate::error::CoordError;
 pub use crate::persistcfg::{
     PersistConfig, PersistFileStorage, PersistS3Storage, PersistStorage, PersisterWithConfig,
@@ -14,6 +14,8 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::mem;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use derivative::Derivative;
@@ -35,8 +37,8 @@ use crate::error::CoordError;
 mod vars;
 
 pub use self::vars::{
-    ClientSeverity, Var, Vars, DEFAULT_DATABASE_NAME, SERVER_MAJOR_VERSION, SERVER_MINOR_VERSION,
-    SERVER_PATCH_VERSION,
+    ClientSeverity, IsolationLevel, SystemVars, Var, Vars, DEFAULT_DATABASE_NAME,
+    SERVER_MAJOR_VERSION, SERVER_MINOR_VERSION, SERVER_PATCH_VERSION,
 };
 
 const DUMMY_CONNECTION_ID: u32 = 0;
@@ -52,6 +54,16 @@ pub struct Session<T = mz_repr::Timestamp> {
     user: String,
     vars: Vars,
     drop_sinks: Vec<(ComputeInstanceId, GlobalId)>,
+    connection_info: Option<ConnectionInfo>,
+}
+
+/// Describes the client connection over which a session was established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The address of the client, if known.
+    pub peer_addr: Option<IpAddr>,
+    /// Whether the connection is encrypted with TLS.
+    pub tls: bool,
 }
 
 impl<T: CoordTimestamp> Session<T> {
@@ -79,6 +91,7 @@ impl<T: CoordTimestamp> Session<T> {
             user,
             vars: Vars::default(),
             drop_sinks: vec![],
+            connection_info: None,
         }
     }
 
@@ -106,6 +119,7 @@ impl<T: CoordTimestamp> Session<T> {
                     pcx: PlanContext::new(wall_time, self.vars.qgm_optimizations()),
                     ops: TransactionOps::None,
                     write_lock_guard: None,
+                    write_lock_deadline: None,
                     access,
                 });
             }
@@ -126,6 +140,7 @@ impl<T: CoordTimestamp> Session<T> {
                 pcx: PlanContext::new(wall_time, self.vars.qgm_optimizations()),
                 ops: TransactionOps::None,
                 write_lock_guard: None,
+                write_lock_deadline: None,
                 access: None,
             };
             match stmts {
@@ -247,6 +262,7 @@ impl<T: CoordTimestamp> Session<T> {
                 pcx: _,
                 ops: TransactionOps::Peeks(ts),
                 write_lock_guard: _,
+                write_lock_deadline: _,
                 access: _,
             }) => Some(ts.clone()),
             _ => None,
@@ -400,6 +416,19 @@ impl<T: CoordTimestamp> Session<T> {
         &self.user
     }
 
+    /// Returns information about the client connection over which the
+    /// session was established, if the session was established over a
+    /// connection whose details are known.
+    pub fn connection_info(&self) -> Option<&ConnectionInfo> {
+        self.connection_info.as_ref()
+    }
+
+    /// Records information about the client connection over which the session
+    /// was established.
+    pub fn set_connection_info(&mut self, info: ConnectionInfo) {
+        self.connection_info = Some(info);
+    }
+
     /// Returns a reference to the variables in this session.
     pub fn vars(&self) -> &Vars {
         &self.vars
@@ -411,13 +440,21 @@ impl<T: CoordTimestamp> Session<T> {
     }
 
     /// Grants the coordinator's write lock guard to this session's inner
-    /// transaction.
+    /// transaction, which may hold it for at most `timeout`, if specified.
     ///
     /// # Panics
     /// If the inner transaction is idle. See
     /// [`TransactionStatus::grant_write_lock`].
-    pub fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>) {
-        self.transaction.grant_write_lock(guard);
+    pub fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>, timeout: Option<Duration>) {
+        self.transaction.grant_write_lock(guard, timeout);
+    }
+
+    /// Returns the time by which the current transaction must release the
+    /// coordinator's write lock, if it holds the lock for a limited time.
+    pub fn write_lock_deadline(&self) -> Option<Instant> {
+        self.transaction
+            .inner()
+            .and_then(|txn| txn.write_lock_deadline)
     }
 
     /// Returns whether or not this session currently holds the write lock.
@@ -427,6 +464,18 @@ impl<T: CoordTimestamp> Session<T> {
             Some(txn) => txn.write_lock_guard.is_some(),
         }
     }
+
+    /// Returns the writes that the current transaction has made but not yet
+    /// committed.
+    pub fn transaction_writes(&self) -> &[WriteOp] {
+        match self.transaction.inner() {
+            Some(Transaction {
+                ops: TransactionOps::Writes(writes),
+                ..
+            }) => writes,
+            _ => &[],
+        }
+    }
 }
 
 /// A prepared statement.
@@ -515,13 +564,53 @@ impl InProgressRows {
 }
 
 /// A channel of batched rows.
-pub type RowBatchStream = UnboundedReceiver<PeekResponseUnary>;
+///
+/// If the stream was constructed with [`RowBatchStream::with_ack`], the sender
+/// is notified each time a batch is received, so that it can limit the number
+/// of batches that are buffered but not yet consumed.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct RowBatchStream {
+    rx: UnboundedReceiver<PeekResponseUnary>,
+    #[derivative(Debug = "ignore")]
+    ack: Option<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl RowBatchStream {
+    /// Creates a new stream that receives batches from `rx`.
+    pub fn new(rx: UnboundedReceiver<PeekResponseUnary>) -> RowBatchStream {
+        RowBatchStream { rx, ack: None }
+    }
+
+    /// Creates a new stream that receives batches from `rx` and calls `ack`
+    /// after each batch is received.
+    pub fn with_ack<F>(rx: UnboundedReceiver<PeekResponseUnary>, ack: F) -> RowBatchStream
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        RowBatchStream {
+            rx,
+            ack: Some(Box::new(ack)),
+        }
+    }
+
+    /// Receives the next batch, or `None` if the sender has gone away.
+    ///
+    /// This method is cancel safe.
+    pub async fn recv(&mut self) -> Option<PeekResponseUnary> {
+        let batch = self.rx.recv().await;
+        if let (Some(_), Some(ack)) = (&batch, &self.ack) {
+            ack();
+        }
+        batch
+    }
+}
 
 /// Converts a RowsFuture to a RowBatchStream.
 pub async fn row_future_to_stream(rows: RowsFuture) -> RowBatchStream {
     let (tx, rx) = unbounded_channel();
     tx.send(rows.await).expect("send must succeed");
-    rx
+    RowBatchStream::new(rx)
 }
 
 /// The transaction status of a session.
@@ -588,19 +677,20 @@ impl<T> TransactionStatus<T> {
         }
     }
 
-    /// Grants the write lock to the inner transaction.
+    /// Grants the write lock to the inner transaction, which may hold it for
+    /// at most `timeout`, if specified.
     ///
     /// # Panics
     /// If `self` is `TransactionStatus::Default`, which indicates that the
     /// transaction is idle, which is not appropriate to assign the
     /// coordinator's write lock to.
-    pub fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>) {
+    pub fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>, timeout: Option<Duration>) {
         match self {
             TransactionStatus::Default => panic!("cannot grant write lock to txn not yet started"),
             TransactionStatus::Started(txn)
             | TransactionStatus::InTransaction(txn)
             | TransactionStatus::InTransactionImplicit(txn)
-            | TransactionStatus::Failed(txn) => txn.grant_write_lock(guard),
+            | TransactionStatus::Failed(txn) => txn.grant_write_lock(guard, timeout),
         }
     }
 }
@@ -620,22 +710,27 @@ pub struct Transaction<T> {
     pub ops: TransactionOps<T>,
     /// Holds the coordinator's write lock.
     write_lock_guard: Option<OwnedMutexGuard<()>>,
+    /// The time by which the transaction must release the write lock, if it
+    /// holds the lock for a limited time. The connection of an explicit
+    /// transaction that still holds the lock afterward is terminated.
+    write_lock_deadline: Option<Instant>,
     /// Access mode (read only, read write).
     access: Option<TransactionAccessMode>,
 }
 
 impl<T> Transaction<T> {
     /// Grants the write lock to this transaction for the remainder of its lifetime.
-    fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>) {
+    fn grant_write_lock(&mut self, guard: OwnedMutexGuard<()>, timeout: Option<Duration>) {
         self.write_lock_guard = Some(guard);
+        self.write_lock_deadline = timeout.map(|timeout| Instant::now() + timeout);
     }
 }
 
 /// The type of operation being performed by the transaction.
 ///
-/// This is needed because we currently do not allow mixing reads and writes in
-/// a transaction. Use this to record what we have done, and what may need to
-/// happen at commit.
+/// This is needed because we currently do not allow arbitrary mixing of reads
+/// and writes in a transaction. Use this to record what we have done, and what
+/// may need to happen at commit.
 #[derive(Debug, Clone, PartialEq)]
 pub enum TransactionOps<T> {
     /// The transaction has been initiated, but no statement has yet been executed
@@ -646,7 +741,7 @@ pub enum TransactionOps<T> {
     /// This transaction has done a TAIL and must do nothing else.
     Tail,
     /// This transaction has had a write (`INSERT`, `UPDATE`, `DELETE`) and must only do
-    /// other writes.
+    /// other writes and reads of tables. Reads see the transaction's writes.
     Writes(Vec<WriteOp>),
 }
 
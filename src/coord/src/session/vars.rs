@@ -9,12 +9,15 @@
 
 use std::borrow::Borrow;
 use std::fmt;
+use std::time::Duration;
 
 use const_format::concatcp;
 use uncased::UncasedStr;
 
 use crate::catalog::builtin::{MZ_CATALOG_SCHEMA, MZ_TEMP_SCHEMA, PG_CATALOG_SCHEMA};
 use mz_ore::cast;
+use mz_repr::adt::interval::Interval;
+use mz_repr::strconv;
 use mz_sql::DEFAULT_SCHEMA;
 
 use crate::error::CoordError;
@@ -49,30 +52,42 @@ const APPLICATION_NAME: ServerVar<str> = ServerVar {
     name: static_uncased_str!("application_name"),
     value: "",
     description: "Sets the application name to be reported in statistics and logs (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
 const CLIENT_ENCODING: ServerVar<str> = ServerVar {
     name: static_uncased_str!("client_encoding"),
     value: "UTF8",
     description: "Sets the client's character set encoding (PostgreSQL).",
+    scope: VarScope::Fixed,
 };
 
 const CLIENT_MIN_MESSAGES: ServerVar<ClientSeverity> = ServerVar {
     name: static_uncased_str!("client_min_messages"),
     value: &ClientSeverity::Notice,
     description: "Sets the message levels that are sent to the client (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
 const CLUSTER: ServerVar<str> = ServerVar {
     name: static_uncased_str!("cluster"),
     value: "default",
     description: "Sets the current cluster (Materialize).",
+    scope: VarScope::Session,
+};
+
+const CLUSTER_MEMORY_LIMIT: ServerVar<usize> = ServerVar {
+    name: static_uncased_str!("cluster_memory_limit"),
+    value: &0,
+    description: "The estimated memory in bytes that the arrangements of a cluster may hold before queries that build dataflows on it are rejected; 0 disables the limit (Materialize).",
+    scope: VarScope::System,
 };
 
 const DATABASE: ServerVar<str> = ServerVar {
     name: static_uncased_str!("database"),
     value: DEFAULT_DATABASE_NAME,
     description: "Sets the current database (CockroachDB).",
+    scope: VarScope::Session,
 };
 
 const DATE_STYLE: ServerVar<str> = ServerVar {
@@ -80,30 +95,92 @@ const DATE_STYLE: ServerVar<str> = ServerVar {
     name: static_uncased_str!("DateStyle"),
     value: "ISO, MDY",
     description: "Sets the display format for date and time values (PostgreSQL).",
+    scope: VarScope::Fixed,
 };
 
 const EXTRA_FLOAT_DIGITS: ServerVar<i32> = ServerVar {
     name: static_uncased_str!("extra_float_digits"),
     value: &3,
     description: "Adjusts the number of digits displayed for floating-point values (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
 const FAILPOINTS: ServerVar<str> = ServerVar {
     name: static_uncased_str!("failpoints"),
     value: "",
     description: "Allows failpoints to be dynamically activated.",
+    scope: VarScope::Session,
+};
+
+const IDLE_IN_TRANSACTION_SESSION_TIMEOUT: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("idle_in_transaction_session_timeout"),
+    value: &Duration::ZERO,
+    description:
+        "Sets the maximum allowed duration of any idling transaction; 0 disables the timeout (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
 const INTEGER_DATETIMES: ServerVar<bool> = ServerVar {
     name: static_uncased_str!("integer_datetimes"),
     value: &true,
     description: "Reports whether the server uses 64-bit-integer dates and times (PostgreSQL).",
+    scope: VarScope::ReadOnly,
+};
+
+const MAX_INDEX_KEYS: ServerVar<i32> = ServerVar {
+    name: static_uncased_str!("max_index_keys"),
+    value: &1000,
+    description: "Shows the maximum number of index keys (PostgreSQL).",
+    scope: VarScope::ReadOnly,
+};
+
+const MAX_READ_STALENESS: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("max_read_staleness"),
+    value: &Duration::ZERO,
+    description: "Sets how stale the data read by queries may be to avoid waiting on lagging inputs (Materialize).",
+    scope: VarScope::Session,
+};
+
+const MAX_RESULT_SIZE: ServerVar<usize> = ServerVar {
+    name: static_uncased_str!("max_result_size"),
+    value: &(1 << 30),
+    description: "The maximum size in bytes of the result of a single query (Materialize).",
+    scope: VarScope::System,
+};
+
+const MAX_RESULT_ROWS: ServerVar<usize> = ServerVar {
+    name: static_uncased_str!("max_result_rows"),
+    value: &0,
+    description: "The maximum number of rows in the result of a single query; 0 disables the limit (Materialize).",
+    scope: VarScope::Session,
+};
+
+const METRICS_SCRAPING_INTERVAL: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("metrics_scraping_interval"),
+    value: &Duration::from_secs(30),
+    description: "Sets how often the server's metrics are scraped into the introspection tables; 0 disables scraping (Materialize).",
+    scope: VarScope::System,
+};
+
+const PEEK_QUEUE_TIMEOUT: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("peek_queue_timeout"),
+    value: &Duration::from_secs(30),
+    description: "Sets how long a query may wait for a cluster to have capacity before it fails (Materialize).",
+    scope: VarScope::System,
+};
+
+const WRITE_LOCK_TIMEOUT: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("write_lock_timeout"),
+    value: &Duration::from_secs(60),
+    description: "Sets how long a transaction may hold the write lock before its connection is terminated; 0 disables the timeout (Materialize).",
+    scope: VarScope::System,
 };
 
 const QGM_OPTIMIZATIONS: ServerVar<bool> = ServerVar {
     name: static_uncased_str!("qgm_optimizations_experimental"),
     value: &false,
     description: "Enables optimizations based on a Query Graph Model (QGM) query representation.",
+    scope: VarScope::Session,
 };
 
 const SEARCH_PATH: ServerVar<[&str]> = ServerVar {
@@ -116,6 +193,7 @@ const SEARCH_PATH: ServerVar<[&str]> = ServerVar {
     ],
     description:
         "Sets the schema search order for names that are not schema-qualified (PostgreSQL).",
+    scope: VarScope::ReadOnly,
 };
 
 const SERVER_VERSION: ServerVar<str> = ServerVar {
@@ -128,6 +206,7 @@ const SERVER_VERSION: ServerVar<str> = ServerVar {
         SERVER_PATCH_VERSION
     ),
     description: "Shows the server version (PostgreSQL).",
+    scope: VarScope::ReadOnly,
 };
 
 const SERVER_VERSION_NUM: ServerVar<i32> = ServerVar {
@@ -136,18 +215,29 @@ const SERVER_VERSION_NUM: ServerVar<i32> = ServerVar {
         + (cast::u8_to_i32(SERVER_MINOR_VERSION) * 100)
         + cast::u8_to_i32(SERVER_PATCH_VERSION)),
     description: "Shows the server version as an integer (PostgreSQL).",
+    scope: VarScope::ReadOnly,
 };
 
 const SQL_SAFE_UPDATES: ServerVar<bool> = ServerVar {
     name: static_uncased_str!("sql_safe_updates"),
     value: &false,
     description: "Prohibits SQL statements that may be overly destructive (CockroachDB).",
+    scope: VarScope::Session,
 };
 
 const STANDARD_CONFORMING_STRINGS: ServerVar<bool> = ServerVar {
     name: static_uncased_str!("standard_conforming_strings"),
     value: &true,
     description: "Causes '...' strings to treat backslashes literally (PostgreSQL).",
+    scope: VarScope::Fixed,
+};
+
+const STATEMENT_TIMEOUT: ServerVar<Duration> = ServerVar {
+    name: static_uncased_str!("statement_timeout"),
+    value: &Duration::ZERO,
+    description:
+        "Sets the maximum allowed duration of any statement; 0 disables the timeout (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
 const TIMEZONE: ServerVar<TimeZone> = ServerVar {
@@ -155,80 +245,124 @@ const TIMEZONE: ServerVar<TimeZone> = ServerVar {
     name: static_uncased_str!("TimeZone"),
     value: &TimeZone::UTC,
     description: "Sets the time zone for displaying and interpreting time stamps (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
-const TRANSACTION_ISOLATION: ServerVar<str> = ServerVar {
+const TRANSACTION_ISOLATION: ServerVar<IsolationLevel> = ServerVar {
     name: static_uncased_str!("transaction_isolation"),
-    value: "serializable",
+    value: &IsolationLevel::Serializable,
     description: "Sets the current transaction's isolation level (PostgreSQL).",
+    scope: VarScope::Session,
 };
 
-/// Session variables.
-///
-/// Materialize roughly follows the PostgreSQL configuration model, which works
-/// as follows. There is a global set of named configuration parameters, like
-/// `DateStyle` and `client_encoding`. These parameters can be set in several
-/// places: in an on-disk configuration file (in Postgres, named
-/// postgresql.conf), in command line arguments when the server is started, or
-/// at runtime via the `ALTER SYSTEM` or `SET` statements. Parameters that are
-/// set in a session take precedence over database defaults, which in turn take
-/// precedence over command line arguments, which in turn take precedence over
-/// settings in the on-disk configuration. Note that changing the value of
-/// parameters obeys transaction semantics: if a transaction fails to commit,
-/// any parameters that were changed in that transaction (i.e., via `SET`)
-/// will be rolled back to their previous value.
-///
-/// The Materialize configuration hierarchy at the moment is much simpler.
-/// Global defaults are hardcoded into the binary, and a select few parameters
-/// can be overridden per session. The infrastructure has been designed with an
-/// eye towards supporting additional layers to the hierarchy, however, should
-/// the need arise.
+/// How a configuration parameter may be changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VarScope {
+    /// The parameter reports a property of the server and cannot be changed.
+    ReadOnly,
+    /// The parameter can be set, but only to its default value. Such
+    /// parameters exist because drivers set them and fail if they cannot.
+    Fixed,
+    /// The parameter can be set for a session with `SET`, for the current
+    /// transaction with `SET LOCAL`, and for a role with `ALTER ROLE ... SET`.
+    Session,
+    /// The parameter configures the server as a whole and can only be set
+    /// with `ALTER SYSTEM`. See [`SystemVars`].
+    System,
+}
+
+/// Declares the [`Vars`] struct.
 ///
-/// The configuration parameters that exist are driven by compatibility with
-/// PostgreSQL drivers that expect them, not because they are particularly
-/// important.
-#[derive(Debug)]
-pub struct Vars {
-    application_name: SessionVar<str>,
-    client_encoding: ServerVar<str>,
-    client_min_messages: SessionVar<ClientSeverity>,
-    cluster: SessionVar<str>,
-    database: SessionVar<str>,
-    date_style: ServerVar<str>,
-    extra_float_digits: SessionVar<i32>,
-    failpoints: ServerVar<str>,
-    integer_datetimes: ServerVar<bool>,
-    qgm_optimizations: SessionVar<bool>,
-    search_path: ServerVar<[&'static str]>,
-    server_version: ServerVar<str>,
-    server_version_num: ServerVar<i32>,
-    sql_safe_updates: SessionVar<bool>,
-    standard_conforming_strings: ServerVar<bool>,
-    timezone: SessionVar<TimeZone>,
-    transaction_isolation: ServerVar<str>,
-}
-
-impl Default for Vars {
-    fn default() -> Vars {
-        Vars {
-            application_name: SessionVar::new(&APPLICATION_NAME),
-            client_encoding: CLIENT_ENCODING,
-            client_min_messages: SessionVar::new(&CLIENT_MIN_MESSAGES),
-            cluster: SessionVar::new(&CLUSTER),
-            database: SessionVar::new(&DATABASE),
-            date_style: DATE_STYLE,
-            extra_float_digits: SessionVar::new(&EXTRA_FLOAT_DIGITS),
-            failpoints: FAILPOINTS,
-            integer_datetimes: INTEGER_DATETIMES,
-            qgm_optimizations: SessionVar::new(&QGM_OPTIMIZATIONS),
-            search_path: SEARCH_PATH,
-            server_version: SERVER_VERSION,
-            server_version_num: SERVER_VERSION_NUM,
-            sql_safe_updates: SessionVar::new(&SQL_SAFE_UPDATES),
-            standard_conforming_strings: STANDARD_CONFORMING_STRINGS,
-            timezone: SessionVar::new(&TIMEZONE),
-            transaction_isolation: TRANSACTION_ISOLATION,
+/// Each configuration parameter is listed once, as the field that stores its
+/// value in the session, the type of that value, and the [`ServerVar`] that
+/// declares its name, default value, description, and [`VarScope`]. Lookups by
+/// name, `SET`, `RESET`, and transaction handling are all derived from the
+/// list, so adding a configuration parameter requires only a `ServerVar`, an
+/// entry in the list, and, if the parameter is read elsewhere, an accessor.
+macro_rules! session_vars {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($field:ident: $ty:ty = $var:ident,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            $($field: SessionVar<$ty>,)*
+        }
+
+        impl Default for $name {
+            fn default() -> $name {
+                $name {
+                    $($field: SessionVar::new(&$var),)*
+                }
+            }
+        }
+
+        impl $name {
+            /// Returns the configuration parameters, in declaration order.
+            fn vars(&self) -> Vec<&dyn SessionVarOps> {
+                vec![$(&self.$field as &dyn SessionVarOps,)*]
+            }
+
+            /// Like [`Self::vars`], but returns mutable references.
+            fn vars_mut(&mut self) -> Vec<&mut dyn SessionVarOps> {
+                vec![$(&mut self.$field as &mut dyn SessionVarOps,)*]
+            }
         }
+    };
+}
+
+session_vars! {
+    /// Session variables.
+    ///
+    /// Materialize roughly follows the PostgreSQL configuration model, which
+    /// works as follows. There is a global set of named configuration
+    /// parameters, like `DateStyle` and `client_encoding`. These parameters can
+    /// be set in several places: in an on-disk configuration file (in Postgres,
+    /// named postgresql.conf), in command line arguments when the server is
+    /// started, or at runtime via the `ALTER SYSTEM` or `SET` statements.
+    /// Parameters that are set in a session take precedence over database
+    /// defaults, which in turn take precedence over command line arguments,
+    /// which in turn take precedence over settings in the on-disk
+    /// configuration. Note that changing the value of parameters obeys
+    /// transaction semantics: if a transaction fails to commit, any parameters
+    /// that were changed in that transaction (i.e., via `SET`) will be rolled
+    /// back to their previous value.
+    ///
+    /// The Materialize configuration hierarchy at the moment is much simpler.
+    /// Global defaults are hardcoded into the binary, and may be overridden per
+    /// role with `ALTER ROLE ... SET`, per session with `SET`, and per
+    /// transaction with `SET LOCAL`. `RESET` restores the role's default, if
+    /// any, or else the global default.
+    ///
+    /// The configuration parameters that exist are driven by compatibility with
+    /// PostgreSQL drivers that expect them, not because they are particularly
+    /// important.
+    #[derive(Debug)]
+    pub struct Vars {
+        application_name: str = APPLICATION_NAME,
+        client_encoding: str = CLIENT_ENCODING,
+        client_min_messages: ClientSeverity = CLIENT_MIN_MESSAGES,
+        cluster: str = CLUSTER,
+        database: str = DATABASE,
+        date_style: str = DATE_STYLE,
+        extra_float_digits: i32 = EXTRA_FLOAT_DIGITS,
+        failpoints: str = FAILPOINTS,
+        idle_in_transaction_session_timeout: Duration = IDLE_IN_TRANSACTION_SESSION_TIMEOUT,
+        integer_datetimes: bool = INTEGER_DATETIMES,
+        max_index_keys: i32 = MAX_INDEX_KEYS,
+        max_read_staleness: Duration = MAX_READ_STALENESS,
+        max_result_rows: usize = MAX_RESULT_ROWS,
+        qgm_optimizations: bool = QGM_OPTIMIZATIONS,
+        search_path: [&'static str] = SEARCH_PATH,
+        server_version: str = SERVER_VERSION,
+        server_version_num: i32 = SERVER_VERSION_NUM,
+        sql_safe_updates: bool = SQL_SAFE_UPDATES,
+        standard_conforming_strings: bool = STANDARD_CONFORMING_STRINGS,
+        statement_timeout: Duration = STATEMENT_TIMEOUT,
+        timezone: TimeZone = TIMEZONE,
+        transaction_isolation: IsolationLevel = TRANSACTION_ISOLATION,
     }
 }
 
@@ -236,26 +370,7 @@ impl Vars {
     /// Returns an iterator over the configuration parameters and their current
     /// values for this session.
     pub fn iter(&self) -> impl Iterator<Item = &dyn Var> {
-        vec![
-            &self.application_name as &dyn Var,
-            &self.client_encoding,
-            &self.client_min_messages,
-            &self.cluster,
-            &self.database,
-            &self.date_style,
-            &self.extra_float_digits,
-            &self.failpoints,
-            &self.integer_datetimes,
-            &self.qgm_optimizations,
-            &self.search_path,
-            &self.server_version,
-            &self.server_version_num,
-            &self.sql_safe_updates,
-            &self.standard_conforming_strings,
-            &self.timezone,
-            &self.transaction_isolation,
-        ]
-        .into_iter()
+        self.vars().into_iter().map(|v| v.as_var())
     }
 
     /// Returns an iterator over configuration parameters (and their current
@@ -284,43 +399,18 @@ impl Vars {
     /// example, `self.get("sql_safe_updates").value()` returns the string
     /// `"true"` or `"false"`, while `self.sql_safe_updates()` returns a bool.
     pub fn get(&self, name: &str) -> Result<&dyn Var, CoordError> {
-        if name == APPLICATION_NAME.name {
-            Ok(&self.application_name)
-        } else if name == CLIENT_ENCODING.name {
-            Ok(&self.client_encoding)
-        } else if name == CLIENT_MIN_MESSAGES.name {
-            Ok(&self.client_min_messages)
-        } else if name == CLUSTER.name {
-            Ok(&self.cluster)
-        } else if name == DATABASE.name {
-            Ok(&self.database)
-        } else if name == DATE_STYLE.name {
-            Ok(&self.date_style)
-        } else if name == EXTRA_FLOAT_DIGITS.name {
-            Ok(&self.extra_float_digits)
-        } else if name == FAILPOINTS.name {
-            Ok(&self.failpoints)
-        } else if name == INTEGER_DATETIMES.name {
-            Ok(&self.integer_datetimes)
-        } else if name == QGM_OPTIMIZATIONS.name {
-            Ok(&self.qgm_optimizations)
-        } else if name == SEARCH_PATH.name {
-            Ok(&self.search_path)
-        } else if name == SERVER_VERSION.name {
-            Ok(&self.server_version)
-        } else if name == SERVER_VERSION_NUM.name {
-            Ok(&self.server_version_num)
-        } else if name == SQL_SAFE_UPDATES.name {
-            Ok(&self.sql_safe_updates)
-        } else if name == STANDARD_CONFORMING_STRINGS.name {
-            Ok(&self.standard_conforming_strings)
-        } else if name == TIMEZONE.name {
-            Ok(&self.timezone)
-        } else if name == TRANSACTION_ISOLATION.name {
-            Ok(&self.transaction_isolation)
-        } else {
-            Err(CoordError::UnknownParameter(name.into()))
-        }
+        self.vars()
+            .into_iter()
+            .find(|v| name == UncasedStr::new(v.name()))
+            .map(|v| v.as_var())
+            .ok_or_else(|| CoordError::UnknownParameter(name.into()))
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<&mut dyn SessionVarOps, CoordError> {
+        self.vars_mut()
+            .into_iter()
+            .find(|v| name == UncasedStr::new(v.name()))
+            .ok_or_else(|| CoordError::UnknownParameter(name.into()))
     }
 
     /// Sets the configuration parameter named `name` to the value represented
@@ -336,137 +426,54 @@ impl Vars {
     /// configuration parameter, or if the named configuration parameter does
     /// not exist, an error is returned.
     pub fn set(&mut self, name: &str, value: &str, local: bool) -> Result<(), CoordError> {
-        if name == APPLICATION_NAME.name {
-            self.application_name.set(value, local)
-        } else if name == CLIENT_ENCODING.name {
-            // Unfortunately, some orm's like Prisma set NAMES to UTF8, thats the only
-            // value we support, so we let is through
-            if UncasedStr::new(value) != CLIENT_ENCODING.value {
-                return Err(CoordError::FixedValueParameter(&CLIENT_ENCODING));
-            } else {
-                Ok(())
-            }
-        } else if name == CLIENT_MIN_MESSAGES.name {
-            if let Ok(_) = ClientSeverity::parse(value) {
-                self.client_min_messages.set(value, local)
-            } else {
-                return Err(CoordError::ConstrainedParameter {
-                    parameter: &CLIENT_MIN_MESSAGES,
-                    value: value.into(),
-                    valid_values: Some(ClientSeverity::valid_values()),
-                });
-            }
-        } else if name == CLUSTER.name {
-            self.cluster.set(value, local)
-        } else if name == DATABASE.name {
-            self.database.set(value, local)
-        } else if name == DATE_STYLE.name {
-            for value in value.split(',') {
-                let value = UncasedStr::new(value.trim());
-                if value != "ISO" && value != "MDY" {
-                    return Err(CoordError::FixedValueParameter(&DATE_STYLE));
-                }
-            }
-            Ok(())
-        } else if name == EXTRA_FLOAT_DIGITS.name {
-            self.extra_float_digits.set(value, local)
-        } else if name == FAILPOINTS.name {
-            for mut cfg in value.trim().split(';') {
-                cfg = cfg.trim();
-                if cfg.is_empty() {
-                    continue;
-                }
-                let mut splits = cfg.splitn(2, '=');
-                let failpoint = splits
-                    .next()
-                    .ok_or_else(|| CoordError::InvalidParameterValue {
-                        parameter: &FAILPOINTS,
-                        value: value.into(),
-                        reason: "missing failpoint name".into(),
-                    })?;
-                let action = splits
-                    .next()
-                    .ok_or_else(|| CoordError::InvalidParameterValue {
-                        parameter: &FAILPOINTS,
-                        value: value.into(),
-                        reason: "missing failpoint action".into(),
-                    })?;
-                fail::cfg(failpoint, action).map_err(|e| CoordError::InvalidParameterValue {
-                    parameter: &FAILPOINTS,
-                    value: value.into(),
-                    reason: e,
-                })?;
-            }
-            Ok(())
-        } else if name == INTEGER_DATETIMES.name {
-            Err(CoordError::ReadOnlyParameter(&INTEGER_DATETIMES))
-        } else if name == QGM_OPTIMIZATIONS.name {
-            self.qgm_optimizations.set(value, local)
-        } else if name == SEARCH_PATH.name {
-            Err(CoordError::ReadOnlyParameter(&SEARCH_PATH))
-        } else if name == SERVER_VERSION.name {
-            Err(CoordError::ReadOnlyParameter(&SERVER_VERSION))
-        } else if name == SERVER_VERSION_NUM.name {
-            Err(CoordError::ReadOnlyParameter(&SERVER_VERSION_NUM))
-        } else if name == SQL_SAFE_UPDATES.name {
-            self.sql_safe_updates.set(value, local)
-        } else if name == STANDARD_CONFORMING_STRINGS.name {
-            match bool::parse(value) {
-                Ok(value) if value == *STANDARD_CONFORMING_STRINGS.value => Ok(()),
-                Ok(_) => Err(CoordError::FixedValueParameter(
-                    &STANDARD_CONFORMING_STRINGS,
-                )),
-                Err(()) => Err(CoordError::InvalidParameterType(
-                    &STANDARD_CONFORMING_STRINGS,
-                )),
-            }
-        } else if name == TIMEZONE.name {
-            if let Ok(_) = TimeZone::parse(value) {
-                self.timezone.set(value, local)
-            } else {
-                return Err(CoordError::ConstrainedParameter {
-                    parameter: &TIMEZONE,
-                    value: value.into(),
-                    valid_values: None,
-                });
+        // Failpoints are configured for the whole process rather than for the
+        // session, so they take effect immediately and are not recorded.
+        if name == FAILPOINTS.name {
+            return set_failpoints(value);
+        }
+        self.get_mut(name)?.set(value, local)
+    }
+
+    /// Restores the configuration parameter named `name` to its default value:
+    /// the default for the session's role, if any, or else the server default.
+    ///
+    /// Like a new value set with [`Vars::set`], the restored value obeys
+    /// transaction semantics, and is always discarded at the end of the
+    /// transaction if `local` is true.
+    pub fn reset(&mut self, name: &str, local: bool) -> Result<(), CoordError> {
+        self.get_mut(name)?.reset(local)
+    }
+
+    /// Restores every configuration parameter that can be set to its default
+    /// value, as with [`Vars::reset`].
+    pub fn reset_all(&mut self) {
+        for var in self.vars_mut() {
+            if var.scope() == VarScope::Session {
+                var.reset(false).expect("session parameters can be reset");
             }
-        } else if name == TRANSACTION_ISOLATION.name {
-            Err(CoordError::ReadOnlyParameter(&TRANSACTION_ISOLATION))
-        } else {
-            Err(CoordError::UnknownParameter(name.into()))
         }
     }
 
+    /// Sets the default value of the configuration parameter named `name` to
+    /// the value represented by `value`, as when applying the session defaults
+    /// stored for the session's role.
+    ///
+    /// The default takes effect only while the session has not otherwise set
+    /// the configuration parameter.
+    pub fn set_default(&mut self, name: &str, value: &str) -> Result<(), CoordError> {
+        if name == FAILPOINTS.name {
+            return Err(CoordError::Unsupported("session defaults for failpoints"));
+        }
+        self.get_mut(name)?.set_default(value)
+    }
+
     /// Commits or rolls back configuration parameter updates made via
-    /// [`Vars::set`] since the last call to `end_transaction`.
+    /// [`Vars::set`] and [`Vars::reset`] since the last call to
+    /// `end_transaction`.
     pub fn end_transaction(&mut self, action: EndTransactionAction) {
-        // IMPORTANT: if you've added a new `SessionVar`, add a corresponding
-        // call to `end_transaction` below.
-        let Vars {
-            application_name,
-            client_encoding: _,
-            client_min_messages,
-            cluster: _,
-            database,
-            date_style: _,
-            extra_float_digits,
-            failpoints: _,
-            integer_datetimes: _,
-            qgm_optimizations,
-            search_path: _,
-            server_version: _,
-            server_version_num: _,
-            sql_safe_updates,
-            standard_conforming_strings: _,
-            timezone: _,
-            transaction_isolation: _,
-        } = self;
-        application_name.end_transaction(action);
-        client_min_messages.end_transaction(action);
-        database.end_transaction(action);
-        qgm_optimizations.end_transaction(action);
-        extra_float_digits.end_transaction(action);
-        sql_safe_updates.end_transaction(action);
+        for var in self.vars_mut() {
+            var.end_transaction(action);
+        }
     }
 
     /// Returns the value of the `application_name` configuration parameter.
@@ -475,8 +482,8 @@ impl Vars {
     }
 
     /// Returns the value of the `client_encoding` configuration parameter.
-    pub fn client_encoding(&self) -> &'static str {
-        self.client_encoding.value
+    pub fn client_encoding(&self) -> &str {
+        self.client_encoding.value()
     }
 
     /// Returns the value of the `client_min_messages` configuration parameter.
@@ -490,8 +497,8 @@ impl Vars {
     }
 
     /// Returns the value of the `DateStyle` configuration parameter.
-    pub fn date_style(&self) -> &'static str {
-        self.date_style.value
+    pub fn date_style(&self) -> &str {
+        self.date_style.value()
     }
 
     /// Returns the value of the `database` configuration parameter.
@@ -504,9 +511,29 @@ impl Vars {
         *self.extra_float_digits.value()
     }
 
+    /// Returns the value of the `idle_in_transaction_session_timeout`
+    /// configuration parameter, or `None` if the timeout is disabled.
+    pub fn idle_in_transaction_session_timeout(&self) -> Option<Duration> {
+        nonzero_timeout(*self.idle_in_transaction_session_timeout.value())
+    }
+
     /// Returns the value of the `integer_datetimes` configuration parameter.
     pub fn integer_datetimes(&self) -> bool {
-        *self.integer_datetimes.value
+        *self.integer_datetimes.value()
+    }
+
+    /// Returns the value of the `max_read_staleness` configuration parameter.
+    pub fn max_read_staleness(&self) -> Duration {
+        *self.max_read_staleness.value()
+    }
+
+    /// Returns the value of the `max_result_rows` configuration parameter, or
+    /// `None` if the limit is disabled.
+    pub fn max_result_rows(&self) -> Option<usize> {
+        match *self.max_result_rows.value() {
+            0 => None,
+            n => Some(n),
+        }
     }
 
     /// Returns the value of the `qgm_optimizations` configuration parameter.
@@ -515,18 +542,18 @@ impl Vars {
     }
 
     /// Returns the value of the `search_path` configuration parameter.
-    pub fn search_path(&self) -> &'static [&'static str] {
-        self.search_path.value
+    pub fn search_path(&self) -> &[&'static str] {
+        self.search_path.value()
     }
 
     /// Returns the value of the `server_version` configuration parameter.
-    pub fn server_version(&self) -> &'static str {
-        self.server_version.value
+    pub fn server_version(&self) -> &str {
+        self.server_version.value()
     }
 
     /// Returns the value of the `server_version_num` configuration parameter.
     pub fn server_version_num(&self) -> i32 {
-        *self.server_version_num.value
+        *self.server_version_num.value()
     }
 
     /// Returns the value of the `sql_safe_updates` configuration parameter.
@@ -537,7 +564,13 @@ impl Vars {
     /// Returns the value of the `standard_conforming_strings` configuration
     /// parameter.
     pub fn standard_conforming_strings(&self) -> bool {
-        *self.standard_conforming_strings.value
+        *self.standard_conforming_strings.value()
+    }
+
+    /// Returns the value of the `statement_timeout` configuration parameter,
+    /// or `None` if the timeout is disabled.
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        nonzero_timeout(*self.statement_timeout.value())
     }
 
     /// Returns the value of the `timezone` configuration parameter.
@@ -547,8 +580,218 @@ impl Vars {
 
     /// Returns the value of the `transaction_isolation` configuration
     /// parameter.
-    pub fn transaction_isolation(&self) -> &'static str {
-        self.transaction_isolation.value
+    pub fn transaction_isolation(&self) -> &IsolationLevel {
+        self.transaction_isolation.value()
+    }
+}
+
+/// Activates the failpoints described by `value`, a semicolon-separated list
+/// of `name=action` pairs.
+fn set_failpoints(value: &str) -> Result<(), CoordError> {
+    for mut cfg in value.trim().split(';') {
+        cfg = cfg.trim();
+        if cfg.is_empty() {
+            continue;
+        }
+        let mut splits = cfg.splitn(2, '=');
+        let failpoint = splits
+            .next()
+            .ok_or_else(|| CoordError::InvalidParameterValue {
+                parameter: &FAILPOINTS,
+                value: value.into(),
+                reason: "missing failpoint name".into(),
+            })?;
+        let action = splits
+            .next()
+            .ok_or_else(|| CoordError::InvalidParameterValue {
+                parameter: &FAILPOINTS,
+                value: value.into(),
+                reason: "missing failpoint action".into(),
+            })?;
+        fail::cfg(failpoint, action).map_err(|e| CoordError::InvalidParameterValue {
+            parameter: &FAILPOINTS,
+            value: value.into(),
+            reason: e,
+        })?;
+    }
+    Ok(())
+}
+
+/// System variables.
+///
+/// System variables configure the server as a whole rather than a single
+/// session. They are changed with `ALTER SYSTEM SET` and `ALTER SYSTEM RESET`,
+/// which persist the new value in the catalog, and take effect immediately,
+/// without a restart.
+///
+/// A system variable that has not been set takes its value from the command
+/// line, if the corresponding option exists, or else from the value
+/// hardcoded into the binary.
+#[derive(Debug, Clone)]
+pub struct SystemVars {
+    cluster_memory_limit: SystemVar<usize>,
+    max_result_size: SystemVar<usize>,
+    metrics_scraping_interval: SystemVar<Duration>,
+    peek_queue_timeout: SystemVar<Duration>,
+    write_lock_timeout: SystemVar<Duration>,
+}
+
+impl Default for SystemVars {
+    fn default() -> SystemVars {
+        SystemVars {
+            cluster_memory_limit: SystemVar::new(&CLUSTER_MEMORY_LIMIT),
+            max_result_size: SystemVar::new(&MAX_RESULT_SIZE),
+            metrics_scraping_interval: SystemVar::new(&METRICS_SCRAPING_INTERVAL),
+            peek_queue_timeout: SystemVar::new(&PEEK_QUEUE_TIMEOUT),
+            write_lock_timeout: SystemVar::new(&WRITE_LOCK_TIMEOUT),
+        }
+    }
+}
+
+impl SystemVars {
+    /// Returns an iterator over the system variables and their current values.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Var> {
+        vec![
+            &self.cluster_memory_limit as &dyn Var,
+            &self.max_result_size,
+            &self.metrics_scraping_interval,
+            &self.peek_queue_timeout,
+            &self.write_lock_timeout,
+        ]
+        .into_iter()
+    }
+
+    /// Returns a [`Var`] representing the system variable with the specified
+    /// name.
+    ///
+    /// System variables are matched case insensitively. If no such system
+    /// variable exists, `get` returns an error.
+    pub fn get(&self, name: &str) -> Result<&dyn Var, CoordError> {
+        if name == CLUSTER_MEMORY_LIMIT.name {
+            Ok(&self.cluster_memory_limit)
+        } else if name == MAX_RESULT_SIZE.name {
+            Ok(&self.max_result_size)
+        } else if name == METRICS_SCRAPING_INTERVAL.name {
+            Ok(&self.metrics_scraping_interval)
+        } else if name == PEEK_QUEUE_TIMEOUT.name {
+            Ok(&self.peek_queue_timeout)
+        } else if name == WRITE_LOCK_TIMEOUT.name {
+            Ok(&self.write_lock_timeout)
+        } else {
+            Err(CoordError::UnknownParameter(name.into()))
+        }
+    }
+
+    /// Sets the system variable named `name` to the value represented by
+    /// `value`.
+    ///
+    /// Like with [`SystemVars::get`], system variables are matched case
+    /// insensitively. If `value` is not valid, as determined by the underlying
+    /// system variable, or if the named system variable does not exist, an
+    /// error is returned.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), CoordError> {
+        if name == CLUSTER_MEMORY_LIMIT.name {
+            self.cluster_memory_limit.set(value)
+        } else if name == MAX_RESULT_SIZE.name {
+            self.max_result_size.set(value)
+        } else if name == METRICS_SCRAPING_INTERVAL.name {
+            self.metrics_scraping_interval.set(value)
+        } else if name == PEEK_QUEUE_TIMEOUT.name {
+            self.peek_queue_timeout.set(value)
+        } else if name == WRITE_LOCK_TIMEOUT.name {
+            self.write_lock_timeout.set(value)
+        } else {
+            Err(CoordError::UnknownParameter(name.into()))
+        }
+    }
+
+    /// Restores the system variable named `name` to its default value.
+    pub fn reset(&mut self, name: &str) -> Result<(), CoordError> {
+        if name == CLUSTER_MEMORY_LIMIT.name {
+            self.cluster_memory_limit.reset();
+        } else if name == MAX_RESULT_SIZE.name {
+            self.max_result_size.reset();
+        } else if name == METRICS_SCRAPING_INTERVAL.name {
+            self.metrics_scraping_interval.reset();
+        } else if name == PEEK_QUEUE_TIMEOUT.name {
+            self.peek_queue_timeout.reset();
+        } else if name == WRITE_LOCK_TIMEOUT.name {
+            self.write_lock_timeout.reset();
+        } else {
+            return Err(CoordError::UnknownParameter(name.into()));
+        }
+        Ok(())
+    }
+
+    /// Sets the default value of the `peek_queue_timeout` system variable, as
+    /// when it is configured on the command line.
+    pub fn set_default_peek_queue_timeout(&mut self, timeout: Duration) {
+        self.peek_queue_timeout.default_value = timeout;
+    }
+
+    /// Sets the default value of the `metrics_scraping_interval` system
+    /// variable, as when it is configured on the command line.
+    pub fn set_default_metrics_scraping_interval(&mut self, interval: Duration) {
+        self.metrics_scraping_interval.default_value = interval;
+    }
+
+    /// Returns the value of the `cluster_memory_limit` system variable, or
+    /// `None` if the limit is disabled.
+    pub fn cluster_memory_limit(&self) -> Option<usize> {
+        match *self.cluster_memory_limit.value() {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Returns the value of the `max_result_size` system variable.
+    pub fn max_result_size(&self) -> usize {
+        *self.max_result_size.value()
+    }
+
+    /// Returns the value of the `metrics_scraping_interval` system variable,
+    /// or `None` if scraping is disabled.
+    pub fn metrics_scraping_interval(&self) -> Option<Duration> {
+        match *self.metrics_scraping_interval.value() {
+            Duration::ZERO => None,
+            interval => Some(interval),
+        }
+    }
+
+    /// Returns the value of the `peek_queue_timeout` system variable.
+    pub fn peek_queue_timeout(&self) -> Duration {
+        *self.peek_queue_timeout.value()
+    }
+
+    /// Returns the value of the `write_lock_timeout` system variable, or
+    /// `None` if the timeout is disabled.
+    pub fn write_lock_timeout(&self) -> Option<Duration> {
+        match *self.write_lock_timeout.value() {
+            Duration::ZERO => None,
+            timeout => Some(timeout),
+        }
+    }
+}
+
+/// Reports whether `value` spells the value `fixed` of a configuration
+/// parameter with [`VarScope::Fixed`].
+///
+/// Every comma-separated component of `value` must match, ignoring case, a
+/// component of `fixed`, so that `ISO` is accepted for a `DateStyle` of
+/// `ISO, MDY`.
+fn is_fixed_value(value: &str, fixed: &str) -> bool {
+    value.split(',').all(|component| {
+        let component = UncasedStr::new(component.trim());
+        fixed.split(',').any(|f| component == f.trim())
+    })
+}
+
+/// Maps a timeout of zero, which disables the timeout, to `None`.
+fn nonzero_timeout(timeout: Duration) -> Option<Duration> {
+    if timeout == Duration::ZERO {
+        None
+    } else {
+        Some(timeout)
     }
 }
 
@@ -586,6 +829,7 @@ where
     name: &'static UncasedStr,
     value: &'static V,
     description: &'static str,
+    scope: VarScope,
 }
 
 impl<V> Var for ServerVar<V>
@@ -619,6 +863,7 @@ where
     local_value: Option<V::Owned>,
     staged_value: Option<V::Owned>,
     session_value: Option<V::Owned>,
+    default_value: Option<V::Owned>,
     parent: &'static ServerVar<V>,
 }
 
@@ -631,32 +876,42 @@ where
             local_value: None,
             staged_value: None,
             session_value: None,
+            default_value: None,
             parent,
         }
     }
 
-    fn set(&mut self, s: &str, local: bool) -> Result<(), CoordError> {
-        match V::parse(s) {
-            Ok(v) => {
-                if local {
-                    self.local_value = Some(v);
+    /// Parses `s` as a new value for the configuration parameter, according
+    /// to its scope.
+    ///
+    /// Returns `None` if the configuration parameter is fixed and `s` spells
+    /// its value, in which case there is nothing to store.
+    fn parse(&self, s: &str) -> Result<Option<V::Owned>, CoordError> {
+        match self.parent.scope {
+            VarScope::ReadOnly | VarScope::System => {
+                Err(CoordError::ReadOnlyParameter(self.parent))
+            }
+            VarScope::Fixed => {
+                let v = V::parse(s).map_err(|()| V::parse_error(self.parent, s))?;
+                let v: &V = v.borrow();
+                if is_fixed_value(&v.format(), &self.parent.value.format()) {
+                    Ok(None)
                 } else {
-                    self.local_value = None;
-                    self.staged_value = Some(v);
+                    Err(CoordError::FixedValueParameter(self.parent))
                 }
-                Ok(())
             }
-            Err(()) => Err(CoordError::InvalidParameterType(self.parent)),
+            VarScope::Session => V::parse(s)
+                .map(Some)
+                .map_err(|()| V::parse_error(self.parent, s)),
         }
     }
 
-    fn end_transaction(&mut self, action: EndTransactionAction) {
-        self.local_value = None;
-        match action {
-            EndTransactionAction::Commit if self.staged_value.is_some() => {
-                self.session_value = self.staged_value.take()
-            }
-            _ => self.staged_value = None,
+    fn stage(&mut self, v: V::Owned, local: bool) {
+        if local {
+            self.local_value = Some(v);
+        } else {
+            self.local_value = None;
+            self.staged_value = Some(v);
         }
     }
 
@@ -666,6 +921,7 @@ where
             .map(|v| v.borrow())
             .or_else(|| self.staged_value.as_ref().map(|v| v.borrow()))
             .or_else(|| self.session_value.as_ref().map(|v| v.borrow()))
+            .or_else(|| self.default_value.as_ref().map(|v| v.borrow()))
             .unwrap_or(self.parent.value)
     }
 }
@@ -692,6 +948,145 @@ where
     }
 }
 
+/// The operations on a [`SessionVar`] that do not depend on the type of its
+/// value, through which [`Vars`] handles its configuration parameters
+/// uniformly.
+trait SessionVarOps: Var {
+    fn as_var(&self) -> &dyn Var;
+
+    fn scope(&self) -> VarScope;
+
+    fn set(&mut self, s: &str, local: bool) -> Result<(), CoordError>;
+
+    fn set_default(&mut self, s: &str) -> Result<(), CoordError>;
+
+    fn reset(&mut self, local: bool) -> Result<(), CoordError>;
+
+    fn end_transaction(&mut self, action: EndTransactionAction);
+}
+
+impl<V> SessionVarOps for SessionVar<V>
+where
+    V: Value + ToOwned + fmt::Debug + ?Sized + 'static,
+    V::Owned: fmt::Debug + Clone,
+{
+    fn as_var(&self) -> &dyn Var {
+        self
+    }
+
+    fn scope(&self) -> VarScope {
+        self.parent.scope
+    }
+
+    fn set(&mut self, s: &str, local: bool) -> Result<(), CoordError> {
+        if let Some(v) = self.parse(s)? {
+            self.stage(v, local);
+        }
+        Ok(())
+    }
+
+    fn set_default(&mut self, s: &str) -> Result<(), CoordError> {
+        if let Some(v) = self.parse(s)? {
+            self.default_value = Some(v);
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self, local: bool) -> Result<(), CoordError> {
+        match self.parent.scope {
+            VarScope::ReadOnly | VarScope::System => {
+                Err(CoordError::ReadOnlyParameter(self.parent))
+            }
+            // A fixed parameter always has its default value.
+            VarScope::Fixed => Ok(()),
+            VarScope::Session => {
+                let v = match &self.default_value {
+                    Some(v) => v.clone(),
+                    None => self.parent.value.to_owned(),
+                };
+                self.stage(v, local);
+                Ok(())
+            }
+        }
+    }
+
+    fn end_transaction(&mut self, action: EndTransactionAction) {
+        self.local_value = None;
+        match action {
+            EndTransactionAction::Commit if self.staged_value.is_some() => {
+                self.session_value = self.staged_value.take()
+            }
+            _ => self.staged_value = None,
+        }
+    }
+}
+
+/// A `SystemVar` is the server-wide value for a configuration parameter. If
+/// unset, the default value is used instead.
+#[derive(Debug, Clone)]
+struct SystemVar<V>
+where
+    V: Value + fmt::Debug + ?Sized + 'static,
+    V::Owned: fmt::Debug + Clone,
+{
+    persisted_value: Option<V::Owned>,
+    default_value: V::Owned,
+    parent: &'static ServerVar<V>,
+}
+
+impl<V> SystemVar<V>
+where
+    V: Value + fmt::Debug + ?Sized + 'static,
+    V::Owned: fmt::Debug + Clone,
+{
+    fn new(parent: &'static ServerVar<V>) -> SystemVar<V> {
+        SystemVar {
+            persisted_value: None,
+            default_value: parent.value.to_owned(),
+            parent,
+        }
+    }
+
+    fn set(&mut self, s: &str) -> Result<(), CoordError> {
+        let v = V::parse(s).map_err(|()| V::parse_error(self.parent, s))?;
+        self.persisted_value = Some(v);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.persisted_value = None;
+    }
+
+    fn value(&self) -> &V {
+        self.persisted_value
+            .as_ref()
+            .unwrap_or(&self.default_value)
+            .borrow()
+    }
+}
+
+impl<V> Var for SystemVar<V>
+where
+    V: Value + fmt::Debug + ?Sized + 'static,
+    V::Owned: fmt::Debug + Clone,
+{
+    fn name(&self) -> &'static str {
+        self.parent.name.as_str()
+    }
+
+    fn value(&self) -> String {
+        SystemVar::value(self).format()
+    }
+
+    fn description(&self) -> &'static str {
+        self.parent.description
+    }
+
+    fn type_name(&self) -> &'static str {
+        V::TYPE_NAME
+    }
+}
+
 /// A value that can be stored in a session variable.
 pub trait Value: ToOwned + Send + Sync {
     /// The name of the value type.
@@ -700,6 +1095,11 @@ pub trait Value: ToOwned + Send + Sync {
     fn parse(s: &str) -> Result<Self::Owned, ()>;
     /// Formats this value as a string.
     fn format(&self) -> String;
+    /// Returns the error to report when a string that is not a valid value of
+    /// this type is given for the configuration parameter `parameter`.
+    fn parse_error(parameter: &'static (dyn Var + Send + Sync), _: &str) -> CoordError {
+        CoordError::InvalidParameterType(parameter)
+    }
 }
 
 impl Value for bool {
@@ -733,6 +1133,41 @@ impl Value for i32 {
     }
 }
 
+impl Value for usize {
+    const TYPE_NAME: &'static str = "integer";
+
+    fn parse(s: &str) -> Result<usize, ()> {
+        s.parse().map_err(|_| ())
+    }
+
+    fn format(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for Duration {
+    const TYPE_NAME: &'static str = "interval";
+
+    fn parse(s: &str) -> Result<Duration, ()> {
+        // Like PostgreSQL, interpret a number without units as a number of
+        // milliseconds, rather than as a number of seconds, as intervals do.
+        if let Ok(millis) = s.trim().parse::<u64>() {
+            return Ok(Duration::from_millis(millis));
+        }
+        let interval = strconv::parse_interval(s).map_err(|_| ())?;
+        interval.duration().map_err(|_| ())
+    }
+
+    fn format(&self) -> String {
+        let interval = Interval {
+            months: 0,
+            days: 0,
+            micros: i64::try_from(self.as_micros()).unwrap_or(i64::MAX),
+        };
+        interval.to_string()
+    }
+}
+
 impl Value for str {
     const TYPE_NAME: &'static str = "string";
 
@@ -857,6 +1292,73 @@ impl Value for ClientSeverity {
     fn format(&self) -> String {
         self.as_str().into()
     }
+
+    fn parse_error(parameter: &'static (dyn Var + Send + Sync), s: &str) -> CoordError {
+        CoordError::ConstrainedParameter {
+            parameter,
+            value: s.into(),
+            valid_values: Some(ClientSeverity::valid_values()),
+        }
+    }
+}
+
+/// The isolation levels that a session may request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IsolationLevel {
+    /// Transactions appear to execute in some total order, which need not
+    /// agree with the order in which they executed in real time. Queries read
+    /// at the latest time that lets them return immediately, which may not
+    /// reflect writes made by other sessions.
+    Serializable,
+    /// Like `Serializable`, but the total order agrees with the real time
+    /// order of transactions. Queries reflect all writes and reads that
+    /// completed before they began, in any session, and wait for their inputs
+    /// to catch up if need be.
+    StrictSerializable,
+}
+
+impl IsolationLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            IsolationLevel::Serializable => "serializable",
+            IsolationLevel::StrictSerializable => "strict serializable",
+        }
+    }
+
+    fn valid_values() -> Vec<&'static str> {
+        vec![
+            IsolationLevel::Serializable.as_str(),
+            IsolationLevel::StrictSerializable.as_str(),
+        ]
+    }
+}
+
+impl Value for IsolationLevel {
+    const TYPE_NAME: &'static str = "string";
+
+    fn parse(s: &str) -> Result<Self::Owned, ()> {
+        let s = UncasedStr::new(s);
+
+        if s == IsolationLevel::Serializable.as_str() {
+            Ok(IsolationLevel::Serializable)
+        } else if s == IsolationLevel::StrictSerializable.as_str() {
+            Ok(IsolationLevel::StrictSerializable)
+        } else {
+            Err(())
+        }
+    }
+
+    fn format(&self) -> String {
+        self.as_str().into()
+    }
+
+    fn parse_error(parameter: &'static (dyn Var + Send + Sync), s: &str) -> CoordError {
+        CoordError::ConstrainedParameter {
+            parameter,
+            value: s.into(),
+            valid_values: Some(IsolationLevel::valid_values()),
+        }
+    }
 }
 
 /// List of valid time zones.
@@ -899,4 +1401,12 @@ impl Value for TimeZone {
     fn format(&self) -> String {
         self.as_str().into()
     }
+
+    fn parse_error(parameter: &'static (dyn Var + Send + Sync), s: &str) -> CoordError {
+        CoordError::ConstrainedParameter {
+            parameter,
+            value: s.into(),
+            valid_values: None,
+        }
+    }
 }
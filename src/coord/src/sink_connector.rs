@@ -8,11 +8,14 @@
 // by the Apache License, Version 2.0.
 
 use std::fs::OpenOptions;
+use std::iter;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
 use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, ResourceSpecifier, TopicReplication};
 use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use tracing::warn;
 
 use mz_dataflow_types::sinks::{
     AvroOcfSinkConnector, AvroOcfSinkConnectorBuilder, KafkaSinkConnector,
@@ -20,10 +23,12 @@ use mz_dataflow_types::sinks::{
     PublishedSchemaInfo, SinkConnector, SinkConnectorBuilder,
 };
 use mz_expr::GlobalId;
+use mz_kafka_util::admin::CreateTopicError;
 use mz_kafka_util::client::MzClientContext;
 use mz_ore::collections::CollectionExt;
 
 use crate::error::CoordError;
+use crate::util::ddl_retry;
 
 pub async fn build(
     builder: SinkConnectorBuilder,
@@ -165,9 +170,6 @@ async fn register_kafka_topic(
 }
 
 /// Publish value and optional key schemas for a given topic.
-///
-/// TODO(benesch): do we need to delete the Kafka topic if publishing the
-// schema fails?
 async fn publish_kafka_schemas(
     ccsr: &mz_ccsr::Client,
     topic: &str,
@@ -202,64 +204,45 @@ async fn publish_kafka_schemas(
     Ok((key_schema_id, value_schema_id))
 }
 
-async fn build_kafka(
-    builder: KafkaSinkConnectorBuilder,
-    id: GlobalId,
-) -> Result<SinkConnector, CoordError> {
-    let maybe_append_nonce = {
-        let reuse_topic = builder.reuse_topic;
-        let topic_suffix_nonce = builder.topic_suffix_nonce;
-        move |topic: &str| {
-            if reuse_topic {
-                topic.to_string()
-            } else {
-                format!("{}-{}-{}", topic, id, topic_suffix_nonce)
-            }
-        }
-    };
-    let topic = maybe_append_nonce(&builder.topic_prefix);
-
-    // Create Kafka topic
-    let mut config = ClientConfig::new();
-    config.set("bootstrap.servers", &builder.broker_addrs.to_string());
-    for (k, v) in builder.config_options.iter() {
-        // Explicitly reject the statistics interval option here because its not
-        // properly supported for this client.
-        // Explicitly reject isolation.level as it's a consumer-specific
-        // parameter and will generate a benign WARN for admin clients
-        if k != "statistics.interval.ms" && k != "isolation.level" {
-            config.set(k, v);
-        }
-    }
-
-    let client: AdminClient<_> = config
-        .create_with_context(MzClientContext)
-        .context("creating admin client failed")?;
-
+/// Creates the topics of a Kafka sink and publishes their schemas, returning
+/// the IDs of the published schemas.
+async fn register_kafka_sink(
+    client: &AdminClient<MzClientContext>,
+    builder: &KafkaSinkConnectorBuilder,
+    topic: &str,
+    consistency_topic: Option<&str>,
+    succeed_if_exists: bool,
+) -> Result<
+    (
+        Option<PublishedSchemaInfo>,
+        Option<KafkaSinkConsistencyConnector>,
+    ),
+    CoordError,
+> {
     register_kafka_topic(
-        &client,
-        &topic,
+        client,
+        topic,
         builder.partition_count,
         builder.replication_factor,
-        builder.reuse_topic,
-        builder.retention,
+        succeed_if_exists,
+        builder.retention.clone(),
     )
     .await
     .context("error registering kafka topic for sink")?;
-    let published_schema_info = match builder.format {
+    let published_schema_info = match &builder.format {
         mz_dataflow_types::sinks::KafkaSinkFormat::Avro {
             key_schema,
             value_schema,
             ccsr_config,
             ..
         } => {
-            let ccsr = ccsr_config.build()?;
+            let ccsr = ccsr_config.clone().build()?;
             let (key_schema_id, value_schema_id) = publish_kafka_schemas(
                 &ccsr,
-                &topic,
+                topic,
                 key_schema.as_deref(),
                 Some(mz_ccsr::SchemaType::Avro),
-                &value_schema,
+                value_schema,
                 mz_ccsr::SchemaType::Avro,
             )
             .await
@@ -272,44 +255,39 @@ async fn build_kafka(
         mz_dataflow_types::sinks::KafkaSinkFormat::Json => None,
     };
 
-    let consistency = match builder.consistency_format {
+    let consistency = match &builder.consistency_format {
         Some(mz_dataflow_types::sinks::KafkaSinkFormat::Avro {
             value_schema,
             ccsr_config,
             ..
         }) => {
-            let consistency_topic = maybe_append_nonce(
-                builder
-                    .consistency_topic_prefix
-                    .as_ref()
-                    .expect("known to exist"),
-            );
+            let consistency_topic = consistency_topic.expect("known to exist");
             // create consistency topic/schema and retrieve schema id
             register_kafka_topic(
-                &client,
-                &consistency_topic,
+                client,
+                consistency_topic,
                 1,
                 builder.replication_factor,
-                builder.reuse_topic,
+                succeed_if_exists,
                 KafkaSinkConnectorRetention::default(),
             )
             .await
             .context("error registering kafka consistency topic for sink")?;
 
-            let ccsr = ccsr_config.build()?;
+            let ccsr = ccsr_config.clone().build()?;
             let (_, consistency_schema_id) = publish_kafka_schemas(
                 &ccsr,
-                &consistency_topic,
+                consistency_topic,
                 None,
                 None,
-                &value_schema,
+                value_schema,
                 mz_ccsr::SchemaType::Avro,
             )
             .await
             .context("error publishing kafka consistency schemas for sink")?;
 
             Some(KafkaSinkConsistencyConnector {
-                topic: consistency_topic,
+                topic: consistency_topic.to_string(),
                 schema_id: consistency_schema_id,
             })
         }
@@ -317,6 +295,174 @@ async fn build_kafka(
         _ => None,
     };
 
+    Ok((published_schema_info, consistency))
+}
+
+/// Reports whether registering a Kafka sink failed with `e` due to a
+/// condition that may resolve itself, like an unreachable broker or schema
+/// registry, such that the registration is worth retrying.
+fn is_transient(e: &CoordError) -> bool {
+    let e = match e {
+        CoordError::Unstructured(e) => e,
+        _ => return false,
+    };
+    e.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<CoordError>() {
+            is_transient(e)
+        } else if let Some(e) = cause.downcast_ref::<CreateTopicError>() {
+            match e {
+                CreateTopicError::Kafka(e) => is_transient_kafka_error(e),
+                // The topic may not have propagated to the broker that was
+                // asked for its metadata yet.
+                CreateTopicError::MissingMetadata => true,
+                CreateTopicError::TopicCountMismatch(_)
+                | CreateTopicError::PartitionCountMismatch { .. } => false,
+            }
+        } else if let Some(e) = cause.downcast_ref::<KafkaError>() {
+            is_transient_kafka_error(e)
+        } else if let Some(e) = cause.downcast_ref::<mz_ccsr::PublishError>() {
+            match e {
+                mz_ccsr::PublishError::Transport(_) => true,
+                mz_ccsr::PublishError::Server { code, .. } => *code >= 500,
+                mz_ccsr::PublishError::IncompatibleSchema
+                | mz_ccsr::PublishError::InvalidSchema { .. } => false,
+            }
+        } else {
+            false
+        }
+    })
+}
+
+fn is_transient_kafka_error(e: &KafkaError) -> bool {
+    matches!(
+        e.rdkafka_error_code(),
+        Some(
+            RDKafkaErrorCode::BrokerTransportFailure
+                | RDKafkaErrorCode::AllBrokersDown
+                | RDKafkaErrorCode::OperationTimedOut
+                | RDKafkaErrorCode::RequestTimedOut
+                | RDKafkaErrorCode::NetworkException
+                | RDKafkaErrorCode::BrokerNotAvailable
+                | RDKafkaErrorCode::LeaderNotAvailable
+                | RDKafkaErrorCode::NotController
+                | RDKafkaErrorCode::NotEnoughReplicas
+        )
+    )
+}
+
+/// Deletes the Kafka topics `topics`, which were created for a sink that
+/// failed to be created.
+///
+/// Errors are logged rather than returned, as there is nothing more to be
+/// done about them.
+async fn delete_kafka_topics(client: &AdminClient<MzClientContext>, topics: &[&str]) {
+    let res = client
+        .delete_topics(
+            topics,
+            &AdminOptions::new().request_timeout(Some(Duration::from_secs(5))),
+        )
+        .await;
+    match res {
+        Ok(results) => {
+            for result in results {
+                match result {
+                    // The topic may never have been created.
+                    Ok(_) | Err((_, RDKafkaErrorCode::UnknownTopicOrPartition)) => (),
+                    Err((topic, e)) => {
+                        warn!("unable to delete topic {} of failed sink: {}", topic, e)
+                    }
+                }
+            }
+        }
+        Err(e) => warn!("unable to delete topics {:?} of failed sink: {}", topics, e),
+    }
+}
+
+async fn build_kafka(
+    builder: KafkaSinkConnectorBuilder,
+    id: GlobalId,
+) -> Result<SinkConnector, CoordError> {
+    let maybe_append_nonce = {
+        let reuse_topic = builder.reuse_topic;
+        let topic_suffix_nonce = builder.topic_suffix_nonce;
+        move |topic: &str| {
+            if reuse_topic {
+                topic.to_string()
+            } else {
+                format!("{}-{}-{}", topic, id, topic_suffix_nonce)
+            }
+        }
+    };
+    let topic = maybe_append_nonce(&builder.topic_prefix);
+
+    // Create Kafka topic
+    let mut config = ClientConfig::new();
+    config.set("bootstrap.servers", &builder.broker_addrs.to_string());
+    for (k, v) in builder.config_options.iter() {
+        // Explicitly reject the statistics interval option here because its not
+        // properly supported for this client.
+        // Explicitly reject isolation.level as it's a consumer-specific
+        // parameter and will generate a benign WARN for admin clients
+        if k != "statistics.interval.ms" && k != "isolation.level" {
+            config.set(k, v);
+        }
+    }
+
+    let client: AdminClient<_> = config
+        .create_with_context(MzClientContext)
+        .context("creating admin client failed")?;
+
+    let consistency_topic = builder.consistency_format.as_ref().map(|_| {
+        maybe_append_nonce(
+            builder
+                .consistency_topic_prefix
+                .as_ref()
+                .expect("known to exist"),
+        )
+    });
+
+    // Registration is retried if it fails transiently. The names of the
+    // topics serve as idempotency keys: a topic created by a failed attempt
+    // is adopted by the next attempt rather than failing it, and republishing
+    // a schema returns the ID under which it was first published.
+    let (client, builder_ref) = (&client, &builder);
+    let (topic_ref, consistency_topic_ref) = (&topic, consistency_topic.as_deref());
+    let registration = ddl_retry()
+        .retry_async(|state| async move {
+            let succeed_if_exists = builder_ref.reuse_topic || state.i > 0;
+            match register_kafka_sink(
+                client,
+                builder_ref,
+                topic_ref,
+                consistency_topic_ref,
+                succeed_if_exists,
+            )
+            .await
+            {
+                Err(e) if is_transient(&e) => {
+                    warn!("transient error registering kafka sink {}: {}", id, e);
+                    Err(e)
+                }
+                res => Ok(res),
+            }
+        })
+        .await
+        .and_then(|res| res);
+    let (published_schema_info, consistency) = match registration {
+        Ok(registration) => registration,
+        Err(e) => {
+            // Topics whose names are not suffixed with a nonce may predate
+            // this sink, so only the others are cleaned up.
+            if !builder.reuse_topic {
+                let topics: Vec<_> = iter::once(topic.as_str())
+                    .chain(consistency_topic.as_deref())
+                    .collect();
+                delete_kafka_topics(client, &topics).await;
+            }
+            return Err(e);
+        }
+    };
+
     Ok(SinkConnector::Kafka(KafkaSinkConnector {
         topic,
         topic_prefix: builder.topic_prefix,
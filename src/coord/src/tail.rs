@@ -8,11 +8,51 @@
 // by the Apache License, Version 2.0.
 
 //! Implementations around supporting the TAIL protocol with the dataflow layer
+//!
+//! A tail created with `max_unacked_batches` is flow controlled. The client
+//! acknowledges each batch as it consumes it, and once `max_unacked_batches`
+//! batches are outstanding, the coordinator drops the tail's dataflow rather
+//! than buffering further batches. When the client has caught up to half that
+//! many outstanding batches, the dataflow is recreated as of the last update
+//! that was sent. Meanwhile, the coordinator holds back compaction of the
+//! tail's inputs to the point the client has acknowledged, so the dataflow can
+//! always be recreated without missing any updates.
+//!
+//! A tail created with `UP TO` ends once its dataflow has produced every update
+//! at times before the `UP TO` timestamp. A tail created with `PROGRESS`
+//! repeats its most recent progress row every [`TAIL_PROGRESS_INTERVAL`] while
+//! it is otherwise idle, so that clients can distinguish an idle tail from a
+//! broken connection.
+//!
+//! A tail with the diffs envelope always reports progress, but without an
+//! `mz_progressed` column: its progress rows are the rows with a `NULL` diff.
+//! Within a timestamp it sends retractions before insertions, and it follows
+//! the updates at each timestamp with a progress row, so that clients can
+//! apply each timestamp's updates as a single transaction.
 
-use mz_dataflow_types::{PeekResponseUnary, TailResponse};
-use mz_repr::adt::numeric;
-use mz_repr::{Datum, Row};
+use std::cmp;
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Duration;
+
+use timely::progress::Antichain;
 use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use mz_dataflow_types::client::ComputeInstanceId;
+use mz_dataflow_types::sinks::SinkAsOf;
+use mz_dataflow_types::{DataflowDesc, PeekResponseUnary, TailResponse};
+use mz_expr::GlobalId;
+use mz_repr::adt::numeric;
+use mz_repr::{Datum, Row, Timestamp};
+use mz_sql::plan::TailEnvelope;
+
+use crate::coord::id_bundle::CollectionIdBundle;
+use crate::error::CoordError;
+
+/// How often a tail with `PROGRESS` that has sent nothing repeats its most
+/// recent progress row.
+pub(crate) const TAIL_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
 
 /// A description of a pending tail from coord's perspective
 pub(crate) struct PendingTail {
@@ -22,30 +62,206 @@ pub(crate) struct PendingTail {
     channel: mpsc::UnboundedSender<PeekResponseUnary>,
     /// Whether progress information should be emitted
     emit_progress: bool,
+    /// The shape of the rows sent to the client
+    envelope: TailEnvelope,
     /// Number of columns in the output
     arity: usize,
+    /// The sink of the tail's running dataflow, or `None` if the tail is
+    /// paused.
+    pub(crate) sink_id: Option<GlobalId>,
+    /// The compute instance on which the tail's dataflow runs.
+    pub(crate) compute_instance: ComputeInstanceId,
+    /// Flow control state, if the tail is flow controlled.
+    pub(crate) flow: Option<TailFlow>,
+    /// The maximum number of data rows the tail may send, if limited.
+    max_result_rows: Option<usize>,
+    /// The number of data rows the tail has sent.
+    rows_sent: usize,
+    /// The timestamp at which the tail ends, if any.
+    up_to: Option<Timestamp>,
+    /// The timestamp of the most recent progress row, if any.
+    progress: Option<Timestamp>,
+    /// Whether the tail has sent anything since the last heartbeat.
+    active: bool,
+}
+
+/// Flow control state of a tail whose client acknowledges the batches it
+/// consumes.
+pub(crate) struct TailFlow {
+    /// The number of unacknowledged batches at which the tail is paused.
+    max_unacked: usize,
+    /// For each batch sent but not yet acknowledged, the time to which the
+    /// tail's inputs may be compacted once it is acknowledged.
+    unacked: VecDeque<Timestamp>,
+    /// The point from which to resume the tail. All updates not beyond this
+    /// point have been sent to the client.
+    resume_at: SinkAsOf,
+    /// The dataflow from which the tail's dataflow is recreated.
+    dataflow: DataflowDesc,
+    /// The compute instance on which the tail's dataflow runs.
+    pub(crate) compute_instance: ComputeInstanceId,
+    /// The inputs of the tail's dataflow.
+    pub(crate) id_bundle: CollectionIdBundle,
+    /// The time at which read holds on the inputs are held.
+    pub(crate) hold: Timestamp,
+}
+
+impl TailFlow {
+    /// Creates flow control state for a tail whose dataflow is `dataflow`.
+    ///
+    /// The caller is responsible for acquiring read holds on `id_bundle` at
+    /// the time of the sink's `as_of` frontier.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dataflow` does not export exactly one sink, or if the sink's
+    /// `as_of` frontier is not a single timestamp.
+    pub(crate) fn new(
+        max_unacked: usize,
+        dataflow: DataflowDesc,
+        compute_instance: ComputeInstanceId,
+        id_bundle: CollectionIdBundle,
+    ) -> TailFlow {
+        assert_eq!(dataflow.sink_exports.len(), 1, "tail must export one sink");
+        let resume_at = dataflow.sink_exports.values().next().unwrap().as_of.clone();
+        let hold = *resume_at
+            .frontier
+            .as_option()
+            .expect("tail as_of must not be empty");
+        TailFlow {
+            max_unacked,
+            unacked: VecDeque::new(),
+            resume_at,
+            dataflow,
+            compute_instance,
+            id_bundle,
+            hold,
+        }
+    }
+
+    /// Records that the client has consumed a batch.
+    ///
+    /// Returns the time to which the tail's inputs may now be compacted.
+    pub(crate) fn ack(&mut self) -> Timestamp {
+        match self.unacked.pop_front() {
+            Some(time) => cmp::max(time, self.hold),
+            None => self.hold,
+        }
+    }
+
+    /// Reports whether the tail is paused but the client has consumed enough
+    /// batches that it should be resumed.
+    pub(crate) fn should_resume(&self, paused: bool) -> bool {
+        paused && self.unacked.len() <= self.max_unacked / 2
+    }
+
+    /// Returns a dataflow that resumes the tail from where it left off,
+    /// exporting the sink `sink_id`.
+    pub(crate) fn resume(&self, sink_id: GlobalId) -> DataflowDesc {
+        resume_dataflow(&self.dataflow, sink_id, &self.resume_at)
+    }
+
+    /// Advances the resume point past the updates in a batch with the given
+    /// `upper`.
+    fn advance(&mut self, upper: &Antichain<Timestamp>) {
+        advance_as_of(&mut self.resume_at, upper);
+    }
+}
+
+/// Returns a copy of `dataflow`, which must export exactly one tail sink, that
+/// instead exports the sink `sink_id` as of `as_of`.
+pub(crate) fn resume_dataflow(
+    dataflow: &DataflowDesc,
+    sink_id: GlobalId,
+    as_of: &SinkAsOf,
+) -> DataflowDesc {
+    let mut dataflow = dataflow.clone();
+    let (_, mut sink_desc) = mem::take(&mut dataflow.sink_exports)
+        .into_iter()
+        .next()
+        .unwrap();
+    sink_desc.as_of = as_of.clone();
+    dataflow.sink_exports.insert(sink_id, sink_desc);
+    dataflow.as_of = None;
+    dataflow.set_as_of(as_of.frontier.clone());
+    dataflow.debug_name = format!("tail-{}", sink_id);
+    dataflow.id = Uuid::new_v4();
+    dataflow
+}
+
+/// Advances `as_of` past all updates not beyond `upper`, so that a sink
+/// resumed as of `as_of` produces only the updates at or beyond `upper`.
+pub(crate) fn advance_as_of(as_of: &mut SinkAsOf, upper: &Antichain<Timestamp>) {
+    let upper = match upper.as_option() {
+        Some(upper) => *upper,
+        None => return,
+    };
+    let time = *as_of.frontier.as_option().unwrap();
+    let next = if as_of.strict { time + 1 } else { time };
+    if upper > next {
+        *as_of = SinkAsOf {
+            frontier: Antichain::from_elem(upper - 1),
+            strict: true,
+        };
+    }
 }
 
 impl PendingTail {
     /// Create a new [PendingTail].
     /// * The `channel` receives batches of finalized PeekResponses.
     /// * If `emit_progress` is true, the finalized rows are either data or progress updates
+    /// * `envelope` determines the shape of the rows and the progress updates
     /// * `arity` is the arity of the sink relation.
+    /// * `sink_id` is the sink of the tail's dataflow, which runs on
+    ///   `compute_instance`.
+    /// * If `flow` is present, the tail is flow controlled.
+    /// * If `max_result_rows` is present, the tail fails once it has sent more
+    ///   than that many data rows.
+    /// * If `up_to` is present, the tail ends once it has sent every update at
+    ///   times before `up_to`.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         channel: mpsc::UnboundedSender<PeekResponseUnary>,
         emit_progress: bool,
+        envelope: TailEnvelope,
         arity: usize,
+        sink_id: GlobalId,
+        compute_instance: ComputeInstanceId,
+        flow: Option<TailFlow>,
+        max_result_rows: Option<usize>,
+        up_to: Option<Timestamp>,
     ) -> Self {
         Self {
             channel,
             emit_progress,
+            envelope,
             arity,
+            sink_id: Some(sink_id),
+            compute_instance,
+            flow,
+            max_result_rows,
+            rows_sent: 0,
+            up_to,
+            progress: None,
+            active: false,
         }
     }
 
+    /// Reports whether the tail's dataflow has been dropped because its client
+    /// fell behind.
+    pub(crate) fn is_paused(&self) -> bool {
+        self.sink_id.is_none()
+    }
+
     /// Process a tail response
     ///
-    /// Returns `true` if the sink should be removed.
+    /// Returns `true` if the sink should be removed, either because its input
+    /// is exhausted or because it has reached its `UP TO` timestamp. If the
+    /// response is a batch that would exceed a flow-controlled tail's limit
+    /// of unacknowledged batches, the batch is discarded and the tail is
+    /// paused; the caller is responsible for dropping the tail's dataflow. If
+    /// the batch would exceed the tail's limit on rows, the tail fails
+    /// instead.
     pub(crate) fn process_response(&mut self, response: TailResponse) -> bool {
         let mut row_buf = Row::default();
         match response {
@@ -54,35 +270,83 @@ impl PendingTail {
                 upper,
                 updates: mut rows,
             }) => {
+                if let Some(flow) = &mut self.flow {
+                    if flow.unacked.len() >= flow.max_unacked {
+                        self.sink_id = None;
+                        return false;
+                    }
+                    flow.advance(&upper);
+                }
+
                 // Sort results by time. We use stable sort here because it will produce deterministic
                 // results since the cursor will always produce rows in the same order.
                 // TODO: Is sorting necessary?
-                rows.sort_by_key(|(time, _, _)| *time);
-
-                let rows = rows
-                    .into_iter()
-                    .map(|(time, row, diff)| {
-                        let mut packer = row_buf.packer();
-                        packer.push(Datum::from(numeric::Numeric::from(time)));
-                        if self.emit_progress {
-                            // When sinking with PROGRESS, the output
-                            // includes an additional column that
-                            // indicates whether a timestamp is
-                            // complete. For regular "data" updates this
-                            // is always `false`.
-                            packer.push(Datum::False);
-                        }
+                // The diffs envelope additionally sends retractions before
+                // insertions within each timestamp.
+                match self.envelope {
+                    TailEnvelope::Updates => rows.sort_by_key(|(time, _, _)| *time),
+                    TailEnvelope::Diffs => rows.sort_by_key(|(time, _, diff)| (*time, *diff > 0)),
+                }
+
+                // Updates at or beyond the `UP TO` timestamp are never sent,
+                // and the tail is complete once no earlier updates remain.
+                let mut complete = upper.is_empty();
+                if let Some(up_to) = self.up_to {
+                    rows.retain(|(time, _, _)| *time < up_to);
+                    complete |= !upper.less_than(&up_to);
+                }
+
+                self.rows_sent += rows.len();
+                if let Some(max_result_rows) = self.max_result_rows {
+                    if self.rows_sent > max_result_rows {
+                        let error = CoordError::ResultRowsExceeded(max_result_rows);
+                        let _ = self
+                            .channel
+                            .send(PeekResponseUnary::Error(error.to_string()));
+                        return true;
+                    }
+                }
+
+                let mut out = Vec::with_capacity(rows.len());
+                let mut rows = rows.into_iter().peekable();
+                while let Some((time, row, diff)) = rows.next() {
+                    let mut packer = row_buf.packer();
+                    packer.push(Datum::from(numeric::Numeric::from(time)));
+                    if self.has_progress_column() {
+                        // When sinking with PROGRESS, the output
+                        // includes an additional column that
+                        // indicates whether a timestamp is
+                        // complete. For regular "data" updates this
+                        // is always `false`.
+                        packer.push(Datum::False);
+                    }
 
-                        packer.push(Datum::Int64(diff));
+                    packer.push(Datum::Int64(diff));
 
-                        packer.extend_by_row(&row);
+                    packer.extend_by_row(&row);
 
-                        row_buf.clone()
-                    })
-                    .collect();
+                    out.push(row_buf.clone());
+
+                    // With the diffs envelope, the last update at each
+                    // timestamp is followed by a progress row that closes
+                    // it. The last timestamp of the batch is closed by the
+                    // progress row at the batch's upper below, or, if the
+                    // input is exhausted, here.
+                    if self.envelope == TailEnvelope::Diffs {
+                        let next = match rows.peek() {
+                            Some((next, _, _)) => Some(*next),
+                            None if upper.is_empty() => Some(time + 1),
+                            None => None,
+                        };
+                        if let Some(next) = next.filter(|next| *next > time) {
+                            out.push(self.progress_row(next));
+                        }
+                    }
+                }
+                let rows = out;
                 // TODO(benesch): the lack of backpressure here can result in
                 // unbounded memory usage.
-                let result = self.channel.send(PeekResponseUnary::Rows(rows));
+                let result = self.send(rows);
                 if result.is_err() {
                     // TODO(benesch): we should actually drop the sink if the
                     // receiver has gone away. E.g. form a DROP SINK command?
@@ -94,21 +358,20 @@ impl PendingTail {
                         1,
                         "TAIL only supports single-dimensional timestamps"
                     );
-                    let mut packer = row_buf.packer();
-                    packer.push(Datum::from(numeric::Numeric::from(*&upper[0])));
-                    packer.push(Datum::True);
-                    // Fill in the diff column and all table columns with NULL.
-                    for _ in 0..(self.arity + 1) {
-                        packer.push(Datum::Null);
-                    }
-
-                    let result = self.channel.send(PeekResponseUnary::Rows(vec![row_buf]));
+                    // A tail never reports progress beyond its `UP TO`
+                    // timestamp.
+                    let progress = match self.up_to {
+                        Some(up_to) => cmp::min(upper[0], up_to),
+                        None => upper[0],
+                    };
+                    self.progress = Some(progress);
+                    let result = self.send_progress(progress);
                     if result.is_err() {
                         // TODO(benesch): we should actually drop the sink if the
                         // receiver has gone away. E.g. form a DROP SINK command?
                     }
                 }
-                upper.is_empty()
+                complete
             }
             TailResponse::DroppedAt(_frontier) => {
                 // TODO: Could perhaps do this earlier, in response to DROP SINK.
@@ -116,4 +379,75 @@ impl PendingTail {
             }
         }
     }
+
+    /// Repeats the tail's most recent progress row if the tail has sent
+    /// nothing since the previous heartbeat.
+    ///
+    /// Heartbeats have no effect on tails without `PROGRESS`, on paused tails,
+    /// and on tails that have not yet reported any progress.
+    pub(crate) fn heartbeat(&mut self) {
+        let active = mem::replace(&mut self.active, false);
+        if !self.emit_progress || active || self.is_paused() {
+            return;
+        }
+        if let Some(progress) = self.progress {
+            // It is not an error for the client to have gone away.
+            let _ = self.send_progress(progress);
+            self.active = false;
+        }
+    }
+
+    /// Reports an error to the client, ending the tail.
+    pub(crate) fn send_error(self, error: String) {
+        // It is not an error for the client to have gone away.
+        let _ = self.channel.send(PeekResponseUnary::Error(error));
+    }
+
+    /// Reports to the client that the tail exceeded its statement timeout,
+    /// ending the tail.
+    pub(crate) fn send_timed_out(&self) {
+        // It is not an error for the client to have gone away.
+        let _ = self.channel.send(PeekResponseUnary::TimedOut);
+    }
+
+    /// Reports whether the tail's rows include an `mz_progressed` column.
+    fn has_progress_column(&self) -> bool {
+        self.emit_progress && self.envelope == TailEnvelope::Updates
+    }
+
+    /// Returns a progress row that reports that the tail is complete up to
+    /// `time`.
+    fn progress_row(&self, time: Timestamp) -> Row {
+        let mut row = Row::default();
+        let mut packer = row.packer();
+        packer.push(Datum::from(numeric::Numeric::from(time)));
+        if self.has_progress_column() {
+            packer.push(Datum::True);
+        }
+        // Fill in the diff column and all table columns with NULL.
+        for _ in 0..(self.arity + 1) {
+            packer.push(Datum::Null);
+        }
+        row
+    }
+
+    /// Sends a progress row that reports that the tail is complete up to
+    /// `time`.
+    fn send_progress(
+        &mut self,
+        time: Timestamp,
+    ) -> Result<(), mpsc::error::SendError<PeekResponseUnary>> {
+        let row = self.progress_row(time);
+        self.send(vec![row])
+    }
+
+    /// Sends a batch of rows to the client.
+    fn send(&mut self, rows: Vec<Row>) -> Result<(), mpsc::error::SendError<PeekResponseUnary>> {
+        self.active = true;
+        if let Some(flow) = &mut self.flow {
+            let time = *flow.resume_at.frontier.as_option().unwrap();
+            flow.unacked.push_back(time);
+        }
+        self.channel.send(PeekResponseUnary::Rows(rows))
+    }
 }
@@ -7,6 +7,9 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
+use mz_ore::retry::Retry;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::oneshot;
 
@@ -59,3 +62,16 @@ impl<T> Drop for ClientTransmitter<T> {
         }
     }
 }
+
+/// Returns the policy with which the external effects of DDL statements, like
+/// creating a Kafka topic or writing a secret, are retried when they fail
+/// transiently.
+///
+/// The policy is bounded so that a persistent failure is reported to the
+/// client, and the catalog changes of the statement rolled back, in a timely
+/// fashion.
+pub fn ddl_retry() -> Retry {
+    Retry::default()
+        .clamp_backoff(Duration::from_secs(1))
+        .max_duration(Duration::from_secs(10))
+}
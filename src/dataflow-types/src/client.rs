@@ -39,6 +39,7 @@ pub use controller::Controller;
 
 pub mod partitioned;
 pub mod replicated;
+pub mod rpc;
 
 /// Explicit instructions for timely dataflow workers.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -171,6 +172,11 @@ pub struct RenderSourcesCommand<T> {
 pub enum StorageCommand<T = mz_repr::Timestamp> {
     /// Create the enumerated sources, each associated with its identifier.
     CreateSources(Vec<CreateSourceCommand<T>>),
+    /// Replace the descriptions of the enumerated sources.
+    ///
+    /// Instances of the sources that are rendered afterwards use the new
+    /// descriptions. Instances that are already running are unaffected.
+    AlterSources(Vec<(GlobalId, SourceDesc)>),
     /// Render the enumerated sources.
     RenderSources(Vec<RenderSourcesCommand<T>>),
     /// Enable compaction in storage-managed collections.
@@ -279,6 +285,13 @@ impl<T: timely::progress::Timestamp> ComputeCommandHistory<T> {
                     drop_command = Some(cmd);
                 }
                 ComputeCommand::CreateDataflows(dataflows) => {
+                    // A dataflow that exports a collection supersedes earlier
+                    // dataflows that exported it, which were dropped before
+                    // the collection was created anew.
+                    for id in dataflows.iter().flat_map(|dataflow| dataflow.export_ids()) {
+                        final_frontiers.remove(&id);
+                        live_dataflows.retain(|dataflow| !dataflow.export_ids().any(|e| e == id));
+                    }
                     live_dataflows.extend(dataflows);
                 }
                 ComputeCommand::AllowCompaction(frontiers) => {
@@ -451,6 +464,13 @@ pub trait GenericClient<C, R>: fmt::Debug + Send {
     /// dataflow server has been shut down, returns `None`.
     async fn recv(&mut self) -> Result<Option<R>, anyhow::Error>;
 
+    /// Returns profilers for the remote processes that serve the client.
+    ///
+    /// A client that is served by the current process has no profilers.
+    fn profilers(&self) -> Vec<rpc::RpcProfiler> {
+        vec![]
+    }
+
     /// Returns an adapter that treats the client as a stream.
     ///
     /// The stream produces the responses that would be produced by repeated
@@ -500,6 +520,9 @@ where
     async fn recv(&mut self) -> Result<Option<R>, anyhow::Error> {
         (**self).recv().await
     }
+    fn profilers(&self) -> Vec<rpc::RpcProfiler> {
+        (**self).profilers()
+    }
 }
 
 #[async_trait]
@@ -510,6 +533,9 @@ impl<T: Send> GenericClient<ComputeCommand<T>, ComputeResponse<T>> for Box<dyn C
     async fn recv(&mut self) -> Result<Option<ComputeResponse<T>>, anyhow::Error> {
         (**self).recv().await
     }
+    fn profilers(&self) -> Vec<rpc::RpcProfiler> {
+        (**self).profilers()
+    }
 }
 
 #[async_trait]
@@ -520,6 +546,9 @@ impl<T: Send> GenericClient<StorageCommand<T>, StorageResponse<T>> for Box<dyn S
     async fn recv(&mut self) -> Result<Option<StorageResponse<T>>, anyhow::Error> {
         (**self).recv().await
     }
+    fn profilers(&self) -> Vec<rpc::RpcProfiler> {
+        (**self).profilers()
+    }
 }
 
 /// An generic command sender.
@@ -615,27 +644,30 @@ where
     C: fmt::Debug + Send,
     R: fmt::Debug + Send,
 {
-    client: partitioned::Partitioned<tcp::TcpClient<C, R>, C, R>,
+    client: partitioned::Partitioned<rpc::RpcClient<C, R>, C, R>,
 }
 
 impl<C, R> RemoteClient<C, R>
 where
     (C, R): partitioned::Partitionable<C, R>,
-    C: fmt::Debug + Send,
-    R: fmt::Debug + Send,
+    C: Serialize + Clone + fmt::Debug + Send + Unpin + 'static,
+    R: DeserializeOwned + fmt::Debug + Send + Unpin + 'static,
 {
-    /// Construct a client backed by multiple tcp connections
+    /// Construct a client backed by multiple RPC connections
+    ///
+    /// Must be called from within a Tokio runtime, as each connection is
+    /// driven by a task of its own.
     pub fn new(addrs: &[impl tokio::net::ToSocketAddrs + std::fmt::Display]) -> Self {
         let mut remotes = Vec::with_capacity(addrs.len());
         for addr in addrs.iter() {
-            remotes.push(tcp::TcpClient::new(addr.to_string()));
+            remotes.push(rpc::RpcClient::new(addr.to_string()));
         }
         Self {
             client: partitioned::Partitioned::new(remotes),
         }
     }
 
-    /// Construct a client backed by multiple tcp connections
+    /// Connects each of the underlying RPC connections
     pub async fn connect(&mut self) {
         // TODO: initiate connections concurrently.
         for remote in self.client.parts.iter_mut() {
@@ -648,8 +680,8 @@ where
 impl<C, R> GenericClient<C, R> for RemoteClient<C, R>
 where
     (C, R): partitioned::Partitionable<C, R>,
-    C: Serialize + fmt::Debug + Unpin + Send,
-    R: DeserializeOwned + fmt::Debug + Unpin + Send,
+    C: Serialize + Clone + fmt::Debug + Unpin + Send + 'static,
+    R: DeserializeOwned + fmt::Debug + Unpin + Send + 'static,
 {
     async fn send(&mut self, cmd: C) -> Result<(), anyhow::Error> {
         trace!("Sending dataflow command: {:?}", cmd);
@@ -660,6 +692,9 @@ where
         trace!("Receiving dataflow response: {:?}", response);
         response
     }
+    fn profilers(&self) -> Vec<rpc::RpcProfiler> {
+        self.client.profilers()
+    }
 }
 
 /// A client backed by a process-local timely worker thread.
@@ -720,180 +755,3 @@ pub mod process_local {
         }
     }
 }
-
-/// A client to a remote dataflow server.
-pub mod tcp {
-    use std::fmt;
-    use std::future::Future;
-    use std::pin::Pin;
-    use std::time::Duration;
-
-    use async_trait::async_trait;
-    use futures::sink::SinkExt;
-    use futures::stream::StreamExt;
-    use serde::de::DeserializeOwned;
-    use serde::ser::Serialize;
-    use tokio::io::{self, AsyncRead, AsyncWrite};
-    use tokio::net::TcpStream;
-    use tokio::time::{self, Instant};
-    use tokio_serde::formats::Bincode;
-    use tokio_util::codec::LengthDelimitedCodec;
-
-    use crate::client::GenericClient;
-
-    enum TcpConn<C, R> {
-        Disconnected,
-        Connecting(Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>),
-        Backoff(Instant),
-        Connected(FramedClient<TcpStream, C, R>),
-    }
-
-    impl<C, R> fmt::Debug for TcpConn<C, R> {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            f.write_str("TcpConn")
-        }
-    }
-
-    /// A client to a remote dataflow server.
-    ///
-    /// If the client experiences errors, it will attempt a reconnection in the `recv` method and
-    /// produce an error for the call in which that reconnection happens, allowing a bearer to
-    /// re-issue commands. As the reconnection happens in `recv()`, the bearer is advised to use
-    /// a `select` style construct to avoid suspending their task by a call to `recv()`.
-    #[derive(Debug)]
-    pub struct TcpClient<C, R> {
-        connection: TcpConn<C, R>,
-        addr: String,
-    }
-
-    impl<C, R> TcpClient<C, R> {
-        /// Creates a new `TcpClient` initially in a disconnected state.
-        ///
-        /// Use the `connect()` method to put the client into a connected state.
-        pub fn new(addr: String) -> TcpClient<C, R> {
-            Self {
-                connection: TcpConn::Disconnected,
-                addr,
-            }
-        }
-
-        /// Reports whether the client is actively connected.
-        pub fn connected(&self) -> bool {
-            matches!(self.connection, TcpConn::Connected(_))
-        }
-
-        /// Connects the underlying `connection`.
-        pub async fn connect(&mut self) {
-            // This is written in state-machine style to be cancellation safe.
-            loop {
-                match &mut self.connection {
-                    TcpConn::Disconnected => {
-                        let connecting = Box::pin(TcpStream::connect(self.addr.clone()));
-                        self.connection = TcpConn::Connecting(connecting);
-                    }
-                    TcpConn::Connecting(connecting) => match connecting.await {
-                        Ok(connection) => {
-                            tracing::info!("Reconnected to {}", self.addr);
-                            self.connection = TcpConn::Connected(framed_client(connection));
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Error connecting to {}: {e}; reconnecting in 1s",
-                                self.addr
-                            );
-                            let deadline = Instant::now() + Duration::from_secs(1);
-                            self.connection = TcpConn::Backoff(deadline);
-                        }
-                    },
-                    TcpConn::Backoff(deadline) => {
-                        time::sleep_until(*deadline).await;
-                        self.connection = TcpConn::Disconnected;
-                    }
-                    TcpConn::Connected(_) => break,
-                }
-            }
-        }
-    }
-
-    #[async_trait]
-    impl<C, R> GenericClient<C, R> for TcpClient<C, R>
-    where
-        C: Serialize + fmt::Debug + Send + Unpin,
-        R: DeserializeOwned + fmt::Debug + Send + Unpin,
-    {
-        async fn send(&mut self, cmd: C) -> Result<(), anyhow::Error> {
-            if let TcpConn::Connected(connection) = &mut self.connection {
-                let result = connection.send(cmd).await;
-                if result.is_err() {
-                    self.connection = TcpConn::Disconnected;
-                }
-                Ok(result?)
-            } else {
-                Err(anyhow::anyhow!("Sent into disconnected channel"))
-            }
-        }
-
-        async fn recv(&mut self) -> Result<Option<R>, anyhow::Error> {
-            if let TcpConn::Connected(connection) = &mut self.connection {
-                match connection.next().await {
-                    Some(Ok(response)) => Ok(Some(response)),
-                    _ => {
-                        self.connection = TcpConn::Disconnected;
-                        self.connect().await;
-                        Err(anyhow::anyhow!("Connection severed; reconnected"))
-                    }
-                }
-            } else {
-                self.connect().await;
-                Err(anyhow::anyhow!("Connection severed; reconnected"))
-            }
-        }
-    }
-
-    /// A framed connection to a dataflowd server.
-    pub type Framed<C, T, U> = tokio_serde::Framed<
-        tokio_util::codec::Framed<C, LengthDelimitedCodec>,
-        T,
-        U,
-        Bincode<T, U>,
-    >;
-
-    /// A framed connection from the server's perspective.
-    pub type FramedServer<A, C, R> = Framed<A, C, R>;
-
-    /// A framed connection from the client's perspective.
-    pub type FramedClient<A, C, R> = Framed<A, R, C>;
-
-    fn length_delimited_codec() -> LengthDelimitedCodec {
-        // NOTE(benesch): using an unlimited maximum frame length is problematic
-        // because Tokio never shrinks its buffer. Sending or receiving one large
-        // message of size N means the client will hold on to a buffer of size
-        // N forever. We should investigate alternative transport protocols that
-        // do not have this limitation.
-        let mut codec = LengthDelimitedCodec::new();
-        codec.set_max_frame_length(usize::MAX);
-        codec
-    }
-
-    /// Constructs a framed connection for the server.
-    pub fn framed_server<A, C, R>(conn: A) -> FramedServer<A, C, R>
-    where
-        A: AsyncRead + AsyncWrite,
-    {
-        tokio_serde::Framed::new(
-            tokio_util::codec::Framed::new(conn, length_delimited_codec()),
-            Bincode::default(),
-        )
-    }
-
-    /// Constructs a framed connection for the client.
-    pub fn framed_client<A, C, R>(conn: A) -> FramedClient<A, C, R>
-    where
-        A: AsyncRead + AsyncWrite,
-    {
-        tokio_serde::Framed::new(
-            tokio_util::codec::Framed::new(conn, length_delimited_codec()),
-            Bincode::default(),
-        )
-    }
-}
@@ -21,7 +21,8 @@
 //! Consult the `StorageController` and `ComputeController` documentation for more information
 //! about each of these interfaces.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 use anyhow::bail;
 use derivative::Derivative;
@@ -31,9 +32,16 @@ use maplit::hashmap;
 use timely::progress::frontier::{Antichain, AntichainRef};
 use timely::progress::Timestamp;
 use tokio_stream::StreamMap;
+use tracing::warn;
 
-use mz_orchestrator::{Orchestrator, ServiceConfig, ServicePort};
+use mz_orchestrator::{
+    AntiAffinity, CpuLimit, DiskLimit, MemoryLimit, Orchestrator, PlacementHints, ReadinessCheck,
+    ReadinessProbe, ServiceConfig, ServiceDiagnostics, ServicePort, Toleration,
+};
+use mz_ore::retry::Retry;
+use mz_ore::str::StrExt;
 
+use crate::client::rpc::RpcProfiler;
 use crate::client::GenericClient;
 use crate::client::{
     ComputeClient, ComputeCommand, ComputeInstanceId, ComputeResponse, InstanceConfig,
@@ -47,6 +55,9 @@ pub mod storage;
 pub use compute::{ComputeController, ComputeControllerMut};
 mod compute;
 
+/// The name of the sole replica of a local or managed compute instance.
+pub const DEFAULT_REPLICA: &str = "default";
+
 /// Configures an orchestrator for the controller.
 pub struct OrchestratorConfig {
     /// The orchestrator implementation to use.
@@ -55,6 +66,24 @@ pub struct OrchestratorConfig {
     pub dataflowd_image: String,
     /// The storage address that compute instances should connect to.
     pub storage_addr: String,
+    /// Labels that a node must have for compute instances to be placed on it.
+    pub compute_node_selector: HashMap<String, String>,
+    /// Taints that compute instances tolerate.
+    pub compute_tolerations: Vec<Toleration>,
+    /// The disk to provision for each process of a compute instance.
+    pub disk_limit: Option<DiskLimit>,
+    /// The resources to allocate to the replica of a managed compute instance
+    /// of each size, by the name of the size.
+    pub cluster_replica_sizes: HashMap<String, ClusterReplicaSize>,
+}
+
+/// The resources allocated to the replica of a managed compute instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterReplicaSize {
+    /// The CPU limit of the replica, if any.
+    pub cpu_limit: Option<CpuLimit>,
+    /// The memory limit of the replica, if any.
+    pub memory_limit: Option<MemoryLimit>,
 }
 
 /// A client that maintains soft state and validates commands, in addition to forwarding them.
@@ -97,7 +126,7 @@ where
                     .expect("cannot create more than one local compute instance");
                 self.compute_mut(instance)
                     .unwrap()
-                    .add_replica("default".into(), client)
+                    .add_replica(DEFAULT_REPLICA.into(), client)
                     .await;
             }
             InstanceConfig::Remote { replicas } => {
@@ -108,65 +137,268 @@ where
                     compute_instance.add_replica(name, client).await;
                 }
             }
-            InstanceConfig::Managed { size: _ } => {
-                let OrchestratorConfig {
-                    orchestrator,
-                    storage_addr,
-                    dataflowd_image,
-                } = match &mut self.orchestrator {
-                    Some(orchestrator) => orchestrator,
-                    // TODO(benesch): bailing here is too late. Something
-                    // earlier needs to recognize when we can't create managed
-                    // instances.
-                    _ => bail!("cannot create managed instances in this configuration"),
+            InstanceConfig::Managed { size } => {
+                let client = match self.ensure_managed_service(instance, &size).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        self.compute.remove(&instance);
+                        return Err(e);
+                    }
                 };
-                let service = orchestrator
-                    .namespace("compute")
-                    .ensure_service(
-                        &format!("cluster-{instance}"),
-                        ServiceConfig {
-                            image: dataflowd_image.clone(),
-                            args: &|ports| {
-                                vec![
-                                    "--runtime=compute".into(),
-                                    format!("--storage-addr={storage_addr}"),
-                                    format!("--listen-addr=0.0.0.0:{}", ports["controller"]),
-                                    format!("0.0.0.0:{}", ports["compute"]),
-                                ]
-                            },
-                            ports: vec![
-                                ServicePort {
-                                    name: "controller".into(),
-                                    port_hint: 2100,
-                                },
-                                ServicePort {
-                                    name: "compute".into(),
-                                    port_hint: 2102,
-                                },
-                            ],
-                            // TODO: use `size` to set these.
-                            cpu_limit: None,
-                            memory_limit: None,
-                            // TODO: support sizes large enough to warrant multiple processes.
-                            processes: 1,
-                            labels: hashmap! {
-                                "cluster-id".into() => instance.to_string(),
-                                "type".into() => "cluster".into(),
-                            },
-                        },
-                    )
-                    .await?;
-                let client = RemoteClient::new(&service.addresses("controller"));
-                let client: Box<dyn ComputeClient<T>> = Box::new(client);
                 self.compute_mut(instance)
                     .unwrap()
-                    .add_replica("default".into(), client)
+                    .add_replica(DEFAULT_REPLICA.into(), client)
                     .await;
             }
         }
 
         Ok(())
     }
+
+    /// Restarts the replica named `replica` of the compute instance
+    /// `instance`, whose configuration is `config`.
+    ///
+    /// The controller reconnects to the replica and replays the instance's
+    /// commands to it. The processes that back the replica of a managed
+    /// instance are relaunched first.
+    pub async fn restart_replica(
+        &mut self,
+        instance: ComputeInstanceId,
+        config: &InstanceConfig,
+        replica: &str,
+    ) -> Result<(), anyhow::Error> {
+        if !self.compute.contains_key(&instance) {
+            bail!("unknown compute instance {instance}");
+        }
+        let client: Box<dyn ComputeClient<T>> = match config {
+            InstanceConfig::Local => bail!("cannot restart the replica of a local cluster"),
+            InstanceConfig::Remote { replicas } => match replicas.get(replica) {
+                Some(hosts) => Box::new(RemoteClient::new(
+                    &hosts.iter().cloned().collect::<Vec<_>>(),
+                )),
+                None => bail!("unknown replica {}", replica.quoted()),
+            },
+            InstanceConfig::Managed { size } => {
+                if replica != DEFAULT_REPLICA {
+                    bail!("unknown replica {}", replica.quoted());
+                }
+                if let Some(OrchestratorConfig { orchestrator, .. }) = &mut self.orchestrator {
+                    orchestrator
+                        .namespace("compute")
+                        .drop_service(&format!("cluster-{instance}"))
+                        .await?;
+                }
+                self.ensure_managed_service(instance, size).await?
+            }
+        };
+        let mut compute = self.compute_mut(instance).unwrap();
+        compute.remove_replica(replica);
+        compute.add_replica(replica.into(), client).await;
+        Ok(())
+    }
+
+    /// Returns diagnostic information about the processes that back the
+    /// replica named `replica` of the compute instance `instance`, whose
+    /// configuration is `config`, like how often they have crashed.
+    ///
+    /// Returns `None` if the replica's processes are not managed by the
+    /// orchestrator.
+    pub async fn replica_diagnostics(
+        &self,
+        instance: ComputeInstanceId,
+        config: &InstanceConfig,
+        replica: &str,
+    ) -> Result<Option<ServiceDiagnostics>, anyhow::Error> {
+        if !self.compute.contains_key(&instance) {
+            bail!("unknown compute instance {instance}");
+        }
+        match config {
+            InstanceConfig::Local => bail!("a local cluster has no replicas"),
+            InstanceConfig::Remote { replicas } => {
+                if !replicas.contains_key(replica) {
+                    bail!("unknown replica {}", replica.quoted());
+                }
+                Ok(None)
+            }
+            InstanceConfig::Managed { .. } if replica != DEFAULT_REPLICA => {
+                bail!("unknown replica {}", replica.quoted())
+            }
+            InstanceConfig::Managed { .. } => match &self.orchestrator {
+                Some(OrchestratorConfig { orchestrator, .. }) => {
+                    let diagnostics = orchestrator
+                        .namespace("compute")
+                        .service_diagnostics(&format!("cluster-{instance}"))
+                        .await?;
+                    Ok(Some(diagnostics))
+                }
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Returns profilers for the remote processes that serve the storage
+    /// layer and the replicas of each compute instance, each with a label
+    /// that describes its process.
+    pub fn profilers(&self) -> Vec<(String, RpcProfiler)> {
+        let mut profilers = vec![];
+        for profiler in self.storage_controller.profilers() {
+            let label = format!("storage ({})", profiler.addr());
+            profilers.push((label, profiler));
+        }
+        for (instance, state) in &self.compute {
+            for (replica, client) in state.client.replicas() {
+                for profiler in client.profilers() {
+                    let label = format!(
+                        "compute instance {instance} replica {replica} ({})",
+                        profiler.addr()
+                    );
+                    profilers.push((label, profiler));
+                }
+            }
+        }
+        profilers
+    }
+
+    /// Launches the service that backs the managed compute instance
+    /// `instance` of size `size`, if it is not already running, and returns a
+    /// client for it.
+    ///
+    /// Launching the service is retried if it fails, which is safe because
+    /// the orchestrator identifies the service by its ID. If the service
+    /// cannot be launched, whatever parts of it were launched are dropped.
+    async fn ensure_managed_service(
+        &mut self,
+        instance: ComputeInstanceId,
+        size: &str,
+    ) -> Result<Box<dyn ComputeClient<T>>, anyhow::Error> {
+        let OrchestratorConfig {
+            orchestrator,
+            storage_addr,
+            dataflowd_image,
+            compute_node_selector,
+            compute_tolerations,
+            disk_limit,
+            cluster_replica_sizes,
+        } = match &mut self.orchestrator {
+            Some(orchestrator) => orchestrator,
+            // TODO(benesch): bailing here is too late. Something
+            // earlier needs to recognize when we can't create managed
+            // instances.
+            _ => bail!("cannot create managed instances in this configuration"),
+        };
+        // New sizes are validated when the instance is created, but a size
+        // may have been removed from the configuration since. Rather than
+        // refusing to launch the instance, launch it without limits.
+        let resources = match cluster_replica_sizes.get(size) {
+            Some(resources) => resources.clone(),
+            None => {
+                if !cluster_replica_sizes.is_empty() {
+                    warn!("launching cluster {instance} of unknown size {size} without limits");
+                }
+                ClusterReplicaSize::default()
+            }
+        };
+        let mut namespace = orchestrator.namespace("compute");
+        let id = format!("cluster-{instance}");
+        let config = ServiceConfig {
+            image: dataflowd_image.clone(),
+            args: vec![
+                "--runtime=compute".into(),
+                format!("--storage-addr={storage_addr}"),
+                "--listen-addr=%{listen_host}:%{ports.controller}".into(),
+                "%{listen_host}:%{ports.compute}".into(),
+            ],
+            ports: vec![
+                ServicePort {
+                    name: "controller".into(),
+                    port_hint: 2100,
+                },
+                ServicePort {
+                    name: "compute".into(),
+                    port_hint: 2102,
+                },
+            ],
+            cpu_limit: resources.cpu_limit,
+            memory_limit: resources.memory_limit,
+            disk_limit: disk_limit.clone(),
+            // TODO: support sizes large enough to warrant multiple processes.
+            processes: 1,
+            readiness_probe: Some(ReadinessProbe {
+                port: "controller".into(),
+                check: ReadinessCheck::Tcp,
+                timeout: Duration::from_secs(60),
+            }),
+            // Spread the processes of the cluster across
+            // nodes, so that losing a node does not take down
+            // every replica.
+            placement: PlacementHints {
+                availability_zones: vec![],
+                anti_affinity: vec![AntiAffinity {
+                    labels: hashmap! {
+                        "cluster-id".into() => instance.to_string(),
+                    },
+                    required: false,
+                }],
+                node_selector: compute_node_selector.clone(),
+                tolerations: compute_tolerations.clone(),
+            },
+            labels: hashmap! {
+                "cluster-id".into() => instance.to_string(),
+                "type".into() => "cluster".into(),
+            },
+            annotations: hashmap! {},
+        };
+        let retry = Retry::default()
+            .clamp_backoff(Duration::from_secs(1))
+            .max_duration(Duration::from_secs(30))
+            .into_retry_stream();
+        tokio::pin!(retry);
+        let mut last_error = None;
+        while retry.next().await.is_some() {
+            match namespace.ensure_service(&id, config.clone()).await {
+                Ok(service) => {
+                    let client = RemoteClient::new(&service.addresses("controller"));
+                    return Ok(Box::new(client));
+                }
+                Err(e) => {
+                    warn!("retrying launch of service {id}: {e:#}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Err(e) = namespace.drop_service(&id).await {
+            warn!("unable to drop service {id} after failed launch: {e:#}");
+        }
+        Err(last_error.expect("retry produces at least one element"))
+    }
+
+    /// Returns the resources that the replica of a managed compute instance
+    /// of size `size` is allocated.
+    ///
+    /// If no sizes are configured, every size is valid and is allocated no
+    /// particular resources. Otherwise, returns `None` if `size` is unknown.
+    pub fn cluster_replica_size(&self, size: &str) -> Option<ClusterReplicaSize> {
+        match &self.orchestrator {
+            Some(OrchestratorConfig {
+                cluster_replica_sizes,
+                ..
+            }) if !cluster_replica_sizes.is_empty() => cluster_replica_sizes.get(size).cloned(),
+            _ => Some(ClusterReplicaSize::default()),
+        }
+    }
+
+    /// Replaces the resources allocated to the replicas of managed compute
+    /// instances of each size.
+    ///
+    /// The new resources apply to replicas launched from now on, i.e., those
+    /// of newly created instances and restarted replicas. Running replicas
+    /// keep their resources.
+    pub fn set_cluster_replica_sizes(&mut self, sizes: HashMap<String, ClusterReplicaSize>) {
+        if let Some(orchestrator) = &mut self.orchestrator {
+            orchestrator.cluster_replica_sizes = sizes;
+        }
+    }
+
     pub async fn drop_instance(
         &mut self,
         instance: ComputeInstanceId,
@@ -175,13 +407,27 @@ where
             if let Some(OrchestratorConfig { orchestrator, .. }) = &mut self.orchestrator {
                 orchestrator
                     .namespace("compute")
-                    .drop_service(&format!("instance-{instance}"))
+                    .drop_service(&format!("cluster-{instance}"))
                     .await?;
             }
             compute.client.send(ComputeCommand::DropInstance).await?;
         }
         Ok(())
     }
+
+    /// Drops every service that the orchestrator launched, e.g., the
+    /// replicas of managed compute instances, and waits for them to
+    /// terminate.
+    ///
+    /// Services that are not dropped can outlive the process, as with
+    /// systemd units or Kubernetes stateful sets. The controller must not be
+    /// used afterwards.
+    pub async fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        if let Some(OrchestratorConfig { orchestrator, .. }) = &mut self.orchestrator {
+            orchestrator.shutdown().await?;
+        }
+        Ok(())
+    }
 }
 
 impl<T> Controller<T> {
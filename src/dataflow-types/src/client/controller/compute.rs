@@ -27,7 +27,7 @@ use std::error::Error;
 use std::fmt;
 
 use differential_dataflow::lattice::Lattice;
-use timely::progress::frontier::MutableAntichain;
+use timely::progress::frontier::{AntichainRef, MutableAntichain};
 use timely::progress::{Antichain, ChangeBatch, Timestamp};
 use uuid::Uuid;
 
@@ -82,6 +82,8 @@ pub enum ComputeError {
     DataflowSinceViolation(GlobalId),
     /// The peek `timestamp` was not greater than the `since` of the identifier.
     PeekSinceViolation(GlobalId),
+    /// The collection was held back by read capabilities other than its own.
+    CollectionInUse(GlobalId),
     /// An error from the underlying client.
     ClientError(anyhow::Error),
     /// An error during an interaction with Storage
@@ -95,7 +97,8 @@ impl Error for ComputeError {
             | Self::IdentifierMissing(_)
             | Self::DataflowMalformed
             | Self::DataflowSinceViolation(_)
-            | Self::PeekSinceViolation(_) => None,
+            | Self::PeekSinceViolation(_)
+            | Self::CollectionInUse(_) => None,
             Self::ClientError(err) => Some(err.root_cause()),
             Self::StorageError(err) => err.source(),
         }
@@ -123,6 +126,9 @@ impl fmt::Display for ComputeError {
                 f,
                 "peek timestamp was not greater than the `since` of the identifier: {id}"
             ),
+            Self::CollectionInUse(id) => {
+                write!(f, "collection is in use by other dataflows or peeks: {id}")
+            }
             Self::ClientError(err) => write!(f, "underlying client error: {err}"),
             Self::StorageError(err) => write!(f, "storage interaction error: {err}"),
         }
@@ -193,6 +199,30 @@ where
             .get(&id)
             .ok_or(ComputeError::IdentifierMissing(id))
     }
+
+    /// Returns the identifiers of the instance's collections, along with their
+    /// states.
+    pub fn collections(&self) -> impl Iterator<Item = (GlobalId, &'a CollectionState<T>)> {
+        self.compute
+            .collections
+            .iter()
+            .map(|(id, collection)| (*id, collection))
+    }
+
+    /// Returns the write frontier of the collection `id` as reported by each
+    /// replica of the instance.
+    ///
+    /// Unlike [`CollectionState::write_frontier`], which is the join of these
+    /// frontiers, this reveals which replicas are behind the others.
+    pub fn replica_write_frontiers(
+        &self,
+        id: GlobalId,
+    ) -> Result<impl Iterator<Item = (&'a str, AntichainRef<'a, T>)>, ComputeError> {
+        self.compute
+            .client
+            .replica_uppers(id)
+            .ok_or(ComputeError::IdentifierMissing(id))
+    }
 }
 
 impl<'a, T> ComputeControllerMut<'a, T>
@@ -359,6 +389,53 @@ where
         self.allow_compaction(compaction_commands).await?;
         Ok(())
     }
+    /// Drops the index `id` and forgets its state, so that a new dataflow may
+    /// create it anew.
+    ///
+    /// Fails without dropping the index if anything besides the index's own
+    /// read policy holds back its `since`, e.g., a dataflow that imports the
+    /// index or an outstanding peek, as those read holds would be lost.
+    pub async fn forget_index(&mut self, id: GlobalId) -> Result<(), ComputeError> {
+        self.check_forget_index(id)?;
+        self.allow_compaction(vec![(id, Antichain::new())]).await?;
+        self.compute.collections.remove(&id);
+        Ok(())
+    }
+    /// Removes the state of the index `id` without dropping the index, so that
+    /// a dataflow to recreate the index can be built as though the index did
+    /// not exist. The state must be returned with
+    /// [`ComputeControllerMut::reattach_index`] before the index is used or
+    /// forgotten.
+    ///
+    /// Fails if [`ComputeControllerMut::forget_index`] would fail.
+    pub fn detach_index(&mut self, id: GlobalId) -> Result<CollectionState<T>, ComputeError> {
+        self.check_forget_index(id)?;
+        Ok(self
+            .compute
+            .collections
+            .remove(&id)
+            .expect("collection known to exist"))
+    }
+    /// Returns the state of an index removed by
+    /// [`ComputeControllerMut::detach_index`].
+    pub fn reattach_index(&mut self, id: GlobalId, state: CollectionState<T>) {
+        let prev = self.compute.collections.insert(id, state);
+        assert!(prev.is_none(), "index {} reattached twice", id);
+    }
+    fn check_forget_index(&self, id: GlobalId) -> Result<(), ComputeError> {
+        let collection = self.as_ref().collection(id)?;
+        let mut others = collection.read_capabilities.clone();
+        others.update_iter(
+            collection
+                .implied_capability
+                .iter()
+                .map(|time| (time.clone(), -1)),
+        );
+        if !others.frontier().is_empty() {
+            return Err(ComputeError::CollectionInUse(id));
+        }
+        Ok(())
+    }
     /// Initiate a peek request for the contents of `id` at `timestamp`.
     pub async fn peek(
         &mut self,
@@ -35,6 +35,7 @@ use mz_expr::{GlobalId, PartitionId};
 use mz_stash::{self, Stash, StashError};
 
 use crate::client::controller::ReadPolicy;
+use crate::client::rpc::RpcProfiler;
 use crate::client::{
     CreateSourceCommand, MzOffset, StorageClient, StorageCommand, StorageResponse,
     TimestampBindingFeedback,
@@ -69,6 +70,15 @@ pub trait StorageController: Debug + Send {
         mut bindings: Vec<(GlobalId, (SourceDesc, Antichain<Self::Timestamp>))>,
     ) -> Result<(), StorageError>;
 
+    /// Replaces the descriptions of existing sources.
+    ///
+    /// Only instances of the sources that are rendered afterwards observe the
+    /// new descriptions.
+    async fn alter_sources(
+        &mut self,
+        descriptions: Vec<(GlobalId, SourceDesc)>,
+    ) -> Result<(), StorageError>;
+
     /// Drops the read capability for the sources and allows their resources to be reclaimed.
     async fn drop_sources(&mut self, identifiers: Vec<GlobalId>) -> Result<(), StorageError>;
 
@@ -127,6 +137,10 @@ pub trait StorageController: Debug + Send {
     ) -> Result<(), anyhow::Error>;
 
     async fn recv(&mut self) -> Result<Option<StorageResponse<Self::Timestamp>>, anyhow::Error>;
+
+    /// Returns profilers for the remote processes that serve the storage
+    /// layer.
+    fn profilers(&self) -> Vec<RpcProfiler>;
 }
 
 /// Controller state maintained for each storage instance.
@@ -301,6 +315,22 @@ where
         Ok(())
     }
 
+    async fn alter_sources(
+        &mut self,
+        descriptions: Vec<(GlobalId, SourceDesc)>,
+    ) -> Result<(), StorageError> {
+        // Validate first, to avoid corrupting state.
+        self.validate_ids(descriptions.iter().map(|(id, _)| *id))?;
+        for (id, desc) in &descriptions {
+            self.collection_mut(*id)?.description.0 = desc.clone();
+        }
+        self.state
+            .client
+            .send(StorageCommand::AlterSources(descriptions))
+            .await
+            .map_err(StorageError::from)
+    }
+
     async fn drop_sources(&mut self, identifiers: Vec<GlobalId>) -> Result<(), StorageError> {
         self.validate_ids(identifiers.iter().cloned())?;
         let policies = identifiers
@@ -564,6 +594,10 @@ where
         self.state.client.recv().await
     }
 
+    fn profilers(&self) -> Vec<RpcProfiler> {
+        self.state.client.profilers()
+    }
+
     /// "Linearize" the listed sources.
     ///
     /// If these sources are valid and "linearizable", then the response
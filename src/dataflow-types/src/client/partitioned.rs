@@ -111,6 +111,13 @@ where
         // Indicate completion of the communication.
         Ok(None)
     }
+
+    fn profilers(&self) -> Vec<RpcProfiler> {
+        self.parts
+            .iter()
+            .flat_map(|part| part.profilers())
+            .collect()
+    }
 }
 
 /// A trait for command–response pairs that can be partitioned across multiple
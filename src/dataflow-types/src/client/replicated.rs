@@ -24,7 +24,8 @@
 
 use std::collections::{HashMap, HashSet};
 
-use timely::progress::{frontier::MutableAntichain, Antichain};
+use timely::progress::frontier::{AntichainRef, MutableAntichain};
+use timely::progress::Antichain;
 
 use crate::client::Peek;
 use mz_expr::GlobalId;
@@ -89,6 +90,27 @@ where
         }
     }
 
+    /// Returns the clients of the replicas, by their identifiers.
+    pub fn replicas(&self) -> impl Iterator<Item = (&str, &C)> {
+        self.replicas
+            .iter()
+            .map(|(identifier, client)| (identifier.as_str(), client))
+    }
+
+    /// Returns the upper frontier of the collection `id` as reported by each
+    /// replica, or `None` if the frontier of `id` is not tracked.
+    pub fn replica_uppers(
+        &self,
+        id: GlobalId,
+    ) -> Option<impl Iterator<Item = (&str, AntichainRef<T>)>> {
+        let (_, frontiers) = self.uppers.get(&id)?;
+        Some(
+            frontiers
+                .iter()
+                .map(|(replica_id, frontier)| (replica_id.as_str(), frontier.frontier())),
+        )
+    }
+
     /// Pipes a command stream at the indicated replica, introducing new dataflow identifiers.
     async fn hydrate_replica(&mut self, replica_id: &str) {
         // Zero out frontiers maintained by this replica.
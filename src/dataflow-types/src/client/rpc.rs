@@ -0,0 +1,1525 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A versioned RPC protocol between the controller and remote dataflow servers.
+//!
+//! A connection multiplexes any number of channels, each of which carries the
+//! commands and responses of one client, along with the control frames that
+//! keep the connection healthy. Each connection opens with a handshake in
+//! which the two sides check that they speak the same protocol version and
+//! agree on the optional features they both support. Afterwards, each side
+//! periodically sends a heartbeat that acknowledges, for each channel, the
+//! messages it has received so far. A connection over which nothing arrives
+//! for [`HEARTBEAT_TIMEOUT`] is presumed dead.
+//!
+//! Channels belong to sessions, which outlive any one connection. Each side
+//! retains the messages it has sent on a channel until the other side
+//! acknowledges them. When a transport reconnects after a transient network
+//! failure, it reopens each of its channels, and the two sides resume each
+//! channel's session by resending exactly the messages that were lost, so the
+//! failure is invisible to the bearer of the client. Only if the server has
+//! restarted, and so has lost the state built up by the session's commands,
+//! does the client report an error, upon which the bearer must replay its
+//! commands from scratch.
+//!
+//! The client side of a connection is driven by a task of its own, so the
+//! connection is kept alive and reestablished whether or not the bearers of
+//! its clients are waiting for responses.
+//!
+//! Besides carrying channels, a connection lets the controller take CPU
+//! profiles of the server's process, so that the processes that back a
+//! deployment can be profiled without attaching external profilers to them.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use futures::sink::{Sink, SinkExt};
+use futures::stream::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::{self, Instant, Interval, MissedTickBehavior};
+use tokio_serde::formats::Bincode;
+use tokio_util::codec::LengthDelimitedCodec;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use mz_ore::cast::CastFrom;
+use mz_ore::task;
+
+use crate::client::GenericClient;
+
+/// The version of the protocol.
+///
+/// Peers that speak different versions refuse to communicate. Bump the version
+/// whenever the framing or the handshake changes incompatibly; use a feature
+/// for backwards compatible extensions.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// The feature that indicates that a peer sends heartbeats.
+pub const FEATURE_HEARTBEAT: &str = "heartbeat";
+
+/// The feature that indicates that a peer can resume a channel's session on a
+/// new connection.
+///
+/// Resuming a session relies on the acknowledgements carried by heartbeats,
+/// and so requires [`FEATURE_HEARTBEAT`].
+pub const FEATURE_RESUME: &str = "resume";
+
+/// The feature that indicates that a server takes CPU profiles of its process
+/// on request.
+pub const FEATURE_PROFILE: &str = "profile";
+
+/// The optional features that this implementation supports.
+pub const FEATURES: &[&str] = &[FEATURE_HEARTBEAT, FEATURE_RESUME, FEATURE_PROFILE];
+
+/// The interval at which peers send heartbeats.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The duration after which a connection over which nothing has arrived is
+/// presumed dead.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The duration within which a peer must complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The duration for which a server that does not linger awaits its client's
+/// reconnection before terminating.
+const RESUME_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The initial delay before a client retries a failed connection attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The maximum delay before a client retries a failed connection attempt.
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// The first frame that each peer sends on a new connection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hello {
+    /// The protocol version that the peer speaks.
+    pub version: u64,
+    /// The optional features that the peer supports.
+    ///
+    /// The server responds with the features that both peers support.
+    pub features: BTreeSet<String>,
+}
+
+/// A frame sent over a connection.
+///
+/// Channels are identified by the session that the client started for them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Frame<M> {
+    /// Opens the connection.
+    Hello(Hello),
+    /// Sent by the client to open `channel`, or to resume it on a new
+    /// connection, having received the first `received` responses of the
+    /// channel's session.
+    Open { channel: Uuid, received: u64 },
+    /// Sent by the server when it accepts `channel`, with the server's
+    /// session for the channel, and having received the first `received`
+    /// commands of the session.
+    Opened {
+        channel: Uuid,
+        session: Uuid,
+        received: u64,
+    },
+    /// A command or a response on `channel`.
+    Message { channel: Uuid, message: M },
+    /// Indicates that the peer is alive, and acknowledges, for each of the
+    /// channels open on the connection, that it has received the first
+    /// messages of the channel's session.
+    Heartbeat { received: Vec<(Uuid, u64)> },
+    /// Closes `channel`, with the reason. Sent by the client when it no longer
+    /// needs the channel, or by the server when it rejects the channel.
+    Close { channel: Uuid, reason: String },
+    /// Sent by the server when it rejects the connection, with the reason.
+    Goodbye(String),
+    /// Sent by the client to request a CPU profile of the server's process
+    /// over `duration`, identified by `request`.
+    Profile { request: u64, duration: Duration },
+    /// Sent by the server with the CPU profile requested by `request`, in the
+    /// folded format of [`mz_prof::StackProfile::to_folded`], or the reason
+    /// that it could not be taken.
+    Profiled {
+        request: u64,
+        profile: Result<String, String>,
+    },
+}
+
+/// Returns the features that both this implementation and a peer that supports
+/// `features` support.
+fn negotiate(features: &BTreeSet<String>) -> BTreeSet<String> {
+    let mut agreed: BTreeSet<String> = FEATURES
+        .iter()
+        .filter(|f| features.contains(**f))
+        .map(|f| f.to_string())
+        .collect();
+    if !agreed.contains(FEATURE_HEARTBEAT) {
+        agreed.remove(FEATURE_RESUME);
+    }
+    agreed
+}
+
+/// Returns a [`Hello`] that offers every supported feature.
+fn hello() -> Hello {
+    Hello {
+        version: PROTOCOL_VERSION,
+        features: FEATURES.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+/// Returns an interval that ticks every [`HEARTBEAT_INTERVAL`].
+fn heartbeat_interval() -> Interval {
+    let mut interval = time::interval(HEARTBEAT_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
+
+/// The messages sent in a session that the peer has not yet acknowledged.
+#[derive(Debug)]
+struct Outbox<M> {
+    /// The number of messages that the peer has acknowledged.
+    acked: u64,
+    /// The number of messages written to the current connection.
+    written: u64,
+    /// The unacknowledged messages, in the order in which they were sent.
+    messages: VecDeque<M>,
+}
+
+impl<M> Default for Outbox<M> {
+    fn default() -> Outbox<M> {
+        Outbox {
+            acked: 0,
+            written: 0,
+            messages: VecDeque::new(),
+        }
+    }
+}
+
+impl<M> Outbox<M>
+where
+    M: Clone + Send,
+{
+    /// Returns the number of messages sent in the session.
+    fn sent(&self) -> u64 {
+        self.acked + u64::cast_from(self.messages.len())
+    }
+
+    /// Enqueues a message to be written.
+    fn push(&mut self, message: M) {
+        self.messages.push_back(message);
+    }
+
+    /// Records that the peer has received the first `received` messages of
+    /// the session.
+    fn ack(&mut self, received: u64) -> Result<(), anyhow::Error> {
+        if received < self.acked || received > self.sent() {
+            bail!(
+                "peer acknowledged {} messages, but {} were sent and {} already acknowledged",
+                received,
+                self.sent(),
+                self.acked
+            );
+        }
+        self.messages
+            .drain(..usize::cast_from(received - self.acked));
+        self.acked = received;
+        self.written = self.written.max(received);
+        Ok(())
+    }
+
+    /// Arranges for every unacknowledged message to be written to a new
+    /// connection.
+    fn rewind(&mut self) {
+        self.written = self.acked;
+    }
+
+    /// Forgets every message, for a new session.
+    fn reset(&mut self) {
+        *self = Outbox::default();
+    }
+
+    /// Writes the messages not yet written to `conn`, on `channel`.
+    ///
+    /// Unless the session can be resumed, written messages are forgotten
+    /// immediately, as they will never be resent.
+    async fn flush<S>(&mut self, conn: &mut S, channel: Uuid, resume: bool) -> Result<(), io::Error>
+    where
+        S: Sink<Frame<M>, Error = io::Error> + Unpin + Send,
+    {
+        while self.written < self.sent() {
+            let message = self.messages[usize::cast_from(self.written - self.acked)].clone();
+            conn.feed(Frame::Message { channel, message }).await?;
+            self.written += 1;
+        }
+        if !resume {
+            self.messages.clear();
+            self.acked = self.written;
+        }
+        conn.flush().await
+    }
+}
+
+/// A request from a client, or from a transport handle, to the task that
+/// drives the transport's connection.
+enum Request<C, R> {
+    /// Opens `channel`, whose events are sent to `events`, and whose state is
+    /// published to `open`.
+    Open {
+        channel: Uuid,
+        events: mpsc::UnboundedSender<Event<R>>,
+        open: watch::Sender<bool>,
+    },
+    /// Sends `command` on `channel`.
+    Send { channel: Uuid, command: C },
+    /// Closes `channel`.
+    Close { channel: Uuid },
+}
+
+/// An event on a channel, which the transport's task reports to the channel's
+/// client.
+enum Event<R> {
+    /// A response arrived.
+    Response(R),
+    /// The server has lost the channel's session, which has been restarted.
+    SessionLost,
+    /// The server closed the channel, for the given reason.
+    Closed(String),
+}
+
+/// A request for a CPU profile of a server's process, which the transport's
+/// task answers on `profile`.
+struct ProfileRequest {
+    duration: Duration,
+    profile: oneshot::Sender<Result<String, anyhow::Error>>,
+}
+
+/// Takes CPU profiles of the process of the server to which an
+/// [`RpcTransport`] connects.
+///
+/// Profilers are cheap to clone, and do not keep the transport alive.
+#[derive(Debug, Clone)]
+pub struct RpcProfiler {
+    addr: String,
+    requests: mpsc::UnboundedSender<ProfileRequest>,
+}
+
+impl RpcProfiler {
+    /// Returns the address of the server.
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Takes a CPU profile of the server's process over `duration`, and
+    /// returns it in the folded format of
+    /// [`mz_prof::StackProfile::to_folded`].
+    ///
+    /// Fails if the transport is not connected, or loses its connection
+    /// before the profile arrives.
+    pub async fn cpu_profile(&self, duration: Duration) -> Result<String, anyhow::Error> {
+        let (tx, rx) = oneshot::channel();
+        let request = ProfileRequest {
+            duration,
+            profile: tx,
+        };
+        self.requests
+            .send(request)
+            .map_err(|_| anyhow!("transport to {} terminated", self.addr))?;
+        rx.await
+            .map_err(|_| anyhow!("transport to {} terminated", self.addr))?
+    }
+}
+
+/// A connection to a server, shared by the clients of every channel that it
+/// carries.
+///
+/// The connection is established, kept alive, and reestablished by a task of
+/// its own, which runs until the transport and each of its clients have been
+/// dropped. Transports are cheap to clone.
+#[derive(Debug)]
+pub struct RpcTransport<C, R> {
+    addr: String,
+    requests: mpsc::UnboundedSender<Request<C, R>>,
+    profiles: mpsc::UnboundedSender<ProfileRequest>,
+}
+
+impl<C, R> Clone for RpcTransport<C, R> {
+    fn clone(&self) -> RpcTransport<C, R> {
+        RpcTransport {
+            addr: self.addr.clone(),
+            requests: self.requests.clone(),
+            profiles: self.profiles.clone(),
+        }
+    }
+}
+
+impl<C, R> RpcTransport<C, R>
+where
+    C: Serialize + Clone + fmt::Debug + Send + Unpin + 'static,
+    R: DeserializeOwned + fmt::Debug + Send + Unpin + 'static,
+{
+    /// Creates a transport to the server at `addr`.
+    ///
+    /// The transport connects once a channel is opened on it. Must be called
+    /// from within a Tokio runtime.
+    pub fn new(addr: String) -> RpcTransport<C, R> {
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        let (profiles_tx, profiles_rx) = mpsc::unbounded_channel();
+        let transport = Transport {
+            addr: addr.clone(),
+            channels: BTreeMap::new(),
+            connection: None,
+            profiles: BTreeMap::new(),
+            next_profile: 0,
+        };
+        task::spawn(
+            || format!("rpc-transport:{}", addr),
+            transport.run(requests_rx, profiles_rx),
+        );
+        RpcTransport {
+            addr,
+            requests: requests_tx,
+            profiles: profiles_tx,
+        }
+    }
+
+    /// Opens a new channel on the transport, and returns its client.
+    pub fn channel(&self) -> RpcClient<C, R> {
+        let channel = Uuid::new_v4();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let (open_tx, open_rx) = watch::channel(false);
+        // The task outlives every handle to the transport.
+        let _ = self.requests.send(Request::Open {
+            channel,
+            events: events_tx,
+            open: open_tx,
+        });
+        RpcClient {
+            addr: self.addr.clone(),
+            channel,
+            requests: self.requests.clone(),
+            profiles: self.profiles.clone(),
+            events: events_rx,
+            open: open_rx,
+        }
+    }
+}
+
+impl<C, R> RpcTransport<C, R> {
+    /// Returns a profiler for the process of the server.
+    pub fn profiler(&self) -> RpcProfiler {
+        RpcProfiler {
+            addr: self.addr.clone(),
+            requests: self.profiles.clone(),
+        }
+    }
+}
+
+/// A client to a remote dataflow server, which communicates over a channel of
+/// an [`RpcTransport`].
+///
+/// The channel's session survives connection failures. Commands sent while
+/// the transport is disconnected are buffered until it reconnects. If the
+/// server has restarted, the session cannot be resumed, and `recv` returns an
+/// error so that the bearer can reissue its commands.
+///
+/// Dropping the client closes its channel.
+#[derive(Debug)]
+pub struct RpcClient<C, R> {
+    addr: String,
+    channel: Uuid,
+    requests: mpsc::UnboundedSender<Request<C, R>>,
+    profiles: mpsc::UnboundedSender<ProfileRequest>,
+    events: mpsc::UnboundedReceiver<Event<R>>,
+    open: watch::Receiver<bool>,
+}
+
+impl<C, R> RpcClient<C, R>
+where
+    C: Serialize + Clone + fmt::Debug + Send + Unpin + 'static,
+    R: DeserializeOwned + fmt::Debug + Send + Unpin + 'static,
+{
+    /// Creates a new `RpcClient` on a transport of its own to the server at
+    /// `addr`, as by [`RpcTransport::new`].
+    ///
+    /// Use the `connect()` method to wait until the client is connected.
+    pub fn new(addr: String) -> RpcClient<C, R> {
+        RpcTransport::new(addr).channel()
+    }
+}
+
+impl<C, R> RpcClient<C, R> {
+    /// Reports whether the client's channel is open on a connection.
+    pub fn connected(&self) -> bool {
+        *self.open.borrow()
+    }
+
+    /// Waits until the client's channel is open on a connection, or until the
+    /// server closes the channel.
+    pub async fn connect(&mut self) {
+        while !*self.open.borrow() {
+            if self.open.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Returns a profiler for the process of the server.
+    pub fn profiler(&self) -> RpcProfiler {
+        RpcProfiler {
+            addr: self.addr.clone(),
+            requests: self.profiles.clone(),
+        }
+    }
+}
+
+impl<C, R> Drop for RpcClient<C, R> {
+    fn drop(&mut self) {
+        let _ = self.requests.send(Request::Close {
+            channel: self.channel,
+        });
+    }
+}
+
+#[async_trait]
+impl<C, R> GenericClient<C, R> for RpcClient<C, R>
+where
+    C: fmt::Debug + Send,
+    R: fmt::Debug + Send,
+{
+    async fn send(&mut self, cmd: C) -> Result<(), anyhow::Error> {
+        let request = Request::Send {
+            channel: self.channel,
+            command: cmd,
+        };
+        self.requests
+            .send(request)
+            .map_err(|_| anyhow!("transport to {} terminated", self.addr))
+    }
+
+    async fn recv(&mut self) -> Result<Option<R>, anyhow::Error> {
+        match self.events.recv().await {
+            Some(Event::Response(response)) => Ok(Some(response)),
+            Some(Event::SessionLost) => bail!("Server at {} restarted; session lost", self.addr),
+            Some(Event::Closed(reason)) => {
+                bail!("Server at {} closed session: {}", self.addr, reason)
+            }
+            None => bail!("Session with {} closed", self.addr),
+        }
+    }
+
+    fn profilers(&self) -> Vec<RpcProfiler> {
+        vec![self.profiler()]
+    }
+}
+
+/// The state of a channel, as tracked by its transport's task.
+struct Channel<C, R> {
+    events: mpsc::UnboundedSender<Event<R>>,
+    open: watch::Sender<bool>,
+    /// The session of the server, once known.
+    server_session: Option<Uuid>,
+    /// Whether the server has opened the channel on the current connection.
+    opened: bool,
+    /// The commands that the server has not yet acknowledged.
+    outbox: Outbox<C>,
+    /// The number of responses received in the session.
+    received: u64,
+}
+
+impl<C, R> Channel<C, R>
+where
+    C: Clone + Send,
+{
+    /// Resumes the channel's session on a connection on which the server has
+    /// opened the channel, or restarts the session if the server has lost it.
+    fn resume(&mut self, addr: &str, session: Uuid, received: u64, resume: bool) {
+        let resumed = match self.server_session {
+            Some(server_session) if server_session == session && resume => {
+                match self.outbox.ack(received) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Cannot resume session with {}: {}", addr, e);
+                        false
+                    }
+                }
+            }
+            Some(_) => false,
+            // This is the first connection, which starts the session. Commands
+            // sent before the connection was established are still pending.
+            None => self.outbox.ack(received).is_ok(),
+        };
+        if resumed {
+            self.outbox.rewind();
+        } else {
+            warn!("Session with {} lost; restarting it", addr);
+            self.outbox.reset();
+            self.received = 0;
+            let _ = self.events.send(Event::SessionLost);
+        }
+        self.server_session = Some(session);
+        self.opened = true;
+        let _ = self.open.send(true);
+    }
+}
+
+/// A connection to a server over which the handshake has completed.
+struct Connection<C, R> {
+    framed: FramedClient<TcpStream, C, R>,
+    /// Whether the peers send heartbeats.
+    heartbeats: bool,
+    /// Whether sessions can be resumed on a new connection.
+    resume: bool,
+    /// Whether the server takes CPU profiles.
+    profile: bool,
+    heartbeat: Interval,
+    /// When a frame last arrived.
+    last_heard: Instant,
+}
+
+/// What the transport's task attends to next.
+enum Wakeup<C, R> {
+    Request(Option<Request<C, R>>),
+    Profile(ProfileRequest),
+    Frame(Option<Result<Frame<R>, io::Error>>),
+    Heartbeat,
+}
+
+/// The state of the task that drives an [`RpcTransport`].
+struct Transport<C, R> {
+    addr: String,
+    channels: BTreeMap<Uuid, Channel<C, R>>,
+    connection: Option<Connection<C, R>>,
+    /// The requests for CPU profiles that await the server's response, by
+    /// their identifiers.
+    profiles: BTreeMap<u64, oneshot::Sender<Result<String, anyhow::Error>>>,
+    /// The identifier of the next request for a CPU profile.
+    next_profile: u64,
+}
+
+impl<C, R> Transport<C, R>
+where
+    C: Serialize + Clone + fmt::Debug + Send + Unpin + 'static,
+    R: DeserializeOwned + fmt::Debug + Send + Unpin + 'static,
+{
+    /// Serves `requests` and `profiles` until every handle to the transport
+    /// has been dropped, connecting to the server while any channel is open.
+    async fn run(
+        mut self,
+        mut requests: mpsc::UnboundedReceiver<Request<C, R>>,
+        mut profiles: mpsc::UnboundedReceiver<ProfileRequest>,
+    ) {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retry_at = Instant::now();
+        loop {
+            let connection = match &mut self.connection {
+                Some(connection) => connection,
+                None => {
+                    let connect = !self.channels.is_empty();
+                    tokio::select! {
+                        request = requests.recv() => match request {
+                            // Without a connection, requests cannot fail.
+                            Some(request) => {
+                                let _ = self.handle_request(request).await;
+                            }
+                            None => return,
+                        },
+                        Some(request) = profiles.recv() => {
+                            let _ = self.handle_profile_request(request).await;
+                        }
+                        () = time::sleep_until(retry_at), if connect => {
+                            match handshake(&self.addr).await {
+                                Ok((framed, hello)) => {
+                                    info!("Connected to {}", self.addr);
+                                    backoff = INITIAL_BACKOFF;
+                                    if let Err(e) = self.connected(framed, hello).await {
+                                        warn!("Connection to {} failed: {}; reconnecting", self.addr, e);
+                                        self.disconnect();
+                                    }
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        "Error connecting to {}: {:#}; reconnecting in {:?}",
+                                        self.addr, e, backoff
+                                    );
+                                    retry_at = Instant::now() + backoff;
+                                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                                }
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+            let wakeup = tokio::select! {
+                request = requests.recv() => Wakeup::Request(request),
+                Some(request) = profiles.recv() => Wakeup::Profile(request),
+                frame = connection.framed.next() => Wakeup::Frame(frame),
+                _ = connection.heartbeat.tick(), if connection.heartbeats => Wakeup::Heartbeat,
+            };
+            let result = match wakeup {
+                Wakeup::Request(Some(request)) => self.handle_request(request).await,
+                Wakeup::Request(None) => return,
+                Wakeup::Profile(request) => self.handle_profile_request(request).await,
+                Wakeup::Frame(frame) => self.handle_frame(frame).await,
+                Wakeup::Heartbeat => self.heartbeat().await,
+            };
+            if let Err(failure) = result {
+                warn!(
+                    "Connection to {} failed: {}; reconnecting",
+                    self.addr, failure
+                );
+                self.disconnect();
+                retry_at = Instant::now();
+            }
+        }
+    }
+
+    /// Adopts a new connection, on which every channel is reopened.
+    async fn connected(
+        &mut self,
+        framed: FramedClient<TcpStream, C, R>,
+        hello: Hello,
+    ) -> Result<(), io::Error> {
+        let connection = self.connection.insert(Connection {
+            framed,
+            heartbeats: hello.features.contains(FEATURE_HEARTBEAT),
+            resume: hello.features.contains(FEATURE_RESUME),
+            profile: hello.features.contains(FEATURE_PROFILE),
+            heartbeat: heartbeat_interval(),
+            last_heard: Instant::now(),
+        });
+        let opens: Vec<_> = self
+            .channels
+            .iter()
+            .map(|(channel, state)| Frame::Open {
+                channel: *channel,
+                received: state.received,
+            })
+            .collect();
+        for open in opens {
+            connection.framed.feed(open).await?;
+        }
+        connection.framed.flush().await
+    }
+
+    /// Forgets the current connection.
+    ///
+    /// The server cannot answer requests for CPU profiles that it received on
+    /// the connection on a new one, so they fail.
+    fn disconnect(&mut self) {
+        self.connection = None;
+        for channel in self.channels.values_mut() {
+            channel.opened = false;
+            channel.outbox.rewind();
+            let _ = channel.open.send(false);
+        }
+        for (_, profile) in std::mem::take(&mut self.profiles) {
+            let _ = profile.send(Err(anyhow!(
+                "connection to {} lost while taking CPU profile",
+                self.addr
+            )));
+        }
+    }
+
+    /// Forwards a request for a CPU profile to the server, or fails it if the
+    /// server cannot take one.
+    async fn handle_profile_request(&mut self, request: ProfileRequest) -> Result<(), String> {
+        let ProfileRequest { duration, profile } = request;
+        let connection = match &mut self.connection {
+            Some(connection) if connection.profile => connection,
+            Some(_) => {
+                let _ = profile.send(Err(anyhow!(
+                    "server at {} does not take CPU profiles",
+                    self.addr
+                )));
+                return Ok(());
+            }
+            None => {
+                let _ = profile.send(Err(anyhow!("not connected to {}", self.addr)));
+                return Ok(());
+            }
+        };
+        let request = self.next_profile;
+        self.next_profile += 1;
+        self.profiles.insert(request, profile);
+        connection
+            .framed
+            .send(Frame::Profile { request, duration })
+            .await
+            .map_err(|e| format!("error sending: {}", e))
+    }
+
+    async fn handle_request(&mut self, request: Request<C, R>) -> Result<(), String> {
+        let Transport {
+            channels,
+            connection,
+            ..
+        } = self;
+        match request {
+            Request::Open {
+                channel,
+                events,
+                open,
+            } => {
+                let state = Channel {
+                    events,
+                    open,
+                    server_session: None,
+                    opened: false,
+                    outbox: Outbox::default(),
+                    received: 0,
+                };
+                channels.insert(channel, state);
+                if let Some(connection) = connection {
+                    let open = Frame::Open {
+                        channel,
+                        received: 0,
+                    };
+                    connection
+                        .framed
+                        .send(open)
+                        .await
+                        .map_err(|e| format!("error sending: {}", e))?;
+                }
+            }
+            Request::Send { channel, command } => {
+                // Commands sent on a channel that the server has closed are
+                // dropped, as the channel's client has been told of its
+                // closure.
+                if let Some(state) = channels.get_mut(&channel) {
+                    state.outbox.push(command);
+                    if let (Some(connection), true) = (connection, state.opened) {
+                        state
+                            .outbox
+                            .flush(&mut connection.framed, channel, connection.resume)
+                            .await
+                            .map_err(|e| format!("error sending: {}", e))?;
+                    }
+                }
+            }
+            Request::Close { channel } => {
+                if let (Some(_), Some(connection)) = (channels.remove(&channel), connection) {
+                    let close = Frame::Close {
+                        channel,
+                        reason: "client closed the channel".into(),
+                    };
+                    connection
+                        .framed
+                        .send(close)
+                        .await
+                        .map_err(|e| format!("error sending: {}", e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_frame(
+        &mut self,
+        frame: Option<Result<Frame<R>, io::Error>>,
+    ) -> Result<(), String> {
+        let Transport {
+            addr,
+            channels,
+            connection,
+            profiles,
+            ..
+        } = self;
+        let connection = connection
+            .as_mut()
+            .expect("frame arrived without a connection");
+        connection.last_heard = Instant::now();
+        match frame {
+            Some(Ok(Frame::Opened {
+                channel,
+                session,
+                received,
+            })) => {
+                if let Some(state) = channels.get_mut(&channel) {
+                    state.resume(addr, session, received, connection.resume);
+                    state
+                        .outbox
+                        .flush(&mut connection.framed, channel, connection.resume)
+                        .await
+                        .map_err(|e| format!("error sending: {}", e))?;
+                }
+            }
+            Some(Ok(Frame::Message { channel, message })) => {
+                if let Some(state) = channels.get_mut(&channel) {
+                    state.received += 1;
+                    let _ = state.events.send(Event::Response(message));
+                }
+            }
+            Some(Ok(Frame::Heartbeat { received })) => {
+                for (channel, received) in received {
+                    if let Some(state) = channels.get_mut(&channel) {
+                        state.outbox.ack(received).map_err(|e| e.to_string())?;
+                    }
+                }
+            }
+            Some(Ok(Frame::Close { channel, reason })) => {
+                if let Some(state) = channels.remove(&channel) {
+                    warn!("Server at {} closed session: {}", addr, reason);
+                    let _ = state.events.send(Event::Closed(reason));
+                }
+            }
+            Some(Ok(Frame::Profiled { request, profile })) => {
+                if let Some(tx) = profiles.remove(&request) {
+                    let profile = profile.map_err(|e| {
+                        anyhow!("server at {} failed to take CPU profile: {}", addr, e)
+                    });
+                    let _ = tx.send(profile);
+                }
+            }
+            Some(Ok(Frame::Goodbye(reason))) => {
+                return Err(format!("server closed connection: {}", reason))
+            }
+            Some(Ok(Frame::Hello(_) | Frame::Open { .. } | Frame::Profile { .. })) => {
+                return Err("unexpected frame".into())
+            }
+            Some(Err(e)) => return Err(format!("error receiving: {}", e)),
+            None => return Err("server closed connection".into()),
+        }
+        Ok(())
+    }
+
+    /// Sends a heartbeat, unless the server's heartbeats have stopped.
+    async fn heartbeat(&mut self) -> Result<(), String> {
+        let connection = self
+            .connection
+            .as_mut()
+            .expect("heartbeat without a connection");
+        if connection.last_heard.elapsed() > HEARTBEAT_TIMEOUT {
+            return Err(format!("no heartbeat for {:?}", HEARTBEAT_TIMEOUT));
+        }
+        let received = self
+            .channels
+            .iter()
+            .filter(|(_, state)| state.opened)
+            .map(|(channel, state)| (*channel, state.received))
+            .collect();
+        connection
+            .framed
+            .send(Frame::Heartbeat { received })
+            .await
+            .map_err(|e| format!("error sending heartbeat: {}", e))
+    }
+}
+
+/// Connects to the server at `addr` and performs the handshake, returning the
+/// connection and the server's [`Hello`].
+async fn handshake<C, R>(
+    addr: &str,
+) -> Result<(FramedClient<TcpStream, C, R>, Hello), anyhow::Error>
+where
+    C: Serialize + Unpin,
+    R: DeserializeOwned + Unpin,
+{
+    let conn = TcpStream::connect(addr).await?;
+    let mut framed = framed_client(conn);
+    framed.send(Frame::Hello(hello())).await?;
+    let hello = match time::timeout(HANDSHAKE_TIMEOUT, framed.next()).await {
+        Ok(Some(Ok(Frame::Hello(hello)))) => hello,
+        Ok(Some(Ok(Frame::Goodbye(reason)))) => bail!("server rejected connection: {}", reason),
+        Ok(Some(Ok(_))) => bail!("unexpected frame during handshake"),
+        Ok(Some(Err(e))) => return Err(e.into()),
+        Ok(None) => bail!("server closed connection during handshake"),
+        Err(_) => bail!("handshake timed out"),
+    };
+    if hello.version != PROTOCOL_VERSION {
+        bail!(
+            "server speaks protocol version {}, but {} is required",
+            hello.version,
+            PROTOCOL_VERSION
+        );
+    }
+    Ok((framed, hello))
+}
+
+/// The channel that a server is serving.
+struct Served<R> {
+    channel: Uuid,
+    /// The server's session for the channel.
+    session: Uuid,
+    /// The responses that the client has not yet acknowledged.
+    outbox: Outbox<R>,
+    /// The number of commands received in the session.
+    received: u64,
+}
+
+impl<R> Served<R> {
+    /// Starts a new session for `channel`.
+    fn new(channel: Uuid) -> Served<R> {
+        Served {
+            channel,
+            session: Uuid::new_v4(),
+            outbox: Outbox::default(),
+            received: 0,
+        }
+    }
+}
+
+/// What the server attends to next.
+enum ServerWakeup<C, R> {
+    Frame(Option<Result<Frame<C>, io::Error>>),
+    Response(Option<R>),
+    Profiled(u64, Result<String, String>),
+    Heartbeat,
+}
+
+/// Serves `client` to controllers that connect to `listener`.
+///
+/// The server serves one channel at a time, on one connection at a time, and
+/// closes any other channel that a controller opens. If `linger` is true, the
+/// server serves channels one after another until `client` shuts down, and a
+/// new channel takes over from a channel whose connection has been lost.
+/// Otherwise the server serves only the first channel's session, and returns if
+/// the controller closes the channel, or does not resume the session within a
+/// minute of losing its connection.
+///
+/// The server takes CPU profiles of its process when the controller requests
+/// them, on tasks of their own, so that serving the channel is not held up
+/// while the profile is taken.
+pub async fn serve<G, C, R>(
+    listener: TcpListener,
+    linger: bool,
+    mut client: G,
+) -> Result<(), anyhow::Error>
+where
+    G: GenericClient<C, R>,
+    C: DeserializeOwned + fmt::Debug + Send + Unpin,
+    R: Serialize + Clone + fmt::Debug + Send + Unpin,
+{
+    let mut served: Option<Served<R>> = None;
+    // The time by which a controller that does not linger must resume.
+    let mut resume_deadline = None;
+
+    loop {
+        let conn = match resume_deadline {
+            Some(deadline) if !linger => {
+                match time::timeout_at(deadline, listener.accept()).await {
+                    Ok(conn) => conn?.0,
+                    Err(_) => {
+                        info!("controller did not resume its session; terminating");
+                        return Ok(());
+                    }
+                }
+            }
+            _ => listener.accept().await?.0,
+        };
+        info!("controller connection accepted");
+
+        let mut conn = framed_server(conn);
+        let hello = match time::timeout(HANDSHAKE_TIMEOUT, conn.next()).await {
+            Ok(Some(Ok(Frame::Hello(hello)))) => hello,
+            _ => {
+                warn!("controller connection failed to complete handshake");
+                continue;
+            }
+        };
+        if hello.version != PROTOCOL_VERSION {
+            let reason = format!(
+                "controller speaks protocol version {}, but {} is required",
+                hello.version, PROTOCOL_VERSION
+            );
+            warn!("rejecting controller connection: {}", reason);
+            let _ = conn.send(Frame::Goodbye(reason)).await;
+            continue;
+        }
+        let features = negotiate(&hello.features);
+        let heartbeats = features.contains(FEATURE_HEARTBEAT);
+        let resume = features.contains(FEATURE_RESUME);
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            features,
+        };
+
+        // Whether the served channel is open on this connection.
+        let mut attached = false;
+        // CPU profiles are sent on the connection on which they were
+        // requested, if it is still alive once they have been taken.
+        let (profiled_tx, mut profiled_rx) = mpsc::unbounded_channel();
+        let mut heartbeat = heartbeat_interval();
+        let mut last_heard = Instant::now();
+        let mut result = conn
+            .send(Frame::Hello(hello))
+            .await
+            .map_err(|e| e.to_string());
+        while result.is_ok() {
+            if let (true, Some(served)) = (attached, &mut served) {
+                result = served
+                    .outbox
+                    .flush(&mut conn, served.channel, resume)
+                    .await
+                    .map_err(|e| e.to_string());
+                if result.is_err() {
+                    break;
+                }
+            }
+            let wakeup = tokio::select! {
+                frame = conn.next() => ServerWakeup::Frame(frame),
+                response = client.recv() => ServerWakeup::Response(response?),
+                Some((request, profile)) = profiled_rx.recv() => {
+                    ServerWakeup::Profiled(request, profile)
+                }
+                _ = heartbeat.tick(), if heartbeats => ServerWakeup::Heartbeat,
+            };
+            match wakeup {
+                ServerWakeup::Frame(frame) => {
+                    last_heard = Instant::now();
+                    match frame {
+                        Some(Ok(Frame::Open { channel, received })) => {
+                            let resumable = match &mut served {
+                                Some(served) if served.channel == channel => {
+                                    resume && served.outbox.ack(received).is_ok()
+                                }
+                                _ => false,
+                            };
+                            let busy = match &served {
+                                Some(served) => served.channel != channel && (attached || !linger),
+                                None => false,
+                            };
+                            let served = if resumable {
+                                info!("controller resumed its session");
+                                let served = served.as_mut().expect("resumable session exists");
+                                served.outbox.rewind();
+                                served
+                            } else if busy {
+                                let reason = "server is serving another session".to_string();
+                                warn!("rejecting controller channel: {}", reason);
+                                result = conn
+                                    .send(Frame::Close { channel, reason })
+                                    .await
+                                    .map_err(|e| e.to_string());
+                                continue;
+                            } else {
+                                info!("controller started a new session");
+                                served.insert(Served::new(channel))
+                            };
+                            attached = true;
+                            resume_deadline = None;
+                            let opened = Frame::Opened {
+                                channel,
+                                session: served.session,
+                                received: served.received,
+                            };
+                            result = conn.send(opened).await.map_err(|e| e.to_string());
+                        }
+                        Some(Ok(Frame::Message { channel, message })) => match &mut served {
+                            Some(served) if attached && served.channel == channel => {
+                                served.received += 1;
+                                client.send(message).await?;
+                            }
+                            _ => warn!("ignoring command on unopened channel {}", channel),
+                        },
+                        Some(Ok(Frame::Heartbeat { received })) => {
+                            for (channel, received) in received {
+                                match &mut served {
+                                    Some(served) if attached && served.channel == channel => {
+                                        if let Err(e) = served.outbox.ack(received) {
+                                            result = Err(e.to_string());
+                                        }
+                                    }
+                                    _ => (),
+                                }
+                            }
+                        }
+                        Some(Ok(Frame::Close { channel, .. })) => {
+                            if attached && served.as_ref().map(|s| s.channel) == Some(channel) {
+                                info!("controller closed its session");
+                                if !linger {
+                                    return Ok(());
+                                }
+                                served = None;
+                                attached = false;
+                            }
+                        }
+                        Some(Ok(Frame::Profile { request, duration })) => {
+                            info!("controller requested a {:?} CPU profile", duration);
+                            let profiled_tx = profiled_tx.clone();
+                            task::spawn(|| "rpc-cpu-profile", async move {
+                                let profile = mz_prof::time::cpu_profile_folded(duration)
+                                    .await
+                                    .map_err(|e| e.to_string());
+                                let _ = profiled_tx.send((request, profile));
+                            });
+                        }
+                        Some(Ok(
+                            Frame::Hello(_)
+                            | Frame::Opened { .. }
+                            | Frame::Goodbye(_)
+                            | Frame::Profiled { .. },
+                        )) => {
+                            result = Err("unexpected frame".into());
+                        }
+                        Some(Err(e)) => result = Err(e.to_string()),
+                        None => break,
+                    }
+                }
+                ServerWakeup::Response(None) => {
+                    info!("dataflow server shut down; terminating");
+                    return Ok(());
+                }
+                // Responses that arrive while no channel is served belong to
+                // no session, and are dropped.
+                ServerWakeup::Response(Some(response)) => {
+                    if let Some(served) = &mut served {
+                        served.outbox.push(response);
+                    }
+                }
+                ServerWakeup::Profiled(request, profile) => {
+                    result = conn
+                        .send(Frame::Profiled { request, profile })
+                        .await
+                        .map_err(|e| e.to_string());
+                }
+                ServerWakeup::Heartbeat => {
+                    if last_heard.elapsed() > HEARTBEAT_TIMEOUT {
+                        result = Err(format!("no heartbeat for {:?}", HEARTBEAT_TIMEOUT));
+                        break;
+                    }
+                    let received = match &served {
+                        Some(served) if attached => vec![(served.channel, served.received)],
+                        _ => vec![],
+                    };
+                    result = conn
+                        .send(Frame::Heartbeat { received })
+                        .await
+                        .map_err(|e| e.to_string());
+                }
+            }
+        }
+        if let Err(e) = result {
+            warn!("controller connection failed: {}", e);
+        }
+        info!("controller connection gone; awaiting reconnection");
+        if served.is_some() {
+            resume_deadline = Some(Instant::now() + RESUME_TIMEOUT);
+        }
+    }
+}
+
+/// A framed connection to a dataflowd server.
+pub type Framed<C, T, U> = tokio_serde::Framed<
+    tokio_util::codec::Framed<C, LengthDelimitedCodec>,
+    Frame<T>,
+    Frame<U>,
+    Bincode<Frame<T>, Frame<U>>,
+>;
+
+/// A framed connection from the server's perspective.
+pub type FramedServer<A, C, R> = Framed<A, C, R>;
+
+/// A framed connection from the client's perspective.
+pub type FramedClient<A, C, R> = Framed<A, R, C>;
+
+fn length_delimited_codec() -> LengthDelimitedCodec {
+    // NOTE(benesch): using an unlimited maximum frame length is problematic
+    // because Tokio never shrinks its buffer. Sending or receiving one large
+    // message of size N means the client will hold on to a buffer of size
+    // N forever. We should investigate alternative transport protocols that
+    // do not have this limitation.
+    let mut codec = LengthDelimitedCodec::new();
+    codec.set_max_frame_length(usize::MAX);
+    codec
+}
+
+/// Constructs a framed connection for the server.
+pub fn framed_server<A, C, R>(conn: A) -> FramedServer<A, C, R>
+where
+    A: AsyncRead + AsyncWrite,
+{
+    tokio_serde::Framed::new(
+        tokio_util::codec::Framed::new(conn, length_delimited_codec()),
+        Bincode::default(),
+    )
+}
+
+/// Constructs a framed connection for the client.
+pub fn framed_client<A, C, R>(conn: A) -> FramedClient<A, C, R>
+where
+    A: AsyncRead + AsyncWrite,
+{
+    tokio_serde::Framed::new(
+        tokio_util::codec::Framed::new(conn, length_delimited_codec()),
+        Bincode::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    /// A dataflow server that responds to each command with the command
+    /// itself.
+    #[derive(Debug)]
+    struct Echo {
+        tx: mpsc::UnboundedSender<u64>,
+        rx: mpsc::UnboundedReceiver<u64>,
+    }
+
+    impl Echo {
+        fn new() -> Echo {
+            let (tx, rx) = mpsc::unbounded_channel();
+            Echo { tx, rx }
+        }
+    }
+
+    #[async_trait]
+    impl GenericClient<u64, u64> for Echo {
+        async fn send(&mut self, cmd: u64) -> Result<(), anyhow::Error> {
+            Ok(self.tx.send(cmd)?)
+        }
+
+        async fn recv(&mut self) -> Result<Option<u64>, anyhow::Error> {
+            Ok(self.rx.recv().await)
+        }
+    }
+
+    /// Starts a server for a new [`Echo`], and returns its address.
+    async fn start_server(linger: bool) -> Result<SocketAddr, anyhow::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        task::spawn(|| "echo-server", serve(listener, linger, Echo::new()));
+        Ok(addr)
+    }
+
+    /// A proxy through which clients reach a server, whose connections can be
+    /// severed, and whose server can be replaced.
+    struct Proxy {
+        addr: SocketAddr,
+        server: Arc<Mutex<SocketAddr>>,
+        /// The tasks that forward the proxy's connections.
+        connections: Arc<Mutex<Vec<JoinHandle<()>>>>,
+        /// The number of connections that the proxy has accepted.
+        accepted: Arc<AtomicUsize>,
+    }
+
+    impl Proxy {
+        async fn start(server: SocketAddr) -> Result<Proxy, anyhow::Error> {
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let proxy = Proxy {
+                addr: listener.local_addr()?,
+                server: Arc::new(Mutex::new(server)),
+                connections: Arc::default(),
+                accepted: Arc::default(),
+            };
+            let server = Arc::clone(&proxy.server);
+            let connections = Arc::clone(&proxy.connections);
+            let accepted = Arc::clone(&proxy.accepted);
+            task::spawn(|| "rpc-proxy", async move {
+                while let Ok((mut downstream, _)) = listener.accept().await {
+                    accepted.fetch_add(1, Ordering::SeqCst);
+                    let server = *server.lock().unwrap();
+                    let handle = task::spawn(|| "rpc-proxy-connection", async move {
+                        if let Ok(mut upstream) = TcpStream::connect(server).await {
+                            let _ =
+                                tokio::io::copy_bidirectional(&mut downstream, &mut upstream).await;
+                        }
+                    });
+                    connections.lock().unwrap().push(handle);
+                }
+            });
+            Ok(proxy)
+        }
+
+        /// Severs every connection through the proxy.
+        fn sever(&self) {
+            for handle in self.connections.lock().unwrap().drain(..) {
+                handle.abort();
+            }
+        }
+
+        fn accepted(&self) -> usize {
+            self.accepted.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Receives the next response on `client`, failing if none arrives in
+    /// time.
+    async fn recv(client: &mut RpcClient<u64, u64>) -> Result<Option<u64>, anyhow::Error> {
+        time::timeout(Duration::from_secs(30), client.recv()).await?
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let features = |fs: &[&str]| fs.iter().map(|f| f.to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(negotiate(&features(FEATURES)), features(FEATURES));
+        assert_eq!(
+            negotiate(&features(&[FEATURE_HEARTBEAT, "telepathy"])),
+            features(&[FEATURE_HEARTBEAT])
+        );
+        // Resuming sessions requires heartbeats.
+        assert_eq!(negotiate(&features(&[FEATURE_RESUME])), features(&[]));
+    }
+
+    #[test]
+    fn test_outbox() {
+        let mut outbox = Outbox::default();
+        for i in 0..5 {
+            outbox.push(i);
+        }
+        outbox.written = 4;
+        outbox.ack(2).unwrap();
+        assert_eq!(outbox.sent(), 5);
+        assert_eq!(outbox.messages, [2, 3, 4]);
+
+        // Acknowledgements cannot go backwards or exceed what was sent.
+        assert!(outbox.ack(1).is_err());
+        assert!(outbox.ack(6).is_err());
+
+        // A new connection rewrites everything not yet acknowledged.
+        outbox.rewind();
+        assert_eq!(outbox.written, 2);
+        outbox.ack(5).unwrap();
+        assert_eq!(outbox.written, 5);
+        assert!(outbox.messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_resume() -> Result<(), anyhow::Error> {
+        let proxy = Proxy::start(start_server(false).await?).await?;
+        let mut client = RpcClient::new(proxy.addr.to_string());
+        client.connect().await;
+        for i in 1..=3 {
+            client.send(i).await?;
+        }
+        for i in 1..=3 {
+            assert_eq!(recv(&mut client).await?, Some(i));
+        }
+
+        // Commands sent while the connection is down are delivered once the
+        // client reconnects, and the session is resumed without loss or
+        // duplication.
+        proxy.sever();
+        client.send(4).await?;
+        client.send(5).await?;
+        assert_eq!(recv(&mut client).await?, Some(4));
+        assert_eq!(recv(&mut client).await?, Some(5));
+        assert_eq!(proxy.accepted(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_session_lost() -> Result<(), anyhow::Error> {
+        let proxy = Proxy::start(start_server(false).await?).await?;
+        let mut client = RpcClient::new(proxy.addr.to_string());
+        client.send(1).await?;
+        assert_eq!(recv(&mut client).await?, Some(1));
+
+        // A restarted server has lost the session, which the client reports
+        // before continuing in a new session.
+        *proxy.server.lock().unwrap() = start_server(false).await?;
+        proxy.sever();
+        let err = recv(&mut client).await.unwrap_err();
+        assert!(err.to_string().contains("session lost"), "{}", err);
+        client.send(2).await?;
+        assert_eq!(recv(&mut client).await?, Some(2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_multiplex() -> Result<(), anyhow::Error> {
+        let proxy = Proxy::start(start_server(true).await?).await?;
+        let transport = RpcTransport::new(proxy.addr.to_string());
+
+        let mut first = transport.channel();
+        first.send(1).await?;
+        assert_eq!(recv(&mut first).await?, Some(1));
+
+        // The server serves one channel at a time, and closes the others.
+        let mut second = transport.channel();
+        let err = recv(&mut second).await.unwrap_err();
+        assert!(
+            err.to_string().contains("serving another session"),
+            "{}",
+            err
+        );
+
+        // Once the first channel is closed, another takes its place, on the
+        // same connection.
+        drop(first);
+        let mut third = transport.channel();
+        third.send(2).await?;
+        assert_eq!(recv(&mut third).await?, Some(2));
+        assert_eq!(proxy.accepted(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_heartbeat_without_recv() -> Result<(), anyhow::Error> {
+        let proxy = Proxy::start(start_server(false).await?).await?;
+        let mut client = RpcClient::new(proxy.addr.to_string());
+        client.send(1).await?;
+
+        // The transport keeps the connection alive while the bearer is not
+        // receiving.
+        time::sleep(HEARTBEAT_TIMEOUT + 2 * HEARTBEAT_INTERVAL).await;
+        assert!(client.connected());
+        assert_eq!(recv(&mut client).await?, Some(1));
+        assert_eq!(proxy.accepted(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_profile() -> Result<(), anyhow::Error> {
+        let proxy = Proxy::start(start_server(false).await?).await?;
+        let mut client = RpcClient::new(proxy.addr.to_string());
+        client.connect().await;
+        let profiler = client.profiler();
+
+        // Keep a thread busy, so that the profile is not empty.
+        let busy = Arc::new(AtomicBool::new(true));
+        let spinner = {
+            let busy = Arc::clone(&busy);
+            std::thread::spawn(move || {
+                while busy.load(Ordering::SeqCst) {
+                    std::hint::spin_loop();
+                }
+            })
+        };
+        let profile = profiler.cpu_profile(Duration::from_secs(1)).await;
+        busy.store(false, Ordering::SeqCst);
+        spinner.join().unwrap();
+        let profile = profile?;
+        assert!(!profile.is_empty());
+        for line in profile.lines() {
+            let (stack, weight) = line.rsplit_once(' ').expect("line has a weight");
+            assert!(!stack.is_empty(), "{}", line);
+            assert!(weight.parse::<f64>()? > 0.0, "{}", line);
+        }
+
+        // A profile whose connection is lost before it arrives fails, rather
+        // than waiting forever.
+        let (profile, ()) = tokio::join!(profiler.cpu_profile(Duration::from_secs(5)), async {
+            time::sleep(Duration::from_millis(500)).await;
+            proxy.sever();
+        });
+        let err = profile.unwrap_err();
+        assert!(err.to_string().contains("connection to"), "{}", err);
+
+        // The channel's session survives the lost connection.
+        client.send(1).await?;
+        assert_eq!(recv(&mut client).await?, Some(1));
+        Ok(())
+    }
+}
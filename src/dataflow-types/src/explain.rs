@@ -30,10 +30,15 @@
 
 use std::fmt;
 
+use serde::Serialize;
+
 use crate::{DataflowDescription, LinearOperator};
 
+use mz_expr::analysis::{Analysis, Attributes};
 use mz_expr::explain::{Indices, ViewExplanation};
-use mz_expr::{ExprHumanizer, GlobalId, OptimizedMirRelationExpr, RowSetFinishing};
+use mz_expr::{
+    ExprHumanizer, GlobalId, MirRelationExpr, OptimizedMirRelationExpr, RowSetFinishing,
+};
 use mz_ore::result::ResultExt;
 use mz_ore::str::{bracketed, separated};
 
@@ -196,13 +201,15 @@ impl<ViewExpr: serde::Serialize> ViewFormatter<ViewExpr> for JsonViewFormatter {
 pub struct DataflowGraphFormatter<'a> {
     expr_humanizer: &'a dyn ExprHumanizer,
     typed: bool,
+    attributes: bool,
 }
 
 impl<'a> DataflowGraphFormatter<'a> {
-    pub fn new(expr_humanizer: &'a dyn ExprHumanizer, typed: bool) -> Self {
+    pub fn new(expr_humanizer: &'a dyn ExprHumanizer, typed: bool, attributes: bool) -> Self {
         Self {
             expr_humanizer,
             typed,
+            attributes,
         }
     }
 }
@@ -228,10 +235,58 @@ impl<'a> ViewFormatter<OptimizedMirRelationExpr> for DataflowGraphFormatter<'a>
         if self.typed {
             explain.explain_types();
         }
+        if self.attributes {
+            explain.explain_attributes();
+        }
         fmt::Display::fmt(&explain, f)
     }
 }
 
+/// Formats the views of a dataflow as JSON, optionally accompanied by the
+/// [`Attributes`] of each node.
+///
+/// When attributes are requested, each view is rendered as an object with a
+/// `plan` field containing the expression and an `attributes` field containing
+/// the attributes of each node in the expression, in left-to-right post-order.
+pub struct JsonGraphFormatter {
+    attributes: bool,
+}
+
+impl JsonGraphFormatter {
+    pub fn new(attributes: bool) -> Self {
+        Self { attributes }
+    }
+}
+
+impl ViewFormatter<OptimizedMirRelationExpr> for JsonGraphFormatter {
+    fn fmt_source_body(&self, f: &mut fmt::Formatter, operator: &LinearOperator) -> fmt::Result {
+        ViewFormatter::<OptimizedMirRelationExpr>::fmt_source_body(
+            &JsonViewFormatter {},
+            f,
+            operator,
+        )
+    }
+
+    fn fmt_view(&self, f: &mut fmt::Formatter, view: &OptimizedMirRelationExpr) -> fmt::Result {
+        #[derive(Serialize)]
+        struct AttributedView<'a> {
+            plan: &'a MirRelationExpr,
+            attributes: &'a [Attributes],
+        }
+
+        if self.attributes {
+            let analysis = Analysis::new(view);
+            let view = AttributedView {
+                plan: view,
+                attributes: analysis.post_order(),
+            };
+            JsonViewFormatter {}.fmt_view(f, &view)
+        } else {
+            JsonViewFormatter {}.fmt_view(f, view)
+        }
+    }
+}
+
 /// Information used when determining the timestamp for a query.
 pub struct TimestampExplanation<T> {
     /// The chosen timestamp from `determine_timestamp`.
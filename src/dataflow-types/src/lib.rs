@@ -26,6 +26,7 @@ mod types;
 pub use errors::*;
 pub use explain::DataflowGraphFormatter;
 pub use explain::Explanation;
+pub use explain::JsonGraphFormatter;
 pub use explain::JsonViewFormatter;
 pub use explain::TimestampExplanation;
 pub use explain::TimestampSource;
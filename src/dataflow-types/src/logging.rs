@@ -18,6 +18,8 @@ use mz_repr::{RelationDesc, ScalarType};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
     pub granularity_ns: u128,
+    /// How long to retain events in time-partitioned logs, if bounded.
+    pub retention_ns: Option<u128>,
     pub active_logs: HashMap<LogVariant, GlobalId>,
     // Whether we should report logs for the log-processing dataflows
     pub log_logging: bool,
@@ -65,7 +67,10 @@ pub enum MaterializedLog {
     KafkaSourceStatistics,
     PeekCurrent,
     PeekDuration,
+    PeekDurationPerMinute,
     SourceInfo,
+    SourceSchemaChanges,
+    UpsertStatistics,
 }
 
 impl LogVariant {
@@ -172,6 +177,22 @@ impl LogVariant {
                 .with_column("timestamp", ScalarType::Int64.nullable(false))
                 .with_key(vec![0, 1, 2, 3]),
 
+            LogVariant::Materialized(MaterializedLog::SourceSchemaChanges) => RelationDesc::empty()
+                .with_column("source_id", ScalarType::String.nullable(false))
+                .with_column("worker", ScalarType::Int64.nullable(false))
+                .with_column("object", ScalarType::String.nullable(false))
+                .with_column("change", ScalarType::String.nullable(false))
+                .with_column("action", ScalarType::String.nullable(false)),
+
+            LogVariant::Materialized(MaterializedLog::UpsertStatistics) => RelationDesc::empty()
+                .with_column("source_id", ScalarType::String.nullable(false))
+                .with_column("dataflow_id", ScalarType::Int64.nullable(false))
+                .with_column("worker", ScalarType::Int64.nullable(false))
+                .with_column("duplicate_keys", ScalarType::Int64.nullable(false))
+                .with_column("tombstones", ScalarType::Int64.nullable(false))
+                .with_column("late_retractions", ScalarType::Int64.nullable(false))
+                .with_key(vec![0, 1, 2]),
+
             LogVariant::Materialized(MaterializedLog::DataflowDependency) => RelationDesc::empty()
                 .with_column("dataflow", ScalarType::String.nullable(false))
                 .with_column("source", ScalarType::String.nullable(false))
@@ -202,6 +223,15 @@ impl LogVariant {
                 .with_column("duration_ns", ScalarType::Int64.nullable(false))
                 .with_column("count", ScalarType::Int64.nullable(false))
                 .with_key(vec![0, 1]),
+
+            LogVariant::Materialized(MaterializedLog::PeekDurationPerMinute) => {
+                RelationDesc::empty()
+                    .with_column("worker", ScalarType::Int64.nullable(false))
+                    .with_column("minute_start", ScalarType::Int64.nullable(false))
+                    .with_column("duration_ns", ScalarType::Int64.nullable(false))
+                    .with_column("count", ScalarType::Int64.nullable(false))
+                    .with_key(vec![0, 1, 2])
+            }
         }
     }
 
@@ -253,7 +283,16 @@ impl LogVariant {
             )],
             LogVariant::Materialized(MaterializedLog::PeekCurrent) => vec![],
             LogVariant::Materialized(MaterializedLog::SourceInfo) => vec![],
+            LogVariant::Materialized(MaterializedLog::SourceSchemaChanges) => vec![(
+                LogVariant::Materialized(MaterializedLog::SourceInfo),
+                vec![(0, 1)],
+            )],
+            LogVariant::Materialized(MaterializedLog::UpsertStatistics) => vec![(
+                LogVariant::Materialized(MaterializedLog::SourceInfo),
+                vec![(0, 1), (1, 2)],
+            )],
             LogVariant::Materialized(MaterializedLog::PeekDuration) => vec![],
+            LogVariant::Materialized(MaterializedLog::PeekDurationPerMinute) => vec![],
         }
     }
 }
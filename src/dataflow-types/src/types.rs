@@ -46,6 +46,8 @@ pub enum PeekResponseUnary {
     Rows(Vec<Row>),
     Error(String),
     Canceled,
+    /// The peek was canceled because it exceeded its statement timeout.
+    TimedOut,
 }
 
 impl PeekResponse {
@@ -437,6 +439,8 @@ pub mod sources {
         use mz_interchange::{avro, protobuf};
         use mz_repr::{ColumnType, RelationDesc, ScalarType};
 
+        use crate::sources::SchemaChangePolicy;
+
         /// A description of how to interpret data from various sources
         ///
         /// Almost all sources only present values as part of their records, but Kafka allows a key to be
@@ -606,6 +610,9 @@ pub mod sources {
             pub schema: String,
             pub schema_registry_config: Option<mz_ccsr::ClientConfig>,
             pub confluent_wire_format: bool,
+            /// How to handle writer schemas that add fields to `schema`. Only
+            /// meaningful when `schema_registry_config` is present.
+            pub schema_change_policy: SchemaChangePolicy,
         }
 
         #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -1152,6 +1159,10 @@ pub mod sources {
         // Represents options specified by user when creating the source, e.g.
         // security settings.
         pub config_options: BTreeMap<String, String>,
+        /// The options whose values are the contents of secrets, mapped to the
+        /// IDs of the secrets. The coordinator adds the contents of the secrets
+        /// to `config_options` before it describes the source to storage.
+        pub secret_options: BTreeMap<String, GlobalId>,
         // Map from partition -> starting offset
         pub start_offsets: HashMap<i32, i64>,
         pub group_id_prefix: Option<String>,
@@ -1189,6 +1200,30 @@ pub mod sources {
         None,
     }
 
+    /// What a source should do when it observes that the schema of its
+    /// upstream data has changed since the source was created.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+    pub enum SchemaChangePolicy {
+        /// Put the source into an errored state.
+        Error,
+        /// Continue decoding with the original schema, dropping the values of
+        /// any new columns.
+        IgnoreNewColumns,
+        /// Carry the values of any new columns, which are treated as nullable.
+        AddNullableColumns,
+    }
+
+    impl SchemaChangePolicy {
+        /// Returns the name of the policy as it is spelled in SQL.
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                SchemaChangePolicy::Error => "error",
+                SchemaChangePolicy::IgnoreNewColumns => "ignore_new_columns",
+                SchemaChangePolicy::AddNullableColumns => "add_nullable_columns",
+            }
+        }
+    }
+
     /// A source of updates for a relational collection.
     ///
     /// A source contains enough information to instantiate a stream of changes,
@@ -1513,6 +1548,13 @@ pub mod sources {
         pub publication: String,
         pub slot_name: String,
         pub details: PostgresSourceDetails,
+        pub schema_change_policy: SchemaChangePolicy,
+        /// The maximum number of upstream connections to use to produce the
+        /// initial snapshot.
+        pub snapshot_parallelism: usize,
+        /// The approximate size, in bytes, of the ranges that tables are
+        /// split into to be copied in parallel during the initial snapshot.
+        pub snapshot_range_bytes: u64,
     }
 
     #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -1855,6 +1897,10 @@ pub mod sinks {
         pub replication_factor: i32,
         pub fuel: usize,
         pub config_options: BTreeMap<String, String>,
+        /// The options whose values are the contents of secrets, mapped to the
+        /// IDs of the secrets. The coordinator adds the contents of the secrets
+        /// to `config_options` before it builds the sink.
+        pub secret_options: BTreeMap<String, GlobalId>,
         // Forces the sink to always write to the same topic across restarts instead
         // of picking a new topic each time.
         pub reuse_topic: bool,
@@ -12,19 +12,16 @@ use std::process;
 use std::sync::{Arc, Mutex};
 
 use anyhow::bail;
-use futures::sink::SinkExt;
-use futures::stream::TryStreamExt;
 use mz_dataflow::DummyBoundary;
 use mz_dataflow_types::sources::AwsExternalId;
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
 use tokio::net::TcpListener;
-use tokio::select;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
 use mz_dataflow::Server;
-use mz_dataflow_types::client::{ComputeClient, GenericClient, StorageClient};
+use mz_dataflow_types::client::{rpc, ComputeClient, GenericClient, StorageClient};
 use mz_dataflow_types::reconciliation::command::ComputeCommandReconcile;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::SYSTEM_TIME;
@@ -107,6 +104,8 @@ struct Args {
         default_value = "127.0.0.1:2101"
     )]
     storage_addr: String,
+    /// Whether to serve subsequent controllers after the first controller's
+    /// session ends, rather than terminating.
     #[clap(long)]
     linger: bool,
     /// Enable command reconciliation.
@@ -270,39 +269,12 @@ struct ServeConfig {
 async fn serve<G, C, R>(
     config: ServeConfig,
     _server: Server,
-    mut client: G,
+    client: G,
 ) -> Result<(), anyhow::Error>
 where
     G: GenericClient<C, R>,
     C: DeserializeOwned + fmt::Debug + Send + Unpin,
-    R: Serialize + fmt::Debug + Send + Unpin,
+    R: Serialize + Clone + fmt::Debug + Send + Unpin,
 {
-    loop {
-        let (conn, _addr) = config.listener.accept().await?;
-        info!("coordinator connection accepted");
-
-        let mut conn = mz_dataflow_types::client::tcp::framed_server(conn);
-        loop {
-            select! {
-                cmd = conn.try_next() => match cmd? {
-                    None => break,
-                    Some(cmd) => { client.send(cmd).await.unwrap(); },
-                },
-                res = client.recv() => {
-                    match res.unwrap() {
-                        None => break,
-                        Some(response) => { conn.send(response).await?; }
-                    }
-                }
-            }
-        }
-        if !config.linger {
-            break;
-        } else {
-            info!("coordinator connection gone; lingering");
-        }
-    }
-
-    info!("coordinator connection gone; terminating");
-    Ok(())
+    rpc::serve(config.listener, config.linger, client).await
 }
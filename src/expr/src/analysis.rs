@@ -0,0 +1,224 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Bottom-up analysis of [`MirRelationExpr`]s.
+//!
+//! An [`Analysis`] derives a set of [`Attributes`] for every node in an
+//! expression in a single post-order pass, using the attributes of each
+//! node's inputs. The same analysis backs the attribute annotations in
+//! `EXPLAIN` and the transforms that rely on these properties, so the two
+//! cannot disagree.
+//!
+//! Attributes are deterministic functions of the expression: they do not
+//! depend on the order in which hash maps are iterated or on any state outside
+//! the expression, so they are safe to include in test output.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use mz_repr::RelationType;
+
+use crate::{Id, LocalId, MirRelationExpr};
+
+/// Properties of the relation computed by a [`MirRelationExpr`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Attributes {
+    /// The number of columns in the relation.
+    pub arity: usize,
+    /// The sets of columns known to form unique keys, each sorted, in sorted
+    /// order.
+    pub keys: Vec<Vec<usize>>,
+    /// Whether every record in the relation is known to have a positive
+    /// multiplicity.
+    pub non_negative: bool,
+    /// An upper bound on the number of distinct records in the relation, if
+    /// one is known.
+    pub cardinality: Option<usize>,
+}
+
+impl Attributes {
+    /// Derives the attributes of `expr`, whose type is `typ`, from the
+    /// attributes of its inputs.
+    ///
+    /// `inputs` must be in the order in which the inputs are visited by
+    /// [`MirRelationExpr::visit_children`]. `locals` contains the attributes
+    /// of the values of the `Let` bindings that are in scope.
+    fn derive(
+        expr: &MirRelationExpr,
+        typ: RelationType,
+        inputs: &[&Attributes],
+        locals: &HashMap<LocalId, Attributes>,
+    ) -> Attributes {
+        use MirRelationExpr::*;
+
+        let (non_negative, cardinality) = match expr {
+            Constant { rows: Ok(rows), .. } => {
+                (rows.iter().all(|(_, diff)| *diff > 0), Some(rows.len()))
+            }
+            Constant { rows: Err(_), .. } => (true, Some(0)),
+            // Collections maintained by the system, like sources, tables, and
+            // views, never contain records with negative multiplicities.
+            Get {
+                id: Id::Global(_), ..
+            } => (true, None),
+            // A local binding that is not in scope can only arise when
+            // analyzing a fragment of an expression, so nothing is known.
+            Get {
+                id: Id::Local(id), ..
+            } => match locals.get(id) {
+                Some(value) => (value.non_negative, value.cardinality),
+                None => (false, None),
+            },
+            Let { .. } => (inputs[1].non_negative, inputs[1].cardinality),
+            Project { .. } | Map { .. } | Filter { .. } | ArrangeBy { .. } => {
+                (inputs[0].non_negative, inputs[0].cardinality)
+            }
+            // A table function may produce any number of records per input
+            // record, and `repeat` may produce negative multiplicities.
+            FlatMap { .. } => (false, None),
+            Join { .. } => (
+                inputs.iter().all(|input| input.non_negative),
+                inputs.iter().try_fold(1usize, |product, input| {
+                    product.checked_mul(input.cardinality?)
+                }),
+            ),
+            Union { .. } => (
+                inputs.iter().all(|input| input.non_negative),
+                inputs
+                    .iter()
+                    .try_fold(0usize, |sum, input| sum.checked_add(input.cardinality?)),
+            ),
+            // A reduction produces each group key exactly once.
+            Reduce { group_key, .. } => {
+                let cardinality = if group_key.is_empty() {
+                    Some(inputs[0].cardinality.map_or(1, |c| c.min(1)))
+                } else {
+                    inputs[0].cardinality
+                };
+                (true, cardinality)
+            }
+            TopK {
+                group_key, limit, ..
+            } => {
+                let cardinality = match (group_key.is_empty(), limit, inputs[0].cardinality) {
+                    (true, Some(limit), Some(c)) => Some(c.min(*limit)),
+                    (true, Some(limit), None) => Some(*limit),
+                    (_, _, c) => c,
+                };
+                (inputs[0].non_negative, cardinality)
+            }
+            Negate { .. } => (false, inputs[0].cardinality),
+            Threshold { .. } => (true, inputs[0].cardinality),
+        };
+
+        let mut keys = typ.keys;
+        for key in &mut keys {
+            key.sort_unstable();
+        }
+        keys.sort();
+        keys.dedup();
+
+        Attributes {
+            arity: typ.column_types.len(),
+            keys,
+            non_negative,
+            cardinality,
+        }
+    }
+}
+
+/// The [`Attributes`] and type of every node in a [`MirRelationExpr`].
+#[derive(Debug)]
+pub struct Analysis {
+    /// The attributes of each node, in left-to-right post-order.
+    attributes: Vec<Attributes>,
+    /// The type of each node, in left-to-right post-order.
+    types: Vec<RelationType>,
+    /// The position of each node in `attributes` and `types`.
+    positions: HashMap<*const MirRelationExpr, usize>,
+}
+
+impl Analysis {
+    /// Analyzes `expr` and all of its subexpressions.
+    ///
+    /// `Get`s of local bindings that are not defined within `expr` are
+    /// analyzed conservatively.
+    pub fn new(expr: &MirRelationExpr) -> Analysis {
+        // Determine which nodes are the values of `Let` bindings, so that the
+        // binding can be recorded as soon as its value has been analyzed. The
+        // value of a `Let` is always visited before its body.
+        let mut bindings = HashMap::new();
+        expr.visit_post(&mut |e| {
+            if let MirRelationExpr::Let { id, value, .. } = e {
+                bindings.insert(&**value as *const MirRelationExpr, *id);
+            }
+        });
+
+        let mut attributes: Vec<Attributes> = vec![];
+        let mut types: Vec<RelationType> = vec![];
+        let mut positions = HashMap::new();
+        let mut locals = HashMap::new();
+        // The positions of the nodes whose parents have not yet been visited.
+        let mut stack: Vec<usize> = vec![];
+        expr.visit_post(&mut |e| {
+            let inputs = stack.split_off(stack.len() - e.num_inputs());
+            let input_types: Vec<_> = inputs.iter().map(|i| types[*i].clone()).collect();
+            let input_attributes: Vec<_> = inputs.iter().map(|i| &attributes[*i]).collect();
+            let typ = e.typ_with_input_types(&input_types);
+            let attrs = Attributes::derive(e, typ.clone(), &input_attributes, &locals);
+
+            let ptr = e as *const MirRelationExpr;
+            if let Some(id) = bindings.get(&ptr) {
+                locals.insert(*id, attrs.clone());
+            }
+            positions.insert(ptr, attributes.len());
+            stack.push(attributes.len());
+            attributes.push(attrs);
+            types.push(typ);
+        });
+        assert_eq!(stack.len(), 1);
+
+        Analysis {
+            attributes,
+            types,
+            positions,
+        }
+    }
+
+    /// Returns the attributes of `expr`, or `None` if `expr` is not a node in
+    /// the analyzed expression.
+    pub fn attributes(&self, expr: &MirRelationExpr) -> Option<&Attributes> {
+        self.position(expr).map(|i| &self.attributes[i])
+    }
+
+    /// Returns the type of `expr`, or `None` if `expr` is not a node in the
+    /// analyzed expression.
+    pub fn typ(&self, expr: &MirRelationExpr) -> Option<&RelationType> {
+        self.position(expr).map(|i| &self.types[i])
+    }
+
+    /// Returns the attributes of every node in the analyzed expression, in
+    /// left-to-right post-order.
+    ///
+    /// This is the order in which nodes are visited by
+    /// [`MirRelationExpr::visit_post`] and its variants, so the attributes can
+    /// be consulted while mutating the expression with
+    /// [`MirRelationExpr::try_visit_mut_post`], as long as only the node being
+    /// visited is replaced.
+    pub fn post_order(&self) -> &[Attributes] {
+        &self.attributes
+    }
+
+    fn position(&self, expr: &MirRelationExpr) -> Option<usize> {
+        self.positions
+            .get(&(expr as *const MirRelationExpr))
+            .copied()
+    }
+}
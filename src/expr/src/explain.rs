@@ -15,7 +15,8 @@
 //!   * Nodes which only have a single input are grouped together
 //!     into "chains."
 //!   * Each chain of `MirRelationExpr`s is referred to by ID, e.g. %4.
-//!   * Nodes may be followed by additional, indented annotations.
+//!   * Nodes may be followed by additional, indented annotations. Type and
+//!     attribute annotations are only printed on request.
 //!   * Columns are referred to by position, e.g. #4.
 //!   * Collections of columns are written as ranges where possible,
 //!     e.g. "#2..#5".
@@ -31,6 +32,7 @@ use std::iter;
 use mz_ore::str::{bracketed, separated, StrExt};
 use mz_repr::RelationType;
 
+use crate::analysis::{Analysis, Attributes};
 use crate::{ExprHumanizer, Id, JoinImplementation, LocalId, MirRelationExpr};
 
 /// An `ViewExplanation` facilitates pretty-printing of a [`MirRelationExpr`].
@@ -61,6 +63,8 @@ pub struct ExplanationNode<'a> {
     pub expr: &'a MirRelationExpr,
     /// The type of the expression, if desired.
     pub typ: Option<RelationType>,
+    /// The attributes of the expression, if desired.
+    pub attributes: Option<Attributes>,
     /// The ID of the linear chain to which this node belongs.
     pub chain: usize,
 }
@@ -139,6 +143,7 @@ impl<'a> ViewExplanation<'a> {
             explanation.nodes.push(ExplanationNode {
                 expr,
                 typ: None,
+                attributes: None,
                 chain: explanation.chain,
             });
             explanation
@@ -181,20 +186,36 @@ impl<'a> ViewExplanation<'a> {
 
     /// Attach type information into the explanation.
     pub fn explain_types(&mut self) {
+        let analysis = self.analyze();
         for node in &mut self.nodes {
-            if let MirRelationExpr::Let { .. } = &node.expr {
-                // Skip.
-                // Since we don't print out Let nodes in the explanation,
-                // types of Let nodes should not be attached to the
-                // explanation. The type information of a Let is always the
-                // same as the the type of the body.
-            } else {
-                // TODO(jamii) `typ` is itself recursive, so this is quadratic :(
-                node.typ = Some(node.expr.typ());
+            // Since we don't print out Let nodes in the explanation, types of
+            // Let nodes should not be attached to the explanation. The type
+            // information of a Let is always the same as the the type of the
+            // body.
+            if !matches!(node.expr, MirRelationExpr::Let { .. }) {
+                node.typ = analysis.typ(node.expr).cloned();
+            }
+        }
+    }
+
+    /// Attach the [`Attributes`] of each node into the explanation.
+    pub fn explain_attributes(&mut self) {
+        let analysis = self.analyze();
+        for node in &mut self.nodes {
+            // As with types, the attributes of a Let are those of its body.
+            if !matches!(node.expr, MirRelationExpr::Let { .. }) {
+                node.attributes = analysis.attributes(node.expr).cloned();
             }
         }
     }
 
+    /// Analyzes the explained expression, which is the last node in
+    /// post-order.
+    fn analyze(&self) -> Analysis {
+        let root = self.nodes.last().expect("explanation has a root").expr;
+        Analysis::new(root)
+    }
+
     fn fmt_node(&self, f: &mut fmt::Formatter, node: &ExplanationNode) -> fmt::Result {
         use MirRelationExpr::*;
 
@@ -356,6 +377,28 @@ impl<'a> ViewExplanation<'a> {
             )?;
         }
 
+        if let Some(attributes) = &node.attributes {
+            writeln!(f, "| | arity = {}", attributes.arity)?;
+            // Keys are already printed with the type, if requested.
+            if node.typ.is_none() {
+                writeln!(
+                    f,
+                    "| | keys = ({})",
+                    separated(
+                        ", ",
+                        attributes
+                            .keys
+                            .iter()
+                            .map(|key| bracketed("(", ")", Indices(key)))
+                    )
+                )?;
+            }
+            writeln!(f, "| | non_negative = {}", attributes.non_negative)?;
+            if let Some(cardinality) = attributes.cardinality {
+                writeln!(f, "| | cardinality <= {}", cardinality)?;
+            }
+        }
+
         Ok(())
     }
 
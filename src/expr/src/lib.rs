@@ -24,6 +24,7 @@ mod linear;
 mod relation;
 mod scalar;
 
+pub mod analysis;
 pub mod explain;
 pub mod proto;
 
@@ -68,6 +68,7 @@ pub enum UnmaterializableFunc {
     MzClusterId,
     MzLogicalTimestamp,
     MzSessionId,
+    MzSessionSettings,
     MzUptime,
     MzVersion,
     PgBackendPid,
@@ -94,6 +95,7 @@ impl UnmaterializableFunc {
             }
             .nullable(false),
             UnmaterializableFunc::MzSessionId => ScalarType::Uuid.nullable(false),
+            UnmaterializableFunc::MzSessionSettings => ScalarType::Jsonb.nullable(false),
             UnmaterializableFunc::MzUptime => ScalarType::Interval.nullable(true),
             UnmaterializableFunc::MzVersion => ScalarType::String.nullable(false),
             UnmaterializableFunc::PgBackendPid => ScalarType::Int32.nullable(false),
@@ -116,6 +118,7 @@ impl fmt::Display for UnmaterializableFunc {
             UnmaterializableFunc::MzClusterId => f.write_str("mz_cluster_id"),
             UnmaterializableFunc::MzLogicalTimestamp => f.write_str("mz_logical_timestamp"),
             UnmaterializableFunc::MzSessionId => f.write_str("mz_session_id"),
+            UnmaterializableFunc::MzSessionSettings => f.write_str("mz_session_settings"),
             UnmaterializableFunc::MzUptime => f.write_str("mz_uptime"),
             UnmaterializableFunc::MzVersion => f.write_str("mz_version"),
             UnmaterializableFunc::PgBackendPid => f.write_str("pg_backend_pid"),
@@ -389,7 +389,8 @@ pub fn bench_avro(c: &mut Criterion) {
     buf.extend(mz_avro::to_avro_datum(&schema, record).unwrap());
     let len = buf.len() as u64;
 
-    let mut decoder = Decoder::new(schema_str, None, "avro_bench".to_string(), false).unwrap();
+    let mut decoder =
+        Decoder::new(schema_str, None, "avro_bench".to_string(), false, false).unwrap();
 
     let mut bg = c.benchmark_group("avro");
     bg.throughput(Throughput::Bytes(len));
@@ -21,7 +21,9 @@ pub use self::encode::{
     encode_datums_as_avro, encode_debezium_transaction_unchecked, get_debezium_transaction_schema,
     AvroEncoder, AvroSchemaGenerator,
 };
-pub use self::schema::{parse_schema, schema_to_relationdesc, ConfluentAvroResolver};
+pub use self::schema::{
+    parse_schema, schema_to_relationdesc, ConfluentAvroResolver, NewWriterFields,
+};
 
 fn is_null(schema: &SchemaPieceOrNamed) -> bool {
     matches!(schema, SchemaPieceOrNamed::Piece(SchemaPiece::Null))
@@ -28,7 +28,7 @@ use mz_repr::adt::jsonb::JsonbPacker;
 use mz_repr::adt::numeric;
 use mz_repr::{Datum, Row, RowPacker};
 
-use crate::avro::ConfluentAvroResolver;
+use crate::avro::{ConfluentAvroResolver, NewWriterFields};
 
 /// Manages decoding of Avro-encoded bytes.
 #[derive(Debug)]
@@ -53,7 +53,7 @@ mod tests {
 "name": "test",
 "fields": [{"name": "f1", "type": "int"}, {"name": "f2", "type": "int"}]
 }"#;
-        let mut decoder = Decoder::new(&schema, None, "Test".to_string(), false).unwrap();
+        let mut decoder = Decoder::new(&schema, None, "Test".to_string(), false, false).unwrap();
         // This is not a valid Avro blob for the given schema
         let mut bad_bytes: &[u8] = &[0];
         assert!(block_on(decoder.decode(&mut bad_bytes)).is_err());
@@ -73,15 +73,23 @@ impl Decoder {
     /// The provided schema is called the "reader schema", which is the schema
     /// that we are expecting to use to decode records. The records may indicate
     /// that they are encoded with a different schema; as long as those.
+    ///
+    /// If `reject_new_fields` is true, records whose writer schema contains
+    /// fields that are absent from the reader schema fail to decode.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         reader_schema: &str,
         schema_registry: Option<mz_ccsr::ClientConfig>,
         debug_name: String,
         confluent_wire_format: bool,
+        reject_new_fields: bool,
     ) -> anyhow::Result<Decoder> {
-        let csr_avro =
-            ConfluentAvroResolver::new(reader_schema, schema_registry, confluent_wire_format)?;
+        let csr_avro = ConfluentAvroResolver::new(
+            reader_schema,
+            schema_registry,
+            confluent_wire_format,
+            reject_new_fields,
+        )?;
 
         Ok(Decoder {
             csr_avro,
@@ -124,6 +132,12 @@ impl Decoder {
         );
         Ok(self.row_buf.clone())
     }
+
+    /// Returns the writer schemas encountered since the last call that
+    /// contain fields absent from the reader schema.
+    pub fn take_new_writer_fields(&mut self) -> Vec<NewWriterFields> {
+        self.csr_avro.take_new_writer_fields()
+    }
 }
 
 pub struct AvroStringDecoder<'a> {
@@ -236,13 +236,21 @@ pub struct ConfluentAvroResolver {
 }
 
 impl ConfluentAvroResolver {
+    /// Constructs a new resolver.
+    ///
+    /// If `reject_new_fields` is true, writer schemas fetched from the schema
+    /// registry that contain fields absent from the reader schema fail to
+    /// resolve, rather than having those fields skipped.
     pub fn new(
         reader_schema: &str,
         config: Option<mz_ccsr::ClientConfig>,
         confluent_wire_format: bool,
+        reject_new_fields: bool,
     ) -> anyhow::Result<Self> {
         let reader_schema = parse_schema(reader_schema)?;
-        let writer_schemas = config.map(SchemaCache::new).transpose()?;
+        let writer_schemas = config
+            .map(|config| SchemaCache::new(config, reject_new_fields))
+            .transpose()?;
         Ok(Self {
             reader_schema,
             writer_schemas,
@@ -250,6 +258,15 @@ impl ConfluentAvroResolver {
         })
     }
 
+    /// Returns the writer schemas fetched since the last call that contain
+    /// fields absent from the reader schema.
+    pub fn take_new_writer_fields(&mut self) -> Vec<NewWriterFields> {
+        match &mut self.writer_schemas {
+            Some(cache) => cache.unreported.drain(..).collect(),
+            None => vec![],
+        }
+    }
+
     pub async fn resolve<'a, 'b>(
         &'a mut self,
         mut bytes: &'b [u8],
@@ -305,17 +322,39 @@ impl fmt::Debug for ConfluentAvroResolver {
     }
 }
 
+/// The fields of a writer schema that are absent from the reader schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewWriterFields {
+    /// The ID of the writer schema in the schema registry.
+    pub schema_id: i32,
+    /// The paths of the new fields, with the names of enclosing records
+    /// separated by dots.
+    pub fields: Vec<String>,
+}
+
 #[derive(Debug)]
 struct SchemaCache {
     cache: HashMap<i32, Result<Schema, AvroError>>,
     ccsr_client: mz_ccsr::Client,
+    reject_new_fields: bool,
+    /// The new fields of each cached writer schema that has any.
+    new_fields: HashMap<i32, Vec<String>>,
+    /// New fields that have not yet been returned by
+    /// [`ConfluentAvroResolver::take_new_writer_fields`].
+    unreported: Vec<NewWriterFields>,
 }
 
 impl SchemaCache {
-    fn new(schema_registry: mz_ccsr::ClientConfig) -> Result<SchemaCache, anyhow::Error> {
+    fn new(
+        schema_registry: mz_ccsr::ClientConfig,
+        reject_new_fields: bool,
+    ) -> Result<SchemaCache, anyhow::Error> {
         Ok(SchemaCache {
             cache: HashMap::new(),
             ccsr_client: schema_registry.build()?,
+            reject_new_fields,
+            new_fields: HashMap::new(),
+            unreported: vec![],
         })
     }
 
@@ -354,6 +393,14 @@ impl SchemaCache {
                 // which  we don't want to repeat for every record. So, parse and resolve it, and cache the
                 // result (whether schema or error).
                 let result = Schema::from_str(&response.raw).and_then(|schema| {
+                    let fields = new_writer_fields(schema.top_node(), reader_schema.top_node());
+                    if !fields.is_empty() {
+                        self.new_fields.insert(id, fields.clone());
+                        self.unreported.push(NewWriterFields {
+                            schema_id: id,
+                            fields,
+                        });
+                    }
                     // Schema fingerprints don't actually capture whether two schemas are meaningfully
                     // different, because they strip out logical types. Thus, resolve in all cases.
                     let resolved = resolve_schemas(&schema, reader_schema)?;
@@ -362,6 +409,160 @@ impl SchemaCache {
                 v.insert(result)
             }
         };
+        if self.reject_new_fields {
+            if let Some(fields) = self.new_fields.get(&id) {
+                bail!(
+                    "writer schema adds fields that are not in the reader schema: {}",
+                    fields.join(", ")
+                );
+            }
+        }
         entry.as_ref().map_err(|e| anyhow::Error::new(e.clone()))
     }
 }
+
+/// Returns the paths of the record fields in `writer` that have no
+/// counterpart in `reader`.
+///
+/// Records that appear in both schemas, either directly or as variants of a
+/// union, are compared field by field, descending into fields that appear in
+/// both. Records nested in arrays or maps are not examined.
+fn new_writer_fields(writer: SchemaNode, reader: SchemaNode) -> Vec<String> {
+    let mut fields = vec![];
+    let mut visiting = HashSet::new();
+    collect_new_writer_fields(writer, reader, "", &mut visiting, &mut fields);
+    fields
+}
+
+fn collect_new_writer_fields(
+    writer: SchemaNode,
+    reader: SchemaNode,
+    path: &str,
+    visiting: &mut HashSet<(String, String)>,
+    out: &mut Vec<String>,
+) {
+    let writer_records = record_nodes(writer);
+    let reader_records = record_nodes(reader);
+    for w in &writer_records {
+        // Pair records by name, unless there is only one record on each side,
+        // in which case schema resolution pairs them regardless of name.
+        let r = if writer_records.len() == 1 && reader_records.len() == 1 {
+            &reader_records[0]
+        } else {
+            match reader_records.iter().find(|r| r.name == w.name) {
+                Some(r) => r,
+                None => continue,
+            }
+        };
+        // Named types can be recursive, so don't descend into a pair of
+        // records that is already being compared.
+        let key = (
+            w.name.map(|n| n.to_string()).unwrap_or_default(),
+            r.name.map(|n| n.to_string()).unwrap_or_default(),
+        );
+        if !visiting.insert(key.clone()) {
+            continue;
+        }
+        let (writer_fields, reader_fields, reader_lookup) = match (w.inner, r.inner) {
+            (
+                SchemaPiece::Record { fields, .. },
+                SchemaPiece::Record {
+                    fields: reader_fields,
+                    lookup,
+                    ..
+                },
+            ) => (fields, reader_fields, lookup),
+            _ => unreachable!("record_nodes returns only records"),
+        };
+        for f in writer_fields {
+            let field_path = if path.is_empty() {
+                f.name.clone()
+            } else {
+                format!("{}.{}", path, f.name)
+            };
+            match reader_lookup.get(&f.name) {
+                None => out.push(field_path),
+                Some(i) => collect_new_writer_fields(
+                    w.step(&f.schema),
+                    r.step(&reader_fields[*i].schema),
+                    &field_path,
+                    visiting,
+                    out,
+                ),
+            }
+        }
+        visiting.remove(&key);
+    }
+}
+
+/// Returns `node` if it is a record, or the variants of `node` that are
+/// records if it is a union.
+fn record_nodes(node: SchemaNode) -> Vec<SchemaNode> {
+    match node.inner {
+        SchemaPiece::Record { .. } => vec![node],
+        SchemaPiece::Union(union) => union
+            .variants()
+            .iter()
+            .map(|v| node.step(v))
+            .filter(|v| matches!(v.inner, SchemaPiece::Record { .. }))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{new_writer_fields, parse_schema};
+
+    #[test]
+    fn test_new_writer_fields() -> anyhow::Result<()> {
+        let reader = parse_schema(
+            r#"{
+                "type": "record",
+                "name": "envelope",
+                "fields": [
+                    {"name": "before", "type": ["null", {
+                        "type": "record",
+                        "name": "row",
+                        "fields": [{"name": "a", "type": "int"}]
+                    }]},
+                    {"name": "after", "type": ["null", "row"]}
+                ]
+            }"#,
+        )?;
+        let writer = parse_schema(
+            r#"{
+                "type": "record",
+                "name": "envelope",
+                "fields": [
+                    {"name": "before", "type": ["null", {
+                        "type": "record",
+                        "name": "row",
+                        "fields": [
+                            {"name": "a", "type": "int"},
+                            {"name": "b", "type": ["null", "string"]}
+                        ]
+                    }]},
+                    {"name": "after", "type": ["null", "row"]},
+                    {"name": "op", "type": "string"}
+                ]
+            }"#,
+        )?;
+        assert_eq!(
+            new_writer_fields(writer.top_node(), reader.top_node()),
+            vec!["before.b", "after.b", "op"],
+        );
+        assert!(new_writer_fields(reader.top_node(), reader.top_node()).is_empty());
+        assert!(new_writer_fields(reader.top_node(), writer.top_node()).is_empty());
+
+        let recursive = parse_schema(
+            r#"{
+                "type": "record",
+                "name": "node",
+                "fields": [{"name": "next", "type": ["null", "node"]}]
+            }"#,
+        )?;
+        assert!(new_writer_fields(recursive.top_node(), recursive.top_node()).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Configuration file support.
+//!
+//! A configuration file is a TOML or YAML document whose top-level keys are
+//! the long names of command-line options, e.g.:
+//!
+//! ```toml
+//! listen-addr = "0.0.0.0:6875"
+//! data-directory = "/var/lib/materialized"
+//! workers = 4
+//! orchestrator-service-label = ["team=storage", "env=${MZ_ENVIRONMENT}"]
+//! ```
+//!
+//! Each entry is translated into the equivalent command-line option. Options
+//! specified on the command line take precedence over options specified in the
+//! configuration file, which in turn take precedence over options specified
+//! via environment variables.
+//!
+//! String values may reference environment variables as `${NAME}`. Use `$$` to
+//! produce a literal dollar sign.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use clap::{ArgMatches, ArgSettings, CommandFactory, FromArgMatches, ValueSource};
+use serde_json::Value;
+
+use crate::Args;
+
+/// The name of the command-line option that specifies the configuration file.
+const CONFIG_FILE_OPTION: &str = "config-file";
+
+/// The environment variable that specifies the configuration file, if the
+/// command-line option is absent.
+const CONFIG_FILE_ENV: &str = "MZ_CONFIG_FILE";
+
+/// Options that may only be specified on the command line.
+const CLI_ONLY_OPTIONS: &[&str] = &[CONFIG_FILE_OPTION, "config-check", "help", "version"];
+
+/// The process's configuration, along with the provenance of each option.
+pub struct LoadedConfig {
+    /// The parsed configuration.
+    pub args: Args,
+    matches: ArgMatches,
+    file_options: BTreeSet<String>,
+}
+
+impl LoadedConfig {
+    /// Describes where the value of the option with long name `name` came
+    /// from.
+    pub fn source(&self, name: &str) -> &'static str {
+        if self.file_options.contains(name) {
+            return "configuration file";
+        }
+        match self.matches.value_source(name.replace('-', "_").as_str()) {
+            Some(ValueSource::CommandLine) => "command line",
+            Some(ValueSource::EnvVariable) => "environment",
+            Some(ValueSource::DefaultValue) | None => "default",
+        }
+    }
+}
+
+/// Loads the process's configuration from the command line, the
+/// configuration file, if any, and the environment.
+///
+/// Errors from parsing the command line are returned as [`clap::Error`]s.
+pub fn load() -> Result<LoadedConfig, anyhow::Error> {
+    let (args, file_options) = args_with_config_file()?;
+    let matches = Args::command().try_get_matches_from(args)?;
+    let args = Args::from_arg_matches(&matches)?;
+    Ok(LoadedConfig {
+        args,
+        matches,
+        file_options,
+    })
+}
+
+/// Returns the process's command-line arguments, with the options from the
+/// configuration file, if any, spliced in ahead of the options specified on
+/// the command line, and the names of the options taken from the
+/// configuration file.
+fn args_with_config_file() -> Result<(Vec<OsString>, BTreeSet<String>), anyhow::Error> {
+    let mut cli_args: Vec<OsString> = env::args_os().collect();
+    let path = match find_config_file(&cli_args) {
+        None => return Ok((cli_args, BTreeSet::new())),
+        Some(path) => path,
+    };
+    let (file_args, file_options) = load_file(Path::new(&path), &cli_args[1..])
+        .with_context(|| format!("loading configuration file {}", path.to_string_lossy()))?;
+    let mut args = vec![cli_args.remove(0)];
+    args.extend(file_args);
+    args.extend(cli_args);
+    Ok((args, file_options))
+}
+
+/// Finds the path to the configuration file, either from the
+/// `--config-file` command-line option or from the environment.
+fn find_config_file(cli_args: &[OsString]) -> Option<OsString> {
+    let flag = format!("--{CONFIG_FILE_OPTION}");
+    let flag_eq = format!("--{CONFIG_FILE_OPTION}=");
+    let mut path = None;
+    let mut args = cli_args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--" {
+            break;
+        } else if arg == flag {
+            path = args.next().cloned();
+        } else if let Some(value) = arg.strip_prefix(&flag_eq) {
+            path = Some(value.into());
+        }
+    }
+    path.or_else(|| env::var_os(CONFIG_FILE_ENV))
+}
+
+/// Loads the configuration file at `path` and translates it into command-line
+/// arguments, omitting any options that are present in `cli_args`.
+///
+/// Also returns the names of the options that were translated.
+fn load_file(
+    path: &Path,
+    cli_args: &[OsString],
+) -> Result<(Vec<OsString>, BTreeSet<String>), anyhow::Error> {
+    let contents = fs::read_to_string(path)?;
+    let entries: BTreeMap<String, Value> = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&contents)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&contents)?,
+        _ => bail!("configuration file must have a .toml, .yaml, or .yml extension"),
+    };
+
+    let command = Args::command();
+    let mut out = vec![];
+    let mut names = BTreeSet::new();
+    for (key, value) in entries {
+        let name = key.replace('_', "-");
+        if CLI_ONLY_OPTIONS.contains(&name.as_str()) {
+            bail!("option {key} may only be specified on the command line");
+        }
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .ok_or_else(|| anyhow!("unknown option {key}"))?;
+        if specified_on_cli(cli_args, &name, arg.get_short()) {
+            continue;
+        }
+        let len = out.len();
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Null => continue,
+                Value::Bool(b) if !arg.is_set(ArgSettings::TakesValue) => {
+                    if b {
+                        out.push(format!("--{name}").into());
+                    }
+                    continue;
+                }
+                _ if !arg.is_set(ArgSettings::TakesValue) => {
+                    bail!("option {key} is a flag and must be set to true or false")
+                }
+                Value::Bool(b) => b.to_string(),
+                Value::Number(n) => n.to_string(),
+                Value::String(s) => {
+                    interpolate(&s).with_context(|| format!("interpolating option {key}"))?
+                }
+                Value::Array(_) | Value::Object(_) => {
+                    bail!("option {key} must be a string, number, boolean, or list thereof")
+                }
+            };
+            out.push(format!("--{name}={value}").into());
+        }
+        if out.len() > len {
+            names.insert(name);
+        }
+    }
+    Ok((out, names))
+}
+
+/// Reports whether the option with long name `name` and optional short name
+/// `short` appears in `cli_args`.
+fn specified_on_cli(cli_args: &[OsString], name: &str, short: Option<char>) -> bool {
+    let long = format!("--{name}");
+    let long_eq = format!("--{name}=");
+    let short = short.map(|c| format!("-{c}"));
+    cli_args
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .take_while(|arg| arg != "--")
+        .any(|arg| {
+            let is_short = match &short {
+                Some(short) => !arg.starts_with("--") && arg.starts_with(short.as_str()),
+                None => false,
+            };
+            arg == long || arg.starts_with(&long_eq) || is_short
+        })
+}
+
+/// Replaces references to environment variables of the form `${NAME}` in `s`
+/// with the values of those variables.
+///
+/// `$$` is replaced with a literal `$`. It is an error to reference an
+/// environment variable that is not set.
+fn interpolate(s: &str) -> Result<String, anyhow::Error> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('$') => out.push('$'),
+            Some('{') => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => bail!("unterminated environment variable reference"),
+                    }
+                }
+                if name.is_empty() {
+                    bail!("empty environment variable reference");
+                }
+                match env::var(&name) {
+                    Ok(value) => out.push_str(&value),
+                    Err(_) => bail!("environment variable {name} is not set"),
+                }
+            }
+            _ => bail!("invalid use of $: expected ${{NAME}} or $$"),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpolate;
+
+    #[test]
+    fn test_interpolate() {
+        std::env::set_var("MZ_CONFIG_TEST_VAR", "value");
+        assert_eq!(interpolate("plain").unwrap(), "plain");
+        assert_eq!(
+            interpolate("a-${MZ_CONFIG_TEST_VAR}-b").unwrap(),
+            "a-value-b"
+        );
+        assert_eq!(interpolate("cost: $$5").unwrap(), "cost: $5");
+        assert!(interpolate("${MZ_CONFIG_TEST_UNSET_VAR}").is_err());
+        assert!(interpolate("${MZ_CONFIG_TEST_VAR").is_err());
+        assert!(interpolate("${}").is_err());
+        assert!(interpolate("$HOME").is_err());
+    }
+}
@@ -18,11 +18,13 @@
 //! [0]: https://paper.dropbox.com/doc/Materialize-architecture-plans--AYSu6vvUu7ZDoOEZl7DNi8UQAg-sZj5rhJmISdZSfK0WBxAl
 
 use std::cmp;
+use std::collections::HashMap;
 use std::env;
 use std::ffi::CStr;
 use std::fmt;
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::panic;
 use std::panic::PanicInfo;
 use std::path::PathBuf;
@@ -39,24 +41,45 @@ use chrono::Utc;
 use clap::{AppSettings, ArgEnum, Parser};
 use fail::FailScenario;
 use http::header::HeaderValue;
+use http::Uri;
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use serde::Deserialize;
 use sysinfo::{ProcessorExt, SystemExt};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use materialized::{
     OrchestratorBackend, OrchestratorConfig, RemoteStorageConfig, SecretsControllerConfig,
     StorageConfig, TlsConfig, TlsMode,
 };
-use mz_coord::{PersistConfig, PersistFileStorage, PersistStorage};
+use mz_authenticator::{
+    Authenticator, CatalogAuthenticator, FronteggAuthenticator, PasswordAuthenticator,
+    TrustAuthenticator,
+};
+use mz_coord::catalog::storage::{CatalogDurability, SqliteSynchronous};
+use mz_coord::{PeekConcurrencyLimits, PersistConfig, PersistFileStorage, PersistStorage};
+use mz_dataflow_types::client::controller::ClusterReplicaSize;
 use mz_dataflow_types::sources::AwsExternalId;
 use mz_frontegg_auth::{FronteggAuthentication, FronteggConfig};
+use mz_orchestrator::{CpuLimit, DiskLimit, MemoryLimit, Toleration};
 use mz_orchestrator_kubernetes::KubernetesOrchestratorConfig;
-use mz_orchestrator_process::ProcessOrchestratorConfig;
+use mz_orchestrator_process::{Launcher, ProcessOrchestratorConfig};
+use mz_orchestrator_systemd::SystemdOrchestratorConfig;
 use mz_ore::cgroup::{detect_memory_limit, MemoryLimit};
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::SYSTEM_TIME;
+use mz_ore::str::StrExt;
+use mz_secrets::DEFAULT_MAX_SECRET_SIZE;
+use mz_secrets_aws::AwsSecretsConfig;
+use mz_secrets_filesystem::{EncryptionKey, DEFAULT_MAX_VERSIONS};
+use mz_secrets_vault::{VaultAuth, VaultConfig};
+
+use crate::config::LoadedConfig;
 
+mod config;
+mod reload;
 mod sys;
 mod tracing;
 
@@ -92,6 +115,17 @@ pub struct Args {
     /// dependencies.
     #[clap(short, long, parse(from_occurrences))]
     version: usize,
+    /// Load options from the specified TOML or YAML configuration file.
+    ///
+    /// Each top-level key in the file names a command-line option, like
+    /// `listen-addr` or `data-directory`. String values may reference
+    /// environment variables as `${NAME}`. Options specified on the command
+    /// line take precedence over options in the configuration file.
+    #[clap(long, env = "MZ_CONFIG_FILE", value_name = "PATH")]
+    config_file: Option<PathBuf>,
+    /// Validate the configuration and exit without starting the server.
+    #[clap(long)]
+    config_check: bool,
     /// Allow running this dev (unoptimized) build.
     #[cfg(debug_assertions)]
     #[clap(long, env = "MZ_DEV")]
@@ -102,7 +136,12 @@ pub struct Args {
     /// Whether to run in safe mode.
     ///
     /// In safe mode, features that provide access to the underlying machine,
-    /// like file sources and sinks, are disabled.
+    /// like file sources and sinks, experimental features, and queries that
+    /// could use an unbounded amount of memory are disabled.
+    ///
+    /// Safe mode can only be enabled when the node is first initialized, and
+    /// cannot be disabled thereafter. It cannot be combined with
+    /// `--experimental`.
     ///
     /// This option is intended for use by the cloud product
     /// (cloud.materialize.com), but may be useful in other contexts as well.
@@ -179,26 +218,97 @@ pub struct Args {
 
     // === Platform options. ===
     /// The service orchestrator implementation to use, if any.
-    #[structopt(long, hide = true, arg_enum)]
-    orchestrator: Option<Orchestrator>,
+    #[structopt(
+        long,
+        hide = true,
+        possible_values(ORCHESTRATORS.iter().map(|(name, _)| *name))
+    )]
+    orchestrator: Option<String>,
     /// Labels to apply to all services created by the orchestrator in the form
     /// `KEY=VALUE`.
     ///
     /// Only valid when `--orchestrator` is specified.
     #[structopt(long, hide = true)]
-    orchestrator_service_label: Vec<OrchestratorLabel>,
+    orchestrator_service_label: Vec<KeyValueArg>,
+    /// Labels, in the form `KEY=VALUE`, that a node must have for compute
+    /// instances to be placed on it.
+    ///
+    /// Only valid when `--orchestrator` is specified.
+    #[structopt(long, hide = true)]
+    orchestrator_compute_node_selector: Vec<KeyValueArg>,
+    /// Taints, in the form `KEY[=VALUE][:EFFECT]`, that compute instances
+    /// tolerate. Use `*` as the key to tolerate taints with any key.
+    ///
+    /// Only valid when `--orchestrator` is specified.
+    #[structopt(long, hide = true)]
+    orchestrator_compute_toleration: Vec<Toleration>,
+    /// The disk, in bytes, to provision for each process of the storage
+    /// runtime and of each compute instance.
+    ///
+    /// Only valid when `--orchestrator` is specified.
+    #[structopt(long, hide = true)]
+    orchestrator_disk_limit: Option<u64>,
+    /// The resources to allocate to each size of cluster, as a JSON object
+    /// that maps the name of each size to its `cpu_limit`, in CPUs, and its
+    /// `memory_limit`, in bytes, e.g.,
+    /// `{"small": {"cpu_limit": 1, "memory_limit": 4294967296}}`.
+    ///
+    /// If unset, clusters of any size may be created, and their resources
+    /// are not limited.
+    ///
+    /// Only valid when `--orchestrator` is specified.
+    #[structopt(long, hide = true, value_name = "JSON")]
+    orchestrator_cluster_replica_sizes: Option<ClusterReplicaSizeMap>,
     /// The Kubernetes context to use with the Kubernetes orchestrator.
     ///
     /// This defaults to `minikube` to prevent disaster (e.g., connecting to a
     /// production cluster that happens to be the active Kubernetes context.)
     #[structopt(long, hide = true, default_value = "minikube")]
     kubernetes_context: String,
+    /// The storage class of the persistent volumes that the Kubernetes
+    /// orchestrator provisions for services with a disk limit.
+    ///
+    /// Defaults to the Kubernetes cluster's default storage class.
+    #[structopt(long, hide = true)]
+    kubernetes_storage_class: Option<String>,
+    /// The IP address on which processes started by the process or systemd
+    /// orchestrator should listen.
+    #[structopt(long, hide = true, default_value = "0.0.0.0")]
+    process_orchestrator_listen_addr: IpAddr,
+    /// The hostname at which processes started by the process or systemd
+    /// orchestrator are reachable by other services.
+    ///
+    /// Set this when services run on other machines or in containers, for
+    /// which `localhost` does not name the machine running materialized.
+    #[structopt(long, hide = true, default_value = "localhost")]
+    process_orchestrator_hostname: String,
+    /// The container runtime, e.g. `docker` or `podman`, with which the
+    /// process orchestrator should run images.
+    ///
+    /// When set, images like `--dataflowd-image` name container images rather
+    /// than binaries alongside the materialized binary.
+    #[structopt(long, hide = true)]
+    process_orchestrator_container_runtime: Option<String>,
+    /// Whether the systemd orchestrator should start units in the service
+    /// manager of the user running materialized rather than in the system's
+    /// service manager.
+    #[structopt(long, hide = true)]
+    systemd_orchestrator_user: bool,
+    /// The prefix of the names of the units started by the systemd
+    /// orchestrator.
+    ///
+    /// Deployments that share a service manager must use distinct prefixes.
+    #[structopt(long, hide = true, default_value = "materialized")]
+    systemd_orchestrator_unit_prefix: String,
     /// The dataflowd image reference to use.
     #[structopt(
         long,
         hide = true,
         required_if_eq("orchestrator", "kubernetes"),
-        default_value_if("orchestrator", Some("process"), Some("dataflowd"))
+        default_value_ifs(&[
+            ("orchestrator", Some("process"), Some("dataflowd")),
+            ("orchestrator", Some("systemd"), Some("dataflowd")),
+        ])
     )]
     dataflowd_image: Option<String>,
 
@@ -206,6 +316,158 @@ pub struct Args {
     /// The secrets controller implementation to use
     #[structopt(long, hide = true, arg_enum)]
     secrets_controller: Option<SecretsController>,
+    /// The secrets controller implementation to migrate secrets from.
+    ///
+    /// Secrets are written to both controllers, and read from the controller
+    /// named by `--secrets-controller`, falling back to this controller.
+    /// `SELECT mz_internal.migrate_secrets()` copies the remaining secrets.
+    /// Secrets cannot be migrated to the Kubernetes secrets controller.
+    #[structopt(long, hide = true, arg_enum)]
+    secrets_migrate_from: Option<SecretsController>,
+    /// A hex-encoded 256-bit key with which the local filesystem secrets
+    /// controller encrypts secrets at rest.
+    ///
+    /// Secrets stored in plaintext are encrypted when `materialized` starts
+    /// with a key. The key must be supplied on every subsequent start.
+    #[clap(
+        long,
+        env = "MZ_SECRETS_ENCRYPTION_KEY",
+        value_name = "KEY",
+        hide_env_values = true
+    )]
+    secrets_encryption_key: Option<EncryptionKey>,
+    /// A base64-encoded 256-bit key, encrypted with AWS KMS, with which the
+    /// local filesystem secrets controller encrypts secrets at rest.
+    ///
+    /// The key is decrypted with AWS KMS when `materialized` starts, using
+    /// the default AWS credentials chain. Such a key is generated by
+    /// `aws kms generate-data-key --key-id <KMS-KEY> --number-of-bytes 32`,
+    /// which reports it as the `CiphertextBlob`.
+    #[clap(
+        long,
+        env = "MZ_SECRETS_ENCRYPTION_KEY_KMS",
+        value_name = "CIPHERTEXT",
+        hide_env_values = true,
+        conflicts_with = "secrets-encryption-key"
+    )]
+    secrets_encryption_key_kms: Option<String>,
+    /// The number of versions of each secret that the local filesystem
+    /// secrets controller retains, including the current version.
+    #[clap(long, value_name = "N", hide = true, default_value_t = DEFAULT_MAX_VERSIONS)]
+    secrets_max_versions: usize,
+    /// The numeric ID of the user that owns the local filesystem secrets
+    /// controller's directory and files.
+    ///
+    /// Defaults to the user that runs `materialized`.
+    #[clap(long, env = "MZ_SECRETS_UID", value_name = "UID")]
+    secrets_uid: Option<u32>,
+    /// The numeric ID of the group that owns the local filesystem secrets
+    /// controller's directory and files.
+    ///
+    /// Defaults to the group of the user that runs `materialized`.
+    #[clap(long, env = "MZ_SECRETS_GID", value_name = "GID")]
+    secrets_gid: Option<u32>,
+    /// Recreate secrets that the catalog knows about but that are missing
+    /// from secrets storage as empty placeholders, rather than leaving them
+    /// unreadable until they are altered or dropped.
+    #[clap(long, hide = true)]
+    recreate_missing_secrets: bool,
+    /// The maximum size of the contents of a secret, in bytes.
+    #[clap(
+        long,
+        env = "MZ_MAX_SECRET_SIZE",
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_SECRET_SIZE
+    )]
+    max_secret_size: usize,
+    /// The address of the Vault server that the Vault secrets controller
+    /// stores secrets in.
+    #[clap(
+        long,
+        env = "VAULT_ADDR",
+        value_name = "URL",
+        hide = true,
+        required_if_eq("secrets-controller", "vault"),
+        required_if_eq("secrets-migrate-from", "vault")
+    )]
+    vault_address: Option<String>,
+    /// The path at which the KV version 2 secrets engine is mounted in Vault.
+    #[clap(long, value_name = "PATH", hide = true, default_value = "secret")]
+    vault_mount: String,
+    /// The path within the Vault mount under which to store secrets.
+    ///
+    /// Secrets are stored under a subpath named after the ID of the
+    /// catalog, so that environments can share a prefix.
+    #[clap(long, value_name = "PATH", hide = true, default_value = "materialize")]
+    vault_path_prefix: String,
+    /// The token with which to authenticate with Vault.
+    #[clap(
+        long,
+        env = "VAULT_TOKEN",
+        value_name = "TOKEN",
+        hide = true,
+        hide_env_values = true
+    )]
+    vault_token: Option<String>,
+    /// The Vault role to log in as using the Kubernetes auth method, with the
+    /// token of the service account that `materialized` runs as.
+    ///
+    /// Takes precedence over `--vault-token`.
+    #[clap(long, value_name = "ROLE", hide = true)]
+    vault_kubernetes_role: Option<String>,
+    /// The path at which the Kubernetes auth method is mounted in Vault.
+    #[clap(long, value_name = "PATH", hide = true, default_value = "kubernetes")]
+    vault_kubernetes_auth_mount: String,
+    /// The prefix of the names of the secrets that the AWS Secrets Manager or
+    /// Parameter Store secrets controller stores.
+    ///
+    /// Defaults to `materialize/` in Secrets Manager, and to `/materialize/`
+    /// in Parameter Store, whose hierarchical names must begin with `/`.
+    /// The prefix is followed by the ID of the catalog and a `/`, so that
+    /// environments can share a prefix.
+    #[clap(
+        long,
+        value_name = "PREFIX",
+        hide = true,
+        alias = "aws-secrets-manager-prefix"
+    )]
+    aws_secrets_prefix: Option<String>,
+    /// The ID or ARN of the KMS key with which AWS encrypts new secrets.
+    ///
+    /// Defaults to the account's AWS managed key for the service that stores
+    /// secrets.
+    #[clap(
+        long,
+        value_name = "KEY",
+        hide = true,
+        alias = "aws-secrets-manager-kms-key-id"
+    )]
+    aws_secrets_kms_key_id: Option<String>,
+    /// Tags, in the form `KEY=VALUE`, to attach to new secrets in AWS.
+    #[clap(
+        long,
+        value_name = "KEY=VALUE",
+        hide = true,
+        alias = "aws-secrets-manager-tag"
+    )]
+    aws_secrets_tag: Vec<KeyValueArg>,
+    /// The ARN of an IAM role to assume when accessing the AWS service that
+    /// stores secrets.
+    ///
+    /// The role is assumed with the credentials from the default AWS
+    /// credentials chain, which can itself be an IAM role, e.g., the role
+    /// associated with a Kubernetes service account.
+    #[clap(
+        long,
+        value_name = "ARN",
+        hide = true,
+        alias = "aws-secrets-manager-role-arn"
+    )]
+    aws_secrets_role_arn: Option<String>,
+    /// The endpoint of the AWS service that stores secrets, if not the
+    /// service's default endpoint, e.g., the endpoint of LocalStack.
+    #[clap(long, value_name = "URL", hide = true)]
+    aws_secrets_endpoint: Option<Uri>,
 
     // === Timely worker configuration. ===
     /// Number of dataflow worker threads.
@@ -246,6 +508,12 @@ pub struct Args {
     /// Set to "off" to disable introspection.
     #[clap(long, env = "MZ_INTROSPECTION_FREQUENCY", parse(try_from_str = parse_optional_duration), value_name = "FREQUENCY", default_value = "1s")]
     introspection_frequency: OptionalDuration,
+    /// How long to retain events in time-partitioned introspection sources,
+    /// like mz_peek_durations and mz_peek_durations_per_minute.
+    ///
+    /// Set to "off" to retain these events indefinitely.
+    #[clap(long, env = "MZ_INTROSPECTION_RETENTION", parse(try_from_str = parse_optional_duration), value_name = "DURATION", default_value = "off")]
+    introspection_retention: OptionalDuration,
     /// How much historical detail to maintain in arrangements.
     ///
     /// Set to "off" to disable logical compaction.
@@ -254,6 +522,37 @@ pub struct Args {
     /// Default frequency with which to advance timestamps
     #[clap(long, env = "MZ_TIMESTAMP_FREQUENCY", hide = true, parse(try_from_str = mz_repr::util::parse_duration), value_name = "DURATION", default_value = "1s")]
     timestamp_frequency: Duration,
+    /// The maximum number of queries that may execute concurrently on each
+    /// cluster, in the form `[CLUSTER=]N`.
+    ///
+    /// A limit without a cluster name applies to every cluster that does not
+    /// have a limit of its own. Additional queries wait until a running query
+    /// on the same cluster completes. If unset, the number of concurrent
+    /// queries is unlimited.
+    #[clap(
+        long,
+        env = "MZ_PEEK_CONCURRENCY_LIMIT",
+        value_name = "[CLUSTER=]N",
+        multiple_occurrences = true,
+        use_delimiter = true
+    )]
+    peek_concurrency_limit: Vec<PeekConcurrencyLimitArg>,
+    /// How long a query may wait for a cluster to have capacity before it
+    /// fails.
+    #[clap(long, env = "MZ_PEEK_QUEUE_TIMEOUT", parse(try_from_str = mz_repr::util::parse_duration), value_name = "DURATION", default_value = "30s")]
+    peek_queue_timeout: Duration,
+    /// The number of bytes of rows that sorting a query's result may hold in
+    /// memory.
+    ///
+    /// Results that exceed the limit are sorted by spilling to temporary
+    /// files. If unset, results are always sorted in memory.
+    #[clap(long, env = "MZ_RESULT_SORT_MEMORY_LIMIT", value_name = "BYTES")]
+    result_sort_memory_limit: Option<usize>,
+    /// The interval at which to refresh the statistics about the indexed
+    /// collection whose statistics are the oldest, or `off` to collect
+    /// statistics only on demand.
+    #[clap(long, env = "MZ_STATISTICS_INTERVAL", parse(try_from_str = parse_optional_duration), value_name = "DURATION", default_value = "off")]
+    statistics_interval: OptionalDuration,
     /// Default frequency with which to scrape prometheus metrics
     #[clap(long, env = "MZ_METRICS_SCRAPING_INTERVAL", hide = true, parse(try_from_str = parse_optional_duration), value_name = "DURATION", default_value = "30s")]
     metrics_scraping_interval: OptionalDuration,
@@ -320,6 +619,19 @@ pub struct Args {
         default_value = "127.0.0.1:6875"
     )]
     listen_addr: SocketAddr,
+    /// How long to wait, upon receiving SIGTERM, for the queries and TAILs in
+    /// progress to finish before terminating their connections.
+    ///
+    /// New connections are rejected as soon as SIGTERM is received, and
+    /// existing connections are closed once they are idle.
+    #[clap(long, env = "MZ_DRAIN_GRACE_PERIOD", parse(try_from_str = mz_repr::util::parse_duration), value_name = "DURATION", default_value = "30s")]
+    drain_grace_period: Duration,
+    /// The maximum number of concurrent client connections.
+    ///
+    /// Additional connections are refused, except those of the `mz_system`
+    /// user. If unset, the number of connections is unlimited.
+    #[clap(long, env = "MZ_MAX_CONNECTIONS", value_name = "N")]
+    max_connections: Option<usize>,
     /// How stringently to demand TLS authentication and encryption.
     ///
     /// If set to "disable", then materialized rejects HTTP and PostgreSQL
@@ -380,6 +692,35 @@ pub struct Args {
         value_name = "PATH"
     )]
     tls_key: Option<PathBuf>,
+    /// How to authenticate users who connect via PostgreSQL or HTTP.
+    ///
+    /// The "trust" authenticator trusts that users are who they claim to be.
+    /// The "password" authenticator checks the passwords that users present
+    /// against the users in the file specified by --password-file. The
+    /// "catalog" authenticator checks the passwords that users present against
+    /// the passwords of their roles, which are set with `CREATE ROLE` and
+    /// `ALTER ROLE`. The "frontegg" authenticator validates Frontegg API tokens
+    /// and JWTs, and is the default when --frontegg-tenant is specified.
+    #[clap(
+        long,
+        env = "MZ_AUTHENTICATOR",
+        possible_values = &["trust", "password", "catalog", "frontegg"],
+        default_value = "trust",
+        default_value_ifs = &[("frontegg-tenant", None, Some("frontegg"))],
+        value_name = "AUTHENTICATOR",
+    )]
+    authenticator: String,
+    /// The password file for the "password" authenticator.
+    ///
+    /// Each line of the file specifies a user and its password in the form
+    /// `user:password`. Blank lines and lines that begin with `#` are ignored.
+    #[clap(
+        long,
+        env = "MZ_PASSWORD_FILE",
+        required_if_eq("authenticator", "password"),
+        value_name = "PATH"
+    )]
+    password_file: Option<PathBuf>,
     /// Specifies the tenant id when authenticating users. Must be a valid UUID.
     #[clap(
         long,
@@ -420,6 +761,146 @@ pub struct Args {
         default_value = "mzdata"
     )]
     data_directory: PathBuf,
+    /// The URL of a PostgreSQL database in which to store the catalog, rather
+    /// than storing it in the data directory.
+    ///
+    /// Storing the catalog in PostgreSQL allows it to outlive the machine on
+    /// which materialized runs. Only the most recently started materialized
+    /// can change a catalog; any materialized that started before it can no
+    /// longer change the catalog.
+    #[clap(
+        long,
+        env = "MZ_CATALOG_POSTGRES_URL",
+        value_name = "URL",
+        hide_env_values = true
+    )]
+    catalog_postgres_url: Option<String>,
+    /// Store the catalog in typed collections of the STORAGE stash in the data
+    /// directory, rather than in its own SQLite database.
+    ///
+    /// The SQLite catalog in the data directory, if any, is migrated into the
+    /// stash when materialized is first started with this option, after which
+    /// the option must always be specified.
+    #[clap(
+        long,
+        hide = true,
+        env = "MZ_CATALOG_STASH",
+        conflicts_with_all = &[
+            "catalog-postgres-url",
+            "catalog-encryption-key",
+            "restore-catalog",
+            "check-catalog-migrations",
+            "check-catalog",
+            "dump-catalog",
+        ]
+    )]
+    catalog_stash: bool,
+    /// The SQLite `synchronous` setting to use for the catalog in the data
+    /// directory.
+    ///
+    /// `normal` is durable against process crashes but may lose the most
+    /// recent catalog changes on power loss. `full` is durable against both,
+    /// at the cost of slower DDL. `off` should only be used for testing.
+    #[clap(
+        long,
+        env = "MZ_CATALOG_SQLITE_SYNCHRONOUS",
+        value_name = "MODE",
+        possible_values = &["off", "normal", "full"],
+        default_value = "normal",
+        conflicts_with = "catalog-postgres-url"
+    )]
+    catalog_sqlite_synchronous: SqliteSynchronous,
+    /// Whether commits to the catalog wait for their changes to be synced to
+    /// durable storage.
+    ///
+    /// `none` skips syncing entirely, overriding
+    /// `--catalog-sqlite-synchronous`, which speeds up workloads that create
+    /// many catalog objects. It is unsafe: recent catalog changes can be lost
+    /// if the machine crashes. Use it only for throwaway test environments.
+    #[clap(
+        long,
+        env = "MZ_CATALOG_DURABILITY",
+        value_name = "MODE",
+        possible_values = &["none", "fsync"],
+        default_value = "fsync"
+    )]
+    catalog_durability: CatalogDurability,
+    /// How many user IDs to allocate from the catalog at once.
+    ///
+    /// Larger blocks make creating many objects cheaper, at the cost of
+    /// leaving gaps in the sequence of user IDs after a restart.
+    #[clap(
+        long,
+        hide = true,
+        env = "MZ_CATALOG_GID_BLOCK_SIZE",
+        value_name = "N",
+        default_value = "1"
+    )]
+    catalog_gid_block_size: u64,
+    /// Encrypt the catalog in the data directory with this key.
+    ///
+    /// The catalog is encrypted with SQLCipher. An unencrypted catalog, and
+    /// the snapshots of it in the data directory, are encrypted when
+    /// materialized is first started with a key, after which the same key must
+    /// always be specified.
+    #[clap(
+        long,
+        env = "MZ_CATALOG_ENCRYPTION_KEY",
+        value_name = "KEY",
+        hide_env_values = true,
+        conflicts_with = "catalog-postgres-url"
+    )]
+    catalog_encryption_key: Option<String>,
+    /// Replace the catalog in the data directory with a backup before
+    /// starting.
+    ///
+    /// The backup must have been written by mz_internal.backup_catalog. A
+    /// data directory is restored only once: later starts with this option
+    /// fail until the catalog.restored file in the data directory is removed.
+    #[clap(long, value_name = "FILE", conflicts_with = "catalog-postgres-url")]
+    restore_catalog: Option<PathBuf>,
+    /// Apply any unapplied catalog migrations to a copy of the catalog, report
+    /// whether they succeeded, and exit without starting the server.
+    ///
+    /// The catalog in the data directory is not modified.
+    #[clap(long, conflicts_with_all = &["catalog-postgres-url", "restore-catalog"])]
+    check_catalog_migrations: bool,
+    /// Check the references between objects in a copy of the catalog, report
+    /// any that are dangling or conflicting, and exit without starting the
+    /// server.
+    ///
+    /// The catalog in the data directory is not modified.
+    #[clap(
+        long,
+        conflicts_with_all = &["catalog-postgres-url", "restore-catalog", "check-catalog-migrations"]
+    )]
+    check_catalog: bool,
+    /// Print a JSON description of the objects in a copy of the catalog and
+    /// exit without starting the server.
+    ///
+    /// The description includes the contents of secrets, which are read from
+    /// the configured secrets storage. The catalog in the data directory is
+    /// not modified.
+    #[clap(
+        long,
+        conflicts_with_all = &["catalog-postgres-url", "restore-catalog", "check-catalog-migrations", "check-catalog"]
+    )]
+    dump_catalog: bool,
+    /// Replay a JSON description of a catalog, as printed by --dump-catalog,
+    /// on the catalog before starting.
+    ///
+    /// The catalog must not yet contain any sources, views, or other items.
+    #[clap(long, value_name = "FILE", conflicts_with = "restore-catalog")]
+    import_catalog: Option<PathBuf>,
+    /// How long the definitions of dropped objects are kept, so that the
+    /// objects can be restored with `UNDROP`.
+    #[clap(long, env = "MZ_DROPPED_OBJECT_RETENTION", parse(try_from_str = mz_repr::util::parse_duration), value_name = "DURATION", default_value = "24h")]
+    dropped_object_retention: Duration,
+    /// How far the subscriber of a replication slot may fall behind before the
+    /// slot is invalidated, releasing the inputs of the slot's publications
+    /// to compact, or `off` to never invalidate slots.
+    #[clap(long, env = "MZ_MAX_REPLICATION_SLOT_LAG", parse(try_from_str = parse_optional_duration), value_name = "DURATION", default_value = "1h")]
+    max_replication_slot_lag: OptionalDuration,
 
     // === AWS options. ===
     /// An external ID to be supplied to all AWS AssumeRole operations.
@@ -469,39 +950,178 @@ pub struct Args {
     tokio_console: bool,
 }
 
-#[derive(ArgEnum, Debug, Clone)]
-enum Orchestrator {
-    Kubernetes,
-    Process,
+/// Builds the configuration of an orchestrator backend from the command-line
+/// arguments.
+type OrchestratorBuilder = fn(&Args) -> Result<OrchestratorBackend, anyhow::Error>;
+
+/// The orchestrator backends that may be selected with `--orchestrator`, by
+/// name.
+const ORCHESTRATORS: &[(&str, OrchestratorBuilder)] = &[
+    ("kubernetes", kubernetes_orchestrator),
+    ("process", process_orchestrator),
+    ("systemd", systemd_orchestrator),
+];
+
+fn kubernetes_orchestrator(args: &Args) -> Result<OrchestratorBackend, anyhow::Error> {
+    Ok(OrchestratorBackend::Kubernetes(
+        KubernetesOrchestratorConfig {
+            context: args.kubernetes_context.clone(),
+            service_labels: args
+                .orchestrator_service_label
+                .iter()
+                .map(|l| (l.key.clone(), l.value.clone()))
+                .collect(),
+            storage_class: args.kubernetes_storage_class.clone(),
+        },
+    ))
+}
+
+fn process_orchestrator(args: &Args) -> Result<OrchestratorBackend, anyhow::Error> {
+    Ok(OrchestratorBackend::Process(ProcessOrchestratorConfig {
+        launcher: match &args.process_orchestrator_container_runtime {
+            Some(runtime) => Launcher::Container {
+                runtime: runtime.clone(),
+            },
+            None => Launcher::Binary {
+                image_dir: image_dir()?,
+            },
+        },
+        data_dir: args.data_directory.clone(),
+        port_range: ORCHESTRATOR_PORT_RANGE,
+        listen_addr: args.process_orchestrator_listen_addr,
+        hostname: args.process_orchestrator_hostname.clone(),
+    }))
 }
 
-#[derive(ArgEnum, Debug, Clone)]
+fn systemd_orchestrator(args: &Args) -> Result<OrchestratorBackend, anyhow::Error> {
+    Ok(OrchestratorBackend::Systemd(SystemdOrchestratorConfig {
+        image_dir: image_dir()?,
+        data_dir: args.data_directory.clone(),
+        port_range: ORCHESTRATOR_PORT_RANGE,
+        listen_addr: args.process_orchestrator_listen_addr,
+        hostname: args.process_orchestrator_hostname.clone(),
+        user: args.systemd_orchestrator_user,
+        unit_prefix: args.systemd_orchestrator_unit_prefix.clone(),
+    }))
+}
+
+/// The range of ports that orchestrators on the local machine allocate to
+/// services.
+///
+/// Chosen arbitrarily to be a relatively unused port range. Could be made
+/// configurable via CLI flags if necessary.
+const ORCHESTRATOR_PORT_RANGE: RangeInclusive<i32> = 2100..=2200;
+
+/// Returns the directory in which orchestrators on the local machine look for
+/// the binaries named by images.
+///
+/// This is the directory containing the running binary. When running via
+/// `cargo run`, this means that debug binaries look for other debug binaries
+/// and release binaries look for other release binaries.
+fn image_dir() -> Result<PathBuf, anyhow::Error> {
+    Ok(env::current_exe()?.parent().unwrap().to_path_buf())
+}
+
+#[derive(ArgEnum, Debug, Clone, PartialEq)]
 enum SecretsController {
     LocalFileSystem,
     Kubernetes,
+    Vault,
+    AwsSecretsManager,
+    AwsParameterStore,
 }
 
 #[derive(Debug)]
-struct OrchestratorLabel {
+struct KeyValueArg {
     key: String,
     value: String,
 }
 
-impl FromStr for OrchestratorLabel {
+impl FromStr for KeyValueArg {
     type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<OrchestratorLabel, anyhow::Error> {
+    fn from_str(s: &str) -> Result<KeyValueArg, anyhow::Error> {
         let mut parts = s.splitn(2, '=');
         let key = parts.next().expect("always one part");
         let value = parts
             .next()
             .ok_or_else(|| anyhow!("must have format KEY=VALUE"))?;
-        Ok(OrchestratorLabel {
+        Ok(KeyValueArg {
             key: key.into(),
             value: value.into(),
         })
     }
 }
 
+/// The resources allocated to each size of cluster, as specified with
+/// `--orchestrator-cluster-replica-sizes`.
+#[derive(Debug, Clone)]
+struct ClusterReplicaSizeMap {
+    /// The specification, as given.
+    raw: String,
+    sizes: HashMap<String, ClusterReplicaSize>,
+}
+
+impl FromStr for ClusterReplicaSizeMap {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<ClusterReplicaSizeMap, anyhow::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Size {
+            cpu_limit: Option<f64>,
+            memory_limit: Option<usize>,
+        }
+
+        let specs: HashMap<String, Size> = serde_json::from_str(s)?;
+        let mut sizes = HashMap::new();
+        for (name, spec) in specs {
+            let cpu_limit = match spec.cpu_limit {
+                None => None,
+                Some(cpus) if cpus > 0.0 => {
+                    let millicpus = (cpus * 1000.0).round() as usize;
+                    Some(CpuLimit::from_millicpus(millicpus))
+                }
+                Some(_) => bail!("cpu_limit of size {} must be positive", name.quoted()),
+            };
+            let memory_limit = spec.memory_limit.map(MemoryLimit::from_bytes);
+            sizes.insert(
+                name,
+                ClusterReplicaSize {
+                    cpu_limit,
+                    memory_limit,
+                },
+            );
+        }
+        Ok(ClusterReplicaSizeMap {
+            raw: s.into(),
+            sizes,
+        })
+    }
+}
+
+/// A peek concurrency limit, optionally scoped to a single cluster.
+#[derive(Debug)]
+struct PeekConcurrencyLimitArg {
+    cluster: Option<String>,
+    limit: usize,
+}
+
+impl FromStr for PeekConcurrencyLimitArg {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<PeekConcurrencyLimitArg, anyhow::Error> {
+        let (cluster, limit) = match s.rsplit_once('=') {
+            Some((cluster, limit)) => (Some(cluster.into()), limit),
+            None => (None, s),
+        };
+        let limit = limit
+            .parse()
+            .map_err(|_| anyhow!("must have format [CLUSTER=]N"))?;
+        if limit == 0 {
+            bail!("must be greater than zero");
+        }
+        Ok(PeekConcurrencyLimitArg { cluster, limit })
+    }
+}
+
 /// This type is a hack to allow a dynamic default for the `--workers` argument,
 /// which depends on the number of available CPUs. Ideally clap would
 /// expose a `default_fn` rather than accepting only string literals.
@@ -537,13 +1157,26 @@ impl fmt::Display for WorkerCount {
 }
 
 fn main() {
-    if let Err(err) = run(Args::parse()) {
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(err) => match err.downcast::<clap::Error>() {
+            Ok(err) => err.exit(),
+            Err(err) => {
+                eprintln!("materialized: {:#}", err);
+                process::exit(1);
+            }
+        },
+    };
+    if let Err(err) = run(config) {
         eprintln!("materialized: {:#}", err);
         process::exit(1);
     }
 }
 
-fn run(args: Args) -> Result<(), anyhow::Error> {
+fn run(config: LoadedConfig) -> Result<(), anyhow::Error> {
+    let system_config = reload::system_config(&config);
+    let args = config.args;
+
     // Configure signal handling as soon as possible. We want signals to be
     // handled to our liking ASAP.
     if !args.no_sigbus_sigsegv_backtraces {
@@ -576,9 +1209,20 @@ fn run(args: Args) -> Result<(), anyhow::Error> {
     // Avoid adding code above this point, because panics in that code won't get
     // handled by the custom panic handler.
     let metrics_registry = MetricsRegistry::new();
-    let mut tracing_stream = runtime.block_on(tracing::configure(&args, &metrics_registry))?;
+    let (mut tracing_stream, log_filter) =
+        runtime.block_on(tracing::configure(&args, &metrics_registry))?;
     panic::set_hook(Box::new(handle_panic));
 
+    // Reload the configuration on SIGHUP. See the `reload` module. Drain the
+    // server and exit on SIGTERM.
+    let (sighup, mut sigterm) = {
+        let _guard = runtime.enter();
+        (
+            signal(SignalKind::hangup()).context("installing SIGHUP handler")?,
+            signal(SignalKind::terminate()).context("installing SIGTERM handler")?,
+        )
+    };
+
     // Initialize fail crate for failpoint support
     let _failpoint_scenario = FailScenario::setup();
 
@@ -604,6 +1248,7 @@ fn run(args: Args) -> Result<(), anyhow::Error> {
 
     // Configure Timely and Differential workers.
     let log_logging = args.debug_introspection;
+    let retention = args.introspection_retention;
     let retain_readings_for = args.retain_prometheus_metrics;
     let metrics_scraping_interval = args.metrics_scraping_interval;
     let logging = args
@@ -611,6 +1256,7 @@ fn run(args: Args) -> Result<(), anyhow::Error> {
         .map(|granularity| mz_coord::LoggingConfig {
             granularity,
             log_logging,
+            retention,
             retain_readings_for,
             metrics_scraping_interval,
         });
@@ -620,38 +1266,21 @@ fn run(args: Args) -> Result<(), anyhow::Error> {
         );
     }
 
-    // Configure connections.
-    let tls = if args.tls_mode == "disable" {
-        if args.tls_ca.is_some() {
-            bail!("cannot specify --tls-mode=disable and --tls-ca simultaneously");
-        }
-        if args.tls_cert.is_some() {
-            bail!("cannot specify --tls-mode=disable and --tls-cert simultaneously");
-        }
-        if args.tls_key.is_some() {
-            bail!("cannot specify --tls-mode=disable and --tls-key simultaneously");
+    // Build the orchestrator backend's configuration before any arguments are
+    // moved out of `args`, as the backend's builder borrows all of them.
+    let orchestrator_backend = match args.orchestrator.as_deref() {
+        None => None,
+        Some(name) => {
+            let (_, builder) = ORCHESTRATORS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .expect("clap enforced");
+            Some(builder(&args)?)
         }
-        None
-    } else {
-        let mode = match args.tls_mode.as_str() {
-            "require" => {
-                if args.tls_ca.is_some() {
-                    bail!("cannot specify --tls-mode=require and --tls-ca simultaneously");
-                }
-                TlsMode::Require
-            }
-            "verify-ca" => TlsMode::VerifyCa {
-                ca: args.tls_ca.unwrap(),
-            },
-            "verify-full" => TlsMode::VerifyFull {
-                ca: args.tls_ca.unwrap(),
-            },
-            _ => unreachable!(),
-        };
-        let cert = args.tls_cert.unwrap();
-        let key = args.tls_key.unwrap();
-        Some(TlsConfig { mode, cert, key })
     };
+
+    // Configure connections.
+    let tls = tls_config(&args)?;
     let frontegg = args
         .frontegg_tenant
         .map(|tenant_id| {
@@ -664,60 +1293,178 @@ fn run(args: Args) -> Result<(), anyhow::Error> {
             })
         })
         .transpose()?;
+    let authenticator: Arc<dyn Authenticator> = match args.authenticator.as_str() {
+        "trust" => Arc::new(TrustAuthenticator),
+        "password" => Arc::new(PasswordAuthenticator::load(
+            args.password_file.as_ref().expect("clap enforced"),
+        )?),
+        "catalog" => Arc::new(CatalogAuthenticator),
+        "frontegg" => match frontegg {
+            Some(frontegg) => Arc::new(FronteggAuthenticator::new(frontegg)),
+            None => bail!("--authenticator=frontegg requires --frontegg-tenant"),
+        },
+        _ => unreachable!("clap enforced"),
+    };
 
     // Configure orchestrator.
-    let orchestrator = match args.orchestrator {
+    let orchestrator = match orchestrator_backend {
         None => {
             if !args.orchestrator_service_label.is_empty() {
                 bail!("--orchestrator-label is only valid with --orchestrator");
             }
+            if !args.orchestrator_compute_node_selector.is_empty() {
+                bail!("--orchestrator-compute-node-selector is only valid with --orchestrator");
+            }
+            if !args.orchestrator_compute_toleration.is_empty() {
+                bail!("--orchestrator-compute-toleration is only valid with --orchestrator");
+            }
+            if args.orchestrator_disk_limit.is_some() {
+                bail!("--orchestrator-disk-limit is only valid with --orchestrator");
+            }
+            if args.orchestrator_cluster_replica_sizes.is_some() {
+                bail!("--orchestrator-cluster-replica-sizes is only valid with --orchestrator");
+            }
             None
         }
         Some(backend) => Some(OrchestratorConfig {
-            backend: match backend {
-                Orchestrator::Kubernetes => {
-                    OrchestratorBackend::Kubernetes(KubernetesOrchestratorConfig {
-                        context: args.kubernetes_context.clone(),
-                        service_labels: args
-                            .orchestrator_service_label
-                            .into_iter()
-                            .map(|l| (l.key, l.value))
-                            .collect(),
-                    })
-                }
-                Orchestrator::Process => {
-                    OrchestratorBackend::Process(ProcessOrchestratorConfig {
-                        // Look for binaries in the same directory as the
-                        // running binary. When running via `cargo run`, this
-                        // means that debug binaries look for other debug
-                        // binaries and release binaries look for other release
-                        // binaries.
-                        image_dir: env::current_exe()?.parent().unwrap().to_path_buf(),
-                        // Chosen arbitrarily to be a relatively unused port
-                        // range. Could be made configurable via CLI flags if
-                        // necessary.
-                        port_range: 2100..=2200,
-                    })
-                }
-            },
+            backend,
             dataflowd_image: args.dataflowd_image.expect("clap enforced"),
+            compute_node_selector: args
+                .orchestrator_compute_node_selector
+                .into_iter()
+                .map(|l| (l.key, l.value))
+                .collect(),
+            compute_tolerations: args.orchestrator_compute_toleration,
+            disk_limit: args.orchestrator_disk_limit.map(DiskLimit::from_bytes),
+            cluster_replica_sizes: args
+                .orchestrator_cluster_replica_sizes
+                .map(|sizes| sizes.sizes)
+                .unwrap_or_default(),
         }),
     };
 
     // Configure secrets controller.
-    let secrets_controller = match args.secrets_controller {
-        None => None,
-        Some(SecretsController::LocalFileSystem) => Some(SecretsControllerConfig::LocalFileSystem),
-        Some(SecretsController::Kubernetes) => Some(SecretsControllerConfig::Kubernetes {
-            context: args.kubernetes_context,
-        }),
+    let uses_local_file_system = matches!(
+        args.secrets_controller,
+        None | Some(SecretsController::LocalFileSystem)
+    ) || matches!(
+        args.secrets_migrate_from,
+        Some(SecretsController::LocalFileSystem)
+    );
+    if (args.secrets_encryption_key.is_some() || args.secrets_encryption_key_kms.is_some())
+        && !uses_local_file_system
+    {
+        bail!(
+            "--secrets-encryption-key and --secrets-encryption-key-kms are only supported by \
+             the local filesystem secrets controller"
+        );
+    }
+    if (args.secrets_uid.is_some() || args.secrets_gid.is_some()) && !uses_local_file_system {
+        bail!(
+            "--secrets-uid and --secrets-gid are only supported by the local filesystem secrets controller"
+        );
+    }
+    if args.secrets_max_versions == 0 {
+        bail!("--secrets-max-versions must be at least 1");
+    }
+    let secrets_encryption_key = match &args.secrets_encryption_key_kms {
+        None => args.secrets_encryption_key.clone(),
+        Some(ciphertext) => {
+            let ciphertext = base64::decode(ciphertext.trim())
+                .context("--secrets-encryption-key-kms must be base64-encoded")?;
+            let key = runtime.block_on(mz_secrets_aws::decrypt_data_key(ciphertext))?;
+            Some(EncryptionKey::from_bytes(&key)?)
+        }
+    };
+    let secrets_controller = secrets_controller_config(
+        args.secrets_controller.as_ref(),
+        secrets_encryption_key.as_ref(),
+        &args,
+    )?;
+    let secrets_controller = match &args.secrets_migrate_from {
+        None => Some(secrets_controller),
+        Some(from) => {
+            if *from
+                == args
+                    .secrets_controller
+                    .clone()
+                    .unwrap_or(SecretsController::LocalFileSystem)
+            {
+                bail!("--secrets-migrate-from must name a different secrets controller");
+            }
+            if let SecretsControllerConfig::Kubernetes { .. } = secrets_controller {
+                bail!("secrets cannot be migrated to the Kubernetes secrets controller");
+            }
+            let from =
+                secrets_controller_config(Some(from), secrets_encryption_key.as_ref(), &args)?;
+            Some(SecretsControllerConfig::Migrating {
+                from: Box::new(from),
+                to: Box::new(secrets_controller),
+            })
+        }
     };
 
+    if args.config_check {
+        match &args.config_file {
+            None => println!("configuration is valid"),
+            Some(path) => println!("{}: configuration is valid", path.display()),
+        }
+        return Ok(());
+    }
+
     // Configure storage.
     let data_directory = args.data_directory;
     fs::create_dir_all(&data_directory)
         .with_context(|| format!("creating data directory: {}", data_directory.display()))?;
 
+    if args.check_catalog_migrations {
+        runtime
+            .block_on(mz_coord::catalog::Catalog::check_migrations(
+                &data_directory,
+                args.catalog_encryption_key.as_deref(),
+                &materialized::BUILD_INFO,
+                SYSTEM_TIME.clone(),
+            ))
+            .context("checking catalog migrations")?;
+        println!(
+            "{}: catalog migrations to {} succeeded",
+            data_directory.display(),
+            materialized::BUILD_INFO.human_version()
+        );
+        return Ok(());
+    }
+
+    if args.check_catalog {
+        let inconsistencies = mz_coord::catalog::Catalog::check_stored_consistency(
+            &data_directory,
+            args.catalog_encryption_key.as_deref(),
+        )
+        .context("checking catalog")?;
+        if inconsistencies.is_empty() {
+            println!("{}: catalog is consistent", data_directory.display());
+            return Ok(());
+        }
+        for inconsistency in &inconsistencies {
+            println!("{}: {}", data_directory.display(), inconsistency);
+        }
+        bail!("catalog has {} inconsistencies", inconsistencies.len());
+    }
+
+    if args.dump_catalog {
+        let dump = runtime
+            .block_on(materialized::dump_catalog(
+                &data_directory,
+                args.catalog_encryption_key.as_deref(),
+                secrets_controller,
+            ))
+            .context("dumping catalog")?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&dump).expect("catalog dump serialization cannot fail")
+        );
+        return Ok(());
+    }
+
     let storage = match (args.storage_compute_addr, args.storage_controller_addr) {
         (None, None) => StorageConfig::Local,
         (Some(compute_addr), Some(controller_addr)) => StorageConfig::Remote(RemoteStorageConfig {
@@ -906,20 +1653,47 @@ max log level: {max_log_level}",
         }
     };
 
-    let server = runtime.block_on(materialized::serve(materialized::Config {
+    let mut peek_concurrency_limits = PeekConcurrencyLimits::default();
+    for PeekConcurrencyLimitArg { cluster, limit } in args.peek_concurrency_limit {
+        match cluster {
+            Some(cluster) => {
+                peek_concurrency_limits.clusters.insert(cluster, limit);
+            }
+            None => peek_concurrency_limits.default = Some(limit),
+        }
+    }
+
+    let (config_reload_tx, config_reload_rx) = mpsc::unbounded_channel();
+    let mut server = runtime.block_on(materialized::serve(materialized::Config {
         workers: args.workers.0,
         timely_worker,
         logging,
         logical_compaction_window: args.logical_compaction_window,
         timestamp_frequency: args.timestamp_frequency,
+        peek_concurrency_limits,
+        peek_queue_timeout: args.peek_queue_timeout,
+        result_sort_memory_limit: args.result_sort_memory_limit,
+        statistics_interval: args.statistics_interval,
         listen_addr: args.listen_addr,
         third_party_metrics_listen_addr: args.third_party_metrics_listen_addr,
         tls,
-        frontegg,
+        authenticator,
         cors_allowed_origins: args.cors_allowed_origin,
         data_directory,
+        catalog_postgres_url: args.catalog_postgres_url,
+        catalog_stash: args.catalog_stash,
+        catalog_sqlite_synchronous: args.catalog_sqlite_synchronous,
+        catalog_durability: args.catalog_durability,
+        catalog_gid_block_size: args.catalog_gid_block_size,
+        catalog_encryption_key: args.catalog_encryption_key,
+        restore_catalog: args.restore_catalog,
+        import_catalog: args.import_catalog,
+        dropped_object_retention: args.dropped_object_retention,
+        max_replication_slot_lag: args.max_replication_slot_lag,
         orchestrator,
         secrets_controller,
+        recreate_missing_secrets: args.recreate_missing_secrets,
+        max_secret_size: args.max_secret_size,
         storage,
         experimental_mode: args.experimental,
         disable_user_indexes: args.disable_user_indexes,
@@ -935,7 +1709,10 @@ max log level: {max_log_level}",
         metrics_registry,
         persist: persist_config,
         now: SYSTEM_TIME.clone(),
+        config_reload_tx: Some(config_reload_tx),
+        max_connections: args.max_connections,
     }))?;
+    server.report_system_config(system_config);
 
     eprintln!(
         "=======================================================================
@@ -995,10 +1772,127 @@ For more details, see https://materialize.com/docs/cli#experimental-mode
         server.local_addr(),
     );
 
-    // Block forever.
-    loop {
-        thread::park();
+    // Serve configuration reloads until SIGTERM arrives, then drain the
+    // server.
+    runtime.block_on(async {
+        tokio::select! {
+            _ = reload::serve(&server, &log_filter, sighup, config_reload_rx) => {
+                unreachable!("configuration reloading stopped unexpectedly")
+            }
+            _ = sigterm.recv() => ::tracing::info!("received SIGTERM; draining server"),
+        }
+        server.drain(args.drain_grace_period).await;
+    });
+
+    // Dropping the server waits for the coordinator to finish shutting down.
+    drop(server);
+    ::tracing::info!("server shut down");
+    Ok(())
+}
+
+/// Constructs the configuration of the secrets controller of kind `kind`
+/// described by the `--secrets-*` options.
+///
+/// The local filesystem secrets controller encrypts secrets with
+/// `encryption_key`, if present.
+fn secrets_controller_config(
+    kind: Option<&SecretsController>,
+    encryption_key: Option<&EncryptionKey>,
+    args: &Args,
+) -> Result<SecretsControllerConfig, anyhow::Error> {
+    Ok(match kind {
+        None | Some(SecretsController::LocalFileSystem) => {
+            SecretsControllerConfig::LocalFileSystem {
+                encryption_key: encryption_key.cloned(),
+                max_versions: args.secrets_max_versions,
+                uid: args.secrets_uid,
+                gid: args.secrets_gid,
+            }
+        }
+        Some(SecretsController::Kubernetes) => SecretsControllerConfig::Kubernetes {
+            context: args.kubernetes_context.clone(),
+        },
+        Some(SecretsController::Vault) => {
+            let auth = match (&args.vault_kubernetes_role, &args.vault_token) {
+                (Some(role), _) => VaultAuth::Kubernetes {
+                    mount: args.vault_kubernetes_auth_mount.clone(),
+                    role: role.clone(),
+                    jwt_path: "/var/run/secrets/kubernetes.io/serviceaccount/token".into(),
+                },
+                (None, Some(token)) => VaultAuth::Token(token.clone()),
+                (None, None) => bail!(
+                    "the vault secrets controller requires --vault-token or --vault-kubernetes-role"
+                ),
+            };
+            SecretsControllerConfig::Vault(VaultConfig {
+                address: args
+                    .vault_address
+                    .clone()
+                    .expect("required when vault is a secrets controller"),
+                mount: args.vault_mount.clone(),
+                path_prefix: args.vault_path_prefix.clone(),
+                auth,
+            })
+        }
+        Some(SecretsController::AwsSecretsManager) => {
+            SecretsControllerConfig::AwsSecretsManager(aws_secrets_config(args, "materialize/"))
+        }
+        Some(SecretsController::AwsParameterStore) => {
+            SecretsControllerConfig::AwsParameterStore(aws_secrets_config(args, "/materialize/"))
+        }
+    })
+}
+
+/// Returns the configuration of an AWS secrets controller, whose secrets'
+/// names begin with `default_prefix` unless `--aws-secrets-prefix` is given.
+fn aws_secrets_config(args: &Args, default_prefix: &str) -> AwsSecretsConfig {
+    AwsSecretsConfig {
+        prefix: args
+            .aws_secrets_prefix
+            .clone()
+            .unwrap_or_else(|| default_prefix.into()),
+        kms_key_id: args.aws_secrets_kms_key_id.clone(),
+        tags: args
+            .aws_secrets_tag
+            .iter()
+            .map(|tag| (tag.key.clone(), tag.value.clone()))
+            .collect(),
+        role_arn: args.aws_secrets_role_arn.clone(),
+        endpoint: args.aws_secrets_endpoint.clone(),
+    }
+}
+
+fn tls_config(args: &Args) -> Result<Option<TlsConfig>, anyhow::Error> {
+    if args.tls_mode == "disable" {
+        if args.tls_ca.is_some() {
+            bail!("cannot specify --tls-mode=disable and --tls-ca simultaneously");
+        }
+        if args.tls_cert.is_some() {
+            bail!("cannot specify --tls-mode=disable and --tls-cert simultaneously");
+        }
+        if args.tls_key.is_some() {
+            bail!("cannot specify --tls-mode=disable and --tls-key simultaneously");
+        }
+        return Ok(None);
     }
+    let mode = match args.tls_mode.as_str() {
+        "require" => {
+            if args.tls_ca.is_some() {
+                bail!("cannot specify --tls-mode=require and --tls-ca simultaneously");
+            }
+            TlsMode::Require
+        }
+        "verify-ca" => TlsMode::VerifyCa {
+            ca: args.tls_ca.clone().unwrap(),
+        },
+        "verify-full" => TlsMode::VerifyFull {
+            ca: args.tls_ca.clone().unwrap(),
+        },
+        _ => unreachable!(),
+    };
+    let cert = args.tls_cert.clone().unwrap();
+    let key = args.tls_key.clone().unwrap();
+    Ok(Some(TlsConfig { mode, cert, key }))
 }
 
 lazy_static! {
@@ -1061,3 +1955,36 @@ fn build_info() -> Vec<String> {
         format!("librdkafka v{}", rdkafka_version.to_string_lossy()),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cluster_replica_sizes() {
+        let sizes: ClusterReplicaSizeMap =
+            r#"{"small": {"cpu_limit": 0.5, "memory_limit": 1024}, "unlimited": {}}"#
+                .parse()
+                .unwrap();
+        assert_eq!(
+            sizes.sizes["small"],
+            ClusterReplicaSize {
+                cpu_limit: Some(CpuLimit::from_millicpus(500)),
+                memory_limit: Some(MemoryLimit::from_bytes(1024)),
+            }
+        );
+        assert_eq!(sizes.sizes["unlimited"], ClusterReplicaSize::default());
+        assert!(r#"{"small": {"cpu_limit": 0}}"#.parse::<ClusterReplicaSizeMap>().is_err());
+        assert!(r#"{"small": {"cpus": 1}}"#.parse::<ClusterReplicaSizeMap>().is_err());
+    }
+
+    #[test]
+    fn test_parse_peek_concurrency_limit() {
+        let arg: PeekConcurrencyLimitArg = "4".parse().unwrap();
+        assert_eq!((arg.cluster, arg.limit), (None, 4));
+        let arg: PeekConcurrencyLimitArg = "default=2".parse().unwrap();
+        assert_eq!((arg.cluster.as_deref(), arg.limit), (Some("default"), 2));
+        assert!("default=0".parse::<PeekConcurrencyLimitArg>().is_err());
+        assert!("default=".parse::<PeekConcurrencyLimitArg>().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Runtime configuration reloading.
+//!
+//! When the process receives `SIGHUP`, or when a user calls `pg_reload_conf()`,
+//! the configuration is reread from the command line, the configuration file,
+//! and the environment, and the options that can change without a restart are
+//! applied. Those options are:
+//!
+//!   * `--log-filter`
+//!   * `--tls-ca`, `--tls-cert`, and `--tls-key`, which are reread from disk
+//!     even if their paths have not changed
+//!   * `--max-connections`, which does not affect connections that are
+//!     already open
+//!   * `--orchestrator-cluster-replica-sizes`, which applies to cluster
+//!     replicas launched after the reload
+//!
+//! Changes to all other options are ignored until the next restart. The active
+//! values of the reloadable options are reported in `mz_system_config`.
+
+use std::path::PathBuf;
+
+use ::tracing::{error, info};
+use tokio::signal::unix::Signal;
+use tokio::sync::mpsc;
+
+use materialized::Server;
+use mz_coord::{RuntimeConfig, SystemConfigEntry};
+
+use crate::config::{self, LoadedConfig};
+use crate::tracing::{parse_log_filter, ReloadableTargets};
+use crate::Args;
+
+/// Describes the active values of the reloadable options in `config`.
+pub fn system_config(config: &LoadedConfig) -> Vec<SystemConfigEntry> {
+    let args = &config.args;
+    let path = |path: &Option<PathBuf>| path.as_ref().map(|path| path.display().to_string());
+    [
+        ("log-filter", Some(args.log_filter.clone())),
+        ("tls-ca", path(&args.tls_ca)),
+        ("tls-cert", path(&args.tls_cert)),
+        ("tls-key", path(&args.tls_key)),
+        (
+            "max-connections",
+            args.max_connections.map(|limit| limit.to_string()),
+        ),
+        (
+            "orchestrator-cluster-replica-sizes",
+            args.orchestrator_cluster_replica_sizes
+                .as_ref()
+                .map(|sizes| sizes.raw.clone()),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| SystemConfigEntry {
+        name: name.into(),
+        value,
+        source: config.source(name).into(),
+    })
+    .collect()
+}
+
+/// Extracts the coordinator's reloadable options from `args`.
+pub fn runtime_config(args: &Args) -> RuntimeConfig {
+    RuntimeConfig {
+        max_connections: args.max_connections,
+        cluster_replica_sizes: args
+            .orchestrator_cluster_replica_sizes
+            .as_ref()
+            .map(|sizes| sizes.sizes.clone())
+            .unwrap_or_default(),
+    }
+}
+
+/// Reloads the configuration whenever `sighup` fires or a request arrives on
+/// `reload_rx`. Never returns.
+pub async fn serve(
+    server: &Server,
+    log_filter: &ReloadableTargets,
+    mut sighup: Signal,
+    mut reload_rx: mpsc::UnboundedReceiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = sighup.recv() => info!("received SIGHUP; reloading configuration"),
+            Some(()) = reload_rx.recv() => {
+                info!("pg_reload_conf() called; reloading configuration")
+            }
+        }
+        if let Err(e) = reload(server, log_filter) {
+            error!("reloading configuration: {:#}", e);
+        }
+    }
+}
+
+/// Rereads the configuration and applies the reloadable options.
+///
+/// Either all reloadable options are applied, or, if an error is returned,
+/// none are.
+fn reload(server: &Server, log_filter: &ReloadableTargets) -> Result<(), anyhow::Error> {
+    let config = config::load()?;
+    let targets = parse_log_filter(&config.args.log_filter)?;
+    server.reload_tls(crate::tls_config(&config.args)?.as_ref())?;
+    log_filter.reload(targets);
+    server.reload_runtime_config(runtime_config(&config.args));
+    server.report_system_config(system_config(&config));
+    info!("configuration reloaded");
+    Ok(())
+}
@@ -216,13 +216,10 @@ pub fn enable_termination_signal_cleanup() -> Result<(), anyhow::Error> {
         signal::SigSet::empty(),
     );
 
-    for signum in &[
-        signal::SIGHUP,
-        signal::SIGINT,
-        signal::SIGALRM,
-        signal::SIGTERM,
-        signal::SIGUSR1,
-    ] {
+    // NOTE: SIGHUP is not a termination signal. It triggers a configuration
+    // reload; see the `reload` module. SIGTERM drains the server before
+    // exiting, which runs the usual exit-time cleanup; see `main`.
+    for signum in &[signal::SIGINT, signal::SIGALRM, signal::SIGUSR1] {
         unsafe { signal::sigaction(*signum, &action) }
             .with_context(|| format!("failed to install handler for {}", signum))?;
     }
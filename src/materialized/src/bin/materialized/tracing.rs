@@ -12,6 +12,7 @@ use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use anyhow::Context as _;
@@ -23,10 +24,11 @@ use opentelemetry::KeyValue;
 use prometheus::IntCounterVec;
 use tonic::metadata::{MetadataKey, MetadataMap};
 use tonic::transport::Endpoint;
-use tracing::{Event, Subscriber};
+use tracing::subscriber::Interest;
+use tracing::{Event, Metadata, Subscriber};
 use tracing_subscriber::filter::{LevelFilter, Targets};
 use tracing_subscriber::fmt;
-use tracing_subscriber::layer::{Context, Layer, Layered, SubscriberExt};
+use tracing_subscriber::layer::{Context, Filter, Layer, Layered, SubscriberExt};
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::util::SubscriberInitExt;
 
@@ -121,21 +123,18 @@ where
 }
 /// Configures tracing according to the provided command-line arguments.
 /// Returns a `Write` stream that represents the main place `tracing` will
-/// log to
+/// log to, and a handle to the log filter, which can be used to change the
+/// filter at runtime.
 pub async fn configure(
     args: &Args,
     metrics_registry: &MetricsRegistry,
-) -> Result<Box<dyn Write>, anyhow::Error> {
+) -> Result<(Box<dyn Write>, ReloadableTargets), anyhow::Error> {
     // NOTE: Try harder than usual to avoid panicking in this function. It runs
     // before our custom panic hook is installed (because the panic hook needs
     // tracing configured to execute), so a panic here will not direct the
     // user to file a bug report.
 
-    let filter = Targets::from_str(&args.log_filter)
-        .context("parsing --log-filter option")?
-        // Ensure panics are logged, even if the user has specified
-        // otherwise.
-        .with_target("panic", LevelFilter::ERROR);
+    let filter = ReloadableTargets::new(parse_log_filter(&args.log_filter)?);
 
     let log_message_counter: ThirdPartyMetric<IntCounterVec> = metrics_registry
         .register_third_party_visible(metric!(
@@ -154,7 +153,7 @@ pub async fn configure(
                     fmt::layer()
                         .with_writer(io::stderr)
                         .with_ansi(atty::is(atty::Stream::Stderr))
-                        .with_filter(filter),
+                        .with_filter(filter.clone()),
                 );
 
             #[cfg(feature = "tokio-console")]
@@ -207,7 +206,7 @@ pub async fn configure(
                         .with_writer(io::stderr)
                         .with_ansi(atty::is(atty::Stream::Stderr))
                         .with_filter(stderr_level)
-                        .with_filter(filter),
+                        .with_filter(filter.clone()),
                 );
 
             #[cfg(feature = "tokio-console")]
@@ -224,7 +223,52 @@ pub async fn configure(
         }
     };
 
-    Ok(stream)
+    Ok((stream, filter))
+}
+
+/// Parses the value of the `--log-filter` option.
+pub fn parse_log_filter(log_filter: &str) -> Result<Targets, anyhow::Error> {
+    let filter = Targets::from_str(log_filter)
+        .context("parsing --log-filter option")?
+        // Ensure panics are logged, even if the user has specified
+        // otherwise.
+        .with_target("panic", LevelFilter::ERROR);
+    Ok(filter)
+}
+
+/// A tracing [`Filter`] whose [`Targets`] can be replaced while the process
+/// is running.
+///
+/// Clones share the same underlying targets.
+#[derive(Debug, Clone)]
+pub struct ReloadableTargets(Arc<RwLock<Targets>>);
+
+impl ReloadableTargets {
+    fn new(targets: Targets) -> Self {
+        ReloadableTargets(Arc::new(RwLock::new(targets)))
+    }
+
+    /// Replaces the targets used by the filter.
+    pub fn reload(&self, targets: Targets) {
+        *self.0.write().expect("lock poisoned") = targets;
+        // Callsites cache whether they are enabled, so force them to consult
+        // the new targets.
+        tracing_core::callsite::rebuild_interest_cache();
+    }
+}
+
+impl<S> Filter<S> for ReloadableTargets {
+    fn enabled(&self, metadata: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        Filter::<S>::enabled(&*self.0.read().expect("lock poisoned"), metadata, cx)
+    }
+
+    fn callsite_enabled(&self, metadata: &'static Metadata<'static>) -> Interest {
+        Filter::<S>::callsite_enabled(&*self.0.read().expect("lock poisoned"), metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Filter::<S>::max_level_hint(&*self.0.read().expect("lock poisoned"))
+    }
 }
 
 /// A tracing [`Layer`] that allows hooking into the reporting/filtering chain
@@ -15,9 +15,9 @@
 
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
-use futures::future::TryFutureExt;
 use headers::authorization::{Authorization, Basic, Bearer};
 use headers::{HeaderMapExt, HeaderName};
 use http::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
@@ -32,14 +32,17 @@ use tower::ServiceBuilder;
 use tower_http::cors::{self, AnyOr, CorsLayer, Origin};
 use tracing::error;
 
+use mz_authenticator::scram::Verifier;
+use mz_authenticator::{Authenticator, Credentials};
 use mz_coord::session::Session;
-use mz_frontegg_auth::FronteggAuthentication;
 use mz_ore::netio::SniffedStream;
+use mz_pgwire::{DrainSignal, ReloadableSslContext};
 
 use crate::http::metrics::MetricsVariant;
 use crate::Metrics;
 
 mod catalog;
+mod cluster;
 mod memory;
 mod metrics;
 mod prof;
@@ -62,17 +65,18 @@ fn sniff_tls(buf: &[u8]) -> bool {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub tls: Option<TlsConfig>,
-    pub frontegg: Option<FronteggAuthentication>,
+    pub authenticator: Arc<dyn Authenticator>,
     pub coord_client: mz_coord::Client,
     pub metrics_registry: MetricsRegistry,
     pub global_metrics: Metrics,
     pub pgwire_metrics: mz_pgwire::Metrics,
     pub allowed_origins: Vec<HeaderValue>,
+    pub drain_signal: DrainSignal,
 }
 
 #[derive(Debug, Clone)]
 pub struct TlsConfig {
-    pub context: SslContext,
+    pub context: ReloadableSslContext,
     pub mode: TlsMode,
 }
 
@@ -85,12 +89,13 @@ pub enum TlsMode {
 #[derive(Debug)]
 pub struct Server {
     tls: Option<TlsConfig>,
-    frontegg: Option<FronteggAuthentication>,
+    authenticator: Arc<dyn Authenticator>,
     coord_client: mz_coord::Client,
     metrics_registry: MetricsRegistry,
     global_metrics: Metrics,
     pgwire_metrics: mz_pgwire::Metrics,
     allowed_origin: AnyOr<Origin>,
+    drain_signal: DrainSignal,
 }
 
 impl Server {
@@ -106,12 +111,13 @@ impl Server {
         };
         Server {
             tls: config.tls,
-            frontegg: config.frontegg,
+            authenticator: config.authenticator,
             coord_client: config.coord_client,
             metrics_registry: config.metrics_registry,
             global_metrics: config.global_metrics,
             pgwire_metrics: config.pgwire_metrics,
             allowed_origin,
+            drain_signal: config.drain_signal,
         }
     }
 
@@ -119,8 +125,8 @@ impl Server {
         self.tls.as_ref().map(|tls| tls.mode)
     }
 
-    fn tls_context(&self) -> Option<&SslContext> {
-        self.tls.as_ref().map(|tls| &tls.context)
+    fn tls_context(&self) -> Option<SslContext> {
+        self.tls.as_ref().map(|tls| tls.context.get())
     }
 
     pub fn match_handshake(&self, buf: &[u8]) -> bool {
@@ -139,9 +145,9 @@ impl Server {
     where
         A: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
     {
-        let conn = match (&self.tls_context(), sniff_tls(&conn.sniff_buffer())) {
+        let conn = match (self.tls_context(), sniff_tls(&conn.sniff_buffer())) {
             (Some(tls_context), true) => {
-                let mut ssl_stream = SslStream::new(Ssl::new(tls_context)?, conn)?;
+                let mut ssl_stream = SslStream::new(Ssl::new(&tls_context)?, conn)?;
                 if let Err(e) = Pin::new(&mut ssl_stream).accept().await {
                     let _ = ssl_stream.get_mut().shutdown().await;
                     return Err(e.into());
@@ -177,40 +183,48 @@ impl Server {
             let metrics_registry = self.metrics_registry.clone();
             let global_metrics = self.global_metrics.clone();
             let pgwire_metrics = self.pgwire_metrics.clone();
-            let frontegg = self.frontegg.clone();
+            let authenticator = Arc::clone(&self.authenticator);
             async move {
                 // There are three places a username may be specified:
                 // - certificate common name
                 // - HTTP Basic authentication
-                // - JWT email address
+                // - the authenticated identity, e.g. a JWT email address
                 // We verify that if any of these are present, they must match any other that
                 // is also present.
 
-                let user = if let Err(e) = cert_user {
-                    Err(e)
-                } else if let Some(frontegg) = &frontegg {
-                    // If we require mzcloud auth, fetch credentials from the http auth
-                    // header. Basic auth comes with a username/password, where the password
-                    // is the client+secret pair. Bearer auth is an existing JWT that must be
-                    // validated. In either case, if a username was specified in the client cert,
-                    // it must match that of the JWT.
-                    validate_http_frontegg_authentication(&req, &frontegg)
-                        .await
-                        .and_then(|email| {
-                            if let Ok(Some(cert_user)) = cert_user {
-                                if email != cert_user {
-                                    anyhow::bail!(
-                                        "JWT email does not match certificate common name"
-                                    );
+                let user = match cert_user {
+                    Err(e) => Err(e),
+                    Ok(cert_user) if authenticator.requires_credentials() => {
+                        // Fetch credentials from the HTTP authorization header.
+                        // Basic auth comes with a username and password, while
+                        // Bearer auth comes with a token, like a JWT, that
+                        // identifies the user itself. In either case, if a
+                        // username was specified in the client cert, it must
+                        // match that of the authenticated user.
+                        http_authenticate(&req, &*authenticator, &coord_client)
+                            .await
+                            .and_then(|user| {
+                                if let Some(cert_user) = cert_user {
+                                    if user != cert_user {
+                                        anyhow::bail!(
+                                            "authenticated user does not match certificate common name"
+                                        );
+                                    }
                                 }
-                            }
-                            Ok(email)
-                        })
-                        .map_err(|_e| "unauthorized")
-                } else {
-                    // If there was no mzcloud auth, we can use the cert's username if present,
-                    // otherwise the system user.
-                    cert_user.map(|cert_user| cert_user.unwrap_or_else(|| SYSTEM_USER.to_string()))
+                                Ok(user)
+                            })
+                            .map_err(|_e| "unauthorized")
+                    }
+                    Ok(cert_user) => {
+                        // If no credentials are required, we can use the cert's
+                        // username if present, otherwise the system user.
+                        let user = cert_user.unwrap_or_else(|| SYSTEM_USER.to_string());
+                        authenticator
+                            .authenticate(Some(&user), Credentials::None)
+                            .await
+                            .map(|authenticated| authenticated.user)
+                            .map_err(|_e| "unauthorized")
+                    }
                 };
 
                 let user = match user {
@@ -220,16 +234,18 @@ impl Server {
 
                 let coord_client = coord_client.new_conn()?;
                 let session = Session::new(coord_client.conn_id(), user);
-                let (mut coord_client, _) =
-                    match coord_client.startup(session, frontegg.is_some()).await {
-                        Ok(coord_client) => coord_client,
-                        Err(e) => {
-                            return Ok(util::error_response(
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                e.to_string(),
-                            ))
-                        }
-                    };
+                let (mut coord_client, _) = match coord_client
+                    .startup(session, authenticator.creates_users())
+                    .await
+                {
+                    Ok(coord_client) => coord_client,
+                    Err(e) => {
+                        return Ok(util::error_response(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            e.to_string(),
+                        ))
+                    }
+                };
 
                 match (req.method(), req.uri().path()) {
                     (&Method::GET, "/") => root::handle_home(req, &mut coord_client).await,
@@ -252,6 +268,12 @@ impl Server {
                     (&Method::GET, "/internal/catalog") => {
                         catalog::handle_internal_catalog(req, &mut coord_client).await
                     }
+                    (&Method::GET, "/clusters") => {
+                        cluster::handle_export_clusters(req, &mut coord_client).await
+                    }
+                    (&Method::PUT, "/clusters") => {
+                        cluster::handle_apply_clusters(req, &mut coord_client).await
+                    }
                     _ => root::handle_static(req, &mut coord_client),
                 }
             }
@@ -272,7 +294,16 @@ impl Server {
             )
             .service(router);
         let http = hyper::server::conn::Http::new();
-        http.serve_connection(conn, svc).err_into().await
+        let conn = http.serve_connection(conn, svc);
+        tokio::pin!(conn);
+        // When the server drains, the connection finishes the request in
+        // progress, if any, and then closes.
+        let mut drain_signal = self.drain_signal.clone();
+        tokio::select! {
+            res = &mut conn => return Ok(res?),
+            _ = drain_signal.draining() => conn.as_mut().graceful_shutdown(),
+        }
+        Ok(conn.await?)
     }
 
     // Handler functions are attached by various submodules. They all have a
@@ -351,25 +382,48 @@ impl hyper::service::Service<Request<Body>> for ThirdPartyServer {
     }
 }
 
-// Uses the HTTP Authorization header to fetch a JWT. Basic auth requires a
-// username and password that is "client,secret" that is exchanged for a JWT,
-// and whose username must match the JWT's email. Bearer auth can provide the
-// JWT directly. The JWT is validated and its email address is returned.
-async fn validate_http_frontegg_authentication(
+// Uses the HTTP Authorization header to authenticate the user. Basic auth
+// presents the username and password as a password credential. Bearer auth
+// presents its token, whose validation determines the user. The authenticated
+// user is returned.
+async fn http_authenticate(
     req: &Request<Body>,
-    frontegg: &FronteggAuthentication,
+    authenticator: &dyn Authenticator,
+    coord_client: &mz_coord::Client,
 ) -> Result<String, anyhow::Error> {
-    let (http_user, jwt) = if let Some(basic) = req.headers().typed_get::<Authorization<Basic>>() {
-        let jwt = frontegg
-            .exchange_password_for_token(basic.0.password())
-            .await?;
-        (Some(basic.0.username().to_string()), jwt.access_token)
-    } else if let Some(basic) = req.headers().typed_get::<Authorization<Bearer>>() {
-        (None, basic.0.token().to_string())
-    } else {
-        anyhow::bail!("expected authorization");
-    };
-
-    let claims = frontegg.validate_access_token(&jwt, http_user.as_deref())?;
-    Ok(claims.email)
+    // Passwords of roles in the catalog are checked here, as the
+    // authenticator cannot see the catalog.
+    if authenticator.checks_role_passwords() {
+        let basic = match req.headers().typed_get::<Authorization<Basic>>() {
+            Some(basic) => basic,
+            None => anyhow::bail!("expected basic authorization"),
+        };
+        let user = basic.0.username();
+        let verifier = coord_client
+            .role_password(user)
+            .await
+            .and_then(|verifier| verifier.parse::<Verifier>().ok())
+            .unwrap_or_else(Verifier::mock);
+        if !verifier.verify_password(basic.0.password()) {
+            anyhow::bail!("invalid password");
+        }
+        return Ok(user.to_string());
+    }
+
+    let (http_user, credentials) =
+        if let Some(basic) = req.headers().typed_get::<Authorization<Basic>>() {
+            (
+                Some(basic.0.username().to_string()),
+                Credentials::Password(basic.0.password().to_string()),
+            )
+        } else if let Some(bearer) = req.headers().typed_get::<Authorization<Bearer>>() {
+            (None, Credentials::Token(bearer.0.token().to_string()))
+        } else {
+            anyhow::bail!("expected authorization");
+        };
+
+    let authenticated = authenticator
+        .authenticate(http_user.as_deref(), credentials)
+        .await?;
+    Ok(authenticated.user)
 }
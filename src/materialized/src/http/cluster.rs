@@ -0,0 +1,59 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Declarative cluster management HTTP endpoints.
+
+use hyper::{header, Body, Request, Response, StatusCode};
+use url::form_urlencoded;
+
+use mz_coord::ClusterSpec;
+
+use crate::http::util;
+
+/// Exports the configuration of every cluster as a JSON cluster spec.
+pub async fn handle_export_clusters(
+    _: Request<Body>,
+    coord_client: &mut mz_coord::SessionClient,
+) -> Result<Response<Body>, anyhow::Error> {
+    let spec = coord_client.export_cluster_spec().await?;
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string_pretty(&spec)?))
+        .unwrap())
+}
+
+/// Creates, alters, and drops clusters to match the JSON cluster spec in the
+/// request body, and responds with the names of the clusters that changed.
+///
+/// If the `dry_run` query parameter is `true`, the changes are reported but
+/// not made.
+pub async fn handle_apply_clusters(
+    req: Request<Body>,
+    coord_client: &mut mz_coord::SessionClient,
+) -> Result<Response<Body>, anyhow::Error> {
+    let res = async {
+        let dry_run = form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes())
+            .any(|(key, value)| key == "dry_run" && value == "true");
+        let body = hyper::body::to_bytes(req).await?;
+        let spec: ClusterSpec = serde_json::from_slice(&body)?;
+        let changes = coord_client.apply_cluster_spec(spec, dry_run).await?;
+        Ok(Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_string(&changes)?))
+            .unwrap())
+    }
+    .await;
+    match res {
+        Ok(res) => Ok(res),
+        Err(e) => Ok(util::error_response(
+            StatusCode::BAD_REQUEST,
+            format!("{:#}", e),
+        )),
+    }
+}
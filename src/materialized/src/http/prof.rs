@@ -17,8 +17,9 @@ use std::time::Duration;
 
 use askama::Template;
 use cfg_if::cfg_if;
-use hyper::{Body, Request, Response};
+use hyper::{header, Body, Request, Response};
 
+use mz_prof::time::DEFAULT_SAMPLE_FREQ;
 use mz_prof::{ProfStartTime, StackProfile};
 
 use crate::http::util;
@@ -60,34 +61,33 @@ struct FlamegraphTemplate<'a> {
     extras: &'a [&'a str],
 }
 
-#[allow(clippy::drop_copy, clippy::unit_arg)]
+async fn cpu_profile<'a>(
+    params: &HashMap<Cow<'a, str>, Cow<'a, str>>,
+) -> anyhow::Result<StackProfile> {
+    let merge_threads = params.get("threads").map(AsRef::as_ref) == Some("merge");
+    mz_prof::time::cpu_profile(Duration::from_secs(10), DEFAULT_SAMPLE_FREQ, merge_threads).await
+}
+
 async fn time_prof<'a>(
     params: &HashMap<Cow<'a, str>, Cow<'a, str>>,
 ) -> anyhow::Result<Response<Body>> {
-    let ctl_lock;
-    cfg_if! {
-        if #[cfg(target_os = "macos")] {
-            ctl_lock = ();
-        } else {
-            ctl_lock = if let Some(ctl) = mz_prof::jemalloc::PROF_CTL.as_ref() {
-                let mut borrow = ctl.lock().await;
-                borrow.deactivate()?;
-                Some(borrow)
-            } else {
-                None
-            };
-        }
-    }
-    let merge_threads = params.get("threads").map(AsRef::as_ref) == Some("merge");
-    // SAFETY: We ensure above that memory profiling is off.
-    // Since we hold the mutex, nobody else can be turning it back on in the intervening time.
-    let stacks =
-        unsafe { mz_prof::time::prof_time(Duration::from_secs(10), 99, merge_threads) }.await?;
-    // Fail with a compile error if we weren't holding the jemalloc lock.
-    drop(ctl_lock);
+    let stacks = cpu_profile(params).await?;
     flamegraph(stacks, "CPU Time Flamegraph", false, &[])
 }
 
+async fn time_folded<'a>(
+    params: &HashMap<Cow<'a, str>, Cow<'a, str>>,
+) -> anyhow::Result<Response<Body>> {
+    let stacks = cpu_profile(params).await?;
+    Ok(Response::builder()
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"mz-cpu.folded\"",
+        )
+        .body(Body::from(stacks.to_folded()))
+        .unwrap())
+}
+
 fn flamegraph(
     stacks: StackProfile,
     title: &str,
@@ -129,7 +129,7 @@ mod disabled {
     use hyper::{Body, Method, Request, Response, StatusCode};
     use url::form_urlencoded;
 
-    use super::{time_prof, MemProfilingStatus, ProfTemplate};
+    use super::{time_folded, time_prof, MemProfilingStatus, ProfTemplate};
     use crate::http::util;
     use crate::BUILD_INFO;
 
@@ -161,6 +161,7 @@ mod disabled {
         };
         match action.as_ref() {
             "time_fg" => time_prof(&params).await,
+            "time_folded" => time_folded(&params).await,
             x => Ok(util::error_response(
                 StatusCode::BAD_REQUEST,
                 format!("unrecognized `action` parameter: {}", x),
@@ -185,7 +186,7 @@ mod enabled {
 
     use mz_prof::jemalloc::{parse_jeheap, JemallocProfCtl, PROF_CTL};
 
-    use super::{flamegraph, time_prof, MemProfilingStatus, ProfTemplate};
+    use super::{flamegraph, time_folded, time_prof, MemProfilingStatus, ProfTemplate};
     use crate::http::util;
     use crate::BUILD_INFO;
 
@@ -325,6 +326,7 @@ mod enabled {
                 flamegraph(stacks, "Heap Flamegraph", true, &stats_rendered)
             }
             "time_fg" => time_prof(&params).await,
+            "time_folded" => time_folded(&params).await,
             x => Ok(util::error_response(
                 StatusCode::BAD_REQUEST,
                 format!("unrecognized `action` parameter: {}", x),
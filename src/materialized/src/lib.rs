@@ -16,38 +16,53 @@
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::mem;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use ::http::header::HeaderValue;
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use compile_time_run::run_command_str;
 use futures::StreamExt;
-use mz_coord::PersistConfig;
+use mz_authenticator::Authenticator;
+use mz_coord::{PeekConcurrencyLimits, PersistConfig, RuntimeConfig, SystemConfigEntry};
+use mz_dataflow_types::client::controller::ClusterReplicaSize;
 use mz_dataflow_types::client::RemoteClient;
 use mz_dataflow_types::sources::AwsExternalId;
-use mz_frontegg_auth::FronteggAuthentication;
-use mz_orchestrator::{Orchestrator, ServiceConfig, ServicePort};
+use mz_orchestrator::{
+    DiskLimit, Orchestrator, PlacementHints, ReadinessCheck, ReadinessProbe, ServiceConfig,
+    ServicePort, Toleration,
+};
 use mz_orchestrator_kubernetes::{KubernetesOrchestrator, KubernetesOrchestratorConfig};
 use mz_orchestrator_process::{ProcessOrchestrator, ProcessOrchestratorConfig};
-use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+use mz_orchestrator_systemd::{SystemdOrchestrator, SystemdOrchestratorConfig};
+use openssl::ssl::{SslAcceptor, SslContext, SslFiletype, SslMethod, SslVerifyMode};
 use tokio::net::TcpListener;
-use tokio::sync::oneshot;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
 use tokio_stream::wrappers::TcpListenerStream;
+use uuid::Uuid;
 
 use mz_build_info::BuildInfo;
+use mz_coord::catalog::storage::{CatalogDump, CatalogDurability, SqliteSynchronous};
 use mz_coord::LoggingConfig;
 use mz_ore::collections::CollectionExt;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::NowFn;
 use mz_ore::option::OptionExt;
 use mz_ore::task;
+use mz_pgwire::{DrainTrigger, ReloadableSslContext};
 use mz_pid_file::PidFile;
-use mz_secrets::SecretsController;
-use mz_secrets_filesystem::FilesystemSecretsController;
+use mz_secrets::{
+    InMemorySecretsController, MigratingSecretsController, SecretOp, SecretsController,
+};
+use mz_secrets_aws::{AwsParameterStoreController, AwsSecretsConfig, AwsSecretsController};
+use mz_secrets_filesystem::{EncryptionKey, FilesystemSecretsController, DEFAULT_MAX_VERSIONS};
 use mz_secrets_kubernetes::KubernetesSecretsController;
+use mz_secrets_vault::{VaultConfig, VaultSecretsController};
 
 use crate::mux::Mux;
 use crate::server_metrics::Metrics;
@@ -108,6 +123,17 @@ pub struct Config {
     pub logical_compaction_window: Option<Duration>,
     /// The interval at which sources should be timestamped.
     pub timestamp_frequency: Duration,
+    /// The maximum number of peeks that may execute concurrently on each
+    /// cluster.
+    pub peek_concurrency_limits: PeekConcurrencyLimits,
+    /// How long a peek may wait for a free slot on its cluster before failing.
+    pub peek_queue_timeout: Duration,
+    /// The number of bytes of rows that finishing a query result may hold in
+    /// memory before spilling to temporary files, if any.
+    pub result_sort_memory_limit: Option<usize>,
+    /// The interval at which to collect statistics about indexed collections
+    /// for the optimizer, if any.
+    pub statistics_interval: Option<Duration>,
 
     // === Connection options. ===
     /// The IP address and port to listen on.
@@ -116,8 +142,8 @@ pub struct Config {
     pub third_party_metrics_listen_addr: Option<SocketAddr>,
     /// TLS encryption configuration.
     pub tls: Option<TlsConfig>,
-    /// Materialize Cloud configuration to enable Frontegg JWT user authentication.
-    pub frontegg: Option<FronteggAuthentication>,
+    /// The authenticator for users of the pgwire and HTTP servers.
+    pub authenticator: Arc<dyn Authenticator>,
     /// Origins for which cross-origin resource sharing (CORS) for HTTP requests
     /// is permitted.
     pub cors_allowed_origins: Vec<HeaderValue>,
@@ -125,6 +151,34 @@ pub struct Config {
     // === Storage options. ===
     /// The directory in which `materialized` should store its own metadata.
     pub data_directory: PathBuf,
+    /// The URL of a PostgreSQL database in which to store the catalog, if the
+    /// catalog is not stored in the data directory.
+    pub catalog_postgres_url: Option<String>,
+    /// Whether to store the catalog in typed collections of the STORAGE stash
+    /// in the data directory, rather than in its own SQLite database.
+    pub catalog_stash: bool,
+    /// How thoroughly to ensure that commits to the catalog in the data
+    /// directory are durable.
+    pub catalog_sqlite_synchronous: SqliteSynchronous,
+    /// Whether commits to the catalog wait for their changes to be synced at
+    /// all. Overrides `catalog_sqlite_synchronous` when commits do not wait.
+    pub catalog_durability: CatalogDurability,
+    /// How many user IDs to allocate from the catalog at once.
+    pub catalog_gid_block_size: u64,
+    /// The key with which to encrypt the catalog in the data directory, if
+    /// any.
+    pub catalog_encryption_key: Option<String>,
+    /// A backup of the catalog with which to replace the catalog in the data
+    /// directory before starting, if any.
+    pub restore_catalog: Option<PathBuf>,
+    /// A dump of a catalog to replay on the catalog before starting, if any.
+    /// The catalog must not yet contain any items.
+    pub import_catalog: Option<PathBuf>,
+    /// How long the definitions of dropped objects are kept for `UNDROP`.
+    pub dropped_object_retention: Duration,
+    /// How far the subscriber of a replication slot may fall behind before
+    /// the slot is invalidated, if there is a limit.
+    pub max_replication_slot_lag: Option<Duration>,
     /// The configuration of the storage layer.
     pub storage: StorageConfig,
 
@@ -135,6 +189,11 @@ pub struct Config {
     // === Secrets Storage options. ===
     /// Optional configuration for a secrets controller.
     pub secrets_controller: Option<SecretsControllerConfig>,
+    /// Whether to recreate secrets that are missing from secrets storage as
+    /// empty placeholders, rather than leaving them unreadable.
+    pub recreate_missing_secrets: bool,
+    /// The maximum size of the contents of a secret, in bytes.
+    pub max_secret_size: usize,
 
     // === AWS options. ===
     /// An [external ID] to be supplied to all AWS AssumeRole operations.
@@ -157,6 +216,11 @@ pub struct Config {
     pub persist: PersistConfig,
     /// Now generation function.
     pub now: NowFn,
+    /// A channel on which `pg_reload_conf()` requests a reload of the server's
+    /// runtime configuration. If absent, reloading via SQL is not supported.
+    pub config_reload_tx: Option<mpsc::UnboundedSender<()>>,
+    /// The maximum number of concurrent client connections, if any.
+    pub max_connections: Option<usize>,
 }
 
 /// Configures TLS encryption for connections.
@@ -207,6 +271,16 @@ pub struct OrchestratorConfig {
     pub backend: OrchestratorBackend,
     /// The dataflowd image reference to use.
     pub dataflowd_image: String,
+    /// Labels that a node must have for compute instances to be placed on it.
+    pub compute_node_selector: HashMap<String, String>,
+    /// Taints that compute instances tolerate.
+    pub compute_tolerations: Vec<Toleration>,
+    /// The disk to provision for each process of the storage runtime and of
+    /// each compute instance.
+    pub disk_limit: Option<DiskLimit>,
+    /// The resources to allocate to the replica of a managed compute instance
+    /// of each size, by the name of the size.
+    pub cluster_replica_sizes: HashMap<String, ClusterReplicaSize>,
 }
 
 /// The orchestrator itself.
@@ -216,18 +290,46 @@ pub enum OrchestratorBackend {
     Kubernetes(KubernetesOrchestratorConfig),
     /// A local process orchestrator.
     Process(ProcessOrchestratorConfig),
+    /// A local orchestrator backed by systemd transient units.
+    Systemd(SystemdOrchestratorConfig),
 }
 
 /// Configuration for the service orchestrator.
 #[derive(Debug, Clone)]
 pub enum SecretsControllerConfig {
-    LocalFileSystem,
+    LocalFileSystem {
+        /// The key with which to encrypt secrets at rest, if any.
+        encryption_key: Option<EncryptionKey>,
+        /// The number of versions of each secret to retain.
+        max_versions: usize,
+        /// The user that owns the secrets, if not the process's.
+        uid: Option<u32>,
+        /// The group that owns the secrets, if not the process's.
+        gid: Option<u32>,
+    },
     // Create a Kubernetes Controller.
     Kubernetes {
         /// The name of a Kubernetes context to use, if the Kubernetes configuration
         /// is loaded from the local kubeconfig.
         context: String,
     },
+    /// Store secrets in HashiCorp Vault.
+    Vault(VaultConfig),
+    /// Store secrets in AWS Secrets Manager.
+    AwsSecretsManager(AwsSecretsConfig),
+    /// Store secrets in the Parameter Store of AWS Systems Manager.
+    AwsParameterStore(AwsSecretsConfig),
+    /// Store secrets in memory, in the given controller. Intended for tests.
+    InMemory(InMemorySecretsController),
+    /// Migrate secrets from one backend to another, by writing secrets to
+    /// both backends and reading them from the new backend with a fallback
+    /// to the old backend.
+    Migrating {
+        /// The backend that secrets are migrated from.
+        from: Box<SecretsControllerConfig>,
+        /// The backend that secrets are migrated to.
+        to: Box<SecretsControllerConfig>,
+    },
 }
 
 /// Configuration of the storage layer.
@@ -253,25 +355,10 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
     let workers = config.workers;
 
     // Validate TLS configuration, if present.
-    let (pgwire_tls, http_tls) = match &config.tls {
-        None => (None, None),
+    let (pgwire_tls, http_tls, tls) = match &config.tls {
+        None => (None, None, None),
         Some(tls_config) => {
-            let context = {
-                // Mozilla publishes three presets: old, intermediate, and modern. They
-                // recommend the intermediate preset for general purpose servers, which
-                // is what we use, as it is compatible with nearly every client released
-                // in the last five years but does not include any known-problematic
-                // ciphers. We once tried to use the modern preset, but it was
-                // incompatible with Fivetran, and presumably other JDBC-based tools.
-                let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
-                if let TlsMode::VerifyCa { ca } | TlsMode::VerifyFull { ca } = &tls_config.mode {
-                    builder.set_ca_file(ca)?;
-                    builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
-                }
-                builder.set_certificate_chain_file(&tls_config.cert)?;
-                builder.set_private_key_file(&tls_config.key, SslFiletype::PEM)?;
-                builder.build().into_context()
-            };
+            let context = ReloadableSslContext::new(build_ssl_context(tls_config)?);
             let pgwire_tls = mz_pgwire::TlsConfig {
                 context: context.clone(),
                 mode: match tls_config.mode {
@@ -280,13 +367,14 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
                 },
             };
             let http_tls = http::TlsConfig {
-                context,
+                context: context.clone(),
                 mode: match tls_config.mode {
                     TlsMode::Require | TlsMode::VerifyCa { .. } => http::TlsMode::Require,
                     TlsMode::VerifyFull { .. } => http::TlsMode::AssumeUser,
                 },
             };
-            (Some(pgwire_tls), Some(http_tls))
+            let tls = (tls_config.mode.clone(), context);
+            (Some(pgwire_tls), Some(http_tls), Some(tls))
         }
     };
 
@@ -309,11 +397,64 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
     let listener = TcpListener::bind(&config.listen_addr).await?;
     let local_addr = listener.local_addr()?;
 
-    // Load the coordinator catalog from disk.
-    let coord_storage = mz_coord::catalog::storage::Connection::open(
-        &config.data_directory,
-        Some(config.experimental_mode),
-    )?;
+    // Restore the catalog from a backup, if requested. The PID file guarantees
+    // that no other process is using the catalog while it is replaced. A data
+    // directory is restored only once, so restarting with the same
+    // configuration fails rather than discarding later changes.
+    if let Some(path) = &config.restore_catalog {
+        mz_coord::catalog::storage::Connection::restore(
+            path,
+            &config.data_directory,
+            config.catalog_encryption_key.as_deref(),
+        )?;
+        tracing::info!("restored catalog from {}", path.display());
+    }
+
+    // Load the coordinator catalog from its storage.
+    if config.catalog_gid_block_size == 0 {
+        bail!("catalog gid block size must be positive");
+    }
+    let catalog_sqlite_synchronous = match config.catalog_durability {
+        CatalogDurability::None => SqliteSynchronous::Off,
+        CatalogDurability::Fsync => config.catalog_sqlite_synchronous,
+    };
+    let mut coord_storage = match &config.catalog_postgres_url {
+        None if config.catalog_stash => mz_coord::catalog::storage::Connection::open_stash(
+            &config.data_directory,
+            Some(config.experimental_mode),
+            Some(config.safe_mode),
+        )?,
+        None => mz_coord::catalog::storage::Connection::open(
+            &config.data_directory,
+            catalog_sqlite_synchronous,
+            config.catalog_encryption_key.as_deref(),
+            Some(config.experimental_mode),
+            Some(config.safe_mode),
+        )?,
+        Some(url) => mz_coord::catalog::storage::Connection::open_postgres(
+            url,
+            config.catalog_durability,
+            Some(config.experimental_mode),
+            Some(config.safe_mode),
+        )?,
+    };
+
+    coord_storage.set_user_gid_block_size(config.catalog_gid_block_size);
+
+    // The contents of the imported secrets are written once the secrets
+    // controller is initialized.
+    let imported_secrets = match &config.import_catalog {
+        None => vec![],
+        Some(path) => {
+            let dump = fs::read(path)
+                .with_context(|| format!("reading catalog dump {}", path.display()))?;
+            let dump: CatalogDump = serde_json::from_slice(&dump)
+                .with_context(|| format!("parsing catalog dump {}", path.display()))?;
+            coord_storage.import(&dump, (config.now)())?;
+            tracing::info!("imported catalog from {}", path.display());
+            dump.secrets()?
+        }
+    };
 
     // Initialize persistence runtime.
     let persister = config
@@ -333,6 +474,10 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
         Some(OrchestratorConfig {
             backend,
             dataflowd_image,
+            compute_node_selector,
+            compute_tolerations,
+            disk_limit,
+            cluster_replica_sizes,
         }) => {
             let orchestrator: Box<dyn Orchestrator> = match backend {
                 OrchestratorBackend::Kubernetes(config) => Box::new(
@@ -340,9 +485,14 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
                         .await
                         .context("connecting to kubernetes")?,
                 ),
-                OrchestratorBackend::Process(config) => {
-                    Box::new(ProcessOrchestrator::new(config).await?)
-                }
+                OrchestratorBackend::Process(process_config) => Box::new(
+                    ProcessOrchestrator::new(process_config, &config.metrics_registry).await?,
+                ),
+                OrchestratorBackend::Systemd(config) => Box::new(
+                    SystemdOrchestrator::new(config)
+                        .await
+                        .context("connecting to systemd")?,
+                ),
             };
 
             if let StorageConfig::Local = &config.storage {
@@ -353,13 +503,12 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
                         "runtime",
                         ServiceConfig {
                             image: dataflowd_image.clone(),
-                            args: &|ports| {
-                                vec![
-                                    "--runtime=storage".into(),
-                                    format!("--workers={storage_workers}"),
-                                    format!("--storage-addr=0.0.0.0:{}", ports["storage"]),
-                                ]
-                            },
+                            args: vec![
+                                "--runtime=storage".into(),
+                                format!("--workers={storage_workers}"),
+                                "--listen-addr=%{listen_host}:%{ports.controller}".into(),
+                                "--storage-addr=%{listen_host}:%{ports.storage}".into(),
+                            ],
                             ports: vec![
                                 ServicePort {
                                     name: "controller".into(),
@@ -373,8 +522,16 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
                             // TODO: limits?
                             cpu_limit: None,
                             memory_limit: None,
+                            disk_limit: disk_limit.clone(),
                             processes: 1,
+                            readiness_probe: Some(ReadinessProbe {
+                                port: "controller".into(),
+                                check: ReadinessCheck::Tcp,
+                                timeout: Duration::from_secs(60),
+                            }),
+                            placement: PlacementHints::default(),
                             labels: HashMap::new(),
+                            annotations: HashMap::new(),
                         },
                     )
                     .await?;
@@ -393,25 +550,33 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
                 orchestrator,
                 dataflowd_image,
                 storage_addr: remote_storage_config.compute_addr.clone(),
+                compute_node_selector,
+                compute_tolerations,
+                disk_limit,
+                cluster_replica_sizes,
             })
         }
     };
 
     // Initialize secrets controller.
-    let secrets_controller: Box<dyn SecretsController> = match config.secrets_controller {
-        None | Some(SecretsControllerConfig::LocalFileSystem) => {
-            let secrets_storage = config.data_directory.join("secrets");
-            fs::create_dir_all(&secrets_storage).with_context(|| {
-                format!("creating secrets directory: {}", secrets_storage.display())
-            })?;
-            Box::new(FilesystemSecretsController::new(secrets_storage))
-        }
-        Some(SecretsControllerConfig::Kubernetes { context }) => Box::new(
-            KubernetesSecretsController::new(context)
-                .await
-                .context("connecting to kubernetes")?,
-        ),
-    };
+    let secrets_controller = open_secrets_controller(
+        config.secrets_controller,
+        &config.data_directory,
+        coord_storage.cluster_id(),
+    )
+    .await?;
+    if !imported_secrets.is_empty() {
+        let ops = imported_secrets
+            .into_iter()
+            .map(|(id, contents)| SecretOp::Ensure { id, contents })
+            .collect::<Vec<_>>();
+        let count = ops.len();
+        secrets_controller
+            .apply(ops)
+            .await
+            .context("writing imported secrets")?;
+        tracing::info!("imported {} secrets", count);
+    }
 
     // Initialize dataflow server.
     let dataflow_config = mz_dataflow::Config {
@@ -493,6 +658,16 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
         persister,
         now: config.now,
         secrets_controller,
+        recreate_missing_secrets: config.recreate_missing_secrets,
+        max_secret_size: config.max_secret_size,
+        peek_concurrency_limits: config.peek_concurrency_limits,
+        peek_queue_timeout: config.peek_queue_timeout,
+        config_reload_tx: config.config_reload_tx,
+        max_connections: config.max_connections,
+        result_sort_memory_limit: config.result_sort_memory_limit,
+        statistics_interval: config.statistics_interval,
+        dropped_object_retention: config.dropped_object_retention,
+        max_replication_slot_lag: config.max_replication_slot_lag,
     })
     .await?;
 
@@ -513,37 +688,41 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
 
     // Launch task to serve connections.
     //
-    // The lifetime of this task is controlled by a trigger that activates on
-    // drop. Draining marks the beginning of the server shutdown process and
-    // indicates that new user connections (i.e., pgwire and HTTP connections)
-    // should be rejected. Once all existing user connections have gracefully
-    // terminated, this task exits.
-    let (drain_trigger, drain_tripwire) = oneshot::channel();
-    task::spawn(|| "pgwire_server", {
+    // The lifetime of this task is controlled by a trigger that activates when
+    // the server is drained or dropped. Draining marks the beginning of the
+    // server shutdown process and indicates that new user connections (i.e.,
+    // pgwire and HTTP connections) should be rejected, and that existing user
+    // connections should close once they are idle. Once all existing user
+    // connections have terminated, this task exits.
+    let (drain_trigger, drain_signal) = mz_pgwire::DrainSignal::new();
+    let serve_task = task::spawn(|| "pgwire_server", {
         let pgwire_server = mz_pgwire::Server::new(mz_pgwire::Config {
             tls: pgwire_tls,
             coord_client: coord_client.clone(),
             metrics_registry: &metrics_registry,
-            frontegg: config.frontegg.clone(),
+            authenticator: Arc::clone(&config.authenticator),
+            drain_signal: drain_signal.clone(),
         });
         let http_server = http::Server::new(http::Config {
             tls: http_tls,
-            frontegg: config.frontegg,
+            authenticator: config.authenticator,
             coord_client: coord_client.clone(),
             metrics_registry,
             global_metrics: metrics,
             pgwire_metrics: pgwire_server.metrics(),
             allowed_origins: config.cors_allowed_origins,
+            drain_signal: drain_signal.clone(),
         });
         let mut mux = Mux::new();
         mux.add_handler(pgwire_server);
         mux.add_handler(http_server);
+        let mut drain_signal = drain_signal;
         async move {
             // TODO(benesch): replace with `listener.incoming()` if that is
             // restored when the `Stream` trait stabilizes.
             let mut incoming = TcpListenerStream::new(listener);
-            mux.serve(incoming.by_ref().take_until(drain_tripwire))
-                .await;
+            let draining = Box::pin(async move { drain_signal.draining().await });
+            mux.serve(incoming.by_ref().take_until(draining)).await;
         }
     });
 
@@ -554,7 +733,7 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
             interval: telemetry.interval,
             cluster_id: coord_handle.cluster_id(),
             workers,
-            coord_client,
+            coord_client: coord_client.clone(),
         };
         task::spawn(|| "telemetry_loop", async move {
             telemetry::report_loop(config).await
@@ -563,19 +742,179 @@ pub async fn serve(mut config: Config) -> Result<Server, anyhow::Error> {
 
     Ok(Server {
         local_addr,
+        tls,
         _pid_file: pid_file,
-        _drain_trigger: drain_trigger,
+        drain_trigger,
+        serve_task: Some(serve_task),
+        coord_client,
         _coord_handle: coord_handle,
         _dataflow_server: dataflow_server,
     })
 }
 
+/// Connects to the secrets backend described by `config`, which must not
+/// itself be a migration between backends.
+///
+/// Describes the contents of a copy of the catalog in `data_directory`, as by
+/// [`mz_coord::catalog::Catalog::dump_stored`], including the contents of its
+/// secrets, which are read from the secrets storage that `secrets_controller`
+/// configures.
+///
+/// The catalog in the data directory is not modified.
+pub async fn dump_catalog(
+    data_directory: &Path,
+    catalog_encryption_key: Option<&str>,
+    secrets_controller: Option<SecretsControllerConfig>,
+) -> Result<CatalogDump, anyhow::Error> {
+    let mut dump = mz_coord::catalog::Catalog::dump_stored(data_directory, catalog_encryption_key)?;
+    if !dump.items.iter().any(|item| item.is_secret()) {
+        return Ok(dump);
+    }
+    let cluster_id = mz_coord::catalog::storage::Connection::open_read_only(
+        data_directory,
+        catalog_encryption_key,
+    )?
+    .cluster_id();
+    let secrets_controller =
+        open_secrets_controller(secrets_controller, data_directory, cluster_id).await?;
+    for item in dump.items.iter_mut().filter(|item| item.is_secret()) {
+        let contents = secrets_controller.read(item.id).await.with_context(|| {
+            format!(
+                "reading secret {}.{}.{} ({})",
+                item.database, item.schema, item.name, item.id
+            )
+        })?;
+        item.secret_contents = Some(hex::encode(contents));
+    }
+    Ok(dump)
+}
+
+/// Initializes the secrets controller that `config` describes, or the local
+/// filesystem secrets controller if `config` is `None`.
+async fn open_secrets_controller(
+    config: Option<SecretsControllerConfig>,
+    data_directory: &Path,
+    cluster_id: Uuid,
+) -> Result<Box<dyn SecretsController>, anyhow::Error> {
+    let config = config.unwrap_or(SecretsControllerConfig::LocalFileSystem {
+        encryption_key: None,
+        max_versions: DEFAULT_MAX_VERSIONS,
+        uid: None,
+        gid: None,
+    });
+    Ok(match config {
+        SecretsControllerConfig::Migrating { from, to } => {
+            // The Kubernetes controller does not yet store secrets, so a
+            // migration to it would report secrets as copied that it cannot
+            // read back.
+            if let SecretsControllerConfig::Kubernetes { .. } = *to {
+                bail!("secrets cannot be migrated to the Kubernetes secrets controller");
+            }
+            let from = build_secrets_controller(*from, data_directory, cluster_id)
+                .await
+                .context("initializing old secrets backend")?;
+            let to = build_secrets_controller(*to, data_directory, cluster_id).await?;
+            Box::new(MigratingSecretsController::new(from, to))
+        }
+        other => build_secrets_controller(other, data_directory, cluster_id).await?,
+    })
+}
+
+/// Backends that may be shared by several environments, like Vault and AWS,
+/// store the secrets of the environment whose catalog has the ID `cluster_id`
+/// under a path or name prefix of their own, so that the controller never
+/// lists, and so never deletes as orphaned, another environment's secrets.
+async fn build_secrets_controller(
+    config: SecretsControllerConfig,
+    data_directory: &Path,
+    cluster_id: Uuid,
+) -> Result<Box<dyn SecretsController>, anyhow::Error> {
+    Ok(match config {
+        SecretsControllerConfig::LocalFileSystem {
+            encryption_key,
+            max_versions,
+            uid,
+            gid,
+        } => {
+            let secrets_storage = data_directory.join("secrets");
+            let mut controller = FilesystemSecretsController::new(secrets_storage, encryption_key)
+                .with_max_versions(max_versions)
+                .with_owner(uid, gid);
+            controller
+                .initialize()
+                .context("initializing secrets directory")?;
+            let encrypted = controller
+                .encrypt_plaintext_secrets()
+                .context("encrypting plaintext secrets")?;
+            if encrypted > 0 {
+                tracing::info!("encrypted {} plaintext secrets", encrypted);
+            }
+            Box::new(controller)
+        }
+        SecretsControllerConfig::Kubernetes { context } => Box::new(
+            KubernetesSecretsController::new(context)
+                .await
+                .context("connecting to kubernetes")?,
+        ),
+        SecretsControllerConfig::Vault(mut vault_config) => {
+            vault_config.path_prefix = format!("{}/{}", vault_config.path_prefix, cluster_id);
+            Box::new(
+                VaultSecretsController::new(vault_config)
+                    .await
+                    .context("connecting to vault")?,
+            )
+        }
+        SecretsControllerConfig::AwsSecretsManager(mut aws_config) => {
+            aws_config.prefix = format!("{}{}/", aws_config.prefix, cluster_id);
+            Box::new(
+                AwsSecretsController::new(aws_config)
+                    .await
+                    .context("connecting to aws secrets manager")?,
+            )
+        }
+        SecretsControllerConfig::AwsParameterStore(mut aws_config) => {
+            aws_config.prefix = format!("{}{}/", aws_config.prefix, cluster_id);
+            Box::new(
+                AwsParameterStoreController::new(aws_config)
+                    .await
+                    .context("connecting to aws parameter store")?,
+            )
+        }
+        SecretsControllerConfig::InMemory(controller) => Box::new(controller),
+        SecretsControllerConfig::Migrating { .. } => {
+            bail!("cannot migrate secrets from or to a migration between backends")
+        }
+    })
+}
+
+/// Builds the SSL context described by a [`TlsConfig`], reading the
+/// certificate, key, and certificate authority from disk.
+fn build_ssl_context(tls_config: &TlsConfig) -> Result<SslContext, anyhow::Error> {
+    // Mozilla publishes three presets: old, intermediate, and modern. They
+    // recommend the intermediate preset for general purpose servers, which
+    // is what we use, as it is compatible with nearly every client released
+    // in the last five years but does not include any known-problematic
+    // ciphers. We once tried to use the modern preset, but it was
+    // incompatible with Fivetran, and presumably other JDBC-based tools.
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+    if let TlsMode::VerifyCa { ca } | TlsMode::VerifyFull { ca } = &tls_config.mode {
+        builder.set_ca_file(ca)?;
+        builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+    builder.set_certificate_chain_file(&tls_config.cert)?;
+    builder.set_private_key_file(&tls_config.key, SslFiletype::PEM)?;
+    Ok(builder.build().into_context())
+}
+
 /// A running `materialized` server.
 pub struct Server {
     local_addr: SocketAddr,
+    tls: Option<(TlsMode, ReloadableSslContext)>,
     _pid_file: PidFile,
     // Drop order matters for these fields.
-    _drain_trigger: oneshot::Sender<()>,
+    drain_trigger: DrainTrigger,
+    serve_task: Option<JoinHandle<()>>,
+    coord_client: mz_coord::Client,
     _coord_handle: mz_coord::Handle,
     _dataflow_server: mz_dataflow::Server,
 }
@@ -584,4 +923,55 @@ impl Server {
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
+
+    /// Rereads the TLS certificate, key, and certificate authority named by
+    /// `tls_config`.
+    ///
+    /// New connections use the reloaded files; connections that have already
+    /// negotiated TLS are unaffected. Enabling or disabling TLS, or changing
+    /// the TLS mode, requires a restart.
+    pub fn reload_tls(&self, tls_config: Option<&TlsConfig>) -> Result<(), anyhow::Error> {
+        match (&self.tls, tls_config) {
+            (None, None) => Ok(()),
+            (Some((mode, context)), Some(tls_config))
+                if mem::discriminant(mode) == mem::discriminant(&tls_config.mode) =>
+            {
+                context.set(build_ssl_context(tls_config)?);
+                Ok(())
+            }
+            _ => bail!("changing the TLS mode requires a restart"),
+        }
+    }
+
+    /// Reports the server's active runtime configuration in
+    /// `mz_system_config`.
+    pub fn report_system_config(&self, entries: Vec<SystemConfigEntry>) {
+        self.coord_client.report_system_config(entries);
+    }
+
+    /// Applies new values of the coordinator's reloadable options.
+    pub fn reload_runtime_config(&self, config: RuntimeConfig) {
+        self.coord_client.reload_runtime_config(config);
+    }
+
+    /// Drains the server in preparation for shutting it down.
+    ///
+    /// The server stops accepting connections and closes each existing
+    /// connection once it is idle, letting the queries and `TAIL`s in progress
+    /// run to completion. Connections that remain open after `grace_period`
+    /// are terminated. The coordinator is then shut down, which stops the
+    /// orchestrated services and flushes the catalog and the STORAGE stash;
+    /// dropping the server waits for it to finish.
+    pub async fn drain(&mut self, grace_period: Duration) {
+        self.drain_trigger.trigger();
+        if let Some(mut serve_task) = self.serve_task.take() {
+            tracing::info!("draining connections for up to {:?}", grace_period);
+            if time::timeout(grace_period, &mut serve_task).await.is_err() {
+                tracing::warn!("drain grace period elapsed; terminating remaining connections");
+                serve_task.abort();
+                let _ = serve_task.await;
+            }
+        }
+        self.coord_client.shutdown();
+    }
 }
@@ -7,12 +7,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
 use async_trait::async_trait;
-use futures::stream::{Stream, StreamExt};
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
 use tokio::io::{self, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
 use tracing::{debug, error};
 
 use mz_ore::netio::{self, SniffedStream, SniffingStream};
@@ -46,13 +50,25 @@ impl Mux {
         self.handlers.push(Box::new(handler));
     }
 
-    /// Serves incoming TCP traffic from `listener`.
+    /// Serves incoming TCP traffic from `incoming` until it is exhausted, then
+    /// waits for the connections that were accepted to close.
+    ///
+    /// Dropping the returned future aborts the connections that remain open.
     pub async fn serve<S>(self, mut incoming: S)
     where
         S: Stream<Item = io::Result<TcpStream>> + Unpin,
     {
         let handlers = Arc::new(self.handlers);
-        while let Some(conn) = incoming.next().await {
+        let mut conns = FuturesUnordered::new();
+        loop {
+            let conn = tokio::select! {
+                conn = incoming.next() => match conn {
+                    Some(conn) => conn,
+                    None => break,
+                },
+                // Reap connections as they close.
+                Some(()) = conns.next() => continue,
+            };
             let conn = match conn {
                 Ok(conn) => conn,
                 Err(err) => {
@@ -72,11 +88,32 @@ impl Mux {
             //
             // [0]: https://news.ycombinator.com/item?id=10608356
             conn.set_nodelay(true).expect("set_nodelay failed");
-            task::spawn(
+            conns.push(ConnectionTask(task::spawn(
                 || "mux_serve",
                 handle_connection(Arc::clone(&handlers), conn),
-            );
+            )));
         }
+        while let Some(()) = conns.next().await {}
+    }
+}
+
+/// The task that serves a connection, which is aborted if dropped before it
+/// completes.
+struct ConnectionTask(JoinHandle<()>);
+
+impl Future for ConnectionTask {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        // A task that panicked or was aborted has closed its connection all
+        // the same.
+        Pin::new(&mut self.0).poll(cx).map(|_| ())
+    }
+}
+
+impl Drop for ConnectionTask {
+    fn drop(&mut self) {
+        self.0.abort();
     }
 }
 
@@ -142,7 +179,8 @@ impl ConnectionHandler for mz_pgwire::Server {
         // Using fully-qualified syntax means we won't accidentally call
         // ourselves (i.e., silently infinitely recurse) if the name or type of
         // `pgwire::Server::handle_connection` changes.
-        mz_pgwire::Server::handle_connection(self, conn).await
+        let peer_addr = conn.get_ref().peer_addr().ok().map(|addr| addr.ip());
+        mz_pgwire::Server::handle_connection(self, conn, peer_addr).await
     }
 }
 
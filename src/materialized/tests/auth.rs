@@ -9,7 +9,7 @@
 
 //! Integration tests for TLS encryption and authentication.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::Infallible;
 use std::error::Error;
 use std::fs::{self, File};
@@ -51,6 +51,7 @@ use tokio::runtime::Runtime;
 use uuid::Uuid;
 
 use materialized::TlsMode;
+use mz_authenticator::{CatalogAuthenticator, PasswordAuthenticator};
 use mz_frontegg_auth::{
     ApiTokenArgs, ApiTokenResponse, Claims, FronteggAuthentication, FronteggConfig, RefreshToken,
     REFRESH_SUFFIX,
@@ -1365,6 +1366,218 @@ fn test_auth() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_auth_password() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let authenticator = PasswordAuthenticator::new(BTreeMap::from([(
+        "materialize".to_string(),
+        "hunter2".to_string(),
+    )]));
+    let header_basic = make_header(Authorization::basic("materialize", "hunter2"));
+    let header_basic_bad = make_header(Authorization::basic("materialize", "hunter3"));
+    let header_bearer = make_header(Authorization::bearer("hunter2").unwrap());
+    let no_headers = HeaderMap::new();
+
+    // Test connecting to a server that checks passwords against a fixed set
+    // of users.
+    let config = util::Config::default().with_authenticator(authenticator);
+    let server = util::start_server(config)?;
+    run_tests(
+        "Password",
+        &server,
+        &[
+            // The correct password should succeed.
+            TestCase::Pgwire {
+                user: "materialize",
+                password: Some("hunter2"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Success,
+            },
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &header_basic,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Success,
+            },
+            // An incorrect password should fail.
+            TestCase::Pgwire {
+                user: "materialize",
+                password: Some("hunter3"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                    assert_eq!(err.message(), "invalid password");
+                })),
+            },
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &header_basic_bad,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|code, message| {
+                    assert_eq!(code, Some(StatusCode::UNAUTHORIZED));
+                    assert_contains!(message, "unauthorized");
+                })),
+            },
+            // A user without a password should fail, even with a password
+            // that is valid for another user.
+            TestCase::Pgwire {
+                user: "other",
+                password: Some("hunter2"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                })),
+            },
+            // Missing credentials and bearer tokens should fail over HTTP.
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &no_headers,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|code, message| {
+                    assert_eq!(code, Some(StatusCode::UNAUTHORIZED));
+                    assert_contains!(message, "unauthorized");
+                })),
+            },
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &header_bearer,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|code, message| {
+                    assert_eq!(code, Some(StatusCode::UNAUTHORIZED));
+                    assert_contains!(message, "unauthorized");
+                })),
+            },
+        ],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_auth_catalog() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    // Set passwords on a server that trusts its clients, then restart it with
+    // an authenticator that checks those passwords.
+    let data_dir = tempfile::tempdir()?;
+    {
+        let server = util::start_server(util::Config::default().data_directory(data_dir.path()))?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("ALTER ROLE materialize PASSWORD 'hunter2'")?;
+        client.batch_execute("CREATE ROLE nologin SUPERUSER PASSWORD 'hunter2'")?;
+        client.batch_execute("CREATE USER nopassword SUPERUSER")?;
+    }
+
+    let header_basic = make_header(Authorization::basic("materialize", "hunter2"));
+    let header_basic_bad = make_header(Authorization::basic("materialize", "hunter3"));
+    let header_basic_nologin = make_header(Authorization::basic("nologin", "hunter2"));
+
+    let config = util::Config::default()
+        .data_directory(data_dir.path())
+        .with_authenticator(CatalogAuthenticator);
+    let server = util::start_server(config)?;
+    run_tests(
+        "Catalog",
+        &server,
+        &[
+            // The correct password should succeed.
+            TestCase::Pgwire {
+                user: "materialize",
+                password: Some("hunter2"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Success,
+            },
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &header_basic,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Success,
+            },
+            // An incorrect password should fail.
+            TestCase::Pgwire {
+                user: "materialize",
+                password: Some("hunter3"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                    assert_eq!(
+                        err.message(),
+                        "password authentication failed for user \"materialize\""
+                    );
+                })),
+            },
+            TestCase::Http {
+                user: "materialize",
+                scheme: Scheme::HTTP,
+                headers: &header_basic_bad,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|code, message| {
+                    assert_eq!(code, Some(StatusCode::UNAUTHORIZED));
+                    assert_contains!(message, "unauthorized");
+                })),
+            },
+            // Roles that may not log in, that have no password, or that do not
+            // exist should fail in the same way.
+            TestCase::Pgwire {
+                user: "nologin",
+                password: Some("hunter2"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                })),
+            },
+            TestCase::Http {
+                user: "nologin",
+                scheme: Scheme::HTTP,
+                headers: &header_basic_nologin,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|code, message| {
+                    assert_eq!(code, Some(StatusCode::UNAUTHORIZED));
+                    assert_contains!(message, "unauthorized");
+                })),
+            },
+            TestCase::Pgwire {
+                user: "nopassword",
+                password: Some(""),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                })),
+            },
+            TestCase::Pgwire {
+                user: "nonexistent",
+                password: Some("hunter2"),
+                ssl_mode: SslMode::Disable,
+                configure: Box::new(|_| Ok(())),
+                assert: Assert::Err(Box::new(|err| {
+                    let err = err.unwrap_db_error();
+                    assert_eq!(*err.code(), SqlState::INVALID_PASSWORD);
+                })),
+            },
+        ],
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_auth_intermediate_ca() -> Result<(), Box<dyn Error>> {
     // Create a CA, an intermediate CA, and a server key pair signed by the
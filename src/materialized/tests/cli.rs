@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use std::ffi::OsStr;
+use std::fs;
 use std::os::unix::ffi::OsStrExt;
 use std::time::Duration;
 
@@ -66,3 +67,51 @@ fn test_threads() {
     // has started correctly, since it runs forever. The success code path is
     // well exercised by integration tests, so it's not a big deal.
 }
+
+#[test]
+fn test_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_check = |path: &std::path::Path| {
+        let mut cmd = cmd();
+        cmd.arg(format!("--config-file={}", path.display()))
+            .arg("--config-check");
+        cmd
+    };
+
+    let valid = dir.path().join("valid.toml");
+    fs::write(
+        &valid,
+        "workers = 2\nlisten-addr = \"${MZ_TEST_LISTEN_ADDR}\"\n",
+    )
+    .unwrap();
+    config_check(&valid)
+        .env("MZ_TEST_LISTEN_ADDR", "127.0.0.1:6876")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("configuration is valid"));
+    config_check(&valid)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "environment variable MZ_TEST_LISTEN_ADDR is not set",
+        ));
+
+    let unknown = dir.path().join("unknown.yaml");
+    fs::write(&unknown, "bogus: 1\n").unwrap();
+    config_check(&unknown)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown option bogus"));
+
+    // Values from the configuration file are validated like command-line
+    // options, and command-line options take precedence.
+    let invalid = dir.path().join("invalid.toml");
+    fs::write(&invalid, "workers = 0\n").unwrap();
+    config_check(&invalid)
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(
+            "error: Invalid value \"0\" for '--workers <N>': must be greater than zero",
+        ));
+    config_check(&invalid).arg("--workers=1").assert().success();
+}
@@ -13,19 +13,27 @@ use std::error::Error;
 use std::io::{Read, Write};
 use std::net::TcpStream;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 use bytes::BytesMut;
 use fallible_iterator::FallibleIterator;
-use futures::future;
+use futures::{future, TryStreamExt};
 use mz_coord::session::DEFAULT_DATABASE_NAME;
 use postgres::binary_copy::BinaryCopyOutIter;
 use postgres::error::SqlState;
 use postgres::types::Type;
 use postgres::SimpleQueryMessage;
 use postgres_array::{Array, Dimension};
+use postgres_protocol::message::backend::{
+    LogicalReplicationMessage, ReplicationMessage, TupleData,
+};
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_postgres::config::ReplicationMode;
+use tokio_postgres::replication::LogicalReplicationStream;
+use tokio_postgres::types::PgLsn;
 
 use mz_ore::collections::CollectionExt;
 use mz_ore::task;
@@ -327,6 +335,57 @@ fn test_simple_query_no_hang() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Ensures that a transaction that holds the write lock blocks the writes of
+// other sessions for no longer than `write_lock_timeout`.
+#[test]
+fn test_write_lock_timeout() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let server = util::start_server(util::Config::default())?;
+    let mut client = server.connect(postgres::NoTls)?;
+    client.batch_execute(
+        "ALTER SYSTEM SET write_lock_timeout = '1s';
+        CREATE TABLE t (a int);
+        INSERT INTO t VALUES (1)",
+    )?;
+
+    // The first session takes the write lock with an `UPDATE`, and then
+    // idles without committing.
+    let mut client1 = server.connect(postgres::NoTls)?;
+    client1.batch_execute("BEGIN; UPDATE t SET a = 2")?;
+
+    // The second session's write waits until the first session's connection
+    // is terminated, which discards the first session's update, rather than
+    // for as long as the first session idles.
+    let start = Instant::now();
+    let mut client2 = server.connect(postgres::NoTls)?;
+    client2.batch_execute("INSERT INTO t VALUES (3)")?;
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert!(client1.batch_execute("COMMIT").is_err());
+    assert!(client1.is_closed());
+    let rows: Vec<i32> = client
+        .query("SELECT a FROM t ORDER BY a", &[])?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(rows, [1, 3]);
+
+    // A transaction that keeps running statements is bounded too.
+    client1 = server.connect(postgres::NoTls)?;
+    client1.batch_execute("BEGIN; UPDATE t SET a = 4")?;
+    let start = Instant::now();
+    let result = loop {
+        let result = client1.batch_execute("UPDATE t SET a = a + 1");
+        if result.is_err() || start.elapsed() > Duration::from_secs(30) {
+            break result;
+        }
+    };
+    assert!(result.is_err());
+    client2.batch_execute("INSERT INTO t VALUES (5)")?;
+
+    Ok(())
+}
+
 #[test]
 fn test_copy() -> Result<(), Box<dyn Error>> {
     mz_ore::test::init_logging();
@@ -461,6 +520,241 @@ fn test_record_types() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Connects to `server` in logical replication mode, returning the client and
+/// the task that drives its connection, which closes the connection when
+/// aborted.
+async fn connect_replication(
+    server: &util::Server,
+) -> Result<(tokio_postgres::Client, JoinHandle<()>), Box<dyn Error>> {
+    let (client, conn) = server
+        .pg_config_async()
+        .replication_mode(ReplicationMode::Logical)
+        .connect(postgres::NoTls)
+        .await?;
+    let handle = task::spawn(|| "connect_replication", async move {
+        // The connection is expected to fail when a replication is ended
+        // with an error.
+        let _ = conn.await;
+    });
+    Ok((client, handle))
+}
+
+/// Starts replicating the publication `pub` from the slot `slot`, retrying
+/// for as long as the slot is still active from a previous replication.
+///
+/// Returns the replication stream and the task that drives its connection.
+async fn start_replication(
+    server: &util::Server,
+    slot: &str,
+    start_lsn: PgLsn,
+) -> Result<(LogicalReplicationStream, JoinHandle<()>), Box<dyn Error>> {
+    let query = format!(
+        "START_REPLICATION SLOT {} LOGICAL {} (proto_version '1', publication_names 'pub')",
+        slot, start_lsn
+    );
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        let (client, conn) = connect_replication(server).await?;
+        match client.copy_both_simple(&query).await {
+            Ok(stream) => return Ok((LogicalReplicationStream::new(stream), conn)),
+            Err(e) if e.to_string().contains("is active") && Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(100)).await
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Reads the next transaction from a replication stream, returning the values
+/// of the rows it inserts and deletes, in text form, and the LSN at which the
+/// transaction ends.
+async fn next_transaction(
+    mut stream: Pin<&mut LogicalReplicationStream>,
+) -> Result<(Vec<String>, Vec<String>, PgLsn), Box<dyn Error>> {
+    let text = |tuple: &[TupleData]| match tuple {
+        [TupleData::Text(value)] => String::from_utf8_lossy(value).into_owned(),
+        _ => panic!("unexpected tuple {:?}", tuple),
+    };
+    let mut inserts = vec![];
+    let mut deletes = vec![];
+    while let Some(message) = stream.try_next().await? {
+        let message = match message {
+            ReplicationMessage::XLogData(data) => data.into_data(),
+            _ => continue,
+        };
+        match message {
+            LogicalReplicationMessage::Insert(insert) => {
+                inserts.push(text(insert.tuple().tuple_data()))
+            }
+            LogicalReplicationMessage::Delete(delete) => {
+                deletes.push(text(delete.old_tuple().unwrap().tuple_data()))
+            }
+            LogicalReplicationMessage::Commit(commit) => {
+                return Ok((inserts, deletes, commit.end_lsn().into()))
+            }
+            _ => (),
+        }
+    }
+    Err("replication ended".into())
+}
+
+// Tests that a publication is replicated from a slot, starting with a
+// snapshot, and that a subscriber that reconnects resumes from the position
+// that it confirmed, neither missing nor duplicating updates.
+#[test]
+fn test_logical_replication() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default()
+        .experimental_mode()
+        .data_directory(data_dir.path());
+    let server = util::start_server(config.clone())?;
+    server.runtime.block_on(async {
+        let (client, _) = server.connect_async(postgres::NoTls).await?;
+        client
+            .batch_execute(
+                "CREATE TABLE t (a int);
+                 INSERT INTO t VALUES (1);
+                 CREATE MATERIALIZED VIEW v AS SELECT a FROM t;
+                 CREATE PUBLICATION pub FOR VIEW v;",
+            )
+            .await?;
+
+        let (replication, _) = connect_replication(&server).await?;
+        replication
+            .simple_query("CREATE_REPLICATION_SLOT sub LOGICAL pgoutput NOEXPORT_SNAPSHOT")
+            .await?;
+
+        // The first replication from the slot begins with a snapshot.
+        let (stream, conn) = start_replication(&server, "sub", PgLsn::from(0)).await?;
+        tokio::pin!(stream);
+        let (inserts, deletes, _) = next_transaction(stream.as_mut()).await?;
+        assert_eq!((inserts, deletes), (vec!["1".to_string()], vec![]));
+
+        client.batch_execute("INSERT INTO t VALUES (2)").await?;
+        let (inserts, _, lsn) = next_transaction(stream.as_mut()).await?;
+        assert_eq!(inserts, vec!["2"]);
+        stream
+            .as_mut()
+            .standby_status_update(lsn, lsn, lsn, 0, 0)
+            .await?;
+
+        // Updates made while the subscriber is disconnected are replicated
+        // once it resumes from the position it confirmed.
+        conn.abort();
+        client
+            .batch_execute("INSERT INTO t VALUES (3); DELETE FROM t WHERE a = 1")
+            .await?;
+        let (stream, _) = start_replication(&server, "sub", lsn).await?;
+        tokio::pin!(stream);
+        let (mut inserts, mut deletes, _) = next_transaction(stream.as_mut()).await?;
+        // The insert and the delete may be replicated in one transaction or
+        // in two.
+        if inserts.is_empty() || deletes.is_empty() {
+            let (more_inserts, more_deletes, _) = next_transaction(stream.as_mut()).await?;
+            inserts.extend(more_inserts);
+            deletes.extend(more_deletes);
+        }
+        assert_eq!(
+            (inserts, deletes),
+            (vec!["3".to_string()], vec!["1".to_string()])
+        );
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+    drop(server);
+
+    // The slot survives a restart. The table's contents do not, so its
+    // subscriber cannot resume, and must recreate the slot.
+    let server = util::start_server(config)?;
+    server.runtime.block_on(async {
+        let (replication, _) = connect_replication(&server).await?;
+        let err = replication
+            .simple_query("CREATE_REPLICATION_SLOT sub LOGICAL pgoutput NOEXPORT_SNAPSHOT")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+        let err = start_replication(&server, "sub", PgLsn::from(0))
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("replication slot \"sub\" has been invalidated"),
+            "{}",
+            err
+        );
+        replication
+            .simple_query("DROP_REPLICATION_SLOT sub")
+            .await?;
+        replication
+            .simple_query("CREATE_REPLICATION_SLOT sub LOGICAL pgoutput NOEXPORT_SNAPSHOT")
+            .await?;
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
+// Tests that a slot whose subscriber falls too far behind is invalidated, so
+// that it no longer holds back compaction.
+#[test]
+fn test_logical_replication_max_lag() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let config = util::Config::default()
+        .experimental_mode()
+        .max_replication_slot_lag(Duration::from_secs(1));
+    let server = util::start_server(config)?;
+    server.runtime.block_on(async {
+        let (client, _) = server.connect_async(postgres::NoTls).await?;
+        client
+            .batch_execute(
+                "CREATE TABLE t (a int);
+                 INSERT INTO t VALUES (1);
+                 CREATE MATERIALIZED VIEW v AS SELECT a FROM t;
+                 CREATE PUBLICATION pub FOR VIEW v;",
+            )
+            .await?;
+        let (replication, _) = connect_replication(&server).await?;
+        replication
+            .simple_query("CREATE_REPLICATION_SLOT sub LOGICAL pgoutput NOEXPORT_SNAPSHOT")
+            .await?;
+
+        // The subscriber never confirms its position, so the replication
+        // ends with an error once the slot is invalidated.
+        let (stream, _) = start_replication(&server, "sub", PgLsn::from(0)).await?;
+        tokio::pin!(stream);
+        let err = loop {
+            match stream.try_next().await {
+                Ok(Some(_)) => (),
+                Ok(None) => panic!("replication ended without an error"),
+                Err(e) => break e,
+            }
+        };
+        assert!(
+            err.to_string()
+                .contains("replication slot \"sub\" was invalidated because its subscriber fell more than 1s behind"),
+            "{}",
+            err
+        );
+
+        let err = start_replication(&server, "sub", PgLsn::from(0))
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("replication slot \"sub\" has been invalidated"),
+            "{}",
+            err
+        );
+        Ok::<_, Box<dyn Error>>(())
+    })?;
+
+    Ok(())
+}
+
 fn pg_test_inner(dir: PathBuf) -> Result<(), Box<dyn Error>> {
     // We want a new server per file, so we can't use pgtest::walk.
     datadriven::walk(dir.to_str().unwrap(), |tf| {
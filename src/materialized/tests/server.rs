@@ -11,11 +11,21 @@
 
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use reqwest::{blocking::Client, StatusCode, Url};
 use tempfile::NamedTempFile;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+
+use materialized::SecretsControllerConfig;
+use mz_ore::now::NOW_ZERO;
+use mz_ore::retry::Retry;
+use mz_secrets::{InMemorySecretsController, SecretsController};
 
 use crate::util::{PostgresErrorExt, KAFKA_ADDRS};
 
@@ -99,6 +109,488 @@ fn test_persistence() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Ensures that a backup of the catalog can be restored into a new data
+// directory, that only superusers can back up the catalog, that a data
+// directory is restored only once, and that invalid backups are rejected.
+#[test]
+fn test_catalog_backup_restore() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let backup_dir = tempfile::tempdir()?;
+    let backup = backup_dir.path().join("catalog-backup");
+
+    {
+        let server = util::start_server(util::Config::default())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+        let query = format!("SELECT mz_internal.backup_catalog('{}')", backup.display());
+        assert!(client.query_one(&query, &[])?.get::<_, bool>(0));
+
+        // Backups never overwrite existing files.
+        let err = client.query_one(&query, &[]).unwrap_db_error();
+        assert!(err.message().contains("already exists"), "{}", err);
+
+        client.batch_execute("CREATE ROLE alice LOGIN")?;
+        let mut alice = server.pg_config().user("alice").connect(postgres::NoTls)?;
+        let other = backup_dir.path().join("other-backup");
+        let query = format!("SELECT mz_internal.backup_catalog('{}')", other.display());
+        let err = alice.query_one(&query, &[]).unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have superuser privileges"
+        );
+        assert!(!other.exists());
+    }
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+    {
+        let server = util::start_server(config.clone().restore_catalog(&backup))?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let views = client
+            .query("SHOW VIEWS", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect::<Vec<String>>();
+        assert_eq!(views, &["v"]);
+        client.batch_execute("CREATE VIEW w AS SELECT 2")?;
+    }
+
+    // Restarting with the same configuration must not discard the changes
+    // made since the restore.
+    match util::start_server(config.clone().restore_catalog(&backup)) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert!(e.to_string().contains("already restored"), "{}", e),
+    }
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let views = client
+            .query("SHOW VIEWS", &[])?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect::<Vec<String>>();
+        assert_eq!(views, &["v", "w"]);
+    }
+    fs::remove_file(data_dir.path().join("catalog.restored"))?;
+
+    let not_a_catalog = NamedTempFile::new()?;
+    match util::start_server(config.restore_catalog(not_a_catalog.path())) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert!(e.to_string().contains("cannot restore catalog"), "{}", e),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_check_catalog_migrations() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    {
+        let server = util::start_server(util::Config::default().data_directory(data_dir.path()))?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+    }
+
+    // Checking the migrations must leave the catalog untouched.
+    let catalog_path = data_dir.path().join("catalog");
+    let before = fs::read(&catalog_path)?;
+    Runtime::new()?.block_on(mz_coord::catalog::Catalog::check_migrations(
+        data_dir.path(),
+        None,
+        &materialized::BUILD_INFO,
+        NOW_ZERO.clone(),
+    ))?;
+    assert_eq!(fs::read(&catalog_path)?, before);
+
+    // A catalog that cannot be opened fails the check.
+    let not_a_catalog = tempfile::tempdir()?;
+    fs::write(not_a_catalog.path().join("catalog"), "bogus")?;
+    let res = Runtime::new()?.block_on(mz_coord::catalog::Catalog::check_migrations(
+        not_a_catalog.path(),
+        None,
+        &materialized::BUILD_INFO,
+        NOW_ZERO.clone(),
+    ));
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+// Ensures that a dump of the catalog can be imported into a new data
+// directory, reproducing the dumped catalog, including its IDs and the
+// contents of its secrets.
+#[test]
+fn test_catalog_dump_import() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let runtime = Runtime::new()?;
+    let data_dir = tempfile::tempdir()?;
+    let secrets = InMemorySecretsController::new();
+    {
+        let server = util::start_server(
+            util::Config::default()
+                .data_directory(data_dir.path())
+                .with_secrets_controller(secrets.clone()),
+        )?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute(
+            "CREATE DATABASE d;
+            CREATE SCHEMA d.s;
+            CREATE TABLE d.s.t (a int);
+            CREATE VIEW d.s.gone AS SELECT 1;
+            DROP VIEW d.s.gone;
+            CREATE VIEW d.s.v AS SELECT a + 1 AS b FROM d.s.t;
+            CREATE DEFAULT INDEX ON d.s.v;
+            CREATE SECRET d.s.sec AS 'hunter2'",
+        )?;
+    }
+    let dump = runtime.block_on(materialized::dump_catalog(
+        data_dir.path(),
+        None,
+        Some(SecretsControllerConfig::InMemory(secrets)),
+    ))?;
+    assert_eq!(
+        dump.items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>(),
+        &["t", "v", "v_primary_idx", "sec"]
+    );
+    // The dropped view leaves a gap in the user IDs.
+    let ids: Vec<u64> = dump
+        .items
+        .iter()
+        .map(|item| item.id.to_string()[1..].parse().unwrap())
+        .collect();
+    assert_eq!(ids[1], ids[0] + 2);
+    let sec = &dump.items[3];
+    assert_eq!(
+        sec.secret_contents.as_deref(),
+        Some(&*hex::encode("hunter2"))
+    );
+    assert!(!sec.create_sql.contains("hunter2"), "{}", sec.create_sql);
+
+    let dump_dir = tempfile::tempdir()?;
+    let dump_path = dump_dir.path().join("dump.json");
+    fs::write(&dump_path, serde_json::to_vec(&dump)?)?;
+
+    // A dump that omits the contents of a secret cannot be imported.
+    let mut incomplete = dump.clone();
+    incomplete.items[3].secret_contents = None;
+    let incomplete_path = dump_dir.path().join("incomplete.json");
+    fs::write(&incomplete_path, serde_json::to_vec(&incomplete)?)?;
+    let import_dir = tempfile::tempdir()?;
+    match util::start_server(
+        util::Config::default()
+            .data_directory(import_dir.path())
+            .import_catalog(&incomplete_path),
+    ) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert!(
+            e.to_string()
+                .contains("dump does not include the contents of secret d.s.sec"),
+            "{}",
+            e
+        ),
+    }
+
+    // The failed import did not modify the catalog.
+    let import_secrets = InMemorySecretsController::new();
+    {
+        let server = util::start_server(
+            util::Config::default()
+                .data_directory(import_dir.path())
+                .with_secrets_controller(import_secrets.clone())
+                .import_catalog(&dump_path),
+        )?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("INSERT INTO d.s.t VALUES (1)")?;
+        let b: i32 = client.query_one("SELECT b FROM d.s.v", &[])?.get(0);
+        assert_eq!(b, 2);
+        assert_eq!(
+            server.runtime.block_on(import_secrets.read(sec.id))?,
+            b"hunter2"
+        );
+
+        // New items are assigned IDs that follow the imported items' IDs.
+        client.batch_execute("CREATE VIEW d.s.w AS SELECT 1")?;
+        let id: String = client
+            .query_one("SELECT id FROM mz_objects WHERE name = 'w'", &[])?
+            .get(0);
+        assert_eq!(id, format!("u{}", ids[3] + 1));
+    }
+    let mut imported = runtime.block_on(materialized::dump_catalog(
+        import_dir.path(),
+        None,
+        Some(SecretsControllerConfig::InMemory(import_secrets)),
+    ))?;
+    imported.items.retain(|item| item.name != "w");
+    assert_eq!(imported, dump);
+
+    // A catalog that already contains items cannot be imported into.
+    match util::start_server(
+        util::Config::default()
+            .data_directory(import_dir.path())
+            .import_catalog(&dump_path),
+    ) {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => assert!(e.to_string().contains("already contains items"), "{}", e),
+    }
+
+    Ok(())
+}
+
+// Ensures that an unencrypted catalog is encrypted when materialized is first
+// started with an encryption key, and that it cannot then be opened without
+// that key.
+#[test]
+fn test_catalog_encryption() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+    }
+
+    let catalog_path = data_dir.path().join("catalog");
+    assert!(fs::read(&catalog_path)?.starts_with(b"SQLite format 3\0"));
+    // Stand in for the snapshot that is taken before the catalog is migrated.
+    let snapshot_path = data_dir.path().join("catalog.v1.bak");
+    fs::copy(&catalog_path, &snapshot_path)?;
+    {
+        let server = util::start_server(config.clone().catalog_encryption_key("key"))?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let n: i32 = client.query_one("SELECT * FROM v", &[])?.get(0);
+        assert_eq!(n, 1);
+    }
+    assert!(!fs::read(&catalog_path)?.starts_with(b"SQLite format 3\0"));
+
+    // The snapshot is encrypted along with the catalog, and can still be
+    // restored with the key.
+    assert!(!fs::read(&snapshot_path)?.starts_with(b"SQLite format 3\0"));
+    assert!(!data_dir.path().join("catalog.v1.encrypted").exists());
+    let restore_dir = tempfile::tempdir()?;
+    mz_coord::catalog::storage::Connection::restore(
+        &snapshot_path,
+        restore_dir.path(),
+        Some("key"),
+    )?;
+    let dump = mz_coord::catalog::Catalog::dump_stored(restore_dir.path(), Some("key"))?;
+    assert_eq!(dump.items.len(), 1);
+
+    for (config, message) in [
+        (config.clone(), "catalog is encrypted"),
+        (
+            config.clone().catalog_encryption_key("wrong"),
+            "unable to decrypt catalog",
+        ),
+    ] {
+        match util::start_server(config) {
+            Ok(_) => panic!("unexpected success"),
+            Err(e) => assert!(e.to_string().contains(message), "{}", e),
+        }
+    }
+
+    let dump = mz_coord::catalog::Catalog::dump_stored(data_dir.path(), Some("key"))?;
+    assert_eq!(dump.items.len(), 1);
+
+    Ok(())
+}
+
+// Ensures that the session defaults set with `ALTER ROLE` survive a restart and
+// yield to the parameters that the client specifies.
+#[test]
+fn test_role_defaults_reboot() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute(
+            "CREATE DATABASE d;
+            ALTER ROLE ALL SET cluster = c;
+            ALTER ROLE ALL SET database = materialize;
+            ALTER ROLE materialize SET database = d;
+            ALTER ROLE materialize SET extra_float_digits = 1",
+        )?;
+
+        let err = client
+            .batch_execute("ALTER ROLE materialize SET transaction_isolation = 'read committed'")
+            .unwrap_db_error();
+        assert!(
+            err.message().contains("invalid value for parameter"),
+            "{}",
+            err
+        );
+        let err = client
+            .batch_execute("ALTER ROLE materialize SET server_version = '10.0.0'")
+            .unwrap_db_error();
+        assert!(err.message().contains("cannot be changed"), "{}", err);
+    }
+
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let cluster: String = client.query_one("SHOW cluster", &[])?.get(0);
+        assert_eq!(cluster, "c");
+        let database: String = client.query_one("SHOW database", &[])?.get(0);
+        assert_eq!(database, "d");
+        let digits: String = client.query_one("SHOW extra_float_digits", &[])?.get(0);
+        assert_eq!(digits, "1");
+
+        // `RESET` restores the role's default rather than the global default.
+        client.batch_execute("SET extra_float_digits = 2; RESET extra_float_digits")?;
+        let digits: String = client.query_one("SHOW extra_float_digits", &[])?.get(0);
+        assert_eq!(digits, "1");
+
+        let mut client = server
+            .pg_config()
+            .dbname("materialize")
+            .connect(postgres::NoTls)?;
+        let database: String = client.query_one("SHOW database", &[])?.get(0);
+        assert_eq!(database, "materialize");
+
+        client.batch_execute("ALTER ROLE ALL RESET cluster")?;
+    }
+
+    {
+        let server = util::start_server(config)?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let cluster: String = client.query_one("SHOW cluster", &[])?.get(0);
+        assert_eq!(cluster, "default");
+    }
+
+    Ok(())
+}
+
+// Ensures that the system variables set with `ALTER SYSTEM` survive a restart
+// and take precedence over the command-line options until they are reset.
+#[test]
+fn test_alter_system_reboot() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default()
+        .data_directory(data_dir.path())
+        .peek_queue_timeout(Duration::from_secs(10));
+    let show = |client: &mut postgres::Client, name: &str| -> Result<String, Box<dyn Error>> {
+        Ok(client.query_one(&*format!("SHOW {}", name), &[])?.get(0))
+    };
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute(
+            "ALTER SYSTEM SET max_result_size = 1024;
+            ALTER SYSTEM SET peek_queue_timeout = '1m'",
+        )?;
+    }
+
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        assert_eq!(show(&mut client, "max_result_size")?, "1024");
+        assert_eq!(show(&mut client, "peek_queue_timeout")?, "00:01:00");
+        client.batch_execute(
+            "ALTER SYSTEM RESET max_result_size;
+            ALTER SYSTEM RESET peek_queue_timeout",
+        )?;
+    }
+
+    {
+        let server = util::start_server(config)?;
+        let mut client = server.connect(postgres::NoTls)?;
+        assert_eq!(show(&mut client, "max_result_size")?, "1073741824");
+        assert_eq!(show(&mut client, "peek_queue_timeout")?, "00:00:10");
+    }
+
+    Ok(())
+}
+
+// Ensures that a new metrics scraping interval applies to the running scraper.
+#[test]
+fn test_alter_system_metrics_scraping_interval() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let server = util::start_server(util::Config::default())?;
+    let mut client = server.connect(postgres::NoTls)?;
+    let mut count_readings = || -> Result<i64, Box<dyn Error>> {
+        Ok(client
+            .query_one("SELECT count(*) FROM mz_metrics", &[])?
+            .get(0))
+    };
+
+    // Readings are retained for a second, so they disappear once scraping
+    // stops, and reappear once it resumes.
+    server
+        .connect(postgres::NoTls)?
+        .batch_execute("ALTER SYSTEM SET metrics_scraping_interval = 0")?;
+    Retry::default()
+        .max_duration(Duration::from_secs(10))
+        .retry(|_| match count_readings() {
+            Ok(0) => Ok(()),
+            Ok(n) => Err(format!("{} readings remain", n)),
+            Err(e) => Err(e.to_string()),
+        })?;
+    server
+        .connect(postgres::NoTls)?
+        .batch_execute("ALTER SYSTEM SET metrics_scraping_interval = '100ms'")?;
+    Retry::default()
+        .max_duration(Duration::from_secs(10))
+        .retry(|_| match count_readings() {
+            Ok(0) => Err("no readings".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        })?;
+
+    Ok(())
+}
+
+// Ensures that the versions recorded when the catalog is created are reported
+// by `mz_catalog_versions` and are unchanged by a reboot that applies no
+// migrations.
+#[test]
+fn test_catalog_versions_reboot() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+    let query = "SELECT schema_version, last_migration_at::text, catalog_content_version
+        FROM mz_catalog_versions";
+
+    let versions = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let rows = client.query(query, &[])?;
+        assert_eq!(rows.len(), 1);
+        let schema_version: Option<i64> = rows[0].get(0);
+        let last_migration_at: Option<String> = rows[0].get(1);
+        let catalog_content_version: String = rows[0].get(2);
+        assert!(schema_version.is_some());
+        assert!(last_migration_at.is_some());
+        assert_ne!(catalog_content_version, "new");
+        (schema_version, last_migration_at, catalog_content_version)
+    };
+
+    {
+        let server = util::start_server(config)?;
+        let mut client = server.connect(postgres::NoTls)?;
+        let row = client.query_one(query, &[])?;
+        let rebooted_versions: (Option<i64>, Option<String>, String) =
+            (row.get(0), row.get(1), row.get(2));
+        assert_eq!(rebooted_versions, versions);
+    }
+
+    Ok(())
+}
+
 // Ensures that once a node is started with `--experimental`, it requires
 // `--experimental` on reboot.
 #[test]
@@ -239,6 +731,55 @@ fn test_safe_mode() -> Result<(), Box<dyn Error>> {
         "cannot create Kerberos-authenticated Kafka sink in safe mode"
     );
 
+    // No options that read files on disk.
+    let err = client
+        .batch_execute(
+            "CREATE SOURCE src
+            FROM KAFKA BROKER 'ignored' TOPIC 'ignored'
+            WITH (security_protocol = 'ssl', ssl_key_location = '/ignored')
+            FORMAT BYTES",
+        )
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "cannot create source with option ssl_key_location in safe mode"
+    );
+    let err = client
+        .batch_execute(
+            "CREATE SOURCE src
+            FROM KAFKA BROKER 'ignored' TOPIC 'ignored'
+            FORMAT AVRO USING SCHEMA FILE '/ignored'",
+        )
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "cannot create source with a schema file in safe mode"
+    );
+
+    // No experimental features.
+    let err = client.batch_execute("SHOW CLUSTERS").unwrap_db_error();
+    assert_eq!(err.message(), "cannot use SHOW CLUSTERS in safe mode");
+
+    // No cross joins of unbounded relations, though cross joins with
+    // relations of known size are okay.
+    client.batch_execute("CREATE TABLE t (a int)")?;
+    let err = client
+        .batch_execute("SELECT * FROM t t1, t t2")
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "cannot create cross join of unbounded relations in safe mode"
+    );
+    let err = client
+        .batch_execute("CREATE VIEW v AS SELECT * FROM t t1, t t2 WHERE t1.a < t2.a")
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "cannot create cross join of unbounded relations in safe mode"
+    );
+    client.batch_execute("SELECT * FROM t t1, t t2 WHERE t1.a = t2.a")?;
+    client.batch_execute("SELECT * FROM t, (SELECT count(*) FROM t)")?;
+
     // Non-Kerberos Kafka sources are okay though.
     client.batch_execute(&*format!(
         "CREATE SOURCE src
@@ -250,6 +791,109 @@ fn test_safe_mode() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Ensures that safe mode is persisted: a node started with `--safe` requires
+// `--safe` on reboot, and only new nodes can start in safe mode.
+#[test]
+fn test_safe_mode_reboot() -> Result<(), Box<dyn Error>> {
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+
+    {
+        let _ = util::start_server(config.clone().safe_mode())?;
+    }
+
+    {
+        match util::start_server(config.clone()) {
+            Ok(_) => panic!("unexpected success"),
+            Err(e) => {
+                if !e
+                    .to_string()
+                    .contains("Materialize previously started with --safe")
+                {
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    {
+        let _ = util::start_server(config.safe_mode())?;
+    }
+
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+
+    {
+        let _ = util::start_server(config.clone())?;
+    }
+
+    {
+        match util::start_server(config.safe_mode()) {
+            Ok(_) => panic!("unexpected success"),
+            Err(e) => {
+                if !e
+                    .to_string()
+                    .contains("Safe mode is only available on new nodes")
+                {
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Test that `mz_internal.mz_cpu_profile` returns a CPU profile in the folded
+// format, with each stack labeled by the process in which it was sampled, and
+// that it reports which remote process it could not profile.
+#[test]
+fn test_cpu_profile() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+
+    let server = util::start_server(util::Config::default())?;
+    let mut client = server
+        .pg_config()
+        .user("mz_system")
+        .connect(postgres::NoTls)?;
+
+    // The server runs in this process, so keeping a thread of this process
+    // busy ensures that the profile is not empty.
+    let busy = Arc::new(AtomicBool::new(true));
+    let spinner = {
+        let busy = Arc::clone(&busy);
+        thread::spawn(move || {
+            while busy.load(Ordering::SeqCst) {
+                std::hint::spin_loop();
+            }
+        })
+    };
+    let profile = client.query_one("SELECT mz_internal.mz_cpu_profile('1s')", &[]);
+    busy.store(false, Ordering::SeqCst);
+    spinner.join().unwrap();
+    let profile: String = profile?.get(0);
+    assert!(!profile.is_empty());
+    for line in profile.lines() {
+        let (stack, weight) = line.rsplit_once(' ').expect("line has a weight");
+        assert!(stack.starts_with("materialized;"), "{}", line);
+        assert!(weight.parse::<f64>()? > 0.0, "{}", line);
+    }
+
+    // The replicas of remote clusters are profiled too, so a replica that
+    // cannot be reached fails the profile.
+    client.batch_execute("CREATE CLUSTER unreachable REMOTE r1 ('localhost:1')")?;
+    let err = client
+        .query_one("SELECT mz_internal.mz_cpu_profile('1s')", &[])
+        .unwrap_db_error();
+    assert!(
+        err.message().contains("replica r1 (localhost:1)"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
 // Test the /sql POST endpoint of the HTTP server.
 #[test]
 fn test_http_sql() -> Result<(), Box<dyn Error>> {
@@ -294,6 +938,49 @@ fn test_http_sql() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Test the /clusters endpoints of the HTTP server.
+#[test]
+fn test_http_clusters() -> Result<(), Box<dyn Error>> {
+    let server = util::start_server(util::Config::default().experimental_mode())?;
+    let url = Url::parse(&format!("http://{}/clusters", server.inner.local_addr()))?;
+
+    let spec = Client::new().get(url.clone()).send()?.text()?;
+    let json: serde_json::Value = serde_json::from_str(&spec)?;
+    assert_eq!(json["clusters"]["default"], "Local");
+
+    // Applying the exported spec changes nothing.
+    let res = Client::new().put(url.clone()).body(spec.clone()).send()?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text()?, r#"{"created":[],"altered":[],"dropped":[]}"#);
+
+    // A dry run reports the changes without making them.
+    let mut changed = json.clone();
+    changed["clusters"]["c1"] = serde_json::json!({
+        "Remote": {"replicas": {"r1": ["localhost:2101"]}, "introspection": null}
+    });
+    let mut dry_run_url = url.clone();
+    dry_run_url.set_query(Some("dry_run=true"));
+    let res = Client::new()
+        .put(dry_run_url)
+        .body(changed.to_string())
+        .send()?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.text()?,
+        r#"{"created":["c1"],"altered":[],"dropped":[]}"#
+    );
+    assert_eq!(Client::new().get(url.clone()).send()?.text()?, spec);
+
+    // Applying a spec requires experimental mode.
+    let server = util::start_server(util::Config::default())?;
+    let url = Url::parse(&format!("http://{}/clusters", server.inner.local_addr()))?;
+    let res = Client::new().put(url).body(spec).send()?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(res.text()?.contains("requires experimental mode"));
+
+    Ok(())
+}
+
 // Test that the server properly handles cancellation requests.
 #[test]
 fn test_cancel_long_running_query() -> Result<(), Box<dyn Error>> {
@@ -323,3 +1010,193 @@ fn test_cancel_long_running_query() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+// Test that queries beyond a cluster's peek concurrency limit wait for a slot,
+// and fail once the peek queue timeout elapses.
+#[test]
+fn test_peek_concurrency_limit() -> Result<(), Box<dyn Error>> {
+    let config = util::Config::default()
+        .peek_concurrency_limit(1)
+        .peek_queue_timeout(Duration::from_secs(2));
+    let server = util::start_server(config)?;
+
+    let mut client = server.connect(postgres::NoTls)?;
+    client.batch_execute("CREATE TABLE t (i INT)")?;
+
+    // Occupy the only slot on the default cluster.
+    let mut blocking_client = server.connect(postgres::NoTls)?;
+    let cancel_token = blocking_client.cancel_token();
+    let blocking_query =
+        thread::spawn(move || blocking_client.simple_query("SELECT * FROM t AS OF now()+'1h'"));
+    thread::sleep(Duration::from_secs(1));
+
+    // A query that waits longer than the peek queue timeout fails.
+    let err = client
+        .simple_query("SELECT * FROM t")
+        .unwrap_err()
+        .unwrap_db_error();
+    assert!(err
+        .message()
+        .starts_with("query timed out after 2s waiting to run on cluster"));
+
+    // A waiting query runs as soon as the slot frees up, well before the
+    // peek queue timeout elapses.
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        let _ = cancel_token.cancel_query(postgres::NoTls);
+    });
+    let start = Instant::now();
+    client.simple_query("SELECT * FROM t")?;
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(blocking_query.join().unwrap().is_err());
+
+    Ok(())
+}
+
+// Test that peek concurrency limits apply to each cluster separately, and that
+// a cluster's own limit overrides the default limit.
+#[test]
+fn test_peek_concurrency_limit_per_cluster() -> Result<(), Box<dyn Error>> {
+    let config = util::Config::default()
+        .peek_concurrency_limit(1)
+        .cluster_peek_concurrency_limit("default", 2)
+        .peek_queue_timeout(Duration::from_secs(2));
+    let server = util::start_server(config)?;
+
+    let mut client = server.connect(postgres::NoTls)?;
+    client.batch_execute("CREATE TABLE t (i INT)")?;
+    client.batch_execute("CREATE CLUSTER other REMOTE r1 ('localhost:1234')")?;
+
+    let mut blocking_clients = vec![];
+    for _ in 0..2 {
+        let mut blocking_client = server.connect(postgres::NoTls)?;
+        let cancel_token = blocking_client.cancel_token();
+        let query =
+            thread::spawn(move || blocking_client.simple_query("SELECT * FROM t AS OF now()+'1h'"));
+        blocking_clients.push((cancel_token, query));
+    }
+    thread::sleep(Duration::from_secs(1));
+
+    // Both blocking queries run on the default cluster, whose limit of two
+    // overrides the default limit of one, and so its slots are now full.
+    assert!(client.simple_query("SELECT * FROM t").is_err());
+
+    // The other cluster is unaffected by the load on the default cluster. The
+    // query is constant, and so needs no replica to answer it.
+    client.batch_execute("SET cluster = other")?;
+    client.simple_query("SELECT 1")?;
+
+    for (cancel_token, query) in blocking_clients {
+        cancel_token.cancel_query(postgres::NoTls)?;
+        assert!(query.join().unwrap().is_err());
+    }
+
+    Ok(())
+}
+
+// Test that `pg_reload_conf()` requests a configuration reload, and that only
+// superusers may request one.
+#[test]
+fn test_reload_conf() -> Result<(), Box<dyn Error>> {
+    let (config_reload_tx, mut config_reload_rx) = mpsc::unbounded_channel();
+    let config = util::Config::default().with_config_reload_tx(config_reload_tx);
+    let server = util::start_server(config)?;
+
+    let mut client = server.connect(postgres::NoTls)?;
+    client.batch_execute("CREATE ROLE alice LOGIN")?;
+    let mut alice = server.pg_config().user("alice").connect(postgres::NoTls)?;
+    let err = alice
+        .query_one("SELECT pg_reload_conf()", &[])
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"alice\" does not have superuser privileges"
+    );
+    assert!(config_reload_rx.try_recv().is_err());
+
+    let reloaded: bool = client.query_one("SELECT pg_reload_conf()", &[])?.get(0);
+    assert!(reloaded);
+    assert!(config_reload_rx.try_recv().is_ok());
+
+    // The function has side effects, so it cannot be part of a larger query.
+    let err = client
+        .query_one("SELECT pg_reload_conf() AND true", &[])
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "pg_reload_conf must be called on its own, as in SELECT pg_reload_conf()"
+    );
+    assert!(config_reload_rx.try_recv().is_err());
+
+    Ok(())
+}
+
+// Test that connections beyond the connection limit are refused, except those
+// of the system user.
+#[test]
+fn test_max_connections() -> Result<(), Box<dyn Error>> {
+    let config = util::Config::default().max_connections(2);
+    let server = util::start_server(config)?;
+
+    let client = server.connect(postgres::NoTls)?;
+    let _other_client = server.connect(postgres::NoTls)?;
+    let err = server
+        .pg_config()
+        .connect(postgres::NoTls)
+        .unwrap_db_error();
+    assert_eq!(err.message(), "too many connections: the limit is 2");
+
+    let mut system_client = server
+        .pg_config()
+        .user("mz_system")
+        .connect(postgres::NoTls)?;
+    system_client.simple_query("SELECT 1")?;
+
+    // Closing a connection frees up its slot once the server notices.
+    drop(system_client);
+    drop(client);
+    Retry::default()
+        .max_duration(Duration::from_secs(10))
+        .retry(|_| server.connect(postgres::NoTls))?;
+
+    Ok(())
+}
+
+#[test]
+fn test_drain() -> Result<(), Box<dyn Error>> {
+    let config = util::Config::default();
+    let mut server = util::start_server(config)?;
+
+    let mut idle_client = server.connect(postgres::NoTls)?;
+    idle_client.batch_execute("CREATE TABLE t (i INT)")?;
+
+    // A query that finishes within the grace period and one that does not.
+    let mut short_client = server.connect(postgres::NoTls)?;
+    let short_query =
+        thread::spawn(move || short_client.simple_query("SELECT * FROM t AS OF now()+'3s'"));
+    let mut long_client = server.connect(postgres::NoTls)?;
+    let long_query =
+        thread::spawn(move || long_client.simple_query("SELECT * FROM t AS OF now()+'1h'"));
+    thread::sleep(Duration::from_secs(1));
+
+    let grace_period = Duration::from_secs(10);
+    let start = Instant::now();
+    server
+        .runtime
+        .clone()
+        .block_on(server.inner.drain(grace_period));
+    assert!(start.elapsed() >= grace_period);
+
+    // The short query completed, while the long query was cut off when the
+    // grace period elapsed.
+    assert!(short_query.join().unwrap().is_ok());
+    assert!(long_query.join().unwrap().is_err());
+
+    // The idle connection was closed as soon as the drain began.
+    assert!(idle_client.is_closed());
+
+    // New connections are rejected.
+    assert!(server.connect(postgres::NoTls).is_err());
+
+    Ok(())
+}
@@ -30,6 +30,8 @@ use chrono::{DateTime, Utc};
 use mz_ore::now::NowFn;
 use mz_ore::now::NOW_ZERO;
 use mz_ore::now::SYSTEM_TIME;
+use mz_ore::retry::Retry;
+use mz_secrets::{InMemorySecretsController, SecretOp, SecretVersion, SecretsController};
 use postgres::Row;
 use regex::Regex;
 use tempfile::NamedTempFile;
@@ -1083,3 +1085,744 @@ write frontier:[            0]\n",
 
     Ok(())
 }
+
+// Test that secret DDL applies the expected operations to the secrets
+// controller.
+#[test]
+fn test_secret_ddl() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let secrets_controller = InMemorySecretsController::new();
+    let config = util::Config::default().with_secrets_controller(secrets_controller.clone());
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+
+    client.batch_execute("CREATE SECRET s AS decode('aHVudGVyMg==', 'base64')")?;
+    let id = match &secrets_controller.ops()[..] {
+        [SecretOp::Ensure { id, contents }] if contents == b"hunter2" => *id,
+        ops => panic!("unexpected secret operations: {:?}", ops),
+    };
+    assert_eq!(
+        server.runtime.block_on(secrets_controller.read(id))?,
+        b"hunter2"
+    );
+    assert_eq!(
+        server
+            .runtime
+            .block_on(secrets_controller.current_version(id))?,
+        SecretVersion(1)
+    );
+
+    // Dropped secrets are deleted from storage after the drop commits.
+    client.batch_execute("DROP SECRET s")?;
+    wait_until(|| Ok(secrets_controller.ops().len() == 2))?;
+    assert_eq!(
+        secrets_controller.ops()[1..],
+        [SecretOp::Delete { id, version: None }]
+    );
+    assert!(server
+        .runtime
+        .block_on(secrets_controller.list())?
+        .is_empty());
+
+    Ok(())
+}
+
+// Test that a write of a secret that fails transiently is retried, and that
+// a secret whose write keeps failing is not created, but is audited.
+#[test]
+fn test_secret_ddl_rollback() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let secrets_controller = InMemorySecretsController::new();
+    let config = util::Config::default().with_secrets_controller(secrets_controller.clone());
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+
+    secrets_controller.fail_applies(2);
+    client.batch_execute("CREATE SECRET retried AS 'hunter2'")?;
+    let id = match &secrets_controller.ops()[..] {
+        [SecretOp::Ensure { id, contents }] if contents == b"hunter2" => *id,
+        ops => panic!("unexpected secret operations: {:?}", ops),
+    };
+    assert_eq!(
+        server.runtime.block_on(secrets_controller.list())?,
+        vec![id]
+    );
+
+    secrets_controller.fail_applies(3);
+    let err = client
+        .batch_execute("CREATE SECRET failed AS 'hunter3'")
+        .unwrap_db_error();
+    assert_contains!(err.message(), "secrets storage unavailable");
+    let count: i64 = client
+        .query_one("SELECT count(*) FROM mz_secrets WHERE name = 'failed'", &[])?
+        .get(0);
+    assert_eq!(count, 0);
+    assert_eq!(
+        server.runtime.block_on(secrets_controller.list())?,
+        vec![id]
+    );
+
+    // The failed write is recorded along with its error, as it may have been
+    // applied in part.
+    let errors: Vec<(Option<i64>, Option<String>)> = client
+        .query(
+            "SELECT version, error FROM mz_secret_audit_events ORDER BY id",
+            &[],
+        )?
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect();
+    match &errors[..] {
+        [(Some(1), None), (None, Some(error))] => {
+            assert_contains!(error, "secrets storage unavailable")
+        }
+        errors => panic!("unexpected secret audit events: {:?}", errors),
+    }
+
+    // The statement can be reissued once storage recovers.
+    client.batch_execute("CREATE SECRET failed AS 'hunter3'")?;
+    assert_eq!(server.runtime.block_on(secrets_controller.list())?.len(), 2);
+
+    Ok(())
+}
+
+// Test that operations on secrets storage are recorded in
+// `mz_secret_audit_events`, and that the record survives restarts.
+#[test]
+fn test_secret_audit_events() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+
+    let id: String = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE SECRET s AS 'hunter2'")?;
+        let id = client
+            .query_one("SELECT id FROM mz_secrets WHERE name = 's'", &[])?
+            .get(0);
+        client.batch_execute("DROP SECRET s")?;
+        wait_until(|| {
+            let count: i64 = client
+                .query_one("SELECT count(*) FROM mz_secret_audit_events", &[])?
+                .get(0);
+            Ok(count == 2)
+        })?;
+        id
+    };
+
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+    let events: Vec<(String, String, Option<i64>, String)> = client
+        .query(
+            "SELECT secret_id, operation, version, \"user\"
+            FROM mz_secret_audit_events
+            ORDER BY id",
+            &[],
+        )?
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (id.clone(), "ensure".into(), Some(1), "materialize".into()),
+            (id, "delete".into(), None, "materialize".into()),
+        ]
+    );
+
+    Ok(())
+}
+
+// Test that statistics survive restarts, so that the plans that consult them
+// do not change.
+#[test]
+fn test_statistics_restart() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+    let stats_query = "SELECT mz_indexes.name, row_count, distinct_keys
+        FROM mz_statistics JOIN mz_indexes ON index_id = mz_indexes.id
+        ORDER BY 1";
+    let plan_query = "EXPLAIN SELECT * FROM a JOIN b USING (x)";
+
+    let read_stats = |client: &mut postgres::Client| -> Result<_, Box<dyn Error>> {
+        let stats: Vec<(String, i64, i64)> = client
+            .query(stats_query, &[])?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        Ok(stats)
+    };
+    let expected = vec![
+        ("a_primary_idx".to_string(), 3, 3),
+        ("b_primary_idx".to_string(), 1, 1),
+    ];
+
+    let plan: String = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute(
+            "CREATE TABLE a (x int);
+             CREATE TABLE b (x int);
+             INSERT INTO a VALUES (1), (2), (3);
+             INSERT INTO b VALUES (1);
+             CREATE DEFAULT INDEX ON a;
+             CREATE DEFAULT INDEX ON b;",
+        )?;
+        // Statistics are collected as of a time that the indexes can be read
+        // at without waiting, which may precede the inserts.
+        wait_until(|| {
+            client.batch_execute("SELECT mz_internal.collect_statistics()")?;
+            Ok(read_stats(&mut client)? == expected)
+        })?;
+        client.query_one(plan_query, &[])?.get(0)
+    };
+
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+    assert_eq!(read_stats(&mut client)?, expected);
+    let restored_plan: String = client.query_one(plan_query, &[])?.get(0);
+    assert_eq!(restored_plan, plan);
+
+    Ok(())
+}
+
+// Test that changes to catalog objects are recorded in `mz_audit_events`, and
+// that the record survives restarts.
+#[test]
+fn test_audit_events() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+
+    let id: String = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE VIEW v AS SELECT 1")?;
+        let id = client
+            .query_one("SELECT id FROM mz_views WHERE name = 'v'", &[])?
+            .get(0);
+        client.batch_execute("ALTER VIEW v RENAME TO w")?;
+        client.batch_execute("DROP VIEW w")?;
+        // Temporary items are not durable, so changes to them are not
+        // recorded.
+        client.batch_execute("CREATE TEMPORARY VIEW t AS SELECT 1")?;
+        id
+    };
+
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+    let events: Vec<(String, String, String, String, String)> = client
+        .query(
+            "SELECT event_type, object_type, object_id, name, \"user\"
+            FROM mz_audit_events
+            WHERE object_type = 'view'
+            ORDER BY id",
+            &[],
+        )?
+        .into_iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3), row.get(4)))
+        .collect();
+    let event = |event_type: &str, name: &str| {
+        (
+            event_type.into(),
+            "view".into(),
+            id.clone(),
+            name.into(),
+            "materialize".into(),
+        )
+    };
+    assert_eq!(
+        events,
+        vec![
+            event("create", "materialize.public.v"),
+            event("alter", "materialize.public.w"),
+            event("drop", "materialize.public.w"),
+        ]
+    );
+
+    let previous_name: String = client
+        .query_one(
+            "SELECT details->>'previous_name' FROM mz_audit_events
+            WHERE object_id = $1 AND event_type = 'alter'",
+            &[&id],
+        )?
+        .get(0);
+    assert_eq!(previous_name, "materialize.public.v");
+
+    Ok(())
+}
+
+// Test that roles that are not superusers are limited to the privileges that
+// they have been granted, and that grants survive restarts.
+#[test]
+fn test_privileges() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let config = util::Config::default().data_directory(data_dir.path());
+
+    {
+        let server = util::start_server(config.clone())?;
+        let mut admin = server.connect(postgres::NoTls)?;
+        admin.batch_execute(
+            "CREATE ROLE alice LOGIN;
+            CREATE TABLE t (a int);
+            INSERT INTO t VALUES (1);",
+        )?;
+        let mut alice = server.pg_config().user("alice").connect(postgres::NoTls)?;
+
+        let err = alice.query("SELECT * FROM t", &[]).unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have SELECT privilege on table \"materialize.public.t\""
+        );
+        // Explaining a query requires the privileges to run it, as `EXPLAIN
+        // ANALYZE` runs it.
+        for stmt in ["EXPLAIN ANALYZE SELECT * FROM t", "EXPLAIN SELECT * FROM t"] {
+            let err = alice.query(stmt, &[]).unwrap_db_error();
+            assert_eq!(
+                err.message(),
+                "role \"alice\" does not have SELECT privilege on table \"materialize.public.t\""
+            );
+        }
+        admin.batch_execute("GRANT SELECT ON t TO alice")?;
+        assert_eq!(alice.query("SELECT * FROM t", &[])?.len(), 1);
+        alice.query("EXPLAIN ANALYZE SELECT * FROM t", &[])?;
+
+        let err = alice
+            .batch_execute("INSERT INTO t VALUES (2)")
+            .unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have INSERT privilege on table \"materialize.public.t\""
+        );
+        let err = alice.batch_execute("DROP TABLE t").unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have ownership of table \"materialize.public.t\""
+        );
+        let err = alice
+            .batch_execute("CREATE VIEW v AS SELECT * FROM t")
+            .unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have CREATE privilege on schema \"materialize.public\""
+        );
+
+        admin.batch_execute("GRANT CREATE ON SCHEMA public TO alice")?;
+        alice.batch_execute("CREATE VIEW v AS SELECT * FROM t")?;
+        assert_eq!(alice.query("SELECT * FROM v", &[])?.len(), 1);
+
+        // The view reads from the table with the privileges of its owner.
+        admin.batch_execute("REVOKE SELECT ON t FROM alice")?;
+        let err = alice.query("SELECT * FROM v", &[]).unwrap_db_error();
+        assert_eq!(
+            err.message(),
+            "role \"alice\" does not have SELECT privilege on table \"materialize.public.t\""
+        );
+        admin.batch_execute("GRANT SELECT ON t TO PUBLIC")?;
+    }
+
+    let server = util::start_server(config)?;
+    let mut alice = server.pg_config().user("alice").connect(postgres::NoTls)?;
+    assert_eq!(alice.query("SELECT * FROM v", &[])?.len(), 1);
+    alice.batch_execute("CREATE VIEW w AS SELECT 1")?;
+    let err = alice
+        .batch_execute("CREATE ROLE bob LOGIN")
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"alice\" does not have superuser privileges"
+    );
+
+    Ok(())
+}
+
+// Test that roles see the SQL of only their own statements in
+// `mz_dataflow_statements`, and that only superusers can read the unredacted
+// `mz_internal.mz_all_dataflow_statements`.
+#[test]
+fn test_dataflow_statements_privacy() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let server = util::start_server(util::Config::default())?;
+    let mut admin = server.connect(postgres::NoTls)?;
+    admin.batch_execute(
+        "CREATE ROLE alice LOGIN;
+        GRANT CREATE ON SCHEMA public TO alice;
+        CREATE TABLE t (a int);
+        CREATE MATERIALIZED VIEW admin_view AS SELECT count(*) FROM t;",
+    )?;
+    let mut alice = server.pg_config().user("alice").connect(postgres::NoTls)?;
+    alice.batch_execute(
+        "CREATE TABLE u (a int);
+        CREATE MATERIALIZED VIEW alice_view AS SELECT count(*) FROM u;",
+    )?;
+
+    // The table is updated periodically.
+    let query = "SELECT dataflow_name, sql IS NOT NULL
+        FROM mz_dataflow_statements
+        WHERE dataflow_name LIKE 'Dataflow: materialize.public.%_view_primary_idx'
+        ORDER BY dataflow_name";
+    let mut rows = vec![];
+    for _ in 0..100 {
+        rows = alice.query(query, &[])?;
+        if rows.len() == 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let rows: Vec<(String, bool)> = rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "Dataflow: materialize.public.admin_view_primary_idx".into(),
+                false
+            ),
+            (
+                "Dataflow: materialize.public.alice_view_primary_idx".into(),
+                true
+            ),
+        ]
+    );
+
+    let err = alice
+        .query("SELECT * FROM mz_internal.mz_all_dataflow_statements", &[])
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"alice\" does not have superuser privileges to read \"mz_internal.mz_all_dataflow_statements\""
+    );
+    // Nor can the table be read through a view that the role owns.
+    alice.batch_execute(
+        "CREATE VIEW leak AS SELECT * FROM mz_internal.mz_all_dataflow_statements",
+    )?;
+    assert!(alice.query("SELECT * FROM leak", &[]).is_err());
+
+    let rows = admin.query(
+        "SELECT \"user\", sql IS NOT NULL
+        FROM mz_internal.mz_all_dataflow_statements
+        WHERE dataflow_name LIKE 'Dataflow: materialize.public.%_view_primary_idx'
+        ORDER BY dataflow_name",
+        &[],
+    )?;
+    let rows: Vec<(String, bool)> = rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+    assert_eq!(
+        rows,
+        vec![("materialize".into(), true), ("alice".into(), true)]
+    );
+
+    Ok(())
+}
+
+// Test that recomputing a materialization rebuilds its indexes in place.
+#[test]
+fn test_recompute_materialization() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let server = util::start_server(util::Config::default())?;
+    let mut client = server.connect(postgres::NoTls)?;
+    client.batch_execute(
+        "CREATE TABLE t (a int);
+        INSERT INTO t VALUES (1), (2);
+        CREATE MATERIALIZED VIEW v AS SELECT sum(a) FROM t;",
+    )?;
+    let view_id: String = client
+        .query_one("SELECT id FROM mz_views WHERE name = 'v'", &[])?
+        .get(0);
+    let index_id: String = client
+        .query_one(
+            "SELECT id FROM mz_indexes WHERE name = 'v_primary_idx'",
+            &[],
+        )?
+        .get(0);
+
+    for id in [&view_id, &index_id] {
+        let recomputed: bool = client
+            .query_one(
+                &*format!("SELECT mz_internal.recompute_materialization('{}')", id),
+                &[],
+            )?
+            .get(0);
+        assert!(recomputed);
+        // The index keeps its ID and its contents, and continues to be
+        // maintained.
+        let n: i64 = client.query_one("SELECT * FROM v", &[])?.get(0);
+        assert_eq!(n, 3);
+        client.batch_execute("INSERT INTO t VALUES (1)")?;
+        client.batch_execute("DELETE FROM t WHERE a = 1")?;
+        client.batch_execute("INSERT INTO t VALUES (1)")?;
+        let n: i64 = client.query_one("SELECT * FROM v", &[])?.get(0);
+        assert_eq!(n, 3);
+        let count: i64 = client
+            .query_one(
+                "SELECT count(*) FROM mz_indexes WHERE on_id = $1",
+                &[&view_id],
+            )?
+            .get(0);
+        assert_eq!(count, 1);
+    }
+
+    // A relation without indexes cannot be recomputed.
+    let table_id: String = client
+        .query_one("SELECT id FROM mz_tables WHERE name = 't'", &[])?
+        .get(0);
+    client.batch_execute("CREATE VIEW w AS SELECT 1")?;
+    let view_id: String = client
+        .query_one("SELECT id FROM mz_views WHERE name = 'w'", &[])?
+        .get(0);
+    let err = client
+        .query_one(
+            &*format!(
+                "SELECT mz_internal.recompute_materialization('{}')",
+                view_id
+            ),
+            &[],
+        )
+        .unwrap_db_error();
+    assert_eq!(err.message(), "materialize.public.w is not materialized");
+    let recomputed: bool = client
+        .query_one(
+            &*format!(
+                "SELECT mz_internal.recompute_materialization('{}')",
+                table_id
+            ),
+            &[],
+        )?
+        .get(0);
+    assert!(recomputed);
+    assert_eq!(client.query("SELECT * FROM t", &[])?.len(), 2);
+
+    Ok(())
+}
+
+// Test that connection and cluster restrictions on roles are enforced.
+#[test]
+fn test_role_restrictions() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let server = util::start_server(util::Config::default())?;
+    let mut admin = server.connect(postgres::NoTls)?;
+    admin.batch_execute(
+        "CREATE ROLE tls LOGIN SUPERUSER REQUIRE TLS;
+        CREATE ROLE remote LOGIN SUPERUSER ALLOWED ADDRESSES ('10.0.0.0/8');
+        CREATE ROLE local LOGIN SUPERUSER ALLOWED ADDRESSES ('127.0.0.1', '::1');
+        CREATE CLUSTER other REMOTE r1 ('localhost:1234');
+        CREATE ROLE confined LOGIN SUPERUSER ALLOWED CLUSTERS (other);
+        CREATE TABLE t (a int);",
+    )?;
+
+    let err = server
+        .pg_config()
+        .user("tls")
+        .connect(postgres::NoTls)
+        .unwrap_db_error();
+    assert_eq!(err.message(), "role \"tls\" may not connect without TLS");
+
+    let err = server
+        .pg_config()
+        .user("remote")
+        .connect(postgres::NoTls)
+        .unwrap_db_error();
+    assert_contains!(err.message(), "role \"remote\" may not connect from ");
+    server.pg_config().user("local").connect(postgres::NoTls)?;
+
+    // The default cluster is not permitted, so the session may not start.
+    let err = server
+        .pg_config()
+        .user("confined")
+        .connect(postgres::NoTls)
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"confined\" is not permitted to use cluster \"default\""
+    );
+    admin.batch_execute("ALTER ROLE confined SET cluster = other")?;
+    let mut confined = server
+        .pg_config()
+        .user("confined")
+        .connect(postgres::NoTls)?;
+    assert_eq!(confined.query_one("SELECT 1", &[])?.get::<_, i32>(0), 1);
+    let err = confined
+        .batch_execute("SET cluster = 'default'")
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"confined\" is not permitted to use cluster \"default\""
+    );
+    // Clusters named explicitly are restricted too.
+    let err = confined
+        .batch_execute("CREATE INDEX i IN CLUSTER default ON t (a)")
+        .unwrap_db_error();
+    assert_eq!(
+        err.message(),
+        "role \"confined\" is not permitted to use cluster \"default\""
+    );
+
+    Ok(())
+}
+
+// Test that the versions of secrets in `mz_secrets` are read from secrets
+// storage at startup, and that storage that cannot list its secrets is left
+// alone.
+#[test]
+fn test_secret_versions_from_storage() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let secrets_controller = InMemorySecretsController::new();
+    let config = util::Config::default()
+        .data_directory(data_dir.path())
+        .with_secrets_controller(secrets_controller.clone());
+    let version = |client: &mut postgres::Client| -> Result<Option<i64>, postgres::Error> {
+        Ok(client
+            .query_one("SELECT version FROM mz_secrets WHERE name = 's'", &[])?
+            .get(0))
+    };
+
+    let id = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE SECRET s AS 'hunter1'")?;
+        assert_eq!(version(&mut client)?, Some(1));
+
+        // Rotate the secret behind the catalog's back.
+        let id = server.runtime.block_on(secrets_controller.list())?[0];
+        server
+            .runtime
+            .block_on(secrets_controller.apply(vec![SecretOp::Ensure {
+                id,
+                contents: b"hunter2".to_vec(),
+            }]))?;
+        id
+    };
+
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        assert_eq!(version(&mut client)?, Some(2));
+    }
+
+    // Storage that cannot list its secrets makes every secret look missing,
+    // so it is not reconciled at all.
+    secrets_controller.disable_listing();
+    let ops = secrets_controller.ops().len();
+    let server = util::start_server(config)?;
+    let mut client = server.connect(postgres::NoTls)?;
+    assert_eq!(version(&mut client)?, None);
+    assert_eq!(secrets_controller.ops().len(), ops);
+    let err = client
+        .query_one("SELECT mz_internal.gc_secrets()", &[])
+        .unwrap_db_error();
+    assert_contains!(err.message(), "secrets storage cannot list secrets");
+    assert_eq!(
+        server.runtime.block_on(secrets_controller.read(id))?,
+        b"hunter2"
+    );
+
+    Ok(())
+}
+
+// Test that secrets storage is reconciled with the catalog at startup.
+#[test]
+fn test_secret_reconciliation() -> Result<(), Box<dyn Error>> {
+    mz_ore::test::init_logging();
+    let data_dir = tempfile::tempdir()?;
+    let secrets_controller = InMemorySecretsController::new();
+    let config = util::Config::default()
+        .data_directory(data_dir.path())
+        .with_secrets_controller(secrets_controller.clone());
+
+    let (s1, s2) = {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("CREATE SECRET s1 AS 'hunter1'")?;
+        client.batch_execute("CREATE SECRET s2 AS 'hunter2'")?;
+        match &secrets_controller.ops()[..] {
+            [SecretOp::Ensure { id: s1, .. }, SecretOp::Ensure { id: s2, .. }] => (*s1, *s2),
+            ops => panic!("unexpected secret operations: {:?}", ops),
+        }
+    };
+
+    // Simulate a drop of `s2` that was interrupted after the catalog
+    // transaction committed, and the loss of `s1` from storage.
+    {
+        let server = util::start_server(config.clone())?;
+        let mut client = server.connect(postgres::NoTls)?;
+        client.batch_execute("DROP SECRET s2")?;
+        wait_until(|| Ok(secrets_controller.ops().len() == 3))?;
+        server.runtime.block_on(secrets_controller.apply(vec![
+            SecretOp::Ensure {
+                id: s2,
+                contents: b"hunter2".to_vec(),
+            },
+            SecretOp::Delete {
+                id: s1,
+                version: None,
+            },
+        ]))?;
+    }
+
+    // Missing secrets do not prevent startup, but their versions are unknown
+    // and they cannot be read. The orphaned secret is deleted.
+    {
+        let server = util::start_server(config.clone())?;
+        assert!(server
+            .runtime
+            .block_on(secrets_controller.list())?
+            .is_empty());
+        let mut client = server.connect(postgres::NoTls)?;
+        let version: Option<i64> = client
+            .query_one("SELECT version FROM mz_secrets WHERE name = 's1'", &[])?
+            .get(0);
+        assert_eq!(version, None);
+
+        // Altering a missing secret recreates it.
+        client.batch_execute("ALTER SECRET s1 AS 'hunter3'")?;
+        assert_eq!(
+            server.runtime.block_on(secrets_controller.read(s1))?,
+            b"hunter3"
+        );
+        server
+            .runtime
+            .block_on(secrets_controller.apply(vec![SecretOp::Delete {
+                id: s1,
+                version: None,
+            }]))?;
+    }
+
+    // Missing secrets are recreated as placeholders if requested.
+    let server = util::start_server(config.recreate_missing_secrets())?;
+    assert_eq!(
+        server.runtime.block_on(secrets_controller.list())?,
+        vec![s1]
+    );
+    assert_eq!(server.runtime.block_on(secrets_controller.read(s1))?, b"");
+    let mut client = server.connect(postgres::NoTls)?;
+    let version: Option<i64> = client
+        .query_one("SELECT version FROM mz_secrets WHERE name = 's1'", &[])?
+        .get(0);
+    assert!(version.is_some());
+    client.batch_execute("DROP SECRET s1")?;
+    wait_until(|| {
+        Ok(server
+            .runtime
+            .block_on(secrets_controller.list())?
+            .is_empty())
+    })?;
+
+    Ok(())
+}
+
+/// Waits for `cond` to hold, for assertions about work, like the deletion of
+/// dropped secrets, that finishes after the statement that caused it.
+fn wait_until<F>(mut cond: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> Result<bool, Box<dyn Error>>,
+{
+    Retry::default()
+        .max_duration(Duration::from_secs(10))
+        .retry(|_| match cond() {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("condition did not hold in time".into()),
+            Err(e) => Err(e),
+        })
+}
@@ -15,20 +15,24 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use lazy_static::lazy_static;
-use mz_coord::PersistConfig;
+use mz_authenticator::{Authenticator, FronteggAuthenticator, TrustAuthenticator};
+use mz_coord::catalog::storage::{CatalogDurability, SqliteSynchronous};
+use mz_coord::{PeekConcurrencyLimits, PersistConfig};
 use mz_dataflow_types::sources::AwsExternalId;
 use mz_frontegg_auth::FronteggAuthentication;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::{NowFn, SYSTEM_TIME};
 use mz_ore::task;
+use mz_secrets::{InMemorySecretsController, DEFAULT_MAX_SECRET_SIZE};
 use postgres::error::DbError;
 use postgres::tls::{MakeTlsConnect, TlsConnect};
 use postgres::types::{FromSql, Type};
 use postgres::Socket;
 use tempfile::TempDir;
 use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
 
-use materialized::{StorageConfig, TlsMode};
+use materialized::{SecretsControllerConfig, StorageConfig, TlsMode};
 
 lazy_static! {
     pub static ref KAFKA_ADDRS: mz_kafka_util::KafkaAddrs = match env::var("KAFKA_ADDRS") {
@@ -43,12 +47,22 @@ pub struct Config {
     aws_external_id: AwsExternalId,
     logging_granularity: Option<Duration>,
     tls: Option<materialized::TlsConfig>,
-    frontegg: Option<FronteggAuthentication>,
+    authenticator: Arc<dyn Authenticator>,
     experimental_mode: bool,
     safe_mode: bool,
     workers: usize,
     logical_compaction_window: Option<Duration>,
     now: NowFn,
+    secrets_controller: Option<InMemorySecretsController>,
+    recreate_missing_secrets: bool,
+    restore_catalog: Option<PathBuf>,
+    import_catalog: Option<PathBuf>,
+    catalog_encryption_key: Option<String>,
+    peek_concurrency_limits: PeekConcurrencyLimits,
+    peek_queue_timeout: Duration,
+    config_reload_tx: Option<mpsc::UnboundedSender<()>>,
+    max_connections: Option<usize>,
+    max_replication_slot_lag: Option<Duration>,
 }
 
 impl Default for Config {
@@ -58,12 +72,22 @@ impl Default for Config {
             aws_external_id: AwsExternalId::NotProvided,
             logging_granularity: Some(Duration::from_secs(1)),
             tls: None,
-            frontegg: None,
+            authenticator: Arc::new(TrustAuthenticator),
             experimental_mode: false,
             safe_mode: false,
             workers: 1,
             logical_compaction_window: None,
             now: SYSTEM_TIME.clone(),
+            secrets_controller: None,
+            recreate_missing_secrets: false,
+            restore_catalog: None,
+            import_catalog: None,
+            catalog_encryption_key: None,
+            peek_concurrency_limits: PeekConcurrencyLimits::default(),
+            peek_queue_timeout: Duration::from_secs(30),
+            config_reload_tx: None,
+            max_connections: None,
+            max_replication_slot_lag: None,
         }
     }
 }
@@ -113,8 +137,15 @@ impl Config {
         self
     }
 
-    pub fn with_frontegg(mut self, frontegg: &FronteggAuthentication) -> Self {
-        self.frontegg = Some(frontegg.clone());
+    pub fn with_frontegg(self, frontegg: &FronteggAuthentication) -> Self {
+        self.with_authenticator(FronteggAuthenticator::new(frontegg.clone()))
+    }
+
+    pub fn with_authenticator<A>(mut self, authenticator: A) -> Self
+    where
+        A: Authenticator + 'static,
+    {
+        self.authenticator = Arc::new(authenticator);
         self
     }
 
@@ -122,6 +153,63 @@ impl Config {
         self.now = now;
         self
     }
+
+    pub fn with_secrets_controller(mut self, controller: InMemorySecretsController) -> Self {
+        self.secrets_controller = Some(controller);
+        self
+    }
+
+    pub fn recreate_missing_secrets(mut self) -> Self {
+        self.recreate_missing_secrets = true;
+        self
+    }
+
+    pub fn restore_catalog(mut self, backup: impl Into<PathBuf>) -> Self {
+        self.restore_catalog = Some(backup.into());
+        self
+    }
+
+    pub fn import_catalog(mut self, dump: impl Into<PathBuf>) -> Self {
+        self.import_catalog = Some(dump.into());
+        self
+    }
+
+    pub fn catalog_encryption_key(mut self, key: &str) -> Self {
+        self.catalog_encryption_key = Some(key.into());
+        self
+    }
+
+    pub fn peek_concurrency_limit(mut self, limit: usize) -> Self {
+        self.peek_concurrency_limits.default = Some(limit);
+        self
+    }
+
+    pub fn cluster_peek_concurrency_limit(mut self, cluster: &str, limit: usize) -> Self {
+        self.peek_concurrency_limits
+            .clusters
+            .insert(cluster.into(), limit);
+        self
+    }
+
+    pub fn peek_queue_timeout(mut self, timeout: Duration) -> Self {
+        self.peek_queue_timeout = timeout;
+        self
+    }
+
+    pub fn with_config_reload_tx(mut self, tx: mpsc::UnboundedSender<()>) -> Self {
+        self.config_reload_tx = Some(tx);
+        self
+    }
+
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn max_replication_slot_lag(mut self, max_replication_slot_lag: Duration) -> Self {
+        self.max_replication_slot_lag = Some(max_replication_slot_lag);
+        self
+    }
 }
 
 pub fn start_server(config: Config) -> Result<Server, anyhow::Error> {
@@ -144,21 +232,40 @@ pub fn start_server(config: Config) -> Result<Server, anyhow::Error> {
             .map(|granularity| mz_coord::LoggingConfig {
                 granularity,
                 log_logging: false,
+                retention: None,
                 retain_readings_for: granularity,
                 metrics_scraping_interval: Some(granularity),
             }),
         timestamp_frequency: Duration::from_secs(1),
+        peek_concurrency_limits: config.peek_concurrency_limits,
+        peek_queue_timeout: config.peek_queue_timeout,
+        result_sort_memory_limit: None,
+        statistics_interval: None,
         logical_compaction_window: config.logical_compaction_window,
         workers: config.workers,
         timely_worker: timely::WorkerConfig::default(),
         data_directory,
+        catalog_postgres_url: None,
+        catalog_stash: false,
+        catalog_sqlite_synchronous: SqliteSynchronous::default(),
+        catalog_durability: CatalogDurability::default(),
+        catalog_gid_block_size: 1,
+        catalog_encryption_key: config.catalog_encryption_key,
+        restore_catalog: config.restore_catalog,
+        import_catalog: config.import_catalog,
+        dropped_object_retention: Duration::from_secs(24 * 60 * 60),
+        max_replication_slot_lag: config.max_replication_slot_lag,
         orchestrator: None,
-        secrets_controller: None,
+        secrets_controller: config
+            .secrets_controller
+            .map(SecretsControllerConfig::InMemory),
+        recreate_missing_secrets: config.recreate_missing_secrets,
+        max_secret_size: DEFAULT_MAX_SECRET_SIZE,
         storage: StorageConfig::Local,
         aws_external_id: config.aws_external_id,
         listen_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
         tls: config.tls,
-        frontegg: config.frontegg,
+        authenticator: config.authenticator,
         experimental_mode: config.experimental_mode,
         safe_mode: config.safe_mode,
         disable_user_indexes: false,
@@ -169,6 +276,8 @@ pub fn start_server(config: Config) -> Result<Server, anyhow::Error> {
         third_party_metrics_listen_addr: None,
         now: config.now,
         cors_allowed_origins: vec![],
+        config_reload_tx: config.config_reload_tx,
+        max_connections: config.max_connections,
     }))?;
     let server = Server {
         inner,
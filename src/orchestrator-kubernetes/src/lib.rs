@@ -7,29 +7,60 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::bail;
 use async_trait::async_trait;
 use k8s_openapi::api::apps::v1::{StatefulSet, StatefulSetSpec};
 use k8s_openapi::api::core::v1::{
-    Container, ContainerPort, Pod, PodSpec, PodTemplateSpec, ResourceRequirements,
-    Service as K8sService, ServicePort, ServiceSpec,
+    Affinity, Container, ContainerPort, EnvVar, EnvVarSource, HTTPGetAction, NodeAffinity,
+    NodeSelector, NodeSelectorRequirement, NodeSelectorTerm, ObjectFieldSelector,
+    PersistentVolumeClaim, PersistentVolumeClaimSpec, Pod, PodAffinityTerm, PodAntiAffinity,
+    PodSpec, PodTemplateSpec, Probe, ResourceRequirements, Service as K8sService, ServicePort,
+    ServiceSpec, TCPSocketAction, Toleration as K8sToleration, VolumeMount,
+    WeightedPodAffinityTerm,
 };
 use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
-use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector as K8sLabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use kube::api::{Api, DeleteParams, ListParams, ObjectMeta, Patch, PatchParams};
 use kube::client::Client;
 use kube::config::{Config, KubeConfigOptions};
 use kube::error::Error;
 use kube::ResourceExt;
 use sha2::{Digest, Sha256};
+use tokio::time;
 
-use mz_orchestrator::{NamespacedOrchestrator, Orchestrator, Service, ServiceConfig};
+use mz_orchestrator::{
+    ExitStatus, LabelRequirement, LabelSelector, NamespacedOrchestrator, Orchestrator,
+    ProcessDiagnostics, ProcessExit, ProcessIndex, ReadinessCheck, Service, ServiceAssignments,
+    ServiceConfig, ServiceDiagnostics, ServiceInfo, ServiceStatus,
+};
 
 const FIELD_MANAGER: &str = "materialized";
 
+/// The label with which the StatefulSet controller records the index of each
+/// of its pods (Kubernetes 1.28 and later).
+const POD_INDEX_LABEL: &str = "apps.kubernetes.io/pod-index";
+/// The environment variable through which a container learns its pod's
+/// index.
+const PROCESS_INDEX_VAR: &str = "MZ_PROCESS_INDEX";
+/// The reference to [`PROCESS_INDEX_VAR`] that Kubernetes expands in
+/// container arguments.
+const PROCESS_INDEX_REFERENCE: &str = "$(MZ_PROCESS_INDEX)";
+
+/// The name of the volume that backs the scratch directory of services with a
+/// disk limit.
+const SCRATCH_VOLUME: &str = "scratch";
+
+/// The path at which the scratch volume is mounted in each pod.
+const SCRATCH_DIRECTORY: &str = "/scratch";
+
 /// Configures a [`KubernetesOrchestrator`].
 #[derive(Debug, Clone)]
 pub struct KubernetesOrchestratorConfig {
@@ -38,6 +69,11 @@ pub struct KubernetesOrchestratorConfig {
     pub context: String,
     /// Labels to install on every service created by the orchestrator.
     pub service_labels: HashMap<String, String>,
+    /// The storage class of the persistent volumes provisioned for services
+    /// with a disk limit.
+    ///
+    /// If `None`, the cluster's default storage class is used.
+    pub storage_class: Option<String>,
 }
 
 /// An orchestrator backed by Kubernetes.
@@ -46,6 +82,9 @@ pub struct KubernetesOrchestrator {
     client: Client,
     kubernetes_namespace: String,
     service_labels: HashMap<String, String>,
+    storage_class: Option<String>,
+    /// The namespaces that have been entered.
+    namespaces: Arc<Mutex<BTreeSet<String>>>,
 }
 
 impl fmt::Debug for KubernetesOrchestrator {
@@ -78,21 +117,38 @@ impl KubernetesOrchestrator {
             client,
             kubernetes_namespace,
             service_labels: config.service_labels,
+            storage_class: config.storage_class,
+            namespaces: Arc::new(Mutex::new(BTreeSet::new())),
         })
     }
 }
 
+#[async_trait]
 impl Orchestrator for KubernetesOrchestrator {
     fn namespace(&self, namespace: &str) -> Box<dyn NamespacedOrchestrator> {
+        self.namespaces
+            .lock()
+            .expect("lock poisoned")
+            .insert(namespace.into());
         Box::new(NamespacedKubernetesOrchestrator {
             service_api: Api::default_namespaced(self.client.clone()),
             stateful_set_api: Api::default_namespaced(self.client.clone()),
             pod_api: Api::default_namespaced(self.client.clone()),
+            pvc_api: Api::default_namespaced(self.client.clone()),
             kubernetes_namespace: self.kubernetes_namespace.clone(),
             namespace: namespace.into(),
             service_labels: self.service_labels.clone(),
+            storage_class: self.storage_class.clone(),
         })
     }
+
+    async fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        let namespaces = self.namespaces.lock().expect("lock poisoned").clone();
+        for namespace in namespaces {
+            self.namespace(&namespace).drop_all().await?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -100,9 +156,11 @@ struct NamespacedKubernetesOrchestrator {
     service_api: Api<K8sService>,
     stateful_set_api: Api<StatefulSet>,
     pod_api: Api<Pod>,
+    pvc_api: Api<PersistentVolumeClaim>,
     kubernetes_namespace: String,
     namespace: String,
     service_labels: HashMap<String, String>,
+    storage_class: Option<String>,
 }
 
 impl fmt::Debug for NamespacedKubernetesOrchestrator {
@@ -111,10 +169,53 @@ impl fmt::Debug for NamespacedKubernetesOrchestrator {
             .field("kubernetes_namespace", &self.kubernetes_namespace)
             .field("namespace", &self.namespace)
             .field("service_labels", &self.service_labels)
+            .field("storage_class", &self.storage_class)
             .finish()
     }
 }
 
+impl NamespacedKubernetesOrchestrator {
+    /// Converts the key of a service label into a Kubernetes label key scoped
+    /// to this namespace.
+    fn make_label_key(&self, key: &str) -> String {
+        format!("{}{}", self.label_key_prefix(), key)
+    }
+
+    /// Returns the prefix that [`Self::make_label_key`] applies to keys.
+    fn label_key_prefix(&self) -> String {
+        format!("{}.materialized.materialize.cloud/", self.namespace)
+    }
+
+    /// Converts `selector` into a Kubernetes label selector string.
+    fn make_label_selector(&self, selector: &LabelSelector) -> String {
+        selector
+            .requirements
+            .iter()
+            .map(|req| match req {
+                LabelRequirement::Equals { key, value } => {
+                    format!("{}={}", self.make_label_key(key), value)
+                }
+                LabelRequirement::NotEquals { key, value } => {
+                    format!("{}!={}", self.make_label_key(key), value)
+                }
+                LabelRequirement::Exists { key } => self.make_label_key(key),
+                LabelRequirement::NotExists { key } => format!("!{}", self.make_label_key(key)),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Recovers the labels or annotations of a service from the keys produced
+    /// by [`Self::make_label_key`], ignoring any other keys.
+    fn strip_label_keys(&self, map: Option<BTreeMap<String, String>>) -> HashMap<String, String> {
+        let prefix = self.label_key_prefix();
+        map.unwrap_or_default()
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(&prefix)?.to_string(), value)))
+            .collect()
+    }
+}
+
 #[async_trait]
 impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
     async fn ensure_service(
@@ -126,17 +227,19 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
             ports: ports_in,
             memory_limit,
             cpu_limit,
+            disk_limit,
             processes,
+            readiness_probe,
+            placement,
             labels: labels_in,
-        }: ServiceConfig<'_>,
+            annotations: annotations_in,
+        }: ServiceConfig,
     ) -> Result<Box<dyn Service>, anyhow::Error> {
+        placement.validate()?;
         let name = format!("{}-{id}", self.namespace);
         let mut labels = BTreeMap::new();
         for (key, value) in labels_in {
-            labels.insert(
-                format!("{}.materialized.materialize.cloud/{}", self.namespace, key),
-                value,
-            );
+            labels.insert(self.make_label_key(&key), value);
         }
         for port in &ports_in {
             labels.insert(
@@ -155,6 +258,10 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
         for (key, value) in &self.service_labels {
             labels.insert(key.clone(), value.clone());
         }
+        let annotations: BTreeMap<_, _> = annotations_in
+            .into_iter()
+            .map(|(key, value)| (self.make_label_key(&key), value))
+            .collect();
         let mut limits = BTreeMap::new();
         if let Some(memory_limit) = memory_limit {
             limits.insert(
@@ -191,21 +298,189 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
             status: None,
         };
 
-        let ports = ports_in
+        let ports: HashMap<_, _> = ports_in
             .iter()
             .map(|p| (p.name.clone(), p.port_hint))
             .collect();
+        // Kubernetes does not block on readiness when applying a stateful set,
+        // but it will withhold traffic from pods that are not yet ready.
+        let readiness_probe = readiness_probe.map(|probe| {
+            let port = IntOrString::Int(ports[&probe.port]);
+            // Probe once per second until the timeout elapses.
+            let failure_threshold = i32::try_from(probe.timeout.as_secs().max(1));
+            let mut k8s_probe = Probe {
+                failure_threshold: Some(failure_threshold.unwrap_or(i32::MAX)),
+                period_seconds: Some(1),
+                ..Default::default()
+            };
+            match probe.check {
+                ReadinessCheck::Tcp => {
+                    k8s_probe.tcp_socket = Some(TCPSocketAction {
+                        port,
+                        ..Default::default()
+                    })
+                }
+                ReadinessCheck::HttpGet { path } => {
+                    k8s_probe.http_get = Some(HTTPGetAction {
+                        path: Some(path),
+                        port,
+                        ..Default::default()
+                    })
+                }
+            }
+            k8s_probe
+        });
+        let node_affinity = if placement.availability_zones.is_empty() {
+            None
+        } else {
+            Some(NodeAffinity {
+                required_during_scheduling_ignored_during_execution: Some(NodeSelector {
+                    node_selector_terms: vec![NodeSelectorTerm {
+                        match_expressions: Some(vec![NodeSelectorRequirement {
+                            key: "topology.kubernetes.io/zone".into(),
+                            operator: "In".into(),
+                            values: Some(placement.availability_zones),
+                        }]),
+                        ..Default::default()
+                    }],
+                }),
+                ..Default::default()
+            })
+        };
+        let mut required_anti_affinity = vec![];
+        let mut preferred_anti_affinity = vec![];
+        for rule in placement.anti_affinity {
+            let term = PodAffinityTerm {
+                label_selector: Some(K8sLabelSelector {
+                    match_labels: Some(
+                        rule.labels
+                            .into_iter()
+                            .map(|(key, value)| (self.make_label_key(&key), value))
+                            .collect(),
+                    ),
+                    ..Default::default()
+                }),
+                topology_key: "kubernetes.io/hostname".into(),
+                ..Default::default()
+            };
+            if rule.required {
+                required_anti_affinity.push(term);
+            } else {
+                preferred_anti_affinity.push(WeightedPodAffinityTerm {
+                    pod_affinity_term: term,
+                    weight: 100,
+                });
+            }
+        }
+        let pod_anti_affinity = if required_anti_affinity.is_empty()
+            && preferred_anti_affinity.is_empty()
+        {
+            None
+        } else {
+            Some(PodAntiAffinity {
+                required_during_scheduling_ignored_during_execution: Some(required_anti_affinity),
+                preferred_during_scheduling_ignored_during_execution: Some(preferred_anti_affinity),
+            })
+        };
+        let node_selector = if placement.node_selector.is_empty() {
+            None
+        } else {
+            Some(placement.node_selector.into_iter().collect())
+        };
+        let tolerations = if placement.tolerations.is_empty() {
+            None
+        } else {
+            Some(
+                placement
+                    .tolerations
+                    .into_iter()
+                    .map(|toleration| K8sToleration {
+                        operator: Some(match toleration.value {
+                            None => "Exists".into(),
+                            Some(_) => "Equal".into(),
+                        }),
+                        key: toleration.key,
+                        value: toleration.value,
+                        effect: toleration.effect.map(|effect| effect.to_string()),
+                        ..Default::default()
+                    })
+                    .collect(),
+            )
+        };
+        let affinity = if node_affinity.is_none() && pod_anti_affinity.is_none() {
+            None
+        } else {
+            Some(Affinity {
+                node_affinity,
+                pod_anti_affinity,
+                ..Default::default()
+            })
+        };
+        // Each pod of a service with a disk limit gets a persistent volume of
+        // its own, which outlives the pod, to back its scratch directory.
+        let volume_claim_templates = disk_limit.map(|disk_limit| {
+            vec![PersistentVolumeClaim {
+                metadata: ObjectMeta {
+                    name: Some(SCRATCH_VOLUME.into()),
+                    labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                spec: Some(PersistentVolumeClaimSpec {
+                    access_modes: Some(vec!["ReadWriteOnce".into()]),
+                    resources: Some(ResourceRequirements {
+                        requests: Some(BTreeMap::from([(
+                            "storage".into(),
+                            Quantity(disk_limit.as_bytes().to_string()),
+                        )])),
+                        ..Default::default()
+                    }),
+                    storage_class_name: self.storage_class.clone(),
+                    ..Default::default()
+                }),
+                status: None,
+            }]
+        });
+        let (volume_mounts, scratch_directory) = match &volume_claim_templates {
+            None => (None, None),
+            Some(_) => (
+                Some(vec![VolumeMount {
+                    name: SCRATCH_VOLUME.into(),
+                    mount_path: SCRATCH_DIRECTORY.into(),
+                    ..Default::default()
+                }]),
+                Some(Path::new(SCRATCH_DIRECTORY)),
+            ),
+        };
         let mut pod_template_spec = PodTemplateSpec {
             metadata: Some(ObjectMeta {
                 labels: Some(labels.clone()),
-                annotations: Some(BTreeMap::new()), // Do not delete, we insert into it below.
+                // Do not delete, we insert into it below.
+                annotations: Some(annotations.clone()),
                 ..Default::default()
             }),
             spec: Some(PodSpec {
                 containers: vec![Container {
                     name: "default".into(),
                     image: Some(image),
-                    args: Some(args(&ports)),
+                    args: Some(
+                        ServiceAssignments {
+                            // Pods have their own network namespace, so
+                            // listening on all interfaces is always
+                            // appropriate.
+                            listen_host: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                            ports: &ports,
+                            // All pods share the pod template, so unless
+                            // there is just one pod, Kubernetes substitutes
+                            // each pod's index when it starts the container.
+                            index: if processes == 1 {
+                                ProcessIndex::Known(0)
+                            } else {
+                                ProcessIndex::Deferred(PROCESS_INDEX_REFERENCE)
+                            },
+                            scratch_directory,
+                        }
+                        .expand_args(&args)?,
+                    ),
                     ports: Some(
                         ports_in
                             .iter()
@@ -220,8 +495,24 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
                         limits: Some(limits),
                         ..Default::default()
                     }),
+                    readiness_probe,
+                    volume_mounts,
+                    env: Some(vec![EnvVar {
+                        name: PROCESS_INDEX_VAR.into(),
+                        value_from: Some(EnvVarSource {
+                            field_ref: Some(ObjectFieldSelector {
+                                field_path: format!("metadata.labels['{POD_INDEX_LABEL}']"),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }]),
                     ..Default::default()
                 }],
+                affinity,
+                node_selector,
+                tolerations,
                 ..Default::default()
             }),
         };
@@ -245,16 +536,19 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
         let stateful_set = StatefulSet {
             metadata: ObjectMeta {
                 name: Some(name.clone()),
+                labels: Some(labels.clone()),
+                annotations: Some(annotations),
                 ..Default::default()
             },
             spec: Some(StatefulSetSpec {
-                selector: LabelSelector {
+                selector: K8sLabelSelector {
                     match_labels: Some(labels.clone()),
                     ..Default::default()
                 },
                 service_name: name.clone(),
                 replicas: Some(processes.try_into()?),
                 template: pod_template_spec,
+                volume_claim_templates,
                 ..Default::default()
             }),
             status: None,
@@ -309,7 +603,8 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
         Ok(Box::new(KubernetesService { hosts, ports }))
     }
 
-    /// Drops the identified service, if it exists.
+    /// Drops the identified service, if it exists, along with any persistent
+    /// volumes provisioned for it.
     async fn drop_service(&mut self, id: &str) -> Result<(), anyhow::Error> {
         let name = format!("{}-{id}", self.namespace);
         let res = self
@@ -317,27 +612,139 @@ impl NamespacedOrchestrator for NamespacedKubernetesOrchestrator {
             .delete(&name, &DeleteParams::default())
             .await;
         match res {
-            Ok(_) => Ok(()),
-            Err(Error::Api(e)) if e.code == 404 => Ok(()),
-            Err(e) => Err(e.into()),
+            Ok(_) => (),
+            Err(Error::Api(e)) if e.code == 404 => (),
+            Err(e) => return Err(e.into()),
         }
+        // Kubernetes does not delete the persistent volume claims of a
+        // stateful set along with the stateful set.
+        let params = ListParams::default().labels(&format!(
+            "materialized.materialize.cloud/namespace={},materialized.materialize.cloud/service-id={id}",
+            self.namespace
+        ));
+        self.pvc_api
+            .delete_collection(&DeleteParams::default(), &params)
+            .await?;
+        Ok(())
     }
 
-    /// Lists the identifiers of all known services.
-    async fn list_services(&self) -> Result<Vec<String>, anyhow::Error> {
-        let stateful_sets = self.stateful_set_api.list(&ListParams::default()).await?;
+    /// Drops every service in the namespace and waits for their pods to
+    /// terminate.
+    async fn drop_all(&mut self) -> Result<(), anyhow::Error> {
+        let params = ListParams::default().labels(&format!(
+            "materialized.materialize.cloud/namespace={}",
+            self.namespace
+        ));
+        self.stateful_set_api
+            .delete_collection(&DeleteParams::default(), &params)
+            .await?;
+        self.pvc_api
+            .delete_collection(&DeleteParams::default(), &params)
+            .await?;
+        // Deleting a stateful set deletes its pods in the background.
+        while !self.pod_api.list(&params).await?.items.is_empty() {
+            time::sleep(Duration::from_secs(1)).await;
+        }
+        Ok(())
+    }
+
+    /// Lists the known services whose labels match `selector`.
+    async fn list_services(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<ServiceInfo>, anyhow::Error> {
+        let mut params = ListParams::default();
+        if !selector.requirements.is_empty() {
+            params = params.labels(&self.make_label_selector(selector));
+        }
+        let stateful_sets = self.stateful_set_api.list(&params).await?;
         let name_prefix = format!("{}-", self.namespace);
         Ok(stateful_sets
             .into_iter()
             .filter_map(|ss| {
-                ss.metadata
+                let id = ss
+                    .metadata
                     .name
-                    .unwrap()
-                    .strip_prefix(&name_prefix)
-                    .map(Into::into)
+                    .as_ref()?
+                    .strip_prefix(&name_prefix)?
+                    .into();
+                let status = match (ss.spec, ss.status) {
+                    (Some(spec), Some(status)) => {
+                        if status.ready_replicas.unwrap_or(0) >= spec.replicas.unwrap_or(1) {
+                            ServiceStatus::Ready
+                        } else {
+                            ServiceStatus::NotReady
+                        }
+                    }
+                    _ => ServiceStatus::Unknown,
+                };
+                Some(ServiceInfo {
+                    id,
+                    labels: self.strip_label_keys(ss.metadata.labels),
+                    annotations: self.strip_label_keys(ss.metadata.annotations),
+                    status,
+                })
             })
             .collect())
     }
+
+    /// Returns diagnostic information about the pods of the identified
+    /// service.
+    ///
+    /// Kubernetes retains only the most recent termination of each container,
+    /// so each process reports at most one recent exit.
+    async fn service_diagnostics(&self, id: &str) -> Result<ServiceDiagnostics, anyhow::Error> {
+        let name = format!("{}-{id}", self.namespace);
+        let stateful_set = match self.stateful_set_api.get(&name).await {
+            Ok(stateful_set) => stateful_set,
+            Err(Error::Api(e)) if e.code == 404 => bail!("unknown service {id}"),
+            Err(e) => return Err(e.into()),
+        };
+        let processes = stateful_set
+            .spec
+            .and_then(|spec| spec.replicas)
+            .unwrap_or(1);
+        let mut diagnostics = vec![];
+        for pod_id in 0..processes {
+            let pod_name = format!("{}-{}", &name, pod_id);
+            let pod = match self.pod_api.get(&pod_name).await {
+                Ok(pod) => pod,
+                // The pod has not been created yet.
+                Err(Error::Api(e)) if e.code == 404 => {
+                    diagnostics.push(ProcessDiagnostics::default());
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let container_status = pod
+                .status
+                .and_then(|status| status.container_statuses)
+                .and_then(|statuses| statuses.into_iter().next());
+            let mut process = ProcessDiagnostics::default();
+            if let Some(container_status) = container_status {
+                process.exit_count = u64::try_from(container_status.restart_count).unwrap_or(0);
+                let terminated = container_status
+                    .last_state
+                    .and_then(|state| state.terminated);
+                if let Some(terminated) = terminated {
+                    let status = match terminated.signal {
+                        Some(signal) => ExitStatus::Signal(signal),
+                        None => ExitStatus::Code(terminated.exit_code),
+                    };
+                    if let Some(finished_at) = terminated.finished_at {
+                        process.recent_exits.push(ProcessExit {
+                            time: finished_at.0.into(),
+                            status,
+                        });
+                    }
+                }
+            }
+            diagnostics.push(process);
+        }
+        Ok(ServiceDiagnostics {
+            processes: diagnostics,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,405 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Fault injection for testing.
+//!
+//! Faults are injected into individual processes of a service, identified by
+//! the service's namespace and ID and the process's index. A paused process
+//! keeps its connections open but stops responding.
+//!
+//! Network faults are injected by a proxy that the orchestrator places in
+//! front of each port of each process: the process listens on a port of its
+//! own, while the port that the service advertises belongs to the proxy. Only
+//! connections to a process's ports pass through its proxies, so connections
+//! that the process makes to other services are affected only by faults
+//! injected into those services.
+
+use std::collections::HashMap;
+use std::future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use scopeguard::defer;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+use tracing::{debug, warn};
+
+use crate::{NamespacedProcessOrchestrator, ProcessControl, ProcessOrchestrator};
+
+/// The network faults injected into the connections to a process.
+#[derive(Debug, Clone, Copy, Default)]
+struct NetworkFaults {
+    /// Whether traffic is held until the partition heals.
+    partitioned: bool,
+    /// The latency added to traffic in each direction.
+    delay: Duration,
+}
+
+/// Controls the network faults injected into the connections to a process.
+#[derive(Debug)]
+pub(crate) struct NetworkControl {
+    tx: watch::Sender<NetworkFaults>,
+    /// Keeps the channel open, so that faults can be updated even if the
+    /// process has no ports.
+    _rx: watch::Receiver<NetworkFaults>,
+}
+
+impl ProcessOrchestrator {
+    /// Pauses the specified process until it is resumed with
+    /// [`ProcessOrchestrator::resume_process`].
+    ///
+    /// Processes are paused with `SIGSTOP`, or via the container runtime for
+    /// processes that run in containers.
+    pub async fn pause_process(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<(), anyhow::Error> {
+        let control = self.process_control(namespace, id, process)?;
+        send_fault(&control, Signal::SIGSTOP, "pause").await
+    }
+
+    /// Resumes the specified process after it was paused with
+    /// [`ProcessOrchestrator::pause_process`].
+    pub async fn resume_process(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<(), anyhow::Error> {
+        let control = self.process_control(namespace, id, process)?;
+        send_fault(&control, Signal::SIGCONT, "unpause").await
+    }
+
+    /// Kills the specified process with `SIGKILL`.
+    ///
+    /// The process is relaunched as if it had crashed. Use
+    /// [`ProcessOrchestrator::delay_restart`] beforehand to control how long
+    /// it stays down.
+    pub async fn kill_process(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<(), anyhow::Error> {
+        let control = self.process_control(namespace, id, process)?;
+        send_fault(&control, Signal::SIGKILL, "kill").await
+    }
+
+    /// Overrides the delay before the specified process is next relaunched
+    /// after it exits.
+    ///
+    /// The override applies to a single relaunch. If the process is currently
+    /// waiting to be relaunched, it applies to the relaunch after that one.
+    pub fn delay_restart(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+        delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        let control = self.process_control(namespace, id, process)?;
+        control.lock().expect("lock poisoned").next_restart_delay = Some(delay);
+        Ok(())
+    }
+
+    /// Partitions the specified process from the network until the partition
+    /// is healed with [`ProcessOrchestrator::heal_process`].
+    ///
+    /// Traffic on connections to the process's ports is held in both
+    /// directions, and new connections are accepted but not connected through
+    /// to the process, so that to its peers the process stops responding. When
+    /// the partition heals, the held traffic is delivered, as TCP would
+    /// retransmit it after a real partition.
+    pub fn partition_process(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<(), anyhow::Error> {
+        self.update_network(namespace, id, process, |faults| faults.partitioned = true)
+    }
+
+    /// Heals a partition created with
+    /// [`ProcessOrchestrator::partition_process`].
+    pub fn heal_process(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<(), anyhow::Error> {
+        self.update_network(namespace, id, process, |faults| faults.partitioned = false)
+    }
+
+    /// Delays the traffic on connections to the specified process's ports by
+    /// `delay` in each direction, or removes the delay if `delay` is zero.
+    ///
+    /// The delay applies to traffic sent after this call.
+    pub fn delay_network(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+        delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        self.update_network(namespace, id, process, |faults| faults.delay = delay)
+    }
+
+    /// Applies `update` to the network faults of the specified process.
+    fn update_network<F>(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+        update: F,
+    ) -> Result<(), anyhow::Error>
+    where
+        F: FnOnce(&mut NetworkFaults),
+    {
+        let control = self.process_control(namespace, id, process)?;
+        let control = control.lock().expect("lock poisoned");
+        let network = control
+            .network
+            .as_ref()
+            .expect("network control is set when fault injection is enabled");
+        let mut faults = *network.tx.borrow();
+        update(&mut faults);
+        network.tx.send(faults).expect("channel held open");
+        Ok(())
+    }
+
+    /// Returns the state shared with the supervisor of the specified process.
+    fn process_control(
+        &self,
+        namespace: &str,
+        id: &str,
+        process: usize,
+    ) -> Result<Arc<Mutex<ProcessControl>>, anyhow::Error> {
+        let supervisors = {
+            let namespaces = self.namespaces.lock().expect("lock poisoned");
+            match namespaces.get(namespace) {
+                Some(supervisors) => Arc::clone(supervisors),
+                None => bail!("unknown namespace {namespace}"),
+            }
+        };
+        let supervisors = supervisors.lock().expect("lock poisoned");
+        let state = supervisors
+            .get(id)
+            .ok_or_else(|| anyhow!("unknown service {namespace}-{id}"))?;
+        match state.controls.get(process) {
+            Some(control) => Ok(Arc::clone(control)),
+            None => bail!("service {namespace}-{id} has no process {process}"),
+        }
+    }
+}
+
+/// Sends `signal` to the process controlled by `control`, or, if the process
+/// runs in a container, runs the container runtime's `runtime_command` on the
+/// container instead.
+async fn send_fault(
+    control: &Mutex<ProcessControl>,
+    signal: Signal,
+    runtime_command: &str,
+) -> Result<(), anyhow::Error> {
+    let (pid, container) = {
+        let control = control.lock().expect("lock poisoned");
+        (control.pid, control.container.clone())
+    };
+    match container {
+        // Signaling the container runtime's client would not affect the
+        // container itself.
+        Some((runtime, name)) => {
+            let status = Command::new(&runtime)
+                .args([runtime_command, &name])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await?;
+            if !status.success() {
+                bail!("{runtime} {runtime_command} {name} failed: {status}");
+            }
+        }
+        None => {
+            let pid = pid.ok_or_else(|| anyhow!("process is not running"))?;
+            signal::kill(Pid::from_raw(i32::try_from(pid)?), signal)?;
+        }
+    }
+    Ok(())
+}
+
+impl NamespacedProcessOrchestrator {
+    /// Places a proxy in front of each of the ports in `ports` of a process of
+    /// the service `full_id`, through which network faults are injected.
+    ///
+    /// Each proxy listens on its port in `ports` and forwards connections to a
+    /// newly allocated port. The proxies' tasks are pushed onto `proxies` as
+    /// they are spawned, so that they can be aborted even if a later proxy
+    /// cannot be placed. Aborting a proxy frees its port. Returns the ports on
+    /// which the process itself must listen, by name, and the control for the
+    /// faults injected into the proxies.
+    pub(crate) fn interpose(
+        &self,
+        full_id: &str,
+        ports: &HashMap<String, i32>,
+        proxies: &mut Vec<JoinHandle<()>>,
+    ) -> Result<(HashMap<String, i32>, NetworkControl), anyhow::Error> {
+        let (tx, rx) = watch::channel(NetworkFaults::default());
+        let mut process_ports = HashMap::new();
+        for (name, port) in ports {
+            // Bind the advertised port now, so that it is listening before
+            // the process's readiness is probed.
+            let listener = std::net::TcpListener::bind(SocketAddr::new(
+                self.listen_addr,
+                u16::try_from(*port)?,
+            ))?;
+            listener.set_nonblocking(true)?;
+            let listener = TcpListener::from_std(listener)?;
+            let process_port = self.alloc_port()?;
+            self.metrics.ports_allocated.inc();
+            process_ports.insert(name.clone(), process_port);
+            // Allocated ports were bound when they were allocated, so they
+            // are valid.
+            let backend = SocketAddr::new(
+                connect_addr(self.listen_addr),
+                u16::try_from(process_port).expect("allocated ports are valid"),
+            );
+            let port_allocator = Arc::clone(&self.port_allocator);
+            let metrics = self.metrics.clone();
+            let faults = rx.clone();
+            proxies.push(mz_ore::task::spawn(
+                || format!("fault-proxy: {full_id}:{name}"),
+                async move {
+                    defer! {
+                        port_allocator.free(process_port);
+                        metrics.ports_allocated.dec();
+                    }
+                    proxy(listener, backend, faults).await
+                },
+            ));
+        }
+        Ok((process_ports, NetworkControl { tx, _rx: rx }))
+    }
+}
+
+/// Returns the address at which to connect to a process that listens on
+/// `listen_addr`.
+fn connect_addr(listen_addr: IpAddr) -> IpAddr {
+    match listen_addr {
+        IpAddr::V4(addr) if addr.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(addr) if addr.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        addr => addr,
+    }
+}
+
+/// Accepts connections on `listener` and forwards them to `backend`, subject
+/// to `faults`.
+async fn proxy(listener: TcpListener, backend: SocketAddr, faults: watch::Receiver<NetworkFaults>) {
+    // The connections are severed along with the proxy.
+    let mut conns = scopeguard::guard(vec![], |conns: Vec<JoinHandle<()>>| {
+        for conn in conns {
+            conn.abort();
+        }
+    });
+    loop {
+        let client = match listener.accept().await {
+            Ok((client, _)) => client,
+            Err(e) => {
+                warn!("fault proxy for {backend} failed to accept connection: {e}");
+                time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let faults = faults.clone();
+        conns.push(mz_ore::task::spawn(
+            || format!("fault-proxy-connection: {backend}"),
+            async move {
+                if let Err(e) = proxy_connection(client, backend, faults).await {
+                    debug!("fault proxy connection to {backend} failed: {e}");
+                }
+            },
+        ));
+    }
+}
+
+/// Forwards the connection `client` to `backend`, subject to `faults`.
+async fn proxy_connection(
+    client: TcpStream,
+    backend: SocketAddr,
+    mut faults: watch::Receiver<NetworkFaults>,
+) -> Result<(), io::Error> {
+    // A partitioned process cannot be reached.
+    wait_until_healed(&mut faults).await;
+    let server = TcpStream::connect(backend).await?;
+    let (client_read, client_write) = client.into_split();
+    let (server_read, server_write) = server.into_split();
+    tokio::try_join!(
+        forward(client_read, server_write, faults.clone()),
+        forward(server_read, client_write, faults),
+    )?;
+    Ok(())
+}
+
+/// Copies data from `from` to `to`, subject to `faults`, until `from` is
+/// closed.
+async fn forward(
+    mut from: OwnedReadHalf,
+    mut to: OwnedWriteHalf,
+    mut faults: watch::Receiver<NetworkFaults>,
+) -> Result<(), io::Error> {
+    // Data is read as soon as it arrives and written once its delay has
+    // elapsed, so that a delay adds latency without limiting throughput.
+    let (tx, mut rx) = mpsc::channel::<(Instant, Vec<u8>)>(64);
+    let read_faults = faults.clone();
+    let read = async move {
+        let mut buf = vec![0; 8192];
+        loop {
+            let n = from.read(&mut buf).await?;
+            if n == 0 {
+                return Ok::<_, io::Error>(());
+            }
+            let deadline = Instant::now() + read_faults.borrow().delay;
+            if tx.send((deadline, buf[..n].to_vec())).await.is_err() {
+                return Ok(());
+            }
+        }
+    };
+    let write = async move {
+        while let Some((deadline, data)) = rx.recv().await {
+            time::sleep_until(deadline).await;
+            wait_until_healed(&mut faults).await;
+            to.write_all(&data).await?;
+        }
+        to.shutdown().await
+    };
+    tokio::try_join!(read, write)?;
+    Ok(())
+}
+
+/// Waits until the process is not partitioned.
+async fn wait_until_healed(faults: &mut watch::Receiver<NetworkFaults>) {
+    while faults.borrow().partitioned {
+        if faults.changed().await.is_err() {
+            // The process's service has been dropped, and the partition will
+            // never heal.
+            future::pending::<()>().await;
+        }
+    }
+}
@@ -8,31 +8,92 @@
 // by the Apache License, Version 2.0.
 
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
 use std::ops::RangeInclusive;
-use std::path::PathBuf;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use itertools::Itertools;
-use scopeguard::defer;
+use scopeguard::{defer, ScopeGuard};
 use tokio::process::Command;
 use tokio::task::JoinHandle;
 use tokio::time::{self, Duration};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use mz_orchestrator::{NamespacedOrchestrator, Orchestrator, Service, ServiceConfig};
+use mz_orchestrator::{
+    ExitStatus, LabelSelector, NamespacedOrchestrator, Orchestrator, PlacementHints,
+    ProcessDiagnostics, ProcessExit, ProcessIndex, Service, ServiceAssignments, ServiceConfig,
+    ServiceDiagnostics, ServiceInfo, ServiceStatus,
+};
 use mz_ore::id_gen::IdAllocator;
+use mz_ore::metrics::{CounterVecExt, GaugeVecExt, MetricsRegistry};
+
+use crate::metrics::Metrics;
+
+#[cfg(feature = "fault-injection")]
+mod fault;
+mod metrics;
+
+/// The number of exits of each process that the orchestrator retains for
+/// [`NamespacedOrchestrator::service_diagnostics`].
+const EXIT_HISTORY_LEN: usize = 16;
+
+/// How long a supervisor waits before relaunching a process that has exited.
+const RESTART_DELAY: Duration = Duration::from_secs(5);
 
 /// Configures a [`ProcessOrchestrator`].
 #[derive(Debug, Clone)]
 pub struct ProcessOrchestratorConfig {
-    /// The directory in which the orchestrator should look for executable
-    /// images.
-    pub image_dir: PathBuf,
+    /// How the orchestrator should launch the images of services.
+    pub launcher: Launcher,
+    /// The directory in which the orchestrator should create a scratch
+    /// directory for each process.
+    ///
+    /// The scratch directory for a process is located at
+    /// `<data_dir>/services/<namespace>-<id>-<process>`.
+    pub data_dir: PathBuf,
     /// The range of ports to allocate.
     pub port_range: RangeInclusive<i32>,
+    /// The IP address on which supervised processes should listen.
+    ///
+    /// Use `0.0.0.0` to listen on all interfaces, or the address of a specific
+    /// interface (e.g., a Docker bridge) to restrict access to that network.
+    pub listen_addr: IpAddr,
+    /// The hostname to report in the addresses of supervised processes.
+    ///
+    /// This must be a name by which the processes are reachable from the
+    /// services that connect to them, which is not necessarily `localhost`
+    /// when those services run on other machines or in containers.
+    pub hostname: String,
+}
+
+/// Describes how a [`ProcessOrchestrator`] launches the image of a service.
+#[derive(Debug, Clone)]
+pub enum Launcher {
+    /// Images name executables in `image_dir`.
+    Binary {
+        /// The directory in which to look for executables.
+        image_dir: PathBuf,
+    },
+    /// Images name container images, which are run via the command-line
+    /// interface of a container runtime.
+    ///
+    /// Each port of a process is published on the same port of the host, and
+    /// the process's scratch directory is mounted at the same path inside the
+    /// container.
+    Container {
+        /// The container runtime's command, e.g. `docker` or `podman`.
+        runtime: String,
+    },
 }
 
 /// An orchestrator backed by processes on the local machine.
@@ -42,137 +103,689 @@ pub struct ProcessOrchestratorConfig {
 /// of `Orchestrator`.
 #[derive(Debug, Clone)]
 pub struct ProcessOrchestrator {
-    image_dir: PathBuf,
+    launcher: Launcher,
+    data_dir: PathBuf,
     port_allocator: Arc<IdAllocator<i32>>,
+    listen_addr: IpAddr,
+    hostname: String,
+    metrics: Metrics,
+    /// The supervisors of each namespace that has been entered, by
+    /// namespace.
+    namespaces: Arc<Mutex<HashMap<String, Supervisors>>>,
 }
 
+/// The services supervised within a namespace, by ID.
+type Supervisors = Arc<Mutex<HashMap<String, ServiceState>>>;
+
 impl ProcessOrchestrator {
     /// Creates a new process orchestrator from the provided configuration.
+    ///
+    /// Metrics about the supervised processes are registered with
+    /// `metrics_registry`.
     pub async fn new(
         ProcessOrchestratorConfig {
-            image_dir,
+            launcher,
+            data_dir,
             port_range,
+            listen_addr,
+            hostname,
         }: ProcessOrchestratorConfig,
+        metrics_registry: &MetricsRegistry,
     ) -> Result<ProcessOrchestrator, anyhow::Error> {
+        let metrics = Metrics::register_with(metrics_registry);
+        let ports_total = port_range.end() - port_range.start() + 1;
+        metrics
+            .ports_total
+            .set(u64::try_from(ports_total).unwrap_or(0));
+        let launcher = match launcher {
+            Launcher::Binary { image_dir } => Launcher::Binary {
+                image_dir: fs::canonicalize(image_dir)?,
+            },
+            launcher @ Launcher::Container { .. } => launcher,
+        };
         Ok(ProcessOrchestrator {
-            image_dir: fs::canonicalize(image_dir)?,
+            launcher,
+            data_dir,
             port_allocator: Arc::new(IdAllocator::new(*port_range.start(), *port_range.end())),
+            listen_addr,
+            hostname,
+            metrics,
+            namespaces: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 }
 
+#[async_trait]
 impl Orchestrator for ProcessOrchestrator {
     fn namespace(&self, namespace: &str) -> Box<dyn NamespacedOrchestrator> {
+        let supervisors = {
+            let mut namespaces = self.namespaces.lock().expect("lock poisoned");
+            Arc::clone(namespaces.entry(namespace.into()).or_default())
+        };
         Box::new(NamespacedProcessOrchestrator {
             namespace: namespace.into(),
-            image_dir: self.image_dir.clone(),
+            launcher: self.launcher.clone(),
+            data_dir: self.data_dir.clone(),
             port_allocator: Arc::clone(&self.port_allocator),
-            supervisors: Arc::new(Mutex::new(HashMap::new())),
+            listen_addr: self.listen_addr,
+            hostname: self.hostname.clone(),
+            metrics: self.metrics.clone(),
+            supervisors,
         })
     }
+
+    async fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        let namespaces: Vec<_> = {
+            let namespaces = self.namespaces.lock().expect("lock poisoned");
+            namespaces.keys().cloned().collect()
+        };
+        for namespace in namespaces {
+            self.namespace(&namespace).drop_all().await?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
 struct NamespacedProcessOrchestrator {
     namespace: String,
-    image_dir: PathBuf,
+    launcher: Launcher,
+    data_dir: PathBuf,
     port_allocator: Arc<IdAllocator<i32>>,
-    supervisors: Arc<Mutex<HashMap<String, Vec<JoinHandle<()>>>>>,
+    listen_addr: IpAddr,
+    hostname: String,
+    metrics: Metrics,
+    supervisors: Supervisors,
+}
+
+/// The state of a service supervised by a [`NamespacedProcessOrchestrator`].
+#[derive(Debug)]
+struct ServiceState {
+    /// For each process in order, the task supervising the process.
+    handles: Vec<JoinHandle<()>>,
+    /// For each process in order, the process's scratch directory.
+    scratch_dirs: Vec<PathBuf>,
+    /// The number of processes that are currently running.
+    running: Arc<AtomicUsize>,
+    /// For each process in order, the process's exit history.
+    diagnostics: Vec<Arc<Mutex<ProcessDiagnostics>>>,
+    /// For each process in order, the state shared with the process's
+    /// supervisor.
+    #[cfg_attr(not(feature = "fault-injection"), allow(dead_code))]
+    controls: Vec<Arc<Mutex<ProcessControl>>>,
+    /// For each process in order, the allocated ports by name.
+    ports: Vec<HashMap<String, i32>>,
+    /// The tasks that proxy connections to the processes' ports, through
+    /// which network faults are injected. Empty unless the
+    /// `fault-injection` feature is enabled.
+    proxies: Vec<JoinHandle<()>>,
+    /// The configuration with which the service was created.
+    config: ServiceConfig,
 }
 
 #[async_trait]
 impl NamespacedOrchestrator for NamespacedProcessOrchestrator {
+    /// Starts the processes of a service.
+    ///
+    /// If the service already exists with the same configuration, it is left
+    /// running. If it exists with a different configuration, its processes
+    /// are stopped and started again with the new configuration.
     async fn ensure_service(
         &mut self,
         id: &str,
-        ServiceConfig {
+        config: ServiceConfig,
+    ) -> Result<Box<dyn Service>, anyhow::Error> {
+        let full_id = format!("{}-{}", self.namespace, id);
+        let existing = {
+            let supervisors = self.supervisors.lock().expect("lock poisoned");
+            supervisors
+                .get(id)
+                .map(|state| (state.config == config, state.ports.clone()))
+        };
+        match existing {
+            Some((true, processes)) => {
+                return Ok(Box::new(ProcessService {
+                    hostname: self.hostname.clone(),
+                    processes,
+                }))
+            }
+            Some((false, _)) => {
+                info!("configuration of service {full_id} changed; restarting it");
+                self.drop_service(id).await?;
+            }
+            None => (),
+        }
+        let ServiceConfig {
             image,
             args,
             ports: ports_in,
             memory_limit: _,
             cpu_limit: _,
+            disk_limit: _,
             processes: processes_in,
+            readiness_probe,
+            placement,
             labels: _,
-        }: ServiceConfig<'_>,
-    ) -> Result<Box<dyn Service>, anyhow::Error> {
-        let full_id = format!("{}-{}", self.namespace, id);
-        let mut supervisors = self.supervisors.lock().expect("lock poisoned");
-        if supervisors.contains_key(id) {
-            unimplemented!("ProcessOrchestrator does not yet support updating existing services");
+            annotations: _,
+        } = config.clone();
+        // All processes run on the local machine, so there is no placement to
+        // perform, but invalid hints are still rejected.
+        placement.validate()?;
+        if placement != PlacementHints::default() {
+            info!("ignoring placement hints for service {full_id}: {placement:?}");
         }
-        let path = self.image_dir.join(image);
         let mut processes = vec![];
-        let mut handles = vec![];
-        for _ in 0..processes_in {
-            let mut ports = HashMap::new();
-            for port in &ports_in {
-                let p = self
-                    .port_allocator
-                    .alloc()
-                    .ok_or_else(|| anyhow!("port exhaustion"))?;
-                ports.insert(port.name.clone(), p);
-            }
-            let args = args(&ports);
-            processes.push(ports.clone());
-            handles.push(mz_ore::task::spawn(
-                || format!("service-supervisor: {full_id}"),
-                {
-                    let full_id = full_id.clone();
-                    let args = args.clone();
-                    let path = path.clone();
-                    let port_allocator = Arc::clone(&self.port_allocator);
-                    async move {
-                        defer! {
-                            for port in ports.values() {
-                                port_allocator.free(*port);
-                            }
+        {
+            let mut supervisors = self.supervisors.lock().expect("lock poisoned");
+            // Every process is prepared before any supervisor is spawned. If
+            // a process cannot be prepared, the resources claimed for the
+            // processes prepared so far are released.
+            let mut claims = scopeguard::guard(Claims::default(), |claims| {
+                claims.release(&self.port_allocator, &self.metrics)
+            });
+            let mut prepared = vec![];
+            for i in 0..processes_in {
+                let scratch_dir = self
+                    .data_dir
+                    .join("services")
+                    .join(format!("{full_id}-{i}"));
+                claims.scratch_dirs.push(scratch_dir.clone());
+                fs::create_dir_all(&scratch_dir)?;
+                let mut ports = HashMap::new();
+                for port in &ports_in {
+                    let p = self.alloc_port()?;
+                    self.metrics.ports_allocated.inc();
+                    claims.ports.push(p);
+                    ports.insert(port.name.clone(), p);
+                }
+                // With fault injection, the process listens on ports of its
+                // own, behind proxies that listen on the advertised ports.
+                #[cfg(feature = "fault-injection")]
+                let (process_ports, network) =
+                    self.interpose(&full_id, &ports, &mut claims.proxies)?;
+                #[cfg(not(feature = "fault-injection"))]
+                let process_ports = ports.clone();
+                let launch = match &self.launcher {
+                    Launcher::Binary { image_dir } => Launch {
+                        program: image_dir.join(&image).into_os_string(),
+                        args: ServiceAssignments {
+                            listen_host: self.listen_addr,
+                            ports: &process_ports,
+                            index: ProcessIndex::Known(i),
+                            scratch_directory: Some(&scratch_dir),
+                        }
+                        .expand_args(&args)?,
+                        container: None,
+                    },
+                    Launcher::Container { runtime } => {
+                        // The scratch directory is mounted at the same path
+                        // inside the container, which must be absolute.
+                        let scratch_dir = fs::canonicalize(&scratch_dir)?;
+                        let name = format!("mz-{full_id}-{i}");
+                        let mut launch_args = vec![
+                            "run".into(),
+                            "--rm".into(),
+                            "--init".into(),
+                            format!("--name={name}"),
+                            format!("--volume={0}:{0}", scratch_dir.display()),
+                            format!("--workdir={}", scratch_dir.display()),
+                        ];
+                        // IPv6 addresses must be bracketed to be
+                        // distinguishable from the ports.
+                        let publish_host = match self.listen_addr {
+                            IpAddr::V4(addr) => addr.to_string(),
+                            IpAddr::V6(addr) => format!("[{addr}]"),
+                        };
+                        for port in process_ports.values() {
+                            launch_args.push(format!("--publish={publish_host}:{port}:{port}"));
                         }
-                        loop {
-                            info!(
-                                "Launching {}: {} {}...",
-                                full_id,
-                                path.display(),
-                                args.iter().join(" ")
-                            );
-                            match Command::new(&path).args(&args).status().await {
-                                Ok(status) => {
-                                    error!("{} exited: {}; relaunching in 5s", full_id, status);
-                                }
-                                Err(e) => {
-                                    error!(
-                                        "{} failed to launch: {}; relaunching in 5s",
-                                        full_id, e
-                                    );
-                                }
+                        launch_args.push(image.clone());
+                        // The process must listen on all of the container's
+                        // interfaces for the published ports to reach it.
+                        launch_args.extend(
+                            ServiceAssignments {
+                                listen_host: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                                ports: &process_ports,
+                                index: ProcessIndex::Known(i),
+                                scratch_directory: Some(&scratch_dir),
                             }
-                            time::sleep(Duration::from_secs(5)).await;
+                            .expand_args(&args)?,
+                        );
+                        Launch {
+                            program: runtime.into(),
+                            args: launch_args,
+                            container: Some((runtime.clone(), name)),
                         }
                     }
+                };
+                prepared.push(PreparedProcess {
+                    scratch_dir,
+                    ports,
+                    launch,
+                    #[cfg(feature = "fault-injection")]
+                    network,
+                });
+            }
+            // The supervisors and the service's state take over the claimed
+            // resources.
+            let Claims {
+                proxies,
+                scratch_dirs,
+                ..
+            } = ScopeGuard::into_inner(claims);
+            let mut handles = vec![];
+            let mut diagnostics = vec![];
+            let mut controls = vec![];
+            let running = Arc::new(AtomicUsize::new(0));
+            for (i, process) in prepared.into_iter().enumerate() {
+                processes.push(process.ports.clone());
+                let process_diagnostics = Arc::new(Mutex::new(ProcessDiagnostics::default()));
+                let control = Arc::new(Mutex::new(ProcessControl {
+                    container: process.launch.container.clone(),
+                    #[cfg(feature = "fault-injection")]
+                    network: Some(process.network),
+                    ..Default::default()
+                }));
+                handles.push(mz_ore::task::spawn(
+                    || format!("service-supervisor: {full_id}"),
+                    supervise(
+                        full_id.clone(),
+                        i,
+                        process.launch,
+                        process.scratch_dir,
+                        process.ports,
+                        Arc::clone(&self.port_allocator),
+                        Arc::clone(&running),
+                        Arc::clone(&process_diagnostics),
+                        Arc::clone(&control),
+                        self.metrics.clone(),
+                    ),
+                ));
+                diagnostics.push(process_diagnostics);
+                controls.push(control);
+            }
+            supervisors.insert(
+                id.into(),
+                ServiceState {
+                    handles,
+                    scratch_dirs,
+                    running,
+                    diagnostics,
+                    controls,
+                    ports: processes.clone(),
+                    proxies,
+                    config,
                 },
-            ))
+            );
+            self.metrics.services.inc();
+        }
+        if let Some(probe) = readiness_probe {
+            let addrs: Vec<_> = processes
+                .iter()
+                .map(|ports| format!("{}:{}", self.hostname, ports[&probe.port]))
+                .collect();
+            if let Err(e) = probe.wait(&full_id, &addrs).await {
+                self.drop_service(id).await?;
+                return Err(e);
+            }
         }
-        supervisors.insert(id.into(), handles);
-        Ok(Box::new(ProcessService { processes }))
+        Ok(Box::new(ProcessService {
+            hostname: self.hostname.clone(),
+            processes,
+        }))
     }
 
     async fn drop_service(&mut self, id: &str) -> Result<(), anyhow::Error> {
-        let mut supervisors = self.supervisors.lock().expect("lock poisoned");
-        if let Some(handles) = supervisors.remove(id) {
-            for handle in handles {
-                handle.abort();
-            }
+        let state = {
+            let mut supervisors = self.supervisors.lock().expect("lock poisoned");
+            supervisors.remove(id)
+        };
+        if let Some(state) = state {
+            self.stop_service(state).await?;
         }
         Ok(())
     }
 
-    async fn list_services(&self) -> Result<Vec<String>, anyhow::Error> {
+    async fn drop_all(&mut self) -> Result<(), anyhow::Error> {
+        let states: Vec<_> = {
+            let mut supervisors = self.supervisors.lock().expect("lock poisoned");
+            supervisors.drain().map(|(_id, state)| state).collect()
+        };
+        for state in states {
+            self.stop_service(state).await?;
+        }
+        Ok(())
+    }
+
+    async fn list_services(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<ServiceInfo>, anyhow::Error> {
+        let supervisors = self.supervisors.lock().expect("lock poisoned");
+        Ok(supervisors
+            .iter()
+            .filter(|(_, state)| selector.matches(&state.config.labels))
+            .map(|(id, state)| {
+                let status = if state.running.load(Ordering::SeqCst) == state.handles.len() {
+                    ServiceStatus::Ready
+                } else {
+                    ServiceStatus::NotReady
+                };
+                ServiceInfo {
+                    id: id.clone(),
+                    labels: state.config.labels.clone(),
+                    annotations: state.config.annotations.clone(),
+                    status,
+                }
+            })
+            .collect())
+    }
+
+    async fn service_diagnostics(&self, id: &str) -> Result<ServiceDiagnostics, anyhow::Error> {
         let supervisors = self.supervisors.lock().expect("lock poisoned");
-        Ok(supervisors.keys().cloned().collect())
+        let state = supervisors
+            .get(id)
+            .ok_or_else(|| anyhow!("unknown service {id}"))?;
+        Ok(ServiceDiagnostics {
+            processes: state
+                .diagnostics
+                .iter()
+                .map(|d| d.lock().expect("lock poisoned").clone())
+                .collect(),
+        })
+    }
+}
+
+impl NamespacedProcessOrchestrator {
+    /// Stops the processes of a service that has been removed from
+    /// `self.supervisors` and cleans up after it.
+    ///
+    /// Returns once every supervisor task has terminated, and so once every
+    /// process has been killed.
+    async fn stop_service(&self, state: ServiceState) -> Result<(), anyhow::Error> {
+        self.metrics.services.dec();
+        for handle in &state.proxies {
+            handle.abort();
+        }
+        for handle in &state.handles {
+            handle.abort();
+        }
+        for handle in state.handles {
+            // The task was aborted, so the only possible outcome is a
+            // cancellation error, unless the task panicked first.
+            if let Err(e) = handle.await {
+                if e.is_panic() {
+                    error!("supervisor task panicked: {e}");
+                }
+            }
+        }
+        for scratch_dir in state.scratch_dirs {
+            remove_scratch_dir(&scratch_dir).await?;
+        }
+        Ok(())
+    }
+
+    /// Allocates a port from the port range that is not already in use by
+    /// another process on this machine.
+    ///
+    /// Ports that are in use are skipped. They are returned to the allocator
+    /// once a free port is found, so that later allocations try them again.
+    fn alloc_port(&self) -> Result<i32, anyhow::Error> {
+        let mut busy = vec![];
+        let res = loop {
+            match self.port_allocator.alloc() {
+                None if busy.is_empty() => break Err(anyhow!("port exhaustion")),
+                None => {
+                    break Err(anyhow!(
+                        "port exhaustion: ports {} are in use by other processes",
+                        busy.iter().join(", ")
+                    ))
+                }
+                Some(port) if port_is_free(self.listen_addr, port) => break Ok(port),
+                Some(port) => {
+                    warn!("port {port} is in use by another process; skipping");
+                    busy.push(port);
+                }
+            }
+        };
+        for port in busy {
+            self.port_allocator.free(port);
+        }
+        res
+    }
+}
+
+/// Reports whether `port` can be bound on `addr`.
+///
+/// The probe listener is closed before returning, so there is a small window
+/// in which another process could claim the port before the supervised
+/// process binds it.
+fn port_is_free(addr: IpAddr, port: i32) -> bool {
+    match u16::try_from(port) {
+        Ok(port) => TcpListener::bind(SocketAddr::new(addr, port)).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// A process of a service that is ready to be supervised.
+struct PreparedProcess {
+    /// The process's scratch directory.
+    scratch_dir: PathBuf,
+    /// The allocated ports by name.
+    ports: HashMap<String, i32>,
+    /// How to launch the process.
+    launch: Launch,
+    /// The control for the faults injected into the process's proxies.
+    #[cfg(feature = "fault-injection")]
+    network: fault::NetworkControl,
+}
+
+/// The resources claimed for the processes of a service before their
+/// supervisors are spawned.
+#[derive(Debug, Default)]
+struct Claims {
+    /// The allocated ports.
+    ports: Vec<i32>,
+    /// The tasks that proxy connections to the processes' ports.
+    proxies: Vec<JoinHandle<()>>,
+    /// The processes' scratch directories.
+    scratch_dirs: Vec<PathBuf>,
+}
+
+impl Claims {
+    /// Releases the claimed resources, for a service that failed to start.
+    fn release(self, port_allocator: &IdAllocator<i32>, metrics: &Metrics) {
+        // The proxies free their own ports when they are aborted.
+        for handle in self.proxies {
+            handle.abort();
+        }
+        for port in self.ports {
+            port_allocator.free(port);
+            metrics.ports_allocated.dec();
+        }
+        // Destructors cannot wait, so the directories are removed
+        // synchronously.
+        for scratch_dir in self.scratch_dirs {
+            match fs::remove_dir_all(&scratch_dir) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => error!(
+                    "failed to remove scratch directory {}: {e}",
+                    scratch_dir.display()
+                ),
+            }
+        }
+    }
+}
+
+/// Describes how to launch one process of a service.
+#[derive(Debug, Clone)]
+struct Launch {
+    /// The program to run.
+    program: OsString,
+    /// The arguments to pass to the program.
+    args: Vec<String>,
+    /// If the process runs in a container, the container runtime and the name
+    /// of the container.
+    container: Option<(String, String)>,
+}
+
+/// The state of a supervised process that is shared between its supervisor
+/// and the orchestrator.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "fault-injection"), allow(dead_code))]
+struct ProcessControl {
+    /// The ID of the process, while it is running.
+    pid: Option<u32>,
+    /// If the process runs in a container, the container runtime and the name
+    /// of the container.
+    container: Option<(String, String)>,
+    /// The delay to wait before the next relaunch of the process, if not
+    /// [`RESTART_DELAY`].
+    next_restart_delay: Option<Duration>,
+    /// The network faults injected into connections to the process.
+    #[cfg(feature = "fault-injection")]
+    network: Option<fault::NetworkControl>,
+}
+
+/// Runs the process described by `launch` in `scratch_dir`, relaunching it
+/// whenever it exits.
+///
+/// The ports in `ports` are returned to `port_allocator` when the returned
+/// future is dropped. `running` is incremented while the process is running.
+/// Each exit of the process is recorded in `diagnostics`. The process's ID is
+/// published in `control` while it runs.
+#[allow(clippy::too_many_arguments)]
+async fn supervise(
+    full_id: String,
+    process: usize,
+    launch: Launch,
+    scratch_dir: PathBuf,
+    ports: HashMap<String, i32>,
+    port_allocator: Arc<IdAllocator<i32>>,
+    running: Arc<AtomicUsize>,
+    diagnostics: Arc<Mutex<ProcessDiagnostics>>,
+    control: Arc<Mutex<ProcessControl>>,
+    metrics: Metrics,
+) {
+    defer! {
+        for port in ports.values() {
+            port_allocator.free(*port);
+            metrics.ports_allocated.dec();
+        }
+        // Killing the container runtime's client does not stop the container,
+        // so the container must be removed explicitly. The removal runs in
+        // the background, as destructors cannot wait.
+        if let Some((runtime, name)) = &launch.container {
+            if let Err(e) = remove_container(runtime, name).spawn() {
+                error!("failed to remove container {name}: {e}");
+            }
+        }
+    }
+    let labels = vec![full_id.clone(), process.to_string()];
+    let restarts = metrics.restarts.get_delete_on_drop_counter(labels.clone());
+    let last_exit_code = metrics.last_exit_code.get_delete_on_drop_gauge(labels);
+    loop {
+        // Remove any container left behind by a previous run, whose name
+        // would otherwise conflict with the new container's.
+        if let Some((runtime, name)) = &launch.container {
+            if let Err(e) = remove_container(runtime, name).status().await {
+                warn!("failed to remove container {name}: {e}");
+            }
+        }
+        info!(
+            "Launching {}: {} {}...",
+            full_id,
+            launch.program.to_string_lossy(),
+            launch.args.iter().join(" ")
+        );
+        let status = match Command::new(&launch.program)
+            .args(&launch.args)
+            .current_dir(&scratch_dir)
+            // Ensure the process stops when the service is dropped and this
+            // task is aborted.
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(mut child) => {
+                running.fetch_add(1, Ordering::SeqCst);
+                control.lock().expect("lock poisoned").pid = child.id();
+                let status = child.wait().await;
+                control.lock().expect("lock poisoned").pid = None;
+                running.fetch_sub(1, Ordering::SeqCst);
+                status
+            }
+            Err(e) => Err(e),
+        };
+        let delay = control
+            .lock()
+            .expect("lock poisoned")
+            .next_restart_delay
+            .take()
+            .unwrap_or(RESTART_DELAY);
+        let exit_status = match status {
+            Ok(status) => {
+                // Follow the shell convention for reporting processes that
+                // were terminated by a signal.
+                let code = status
+                    .code()
+                    .or_else(|| status.signal().map(|signal| 128 + signal))
+                    .unwrap_or(-1);
+                last_exit_code.set(i64::from(code));
+                error!("{} exited: {}; relaunching in {:?}", full_id, status, delay);
+                match (status.code(), status.signal()) {
+                    (_, Some(signal)) => ExitStatus::Signal(signal),
+                    (code, None) => ExitStatus::Code(code.unwrap_or(-1)),
+                }
+            }
+            Err(e) => {
+                error!(
+                    "{} failed to launch: {}; relaunching in {:?}",
+                    full_id, e, delay
+                );
+                ExitStatus::LaunchFailed(e.to_string())
+            }
+        };
+        {
+            let mut diagnostics = diagnostics.lock().expect("lock poisoned");
+            diagnostics.exit_count += 1;
+            if diagnostics.recent_exits.len() == EXIT_HISTORY_LEN {
+                diagnostics.recent_exits.remove(0);
+            }
+            diagnostics.recent_exits.push(ProcessExit {
+                time: SystemTime::now(),
+                status: exit_status,
+            });
+        }
+        time::sleep(delay).await;
+        restarts.inc();
+    }
+}
+
+/// Returns a command that forcibly removes the container named `name` via
+/// `runtime`, if the container exists.
+fn remove_container(runtime: &str, name: &str) -> Command {
+    let mut command = Command::new(runtime);
+    command
+        .args(["rm", "--force", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    command
+}
+
+/// Removes the scratch directory at `path`, if it exists.
+async fn remove_scratch_dir(path: &Path) -> Result<(), anyhow::Error> {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow!(e).context(format!("removing scratch directory {}", path.display()))),
     }
 }
 
 #[derive(Debug, Clone)]
 struct ProcessService {
+    /// The hostname at which the processes are reachable.
+    hostname: String,
     /// For each process in order, the allocated ports by name.
     processes: Vec<HashMap<String, i32>>,
 }
@@ -181,7 +794,428 @@ impl Service for ProcessService {
     fn addresses(&self, port: &str) -> Vec<String> {
         self.processes
             .iter()
-            .map(|p| format!("localhost:{}", p[port]))
+            .map(|p| format!("{}:{}", self.hostname, p[port]))
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    use mz_orchestrator::{ExitStatus, Orchestrator, PlacementHints, ServiceConfig, ServicePort};
+    use mz_ore::metrics::MetricsRegistry;
+    use tokio::time::{self, Duration};
+
+    use super::{Launcher, ProcessOrchestrator, ProcessOrchestratorConfig};
+
+    async fn orchestrator(data_dir: &Path) -> ProcessOrchestrator {
+        ProcessOrchestrator::new(
+            ProcessOrchestratorConfig {
+                launcher: Launcher::Binary {
+                    image_dir: "/bin".into(),
+                },
+                data_dir: data_dir.into(),
+                port_range: 32000..=32999,
+                listen_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                hostname: "localhost".into(),
+            },
+            &MetricsRegistry::new(),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn sleep_config(processes: usize) -> ServiceConfig {
+        ServiceConfig {
+            image: "sleep".into(),
+            args: vec!["1000".into()],
+            ..sh_config(processes, "")
+        }
+    }
+
+    /// Returns the configuration of a service whose processes run `script`.
+    fn sh_config(processes: usize, script: &str) -> ServiceConfig {
+        ServiceConfig {
+            image: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            ports: vec![],
+            cpu_limit: None,
+            memory_limit: None,
+            disk_limit: None,
+            processes,
+            readiness_probe: None,
+            placement: PlacementHints::default(),
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+        }
+    }
+
+    /// Waits for every process of the service to start and returns their IDs.
+    async fn pids(orchestrator: &ProcessOrchestrator, namespace: &str, id: &str) -> Vec<u32> {
+        loop {
+            let pids: Option<Vec<_>> = {
+                let namespaces = orchestrator.namespaces.lock().unwrap();
+                let supervisors = namespaces[namespace].lock().unwrap();
+                supervisors[id]
+                    .controls
+                    .iter()
+                    .map(|control| control.lock().unwrap().pid)
+                    .collect()
+            };
+            match pids {
+                Some(pids) => return pids,
+                None => time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+    }
+
+    /// Reports whether the process is running, i.e., exists and is not a
+    /// zombie.
+    fn is_running(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+            Ok(stat) => !stat
+                .rsplit_once(") ")
+                .map_or(false, |(_, s)| s.starts_with('Z')),
+            Err(_) => false,
+        }
+    }
+
+    /// Waits for the processes to stop, as signals are delivered
+    /// asynchronously.
+    async fn assert_stopped(pids: &[u32]) {
+        for _ in 0..500 {
+            if pids.iter().all(|pid| !is_running(*pid)) {
+                return;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("processes {pids:?} still running");
+    }
+
+    #[tokio::test]
+    async fn test_drop_all() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = orchestrator(data_dir.path()).await;
+        let mut a = orchestrator.namespace("a");
+        let mut b = orchestrator.namespace("b");
+        a.ensure_service("s1", sleep_config(2)).await.unwrap();
+        a.ensure_service("s2", sleep_config(1)).await.unwrap();
+        b.ensure_service("s1", sleep_config(1)).await.unwrap();
+        let a_pids = [
+            pids(&orchestrator, "a", "s1").await,
+            pids(&orchestrator, "a", "s2").await,
+        ]
+        .concat();
+        let b_pids = pids(&orchestrator, "b", "s1").await;
+
+        // Services created through one handle can be dropped through another
+        // handle to the same namespace, and other namespaces are untouched.
+        orchestrator.namespace("a").drop_all().await.unwrap();
+        assert_stopped(&a_pids).await;
+        assert!(b_pids.iter().all(|pid| is_running(*pid)));
+        assert!(a
+            .list_services(&Default::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(b.list_services(&Default::default()).await.unwrap().len(), 1);
+        assert!(!data_dir.path().join("services").join("a-s1-0").exists());
+        assert!(data_dir.path().join("services").join("b-s1-0").exists());
+
+        // The namespace remains usable.
+        a.ensure_service("s1", sleep_config(1)).await.unwrap();
+        assert_eq!(a.list_services(&Default::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut orchestrator = orchestrator(data_dir.path()).await;
+        let mut all_pids = vec![];
+        for namespace in ["a", "b"] {
+            orchestrator
+                .namespace(namespace)
+                .ensure_service("s", sleep_config(1))
+                .await
+                .unwrap();
+            all_pids.extend(pids(&orchestrator, namespace, "s").await);
+        }
+        orchestrator.shutdown().await.unwrap();
+        assert_stopped(&all_pids).await;
+        for namespace in ["a", "b"] {
+            let services = orchestrator
+                .namespace(namespace)
+                .list_services(&Default::default())
+                .await
+                .unwrap();
+            assert!(services.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_service_failure() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = ProcessOrchestrator::new(
+            ProcessOrchestratorConfig {
+                launcher: Launcher::Binary {
+                    image_dir: "/bin".into(),
+                },
+                data_dir: data_dir.path().into(),
+                port_range: 32000..=32001,
+                listen_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                hostname: "localhost".into(),
+            },
+            &MetricsRegistry::new(),
+        )
+        .await
+        .unwrap();
+        let mut namespace = orchestrator.namespace("a");
+        let config = |processes| ServiceConfig {
+            ports: vec![ServicePort {
+                name: "p".into(),
+                port_hint: 2100,
+            }],
+            ..sleep_config(processes)
+        };
+
+        // The third process cannot be given a port, so no process is
+        // launched, and the ports and scratch directories of the first two
+        // are released.
+        let err = namespace
+            .ensure_service("big", config(3))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("port exhaustion"), "{err}");
+        assert!(namespace
+            .list_services(&Default::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!data_dir.path().join("services").join("a-big-0").exists());
+        assert!(!data_dir.path().join("services").join("a-big-1").exists());
+        namespace.ensure_service("small", config(2)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_service_diagnostics() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = orchestrator(data_dir.path()).await;
+        let mut namespace = orchestrator.namespace("a");
+        let start = SystemTime::now();
+        namespace
+            .ensure_service("crash", sh_config(2, "kill -SEGV $$"))
+            .await
+            .unwrap();
+        namespace
+            .ensure_service("fail", sh_config(1, "exit 3"))
+            .await
+            .unwrap();
+        namespace
+            .ensure_service("sleep", sleep_config(1))
+            .await
+            .unwrap();
+
+        // Exits are recorded as soon as they happen, before the processes are
+        // relaunched.
+        let mut diagnostics = namespace.service_diagnostics("crash").await.unwrap();
+        for _ in 0..500 {
+            if diagnostics.processes.iter().all(|p| p.exit_count > 0) {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+            diagnostics = namespace.service_diagnostics("crash").await.unwrap();
+        }
+        assert_eq!(diagnostics.processes.len(), 2);
+        for process in &diagnostics.processes {
+            assert_eq!(process.exit_count, 1);
+            assert_eq!(process.recent_exits[0].status, ExitStatus::Signal(11));
+        }
+        assert_eq!(diagnostics.exits_since(start), 2);
+        assert_eq!(
+            diagnostics.last_exit().unwrap().status.to_string(),
+            "SIGSEGV"
+        );
+
+        let mut diagnostics = namespace.service_diagnostics("fail").await.unwrap();
+        for _ in 0..500 {
+            if diagnostics.processes[0].exit_count > 0 {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+            diagnostics = namespace.service_diagnostics("fail").await.unwrap();
+        }
+        assert_eq!(
+            diagnostics.processes[0].recent_exits[0].status,
+            ExitStatus::Code(3)
+        );
+
+        let diagnostics = namespace.service_diagnostics("sleep").await.unwrap();
+        assert_eq!(diagnostics.processes[0].exit_count, 0);
+        assert!(diagnostics.last_exit().is_none());
+
+        assert!(namespace.service_diagnostics("bogus").await.is_err());
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_process_faults() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = orchestrator(data_dir.path()).await;
+        let mut namespace = orchestrator.namespace("a");
+        namespace
+            .ensure_service("s", sleep_config(1))
+            .await
+            .unwrap();
+        let pid = pids(&orchestrator, "a", "s").await[0];
+
+        // A paused process is stopped but not exited.
+        let state = || {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap();
+            stat.rsplit_once(") ").unwrap().1.chars().next().unwrap()
+        };
+        orchestrator.pause_process("a", "s", 0).await.unwrap();
+        for _ in 0..500 {
+            if state() == 'T' {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(state(), 'T');
+        orchestrator.resume_process("a", "s", 0).await.unwrap();
+        for _ in 0..500 {
+            if state() != 'T' {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_ne!(state(), 'T');
+
+        // A killed process is relaunched after the overridden delay.
+        orchestrator
+            .delay_restart("a", "s", 0, Duration::from_millis(100))
+            .unwrap();
+        orchestrator.kill_process("a", "s", 0).await.unwrap();
+        assert_stopped(&[pid]).await;
+        let mut new_pid = pids(&orchestrator, "a", "s").await[0];
+        for _ in 0..500 {
+            if new_pid != pid {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+            new_pid = pids(&orchestrator, "a", "s").await[0];
+        }
+        assert_ne!(new_pid, pid);
+        assert!(is_running(new_pid));
+        let diagnostics = namespace.service_diagnostics("s").await.unwrap();
+        assert_eq!(
+            diagnostics.processes[0].recent_exits[0].status,
+            ExitStatus::Signal(9)
+        );
+
+        assert!(orchestrator.pause_process("a", "bogus", 0).await.is_err());
+        assert!(orchestrator.kill_process("a", "s", 1).await.is_err());
+        assert!(orchestrator
+            .delay_network("b", "s", 0, Duration::ZERO)
+            .is_err());
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn test_network_faults() {
+        use mz_orchestrator::ServicePort;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::{TcpListener, TcpStream};
+
+        async fn echo(conn: &mut TcpStream, timeout: Duration) -> Result<(), time::error::Elapsed> {
+            time::timeout(timeout, async {
+                conn.write_all(b"ping").await.unwrap();
+                let mut buf = [0; 4];
+                conn.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"ping");
+            })
+            .await
+        }
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = orchestrator(data_dir.path()).await;
+        let mut namespace = orchestrator.namespace("a");
+
+        // The process reports the port on which it must listen, and the test
+        // serves an echo server there in its stead.
+        let service = namespace
+            .ensure_service(
+                "s",
+                ServiceConfig {
+                    ports: vec![ServicePort {
+                        name: "p".into(),
+                        port_hint: 0,
+                    }],
+                    ..sh_config(1, "echo %{ports.p} > port; exec sleep 1000")
+                },
+            )
+            .await
+            .unwrap();
+        let port_file = data_dir.path().join("services").join("a-s-0").join("port");
+        let port = loop {
+            match std::fs::read_to_string(&port_file) {
+                Ok(port) if port.ends_with('\n') => break port.trim().to_string(),
+                _ => time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let listener = TcpListener::bind(format!("127.0.0.1:{port}"))
+            .await
+            .unwrap();
+        mz_ore::task::spawn(|| "echo", async move {
+            loop {
+                let (mut conn, _) = listener.accept().await.unwrap();
+                mz_ore::task::spawn(|| "echo-connection", async move {
+                    let (mut read, mut write) = conn.split();
+                    let _ = tokio::io::copy(&mut read, &mut write).await;
+                });
+            }
+        });
+
+        let addr = &service.addresses("p")[0];
+        assert_ne!(addr, &format!("localhost:{port}"));
+        let mut conn = TcpStream::connect(addr).await.unwrap();
+        echo(&mut conn, Duration::from_secs(10)).await.unwrap();
+
+        // A delay applies in each direction.
+        orchestrator
+            .delay_network("a", "s", 0, Duration::from_millis(300))
+            .unwrap();
+        let start = time::Instant::now();
+        echo(&mut conn, Duration::from_secs(10)).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(600));
+        orchestrator
+            .delay_network("a", "s", 0, Duration::ZERO)
+            .unwrap();
+
+        // A partition holds traffic on existing and new connections until it
+        // heals.
+        orchestrator.partition_process("a", "s", 0).unwrap();
+        conn.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        assert!(
+            time::timeout(Duration::from_millis(500), conn.read_exact(&mut buf))
+                .await
+                .is_err()
+        );
+        let mut new_conn = TcpStream::connect(addr).await.unwrap();
+        assert!(echo(&mut new_conn, Duration::from_millis(500))
+            .await
+            .is_err());
+        orchestrator.heal_process("a", "s", 0).unwrap();
+        time::timeout(Duration::from_secs(10), conn.read_exact(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf, b"ping");
+        let mut new_conn = TcpStream::connect(addr).await.unwrap();
+        echo(&mut new_conn, Duration::from_secs(10)).await.unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Metrics for the process orchestrator.
+
+use mz_ore::metric;
+use mz_ore::metrics::{IntCounterVec, IntGaugeVec, MetricsRegistry, UIntGauge};
+
+/// Metrics reported by a [`ProcessOrchestrator`](crate::ProcessOrchestrator).
+#[derive(Debug, Clone)]
+pub(crate) struct Metrics {
+    pub(crate) services: UIntGauge,
+    pub(crate) restarts: IntCounterVec,
+    pub(crate) last_exit_code: IntGaugeVec,
+    pub(crate) ports_allocated: UIntGauge,
+    pub(crate) ports_total: UIntGauge,
+}
+
+impl Metrics {
+    pub(crate) fn register_with(registry: &MetricsRegistry) -> Metrics {
+        Metrics {
+            services: registry.register(metric!(
+                name: "mz_orchestrator_process_services",
+                help: "The number of services supervised by the process orchestrator",
+            )),
+            restarts: registry.register(metric!(
+                name: "mz_orchestrator_process_restarts_total",
+                help: "The number of times a supervised process has been relaunched",
+                var_labels: ["service", "process"],
+            )),
+            last_exit_code: registry.register(metric!(
+                name: "mz_orchestrator_process_last_exit_code",
+                help: "The exit code of the last run of a supervised process, or 128 plus \
+                       the signal number if the process was killed by a signal",
+                var_labels: ["service", "process"],
+            )),
+            ports_allocated: registry.register(metric!(
+                name: "mz_orchestrator_process_ports_allocated",
+                help: "The number of ports in the port range allocated to supervised processes",
+            )),
+            ports_total: registry.register(metric!(
+                name: "mz_orchestrator_process_ports_total",
+                help: "The number of ports in the port range",
+            )),
+        }
+    }
+}
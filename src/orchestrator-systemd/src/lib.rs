@@ -0,0 +1,841 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! An orchestrator that runs services as systemd transient units.
+//!
+//! Each process of a service is started as a transient service unit via the
+//! systemd D-Bus API. systemd supervises the process, restarting it whenever
+//! it exits, enforces the service's memory and CPU limits, and routes the
+//! process's output to the journal. The units do not outlive the service: they
+//! are stopped when the service is dropped, and systemd garbage collects them
+//! once they are inactive.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use itertools::Itertools;
+use tracing::{info, warn};
+use zbus::dbus_proxy;
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::Connection;
+
+use mz_orchestrator::{
+    CpuLimit, ExitStatus, LabelSelector, MemoryLimit, NamespacedOrchestrator, Orchestrator,
+    PlacementHints, ProcessDiagnostics, ProcessExit, ProcessIndex, Service, ServiceAssignments,
+    ServiceConfig, ServiceDiagnostics, ServiceInfo, ServiceStatus,
+};
+use mz_ore::id_gen::IdAllocator;
+
+/// Configures a [`SystemdOrchestrator`].
+#[derive(Debug, Clone)]
+pub struct SystemdOrchestratorConfig {
+    /// The directory in which to look for the executables named by the images
+    /// of services.
+    pub image_dir: PathBuf,
+    /// The directory in which the orchestrator should create a scratch
+    /// directory for each process.
+    ///
+    /// The scratch directory for a process is located at
+    /// `<data_dir>/services/<namespace>-<id>-<process>`, and is the working
+    /// directory of the process's unit.
+    pub data_dir: PathBuf,
+    /// The range of ports to allocate.
+    pub port_range: RangeInclusive<i32>,
+    /// The IP address on which supervised processes should listen.
+    pub listen_addr: IpAddr,
+    /// The hostname to report in the addresses of supervised processes.
+    pub hostname: String,
+    /// Whether to manage units in the service manager of the calling user,
+    /// rather than in the system's service manager.
+    pub user: bool,
+    /// The prefix of the name of every unit the orchestrator creates.
+    ///
+    /// The unit for a process is named
+    /// `<unit_prefix>-<namespace>-<id>-<process>.service`. Deployments that
+    /// share a service manager must use distinct prefixes.
+    pub unit_prefix: String,
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Manager {
+    fn start_transient_unit(
+        &self,
+        name: &str,
+        mode: &str,
+        properties: &[(&str, Value<'_>)],
+        aux: &[(&str, &[(&str, Value<'_>)])],
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    fn reset_failed_unit(&self, name: &str) -> zbus::Result<()>;
+
+    fn get_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Unit {
+    #[dbus_proxy(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Service",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait ServiceUnit {
+    #[dbus_proxy(property, name = "NRestarts")]
+    fn n_restarts(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn exec_main_code(&self) -> zbus::Result<i32>;
+
+    #[dbus_proxy(property)]
+    fn exec_main_status(&self) -> zbus::Result<i32>;
+
+    #[dbus_proxy(property)]
+    fn exec_main_exit_timestamp(&self) -> zbus::Result<u64>;
+
+    #[dbus_proxy(property, name = "MainPID")]
+    fn main_pid(&self) -> zbus::Result<u32>;
+}
+
+/// The value of `ExecMainCode` for a process that exited normally, as
+/// reported by `waitid(2)`.
+const CLD_EXITED: i32 = 1;
+
+/// An orchestrator backed by systemd transient units on the local machine.
+///
+/// Unlike the process orchestrator, processes are supervised by systemd rather
+/// than by this process, so they are subject to the service's resource limits
+/// and their output is captured by the journal.
+#[derive(Debug, Clone)]
+pub struct SystemdOrchestrator {
+    config: Arc<SystemdOrchestratorConfig>,
+    manager: ManagerProxy<'static>,
+    port_allocator: Arc<IdAllocator<i32>>,
+    /// The services of each namespace that has been entered, by namespace.
+    namespaces: Arc<Mutex<HashMap<String, Services>>>,
+}
+
+/// The services within a namespace, by ID.
+type Services = Arc<Mutex<HashMap<String, ServiceState>>>;
+
+impl SystemdOrchestrator {
+    /// Creates a new systemd orchestrator from the provided configuration.
+    ///
+    /// Returns an error if the service manager cannot be reached.
+    pub async fn new(
+        config: SystemdOrchestratorConfig,
+    ) -> Result<SystemdOrchestrator, anyhow::Error> {
+        let connection = if config.user {
+            Connection::session().await
+        } else {
+            Connection::system().await
+        }
+        .context("connecting to D-Bus")?;
+        let manager = ManagerProxy::new(&connection)
+            .await
+            .context("connecting to the systemd service manager")?;
+        let config = SystemdOrchestratorConfig {
+            image_dir: fs::canonicalize(&config.image_dir)?,
+            ..config
+        };
+        Ok(SystemdOrchestrator {
+            port_allocator: Arc::new(IdAllocator::new(
+                *config.port_range.start(),
+                *config.port_range.end(),
+            )),
+            config: Arc::new(config),
+            manager,
+            namespaces: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+}
+
+#[async_trait]
+impl Orchestrator for SystemdOrchestrator {
+    fn namespace(&self, namespace: &str) -> Box<dyn NamespacedOrchestrator> {
+        let services = {
+            let mut namespaces = self.namespaces.lock().expect("lock poisoned");
+            Arc::clone(namespaces.entry(namespace.into()).or_default())
+        };
+        Box::new(NamespacedSystemdOrchestrator {
+            namespace: namespace.into(),
+            config: Arc::clone(&self.config),
+            manager: self.manager.clone(),
+            port_allocator: Arc::clone(&self.port_allocator),
+            services,
+        })
+    }
+
+    async fn shutdown(&mut self) -> Result<(), anyhow::Error> {
+        let namespaces: Vec<_> = {
+            let namespaces = self.namespaces.lock().expect("lock poisoned");
+            namespaces.keys().cloned().collect()
+        };
+        for namespace in namespaces {
+            self.namespace(&namespace).drop_all().await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct NamespacedSystemdOrchestrator {
+    namespace: String,
+    config: Arc<SystemdOrchestratorConfig>,
+    manager: ManagerProxy<'static>,
+    port_allocator: Arc<IdAllocator<i32>>,
+    services: Services,
+}
+
+/// The state of a service managed by a [`NamespacedSystemdOrchestrator`].
+#[derive(Debug)]
+struct ServiceState {
+    /// For each process in order, the name of the process's unit.
+    units: Vec<String>,
+    /// For each process in order, the process's scratch directory.
+    scratch_dirs: Vec<PathBuf>,
+    /// The ports allocated to the service's processes.
+    ports: Vec<i32>,
+    /// For each process in order, the allocated ports by name.
+    processes: Vec<HashMap<String, i32>>,
+    /// The configuration with which the service was started.
+    config: ServiceConfig,
+}
+
+#[async_trait]
+impl NamespacedOrchestrator for NamespacedSystemdOrchestrator {
+    /// Starts the units of a service.
+    ///
+    /// If the service already exists with the same configuration and all of
+    /// its units are still running, it is left running. Otherwise, any existing
+    /// units are stopped and started again with the new configuration.
+    async fn ensure_service(
+        &mut self,
+        id: &str,
+        config: ServiceConfig,
+    ) -> Result<Box<dyn Service>, anyhow::Error> {
+        let full_id = format!("{}-{}", self.namespace, id);
+        let existing = {
+            let services = self.services.lock().expect("lock poisoned");
+            services.get(id).map(|state| {
+                (
+                    state.config == config,
+                    state.units.clone(),
+                    state.processes.clone(),
+                )
+            })
+        };
+        match existing {
+            Some((true, units, processes)) => {
+                let mut running = true;
+                for unit in &units {
+                    // A unit whose process is waiting to be restarted is
+                    // activating.
+                    running &= matches!(
+                        self.active_state(unit).await?.as_deref(),
+                        Some("active" | "activating")
+                    );
+                }
+                if running {
+                    return Ok(Box::new(SystemdService {
+                        hostname: self.config.hostname.clone(),
+                        processes,
+                    }));
+                }
+                info!("units of service {full_id} are no longer running; restarting it");
+            }
+            Some((false, _, _)) => {
+                info!("configuration of service {full_id} changed; restarting it");
+            }
+            None => (),
+        }
+        let ServiceConfig {
+            image,
+            args,
+            ports: ports_in,
+            memory_limit,
+            cpu_limit,
+            disk_limit: _,
+            processes: processes_in,
+            readiness_probe,
+            placement,
+            labels: _,
+            annotations: _,
+        } = config.clone();
+        // All units run on the local machine, so there is no placement to
+        // perform, but invalid hints are still rejected.
+        placement.validate()?;
+        if placement != PlacementHints::default() {
+            info!("ignoring placement hints for service {full_id}: {placement:?}");
+        }
+        self.drop_service(id).await?;
+
+        let mut state = ServiceState {
+            units: vec![],
+            scratch_dirs: vec![],
+            ports: vec![],
+            processes: vec![],
+            config,
+        };
+        let res: Result<(), anyhow::Error> = async {
+            for i in 0..processes_in {
+                let unit = format!("{}-{full_id}-{i}.service", self.config.unit_prefix);
+                let scratch_dir = self
+                    .config
+                    .data_dir
+                    .join("services")
+                    .join(format!("{full_id}-{i}"));
+                fs::create_dir_all(&scratch_dir)?;
+                let scratch_dir = fs::canonicalize(&scratch_dir)?;
+                state.scratch_dirs.push(scratch_dir.clone());
+                let mut ports = HashMap::new();
+                for port in &ports_in {
+                    let p = self.alloc_port()?;
+                    state.ports.push(p);
+                    ports.insert(port.name.clone(), p);
+                }
+                let program = self.config.image_dir.join(&image);
+                let program = program.to_string_lossy().into_owned();
+                let mut argv = vec![program.clone()];
+                argv.extend(
+                    ServiceAssignments {
+                        listen_host: self.config.listen_addr,
+                        ports: &ports,
+                        index: ProcessIndex::Known(i),
+                        scratch_directory: Some(&scratch_dir),
+                    }
+                    .expand_args(&args)?,
+                );
+                info!("Starting unit {unit}: {}...", argv.iter().join(" "));
+
+                // A unit with the same name may have been left behind by a
+                // previous run that did not shut down cleanly.
+                self.stop_unit(&unit).await;
+
+                let properties = unit_properties(
+                    &full_id,
+                    i,
+                    program,
+                    argv,
+                    &scratch_dir,
+                    memory_limit.as_ref(),
+                    cpu_limit.as_ref(),
+                )?;
+                self.manager
+                    .start_transient_unit(&unit, "replace", &properties, &[])
+                    .await
+                    .with_context(|| format!("starting unit {unit}"))?;
+                state.units.push(unit);
+                state.processes.push(ports);
+            }
+            Ok(())
+        }
+        .await;
+        let processes = state.processes.clone();
+        {
+            let mut services = self.services.lock().expect("lock poisoned");
+            services.insert(id.into(), state);
+        }
+        if let Err(e) = res {
+            self.drop_service(id).await?;
+            return Err(e);
+        }
+
+        if let Some(probe) = readiness_probe {
+            let addrs: Vec<_> = processes
+                .iter()
+                .map(|ports| format!("{}:{}", self.config.hostname, ports[&probe.port]))
+                .collect();
+            if let Err(e) = probe.wait(&full_id, &addrs).await {
+                self.drop_service(id).await?;
+                return Err(e);
+            }
+        }
+        Ok(Box::new(SystemdService {
+            hostname: self.config.hostname.clone(),
+            processes,
+        }))
+    }
+
+    async fn drop_service(&mut self, id: &str) -> Result<(), anyhow::Error> {
+        let state = {
+            let mut services = self.services.lock().expect("lock poisoned");
+            services.remove(id)
+        };
+        if let Some(state) = state {
+            self.stop_service(state).await?;
+        }
+        Ok(())
+    }
+
+    async fn drop_all(&mut self) -> Result<(), anyhow::Error> {
+        let states: Vec<_> = {
+            let mut services = self.services.lock().expect("lock poisoned");
+            services.drain().map(|(_id, state)| state).collect()
+        };
+        for state in states {
+            self.stop_service(state).await?;
+        }
+        Ok(())
+    }
+
+    async fn list_services(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<ServiceInfo>, anyhow::Error> {
+        let services: Vec<_> = {
+            let services = self.services.lock().expect("lock poisoned");
+            services
+                .iter()
+                .filter(|(_, state)| selector.matches(&state.config.labels))
+                .map(|(id, state)| {
+                    (
+                        id.clone(),
+                        state.units.clone(),
+                        state.config.labels.clone(),
+                        state.config.annotations.clone(),
+                    )
+                })
+                .collect()
+        };
+        let mut infos = vec![];
+        for (id, units, labels, annotations) in services {
+            let mut status = ServiceStatus::Ready;
+            for unit in &units {
+                if self.active_state(unit).await?.as_deref() != Some("active") {
+                    status = ServiceStatus::NotReady;
+                    break;
+                }
+            }
+            infos.push(ServiceInfo {
+                id,
+                labels,
+                annotations,
+                status,
+            });
+        }
+        Ok(infos)
+    }
+
+    /// Reports the exits of the processes of a service.
+    ///
+    /// systemd retains only the most recent exit of a unit's process, so at
+    /// most one exit is reported per process.
+    async fn service_diagnostics(&self, id: &str) -> Result<ServiceDiagnostics, anyhow::Error> {
+        let units = {
+            let services = self.services.lock().expect("lock poisoned");
+            services
+                .get(id)
+                .ok_or_else(|| anyhow!("unknown service {id}"))?
+                .units
+                .clone()
+        };
+        let mut processes = vec![];
+        for unit in units {
+            processes.push(self.process_diagnostics(&unit).await?);
+        }
+        Ok(ServiceDiagnostics { processes })
+    }
+}
+
+impl NamespacedSystemdOrchestrator {
+    /// Stops the units of a service that has been removed from
+    /// `self.services` and cleans up after it.
+    async fn stop_service(&self, state: ServiceState) -> Result<(), anyhow::Error> {
+        for unit in &state.units {
+            self.stop_unit(unit).await;
+        }
+        for port in state.ports {
+            self.port_allocator.free(port);
+        }
+        for scratch_dir in state.scratch_dirs {
+            remove_scratch_dir(&scratch_dir).await?;
+        }
+        Ok(())
+    }
+
+    /// Stops the unit named `unit` and clears any record of its failure, so
+    /// that a new unit with the same name can be started.
+    ///
+    /// Errors are logged rather than returned, as the unit may not exist.
+    async fn stop_unit(&self, unit: &str) {
+        if let Err(e) = self.manager.stop_unit(unit, "replace").await {
+            if !is_no_such_unit(&e) {
+                warn!("failed to stop unit {unit}: {e}");
+            }
+        }
+        if let Err(e) = self.manager.reset_failed_unit(unit).await {
+            if !is_no_such_unit(&e) {
+                warn!("failed to reset unit {unit}: {e}");
+            }
+        }
+    }
+
+    /// Returns the active state of the unit named `unit`, or `None` if the
+    /// unit is not loaded.
+    async fn active_state(&self, unit: &str) -> Result<Option<String>, anyhow::Error> {
+        let path = match self.manager.get_unit(unit).await {
+            Ok(path) => path,
+            Err(e) if is_no_such_unit(&e) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let proxy = UnitProxy::builder(self.manager.connection())
+            .path(path)?
+            .build()
+            .await?;
+        Ok(Some(proxy.active_state().await?))
+    }
+
+    /// Returns the exit history of the process of the unit named `unit`.
+    async fn process_diagnostics(&self, unit: &str) -> Result<ProcessDiagnostics, anyhow::Error> {
+        let path = match self.manager.get_unit(unit).await {
+            Ok(path) => path,
+            Err(e) if is_no_such_unit(&e) => return Ok(ProcessDiagnostics::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let proxy = ServiceUnitProxy::builder(self.manager.connection())
+            .path(path)?
+            .build()
+            .await?;
+        let restarts = proxy.n_restarts().await?;
+        let exit_timestamp = proxy.exec_main_exit_timestamp().await?;
+        let mut diagnostics = ProcessDiagnostics {
+            exit_count: u64::from(restarts),
+            recent_exits: vec![],
+        };
+        // A zero timestamp indicates that the process has never exited.
+        if exit_timestamp != 0 {
+            let status = match proxy.exec_main_code().await? {
+                CLD_EXITED => ExitStatus::Code(proxy.exec_main_status().await?),
+                _ => ExitStatus::Signal(proxy.exec_main_status().await?),
+            };
+            diagnostics.recent_exits.push(ProcessExit {
+                time: SystemTime::UNIX_EPOCH + Duration::from_micros(exit_timestamp),
+                status,
+            });
+            // The most recent exit may not yet have been followed by a
+            // restart.
+            diagnostics.exit_count = diagnostics.exit_count.max(1);
+        }
+        Ok(diagnostics)
+    }
+
+    /// Allocates a port from the port range that is not already in use by
+    /// another process on this machine.
+    ///
+    /// Ports that are in use are skipped. They are returned to the allocator
+    /// once a free port is found, so that later allocations try them again.
+    fn alloc_port(&self) -> Result<i32, anyhow::Error> {
+        let mut busy = vec![];
+        let res = loop {
+            match self.port_allocator.alloc() {
+                None => break Err(anyhow!("port exhaustion")),
+                Some(port) if port_is_free(self.config.listen_addr, port) => break Ok(port),
+                Some(port) => {
+                    warn!("port {port} is in use by another process; skipping");
+                    busy.push(port);
+                }
+            }
+        };
+        for port in busy {
+            self.port_allocator.free(port);
+        }
+        res
+    }
+}
+
+/// Returns the properties of the transient unit for process `i` of the service
+/// `full_id`, which runs `argv` via `program` in `scratch_dir`.
+fn unit_properties(
+    full_id: &str,
+    i: usize,
+    program: String,
+    argv: Vec<String>,
+    scratch_dir: &Path,
+    memory_limit: Option<&MemoryLimit>,
+    cpu_limit: Option<&CpuLimit>,
+) -> Result<Vec<(&'static str, Value<'static>)>, anyhow::Error> {
+    let mut properties = vec![
+        (
+            "Description",
+            Value::from(format!("Materialize service {full_id}")),
+        ),
+        ("ExecStart", Value::from(vec![(program, argv, false)])),
+        (
+            "WorkingDirectory",
+            Value::from(scratch_dir.to_string_lossy().into_owned()),
+        ),
+        ("Restart", Value::from("always")),
+        ("RestartUSec", Value::from(5_000_000u64)),
+        // Never give up on restarting a crashing process.
+        ("StartLimitIntervalUSec", Value::from(0u64)),
+        ("CollectMode", Value::from("inactive-or-failed")),
+        ("StandardOutput", Value::from("journal")),
+        ("StandardError", Value::from("journal")),
+        ("SyslogIdentifier", Value::from(format!("mz-{full_id}-{i}"))),
+    ];
+    if let Some(memory_limit) = memory_limit {
+        let bytes = u64::try_from(memory_limit.as_bytes())?;
+        properties.push(("MemoryMax", Value::from(bytes)));
+    }
+    if let Some(cpu_limit) = cpu_limit {
+        // The quota is the CPU time the unit may use per second of wall-clock
+        // time, and one millicpu is one millisecond per second.
+        let usecs = u64::try_from(cpu_limit.as_millicpus())? * 1_000;
+        properties.push(("CPUQuotaPerSecUSec", Value::from(usecs)));
+    }
+    Ok(properties)
+}
+
+/// Reports whether `e` indicates that a unit does not exist or is not loaded.
+fn is_no_such_unit(e: &zbus::Error) -> bool {
+    match e {
+        zbus::Error::MethodError(name, _, _) => {
+            name.as_str() == "org.freedesktop.systemd1.NoSuchUnit"
+        }
+        _ => false,
+    }
+}
+
+/// Reports whether `port` can be bound on `addr`.
+fn port_is_free(addr: IpAddr, port: i32) -> bool {
+    match u16::try_from(port) {
+        Ok(port) => TcpListener::bind(SocketAddr::new(addr, port)).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Removes the scratch directory at `path`, if it exists.
+async fn remove_scratch_dir(path: &Path) -> Result<(), anyhow::Error> {
+    match tokio::fs::remove_dir_all(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow!(e).context(format!("removing scratch directory {}", path.display()))),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SystemdService {
+    /// The hostname at which the processes are reachable.
+    hostname: String,
+    /// For each process in order, the allocated ports by name.
+    processes: Vec<HashMap<String, i32>>,
+}
+
+impl Service for SystemdService {
+    fn addresses(&self, port: &str) -> Vec<String> {
+        self.processes
+            .iter()
+            .map(|p| format!("{}:{}", self.hostname, p[port]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::path::Path;
+
+    use mz_orchestrator::{CpuLimit, MemoryLimit, Orchestrator, PlacementHints, ServiceConfig};
+    use zbus::zvariant::Value;
+
+    use tokio::time::{self, Duration};
+
+    use super::{
+        is_no_such_unit, unit_properties, ServiceUnitProxy, SystemdOrchestrator,
+        SystemdOrchestratorConfig, UnitProxy,
+    };
+
+    #[test]
+    fn test_unit_properties() {
+        let property = |properties: &[(&str, Value)], name| {
+            properties
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.clone())
+        };
+        let argv = vec!["/bin/sleep".to_string(), "1000".to_string()];
+        let properties = unit_properties(
+            "ns-s",
+            1,
+            "/bin/sleep".into(),
+            argv.clone(),
+            Path::new("/data/services/ns-s-1"),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            property(&properties, "ExecStart"),
+            Some(Value::from(vec![(
+                "/bin/sleep".to_string(),
+                argv.clone(),
+                false
+            )]))
+        );
+        assert_eq!(
+            property(&properties, "WorkingDirectory"),
+            Some(Value::from("/data/services/ns-s-1"))
+        );
+        assert_eq!(
+            property(&properties, "SyslogIdentifier"),
+            Some(Value::from("mz-ns-s-1"))
+        );
+        assert_eq!(property(&properties, "MemoryMax"), None);
+        assert_eq!(property(&properties, "CPUQuotaPerSecUSec"), None);
+
+        let properties = unit_properties(
+            "ns-s",
+            0,
+            "/bin/sleep".into(),
+            argv,
+            Path::new("/data/services/ns-s-0"),
+            Some(&MemoryLimit::from_bytes(1 << 30)),
+            Some(&CpuLimit::from_millicpus(1500)),
+        )
+        .unwrap();
+        assert_eq!(
+            property(&properties, "MemoryMax"),
+            Some(Value::from(1u64 << 30))
+        );
+        // 1.5 CPUs may use 1.5 seconds of CPU time per second.
+        assert_eq!(
+            property(&properties, "CPUQuotaPerSecUSec"),
+            Some(Value::from(1_500_000u64))
+        );
+    }
+
+    fn sleep_config(args: &[&str]) -> ServiceConfig {
+        ServiceConfig {
+            image: "sleep".into(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ports: vec![],
+            cpu_limit: None,
+            memory_limit: None,
+            disk_limit: None,
+            processes: 1,
+            readiness_probe: None,
+            placement: PlacementHints::default(),
+            labels: HashMap::new(),
+            annotations: HashMap::new(),
+        }
+    }
+
+    /// Returns the ID of the main process of the service's first unit.
+    async fn main_pid(orchestrator: &SystemdOrchestrator, unit: &str) -> u32 {
+        let path = orchestrator.manager.get_unit(unit).await.unwrap();
+        let proxy = ServiceUnitProxy::builder(orchestrator.manager.connection())
+            .path(path)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        proxy.main_pid().await.unwrap()
+    }
+
+    /// Waits for the unit to stop, as units are stopped asynchronously.
+    async fn wait_until_stopped(orchestrator: &SystemdOrchestrator, unit: &str) {
+        for _ in 0..500 {
+            let path = match orchestrator.manager.get_unit(unit).await {
+                Ok(path) => path,
+                Err(e) if is_no_such_unit(&e) => return,
+                Err(e) => panic!("{e}"),
+            };
+            let proxy = UnitProxy::builder(orchestrator.manager.connection())
+                .path(path)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            if proxy.active_state().await.unwrap() == "inactive" {
+                return;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("unit {unit} did not stop");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a systemd user session"]
+    async fn test_ensure_service_idempotent() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let orchestrator = SystemdOrchestrator::new(SystemdOrchestratorConfig {
+            image_dir: "/bin".into(),
+            data_dir: data_dir.path().into(),
+            port_range: 33000..=33999,
+            listen_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            hostname: "localhost".into(),
+            user: true,
+            unit_prefix: format!("mz-test-{}", std::process::id()),
+        })
+        .await
+        .unwrap();
+        let unit = format!("mz-test-{}-ns-s-0.service", std::process::id());
+        let mut namespace = orchestrator.namespace("ns");
+
+        // Ensuring a service with an unchanged configuration leaves its
+        // process running.
+        namespace
+            .ensure_service("s", sleep_config(&["1000"]))
+            .await
+            .unwrap();
+        let pid = main_pid(&orchestrator, &unit).await;
+        assert_ne!(pid, 0);
+        namespace
+            .ensure_service("s", sleep_config(&["1000"]))
+            .await
+            .unwrap();
+        assert_eq!(main_pid(&orchestrator, &unit).await, pid);
+
+        // Changing the configuration restarts it.
+        namespace
+            .ensure_service("s", sleep_config(&["2000"]))
+            .await
+            .unwrap();
+        let new_pid = main_pid(&orchestrator, &unit).await;
+        assert_ne!(new_pid, pid);
+
+        // As does its unit being stopped behind the orchestrator's back.
+        orchestrator
+            .manager
+            .stop_unit(&unit, "replace")
+            .await
+            .unwrap();
+        wait_until_stopped(&orchestrator, &unit).await;
+        namespace
+            .ensure_service("s", sleep_config(&["2000"]))
+            .await
+            .unwrap();
+        let restarted_pid = main_pid(&orchestrator, &unit).await;
+        assert_ne!(restarted_pid, 0);
+        assert_ne!(restarted_pid, new_pid);
+
+        namespace.drop_service("s").await.unwrap();
+        wait_until_stopped(&orchestrator, &unit).await;
+    }
+}
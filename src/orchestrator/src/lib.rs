@@ -7,12 +7,19 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::HashMap;
-use std::fmt;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
+use anyhow::{anyhow, bail};
 use async_trait::async_trait;
-use derivative::Derivative;
 use dyn_clonable::clonable;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
 
 /// An orchestrator manages services.
 ///
@@ -28,9 +35,16 @@ use dyn_clonable::clonable;
 /// The intent is that you can implement `Orchestrator` with pods in Kubernetes,
 /// containers in Docker, or processes on your local machine.
 #[clonable]
+#[async_trait]
 pub trait Orchestrator: fmt::Debug + Clone + Send {
     /// Enter a namespace in the orchestrator.
     fn namespace(&self, namespace: &str) -> Box<dyn NamespacedOrchestrator>;
+
+    /// Drops every service in every namespace that has been entered via
+    /// [`Orchestrator::namespace`], and waits for the services to terminate.
+    ///
+    /// The orchestrator remains usable afterwards.
+    async fn shutdown(&mut self) -> Result<(), anyhow::Error>;
 }
 
 /// An orchestrator restricted to a single namespace.
@@ -45,14 +59,29 @@ pub trait NamespacedOrchestrator: fmt::Debug + Clone + Send {
     async fn ensure_service(
         &mut self,
         id: &str,
-        config: ServiceConfig<'_>,
+        config: ServiceConfig,
     ) -> Result<Box<dyn Service>, anyhow::Error>;
 
     /// Drops the identified service, if it exists.
     async fn drop_service(&mut self, id: &str) -> Result<(), anyhow::Error>;
 
-    /// Lists the identifiers of all known services.
-    async fn list_services(&self) -> Result<Vec<String>, anyhow::Error>;
+    /// Drops every service in the namespace and waits for the services to
+    /// terminate.
+    async fn drop_all(&mut self) -> Result<(), anyhow::Error>;
+
+    /// Lists the known services whose labels match `selector`.
+    ///
+    /// Use [`LabelSelector::default`] to list all known services.
+    async fn list_services(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<ServiceInfo>, anyhow::Error>;
+
+    /// Returns diagnostic information about the processes of the identified
+    /// service, like how often and how recently they have exited.
+    ///
+    /// Returns an error if the service does not exist.
+    async fn service_diagnostics(&self, id: &str) -> Result<ServiceDiagnostics, anyhow::Error>;
 }
 
 /// Describes a running service managed by an `Orchestrator`.
@@ -65,30 +94,369 @@ pub trait Service: fmt::Debug {
 }
 
 /// Describes the desired state of a service.
-#[derive(Derivative, Clone)]
-#[derivative(Debug)]
-pub struct ServiceConfig<'a> {
+///
+/// A configuration is plain data, so it can be logged, and orchestrators can
+/// compare it to the configuration of an existing service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceConfig {
     /// An opaque identifier for the executable or container image to run.
     ///
     /// Often names a container on Docker Hub or a path on the local machine.
     pub image: String,
-    /// A function that generates the arguments for each process the service
-    /// given the mapping from port names to assignments.
-    #[derivative(Debug = "ignore")]
-    pub args: &'a (dyn Fn(&HashMap<String, i32>) -> Vec<String> + Send + Sync),
+    /// Templates for the arguments of each process of the service.
+    ///
+    /// The orchestrator expands the following placeholders in each template
+    /// with the assignments that it has made for the process:
+    ///
+    ///   * `%{listen_host}`: the IP address on which the process should
+    ///     listen. IPv6 addresses are enclosed in brackets, so that the host
+    ///     can be followed by a port, as in `%{listen_host}:%{ports.NAME}`.
+    ///   * `%{ports.NAME}`: the port assigned to the entry named `NAME` in
+    ///     [`ServiceConfig::ports`].
+    ///   * `%{index}`: the index of the process within the service.
+    ///   * `%{scratch_dir}`: the process's scratch directory.
+    ///
+    /// `%%` expands to a literal `%`, as does a `%` that does not begin a
+    /// placeholder. See [`ServiceAssignments::expand_args`].
+    pub args: Vec<String>,
     /// Ports to expose.
     pub ports: Vec<ServicePort>,
     /// An optional limit on the memory that the service can use.
     pub memory_limit: Option<MemoryLimit>,
     /// An optional limit on the CPU that the service can use.
     pub cpu_limit: Option<CpuLimit>,
+    /// An optional amount of disk to provision for each process of the
+    /// service.
+    ///
+    /// Orchestrators that support it back each process's scratch directory
+    /// with durable storage of this size, which survives restarts of the
+    /// process. Other orchestrators ignore it.
+    pub disk_limit: Option<DiskLimit>,
     /// The number of processes to run.
     pub processes: usize,
+    /// An optional check that each process must pass before the service is
+    /// considered ready.
+    pub readiness_probe: Option<ReadinessProbe>,
+    /// Hints about where the orchestrator should place the processes of the
+    /// service.
+    pub placement: PlacementHints,
     /// Arbitrary key–value pairs to attach to the service in the orchestrator
     /// backend.
     ///
     /// The orchestrator backend may apply a prefix to the key if appropriate.
     pub labels: HashMap<String, String>,
+    /// Arbitrary key–value pairs to attach to the service in the orchestrator
+    /// backend as non-identifying metadata.
+    ///
+    /// Unlike labels, annotations cannot be used to select services. The
+    /// orchestrator backend may apply a prefix to the key if appropriate.
+    pub annotations: HashMap<String, String>,
+}
+
+/// Describes a service known to an orchestrator, as returned by
+/// [`NamespacedOrchestrator::list_services`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    /// The identifier of the service.
+    pub id: String,
+    /// The service's labels, as specified in [`ServiceConfig::labels`].
+    pub labels: HashMap<String, String>,
+    /// The service's annotations, as specified in
+    /// [`ServiceConfig::annotations`].
+    pub annotations: HashMap<String, String>,
+    /// The status of the service.
+    pub status: ServiceStatus,
+}
+
+/// The status of a service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceStatus {
+    /// Every process of the service is up.
+    Ready,
+    /// At least one process of the service is not up, e.g., because it is
+    /// still starting or because it crashed.
+    NotReady,
+    /// The orchestrator backend has not yet determined the status of the
+    /// service.
+    Unknown,
+}
+
+/// Diagnostic information about the processes of a service, as returned by
+/// [`NamespacedOrchestrator::service_diagnostics`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceDiagnostics {
+    /// For each process of the service, in order, its diagnostics.
+    pub processes: Vec<ProcessDiagnostics>,
+}
+
+impl ServiceDiagnostics {
+    /// Returns the number of retained exits of any process of the service
+    /// that occurred at or after `since`.
+    pub fn exits_since(&self, since: SystemTime) -> usize {
+        self.processes
+            .iter()
+            .flat_map(|p| &p.recent_exits)
+            .filter(|exit| exit.time >= since)
+            .count()
+    }
+
+    /// Returns the most recent exit of any process of the service, if any
+    /// exit has been retained.
+    pub fn last_exit(&self) -> Option<&ProcessExit> {
+        self.processes
+            .iter()
+            .filter_map(|p| p.recent_exits.last())
+            .max_by_key(|exit| exit.time)
+    }
+
+    /// Describes the exits of the service's processes as of `now`, counting
+    /// separately those in the `window` before `now`, e.g., "exited 3 times
+    /// in the last 300s (5 times in total), last exit: SIGSEGV 42s ago".
+    pub fn summarize(&self, now: SystemTime, window: Duration) -> String {
+        let total: u64 = self.processes.iter().map(|p| p.exit_count).sum();
+        if total == 0 {
+            return "no exits".into();
+        }
+        let recent = self.exits_since(now.checked_sub(window).unwrap_or(SystemTime::UNIX_EPOCH));
+        let times = |n| match n {
+            1 => "once".to_string(),
+            n => format!("{n} times"),
+        };
+        let mut summary = format!(
+            "exited {} in the last {}s ({} in total)",
+            times(recent as u64),
+            window.as_secs(),
+            times(total),
+        );
+        if let Some(exit) = self.last_exit() {
+            let ago = now.duration_since(exit.time).unwrap_or_default();
+            write!(
+                summary,
+                ", last exit: {} {}s ago",
+                exit.status,
+                ago.as_secs()
+            )
+            .unwrap();
+        }
+        summary
+    }
+}
+
+/// Diagnostic information about one process of a service.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessDiagnostics {
+    /// The number of times the process has exited.
+    pub exit_count: u64,
+    /// The most recent exits of the process, from oldest to newest.
+    ///
+    /// Orchestrator backends retain only a bounded number of exits, so this
+    /// may contain fewer than `exit_count` entries.
+    pub recent_exits: Vec<ProcessExit>,
+}
+
+/// Describes one exit of a process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessExit {
+    /// When the process exited.
+    pub time: SystemTime,
+    /// How the process exited.
+    pub status: ExitStatus,
+}
+
+/// How a process exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The process exited with the given exit code.
+    Code(i32),
+    /// The process was terminated by the given signal.
+    Signal(i32),
+    /// The process could not be launched.
+    LaunchFailed(String),
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExitStatus::Code(code) => write!(f, "exit code {code}"),
+            ExitStatus::Signal(signal) => match signal_name(*signal) {
+                Some(name) => f.write_str(name),
+                None => write!(f, "signal {signal}"),
+            },
+            ExitStatus::LaunchFailed(e) => write!(f, "failed to launch: {e}"),
+        }
+    }
+}
+
+/// Returns the conventional name of the signal numbered `signal`, if it is
+/// one of the signals that commonly terminate processes.
+fn signal_name(signal: i32) -> Option<&'static str> {
+    match signal {
+        1 => Some("SIGHUP"),
+        2 => Some("SIGINT"),
+        3 => Some("SIGQUIT"),
+        4 => Some("SIGILL"),
+        6 => Some("SIGABRT"),
+        8 => Some("SIGFPE"),
+        9 => Some("SIGKILL"),
+        11 => Some("SIGSEGV"),
+        13 => Some("SIGPIPE"),
+        15 => Some("SIGTERM"),
+        _ => None,
+    }
+}
+
+/// Selects services by their labels.
+///
+/// A service matches the selector if it satisfies every requirement. The
+/// default selector has no requirements and so matches every service.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    /// The requirements that a service's labels must satisfy.
+    pub requirements: Vec<LabelRequirement>,
+}
+
+impl LabelSelector {
+    /// Reports whether `labels` satisfy every requirement of the selector.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements.iter().all(|req| req.matches(labels))
+    }
+}
+
+/// A requirement on the labels of a service.
+///
+/// The keys are in the same form as the keys of [`ServiceConfig::labels`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelRequirement {
+    /// The label `key` must be present and have value `value`.
+    Equals {
+        /// The key of the label.
+        key: String,
+        /// The required value.
+        value: String,
+    },
+    /// The label `key` must be absent or have a value other than `value`.
+    NotEquals {
+        /// The key of the label.
+        key: String,
+        /// The excluded value.
+        value: String,
+    },
+    /// The label `key` must be present, with any value.
+    Exists {
+        /// The key of the label.
+        key: String,
+    },
+    /// The label `key` must be absent.
+    NotExists {
+        /// The key of the label.
+        key: String,
+    },
+}
+
+impl LabelRequirement {
+    /// Reports whether `labels` satisfy the requirement.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        match self {
+            LabelRequirement::Equals { key, value } => labels.get(key) == Some(value),
+            LabelRequirement::NotEquals { key, value } => labels.get(key) != Some(value),
+            LabelRequirement::Exists { key } => labels.contains_key(key),
+            LabelRequirement::NotExists { key } => !labels.contains_key(key),
+        }
+    }
+}
+
+/// Assignments that the orchestrator has made for a process in a service.
+#[derive(Debug, Clone)]
+pub struct ServiceAssignments<'a> {
+    /// The IP address that the process should listen on.
+    pub listen_host: IpAddr,
+    /// The assigned port for each entry in [`ServiceConfig::ports`].
+    pub ports: &'a HashMap<String, i32>,
+    /// The index of the process within the service.
+    pub index: ProcessIndex<'a>,
+    /// A directory that the process may use to store scratch data, if the
+    /// orchestrator provides one.
+    ///
+    /// The orchestrator removes the directory when the service is dropped.
+    pub scratch_directory: Option<&'a Path>,
+}
+
+/// How the index of a process within its service is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessIndex<'a> {
+    /// The orchestrator knows the index when it generates the process's
+    /// arguments.
+    Known(usize),
+    /// The runtime that launches the process substitutes the index, e.g.,
+    /// from an environment variable. `%{index}` expands to the given text,
+    /// which the runtime replaces with the index.
+    Deferred(&'a str),
+}
+
+impl ServiceAssignments<'_> {
+    /// Expands the placeholders in the argument templates `args`, as
+    /// described in [`ServiceConfig::args`].
+    ///
+    /// A `%` that is not followed by `{` or `%` is copied verbatim, so that
+    /// arguments written before templates existed, like `--format=%s`, keep
+    /// their meaning. Returns an error if a placeholder is unterminated or
+    /// refers to an assignment that the orchestrator has not made.
+    pub fn expand_args(&self, args: &[String]) -> Result<Vec<String>, anyhow::Error> {
+        args.iter().map(|arg| self.expand_arg(arg)).collect()
+    }
+
+    fn expand_arg(&self, template: &str) -> Result<String, anyhow::Error> {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find('%') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            if let Some(r) = rest.strip_prefix('%') {
+                out.push('%');
+                rest = r;
+                continue;
+            }
+            let r = match rest.strip_prefix('{') {
+                Some(r) => r,
+                None => {
+                    out.push('%');
+                    continue;
+                }
+            };
+            let (placeholder, r) = r.split_once('}').ok_or_else(|| {
+                anyhow!("invalid argument template {template:?}: unterminated placeholder")
+            })?;
+            rest = r;
+            match placeholder {
+                "listen_host" => match self.listen_host {
+                    IpAddr::V4(addr) => write!(out, "{addr}")?,
+                    IpAddr::V6(addr) => write!(out, "[{addr}]")?,
+                },
+                "index" => match self.index {
+                    ProcessIndex::Known(index) => write!(out, "{index}")?,
+                    ProcessIndex::Deferred(reference) => out.push_str(reference),
+                },
+                "scratch_dir" => match self.scratch_directory.map(|dir| dir.to_str()) {
+                    Some(Some(dir)) => out.push_str(dir),
+                    Some(None) => bail!(
+                        "argument template {template:?}: scratch directory is not valid UTF-8"
+                    ),
+                    None => bail!("argument template {template:?}: scratch directory unavailable"),
+                },
+                _ => match placeholder.strip_prefix("ports.") {
+                    Some(name) => match self.ports.get(name) {
+                        Some(port) => write!(out, "{port}")?,
+                        None => bail!("argument template {template:?}: unknown port {name:?}"),
+                    },
+                    None => {
+                        bail!("argument template {template:?}: unknown placeholder {placeholder:?}")
+                    }
+                },
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
 }
 
 /// A named port associated with a service.
@@ -104,6 +472,263 @@ pub struct ServicePort {
     pub port_hint: i32,
 }
 
+/// Describes how to determine whether a process of a service is ready.
+///
+/// Orchestrators that support readiness probes will not return from
+/// [`NamespacedOrchestrator::ensure_service`] until every process of the
+/// service passes the probe, or until the probe times out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadinessProbe {
+    /// The name of the port, from [`ServiceConfig::ports`], to probe.
+    pub port: String,
+    /// The type of check to perform against the port.
+    pub check: ReadinessCheck,
+    /// How long to wait for all processes to pass the probe before giving up.
+    pub timeout: Duration,
+}
+
+impl ReadinessProbe {
+    /// Waits until the process listening at each of `addrs` passes the probe,
+    /// or returns an error if the probe's timeout elapses first.
+    ///
+    /// This is a helper for orchestrator backends that run processes
+    /// themselves and so must probe them directly. `service` names the service
+    /// in error messages.
+    pub async fn wait(&self, service: &str, addrs: &[String]) -> Result<(), anyhow::Error> {
+        let deadline = Instant::now() + self.timeout;
+        for (i, addr) in addrs.iter().enumerate() {
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let res = match time::timeout(remaining, self.check.perform(addr)).await {
+                    Ok(res) => res,
+                    Err(_) => Err(anyhow!("timed out")),
+                };
+                match res {
+                    Ok(()) => break,
+                    Err(e) if Instant::now() >= deadline => bail!(
+                        "process {} of {} did not become ready at {} within {:?}: {}",
+                        i,
+                        service,
+                        addr,
+                        self.timeout,
+                        e
+                    ),
+                    Err(_) => time::sleep(Duration::from_millis(100)).await,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The type of check performed by a [`ReadinessProbe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadinessCheck {
+    /// The process is ready once it accepts TCP connections on the port.
+    Tcp,
+    /// The process is ready once an HTTP GET request for `path` on the port
+    /// returns a successful status code.
+    HttpGet {
+        /// The path to request, e.g. `/status`.
+        path: String,
+    },
+}
+
+impl ReadinessCheck {
+    /// Performs the check once against the process listening at `addr`.
+    pub async fn perform(&self, addr: &str) -> Result<(), anyhow::Error> {
+        let mut conn = TcpStream::connect(addr).await?;
+        match self {
+            ReadinessCheck::Tcp => Ok(()),
+            ReadinessCheck::HttpGet { path } => {
+                let request = format!("GET {path} HTTP/1.0\r\nHost: {addr}\r\n\r\n");
+                conn.write_all(request.as_bytes()).await?;
+                // The status line looks like `HTTP/1.0 200 OK`.
+                let mut status_line = String::new();
+                BufReader::new(conn).read_line(&mut status_line).await?;
+                let code = status_line
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|code| code.parse::<u16>().ok());
+                match code {
+                    Some(code) if (200..300).contains(&code) => Ok(()),
+                    _ => bail!("unexpected HTTP response: {}", status_line.trim_end()),
+                }
+            }
+        }
+    }
+}
+
+/// Hints about where an orchestrator should place the processes of a service.
+///
+/// Orchestrators that do not manage placement, like the process orchestrator,
+/// validate the hints but otherwise ignore them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlacementHints {
+    /// The availability zones in which the service's processes may be placed.
+    ///
+    /// If empty, processes may be placed in any availability zone.
+    pub availability_zones: Vec<String>,
+    /// Rules that keep the service's processes away from the processes of
+    /// other services.
+    pub anti_affinity: Vec<AntiAffinity>,
+    /// Labels that a node must have for the service's processes to be placed
+    /// on it.
+    ///
+    /// If empty, processes may be placed on any node.
+    pub node_selector: HashMap<String, String>,
+    /// Taints that the service's processes tolerate.
+    ///
+    /// Processes are never placed on nodes with taints they do not tolerate.
+    pub tolerations: Vec<Toleration>,
+}
+
+impl PlacementHints {
+    /// Reports whether the hints are well formed.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let mut seen = HashSet::new();
+        for zone in &self.availability_zones {
+            if zone.is_empty() {
+                bail!("availability zone names must not be empty");
+            }
+            if !seen.insert(zone) {
+                bail!("availability zone {} specified more than once", zone);
+            }
+        }
+        for rule in &self.anti_affinity {
+            if rule.labels.is_empty() {
+                bail!("anti-affinity rules must specify at least one label");
+            }
+        }
+        for key in self.node_selector.keys() {
+            if key.is_empty() {
+                bail!("node selector keys must not be empty");
+            }
+        }
+        for toleration in &self.tolerations {
+            if toleration.key.is_none() && toleration.value.is_some() {
+                bail!("tolerations that specify a value must also specify a key");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Permits the processes of a service to be placed on nodes with a matching
+/// taint.
+///
+/// Tolerations are written as `KEY[=VALUE][:EFFECT]`, or `*[:EFFECT]` to
+/// tolerate taints with any key, mirroring the syntax of `kubectl taint`. A
+/// value may contain `:`; only a trailing `:EFFECT` that names an effect is
+/// taken to be one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toleration {
+    /// The key of the taints to tolerate, or `None` to tolerate taints with
+    /// any key.
+    pub key: Option<String>,
+    /// The value of the taints to tolerate, or `None` to tolerate taints with
+    /// any value.
+    pub value: Option<String>,
+    /// The effect of the taints to tolerate, or `None` to tolerate taints with
+    /// any effect.
+    pub effect: Option<TaintEffect>,
+}
+
+impl FromStr for Toleration {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Toleration, anyhow::Error> {
+        // Keys cannot contain colons, so without a value, any colon
+        // introduces the effect. Values can contain colons, so a colon after
+        // the value introduces the effect only if what follows it names one.
+        let (key, value, effect) = match s.split_once('=') {
+            None => match s.split_once(':') {
+                None => (s, None, None),
+                Some((key, effect)) => (key, None, Some(effect.parse()?)),
+            },
+            Some((key, rest)) => {
+                let split = rest.rsplit_once(':').and_then(|(value, effect)| {
+                    let effect: TaintEffect = effect.parse().ok()?;
+                    Some((value, effect))
+                });
+                match split {
+                    None => (key, Some(rest), None),
+                    Some((value, effect)) => (key, Some(value), Some(effect)),
+                }
+            }
+        };
+        if key.contains(':') {
+            bail!("toleration key {key} must not contain ':'");
+        }
+        let value = value.map(|value| value.to_string());
+        let key = match key {
+            "" => bail!("toleration must have format KEY[=VALUE][:EFFECT]"),
+            "*" => None,
+            key => Some(key.to_string()),
+        };
+        let toleration = Toleration { key, value, effect };
+        if toleration.key.is_none() && toleration.value.is_some() {
+            bail!("toleration for any key must not specify a value");
+        }
+        Ok(toleration)
+    }
+}
+
+/// The effect of a taint on the placement of processes that do not tolerate
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaintEffect {
+    /// Processes are not placed on the node.
+    NoSchedule,
+    /// Processes are placed on the node only if there is no alternative.
+    PreferNoSchedule,
+    /// Processes are not placed on the node, and processes already running on
+    /// the node are evicted.
+    NoExecute,
+}
+
+impl fmt::Display for TaintEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TaintEffect::NoSchedule => f.write_str("NoSchedule"),
+            TaintEffect::PreferNoSchedule => f.write_str("PreferNoSchedule"),
+            TaintEffect::NoExecute => f.write_str("NoExecute"),
+        }
+    }
+}
+
+impl FromStr for TaintEffect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<TaintEffect, anyhow::Error> {
+        match s {
+            "NoSchedule" => Ok(TaintEffect::NoSchedule),
+            "PreferNoSchedule" => Ok(TaintEffect::PreferNoSchedule),
+            "NoExecute" => Ok(TaintEffect::NoExecute),
+            _ => bail!(
+                "unknown taint effect {s}: expected NoSchedule, PreferNoSchedule, or NoExecute"
+            ),
+        }
+    }
+}
+
+/// Prevents the processes of a service from being co-located on the same node
+/// as the processes of the services that match `labels`.
+///
+/// A service whose own labels match `labels` is spread across nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AntiAffinity {
+    /// Labels, in the same form as [`ServiceConfig::labels`], that select the
+    /// services to avoid.
+    pub labels: HashMap<String, String>,
+    /// Whether the rule must be satisfied.
+    ///
+    /// If false, the orchestrator will make a best effort to satisfy the
+    /// rule, but will place processes in violation of the rule if there is no
+    /// alternative.
+    pub required: bool,
+}
+
 /// Describes a limit on memory resources.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MemoryLimit {
@@ -112,7 +737,7 @@ pub struct MemoryLimit {
 
 impl MemoryLimit {
     /// Constructs a new memory limit from a number of bytes.
-    pub fn from_bytes(&self, bytes: usize) -> MemoryLimit {
+    pub fn from_bytes(bytes: usize) -> MemoryLimit {
         MemoryLimit { bytes }
     }
 
@@ -122,6 +747,24 @@ impl MemoryLimit {
     }
 }
 
+/// Describes a limit on disk resources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskLimit {
+    bytes: u64,
+}
+
+impl DiskLimit {
+    /// Constructs a new disk limit from a number of bytes.
+    pub fn from_bytes(bytes: u64) -> DiskLimit {
+        DiskLimit { bytes }
+    }
+
+    /// Returns the disk limit in bytes.
+    pub fn as_bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
 /// Describes a limit on CPU resources.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CpuLimit {
@@ -130,7 +773,7 @@ pub struct CpuLimit {
 
 impl CpuLimit {
     /// Constructs a new CPU limit from a number of millicpus.
-    pub fn from_millicpus(&self, millicpus: usize) -> CpuLimit {
+    pub fn from_millicpus(millicpus: usize) -> CpuLimit {
         CpuLimit { millicpus }
     }
 
@@ -139,3 +782,210 @@ impl CpuLimit {
         self.millicpus
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        ExitStatus, ProcessDiagnostics, ProcessExit, ProcessIndex, ServiceAssignments,
+        ServiceDiagnostics, TaintEffect, Toleration,
+    };
+
+    fn exit(time: SystemTime, status: ExitStatus) -> ProcessExit {
+        ProcessExit { time, status }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(10_000);
+        let window = Duration::from_secs(300);
+        let ago = |secs| now - Duration::from_secs(secs);
+
+        let diagnostics = ServiceDiagnostics {
+            processes: vec![ProcessDiagnostics::default(); 2],
+        };
+        assert_eq!(diagnostics.summarize(now, window), "no exits");
+
+        let diagnostics = ServiceDiagnostics {
+            processes: vec![
+                ProcessDiagnostics {
+                    // Older exits than those retained count toward the total.
+                    exit_count: 3,
+                    recent_exits: vec![
+                        exit(ago(600), ExitStatus::Code(1)),
+                        exit(ago(100), ExitStatus::Signal(11)),
+                    ],
+                },
+                ProcessDiagnostics {
+                    exit_count: 1,
+                    recent_exits: vec![exit(ago(42), ExitStatus::Signal(9))],
+                },
+            ],
+        };
+        assert_eq!(diagnostics.exits_since(ago(300)), 2);
+        assert_eq!(
+            diagnostics.last_exit(),
+            Some(&exit(ago(42), ExitStatus::Signal(9)))
+        );
+        assert_eq!(
+            diagnostics.summarize(now, window),
+            "exited 2 times in the last 300s (4 times in total), last exit: SIGKILL 42s ago"
+        );
+
+        let diagnostics = ServiceDiagnostics {
+            processes: vec![ProcessDiagnostics {
+                exit_count: 1,
+                recent_exits: vec![exit(ago(900), ExitStatus::Signal(64))],
+            }],
+        };
+        assert_eq!(
+            diagnostics.summarize(now, window),
+            "exited 0 times in the last 300s (once in total), last exit: signal 64 900s ago"
+        );
+    }
+
+    #[test]
+    fn test_toleration_from_str() {
+        let toleration = |key: Option<&str>, value: Option<&str>, effect| Toleration {
+            key: key.map(|k| k.to_string()),
+            value: value.map(|v| v.to_string()),
+            effect,
+        };
+        let valid = [
+            ("key", toleration(Some("key"), None, None)),
+            ("key=value", toleration(Some("key"), Some("value"), None)),
+            (
+                "key:NoSchedule",
+                toleration(Some("key"), None, Some(TaintEffect::NoSchedule)),
+            ),
+            (
+                "example.com/key=value:NoExecute",
+                toleration(
+                    Some("example.com/key"),
+                    Some("value"),
+                    Some(TaintEffect::NoExecute),
+                ),
+            ),
+            ("*", toleration(None, None, None)),
+            (
+                "*:PreferNoSchedule",
+                toleration(None, None, Some(TaintEffect::PreferNoSchedule)),
+            ),
+            ("key=", toleration(Some("key"), Some(""), None)),
+            // Colons within values are part of the value, unless followed by
+            // an effect.
+            ("key=a:b", toleration(Some("key"), Some("a:b"), None)),
+            ("key=a:b:", toleration(Some("key"), Some("a:b:"), None)),
+            (
+                "key=a:b:NoSchedule",
+                toleration(Some("key"), Some("a:b"), Some(TaintEffect::NoSchedule)),
+            ),
+            (
+                "key=:NoSchedule",
+                toleration(Some("key"), Some(""), Some(TaintEffect::NoSchedule)),
+            ),
+        ];
+        for (s, expected) in valid {
+            assert_eq!(s.parse::<Toleration>().unwrap(), expected, "parsing {s}");
+        }
+
+        let invalid = [
+            ("", "toleration must have format"),
+            ("=value", "toleration must have format"),
+            (":NoSchedule", "toleration must have format"),
+            ("*=value", "must not specify a value"),
+            ("key:Bogus", "unknown taint effect Bogus"),
+            ("key:", "unknown taint effect"),
+            ("a:b=c", "toleration key a:b must not contain ':'"),
+        ];
+        for (s, expected) in invalid {
+            let err = s.parse::<Toleration>().unwrap_err().to_string();
+            assert!(err.contains(expected), "parsing {s}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_expand_args() {
+        let ports = HashMap::from([("sql".to_string(), 6875), ("http".to_string(), 6876)]);
+        let assignments = ServiceAssignments {
+            listen_host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ports: &ports,
+            index: ProcessIndex::Known(2),
+            scratch_directory: Some(Path::new("/scratch")),
+        };
+        let expand = |arg: &str| assignments.expand_args(&[arg.to_string()]);
+
+        for (template, expected) in [
+            ("plain", "plain"),
+            ("", ""),
+            (
+                "--listen-addr=%{listen_host}:%{ports.sql}",
+                "--listen-addr=127.0.0.1:6875",
+            ),
+            ("%{ports.http}%{ports.sql}", "68766875"),
+            ("--process=%{index}", "--process=2"),
+            (
+                "--data-directory=%{scratch_dir}/data",
+                "--data-directory=/scratch/data",
+            ),
+            // Escaped and stray percent signs are literal.
+            ("100%%", "100%"),
+            ("%%{index}", "%{index}"),
+            ("--format=%s %d", "--format=%s %d"),
+            ("50%", "50%"),
+            ("%", "%"),
+            ("%%%{index}", "%2"),
+        ] {
+            assert_eq!(expand(template).unwrap(), vec![expected], "{template}");
+        }
+
+        for template in [
+            "%{index",
+            "%{}",
+            "%{bogus}",
+            "%{ports.bogus}",
+            "%{ports}",
+            "%{ listen_host }",
+        ] {
+            assert!(expand(template).is_err(), "{template}");
+        }
+
+        // Each argument is expanded independently.
+        assert_eq!(
+            assignments
+                .expand_args(&["-c".into(), "echo %{index}".into()])
+                .unwrap(),
+            vec!["-c", "echo 2"],
+        );
+
+        // IPv6 hosts are bracketed, so that they can be followed by a port.
+        let v6_assignments = ServiceAssignments {
+            listen_host: IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ..assignments.clone()
+        };
+        let addr = v6_assignments
+            .expand_args(&["%{listen_host}:%{ports.sql}".into()])
+            .unwrap();
+        assert_eq!(addr, vec!["[::1]:6875"]);
+        assert!(addr[0].parse::<SocketAddr>().is_ok());
+
+        // A deferred index expands to the reference that the runtime
+        // replaces, and a missing scratch directory is an error.
+        let assignments = ServiceAssignments {
+            index: ProcessIndex::Deferred("$(MZ_PROCESS_INDEX)"),
+            scratch_directory: None,
+            ..assignments
+        };
+        assert_eq!(
+            assignments
+                .expand_args(&["--process=%{index}".into()])
+                .unwrap(),
+            vec!["--process=$(MZ_PROCESS_INDEX)"],
+        );
+        assert!(assignments.expand_args(&["%{scratch_dir}".into()]).is_err());
+    }
+}
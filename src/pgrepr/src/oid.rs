@@ -99,3 +99,15 @@ pub const FUNC_MZ_TYPE_NAME: u32 = 16_453;
 pub const TYPE_ANYCOMPATIBLELIST_OID: u32 = 16_454;
 pub const TYPE_ANYCOMPATIBLEMAP_OID: u32 = 16_455;
 pub const FUNC_MAP_LENGTH_OID: u32 = 16_456;
+pub const FUNC_GC_SECRETS_OID: u32 = 16_457;
+pub const FUNC_RECOMPUTE_MATERIALIZATION_OID: u32 = 16_458;
+pub const FUNC_RESTART_REPLICA_OID: u32 = 16_459;
+pub const FUNC_REBUILD_UPSERT_STATE_OID: u32 = 16_460;
+pub const FUNC_MIGRATE_SECRETS_OID: u32 = 16_461;
+pub const FUNC_COLLECT_STATISTICS_OID: u32 = 16_462;
+pub const FUNC_BACKUP_CATALOG_OID: u32 = 16_463;
+pub const FUNC_MZ_CPU_PROFILE_OID: u32 = 16_464;
+pub const FUNC_CHECK_CATALOG_OID: u32 = 16_465;
+pub const FUNC_MZ_CANCEL_QUERY_OID: u32 = 16_466;
+pub const FUNC_MZ_SESSION_SETTINGS_OID: u32 = 16_467;
+pub const FUNC_REPLICA_DIAGNOSTICS_OID: u32 = 16_468;
@@ -200,6 +200,9 @@ impl Encoder<BackendMessage> for Codec {
         let byte = match &msg {
             BackendMessage::AuthenticationOk => b'R',
             BackendMessage::AuthenticationCleartextPassword => b'R',
+            BackendMessage::AuthenticationSasl { .. } => b'R',
+            BackendMessage::AuthenticationSaslContinue(_) => b'R',
+            BackendMessage::AuthenticationSaslFinal(_) => b'R',
             BackendMessage::RowDescription(_) => b'T',
             BackendMessage::DataRow(_) => b'D',
             BackendMessage::CommandComplete { .. } => b'C',
@@ -222,6 +225,7 @@ impl Encoder<BackendMessage> for Codec {
             }
             BackendMessage::CopyInResponse { .. } => b'G',
             BackendMessage::CopyOutResponse { .. } => b'H',
+            BackendMessage::CopyBothResponse { .. } => b'W',
             BackendMessage::CopyData(_) => b'd',
             BackendMessage::CopyDone => b'c',
         };
@@ -240,6 +244,10 @@ impl Encoder<BackendMessage> for Codec {
             | BackendMessage::CopyOutResponse {
                 overall_format,
                 column_formats,
+            }
+            | BackendMessage::CopyBothResponse {
+                overall_format,
+                column_formats,
             } => {
                 dst.put_format_i8(overall_format);
                 dst.put_length_i16(column_formats.len())?;
@@ -257,6 +265,21 @@ impl Encoder<BackendMessage> for Codec {
             BackendMessage::AuthenticationCleartextPassword => {
                 dst.put_u32(3);
             }
+            BackendMessage::AuthenticationSasl { mechanisms } => {
+                dst.put_u32(10);
+                for mechanism in mechanisms {
+                    dst.put_string(mechanism);
+                }
+                dst.put_u8(b'\0');
+            }
+            BackendMessage::AuthenticationSaslContinue(data) => {
+                dst.put_u32(11);
+                dst.put_slice(&data);
+            }
+            BackendMessage::AuthenticationSaslFinal(data) => {
+                dst.put_u32(12);
+                dst.put_slice(&data);
+            }
             BackendMessage::RowDescription(fields) => {
                 dst.put_length_i16(fields.len())?;
                 for f in &fields {
@@ -508,7 +531,7 @@ impl Decoder for Codec {
                         b'X' => decode_terminate(buf)?,
 
                         // Authentication.
-                        b'p' => decode_password(buf)?,
+                        b'p' => FrontendMessage::RawAuthentication(buf.buf.to_vec()),
 
                         // Copy from flow.
                         b'f' => decode_copy_fail(buf)?,
@@ -537,12 +560,43 @@ fn decode_terminate(mut _buf: Cursor) -> Result<FrontendMessage, io::Error> {
     Ok(FrontendMessage::Terminate)
 }
 
-fn decode_password(mut buf: Cursor) -> Result<FrontendMessage, io::Error> {
+/// Decodes the contents of a [`FrontendMessage::RawAuthentication`] sent in
+/// response to a request for a cleartext password.
+pub fn decode_password(buf: &[u8]) -> Result<FrontendMessage, io::Error> {
+    let mut buf = Cursor::new(buf);
     Ok(FrontendMessage::Password {
         password: buf.read_cstr()?.to_owned(),
     })
 }
 
+/// Decodes the contents of the [`FrontendMessage::RawAuthentication`] that
+/// begins a SASL exchange.
+pub fn decode_sasl_initial_response(buf: &[u8]) -> Result<FrontendMessage, io::Error> {
+    let mut buf = Cursor::new(buf);
+    let mechanism = buf.read_cstr()?.to_owned();
+    // A length of -1 indicates that there is no initial response.
+    let initial_response = match buf.read_i32()? {
+        -1 => vec![],
+        len => {
+            let len = usize::try_from(len).map_err(input_err)?;
+            if buf.buf.len() != len {
+                return Err(input_err("invalid SASL initial response length"));
+            }
+            buf.buf.to_vec()
+        }
+    };
+    Ok(FrontendMessage::SaslInitialResponse {
+        mechanism,
+        initial_response,
+    })
+}
+
+/// Decodes the contents of a [`FrontendMessage::RawAuthentication`] that
+/// continues a SASL exchange.
+pub fn decode_sasl_response(buf: &[u8]) -> Result<FrontendMessage, io::Error> {
+    Ok(FrontendMessage::SaslResponse(buf.to_vec()))
+}
+
 fn decode_query(mut buf: Cursor) -> Result<FrontendMessage, io::Error> {
     Ok(FrontendMessage::Query {
         sql: buf.read_cstr()?.to_string(),
@@ -26,8 +26,11 @@ mod codec;
 mod message;
 mod metrics;
 mod protocol;
+mod replication;
 mod server;
 
 pub use metrics::Metrics;
 pub use protocol::match_handshake;
-pub use server::{Config, Server, TlsConfig, TlsMode};
+pub use server::{
+    Config, DrainSignal, DrainTrigger, ReloadableSslContext, Server, TlsConfig, TlsMode,
+};
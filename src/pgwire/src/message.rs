@@ -188,9 +188,21 @@ pub enum FrontendMessage {
 
     CopyFail(String),
 
+    /// An authentication message whose contents depend on the authentication
+    /// method that the server requested, and so cannot be decoded without
+    /// knowing it.
+    RawAuthentication(Vec<u8>),
+
     Password {
         password: String,
     },
+
+    SaslInitialResponse {
+        mechanism: String,
+        initial_response: Vec<u8>,
+    },
+
+    SaslResponse(Vec<u8>),
 }
 
 impl FrontendMessage {
@@ -210,7 +222,10 @@ impl FrontendMessage {
             FrontendMessage::CopyData(_) => "copy_data",
             FrontendMessage::CopyDone => "copy_done",
             FrontendMessage::CopyFail(_) => "copy_fail",
+            FrontendMessage::RawAuthentication(_) => "raw_authentication",
             FrontendMessage::Password { .. } => "password",
+            FrontendMessage::SaslInitialResponse { .. } => "sasl_initial_response",
+            FrontendMessage::SaslResponse(_) => "sasl_response",
         }
     }
 }
@@ -222,6 +237,11 @@ impl FrontendMessage {
 pub enum BackendMessage {
     AuthenticationOk,
     AuthenticationCleartextPassword,
+    AuthenticationSasl {
+        mechanisms: Vec<&'static str>,
+    },
+    AuthenticationSaslContinue(Vec<u8>),
+    AuthenticationSaslFinal(Vec<u8>),
     CommandComplete {
         tag: String,
     },
@@ -249,6 +269,10 @@ pub enum BackendMessage {
         overall_format: mz_pgrepr::Format,
         column_formats: Vec<mz_pgrepr::Format>,
     },
+    CopyBothResponse {
+        overall_format: mz_pgrepr::Format,
+        column_formats: Vec<mz_pgrepr::Format>,
+    },
     CopyData(Vec<u8>),
     CopyDone,
 }
@@ -366,9 +390,15 @@ impl ErrorResponse {
             CoordError::InvalidAlterOnDisabledIndex(_) => SqlState::INTERNAL_ERROR,
             CoordError::Catalog(_) => SqlState::INTERNAL_ERROR,
             CoordError::ChangedPlan => SqlState::FEATURE_NOT_SUPPORTED,
+            CoordError::ClusterMemoryExceeded { .. } => SqlState::OUT_OF_MEMORY,
+            CoordError::ClusterNotPermitted { .. } => SqlState::INSUFFICIENT_PRIVILEGE,
+            CoordError::ConnectionNotPermitted { .. } => {
+                SqlState::INVALID_AUTHORIZATION_SPECIFICATION
+            }
             CoordError::ConstrainedParameter { .. } => SqlState::INVALID_PARAMETER_VALUE,
             CoordError::AutomaticTimestampFailure { .. } => SqlState::INTERNAL_ERROR,
             CoordError::DuplicateCursor(_) => SqlState::DUPLICATE_CURSOR,
+            CoordError::DuplicateReplicationSlot(_) => SqlState::DUPLICATE_OBJECT,
             CoordError::Eval(EvalError::CharacterNotValidForEncoding(_)) => {
                 SqlState::PROGRAM_LIMIT_EXCEEDED
             }
@@ -385,24 +415,36 @@ impl ErrorResponse {
             CoordError::InvalidRematerialization { .. } => SqlState::FEATURE_NOT_SUPPORTED,
             CoordError::InvalidParameterType(_) => SqlState::INVALID_PARAMETER_VALUE,
             CoordError::InvalidParameterValue { .. } => SqlState::INVALID_PARAMETER_VALUE,
+            CoordError::InvalidSecret { .. } => SqlState::INVALID_PARAMETER_VALUE,
             CoordError::InvalidTableMutationSelection => SqlState::INVALID_TRANSACTION_STATE,
             CoordError::ConstraintViolation(NotNullViolation(_)) => SqlState::NOT_NULL_VIOLATION,
+            CoordError::LoginNotPermitted(_) => SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
+            CoordError::OperationNotPermitted { .. } => SqlState::INSUFFICIENT_PRIVILEGE,
             CoordError::OperationProhibitsTransaction(_) => SqlState::ACTIVE_SQL_TRANSACTION,
             CoordError::OperationRequiresTransaction(_) => SqlState::NO_ACTIVE_SQL_TRANSACTION,
+            CoordError::PermissionDenied { .. } => SqlState::INSUFFICIENT_PRIVILEGE,
             CoordError::Persistence(_) => SqlState::INTERNAL_ERROR,
             CoordError::PreparedStatementExists(_) => SqlState::DUPLICATE_PSTATEMENT,
+            CoordError::PeekQueueTimeout { .. } => SqlState::QUERY_CANCELED,
             CoordError::QGM(_) => SqlState::INTERNAL_ERROR,
             CoordError::ReadOnlyTransaction => SqlState::READ_ONLY_SQL_TRANSACTION,
             CoordError::ReadOnlyParameter(_) => SqlState::CANT_CHANGE_RUNTIME_PARAM,
             CoordError::RecursionLimit(_) => SqlState::INTERNAL_ERROR,
+            CoordError::ReplicationSlotActive(_) => SqlState::OBJECT_IN_USE,
+            CoordError::ResultRowsExceeded(_) => SqlState::PROGRAM_LIMIT_EXCEEDED,
             CoordError::RelationOutsideTimeDomain { .. } => SqlState::INVALID_TRANSACTION_STATE,
-            CoordError::SafeModeViolation(_) => SqlState::INTERNAL_ERROR,
+            CoordError::SafeModeViolation(_) => SqlState::FEATURE_NOT_SUPPORTED,
+            CoordError::SecretTooLarge { .. } => SqlState::PROGRAM_LIMIT_EXCEEDED,
             CoordError::SqlCatalog(_) => SqlState::INTERNAL_ERROR,
+            CoordError::StatementTimeout => SqlState::QUERY_CANCELED,
             CoordError::TailOnlyTransaction => SqlState::INVALID_TRANSACTION_STATE,
+            CoordError::TooManyConnections { .. } => SqlState::TOO_MANY_CONNECTIONS,
             CoordError::Transform(_) => SqlState::INTERNAL_ERROR,
             CoordError::UnknownCursor(_) => SqlState::INVALID_CURSOR_NAME,
             CoordError::UnknownParameter(_) => SqlState::UNDEFINED_OBJECT,
             CoordError::UnknownPreparedStatement(_) => SqlState::UNDEFINED_PSTATEMENT,
+            CoordError::UnknownPublication(_) => SqlState::UNDEFINED_OBJECT,
+            CoordError::UnknownReplicationSlot(_) => SqlState::UNDEFINED_OBJECT,
             CoordError::UnknownLoginRole(_) => SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
             CoordError::UnmaterializableFunction(_) => SqlState::FEATURE_NOT_SUPPORTED,
             CoordError::Unsupported(..) => SqlState::FEATURE_NOT_SUPPORTED,
@@ -13,9 +13,12 @@ use std::convert::{TryFrom, TryInto};
 use std::future::Future;
 use std::iter;
 use std::mem;
+use std::net::IpAddr;
+use std::str;
 
+use anyhow::anyhow;
 use byteorder::{ByteOrder, NetworkEndian};
-use futures::future::{pending, BoxFuture, FutureExt};
+use futures::future::{pending, select_all, BoxFuture, FutureExt};
 use itertools::izip;
 use mz_expr::GlobalId;
 use openssl::nid::Nid;
@@ -25,14 +28,16 @@ use tokio::select;
 use tokio::time::{self, Duration, Instant};
 use tracing::debug;
 
+use mz_authenticator::scram::{self, ServerExchange, Verifier};
+use mz_authenticator::{Authenticator, Credentials};
 use mz_coord::session::{
-    row_future_to_stream, EndTransactionAction, InProgressRows, Portal, PortalState,
-    RowBatchStream, Session, TransactionStatus,
+    row_future_to_stream, ConnectionInfo, EndTransactionAction, InProgressRows, Portal,
+    PortalState, RowBatchStream, Session, TransactionStatus,
 };
-use mz_coord::ExecuteResponse;
+use mz_coord::{ExecuteResponse, ReplicationStream};
 use mz_dataflow_types::PeekResponseUnary;
-use mz_frontegg_auth::FronteggAuthentication;
 use mz_ore::cast::CastFrom;
+use mz_ore::collections::CollectionExt;
 use mz_ore::netio::AsyncReady;
 use mz_ore::str::StrExt;
 use mz_pgcopy::CopyFormatParams;
@@ -41,12 +46,13 @@ use mz_sql::ast::display::AstDisplay;
 use mz_sql::ast::{FetchDirection, Ident, NoticeSeverity, Raw, Statement};
 use mz_sql::plan::{CopyFormat, CopyParams, ExecuteTimeout, StatementDesc};
 
-use crate::codec::FramedConn;
+use crate::codec::{self, FramedConn};
 use crate::message::{
     self, BackendMessage, ErrorResponse, FrontendMessage, Severity, VERSIONS, VERSION_3,
 };
 use crate::metrics::Metrics;
-use crate::server::{Conn, TlsMode};
+use crate::replication::{self, ReplicationCommand, StandbyMessage, TransactionAssembler};
+use crate::server::{Conn, DrainSignal, TlsMode};
 
 /// Reports whether the given stream begins with a pgwire handshake.
 ///
@@ -80,9 +86,14 @@ pub struct RunParams<'a, A> {
     pub version: i32,
     /// The parameters that the client provided in the startup message.
     pub params: HashMap<String, String>,
+    /// The address of the client, if known.
+    pub peer_addr: Option<IpAddr>,
     /// The server's metrics.
     pub metrics: &'a Metrics,
-    pub frontegg: Option<&'a FronteggAuthentication>,
+    /// The authenticator with which to authenticate the client.
+    pub authenticator: &'a dyn Authenticator,
+    /// The signal that tells the connection that the server is draining.
+    pub drain_signal: DrainSignal,
 }
 
 /// Runs a pgwire connection to completion.
@@ -101,8 +112,10 @@ pub async fn run<'a, A>(
         conn,
         version,
         mut params,
+        peer_addr,
         metrics,
-        frontegg,
+        authenticator,
+        drain_signal,
     }: RunParams<'a, A>,
 ) -> Result<(), io::Error>
 where
@@ -119,6 +132,32 @@ where
 
     let user = params.remove("user").unwrap_or_else(String::new);
 
+    // Subscribers connect with `replication=database` to issue logical
+    // replication commands. The boolean values request physical replication.
+    let replication = match params.remove("replication").as_deref() {
+        None | Some("false" | "off" | "no" | "0") => false,
+        Some("database") => true,
+        Some("true" | "on" | "yes" | "1") => {
+            return conn
+                .send(ErrorResponse::fatal(
+                    SqlState::FEATURE_NOT_SUPPORTED,
+                    "physical replication is not supported",
+                ))
+                .await;
+        }
+        Some(value) => {
+            return conn
+                .send(ErrorResponse::fatal(
+                    SqlState::INVALID_PARAMETER_VALUE,
+                    format!(
+                        "invalid value for parameter \"replication\": {}",
+                        value.quoted()
+                    ),
+                ))
+                .await;
+        }
+    };
+
     // Validate that the connection is compatible with the TLS mode.
     //
     // The match here explicitly spells out all cases to be resilient to
@@ -159,28 +198,45 @@ where
         }
     }
 
-    let is_expired = if let Some(frontegg) = frontegg {
-        conn.send(BackendMessage::AuthenticationCleartextPassword)
-            .await?;
-        conn.flush().await?;
-        let password = match conn.recv().await? {
-            Some(FrontendMessage::Password { password }) => password,
-            _ => {
-                return conn
-                    .send(ErrorResponse::fatal(
-                        SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
-                        "expected Password message",
-                    ))
-                    .await
+    let is_expired = if authenticator.checks_role_passwords() {
+        if let Err(e) = authenticate_scram(conn, &coord_client, &user).await? {
+            debug!("authentication failed for user {}: {:#}", user.quoted(), e);
+            return conn
+                .send(ErrorResponse::fatal(
+                    SqlState::INVALID_PASSWORD,
+                    format!("password authentication failed for user {}", user.quoted()),
+                ))
+                .await;
+        }
+        // Passwords do not expire, so is_expired never resolves.
+        pending().right_future()
+    } else {
+        let credentials = if authenticator.requires_credentials() {
+            conn.send(BackendMessage::AuthenticationCleartextPassword)
+                .await?;
+            conn.flush().await?;
+            match recv_authentication(conn, codec::decode_password).await? {
+                Some(FrontendMessage::Password { password }) => Credentials::Password(password),
+                _ => {
+                    return conn
+                        .send(ErrorResponse::fatal(
+                            SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
+                            "expected Password message",
+                        ))
+                        .await
+                }
             }
+        } else {
+            Credentials::None
         };
-        match frontegg
-            .exchange_password_for_token(&password)
-            .await
-            .and_then(|token| frontegg.check_expiry(token, user.clone()))
-        {
-            Ok(check) => check.left_future(),
-            _ => {
+        match authenticator.authenticate(Some(&user), credentials).await {
+            Ok(authenticated) => match authenticated.expired {
+                Some(expired) => expired.left_future(),
+                // The authentication cannot expire, so is_expired never resolves.
+                None => pending().right_future(),
+            },
+            Err(e) if authenticator.requires_credentials() => {
+                debug!("authentication failed for user {}: {:#}", user.quoted(), e);
                 return conn
                     .send(ErrorResponse::fatal(
                         SqlState::INVALID_PASSWORD,
@@ -188,21 +244,32 @@ where
                     ))
                     .await;
             }
+            Err(e) => {
+                return conn
+                    .send(ErrorResponse::fatal(
+                        SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
+                        format!("authentication failed: {:#}", e),
+                    ))
+                    .await;
+            }
         }
-    } else {
-        // No frontegg check, so is_expired never resolves.
-        pending().right_future()
     };
 
     // Construct session.
     let mut session = Session::new(conn.id(), user);
+    session.set_connection_info(ConnectionInfo {
+        peer_addr,
+        tls: matches!(conn.inner(), Conn::Ssl(_)),
+    });
     for (name, value) in params {
         let local = false;
         let _ = session.vars_mut().set(&name, &value, local);
     }
 
     // Register session with coordinator.
-    let (mut coord_client, startup) = match coord_client.startup(session, frontegg.is_some()).await
+    let (mut coord_client, startup) = match coord_client
+        .startup(session, authenticator.creates_users())
+        .await
     {
         Ok(startup) => startup,
         Err(e) => {
@@ -232,6 +299,9 @@ where
         metrics,
         conn,
         coord_client: &mut coord_client,
+        replication,
+        drain_signal,
+        ready_for_query: true,
     };
 
     select! {
@@ -245,6 +315,89 @@ where
     }
 }
 
+/// Authenticates the client with SCRAM-SHA-256, against the password of the
+/// role named `user` in the catalog.
+///
+/// The outer result reports errors communicating with the client, and the
+/// inner result whether the client proved that it knows the password.
+async fn authenticate_scram<A>(
+    conn: &mut FramedConn<A>,
+    coord_client: &mz_coord::ConnClient,
+    user: &str,
+) -> Result<Result<(), anyhow::Error>, io::Error>
+where
+    A: AsyncRead + AsyncWrite + AsyncReady + Send + Sync + Unpin,
+{
+    // Roles without a usable password go through the same exchange with a
+    // mock verifier, so that clients cannot tell which roles exist.
+    let verifier = coord_client
+        .role_password(user)
+        .await
+        .and_then(|verifier| verifier.parse().ok())
+        .unwrap_or_else(Verifier::mock);
+
+    conn.send(BackendMessage::AuthenticationSasl {
+        mechanisms: vec![scram::MECHANISM],
+    })
+    .await?;
+    conn.flush().await?;
+    let client_first = match recv_authentication(conn, codec::decode_sasl_initial_response).await? {
+        Some(FrontendMessage::SaslInitialResponse {
+            mechanism,
+            initial_response,
+        }) if mechanism == scram::MECHANISM => initial_response,
+        _ => return Ok(Err(anyhow!("expected SASLInitialResponse message"))),
+    };
+    let (exchange, server_first) = match str::from_utf8(&client_first)
+        .map_err(anyhow::Error::from)
+        .and_then(|client_first| ServerExchange::start(verifier, client_first))
+    {
+        Ok(started) => started,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    conn.send(BackendMessage::AuthenticationSaslContinue(
+        server_first.into_bytes(),
+    ))
+    .await?;
+    conn.flush().await?;
+    let client_final = match recv_authentication(conn, codec::decode_sasl_response).await? {
+        Some(FrontendMessage::SaslResponse(response)) => response,
+        _ => return Ok(Err(anyhow!("expected SASLResponse message"))),
+    };
+    let server_final = match str::from_utf8(&client_final)
+        .map_err(anyhow::Error::from)
+        .and_then(|client_final| exchange.finish(client_final))
+    {
+        Ok(server_final) => server_final,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    conn.send(BackendMessage::AuthenticationSaslFinal(
+        server_final.into_bytes(),
+    ))
+    .await?;
+    Ok(Ok(()))
+}
+
+/// Receives an authentication message from the client and decodes it with
+/// `decode`.
+///
+/// Returns `None` if the client sends any other message, or an authentication
+/// message that `decode` rejects.
+async fn recv_authentication<A>(
+    conn: &mut FramedConn<A>,
+    decode: fn(&[u8]) -> Result<FrontendMessage, io::Error>,
+) -> Result<Option<FrontendMessage>, io::Error>
+where
+    A: AsyncRead + AsyncWrite + AsyncReady + Send + Sync + Unpin,
+{
+    match conn.recv().await? {
+        Some(FrontendMessage::RawAuthentication(buf)) => Ok(decode(&buf).ok()),
+        _ => Ok(None),
+    }
+}
+
 #[derive(Debug)]
 enum State {
     Ready,
@@ -256,6 +409,13 @@ struct StateMachine<'a, A> {
     conn: &'a mut FramedConn<A>,
     coord_client: &'a mut mz_coord::SessionClient,
     metrics: &'a Metrics,
+    /// Whether the connection accepts replication commands.
+    replication: bool,
+    /// The signal that tells the connection that the server is draining.
+    drain_signal: DrainSignal,
+    /// Whether the last message sent to the client was `ReadyForQuery`, in
+    /// which case the client has no requests outstanding.
+    ready_for_query: bool,
 }
 
 impl<'a, A> StateMachine<'a, A>
@@ -280,7 +440,32 @@ where
     }
 
     async fn advance_ready(&mut self) -> Result<State, io::Error> {
-        let message = self.conn.recv().await?;
+        let idle = self.is_idle();
+        let message = match self.idle_in_transaction_timeout() {
+            // A connection that is idle outside of a transaction block is
+            // closed when the server drains. Connections with requests or
+            // transactions in progress are instead left to finish them.
+            None if idle => select! {
+                message = self.conn.recv() => message?,
+                _ = self.drain_signal.draining() => {
+                    return self
+                        .error(ErrorResponse::fatal(
+                            SqlState::ADMIN_SHUTDOWN,
+                            "terminating connection due to administrator command",
+                        ))
+                        .await;
+                }
+            },
+            None => self.conn.recv().await?,
+            // A transaction whose time to hold the write lock has run out is
+            // not allowed to run another statement.
+            Some((timeout, error)) if timeout.is_zero() => return self.error(error).await,
+            Some((timeout, error)) => match time::timeout(timeout, self.conn.recv()).await {
+                Ok(message) => message?,
+                Err(_) => return self.error(error).await,
+            },
+        };
+        self.ready_for_query = false;
         let timer = Instant::now();
         let name = match &message {
             Some(message) => message.name(),
@@ -343,7 +528,10 @@ where
             Some(FrontendMessage::CopyData(_))
             | Some(FrontendMessage::CopyDone)
             | Some(FrontendMessage::CopyFail(_))
-            | Some(FrontendMessage::Password { .. }) => State::Drain,
+            | Some(FrontendMessage::RawAuthentication(_))
+            | Some(FrontendMessage::Password { .. })
+            | Some(FrontendMessage::SaslInitialResponse { .. })
+            | Some(FrontendMessage::SaslResponse(_)) => State::Drain,
             None => State::Done,
         };
 
@@ -359,6 +547,56 @@ where
         Ok(next_state)
     }
 
+    /// Reports whether the client has no requests outstanding and no
+    /// transaction block open.
+    fn is_idle(&mut self) -> bool {
+        self.ready_for_query
+            && matches!(
+                self.coord_client.session().transaction(),
+                TransactionStatus::Default
+            )
+    }
+
+    /// Returns how long the client may wait before sending its next message,
+    /// and the error with which its connection is terminated if it waits any
+    /// longer, if it is inside an explicit transaction whose duration is
+    /// limited.
+    ///
+    /// The time that the transaction may be idle is limited by
+    /// `idle_in_transaction_session_timeout`. A transaction that holds the
+    /// write lock blocks every other write, so the total time that it may hold
+    /// the lock is limited too, by the `write_lock_timeout` system variable.
+    fn idle_in_transaction_timeout(&mut self) -> Option<(Duration, ErrorResponse)> {
+        let session = self.coord_client.session();
+        match session.transaction() {
+            TransactionStatus::InTransaction(_) | TransactionStatus::Failed(_) => (),
+            _ => return None,
+        }
+        let idle_timeout = session
+            .vars()
+            .idle_in_transaction_session_timeout()
+            .map(|timeout| {
+                let error = ErrorResponse::fatal(
+                    SqlState::IDLE_IN_TRANSACTION_SESSION_TIMEOUT,
+                    "terminating connection due to idle-in-transaction timeout",
+                );
+                (timeout, error)
+            });
+        let write_lock_timeout = session.write_lock_deadline().map(|deadline| {
+            let timeout = Instant::from_std(deadline).saturating_duration_since(Instant::now());
+            let error = ErrorResponse::fatal(
+                SqlState::LOCK_NOT_AVAILABLE,
+                "terminating connection because its transaction held the write lock for \
+                 longer than write_lock_timeout",
+            );
+            (timeout, error)
+        });
+        idle_timeout
+            .into_iter()
+            .chain(write_lock_timeout)
+            .min_by_key(|(timeout, _)| *timeout)
+    }
+
     async fn advance_drain(&mut self) -> Result<State, io::Error> {
         match self.conn.recv().await? {
             Some(FrontendMessage::Sync) => self.sync().await,
@@ -445,6 +683,12 @@ where
     // transactions are handled.
     // From https://www.postgresql.org/docs/current/protocol-flow.html
     async fn query(&mut self, sql: String) -> Result<State, io::Error> {
+        if self.replication {
+            if let Some(command) = replication::parse_command(&sql) {
+                return self.replication_command(command).await;
+            }
+        }
+
         // Parse first before doing any transaction checking.
         let stmts = match parse_sql(&sql) {
             Ok(stmts) => stmts,
@@ -494,6 +738,282 @@ where
         self.ready().await
     }
 
+    /// Runs a command that is only available on replication connections, as a
+    /// single-statement transaction.
+    async fn replication_command(
+        &mut self,
+        command: Result<ReplicationCommand, String>,
+    ) -> Result<State, io::Error> {
+        let command = match command {
+            Ok(command) => command,
+            Err(msg) => {
+                self.error(ErrorResponse::error(SqlState::SYNTAX_ERROR, msg))
+                    .await?;
+                return self.ready().await;
+            }
+        };
+        if self.is_aborted_txn() {
+            self.aborted_txn_error().await?;
+            return self.ready().await;
+        }
+        if let TransactionStatus::InTransaction(_) = self.coord_client.session().transaction() {
+            self.error(ErrorResponse::error(
+                SqlState::ACTIVE_SQL_TRANSACTION,
+                "replication commands cannot run inside a transaction block",
+            ))
+            .await?;
+            return self.ready().await;
+        }
+
+        self.start_transaction(Some(1)).await;
+        let state = match command {
+            ReplicationCommand::IdentifySystem => {
+                // Materialize has no write-ahead log, so there is no meaningful
+                // log position to report. Replication starts from the slot's
+                // confirmed position regardless.
+                let stmt = parse_sql(
+                    "SELECT mz_cluster_id()::text AS systemid, 1::int4 AS timeline, \
+                     '0/0' AS xlogpos, current_database() AS dbname",
+                )
+                .expect("valid SQL")
+                .into_element();
+                self.one_query(stmt).await?
+            }
+            ReplicationCommand::CreateReplicationSlot { name, temporary } => {
+                match self
+                    .coord_client
+                    .create_replication_slot(name.clone(), temporary)
+                    .await
+                {
+                    Ok(()) => {
+                        let desc = RelationDesc::empty()
+                            .with_column("slot_name", ScalarType::String.nullable(false))
+                            .with_column("consistent_point", ScalarType::String.nullable(false))
+                            .with_column("snapshot_name", ScalarType::String.nullable(true))
+                            .with_column("output_plugin", ScalarType::String.nullable(false));
+                        let row = Row::pack_slice(&[
+                            Datum::String(&name),
+                            Datum::String(&replication::format_lsn(0)),
+                            Datum::Null,
+                            Datum::String("pgoutput"),
+                        ]);
+                        self.send_single_row(desc, row, "CREATE_REPLICATION_SLOT")
+                            .await?
+                    }
+                    Err(e) => {
+                        self.error(ErrorResponse::from_coord(Severity::Error, e))
+                            .await?
+                    }
+                }
+            }
+            ReplicationCommand::DropReplicationSlot { name } => {
+                match self.coord_client.drop_replication_slot(name).await {
+                    Ok(()) => {
+                        self.send(BackendMessage::CommandComplete {
+                            tag: "DROP_REPLICATION_SLOT".into(),
+                        })
+                        .await?;
+                        State::Ready
+                    }
+                    Err(e) => {
+                        self.error(ErrorResponse::from_coord(Severity::Error, e))
+                            .await?
+                    }
+                }
+            }
+            ReplicationCommand::StartReplication {
+                slot,
+                start_lsn,
+                publications,
+            } => {
+                self.start_replication(slot, start_lsn, publications)
+                    .await?
+            }
+            ReplicationCommand::ClearSearchPath => {
+                // The search path cannot be changed, but subscribers only
+                // refer to objects by their qualified names, so it is enough
+                // to acknowledge the request.
+                let desc = RelationDesc::empty()
+                    .with_column("set_config", ScalarType::String.nullable(true));
+                let row = Row::pack_slice(&[Datum::String("")]);
+                self.send_single_row(desc, row, "SELECT 1").await?
+            }
+        };
+
+        match state {
+            State::Ready => {
+                if self.coord_client.session().transaction().is_implicit() {
+                    self.commit_transaction().await?;
+                }
+            }
+            State::Drain => (),
+            State::Done => return Ok(State::Done),
+        }
+        self.ready().await
+    }
+
+    /// Sends a result that consists of the single row `row`, described by
+    /// `desc`.
+    async fn send_single_row(
+        &mut self,
+        desc: RelationDesc,
+        row: Row,
+        tag: &str,
+    ) -> Result<State, io::Error> {
+        let formats = vec![mz_pgrepr::Format::Text; desc.arity()];
+        self.send(BackendMessage::RowDescription(
+            message::encode_row_description(&desc, &formats),
+        ))
+        .await?;
+        self.conn.set_encode_state(
+            desc.typ()
+                .column_types
+                .iter()
+                .map(|ty| mz_pgrepr::Type::from(&ty.scalar_type))
+                .zip(formats)
+                .collect(),
+        );
+        self.send(BackendMessage::DataRow(mz_pgrepr::values_from_row(
+            row,
+            desc.typ(),
+        )))
+        .await?;
+        self.send(BackendMessage::CommandComplete {
+            tag: tag.to_string(),
+        })
+        .await?;
+        Ok(State::Ready)
+    }
+
+    /// Streams the publications `publications` from the replication slot
+    /// `slot` until the subscriber ends the replication.
+    async fn start_replication(
+        &mut self,
+        slot: String,
+        start_lsn: u64,
+        publications: Vec<String>,
+    ) -> Result<State, io::Error> {
+        let ReplicationStream { relations, confirm } = match self
+            .coord_client
+            .start_replication(slot, publications, start_lsn)
+            .await
+        {
+            Ok(stream) => stream,
+            Err(e) => {
+                return self
+                    .error(ErrorResponse::from_coord(Severity::Error, e))
+                    .await
+            }
+        };
+        let mut assembler = TransactionAssembler::new(&relations);
+        let mut streams: Vec<_> = relations.into_iter().map(|relation| relation.rx).collect();
+
+        self.send(BackendMessage::CopyBothResponse {
+            overall_format: mz_pgrepr::Format::Text,
+            column_formats: vec![],
+        })
+        .await?;
+
+        // Keepalives let the subscriber know that the connection is healthy
+        // even when there are no updates to send.
+        let mut keepalive = time::interval(Duration::from_secs(10));
+        loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    let data = replication::encode_keepalive(assembler.sent(), false);
+                    self.send(BackendMessage::CopyData(data)).await?;
+                }
+                _ = self.coord_client.canceled() => {
+                    return self
+                        .error(ErrorResponse::error(
+                            SqlState::QUERY_CANCELED,
+                            "canceling statement due to user request",
+                        ))
+                        .await;
+                }
+                message = self.conn.recv() => match message? {
+                    Some(FrontendMessage::CopyData(data)) => {
+                        match replication::decode_standby_message(&data) {
+                            // The subscriber cannot have flushed updates that
+                            // have not been sent.
+                            Ok(StandbyMessage::StatusUpdate { flush }) => {
+                                confirm(cmp::min(flush, assembler.sent()))
+                            }
+                            Ok(StandbyMessage::Other) => (),
+                            Err(msg) => {
+                                return self
+                                    .error(ErrorResponse::error(SqlState::PROTOCOL_VIOLATION, msg))
+                                    .await;
+                            }
+                        }
+                    }
+                    Some(FrontendMessage::CopyDone) => break,
+                    Some(FrontendMessage::CopyFail(msg)) => {
+                        return self
+                            .error(ErrorResponse::error(
+                                SqlState::QUERY_CANCELED,
+                                format!("replication failed: {}", msg),
+                            ))
+                            .await;
+                    }
+                    Some(FrontendMessage::Terminate) | None => return Ok(State::Done),
+                    Some(message) => {
+                        return self
+                            .error(ErrorResponse::fatal(
+                                SqlState::PROTOCOL_VIOLATION,
+                                format!("unexpected message type {} during replication", message.name()),
+                            ))
+                            .await;
+                    }
+                },
+                (i, batch) = recv_any(&mut streams) => match batch {
+                    // The tails are only dropped once the replication ends.
+                    None => break,
+                    Some(PeekResponseUnary::Rows(rows)) => {
+                        if let Err(msg) = assembler.push(i, rows) {
+                            return self
+                                .error(ErrorResponse::error(SqlState::INTERNAL_ERROR, msg))
+                                .await;
+                        }
+                        for data in assembler.drain() {
+                            self.send(BackendMessage::CopyData(data)).await?;
+                        }
+                    }
+                    Some(PeekResponseUnary::Error(text)) => {
+                        return self
+                            .error(ErrorResponse::error(SqlState::INTERNAL_ERROR, text))
+                            .await;
+                    }
+                    Some(PeekResponseUnary::Canceled) => {
+                        return self
+                            .error(ErrorResponse::error(
+                                SqlState::QUERY_CANCELED,
+                                "canceling statement due to user request",
+                            ))
+                            .await;
+                    }
+                    Some(PeekResponseUnary::TimedOut) => {
+                        return self
+                            .error(ErrorResponse::error(
+                                SqlState::QUERY_CANCELED,
+                                "canceling statement due to statement timeout",
+                            ))
+                            .await;
+                    }
+                },
+            }
+
+            self.conn.flush().await?;
+        }
+
+        self.send(BackendMessage::CopyDone).await?;
+        self.send(BackendMessage::CommandComplete {
+            tag: "START_REPLICATION".into(),
+        })
+        .await?;
+        Ok(State::Ready)
+    }
+
     async fn parse(
         &mut self,
         name: String,
@@ -1002,6 +1522,7 @@ where
     async fn ready(&mut self) -> Result<State, io::Error> {
         let txn_state = self.coord_client.session().transaction().into();
         self.send(BackendMessage::ReadyForQuery(txn_state)).await?;
+        self.ready_for_query = true;
         self.flush().await
     }
 
@@ -1070,6 +1591,9 @@ where
             ExecuteResponse::CreatedIndex { existed } => {
                 created!(existed, SqlState::DUPLICATE_OBJECT, "index")
             }
+            ExecuteResponse::CreatedPublication { existed } => {
+                created!(existed, SqlState::DUPLICATE_OBJECT, "publication")
+            }
             ExecuteResponse::CreatedSecret { existed } => {
                 created!(existed, SqlState::DUPLICATE_OBJECT, "secret")
             }
@@ -1080,6 +1604,9 @@ where
             ExecuteResponse::CreatedSink { existed } => {
                 created!(existed, SqlState::DUPLICATE_OBJECT, "sink")
             }
+            ExecuteResponse::CreatedTask { existed } => {
+                created!(existed, SqlState::DUPLICATE_OBJECT, "task")
+            }
             ExecuteResponse::CreatedView { existed } => {
                 created!(existed, SqlState::DUPLICATE_OBJECT, "view")
             }
@@ -1102,6 +1629,8 @@ where
             ExecuteResponse::DroppedView => command_complete!("DROP VIEW"),
             ExecuteResponse::DroppedType => command_complete!("DROP TYPE"),
             ExecuteResponse::DroppedSecret => command_complete!("DROP SECRET"),
+            ExecuteResponse::DroppedPublication => command_complete!("DROP PUBLICATION"),
+            ExecuteResponse::DroppedTask => command_complete!("DROP TASK"),
             ExecuteResponse::EmptyQuery => {
                 self.send(BackendMessage::EmptyQueryResponse).await?;
                 Ok(State::Ready)
@@ -1144,7 +1673,7 @@ where
                 )
                 .await
             }
-            ExecuteResponse::SetVariable { name } => {
+            ExecuteResponse::SetVariable { name, reset } => {
                 // This code is somewhat awkwardly structured because we
                 // can't hold `var` across an await point.
                 let qn = name.to_string();
@@ -1162,7 +1691,22 @@ where
                 if let Some(msg) = msg {
                     self.send(msg).await?;
                 }
-                command_complete!("SET")
+                if reset {
+                    command_complete!("RESET")
+                } else {
+                    command_complete!("SET")
+                }
+            }
+            ExecuteResponse::ResetAllVariables => {
+                let msgs: Vec<_> = self
+                    .coord_client
+                    .session()
+                    .vars()
+                    .notify_set()
+                    .map(|var| BackendMessage::ParameterStatus(var.name(), var.value()))
+                    .collect();
+                self.send_all(msgs).await?;
+                command_complete!("RESET")
             }
             ExecuteResponse::StartedTransaction { duplicated } => {
                 if duplicated {
@@ -1239,9 +1783,16 @@ where
                     row_desc.expect("missing row description for ExecuteResponse::CopyFrom");
                 self.copy_from(id, columns, params, row_desc).await
             }
+            ExecuteResponse::Undropped => command_complete!("UNDROP"),
             ExecuteResponse::Updated(n) => command_complete!("UPDATE {}", n),
             ExecuteResponse::AlteredObject(o) => command_complete!("ALTER {}", o),
             ExecuteResponse::AlteredIndexLogicalCompaction => command_complete!("ALTER INDEX"),
+            ExecuteResponse::AlteredDatabase => command_complete!("ALTER DATABASE"),
+            ExecuteResponse::AlteredSchema => command_complete!("ALTER SCHEMA"),
+            ExecuteResponse::AlteredSystemConfiguration => command_complete!("ALTER SYSTEM"),
+            ExecuteResponse::Commented => command_complete!("COMMENT"),
+            ExecuteResponse::GrantedPrivilege => command_complete!("GRANT"),
+            ExecuteResponse::RevokedPrivilege => command_complete!("REVOKE"),
             ExecuteResponse::Prepare => command_complete!("PREPARE"),
             ExecuteResponse::Deallocate { all } => {
                 command_complete!("DEALLOCATE{}", if all { " ALL" } else { "" })
@@ -1340,6 +1891,7 @@ where
                         Some(PeekResponseUnary::Rows(rows)) => FetchResult::Rows(Some(rows)),
                         Some(PeekResponseUnary::Error(err)) => FetchResult::Error(err),
                         Some(PeekResponseUnary::Canceled) => FetchResult::Canceled,
+                        Some(PeekResponseUnary::TimedOut) => FetchResult::TimedOut,
                     },
                 }
             };
@@ -1420,6 +1972,14 @@ where
                         ))
                         .await;
                 }
+                FetchResult::TimedOut => {
+                    return self
+                        .error(ErrorResponse::error(
+                            SqlState::QUERY_CANCELED,
+                            "canceling statement due to statement timeout",
+                        ))
+                        .await;
+                }
             }
         }
 
@@ -1542,6 +2102,13 @@ where
                             ))
                             .await;
                     }
+                    Some(PeekResponseUnary::TimedOut) => {
+                        return self.error(ErrorResponse::error(
+                                SqlState::QUERY_CANCELED,
+                                "canceling statement due to statement timeout",
+                            ))
+                            .await;
+                    }
                     Some(PeekResponseUnary::Rows(rows)) => {
                         count += rows.len();
                         for row in rows {
@@ -1817,5 +2384,18 @@ fn is_txn_exit_stmt(stmt: Option<&Statement<Raw>>) -> bool {
 enum FetchResult {
     Rows(Option<Vec<Row>>),
     Canceled,
+    TimedOut,
     Error(String),
 }
+
+/// Receives the next batch from any of `streams`, along with the index of the
+/// stream that produced it.
+///
+/// This function is cancel safe.
+async fn recv_any(streams: &mut [RowBatchStream]) -> (usize, Option<PeekResponseUnary>) {
+    if streams.is_empty() {
+        return pending().await;
+    }
+    let (batch, i, _) = select_all(streams.iter_mut().map(|stream| stream.recv().boxed())).await;
+    (i, batch)
+}
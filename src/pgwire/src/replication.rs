@@ -0,0 +1,728 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The PostgreSQL logical replication protocol, through which subscribers
+//! stream publications.
+//!
+//! A connection whose startup message sets `replication=database` accepts the
+//! replication commands that PostgreSQL subscribers issue, in addition to SQL.
+//! Once a subscriber starts replication, the updates to the published views
+//! are streamed to it in the `pgoutput` format, as protocol version 1.
+//!
+//! Materialize timestamps double as log sequence numbers (LSNs). The updates at
+//! each timestamp are sent as one transaction, whose commit ends at the LSN
+//! one past its timestamp, so that a subscriber that confirms an LSN has
+//! applied exactly the transactions at earlier timestamps.
+//!
+//! # Useful references
+//!
+//!   * [Streaming Replication Protocol](https://www.postgresql.org/docs/current/protocol-replication.html)
+//!   * [Logical Replication Message Formats](https://www.postgresql.org/docs/current/protocol-logicalrep-message-formats.html)
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::{BufMut, BytesMut};
+
+use mz_coord::ReplicatedRelation;
+use mz_repr::{Datum, Diff, RelationDesc, Row, Timestamp};
+
+/// The number of microseconds between the Unix epoch and the PostgreSQL
+/// epoch, 2000-01-01.
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+/// A command that is only available on replication connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplicationCommand {
+    /// `IDENTIFY_SYSTEM`.
+    IdentifySystem,
+    /// `CREATE_REPLICATION_SLOT`.
+    CreateReplicationSlot {
+        /// The name of the slot.
+        name: String,
+        /// Whether the slot is dropped when the connection ends.
+        temporary: bool,
+    },
+    /// `DROP_REPLICATION_SLOT`.
+    DropReplicationSlot {
+        /// The name of the slot.
+        name: String,
+    },
+    /// `START_REPLICATION`.
+    StartReplication {
+        /// The name of the slot.
+        slot: String,
+        /// The LSN from which the subscriber requests updates.
+        start_lsn: u64,
+        /// The names of the publications to stream.
+        publications: Vec<String>,
+    },
+    /// The `SELECT pg_catalog.set_config('search_path', '', false)` that
+    /// subscribers issue when they connect.
+    ClearSearchPath,
+}
+
+/// The query that subscribers issue to clear the search path.
+const CLEAR_SEARCH_PATH: &str = "SELECT pg_catalog.set_config('search_path', '', false)";
+
+/// Parses `sql` as a replication command.
+///
+/// Returns `None` if `sql` is not a replication command, in which case it
+/// should be handled as SQL.
+pub fn parse_command(sql: &str) -> Option<Result<ReplicationCommand, String>> {
+    let trimmed = sql.trim().trim_end_matches(';').trim_end();
+    if trimmed.eq_ignore_ascii_case(CLEAR_SEARCH_PATH) {
+        return Some(Ok(ReplicationCommand::ClearSearchPath));
+    }
+    let mut tokens = match tokenize(trimmed) {
+        Ok(tokens) => Tokens::new(tokens),
+        // Let the SQL parser report the error, if the query is not a
+        // replication command.
+        Err(e) => return starts_with_command(trimmed).then(|| Err(e)),
+    };
+    let command = match tokens.peek_keyword() {
+        Some(keyword) if COMMANDS.contains(&keyword.as_str()) => keyword,
+        _ => return None,
+    };
+    tokens.next();
+    let res = match command.as_str() {
+        "IDENTIFY_SYSTEM" => Ok(ReplicationCommand::IdentifySystem),
+        "CREATE_REPLICATION_SLOT" => parse_create_replication_slot(&mut tokens),
+        "DROP_REPLICATION_SLOT" => parse_drop_replication_slot(&mut tokens),
+        "START_REPLICATION" => parse_start_replication(&mut tokens),
+        _ => unreachable!(),
+    };
+    Some(res.and_then(|command| match tokens.next() {
+        None => Ok(command),
+        Some(token) => Err(format!("syntax error at or near {}", token)),
+    }))
+}
+
+/// The replication commands that Materialize supports.
+const COMMANDS: [&str; 4] = [
+    "IDENTIFY_SYSTEM",
+    "CREATE_REPLICATION_SLOT",
+    "DROP_REPLICATION_SLOT",
+    "START_REPLICATION",
+];
+
+fn starts_with_command(sql: &str) -> bool {
+    let word: String = sql
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    COMMANDS.contains(&word.to_ascii_uppercase().as_str())
+}
+
+fn parse_create_replication_slot(tokens: &mut Tokens) -> Result<ReplicationCommand, String> {
+    let name = tokens.expect_ident()?;
+    let temporary = tokens.parse_keyword("TEMPORARY");
+    if tokens.parse_keyword("PHYSICAL") {
+        return Err("physical replication is not supported".into());
+    }
+    tokens.expect_keyword("LOGICAL")?;
+    let plugin = tokens.expect_ident()?;
+    if plugin != "pgoutput" {
+        return Err(format!(
+            "output plugin \"{}\" is not supported; only \"pgoutput\" is",
+            plugin
+        ));
+    }
+    // The snapshot is streamed from the slot when replication starts, so a
+    // snapshot cannot be exported or used.
+    let use_snapshot_error = "USE_SNAPSHOT is not supported: create the subscription \
+                              WITH (copy_data = false), as its initial data is replicated \
+                              from the slot";
+    if tokens.parse_keyword("USE_SNAPSHOT") {
+        return Err(use_snapshot_error.into());
+    } else if !tokens.parse_keyword("NOEXPORT_SNAPSHOT") {
+        tokens.parse_keyword("EXPORT_SNAPSHOT");
+    }
+    for (option, value) in tokens.parse_options()? {
+        match (option.as_str(), value.as_deref()) {
+            ("SNAPSHOT", Some("use")) => return Err(use_snapshot_error.into()),
+            ("SNAPSHOT", _) | ("TWO_PHASE", Some("false")) => (),
+            _ => return Err(format!("option \"{}\" is not supported", option)),
+        }
+    }
+    Ok(ReplicationCommand::CreateReplicationSlot { name, temporary })
+}
+
+fn parse_drop_replication_slot(tokens: &mut Tokens) -> Result<ReplicationCommand, String> {
+    let name = tokens.expect_ident()?;
+    // Slots are never waited on, as dropping an active slot is an error.
+    tokens.parse_keyword("WAIT");
+    Ok(ReplicationCommand::DropReplicationSlot { name })
+}
+
+fn parse_start_replication(tokens: &mut Tokens) -> Result<ReplicationCommand, String> {
+    if !tokens.parse_keyword("SLOT") {
+        return Err("physical replication is not supported".into());
+    }
+    let slot = tokens.expect_ident()?;
+    tokens.expect_keyword("LOGICAL")?;
+    let start_lsn = match tokens.next() {
+        Some(Token::Word(word)) => parse_lsn(&word)?,
+        _ => return Err("expected a log sequence number".into()),
+    };
+    let mut publications = None;
+    for (option, value) in tokens.parse_options()? {
+        match (option.as_str(), value) {
+            ("PROTO_VERSION", Some(version)) if version == "1" => (),
+            ("PROTO_VERSION", Some(version)) => {
+                return Err(format!(
+                    "proto_version \"{}\" is not supported; only version 1 is",
+                    version
+                ))
+            }
+            ("PUBLICATION_NAMES", Some(names)) => {
+                publications = Some(parse_identifier_list(&names)?);
+            }
+            ("BINARY", Some(value)) if value == "false" || value == "off" => (),
+            _ => return Err(format!("option \"{}\" is not supported", option)),
+        }
+    }
+    let publications = match publications {
+        Some(publications) => publications,
+        None => return Err("publication_names parameter missing".into()),
+    };
+    Ok(ReplicationCommand::StartReplication {
+        slot,
+        start_lsn,
+        publications,
+    })
+}
+
+/// Parses a comma-separated list of identifiers, as in the
+/// `publication_names` option.
+fn parse_identifier_list(s: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Tokens::new(tokenize(s)?);
+    let mut names = vec![tokens.expect_ident()?];
+    while tokens.parse_punct(',') {
+        names.push(tokens.expect_ident()?);
+    }
+    match tokens.next() {
+        None => Ok(names),
+        Some(_) => Err(format!("invalid list syntax in parameter \"{}\"", s)),
+    }
+}
+
+/// Formats a log sequence number in the `X/X` form that PostgreSQL uses.
+pub fn format_lsn(lsn: u64) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+}
+
+/// Parses a log sequence number in the `X/X` form that PostgreSQL uses.
+fn parse_lsn(s: &str) -> Result<u64, String> {
+    let err = || format!("invalid log sequence number: \"{}\"", s);
+    let (hi, lo) = s.split_once('/').ok_or_else(err)?;
+    let hi = u32::from_str_radix(hi, 16).map_err(|_| err())?;
+    let lo = u32::from_str_radix(lo, 16).map_err(|_| err())?;
+    Ok(u64::from(hi) << 32 | u64::from(lo))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    QuotedIdent(String),
+    String(String),
+    Punct(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "\"{}\"", word),
+            Token::QuotedIdent(ident) => write!(f, "\"{}\"", ident),
+            Token::String(s) => write!(f, "'{}'", s),
+            Token::Punct(c) => write!(f, "\"{}\"", c),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' | ')' | ',' | ';' => tokens.push(Token::Punct(c)),
+            '"' | '\'' => {
+                let quote = c;
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(c) if c == quote && chars.peek() == Some(&quote) => {
+                            chars.next();
+                            value.push(quote);
+                        }
+                        Some(c) if c == quote => break,
+                        Some(c) => value.push(c),
+                        None => return Err("unterminated quoted string".into()),
+                    }
+                }
+                tokens.push(match quote {
+                    '"' => Token::QuotedIdent(value),
+                    _ => Token::String(value),
+                });
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '/' => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.peek() {
+                    if !(c.is_alphanumeric() || *c == '_' || *c == '/') {
+                        break;
+                    }
+                    word.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(format!("syntax error at or near \"{}\"", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Tokens {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Tokens {
+    fn new(tokens: Vec<Token>) -> Tokens {
+        Tokens {
+            tokens: tokens.into_iter().peekable(),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        // A trailing semicolon ends the command.
+        match self.tokens.next() {
+            Some(Token::Punct(';')) if self.tokens.peek().is_none() => None,
+            token => token,
+        }
+    }
+
+    fn peek_keyword(&mut self) -> Option<String> {
+        match self.tokens.peek() {
+            Some(Token::Word(word)) => Some(word.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword().as_deref() == Some(keyword) {
+            self.tokens.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.parse_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!("expected {}", keyword))
+        }
+    }
+
+    fn parse_punct(&mut self, punct: char) -> bool {
+        if self.tokens.peek() == Some(&Token::Punct(punct)) {
+            self.tokens.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses an identifier, which is folded to lower case unless quoted.
+    fn expect_ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word.to_lowercase()),
+            Some(Token::QuotedIdent(ident)) => Ok(ident),
+            Some(token) => Err(format!("syntax error at or near {}", token)),
+            None => Err("syntax error at end of input".into()),
+        }
+    }
+
+    /// Parses an optional parenthesized list of options, each of which is a
+    /// name, folded to upper case, and an optional value.
+    fn parse_options(&mut self) -> Result<Vec<(String, Option<String>)>, String> {
+        let mut options = vec![];
+        if !self.parse_punct('(') {
+            return Ok(options);
+        }
+        loop {
+            let name = self.expect_ident()?.to_ascii_uppercase();
+            let value = match self.tokens.peek() {
+                Some(Token::String(_)) | Some(Token::Word(_)) => match self.tokens.next() {
+                    Some(Token::String(value)) | Some(Token::Word(value)) => Some(value),
+                    _ => unreachable!(),
+                },
+                _ => None,
+            };
+            options.push((name, value));
+            if self.parse_punct(')') {
+                return Ok(options);
+            }
+            if !self.parse_punct(',') {
+                return Err("expected \",\" or \")\"".into());
+            }
+        }
+    }
+}
+
+/// A message from the subscriber during replication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StandbyMessage {
+    /// The subscriber has durably applied all updates before `flush`.
+    StatusUpdate {
+        /// The LSN through which the subscriber has flushed.
+        flush: u64,
+    },
+    /// Any other message, which is ignored.
+    Other,
+}
+
+/// Decodes the contents of a `CopyData` message that the subscriber sent
+/// during replication.
+pub fn decode_standby_message(data: &[u8]) -> Result<StandbyMessage, String> {
+    match data.first() {
+        // Standby status update: write, flush and apply positions, send time
+        // and reply request.
+        Some(b'r') if data.len() >= 34 => {
+            let flush = u64::from_be_bytes(data[9..17].try_into().unwrap());
+            Ok(StandbyMessage::StatusUpdate { flush })
+        }
+        // Hot standby feedback, which only concerns physical replication.
+        Some(b'h') => Ok(StandbyMessage::Other),
+        _ => Err("invalid standby message".into()),
+    }
+}
+
+/// Returns the current time, in microseconds since the PostgreSQL epoch.
+fn pg_now() -> i64 {
+    let since_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    i64::try_from(since_unix.as_micros())
+        .unwrap_or(i64::MAX)
+        .saturating_sub(PG_EPOCH_OFFSET_MICROS)
+}
+
+/// Encodes a primary keepalive message, which reports that all updates before
+/// `wal_end` have been sent.
+pub fn encode_keepalive(wal_end: u64, reply_requested: bool) -> Vec<u8> {
+    let mut buf = vec![b'k'];
+    buf.put_u64(wal_end);
+    buf.put_i64(pg_now());
+    buf.put_u8(u8::from(reply_requested));
+    buf
+}
+
+/// Encodes an `XLogData` message that carries the `pgoutput` message `data`.
+fn encode_xlog_data(start: u64, end: u64, data: &[u8]) -> Vec<u8> {
+    let mut buf = vec![b'w'];
+    buf.put_u64(start);
+    buf.put_u64(end);
+    buf.put_i64(pg_now());
+    buf.put_slice(data);
+    buf
+}
+
+/// A view streamed during replication.
+struct Relation {
+    oid: u32,
+    schema: String,
+    name: String,
+    desc: RelationDesc,
+    /// The frontier of the view's tail. All updates at earlier times have
+    /// been received.
+    frontier: Timestamp,
+    /// Whether the view's `Relation` message has been sent.
+    described: bool,
+}
+
+/// Assembles the updates of the tails of the replicated views into
+/// transactions.
+///
+/// Each transaction contains the updates to all views at one timestamp, and is
+/// complete once every tail has advanced past that timestamp.
+pub struct TransactionAssembler {
+    relations: Vec<Relation>,
+    /// The updates not yet sent, by timestamp. Each update names the index of
+    /// its view.
+    pending: BTreeMap<Timestamp, Vec<(usize, Row, Diff)>>,
+    /// The LSN before which all transactions have been sent.
+    sent: u64,
+    /// The ID of the next transaction.
+    next_xid: u32,
+}
+
+impl TransactionAssembler {
+    /// Creates an assembler for the given views, whose tails are received
+    /// separately.
+    pub fn new(relations: &[ReplicatedRelation]) -> TransactionAssembler {
+        TransactionAssembler {
+            relations: relations
+                .iter()
+                .map(|relation| Relation {
+                    oid: relation.oid,
+                    schema: relation.schema.clone(),
+                    name: relation.name.clone(),
+                    desc: relation.desc.clone(),
+                    frontier: Timestamp::default(),
+                    described: false,
+                })
+                .collect(),
+            pending: BTreeMap::new(),
+            sent: 0,
+            next_xid: 1,
+        }
+    }
+
+    /// Returns the LSN before which all transactions have been sent.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Records a batch of rows from the tail of the view at index `relation`.
+    ///
+    /// The rows must include the tail's progress columns.
+    pub fn push(&mut self, relation: usize, rows: Vec<Row>) -> Result<(), String> {
+        for row in rows {
+            let mut datums = row.iter();
+            let time = match datums.next() {
+                Some(Datum::Numeric(time)) => {
+                    u64::try_from(time.0).map_err(|_| "invalid tail timestamp".to_string())?
+                }
+                _ => return Err("invalid tail timestamp".into()),
+            };
+            let progressed = datums.next() == Some(Datum::True);
+            if progressed {
+                let frontier = &mut self.relations[relation].frontier;
+                *frontier = std::cmp::max(*frontier, time);
+                continue;
+            }
+            let diff = match datums.next() {
+                Some(Datum::Int64(diff)) => diff,
+                _ => return Err("invalid tail diff".into()),
+            };
+            self.pending
+                .entry(time)
+                .or_default()
+                .push((relation, Row::pack(datums), diff));
+        }
+        Ok(())
+    }
+
+    /// Drains the transactions that are complete, returning the `CopyData`
+    /// messages that send them, followed by a keepalive if the frontier of
+    /// sent transactions advanced.
+    pub fn drain(&mut self) -> Vec<Vec<u8>> {
+        let frontier = self
+            .relations
+            .iter()
+            .map(|relation| relation.frontier)
+            .min()
+            .unwrap_or_default();
+        let mut messages = vec![];
+        while let Some(entry) = self.pending.first_entry() {
+            if *entry.key() >= frontier {
+                break;
+            }
+            let (time, updates) = entry.remove_entry();
+            self.encode_transaction(time, updates, &mut messages);
+        }
+        if frontier > self.sent {
+            self.sent = frontier;
+            messages.push(encode_keepalive(frontier, false));
+        }
+        messages
+    }
+
+    /// Encodes the updates at `time` as a transaction.
+    fn encode_transaction(
+        &mut self,
+        time: Timestamp,
+        mut updates: Vec<(usize, Row, Diff)>,
+        messages: &mut Vec<Vec<u8>>,
+    ) {
+        // Retractions are applied first, so that a row that is updated is
+        // deleted before its new version is inserted.
+        updates.sort_by_key(|(relation, _, diff)| (*diff > 0, *relation));
+        updates.retain(|(_, _, diff)| *diff != 0);
+        if updates.is_empty() {
+            return;
+        }
+        let commit_time = i64::try_from(time)
+            .unwrap_or(i64::MAX)
+            .saturating_mul(1000)
+            .saturating_sub(PG_EPOCH_OFFSET_MICROS);
+        let xid = self.next_xid;
+        self.next_xid = self.next_xid.wrapping_add(1);
+
+        // Every message but the commit ends at the transaction's timestamp,
+        // so that a subscriber that confirms its position mid-transaction
+        // does not confirm the transaction.
+        let mut push = |data: Vec<u8>, end| messages.push(encode_xlog_data(time, end, &data));
+
+        let mut begin = vec![b'B'];
+        begin.put_u64(time);
+        begin.put_i64(commit_time);
+        begin.put_u32(xid);
+        push(begin, time);
+
+        for (relation, row, diff) in updates {
+            let relation = &mut self.relations[relation];
+            if !relation.described {
+                push(encode_relation(relation), time);
+                relation.described = true;
+            }
+            let tuple = encode_tuple(&relation.desc, &row);
+            let (kind, tuple_kind) = if diff < 0 { (b'D', b'O') } else { (b'I', b'N') };
+            for _ in 0..diff.unsigned_abs() {
+                let mut message = vec![kind];
+                message.put_u32(relation.oid);
+                message.put_u8(tuple_kind);
+                message.put_slice(&tuple);
+                push(message, time);
+            }
+        }
+
+        let mut commit = vec![b'C'];
+        commit.put_u8(0);
+        commit.put_u64(time);
+        commit.put_u64(time + 1);
+        commit.put_i64(commit_time);
+        push(commit, time + 1);
+    }
+}
+
+/// Encodes a `Relation` message that describes the columns of a view.
+///
+/// Views have no key, so all columns identify a row, as if the view had
+/// `REPLICA IDENTITY FULL`.
+fn encode_relation(relation: &Relation) -> Vec<u8> {
+    let mut buf = vec![b'R'];
+    buf.put_u32(relation.oid);
+    put_string(&mut buf, &relation.schema);
+    put_string(&mut buf, &relation.name);
+    buf.put_u8(b'f');
+    buf.put_u16(u16::try_from(relation.desc.arity()).expect("too many columns"));
+    for (name, typ) in relation.desc.iter() {
+        let pg_type = mz_pgrepr::Type::from(&typ.scalar_type);
+        buf.put_u8(1);
+        put_string(&mut buf, name.as_str());
+        buf.put_u32(pg_type.oid());
+        buf.put_i32(pg_type.typmod());
+    }
+    buf
+}
+
+/// Encodes the values of `row` as `TupleData`, in the text format.
+fn encode_tuple(desc: &RelationDesc, row: &Row) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    buf.put_u16(u16::try_from(desc.arity()).expect("too many columns"));
+    for (datum, typ) in row.iter().zip(desc.typ().column_types.iter()) {
+        match mz_pgrepr::Value::from_datum(datum, &typ.scalar_type) {
+            None => buf.put_u8(b'n'),
+            Some(value) => {
+                let mut text = BytesMut::new();
+                value.encode_text(&mut text);
+                buf.put_u8(b't');
+                buf.put_u32(u32::try_from(text.len()).expect("value too large"));
+                buf.put_slice(&text);
+            }
+        }
+    }
+    buf.to_vec()
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &str) {
+    buf.put_slice(s.as_bytes());
+    buf.put_u8(b'\0');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let parse = |sql| parse_command(sql).map(|res| res.unwrap());
+        assert_eq!(parse("SELECT 1"), None);
+        assert_eq!(
+            parse("identify_system;"),
+            Some(ReplicationCommand::IdentifySystem)
+        );
+        assert_eq!(
+            parse("SELECT pg_catalog.set_config('search_path', '', false);"),
+            Some(ReplicationCommand::ClearSearchPath)
+        );
+        assert_eq!(
+            parse(r#"CREATE_REPLICATION_SLOT "Sub" TEMPORARY LOGICAL pgoutput NOEXPORT_SNAPSHOT"#),
+            Some(ReplicationCommand::CreateReplicationSlot {
+                name: "Sub".into(),
+                temporary: true,
+            })
+        );
+        assert_eq!(
+            parse(r#"CREATE_REPLICATION_SLOT sub LOGICAL pgoutput (SNAPSHOT 'nothing')"#),
+            Some(ReplicationCommand::CreateReplicationSlot {
+                name: "sub".into(),
+                temporary: false,
+            })
+        );
+        assert_eq!(
+            parse("DROP_REPLICATION_SLOT sub WAIT"),
+            Some(ReplicationCommand::DropReplicationSlot { name: "sub".into() })
+        );
+        assert_eq!(
+            parse(
+                r#"START_REPLICATION SLOT "sub" LOGICAL 1/A (proto_version '1', publication_names '"P1",p2')"#
+            ),
+            Some(ReplicationCommand::StartReplication {
+                slot: "sub".into(),
+                start_lsn: (1 << 32) + 10,
+                publications: vec!["P1".into(), "p2".into()],
+            })
+        );
+
+        let parse_err = |sql| parse_command(sql).unwrap().unwrap_err();
+        assert_eq!(
+            parse_err("CREATE_REPLICATION_SLOT sub LOGICAL wal2json"),
+            r#"output plugin "wal2json" is not supported; only "pgoutput" is"#
+        );
+        assert!(
+            parse_err("CREATE_REPLICATION_SLOT sub LOGICAL pgoutput USE_SNAPSHOT")
+                .starts_with("USE_SNAPSHOT is not supported")
+        );
+        assert_eq!(
+            parse_err("START_REPLICATION SLOT sub LOGICAL 0/0 (proto_version '2')"),
+            r#"proto_version "2" is not supported; only version 1 is"#
+        );
+        assert_eq!(
+            parse_err("START_REPLICATION SLOT sub LOGICAL 0/0"),
+            "publication_names parameter missing"
+        );
+        assert_eq!(
+            parse_err("START_REPLICATION 0/0"),
+            "physical replication is not supported"
+        );
+    }
+
+    #[test]
+    fn test_lsn() {
+        for lsn in [0, 1, 1650000000000, u64::MAX] {
+            assert_eq!(parse_lsn(&format_lsn(lsn)), Ok(lsn));
+        }
+        assert_eq!(format_lsn(0x16B3748), "0/16B3748");
+    }
+}
@@ -9,16 +9,19 @@
 
 use futures::ready;
 use std::fmt;
+use std::net::IpAddr;
 use std::pin::Pin;
+use std::sync::{Arc, RwLock};
 use std::task::{Context, Poll};
 
 use async_trait::async_trait;
 use openssl::ssl::{Ssl, SslContext};
 use tokio::io::{self, AsyncRead, AsyncWrite, AsyncWriteExt, Interest, ReadBuf, Ready};
+use tokio::sync::watch;
 use tokio_openssl::SslStream;
 use tracing::trace;
 
-use mz_frontegg_auth::FronteggAuthentication;
+use mz_authenticator::Authenticator;
 use mz_ore::cast::CastFrom;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::netio::AsyncReady;
@@ -38,25 +41,88 @@ pub struct Config<'a> {
     /// If not present, then TLS is not enabled, and clients requests to
     /// negotiate TLS will be rejected.
     pub tls: Option<TlsConfig>,
-    /// The Frontegg authentication configuration.
+    /// The authenticator with which to authenticate clients.
     ///
-    /// If present, Frontegg authentication is enabled, and users may present
-    /// a valid Frontegg API token as a password to authenticate. Otherwise,
-    /// password authentication is disabled.
-    pub frontegg: Option<FronteggAuthentication>,
+    /// If the authenticator requires credentials, clients are asked for a
+    /// password during startup.
+    pub authenticator: Arc<dyn Authenticator>,
     /// The registry that the pg wire server uses to report metrics.
     pub metrics_registry: &'a MetricsRegistry,
+    /// The signal that tells connections that the server is draining.
+    pub drain_signal: DrainSignal,
 }
 
 /// Configures a server's TLS encryption and authentication.
 #[derive(Debug)]
 pub struct TlsConfig {
     /// The SSL context used to manage incoming TLS negotiations.
-    pub context: SslContext,
+    pub context: ReloadableSslContext,
     /// The TLS mode.
     pub mode: TlsMode,
 }
 
+/// An [`SslContext`] that can be replaced while the server is running, e.g.,
+/// to pick up a renewed certificate.
+///
+/// Clones share the same underlying context. Connections that have already
+/// negotiated TLS are unaffected by a replacement.
+#[derive(Debug, Clone)]
+pub struct ReloadableSslContext(Arc<RwLock<SslContext>>);
+
+impl ReloadableSslContext {
+    /// Constructs a new reloadable context that initially holds `context`.
+    pub fn new(context: SslContext) -> ReloadableSslContext {
+        ReloadableSslContext(Arc::new(RwLock::new(context)))
+    }
+
+    /// Returns the current SSL context.
+    pub fn get(&self) -> SslContext {
+        self.0.read().expect("lock poisoned").clone()
+    }
+
+    /// Replaces the SSL context used for future TLS negotiations.
+    pub fn set(&self, context: SslContext) {
+        *self.0.write().expect("lock poisoned") = context;
+    }
+}
+
+/// Triggers a [`DrainSignal`], either explicitly or when dropped.
+#[derive(Debug)]
+pub struct DrainTrigger(watch::Sender<bool>);
+
+impl DrainTrigger {
+    /// Tells connections that the server is draining.
+    pub fn trigger(&self) {
+        // If sending fails, no connections remain to be told.
+        let _ = self.0.send(true);
+    }
+}
+
+/// A signal that tells connections that the server is draining, i.e., that
+/// they should close once they are idle.
+///
+/// Clones observe the same trigger.
+#[derive(Debug, Clone)]
+pub struct DrainSignal(watch::Receiver<bool>);
+
+impl DrainSignal {
+    /// Constructs a new signal along with the trigger that activates it.
+    pub fn new() -> (DrainTrigger, DrainSignal) {
+        let (tx, rx) = watch::channel(false);
+        (DrainTrigger(tx), DrainSignal(rx))
+    }
+
+    /// Waits until the server is draining.
+    pub async fn draining(&mut self) {
+        while !*self.0.borrow() {
+            if self.0.changed().await.is_err() {
+                // The trigger was dropped.
+                return;
+            }
+        }
+    }
+}
+
 /// Specifies how strictly to enforce TLS encryption and authentication.
 #[derive(Debug, Clone, Copy)]
 pub enum TlsMode {
@@ -72,7 +138,8 @@ pub struct Server {
     tls: Option<TlsConfig>,
     coord_client: mz_coord::Client,
     metrics: Metrics,
-    frontegg: Option<FronteggAuthentication>,
+    authenticator: Arc<dyn Authenticator>,
+    drain_signal: DrainSignal,
 }
 
 impl Server {
@@ -82,11 +149,17 @@ impl Server {
             metrics: Metrics::register_into(config.metrics_registry),
             tls: config.tls,
             coord_client: config.coord_client,
-            frontegg: config.frontegg,
+            authenticator: config.authenticator,
+            drain_signal: config.drain_signal,
         }
     }
 
-    pub async fn handle_connection<A>(&self, conn: A) -> Result<(), anyhow::Error>
+    /// Handles a pgwire connection from the client at `peer_addr`, if known.
+    pub async fn handle_connection<A>(
+        &self,
+        conn: A,
+        peer_addr: Option<IpAddr>,
+    ) -> Result<(), anyhow::Error>
     where
         A: AsyncRead + AsyncWrite + AsyncReady + Send + Sync + Unpin + fmt::Debug + 'static,
     {
@@ -118,8 +191,10 @@ impl Server {
                         conn: &mut conn,
                         version,
                         params,
+                        peer_addr,
                         metrics: &self.metrics,
-                        frontegg: self.frontegg.as_ref(),
+                        authenticator: &*self.authenticator,
+                        drain_signal: self.drain_signal.clone(),
                     })
                     .await?;
                     conn.flush().await?;
@@ -140,7 +215,7 @@ impl Server {
                     (Conn::Unencrypted(mut conn), Some(tls)) => {
                         trace!("cid={} send=AcceptSsl", conn_id);
                         conn.write_all(&[ACCEPT_SSL_ENCRYPTION]).await?;
-                        let mut ssl_stream = SslStream::new(Ssl::new(&tls.context)?, conn)?;
+                        let mut ssl_stream = SslStream::new(Ssl::new(&tls.context.get())?, conn)?;
                         if let Err(e) = Pin::new(&mut ssl_stream).accept().await {
                             let _ = ssl_stream.get_mut().shutdown().await;
                             return Err(e.into());
@@ -227,6 +227,15 @@ pub async fn drop_replication_slots(conn: &str, slots: &[String]) -> Result<(),
     Ok(())
 }
 
+/// Starts a regular, non-replication connection to the upstream database
+pub async fn connect(conn: &str) -> Result<Client, anyhow::Error> {
+    let config: Config = conn.parse()?;
+    let tls = make_tls(&config)?;
+    let (client, connection) = config.connect(tls).await?;
+    task::spawn(|| format!("postgres_connect:{conn}"), connection);
+    Ok(client)
+}
+
 /// Starts a replication connection to the upstream database
 pub async fn connect_replication(conn: &str) -> Result<Client, anyhow::Error> {
     let mut config: Config = conn.parse()?;
@@ -8,7 +8,12 @@
 // by the Apache License, Version 2.0.
 
 use serde::Serialize;
-use std::{collections::HashMap, ffi::c_void, time::Instant};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::c_void,
+    fmt::Write,
+    time::Instant,
+};
 
 #[cfg(feature = "jemalloc")]
 pub mod jemalloc;
@@ -74,6 +79,38 @@ impl StackProfile {
             idx: 0,
         }
     }
+
+    /// Renders the profile in the folded format consumed by flamegraph tools
+    /// like `inferno` and `flamegraph.pl`.
+    ///
+    /// Each line describes one distinct stack: its frames from outermost to
+    /// innermost, separated by semicolons, then a space and the stack's
+    /// weight. The annotation of an annotated stack, like the name of the
+    /// thread on which it was sampled, is its outermost frame.
+    pub fn to_folded(&self) -> String {
+        let addr_to_symbols = symbolicate(self);
+        let mut weights = BTreeMap::new();
+        for (stack, annotation) in self.iter() {
+            let frames: Vec<&str> = annotation
+                .into_iter()
+                .chain(
+                    stack
+                        .addrs
+                        .iter()
+                        .flat_map(|addr| addr_to_symbols[addr].iter().map(String::as_str)),
+                )
+                .collect();
+            if !frames.is_empty() {
+                *weights.entry(frames.join(";")).or_insert(0.0) += stack.weight;
+            }
+        }
+        let mut folded = String::new();
+        for (frames, weight) in weights {
+            // String's `std::fmt::Write` implementation never fails.
+            writeln!(folded, "{} {}", frames, weight).unwrap();
+        }
+        folded
+    }
 }
 #[derive(Serialize)]
 pub struct SymbolTrieNode {
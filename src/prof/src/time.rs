@@ -8,6 +8,7 @@
 // by the Apache License, Version 2.0.
 
 use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::bail;
 use pprof::ProfilerGuard;
@@ -15,6 +16,71 @@ use tokio::time::{self, Duration};
 
 use crate::{StackProfile, WeightedStack};
 
+/// The frequency, in hertz, at which CPU profiles are usually sampled.
+pub const DEFAULT_SAMPLE_FREQ: u32 = 99;
+
+/// The longest CPU profile that [`cpu_profile`] will take.
+pub const MAX_PROFILE_DURATION: Duration = Duration::from_secs(300);
+
+/// Whether a CPU profile is being taken by [`cpu_profile`].
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+struct ProfilingGuard;
+
+impl Drop for ProfilingGuard {
+    fn drop(&mut self) {
+        PROFILING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Takes a CPU profile of every thread in the current process over
+/// `total_time`.
+///
+/// Unlike [`prof_time`], this function is safe to call: jemalloc memory
+/// profiling, if enabled, is turned off before the CPU profile is taken. Only
+/// one CPU profile can be taken at a time.
+pub async fn cpu_profile(
+    total_time: Duration,
+    sample_freq: u32,
+    merge_threads: bool,
+) -> anyhow::Result<StackProfile> {
+    if total_time > MAX_PROFILE_DURATION {
+        bail!(
+            "CPU profiles cannot be longer than {} seconds",
+            MAX_PROFILE_DURATION.as_secs()
+        );
+    }
+    if PROFILING.swap(true, Ordering::SeqCst) {
+        bail!("a CPU profile is already being taken");
+    }
+    let _guard = ProfilingGuard;
+
+    #[cfg(feature = "jemalloc")]
+    let _ctl = match crate::jemalloc::PROF_CTL.as_ref() {
+        Some(ctl) => {
+            let mut ctl = ctl.lock().await;
+            ctl.deactivate()?;
+            Some(ctl)
+        }
+        None => None,
+    };
+    // SAFETY: memory profiling is off, and nobody can turn it back on while
+    // we hold its lock.
+    unsafe { prof_time(total_time, sample_freq, merge_threads) }.await
+}
+
+/// Takes a CPU profile of every thread in the current process over
+/// `total_time`, as by [`cpu_profile`], and renders it in the folded format of
+/// [`StackProfile::to_folded`].
+pub async fn cpu_profile_folded(total_time: Duration) -> anyhow::Result<String> {
+    let profile = cpu_profile(total_time, DEFAULT_SAMPLE_FREQ, false).await?;
+    // Symbolication is slow and blocking, so keep it off the async runtime.
+    let folded =
+        mz_ore::task::spawn_blocking(|| "cpu_profile_symbolicate", move || profile.to_folded())
+            .await?;
+    Ok(folded)
+}
+
 /// # Safety
 ///
 /// Nothing else must be attempting to unwind backtraces while this is called.
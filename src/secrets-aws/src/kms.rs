@@ -0,0 +1,37 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Keys that are stored encrypted by [AWS KMS].
+//!
+//! [AWS KMS]: https://aws.amazon.com/kms/
+
+use anyhow::{bail, Context, Error};
+use aws_sdk_kms::types::Blob;
+
+use mz_aws_util::config::AwsConfig;
+
+/// Decrypts `ciphertext`, a key that AWS KMS encrypted, like the
+/// `CiphertextBlob` that KMS's `GenerateDataKey` operation returns.
+///
+/// The ciphertext identifies the KMS key that encrypted it, so no key ID is
+/// needed. The AWS region and credentials are loaded from the environment.
+pub async fn decrypt_data_key(ciphertext: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let aws_config = AwsConfig::load_from_env().await;
+    let client = mz_aws_util::kms::client(&aws_config);
+    let output = client
+        .decrypt()
+        .ciphertext_blob(Blob::new(ciphertext))
+        .send()
+        .await
+        .context("decrypting key with AWS KMS")?;
+    match output.plaintext() {
+        Some(plaintext) => Ok(plaintext.as_ref().to_vec()),
+        None => bail!("AWS KMS did not return the decrypted key"),
+    }
+}
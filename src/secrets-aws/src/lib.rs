@@ -0,0 +1,91 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Secrets storage in [AWS Secrets Manager] or in the [Parameter Store] of
+//! AWS Systems Manager.
+//!
+//! Each secret is stored under the name `<prefix><id>`: as a binary secret in
+//! Secrets Manager, or as the base64-encoded value of a `SecureString`
+//! parameter in Parameter Store. See [`AwsSecretsController`] and
+//! [`AwsParameterStoreController`] for how each service versions secrets.
+//!
+//! Credentials are discovered by the default AWS credentials chain, which
+//! includes the web identity token with which Kubernetes service accounts
+//! assume IAM roles. The controllers can additionally assume a configured IAM
+//! role.
+//!
+//! Keys that AWS KMS encrypted, like the keys with which other secrets
+//! controllers encrypt secrets at rest, can be decrypted with
+//! [`decrypt_data_key`].
+//!
+//! [AWS Secrets Manager]: https://aws.amazon.com/secrets-manager/
+//! [Parameter Store]: https://docs.aws.amazon.com/systems-manager/latest/userguide/systems-manager-parameter-store.html
+
+use std::collections::BTreeMap;
+
+use aws_config::default_provider::{credentials, region};
+use aws_config::meta::region::ProvideRegion;
+use aws_config::sts::AssumeRoleProvider;
+use aws_smithy_http::endpoint::Endpoint;
+use aws_types::credentials::SharedCredentialsProvider;
+use http::Uri;
+
+use mz_aws_util::config::AwsConfig;
+
+mod kms;
+mod parameter_store;
+mod secrets_manager;
+
+pub use kms::decrypt_data_key;
+pub use parameter_store::{AwsParameterStoreController, AwsParameterStoreReader};
+pub use secrets_manager::{AwsSecretsController, AwsSecretsReader};
+
+/// The configuration of the controllers and readers of secrets in AWS.
+#[derive(Debug, Clone)]
+pub struct AwsSecretsConfig {
+    /// The prefix of the names of the secrets, e.g., `materialize/`.
+    ///
+    /// Must not be empty. The names of parameters in Parameter Store must
+    /// begin with `/` if they contain a `/`.
+    pub prefix: String,
+    /// The ID or ARN of the KMS key with which to encrypt new secrets.
+    ///
+    /// If not present, secrets are encrypted with the account's AWS managed
+    /// key for the service.
+    pub kms_key_id: Option<String>,
+    /// The tags to attach to new secrets.
+    pub tags: BTreeMap<String, String>,
+    /// The ARN of an IAM role to assume when accessing the service.
+    pub role_arn: Option<String>,
+    /// The endpoint of the service, if not the service's default endpoint
+    /// for the region, e.g., the endpoint of LocalStack.
+    pub endpoint: Option<Uri>,
+}
+
+/// Loads the AWS configuration with which to access the service that stores
+/// secrets, as configured by `config`.
+///
+/// The AWS region and credentials are loaded from the environment.
+async fn load_aws_config(config: &AwsSecretsConfig) -> AwsConfig {
+    let mut loader = aws_config::from_env();
+    if let Some(role_arn) = &config.role_arn {
+        let mut role_provider = AssumeRoleProvider::builder(role_arn).session_name("materialized");
+        if let Some(region) = region::default_provider().region().await {
+            role_provider = role_provider.region(region);
+        }
+        let default_provider =
+            SharedCredentialsProvider::new(credentials::default_provider().await);
+        loader = loader.credentials_provider(role_provider.build(default_provider));
+    }
+    let mut aws_config = AwsConfig::from_loader(loader).await;
+    if let Some(endpoint) = &config.endpoint {
+        aws_config.set_endpoint(Endpoint::immutable(endpoint.clone()));
+    }
+    aws_config
+}
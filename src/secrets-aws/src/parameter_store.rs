@@ -0,0 +1,344 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Secrets storage in the Parameter Store of AWS Systems Manager.
+
+use anyhow::{bail, Context, Error};
+use async_trait::async_trait;
+use aws_sdk_ssm::model::{
+    ParameterStringFilter, ParameterTier, ParameterType, ResourceTypeForTagging, Tag,
+};
+use aws_sdk_ssm::types::SdkError;
+use aws_sdk_ssm::Client;
+
+use mz_expr::GlobalId;
+use mz_secrets::{RevertibleStorage, SecretOp, SecretVersion, SecretsController};
+
+use crate::AwsSecretsConfig;
+
+/// Reads secrets from Parameter Store.
+///
+/// Readers are cheap to clone.
+#[derive(Debug, Clone)]
+pub struct AwsParameterStoreReader {
+    client: Client,
+    config: AwsSecretsConfig,
+}
+
+impl AwsParameterStoreReader {
+    /// Creates a reader that reads secrets as configured by `config`.
+    ///
+    /// The AWS region and credentials are loaded from the environment.
+    pub async fn new(config: AwsSecretsConfig) -> AwsParameterStoreReader {
+        let aws_config = crate::load_aws_config(&config).await;
+        AwsParameterStoreReader {
+            client: mz_aws_util::ssm::client(&aws_config),
+            config,
+        }
+    }
+
+    /// Reads the contents of the current version of the secret `id`.
+    pub async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, None).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the contents of version `version` of the secret `id`.
+    pub async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, Some(version)).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("version {} of secret {} does not exist", version, id),
+        }
+    }
+
+    /// Returns the current version of the secret `id`.
+    pub async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        match self.try_read(id, None).await? {
+            Some((version, _)) => Ok(version),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the version and contents of version `version` of the secret
+    /// `id`, or of its current version if `version` is absent, if it exists.
+    async fn try_read(
+        &self,
+        id: GlobalId,
+        version: Option<SecretVersion>,
+    ) -> Result<Option<(SecretVersion, Vec<u8>)>, Error> {
+        // A version of a parameter is selected by suffixing its name with
+        // the version.
+        let name = match version {
+            Some(version) => format!("{}:{}", self.name(id), version),
+            None => self.name(id),
+        };
+        let res = self
+            .client
+            .get_parameter()
+            .name(name)
+            .with_decryption(true)
+            .send()
+            .await;
+        match res {
+            Ok(output) => {
+                let parameter = match output.parameter {
+                    Some(parameter) => parameter,
+                    None => bail!("parameter store did not return secret {}", id),
+                };
+                let contents = base64::decode(parameter.value.unwrap_or_default())
+                    .with_context(|| format!("decoding secret {}", id))?;
+                Ok(Some((SecretVersion(parameter.version as u64), contents)))
+            }
+            // Parameter Store only retains the 100 most recent versions of a
+            // parameter.
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_parameter_not_found() || err.is_parameter_version_not_found() =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e).with_context(|| format!("reading secret {}", id)),
+        }
+    }
+
+    /// Writes a new version of the secret `id` with the given `contents`,
+    /// creating the secret if it does not exist, and returns the new version.
+    async fn write(&self, id: GlobalId, contents: &[u8]) -> Result<SecretVersion, Error> {
+        let output = self
+            .client
+            .put_parameter()
+            .name(self.name(id))
+            .value(base64::encode(contents))
+            .r#type(ParameterType::SecureString)
+            .set_key_id(self.config.kms_key_id.clone())
+            // Parameters that outgrow the standard tier are moved to the
+            // advanced tier automatically.
+            .tier(ParameterTier::IntelligentTiering)
+            .overwrite(true)
+            .send()
+            .await
+            .with_context(|| format!("writing secret {}", id))?;
+        let version = SecretVersion(output.version as u64);
+        // Tags cannot be attached by a write that may overwrite an existing
+        // parameter, so they are attached to new parameters separately.
+        if version == SecretVersion(1) && !self.config.tags.is_empty() {
+            let tags = self
+                .config
+                .tags
+                .iter()
+                .map(|(key, value)| Tag::builder().key(key).value(value).build())
+                .collect();
+            self.client
+                .add_tags_to_resource()
+                .resource_type(ResourceTypeForTagging::Parameter)
+                .resource_id(self.name(id))
+                .set_tags(Some(tags))
+                .send()
+                .await
+                .with_context(|| format!("tagging secret {}", id))?;
+        }
+        Ok(version)
+    }
+
+    /// Deletes every version of the secret `id`.
+    ///
+    /// Deleting a secret that does not exist is not an error.
+    async fn delete(&self, id: GlobalId) -> Result<(), Error> {
+        let res = self
+            .client
+            .delete_parameter()
+            .name(self.name(id))
+            .send()
+            .await;
+        match res {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. }) if err.is_parameter_not_found() => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("deleting secret {}", id)),
+        }
+    }
+
+    /// Lists the IDs of the secrets in Parameter Store.
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        let filter = ParameterStringFilter::builder()
+            .key("Name")
+            .option("BeginsWith")
+            .values(&self.config.prefix)
+            .build();
+        let mut next_token = None;
+        let mut ids = vec![];
+        loop {
+            let output = self
+                .client
+                .describe_parameters()
+                .parameter_filters(filter.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+                .context("listing secrets")?;
+            // Ignore parameters that are not named after a secret.
+            ids.extend(
+                output
+                    .parameters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|parameter| parameter.name)
+                    .filter_map(|name| name.strip_prefix(&self.config.prefix)?.parse().ok()),
+            );
+            match output.next_token {
+                Some(token) => next_token = Some(token),
+                None => return Ok(ids),
+            }
+        }
+    }
+
+    /// Returns the name of the parameter that stores the secret `id`.
+    fn name(&self, id: GlobalId) -> String {
+        format!("{}{}", self.config.prefix, id)
+    }
+}
+
+/// Stores secrets in Parameter Store.
+///
+/// Each secret is stored as a `SecureString` parameter, encrypted with the
+/// configured KMS key. The versions of secrets are the versions that
+/// Parameter Store assigns to parameters, and Parameter Store retains the 100
+/// most recent versions of each. Parameter Store cannot delete individual
+/// versions.
+///
+/// Parameter Store cannot apply several operations atomically, so the
+/// controller applies them one at a time and, if one fails, reverts the
+/// operations it has already applied. Reverting an update restores the
+/// previous version's contents as a new version, and reverting a deletion
+/// recreates the secret with the contents of its current version, as its
+/// first version.
+#[derive(Debug)]
+pub struct AwsParameterStoreController {
+    reader: AwsParameterStoreReader,
+}
+
+impl AwsParameterStoreController {
+    /// Creates a controller that stores secrets as configured by `config`.
+    ///
+    /// Fails if the controller cannot list the secrets in Parameter Store.
+    pub async fn new(config: AwsSecretsConfig) -> Result<AwsParameterStoreController, Error> {
+        if config.prefix.is_empty() {
+            bail!("the secret name prefix must not be empty");
+        }
+        let reader = AwsParameterStoreReader::new(config).await;
+        reader.list().await?;
+        Ok(AwsParameterStoreController { reader })
+    }
+
+    /// Returns a reader for the secrets that the controller stores.
+    pub fn reader(&self) -> AwsParameterStoreReader {
+        self.reader.clone()
+    }
+}
+
+/// A change that [`SecretsController::apply`] made to a secret, which it
+/// reverts if a later operation fails.
+enum Change {
+    /// Version `version` of the secret `id` was written.
+    Ensured {
+        id: GlobalId,
+        version: SecretVersion,
+    },
+    /// The secret `id`, whose current version had contents `contents`, was
+    /// deleted.
+    Deleted { id: GlobalId, contents: Vec<u8> },
+}
+
+/// Applies the operations of an [`AwsParameterStoreController`] to
+/// Parameter Store.
+struct Writer<'a>(&'a AwsParameterStoreReader);
+
+#[async_trait]
+impl RevertibleStorage for Writer<'_> {
+    type Change = Change;
+
+    // Writes report the version they create, which is all that is needed to
+    // revert them, so only deletions read a secret before changing it.
+    async fn apply_op(
+        &self,
+        op: &SecretOp,
+    ) -> Result<(Option<SecretVersion>, Option<Change>), Error> {
+        let reader = self.0;
+        match op {
+            SecretOp::Ensure { id, contents } => {
+                let version = reader.write(*id, contents).await?;
+                Ok((Some(version), Some(Change::Ensured { id: *id, version })))
+            }
+            SecretOp::Delete { id, version: None } => {
+                // Deleting a secret that does not exist is not an error, and
+                // needs no reversion.
+                match reader.try_read(*id, None).await? {
+                    Some((_, contents)) => {
+                        reader.delete(*id).await?;
+                        Ok((None, Some(Change::Deleted { id: *id, contents })))
+                    }
+                    None => Ok((None, None)),
+                }
+            }
+            SecretOp::Delete {
+                id,
+                version: Some(_),
+            } => bail!("cannot delete individual versions of secret {}", id),
+        }
+    }
+
+    async fn revert(&self, change: Change) -> Result<(), Error> {
+        let reader = self.0;
+        match change {
+            // Parameter Store numbers the versions of a new parameter from 1,
+            // so the first version was a creation.
+            Change::Ensured {
+                id,
+                version: SecretVersion(1),
+            } => reader.delete(id).await?,
+            Change::Ensured { id, version } => {
+                let previous = reader
+                    .read_version(id, SecretVersion(version.0 - 1))
+                    .await?;
+                reader.write(id, &previous).await?;
+            }
+            Change::Deleted { id, contents } => {
+                reader.write(id, &contents).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SecretsController for AwsParameterStoreController {
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, Error> {
+        mz_secrets::apply_reverting(&Writer(&self.reader), &ops).await
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        self.reader.read(id).await
+    }
+
+    async fn read_version(&self, id: GlobalId, version: SecretVersion) -> Result<Vec<u8>, Error> {
+        self.reader.read_version(id, version).await
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        self.reader.current_version(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        self.reader.list().await
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Secrets storage in AWS Secrets Manager.
+
+use anyhow::{bail, Context, Error};
+use async_trait::async_trait;
+use aws_sdk_secretsmanager::model::{Filter, FilterNameStringType, Tag};
+use aws_sdk_secretsmanager::types::{Blob, SdkError};
+use aws_sdk_secretsmanager::Client;
+
+use mz_expr::GlobalId;
+use mz_secrets::{RevertibleStorage, SecretOp, SecretVersion, SecretsController};
+
+use crate::AwsSecretsConfig;
+
+/// The number of days for which a deleted secret can be restored, which is
+/// the minimum that Secrets Manager allows.
+const RECOVERY_WINDOW_DAYS: i64 = 7;
+
+/// Reads secrets from Secrets Manager.
+///
+/// Readers are cheap to clone.
+#[derive(Debug, Clone)]
+pub struct AwsSecretsReader {
+    client: Client,
+    config: AwsSecretsConfig,
+}
+
+impl AwsSecretsReader {
+    /// Creates a reader that reads secrets as configured by `config`.
+    ///
+    /// The AWS region and credentials are loaded from the environment.
+    pub async fn new(config: AwsSecretsConfig) -> AwsSecretsReader {
+        let aws_config = crate::load_aws_config(&config).await;
+        AwsSecretsReader {
+            client: mz_aws_util::secretsmanager::client(&aws_config),
+            config,
+        }
+    }
+
+    /// Reads the contents of the current version of the secret `id`.
+    pub async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, None).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the contents of version `version` of the secret `id`.
+    pub async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, Some(version)).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("version {} of secret {} does not exist", version, id),
+        }
+    }
+
+    /// Returns the current version of the secret `id`.
+    pub async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        match self.try_read(id, None).await? {
+            Some((version, _)) => Ok(version),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the version and contents of version `version` of the secret
+    /// `id`, or of its current version if `version` is absent, if it exists.
+    async fn try_read(
+        &self,
+        id: GlobalId,
+        version: Option<SecretVersion>,
+    ) -> Result<Option<(SecretVersion, Vec<u8>)>, Error> {
+        let res = self
+            .client
+            .get_secret_value()
+            .secret_id(self.name(id))
+            .set_version_id(version.map(version_id))
+            .send()
+            .await;
+        match res {
+            Ok(output) => {
+                let version = output
+                    .version_id
+                    .and_then(|version_id| version_id.parse().ok())
+                    .map_or(SecretVersion(0), SecretVersion);
+                match output.secret_binary {
+                    Some(blob) => Ok(Some((version, blob.into_inner()))),
+                    None => bail!("secret {} is not a binary secret", id),
+                }
+            }
+            Err(SdkError::ServiceError { err, .. }) if err.is_resource_not_found_exception() => {
+                Ok(None)
+            }
+            Err(e) => Err(e).with_context(|| format!("reading secret {}", id)),
+        }
+    }
+
+    /// Writes version `version` of the secret `id` with the given `contents`,
+    /// creating the secret if it does not exist.
+    ///
+    /// `exists` reports whether the secret is known to exist already.
+    async fn write(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+        contents: &[u8],
+        exists: bool,
+    ) -> Result<(), Error> {
+        let blob = Blob::new(contents);
+        if exists {
+            self.client
+                .put_secret_value()
+                .secret_id(self.name(id))
+                .client_request_token(version_id(version))
+                .secret_binary(blob)
+                .send()
+                .await
+                .with_context(|| format!("updating secret {}", id))?;
+        } else {
+            let tags = self
+                .config
+                .tags
+                .iter()
+                .map(|(key, value)| Tag::builder().key(key).value(value).build())
+                .collect();
+            self.client
+                .create_secret()
+                .name(self.name(id))
+                .set_kms_key_id(self.config.kms_key_id.clone())
+                .set_tags(Some(tags))
+                .client_request_token(version_id(version))
+                .secret_binary(blob)
+                .send()
+                .await
+                .with_context(|| format!("creating secret {}", id))?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the secret `id`.
+    ///
+    /// If `recoverable` is true, the secret is scheduled for deletion at the
+    /// end of the recovery window, and can be restored with
+    /// [`AwsSecretsReader::restore`] until then. Otherwise the secret is
+    /// deleted immediately.
+    ///
+    /// Returns whether the secret existed.
+    async fn delete(&self, id: GlobalId, recoverable: bool) -> Result<bool, Error> {
+        let mut request = self.client.delete_secret().secret_id(self.name(id));
+        if recoverable {
+            request = request.recovery_window_in_days(RECOVERY_WINDOW_DAYS);
+        } else {
+            request = request.force_delete_without_recovery(true);
+        }
+        match request.send().await {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError { err, .. }) if err.is_resource_not_found_exception() => {
+                Ok(false)
+            }
+            Err(e) => Err(e).with_context(|| format!("deleting secret {}", id)),
+        }
+    }
+
+    /// Cancels the scheduled deletion of the secret `id`.
+    async fn restore(&self, id: GlobalId) -> Result<(), Error> {
+        self.client
+            .restore_secret()
+            .secret_id(self.name(id))
+            .send()
+            .await
+            .with_context(|| format!("restoring secret {}", id))?;
+        Ok(())
+    }
+
+    /// Lists the IDs of the secrets in Secrets Manager.
+    ///
+    /// Secrets that are scheduled for deletion are not listed.
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        let filter = Filter::builder()
+            .key(FilterNameStringType::Name)
+            .values(&self.config.prefix)
+            .build();
+        let mut next_token = None;
+        let mut ids = vec![];
+        loop {
+            let output = self
+                .client
+                .list_secrets()
+                .filters(filter.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+                .context("listing secrets")?;
+            // The name filter matches any name that begins with the prefix,
+            // so ignore names that are not named after a secret.
+            ids.extend(
+                output
+                    .secret_list
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|entry| entry.name)
+                    .filter_map(|name| name.strip_prefix(&self.config.prefix)?.parse().ok()),
+            );
+            match output.next_token {
+                Some(token) => next_token = Some(token),
+                None => return Ok(ids),
+            }
+        }
+    }
+
+    /// Returns the name of the secret `id` in Secrets Manager.
+    fn name(&self, id: GlobalId) -> String {
+        format!("{}{}", self.config.prefix, id)
+    }
+}
+
+/// Returns the ID of the Secrets Manager version that stores `version`.
+fn version_id(version: SecretVersion) -> String {
+    // Version IDs must be at least 32 characters long.
+    format!("{:032}", version.0)
+}
+
+/// Stores secrets in Secrets Manager.
+///
+/// The version of a secret is stored as the ID of the Secrets Manager version
+/// that holds it, zero-padded to the minimum length of a version ID. Secrets
+/// Manager versions that were not created by the controller are version 0.
+/// Secrets Manager decides how many previous versions of a secret to retain,
+/// and cannot delete individual versions.
+///
+/// Secrets Manager cannot apply several operations atomically, so the
+/// controller applies them one at a time and, if one fails, reverts the
+/// operations it has already applied. Reverting an update restores the
+/// previous contents of a secret as a new version. Deleted secrets are
+/// scheduled for deletion at the end of a seven day recovery window, rather
+/// than deleted immediately, so that a deletion can be reverted by restoring
+/// the secret.
+#[derive(Debug)]
+pub struct AwsSecretsController {
+    reader: AwsSecretsReader,
+}
+
+impl AwsSecretsController {
+    /// Creates a controller that stores secrets as configured by `config`.
+    ///
+    /// Fails if the controller cannot list the secrets in Secrets Manager.
+    pub async fn new(config: AwsSecretsConfig) -> Result<AwsSecretsController, Error> {
+        if config.prefix.is_empty() {
+            bail!("the secret name prefix must not be empty");
+        }
+        let reader = AwsSecretsReader::new(config).await;
+        reader.list().await?;
+        Ok(AwsSecretsController { reader })
+    }
+
+    /// Returns a reader for the secrets that the controller stores.
+    pub fn reader(&self) -> AwsSecretsReader {
+        self.reader.clone()
+    }
+}
+
+/// A change that [`SecretsController::apply`] made to a secret, which it
+/// reverts if a later operation fails.
+enum Change {
+    /// Version `version` of the secret `id` was written over the secret's
+    /// previous contents, if it existed.
+    Ensured {
+        id: GlobalId,
+        version: SecretVersion,
+        previous: Option<Vec<u8>>,
+    },
+    /// The secret `id` was scheduled for deletion.
+    Deleted { id: GlobalId },
+}
+
+/// Applies the operations of an [`AwsSecretsController`] to Secrets Manager.
+struct Writer<'a>(&'a AwsSecretsReader);
+
+#[async_trait]
+impl RevertibleStorage for Writer<'_> {
+    type Change = Change;
+
+    async fn apply_op(
+        &self,
+        op: &SecretOp,
+    ) -> Result<(Option<SecretVersion>, Option<Change>), Error> {
+        let reader = self.0;
+        match op {
+            SecretOp::Ensure { id, contents } => {
+                // The controller numbers versions itself, so the current
+                // version must be read to number the next one.
+                let (version, previous) = match reader.try_read(*id, None).await? {
+                    Some((current, previous)) => (current.next(), Some(previous)),
+                    None => (SecretVersion(1), None),
+                };
+                reader
+                    .write(*id, version, contents, previous.is_some())
+                    .await?;
+                let change = Change::Ensured {
+                    id: *id,
+                    version,
+                    previous,
+                };
+                Ok((Some(version), Some(change)))
+            }
+            SecretOp::Delete { id, version: None } => {
+                // Deleting a secret that does not exist is not an error, and
+                // needs no reversion.
+                let deleted = reader.delete(*id, true).await?;
+                Ok((None, deleted.then(|| Change::Deleted { id: *id })))
+            }
+            SecretOp::Delete {
+                id,
+                version: Some(_),
+            } => bail!("cannot delete individual versions of secret {}", id),
+        }
+    }
+
+    async fn revert(&self, change: Change) -> Result<(), Error> {
+        let reader = self.0;
+        match change {
+            // The secret was created by the change, and is not restored, so
+            // it is deleted immediately.
+            Change::Ensured {
+                id, previous: None, ..
+            } => {
+                reader.delete(id, false).await?;
+            }
+            // Restore the previous contents as a version that follows the
+            // version that the change wrote.
+            Change::Ensured {
+                id,
+                version,
+                previous: Some(previous),
+            } => reader.write(id, version.next(), &previous, true).await?,
+            Change::Deleted { id } => reader.restore(id).await?,
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SecretsController for AwsSecretsController {
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, Error> {
+        mz_secrets::apply_reverting(&Writer(&self.reader), &ops).await
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        self.reader.read(id).await
+    }
+
+    async fn read_version(&self, id: GlobalId, version: SecretVersion) -> Result<Vec<u8>, Error> {
+        self.reader.read_version(id, version).await
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        self.reader.current_version(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        self.reader.list().await
+    }
+}
@@ -0,0 +1,431 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Tests of the AWS secrets controllers against a fake server that implements
+//! the subset of the Secrets Manager and Systems Manager APIs that the
+//! controllers use.
+
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+
+use mz_expr::GlobalId;
+use mz_secrets::{SecretOp, SecretVersion, SecretsController};
+use mz_secrets_aws::{AwsParameterStoreController, AwsSecretsConfig, AwsSecretsController};
+
+/// A secret in the fake Secrets Manager.
+struct Secret {
+    /// The ID and base64-encoded contents of each version, oldest first.
+    versions: Vec<(String, String)>,
+    tags: Value,
+    /// Whether the secret is scheduled for deletion.
+    deleted: bool,
+}
+
+#[derive(Default)]
+struct FakeAws {
+    /// The secrets in Secrets Manager, by name.
+    secrets: BTreeMap<String, Secret>,
+    /// The versions of the parameters in Parameter Store, oldest first, by
+    /// name.
+    parameters: BTreeMap<String, Vec<String>>,
+    /// The tags of the parameters in Parameter Store, by name.
+    parameter_tags: BTreeMap<String, Value>,
+    /// The names of the secrets or parameters to which writes fail.
+    failing_writes: HashSet<String>,
+}
+
+type Error = (&'static str, String);
+
+fn not_found(name: &str) -> Error {
+    ("ResourceNotFoundException", format!("{} not found", name))
+}
+
+fn invalid(message: &str) -> Error {
+    ("InvalidRequestException", message.into())
+}
+
+impl FakeAws {
+    fn secrets_manager(&mut self, op: &str, req: &Value) -> Result<Value, Error> {
+        let id = req["SecretId"].as_str().unwrap_or("").to_string();
+        if matches!(op, "CreateSecret" | "PutSecretValue") {
+            let name = req["Name"].as_str().unwrap_or(&id);
+            if self.failing_writes.contains(name) {
+                return Err(("InvalidParameterException", "write failed".into()));
+            }
+        }
+        match op {
+            "CreateSecret" => {
+                let name = req["Name"].as_str().unwrap().to_string();
+                if self.secrets.contains_key(&name) {
+                    return Err(("ResourceExistsException", format!("{} exists", name)));
+                }
+                let version = (
+                    req["ClientRequestToken"].as_str().unwrap().to_string(),
+                    req["SecretBinary"].as_str().unwrap().to_string(),
+                );
+                self.secrets.insert(
+                    name.clone(),
+                    Secret {
+                        versions: vec![version],
+                        tags: req["Tags"].clone(),
+                        deleted: false,
+                    },
+                );
+                Ok(json!({ "Name": name }))
+            }
+            "PutSecretValue" => {
+                let secret = self.secrets.get_mut(&id).ok_or_else(|| not_found(&id))?;
+                if secret.deleted {
+                    return Err(invalid("secret is scheduled for deletion"));
+                }
+                secret.versions.push((
+                    req["ClientRequestToken"].as_str().unwrap().to_string(),
+                    req["SecretBinary"].as_str().unwrap().to_string(),
+                ));
+                Ok(json!({ "Name": id }))
+            }
+            "GetSecretValue" => {
+                let secret = self.secrets.get(&id).ok_or_else(|| not_found(&id))?;
+                if secret.deleted {
+                    return Err(invalid("secret is scheduled for deletion"));
+                }
+                let version = match req["VersionId"].as_str() {
+                    Some(version_id) => secret.versions.iter().find(|(v, _)| v == version_id),
+                    None => secret.versions.last(),
+                };
+                let (version_id, contents) = version.ok_or_else(|| not_found(&id))?;
+                Ok(json!({ "Name": id, "VersionId": version_id, "SecretBinary": contents }))
+            }
+            "DeleteSecret" => {
+                let secret = self.secrets.get_mut(&id).ok_or_else(|| not_found(&id))?;
+                if req["ForceDeleteWithoutRecovery"] == true {
+                    self.secrets.remove(&id);
+                } else if secret.deleted {
+                    return Err(invalid("secret is already scheduled for deletion"));
+                } else {
+                    assert_eq!(req["RecoveryWindowInDays"], 7);
+                    secret.deleted = true;
+                }
+                Ok(json!({ "Name": id }))
+            }
+            "RestoreSecret" => {
+                let secret = self.secrets.get_mut(&id).ok_or_else(|| not_found(&id))?;
+                secret.deleted = false;
+                Ok(json!({ "Name": id }))
+            }
+            "ListSecrets" => {
+                let prefix = req["Filters"][0]["Values"][0].as_str().unwrap();
+                let names: Vec<_> = self
+                    .secrets
+                    .iter()
+                    .filter(|(name, secret)| name.starts_with(prefix) && !secret.deleted)
+                    .map(|(name, _)| name)
+                    .collect();
+                Ok(page(&names, req, "SecretList"))
+            }
+            _ => Err(("UnknownOperationException", op.into())),
+        }
+    }
+
+    fn parameter_store(&mut self, op: &str, req: &Value) -> Result<Value, Error> {
+        let name = req["Name"].as_str().unwrap_or("").to_string();
+        let not_found = || ("ParameterNotFound", format!("{} not found", name));
+        match op {
+            "PutParameter" => {
+                if self.failing_writes.contains(&name) {
+                    return Err(("ParameterLimitExceeded", "write failed".into()));
+                }
+                assert_eq!(req["Type"], "SecureString");
+                assert_eq!(req["Overwrite"], true);
+                let versions = self.parameters.entry(name).or_default();
+                versions.push(req["Value"].as_str().unwrap().to_string());
+                Ok(json!({ "Version": versions.len(), "Tier": "Standard" }))
+            }
+            "GetParameter" => {
+                let (name, version) = match name.rsplit_once(':') {
+                    Some((name, version)) => (name.to_string(), Some(version.parse().unwrap())),
+                    None => (name.clone(), None),
+                };
+                let versions = self.parameters.get(&name).ok_or_else(not_found)?;
+                let version: usize = version.unwrap_or(versions.len());
+                match versions.get(version.wrapping_sub(1)) {
+                    Some(value) => Ok(json!({ "Parameter": {
+                        "Name": name,
+                        "Type": "SecureString",
+                        "Value": value,
+                        "Version": version,
+                    } })),
+                    None => Err(("ParameterVersionNotFound", name)),
+                }
+            }
+            "DeleteParameter" => {
+                self.parameters.remove(&name).ok_or_else(not_found)?;
+                self.parameter_tags.remove(&name);
+                Ok(json!({}))
+            }
+            "AddTagsToResource" => {
+                assert_eq!(req["ResourceType"], "Parameter");
+                let name = req["ResourceId"].as_str().unwrap().to_string();
+                self.parameter_tags.insert(name, req["Tags"].clone());
+                Ok(json!({}))
+            }
+            "DescribeParameters" => {
+                let filter = &req["ParameterFilters"][0];
+                assert_eq!(filter["Option"], "BeginsWith");
+                let prefix = filter["Values"][0].as_str().unwrap();
+                let names: Vec<_> = self
+                    .parameters
+                    .keys()
+                    .filter(|name| name.starts_with(prefix))
+                    .collect();
+                Ok(page(&names, req, "Parameters"))
+            }
+            _ => Err(("UnknownOperationException", op.into())),
+        }
+    }
+}
+
+/// Returns the page of `names` that follows the request's `NextToken`, with
+/// one name per page so that the controllers must paginate.
+fn page(names: &[&String], req: &Value, field: &str) -> Value {
+    let i: usize = req["NextToken"].as_str().map_or(0, |t| t.parse().unwrap());
+    let entries = names.get(i).map(|name| json!({ "Name": name }));
+    let mut response = json!({});
+    response[field] = Value::Array(entries.into_iter().collect());
+    if i + 1 < names.len() {
+        response["NextToken"] = json!((i + 1).to_string());
+    }
+    response
+}
+
+async fn handle(aws: Arc<Mutex<FakeAws>>, req: Request<Body>) -> Response<Body> {
+    let target = req
+        .headers()
+        .get("X-Amz-Target")
+        .and_then(|target| target.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let mut aws = aws.lock().unwrap();
+    let res = match target.split_once('.') {
+        Some(("secretsmanager", op)) => aws.secrets_manager(op, &body),
+        Some(("AmazonSSM", op)) => aws.parameter_store(op, &body),
+        _ => Err(("UnknownOperationException", target.clone())),
+    };
+    let (status, body) = match res {
+        Ok(body) => (StatusCode::OK, body),
+        Err((kind, message)) => (
+            StatusCode::BAD_REQUEST,
+            json!({ "__type": kind, "message": message }),
+        ),
+    };
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/x-amz-json-1.1")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Starts a fake AWS server, and returns its state and the configuration of
+/// controllers that store secrets under `prefix` in it.
+fn start_server(prefix: &str) -> (Arc<Mutex<FakeAws>>, AwsSecretsConfig) {
+    // The fake server does not check credentials, but the clients need some
+    // to sign their requests.
+    env::set_var("AWS_ACCESS_KEY_ID", "dummy-access-key-id");
+    env::set_var("AWS_SECRET_ACCESS_KEY", "dummy-secret-access-key");
+    env::set_var("AWS_REGION", "us-east-1");
+
+    let aws = Arc::new(Mutex::new(FakeAws::default()));
+    let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+    let endpoint = format!("http://{}", incoming.local_addr());
+    let service = {
+        let aws = Arc::clone(&aws);
+        make_service_fn(move |_conn| {
+            let aws = Arc::clone(&aws);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let aws = Arc::clone(&aws);
+                    async move { Ok::<_, Infallible>(handle(aws, req).await) }
+                }))
+            }
+        })
+    };
+    tokio::spawn(Server::builder(incoming).serve(service));
+    let config = AwsSecretsConfig {
+        prefix: prefix.into(),
+        kms_key_id: None,
+        tags: [("team".to_string(), "storage".to_string())]
+            .into_iter()
+            .collect(),
+        role_arn: None,
+        endpoint: Some(endpoint.parse().unwrap()),
+    };
+    (aws, config)
+}
+
+fn ensure(id: GlobalId, contents: &[u8]) -> SecretOp {
+    SecretOp::Ensure {
+        id,
+        contents: contents.to_vec(),
+    }
+}
+
+fn delete(id: GlobalId, version: Option<SecretVersion>) -> SecretOp {
+    SecretOp::Delete { id, version }
+}
+
+/// Tests the operations that every controller supports.
+async fn test_versions(controller: &dyn SecretsController) -> Result<(), anyhow::Error> {
+    let (a, b, c) = (GlobalId::User(1), GlobalId::User(2), GlobalId::User(3));
+
+    let versions = controller
+        .apply(vec![ensure(a, b"hunter1"), ensure(a, b"\xff\x00hunter2")])
+        .await?;
+    assert_eq!(versions, vec![SecretVersion(1), SecretVersion(2)]);
+    assert_eq!(controller.read(a).await?, b"\xff\x00hunter2");
+    assert_eq!(controller.current_version(a).await?, SecretVersion(2));
+    assert_eq!(
+        controller.read_version(a, SecretVersion(1)).await?,
+        b"hunter1"
+    );
+    assert!(controller.read_version(a, SecretVersion(3)).await.is_err());
+
+    // Neither service can delete individual versions.
+    assert!(controller
+        .apply(vec![delete(a, Some(SecretVersion(1)))])
+        .await
+        .is_err());
+
+    controller
+        .apply(vec![ensure(b, b""), ensure(c, b"hunter3")])
+        .await?;
+    assert_eq!(controller.list().await?, vec![a, b, c]);
+    controller.apply(vec![delete(b, None)]).await?;
+    assert!(controller.read(b).await.is_err());
+    assert_eq!(controller.list().await?, vec![a, c]);
+    // Deleting a secret that does not exist is not an error.
+    controller
+        .apply(vec![delete(GlobalId::User(4), None)])
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_secrets_manager_versions() -> Result<(), anyhow::Error> {
+    let (aws, config) = start_server("materialize/");
+    let controller = AwsSecretsController::new(config).await?;
+    test_versions(&controller).await?;
+
+    let aws = aws.lock().unwrap();
+    let secret = &aws.secrets["materialize/u1"];
+    assert_eq!(secret.tags, json!([{ "Key": "team", "Value": "storage" }]));
+    // Deleted secrets remain recoverable.
+    assert!(aws.secrets["materialize/u2"].deleted);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_secrets_manager_revert() -> Result<(), anyhow::Error> {
+    let (a, b, c, d) = (
+        GlobalId::User(1),
+        GlobalId::User(2),
+        GlobalId::User(3),
+        GlobalId::User(4),
+    );
+    let (aws, config) = start_server("materialize/");
+    let controller = AwsSecretsController::new(config).await?;
+    controller
+        .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+        .await?;
+    aws.lock()
+        .unwrap()
+        .failing_writes
+        .insert("materialize/u3".into());
+
+    // An update is reverted by restoring the previous contents as a new
+    // version, a creation by deleting the secret immediately, and a deletion
+    // by restoring the secret.
+    let res = controller
+        .apply(vec![
+            ensure(a, b"hunter2"),
+            delete(b, None),
+            ensure(d, b"hunter4"),
+            ensure(c, b"hunter3"),
+        ])
+        .await;
+    assert!(res.is_err());
+    assert_eq!(controller.read(a).await?, b"hunter1");
+    assert_eq!(controller.current_version(a).await?, SecretVersion(3));
+    assert_eq!(controller.read(b).await?, b"swordfish");
+    assert_eq!(controller.list().await?, vec![a, b]);
+    assert!(!aws.lock().unwrap().secrets.contains_key("materialize/u4"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parameter_store_versions() -> Result<(), anyhow::Error> {
+    let (aws, config) = start_server("/materialize/");
+    let controller = AwsParameterStoreController::new(config).await?;
+    test_versions(&controller).await?;
+
+    // Only new parameters are tagged.
+    let aws = aws.lock().unwrap();
+    let tags: Vec<_> = aws.parameter_tags.keys().collect();
+    assert_eq!(tags, vec!["/materialize/u1", "/materialize/u3"]);
+    assert_eq!(
+        aws.parameter_tags["/materialize/u1"],
+        json!([{ "Key": "team", "Value": "storage" }])
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parameter_store_revert() -> Result<(), anyhow::Error> {
+    let (a, b, c, d) = (
+        GlobalId::User(1),
+        GlobalId::User(2),
+        GlobalId::User(3),
+        GlobalId::User(4),
+    );
+    let (aws, config) = start_server("/materialize/");
+    let controller = AwsParameterStoreController::new(config).await?;
+    controller
+        .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+        .await?;
+    aws.lock()
+        .unwrap()
+        .failing_writes
+        .insert("/materialize/u3".into());
+
+    // An update is reverted by restoring the previous version's contents as
+    // a new version, a creation by deleting the parameter, and a deletion by
+    // recreating the parameter.
+    let res = controller
+        .apply(vec![
+            ensure(a, b"hunter2"),
+            delete(b, None),
+            ensure(d, b"hunter4"),
+            ensure(c, b"hunter3"),
+        ])
+        .await;
+    assert!(res.is_err());
+    assert_eq!(controller.read(a).await?, b"hunter1");
+    assert_eq!(controller.current_version(a).await?, SecretVersion(3));
+    assert_eq!(controller.read(b).await?, b"swordfish");
+    assert_eq!(controller.list().await?, vec![a, b]);
+    Ok(())
+}
@@ -6,42 +6,1005 @@
 // As of the Change Date specified in that file, in accordance with
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
-use anyhow::Error;
-use mz_secrets::{SecretOp, SecretsController};
+use anyhow::{anyhow, bail, Context, Error};
+use async_trait::async_trait;
+use mz_expr::GlobalId;
+use mz_secrets::{SecretOp, SecretVersion, SecretsController};
+use nix::unistd::{self, Gid, Uid};
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::fs::{DirBuilder, File, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+/// The number of versions of each secret that a
+/// [`FilesystemSecretsController`] retains by default.
+pub const DEFAULT_MAX_VERSIONS: usize = 2;
+
+/// The mode of the directory that stores secrets.
+const DIR_MODE: u32 = 0o700;
+/// The mode of the files that store secrets.
+const FILE_MODE: u32 = 0o600;
+
+/// The prefix of the files that store encrypted secrets.
+///
+/// Files without this prefix store secrets in plaintext, as written before an
+/// encryption key was configured.
+const ENCRYPTED_MAGIC: &[u8] = b"\0MZSEC\x01";
+/// The length of an [`EncryptionKey`], in bytes.
+const KEY_LEN: usize = 32;
+/// The length of the nonce that prefixes each encrypted secret, in bytes.
+const NONCE_LEN: usize = 12;
+/// The length of the authentication tag that suffixes each encrypted secret,
+/// in bytes.
+const TAG_LEN: usize = 16;
+
+/// A 256-bit key with which to encrypt secrets at rest.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl FromStr for EncryptionKey {
+    type Err = Error;
+
+    /// Parses a key from its hex encoding.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut key = [0; KEY_LEN];
+        hex::decode_to_slice(s.trim(), &mut key)
+            .map_err(|_| anyhow!("encryption key must be {} hex-encoded bytes", KEY_LEN))?;
+        Ok(EncryptionKey(key))
+    }
+}
+
+impl EncryptionKey {
+    /// Constructs a key from its raw bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let key = bytes
+            .try_into()
+            .map_err(|_| anyhow!("encryption key must be {} bytes", KEY_LEN))?;
+        Ok(EncryptionKey(key))
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("EncryptionKey(<redacted>)")
+    }
+}
+
+/// Stores secrets in files in a directory, one file per version of a secret.
+///
+/// Version `v` of the secret `id` is stored in the file named `<id>.<v>`. The
+/// controller retains the newest versions of each secret, up to a configured
+/// maximum, and removes older versions when it creates new ones. Secrets that
+/// were stored before secrets were versioned are stored in the file named
+/// `<id>`, and are version 0.
+///
+/// If an encryption key is configured, the contents of each secret are
+/// encrypted with AES-256-GCM under a random nonce that is stored alongside
+/// them. The name of the file is authenticated too, so that the file for one
+/// secret or version cannot be substituted for another's.
+///
+/// The directory is only accessible to its owner, and each file only readable
+/// and writable by its owner, regardless of the process's umask. The
+/// directory and files can optionally be owned by a configured user and
+/// group, rather than by the process's.
 pub struct FilesystemSecretsController {
     secrets_storage_path: PathBuf,
+    encryption_key: Option<EncryptionKey>,
+    max_versions: usize,
+    uid: Option<Uid>,
+    gid: Option<Gid>,
 }
 
 impl FilesystemSecretsController {
-    pub fn new(secrets_storage_path: PathBuf) -> Self {
+    pub fn new(secrets_storage_path: PathBuf, encryption_key: Option<EncryptionKey>) -> Self {
         Self {
             secrets_storage_path,
+            encryption_key,
+            max_versions: DEFAULT_MAX_VERSIONS,
+            uid: None,
+            gid: None,
         }
     }
-}
 
-impl SecretsController for FilesystemSecretsController {
-    fn apply(&mut self, ops: Vec<SecretOp>) -> Result<(), Error> {
-        assert_eq!(ops.len(), 1);
-        for op in ops.iter() {
-            match op {
-                SecretOp::Ensure { id, contents } => {
-                    // create will override an existing file
-                    let mut file = File::create(self.secrets_storage_path.join(format!("{}", id)))?;
-                    file.write_all(contents)?;
-                    file.sync_all()?;
+    /// Sets the user and group that own the secrets directory and the files
+    /// within it.
+    ///
+    /// An owner that is `None` is left as the process's. Changing the owner
+    /// typically requires the process to be privileged.
+    pub fn with_owner(mut self, uid: Option<u32>, gid: Option<u32>) -> Self {
+        self.uid = uid.map(Uid::from_raw);
+        self.gid = gid.map(Gid::from_raw);
+        self
+    }
+
+    /// Prepares the secrets directory for use.
+    ///
+    /// The directory is created, with mode 0700, if it does not exist, and is
+    /// restricted to mode 0700 if it does. Files stored before their
+    /// permissions were restricted are restricted to mode 0600, and the
+    /// directory and files are given the configured owner. Changes that a
+    /// crash interrupted are undone, and the scratch files that they left
+    /// behind are removed.
+    pub fn initialize(&self) -> Result<(), Error> {
+        let path = &self.secrets_storage_path;
+        DirBuilder::new()
+            .recursive(true)
+            .mode(DIR_MODE)
+            .create(path)
+            .with_context(|| format!("creating secrets directory {}", path.display()))?;
+        fs::set_permissions(path, Permissions::from_mode(DIR_MODE))
+            .with_context(|| format!("restricting secrets directory {}", path.display()))?;
+        self.chown(path)?;
+        self.recover()?;
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                let path = entry.path();
+                fs::set_permissions(&path, Permissions::from_mode(FILE_MODE))?;
+                self.chown(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the number of versions of each secret that the controller
+    /// retains, including the current version.
+    ///
+    /// Panics if `max_versions` is zero.
+    pub fn with_max_versions(mut self, max_versions: usize) -> Self {
+        assert!(max_versions > 0, "must retain at least one version");
+        self.max_versions = max_versions;
+        self
+    }
+
+    /// Encrypts the versions of secrets that were stored in plaintext before
+    /// an encryption key was configured, and returns how many there were.
+    ///
+    /// Each version is encrypted in place, atomically, so that the process
+    /// can safely be repeated if it is interrupted.
+    ///
+    /// Does nothing if no encryption key is configured.
+    pub fn encrypt_plaintext_secrets(&mut self) -> Result<usize, Error> {
+        if self.encryption_key.is_none() {
+            return Ok(0);
+        }
+        let mut count = 0;
+        for id in self.ids()? {
+            for version in self.versions(id)? {
+                let path = self.secret_path(id, version);
+                let contents = fs::read(&path)?;
+                if contents.starts_with(ENCRYPTED_MAGIC) {
+                    continue;
+                }
+                let scratch = self.scratch_path(id, count, "plaintext");
+                let res = self.seal(id, version, &contents).and_then(|sealed| {
+                    self.write_synced(&scratch, &sealed)?;
+                    fs::rename(&scratch, &path)?;
+                    Ok(())
+                });
+                if let Err(e) = res {
+                    let _ = fs::remove_file(&scratch);
+                    return Err(e);
+                }
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.sync_dir()?;
+        }
+        Ok(count)
+    }
+
+    /// Returns the IDs of the secrets that are stored, in ascending order.
+    fn ids(&self) -> Result<Vec<GlobalId>, Error> {
+        let mut ids = BTreeSet::new();
+        for entry in fs::read_dir(&self.secrets_storage_path)? {
+            // Ignore files that are not named after a version of a secret.
+            if let Some((id, _)) = entry?.file_name().to_str().and_then(parse_file_name) {
+                ids.insert(id);
+            }
+        }
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Returns the versions of the secret `id` that are stored, in ascending
+    /// order.
+    fn versions(&self, id: GlobalId) -> Result<Vec<SecretVersion>, Error> {
+        let mut versions = vec![];
+        for entry in fs::read_dir(&self.secrets_storage_path)? {
+            match entry?.file_name().to_str().and_then(parse_file_name) {
+                Some((file_id, version)) if file_id == id => versions.push(version),
+                _ => (),
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Returns the contents of the file that stores version `version` of the
+    /// secret `id` with the given `contents`.
+    fn seal(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+        contents: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let key = match &self.encryption_key {
+            None => return Ok(contents.to_vec()),
+            Some(key) => key,
+        };
+        let mut nonce = [0; NONCE_LEN];
+        rand_bytes(&mut nonce)?;
+        let mut tag = [0; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key.0,
+            Some(&nonce),
+            file_name(id, version).as_bytes(),
+            contents,
+            &mut tag,
+        )?;
+        let mut sealed =
+            Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len() + TAG_LEN);
+        sealed.extend_from_slice(ENCRYPTED_MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed.extend_from_slice(&tag);
+        Ok(sealed)
+    }
+
+    /// Returns the contents of version `version` of the secret `id` from the
+    /// contents of the file that stores it.
+    fn open(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+        stored: Vec<u8>,
+    ) -> Result<Vec<u8>, Error> {
+        if !stored.starts_with(ENCRYPTED_MAGIC) {
+            return Ok(stored);
+        }
+        let key = match &self.encryption_key {
+            None => bail!(
+                "secret {} is encrypted, but no encryption key is configured",
+                id
+            ),
+            Some(key) => key,
+        };
+        let sealed = &stored[ENCRYPTED_MAGIC.len()..];
+        if sealed.len() < NONCE_LEN + TAG_LEN {
+            bail!("encrypted secret {} is truncated", id);
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+        decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key.0,
+            Some(nonce),
+            file_name(id, version).as_bytes(),
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| {
+            anyhow!(
+                "decrypting secret {}: wrong encryption key or corrupted contents",
+                id
+            )
+        })
+    }
+
+    /// Returns the path of the file that stores version `version` of the
+    /// secret `id`.
+    fn secret_path(&self, id: GlobalId, version: SecretVersion) -> PathBuf {
+        self.secrets_storage_path.join(file_name(id, version))
+    }
+
+    /// Returns the path of a scratch file for the `i`th change of a batch
+    /// that affects the secret `id`.
+    ///
+    /// Scratch files are hidden, and their names do not parse as secret IDs,
+    /// so they are never mistaken for secrets by [`SecretsController::list`].
+    fn scratch_path(&self, id: GlobalId, i: usize, kind: &str) -> PathBuf {
+        self.secrets_storage_path
+            .join(format!(".{}.{}.{}", id, i, kind))
+    }
+
+    /// Applies `changes` in order, recording how to undo each change in
+    /// `undo`.
+    ///
+    /// The contents of each placed version must have been staged in the file
+    /// at `staged[i]`, where `i` is the index of the change.
+    fn commit(
+        &self,
+        changes: &[Change],
+        staged: &[Option<PathBuf>],
+        undo: &mut Vec<Undo>,
+    ) -> Result<(), Error> {
+        for (i, change) in changes.iter().enumerate() {
+            let path = self.secret_path(change.id, change.version);
+            match &staged[i] {
+                Some(staged_path) => {
+                    undo.push(Undo {
+                        path: path.clone(),
+                        backup: None,
+                        placed: false,
+                    });
+                    fs::rename(staged_path, &path)?;
+                    undo.last_mut().expect("just pushed").placed = true;
                 }
-                SecretOp::Delete { id } => {
-                    fs::remove_file(self.secrets_storage_path.join(format!("{}", id)))?;
+                None => {
+                    // Move the version out of the way rather than removing
+                    // it, so that it can be restored.
+                    let backup = self.scratch_path(change.id, i, "backup");
+                    fs::rename(&path, &backup)?;
+                    undo.push(Undo {
+                        path,
+                        backup: Some(backup),
+                        placed: false,
+                    });
                 }
             }
         }
+        Ok(())
+    }
+
+    /// Plans `ops` as a sequence of changes, and returns the changes along
+    /// with the versions that the `Ensure` operations create.
+    fn plan<'a>(
+        &self,
+        ops: &'a [SecretOp],
+    ) -> Result<(Vec<Change<'a>>, Vec<SecretVersion>), Error> {
+        // The versions of each affected secret as of the change being
+        // planned.
+        let mut planned: HashMap<GlobalId, Vec<SecretVersion>> = HashMap::new();
+        let mut changes = vec![];
+        let mut ensured = vec![];
+        for op in ops {
+            let id = op.id();
+            let versions = match planned.entry(id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.versions(id)?),
+            };
+            let removed = match op {
+                SecretOp::Ensure { contents, .. } => {
+                    let version = match versions.last() {
+                        Some(current) => current.next(),
+                        None => SecretVersion(1),
+                    };
+                    versions.push(version);
+                    ensured.push(version);
+                    changes.push(Change {
+                        id,
+                        version,
+                        contents: Some(contents),
+                    });
+                    let excess = versions.len().saturating_sub(self.max_versions);
+                    versions.drain(..excess).collect()
+                }
+                SecretOp::Delete { version: None, .. } => {
+                    if versions.is_empty() {
+                        bail!("secret {} does not exist", id);
+                    }
+                    mem::take(versions)
+                }
+                SecretOp::Delete {
+                    version: Some(version),
+                    ..
+                } => match versions.iter().position(|v| v == version) {
+                    None => bail!("version {} of secret {} does not exist", version, id),
+                    Some(i) if i == versions.len() - 1 => {
+                        bail!("cannot delete the current version of secret {}", id)
+                    }
+                    Some(i) => vec![versions.remove(i)],
+                },
+            };
+            changes.extend(removed.into_iter().map(|version| Change {
+                id,
+                version,
+                contents: None,
+            }));
+        }
+        Ok((changes, ensured))
+    }
+
+    /// Writes the contents of the versions that `changes` place to scratch
+    /// files, and returns the path of the scratch file for each change that
+    /// places a version.
+    fn stage(&self, changes: &[Change]) -> Result<Vec<Option<PathBuf>>, Error> {
+        let mut staged = Vec::with_capacity(changes.len());
+        for (i, change) in changes.iter().enumerate() {
+            match change.contents {
+                Some(contents) => {
+                    let path = self.scratch_path(change.id, i, "staged");
+                    // Record the path before writing, so that a partially
+                    // written file is cleaned up too.
+                    staged.push(Some(path.clone()));
+                    let res = self
+                        .seal(change.id, change.version, contents)
+                        .and_then(|sealed| self.write_synced(&path, &sealed));
+                    if let Err(e) = res {
+                        for path in staged.iter().flatten() {
+                            let _ = fs::remove_file(path);
+                        }
+                        return Err(e);
+                    }
+                }
+                None => staged.push(None),
+            }
+        }
+        Ok(staged)
+    }
+
+    /// Commits `changes`, whose contents are staged in `staged`, such that
+    /// either all or none of them take effect, even if the process crashes.
+    ///
+    /// Before any change is made, the renames that make up the changes are
+    /// recorded in a journal. The journal is removed once every change has
+    /// durably taken effect, so a journal that survives a crash identifies
+    /// changes that [`FilesystemSecretsController::recover`] must undo.
+    fn commit_journaled(
+        &self,
+        changes: &[Change],
+        staged: &[Option<PathBuf>],
+    ) -> Result<(), Error> {
+        let mut undo = vec![];
+        let res = self.write_journal(changes, staged).and_then(|()| {
+            self.commit(changes, staged, &mut undo)?;
+            self.sync_dir()?;
+            fs::remove_file(self.journal_path())?;
+            self.sync_dir()
+        });
+        if let Err(e) = res {
+            for Undo {
+                path,
+                backup,
+                placed,
+            } in undo.into_iter().rev()
+            {
+                if placed {
+                    let _ = fs::remove_file(&path);
+                }
+                if let Some(backup) = backup {
+                    let _ = fs::rename(&backup, &path);
+                }
+            }
+            for path in staged.iter().flatten() {
+                let _ = fs::remove_file(path);
+            }
+            let _ = self.sync_dir();
+            // Only remove the journal once the undone changes are durable,
+            // so that recovery finishes undoing them if the process crashes
+            // before then.
+            let _ = fs::remove_file(self.journal_path());
+            let _ = self.sync_dir();
+            return Err(e);
+        }
+
+        // Every change has durably taken effect, so the removed versions are
+        // no longer needed. Failing to remove them does not affect the
+        // outcome, as they are never mistaken for secrets, and recovery
+        // removes them later.
+        for Undo { backup, .. } in undo {
+            if let Some(backup) = backup {
+                let _ = fs::remove_file(&backup);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the journal of the changes being committed.
+    fn journal_path(&self) -> PathBuf {
+        self.secrets_storage_path.join(".journal")
+    }
+
+    /// Durably records the renames that committing `changes` will perform,
+    /// one per line, in the order in which they will be performed.
+    ///
+    /// A line `place <staged> <name>` records that the staged file `staged`
+    /// will be renamed to the file `name` that stores a new version, and a
+    /// line `remove <name> <backup>` that the file `name` will be set aside
+    /// as `backup`.
+    fn write_journal(&self, changes: &[Change], staged: &[Option<PathBuf>]) -> Result<(), Error> {
+        let mut journal = String::new();
+        for (i, change) in changes.iter().enumerate() {
+            let name = file_name(change.id, change.version);
+            let line = match &staged[i] {
+                Some(staged_path) => format!("place\t{}\t{}\n", scratch_name(staged_path), name),
+                None => {
+                    let backup = self.scratch_path(change.id, i, "backup");
+                    format!("remove\t{}\t{}\n", name, scratch_name(&backup))
+                }
+            };
+            journal.push_str(&line);
+        }
+        // Write the journal under a scratch name first, so that a partially
+        // written journal is never mistaken for a complete one.
+        let scratch = self.secrets_storage_path.join(".journal.staged");
+        let res = self
+            .write_synced(&scratch, journal.as_bytes())
+            .and_then(|()| {
+                fs::rename(&scratch, self.journal_path())?;
+                self.sync_dir()
+            });
+        if res.is_err() {
+            let _ = fs::remove_file(&scratch);
+        }
+        res
+    }
+
+    /// Restores the secrets directory to a consistent state after a crash.
+    ///
+    /// If the process crashed while committing changes, the changes recorded
+    /// in the journal that took effect are undone in reverse order. Scratch
+    /// files left behind by interrupted operations are then removed.
+    fn recover(&self) -> Result<(), Error> {
+        let dir = &self.secrets_storage_path;
+        let journal_path = self.journal_path();
+        let journal = match fs::read_to_string(&journal_path) {
+            Ok(journal) => Some(journal),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(journal) = journal {
+            let mut entries = vec![];
+            for line in journal.lines() {
+                let entry = match line.split('\t').collect::<Vec<_>>()[..] {
+                    ["place", staged, name] => (true, dir.join(name), dir.join(staged)),
+                    ["remove", name, backup] => (false, dir.join(name), dir.join(backup)),
+                    _ => bail!(
+                        "corrupt secrets journal {}: {:?}",
+                        journal_path.display(),
+                        line
+                    ),
+                };
+                entries.push(entry);
+            }
+            for (placed, path, scratch) in entries.into_iter().rev() {
+                if placed {
+                    // The version was placed if its staged file is gone.
+                    if !scratch.exists() && path.exists() {
+                        fs::remove_file(&path)?;
+                    }
+                } else if scratch.exists() {
+                    // The version was set aside, so restore it.
+                    fs::rename(&scratch, &path)?;
+                }
+            }
+            self.sync_dir()?;
+            fs::remove_file(&journal_path)?;
+        }
+        let mut removed = false;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let is_scratch = entry
+                .file_name()
+                .to_str()
+                .map_or(false, |name| name.starts_with('.'));
+            if is_scratch && entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+                removed = true;
+            }
+        }
+        if removed {
+            self.sync_dir()?;
+        }
+        Ok(())
+    }
+
+    /// Writes `contents` to a new file at `path` that only its owner can
+    /// access, and syncs the file.
+    fn write_synced(&self, path: &Path, contents: &[u8]) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(FILE_MODE)
+            .open(path)?;
+        self.chown(path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Gives `path` the configured owner, if any.
+    fn chown(&self, path: &Path) -> Result<(), Error> {
+        if self.uid.is_some() || self.gid.is_some() {
+            unistd::chown(path, self.uid, self.gid)
+                .with_context(|| format!("changing the owner of {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Syncs the directory that stores the secrets, which makes renames of
+    /// the files within it durable.
+    fn sync_dir(&self) -> Result<(), Error> {
+        File::open(&self.secrets_storage_path)?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// A change to the version `version` of the secret `id`, which either places
+/// a new version with the given contents or removes an existing version.
+struct Change<'a> {
+    id: GlobalId,
+    version: SecretVersion,
+    contents: Option<&'a [u8]>,
+}
+
+/// How to undo a change to the file that stores a version of a secret.
+struct Undo {
+    /// The path of the file.
+    path: PathBuf,
+    /// Where the file was moved, if it was removed.
+    backup: Option<PathBuf>,
+    /// Whether a new file was moved into place.
+    placed: bool,
+}
+
+/// Returns the name of the file that stores version `version` of the secret
+/// `id`.
+fn file_name(id: GlobalId, version: SecretVersion) -> String {
+    if version == SecretVersion(0) {
+        id.to_string()
+    } else {
+        format!("{}.{}", id, version)
+    }
+}
+
+/// Returns the name of the scratch file at `path`.
+fn scratch_name(path: &Path) -> String {
+    path.file_name()
+        .expect("scratch paths name a file")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Parses the ID and version of a secret from the name of the file that
+/// stores it.
+///
+/// Scratch files are hidden, so their names never parse.
+fn parse_file_name(name: &str) -> Option<(GlobalId, SecretVersion)> {
+    match name.split_once('.') {
+        Some((id, version)) => Some((id.parse().ok()?, SecretVersion(version.parse().ok()?))),
+        None => Some((name.parse().ok()?, SecretVersion(0))),
+    }
+}
+
+#[async_trait]
+impl SecretsController for FilesystemSecretsController {
+    /// Applies `ops` to the secrets directory, such that either all or none
+    /// of them take effect.
+    ///
+    /// The operations are first planned as a sequence of changes, each of
+    /// which places or removes one version of a secret. Ensuring a secret
+    /// places its next version, and removes its oldest versions beyond the
+    /// maximum number that the controller retains.
+    ///
+    /// The contents of placed versions are first encrypted, if an encryption
+    /// key is configured, and written and synced to scratch files. A journal
+    /// of the changes is then made durable, the scratch files are renamed
+    /// into place, and removed versions are set aside until every change has
+    /// succeeded. If any change fails, the changes made so far are undone in
+    /// reverse order. If the process crashes instead, the journal lets
+    /// [`FilesystemSecretsController::initialize`] undo them when it next
+    /// starts.
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, Error> {
+        let (changes, ensured) = self.plan(&ops)?;
+        let staged = self.stage(&changes)?;
+        self.commit_journaled(&changes, &staged)?;
+        Ok(ensured)
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        let version = self.current_version(id).await?;
+        self.read_version(id, version).await
+    }
+
+    async fn read_version(&self, id: GlobalId, version: SecretVersion) -> Result<Vec<u8>, Error> {
+        match fs::read(self.secret_path(id, version)) {
+            Ok(stored) => self.open(id, version, stored),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                bail!("version {} of secret {} does not exist", version, id)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        match self.versions(id)?.last() {
+            Some(version) => Ok(*version),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        self.ids()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> EncryptionKey {
+        EncryptionKey([byte; KEY_LEN])
+    }
+
+    fn ensure(id: GlobalId, contents: &[u8]) -> SecretOp {
+        SecretOp::Ensure {
+            id,
+            contents: contents.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_roundtrip() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let id = GlobalId::User(1);
+        let controller = FilesystemSecretsController::new(dir.path().into(), Some(key(1)));
+        controller.apply(vec![ensure(id, b"hunter2")]).await?;
+
+        assert_eq!(controller.read(id).await?, b"hunter2");
+        let stored = fs::read(dir.path().join("u1.1"))?;
+        assert!(stored.starts_with(ENCRYPTED_MAGIC));
+        assert!(!stored.windows(7).any(|w| w == b"hunter2"));
+
+        let wrong_key = FilesystemSecretsController::new(dir.path().into(), Some(key(2)));
+        assert!(wrong_key.read(id).await.is_err());
+        let no_key = FilesystemSecretsController::new(dir.path().into(), None);
+        assert!(no_key.read(id).await.is_err());
+
+        // The file for one secret must not be readable as another's, nor the
+        // file for one version as another's.
+        let other = GlobalId::User(2);
+        fs::copy(dir.path().join("u1.1"), dir.path().join("u2.1"))?;
+        assert!(controller.read(other).await.is_err());
+        fs::copy(dir.path().join("u1.1"), dir.path().join("u1.2"))?;
+        assert!(controller.read(id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_plaintext_secrets() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let id = GlobalId::User(1);
+        let plaintext = FilesystemSecretsController::new(dir.path().into(), None);
+        plaintext
+            .apply(vec![ensure(id, b"hunter1"), ensure(id, b"hunter2")])
+            .await?;
+        assert_eq!(fs::read(dir.path().join("u1.2"))?, b"hunter2");
+
+        let mut controller = FilesystemSecretsController::new(dir.path().into(), Some(key(1)));
+        assert_eq!(controller.read(id).await?, b"hunter2");
+        assert_eq!(controller.encrypt_plaintext_secrets()?, 2);
+        assert_eq!(controller.encrypt_plaintext_secrets()?, 0);
+        assert!(fs::read(dir.path().join("u1.1"))?.starts_with(ENCRYPTED_MAGIC));
+        assert!(fs::read(dir.path().join("u1.2"))?.starts_with(ENCRYPTED_MAGIC));
+        assert_eq!(controller.read(id).await?, b"hunter2");
+        assert_eq!(
+            controller.read_version(id, SecretVersion(1)).await?,
+            b"hunter1"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_versions() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let id = GlobalId::User(1);
+        let controller =
+            FilesystemSecretsController::new(dir.path().into(), None).with_max_versions(3);
+
+        // Secrets stored before secrets were versioned are version 0.
+        fs::write(dir.path().join("u1"), b"hunter0")?;
+        assert_eq!(controller.current_version(id).await?, SecretVersion(0));
+        assert_eq!(controller.read(id).await?, b"hunter0");
+
+        let versions = controller
+            .apply(vec![ensure(id, b"hunter1"), ensure(id, b"hunter2")])
+            .await?;
+        assert_eq!(versions, vec![SecretVersion(1), SecretVersion(2)]);
+        assert_eq!(
+            controller.read_version(id, SecretVersion(0)).await?,
+            b"hunter0"
+        );
+
+        // Only the newest versions are retained.
+        assert_eq!(
+            controller.apply(vec![ensure(id, b"hunter3")]).await?,
+            vec![SecretVersion(3)]
+        );
+        assert!(controller.read_version(id, SecretVersion(0)).await.is_err());
+        assert_eq!(controller.versions(id)?, [1, 2, 3].map(SecretVersion));
+        assert_eq!(controller.read(id).await?, b"hunter3");
+
+        // Deleting a version other than the current one keeps the others.
+        assert!(controller
+            .apply(vec![SecretOp::Delete {
+                id,
+                version: Some(SecretVersion(3)),
+            }])
+            .await
+            .is_err());
+        controller
+            .apply(vec![SecretOp::Delete {
+                id,
+                version: Some(SecretVersion(1)),
+            }])
+            .await?;
+        assert_eq!(controller.versions(id)?, [2, 3].map(SecretVersion));
+        assert_eq!(controller.list().await?, vec![id]);
+
+        controller
+            .apply(vec![SecretOp::Delete { id, version: None }])
+            .await?;
+        assert!(controller.read(id).await.is_err());
+        assert!(controller.list().await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_atomically() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let controller = FilesystemSecretsController::new(dir.path().into(), None);
+        controller.apply(vec![ensure(a, b"hunter1")]).await?;
+
+        // Deleting a secret that does not exist fails the whole batch.
+        let res = controller
+            .apply(vec![
+                ensure(a, b"hunter2"),
+                SecretOp::Delete {
+                    id: b,
+                    version: None,
+                },
+            ])
+            .await;
+        assert!(res.is_err());
+        assert_eq!(controller.versions(a)?, [SecretVersion(1)]);
+        assert_eq!(controller.read(a).await?, b"hunter1");
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    fn files(dir: &Path) -> Result<Vec<String>, Error> {
+        let mut files = vec![];
+        for entry in fs::read_dir(dir)? {
+            files.push(entry?.file_name().to_string_lossy().into_owned());
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    #[tokio::test]
+    async fn test_rollback() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let controller =
+            FilesystemSecretsController::new(dir.path().into(), None).with_max_versions(1);
+        controller
+            .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+            .await?;
+
+        // A change that fails partway through the commit undoes the changes
+        // that preceded it.
+        let ops = vec![
+            ensure(a, b"hunter2"),
+            SecretOp::Delete {
+                id: b,
+                version: None,
+            },
+        ];
+        let (changes, _) = controller.plan(&ops)?;
+        let staged = controller.stage(&changes)?;
+        fs::remove_file(dir.path().join("u2.1"))?;
+        assert!(controller.commit_journaled(&changes, &staged).is_err());
+        assert_eq!(controller.versions(a)?, [SecretVersion(1)]);
+        assert_eq!(controller.read(a).await?, b"hunter1");
+        assert_eq!(files(dir.path())?, ["u1.1"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recover() -> Result<(), Error> {
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let ops = vec![
+            ensure(a, b"hunter2"),
+            SecretOp::Delete {
+                id: b,
+                version: None,
+            },
+        ];
+        // Crash after each prefix of the renames that make up the changes,
+        // which place `u1.2` and remove `u1.1` and `u2.1`.
+        for renamed in 0..=3 {
+            let dir = tempfile::tempdir()?;
+            let controller =
+                FilesystemSecretsController::new(dir.path().into(), None).with_max_versions(1);
+            controller.initialize()?;
+            controller
+                .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+                .await?;
+
+            let (changes, _) = controller.plan(&ops)?;
+            assert_eq!(changes.len(), 3);
+            let staged = controller.stage(&changes)?;
+            controller.write_journal(&changes, &staged)?;
+            controller.commit(&changes[..renamed], &staged, &mut vec![])?;
+
+            // The changes did not all durably take effect, so restarting
+            // undoes those that did.
+            let controller =
+                FilesystemSecretsController::new(dir.path().into(), None).with_max_versions(1);
+            controller.initialize()?;
+            assert_eq!(controller.read(a).await?, b"hunter1");
+            assert_eq!(controller.read(b).await?, b"swordfish");
+            assert_eq!(files(dir.path())?, ["u1.1", "u2.1"]);
+        }
+
+        // Crash after the journal is removed, but before the removed versions
+        // are cleaned up. The changes took effect, so restarting keeps them.
+        let dir = tempfile::tempdir()?;
+        let controller =
+            FilesystemSecretsController::new(dir.path().into(), None).with_max_versions(1);
+        controller
+            .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+            .await?;
+        let (changes, _) = controller.plan(&ops)?;
+        let staged = controller.stage(&changes)?;
+        controller.write_journal(&changes, &staged)?;
+        controller.commit(&changes, &staged, &mut vec![])?;
+        fs::remove_file(controller.journal_path())?;
+        assert_eq!(files(dir.path())?.len(), 3);
+        controller.initialize()?;
+        assert_eq!(controller.read(a).await?, b"hunter2");
+        assert!(controller.read(b).await.is_err());
+        assert_eq!(files(dir.path())?, ["u1.2"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_permissions() -> Result<(), Error> {
+        let mode = |path: &Path| -> Result<u32, Error> {
+            Ok(fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("secrets");
+        let controller = FilesystemSecretsController::new(path.clone(), None);
+        controller.initialize()?;
+        assert_eq!(mode(&path)?, DIR_MODE);
+
+        let id = GlobalId::User(1);
+        controller.apply(vec![ensure(id, b"hunter2")]).await?;
+        assert_eq!(mode(&path.join("u1.1"))?, FILE_MODE);
+
+        // Secrets stored with looser permissions are restricted.
+        fs::write(path.join("u2"), b"hunter0")?;
+        fs::set_permissions(path.join("u2"), Permissions::from_mode(0o644))?;
+        controller.initialize()?;
+        assert_eq!(mode(&path.join("u2"))?, FILE_MODE);
+
+        // A directory that other users can access is restricted.
+        fs::set_permissions(&path, Permissions::from_mode(0o755))?;
+        controller.initialize()?;
+        assert_eq!(mode(&path)?, DIR_MODE);
+        Ok(())
+    }
 
-        return Ok(());
+    #[test]
+    fn test_parse_encryption_key() {
+        assert!("00".repeat(KEY_LEN).parse::<EncryptionKey>().is_ok());
+        assert!("00".repeat(KEY_LEN - 1).parse::<EncryptionKey>().is_err());
+        assert!("zz".repeat(KEY_LEN).parse::<EncryptionKey>().is_err());
+        assert!(EncryptionKey::from_bytes(&[0; KEY_LEN]).is_ok());
+        assert!(EncryptionKey::from_bytes(&[0; KEY_LEN + 1]).is_err());
     }
 }
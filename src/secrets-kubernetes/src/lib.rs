@@ -8,9 +8,11 @@
 // by the Apache License, Version 2.0.
 
 use anyhow::{bail, Error};
+use async_trait::async_trait;
 use kube::config::KubeConfigOptions;
 use kube::{Client, Config};
-use mz_secrets::{SecretOp, SecretsController};
+use mz_expr::GlobalId;
+use mz_secrets::{SecretOp, SecretVersion, SecretsController};
 
 pub struct KubernetesSecretsController {
     _kube_client: Client,
@@ -40,8 +42,36 @@ impl KubernetesSecretsController {
     }
 }
 
+#[async_trait]
 impl SecretsController for KubernetesSecretsController {
-    fn apply(&mut self, _ops: Vec<SecretOp>) -> Result<(), Error> {
-        return Ok(());
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, Error> {
+        // Secrets are not stored yet, so every ensured secret is new.
+        Ok(ops
+            .iter()
+            .filter(|op| matches!(op, SecretOp::Ensure { .. }))
+            .map(|_| SecretVersion(1))
+            .collect())
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        bail!("secret {id} does not exist")
+    }
+
+    async fn read_version(&self, id: GlobalId, _version: SecretVersion) -> Result<Vec<u8>, Error> {
+        bail!("secret {id} does not exist")
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        bail!("secret {id} does not exist")
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        Ok(vec![])
+    }
+
+    fn can_list(&self) -> bool {
+        // Secrets are not stored yet, so the empty list above says nothing
+        // about which secrets exist.
+        false
     }
 }
@@ -0,0 +1,502 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Secrets storage in the KV version 2 secrets engine of [HashiCorp Vault].
+//!
+//! Each secret is stored at `<mount>/<path prefix>/<id>`, with its binary
+//! contents base64-encoded in the `contents` field. The versions of secrets
+//! are the versions that Vault assigns, and Vault retains as many versions as
+//! the `max_versions` setting of the mount allows.
+//!
+//! [HashiCorp Vault]: https://www.vaultproject.io
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Context, Error};
+use async_trait::async_trait;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+
+use mz_expr::GlobalId;
+use mz_secrets::{RevertibleStorage, SecretOp, SecretVersion, SecretsController};
+
+/// The configuration of a [`VaultSecretsController`] or
+/// [`VaultSecretsReader`].
+#[derive(Debug, Clone)]
+pub struct VaultConfig {
+    /// The address of the Vault server, e.g., `https://vault.example.com:8200`.
+    pub address: String,
+    /// The path at which the KV version 2 secrets engine is mounted.
+    pub mount: String,
+    /// The path within the mount under which to store secrets.
+    pub path_prefix: String,
+    /// How to authenticate with Vault.
+    pub auth: VaultAuth,
+}
+
+/// A method of authenticating with Vault.
+#[derive(Clone)]
+pub enum VaultAuth {
+    /// Authenticate with a Vault token.
+    Token(String),
+    /// Authenticate with the token of the Kubernetes service account that
+    /// the process runs as.
+    Kubernetes {
+        /// The path at which the Kubernetes auth method is mounted.
+        mount: String,
+        /// The Vault role to log in as.
+        role: String,
+        /// The path of the file containing the service account token.
+        jwt_path: PathBuf,
+    },
+}
+
+impl fmt::Debug for VaultAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VaultAuth::Token(_) => f.write_str("Token(<redacted>)"),
+            VaultAuth::Kubernetes {
+                mount,
+                role,
+                jwt_path,
+            } => f
+                .debug_struct("Kubernetes")
+                .field("mount", mount)
+                .field("role", role)
+                .field("jwt_path", jwt_path)
+                .finish(),
+        }
+    }
+}
+
+/// Reads secrets from Vault.
+///
+/// Readers are cheap to clone, and all clones share the same Vault token.
+#[derive(Debug, Clone)]
+pub struct VaultSecretsReader {
+    inner: Arc<ReaderInner>,
+}
+
+#[derive(Debug)]
+struct ReaderInner {
+    client: reqwest::Client,
+    config: VaultConfig,
+    /// The Vault token with which to authenticate requests, if one has been
+    /// obtained.
+    token: Mutex<Option<String>>,
+}
+
+impl VaultSecretsReader {
+    /// Creates a reader that reads secrets as configured by `config`.
+    pub fn new(mut config: VaultConfig) -> VaultSecretsReader {
+        config.address = config.address.trim_end_matches('/').into();
+        config.mount = config.mount.trim_matches('/').into();
+        config.path_prefix = config.path_prefix.trim_matches('/').into();
+        let token = match &config.auth {
+            VaultAuth::Token(token) => Some(token.clone()),
+            VaultAuth::Kubernetes { .. } => None,
+        };
+        VaultSecretsReader {
+            inner: Arc::new(ReaderInner {
+                client: reqwest::Client::new(),
+                config,
+                token: Mutex::new(token),
+            }),
+        }
+    }
+
+    /// Reads the contents of the current version of the secret `id`.
+    pub async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, None).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the contents of version `version` of the secret `id`.
+    pub async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, Error> {
+        match self.try_read(id, Some(version)).await? {
+            Some((_, contents)) => Ok(contents),
+            None => bail!("version {} of secret {} does not exist", version, id),
+        }
+    }
+
+    /// Returns the current version of the secret `id`.
+    pub async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        match self.try_read(id, None).await? {
+            Some((version, _)) => Ok(version),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    /// Reads the version and contents of version `version` of the secret
+    /// `id`, or of its current version if `version` is absent, if it exists.
+    async fn try_read(
+        &self,
+        id: GlobalId,
+        version: Option<SecretVersion>,
+    ) -> Result<Option<(SecretVersion, Vec<u8>)>, Error> {
+        let url = self.url("data", &id.to_string());
+        let query: Vec<_> = version.iter().map(|v| ("version", v.0)).collect();
+        let response = self.send(|client| client.get(&url).query(&query)).await?;
+        // Vault reports deleted and destroyed versions as missing.
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response: DataResponse<ReadData> = check_status(response).await?.json().await?;
+        let contents = base64::decode(&response.data.data.contents)
+            .with_context(|| format!("decoding secret {}", id))?;
+        Ok(Some((
+            SecretVersion(response.data.metadata.version),
+            contents,
+        )))
+    }
+
+    /// Writes a new version of the secret `id` with the given `contents`, and
+    /// returns the new version.
+    async fn write(&self, id: GlobalId, contents: &[u8]) -> Result<SecretVersion, Error> {
+        let url = self.url("data", &id.to_string());
+        let body = json!({ "data": { "contents": base64::encode(contents) } });
+        let response = self.send(|client| client.post(&url).json(&body)).await?;
+        let response: DataResponse<VersionMetadata> = check_status(response).await?.json().await?;
+        Ok(SecretVersion(response.data.version))
+    }
+
+    /// Permanently destroys version `version` of the secret `id`.
+    async fn destroy_version(&self, id: GlobalId, version: SecretVersion) -> Result<(), Error> {
+        let url = self.url("destroy", &id.to_string());
+        let body = json!({ "versions": [version.0] });
+        let response = self.send(|client| client.post(&url).json(&body)).await?;
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// Deletes every version of the secret `id`.
+    ///
+    /// Deleting a secret that does not exist is not an error.
+    async fn delete(&self, id: GlobalId) -> Result<(), Error> {
+        let url = self.url("metadata", &id.to_string());
+        let response = self.send(|client| client.delete(&url)).await?;
+        check_status(response).await?;
+        Ok(())
+    }
+
+    /// Lists the IDs of the secrets in Vault.
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        let url = self.url("metadata", "");
+        let response = self
+            .send(|client| client.get(&url).query(&[("list", "true")]))
+            .await?;
+        // Vault reports an empty directory as a missing one.
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        let response: DataResponse<ListData> = check_status(response).await?.json().await?;
+        // Ignore keys that are not named after a secret, like subdirectories.
+        Ok(response
+            .data
+            .keys
+            .iter()
+            .filter_map(|key| key.parse().ok())
+            .collect())
+    }
+
+    /// Returns the URL of the API endpoint of the KV secrets engine of kind
+    /// `kind` for the given path under the path prefix.
+    fn url(&self, kind: &str, path: &str) -> String {
+        let config = &self.inner.config;
+        let mut url = format!("{}/v1/{}/{}", config.address, config.mount, kind);
+        for component in [config.path_prefix.as_str(), path] {
+            if !component.is_empty() {
+                url.push('/');
+                url.push_str(component);
+            }
+        }
+        url
+    }
+
+    /// Sends the request built by `build`, authenticated with the current
+    /// Vault token.
+    ///
+    /// If the request is denied and the token was obtained by logging in, the
+    /// token may have expired, so the request is retried once with a new
+    /// token.
+    async fn send<F>(&self, build: F) -> Result<Response, Error>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let token = self.token().await?;
+        let response = build(&self.inner.client)
+            .header("X-Vault-Token", &token)
+            .send()
+            .await?;
+        if response.status() != StatusCode::FORBIDDEN
+            || matches!(self.inner.config.auth, VaultAuth::Token(_))
+        {
+            return Ok(response);
+        }
+        self.inner.token.lock().expect("lock poisoned").take();
+        let token = self.token().await?;
+        Ok(build(&self.inner.client)
+            .header("X-Vault-Token", &token)
+            .send()
+            .await?)
+    }
+
+    /// Returns the current Vault token, logging in to obtain one if
+    /// necessary.
+    async fn token(&self) -> Result<String, Error> {
+        let token = self.inner.token.lock().expect("lock poisoned").clone();
+        if let Some(token) = token {
+            return Ok(token);
+        }
+        let token = self.login().await?;
+        *self.inner.token.lock().expect("lock poisoned") = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Logs in to Vault with the configured auth method.
+    async fn login(&self) -> Result<String, Error> {
+        let (mount, role, jwt_path) = match &self.inner.config.auth {
+            VaultAuth::Token(token) => return Ok(token.clone()),
+            VaultAuth::Kubernetes {
+                mount,
+                role,
+                jwt_path,
+            } => (mount, role, jwt_path),
+        };
+        let jwt = tokio::fs::read_to_string(jwt_path)
+            .await
+            .with_context(|| format!("reading service account token {}", jwt_path.display()))?;
+        let url = format!(
+            "{}/v1/auth/{}/login",
+            self.inner.config.address,
+            mount.trim_matches('/')
+        );
+        let response = self
+            .inner
+            .client
+            .post(&url)
+            .json(&json!({ "role": role, "jwt": jwt.trim() }))
+            .send()
+            .await?;
+        let response: LoginResponse = check_status(response)
+            .await
+            .context("logging in to vault")?
+            .json()
+            .await?;
+        Ok(response.auth.client_token)
+    }
+}
+
+/// Stores secrets in Vault.
+///
+/// Vault cannot apply several operations atomically, so the controller
+/// applies them one at a time and, if one fails, reverts the operations it
+/// has already applied. The reversion is itself best effort, and cannot
+/// restore destroyed versions. Reverting an update restores the previous
+/// version's contents as a new version.
+#[derive(Debug)]
+pub struct VaultSecretsController {
+    reader: VaultSecretsReader,
+}
+
+impl VaultSecretsController {
+    /// Creates a controller that stores secrets as configured by `config`.
+    ///
+    /// Fails if the controller cannot authenticate with Vault.
+    pub async fn new(config: VaultConfig) -> Result<VaultSecretsController, Error> {
+        let reader = VaultSecretsReader::new(config);
+        reader.token().await?;
+        Ok(VaultSecretsController { reader })
+    }
+
+    /// Returns a reader for the secrets that the controller stores, which
+    /// shares the controller's Vault token.
+    pub fn reader(&self) -> VaultSecretsReader {
+        self.reader.clone()
+    }
+}
+
+/// A change that [`SecretsController::apply`] made to a secret, which it
+/// reverts if a later operation fails.
+enum Change {
+    /// Version `version` of the secret `id` was written.
+    Ensured {
+        id: GlobalId,
+        version: SecretVersion,
+    },
+    /// The secret `id`, whose current version had contents `contents`, was
+    /// deleted.
+    Deleted { id: GlobalId, contents: Vec<u8> },
+}
+
+/// Applies the operations of a [`VaultSecretsController`] to Vault.
+struct Writer<'a>(&'a VaultSecretsReader);
+
+#[async_trait]
+impl RevertibleStorage for Writer<'_> {
+    type Change = Change;
+
+    // Writes report the version they create, which is all that is needed to
+    // revert them, so only deletions read a secret before changing it.
+    async fn apply_op(
+        &self,
+        op: &SecretOp,
+    ) -> Result<(Option<SecretVersion>, Option<Change>), Error> {
+        let reader = self.0;
+        match op {
+            SecretOp::Ensure { id, contents } => {
+                let version = reader.write(*id, contents).await?;
+                Ok((Some(version), Some(Change::Ensured { id: *id, version })))
+            }
+            SecretOp::Delete { id, version: None } => {
+                // Deleting a secret that does not exist is not an error, and
+                // needs no reversion.
+                match reader.try_read(*id, None).await? {
+                    Some((_, contents)) => {
+                        reader.delete(*id).await?;
+                        Ok((None, Some(Change::Deleted { id: *id, contents })))
+                    }
+                    None => Ok((None, None)),
+                }
+            }
+            SecretOp::Delete {
+                id,
+                version: Some(version),
+            } => {
+                if reader.current_version(*id).await? == *version {
+                    bail!("cannot delete the current version of secret {}", id)
+                }
+                reader.destroy_version(*id, *version).await?;
+                Ok((None, None))
+            }
+        }
+    }
+
+    async fn revert(&self, change: Change) -> Result<(), Error> {
+        let reader = self.0;
+        let id = match &change {
+            Change::Ensured { id, .. } | Change::Deleted { id, .. } => *id,
+        };
+        let res = async {
+            match change {
+                // Vault numbers the versions of a new secret from 1, so the
+                // first version was a creation.
+                Change::Ensured {
+                    version: SecretVersion(1),
+                    ..
+                } => reader.delete(id).await,
+                Change::Ensured { version, .. } => {
+                    let contents = reader
+                        .read_version(id, SecretVersion(version.0 - 1))
+                        .await?;
+                    reader.write(id, &contents).await?;
+                    Ok(())
+                }
+                Change::Deleted { contents, .. } => {
+                    reader.write(id, &contents).await?;
+                    Ok(())
+                }
+            }
+        }
+        .await;
+        res.with_context(|| format!("reverting a change to secret {} in vault", id))
+    }
+}
+
+#[async_trait]
+impl SecretsController for VaultSecretsController {
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, Error> {
+        mz_secrets::apply_reverting(&Writer(&self.reader), &ops).await
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, Error> {
+        self.reader.read(id).await
+    }
+
+    async fn read_version(&self, id: GlobalId, version: SecretVersion) -> Result<Vec<u8>, Error> {
+        self.reader.read_version(id, version).await
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, Error> {
+        self.reader.current_version(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, Error> {
+        self.reader.list().await
+    }
+}
+
+/// Returns `response` if its status indicates success, or an error that
+/// describes the failure otherwise.
+async fn check_status(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let errors = match response.json::<ErrorResponse>().await {
+        Ok(response) if !response.errors.is_empty() => response.errors.join("; "),
+        _ => "no details".into(),
+    };
+    Err(anyhow!(
+        "vault request failed with status {}: {}",
+        status,
+        errors
+    ))
+}
+
+#[derive(Deserialize)]
+struct DataResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct ReadData {
+    data: SecretData,
+    metadata: VersionMetadata,
+}
+
+#[derive(Deserialize)]
+struct VersionMetadata {
+    version: u64,
+}
+
+#[derive(Deserialize)]
+struct SecretData {
+    contents: String,
+}
+
+#[derive(Deserialize)]
+struct ListData {
+    keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(default)]
+    errors: Vec<String>,
+}
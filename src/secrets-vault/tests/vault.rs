@@ -0,0 +1,322 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Tests of the Vault secrets controller against a fake Vault server that
+//! implements the subset of the KV version 2 and Kubernetes auth APIs that
+//! the controller uses.
+
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+
+use mz_expr::GlobalId;
+use mz_secrets::{SecretOp, SecretVersion, SecretsController};
+use mz_secrets_vault::{VaultAuth, VaultConfig, VaultSecretsController, VaultSecretsReader};
+
+const JWT: &str = "service-account-token";
+
+#[derive(Default)]
+struct FakeVault {
+    /// The versions of each secret, by path, where destroyed versions are
+    /// absent.
+    secrets: BTreeMap<String, Vec<Option<String>>>,
+    /// The tokens that the server accepts.
+    tokens: HashSet<String>,
+    /// The number of successful logins.
+    logins: usize,
+    /// The number of reads of secret data.
+    reads: usize,
+    /// The paths of the secrets to which writes fail.
+    failing_writes: HashSet<String>,
+}
+
+async fn handle(vault: Arc<Mutex<FakeVault>>, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().trim_start_matches("/v1/").to_string();
+    let query = req.uri().query().unwrap_or("").to_string();
+    let token = req
+        .headers()
+        .get("X-Vault-Token")
+        .and_then(|token| token.to_str().ok())
+        .map(String::from);
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let mut vault = vault.lock().unwrap();
+
+    if method == Method::POST && path == "auth/kubernetes/login" {
+        if body["role"] != "materialize" || body["jwt"] != JWT {
+            return respond(StatusCode::BAD_REQUEST, json!({ "errors": ["bad login"] }));
+        }
+        vault.logins += 1;
+        let token = format!("token-{}", vault.logins);
+        vault.tokens.insert(token.clone());
+        return respond(StatusCode::OK, json!({ "auth": { "client_token": token } }));
+    }
+    if !token.map_or(false, |token| vault.tokens.contains(&token)) {
+        return respond(
+            StatusCode::FORBIDDEN,
+            json!({ "errors": ["permission denied"] }),
+        );
+    }
+
+    let (kind, name) = match path.strip_prefix("secret/") {
+        Some(path) => path.split_once('/').unwrap_or((path, "")),
+        None => return respond(StatusCode::NOT_FOUND, json!({ "errors": [] })),
+    };
+    let name = name.to_string();
+    match (method.as_str(), kind) {
+        ("GET", "data") => {
+            vault.reads += 1;
+            let versions = match vault.secrets.get(&name) {
+                Some(versions) => versions,
+                None => return respond(StatusCode::NOT_FOUND, json!({ "errors": [] })),
+            };
+            let version = match query.strip_prefix("version=") {
+                Some(version) => version.parse().unwrap(),
+                None => versions.len(),
+            };
+            match versions.get(version.wrapping_sub(1)) {
+                Some(Some(contents)) => respond(
+                    StatusCode::OK,
+                    json!({ "data": {
+                        "data": { "contents": contents },
+                        "metadata": { "version": version },
+                    } }),
+                ),
+                _ => respond(StatusCode::NOT_FOUND, json!({ "errors": [] })),
+            }
+        }
+        ("POST", "data") => {
+            if vault.failing_writes.contains(&name) {
+                return respond(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    json!({ "errors": ["write failed"] }),
+                );
+            }
+            let contents = body["data"]["contents"].as_str().unwrap().to_string();
+            let versions = vault.secrets.entry(name).or_default();
+            versions.push(Some(contents));
+            respond(
+                StatusCode::OK,
+                json!({ "data": { "version": versions.len() } }),
+            )
+        }
+        ("POST", "destroy") => {
+            if let Some(versions) = vault.secrets.get_mut(&name) {
+                for version in body["versions"].as_array().unwrap() {
+                    let version = version.as_u64().unwrap() as usize;
+                    if let Some(contents) = versions.get_mut(version - 1) {
+                        *contents = None;
+                    }
+                }
+            }
+            respond(StatusCode::NO_CONTENT, Value::Null)
+        }
+        ("DELETE", "metadata") => {
+            vault.secrets.remove(&name);
+            respond(StatusCode::NO_CONTENT, Value::Null)
+        }
+        ("GET", "metadata") if query == "list=true" => {
+            let prefix = format!("{}/", name);
+            let keys: Vec<_> = vault
+                .secrets
+                .keys()
+                .filter_map(|path| path.strip_prefix(&prefix))
+                .collect();
+            if keys.is_empty() {
+                respond(StatusCode::NOT_FOUND, json!({ "errors": [] }))
+            } else {
+                respond(StatusCode::OK, json!({ "data": { "keys": keys } }))
+            }
+        }
+        _ => respond(StatusCode::METHOD_NOT_ALLOWED, json!({ "errors": [] })),
+    }
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    let body = match body {
+        Value::Null => Body::empty(),
+        body => Body::from(body.to_string()),
+    };
+    Response::builder().status(status).body(body).unwrap()
+}
+
+/// Starts a fake Vault server, and returns its state and address.
+fn start_server() -> (Arc<Mutex<FakeVault>>, String) {
+    let vault = Arc::new(Mutex::new(FakeVault::default()));
+    vault.lock().unwrap().tokens.insert("root".into());
+    let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+    let address = format!("http://{}", incoming.local_addr());
+    let service = {
+        let vault = Arc::clone(&vault);
+        make_service_fn(move |_conn| {
+            let vault = Arc::clone(&vault);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let vault = Arc::clone(&vault);
+                    async move { Ok::<_, Infallible>(handle(vault, req).await) }
+                }))
+            }
+        })
+    };
+    tokio::spawn(Server::builder(incoming).serve(service));
+    (vault, address)
+}
+
+fn config(address: String, auth: VaultAuth) -> VaultConfig {
+    VaultConfig {
+        address,
+        mount: "secret".into(),
+        path_prefix: "/materialize/".into(),
+        auth,
+    }
+}
+
+fn ensure(id: GlobalId, contents: &[u8]) -> SecretOp {
+    SecretOp::Ensure {
+        id,
+        contents: contents.to_vec(),
+    }
+}
+
+fn delete(id: GlobalId, version: Option<SecretVersion>) -> SecretOp {
+    SecretOp::Delete { id, version }
+}
+
+#[tokio::test]
+async fn test_versions() -> Result<(), anyhow::Error> {
+    let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+    let (vault, address) = start_server();
+    let controller =
+        VaultSecretsController::new(config(address, VaultAuth::Token("root".into()))).await?;
+
+    let versions = controller
+        .apply(vec![ensure(a, b"hunter1"), ensure(a, b"hunter2")])
+        .await?;
+    assert_eq!(versions, vec![SecretVersion(1), SecretVersion(2)]);
+    // Writes report the version they create, so applying them reads
+    // nothing.
+    assert_eq!(vault.lock().unwrap().reads, 0);
+    assert_eq!(controller.read(a).await?, b"hunter2");
+    assert_eq!(controller.current_version(a).await?, SecretVersion(2));
+    assert_eq!(
+        controller.read_version(a, SecretVersion(1)).await?,
+        b"hunter1"
+    );
+
+    // The current version cannot be destroyed on its own.
+    assert!(controller
+        .apply(vec![delete(a, Some(SecretVersion(2)))])
+        .await
+        .is_err());
+    controller
+        .apply(vec![delete(a, Some(SecretVersion(1)))])
+        .await?;
+    assert!(controller.read_version(a, SecretVersion(1)).await.is_err());
+    assert_eq!(controller.read(a).await?, b"hunter2");
+
+    controller.apply(vec![ensure(b, b"")]).await?;
+    assert_eq!(controller.list().await?, vec![a, b]);
+    controller.apply(vec![delete(a, None)]).await?;
+    assert!(controller.read(a).await.is_err());
+    assert_eq!(controller.list().await?, vec![b]);
+    // Deleting a secret that does not exist is not an error.
+    controller.apply(vec![delete(a, None)]).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revert() -> Result<(), anyhow::Error> {
+    let (a, b, c) = (GlobalId::User(1), GlobalId::User(2), GlobalId::User(3));
+    let (vault, address) = start_server();
+    let controller =
+        VaultSecretsController::new(config(address, VaultAuth::Token("root".into()))).await?;
+    controller
+        .apply(vec![ensure(a, b"hunter1"), ensure(b, b"swordfish")])
+        .await?;
+    vault
+        .lock()
+        .unwrap()
+        .failing_writes
+        .insert(format!("materialize/{}", c));
+
+    // An update is reverted by restoring the previous contents as a new
+    // version, a creation by deleting the secret, and a deletion by
+    // recreating the secret.
+    let res = controller
+        .apply(vec![
+            ensure(a, b"hunter2"),
+            delete(b, None),
+            ensure(GlobalId::User(4), b"hunter4"),
+            ensure(c, b"hunter3"),
+        ])
+        .await;
+    assert!(res.is_err());
+    assert_eq!(controller.read(a).await?, b"hunter1");
+    assert_eq!(controller.current_version(a).await?, SecretVersion(3));
+    assert_eq!(controller.read(b).await?, b"swordfish");
+    assert_eq!(controller.list().await?, vec![a, b]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_kubernetes_auth() -> Result<(), anyhow::Error> {
+    let id = GlobalId::User(1);
+    let (vault, address) = start_server();
+    let jwt = tempfile::NamedTempFile::new()?;
+    std::fs::write(jwt.path(), format!("{}\n", JWT))?;
+    let config = config(
+        address,
+        VaultAuth::Kubernetes {
+            mount: "kubernetes".into(),
+            role: "materialize".into(),
+            jwt_path: jwt.path().into(),
+        },
+    );
+
+    let controller = VaultSecretsController::new(config.clone()).await?;
+    assert_eq!(vault.lock().unwrap().logins, 1);
+    controller.apply(vec![ensure(id, b"hunter2")]).await?;
+
+    // Readers share the controller's token.
+    let reader = controller.reader();
+    assert_eq!(reader.read(id).await?, b"hunter2");
+    assert_eq!(vault.lock().unwrap().logins, 1);
+
+    // An expired token is replaced by logging in again.
+    vault.lock().unwrap().tokens.clear();
+    assert_eq!(reader.current_version(id).await?, SecretVersion(1));
+    assert_eq!(controller.read(id).await?, b"hunter2");
+    assert_eq!(vault.lock().unwrap().logins, 2);
+
+    // A reader of its own logs in when it first reads.
+    let reader = VaultSecretsReader::new(config);
+    assert_eq!(reader.read(id).await?, b"hunter2");
+    assert_eq!(vault.lock().unwrap().logins, 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_denied() -> Result<(), anyhow::Error> {
+    let (_vault, address) = start_server();
+    let controller =
+        VaultSecretsController::new(config(address, VaultAuth::Token("wrong".into()))).await?;
+    let err = controller.read(GlobalId::User(1)).await.unwrap_err();
+    assert!(
+        err.to_string().contains("permission denied"),
+        "unexpected error: {:#}",
+        err
+    );
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Validation of the contents of secrets.
+
+use std::fmt;
+use std::str::{self, FromStr};
+
+use anyhow::bail;
+
+/// A format to which the contents of a secret must conform.
+///
+/// The format is checked when the contents of a secret are written, so that
+/// malformed contents are rejected up front rather than when a connector
+/// later fails to use them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretFormat {
+    /// Arbitrary bytes.
+    Binary,
+    /// Text encoded as UTF-8, like a password.
+    Utf8,
+    /// One or more PEM-encoded blocks, like a certificate or a private key.
+    Pem,
+}
+
+impl SecretFormat {
+    /// The name of every format, as accepted by [`SecretFormat::from_str`].
+    pub const NAMES: &'static [&'static str] = &["binary", "utf8", "pem"];
+
+    /// Returns the name of the format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretFormat::Binary => "binary",
+            SecretFormat::Utf8 => "utf8",
+            SecretFormat::Pem => "pem",
+        }
+    }
+
+    /// Checks that `contents` conform to the format.
+    ///
+    /// The error describes how the contents are malformed, but never includes
+    /// the contents themselves.
+    pub fn validate(&self, contents: &[u8]) -> Result<(), anyhow::Error> {
+        match self {
+            SecretFormat::Binary => Ok(()),
+            SecretFormat::Utf8 => match str::from_utf8(contents) {
+                Ok(_) => Ok(()),
+                Err(e) => bail!("invalid UTF-8 sequence at byte offset {}", e.valid_up_to()),
+            },
+            SecretFormat::Pem => {
+                if str::from_utf8(contents).is_err() {
+                    bail!("PEM data must be text");
+                }
+                match pem::parse_many(contents) {
+                    Ok(blocks) if blocks.is_empty() => bail!("no PEM blocks found"),
+                    Ok(_) => Ok(()),
+                    // The error only describes the structure of the data.
+                    Err(e) => bail!("malformed PEM block: {}", e),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SecretFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SecretFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<SecretFormat, anyhow::Error> {
+        match s {
+            "binary" => Ok(SecretFormat::Binary),
+            "utf8" => Ok(SecretFormat::Utf8),
+            "pem" => Ok(SecretFormat::Pem),
+            _ => bail!("unknown secret format: {}", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBszCCAVmgAwIBAgIUQ0fy5tC+3AYXb5hvnJtcA/Ud4s4wCgYIKoZIzj0EAwIw
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_validate() {
+        assert!(SecretFormat::Binary.validate(b"\xff\x00").is_ok());
+
+        assert!(SecretFormat::Utf8.validate("hunter2 ☃".as_bytes()).is_ok());
+        let err = SecretFormat::Utf8.validate(b"hunter\xff").unwrap_err();
+        assert_eq!(err.to_string(), "invalid UTF-8 sequence at byte offset 6");
+
+        assert!(SecretFormat::Pem.validate(CERT.as_bytes()).is_ok());
+        let two = format!("{}{}", CERT, CERT);
+        assert!(SecretFormat::Pem.validate(two.as_bytes()).is_ok());
+        let err = SecretFormat::Pem.validate(b"hunter2").unwrap_err();
+        assert_eq!(err.to_string(), "no PEM blocks found");
+        assert!(SecretFormat::Pem.validate(b"\xff").is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        for name in SecretFormat::NAMES {
+            assert_eq!(name.parse::<SecretFormat>().unwrap().as_str(), *name);
+        }
+        assert!("text".parse::<SecretFormat>().is_err());
+    }
+}
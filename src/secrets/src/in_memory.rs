@@ -0,0 +1,280 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! An in-memory secrets controller, for tests.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use anyhow::bail;
+use async_trait::async_trait;
+use mz_expr::GlobalId;
+
+use crate::{SecretOp, SecretVersion, SecretsController};
+
+/// Stores secrets in memory.
+///
+/// The controller records every operation that it applies successfully, so
+/// that tests can assert on the operations that they cause. Clones of the
+/// controller share their secrets and their log of operations, so a test can
+/// retain a clone of a controller that it hands off to the code under test.
+///
+/// The controller retains every version of every secret. Like the filesystem
+/// controller, it refuses to delete secrets or versions that do not exist,
+/// and rejects all of a batch of operations if it rejects any of them. Tests
+/// can make it fail applications outright, as storage that is temporarily
+/// unavailable would, with [`InMemorySecretsController::fail_applies`], and
+/// make it unable to list secrets, as some backends are, with
+/// [`InMemorySecretsController::disable_listing`].
+#[derive(Clone, Default)]
+pub struct InMemorySecretsController {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    secrets: HashMap<GlobalId, BTreeMap<SecretVersion, Vec<u8>>>,
+    ops: Vec<SecretOp>,
+    failing_applies: usize,
+    listing_disabled: bool,
+}
+
+impl InMemorySecretsController {
+    /// Creates a controller that stores no secrets.
+    pub fn new() -> InMemorySecretsController {
+        InMemorySecretsController::default()
+    }
+
+    /// Returns the operations that the controller has applied, in the order
+    /// in which they were applied.
+    pub fn ops(&self) -> Vec<SecretOp> {
+        self.state.lock().expect("lock poisoned").ops.clone()
+    }
+
+    /// Makes the next `n` applications fail without applying any of their
+    /// operations.
+    pub fn fail_applies(&self, n: usize) {
+        self.state.lock().expect("lock poisoned").failing_applies = n;
+    }
+
+    /// Makes the controller report that it cannot list secrets, and list
+    /// none.
+    pub fn disable_listing(&self) {
+        self.state.lock().expect("lock poisoned").listing_disabled = true;
+    }
+}
+
+impl fmt::Debug for InMemorySecretsController {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.state.lock().expect("lock poisoned");
+        f.debug_struct("InMemorySecretsController")
+            .field("secrets", &state.secrets.keys().collect::<Vec<_>>())
+            .field("ops", &state.ops)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl SecretsController for InMemorySecretsController {
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, anyhow::Error> {
+        let mut state = self.state.lock().expect("lock poisoned");
+        if state.failing_applies > 0 {
+            state.failing_applies -= 1;
+            bail!("secrets storage unavailable");
+        }
+        let mut secrets = state.secrets.clone();
+        let mut ensured = vec![];
+        for op in &ops {
+            match op {
+                SecretOp::Ensure { id, contents } => {
+                    let versions = secrets.entry(*id).or_default();
+                    let version = match versions.keys().next_back() {
+                        Some(current) => current.next(),
+                        None => SecretVersion(1),
+                    };
+                    versions.insert(version, contents.clone());
+                    ensured.push(version);
+                }
+                SecretOp::Delete { id, version: None } => {
+                    if secrets.remove(id).is_none() {
+                        bail!("secret {} does not exist", id);
+                    }
+                }
+                SecretOp::Delete {
+                    id,
+                    version: Some(version),
+                } => {
+                    let versions = match secrets.get_mut(id) {
+                        Some(versions) => versions,
+                        None => bail!("secret {} does not exist", id),
+                    };
+                    if versions.keys().next_back() == Some(version) {
+                        bail!("cannot delete the current version of secret {}", id);
+                    }
+                    if versions.remove(version).is_none() {
+                        bail!("version {} of secret {} does not exist", version, id);
+                    }
+                }
+            }
+        }
+        state.secrets = secrets;
+        state.ops.extend(ops);
+        Ok(ensured)
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, anyhow::Error> {
+        let version = self.current_version(id).await?;
+        self.read_version(id, version).await
+    }
+
+    async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let state = self.state.lock().expect("lock poisoned");
+        match state.secrets.get(&id) {
+            Some(versions) => match versions.get(&version) {
+                Some(contents) => Ok(contents.clone()),
+                None => bail!("version {} of secret {} does not exist", version, id),
+            },
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, anyhow::Error> {
+        let state = self.state.lock().expect("lock poisoned");
+        match state.secrets.get(&id) {
+            Some(versions) => Ok(*versions.keys().next_back().expect("secrets have a version")),
+            None => bail!("secret {} does not exist", id),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, anyhow::Error> {
+        let state = self.state.lock().expect("lock poisoned");
+        if state.listing_disabled {
+            return Ok(vec![]);
+        }
+        Ok(state.secrets.keys().copied().collect())
+    }
+
+    fn can_list(&self) -> bool {
+        !self.state.lock().expect("lock poisoned").listing_disabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply_atomically() -> Result<(), anyhow::Error> {
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let controller = InMemorySecretsController::new();
+        let log = controller.clone();
+
+        let op = ensure(a, b"hunter2");
+        controller.apply(vec![op.clone()]).await?;
+        assert_eq!(controller.read(a).await?, b"hunter2");
+
+        // Deleting a secret that does not exist fails the whole batch.
+        let res = controller
+            .apply(vec![delete(a, None), delete(b, None)])
+            .await;
+        assert!(res.is_err());
+        assert_eq!(controller.list().await?, vec![a]);
+
+        controller.apply(vec![delete(a, None)]).await?;
+        assert!(controller.read(a).await.is_err());
+        assert_eq!(log.ops(), vec![op, delete(a, None)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_versions() -> Result<(), anyhow::Error> {
+        let id = GlobalId::User(1);
+        let controller = InMemorySecretsController::new();
+
+        let versions = controller
+            .apply(vec![ensure(id, b"hunter1"), ensure(id, b"hunter2")])
+            .await?;
+        assert_eq!(versions, vec![SecretVersion(1), SecretVersion(2)]);
+        assert_eq!(
+            controller.apply(vec![ensure(id, b"hunter3")]).await?,
+            vec![SecretVersion(3)]
+        );
+        assert_eq!(controller.current_version(id).await?, SecretVersion(3));
+        assert_eq!(controller.read(id).await?, b"hunter3");
+        assert_eq!(
+            controller.read_version(id, SecretVersion(1)).await?,
+            b"hunter1"
+        );
+
+        // The current version cannot be deleted on its own.
+        assert!(controller
+            .apply(vec![delete(id, Some(SecretVersion(3)))])
+            .await
+            .is_err());
+        controller
+            .apply(vec![delete(id, Some(SecretVersion(1)))])
+            .await?;
+        assert!(controller.read_version(id, SecretVersion(1)).await.is_err());
+        assert_eq!(
+            controller.read_version(id, SecretVersion(2)).await?,
+            b"hunter2"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fail_applies() -> Result<(), anyhow::Error> {
+        let id = GlobalId::User(1);
+        let controller = InMemorySecretsController::new();
+
+        controller.fail_applies(2);
+        for _ in 0..2 {
+            assert!(controller
+                .apply(vec![ensure(id, b"hunter2")])
+                .await
+                .is_err());
+        }
+        assert!(controller.list().await?.is_empty());
+        assert!(controller.ops().is_empty());
+        controller.apply(vec![ensure(id, b"hunter2")]).await?;
+        assert_eq!(controller.read(id).await?, b"hunter2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disable_listing() -> Result<(), anyhow::Error> {
+        let id = GlobalId::User(1);
+        let controller = InMemorySecretsController::new();
+        controller.apply(vec![ensure(id, b"hunter2")]).await?;
+        assert!(controller.can_list());
+        assert_eq!(controller.list().await?, vec![id]);
+
+        controller.disable_listing();
+        assert!(!controller.can_list());
+        assert!(controller.list().await?.is_empty());
+        assert_eq!(controller.read(id).await?, b"hunter2");
+        Ok(())
+    }
+
+    fn ensure(id: GlobalId, contents: &[u8]) -> SecretOp {
+        SecretOp::Ensure {
+            id,
+            contents: contents.to_vec(),
+        }
+    }
+
+    fn delete(id: GlobalId, version: Option<SecretVersion>) -> SecretOp {
+        SecretOp::Delete { id, version }
+    }
+}
@@ -6,34 +6,177 @@
 // As of the Change Date specified in that file, in accordance with
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
+
+use std::fmt;
+
+use anyhow::bail;
+use async_trait::async_trait;
 use mz_expr::GlobalId;
 
+mod format;
+mod in_memory;
+mod migrating;
+mod reverting;
+
+pub use format::SecretFormat;
+pub use in_memory::InMemorySecretsController;
+pub use migrating::MigratingSecretsController;
+pub use reverting::{apply_reverting, RevertibleStorage};
+
+/// The default maximum size of the contents of a secret, in bytes.
+pub const DEFAULT_MAX_SECRET_SIZE: usize = 64 << 10;
+
 /// Securely stores secrets.
-pub trait SecretsController: Send {
+///
+/// Secrets are versioned. Each time the contents of a secret are ensured, the
+/// secret gets a new version, which becomes its current version. Controllers
+/// may retain some of the previous versions of a secret, so that users of the
+/// secret that were configured with a previous version can continue to read
+/// it until they pick up the rotation.
+///
+/// Implementations that store secrets over the network must not block the
+/// calling thread while they wait on the network, as the coordinator reads
+/// secrets from its own task. The coordinator applies operations from a task
+/// of their own, one application at a time, so reads may run concurrently
+/// with an application.
+#[async_trait]
+pub trait SecretsController: Send + Sync {
     /// Applies the specified secret operations in bulk.
     ///
     /// Implementations must apply the operations atomically. If the method
-    /// returns `Ok(())`, then all operations have been applied successfully;
-    /// if the method returns `Err(())`, then none of the operations have been
+    /// returns `Ok(_)`, then all operations have been applied successfully;
+    /// if the method returns `Err(_)`, then none of the operations have been
     /// applied.
     ///
+    /// On success, returns the version that each [`SecretOp::Ensure`]
+    /// operation created, in the order of the operations.
+    ///
     /// Implementations are permitted to reject combinations of operations which
-    /// they cannot apply atomically.
-    fn apply(&mut self, ops: Vec<SecretOp>) -> Result<(), anyhow::Error>;
+    /// they cannot apply atomically, or operations that they do not support.
+    ///
+    /// Implementations must not retry indefinitely. Callers may instead bound how long they wait by dropping
+    /// the returned future, e.g. after a timeout. Atomicity is not guaranteed
+    /// for an application that is canceled in this way: any subset of the
+    /// operations may have been applied. Callers must therefore only cancel
+    /// applications whose partial effects they can tolerate, like writing a
+    /// secret that is not yet known to the catalog, which leaves at worst an
+    /// orphaned secret that is deleted when the coordinator next starts.
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, anyhow::Error>;
+
+    /// Reads the contents of the current version of the secret with the
+    /// specified ID.
+    ///
+    /// Returns an error if the secret does not exist in storage.
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, anyhow::Error>;
+
+    /// Reads the contents of the specified version of the secret with the
+    /// specified ID.
+    ///
+    /// Returns an error if the secret does not exist in storage, or if the
+    /// controller no longer retains the version.
+    async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, anyhow::Error>;
+
+    /// Returns the current version of the secret with the specified ID.
+    ///
+    /// Users of a secret can compare the current version with the version
+    /// they were configured with to detect that the secret has been rotated.
+    ///
+    /// Returns an error if the secret does not exist in storage.
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, anyhow::Error>;
+
+    /// Lists the IDs of all secrets in storage.
+    ///
+    /// Controllers that cannot list the secrets in storage return an empty
+    /// list; see [`SecretsController::can_list`].
+    async fn list(&self) -> Result<Vec<GlobalId>, anyhow::Error>;
+
+    /// Reports whether the controller can list the secrets in storage.
+    ///
+    /// Callers must not conclude that a secret is missing from storage, or
+    /// that a secret in storage is orphaned, from the result of
+    /// [`SecretsController::list`] unless this returns `true`.
+    fn can_list(&self) -> bool {
+        true
+    }
+
+    /// Copies the secrets that have not yet been migrated to a new backend,
+    /// if the controller is migrating secrets between backends.
+    ///
+    /// Returns the number of secrets copied. Returns an error if the
+    /// controller is not migrating secrets.
+    async fn migrate(&self) -> Result<usize, anyhow::Error> {
+        bail!("secrets storage is not migrating between backends")
+    }
+}
+
+/// A version of a secret.
+///
+/// The versions of a secret increase monotonically: each new version of a
+/// secret is greater than all of its previous versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SecretVersion(pub u64);
+
+impl SecretVersion {
+    /// Returns the version that follows this version.
+    pub fn next(self) -> SecretVersion {
+        SecretVersion(self.0 + 1)
+    }
+}
+
+impl fmt::Display for SecretVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
 /// An operation on a [`SecretsController`].
+#[derive(Clone, PartialEq, Eq)]
 pub enum SecretOp {
-    /// Create or update the contents of a secret.
+    /// Create or update the contents of a secret, which creates a new version
+    /// of the secret.
     Ensure {
         /// The ID of the secret to create or update.
         id: GlobalId,
         /// The binary contents of the secret.
         contents: Vec<u8>,
     },
-    /// Delete a secret.
+    /// Delete a secret, or one of its versions.
     Delete {
         /// The id of the secret to delete.
         id: GlobalId,
+        /// The version of the secret to delete, which must not be the current
+        /// version. If absent, every version of the secret is deleted.
+        version: Option<SecretVersion>,
     },
 }
+
+impl SecretOp {
+    /// Returns the ID of the secret that the operation applies to.
+    pub fn id(&self) -> GlobalId {
+        match self {
+            SecretOp::Ensure { id, .. } | SecretOp::Delete { id, .. } => *id,
+        }
+    }
+}
+
+impl fmt::Debug for SecretOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // Never render the contents of secrets.
+            SecretOp::Ensure { id, .. } => f
+                .debug_struct("Ensure")
+                .field("id", id)
+                .field("contents", &"<redacted>")
+                .finish(),
+            SecretOp::Delete { id, version } => f
+                .debug_struct("Delete")
+                .field("id", id)
+                .field("version", version)
+                .finish(),
+        }
+    }
+}
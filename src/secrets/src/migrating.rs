@@ -0,0 +1,327 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A secrets controller that migrates secrets between two backends.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use mz_expr::GlobalId;
+use tracing::warn;
+
+use crate::{SecretOp, SecretVersion, SecretsController};
+
+/// Migrates secrets from an old backend to a new backend without downtime.
+///
+/// The controller writes every secret to both backends, and reads each secret
+/// from the new backend, falling back to the old backend for secrets that
+/// have not yet been copied to the new backend. [`SecretsController::migrate`]
+/// copies the secrets that exist only in the old backend to the new backend.
+/// Once it has done so, the deployment can switch over to the new backend
+/// alone, and, should the switch need to be rolled back, the old backend
+/// still holds every secret.
+///
+/// The new backend is authoritative. Operations are applied to it first, and
+/// atomically; they are then applied to the old backend on a best-effort
+/// basis. If the old backend fails to apply them, the failure is logged, but
+/// the operations are still reported as applied. A secret that the old
+/// backend fails to delete remains visible through [`SecretsController::list`]
+/// and is deleted when the coordinator next starts.
+///
+/// The backends number the versions of a secret independently. The versions
+/// that the controller reports are those of the backend from which it reads
+/// the secret.
+pub struct MigratingSecretsController {
+    old: Box<dyn SecretsController>,
+    new: Box<dyn SecretsController>,
+}
+
+impl MigratingSecretsController {
+    /// Creates a controller that migrates secrets from `old` to `new`.
+    pub fn new(
+        old: Box<dyn SecretsController>,
+        new: Box<dyn SecretsController>,
+    ) -> MigratingSecretsController {
+        MigratingSecretsController { old, new }
+    }
+
+    /// Returns the backend from which the secret with the specified ID is
+    /// read.
+    async fn backend_for(&self, id: GlobalId) -> &dyn SecretsController {
+        if self.new.current_version(id).await.is_ok() {
+            &*self.new
+        } else {
+            &*self.old
+        }
+    }
+}
+
+impl fmt::Debug for MigratingSecretsController {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MigratingSecretsController")
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SecretsController for MigratingSecretsController {
+    async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, anyhow::Error> {
+        let mut new_ids: HashSet<_> = self.new.list().await?.into_iter().collect();
+        let mut old_ids: HashSet<_> = self.old.list().await?.into_iter().collect();
+        let (new_ops, old_ops) = route_ops(ops, &mut new_ids, &mut old_ids);
+
+        let versions = if new_ops.is_empty() {
+            vec![]
+        } else {
+            self.new.apply(new_ops).await?
+        };
+        if !old_ops.is_empty() {
+            if let Err(e) = self.old.apply(old_ops).await {
+                warn!(
+                    "failed to apply secret operations to the old secrets backend, which \
+                     will diverge from the new backend until secrets are reconciled: {:#}",
+                    e
+                );
+            }
+        }
+        Ok(versions)
+    }
+
+    async fn read(&self, id: GlobalId) -> Result<Vec<u8>, anyhow::Error> {
+        self.backend_for(id).await.read(id).await
+    }
+
+    async fn read_version(
+        &self,
+        id: GlobalId,
+        version: SecretVersion,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        self.backend_for(id).await.read_version(id, version).await
+    }
+
+    async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, anyhow::Error> {
+        self.backend_for(id).await.current_version(id).await
+    }
+
+    async fn list(&self) -> Result<Vec<GlobalId>, anyhow::Error> {
+        let mut ids: BTreeSet<_> = self.new.list().await?.into_iter().collect();
+        ids.extend(self.old.list().await?);
+        Ok(ids.into_iter().collect())
+    }
+
+    fn can_list(&self) -> bool {
+        self.old.can_list() && self.new.can_list()
+    }
+
+    async fn migrate(&self) -> Result<usize, anyhow::Error> {
+        let new_ids: HashSet<_> = self.new.list().await?.into_iter().collect();
+        let mut copied = 0;
+        // Copy the secrets one at a time, so that a migration that fails
+        // partway through keeps its progress.
+        for id in self.old.list().await? {
+            if !new_ids.contains(&id) {
+                let contents = self.old.read(id).await?;
+                self.new
+                    .apply(vec![SecretOp::Ensure {
+                        id,
+                        contents: contents.clone(),
+                    }])
+                    .await?;
+                // Only count the secret as copied once the new backend
+                // returns it, as the old backend may be switched off on the
+                // strength of the count.
+                match self.new.read(id).await {
+                    Ok(stored) if stored == contents => copied += 1,
+                    Ok(_) => bail!(
+                        "new secrets backend stored different contents for secret {}",
+                        id
+                    ),
+                    Err(e) => {
+                        return Err(
+                            e.context(format!("new secrets backend did not store secret {}", id))
+                        )
+                    }
+                }
+            }
+        }
+        Ok(copied)
+    }
+}
+
+/// Splits `ops` into the operations to apply to the new backend and the
+/// operations to apply to the old backend, given the IDs of the secrets that
+/// each backend stores.
+///
+/// Every secret is ensured in both backends. A deletion applies to each
+/// backend that stores the secret; a deletion of a secret that neither
+/// backend stores applies to the new backend, so that it fails as it would
+/// without a migration. A deletion of a version applies only to the backend
+/// from which the secret is read, as the backends number versions
+/// independently.
+fn route_ops(
+    ops: Vec<SecretOp>,
+    new_ids: &mut HashSet<GlobalId>,
+    old_ids: &mut HashSet<GlobalId>,
+) -> (Vec<SecretOp>, Vec<SecretOp>) {
+    let mut new_ops = vec![];
+    let mut old_ops = vec![];
+    for op in ops {
+        match &op {
+            SecretOp::Ensure { id, .. } => {
+                new_ids.insert(*id);
+                old_ids.insert(*id);
+                old_ops.push(op.clone());
+                new_ops.push(op);
+            }
+            SecretOp::Delete { id, version: None } => {
+                let in_new = new_ids.remove(id);
+                let in_old = old_ids.remove(id);
+                if in_old {
+                    old_ops.push(op.clone());
+                }
+                if in_new || !in_old {
+                    new_ops.push(op);
+                }
+            }
+            SecretOp::Delete {
+                id,
+                version: Some(_),
+            } => {
+                if new_ids.contains(id) || !old_ids.contains(id) {
+                    new_ops.push(op);
+                } else {
+                    old_ops.push(op);
+                }
+            }
+        }
+    }
+    (new_ops, old_ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InMemorySecretsController;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dual_write() -> Result<(), anyhow::Error> {
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let old = InMemorySecretsController::new();
+        let new = InMemorySecretsController::new();
+        old.apply(vec![ensure(a, b"hunter1")]).await?;
+
+        let controller =
+            MigratingSecretsController::new(Box::new(old.clone()), Box::new(new.clone()));
+
+        // Secrets that have not been copied are read from the old backend.
+        assert_eq!(controller.read(a).await?, b"hunter1");
+        assert_eq!(controller.list().await?, vec![a]);
+
+        // New secrets are written to both backends.
+        let versions = controller.apply(vec![ensure(b, b"hunter2")]).await?;
+        assert_eq!(versions, vec![SecretVersion(1)]);
+        assert_eq!(old.read(b).await?, b"hunter2");
+        assert_eq!(new.read(b).await?, b"hunter2");
+        assert_eq!(controller.list().await?, vec![a, b]);
+
+        // Deleting a secret that only the old backend stores succeeds.
+        controller.apply(vec![delete(a, None)]).await?;
+        assert!(controller.read(a).await.is_err());
+        assert_eq!(old.list().await?, vec![b]);
+
+        // Deleting a secret that neither backend stores fails.
+        assert!(controller.apply(vec![delete(a, None)]).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate() -> Result<(), anyhow::Error> {
+        let (a, b) = (GlobalId::User(1), GlobalId::User(2));
+        let old = InMemorySecretsController::new();
+        let new = InMemorySecretsController::new();
+        old.apply(vec![ensure(a, b"hunter1"), ensure(a, b"hunter2")])
+            .await?;
+
+        let controller =
+            MigratingSecretsController::new(Box::new(old.clone()), Box::new(new.clone()));
+        controller.apply(vec![ensure(b, b"hunter3")]).await?;
+
+        // Only the secret that the new backend lacks is copied, and only its
+        // current version.
+        assert_eq!(controller.migrate().await?, 1);
+        assert_eq!(new.read(a).await?, b"hunter2");
+        assert_eq!(new.current_version(a).await?, SecretVersion(1));
+        assert_eq!(controller.current_version(a).await?, SecretVersion(1));
+        assert_eq!(controller.migrate().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_unstored() -> Result<(), anyhow::Error> {
+        let a = GlobalId::User(1);
+        let old = InMemorySecretsController::new();
+        old.apply(vec![ensure(a, b"hunter1")]).await?;
+
+        // A backend that accepts writes without storing them is not counted
+        // as having received a copy.
+        let controller = MigratingSecretsController::new(Box::new(old), Box::new(Discarding));
+        let err = controller.migrate().await.unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("did not store secret u1"),
+            "{:#}",
+            err
+        );
+        Ok(())
+    }
+
+    /// A backend that reports every write as applied, but stores nothing.
+    #[derive(Debug)]
+    struct Discarding;
+
+    #[async_trait]
+    impl SecretsController for Discarding {
+        async fn apply(&self, ops: Vec<SecretOp>) -> Result<Vec<SecretVersion>, anyhow::Error> {
+            Ok(ops.iter().map(|_| SecretVersion(1)).collect())
+        }
+
+        async fn read(&self, id: GlobalId) -> Result<Vec<u8>, anyhow::Error> {
+            bail!("secret {} does not exist", id)
+        }
+
+        async fn read_version(
+            &self,
+            id: GlobalId,
+            _: SecretVersion,
+        ) -> Result<Vec<u8>, anyhow::Error> {
+            bail!("secret {} does not exist", id)
+        }
+
+        async fn current_version(&self, id: GlobalId) -> Result<SecretVersion, anyhow::Error> {
+            bail!("secret {} does not exist", id)
+        }
+
+        async fn list(&self) -> Result<Vec<GlobalId>, anyhow::Error> {
+            Ok(vec![])
+        }
+    }
+
+    fn ensure(id: GlobalId, contents: &[u8]) -> SecretOp {
+        SecretOp::Ensure {
+            id,
+            contents: contents.to_vec(),
+        }
+    }
+
+    fn delete(id: GlobalId, version: Option<SecretVersion>) -> SecretOp {
+        SecretOp::Delete { id, version }
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Application of secret operations to storage that cannot apply several
+//! operations atomically.
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::{SecretOp, SecretVersion};
+
+/// Storage that applies secret operations one at a time, and that can revert
+/// the changes that it has made.
+///
+/// Such storage can approximate the atomicity that
+/// [`SecretsController::apply`](crate::SecretsController::apply) requires
+/// with [`apply_reverting`].
+#[async_trait]
+pub trait RevertibleStorage: Sync {
+    /// A change that applying an operation made, which records what is
+    /// needed to revert it.
+    type Change: Send;
+
+    /// Applies `op`.
+    ///
+    /// Returns the version that the operation created, if it is a
+    /// [`SecretOp::Ensure`], and the change that it made, if it made one that
+    /// can be reverted.
+    async fn apply_op(
+        &self,
+        op: &SecretOp,
+    ) -> Result<(Option<SecretVersion>, Option<Self::Change>), anyhow::Error>;
+
+    /// Reverts `change`.
+    async fn revert(&self, change: Self::Change) -> Result<(), anyhow::Error>;
+}
+
+/// Applies `ops` to `storage` one at a time, and returns the versions that
+/// the [`SecretOp::Ensure`] operations created.
+///
+/// If an operation fails, the changes made by the operations that preceded it
+/// are reverted in reverse order, and the operation's error is returned. The
+/// reversion is best effort: a change that cannot be reverted is logged, and
+/// the remaining changes are still reverted.
+pub async fn apply_reverting<S>(
+    storage: &S,
+    ops: &[SecretOp],
+) -> Result<Vec<SecretVersion>, anyhow::Error>
+where
+    S: RevertibleStorage + ?Sized,
+{
+    let mut changes = vec![];
+    let mut ensured = vec![];
+    let mut res = Ok(());
+    for op in ops {
+        match storage.apply_op(op).await {
+            Ok((version, change)) => {
+                ensured.extend(version);
+                changes.extend(change);
+            }
+            Err(e) => {
+                res = Err(e);
+                break;
+            }
+        }
+    }
+    if res.is_err() {
+        for change in changes.into_iter().rev() {
+            if let Err(e) = storage.revert(change).await {
+                warn!("failed to revert a change to secrets storage: {:#}", e);
+            }
+        }
+    }
+    res.map(|()| ensured)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::bail;
+    use mz_expr::GlobalId;
+
+    use super::*;
+
+    /// Storage that records the secrets it has ensured, and fails to apply
+    /// operations on the secret `u0`.
+    #[derive(Default)]
+    struct Log {
+        applied: Mutex<Vec<GlobalId>>,
+    }
+
+    #[async_trait]
+    impl RevertibleStorage for Log {
+        type Change = GlobalId;
+
+        async fn apply_op(
+            &self,
+            op: &SecretOp,
+        ) -> Result<(Option<SecretVersion>, Option<GlobalId>), anyhow::Error> {
+            let id = op.id();
+            if id == GlobalId::User(0) {
+                bail!("storage unavailable");
+            }
+            match op {
+                SecretOp::Ensure { .. } => {
+                    self.applied.lock().expect("lock poisoned").push(id);
+                    Ok((Some(SecretVersion(1)), Some(id)))
+                }
+                SecretOp::Delete { .. } => Ok((None, None)),
+            }
+        }
+
+        async fn revert(&self, id: GlobalId) -> Result<(), anyhow::Error> {
+            let mut applied = self.applied.lock().expect("lock poisoned");
+            assert_eq!(applied.pop(), Some(id), "changes reverted out of order");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_reverting() -> Result<(), anyhow::Error> {
+        let ensure = |id| SecretOp::Ensure {
+            id: GlobalId::User(id),
+            contents: vec![],
+        };
+        let storage = Log::default();
+
+        let versions = apply_reverting(&storage, &[ensure(1), ensure(2)]).await?;
+        assert_eq!(versions, vec![SecretVersion(1); 2]);
+
+        // The deletion made no revertible change, so only the ensures are
+        // reverted.
+        let delete = SecretOp::Delete {
+            id: GlobalId::User(3),
+            version: None,
+        };
+        let res = apply_reverting(&storage, &[ensure(4), delete, ensure(5), ensure(0)]).await;
+        assert_eq!(res.unwrap_err().to_string(), "storage unavailable");
+        assert_eq!(
+            *storage.applied.lock().expect("lock poisoned"),
+            vec![GlobalId::User(1), GlobalId::User(2)]
+        );
+        Ok(())
+    }
+}
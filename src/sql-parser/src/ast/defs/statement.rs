@@ -48,20 +48,33 @@ pub enum Statement<T: AstInfo> {
     CreateRole(CreateRoleStatement),
     CreateCluster(CreateClusterStatement),
     CreateSecret(CreateSecretStatement<T>),
+    CreatePublication(CreatePublicationStatement<T>),
+    CreateTask(CreateTaskStatement<T>),
     AlterObjectRename(AlterObjectRenameStatement<T>),
+    AlterDatabaseRename(AlterDatabaseRenameStatement<T>),
+    AlterSchemaRename(AlterSchemaRenameStatement<T>),
     AlterIndex(AlterIndexStatement<T>),
     AlterSecret(AlterSecretStatement<T>),
     AlterCluster(AlterClusterStatement),
+    AlterRole(AlterRoleStatement),
+    AlterRoleSet(AlterRoleSetStatement),
+    AlterSystemSet(AlterSystemSetStatement),
+    GrantPrivileges(GrantPrivilegesStatement<T>),
+    RevokePrivileges(RevokePrivilegesStatement<T>),
+    Comment(CommentStatement<T>),
     Discard(DiscardStatement),
     DropDatabase(DropDatabaseStatement<T>),
     DropSchema(DropSchemaStatement<T>),
     DropObjects(DropObjectsStatement<T>),
     DropRoles(DropRolesStatement),
     DropClusters(DropClustersStatement),
+    Undrop(UndropStatement),
     SetVariable(SetVariableStatement),
+    ResetVariable(ResetVariableStatement),
     ShowDatabases(ShowDatabasesStatement<T>),
     ShowSchemas(ShowSchemasStatement<T>),
     ShowObjects(ShowObjectsStatement<T>),
+    ShowDroppedObjects(ShowDroppedObjectsStatement<T>),
     ShowIndexes(ShowIndexesStatement<T>),
     ShowColumns(ShowColumnsStatement<T>),
     ShowCreateView(ShowCreateViewStatement<T>),
@@ -76,6 +89,7 @@ pub enum Statement<T: AstInfo> {
     Rollback(RollbackStatement),
     Tail(TailStatement<T>),
     Explain(ExplainStatement<T>),
+    ExplainDrop(ExplainDropStatement<T>),
     Declare(DeclareStatement<T>),
     Fetch(FetchStatement),
     Close(CloseStatement),
@@ -103,22 +117,35 @@ impl<T: AstInfo> AstDisplay for Statement<T> {
             Statement::CreateIndex(stmt) => f.write_node(stmt),
             Statement::CreateRole(stmt) => f.write_node(stmt),
             Statement::CreateSecret(stmt) => f.write_node(stmt),
+            Statement::CreatePublication(stmt) => f.write_node(stmt),
+            Statement::CreateTask(stmt) => f.write_node(stmt),
             Statement::CreateType(stmt) => f.write_node(stmt),
             Statement::CreateCluster(stmt) => f.write_node(stmt),
             Statement::AlterObjectRename(stmt) => f.write_node(stmt),
+            Statement::AlterDatabaseRename(stmt) => f.write_node(stmt),
+            Statement::AlterSchemaRename(stmt) => f.write_node(stmt),
             Statement::AlterIndex(stmt) => f.write_node(stmt),
             Statement::AlterSecret(stmt) => f.write_node(stmt),
             Statement::AlterCluster(stmt) => f.write_node(stmt),
+            Statement::AlterRole(stmt) => f.write_node(stmt),
+            Statement::AlterRoleSet(stmt) => f.write_node(stmt),
+            Statement::AlterSystemSet(stmt) => f.write_node(stmt),
+            Statement::GrantPrivileges(stmt) => f.write_node(stmt),
+            Statement::RevokePrivileges(stmt) => f.write_node(stmt),
+            Statement::Comment(stmt) => f.write_node(stmt),
             Statement::Discard(stmt) => f.write_node(stmt),
             Statement::DropDatabase(stmt) => f.write_node(stmt),
             Statement::DropSchema(stmt) => f.write_node(stmt),
             Statement::DropObjects(stmt) => f.write_node(stmt),
             Statement::DropRoles(stmt) => f.write_node(stmt),
             Statement::DropClusters(stmt) => f.write_node(stmt),
+            Statement::Undrop(stmt) => f.write_node(stmt),
             Statement::SetVariable(stmt) => f.write_node(stmt),
+            Statement::ResetVariable(stmt) => f.write_node(stmt),
             Statement::ShowDatabases(stmt) => f.write_node(stmt),
             Statement::ShowSchemas(stmt) => f.write_node(stmt),
             Statement::ShowObjects(stmt) => f.write_node(stmt),
+            Statement::ShowDroppedObjects(stmt) => f.write_node(stmt),
             Statement::ShowIndexes(stmt) => f.write_node(stmt),
             Statement::ShowColumns(stmt) => f.write_node(stmt),
             Statement::ShowCreateView(stmt) => f.write_node(stmt),
@@ -133,6 +160,7 @@ impl<T: AstInfo> AstDisplay for Statement<T> {
             Statement::Rollback(stmt) => f.write_node(stmt),
             Statement::Tail(stmt) => f.write_node(stmt),
             Statement::Explain(stmt) => f.write_node(stmt),
+            Statement::ExplainDrop(stmt) => f.write_node(stmt),
             Statement::Declare(stmt) => f.write_node(stmt),
             Statement::Close(stmt) => f.write_node(stmt),
             Statement::Fetch(stmt) => f.write_node(stmt),
@@ -149,20 +177,43 @@ impl_display_t!(Statement);
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SelectStatement<T: AstInfo> {
     pub query: Query<T>,
-    pub as_of: Option<Expr<T>>,
+    pub as_of: Option<AsOf<T>>,
 }
 
 impl<T: AstInfo> AstDisplay for SelectStatement<T> {
     fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
         f.write_node(&self.query);
         if let Some(as_of) = &self.as_of {
-            f.write_str(" AS OF ");
+            f.write_str(" ");
             f.write_node(as_of);
         }
     }
 }
 impl_display_t!(SelectStatement);
 
+/// The `AS OF` clause of a `SELECT` or `TAIL`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AsOf<T: AstInfo> {
+    /// `AS OF <expr>`
+    At(Expr<T>),
+    /// `AS OF AT LEAST <expr>`
+    AtLeast(Expr<T>),
+}
+
+impl<T: AstInfo> AstDisplay for AsOf<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("AS OF ");
+        match self {
+            AsOf::At(expr) => f.write_node(expr),
+            AsOf::AtLeast(expr) => {
+                f.write_str("AT LEAST ");
+                f.write_node(expr);
+            }
+        }
+    }
+}
+impl_display_t!(AsOf);
+
 /// `INSERT`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct InsertStatement<T: AstInfo> {
@@ -767,7 +818,8 @@ impl AstDisplay for CreateRoleStatement {
 }
 impl_display!(CreateRoleStatement);
 
-/// Options that can be attached to [`CreateRoleStatement`].
+/// Options that can be attached to [`CreateRoleStatement`] and
+/// [`AlterRoleStatement`].
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CreateRoleOption {
     /// The `SUPERUSER` option.
@@ -778,6 +830,15 @@ pub enum CreateRoleOption {
     Login,
     /// The `NOLOGIN` option.
     NoLogin,
+    /// The `PASSWORD '...'` option, or `PASSWORD NULL` if the password is
+    /// `None`.
+    Password(Option<String>),
+    /// The `REQUIRE TLS` option.
+    RequireTls,
+    /// The `ALLOWED ADDRESSES (...)` option.
+    AllowedAddresses(Vec<String>),
+    /// The `ALLOWED CLUSTERS (...)` option.
+    AllowedClusters(Vec<Ident>),
 }
 
 impl AstDisplay for CreateRoleOption {
@@ -787,6 +848,30 @@ impl AstDisplay for CreateRoleOption {
             CreateRoleOption::NoSuperUser => f.write_str("NOSUPERUSER"),
             CreateRoleOption::Login => f.write_str("LOGIN"),
             CreateRoleOption::NoLogin => f.write_str("NOLOGIN"),
+            CreateRoleOption::Password(None) => f.write_str("PASSWORD NULL"),
+            CreateRoleOption::Password(Some(password)) => {
+                f.write_str("PASSWORD '");
+                f.write_node(&display::escape_single_quote_string(password));
+                f.write_str("'");
+            }
+            CreateRoleOption::RequireTls => f.write_str("REQUIRE TLS"),
+            CreateRoleOption::AllowedAddresses(addrs) => {
+                f.write_str("ALLOWED ADDRESSES (");
+                for (i, addr) in addrs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ");
+                    }
+                    f.write_str("'");
+                    f.write_node(&display::escape_single_quote_string(addr));
+                    f.write_str("'");
+                }
+                f.write_str(")");
+            }
+            CreateRoleOption::AllowedClusters(clusters) => {
+                f.write_str("ALLOWED CLUSTERS (");
+                f.write_node(&display::comma_separated(clusters));
+                f.write_str(")");
+            }
         }
     }
 }
@@ -798,6 +883,7 @@ pub struct CreateSecretStatement<T: AstInfo> {
     pub name: UnresolvedObjectName,
     pub if_not_exists: bool,
     pub value: Expr<T>,
+    pub with_options: Vec<SqlOption<T>>,
 }
 
 impl<T: AstInfo> AstDisplay for CreateSecretStatement<T> {
@@ -809,10 +895,63 @@ impl<T: AstInfo> AstDisplay for CreateSecretStatement<T> {
         f.write_node(&self.name);
         f.write_str(" AS ");
         f.write_node(&self.value);
+        if !self.with_options.is_empty() {
+            f.write_str(" WITH (");
+            f.write_node(&display::comma_separated(&self.with_options));
+            f.write_str(")");
+        }
     }
 }
 impl_display_t!(CreateSecretStatement);
 
+/// A `CREATE PUBLICATION` statement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CreatePublicationStatement<T: AstInfo> {
+    pub name: UnresolvedObjectName,
+    pub if_not_exists: bool,
+    /// The views that the publication publishes.
+    pub views: Vec<T::ObjectName>,
+}
+
+impl<T: AstInfo> AstDisplay for CreatePublicationStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("CREATE PUBLICATION ");
+        if self.if_not_exists {
+            f.write_str("IF NOT EXISTS ");
+        }
+        f.write_node(&self.name);
+        f.write_str(" FOR VIEW ");
+        f.write_node(&display::comma_separated(&self.views));
+    }
+}
+impl_display_t!(CreatePublicationStatement);
+
+/// A `CREATE TASK` statement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CreateTaskStatement<T: AstInfo> {
+    pub name: UnresolvedObjectName,
+    pub if_not_exists: bool,
+    /// The interval at which the task runs, e.g. `5 minutes`.
+    pub schedule: String,
+    /// The statement that the task runs.
+    pub statement: InsertStatement<T>,
+}
+
+impl<T: AstInfo> AstDisplay for CreateTaskStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("CREATE TASK ");
+        if self.if_not_exists {
+            f.write_str("IF NOT EXISTS ");
+        }
+        f.write_node(&self.name);
+        f.write_str(" SCHEDULE '");
+        f.write_node(&display::escape_single_quote_string(&self.schedule));
+        f.write_str("' AS ");
+        f.write_node(&self.statement);
+    }
+}
+impl_display_t!(CreateTaskStatement);
+
 /// `CREATE TYPE ..`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CreateTypeStatement<T: AstInfo> {
@@ -890,6 +1029,8 @@ pub enum ClusterOption {
     IntrospectionGranularity(WithOptionValue),
     /// The `INTROSPECTION DEBUGGING [[=] <enabled>] option.
     IntrospectionDebugging(WithOptionValue),
+    /// The `INTROSPECTION RETENTION [[=] <interval>] option.
+    IntrospectionRetention(WithOptionValue),
 }
 
 impl AstDisplay for ClusterOption {
@@ -914,6 +1055,10 @@ impl AstDisplay for ClusterOption {
                 f.write_str("INTROSPECTION DEBUGGING ");
                 f.write_node(debugging);
             }
+            ClusterOption::IntrospectionRetention(retention) => {
+                f.write_str("INTROSPECTION RETENTION ");
+                f.write_node(retention);
+            }
         }
     }
 }
@@ -961,6 +1106,108 @@ impl<T: AstInfo> AstDisplay for AlterObjectRenameStatement<T> {
 }
 impl_display_t!(AlterObjectRenameStatement);
 
+/// `ALTER DATABASE ... RENAME TO ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlterDatabaseRenameStatement<T: AstInfo> {
+    pub name: T::DatabaseName,
+    pub if_exists: bool,
+    pub to_name: Ident,
+}
+
+impl<T: AstInfo> AstDisplay for AlterDatabaseRenameStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("ALTER DATABASE ");
+        if self.if_exists {
+            f.write_str("IF EXISTS ");
+        }
+        f.write_node(&self.name);
+        f.write_str(" RENAME TO ");
+        f.write_node(&self.to_name);
+    }
+}
+impl_display_t!(AlterDatabaseRenameStatement);
+
+/// `ALTER SCHEMA ... RENAME TO ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlterSchemaRenameStatement<T: AstInfo> {
+    pub name: T::SchemaName,
+    pub if_exists: bool,
+    pub to_name: Ident,
+}
+
+impl<T: AstInfo> AstDisplay for AlterSchemaRenameStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("ALTER SCHEMA ");
+        if self.if_exists {
+            f.write_str("IF EXISTS ");
+        }
+        f.write_node(&self.name);
+        f.write_str(" RENAME TO ");
+        f.write_node(&self.to_name);
+    }
+}
+impl_display_t!(AlterSchemaRenameStatement);
+
+/// `COMMENT ON ... IS ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommentStatement<T: AstInfo> {
+    pub object: CommentObject<T>,
+    /// The new comment, or `None` to remove the existing comment.
+    pub comment: Option<String>,
+}
+
+impl<T: AstInfo> AstDisplay for CommentStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("COMMENT ON ");
+        f.write_node(&self.object);
+        f.write_str(" IS ");
+        match &self.comment {
+            Some(comment) => {
+                f.write_str("'");
+                f.write_node(&display::escape_single_quote_string(comment));
+                f.write_str("'");
+            }
+            None => f.write_str("NULL"),
+        }
+    }
+}
+impl_display_t!(CommentStatement);
+
+/// The object that a [`CommentStatement`] applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CommentObject<T: AstInfo> {
+    Item {
+        object_type: ObjectType,
+        name: T::ObjectName,
+    },
+    Column {
+        relation_name: T::ObjectName,
+        column_name: Ident,
+    },
+}
+
+impl<T: AstInfo> AstDisplay for CommentObject<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        match self {
+            CommentObject::Item { object_type, name } => {
+                f.write_node(object_type);
+                f.write_str(" ");
+                f.write_node(name);
+            }
+            CommentObject::Column {
+                relation_name,
+                column_name,
+            } => {
+                f.write_str("COLUMN ");
+                f.write_node(relation_name);
+                f.write_str(".");
+                f.write_node(column_name);
+            }
+        }
+    }
+}
+impl_display_t!(CommentObject);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AlterIndexAction {
     SetOptions(Vec<WithOption>),
@@ -1052,6 +1299,206 @@ impl AstDisplay for AlterClusterStatement {
 
 impl_display!(AlterClusterStatement);
 
+/// `ALTER ROLE ... [WITH] <options>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlterRoleStatement {
+    pub name: Ident,
+    /// The options to change, in the order they were presented.
+    pub options: Vec<CreateRoleOption>,
+}
+
+impl AstDisplay for AlterRoleStatement {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("ALTER ROLE ");
+        f.write_node(&self.name);
+        for option in &self.options {
+            f.write_str(" ");
+            option.fmt(f)
+        }
+    }
+}
+
+impl_display!(AlterRoleStatement);
+
+/// `ALTER ROLE ... SET ...` or `ALTER ROLE ... RESET ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlterRoleSetStatement {
+    /// The role whose default to change, or `None` to change the default for
+    /// every role.
+    pub name: Option<Ident>,
+    pub variable: Ident,
+    /// The new default, or `None` to remove the existing default.
+    pub value: Option<SetVariableValue>,
+}
+
+impl AstDisplay for AlterRoleSetStatement {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("ALTER ROLE ");
+        match &self.name {
+            Some(name) => f.write_node(name),
+            None => f.write_str("ALL"),
+        }
+        match &self.value {
+            Some(value) => {
+                f.write_str(" SET ");
+                f.write_node(&self.variable);
+                f.write_str(" = ");
+                f.write_node(value);
+            }
+            None => {
+                f.write_str(" RESET ");
+                f.write_node(&self.variable);
+            }
+        }
+    }
+}
+
+impl_display!(AlterRoleSetStatement);
+
+/// `ALTER SYSTEM SET ...` or `ALTER SYSTEM RESET ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlterSystemSetStatement {
+    pub variable: Ident,
+    /// The new value, or `None` to restore the default value.
+    pub value: Option<SetVariableValue>,
+}
+
+impl AstDisplay for AlterSystemSetStatement {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        match &self.value {
+            Some(value) => {
+                f.write_str("ALTER SYSTEM SET ");
+                f.write_node(&self.variable);
+                f.write_str(" = ");
+                f.write_node(value);
+            }
+            None => {
+                f.write_str("ALTER SYSTEM RESET ");
+                f.write_node(&self.variable);
+            }
+        }
+    }
+}
+
+impl_display!(AlterSystemSetStatement);
+
+/// `GRANT ... ON ... TO ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GrantPrivilegesStatement<T: AstInfo> {
+    /// The privileges to grant, or `None` for `ALL PRIVILEGES`.
+    pub privileges: Option<Vec<Privilege>>,
+    pub object: PrivilegeObject<T>,
+    /// The roles to which to grant the privileges, where `None` stands for
+    /// `PUBLIC`.
+    pub roles: Vec<Option<Ident>>,
+}
+
+impl<T: AstInfo> AstDisplay for GrantPrivilegesStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("GRANT ");
+        display_privileges(f, &self.privileges);
+        f.write_str(" ON ");
+        f.write_node(&self.object);
+        f.write_str(" TO ");
+        display_privilege_roles(f, &self.roles);
+    }
+}
+impl_display_t!(GrantPrivilegesStatement);
+
+/// `REVOKE ... ON ... FROM ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RevokePrivilegesStatement<T: AstInfo> {
+    /// The privileges to revoke, or `None` for `ALL PRIVILEGES`.
+    pub privileges: Option<Vec<Privilege>>,
+    pub object: PrivilegeObject<T>,
+    /// The roles from which to revoke the privileges, where `None` stands for
+    /// `PUBLIC`.
+    pub roles: Vec<Option<Ident>>,
+}
+
+impl<T: AstInfo> AstDisplay for RevokePrivilegesStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("REVOKE ");
+        display_privileges(f, &self.privileges);
+        f.write_str(" ON ");
+        f.write_node(&self.object);
+        f.write_str(" FROM ");
+        display_privilege_roles(f, &self.roles);
+    }
+}
+impl_display_t!(RevokePrivilegesStatement);
+
+fn display_privileges<W: fmt::Write>(f: &mut AstFormatter<W>, privileges: &Option<Vec<Privilege>>) {
+    match privileges {
+        Some(privileges) => f.write_node(&display::comma_separated(privileges)),
+        None => f.write_str("ALL PRIVILEGES"),
+    }
+}
+
+fn display_privilege_roles<W: fmt::Write>(f: &mut AstFormatter<W>, roles: &[Option<Ident>]) {
+    for (i, role) in roles.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ");
+        }
+        match role {
+            Some(role) => f.write_node(role),
+            None => f.write_str("PUBLIC"),
+        }
+    }
+}
+
+/// A privilege that can be granted on an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Create,
+}
+
+impl AstDisplay for Privilege {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str(match self {
+            Privilege::Select => "SELECT",
+            Privilege::Insert => "INSERT",
+            Privilege::Update => "UPDATE",
+            Privilege::Delete => "DELETE",
+            Privilege::Create => "CREATE",
+        })
+    }
+}
+impl_display!(Privilege);
+
+/// The object to which a [`GrantPrivilegesStatement`] or
+/// [`RevokePrivilegesStatement`] applies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PrivilegeObject<T: AstInfo> {
+    Table(T::ObjectName),
+    Schema(T::SchemaName),
+    Database(T::DatabaseName),
+}
+
+impl<T: AstInfo> AstDisplay for PrivilegeObject<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        match self {
+            PrivilegeObject::Table(name) => {
+                f.write_str("TABLE ");
+                f.write_node(name);
+            }
+            PrivilegeObject::Schema(name) => {
+                f.write_str("SCHEMA ");
+                f.write_node(name);
+            }
+            PrivilegeObject::Database(name) => {
+                f.write_str("DATABASE ");
+                f.write_node(name);
+            }
+        }
+    }
+}
+impl_display_t!(PrivilegeObject);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DiscardStatement {
     pub target: DiscardTarget,
@@ -1203,6 +1650,21 @@ impl AstDisplay for DropClustersStatement {
 }
 impl_display!(DropClustersStatement);
 
+/// `UNDROP <name>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UndropStatement {
+    /// The name of the dropped object to restore.
+    pub name: UnresolvedObjectName,
+}
+
+impl AstDisplay for UndropStatement {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("UNDROP ");
+        f.write_node(&self.name);
+    }
+}
+impl_display!(UndropStatement);
+
 /// `SET <variable>`
 ///
 /// Note: this is not a standard SQL statement, but it is supported by at
@@ -1212,7 +1674,8 @@ impl_display!(DropClustersStatement);
 pub struct SetVariableStatement {
     pub local: bool,
     pub variable: Ident,
-    pub value: SetVariableValue,
+    /// The new value, or `None` to restore the default value.
+    pub value: Option<SetVariableValue>,
 }
 
 impl AstDisplay for SetVariableStatement {
@@ -1223,11 +1686,32 @@ impl AstDisplay for SetVariableStatement {
         }
         f.write_node(&self.variable);
         f.write_str(" = ");
-        f.write_node(&self.value);
+        match &self.value {
+            Some(value) => f.write_node(value),
+            None => f.write_str("DEFAULT"),
+        }
     }
 }
 impl_display!(SetVariableStatement);
 
+/// `RESET <variable>` or `RESET ALL`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResetVariableStatement {
+    /// The variable to reset, or `None` to reset all variables.
+    pub variable: Option<Ident>,
+}
+
+impl AstDisplay for ResetVariableStatement {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("RESET ");
+        match &self.variable {
+            Some(variable) => f.write_node(variable),
+            None => f.write_str("ALL"),
+        }
+    }
+}
+impl_display!(ResetVariableStatement);
+
 /// `SHOW <variable>`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ShowVariableStatement {
@@ -1332,6 +1816,8 @@ impl<T: AstInfo> AstDisplay for ShowObjectsStatement<T> {
             ObjectType::Cluster => "CLUSTERS",
             ObjectType::Object => "OBJECTS",
             ObjectType::Secret => "SECRETS",
+            ObjectType::Publication => "PUBLICATIONS",
+            ObjectType::Task => "TASKS",
             ObjectType::Index => unreachable!(),
         });
         if let Some(from) = &self.from {
@@ -1346,12 +1832,30 @@ impl<T: AstInfo> AstDisplay for ShowObjectsStatement<T> {
 }
 impl_display_t!(ShowObjectsStatement);
 
+/// `SHOW DROPPED OBJECTS`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShowDroppedObjectsStatement<T: AstInfo> {
+    pub filter: Option<ShowStatementFilter<T>>,
+}
+
+impl<T: AstInfo> AstDisplay for ShowDroppedObjectsStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("SHOW DROPPED OBJECTS");
+        if let Some(filter) = &self.filter {
+            f.write_str(" ");
+            f.write_node(filter);
+        }
+    }
+}
+impl_display_t!(ShowDroppedObjectsStatement);
+
 /// `SHOW INDEX|INDEXES|KEYS`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ShowIndexesStatement<T: AstInfo> {
     pub table_name: Option<T::ObjectName>,
     pub in_cluster: Option<T::ClusterName>,
     pub extended: bool,
+    pub full: bool,
     pub filter: Option<ShowStatementFilter<T>>,
 }
 
@@ -1361,6 +1865,9 @@ impl<T: AstInfo> AstDisplay for ShowIndexesStatement<T> {
         if self.extended {
             f.write_str("EXTENDED ");
         }
+        if self.full {
+            f.write_str("FULL ");
+        }
         f.write_str("INDEXES ");
         if let Some(table_name) = &self.table_name {
             f.write_str("FROM ");
@@ -1549,7 +2056,8 @@ impl_display!(RollbackStatement);
 pub struct TailStatement<T: AstInfo> {
     pub relation: TailRelation<T>,
     pub options: Vec<WithOption>,
-    pub as_of: Option<Expr<T>>,
+    pub as_of: Option<AsOf<T>>,
+    pub up_to: Option<Expr<T>>,
 }
 
 impl<T: AstInfo> AstDisplay for TailStatement<T> {
@@ -1562,9 +2070,13 @@ impl<T: AstInfo> AstDisplay for TailStatement<T> {
             f.write_str(")");
         }
         if let Some(as_of) = &self.as_of {
-            f.write_str(" AS OF ");
+            f.write_str(" ");
             f.write_node(as_of);
         }
+        if let Some(up_to) = &self.up_to {
+            f.write_str(" UP TO ");
+            f.write_node(up_to);
+        }
     }
 }
 impl_display_t!(TailStatement);
@@ -1600,21 +2112,50 @@ pub struct ExplainStatement<T: AstInfo> {
 impl<T: AstInfo> AstDisplay for ExplainStatement<T> {
     fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
         f.write_str("EXPLAIN ");
-        if self.options.timing {
-            f.write_str("(TIMING ");
-            f.write_str(self.options.timing);
-            f.write_str(") ");
-        }
         if self.options.typed {
             f.write_str("TYPED ");
         }
+        let mut options = vec![];
+        if self.options.timing {
+            options.push("TIMING true");
+        }
+        if self.options.attributes {
+            options.push("ATTRIBUTES true");
+        }
+        if self.options.format == ExplainFormat::Json {
+            options.push("FORMAT JSON");
+        }
+        if !options.is_empty() {
+            f.write_str("(");
+            f.write_str(options.join(", "));
+            f.write_str(") ");
+        }
         f.write_node(&self.stage);
-        f.write_str(" FOR ");
+        if self.stage == ExplainStage::Analyze {
+            f.write_str(" ");
+        } else {
+            f.write_str(" FOR ");
+        }
         f.write_node(&self.explainee);
     }
 }
 impl_display_t!(ExplainStatement);
 
+/// `EXPLAIN DROP ...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExplainDropStatement<T: AstInfo> {
+    /// The `DROP` statement to explain.
+    pub stmt: Box<Statement<T>>,
+}
+
+impl<T: AstInfo> AstDisplay for ExplainDropStatement<T> {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        f.write_str("EXPLAIN ");
+        f.write_node(&self.stmt);
+    }
+}
+impl_display_t!(ExplainDropStatement);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum InsertSource<T: AstInfo> {
     Query(Query<T>),
@@ -1643,6 +2184,8 @@ pub enum ObjectType {
     Cluster,
     Object,
     Secret,
+    Publication,
+    Task,
 }
 
 impl AstDisplay for ObjectType {
@@ -1658,6 +2201,8 @@ impl AstDisplay for ObjectType {
             ObjectType::Cluster => "CLUSTER",
             ObjectType::Object => "OBJECT",
             ObjectType::Secret => "SECRET",
+            ObjectType::Publication => "PUBLICATION",
+            ObjectType::Task => "TASK",
         })
     }
 }
@@ -1877,6 +2422,9 @@ pub enum ExplainStage {
     PhysicalPlan,
     /// The dependent and selected timestamps
     Timestamp,
+    /// The dataflow's operators, annotated with the time they spent executing
+    /// the query and the number of rows they produced
+    Analyze,
 }
 
 impl AstDisplay for ExplainStage {
@@ -1889,6 +2437,7 @@ impl AstDisplay for ExplainStage {
             ExplainStage::OptimizedPlan => f.write_str("OPTIMIZED PLAN"),
             ExplainStage::PhysicalPlan => f.write_str("PHYSICAL PLAN"),
             ExplainStage::Timestamp => f.write_str("TIMESTAMP"),
+            ExplainStage::Analyze => f.write_str("ANALYZE"),
         }
     }
 }
@@ -1904,7 +2453,29 @@ pub enum Explainee<T: AstInfo> {
 pub struct ExplainOptions {
     pub typed: bool,
     pub timing: bool,
+    /// Whether to annotate each node of the plan with its attributes.
+    pub attributes: bool,
+    pub format: ExplainFormat,
+}
+
+/// The format in which to render an `EXPLAIN`ed plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExplainFormat {
+    /// Human-readable text.
+    Text,
+    /// JSON.
+    Json,
+}
+
+impl AstDisplay for ExplainFormat {
+    fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
+        match self {
+            ExplainFormat::Text => f.write_str("TEXT"),
+            ExplainFormat::Json => f.write_str("JSON"),
+        }
+    }
 }
+impl_display!(ExplainFormat);
 
 impl<T: AstInfo> AstDisplay for Explainee<T> {
     fn fmt<W: fmt::Write>(&self, f: &mut AstFormatter<W>) {
@@ -231,18 +231,23 @@ impl<'a> Parser<'a> {
                     self.prev_token();
                     Ok(Statement::Select(SelectStatement {
                         query: self.parse_query()?,
-                        as_of: self.parse_optional_as_of()?,
+                        as_of: self.parse_optional_query_as_of()?,
                     }))
                 }
                 Token::Keyword(CREATE) => Ok(self.parse_create()?),
+                Token::Keyword(COMMENT) => Ok(self.parse_comment()?),
+                Token::Keyword(GRANT) => Ok(self.parse_grant()?),
+                Token::Keyword(REVOKE) => Ok(self.parse_revoke()?),
                 Token::Keyword(DISCARD) => Ok(self.parse_discard()?),
                 Token::Keyword(DROP) => Ok(self.parse_drop()?),
+                Token::Keyword(UNDROP) => Ok(self.parse_undrop()?),
                 Token::Keyword(DELETE) => Ok(self.parse_delete()?),
                 Token::Keyword(INSERT) => Ok(self.parse_insert()?),
                 Token::Keyword(UPDATE) => Ok(self.parse_update()?),
                 Token::Keyword(ALTER) => Ok(self.parse_alter()?),
                 Token::Keyword(COPY) => Ok(self.parse_copy()?),
                 Token::Keyword(SET) => Ok(self.parse_set()?),
+                Token::Keyword(RESET) => Ok(self.parse_reset()?),
                 Token::Keyword(SHOW) => Ok(self.parse_show()?),
                 Token::Keyword(START) => Ok(self.parse_start_transaction()?),
                 // `BEGIN` is a nonstandard but common alias for the
@@ -1578,6 +1583,10 @@ impl<'a> Parser<'a> {
             self.parse_create_table()
         } else if self.peek_keyword(SECRET) {
             self.parse_create_secret()
+        } else if self.peek_keyword(PUBLICATION) {
+            self.parse_create_publication()
+        } else if self.peek_keyword(TASK) {
+            self.parse_create_task()
         } else {
             let index = self.index;
 
@@ -1595,7 +1604,7 @@ impl<'a> Parser<'a> {
             } else {
                 self.expected(
                     self.peek_pos(),
-                    "DATABASE, SCHEMA, ROLE, USER, TYPE, INDEX, SINK, SOURCE, TABLE, SECRET or [OR REPLACE] [TEMPORARY] [MATERIALIZED] VIEW or VIEWS after CREATE",
+                    "DATABASE, SCHEMA, ROLE, USER, TYPE, INDEX, SINK, SOURCE, TABLE, SECRET, PUBLICATION, TASK or [OR REPLACE] [TEMPORARY] [MATERIALIZED] VIEW or VIEWS after CREATE",
                     self.peek_token(),
                 )
             }
@@ -2391,23 +2400,63 @@ impl<'a> Parser<'a> {
             _ => unreachable!(),
         };
         let name = self.parse_identifier()?;
+        let options = self.parse_role_options()?;
+        Ok(Statement::CreateRole(CreateRoleStatement {
+            is_user,
+            name,
+            options,
+        }))
+    }
+
+    fn parse_role_options(&mut self) -> Result<Vec<CreateRoleOption>, ParserError> {
         let _ = self.parse_keyword(WITH);
         let mut options = vec![];
         loop {
-            match self.parse_one_of_keywords(&[SUPERUSER, NOSUPERUSER, LOGIN, NOLOGIN]) {
+            match self.parse_one_of_keywords(&[
+                SUPERUSER,
+                NOSUPERUSER,
+                LOGIN,
+                NOLOGIN,
+                PASSWORD,
+                REQUIRE,
+                ALLOWED,
+            ]) {
                 None => break,
                 Some(SUPERUSER) => options.push(CreateRoleOption::SuperUser),
                 Some(NOSUPERUSER) => options.push(CreateRoleOption::NoSuperUser),
                 Some(LOGIN) => options.push(CreateRoleOption::Login),
                 Some(NOLOGIN) => options.push(CreateRoleOption::NoLogin),
+                Some(PASSWORD) => {
+                    let password = if self.parse_keyword(NULL) {
+                        None
+                    } else {
+                        Some(self.parse_literal_string()?)
+                    };
+                    options.push(CreateRoleOption::Password(password));
+                }
+                Some(REQUIRE) => {
+                    self.expect_keyword(TLS)?;
+                    options.push(CreateRoleOption::RequireTls);
+                }
+                Some(ALLOWED) => match self.expect_one_of_keywords(&[ADDRESSES, CLUSTERS])? {
+                    ADDRESSES => {
+                        self.expect_token(&Token::LParen)?;
+                        let addrs = self.parse_comma_separated(Parser::parse_literal_string)?;
+                        self.expect_token(&Token::RParen)?;
+                        options.push(CreateRoleOption::AllowedAddresses(addrs));
+                    }
+                    CLUSTERS => {
+                        self.expect_token(&Token::LParen)?;
+                        let clusters = self.parse_comma_separated(Parser::parse_identifier)?;
+                        self.expect_token(&Token::RParen)?;
+                        options.push(CreateRoleOption::AllowedClusters(clusters));
+                    }
+                    _ => unreachable!(),
+                },
                 Some(_) => unreachable!(),
             }
         }
-        Ok(Statement::CreateRole(CreateRoleStatement {
-            is_user,
-            name,
-            options,
-        }))
+        Ok(options)
     }
 
     fn parse_create_secret(&mut self) -> Result<Statement<Raw>, ParserError> {
@@ -2416,10 +2465,44 @@ impl<'a> Parser<'a> {
         let name = self.parse_object_name()?;
         self.expect_keyword(AS)?;
         let value = self.parse_expr()?;
+        let with_options = self.parse_opt_with_sql_options()?;
         Ok(Statement::CreateSecret(CreateSecretStatement {
             name,
             if_not_exists,
             value,
+            with_options,
+        }))
+    }
+
+    fn parse_create_publication(&mut self) -> Result<Statement<Raw>, ParserError> {
+        self.expect_keyword(PUBLICATION)?;
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
+        self.expect_keywords(&[FOR, VIEW])?;
+        let views = self.parse_comma_separated(Parser::parse_raw_name)?;
+        Ok(Statement::CreatePublication(CreatePublicationStatement {
+            name,
+            if_not_exists,
+            views,
+        }))
+    }
+
+    fn parse_create_task(&mut self) -> Result<Statement<Raw>, ParserError> {
+        self.expect_keyword(TASK)?;
+        let if_not_exists = self.parse_if_not_exists()?;
+        let name = self.parse_object_name()?;
+        self.expect_keyword(SCHEDULE)?;
+        let schedule = self.parse_literal_string()?;
+        self.expect_keywords(&[AS, INSERT])?;
+        let statement = match self.parse_insert()? {
+            Statement::Insert(statement) => statement,
+            _ => unreachable!("parse_insert returns an INSERT statement"),
+        };
+        Ok(Statement::CreateTask(CreateTaskStatement {
+            name,
+            if_not_exists,
+            schedule,
+            statement,
         }))
     }
 
@@ -2492,21 +2575,29 @@ impl<'a> Parser<'a> {
                 let _ = self.consume_token(&Token::Eq);
                 Ok(ClusterOption::Size(self.parse_with_option_value()?))
             }
-            INTROSPECTION => match self.expect_one_of_keywords(&[DEBUGGING, GRANULARITY])? {
-                DEBUGGING => {
-                    let _ = self.consume_token(&Token::Eq);
-                    Ok(ClusterOption::IntrospectionDebugging(
-                        self.parse_with_option_value()?,
-                    ))
-                }
-                GRANULARITY => {
-                    let _ = self.consume_token(&Token::Eq);
-                    Ok(ClusterOption::IntrospectionGranularity(
-                        self.parse_with_option_value()?,
-                    ))
+            INTROSPECTION => {
+                match self.expect_one_of_keywords(&[DEBUGGING, GRANULARITY, RETENTION])? {
+                    DEBUGGING => {
+                        let _ = self.consume_token(&Token::Eq);
+                        Ok(ClusterOption::IntrospectionDebugging(
+                            self.parse_with_option_value()?,
+                        ))
+                    }
+                    GRANULARITY => {
+                        let _ = self.consume_token(&Token::Eq);
+                        Ok(ClusterOption::IntrospectionGranularity(
+                            self.parse_with_option_value()?,
+                        ))
+                    }
+                    RETENTION => {
+                        let _ = self.consume_token(&Token::Eq);
+                        Ok(ClusterOption::IntrospectionRetention(
+                            self.parse_with_option_value()?,
+                        ))
+                    }
+                    _ => unreachable!(),
                 }
-                _ => unreachable!(),
-            },
+            }
             _ => unreachable!(),
         }
     }
@@ -2565,11 +2656,30 @@ impl<'a> Parser<'a> {
         Ok(Statement::Discard(DiscardStatement { target }))
     }
 
+    fn parse_undrop(&mut self) -> Result<Statement<Raw>, ParserError> {
+        Ok(Statement::Undrop(UndropStatement {
+            name: self.parse_object_name()?,
+        }))
+    }
+
     fn parse_drop(&mut self) -> Result<Statement<Raw>, ParserError> {
         let materialized = self.parse_keyword(MATERIALIZED);
 
         let object_type = match self.parse_one_of_keywords(&[
-            DATABASE, INDEX, ROLE, CLUSTER, SECRET, SCHEMA, SINK, SOURCE, TABLE, TYPE, USER, VIEW,
+            DATABASE,
+            INDEX,
+            ROLE,
+            CLUSTER,
+            SECRET,
+            PUBLICATION,
+            TASK,
+            SCHEMA,
+            SINK,
+            SOURCE,
+            TABLE,
+            TYPE,
+            USER,
+            VIEW,
         ]) {
             Some(DATABASE) => {
                 let if_exists = self.parse_if_exists()?;
@@ -2625,11 +2735,13 @@ impl<'a> Parser<'a> {
             Some(TYPE) => ObjectType::Type,
             Some(VIEW) => ObjectType::View,
             Some(SECRET) => ObjectType::Secret,
+            Some(PUBLICATION) => ObjectType::Publication,
+            Some(TASK) => ObjectType::Task,
             _ => {
                 return self.expected(
                     self.peek_pos(),
-                    "DATABASE, INDEX, ROLE, CLUSTER, SECRET, SCHEMA, SINK, SOURCE, \
-                     TABLE, TYPE, USER, VIEW after DROP",
+                    "DATABASE, INDEX, ROLE, CLUSTER, SECRET, PUBLICATION, TASK, SCHEMA, SINK, \
+                     SOURCE, TABLE, TYPE, USER, VIEW after DROP",
                     self.peek_token(),
                 );
             }
@@ -2910,9 +3022,9 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_alter(&mut self) -> Result<Statement<Raw>, ParserError> {
-        let object_type = match self
-            .expect_one_of_keywords(&[SINK, SOURCE, VIEW, TABLE, INDEX, SECRET, CLUSTER])?
-        {
+        let object_type = match self.expect_one_of_keywords(&[
+            SINK, SOURCE, VIEW, TABLE, INDEX, SECRET, CLUSTER, ROLE, DATABASE, SCHEMA, SYSTEM,
+        ])? {
             SINK => ObjectType::Sink,
             SOURCE => ObjectType::Source,
             VIEW => ObjectType::View,
@@ -2920,6 +3032,32 @@ impl<'a> Parser<'a> {
             INDEX => return self.parse_alter_index(),
             SECRET => return self.parse_alter_secret(),
             CLUSTER => return self.parse_alter_cluster(),
+            ROLE => return self.parse_alter_role(),
+            SYSTEM => return self.parse_alter_system(),
+            DATABASE => {
+                let if_exists = self.parse_if_exists()?;
+                let name = self.parse_database_name()?;
+                self.expect_keywords(&[RENAME, TO])?;
+                let to_name = self.parse_identifier()?;
+                return Ok(Statement::AlterDatabaseRename(
+                    AlterDatabaseRenameStatement {
+                        name,
+                        if_exists,
+                        to_name,
+                    },
+                ));
+            }
+            SCHEMA => {
+                let if_exists = self.parse_if_exists()?;
+                let name = self.parse_schema_name()?;
+                self.expect_keywords(&[RENAME, TO])?;
+                let to_name = self.parse_identifier()?;
+                return Ok(Statement::AlterSchemaRename(AlterSchemaRenameStatement {
+                    name,
+                    if_exists,
+                    to_name,
+                }));
+            }
             _ => unreachable!(),
         };
 
@@ -2937,6 +3075,112 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_comment(&mut self) -> Result<Statement<Raw>, ParserError> {
+        self.expect_keyword(ON)?;
+        let object = match self.expect_one_of_keywords(&[TABLE, VIEW, COLUMN])? {
+            TABLE => CommentObject::Item {
+                object_type: ObjectType::Table,
+                name: self.parse_raw_name()?,
+            },
+            VIEW => CommentObject::Item {
+                object_type: ObjectType::View,
+                name: self.parse_raw_name()?,
+            },
+            COLUMN => {
+                let mut idents = self.parse_identifiers()?;
+                if idents.len() < 2 {
+                    return parser_err!(
+                        self,
+                        self.peek_prev_pos(),
+                        "column name must be qualified by a table or view name"
+                    );
+                }
+                let column_name = idents.pop().unwrap();
+                CommentObject::Column {
+                    relation_name: RawObjectName::Name(UnresolvedObjectName(idents)),
+                    column_name,
+                }
+            }
+            _ => unreachable!(),
+        };
+        self.expect_keyword(IS)?;
+        let comment = if self.parse_keyword(NULL) {
+            None
+        } else {
+            Some(self.parse_literal_string()?)
+        };
+        Ok(Statement::Comment(CommentStatement { object, comment }))
+    }
+
+    fn parse_grant(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let privileges = self.parse_privileges()?;
+        self.expect_keyword(ON)?;
+        let object = self.parse_privilege_object()?;
+        self.expect_keyword(TO)?;
+        let roles = self.parse_comma_separated(Parser::parse_privilege_role)?;
+        Ok(Statement::GrantPrivileges(GrantPrivilegesStatement {
+            privileges,
+            object,
+            roles,
+        }))
+    }
+
+    fn parse_revoke(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let privileges = self.parse_privileges()?;
+        self.expect_keyword(ON)?;
+        let object = self.parse_privilege_object()?;
+        self.expect_keyword(FROM)?;
+        let roles = self.parse_comma_separated(Parser::parse_privilege_role)?;
+        Ok(Statement::RevokePrivileges(RevokePrivilegesStatement {
+            privileges,
+            object,
+            roles,
+        }))
+    }
+
+    /// Parses the privileges of a `GRANT` or `REVOKE` statement, returning
+    /// `None` for `ALL [PRIVILEGES]`.
+    fn parse_privileges(&mut self) -> Result<Option<Vec<Privilege>>, ParserError> {
+        if self.parse_keyword(ALL) {
+            let _ = self.parse_keyword(PRIVILEGES);
+            return Ok(None);
+        }
+        let privileges = self.parse_comma_separated(|parser| {
+            Ok(
+                match parser.expect_one_of_keywords(&[SELECT, INSERT, UPDATE, DELETE, CREATE])? {
+                    SELECT => Privilege::Select,
+                    INSERT => Privilege::Insert,
+                    UPDATE => Privilege::Update,
+                    DELETE => Privilege::Delete,
+                    CREATE => Privilege::Create,
+                    _ => unreachable!(),
+                },
+            )
+        })?;
+        Ok(Some(privileges))
+    }
+
+    fn parse_privilege_object(&mut self) -> Result<PrivilegeObject<Raw>, ParserError> {
+        Ok(
+            match self.parse_one_of_keywords(&[TABLE, SCHEMA, DATABASE]) {
+                Some(SCHEMA) => PrivilegeObject::Schema(self.parse_schema_name()?),
+                Some(DATABASE) => PrivilegeObject::Database(self.parse_database_name()?),
+                Some(TABLE) | None => PrivilegeObject::Table(self.parse_raw_name()?),
+                _ => unreachable!(),
+            },
+        )
+    }
+
+    /// Parses the name of a role in a `GRANT` or `REVOKE` statement, returning
+    /// `None` for `PUBLIC`.
+    fn parse_privilege_role(&mut self) -> Result<Option<Ident>, ParserError> {
+        if self.parse_keyword(PUBLIC) {
+            Ok(None)
+        } else {
+            Ok(Some(self.parse_identifier()?))
+        }
+    }
+
     fn parse_alter_index(&mut self) -> Result<Statement<Raw>, ParserError> {
         let if_exists = self.parse_if_exists()?;
         let name = self.parse_raw_name()?;
@@ -3029,6 +3273,79 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_alter_role(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let name = if self.parse_keyword(ALL) {
+            None
+        } else {
+            Some(self.parse_identifier()?)
+        };
+        let keyword = match (self.parse_one_of_keywords(&[SET, RESET]), &name) {
+            (Some(keyword), _) => keyword,
+            (None, Some(name)) => {
+                let options = self.parse_role_options()?;
+                if options.is_empty() {
+                    return self.expected(
+                        self.peek_pos(),
+                        "SET, RESET, or a role option",
+                        self.peek_token(),
+                    );
+                }
+                return Ok(Statement::AlterRole(AlterRoleStatement {
+                    name: name.clone(),
+                    options,
+                }));
+            }
+            // Only session defaults can be changed for every role at once.
+            (None, None) => {
+                return self.expected(self.peek_pos(), "one of SET or RESET", self.peek_token())
+            }
+        };
+        let (variable, value) = match keyword {
+            SET => {
+                let variable = self.parse_identifier()?;
+                if !self.consume_token(&Token::Eq) {
+                    self.expect_keyword(TO)?;
+                }
+                let value = if self.parse_keyword(DEFAULT) {
+                    None
+                } else {
+                    Some(self.parse_set_variable_value()?)
+                };
+                (variable, value)
+            }
+            RESET => (self.parse_identifier()?, None),
+            _ => unreachable!(),
+        };
+        Ok(Statement::AlterRoleSet(AlterRoleSetStatement {
+            name,
+            variable,
+            value,
+        }))
+    }
+
+    fn parse_alter_system(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let (variable, value) = match self.expect_one_of_keywords(&[SET, RESET])? {
+            SET => {
+                let variable = self.parse_identifier()?;
+                if !self.consume_token(&Token::Eq) {
+                    self.expect_keyword(TO)?;
+                }
+                let value = if self.parse_keyword(DEFAULT) {
+                    None
+                } else {
+                    Some(self.parse_set_variable_value()?)
+                };
+                (variable, value)
+            }
+            RESET => (self.parse_identifier()?, None),
+            _ => unreachable!(),
+        };
+        Ok(Statement::AlterSystemSet(AlterSystemSetStatement {
+            variable,
+            value,
+        }))
+    }
+
     /// Parse a copy statement
     fn parse_copy(&mut self) -> Result<Statement<Raw>, ParserError> {
         let relation = if self.consume_token(&Token::LParen) {
@@ -3933,12 +4250,10 @@ impl<'a> Parser<'a> {
             }
         }
         if normal {
-            let token = self.peek_token();
-            let value = match (self.parse_value(), token) {
-                (Ok(value), _) => SetVariableValue::Literal(value),
-                (Err(_), Some(Token::Keyword(kw))) => SetVariableValue::Ident(kw.into_ident()),
-                (Err(_), Some(Token::Ident(id))) => SetVariableValue::Ident(Ident::new(id)),
-                (Err(_), other) => self.expected(self.peek_pos(), "variable value", other)?,
+            let value = if self.parse_keyword(DEFAULT) {
+                None
+            } else {
+                Some(self.parse_set_variable_value()?)
             };
             Ok(Statement::SetVariable(SetVariableStatement {
                 local: modifier == Some(LOCAL),
@@ -3962,6 +4277,27 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_reset(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let variable = if self.parse_keyword(ALL) {
+            None
+        } else {
+            Some(self.parse_identifier()?)
+        };
+        Ok(Statement::ResetVariable(ResetVariableStatement {
+            variable,
+        }))
+    }
+
+    fn parse_set_variable_value(&mut self) -> Result<SetVariableValue, ParserError> {
+        let token = self.peek_token();
+        match (self.parse_value(), token) {
+            (Ok(value), _) => Ok(SetVariableValue::Literal(value)),
+            (Err(_), Some(Token::Keyword(kw))) => Ok(SetVariableValue::Ident(kw.into_ident())),
+            (Err(_), Some(Token::Ident(id))) => Ok(SetVariableValue::Ident(Ident::new(id))),
+            (Err(_), other) => self.expected(self.peek_pos(), "variable value", other),
+        }
+    }
+
     fn parse_show(&mut self) -> Result<Statement<Raw>, ParserError> {
         if self.parse_keyword(DATABASES) {
             return Ok(Statement::ShowDatabases(ShowDatabasesStatement {
@@ -3969,6 +4305,12 @@ impl<'a> Parser<'a> {
             }));
         }
 
+        if self.parse_keywords(&[DROPPED, OBJECTS]) {
+            return Ok(Statement::ShowDroppedObjects(ShowDroppedObjectsStatement {
+                filter: self.parse_show_statement_filter()?,
+            }));
+        }
+
         let extended = self.parse_keyword(EXTENDED);
         if extended {
             self.expect_one_of_keywords(&[
@@ -3984,6 +4326,9 @@ impl<'a> Parser<'a> {
             } else {
                 self.expect_one_of_keywords(&[
                     COLUMNS,
+                    INDEX,
+                    INDEXES,
+                    KEYS,
                     MATERIALIZED,
                     OBJECTS,
                     ROLES,
@@ -4019,7 +4364,18 @@ impl<'a> Parser<'a> {
                 filter: self.parse_show_statement_filter()?,
             }))
         } else if let Some(object_type) = self.parse_one_of_keywords(&[
-            OBJECTS, ROLES, CLUSTERS, SINKS, SOURCES, TABLES, TYPES, USERS, VIEWS, SECRETS,
+            OBJECTS,
+            ROLES,
+            CLUSTERS,
+            SINKS,
+            SOURCES,
+            TABLES,
+            TYPES,
+            USERS,
+            VIEWS,
+            SECRETS,
+            PUBLICATIONS,
+            TASKS,
         ]) {
             let object_type = match object_type {
                 OBJECTS => ObjectType::Object,
@@ -4031,6 +4387,8 @@ impl<'a> Parser<'a> {
                 TYPES => ObjectType::Type,
                 VIEWS => ObjectType::View,
                 SECRETS => ObjectType::Secret,
+                PUBLICATIONS => ObjectType::Publication,
+                TASKS => ObjectType::Task,
                 _ => unreachable!(),
             };
 
@@ -4091,6 +4449,7 @@ impl<'a> Parser<'a> {
                         table_name,
                         in_cluster,
                         extended,
+                        full,
                         filter,
                     }))
                 }
@@ -4466,6 +4825,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse `AS OF` or `AS OF AT LEAST`, if present.
+    fn parse_optional_query_as_of(&mut self) -> Result<Option<AsOf<Raw>>, ParserError> {
+        if self.parse_keyword(AS) {
+            self.expect_keyword(OF)?;
+            let at_least = self.parse_keywords(&[AT, LEAST]);
+            match self.parse_expr() {
+                Ok(expr) if at_least => Ok(Some(AsOf::AtLeast(expr))),
+                Ok(expr) => Ok(Some(AsOf::At(expr))),
+                Err(e) => {
+                    self.expected(e.pos, "a timestamp value after 'AS OF'", self.peek_token())
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Parse a comma-delimited list of projections after SELECT
     fn parse_select_item(&mut self) -> Result<SelectItem<Raw>, ParserError> {
         if self.consume_token(&Token::Star) {
@@ -4583,34 +4959,64 @@ impl<'a> Parser<'a> {
             TailRelation::Name(self.parse_raw_name()?)
         };
         let options = self.parse_opt_with_options()?;
-        let as_of = self.parse_optional_as_of()?;
+        let as_of = self.parse_optional_query_as_of()?;
+        let up_to = if self.parse_keywords(&[UP, TO]) {
+            match self.parse_expr() {
+                Ok(expr) => Some(expr),
+                Err(e) => {
+                    return self.expected(
+                        e.pos,
+                        "a timestamp value after 'UP TO'",
+                        self.peek_token(),
+                    )
+                }
+            }
+        } else {
+            None
+        };
         Ok(Statement::Tail(TailStatement {
             relation,
             options,
             as_of,
+            up_to,
         }))
     }
 
     /// Parse an `EXPLAIN` statement, assuming that the `EXPLAIN` token
     /// has already been consumed.
     fn parse_explain(&mut self) -> Result<Statement<Raw>, ParserError> {
+        if self.parse_keyword(DROP) {
+            return self.parse_explain_drop();
+        }
+
         // (TYPED)?
         let typed = self.parse_keyword(TYPED);
         let mut timing = false;
+        let mut attributes = false;
+        let mut format = ExplainFormat::Text;
 
-        // options: ( '(' TIMING (true|false) ')' )?
+        // options: ( '(' option (',' option)* ')' )?
+        // option: TIMING (true|false) | ATTRIBUTES (true|false) | FORMAT (TEXT|JSON)
         if let Some(Token::LParen) = self.peek_token() {
             // Check whether a valid option is after the parentheses, since the
             // parentheses may belong to the actual query to be explained.
             match self.peek_nth_token(1) {
-                Some(Token::Keyword(TIMING)) => {
+                Some(Token::Keyword(TIMING | ATTRIBUTES | FORMAT)) => {
                     self.next_token(); // Consume the LParen
-                    self.parse_comma_separated(|s| match s.expect_one_of_keywords(&[TIMING])? {
-                        TIMING => {
-                            timing = s.parse_boolean_value()?;
-                            Ok(())
+                    self.parse_comma_separated(|s| {
+                        match s.expect_one_of_keywords(&[TIMING, ATTRIBUTES, FORMAT])? {
+                            TIMING => timing = s.parse_boolean_value()?,
+                            ATTRIBUTES => attributes = s.parse_boolean_value()?,
+                            FORMAT => {
+                                format = match s.expect_one_of_keywords(&[TEXT, JSON])? {
+                                    TEXT => ExplainFormat::Text,
+                                    JSON => ExplainFormat::Json,
+                                    _ => unreachable!(),
+                                }
+                            }
+                            _ => unreachable!(),
                         }
-                        _ => unreachable!(),
+                        Ok(())
                     })?;
                     self.expect_token(&Token::RParen)?;
                 }
@@ -4618,8 +5024,9 @@ impl<'a> Parser<'a> {
             }
         }
 
-        // (RAW | DECORRELATED | OPTIMIZED | PHYSICAL)? PLAN
+        // (RAW | DECORRELATED | OPTIMIZED | PHYSICAL)? PLAN | ANALYZE
         let stage = match self.parse_one_of_keywords(&[
+            ANALYZE,
             RAW,
             DECORRELATED,
             OPTIMIZED,
@@ -4661,6 +5068,11 @@ impl<'a> Parser<'a> {
                 self.expect_keywords(&[FOR])?;
                 ExplainStage::Timestamp
             }
+            Some(ANALYZE) => {
+                // `FOR` is optional, as in PostgreSQL's `EXPLAIN ANALYZE`.
+                let _ = self.parse_keyword(FOR);
+                ExplainStage::Analyze
+            }
             None => ExplainStage::OptimizedPlan,
             _ => unreachable!(),
         };
@@ -4672,7 +5084,12 @@ impl<'a> Parser<'a> {
             Explainee::Query(self.parse_query()?)
         };
 
-        let options = ExplainOptions { typed, timing };
+        let options = ExplainOptions {
+            typed,
+            timing,
+            attributes,
+            format,
+        };
         Ok(Statement::Explain(ExplainStatement {
             stage,
             explainee,
@@ -4680,6 +5097,22 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    /// Parse an `EXPLAIN DROP` statement, assuming that the `EXPLAIN DROP`
+    /// tokens have already been consumed.
+    fn parse_explain_drop(&mut self) -> Result<Statement<Raw>, ParserError> {
+        let pos = self.peek_pos();
+        let stmt = match self.parse_drop()? {
+            stmt @ Statement::DropDatabase(_)
+            | stmt @ Statement::DropSchema(_)
+            | stmt @ Statement::DropObjects(_)
+            | stmt @ Statement::DropClusters(_) => stmt,
+            _ => return parser_err!(self, pos, "EXPLAIN DROP ROLE is not supported"),
+        };
+        Ok(Statement::ExplainDrop(ExplainDropStatement {
+            stmt: Box::new(stmt),
+        }))
+    }
+
     /// Parse a `DECLARE` statement, assuming that the `DECLARE` token
     /// has already been consumed.
     fn parse_declare(&mut self) -> Result<Statement<Raw>, ParserError> {
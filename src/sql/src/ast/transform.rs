@@ -12,7 +12,10 @@
 use std::collections::{HashMap, HashSet};
 
 use mz_ore::str::StrExt;
-use mz_sql_parser::ast::{CreateSecretStatement, RawObjectName};
+use mz_sql_parser::ast::{
+    CreatePublicationStatement, CreateSecretStatement, CreateTaskStatement, InsertSource,
+    RawObjectName,
+};
 
 use crate::ast::visit::{self, Visit};
 use crate::ast::visit_mut::{self, VisitMut};
@@ -23,6 +26,17 @@ use crate::ast::{
 };
 use crate::names::FullObjectName;
 
+/// Replaces each reference by ID in `create_stmt` to an item whose ID does not
+/// satisfy `exists` with a reference to the item by name, e.g., so that the
+/// statement can be replanned after the items it referenced were recreated
+/// with new IDs.
+pub fn create_stmt_unbind_ids<F>(create_stmt: &mut Statement<Raw>, exists: F)
+where
+    F: Fn(&str) -> bool,
+{
+    IdUnbinder { exists }.visit_statement_mut(create_stmt);
+}
+
 /// Changes the `name` used in an item's `CREATE` statement. To complete a
 /// rename operation, you must also call `create_stmt_rename_refs` on all dependent
 /// items.
@@ -45,7 +59,9 @@ pub fn create_stmt_rename(create_stmt: &mut Statement<Raw>, to_item_name: String
             let object_name_len = name.0.len() - 1;
             name.0[object_name_len] = Ident::new(to_item_name);
         }
-        Statement::CreateSecret(CreateSecretStatement { name, .. }) => {
+        Statement::CreateSecret(CreateSecretStatement { name, .. })
+        | Statement::CreatePublication(CreatePublicationStatement { name, .. })
+        | Statement::CreateTask(CreateTaskStatement { name, .. }) => {
             let object_name_len = name.0.len() - 1;
             name.0[object_name_len] = Ident::new(to_item_name);
         }
@@ -89,6 +105,17 @@ pub fn create_stmt_rename_refs(
         Statement::CreateSink(CreateSinkStatement { from, .. }) => {
             maybe_update_object_name(from.name_mut());
         }
+        Statement::CreatePublication(CreatePublicationStatement { views, .. }) => {
+            for view in views {
+                maybe_update_object_name(view.name_mut());
+            }
+        }
+        Statement::CreateTask(CreateTaskStatement { statement, .. }) => {
+            maybe_update_object_name(statement.table_name.name_mut());
+            if let InsertSource::Query(query) = &mut statement.source {
+                rewrite_query(from_name, to_item_name, query)?;
+            }
+        }
         Statement::CreateView(CreateViewStatement {
             definition: ViewDefinition { query, .. },
             ..
@@ -102,6 +129,41 @@ pub fn create_stmt_rename_refs(
     Ok(())
 }
 
+/// Updates all references to items in the database named `from_database` in
+/// `create_stmt` to refer to the database as `to_database`.
+///
+/// Only fully qualified item names are rewritten, as a name that is not fully
+/// qualified never includes a database name.
+pub fn create_stmt_rename_database_refs(
+    create_stmt: &mut Statement<Raw>,
+    from_database: &str,
+    to_database: &str,
+) -> Result<(), String> {
+    QualifierRewriter::rewrite(
+        create_stmt,
+        vec![Ident::new(from_database)],
+        vec![Ident::new(to_database)],
+    )
+}
+
+/// Updates all references to items in the schema named `from_schema` of the
+/// database named `database` in `create_stmt` to refer to the schema as
+/// `to_schema`, or errors if a reference to a column is qualified by the
+/// schema name but not the database name, in which case the rewritten
+/// reference could be ambiguous.
+pub fn create_stmt_rename_schema_refs(
+    create_stmt: &mut Statement<Raw>,
+    database: &str,
+    from_schema: &str,
+    to_schema: &str,
+) -> Result<(), String> {
+    QualifierRewriter::rewrite(
+        create_stmt,
+        vec![Ident::new(database), Ident::new(from_schema)],
+        vec![Ident::new(database), Ident::new(to_schema)],
+    )
+}
+
 /// Rewrites `query`'s references of `from` to `to` or errors if too ambiguous.
 fn rewrite_query(from: FullObjectName, to: String, query: &mut Query<Raw>) -> Result<(), String> {
     let from_ident = Ident::new(from.item.clone());
@@ -339,3 +401,108 @@ impl<'ast> VisitMut<'ast, Raw> for CreateSqlRewriter {
         }
     }
 }
+
+/// Replaces references by ID to items that no longer exist with references
+/// by name.
+struct IdUnbinder<F> {
+    exists: F,
+}
+
+impl<'ast, F> VisitMut<'ast, Raw> for IdUnbinder<F>
+where
+    F: Fn(&str) -> bool,
+{
+    fn visit_object_name_mut(
+        &mut self,
+        object_name: &'ast mut <mz_sql_parser::ast::Raw as AstInfo>::ObjectName,
+    ) {
+        if let RawObjectName::Id(id, name) = object_name {
+            if !(self.exists)(id) {
+                *object_name = RawObjectName::Name(name.clone());
+            }
+        }
+    }
+}
+
+/// Rewrites the database or schema qualifiers of fully qualified item names.
+///
+/// `from` and `to` are either a database name or a database name followed by
+/// a schema name.
+struct QualifierRewriter {
+    from: Vec<Ident>,
+    to: Vec<Ident>,
+    err: Option<String>,
+}
+
+impl QualifierRewriter {
+    fn rewrite(
+        create_stmt: &mut Statement<Raw>,
+        from: Vec<Ident>,
+        to: Vec<Ident>,
+    ) -> Result<(), String> {
+        let mut v = QualifierRewriter {
+            from,
+            to,
+            err: None,
+        };
+        v.visit_statement_mut(create_stmt);
+        match v.err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrites `name` if it is a fully qualified item name, i.e., a database,
+    /// a schema, and an item name, within `self.from`.
+    fn maybe_rewrite_item_name(&mut self, name: &mut [Ident]) {
+        if name.len() == 3 && name.starts_with(&self.from) {
+            name[..self.from.len()].clone_from_slice(&self.to);
+        } else if name.len() == 2 && self.from.len() == 2 && name[0] == self.from[1] {
+            // A schema-qualified name might refer to an item in the renamed
+            // schema of the active database, which the rewrite would lose.
+            self.err = Some(format!(
+                "{} is not sufficiently qualified to support renaming",
+                name.iter()
+                    .map(|i| i.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".")
+                    .quoted()
+            ));
+        }
+    }
+}
+
+impl<'ast> VisitMut<'ast, Raw> for QualifierRewriter {
+    fn visit_expr_mut(&mut self, e: &'ast mut Expr<Raw>) {
+        match e {
+            Expr::Identifier(id) => {
+                // The last ID component is a column name that should not be
+                // considered in the rewrite.
+                let i = id.len() - 1;
+                self.maybe_rewrite_item_name(&mut id[..i]);
+            }
+            Expr::QualifiedWildcard(id) => {
+                self.maybe_rewrite_item_name(id);
+            }
+            _ => visit_mut::visit_expr_mut(self, e),
+        }
+    }
+
+    fn visit_unresolved_object_name_mut(
+        &mut self,
+        unresolved_object_name: &'ast mut UnresolvedObjectName,
+    ) {
+        self.maybe_rewrite_item_name(&mut unresolved_object_name.0);
+    }
+
+    fn visit_object_name_mut(
+        &mut self,
+        object_name: &'ast mut <mz_sql_parser::ast::Raw as AstInfo>::ObjectName,
+    ) {
+        match object_name {
+            RawObjectName::Name(n) | RawObjectName::Id(_, n) => {
+                self.maybe_rewrite_item_name(&mut n.0)
+            }
+        }
+    }
+}
@@ -158,6 +158,16 @@ pub trait SessionCatalog: fmt::Debug + ExprHumanizer {
         item_name: &PartialObjectName,
     ) -> Result<&dyn CatalogItem, CatalogError>;
 
+    /// Resolves a partially-specified name to a dropped item whose definition
+    /// is still retained.
+    ///
+    /// The schemas are searched as in [`SessionCatalog::resolve_item`]. If
+    /// several dropped items match, the most recently dropped one is chosen.
+    fn resolve_dropped_item(
+        &self,
+        item_name: &PartialObjectName,
+    ) -> Result<&dyn CatalogDroppedItem, CatalogError>;
+
     /// Gets an item by its ID.
     fn try_get_item(&self, id: &GlobalId) -> Option<&dyn CatalogItem>;
 
@@ -272,6 +282,18 @@ pub trait CatalogComputeInstance {
     fn indexes(&self) -> &std::collections::HashSet<GlobalId>;
 }
 
+/// A dropped item in a [`SessionCatalog`] that can still be restored.
+pub trait CatalogDroppedItem {
+    /// Returns the ID that the item had before it was dropped.
+    fn id(&self) -> GlobalId;
+
+    /// Returns the fully qualified name that the item had when it was dropped.
+    fn name(&self) -> &str;
+
+    /// Returns the SQL statement that created the item.
+    fn create_sql(&self) -> &str;
+}
+
 /// An item in a [`SessionCatalog`].
 ///
 /// Note that "item" has a very specific meaning in the context of a SQL
@@ -350,6 +372,10 @@ pub enum CatalogItemType {
     Func,
     /// A Secret.
     Secret,
+    /// A publication.
+    Publication,
+    /// A scheduled task.
+    Task,
 }
 
 impl fmt::Display for CatalogItemType {
@@ -363,6 +389,8 @@ impl fmt::Display for CatalogItemType {
             CatalogItemType::Type => f.write_str("type"),
             CatalogItemType::Func => f.write_str("func"),
             CatalogItemType::Secret => f.write_str("secret"),
+            CatalogItemType::Publication => f.write_str("publication"),
+            CatalogItemType::Task => f.write_str("task"),
         }
     }
 }
@@ -457,6 +485,8 @@ pub enum CatalogError {
     UnknownRole(String),
     /// Unknown compute instance.
     UnknownComputeInstance(String),
+    /// Unknown compute instance replica.
+    UnknownComputeInstanceReplica(String),
     /// Unknown item.
     UnknownItem(String),
     /// Unknown function.
@@ -481,6 +511,9 @@ impl fmt::Display for CatalogError {
             Self::UnknownSchema(name) => write!(f, "unknown schema '{}'", name),
             Self::UnknownRole(name) => write!(f, "unknown role '{}'", name),
             Self::UnknownComputeInstance(name) => write!(f, "unknown cluster '{}'", name),
+            Self::UnknownComputeInstanceReplica(name) => {
+                write!(f, "unknown cluster replica '{}'", name)
+            }
             Self::UnknownItem(name) => write!(f, "unknown catalog item '{}'", name),
             Self::InvalidDependency { name, typ } => write!(
                 f,
@@ -580,6 +613,13 @@ impl SessionCatalog for DummyCatalog {
         unimplemented!();
     }
 
+    fn resolve_dropped_item(
+        &self,
+        _: &PartialObjectName,
+    ) -> Result<&dyn CatalogDroppedItem, CatalogError> {
+        unimplemented!();
+    }
+
     fn resolve_compute_instance(
         &self,
         _: Option<&str>,
@@ -1754,6 +1754,12 @@ lazy_static! {
             "char_length" => Scalar {
                 params!(String) => UnaryFunc::CharLength, 1381;
             },
+            "col_description" => Scalar {
+                params!(Oid, Int32) => sql_impl_func(
+                    "(SELECT description FROM pg_catalog.pg_description
+                     WHERE objoid = $1 AND objsubid = $2)"
+                ) => String, 1216;
+            },
             "concat" => Scalar {
                 params!(Any...) => Operation::variadic(|ecx, cexprs| {
                     if cexprs.is_empty() {
@@ -1991,12 +1997,12 @@ lazy_static! {
                 }), 1375;
             },
             "obj_description" => Scalar {
-                params!(Oid, String) => Operation::binary(|_ecx, _oid, _catalog| {
-                    // This function is meant to return the comment on a
-                    // database object, but we don't presently support comments,
-                    // so stubbed out out to always return NULL.
-                    Ok(HirScalarExpr::literal_null(ScalarType::String))
-                }), 1215;
+                // Comments are only supported on relations, so only lookups in
+                // `pg_class` can find a comment.
+                params!(Oid, String) => sql_impl_func(
+                    "(SELECT description FROM pg_catalog.pg_description
+                     WHERE objoid = $1 AND objsubid = 0 AND $2 = 'pg_class')"
+                ) => String, 1215;
             },
             "pg_column_size" => Scalar {
                 params!(Any) => UnaryFunc::PgColumnSize(func::PgColumnSize) => Int32, 1269;
@@ -2105,6 +2111,13 @@ lazy_static! {
             "pg_postmaster_start_time" => Scalar {
                 params!() => UnmaterializableFunc::PgPostmasterStartTime, 2560;
             },
+            "pg_reload_conf" => Scalar {
+                // Like the administrative functions in `mz_internal`, this
+                // function has side effects, so it is planned specially.
+                params!() => Operation::nullary(|_ecx| {
+                    sql_bail!("pg_reload_conf must be called on its own, as in SELECT pg_reload_conf()")
+                }) => Bool, 2621;
+            },
             "pg_table_is_visible" => Scalar {
                 params!(Oid) => sql_impl_func(
                     "(SELECT s.name = ANY(current_schemas(true))
@@ -2726,6 +2739,13 @@ lazy_static! {
             "map_length" => Scalar {
                 params![MapAny] => UnaryFunc::MapLength => Int32, oid::FUNC_MAP_LENGTH_OID;
             },
+            "mz_cancel_query" => Scalar {
+                // Like the administrative functions in `mz_internal`, this
+                // function has side effects, so it is planned specially.
+                params!(Int32) => Operation::unary(|_ecx, _pid| {
+                    sql_bail!("mz_cancel_query must be called on its own, as in SELECT mz_cancel_query(...)")
+                }) => Bool, oid::FUNC_MZ_CANCEL_QUERY_OID;
+            },
             "mz_cluster_id" => Scalar {
                 params!() => UnmaterializableFunc::MzClusterId, oid::FUNC_MZ_CLUSTER_ID_OID;
             },
@@ -2805,6 +2825,24 @@ lazy_static! {
         use ParamType::*;
         use ScalarType::*;
         builtins! {
+            "backup_catalog" => Scalar {
+                params!(String) => admin_func("backup_catalog") => Bool, oid::FUNC_BACKUP_CATALOG_OID;
+            },
+            "check_catalog" => Scalar {
+                params!() => admin_func("check_catalog") => String, oid::FUNC_CHECK_CATALOG_OID;
+            },
+            "collect_statistics" => Scalar {
+                params!() => admin_func("collect_statistics") => Int64, oid::FUNC_COLLECT_STATISTICS_OID;
+            },
+            "gc_secrets" => Scalar {
+                params!() => admin_func("gc_secrets") => Int64, oid::FUNC_GC_SECRETS_OID;
+            },
+            "migrate_secrets" => Scalar {
+                params!() => admin_func("migrate_secrets") => Int64, oid::FUNC_MIGRATE_SECRETS_OID;
+            },
+            "mz_cpu_profile" => Scalar {
+                params!(String) => admin_func("mz_cpu_profile") => String, oid::FUNC_MZ_CPU_PROFILE_OID;
+            },
             "mz_all" => Aggregate {
                 params!(Any) => AggregateFunc::All => Bool, oid::FUNC_MZ_ALL_OID;
             },
@@ -2857,16 +2895,53 @@ lazy_static! {
             "mz_session_id" => Scalar {
                 params!() => UnmaterializableFunc::MzSessionId, oid::FUNC_MZ_SESSION_ID_OID;
             },
+            // Reports the session variables of the calling session, as a JSON
+            // array, for `pg_settings`.
+            "mz_session_settings" => Scalar {
+                params!() => UnmaterializableFunc::MzSessionSettings,
+                    oid::FUNC_MZ_SESSION_SETTINGS_OID;
+            },
             "mz_sleep" => Scalar {
                 params!(Float64) => UnaryFunc::Sleep(func::Sleep), oid::FUNC_MZ_SLEEP_OID;
             },
             "mz_type_name" => Scalar {
                 params!(Oid) => UnaryFunc::MzTypeName(func::MzTypeName), oid::FUNC_MZ_TYPE_NAME;
+            },
+            "rebuild_upsert_state" => Scalar {
+                params!(String) => admin_func("rebuild_upsert_state") => Int64,
+                    oid::FUNC_REBUILD_UPSERT_STATE_OID;
+            },
+            "replica_diagnostics" => Scalar {
+                params!(String) => admin_func("replica_diagnostics") => String,
+                    oid::FUNC_REPLICA_DIAGNOSTICS_OID;
+            },
+            "recompute_materialization" => Scalar {
+                params!(String) => admin_func("recompute_materialization") => Bool,
+                    oid::FUNC_RECOMPUTE_MATERIALIZATION_OID;
+            },
+            "restart_replica" => Scalar {
+                params!(String) => admin_func("restart_replica") => Bool,
+                    oid::FUNC_RESTART_REPLICA_OID;
             }
         }
     };
 }
 
+/// Returns the operation for an administrative function in `mz_internal`.
+///
+/// These functions have side effects, so they are only permitted as the sole
+/// expression in an otherwise empty `SELECT` statement, which is planned
+/// specially. Any other call is an error.
+fn admin_func(name: &'static str) -> Operation<HirScalarExpr> {
+    Operation::variadic(move |_ecx, _exprs| {
+        sql_bail!(
+            "mz_internal.{} must be called on its own, as in SELECT mz_internal.{}(...)",
+            name,
+            name
+        )
+    })
+}
+
 fn digest(algorithm: &'static str) -> Operation<HirScalarExpr> {
     Operation::unary(move |_ecx, input| {
         let algorithm = HirScalarExpr::literal(Datum::String(algorithm), ScalarType::String);
@@ -26,7 +26,12 @@ use reqwest::Url;
 use tokio::time::Duration;
 
 use mz_ccsr::tls::{Certificate, Identity};
-use mz_sql_parser::ast::Value;
+use mz_expr::GlobalId;
+use mz_sql_parser::ast::{AstInfo, SqlOption, Value};
+
+use crate::catalog::{CatalogItemType, SessionCatalog};
+use crate::normalize;
+use crate::plan::with_options::{OptionSchema, OptionSpec, OptionType};
 
 enum ValType {
     Path,
@@ -68,6 +73,9 @@ struct Config {
     // If set, look for an environment variable named `<name>_env` to possibly
     // define the named setting.
     include_env_var: bool,
+    // If set, look for an option named `<name>_secret` that names a secret
+    // whose contents define the named setting.
+    include_secret: bool,
 }
 
 impl Config {
@@ -78,6 +86,7 @@ impl Config {
             transform: convert::identity,
             default: None,
             include_env_var: false,
+            include_secret: false,
         }
     }
 
@@ -123,6 +132,17 @@ impl Config {
         self
     }
 
+    /// Allows for the contents of a secret to define this configuration
+    /// option.
+    fn include_secret(mut self) -> Self {
+        assert!(
+            self.default.is_none(),
+            "cannot currently both set default values and include secrets on the same config"
+        );
+        self.include_secret = true;
+        self
+    }
+
     /// Get the appropriate String to use as the Kafka config key.
     fn get_kafka_config_key(&self) -> String {
         self.name.replace('_', ".")
@@ -132,6 +152,11 @@ impl Config {
     fn get_env_var_key(&self) -> String {
         format!("{}_env", self.name)
     }
+
+    /// Gets the key to lookup for configs that support secrets.
+    fn get_secret_key(&self) -> String {
+        format!("{}_secret", self.name)
+    }
 }
 
 fn extract(
@@ -140,6 +165,25 @@ fn extract(
 ) -> Result<BTreeMap<String, String>, anyhow::Error> {
     let mut out = BTreeMap::new();
     for config in configs {
+        // Settings that are defined by secrets are extracted by
+        // `extract_secret_config`, as their values are not known here.
+        if config.include_secret && input.contains_key(&config.get_secret_key()) {
+            let other = if input.contains_key(config.name) {
+                Some(config.name.to_string())
+            } else if config.include_env_var && input.contains_key(&config.get_env_var_key()) {
+                Some(config.get_env_var_key())
+            } else {
+                None
+            };
+            if let Some(other) = other {
+                bail!(
+                    "Invalid WITH options: cannot specify both {} and {} options at the same time",
+                    other,
+                    config.get_secret_key()
+                )
+            }
+            continue;
+        }
         // Look for config.name
         let value = match input.remove(config.name) {
             Some(v) => match config.val_type.process_val(&v) {
@@ -196,54 +240,120 @@ fn extract(
 pub fn extract_config(
     with_options: &mut BTreeMap<String, Value>,
 ) -> Result<BTreeMap<String, String>, anyhow::Error> {
-    extract(
-        with_options,
-        &[
-            Config::string("acks"),
-            Config::string("client_id"),
-            Config::new(
-                "statistics_interval_ms",
-                // The range of values comes from `statistics.interval.ms` in
-                // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
-                ValType::Number(0, 86_400_000),
-            )
-            .set_default(Some(
-                chrono::Duration::seconds(1).num_milliseconds().to_string(),
-            )),
-            Config::new(
-                "topic_metadata_refresh_interval_ms",
-                // The range of values comes from `topic.metadata.refresh.interval.ms` in
-                // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
-                ValType::Number(0, 3_600_000),
-            ),
-            Config::new("enable_auto_commit", ValType::Boolean),
-            Config::string("isolation_level").set_default(Some(String::from("read_committed"))),
-            Config::string("security_protocol"),
-            Config::path("sasl_kerberos_keytab"),
-            Config::string("sasl_username"),
-            Config::string("sasl_password").include_env_var(),
-            Config::string("sasl_kerberos_kinit_cmd"),
-            Config::string("sasl_kerberos_min_time_before_relogin"),
-            Config::string("sasl_kerberos_principal"),
-            Config::string("sasl_kerberos_service_name"),
-            // For historical reasons, we allow `sasl_mechanisms` to be lowercase or
-            // mixed case, while librdkafka requires all uppercase (e.g., `PLAIN`,
-            // not `plain`).
-            Config::string("sasl_mechanisms").set_transform(|s| s.to_uppercase()),
-            Config::path("ssl_ca_location"),
-            Config::path("ssl_certificate_location"),
-            Config::path("ssl_key_location"),
-            Config::string("ssl_key_password").include_env_var(),
-            Config::new("transaction_timeout_ms", ValType::Number(0, i32::MAX)),
-            Config::new("enable_idempotence", ValType::Boolean),
-            Config::new(
-                "fetch_message_max_bytes",
-                // The range of values comes from `fetch.message.max.bytes` in
-                // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
-                ValType::Number(0, 1_000_000_000),
-            ),
-        ],
-    )
+    extract(with_options, &configs())
+}
+
+/// Parse the `with_options` from a `CREATE SOURCE` or `CREATE SINK`
+/// statement to determine the config options whose values are the contents
+/// of secrets, like `sasl_password_secret`, and remove them from `input`.
+///
+/// Returns the IDs of the secrets, keyed by the config option that each
+/// defines, e.g. `sasl.password`.
+///
+/// # Errors
+///
+/// - If an option does not name a secret in `catalog`.
+pub fn extract_secret_config<T: AstInfo>(
+    catalog: &dyn SessionCatalog,
+    with_options: &[SqlOption<T>],
+    input: &mut BTreeMap<String, Value>,
+) -> Result<BTreeMap<String, GlobalId>, anyhow::Error> {
+    let mut out = BTreeMap::new();
+    for config in configs() {
+        let key = config.get_secret_key();
+        if !config.include_secret || input.remove(&key).is_none() {
+            continue;
+        }
+        let name = with_options.iter().find_map(|option| match option {
+            SqlOption::ObjectName { name, object_name } if normalize::ident_ref(name) == key => {
+                Some(object_name.clone())
+            }
+            _ => None,
+        });
+        let name = match name {
+            Some(name) => normalize::unresolved_object_name(name)?,
+            None => bail!("Invalid WITH option {}: must name a secret", key),
+        };
+        let item = catalog.resolve_item(&name)?;
+        if item.item_type() != CatalogItemType::Secret {
+            bail!(
+                "Invalid WITH option {}: {} is not a secret",
+                key,
+                catalog.resolve_full_name(item.name())
+            );
+        }
+        out.insert(config.get_kafka_config_key(), item.id());
+    }
+    Ok(out)
+}
+
+/// Returns the names of the `WITH` options that [`extract_config`] and
+/// [`extract_secret_config`] extract.
+pub fn config_option_names() -> Vec<String> {
+    let mut names = vec![];
+    for config in configs() {
+        if config.include_env_var {
+            names.push(config.get_env_var_key());
+        }
+        if config.include_secret {
+            names.push(config.get_secret_key());
+        }
+        names.push(config.name.to_string());
+    }
+    names
+}
+
+/// The Kafka cluster configurations that users can supply.
+fn configs() -> Vec<Config> {
+    vec![
+        Config::string("acks"),
+        Config::string("client_id"),
+        Config::new(
+            "statistics_interval_ms",
+            // The range of values comes from `statistics.interval.ms` in
+            // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
+            ValType::Number(0, 86_400_000),
+        )
+        .set_default(Some(
+            chrono::Duration::seconds(1).num_milliseconds().to_string(),
+        )),
+        Config::new(
+            "topic_metadata_refresh_interval_ms",
+            // The range of values comes from `topic.metadata.refresh.interval.ms` in
+            // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
+            ValType::Number(0, 3_600_000),
+        ),
+        Config::new("enable_auto_commit", ValType::Boolean),
+        Config::string("isolation_level").set_default(Some(String::from("read_committed"))),
+        Config::string("security_protocol"),
+        Config::path("sasl_kerberos_keytab"),
+        Config::string("sasl_username"),
+        Config::string("sasl_password")
+            .include_env_var()
+            .include_secret(),
+        Config::string("sasl_kerberos_kinit_cmd"),
+        Config::string("sasl_kerberos_min_time_before_relogin"),
+        Config::string("sasl_kerberos_principal"),
+        Config::string("sasl_kerberos_service_name"),
+        // For historical reasons, we allow `sasl_mechanisms` to be lowercase or
+        // mixed case, while librdkafka requires all uppercase (e.g., `PLAIN`,
+        // not `plain`).
+        Config::string("sasl_mechanisms").set_transform(|s| s.to_uppercase()),
+        Config::path("ssl_ca_location"),
+        Config::path("ssl_certificate_location"),
+        Config::path("ssl_key_location"),
+        Config::string("ssl_key_password")
+            .include_env_var()
+            .include_secret(),
+        Config::new("transaction_timeout_ms", ValType::Number(0, i32::MAX)),
+        Config::new("enable_idempotence", ValType::Boolean),
+        Config::new(
+            "fetch_message_max_bytes",
+            // The range of values comes from `fetch.message.max.bytes` in
+            // https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
+            ValType::Number(0, 1_000_000_000),
+        ),
+    ]
 }
 
 /// Create a new `rdkafka::ClientConfig` with the provided
@@ -460,45 +570,41 @@ impl ClientContext for KafkaErrCheckContext {
     }
 }
 
+/// Returns the schema of the `WITH` options of a Confluent Schema Registry
+/// connection.
+fn ccsr_option_schema() -> OptionSchema {
+    OptionSchema::new("CONFLUENT SCHEMA REGISTRY").with([
+        OptionSpec::new("ssl_ca_location", OptionType::String),
+        OptionSpec::new("ssl_certificate_location", OptionType::String),
+        OptionSpec::new("ssl_key_location", OptionType::String),
+        OptionSpec::new("username", OptionType::String),
+        OptionSpec::new("password", OptionType::String),
+    ])
+}
+
 // Generates a `ccsr::ClientConfig` based on the configuration extracted from
 // `extract_security_config()`. Currently only supports SSL auth.
-pub fn generate_ccsr_client_config(
+//
+// Returns an error if `ccsr_options` does not validate against the schema of
+// Confluent Schema Registry options.
+pub fn generate_ccsr_client_config<T: AstInfo>(
     csr_url: Url,
     _kafka_options: &BTreeMap<String, String>,
-    ccsr_options: &mut BTreeMap<String, Value>,
+    ccsr_options: &[SqlOption<T>],
 ) -> Result<mz_ccsr::ClientConfig, anyhow::Error> {
+    let mut ccsr_options = ccsr_option_schema().validate(ccsr_options)?;
     let mut client_config = mz_ccsr::ClientConfig::new(csr_url);
 
     // If provided, prefer SSL options from the schema registry configuration
-    if let Some(ca_path) = match ccsr_options.remove("ssl_ca_location").as_ref() {
-        Some(Value::String(path)) => Some(path),
-        Some(_) => {
-            bail!("ssl_ca_location must be a string");
-        }
-        None => None,
-    } {
+    if let Some(ca_path) = ccsr_options.take_string("ssl_ca_location") {
         let mut ca_buf = Vec::new();
         File::open(ca_path)?.read_to_end(&mut ca_buf)?;
         let cert = Certificate::from_pem(&ca_buf)?;
         client_config = client_config.add_root_certificate(cert);
     }
 
-    let ssl_key_location = ccsr_options.remove("ssl_key_location");
-    let key_path = match &&ssl_key_location {
-        Some(Value::String(path)) => Some(path),
-        Some(_) => {
-            bail!("ssl_key_location must be a string");
-        }
-        None => None,
-    };
-    let ssl_certificate_location = ccsr_options.remove("ssl_certificate_location");
-    let cert_path = match &ssl_certificate_location {
-        Some(Value::String(path)) => Some(path),
-        Some(_) => {
-            bail!("ssl_certificate_location must be a string");
-        }
-        None => None,
-    };
+    let key_path = ccsr_options.take_string("ssl_key_location");
+    let cert_path = ccsr_options.take_string("ssl_certificate_location");
     match (key_path, cert_path) {
         (Some(key_path), Some(cert_path)) => {
             // `reqwest` expects identity `pem` files to contain one key and
@@ -518,14 +624,11 @@ pub fn generate_ccsr_client_config(
         ),
     }
 
-    let mut ccsr_options = extract(
-        ccsr_options,
-        &[Config::string("username"), Config::string("password")],
-    )?;
-
-    if let Some(username) = ccsr_options.remove("username") {
-        client_config = client_config.auth(username, ccsr_options.remove("password"));
+    let password = ccsr_options.take_string("password");
+    if let Some(username) = ccsr_options.take_string("username") {
+        client_config = client_config.auth(username, password);
     }
 
+    ccsr_options.ensure_consumed()?;
     Ok(client_config)
 }
@@ -24,11 +24,11 @@ use mz_repr::ColumnName;
 use mz_sql_parser::ast::display::AstDisplay;
 use mz_sql_parser::ast::visit_mut::{self, VisitMut};
 use mz_sql_parser::ast::{
-    AstInfo, CreateIndexStatement, CreateSecretStatement, CreateSinkStatement,
-    CreateSourceStatement, CreateTableStatement, CreateTypeAs, CreateTypeStatement,
-    CreateViewStatement, Function, FunctionArgs, Ident, IfExistsBehavior, Op, Query, SqlOption,
-    Statement, TableFactor, TableFunction, UnresolvedObjectName, UnresolvedSchemaName, Value,
-    ViewDefinition,
+    AstInfo, CreateIndexStatement, CreatePublicationStatement, CreateSecretStatement,
+    CreateSinkStatement, CreateSourceStatement, CreateTableStatement, CreateTaskStatement,
+    CreateTypeAs, CreateTypeStatement, CreateViewStatement, Function, FunctionArgs, Ident,
+    IfExistsBehavior, Op, Query, SqlOption, Statement, TableFactor, TableFunction,
+    UnresolvedObjectName, UnresolvedSchemaName, Value, ViewDefinition,
 };
 
 use crate::names::{
@@ -198,6 +198,25 @@ pub fn create_statement(
         Ok(())
     }
 
+    // Options like `sasl_password_secret` name the secrets that configure a
+    // source or sink. Names that do not resolve are left as is, for the
+    // planner to report.
+    fn normalize_secret_options(scx: &StatementContext, with_options: &mut [SqlOption<Aug>]) {
+        for option in with_options {
+            if let SqlOption::ObjectName { name, object_name } = option {
+                if !ident_ref(name).ends_with("_secret") {
+                    continue;
+                }
+                let item = unresolved_object_name(object_name.clone())
+                    .ok()
+                    .and_then(|name| scx.catalog.resolve_item(&name).ok());
+                if let Some(item) = item {
+                    *object_name = unresolve(scx.catalog.resolve_full_name(item.name()));
+                }
+            }
+        }
+    }
+
     struct QueryNormalizer<'a> {
         scx: &'a StatementContext<'a>,
         ctes: Vec<Ident>,
@@ -295,7 +314,7 @@ pub fn create_statement(
             name,
             col_names: _,
             connector: _,
-            with_options: _,
+            with_options,
             format: _,
             include_metadata: _,
             envelope: _,
@@ -304,6 +323,7 @@ pub fn create_statement(
             key_constraint: _,
         }) => {
             *name = allocate_name(name)?;
+            normalize_secret_options(scx, with_options);
             *if_not_exists = false;
             *materialized = false;
         }
@@ -334,7 +354,7 @@ pub fn create_statement(
         Statement::CreateSink(CreateSinkStatement {
             name,
             connector: _,
-            with_options: _,
+            with_options,
             in_cluster: _,
             format: _,
             envelope: _,
@@ -344,6 +364,7 @@ pub fn create_statement(
             ..
         }) => {
             *name = allocate_name(name)?;
+            normalize_secret_options(scx, with_options);
             *if_not_exists = false;
         }
 
@@ -425,11 +446,38 @@ pub fn create_statement(
             name,
             if_not_exists,
             value: _,
+            with_options: _,
         }) => {
             *name = allocate_name(name)?;
             *if_not_exists = false;
         }
 
+        Statement::CreatePublication(CreatePublicationStatement {
+            name,
+            if_not_exists,
+            views: _,
+        }) => {
+            *name = allocate_name(name)?;
+            *if_not_exists = false;
+        }
+
+        Statement::CreateTask(CreateTaskStatement {
+            name,
+            if_not_exists,
+            schedule: _,
+            statement,
+        }) => {
+            *name = allocate_name(name)?;
+            {
+                let mut normalizer = QueryNormalizer::new(scx);
+                normalizer.visit_insert_statement_mut(statement);
+                if let Some(err) = normalizer.err {
+                    return Err(err.into());
+                }
+            }
+            *if_not_exists = false;
+        }
+
         _ => unreachable!(),
     }
 
@@ -465,6 +513,14 @@ macro_rules! with_option_type {
             _ => ::anyhow::bail!("expected Interval"),
         }
     };
+    ($name:expr, usize) => {
+        match $name {
+            Some(crate::ast::WithOptionValue::Value(crate::ast::Value::Number(value))) => {
+                value.parse::<usize>()?
+            }
+            _ => ::anyhow::bail!("expected usize"),
+        }
+    };
 }
 
 /// Ensures that the given set of options are empty, useful for validating that
@@ -500,6 +556,7 @@ pub(crate) fn ensure_empty_options<V>(
 ///   option which will be interpreted as true: (`WITH (name)`.
 /// - `Interval`: expects either a SQL interval or string that can be parsed as
 ///   an interval.
+/// - `usize`: expects a non-negative SQL integer (`WITH (name = 10)`).
 macro_rules! with_options {
   (struct $name:ident {
         $($field_name:ident: $field_type:ident,)*
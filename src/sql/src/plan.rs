@@ -31,6 +31,7 @@ use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use enum_kinds::EnumKind;
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 
 use mz_dataflow_types::client::ComputeInstanceId;
@@ -39,10 +40,11 @@ use mz_dataflow_types::sources::SourceConnector;
 use mz_expr::{GlobalId, MirRelationExpr, MirScalarExpr, RowSetFinishing};
 use mz_ore::now::{self, NOW_ZERO};
 use mz_repr::{ColumnName, Diff, RelationDesc, Row, ScalarType};
+use mz_secrets::SecretFormat;
 
 use crate::ast::{
-    ExplainOptions, ExplainStage, Expr, FetchDirection, NoticeSeverity, ObjectType, Raw, Statement,
-    TransactionAccessMode,
+    ExplainOptions, ExplainStage, Expr, FetchDirection, NoticeSeverity, ObjectType, Privilege, Raw,
+    Statement, TransactionAccessMode,
 };
 use crate::catalog::{CatalogType, IdReference};
 use crate::names::{
@@ -61,6 +63,7 @@ pub(crate) mod statement;
 pub(crate) mod transform_ast;
 pub(crate) mod transform_expr;
 pub(crate) mod typeconv;
+pub(crate) mod with_options;
 
 pub use self::expr::{HirRelationExpr, HirScalarExpr};
 pub use error::PlanError;
@@ -78,6 +81,8 @@ pub enum Plan {
     CreateComputeInstance(CreateComputeInstancePlan),
     CreateSource(CreateSourcePlan),
     CreateSecret(CreateSecretPlan),
+    CreatePublication(CreatePublicationPlan),
+    CreateTask(CreateTaskPlan),
     CreateSink(CreateSinkPlan),
     CreateTable(CreateTablePlan),
     CreateView(CreateViewPlan),
@@ -95,6 +100,8 @@ pub enum Plan {
     ShowAllVariables,
     ShowVariable(ShowVariablePlan),
     SetVariable(SetVariablePlan),
+    ResetVariable(ResetVariablePlan),
+    ResetAllVariables,
     StartTransaction(StartTransactionPlan),
     CommitTransaction,
     AbortTransaction,
@@ -103,6 +110,7 @@ pub enum Plan {
     SendRows(SendRowsPlan),
     CopyFrom(CopyFromPlan),
     Explain(ExplainPlan),
+    ExplainDrop(ExplainDropPlan),
     SendDiffs(SendDiffsPlan),
     Insert(InsertPlan),
     AlterNoop(AlterNoopPlan),
@@ -111,6 +119,16 @@ pub enum Plan {
     AlterIndexResetOptions(AlterIndexResetOptionsPlan),
     AlterIndexEnable(AlterIndexEnablePlan),
     AlterItemRename(AlterItemRenamePlan),
+    AlterSecret(AlterSecretPlan),
+    AlterDatabaseRename(AlterDatabaseRenamePlan),
+    AlterSchemaRename(AlterSchemaRenamePlan),
+    AlterRole(AlterRolePlan),
+    AlterRoleSet(AlterRoleSetPlan),
+    AlterSystemSet(AlterSystemSetPlan),
+    GrantPrivileges(UpdatePrivilegesPlan),
+    RevokePrivileges(UpdatePrivilegesPlan),
+    Comment(CommentPlan),
+    Undrop(UndropPlan),
     Declare(DeclarePlan),
     Fetch(FetchPlan),
     Close(ClosePlan),
@@ -119,6 +137,7 @@ pub enum Plan {
     Execute(ExecutePlan),
     Deallocate(DeallocatePlan),
     Raise(RaisePlan),
+    Admin(AdminPlan),
 }
 
 #[derive(Debug)]
@@ -142,6 +161,56 @@ pub struct CreateSchemaPlan {
 #[derive(Debug)]
 pub struct CreateRolePlan {
     pub name: String,
+    pub restrictions: RoleRestrictions,
+    /// The role's password, either in plain text or as a SCRAM-SHA-256
+    /// verifier, if it has one.
+    pub password: Option<String>,
+}
+
+/// Restrictions on the connections that a role may establish and on the
+/// objects that it may access.
+///
+/// The default restrictions are no restrictions at all.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleRestrictions {
+    /// The networks from which the role may connect. If empty, the role may
+    /// connect from any address.
+    pub allowed_addresses: Vec<IpNet>,
+    /// Whether the role's connections must use TLS.
+    pub require_tls: bool,
+    /// The names of the clusters that the role's sessions may use, or `None`
+    /// if the role may use any cluster.
+    pub allowed_clusters: Option<Vec<String>>,
+    /// Whether the role bypasses privilege checks. Roles that are not
+    /// superusers may only access the objects that they own or on which they
+    /// were granted privileges.
+    #[serde(default = "default_superuser")]
+    pub superuser: bool,
+    /// Whether the role may start sessions.
+    #[serde(default = "default_login")]
+    pub login: bool,
+}
+
+impl Default for RoleRestrictions {
+    fn default() -> RoleRestrictions {
+        RoleRestrictions {
+            allowed_addresses: vec![],
+            require_tls: false,
+            allowed_clusters: None,
+            superuser: true,
+            login: true,
+        }
+    }
+}
+
+/// Roles were superusers before they could be anything else.
+fn default_superuser() -> bool {
+    true
+}
+
+/// Roles could log in before they could be anything else.
+fn default_login() -> bool {
+    true
 }
 
 #[derive(Debug)]
@@ -151,7 +220,7 @@ pub struct CreateComputeInstancePlan {
     pub config: ComputeInstanceConfig,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ComputeInstanceConfig {
     Local,
     Remote {
@@ -175,13 +244,28 @@ impl ComputeInstanceConfig {
     }
 }
 
+/// Configuration of a replica of a compute instance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ComputeInstanceReplicaConfig {
+    /// A replica that runs on the specified hosts.
+    Remote { hosts: BTreeSet<String> },
+    /// A replica of the specified size whose processes are managed by the
+    /// orchestrator.
+    Managed { size: String },
+}
+
 /// Configuration of introspection for a compute instance.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ComputeInstanceIntrospectionConfig {
     /// Whether to introspect the introspection.
     pub debugging: bool,
     /// The interval at which to introspect.
     pub granularity: Duration,
+    /// How long to retain events in time-partitioned introspection sources.
+    ///
+    /// If unset, events are retained indefinitely.
+    #[serde(default)]
+    pub retention: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -200,6 +284,20 @@ pub struct CreateSecretPlan {
     pub if_not_exists: bool,
 }
 
+#[derive(Debug)]
+pub struct CreatePublicationPlan {
+    pub name: QualifiedObjectName,
+    pub publication: Publication,
+    pub if_not_exists: bool,
+}
+
+#[derive(Debug)]
+pub struct CreateTaskPlan {
+    pub name: QualifiedObjectName,
+    pub task: Task,
+    pub if_not_exists: bool,
+}
+
 #[derive(Debug)]
 pub struct CreateSinkPlan {
     pub name: QualifiedObjectName,
@@ -281,10 +379,16 @@ pub struct ShowVariablePlan {
 #[derive(Debug)]
 pub struct SetVariablePlan {
     pub name: String,
-    pub value: String,
+    /// The new value, or `None` to restore the default value.
+    pub value: Option<String>,
     pub local: bool,
 }
 
+#[derive(Debug)]
+pub struct ResetVariablePlan {
+    pub name: String,
+}
+
 #[derive(Debug)]
 pub struct PeekPlan {
     pub source: MirRelationExpr,
@@ -300,6 +404,27 @@ pub struct TailPlan {
     pub when: QueryWhen,
     pub copy_to: Option<CopyFormat>,
     pub emit_progress: bool,
+    /// The shape of the rows the tail emits.
+    pub envelope: TailEnvelope,
+    /// The number of batches the client may leave unacknowledged before the
+    /// tail is paused, if the tail is flow controlled.
+    pub max_unacked_batches: Option<usize>,
+    /// The timestamp at which the tail ends, if any. Only updates at times
+    /// before this timestamp are emitted.
+    pub up_to: Option<MirScalarExpr>,
+}
+
+/// The shape of the rows a `TAIL` emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailEnvelope {
+    /// Rows of the form `(mz_timestamp, [mz_progressed], mz_diff, columns...)`,
+    /// where the `mz_progressed` column is present only with `PROGRESS`.
+    Updates,
+    /// Rows of the form `(mz_timestamp, mz_diff, columns...)`. Within each
+    /// timestamp, retractions precede insertions, and every timestamp with
+    /// updates is followed by a progress row, whose `mz_diff` is `NULL`, at a
+    /// later timestamp.
+    Diffs,
 }
 
 #[derive(Debug)]
@@ -330,6 +455,16 @@ pub struct ExplainPlan {
     pub row_set_finishing: Option<RowSetFinishing>,
     pub stage: ExplainStage,
     pub options: ExplainOptions,
+    /// The items that the statement refers to.
+    pub depends_on: Vec<GlobalId>,
+}
+
+#[derive(Debug)]
+pub struct ExplainDropPlan {
+    /// The plan of the `DROP` statement to explain, which is one of
+    /// [`Plan::DropDatabase`], [`Plan::DropSchema`], [`Plan::DropItems`], or
+    /// [`Plan::DropComputeInstances`].
+    pub plan: Box<Plan>,
 }
 
 #[derive(Debug)]
@@ -370,12 +505,16 @@ pub struct AlterComputeInstancePlan {
 pub struct AlterIndexSetOptionsPlan {
     pub id: GlobalId,
     pub options: Vec<IndexOption>,
+    /// The `CREATE INDEX` statement of the index, with the new options.
+    pub create_sql: String,
 }
 
 #[derive(Debug)]
 pub struct AlterIndexResetOptionsPlan {
     pub id: GlobalId,
     pub options: Vec<IndexOptionName>,
+    /// The `CREATE INDEX` statement of the index, without the reset options.
+    pub create_sql: String,
 }
 
 #[derive(Debug)]
@@ -391,6 +530,99 @@ pub struct AlterItemRenamePlan {
     pub object_type: ObjectType,
 }
 
+#[derive(Debug)]
+pub struct AlterSecretPlan {
+    pub id: GlobalId,
+    pub secret_as: MirScalarExpr,
+    /// The format to which the new contents of the secret must conform.
+    pub format: SecretFormat,
+}
+
+#[derive(Debug)]
+pub struct AlterDatabaseRenamePlan {
+    /// The ID of the database to rename, or `None` if it does not exist and
+    /// the statement specified `IF EXISTS`.
+    pub id: Option<DatabaseId>,
+    pub to_name: String,
+}
+
+#[derive(Debug)]
+pub struct AlterSchemaRenamePlan {
+    /// The IDs of the schema to rename and its database, or `None` if the
+    /// schema does not exist and the statement specified `IF EXISTS`.
+    pub id: Option<(DatabaseId, SchemaId)>,
+    pub to_name: String,
+}
+
+#[derive(Debug)]
+pub struct AlterRolePlan {
+    pub id: i64,
+    pub name: String,
+    /// Whether the role should be a superuser, or `None` to leave it
+    /// unchanged.
+    pub superuser: Option<bool>,
+    /// Whether the role should be able to log in, or `None` to leave it
+    /// unchanged.
+    pub login: Option<bool>,
+    /// The role's new password, either in plain text or as a SCRAM-SHA-256
+    /// verifier, `Some(None)` to remove its password, or `None` to leave it
+    /// unchanged.
+    pub password: Option<Option<String>>,
+}
+
+#[derive(Debug)]
+pub struct AlterRoleSetPlan {
+    /// The ID of the role whose default to change, or `None` if the default
+    /// applies to every role.
+    pub role_id: Option<i64>,
+    pub name: String,
+    /// The new default, or `None` if the existing default should be removed.
+    pub value: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct AlterSystemSetPlan {
+    pub name: String,
+    /// The new value, or `None` if the default value should be restored.
+    pub value: Option<String>,
+}
+
+/// A plan to grant or revoke privileges.
+#[derive(Debug)]
+pub struct UpdatePrivilegesPlan {
+    pub object: PrivilegeObjectId,
+    pub privileges: Vec<Privilege>,
+    /// The IDs of the roles whose privileges to update, where `None` stands for
+    /// every role.
+    pub role_ids: Vec<Option<i64>>,
+}
+
+/// Identifies an object on which privileges can be granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegeObjectId {
+    Database(DatabaseId),
+    Schema(SchemaId),
+    Item(GlobalId),
+}
+
+#[derive(Debug)]
+pub struct UndropPlan {
+    /// The ID that the dropped item had.
+    pub id: GlobalId,
+    /// The plan that recreates the item from its definition.
+    pub plan: Box<Plan>,
+}
+
+#[derive(Debug)]
+pub struct CommentPlan {
+    pub id: GlobalId,
+    /// The 1-based position of the commented column, or `None` if the comment
+    /// applies to the object itself.
+    pub column_position: Option<usize>,
+    /// The new comment, or `None` if the existing comment should be removed.
+    pub comment: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct DeclarePlan {
     pub name: String,
@@ -432,6 +664,86 @@ pub struct RaisePlan {
     pub severity: NoticeSeverity,
 }
 
+/// A plan for a `SELECT` statement that calls one of the administrative
+/// functions in `mz_internal`, e.g., `SELECT mz_internal.gc_secrets()`.
+#[derive(Debug)]
+pub struct AdminPlan {
+    pub operation: AdminOperation,
+    /// The description of the single row that reports the operation's
+    /// outcome.
+    pub desc: RelationDesc,
+}
+
+/// An administrative operation.
+#[derive(Debug)]
+pub enum AdminOperation {
+    /// Restarts the named replica of a compute instance.
+    RestartReplica {
+        compute_instance: ComputeInstanceId,
+        replica: String,
+    },
+    /// Describes how often the processes of the named replica of a compute
+    /// instance have exited.
+    ReplicaDiagnostics {
+        compute_instance: ComputeInstanceId,
+        replica: String,
+    },
+    /// Recomputes an index, or every index on a relation, from its inputs.
+    RecomputeMaterialization { id: GlobalId },
+    /// Rebuilds the upsert state of a source from the source's retained
+    /// history, by recomputing every index that reads from the source.
+    RebuildUpsertState { id: GlobalId },
+    /// Deletes the secrets that do not belong to any catalog item.
+    GcSecrets,
+    /// Copies the secrets that have not yet been migrated to a new secrets
+    /// backend.
+    MigrateSecrets,
+    /// Starts collecting statistics about every user index.
+    CollectStatistics,
+    /// Writes a consistent snapshot of the catalog to a new file.
+    BackupCatalog { path: String },
+    /// Checks the referential integrity of the catalog's storage.
+    CheckCatalog,
+    /// Takes a CPU profile of the server process, and of the remote processes
+    /// that serve its storage and compute layers, for the given duration.
+    CpuProfile { duration: Duration },
+    /// Cancels the statement that the identified connection is running.
+    CancelQuery { conn_id: u32 },
+    /// Requests that the server reload its runtime configuration.
+    ReloadConfig,
+}
+
+impl AdminOperation {
+    /// Returns the name of the function that requests the operation.
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            AdminOperation::RestartReplica { .. } => "restart_replica",
+            AdminOperation::ReplicaDiagnostics { .. } => "replica_diagnostics",
+            AdminOperation::RecomputeMaterialization { .. } => "recompute_materialization",
+            AdminOperation::RebuildUpsertState { .. } => "rebuild_upsert_state",
+            AdminOperation::GcSecrets => "gc_secrets",
+            AdminOperation::MigrateSecrets => "migrate_secrets",
+            AdminOperation::CollectStatistics => "collect_statistics",
+            AdminOperation::BackupCatalog { .. } => "backup_catalog",
+            AdminOperation::CheckCatalog => "check_catalog",
+            AdminOperation::CpuProfile { .. } => "mz_cpu_profile",
+            AdminOperation::CancelQuery { .. } => "mz_cancel_query",
+            AdminOperation::ReloadConfig => "pg_reload_conf",
+        }
+    }
+
+    /// Returns the schema-qualified name of the function that requests the
+    /// operation.
+    pub fn qualified_function_name(&self) -> String {
+        let schema = match self {
+            AdminOperation::CancelQuery { .. } => "mz_catalog",
+            AdminOperation::ReloadConfig => "pg_catalog",
+            _ => "mz_internal",
+        };
+        format!("{}.{}", schema, self.function_name())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Table {
     pub create_sql: String,
@@ -446,12 +758,32 @@ pub struct Source {
     pub create_sql: String,
     pub connector: SourceConnector,
     pub desc: RelationDesc,
+    /// The secrets that configure the source.
+    pub depends_on: Vec<GlobalId>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Secret {
     pub create_sql: String,
     pub secret_as: MirScalarExpr,
+    /// The format to which the contents of the secret must conform.
+    pub format: SecretFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct Publication {
+    pub create_sql: String,
+    /// The views that the publication publishes, in the order in which they
+    /// were named.
+    pub views: Vec<GlobalId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub create_sql: String,
+    /// The interval at which the task runs.
+    pub schedule: Duration,
+    pub depends_on: Vec<GlobalId>,
 }
 
 #[derive(Clone, Debug)]
@@ -500,6 +832,12 @@ pub enum QueryWhen {
     ///
     /// The expression may have any type.
     AtTimestamp(MirScalarExpr),
+    /// The peek should occur at a timestamp chosen as for `Immediately`, but
+    /// no earlier than the timestamp described by the specified expression.
+    /// The peek waits for its inputs to reach that timestamp if need be.
+    ///
+    /// The expression may have any type.
+    AtLeastTimestamp(MirScalarExpr),
 }
 
 #[derive(Debug)]
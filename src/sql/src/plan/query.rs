@@ -55,10 +55,11 @@ use mz_repr::{
 
 use mz_sql_parser::ast::visit_mut::{self, VisitMut};
 use mz_sql_parser::ast::{
-    Assignment, DeleteStatement, Distinct, Expr, Function, FunctionArgs, HomogenizingFunction,
-    Ident, InsertSource, IsExprConstruct, Join, JoinConstraint, JoinOperator, Limit, OrderByExpr,
-    Query, Select, SelectItem, SetExpr, SetOperator, SubscriptPosition, TableAlias, TableFactor,
-    TableFunction, TableWithJoins, UnresolvedObjectName, UpdateStatement, Value, Values,
+    AsOf, Assignment, DeleteStatement, Distinct, Expr, Function, FunctionArgs,
+    HomogenizingFunction, Ident, InsertSource, IsExprConstruct, Join, JoinConstraint, JoinOperator,
+    Limit, OrderByExpr, Query, Select, SelectItem, SetExpr, SetOperator, SubscriptPosition,
+    TableAlias, TableFactor, TableFunction, TableWithJoins, UnresolvedObjectName, UpdateStatement,
+    Value, Values,
 };
 
 use crate::catalog::{CatalogItemType, CatalogType, SessionCatalog};
@@ -743,13 +744,40 @@ where
     Ok(expr.map(map_exprs).project(project_key))
 }
 
-/// Plans an expression in the AS OF position of a `SELECT` or `TAIL` statement.
-pub fn plan_as_of(scx: &StatementContext, expr: Option<Expr<Aug>>) -> Result<QueryWhen, PlanError> {
-    let mut expr = match expr {
-        None => return Ok(QueryWhen::Immediately),
-        Some(expr) => expr,
-    };
+/// Plans the `AS OF` clause of a `SELECT` or `TAIL` statement.
+pub fn plan_as_of(
+    scx: &StatementContext,
+    as_of: Option<AsOf<Aug>>,
+) -> Result<QueryWhen, PlanError> {
+    match as_of {
+        None => Ok(QueryWhen::Immediately),
+        Some(AsOf::At(expr)) => Ok(QueryWhen::AtTimestamp(plan_timestamp_expr(
+            scx, "AS OF", expr,
+        )?)),
+        Some(AsOf::AtLeast(expr)) => Ok(QueryWhen::AtLeastTimestamp(plan_timestamp_expr(
+            scx,
+            "AS OF AT LEAST",
+            expr,
+        )?)),
+    }
+}
+
+/// Plans the expression in the `UP TO` clause of a `TAIL`.
+pub fn plan_up_to(
+    scx: &StatementContext,
+    expr: Option<Expr<Aug>>,
+) -> Result<Option<MirScalarExpr>, PlanError> {
+    expr.map(|expr| plan_timestamp_expr(scx, "UP TO", expr))
+        .transpose()
+}
 
+/// Plans an expression that the coordinator evaluates to a timestamp, like
+/// the expression in an `AS OF` clause.
+fn plan_timestamp_expr(
+    scx: &StatementContext,
+    name: &'static str,
+    mut expr: Expr<Aug>,
+) -> Result<MirScalarExpr, PlanError> {
     let scope = Scope::empty();
     let desc = RelationDesc::empty();
     let qcx = QueryContext::root(scx, QueryLifetime::OneShot(scx.pcx()?));
@@ -758,7 +786,7 @@ pub fn plan_as_of(scx: &StatementContext, expr: Option<Expr<Aug>>) -> Result<Que
 
     let ecx = &ExprContext {
         qcx: &qcx,
-        name: "AS OF",
+        name,
         scope: &scope,
         relation_type: &desc.typ(),
         allow_aggregates: false,
@@ -768,7 +796,7 @@ pub fn plan_as_of(scx: &StatementContext, expr: Option<Expr<Aug>>) -> Result<Que
     let expr = plan_expr(ecx, &expr)?
         .type_as_any(ecx)?
         .lower_uncorrelated()?;
-    Ok(QueryWhen::AtTimestamp(expr))
+    Ok(expr)
 }
 
 /// Plans an expression in the AS position of a `CREATE SECRET`.
@@ -120,15 +120,28 @@ pub fn describe(
         Statement::CreateRole(stmt) => Some(ddl::describe_create_role(&scx, stmt)?),
         Statement::CreateCluster(stmt) => Some(ddl::describe_create_cluster(&scx, stmt)?),
         Statement::CreateSecret(stmt) => Some(ddl::describe_create_secret(&scx, stmt)?),
+        Statement::CreatePublication(stmt) => Some(ddl::describe_create_publication(&scx, stmt)?),
+        Statement::CreateTask(stmt) => Some(ddl::describe_create_task(&scx, stmt)?),
         Statement::DropDatabase(stmt) => Some(ddl::describe_drop_database(&scx, stmt)?),
         Statement::DropSchema(stmt) => Some(ddl::describe_drop_schema(&scx, stmt)?),
         Statement::DropObjects(stmt) => Some(ddl::describe_drop_objects(&scx, stmt)?),
         Statement::DropRoles(stmt) => Some(ddl::describe_drop_role(&scx, stmt)?),
         Statement::DropClusters(stmt) => Some(ddl::describe_drop_cluster(&scx, stmt)?),
         Statement::AlterObjectRename(stmt) => Some(ddl::describe_alter_object_rename(&scx, stmt)?),
+        Statement::AlterDatabaseRename(stmt) => {
+            Some(ddl::describe_alter_database_rename(&scx, stmt)?)
+        }
+        Statement::AlterSchemaRename(stmt) => Some(ddl::describe_alter_schema_rename(&scx, stmt)?),
         Statement::AlterIndex(stmt) => Some(ddl::describe_alter_index_options(&scx, stmt)?),
         Statement::AlterSecret(stmt) => Some(ddl::describe_alter_secret_options(&scx, stmt)?),
         Statement::AlterCluster(stmt) => Some(ddl::describe_alter_cluster(&scx, stmt)?),
+        Statement::AlterRole(stmt) => Some(ddl::describe_alter_role(&scx, stmt)?),
+        Statement::AlterRoleSet(stmt) => Some(ddl::describe_alter_role_set(&scx, stmt)?),
+        Statement::AlterSystemSet(stmt) => Some(ddl::describe_alter_system_set(&scx, stmt)?),
+        Statement::GrantPrivileges(stmt) => Some(ddl::describe_grant_privileges(&scx, stmt)?),
+        Statement::RevokePrivileges(stmt) => Some(ddl::describe_revoke_privileges(&scx, stmt)?),
+        Statement::Comment(stmt) => Some(ddl::describe_comment(&scx, stmt)?),
+        Statement::Undrop(stmt) => Some(ddl::describe_undrop(&scx, stmt)?),
 
         // `SHOW` statements.
         Statement::ShowCreateTable(stmt) => Some(show::describe_show_create_table(&scx, stmt)?),
@@ -140,10 +153,12 @@ pub fn describe(
         Statement::ShowDatabases(_) => None,
         Statement::ShowSchemas(_) => None,
         Statement::ShowObjects(_) => None,
+        Statement::ShowDroppedObjects(_) => None,
         Statement::ShowIndexes(_) => None,
 
         // SCL statements.
         Statement::SetVariable(stmt) => Some(scl::describe_set_variable(&scx, stmt)?),
+        Statement::ResetVariable(stmt) => Some(scl::describe_reset_variable(&scx, stmt)?),
         Statement::ShowVariable(stmt) => Some(scl::describe_show_variable(&scx, stmt)?),
         Statement::Discard(stmt) => Some(scl::describe_discard(&scx, stmt)?),
         Statement::Declare(stmt) => Some(scl::describe_declare(&scx, stmt)?),
@@ -159,6 +174,7 @@ pub fn describe(
         Statement::Delete(_) => None,
         Statement::Select(_) => None,
         Statement::Explain(_) => None,
+        Statement::ExplainDrop(stmt) => Some(ddl::describe_explain_drop(&scx, stmt)?),
         Statement::Tail(_) => None,
         Statement::Copy(_) => None,
 
@@ -178,6 +194,7 @@ pub fn describe(
         | Statement::ShowDatabases(_)
         | Statement::ShowSchemas(_)
         | Statement::ShowObjects(_)
+        | Statement::ShowDroppedObjects(_)
         | Statement::ShowIndexes(_)
         | Statement::Insert(_)
         | Statement::Update(_)
@@ -196,6 +213,9 @@ pub fn describe(
         Some(Statement::ShowDatabases(stmt)) => show::show_databases(&scx, stmt)?.describe()?,
         Some(Statement::ShowSchemas(stmt)) => show::show_schemas(&scx, stmt)?.describe()?,
         Some(Statement::ShowObjects(stmt)) => show::show_objects(&scx, stmt)?.describe()?,
+        Some(Statement::ShowDroppedObjects(stmt)) => {
+            show::show_dropped_objects(&scx, stmt)?.describe()?
+        }
         Some(Statement::ShowIndexes(stmt)) => show::show_indexes(&scx, stmt)?.describe()?,
 
         // SCL statements.
@@ -317,6 +337,14 @@ pub fn plan(
             let (stmt, _) = resolve_stmt!(Statement::CreateSecret, scx, stmt);
             ddl::plan_create_secret(scx, stmt)
         }
+        stmt @ Statement::CreatePublication(_) => {
+            let (stmt, _) = resolve_stmt!(Statement::CreatePublication, scx, stmt);
+            ddl::plan_create_publication(scx, stmt)
+        }
+        stmt @ Statement::CreateTask(_) => {
+            let (stmt, depends_on) = resolve_stmt!(Statement::CreateTask, scx, stmt);
+            ddl::plan_create_task(scx, stmt, depends_on)
+        }
         Statement::DropDatabase(stmt) => ddl::plan_drop_database(scx, stmt),
         Statement::DropSchema(stmt) => ddl::plan_drop_schema(scx, stmt),
         Statement::DropObjects(stmt) => ddl::plan_drop_objects(scx, stmt),
@@ -333,6 +361,8 @@ pub fn plan(
             ddl::plan_alter_index_options(scx, stmt)
         }
         Statement::AlterObjectRename(stmt) => ddl::plan_alter_object_rename(scx, stmt),
+        Statement::AlterDatabaseRename(stmt) => ddl::plan_alter_database_rename(scx, stmt),
+        Statement::AlterSchemaRename(stmt) => ddl::plan_alter_schema_rename(scx, stmt),
 
         stmt @ Statement::AlterSecret(_) => {
             let (stmt, _) = resolve_stmt!(Statement::AlterSecret, scx, stmt);
@@ -342,6 +372,20 @@ pub fn plan(
             let (stmt, _) = resolve_stmt!(Statement::AlterCluster, scx, stmt);
             ddl::plan_alter_cluster(scx, stmt)
         }
+        stmt @ Statement::AlterRole(_) => {
+            let (stmt, _) = resolve_stmt!(Statement::AlterRole, scx, stmt);
+            ddl::plan_alter_role(scx, stmt)
+        }
+        stmt @ Statement::AlterRoleSet(_) => {
+            let (stmt, _) = resolve_stmt!(Statement::AlterRoleSet, scx, stmt);
+            ddl::plan_alter_role_set(scx, stmt)
+        }
+        Statement::AlterSystemSet(stmt) => ddl::plan_alter_system_set(scx, stmt),
+        Statement::GrantPrivileges(stmt) => ddl::plan_grant_privileges(scx, stmt),
+        Statement::RevokePrivileges(stmt) => ddl::plan_revoke_privileges(scx, stmt),
+        Statement::Comment(stmt) => ddl::plan_comment(scx, stmt),
+        Statement::Undrop(stmt) => ddl::plan_undrop(scx, stmt),
+        Statement::ExplainDrop(stmt) => ddl::plan_explain_drop(scx, stmt),
 
         // DML statements.
         stmt @ Statement::Insert(_) => {
@@ -361,8 +405,8 @@ pub fn plan(
             dml::plan_select(scx, stmt, params, None)
         }
         stmt @ Statement::Explain(_) => {
-            let (stmt, _) = resolve_stmt!(Statement::Explain, scx, stmt);
-            dml::plan_explain(scx, stmt, params)
+            let (stmt, depends_on) = resolve_stmt!(Statement::Explain, scx, stmt);
+            dml::plan_explain(scx, stmt, params, depends_on)
         }
         stmt @ Statement::Tail(_) => {
             let (stmt, depends_on) = resolve_stmt!(Statement::Tail, scx, stmt);
@@ -414,12 +458,20 @@ pub fn plan(
             let (stmt, _) = resolve_stmt!(Statement::ShowObjects, scx, stmt);
             show::show_objects(scx, stmt)?.plan()
         }
+        stmt @ Statement::ShowDroppedObjects(_) => {
+            let (stmt, _) = resolve_stmt!(Statement::ShowDroppedObjects, scx, stmt);
+            show::show_dropped_objects(scx, stmt)?.plan()
+        }
 
         // SCL statements.
         stmt @ Statement::SetVariable(_) => {
             let (stmt, _) = resolve_stmt!(Statement::SetVariable, scx, stmt);
             scl::plan_set_variable(scx, stmt)
         }
+        stmt @ Statement::ResetVariable(_) => {
+            let (stmt, _) = resolve_stmt!(Statement::ResetVariable, scx, stmt);
+            scl::plan_reset_variable(scx, stmt)
+        }
         stmt @ Statement::ShowVariable(_) => {
             let (stmt, _) = resolve_stmt!(Statement::ShowVariable, scx, stmt);
             scl::plan_show_variable(scx, stmt)
@@ -498,7 +550,9 @@ impl PartialEq<ObjectType> for CatalogItemType {
             | (CatalogItemType::View, ObjectType::View)
             | (CatalogItemType::Index, ObjectType::Index)
             | (CatalogItemType::Type, ObjectType::Type)
-            | (CatalogItemType::Secret, ObjectType::Secret) => true,
+            | (CatalogItemType::Secret, ObjectType::Secret)
+            | (CatalogItemType::Publication, ObjectType::Publication)
+            | (CatalogItemType::Task, ObjectType::Task) => true,
             (_, _) => false,
         }
     }
@@ -736,6 +790,11 @@ impl<'a> StatementContext<'a> {
     }
 
     pub fn require_experimental_mode(&self, feature_name: &str) -> Result<(), anyhow::Error> {
+        // Experimental mode cannot be enabled in safe mode, so point the user
+        // at the restriction that actually applies.
+        if self.catalog.config().safe_mode {
+            bail!("cannot use {} in safe mode", feature_name)
+        }
         if !self.experimental_mode() {
             bail!(
                 "{} requires experimental mode; see \
@@ -14,6 +14,7 @@
 
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -22,6 +23,7 @@ use aws_arn::ARN;
 use bytes::Bytes;
 use chrono::{NaiveDate, NaiveDateTime};
 use globset::GlobBuilder;
+use ipnet::IpNet;
 use itertools::Itertools;
 use mz_postgres_util::TableInfo;
 use prost::Message;
@@ -42,8 +44,8 @@ use mz_dataflow_types::sources::{
     provide_default_metadata, DebeziumDedupProjection, DebeziumEnvelope, DebeziumMode,
     DebeziumSourceProjection, ExternalSourceConnector, FileSourceConnector, IncludedColumnPos,
     KafkaSourceConnector, KeyEnvelope, KinesisSourceConnector, PostgresSourceConnector,
-    PubNubSourceConnector, S3SourceConnector, SourceConnector, SourceEnvelope, Timeline,
-    UnplannedSourceEnvelope, UpsertStyle,
+    PubNubSourceConnector, S3SourceConnector, SchemaChangePolicy, SourceConnector, SourceEnvelope,
+    Timeline, UnplannedSourceEnvelope, UpsertStyle,
 };
 use mz_expr::{CollectionPlan, GlobalId};
 use mz_interchange::avro::{self, AvroSchemaGenerator};
@@ -51,23 +53,29 @@ use mz_interchange::envelopes;
 use mz_ore::collections::CollectionExt;
 use mz_ore::str::StrExt;
 use mz_repr::{strconv, ColumnName, RelationDesc, RelationType, ScalarType};
+use mz_secrets::SecretFormat;
 
 use crate::ast::display::AstDisplay;
+use crate::ast::transform;
 use crate::ast::visit::Visit;
 use crate::ast::{
-    AlterClusterStatement, AlterIndexAction, AlterIndexStatement, AlterObjectRenameStatement,
-    AlterSecretStatement, AstInfo, AvroSchema, ClusterOption, ColumnOption, Compression,
-    CreateClusterStatement, CreateDatabaseStatement, CreateIndexStatement, CreateRoleOption,
-    CreateRoleStatement, CreateSchemaStatement, CreateSecretStatement, CreateSinkConnector,
-    CreateSinkStatement, CreateSourceConnector, CreateSourceFormat, CreateSourceStatement,
-    CreateTableStatement, CreateTypeAs, CreateTypeStatement, CreateViewStatement,
-    CreateViewsDefinitions, CreateViewsSourceTarget, CreateViewsStatement, CsrConnectorAvro,
-    CsrConnectorProto, CsrSeedCompiled, CsrSeedCompiledOrLegacy, CsvColumns, DbzMode,
-    DropClustersStatement, DropDatabaseStatement, DropObjectsStatement, DropRolesStatement,
-    DropSchemaStatement, Envelope, Expr, Format, Ident, IfExistsBehavior, KafkaConsistency,
-    KeyConstraint, ObjectType, Op, ProtobufSchema, Query, Raw, Select, SelectItem, SetExpr,
-    SourceIncludeMetadata, SourceIncludeMetadataType, SqlOption, Statement, SubscriptPosition,
-    TableConstraint, TableFactor, TableWithJoins, UnresolvedDatabaseName, UnresolvedObjectName,
+    AlterClusterStatement, AlterDatabaseRenameStatement, AlterIndexAction, AlterIndexStatement,
+    AlterObjectRenameStatement, AlterRoleSetStatement, AlterRoleStatement,
+    AlterSchemaRenameStatement, AlterSecretStatement, AlterSystemSetStatement, AstInfo, AvroSchema,
+    ClusterOption, ColumnOption, CommentObject, CommentStatement, Compression,
+    CreateClusterStatement, CreateDatabaseStatement, CreateIndexStatement,
+    CreatePublicationStatement, CreateRoleOption, CreateRoleStatement, CreateSchemaStatement,
+    CreateSecretStatement, CreateSinkConnector, CreateSinkStatement, CreateSourceConnector,
+    CreateSourceFormat, CreateSourceStatement, CreateTableStatement, CreateTaskStatement,
+    CreateTypeAs, CreateTypeStatement, CreateViewStatement, CreateViewsDefinitions,
+    CreateViewsSourceTarget, CreateViewsStatement, CsrConnectorAvro, CsrConnectorProto,
+    CsrSeedCompiled, CsrSeedCompiledOrLegacy, CsvColumns, DbzMode, DropClustersStatement,
+    DropDatabaseStatement, DropObjectsStatement, DropRolesStatement, DropSchemaStatement, Envelope,
+    ExplainDropStatement, Expr, Format, GrantPrivilegesStatement, Ident, IfExistsBehavior,
+    KafkaConsistency, KeyConstraint, ObjectType, Op, Privilege, PrivilegeObject, ProtobufSchema,
+    Query, Raw, RevokePrivilegesStatement, Select, SelectItem, SetExpr, SourceIncludeMetadata,
+    SourceIncludeMetadataType, SqlOption, Statement, SubscriptPosition, TableConstraint,
+    TableFactor, TableWithJoins, UndropStatement, UnresolvedDatabaseName, UnresolvedObjectName,
     Value, ViewDefinition, WithOption,
 };
 use crate::catalog::{CatalogItem, CatalogItemType, CatalogType, CatalogTypeDetails};
@@ -79,17 +87,23 @@ use crate::names::{
 };
 use crate::normalize;
 use crate::normalize::ident;
+use crate::parse;
 use crate::plan::error::PlanError;
 use crate::plan::query::QueryLifetime;
-use crate::plan::statement::{StatementContext, StatementDesc};
+use crate::plan::statement::{scl, StatementContext, StatementDesc};
+use crate::plan::with_options::{Deprecation, OptionSchema, OptionSpec, OptionType, WithOptions};
 use crate::plan::{
-    plan_utils, query, AlterComputeInstancePlan, AlterIndexEnablePlan, AlterIndexResetOptionsPlan,
-    AlterIndexSetOptionsPlan, AlterItemRenamePlan, AlterNoopPlan, ComputeInstanceConfig,
-    ComputeInstanceIntrospectionConfig, CreateComputeInstancePlan, CreateDatabasePlan,
-    CreateIndexPlan, CreateRolePlan, CreateSchemaPlan, CreateSecretPlan, CreateSinkPlan,
-    CreateSourcePlan, CreateTablePlan, CreateTypePlan, CreateViewPlan, CreateViewsPlan,
+    plan_utils, query, AlterComputeInstancePlan, AlterDatabaseRenamePlan, AlterIndexEnablePlan,
+    AlterIndexResetOptionsPlan, AlterIndexSetOptionsPlan, AlterItemRenamePlan, AlterNoopPlan,
+    AlterRolePlan, AlterRoleSetPlan, AlterSchemaRenamePlan, AlterSecretPlan, AlterSystemSetPlan,
+    CommentPlan, ComputeInstanceConfig, ComputeInstanceIntrospectionConfig,
+    CreateComputeInstancePlan, CreateDatabasePlan, CreateIndexPlan, CreatePublicationPlan,
+    CreateRolePlan, CreateSchemaPlan, CreateSecretPlan, CreateSinkPlan, CreateSourcePlan,
+    CreateTablePlan, CreateTaskPlan, CreateTypePlan, CreateViewPlan, CreateViewsPlan,
     DropComputeInstancesPlan, DropDatabasePlan, DropItemsPlan, DropRolesPlan, DropSchemaPlan,
-    Index, IndexOption, IndexOptionName, Params, Plan, Secret, Sink, Source, Table, Type, View,
+    ExplainDropPlan, Index, IndexOption, IndexOptionName, Params, Plan, PrivilegeObjectId,
+    Publication, RoleRestrictions, Secret, Sink, Source, Table, Task, Type, UndropPlan,
+    UpdatePrivilegesPlan, View,
 };
 use crate::pure::Schema;
 
@@ -295,10 +309,89 @@ pub fn describe_create_source(
     Ok(StatementDesc::new(None))
 }
 
+/// Returns the schema of the `WITH` options of `CREATE SOURCE`, which depends
+/// on the source's connector and envelope.
+fn source_option_schema(connector: &CreateSourceConnector, envelope: &Envelope) -> OptionSchema {
+    let schema = OptionSchema::new("CREATE SOURCE").with([
+        OptionSpec::new("timestamp_frequency_ms", OptionType::NonNegativeInteger),
+        OptionSpec::new(
+            "schema_change_policy",
+            OptionType::OneOf(&["error", "ignore_new_columns", "add_nullable_columns"]),
+        ),
+        OptionSpec::new("ignore_source_keys", OptionType::Bool).default(Value::Boolean(false)),
+        OptionSpec::new("timeline", OptionType::String),
+    ]);
+    let schema = match connector {
+        CreateSourceConnector::Kafka(_) => schema.with(kafka_config_option_specs()).with([
+            OptionSpec::new("group_id_prefix", OptionType::String),
+            OptionSpec::new("start_offset", OptionType::NonNegativeIntegers),
+        ]),
+        CreateSourceConnector::Kinesis { .. } | CreateSourceConnector::S3 { .. } => {
+            schema.with(aws_option_specs())
+        }
+        CreateSourceConnector::File { .. } => {
+            schema.with([OptionSpec::new("tail", OptionType::Bool).default(Value::Boolean(false))])
+        }
+        CreateSourceConnector::AvroOcf { .. } => schema.with([
+            OptionSpec::new("tail", OptionType::Bool).default(Value::Boolean(false)),
+            // Purification inlines the schema of the file.
+            OptionSpec::new("reader_schema", OptionType::String).required(),
+        ]),
+        CreateSourceConnector::Postgres { .. } => schema.with([
+            OptionSpec::new("snapshot_parallelism", OptionType::PositiveInteger)
+                .default(Value::Number("1".into())),
+            OptionSpec::new("snapshot_range_size", OptionType::PositiveInteger)
+                .default(Value::Number("1073741824".into())),
+        ]),
+        CreateSourceConnector::PubNub { .. } => schema,
+    };
+    match envelope {
+        Envelope::Debezium(DbzMode::Plain) => schema.with([
+            OptionSpec::new(
+                "deduplication",
+                OptionType::OneOf(&["none", "ordered", "full", "full_in_range"]),
+            ),
+            OptionSpec::new("deduplication_start", OptionType::String),
+            OptionSpec::new("deduplication_end", OptionType::String),
+            OptionSpec::new("deduplication_pad_start", OptionType::String),
+        ]),
+        Envelope::CdcV2 => schema.with([OptionSpec::new("epoch_ms_timeline", OptionType::Bool)]),
+        Envelope::None | Envelope::Debezium(DbzMode::Upsert) | Envelope::Upsert => schema,
+    }
+}
+
+/// Returns the specifications of the Kafka client options, which
+/// [`kafka_util::extract_config`] validates.
+fn kafka_config_option_specs() -> Vec<OptionSpec> {
+    kafka_util::config_option_names()
+        .into_iter()
+        .map(|name| OptionSpec::new(name, OptionType::Unchecked))
+        .collect()
+}
+
+/// Returns the specifications of the AWS connection options, which
+/// [`normalize::aws_config`] extracts.
+fn aws_option_specs() -> Vec<OptionSpec> {
+    [
+        "access_key_id",
+        "secret_access_key",
+        "token",
+        "profile",
+        "region",
+        "endpoint",
+        "role_arn",
+    ]
+    .into_iter()
+    .map(|name| OptionSpec::new(name, OptionType::String))
+    .collect()
+}
+
 pub fn plan_create_source(
     scx: &StatementContext,
-    stmt: CreateSourceStatement<Aug>,
+    mut stmt: CreateSourceStatement<Aug>,
 ) -> Result<Plan, anyhow::Error> {
+    let option_schema = source_option_schema(&stmt.connector, &stmt.envelope);
+    option_schema.canonicalize(&mut stmt.with_options);
     let CreateSourceStatement {
         name,
         col_names,
@@ -313,19 +406,23 @@ pub fn plan_create_source(
     } = &stmt;
 
     let with_options_original = with_options;
-    let mut with_options = normalize::options(with_options);
+    let mut with_options = option_schema.validate(with_options)?;
 
-    let ts_frequency = match with_options.remove("timestamp_frequency_ms") {
-        Some(val) => match val {
-            Value::Number(n) => match n.parse::<u64>() {
-                Ok(n) => Duration::from_millis(n),
-                Err(_) => bail!("timestamp_frequency_ms must be an u64"),
-            },
-            _ => bail!("timestamp_frequency_ms must be an u64"),
-        },
+    let ts_frequency = match with_options.take_u64("timestamp_frequency_ms") {
+        Some(n) => Duration::from_millis(n),
         None => scx.catalog.config().timestamp_frequency,
     };
 
+    let schema_change_policy =
+        with_options
+            .take_string("schema_change_policy")
+            .map(|s| match s.as_str() {
+                "error" => SchemaChangePolicy::Error,
+                "ignore_new_columns" => SchemaChangePolicy::IgnoreNewColumns,
+                "add_nullable_columns" => SchemaChangePolicy::AddNullableColumns,
+                _ => unreachable!("validated as one of the policies"),
+            });
+
     if !matches!(connector, CreateSourceConnector::Kafka { .. })
         && include_metadata
             .iter()
@@ -338,20 +435,21 @@ pub fn plan_create_source(
         bail_unsupported!("INCLUDE metadata with non-Kafka sources");
     }
 
-    let (external_connector, encoding) = match connector {
+    let (external_connector, mut encoding) = match connector {
         CreateSourceConnector::Kafka(kafka) => {
             let (broker, topic) = match &kafka.connector {
                 mz_sql_parser::ast::KafkaConnector::Inline { broker } => (broker, &kafka.topic),
                 // Temporary until the rest of the connector plumbing is finished
                 mz_sql_parser::ast::KafkaConnector::Reference { .. } => unreachable!(),
             };
-            let config_options = kafka_util::extract_config(&mut with_options)?;
+            let config_options = kafka_util::extract_config(with_options.remaining_mut())?;
+            let secret_options = kafka_util::extract_secret_config(
+                scx.catalog,
+                with_options_original,
+                with_options.remaining_mut(),
+            )?;
 
-            let group_id_prefix = match with_options.remove("group_id_prefix") {
-                None => None,
-                Some(Value::String(s)) => Some(s),
-                Some(_) => bail!("group_id_prefix must be a string"),
-            };
+            let group_id_prefix = with_options.take_string("group_id_prefix");
 
             let parse_offset = |s: &str| match s.parse::<i64>() {
                 Ok(n) if n >= 0 => Ok(n),
@@ -359,7 +457,7 @@ pub fn plan_create_source(
             };
 
             let mut start_offsets = HashMap::new();
-            match with_options.remove("start_offset") {
+            match with_options.take("start_offset") {
                 None => {
                     start_offsets.insert(0, 0);
                 }
@@ -372,11 +470,11 @@ pub fn plan_create_source(
                             Value::Number(n) => {
                                 start_offsets.insert(i32::try_from(i)?, parse_offset(n)?);
                             }
-                            _ => bail!("start_offset value must be a number: {}", v),
+                            _ => unreachable!("validated as an array of integers"),
                         }
                     }
                 }
-                Some(v) => bail!("invalid start_offset value: {}", v),
+                Some(_) => unreachable!("validated as an integer or an array of integers"),
             }
 
             let encoding = get_encoding(format, envelope, with_options_original)?;
@@ -385,6 +483,7 @@ pub fn plan_create_source(
                 addrs: broker.parse()?,
                 topic: topic.clone(),
                 config_options,
+                secret_options,
                 start_offsets,
                 group_id_prefix,
                 cluster_id: scx.catalog.config().cluster_id,
@@ -472,18 +571,14 @@ pub fn plan_create_source(
                 .region
                 .ok_or_else(|| anyhow!("Provided ARN does not include an AWS region"))?;
 
-            let aws = normalize::aws_config(&mut with_options, Some(region.into()))?;
+            let aws = normalize::aws_config(with_options.remaining_mut(), Some(region.into()))?;
             let connector =
                 ExternalSourceConnector::Kinesis(KinesisSourceConnector { stream_name, aws });
             let encoding = get_encoding(format, envelope, with_options_original)?;
             (connector, encoding)
         }
         CreateSourceConnector::File { path, compression } => {
-            let tail = match with_options.remove("tail") {
-                None => false,
-                Some(Value::Boolean(b)) => b,
-                Some(_) => bail!("tail must be a boolean"),
-            };
+            let tail = with_options.take_bool("tail").expect("tail has a default");
 
             let connector = ExternalSourceConnector::File(FileSourceConnector {
                 path: path.clone().into(),
@@ -504,7 +599,7 @@ pub fn plan_create_source(
             pattern,
             compression,
         } => {
-            let aws = normalize::aws_config(&mut with_options, None)?;
+            let aws = normalize::aws_config(with_options.remaining_mut(), None)?;
             let mut converted_sources = Vec::new();
             for ks in key_sources {
                 let dtks = match ks {
@@ -553,6 +648,14 @@ pub fn plan_create_source(
             let slot_name = slot
                 .as_ref()
                 .ok_or_else(|| anyhow!("Postgres sources must provide a slot name"))?;
+            let snapshot_parallelism = with_options
+                .take_u64("snapshot_parallelism")
+                .expect("snapshot_parallelism has a default");
+            let snapshot_parallelism = usize::try_from(snapshot_parallelism)
+                .map_err(|_| anyhow!("snapshot_parallelism is too large"))?;
+            let snapshot_range_bytes = with_options
+                .take_u64("snapshot_range_size")
+                .expect("snapshot_range_size has a default");
             let connector = ExternalSourceConnector::Postgres(PostgresSourceConnector {
                 conn: conn.clone(),
                 publication: publication.clone(),
@@ -562,6 +665,9 @@ pub fn plan_create_source(
                         .as_ref()
                         .expect("Postgres source must provide associated details"),
                 )?))?,
+                schema_change_policy: schema_change_policy.unwrap_or(SchemaChangePolicy::Error),
+                snapshot_parallelism,
+                snapshot_range_bytes,
             });
 
             let encoding = SourceDataEncoding::Single(DataEncoding::Postgres);
@@ -582,11 +688,7 @@ pub fn plan_create_source(
             (connector, SourceDataEncoding::Single(DataEncoding::Text))
         }
         CreateSourceConnector::AvroOcf { path, .. } => {
-            let tail = match with_options.remove("tail") {
-                None => false,
-                Some(Value::Boolean(b)) => b,
-                Some(_) => bail!("tail must be a boolean"),
-            };
+            let tail = with_options.take_bool("tail").expect("tail has a default");
 
             let connector = ExternalSourceConnector::AvroOcf(FileSourceConnector {
                 path: path.clone().into(),
@@ -596,19 +698,23 @@ pub fn plan_create_source(
             if !matches!(format, CreateSourceFormat::None) {
                 bail!("avro ocf sources cannot specify a format");
             }
-            let reader_schema = match with_options
-                .remove("reader_schema")
-                .expect("purification guarantees presence of reader_schema")
-            {
-                Value::String(s) => s,
-                _ => bail!("reader_schema option must be a string"),
-            };
+            let reader_schema = with_options
+                .take_string("reader_schema")
+                .expect("reader_schema is required");
             let encoding = SourceDataEncoding::Single(DataEncoding::AvroOcf(AvroOcfEncoding {
                 reader_schema,
             }));
             (connector, encoding)
         }
     };
+    if let Some(policy) = schema_change_policy {
+        if !matches!(external_connector, ExternalSourceConnector::Postgres(_)) {
+            if matches!(envelope, Envelope::CdcV2) {
+                bail_unsupported!("schema_change_policy with ENVELOPE MATERIALIZE");
+            }
+            apply_avro_schema_change_policy(&mut encoding, policy)?;
+        }
+    }
     let (key_desc, value_desc) = encoding.desc()?;
 
     let key_envelope = get_key_envelope(include_metadata, envelope, &encoding)?;
@@ -630,13 +736,12 @@ pub fn plan_create_source(
                 DbzMode::Plain => {
                     let dedup_projection = typecheck_debezium_dedup(&value_desc);
 
-                    let dedup_mode = match with_options.remove("deduplication") {
+                    let dedup_mode = match with_options.take_string("deduplication") {
                         None => match dedup_projection {
                             Ok(_) => Cow::from("ordered"),
                             Err(_) => Cow::from("none"),
                         },
-                        Some(Value::String(s)) => Cow::from(s),
-                        _ => bail!("deduplication option must be a string"),
+                        Some(s) => Cow::from(s),
                     };
 
                     match dedup_mode.as_ref() {
@@ -675,17 +780,15 @@ pub fn plan_create_source(
                                 )
                             };
 
-                            let dedup_start = match with_options.remove("deduplication_start") {
-                                None => None,
-                                Some(Value::String(start)) => Some(parse_datetime(&start)?),
-                                _ => bail!("deduplication_start option must be a string"),
-                            };
+                            let dedup_start = with_options
+                                .take_string("deduplication_start")
+                                .map(|start| parse_datetime(&start))
+                                .transpose()?;
 
-                            let dedup_end = match with_options.remove("deduplication_end") {
-                                None => None,
-                                Some(Value::String(end)) => Some(parse_datetime(&end)?),
-                                _ => bail!("deduplication_end option must be a string"),
-                            };
+                            let dedup_end = with_options
+                                .take_string("deduplication_end")
+                                .map(|end| parse_datetime(&end))
+                                .transpose()?;
 
                             match dedup_start.zip(dedup_end) {
                                 Some((start, end)) => {
@@ -697,16 +800,10 @@ pub fn plan_create_source(
                                         );
                                     }
 
-                                    let pad_start =
-                                        match with_options.remove("deduplication_pad_start") {
-                                            None => None,
-                                            Some(Value::String(pad_start)) => {
-                                                Some(parse_datetime(&pad_start)?)
-                                            }
-                                            _ => bail!(
-                                                "deduplication_pad_start option must be a string"
-                                            ),
-                                        };
+                                    let pad_start = with_options
+                                        .take_string("deduplication_pad_start")
+                                        .map(|pad_start| parse_datetime(&pad_start))
+                                        .transpose()?;
 
                                     UnplannedSourceEnvelope::Debezium(DebeziumEnvelope {
                                         before_idx,
@@ -716,7 +813,7 @@ pub fn plan_create_source(
                                             end,
                                             pad_start,
                                             projection: dedup_projection?,
-                                        }
+                                        },
                                     })
                                 }
                                 _ => bail!(
@@ -725,9 +822,7 @@ pub fn plan_create_source(
                                 ),
                             }
                         }
-                        _ => bail!(
-                            "deduplication must be one of 'none', 'ordered', 'full' or 'full_in_range'."
-                        ),
+                        _ => unreachable!("validated as one of the deduplication modes"),
                     }
                 }
             }
@@ -791,11 +886,9 @@ pub fn plan_create_source(
         col_names
     };
 
-    let ignore_source_keys = match with_options.remove("ignore_source_keys") {
-        None => false,
-        Some(Value::Boolean(b)) => b,
-        Some(_) => bail!("ignore_source_keys must be a boolean"),
-    };
+    let ignore_source_keys = with_options
+        .take_bool("ignore_source_keys")
+        .expect("ignore_source_keys has a default");
 
     if ignore_source_keys {
         desc = desc.without_keys();
@@ -844,22 +937,25 @@ pub fn plan_create_source(
     let create_sql = normalize::create_statement(&scx, Statement::CreateSource(stmt))?;
 
     // Allow users to specify a timeline. If they do not, determine a default timeline for the source.
-    let timeline = if let Some(timeline) = with_options.remove("timeline") {
-        match timeline {
-            Value::String(timeline) => Timeline::User(timeline),
-            v => bail!("unsupported timeline value {}", v.to_ast_string()),
-        }
+    let timeline = if let Some(timeline) = with_options.take_string("timeline") {
+        Timeline::User(timeline)
     } else {
         match envelope {
-            SourceEnvelope::CdcV2 => match with_options.remove("epoch_ms_timeline") {
+            SourceEnvelope::CdcV2 => match with_options.take_bool("epoch_ms_timeline") {
                 None => Timeline::External(name.to_string()),
-                Some(Value::Boolean(true)) => Timeline::EpochMilliseconds,
-                Some(v) => bail!("unsupported epoch_ms_timeline value {}", v),
+                Some(true) => Timeline::EpochMilliseconds,
+                Some(false) => bail!("unsupported epoch_ms_timeline value false"),
             },
             _ => Timeline::EpochMilliseconds,
         }
     };
 
+    // Sources depend on the secrets that configure them.
+    let depends_on = match &external_connector {
+        ExternalSourceConnector::Kafka(kafka) => kafka.secret_options.values().copied().collect(),
+        _ => vec![],
+    };
+
     let source = Source {
         create_sql,
         connector: SourceConnector::External {
@@ -871,9 +967,10 @@ pub fn plan_create_source(
             timeline,
         },
         desc,
+        depends_on,
     };
 
-    normalize::ensure_empty_options(&with_options, "CREATE SOURCE")?;
+    with_options.ensure_consumed()?;
 
     Ok(Plan::CreateSource(CreateSourcePlan {
         name,
@@ -1059,6 +1156,40 @@ fn get_encoding<T: mz_sql_parser::ast::AstInfo>(
     Ok(encoding)
 }
 
+/// Applies a `schema_change_policy` to the Avro encodings in `encoding` whose
+/// writer schemas are fetched from a schema registry.
+fn apply_avro_schema_change_policy(
+    encoding: &mut SourceDataEncoding,
+    policy: SchemaChangePolicy,
+) -> Result<(), anyhow::Error> {
+    if policy == SchemaChangePolicy::AddNullableColumns {
+        bail!("schema_change_policy 'add_nullable_columns' is only supported for Postgres sources");
+    }
+    let encodings = match encoding {
+        SourceDataEncoding::Single(value) => vec![value],
+        SourceDataEncoding::KeyValue { key, value } => vec![key, value],
+    };
+    let mut applied = false;
+    for encoding in encodings {
+        if let DataEncoding::Avro(AvroEncoding {
+            schema_registry_config: Some(_),
+            schema_change_policy,
+            ..
+        }) = encoding
+        {
+            *schema_change_policy = policy;
+            applied = true;
+        }
+    }
+    if !applied {
+        bail!(
+            "schema_change_policy is only supported for Postgres sources and \
+             sources that use a Confluent Schema Registry Avro format"
+        );
+    }
+    Ok(())
+}
+
 fn get_encoding_inner<T: mz_sql_parser::ast::AstInfo>(
     format: &Format<T>,
     with_options: &Vec<SqlOption<T>>,
@@ -1108,15 +1239,10 @@ fn get_encoding_inner<T: mz_sql_parser::ast::AstInfo>(
                             with_options: ccsr_options,
                         },
                 } => {
-                    let mut ccsr_with_options = normalize::options(&ccsr_options);
                     let ccsr_config = kafka_util::generate_ccsr_client_config(
                         url.parse()?,
                         &kafka_util::extract_config(&mut normalize::options(with_options))?,
-                        &mut ccsr_with_options,
-                    )?;
-                    normalize::ensure_empty_options(
-                        &ccsr_with_options,
-                        "CONFLUENT SCHEMA REGISTRY",
+                        ccsr_options,
                     )?;
                     if let Some(seed) = seed {
                         Schema {
@@ -1137,11 +1263,13 @@ fn get_encoding_inner<T: mz_sql_parser::ast::AstInfo>(
                         schema: key_schema,
                         schema_registry_config: schema_registry_config.clone(),
                         confluent_wire_format,
+                        schema_change_policy: SchemaChangePolicy::IgnoreNewColumns,
                     }),
                     value: DataEncoding::Avro(AvroEncoding {
                         schema: value_schema,
                         schema_registry_config,
                         confluent_wire_format,
+                        schema_change_policy: SchemaChangePolicy::IgnoreNewColumns,
                     }),
                 });
             } else {
@@ -1149,6 +1277,7 @@ fn get_encoding_inner<T: mz_sql_parser::ast::AstInfo>(
                     schema: value_schema,
                     schema_registry_config,
                     confluent_wire_format,
+                    schema_change_policy: SchemaChangePolicy::IgnoreNewColumns,
                 })
             }
         }
@@ -1164,17 +1293,11 @@ fn get_encoding_inner<T: mz_sql_parser::ast::AstInfo>(
                 if let Some(CsrSeedCompiledOrLegacy::Compiled(CsrSeedCompiled { key, value })) =
                     seed
                 {
-                    let mut ccsr_with_options = normalize::options(&ccsr_options);
-
                     // We validate here instead of in purification, to match the behavior of avro
                     let _ccsr_config = kafka_util::generate_ccsr_client_config(
                         url.parse()?,
                         &kafka_util::extract_config(&mut normalize::options(with_options))?,
-                        &mut ccsr_with_options,
-                    )?;
-                    normalize::ensure_empty_options(
-                        &ccsr_with_options,
-                        "CONFLUENT SCHEMA REGISTRY",
+                        ccsr_options,
                     )?;
 
                     let value = DataEncoding::Protobuf(ProtobufEncoding {
@@ -1621,12 +1744,33 @@ pub fn plan_create_views(
     }
 }
 
+/// Returns the schema of the `WITH` options of `CREATE SINK`, which depends on
+/// the sink's connector.
+fn sink_option_schema(connector: &CreateSinkConnector<Aug>) -> OptionSchema {
+    let schema = OptionSchema::new("CREATE SINK");
+    match connector {
+        CreateSinkConnector::Kafka { .. } => schema.with(kafka_config_option_specs()).with([
+            OptionSpec::new("consistency_topic", OptionType::String)
+                .deprecated(Deprecation::SupersededBy("CONSISTENCY TOPIC")),
+            OptionSpec::new("reuse_topic", OptionType::Bool).default(Value::Boolean(false)),
+            OptionSpec::new("avro_key_fullname", OptionType::String),
+            OptionSpec::new("avro_value_fullname", OptionType::String),
+            OptionSpec::new("partition_count", OptionType::Integer),
+            OptionSpec::new("replication_factor", OptionType::Integer),
+            OptionSpec::new("retention_ms", OptionType::Integer),
+            OptionSpec::new("retention_bytes", OptionType::Integer),
+        ]),
+        CreateSinkConnector::AvroOcf { .. } => schema,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn kafka_sink_builder(
     scx: &StatementContext,
     format: Option<Format<Aug>>,
     consistency: Option<KafkaConsistency<Aug>>,
-    with_options: &mut BTreeMap<String, Value>,
+    with_options_original: &[SqlOption<Aug>],
+    with_options: &mut WithOptions,
     broker: String,
     topic_prefix: String,
     relation_key_indices: Option<Vec<usize>>,
@@ -1635,38 +1779,29 @@ fn kafka_sink_builder(
     topic_suffix_nonce: String,
     root_dependencies: &[&dyn CatalogItem],
 ) -> Result<SinkConnectorBuilder, anyhow::Error> {
-    let consistency_topic = match with_options.remove("consistency_topic") {
-        None => None,
-        Some(Value::String(topic)) => Some(topic),
-        Some(_) => bail!("consistency_topic must be a string"),
-    };
+    let consistency_topic = with_options.take_string("consistency_topic");
     if consistency_topic.is_some() && consistency.is_some() {
         // We're keeping consistency_topic around for backwards compatibility. Users
         // should not be able to specify consistency_topic and the newer CONSISTENCY options.
         bail!("Cannot specify consistency_topic and CONSISTENCY options simultaneously");
     }
-    let reuse_topic = match with_options.remove("reuse_topic") {
-        Some(Value::Boolean(b)) => b,
-        None => false,
-        Some(_) => bail!("reuse_topic must be a boolean"),
-    };
-    let config_options = kafka_util::extract_config(with_options)?;
+    let reuse_topic = with_options
+        .take_bool("reuse_topic")
+        .expect("reuse_topic has a default");
+    let config_options = kafka_util::extract_config(with_options.remaining_mut())?;
+    let secret_options = kafka_util::extract_secret_config(
+        scx.catalog,
+        with_options_original,
+        with_options.remaining_mut(),
+    )?;
 
-    let avro_key_fullname = match with_options.remove("avro_key_fullname") {
-        Some(Value::String(s)) => Some(s),
-        None => None,
-        Some(_) => bail!("avro_key_fullname must be a string"),
-    };
+    let avro_key_fullname = with_options.take_string("avro_key_fullname");
 
     if key_desc_and_indices.is_none() && avro_key_fullname.is_some() {
         bail!("Cannot specify avro_key_fullname without a corresponding KEY field");
     }
 
-    let avro_value_fullname = match with_options.remove("avro_value_fullname") {
-        Some(Value::String(s)) => Some(s),
-        None => None,
-        Some(_) => bail!("avro_value_fullname must be a string"),
-    };
+    let avro_value_fullname = with_options.take_string("avro_value_fullname");
 
     if key_desc_and_indices.is_some()
         && (avro_key_fullname.is_some() ^ avro_value_fullname.is_some())
@@ -1686,13 +1821,11 @@ fn kafka_sink_builder(
             if seed.is_some() {
                 bail!("SEED option does not make sense with sinks");
             }
-            let mut ccsr_with_options = normalize::options(&with_options);
-
             let schema_registry_url = url.parse::<Url>()?;
             let ccsr_config = kafka_util::generate_ccsr_client_config(
                 schema_registry_url.clone(),
                 &config_options,
-                &mut ccsr_with_options,
+                &with_options,
             )?;
 
             let include_transaction =
@@ -1711,8 +1844,6 @@ fn kafka_sink_builder(
                 .key_writer_schema()
                 .map(|key_schema| key_schema.to_string());
 
-            normalize::ensure_empty_options(&ccsr_with_options, "CONFLUENT SCHEMA REGISTRY")?;
-
             KafkaSinkFormat::Avro {
                 schema_registry_url,
                 key_schema,
@@ -1759,11 +1890,7 @@ fn kafka_sink_builder(
     };
 
     // Use the user supplied value for partition count, or default to -1 (broker default)
-    let partition_count = match with_options.remove("partition_count") {
-        None => -1,
-        Some(Value::Number(n)) => n.parse::<i32>()?,
-        Some(_) => bail!("partition count for sink topics must be an integer"),
-    };
+    let partition_count = with_options.take_i64("partition_count").unwrap_or(-1);
 
     if partition_count == 0 || partition_count < -1 {
         bail!(
@@ -1771,34 +1898,26 @@ fn kafka_sink_builder(
         );
     }
 
+    let partition_count = i32::try_from(partition_count)?;
+
     // Use the user supplied value for replication factor, or default to -1 (broker default)
-    let replication_factor = match with_options.remove("replication_factor") {
-        None => -1,
-        Some(Value::Number(n)) => n.parse::<i32>()?,
-        Some(_) => bail!("replication factor for sink topics must be an integer"),
-    };
+    let replication_factor = with_options.take_i64("replication_factor").unwrap_or(-1);
 
     if replication_factor == 0 || replication_factor < -1 {
         bail!(
             "replication factor for sink topics must be a positive integer or -1 for broker default"
         );
     }
+    let replication_factor = i32::try_from(replication_factor)?;
 
-    let retention_duration = match with_options.remove("retention_ms") {
+    let retention_duration = match with_options.take_i64("retention_ms") {
         None => None,
-        Some(Value::Number(n)) => match n.parse::<i64>()? {
-            -1 => Some(None),
-            millis @ 0.. => Some(Some(Duration::from_millis(millis as u64))),
-            _ => bail!("retention ms for sink topics must be greater than or equal to -1"),
-        },
-        Some(_) => bail!("retention ms for sink topics must be an integer"),
+        Some(-1) => Some(None),
+        Some(millis @ 0..) => Some(Some(Duration::from_millis(millis as u64))),
+        Some(_) => bail!("retention ms for sink topics must be greater than or equal to -1"),
     };
 
-    let retention_bytes = match with_options.remove("retention_bytes") {
-        None => None,
-        Some(Value::Number(n)) => Some(n.parse::<i64>()?),
-        Some(_) => bail!("retention bytes for sink topics must be an integer"),
-    };
+    let retention_bytes = with_options.take_i64("retention_bytes");
 
     if retention_bytes.unwrap_or(0) < -1 {
         bail!("retention bytes for sink topics must be greater than or equal to -1");
@@ -1822,6 +1941,7 @@ fn kafka_sink_builder(
         replication_factor,
         fuel: 10000,
         config_options,
+        secret_options,
         relation_key_indices,
         key_desc_and_indices,
         value_desc,
@@ -1863,11 +1983,10 @@ fn get_kafka_sink_consistency_config(
                     bail!("SEED option does not make sense with sinks");
                 }
                 let schema_registry_url = url.parse::<Url>()?;
-                let mut ccsr_with_options = normalize::options(&with_options);
                 let ccsr_config = kafka_util::generate_ccsr_client_config(
                     schema_registry_url.clone(),
                     config_options,
-                    &mut ccsr_with_options,
+                    &with_options,
                 )?;
 
                 Some((
@@ -1966,8 +2085,10 @@ pub fn describe_create_sink(
 pub fn plan_create_sink(
     scx: &StatementContext,
     mut stmt: CreateSinkStatement<Aug>,
-    depends_on: HashSet<GlobalId>,
+    mut depends_on: HashSet<GlobalId>,
 ) -> Result<Plan, anyhow::Error> {
+    let option_schema = sink_option_schema(&stmt.connector);
+    option_schema.canonicalize(&mut stmt.with_options);
     let compute_instance = match &stmt.in_cluster {
         None => scx.resolve_compute_instance(None)?.id(),
         Some(in_cluster) => in_cluster.0,
@@ -2007,7 +2128,8 @@ pub fn plan_create_sink(
         scx.catalog.config().nonce
     );
 
-    let mut with_options = normalize::options(&with_options);
+    let with_options_original = with_options;
+    let mut with_options = option_schema.validate(&with_options_original)?;
 
     let desc = from.desc(&scx.catalog.resolve_full_name(from.name()))?;
     let key_indices = match &connector {
@@ -2095,6 +2217,7 @@ pub fn plan_create_sink(
             scx,
             format,
             consistency,
+            &with_options_original,
             &mut with_options,
             broker,
             topic,
@@ -2109,7 +2232,12 @@ pub fn plan_create_sink(
         }
     };
 
-    normalize::ensure_empty_options(&with_options, "CREATE SINK")?;
+    with_options.ensure_consumed()?;
+
+    // Sinks depend on the secrets that configure them.
+    if let SinkConnectorBuilder::Kafka(builder) = &connector_builder {
+        depends_on.extend(builder.secret_options.values());
+    }
 
     let depends_on = depends_on.into_iter().collect();
     Ok(Plan::CreateSink(CreateSinkPlan {
@@ -2188,7 +2316,13 @@ fn get_root_dependencies<'a>(
 
     while let Some(dep) = work_queue.pop() {
         let item = scx.get_item(&dep);
-        let transitive_uses = item.uses().iter().filter(|id| id.is_user());
+        // Secrets configure the items that use them, but are not the source
+        // of any of their data.
+        let transitive_uses = item
+            .uses()
+            .iter()
+            .filter(|id| id.is_user())
+            .filter(|id| scx.get_item(id).item_type() != CatalogItemType::Secret);
         let mut transitive_uses = transitive_uses.peekable();
         if let Some(_) = transitive_uses.peek() {
             for transitive_dep in transitive_uses {
@@ -2395,12 +2529,21 @@ pub fn plan_create_type(
     let mut record_fields = vec![];
     match &as_type {
         CreateTypeAs::List { with_options } | CreateTypeAs::Map { with_options } => {
-            let mut with_options = normalize::option_objects(&with_options);
             let option_keys = match as_type {
                 CreateTypeAs::List { .. } => vec!["element_type"],
                 CreateTypeAs::Map { .. } => vec!["key_type", "value_type"],
                 _ => vec![],
             };
+            // The options are data types, which the schema does not check, so
+            // it only rejects unknown options.
+            OptionSchema::new("CREATE TYPE")
+                .with(
+                    option_keys
+                        .iter()
+                        .map(|key| OptionSpec::new(*key, OptionType::Unchecked)),
+                )
+                .validate(with_options)?;
+            let mut with_options = normalize::option_objects(with_options);
 
             for key in option_keys {
                 match with_options.remove(&key.to_string()) {
@@ -2412,8 +2555,6 @@ pub fn plan_create_type(
                     None => bail!("{} parameter required", key),
                 };
             }
-
-            normalize::ensure_empty_options(&with_options, "CREATE TYPE")?;
         }
         CreateTypeAs::Record { ref column_defs } => {
             for column_def in column_defs {
@@ -2494,6 +2635,10 @@ pub fn plan_create_role(
 ) -> Result<Plan, anyhow::Error> {
     let mut login = None;
     let mut super_user = None;
+    let mut password = None;
+    let mut require_tls = false;
+    let mut allowed_addresses = None;
+    let mut allowed_clusters = None;
     for option in options {
         match option {
             CreateRoleOption::Login | CreateRoleOption::NoLogin if login.is_some() => {
@@ -2502,26 +2647,60 @@ pub fn plan_create_role(
             CreateRoleOption::SuperUser | CreateRoleOption::NoSuperUser if super_user.is_some() => {
                 bail!("conflicting or redundant options");
             }
+            CreateRoleOption::Password(_) if password.is_some() => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::RequireTls if require_tls => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::AllowedAddresses(_) if allowed_addresses.is_some() => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::AllowedClusters(_) if allowed_clusters.is_some() => {
+                bail!("conflicting or redundant options");
+            }
             CreateRoleOption::Login => login = Some(true),
             CreateRoleOption::NoLogin => login = Some(false),
             CreateRoleOption::SuperUser => super_user = Some(true),
             CreateRoleOption::NoSuperUser => super_user = Some(false),
+            CreateRoleOption::Password(p) => password = Some(p),
+            CreateRoleOption::RequireTls => require_tls = true,
+            CreateRoleOption::AllowedAddresses(addrs) => {
+                let addrs = addrs
+                    .iter()
+                    .map(|addr| parse_allowed_address(addr))
+                    .collect::<Result<_, _>>()?;
+                allowed_addresses = Some(addrs);
+            }
+            CreateRoleOption::AllowedClusters(clusters) => {
+                allowed_clusters = Some(clusters.into_iter().map(normalize::ident).collect());
+            }
         }
     }
-    if is_user && login.is_none() {
-        login = Some(true);
-    }
-    if login != Some(true) {
-        bail_unsupported!("non-login users");
-    }
-    if super_user != Some(true) {
-        bail_unsupported!("non-superusers");
-    }
     Ok(Plan::CreateRole(CreateRolePlan {
         name: normalize::ident(name),
+        restrictions: RoleRestrictions {
+            allowed_addresses: allowed_addresses.unwrap_or_default(),
+            require_tls,
+            allowed_clusters,
+            superuser: super_user.unwrap_or(false),
+            // As in PostgreSQL, users can log in by default, but roles cannot.
+            login: login.unwrap_or(is_user),
+        },
+        password: password.flatten(),
     }))
 }
 
+/// Parses an entry in an `ALLOWED ADDRESSES` list, which is either a network
+/// in CIDR notation or a single IP address.
+fn parse_allowed_address(addr: &str) -> Result<IpNet, anyhow::Error> {
+    match (addr.parse::<IpNet>(), addr.parse::<IpAddr>()) {
+        (Ok(net), _) => Ok(net.trunc()),
+        (_, Ok(addr)) => Ok(IpNet::from(addr)),
+        _ => bail!("invalid network address: {}", addr.quoted()),
+    }
+}
+
 pub fn describe_create_cluster(
     _: &StatementContext,
     _: &CreateClusterStatement,
@@ -2552,6 +2731,7 @@ fn plan_cluster_options(
     let mut size = None;
     let mut introspection_debugging = None;
     let mut introspection_granularity = None;
+    let mut introspection_retention = None;
 
     for option in options {
         match option {
@@ -2577,6 +2757,12 @@ fn plan_cluster_options(
                 }
                 introspection_granularity = Some(with_option_type!(Some(interval), Interval));
             }
+            ClusterOption::IntrospectionRetention(interval) => {
+                if introspection_retention.is_some() {
+                    bail!("INTROSPECTION RETENTION specified more than once");
+                }
+                introspection_retention = Some(with_option_type!(Some(interval), Interval));
+            }
             ClusterOption::Size(s) => {
                 if size.is_some() {
                     bail!("SIZE specified more than once");
@@ -2586,11 +2772,24 @@ fn plan_cluster_options(
         }
     }
 
+    let retention = match introspection_retention {
+        None => None,
+        Some(retention) => Some(retention.duration()?),
+    };
+
     let introspection = match (introspection_debugging, introspection_granularity) {
-        (None | Some(false), None) => None,
+        (None | Some(false), None) => {
+            if retention.is_some() {
+                bail!(
+                    "INTROSPECTION RETENTION cannot be specified without INTROSPECTION GRANULARITY"
+                );
+            }
+            None
+        }
         (debugging, Some(granularity)) => Some(ComputeInstanceIntrospectionConfig {
             debugging: debugging.unwrap_or(false),
             granularity: granularity.duration()?,
+            retention,
         }),
         (Some(true), None) => {
             bail!("INTROSPECTION DEBUGGING cannot be specified without INTROSPECTION GRANULARITY")
@@ -2622,25 +2821,49 @@ pub fn describe_create_secret<T: mz_sql_parser::ast::AstInfo>(
     Ok(StatementDesc::new(None))
 }
 
+/// Returns the schema of the `WITH` options of `CREATE SECRET`.
+fn secret_option_schema() -> OptionSchema {
+    OptionSchema::new("CREATE SECRET").with([OptionSpec::new(
+        "format",
+        OptionType::OneOf(SecretFormat::NAMES),
+    )
+    .default(Value::String(SecretFormat::Binary.as_str().into()))])
+}
+
 pub fn plan_create_secret(
     scx: &StatementContext,
-    stmt: CreateSecretStatement<Aug>,
+    mut stmt: CreateSecretStatement<Aug>,
 ) -> Result<Plan, anyhow::Error> {
     scx.require_experimental_mode("CREATE SECRET")?;
 
+    let option_schema = secret_option_schema();
+    option_schema.canonicalize(&mut stmt.with_options);
     let CreateSecretStatement {
         name,
         if_not_exists,
         value,
+        with_options,
     } = &stmt;
 
+    let mut with_options = option_schema.validate(with_options)?;
+    let format = with_options
+        .take_string("format")
+        .expect("format has a default")
+        .parse()?;
+    with_options.ensure_consumed()?;
+
     let name = scx.allocate_qualified_name(normalize::unresolved_object_name(name.to_owned())?)?;
-    let create_sql = normalize::create_statement(&scx, Statement::CreateSecret(stmt.clone()))?;
     let secret_as = query::plan_secret_as(scx, value.clone())?;
 
+    // The contents of the secret must never be stored in the catalog.
+    let mut redacted = stmt.clone();
+    redacted.value = Expr::Value(Value::String("********".into()));
+    let create_sql = normalize::create_statement(&scx, Statement::CreateSecret(redacted))?;
+
     let secret = Secret {
         create_sql,
         secret_as,
+        format,
     };
 
     let full_name = scx.catalog.resolve_full_name(&name);
@@ -2653,6 +2876,99 @@ pub fn plan_create_secret(
     }))
 }
 
+pub fn describe_create_publication<T: mz_sql_parser::ast::AstInfo>(
+    _: &StatementContext,
+    _: &CreatePublicationStatement<T>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_create_publication(
+    scx: &StatementContext,
+    stmt: CreatePublicationStatement<Aug>,
+) -> Result<Plan, anyhow::Error> {
+    let CreatePublicationStatement {
+        name,
+        if_not_exists,
+        views: view_names,
+    } = &stmt;
+
+    let name = scx.allocate_qualified_name(normalize::unresolved_object_name(name.to_owned())?)?;
+
+    let mut views = vec![];
+    for view_name in view_names {
+        let view = scx.get_item_by_resolved_name(view_name)?;
+        if view.item_type() != CatalogItemType::View {
+            bail!(
+                "{} cannot be published because it is a {}",
+                view_name.full_name_str(),
+                view.item_type()
+            );
+        }
+        if views.contains(&view.id()) {
+            bail!(
+                "view {} is published more than once",
+                view_name.full_name_str()
+            );
+        }
+        views.push(view.id());
+    }
+
+    let create_sql = normalize::create_statement(scx, Statement::CreatePublication(stmt.clone()))?;
+
+    Ok(Plan::CreatePublication(CreatePublicationPlan {
+        name,
+        publication: Publication { create_sql, views },
+        if_not_exists: *if_not_exists,
+    }))
+}
+
+pub fn describe_create_task(
+    _: &StatementContext,
+    _: &CreateTaskStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_create_task(
+    scx: &StatementContext,
+    stmt: CreateTaskStatement<Aug>,
+    depends_on: HashSet<GlobalId>,
+) -> Result<Plan, anyhow::Error> {
+    let CreateTaskStatement {
+        name,
+        if_not_exists,
+        schedule,
+        statement,
+    } = &stmt;
+
+    let name = scx.allocate_qualified_name(normalize::unresolved_object_name(name.to_owned())?)?;
+
+    let schedule = strconv::parse_interval(schedule)
+        .map_err(anyhow::Error::from)
+        .and_then(|interval| interval.duration())
+        .map_err(|e| anyhow!("invalid task schedule {}: {}", schedule.quoted(), e))?;
+    if schedule < Duration::from_secs(1) {
+        bail!("task schedule must be at least 1 second");
+    }
+
+    // Plan the statement now, so that a task that can never run successfully
+    // is rejected when it is created rather than each time it runs.
+    super::dml::plan_insert(scx, statement.clone(), &Params::empty())?;
+
+    let create_sql = normalize::create_statement(scx, Statement::CreateTask(stmt.clone()))?;
+
+    Ok(Plan::CreateTask(CreateTaskPlan {
+        name,
+        task: Task {
+            create_sql,
+            schedule,
+            depends_on: depends_on.into_iter().collect(),
+        },
+        if_not_exists: *if_not_exists,
+    }))
+}
+
 pub fn describe_drop_database(
     _: &StatementContext,
     _: &DropDatabaseStatement<Raw>,
@@ -2733,7 +3049,9 @@ pub fn plan_drop_objects(
         | ObjectType::Index
         | ObjectType::Sink
         | ObjectType::Type
-        | ObjectType::Secret => plan_drop_items(scx, object_type, names, cascade),
+        | ObjectType::Secret
+        | ObjectType::Publication
+        | ObjectType::Task => plan_drop_items(scx, object_type, names, cascade),
         ObjectType::Role => unreachable!("DROP ROLE handled separately"),
         ObjectType::Cluster => unreachable!("DROP CLUSTER handled separately"),
         ObjectType::Object => unreachable!("cannot drop generic OBJECT, must provide object type"),
@@ -2834,6 +3152,224 @@ pub fn plan_drop_role(
     Ok(Plan::DropRoles(DropRolesPlan { names: out }))
 }
 
+pub fn describe_alter_role(
+    _: &StatementContext,
+    _: &AlterRoleStatement,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_alter_role(
+    scx: &StatementContext,
+    AlterRoleStatement { name, options }: AlterRoleStatement,
+) -> Result<Plan, anyhow::Error> {
+    let name = normalize::ident(name);
+    let id = scx.catalog.resolve_role(&name)?.id();
+    let mut login = None;
+    let mut superuser = None;
+    let mut password = None;
+    for option in options {
+        match option {
+            CreateRoleOption::Login | CreateRoleOption::NoLogin if login.is_some() => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::SuperUser | CreateRoleOption::NoSuperUser if superuser.is_some() => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::Password(_) if password.is_some() => {
+                bail!("conflicting or redundant options");
+            }
+            CreateRoleOption::Login => login = Some(true),
+            CreateRoleOption::NoLogin => login = Some(false),
+            CreateRoleOption::SuperUser => superuser = Some(true),
+            CreateRoleOption::NoSuperUser => superuser = Some(false),
+            CreateRoleOption::Password(p) => password = Some(p),
+            CreateRoleOption::RequireTls => bail_unsupported!("ALTER ROLE ... REQUIRE TLS"),
+            CreateRoleOption::AllowedAddresses(_) => {
+                bail_unsupported!("ALTER ROLE ... ALLOWED ADDRESSES")
+            }
+            CreateRoleOption::AllowedClusters(_) => {
+                bail_unsupported!("ALTER ROLE ... ALLOWED CLUSTERS")
+            }
+        }
+    }
+    Ok(Plan::AlterRole(AlterRolePlan {
+        id,
+        name,
+        superuser,
+        login,
+        password,
+    }))
+}
+
+pub fn describe_alter_role_set(
+    _: &StatementContext,
+    _: &AlterRoleSetStatement,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_alter_role_set(
+    scx: &StatementContext,
+    AlterRoleSetStatement {
+        name,
+        variable,
+        value,
+    }: AlterRoleSetStatement,
+) -> Result<Plan, anyhow::Error> {
+    let role_id = match name {
+        Some(name) => Some(scx.catalog.resolve_role(&normalize::ident(name))?.id()),
+        None => None,
+    };
+    Ok(Plan::AlterRoleSet(AlterRoleSetPlan {
+        role_id,
+        name: variable.to_string(),
+        value: value.map(scl::plan_set_variable_value),
+    }))
+}
+
+pub fn describe_alter_system_set(
+    _: &StatementContext,
+    _: &AlterSystemSetStatement,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_alter_system_set(
+    _: &StatementContext,
+    AlterSystemSetStatement { variable, value }: AlterSystemSetStatement,
+) -> Result<Plan, anyhow::Error> {
+    Ok(Plan::AlterSystemSet(AlterSystemSetPlan {
+        name: variable.to_string(),
+        value: value.map(scl::plan_set_variable_value),
+    }))
+}
+
+pub fn describe_grant_privileges(
+    _: &StatementContext,
+    _: &GrantPrivilegesStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_grant_privileges(
+    scx: &StatementContext,
+    GrantPrivilegesStatement {
+        privileges,
+        object,
+        roles,
+    }: GrantPrivilegesStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    Ok(Plan::GrantPrivileges(plan_update_privileges(
+        scx, privileges, object, roles,
+    )?))
+}
+
+pub fn describe_revoke_privileges(
+    _: &StatementContext,
+    _: &RevokePrivilegesStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_revoke_privileges(
+    scx: &StatementContext,
+    RevokePrivilegesStatement {
+        privileges,
+        object,
+        roles,
+    }: RevokePrivilegesStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    Ok(Plan::RevokePrivileges(plan_update_privileges(
+        scx, privileges, object, roles,
+    )?))
+}
+
+fn plan_update_privileges(
+    scx: &StatementContext,
+    privileges: Option<Vec<Privilege>>,
+    object: PrivilegeObject<Raw>,
+    roles: Vec<Option<Ident>>,
+) -> Result<UpdatePrivilegesPlan, anyhow::Error> {
+    // As in PostgreSQL, the privileges that apply to an object depend on its
+    // type, and `ALL PRIVILEGES` stands for all of them.
+    let (object, object_desc, valid_privileges): (_, _, &[_]) = match object {
+        PrivilegeObject::Table(name) => {
+            let entry = scx.resolve_item(name)?;
+            let full_name = scx.catalog.resolve_full_name(entry.name());
+            if entry.id().is_system() {
+                bail!(
+                    "cannot change privileges on {} because it is required by the database system",
+                    full_name
+                );
+            }
+            let valid_privileges: &[_] = match entry.item_type() {
+                CatalogItemType::Table => &[
+                    Privilege::Select,
+                    Privilege::Insert,
+                    Privilege::Update,
+                    Privilege::Delete,
+                ],
+                CatalogItemType::View | CatalogItemType::Source => &[Privilege::Select],
+                _ => bail!("{} is a {}, not a relation", full_name, entry.item_type()),
+            };
+            (
+                PrivilegeObjectId::Item(entry.id()),
+                entry.item_type().to_string(),
+                valid_privileges,
+            )
+        }
+        PrivilegeObject::Schema(name) => {
+            let schema = scx.resolve_schema(name)?;
+            let schema_id = match (schema.database(), schema.id()) {
+                (ResolvedDatabaseSpecifier::Id(_), SchemaSpecifier::Id(id)) => *id,
+                _ => bail!(
+                    "cannot change privileges on schema {} because it is required by the database system",
+                    schema.name().schema
+                ),
+            };
+            (
+                PrivilegeObjectId::Schema(schema_id),
+                "schema".into(),
+                &[Privilege::Create],
+            )
+        }
+        PrivilegeObject::Database(name) => {
+            let database = scx.resolve_database(&name)?;
+            (
+                PrivilegeObjectId::Database(database.id()),
+                "database".into(),
+                &[Privilege::Create],
+            )
+        }
+    };
+    let privileges = match privileges {
+        Some(privileges) => {
+            for privilege in &privileges {
+                if !valid_privileges.contains(privilege) {
+                    bail!("invalid privilege type {} for {}", privilege, object_desc);
+                }
+            }
+            privileges
+        }
+        None => valid_privileges.to_vec(),
+    };
+    let role_ids = roles
+        .into_iter()
+        .map(|role| match role {
+            Some(role) => Ok(Some(
+                scx.catalog.resolve_role(&normalize::ident(role))?.id(),
+            )),
+            None => Ok(None),
+        })
+        .collect::<Result<_, anyhow::Error>>()?;
+    Ok(UpdatePrivilegesPlan {
+        object,
+        privileges,
+        role_ids,
+    })
+}
+
 pub fn describe_drop_cluster(
     _: &StatementContext,
     _: &DropClustersStatement,
@@ -2935,7 +3471,9 @@ pub fn plan_drop_item(
                     | CatalogItemType::View
                     | CatalogItemType::Sink
                     | CatalogItemType::Type
-                    | CatalogItemType::Secret => {
+                    | CatalogItemType::Secret
+                    | CatalogItemType::Publication
+                    | CatalogItemType::Task => {
                         bail!(
                             "cannot drop {}: still depended upon by catalog item '{}'",
                             scx.catalog.resolve_full_name(catalog_entry.name()),
@@ -2950,6 +3488,33 @@ pub fn plan_drop_item(
     Ok(Some(catalog_entry.id()))
 }
 
+pub fn describe_explain_drop(
+    _: &StatementContext,
+    _: &ExplainDropStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(Some(
+        RelationDesc::empty()
+            .with_column("name", ScalarType::String.nullable(false))
+            .with_column("type", ScalarType::String.nullable(false)),
+    )))
+}
+
+pub fn plan_explain_drop(
+    scx: &StatementContext,
+    ExplainDropStatement { stmt }: ExplainDropStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    let plan = match *stmt {
+        Statement::DropDatabase(stmt) => plan_drop_database(scx, stmt)?,
+        Statement::DropSchema(stmt) => plan_drop_schema(scx, stmt)?,
+        Statement::DropObjects(stmt) => plan_drop_objects(scx, stmt)?,
+        Statement::DropClusters(stmt) => plan_drop_cluster(scx, stmt)?,
+        stmt => unreachable!("EXPLAIN of unsupported DROP statement: {}", stmt),
+    };
+    Ok(Plan::ExplainDrop(ExplainDropPlan {
+        plan: Box::new(plan),
+    }))
+}
+
 with_options! {
     struct IndexWithOptions {
         logical_compaction_window: String,
@@ -3007,9 +3572,17 @@ pub fn plan_alter_index_options(
     }
     let id = entry.id();
 
+    // The options of the index are recorded in its `CREATE INDEX` statement,
+    // so that they survive restarts.
+    let mut create_stmt = parse::parse(entry.create_sql())?.into_element();
+    let with_options = match &mut create_stmt {
+        Statement::CreateIndex(stmt) => &mut stmt.with_options,
+        _ => unreachable!("index has a CREATE INDEX statement"),
+    };
+
     match actions {
         AlterIndexAction::ResetOptions(options) => {
-            let options = options
+            let options: Vec<_> = options
                 .into_iter()
                 .filter_map(|o| match normalize::ident(o).as_str() {
                     "logical_compaction_window" => Some(IndexOptionName::LogicalCompactionWindow),
@@ -3018,22 +3591,43 @@ pub fn plan_alter_index_options(
                     _ => None,
                 })
                 .collect();
+            with_options.retain(|o| {
+                !options
+                    .iter()
+                    .any(|name| normalize::ident(o.key.clone()) == index_option_key(*name))
+            });
             Ok(Plan::AlterIndexResetOptions(AlterIndexResetOptionsPlan {
                 id,
                 options,
+                create_sql: create_stmt.to_ast_string_stable(),
             }))
         }
         AlterIndexAction::SetOptions(options) => {
+            with_options.retain(|o| {
+                let key = normalize::ident(o.key.clone());
+                !options
+                    .iter()
+                    .any(|new| normalize::ident(new.key.clone()) == key)
+            });
+            with_options.extend(options.iter().cloned());
             let options = plan_index_options(options)?;
             Ok(Plan::AlterIndexSetOptions(AlterIndexSetOptionsPlan {
                 id,
                 options,
+                create_sql: create_stmt.to_ast_string_stable(),
             }))
         }
         AlterIndexAction::Enable => Ok(Plan::AlterIndexEnable(AlterIndexEnablePlan { id })),
     }
 }
 
+/// Returns the key of the `WITH` option that sets the index option `name`.
+fn index_option_key(name: IndexOptionName) -> &'static str {
+    match name {
+        IndexOptionName::LogicalCompactionWindow => "logical_compaction_window",
+    }
+}
+
 pub fn describe_alter_object_rename(
     _: &StatementContext,
     _: &AlterObjectRenameStatement<Raw>,
@@ -3084,6 +3678,183 @@ pub fn plan_alter_object_rename(
     }
 }
 
+pub fn describe_alter_database_rename(
+    _: &StatementContext,
+    _: &AlterDatabaseRenameStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_alter_database_rename(
+    scx: &StatementContext,
+    AlterDatabaseRenameStatement {
+        name,
+        if_exists,
+        to_name,
+    }: AlterDatabaseRenameStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    let id = match scx.resolve_database(&name) {
+        Ok(database) => Some(database.id()),
+        // TODO(benesch/jkosh44): generate a notice indicating that the database does not exist.
+        Err(_) if if_exists => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Plan::AlterDatabaseRename(AlterDatabaseRenamePlan {
+        id,
+        to_name: normalize::ident(to_name),
+    }))
+}
+
+pub fn describe_alter_schema_rename(
+    _: &StatementContext,
+    _: &AlterSchemaRenameStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_alter_schema_rename(
+    scx: &StatementContext,
+    AlterSchemaRenameStatement {
+        name,
+        if_exists,
+        to_name,
+    }: AlterSchemaRenameStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    let id = match scx.resolve_schema(name) {
+        Ok(schema) => {
+            let database_id = match schema.database() {
+                ResolvedDatabaseSpecifier::Ambient => bail!(
+                    "cannot rename schema {} because it is required by the database system",
+                    schema.name().schema
+                ),
+                ResolvedDatabaseSpecifier::Id(id) => id,
+            };
+            let schema_id = match schema.id() {
+                SchemaSpecifier::Temporary => bail!(
+                    "cannot rename schema {} because it is a temporary schema",
+                    schema.name().schema,
+                ),
+                SchemaSpecifier::Id(id) => id,
+            };
+            Some((database_id.clone(), schema_id.clone()))
+        }
+        // TODO(benesch/jkosh44): generate a notice indicating that the schema does not exist.
+        Err(_) if if_exists => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Plan::AlterSchemaRename(AlterSchemaRenamePlan {
+        id,
+        to_name: normalize::ident(to_name),
+    }))
+}
+
+pub fn describe_comment(
+    _: &StatementContext,
+    _: &CommentStatement<Raw>,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_comment(
+    scx: &StatementContext,
+    CommentStatement { object, comment }: CommentStatement<Raw>,
+) -> Result<Plan, anyhow::Error> {
+    let (id, column_position) = match object {
+        CommentObject::Item { object_type, name } => {
+            let entry = scx.resolve_item(name)?;
+            if entry.item_type() != object_type {
+                bail!(
+                    "{} is a {} not a {}",
+                    scx.catalog.resolve_full_name(entry.name()),
+                    entry.item_type(),
+                    object_type
+                )
+            }
+            (entry.id(), None)
+        }
+        CommentObject::Column {
+            relation_name,
+            column_name,
+        } => {
+            let entry = scx.resolve_item(relation_name)?;
+            let full_name = scx.catalog.resolve_full_name(entry.name());
+            let desc = match entry.item_type() {
+                CatalogItemType::Table | CatalogItemType::View | CatalogItemType::Source => {
+                    entry.desc(&full_name)?
+                }
+                _ => bail!(
+                    "{} is a {}, which does not have columns",
+                    full_name,
+                    entry.item_type()
+                ),
+            };
+            let column_name = normalize::column_name(column_name);
+            match desc.get_by_name(&column_name) {
+                Some((i, _)) => (entry.id(), Some(i + 1)),
+                None => bail!(
+                    "column {} of relation {} does not exist",
+                    column_name.as_str().quoted(),
+                    full_name.to_string().quoted()
+                ),
+            }
+        }
+    };
+    // As in PostgreSQL, an empty comment is equivalent to removing the comment.
+    let comment = comment.filter(|c| !c.is_empty());
+    Ok(Plan::Comment(CommentPlan {
+        id,
+        column_position,
+        comment,
+    }))
+}
+
+pub fn describe_undrop(
+    _: &StatementContext,
+    _: &UndropStatement,
+) -> Result<StatementDesc, anyhow::Error> {
+    Ok(StatementDesc::new(None))
+}
+
+pub fn plan_undrop(
+    scx: &StatementContext,
+    UndropStatement { name }: UndropStatement,
+) -> Result<Plan, anyhow::Error> {
+    let name = normalize::unresolved_object_name(name)?;
+    let item = scx.catalog.resolve_dropped_item(&name)?;
+    let mut stmt = parse::parse(item.create_sql())?.into_element();
+    match &stmt {
+        // The contents of a secret are removed along with the secret.
+        Statement::CreateSecret(_) => bail!(
+            "cannot undrop secret {}, as its contents were removed when it was dropped",
+            item.name().quoted()
+        ),
+        // The data in a table is discarded along with the table, so a restored
+        // table would silently be empty.
+        Statement::CreateTable(_) => bail!(
+            "cannot undrop table {}, as its data was discarded when it was dropped; \
+             recreate it with CREATE TABLE instead",
+            item.name().quoted()
+        ),
+        // A recreated sink would not resume where the dropped sink left off.
+        Statement::CreateSink(_) => bail!(
+            "cannot undrop sink {}; recreate it with CREATE SINK instead",
+            item.name().quoted()
+        ),
+        _ => (),
+    }
+    // The items that the dropped item referenced may themselves have been
+    // dropped and restored with new IDs, so refer to those items by name.
+    transform::create_stmt_unbind_ids(&mut stmt, |id| match id.parse() {
+        Ok(id) => scx.catalog.try_get_item(&id).is_some(),
+        Err(_) => false,
+    });
+    let plan = super::plan(scx.pcx, scx.catalog, stmt, &Params::empty())?;
+    Ok(Plan::Undrop(UndropPlan {
+        id: item.id(),
+        plan: Box::new(plan),
+    }))
+}
+
 pub fn describe_alter_secret_options(
     _: &StatementContext,
     _: &AlterSecretStatement<Raw>,
@@ -3092,14 +3863,53 @@ pub fn describe_alter_secret_options(
 }
 
 pub fn plan_alter_secret(
-    _: &StatementContext,
+    scx: &StatementContext,
     AlterSecretStatement {
-        secret_name: _,
-        if_exists: _,
-        value: _,
+        secret_name,
+        if_exists,
+        value,
     }: AlterSecretStatement<Aug>,
 ) -> Result<Plan, anyhow::Error> {
-    bail_unsupported!("ALTER SECRET")
+    scx.require_experimental_mode("ALTER SECRET")?;
+
+    let entry = match scx.get_item_by_resolved_name(&secret_name) {
+        Ok(entry) => entry,
+        Err(_) if if_exists => {
+            return Ok(Plan::AlterNoop(AlterNoopPlan {
+                object_type: ObjectType::Secret,
+            }));
+        }
+        Err(e) => return Err(e),
+    };
+    if entry.item_type() != CatalogItemType::Secret {
+        bail!(
+            "{} is a {} not a secret",
+            secret_name.full_name_str(),
+            entry.item_type()
+        )
+    }
+
+    // The format of the secret is recorded in its `CREATE SECRET` statement,
+    // and applies to every version of the secret.
+    let format = match parse::parse(entry.create_sql())?.into_element() {
+        Statement::CreateSecret(mut stmt) => {
+            let option_schema = secret_option_schema();
+            option_schema.canonicalize(&mut stmt.with_options);
+            option_schema
+                .validate(&stmt.with_options)?
+                .take_string("format")
+                .expect("format has a default")
+                .parse()?
+        }
+        _ => unreachable!("secret has a CREATE SECRET statement"),
+    };
+    let secret_as = query::plan_secret_as(scx, value)?;
+
+    Ok(Plan::AlterSecret(AlterSecretPlan {
+        id: entry.id(),
+        secret_as,
+        format,
+    }))
 }
 
 pub fn describe_alter_cluster(
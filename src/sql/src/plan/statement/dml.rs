@@ -13,28 +13,32 @@
 //! `INSERT`, `SELECT`, `TAIL`, and `COPY`.
 
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 
 use mz_expr::{GlobalId, MirRelationExpr};
 use mz_ore::collections::CollectionExt;
+use mz_ore::str::StrExt;
 use mz_repr::adt::numeric::NumericMaxScale;
-use mz_repr::{RelationDesc, ScalarType};
+use mz_repr::{strconv, RelationDesc, ScalarType};
 use mz_sql_parser::ast::AstInfo;
 
 use crate::ast::{
     CopyDirection, CopyRelation, CopyStatement, CopyTarget, CreateViewStatement, DeleteStatement,
-    ExplainStage, ExplainStatement, Explainee, Ident, InsertStatement, Query, SelectStatement,
-    Statement, TailRelation, TailStatement, UpdateStatement, ViewDefinition,
+    ExplainFormat, ExplainStage, ExplainStatement, Explainee, Expr, FunctionArgs, Ident,
+    InsertStatement, Query, SelectItem, SelectStatement, SetExpr, Statement, TailRelation,
+    TailStatement, UpdateStatement, Value, ViewDefinition,
 };
 use crate::catalog::CatalogItemType;
 use crate::names::{resolve_names, Aug, ResolvedObjectName};
+use crate::normalize;
 use crate::plan::query::QueryLifetime;
 use crate::plan::statement::{StatementContext, StatementDesc};
 use crate::plan::{query, QueryContext};
 use crate::plan::{
-    CopyFormat, CopyFromPlan, CopyParams, ExplainPlan, InsertPlan, MutationKind, Params, PeekPlan,
-    Plan, ReadThenWritePlan, TailFrom, TailPlan,
+    AdminOperation, AdminPlan, CopyFormat, CopyFromPlan, CopyParams, ExplainPlan, InsertPlan,
+    MutationKind, Params, PeekPlan, Plan, ReadThenWritePlan, TailEnvelope, TailFrom, TailPlan,
 };
 
 // TODO(benesch): currently, describing a `SELECT` or `INSERT` query
@@ -139,6 +143,9 @@ pub fn describe_select(
     scx: &StatementContext,
     stmt: SelectStatement<Aug>,
 ) -> Result<StatementDesc, anyhow::Error> {
+    if let Some(AdminPlan { desc, .. }) = plan_admin_select(scx, &stmt)? {
+        return Ok(StatementDesc::new(Some(desc)));
+    }
     let query::PlannedQuery { desc, .. } =
         query::plan_root_query(scx, stmt.query, QueryLifetime::OneShot(scx.pcx()?))?;
     Ok(StatementDesc::new(Some(desc)))
@@ -150,6 +157,13 @@ pub fn plan_select(
     params: &Params,
     copy_to: Option<CopyFormat>,
 ) -> Result<Plan, anyhow::Error> {
+    let stmt = SelectStatement { query, as_of };
+    if copy_to.is_none() {
+        if let Some(plan) = plan_admin_select(scx, &stmt)? {
+            return Ok(Plan::Admin(plan));
+        }
+    }
+    let SelectStatement { query, as_of } = stmt;
     let query::PlannedQuery {
         expr, finishing, ..
     } = plan_query(scx, query, params, QueryLifetime::OneShot(scx.pcx()?))?;
@@ -162,6 +176,169 @@ pub fn plan_select(
     }))
 }
 
+/// Plans a `SELECT` statement that consists of nothing but a call to one of
+/// the administrative functions in `mz_internal`, like
+/// `SELECT mz_internal.gc_secrets()`, or to `mz_cancel_query` or
+/// `pg_reload_conf`.
+///
+/// These functions have side effects, so they cannot be planned as ordinary
+/// expressions. Instead the coordinator performs the operation and reports
+/// its outcome as a single row. Returns `None` if the statement is not such a
+/// call, in which case calls to the functions fail during ordinary planning.
+fn plan_admin_select(
+    scx: &StatementContext,
+    SelectStatement { query, as_of }: &SelectStatement<Aug>,
+) -> Result<Option<AdminPlan>, anyhow::Error> {
+    let select = match &query.body {
+        SetExpr::Select(select)
+            if query.ctes.is_empty()
+                && query.order_by.is_empty()
+                && query.limit.is_none()
+                && query.offset.is_none()
+                && as_of.is_none() =>
+        {
+            select
+        }
+        _ => return Ok(None),
+    };
+    let (func, alias) = match select.projection.as_slice() {
+        [SelectItem::Expr {
+            expr: Expr::Function(func),
+            alias,
+        }] if select.distinct.is_none()
+            && select.from.is_empty()
+            && select.selection.is_none()
+            && select.group_by.is_empty()
+            && select.having.is_none()
+            && select.options.is_empty()
+            && func.filter.is_none()
+            && func.over.is_none()
+            && !func.distinct =>
+        {
+            (func, alias)
+        }
+        _ => return Ok(None),
+    };
+    let name = match scx.resolve_function(func.name.clone()) {
+        Ok(item) => scx.catalog.resolve_full_name(item.name()),
+        Err(_) => return Ok(None),
+    };
+    let arity = match (name.schema.as_str(), name.item.as_str()) {
+        (
+            "mz_internal",
+            "restart_replica"
+            | "replica_diagnostics"
+            | "recompute_materialization"
+            | "rebuild_upsert_state"
+            | "backup_catalog"
+            | "mz_cpu_profile",
+        ) => 1,
+        (
+            "mz_internal",
+            "gc_secrets" | "migrate_secrets" | "collect_statistics" | "check_catalog",
+        ) => 0,
+        ("mz_catalog", "mz_cancel_query") => 1,
+        ("pg_catalog", "pg_reload_conf") => 0,
+        _ => return Ok(None),
+    };
+    let args = match &func.args {
+        FunctionArgs::Args { args, order_by } if order_by.is_empty() && args.len() == arity => args,
+        _ => return Ok(None),
+    };
+    // The process ID that `mz_cancel_query` takes is a number, like the
+    // result of `pg_backend_pid()`. Every other function takes strings.
+    let numeric_args = name.item == "mz_cancel_query";
+    let mut literals = vec![];
+    for arg in args {
+        match arg {
+            Expr::Value(Value::Number(n)) if numeric_args => literals.push(n.as_str()),
+            Expr::Value(Value::String(s)) if !numeric_args => literals.push(s.as_str()),
+            _ if numeric_args => bail!("arguments to {} must be integer literals", name),
+            _ => bail!("arguments to {} must be string literals", name),
+        }
+    }
+
+    let (operation, typ) = match (name.item.as_str(), literals.as_slice()) {
+        ("restart_replica", [replica]) => {
+            let compute_instance = scx.resolve_compute_instance(None)?;
+            let operation = AdminOperation::RestartReplica {
+                compute_instance: compute_instance.id(),
+                replica: replica.to_string(),
+            };
+            (operation, ScalarType::Bool)
+        }
+        ("replica_diagnostics", [replica]) => {
+            let compute_instance = scx.resolve_compute_instance(None)?;
+            let operation = AdminOperation::ReplicaDiagnostics {
+                compute_instance: compute_instance.id(),
+                replica: replica.to_string(),
+            };
+            (operation, ScalarType::String)
+        }
+        ("recompute_materialization", [id]) => {
+            let id = id
+                .parse::<GlobalId>()
+                .map_err(|_| anyhow!("invalid object ID: {}", id.quoted()))?;
+            if scx.catalog.try_get_item(&id).is_none() {
+                bail!("unknown catalog item '{}'", id);
+            }
+            (
+                AdminOperation::RecomputeMaterialization { id },
+                ScalarType::Bool,
+            )
+        }
+        ("rebuild_upsert_state", [id]) => {
+            let id = id
+                .parse::<GlobalId>()
+                .map_err(|_| anyhow!("invalid object ID: {}", id.quoted()))?;
+            if scx.catalog.try_get_item(&id).is_none() {
+                bail!("unknown catalog item '{}'", id);
+            }
+            (AdminOperation::RebuildUpsertState { id }, ScalarType::Int64)
+        }
+        ("backup_catalog", [path]) => {
+            if !Path::new(path).is_absolute() {
+                bail!("backup path must be absolute: {}", path.quoted());
+            }
+            (
+                AdminOperation::BackupCatalog {
+                    path: path.to_string(),
+                },
+                ScalarType::Bool,
+            )
+        }
+        ("mz_cpu_profile", [duration]) => {
+            let duration = strconv::parse_interval(duration)
+                .map_err(anyhow::Error::from)
+                .and_then(|interval| interval.duration())
+                .map_err(|e| anyhow!("invalid profile duration {}: {}", duration.quoted(), e))?;
+            (AdminOperation::CpuProfile { duration }, ScalarType::String)
+        }
+        ("mz_cancel_query", [pid]) => {
+            let pid = pid
+                .parse::<i32>()
+                .map_err(|_| anyhow!("invalid process ID: {}", pid))?;
+            // `pg_backend_pid()` reports connection IDs as signed integers.
+            let conn_id = pid as u32;
+            (AdminOperation::CancelQuery { conn_id }, ScalarType::Bool)
+        }
+        ("gc_secrets", []) => (AdminOperation::GcSecrets, ScalarType::Int64),
+        ("migrate_secrets", []) => (AdminOperation::MigrateSecrets, ScalarType::Int64),
+        ("collect_statistics", []) => (AdminOperation::CollectStatistics, ScalarType::Int64),
+        ("check_catalog", []) => (AdminOperation::CheckCatalog, ScalarType::String),
+        ("pg_reload_conf", []) => (AdminOperation::ReloadConfig, ScalarType::Bool),
+        _ => unreachable!("arity checked above"),
+    };
+    let column_name = match alias {
+        Some(alias) => normalize::column_name(alias.clone()),
+        None => name.item.into(),
+    };
+    Ok(Some(AdminPlan {
+        operation,
+        desc: RelationDesc::empty().with_column(column_name, typ.nullable(false)),
+    }))
+}
+
 pub fn describe_explain(
     scx: &StatementContext,
     ExplainStatement {
@@ -177,6 +354,7 @@ pub fn describe_explain(
             ExplainStage::OptimizedPlan { .. } => "Optimized Plan",
             ExplainStage::PhysicalPlan => "Physical Plan",
             ExplainStage::Timestamp => "Timestamp",
+            ExplainStage::Analyze => "Analyzed Plan",
         },
         ScalarType::String.nullable(false),
     )))
@@ -203,7 +381,19 @@ pub fn plan_explain(
         options,
     }: ExplainStatement<Aug>,
     params: &Params,
+    depends_on: HashSet<GlobalId>,
 ) -> Result<Plan, anyhow::Error> {
+    if (options.attributes || options.format != ExplainFormat::Text)
+        && !matches!(
+            stage,
+            ExplainStage::DecorrelatedPlan | ExplainStage::OptimizedPlan
+        )
+    {
+        bail!(
+            "EXPLAIN {} does not support the ATTRIBUTES or FORMAT options",
+            stage
+        );
+    }
     let is_view = matches!(explainee, Explainee::View(_));
     let query = match explainee {
         Explainee::View(name) => {
@@ -247,6 +437,7 @@ pub fn plan_explain(
         row_set_finishing: finishing,
         stage,
         options,
+        depends_on: depends_on.into_iter().collect(),
     }))
 }
 
@@ -275,9 +466,35 @@ with_options! {
     struct TailOptions {
         snapshot: bool,
         progress: bool,
+        max_unacked_batches: usize,
+        envelope: String,
      }
 }
 
+impl TailOptions {
+    /// Returns the envelope of the tail, and whether it emits progress rows.
+    fn envelope(&self) -> Result<(TailEnvelope, bool), anyhow::Error> {
+        let envelope = match &self.envelope {
+            None => TailEnvelope::Updates,
+            Some(envelope) => match envelope.to_lowercase().as_str() {
+                "updates" => TailEnvelope::Updates,
+                "diffs" => TailEnvelope::Diffs,
+                _ => bail!("unknown ENVELOPE: {}", envelope),
+            },
+        };
+        match envelope {
+            TailEnvelope::Updates => Ok((envelope, self.progress.unwrap_or(false))),
+            // Progress rows are what make the diffs envelope useful, and they
+            // are distinguished by their `NULL` diff rather than by an
+            // `mz_progressed` column.
+            TailEnvelope::Diffs if self.progress.is_some() => {
+                bail!("PROGRESS cannot be used with ENVELOPE = 'diffs'")
+            }
+            TailEnvelope::Diffs => Ok((envelope, true)),
+        }
+    }
+}
+
 pub fn describe_tail(
     scx: &StatementContext,
     stmt: TailStatement<Aug>,
@@ -295,7 +512,7 @@ pub fn describe_tail(
         }
     };
     let options = TailOptions::try_from(stmt.options)?;
-    let progress = options.progress.unwrap_or(false);
+    let (envelope, progress) = options.envelope()?;
     let mut desc = RelationDesc::empty().with_column(
         "mz_timestamp",
         ScalarType::Numeric {
@@ -303,7 +520,7 @@ pub fn describe_tail(
         }
         .nullable(false),
     );
-    if progress {
+    if progress && envelope == TailEnvelope::Updates {
         desc = desc.with_column("mz_progressed", ScalarType::Bool.nullable(false));
     }
     desc = desc.with_column("mz_diff", ScalarType::Int64.nullable(true));
@@ -322,6 +539,7 @@ pub fn plan_tail(
         relation,
         options,
         as_of,
+        up_to,
     }: TailStatement<Aug>,
     copy_to: Option<CopyFormat>,
     depends_on: HashSet<GlobalId>,
@@ -337,7 +555,9 @@ pub fn plan_tail(
                 | CatalogItemType::Index
                 | CatalogItemType::Sink
                 | CatalogItemType::Type
-                | CatalogItemType::Secret => bail!(
+                | CatalogItemType::Secret
+                | CatalogItemType::Publication
+                | CatalogItemType::Task => bail!(
                     "'{}' cannot be tailed because it is a {}",
                     name.full_name_str(),
                     entry.item_type(),
@@ -367,13 +587,21 @@ pub fn plan_tail(
     };
 
     let when = query::plan_as_of(scx, as_of)?;
+    let up_to = query::plan_up_to(scx, up_to)?;
     let options = TailOptions::try_from(options)?;
+    if options.max_unacked_batches == Some(0) {
+        bail!("max_unacked_batches must be greater than zero");
+    }
+    let (envelope, emit_progress) = options.envelope()?;
     Ok(Plan::Tail(TailPlan {
         from,
         when,
         with_snapshot: options.snapshot.unwrap_or(true),
         copy_to,
-        emit_progress: options.progress.unwrap_or(false),
+        emit_progress,
+        envelope,
+        max_unacked_batches: options.max_unacked_batches,
+        up_to,
     }))
 }
 
@@ -23,8 +23,9 @@ use crate::ast::visit_mut::VisitMut;
 use crate::ast::{
     ObjectType, Raw, SelectStatement, ShowColumnsStatement, ShowCreateIndexStatement,
     ShowCreateSinkStatement, ShowCreateSourceStatement, ShowCreateTableStatement,
-    ShowCreateViewStatement, ShowDatabasesStatement, ShowIndexesStatement, ShowObjectsStatement,
-    ShowSchemasStatement, ShowStatementFilter, Statement, Value,
+    ShowCreateViewStatement, ShowDatabasesStatement, ShowDroppedObjectsStatement,
+    ShowIndexesStatement, ShowObjectsStatement, ShowSchemasStatement, ShowStatementFilter,
+    Statement, Value,
 };
 use crate::catalog::CatalogItemType;
 use crate::names::{
@@ -203,6 +204,14 @@ pub fn show_databases<'a>(
     ShowSelect::new(scx, query, filter, None, None)
 }
 
+pub fn show_dropped_objects<'a>(
+    scx: &'a StatementContext<'a>,
+    ShowDroppedObjectsStatement { filter }: ShowDroppedObjectsStatement<Aug>,
+) -> Result<ShowSelect<'a>, anyhow::Error> {
+    let query = "SELECT name, type, dropped_at FROM mz_catalog.mz_dropped_objects".to_string();
+    ShowSelect::new(scx, query, filter, Some("dropped_at, name"), None)
+}
+
 pub fn show_schemas<'a>(
     scx: &'a StatementContext<'a>,
     ShowSchemasStatement {
@@ -274,6 +283,8 @@ pub fn show_objects<'a>(
         ObjectType::Role => bail_unsupported!("SHOW ROLES"),
         ObjectType::Cluster => show_clusters(scx, filter),
         ObjectType::Secret => show_secrets(scx, from, filter),
+        ObjectType::Publication => show_publications(scx, from, filter),
+        ObjectType::Task => show_tasks(scx, from, filter),
         ObjectType::Index => unreachable!("SHOW INDEX handled separately"),
     }
 }
@@ -512,6 +523,7 @@ pub fn show_indexes<'a>(
     scx: &'a StatementContext<'a>,
     ShowIndexesStatement {
         extended,
+        full,
         in_cluster,
         table_name,
         filter,
@@ -548,6 +560,27 @@ pub fn show_indexes<'a>(
         "parsing failed to enforce either table_name or in_cluster's presence"
     );
 
+    // `SHOW FULL INDEXES` additionally reports how each index is used and the
+    // number of records in its arrangements, to help identify indexes that
+    // consume memory without serving any queries.
+    let (usage_projection, usage_join) = if full {
+        (
+            ",
+            COALESCE(idx_usage.dataflow_imports, 0) AS dataflow_imports,
+            idx_usage.last_fast_path_peek AS last_fast_path_peek,
+            records.records AS records",
+            "
+            LEFT JOIN mz_catalog.mz_index_usage AS idx_usage ON idxs.id = idx_usage.index_id
+            LEFT JOIN (
+                SELECT name, SUM(records) AS records
+                FROM mz_catalog.mz_records_per_dataflow_global
+                GROUP BY name
+            ) AS records ON records.name = idx_usage.dataflow_name",
+        )
+    } else {
+        ("", "")
+    };
+
     let query = format!(
         "SELECT
             clusters.name AS cluster,
@@ -557,16 +590,18 @@ pub fn show_indexes<'a>(
             obj_cols.name AS column_name,
             idx_cols.on_expression AS expression,
             idx_cols.nullable AS nullable,
-            idxs.enabled AS enabled
+            idxs.enabled AS enabled{}
         FROM
             mz_catalog.mz_indexes AS idxs
             JOIN mz_catalog.mz_index_columns AS idx_cols ON idxs.id = idx_cols.index_id
             JOIN mz_catalog.mz_objects AS objs ON idxs.on_id = objs.id
             JOIN mz_catalog.mz_clusters AS clusters ON clusters.id = idxs.cluster_id
             LEFT JOIN mz_catalog.mz_columns AS obj_cols
-                ON idxs.on_id = obj_cols.id AND idx_cols.on_position = obj_cols.position
+                ON idxs.on_id = obj_cols.id AND idx_cols.on_position = obj_cols.position{}
         WHERE
             {}",
+        usage_projection,
+        usage_join,
         itertools::join(query_filter.iter(), " AND ")
     );
 
@@ -640,6 +675,40 @@ pub fn show_secrets<'a>(
     ShowSelect::new(scx, query, filter, None, None)
 }
 
+pub fn show_publications<'a>(
+    scx: &'a StatementContext<'a>,
+    from: Option<ResolvedSchemaName>,
+    filter: Option<ShowStatementFilter<Aug>>,
+) -> Result<ShowSelect<'a>, anyhow::Error> {
+    let schema_spec = scx.resolve_optional_schema(&from)?;
+
+    let query = format!(
+        "SELECT p.name FROM mz_catalog.mz_publications p
+        JOIN mz_catalog.mz_schemas s ON p.schema_id = s.id
+        WHERE schema_id = {}",
+        schema_spec,
+    );
+
+    ShowSelect::new(scx, query, filter, None, None)
+}
+
+pub fn show_tasks<'a>(
+    scx: &'a StatementContext<'a>,
+    from: Option<ResolvedSchemaName>,
+    filter: Option<ShowStatementFilter<Aug>>,
+) -> Result<ShowSelect<'a>, anyhow::Error> {
+    let schema_spec = scx.resolve_optional_schema(&from)?;
+
+    let query = format!(
+        "SELECT t.name, t.schedule FROM mz_catalog.mz_scheduled_tasks t
+        JOIN mz_catalog.mz_schemas s ON t.schema_id = s.id
+        WHERE schema_id = {}",
+        schema_spec,
+    );
+
+    ShowSelect::new(scx, query, filter, None, None)
+}
+
 /// An intermediate result when planning a `SHOW` query.
 ///
 /// Can be interrogated for its columns, or converted into a proper [`Plan`].
@@ -0,0 +1,441 @@
+// Copyright Materialize, Inc. and contributors. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Declarative schemas for the `WITH` options of sources, sinks, and secrets.
+//!
+//! An [`OptionSchema`] lists the options that a statement accepts, along with
+//! their types, defaults, and whether they are required or deprecated.
+//! Validating a statement's options against the schema checks all of them up
+//! front, with uniform error messages, and yields [`WithOptions`] from which
+//! the planner takes typed values.
+//!
+//! Deprecated options that have been renamed are rewritten to their new names
+//! by [`OptionSchema::canonicalize`] before the statement's `create_sql` is
+//! generated, so that `SHOW CREATE` renders the canonical form of the
+//! statement, which plans identically.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use itertools::Itertools;
+use tracing::warn;
+
+use mz_sql_parser::ast::{AstInfo, Ident, SqlOption, Value};
+
+use crate::normalize;
+
+/// The type of the value of a `WITH` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// A boolean.
+    Bool,
+    /// A string.
+    String,
+    /// A 64-bit signed integer.
+    Integer,
+    /// A 64-bit unsigned integer.
+    NonNegativeInteger,
+    /// A 64-bit unsigned integer greater than zero.
+    PositiveInteger,
+    /// A nonnegative integer, or an array of nonnegative integers.
+    NonNegativeIntegers,
+    /// One of the listed strings.
+    OneOf(&'static [&'static str]),
+    /// A value that is validated by the code that consumes the option.
+    Unchecked,
+}
+
+impl OptionType {
+    /// Reports whether `value` is of this type.
+    fn matches(&self, value: &Value) -> bool {
+        let nonnegative = |n: &str| n.parse::<u64>().is_ok();
+        match (self, value) {
+            (OptionType::Bool, Value::Boolean(_)) => true,
+            (OptionType::String, Value::String(_)) => true,
+            (OptionType::Integer, Value::Number(n)) => n.parse::<i64>().is_ok(),
+            (OptionType::NonNegativeInteger, Value::Number(n)) => nonnegative(n),
+            (OptionType::PositiveInteger, Value::Number(n)) => {
+                matches!(n.parse::<u64>(), Ok(n) if n > 0)
+            }
+            (OptionType::NonNegativeIntegers, Value::Number(n)) => nonnegative(n),
+            (OptionType::NonNegativeIntegers, Value::Array(vs)) => vs
+                .iter()
+                .all(|v| matches!(v, Value::Number(n) if nonnegative(n))),
+            (OptionType::OneOf(choices), Value::String(s)) => choices.contains(&s.as_str()),
+            (OptionType::Unchecked, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Describes the values of this type, for error messages.
+    fn describe(&self) -> String {
+        match self {
+            OptionType::Bool => "a boolean".into(),
+            OptionType::String => "a string".into(),
+            OptionType::Integer => "an integer".into(),
+            OptionType::NonNegativeInteger => "a nonnegative integer".into(),
+            OptionType::PositiveInteger => "a positive integer".into(),
+            OptionType::NonNegativeIntegers => {
+                "a nonnegative integer or an array of nonnegative integers".into()
+            }
+            OptionType::OneOf(choices) => {
+                let choices: Vec<_> = choices.iter().map(|c| format!("'{}'", c)).collect();
+                match choices.as_slice() {
+                    [] => unreachable!("options have at least one choice"),
+                    [choice] => choice.clone(),
+                    [a, b] => format!("one of {} or {}", a, b),
+                    [init @ .., last] => format!("one of {}, or {}", init.join(", "), last),
+                }
+            }
+            OptionType::Unchecked => "any value".into(),
+        }
+    }
+}
+
+/// How a `WITH` option is deprecated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deprecation {
+    /// The option has been renamed to the specified name. The old name is
+    /// still accepted, but is rewritten to the new name.
+    RenamedTo(&'static str),
+    /// The option is still accepted, but is superseded by the specified
+    /// syntax.
+    SupersededBy(&'static str),
+}
+
+/// The specification of a `WITH` option.
+#[derive(Debug, Clone)]
+pub struct OptionSpec {
+    name: String,
+    ty: OptionType,
+    default: Option<Value>,
+    required: bool,
+    deprecation: Option<Deprecation>,
+}
+
+impl OptionSpec {
+    /// Specifies an optional option of the given type, without a default.
+    pub fn new(name: impl Into<String>, ty: OptionType) -> OptionSpec {
+        OptionSpec {
+            name: name.into(),
+            ty,
+            default: None,
+            required: false,
+            deprecation: None,
+        }
+    }
+
+    /// Sets the value that the option takes when it is not specified.
+    pub fn default(mut self, value: Value) -> OptionSpec {
+        assert!(self.ty.matches(&value), "default of wrong type");
+        self.default = Some(value);
+        self
+    }
+
+    /// Requires that the option be specified.
+    pub fn required(mut self) -> OptionSpec {
+        self.required = true;
+        self
+    }
+
+    /// Marks the option as deprecated.
+    pub fn deprecated(mut self, deprecation: Deprecation) -> OptionSpec {
+        self.deprecation = Some(deprecation);
+        self
+    }
+}
+
+/// The options that a statement accepts in its `WITH` clause.
+#[derive(Debug, Clone)]
+pub struct OptionSchema {
+    context: &'static str,
+    specs: BTreeMap<String, OptionSpec>,
+}
+
+impl OptionSchema {
+    /// Creates a schema that accepts no options. `context` names the
+    /// statement in error messages, e.g. `CREATE SOURCE`.
+    pub fn new(context: &'static str) -> OptionSchema {
+        OptionSchema {
+            context,
+            specs: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the specified options to the schema.
+    pub fn with(mut self, specs: impl IntoIterator<Item = OptionSpec>) -> OptionSchema {
+        for spec in specs {
+            self.specs.insert(spec.name.clone(), spec);
+        }
+        self
+    }
+
+    /// Rewrites the names of renamed options in `options` to their new names.
+    pub fn canonicalize<T: AstInfo>(&self, options: &mut [SqlOption<T>]) {
+        for option in options {
+            let name = match option {
+                SqlOption::Value { name, .. }
+                | SqlOption::ObjectName { name, .. }
+                | SqlOption::DataType { name, .. } => name,
+            };
+            if let Some(OptionSpec {
+                deprecation: Some(Deprecation::RenamedTo(new_name)),
+                ..
+            }) = self.specs.get(name.as_str())
+            {
+                *name = Ident::new(*new_name);
+            }
+        }
+    }
+
+    /// Validates `options` against the schema.
+    ///
+    /// Returns an error that lists the valid options if any option is not in
+    /// the schema, or an error if any option is of the wrong type or a
+    /// required option is missing. Logs a warning for each deprecated option.
+    pub fn validate<T: AstInfo>(
+        &self,
+        options: &[SqlOption<T>],
+    ) -> Result<WithOptions, anyhow::Error> {
+        let mut values = BTreeMap::new();
+        let mut unexpected = vec![];
+        for (name, value) in normalize::options(options) {
+            let spec = match self.specs.get(&name) {
+                Some(spec) => spec,
+                None => {
+                    unexpected.push(name);
+                    continue;
+                }
+            };
+            let name = match &spec.deprecation {
+                Some(Deprecation::RenamedTo(new_name)) => {
+                    warn!(
+                        "{} option {} is deprecated; use {}",
+                        self.context, name, new_name
+                    );
+                    new_name.to_string()
+                }
+                Some(Deprecation::SupersededBy(syntax)) => {
+                    warn!(
+                        "{} option {} is deprecated; use {}",
+                        self.context, name, syntax
+                    );
+                    name
+                }
+                None => name,
+            };
+            let ty = self.specs[&name].ty;
+            if !ty.matches(&value) {
+                bail!("{} must be {}", name, ty.describe());
+            }
+            values.insert(name, value);
+        }
+        if !unexpected.is_empty() {
+            bail!(
+                "unexpected parameters for {}: {} (valid parameters are: {})",
+                self.context,
+                unexpected.join(","),
+                self.valid_names().join(", ")
+            );
+        }
+        let missing: Vec<_> = self
+            .specs
+            .values()
+            .filter(|spec| spec.required && !values.contains_key(&spec.name))
+            .map(|spec| spec.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            bail!(
+                "missing required parameters for {}: {}",
+                self.context,
+                missing.join(",")
+            );
+        }
+        Ok(WithOptions {
+            schema: self.clone(),
+            values,
+        })
+    }
+
+    /// Returns the names of the options that are not deprecated.
+    fn valid_names(&self) -> impl Iterator<Item = &str> {
+        self.specs
+            .values()
+            .filter(|spec| spec.deprecation.is_none())
+            .map(|spec| spec.name.as_str())
+    }
+
+    fn spec(&self, name: &str) -> &OptionSpec {
+        self.specs
+            .get(name)
+            .unwrap_or_else(|| panic!("option {} is not in the {} schema", name, self.context))
+    }
+}
+
+/// `WITH` options that have been validated against an [`OptionSchema`].
+///
+/// Planning takes each option that it uses, which yields the specified value
+/// or the option's default. [`WithOptions::ensure_consumed`] then rejects the
+/// options that were specified but not used, e.g., because they do not apply
+/// in combination with other options.
+#[derive(Debug)]
+pub struct WithOptions {
+    schema: OptionSchema,
+    values: BTreeMap<String, Value>,
+}
+
+impl WithOptions {
+    /// Takes the value of the named option, or its default.
+    pub fn take(&mut self, name: &str) -> Option<Value> {
+        let spec = self.schema.spec(name);
+        self.values.remove(name).or_else(|| spec.default.clone())
+    }
+
+    /// Takes the value of the named boolean option.
+    pub fn take_bool(&mut self, name: &str) -> Option<bool> {
+        self.take(name).map(|value| match value {
+            Value::Boolean(b) => b,
+            _ => unreachable!("validated as a boolean"),
+        })
+    }
+
+    /// Takes the value of the named string option.
+    pub fn take_string(&mut self, name: &str) -> Option<String> {
+        self.take(name).map(|value| match value {
+            Value::String(s) => s,
+            _ => unreachable!("validated as a string"),
+        })
+    }
+
+    /// Takes the value of the named integer option.
+    pub fn take_i64(&mut self, name: &str) -> Option<i64> {
+        self.take(name).map(|value| match value {
+            Value::Number(n) => n.parse().expect("validated as an integer"),
+            _ => unreachable!("validated as an integer"),
+        })
+    }
+
+    /// Takes the value of the named nonnegative integer option.
+    pub fn take_u64(&mut self, name: &str) -> Option<u64> {
+        self.take(name).map(|value| match value {
+            Value::Number(n) => n.parse().expect("validated as a nonnegative integer"),
+            _ => unreachable!("validated as a nonnegative integer"),
+        })
+    }
+
+    /// Returns the options that have not yet been taken, for consumption by
+    /// code that extracts groups of options itself, like Kafka client
+    /// options.
+    pub fn remaining_mut(&mut self) -> &mut BTreeMap<String, Value> {
+        &mut self.values
+    }
+
+    /// Returns an error if any option was specified but not taken.
+    pub fn ensure_consumed(self) -> Result<(), anyhow::Error> {
+        normalize::ensure_empty_options(&self.values, self.schema.context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mz_sql_parser::ast::display::AstDisplay;
+    use mz_sql_parser::ast::Raw;
+
+    use super::*;
+
+    fn schema() -> OptionSchema {
+        OptionSchema::new("CREATE THING").with([
+            OptionSpec::new("tail", OptionType::Bool).default(Value::Boolean(false)),
+            OptionSpec::new("parallelism", OptionType::PositiveInteger),
+            OptionSpec::new("policy", OptionType::OneOf(&["error", "ignore", "add"])),
+            OptionSpec::new("path", OptionType::String).required(),
+            OptionSpec::new("old_path", OptionType::String)
+                .deprecated(Deprecation::RenamedTo("path")),
+        ])
+    }
+
+    fn options(options: &[(&str, Value)]) -> Vec<SqlOption<Raw>> {
+        options
+            .iter()
+            .map(|(name, value)| SqlOption::Value {
+                name: Ident::new(*name),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), anyhow::Error> {
+        let schema = schema();
+
+        let mut opts = schema.validate(&options(&[
+            ("path", Value::String("/a".into())),
+            ("parallelism", Value::Number("2".into())),
+        ]))?;
+        assert_eq!(opts.take_bool("tail"), Some(false));
+        assert_eq!(opts.take_u64("parallelism"), Some(2));
+        assert_eq!(opts.take_string("policy"), None);
+        assert!(opts.ensure_consumed().is_err());
+
+        let err = schema
+            .validate(&options(&[("bogus", Value::Boolean(true))]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected parameters for CREATE THING: bogus \
+             (valid parameters are: parallelism, path, policy, tail)"
+        );
+
+        let err = schema
+            .validate(&options(&[("policy", Value::String("bogus".into()))]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "policy must be one of 'error', 'ignore', or 'add'"
+        );
+
+        let err = schema
+            .validate(&options(&[("parallelism", Value::Number("0".into()))]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "parallelism must be a positive integer");
+
+        let err = schema.validate(&options(&[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing required parameters for CREATE THING: path"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonicalize() -> Result<(), anyhow::Error> {
+        let schema = schema();
+
+        // Renamed options are accepted under their old names...
+        let old = options(&[("old_path", Value::String("/a".into()))]);
+        let mut opts = schema.validate(&old)?;
+        assert_eq!(opts.take_string("path"), Some("/a".into()));
+        opts.ensure_consumed()?;
+
+        // ...but are rendered under their new names, which plan identically.
+        let mut canonical = old.clone();
+        schema.canonicalize(&mut canonical);
+        assert_eq!(
+            canonical
+                .iter()
+                .map(|o| o.to_ast_string())
+                .collect::<Vec<_>>(),
+            vec!["path = '/a'"]
+        );
+        let mut opts = schema.validate(&canonical)?;
+        assert_eq!(opts.take_string("path"), Some("/a".into()));
+        opts.ensure_consumed()?;
+        Ok(())
+    }
+}
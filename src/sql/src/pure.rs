@@ -52,9 +52,15 @@ use crate::normalize;
 /// time to complete. As a result purification does *not* have access to a
 /// [`SessionCatalog`](crate::catalog::SessionCatalog), as that would require
 /// locking access to the catalog for an unbounded amount of time.
+///
+/// For the same reason, the contents of the secrets that the statement's
+/// `WITH` options name must be read in advance, and are passed as
+/// `secret_contents`, keyed by the configuration option that each defines.
+/// See [`kafka_util::extract_secret_config`].
 pub async fn purify_create_source(
     now: u64,
     aws_external_id: AwsExternalId,
+    secret_contents: BTreeMap<String, String>,
     mut stmt: CreateSourceStatement<Raw>,
 ) -> Result<CreateSourceStatement<Raw>, anyhow::Error> {
     let CreateSourceStatement {
@@ -86,6 +92,7 @@ pub async fn purify_create_source(
 
                     // Verify that the provided security options are valid and then test them.
                     config_options = kafka_util::extract_config(&mut with_options_map)?;
+                    config_options.extend(secret_contents);
                     let consumer = kafka_util::create_consumer(&broker, &topic, &config_options)
                         .await
                         .map_err(|e| {
@@ -370,11 +377,8 @@ async fn purify_csr_connector_proto(
         None => {
             let url: Url = url.parse()?;
             let kafka_options = kafka_util::extract_config(&mut normalize::options(with_options))?;
-            let ccsr_config = kafka_util::generate_ccsr_client_config(
-                url,
-                &kafka_options,
-                &mut normalize::options(&ccsr_options),
-            )?;
+            let ccsr_config =
+                kafka_util::generate_ccsr_client_config(url, &kafka_options, ccsr_options)?;
 
             let value =
                 compile_proto(&format!("{}-value", topic), ccsr_config.clone().build()?).await?;
@@ -422,11 +426,7 @@ async fn purify_csr_connector_avro(
         let url = url.parse()?;
 
         let ccsr_config = task::block_in_place(|| {
-            kafka_util::generate_ccsr_client_config(
-                url,
-                &connector_options,
-                &mut normalize::options(ccsr_options),
-            )
+            kafka_util::generate_ccsr_client_config(url, &connector_options, ccsr_options)
         })?;
 
         let Schema {
@@ -9,8 +9,9 @@
 
 use crate::ast::Expr;
 use crate::catalog::{
-    CatalogComputeInstance, CatalogConfig, CatalogDatabase, CatalogError, CatalogItem,
-    CatalogItemType, CatalogRole, CatalogSchema, CatalogTypeDetails, IdReference, SessionCatalog,
+    CatalogComputeInstance, CatalogConfig, CatalogDatabase, CatalogDroppedItem, CatalogError,
+    CatalogItem, CatalogItemType, CatalogRole, CatalogSchema, CatalogTypeDetails, IdReference,
+    SessionCatalog,
 };
 use crate::func::{Func, MZ_CATALOG_BUILTINS, MZ_INTERNAL_BUILTINS, PG_CATALOG_BUILTINS};
 use crate::names::{
@@ -240,6 +241,13 @@ impl SessionCatalog for TestCatalog {
         Err(CatalogError::UnknownFunction(partial_name.item.clone()))
     }
 
+    fn resolve_dropped_item(
+        &self,
+        partial_name: &PartialObjectName,
+    ) -> Result<&dyn CatalogDroppedItem, CatalogError> {
+        Err(CatalogError::UnknownItem(partial_name.item.clone()))
+    }
+
     fn resolve_compute_instance(
         &self,
         compute_instance_name: Option<&str>,
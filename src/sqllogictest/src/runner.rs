@@ -34,6 +34,7 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::ops;
 use std::path::Path;
 use std::str;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, bail};
@@ -42,11 +43,14 @@ use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use fallible_iterator::FallibleIterator;
 use lazy_static::lazy_static;
 use md5::{Digest, Md5};
+use mz_authenticator::TrustAuthenticator;
+use mz_coord::catalog::storage::{CatalogDurability, SqliteSynchronous};
 use mz_coord::PersistConfig;
 use mz_dataflow_types::sources::AwsExternalId;
 use mz_ore::metrics::MetricsRegistry;
 use mz_ore::now::SYSTEM_TIME;
 use mz_ore::task;
+use mz_secrets::DEFAULT_MAX_SECRET_SIZE;
 use postgres_protocol::types;
 use regex::Regex;
 use tempfile::TempDir;
@@ -553,17 +557,33 @@ impl Runner {
         let mz_config = materialized::Config {
             logging: None,
             timestamp_frequency: Duration::from_secs(1),
+            peek_concurrency_limits: Default::default(),
+            peek_queue_timeout: Duration::from_secs(30),
+            result_sort_memory_limit: None,
+            statistics_interval: None,
             logical_compaction_window: None,
             workers: config.workers,
             timely_worker: timely::WorkerConfig::default(),
             data_directory: temp_dir.path().to_path_buf(),
+            catalog_postgres_url: None,
+            catalog_stash: false,
+            catalog_sqlite_synchronous: SqliteSynchronous::default(),
+            catalog_durability: CatalogDurability::default(),
+            catalog_gid_block_size: 1,
+            catalog_encryption_key: None,
+            restore_catalog: None,
+            import_catalog: None,
+            dropped_object_retention: Duration::from_secs(24 * 60 * 60),
+            max_replication_slot_lag: None,
             storage: materialized::StorageConfig::Local,
             orchestrator: None,
             secrets_controller: None,
+            recreate_missing_secrets: false,
+            max_secret_size: DEFAULT_MAX_SECRET_SIZE,
             aws_external_id: AwsExternalId::NotProvided,
             listen_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
             tls: None,
-            frontegg: None,
+            authenticator: Arc::new(TrustAuthenticator),
             cors_allowed_origins: vec![],
             experimental_mode: true,
             disable_user_indexes: false,
@@ -574,6 +594,8 @@ impl Runner {
             persist: PersistConfig::disabled(),
             third_party_metrics_listen_addr: None,
             now: SYSTEM_TIME.clone(),
+            config_reload_tx: None,
+            max_connections: None,
         };
         let server = materialized::serve(mz_config).await?;
         let client = connect(&server).await;
@@ -193,6 +193,19 @@ pub trait Stash {
         Ok(())
     }
 
+    /// Atomically applies batches of updates to any number of collections.
+    ///
+    /// Each batch adds its entries to its collection, as if by
+    /// [`Stash::update_many`], then advances the collection's upper and since
+    /// frontiers, as if by [`Stash::seal`] and [`Stash::compact`]. See
+    /// [`AppendBatch`] for how to construct a batch.
+    ///
+    /// If this method returns `Ok`, every batch has been made durable.
+    /// Otherwise, none of the batches have been applied.
+    fn append<I>(&self, batches: I) -> Result<(), StashError>
+    where
+        I: IntoIterator<Item = AppendBatch>;
+
     /// Reports the current since frontier.
     fn since<K, V>(
         &self,
@@ -240,6 +253,67 @@ impl<K, V> Clone for StashCollection<K, V> {
 
 impl<K, V> Copy for StashCollection<K, V> {}
 
+impl<K, V> StashCollection<K, V>
+where
+    K: Codec,
+    V: Codec,
+{
+    /// Returns an empty batch of updates to this collection, which advances
+    /// the collection's upper frontier to `upper` and its since frontier to
+    /// `compact` when it is applied by [`Stash::append`].
+    pub fn make_batch(
+        &self,
+        upper: Antichain<Timestamp>,
+        compact: Antichain<Timestamp>,
+    ) -> AppendBatch {
+        AppendBatch {
+            collection_id: self.id,
+            upper,
+            compact,
+            entries: vec![],
+        }
+    }
+
+    /// Adds an entry to `batch`, which must have been made by
+    /// [`StashCollection::make_batch`] on this collection.
+    pub fn append_to_batch(
+        &self,
+        batch: &mut AppendBatch,
+        key: &K,
+        value: &V,
+        time: Timestamp,
+        diff: Diff,
+    ) {
+        assert_eq!(
+            batch.collection_id, self.id,
+            "batch belongs to a different collection"
+        );
+        let mut key_buf = vec![];
+        let mut value_buf = vec![];
+        key.encode(&mut key_buf);
+        value.encode(&mut value_buf);
+        batch.entries.push(((key_buf, value_buf), time, diff));
+    }
+}
+
+/// A batch of updates to a single [`StashCollection`], which is applied
+/// atomically with the batches of updates to other collections by
+/// [`Stash::append`].
+///
+/// The entries in a batch are subject to the same requirements as those
+/// passed to [`Stash::update_many`], and the frontiers to which the batch
+/// advances its collection to the same requirements as those passed to
+/// [`Stash::seal`] and [`Stash::compact`], where the since frontier is checked
+/// against the upper frontier to which the batch advances the collection.
+#[derive(Debug, Clone)]
+pub struct AppendBatch {
+    collection_id: Id,
+    upper: Antichain<Timestamp>,
+    compact: Antichain<Timestamp>,
+    /// The encoded entries.
+    entries: Vec<((Vec<u8>, Vec<u8>), Timestamp, Diff)>,
+}
+
 struct AntichainFormatter<'a, T>(&'a [T]);
 
 impl<T> fmt::Display for AntichainFormatter<'_, T>
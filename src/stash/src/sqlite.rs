@@ -22,7 +22,8 @@ use mz_persist_types::Codec;
 use timely::progress::frontier::AntichainRef;
 
 use crate::{
-    AntichainFormatter, Diff, Id, InternalStashError, Stash, StashCollection, StashError, Timestamp,
+    AntichainFormatter, AppendBatch, Diff, Id, InternalStashError, Stash, StashCollection,
+    StashError, Timestamp,
 };
 
 const APPLICATION_ID: i32 = 0x0872_e898; // chosen randomly
@@ -112,6 +113,56 @@ impl Sqlite {
         )?;
         Ok(Antichain::from_iter(upper))
     }
+
+    fn seal_tx(
+        &self,
+        tx: &Transaction,
+        collection_id: Id,
+        new_upper: &Antichain<Timestamp>,
+    ) -> Result<(), StashError> {
+        let upper = self.upper_tx(tx, collection_id)?;
+        if PartialOrder::less_than(new_upper, &upper) {
+            return Err(StashError::from(format!(
+                "seal request {} is less than the current upper frontier {}",
+                AntichainFormatter(new_upper),
+                AntichainFormatter(&upper),
+            )));
+        }
+        tx.prepare_cached("UPDATE uppers SET upper = $upper WHERE collection_id = $collection_id")?
+            .execute(
+                named_params! {"$upper": new_upper.as_option(), "$collection_id": collection_id},
+            )?;
+        Ok(())
+    }
+
+    fn compact_tx(
+        &self,
+        tx: &Transaction,
+        collection_id: Id,
+        new_since: &Antichain<Timestamp>,
+    ) -> Result<(), StashError> {
+        let since = self.since_tx(tx, collection_id)?;
+        let upper = self.upper_tx(tx, collection_id)?;
+        if PartialOrder::less_than(&upper, new_since) {
+            return Err(StashError::from(format!(
+                "compact request {} is greater than the current upper frontier {}",
+                AntichainFormatter(new_since),
+                AntichainFormatter(&upper)
+            )));
+        }
+        if PartialOrder::less_than(new_since, &since) {
+            return Err(StashError::from(format!(
+                "compact request {} is less than the current since frontier {}",
+                AntichainFormatter(new_since),
+                AntichainFormatter(&since)
+            )));
+        }
+        tx.prepare_cached("UPDATE sinces SET since = $since WHERE collection_id = $collection_id")?
+            .execute(
+                named_params! {"$since": new_since.as_option(), "$collection_id": collection_id},
+            )?;
+        Ok(())
+    }
 }
 
 impl Stash for Sqlite {
@@ -295,22 +346,9 @@ impl Stash for Sqlite {
     ) -> Result<(), StashError> {
         let mut conn = self.conn.lock().expect("lock poisoned");
         let tx = conn.transaction()?;
-        let mut update_stmt =
-            tx.prepare("UPDATE uppers SET upper = $upper WHERE collection_id = $collection_id")?;
         for (collection, new_upper) in seals {
-            let upper = self.upper_tx(&tx, collection.id)?;
-            if PartialOrder::less_than(new_upper, &upper) {
-                return Err(StashError::from(format!(
-                    "seal request {} is less than the current upper frontier {}",
-                    AntichainFormatter(new_upper),
-                    AntichainFormatter(&upper),
-                )));
-            }
-            update_stmt.execute(
-                named_params! {"$upper": new_upper.as_option(), "$collection_id": collection.id},
-            )?;
+            self.seal_tx(&tx, collection.id, new_upper)?;
         }
-        drop(update_stmt);
         tx.commit()?;
         Ok(())
     }
@@ -329,30 +367,9 @@ impl Stash for Sqlite {
     ) -> Result<(), StashError> {
         let mut conn = self.conn.lock().expect("lock poisoned");
         let tx = conn.transaction()?;
-        let mut compact_stmt =
-            tx.prepare("UPDATE sinces SET since = $since WHERE collection_id = $collection_id")?;
         for (collection, new_since) in compactions {
-            let since = self.since_tx(&tx, collection.id)?;
-            let upper = self.upper_tx(&tx, collection.id)?;
-            if PartialOrder::less_than(&upper, new_since) {
-                return Err(StashError::from(format!(
-                    "compact request {} is greater than the current upper frontier {}",
-                    AntichainFormatter(new_since),
-                    AntichainFormatter(&upper)
-                )));
-            }
-            if PartialOrder::less_than(new_since, &since) {
-                return Err(StashError::from(format!(
-                    "compact request {} is less than the current since frontier {}",
-                    AntichainFormatter(new_since),
-                    AntichainFormatter(&since)
-                )));
-            }
-            compact_stmt.execute(
-                named_params! {"$since": new_since.as_option(), "$collection_id": collection.id},
-            )?;
+            self.compact_tx(&tx, collection.id, new_since)?;
         }
-        drop(compact_stmt);
         tx.commit()?;
         Ok(())
     }
@@ -420,6 +437,42 @@ impl Stash for Sqlite {
         Ok(())
     }
 
+    fn append<I>(&self, batches: I) -> Result<(), StashError>
+    where
+        I: IntoIterator<Item = AppendBatch>,
+    {
+        let mut conn = self.conn.lock().expect("lock poisoned");
+        let tx = conn.transaction()?;
+        for batch in batches {
+            let upper = self.upper_tx(&tx, batch.collection_id)?;
+            let mut insert_stmt = tx.prepare_cached(
+                "INSERT INTO data (collection_id, key, value, time, diff)
+                 VALUES ($collection_id, $key, $value, $time, $diff)",
+            )?;
+            for ((key, value), time, diff) in batch.entries {
+                if !upper.less_equal(&time) {
+                    return Err(StashError::from(format!(
+                        "entry time {} is less than the current upper frontier {}",
+                        time,
+                        AntichainFormatter(&upper)
+                    )));
+                }
+                insert_stmt.execute(named_params! {
+                    "$collection_id": batch.collection_id,
+                    "$key": key,
+                    "$value": value,
+                    "$time": time,
+                    "$diff": diff,
+                })?;
+            }
+            drop(insert_stmt);
+            self.seal_tx(&tx, batch.collection_id, &batch.upper)?;
+            self.compact_tx(&tx, batch.collection_id, &batch.compact)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Reports the current since frontier.
     fn since<K, V>(
         &self,
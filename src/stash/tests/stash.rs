@@ -19,6 +19,13 @@ fn test_stash_sqlite() -> Result<(), anyhow::Error> {
     test_stash(conn)
 }
 
+#[test]
+fn test_stash_sqlite_append() -> Result<(), anyhow::Error> {
+    let file = NamedTempFile::new()?;
+    let conn = Sqlite::open(file.path())?;
+    test_append(conn)
+}
+
 fn test_stash<S: Stash>(mut stash: S) -> Result<(), anyhow::Error> {
     // Create an arrangement, write some data into it, then read it back.
     let orders = stash.collection::<String, String>("orders")?;
@@ -154,3 +161,67 @@ fn test_stash<S: Stash>(mut stash: S) -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+fn test_append<S: Stash>(stash: S) -> Result<(), anyhow::Error> {
+    // Append to two collections at once.
+    let orders = stash.collection::<String, String>("orders")?;
+    let other = stash.collection::<String, String>("other")?;
+    let mut orders_batch = orders.make_batch(Antichain::from_elem(2), Antichain::from_elem(1));
+    orders.append_to_batch(&mut orders_batch, &"widgets".into(), &"1".into(), 1, 1);
+    orders.append_to_batch(&mut orders_batch, &"wombats".into(), &"2".into(), 1, 1);
+    let mut other_batch = other.make_batch(Antichain::from_elem(2), Antichain::from_elem(2));
+    other.append_to_batch(&mut other_batch, &"foo".into(), &"bar".into(), 0, 1);
+    stash.append([orders_batch, other_batch])?;
+    assert_eq!(
+        stash.iter(orders)?,
+        &[
+            (("widgets".into(), "1".into()), 1, 1),
+            (("wombats".into(), "2".into()), 1, 1),
+        ]
+    );
+    assert_eq!(stash.iter(other)?, &[(("foo".into(), "bar".into()), 2, 1)]);
+    assert_eq!(stash.upper(orders)?, Antichain::from_elem(2));
+    assert_eq!(stash.since(orders)?, Antichain::from_elem(1));
+    assert_eq!(stash.upper(other)?, Antichain::from_elem(2));
+    assert_eq!(stash.since(other)?, Antichain::from_elem(2));
+
+    // A batch that cannot be applied prevents the application of all the
+    // batches it is appended with.
+    let mut orders_batch = orders.make_batch(Antichain::from_elem(3), Antichain::from_elem(3));
+    orders.append_to_batch(&mut orders_batch, &"wombats".into(), &"2".into(), 2, -1);
+    let mut other_batch = other.make_batch(Antichain::from_elem(3), Antichain::from_elem(3));
+    other.append_to_batch(&mut other_batch, &"foo".into(), &"baz".into(), 1, 1);
+    assert_eq!(
+        stash
+            .append([orders_batch, other_batch])
+            .unwrap_err()
+            .to_string(),
+        "stash error: entry time 1 is less than the current upper frontier {2}",
+    );
+    let orders_batch = orders.make_batch(Antichain::from_elem(3), Antichain::from_elem(3));
+    let other_batch = other.make_batch(Antichain::from_elem(1), Antichain::from_elem(2));
+    assert_eq!(
+        stash
+            .append([orders_batch, other_batch])
+            .unwrap_err()
+            .to_string(),
+        "stash error: seal request {1} is less than the current upper frontier {2}",
+    );
+    let orders_batch = orders.make_batch(Antichain::from_elem(3), Antichain::from_elem(4));
+    assert_eq!(
+        stash.append([orders_batch]).unwrap_err().to_string(),
+        "stash error: compact request {4} is greater than the current upper frontier {3}",
+    );
+    assert_eq!(
+        stash.iter(orders)?,
+        &[
+            (("widgets".into(), "1".into()), 1, 1),
+            (("wombats".into(), "2".into()), 1, 1),
+        ]
+    );
+    assert_eq!(stash.iter(other)?, &[(("foo".into(), "bar".into()), 2, 1)]);
+    assert_eq!(stash.upper(orders)?, Antichain::from_elem(2));
+    assert_eq!(stash.since(orders)?, Antichain::from_elem(1));
+
+    Ok(())
+}
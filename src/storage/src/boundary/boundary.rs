@@ -193,6 +193,11 @@ mod boundary_hook {
                     self.sources.insert(source.id, source.desc.clone());
                 }
             }
+            if let StorageCommand::AlterSources(sources) = &cmd {
+                for (id, desc) in sources {
+                    self.sources.insert(*id, desc.clone());
+                }
+            }
 
             self.client.send(cmd).await?;
             if !render_requests.is_empty() {
@@ -9,14 +9,18 @@
 
 use futures::executor::block_on;
 
+use mz_dataflow_types::sources::SchemaChangePolicy;
 use mz_dataflow_types::DecodeError;
 use mz_interchange::avro::Decoder;
 use mz_repr::Row;
 
+use crate::source::SchemaChange;
+
 #[derive(Debug)]
 pub struct AvroDecoderState {
     decoder: Decoder,
     events_success: i64,
+    schema_change_policy: SchemaChangePolicy,
 }
 
 impl AvroDecoderState {
@@ -26,6 +30,7 @@ impl AvroDecoderState {
         schema_registry_config: Option<mz_ccsr::ClientConfig>,
         debug_name: String,
         confluent_wire_format: bool,
+        schema_change_policy: SchemaChangePolicy,
     ) -> Result<Self, anyhow::Error> {
         Ok(AvroDecoderState {
             decoder: Decoder::new(
@@ -33,8 +38,10 @@ impl AvroDecoderState {
                 schema_registry_config,
                 debug_name,
                 confluent_wire_format,
+                schema_change_policy == SchemaChangePolicy::Error,
             )?,
             events_success: 0,
+            schema_change_policy,
         })
     }
 
@@ -50,4 +57,18 @@ impl AvroDecoderState {
             ))),
         }
     }
+
+    /// Returns the writer schemas encountered since the last call that add
+    /// fields to the reader schema.
+    pub fn take_schema_changes(&mut self) -> Vec<SchemaChange> {
+        self.decoder
+            .take_new_writer_fields()
+            .into_iter()
+            .map(|new| SchemaChange {
+                object: format!("Avro schema (id = {})", new.schema_id),
+                change: format!("added fields {}", new.fields.join(", ")),
+                action: self.schema_change_policy.as_str().into(),
+            })
+            .collect()
+    }
 }
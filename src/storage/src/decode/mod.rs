@@ -16,7 +16,7 @@ use differential_dataflow::Hashable;
 use differential_dataflow::{AsCollection, Collection};
 use futures::executor::block_on;
 use mz_avro::{AvroDeserializer, GeneralDeserializer};
-use mz_expr::PartitionId;
+use mz_expr::{PartitionId, SourceInstanceId};
 use mz_repr::MessagePayload;
 use timely::dataflow::channels::pact::{Exchange, Pipeline};
 use timely::dataflow::operators::Operator;
@@ -26,7 +26,7 @@ use timely::scheduling::SyncActivator;
 use mz_dataflow_types::{
     sources::{
         encoding::{AvroEncoding, AvroOcfEncoding, DataEncoding, RegexEncoding},
-        IncludedColumnSource, SourceEnvelope,
+        IncludedColumnSource, SchemaChangePolicy, SourceEnvelope,
     },
     DecodeError, LinearOperator,
 };
@@ -38,7 +38,8 @@ use tracing::error;
 use self::avro::AvroDecoderState;
 use self::csv::CsvDecoderState;
 use self::protobuf::ProtobufDecoderState;
-use crate::source::{DecodeResult, SourceOutput};
+use crate::source::{log_schema_changes, DecodeResult, SchemaChange, SourceOutput};
+use crate::Logger;
 use metrics::DecodeMetrics;
 
 mod avro;
@@ -53,7 +54,8 @@ pub fn decode_cdcv2<G: Scope<Timestamp = Timestamp>>(
     confluent_wire_format: bool,
 ) -> (Collection<G, Row, Diff>, Box<dyn Any>) {
     // We will have already checked validity of the schema by now, so this can't fail.
-    let mut resolver = ConfluentAvroResolver::new(schema, registry, confluent_wire_format).unwrap();
+    let mut resolver =
+        ConfluentAvroResolver::new(schema, registry, confluent_wire_format, false).unwrap();
     let channel = Rc::new(RefCell::new(VecDeque::new()));
     let activator: Rc<RefCell<Option<SyncActivator>>> = Rc::new(RefCell::new(None));
     let mut vector = Vec::new();
@@ -219,6 +221,14 @@ impl DataDecoder {
         }
     }
 
+    /// Returns the upstream schema changes observed since the last call.
+    pub fn take_schema_changes(&mut self) -> Vec<SchemaChange> {
+        match &mut self.inner {
+            DataDecoderInner::Avro(avro) => avro.take_schema_changes(),
+            _ => vec![],
+        }
+    }
+
     pub fn log_errors(&self, n: usize) {
         self.metrics.count_errors(&self.inner, n);
     }
@@ -243,12 +253,14 @@ fn get_decoder(
             schema,
             schema_registry_config,
             confluent_wire_format,
+            schema_change_policy,
         }) => {
             let state = avro::AvroDecoderState::new(
                 &schema,
                 schema_registry_config,
                 debug_name.to_string(),
                 confluent_wire_format,
+                schema_change_policy,
             )
             .expect("Failed to create avro decoder, even though we validated ccsr client creation in purification.");
             DataDecoder {
@@ -285,9 +297,14 @@ fn get_decoder(
             DataDecoder { inner, metrics }
         }
         DataEncoding::AvroOcf(AvroOcfEncoding { reader_schema }) => {
-            let state =
-                avro::AvroDecoderState::new(&reader_schema, None, debug_name.to_string(), false)
-                    .expect("Schema was verified to be correct during purification");
+            let state = avro::AvroDecoderState::new(
+                &reader_schema,
+                None,
+                debug_name.to_string(),
+                false,
+                SchemaChangePolicy::IgnoreNewColumns,
+            )
+            .expect("Schema was verified to be correct during purification");
             DataDecoder {
                 inner: DataDecoderInner::Avro(state),
                 metrics,
@@ -348,6 +365,8 @@ pub fn render_decode_delimited<G>(
     // `None`.
     operators: &mut Option<LinearOperator>,
     metrics: DecodeMetrics,
+    source_id: SourceInstanceId,
+    mut logger: Option<Logger>,
 ) -> (Stream<G, DecodeResult>, Option<Box<dyn Any>>)
 where
     G: Scope,
@@ -420,6 +439,11 @@ where
             if n_successes > 0 {
                 value_decoder.log_successes(n_successes);
             }
+            let mut schema_changes = value_decoder.take_schema_changes();
+            if let Some(key_decoder) = &mut key_decoder {
+                schema_changes.extend(key_decoder.take_schema_changes());
+            }
+            log_schema_changes(&mut logger, source_id, schema_changes);
         }
     });
     (results, None)
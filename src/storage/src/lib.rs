@@ -54,4 +54,30 @@ pub enum StorageEvent {
         /// Difference between the previous timestamp and current highest timestamp we've seen
         timestamp: i64,
     },
+    /// A change to the upstream schema observed by a source.
+    SourceSchemaChange {
+        /// Materialize source identifier.
+        source_id: SourceInstanceId,
+        /// The upstream object whose schema changed, e.g. a table or an Avro
+        /// record.
+        object: String,
+        /// A description of the change.
+        change: String,
+        /// The action taken according to the source's schema change policy.
+        action: String,
+    },
+    /// Tracks the records that an upsert source has processed.
+    UpsertStatistics {
+        /// Materialize source identifier.
+        source_id: SourceInstanceId,
+        /// Difference between the previous and current number of records that
+        /// replaced the value of their key, or that were superseded by a later
+        /// record for their key at the same time.
+        duplicate_keys: i64,
+        /// Difference between the previous and current number of tombstones.
+        tombstones: i64,
+        /// Difference between the previous and current number of tombstones
+        /// for keys that had no value.
+        late_retractions: i64,
+    },
 }
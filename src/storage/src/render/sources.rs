@@ -272,6 +272,8 @@ where
                 .get(&src_id)
                 .map(|history| history.clone());
             let source_name = format!("{}-{}", connector.name(), uid);
+            let decode_logger = materialized_logging.clone();
+            let upsert_logger = materialized_logging.clone();
             let source_config = SourceConfig {
                 name: source_name.clone(),
                 upstream_name: connector.upstream_name().map(ToOwned::to_owned),
@@ -392,6 +394,7 @@ where
                             schema,
                             schema_registry_config,
                             confluent_wire_format,
+                            ..
                         } = match value_encoding {
                             DataEncoding::Avro(enc) => enc,
                             _ => unreachable!("Attempted to create non-Avro CDCv2 source"),
@@ -421,6 +424,8 @@ where
                                 metadata_columns,
                                 &mut linear_operators,
                                 storage_state.decode_metrics.clone(),
+                                uid,
+                                decode_logger,
                             ),
                             SourceType::ByteStream(source) => render_decode(
                                 &source,
@@ -489,6 +494,8 @@ where
                                         }
                                     },
                                     upsert_envelope.clone(),
+                                    &storage_state.source_metrics,
+                                    upsert_logger,
                                 );
 
                                 // When persistence is enabled we need to seal up both the
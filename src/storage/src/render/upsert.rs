@@ -24,12 +24,16 @@ use mz_dataflow_types::{
     DataflowError, DecodeError, LinearOperator, SourceError, SourceErrorDetails,
 };
 use mz_expr::{EvalError, MirScalarExpr, SourceInstanceId};
+use mz_ore::metrics::{CounterVecExt, DeleteOnDropCounter};
 use mz_ore::result::ResultExt;
 use mz_persist::operators::upsert::{PersistentUpsert, PersistentUpsertConfig};
 use mz_repr::{Datum, Diff, Row, RowArena, Timestamp};
+use prometheus::core::AtomicU64;
 use tracing::error;
 
+use crate::source::metrics::SourceBaseMetrics;
 use crate::source::DecodeResult;
+use crate::{Logger, StorageEvent};
 use mz_timely_util::operator::StreamExt;
 
 #[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
@@ -45,6 +49,98 @@ struct UpsertSourceData {
     metadata: Row,
 }
 
+/// Per-worker statistics about the keys that an upsert operator has seen.
+///
+/// The statistics are exported both as Prometheus metrics and, if logging is
+/// enabled, to the `mz_source_upsert_statistics` introspection source.
+struct UpsertMetrics {
+    /// Updates that replaced the value for their key, or that were superseded
+    /// by a later update to their key at the same time.
+    duplicate_keys: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    /// Updates that deleted the value for their key.
+    tombstones: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    /// Deletions of keys that had no value.
+    late_retractions: DeleteOnDropCounter<'static, AtomicU64, Vec<String>>,
+    source_id: SourceInstanceId,
+    logger: Option<Logger>,
+    /// The statistics that have been logged, and the changes to them that
+    /// have not yet been logged, as `(duplicate_keys, tombstones,
+    /// late_retractions)`.
+    logged: (i64, i64, i64),
+    unlogged: (i64, i64, i64),
+}
+
+impl UpsertMetrics {
+    fn new(
+        base_metrics: &SourceBaseMetrics,
+        source_id: SourceInstanceId,
+        worker_id: usize,
+        logger: Option<Logger>,
+    ) -> Self {
+        let labels = &[source_id.to_string(), worker_id.to_string()];
+        let upsert = &base_metrics.upsert;
+        Self {
+            duplicate_keys: upsert
+                .duplicate_keys
+                .get_delete_on_drop_counter(labels.to_vec()),
+            tombstones: upsert
+                .tombstones
+                .get_delete_on_drop_counter(labels.to_vec()),
+            late_retractions: upsert
+                .late_retractions
+                .get_delete_on_drop_counter(labels.to_vec()),
+            source_id,
+            logger,
+            logged: (0, 0, 0),
+            unlogged: (0, 0, 0),
+        }
+    }
+
+    fn duplicate_key(&mut self) {
+        self.duplicate_keys.inc();
+        self.unlogged.0 += 1;
+    }
+
+    fn tombstone(&mut self) {
+        self.tombstones.inc();
+        self.unlogged.1 += 1;
+    }
+
+    fn late_retraction(&mut self) {
+        self.late_retractions.inc();
+        self.unlogged.2 += 1;
+    }
+
+    /// Logs the changes to the statistics since the last call.
+    fn log(&mut self) {
+        let (duplicate_keys, tombstones, late_retractions) = std::mem::take(&mut self.unlogged);
+        if (duplicate_keys, tombstones, late_retractions) == (0, 0, 0) {
+            return;
+        }
+        if let Some(logger) = &self.logger {
+            logger.log(StorageEvent::UpsertStatistics {
+                source_id: self.source_id,
+                duplicate_keys,
+                tombstones,
+                late_retractions,
+            });
+        }
+        self.logged.0 += duplicate_keys;
+        self.logged.1 += tombstones;
+        self.logged.2 += late_retractions;
+    }
+}
+
+impl Drop for UpsertMetrics {
+    fn drop(&mut self) {
+        // Retract the statistics from the introspection source, as the
+        // Prometheus metrics are deleted on drop.
+        let (duplicate_keys, tombstones, late_retractions) = self.logged;
+        self.unlogged = (-duplicate_keys, -tombstones, -late_retractions);
+        self.log();
+    }
+}
+
 /// Entrypoint to the upsert-specific transformations involved
 /// in rendering a stream that came from an upsert source.
 /// Upsert-specific operators are different from the rest of
@@ -67,6 +163,8 @@ pub(crate) fn upsert<G>(
         PersistentUpsertConfig<Result<Row, DecodeError>, Result<Row, DecodeError>>,
     >,
     upsert_envelope: UpsertEnvelope,
+    base_metrics: &SourceBaseMetrics,
+    logger: Option<Logger>,
 ) -> (
     Stream<G, (Row, Timestamp, Diff)>,
     Stream<G, (mz_dataflow_types::DataflowError, Timestamp, Diff)>,
@@ -161,6 +259,7 @@ where
                 as_of_frontier,
                 source_arity,
                 upsert_envelope,
+                UpsertMetrics::new(base_metrics, source_id, stream.scope().index(), logger),
             );
 
             let upsert_errs = operator::empty(&stream.scope());
@@ -305,6 +404,7 @@ fn upsert_core<G>(
     as_of_frontier: Antichain<Timestamp>,
     source_arity: usize,
     upsert_envelope: UpsertEnvelope,
+    mut metrics: UpsertMetrics,
 ) -> Stream<G, (Result<Row, DataflowError>, u64, Diff)>
 where
     G: Scope<Timestamp = Timestamp>,
@@ -364,6 +464,7 @@ where
 
                         match entry {
                             std::collections::hash_map::Entry::Occupied(mut e) => {
+                                metrics.duplicate_key();
                                 // If the time is equal, toss out the row with the
                                 // lower offset
                                 if e.get().position < new_position {
@@ -391,6 +492,7 @@ where
                             // we could produce and then remove the error from the output).
                             match key {
                                 Some(decoded_key) => {
+                                    let tombstone = data.value.is_none();
                                     let (decoded_key, decoded_value): (
                                         _,
                                         Result<_, DataflowError>,
@@ -510,6 +612,15 @@ where
                                         })
                                     };
 
+                                    if tombstone {
+                                        metrics.tombstone();
+                                        if old_value.is_none() {
+                                            metrics.late_retraction();
+                                        }
+                                    } else if old_value.is_some() {
+                                        metrics.duplicate_key();
+                                    }
+
                                     if let Some(old_value) = old_value {
                                         // Ensure we put the source in a permanently error'd state
                                         // than to keep on trucking with wrong results.
@@ -543,6 +654,7 @@ where
                 for time in removed_times {
                     to_send.remove(&time);
                 }
+                metrics.log();
             }
         },
     );
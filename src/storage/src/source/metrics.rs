@@ -80,6 +80,36 @@ impl KinesisMetrics {
     }
 }
 
+/// The base metrics set for the upsert operator.
+#[derive(Clone, Debug)]
+pub(crate) struct UpsertMetrics {
+    pub(crate) duplicate_keys: IntCounterVec,
+    pub(crate) tombstones: IntCounterVec,
+    pub(crate) late_retractions: IntCounterVec,
+}
+
+impl UpsertMetrics {
+    fn register_with(registry: &MetricsRegistry) -> Self {
+        Self {
+            duplicate_keys: registry.register(metric!(
+                name: "mz_upsert_duplicate_keys_total",
+                help: "The number of updates to a key that replaced a value for the key, including updates that were superseded by a later update to the key at the same time.",
+                var_labels: ["source_id", "worker_id"],
+            )),
+            tombstones: registry.register(metric!(
+                name: "mz_upsert_tombstones_total",
+                help: "The number of updates that deleted the value for their key.",
+                var_labels: ["source_id", "worker_id"],
+            )),
+            late_retractions: registry.register(metric!(
+                name: "mz_upsert_late_retractions_total",
+                help: "The number of updates that deleted the value for a key that had no value.",
+                var_labels: ["source_id", "worker_id"],
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct SourceSpecificMetrics {
     pub(super) operator_scheduled_counter: IntCounterVec,
@@ -214,6 +244,7 @@ pub struct SourceBaseMetrics {
 
     pub(crate) s3: S3Metrics,
     pub(crate) kinesis: KinesisMetrics,
+    pub(crate) upsert: UpsertMetrics,
 
     pub(crate) bytes_read: IntCounter,
 }
@@ -228,6 +259,7 @@ impl SourceBaseMetrics {
 
             s3: S3Metrics::register_with(registry),
             kinesis: KinesisMetrics::register_with(registry),
+            upsert: UpsertMetrics::register_with(registry),
 
             bytes_read: registry.register(metric!(
                 name: "mz_bytes_read_total",
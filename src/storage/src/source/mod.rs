@@ -612,6 +612,43 @@ enum MessageProcessing {
 type EventSender =
     mpsc::Sender<Event<Option<Timestamp>, Result<(Row, Timestamp, Diff), SourceError>>>;
 
+/// A change to the upstream schema observed by a source, as recorded in
+/// `mz_source_schema_changes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// The upstream object whose schema changed.
+    pub object: String,
+    /// A description of the change.
+    pub change: String,
+    /// The name of the schema change policy that was applied, or `error` if
+    /// the change is one that no policy can accommodate.
+    pub action: String,
+}
+
+impl SchemaChange {
+    fn into_event(self, source_id: SourceInstanceId) -> StorageEvent {
+        StorageEvent::SourceSchemaChange {
+            source_id,
+            object: self.object,
+            change: self.change,
+            action: self.action,
+        }
+    }
+}
+
+/// Logs `changes` to `logger`, if present.
+pub(crate) fn log_schema_changes(
+    logger: &mut Option<Logger>,
+    source_id: SourceInstanceId,
+    changes: Vec<SchemaChange>,
+) {
+    if let Some(logger) = logger.as_mut() {
+        for change in changes {
+            logger.log(change.into_event(source_id));
+        }
+    }
+}
+
 /// An active transaction at a particular point in time. An instance of this struct is provided to
 /// a source when calling start_tx() on its timestamper. This has the effect of freezing the
 /// timestamper clock while the data for the transaction is sent.
@@ -654,16 +691,23 @@ impl SourceTransaction<'_> {
 pub struct Timestamper {
     inner: Arc<RwLock<Timestamp>>,
     sender: EventSender,
+    schema_change_sender: mpsc::UnboundedSender<SchemaChange>,
     tick_duration: Duration,
     now: NowFn,
 }
 
 impl Timestamper {
-    fn new(sender: EventSender, tick_duration: Duration, now: NowFn) -> Self {
+    fn new(
+        sender: EventSender,
+        schema_change_sender: mpsc::UnboundedSender<SchemaChange>,
+        tick_duration: Duration,
+        now: NowFn,
+    ) -> Self {
         let ts = now();
         Self {
             inner: Arc::new(RwLock::new(ts)),
             sender,
+            schema_change_sender,
             tick_duration,
             now,
         }
@@ -689,6 +733,12 @@ impl Timestamper {
         self.start_tx().await.delete(row).await
     }
 
+    /// Records a change to the upstream schema in `mz_source_schema_changes`.
+    pub fn report_schema_change(&self, change: SchemaChange) {
+        // The receiver is only dropped when the source is shutting down.
+        let _ = self.schema_change_sender.send(change);
+    }
+
     /// Records an error. After this method is called the source will permanently be in an errored
     /// state
     async fn error(&self, err: SourceError) -> anyhow::Result<()> {
@@ -775,12 +825,13 @@ where
     } = config;
 
     let (tx, mut rx) = mpsc::channel(64);
+    let (schema_change_tx, mut schema_change_rx) = mpsc::unbounded_channel();
 
     if active {
         task::spawn(
             || format!("source_simple_timestamper:{}", id.source_id),
             async move {
-                let timestamper = Timestamper::new(tx, timestamp_frequency, now);
+                let timestamper = Timestamper::new(tx, schema_change_tx, timestamp_frequency, now);
                 let source = connector.start(&timestamper);
                 tokio::pin!(source);
 
@@ -807,6 +858,7 @@ where
         let activator = Arc::new(scope.sync_activator_for(&info.address[..]));
 
         let metrics_name = upstream_name.unwrap_or(name);
+        let mut schema_change_logger = logger.clone();
         let mut metrics = SourceMetrics::new(
             &base_metrics,
             &metrics_name,
@@ -829,6 +881,12 @@ where
             let waker = futures::task::waker_ref(&activator);
             let mut context = Context::from_waker(&waker);
 
+            let mut schema_changes = vec![];
+            while let Poll::Ready(Some(change)) = schema_change_rx.poll_recv(&mut context) {
+                schema_changes.push(change);
+            }
+            log_schema_changes(&mut schema_change_logger, id, schema_changes);
+
             while let Poll::Ready(item) = rx.poll_recv(&mut context) {
                 match item {
                     Some(Event::Progress(None)) => unreachable!(),
@@ -7,9 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::cmp;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt::{self, Write as _};
 use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, bail};
@@ -20,18 +24,23 @@ use mz_postgres_util::TableInfo;
 use postgres_protocol::message::backend::{
     LogicalReplicationMessage, ReplicationMessage, TupleData,
 };
-use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 use tokio::runtime::Handle;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinHandle;
 use tokio_postgres::error::{DbError, Severity, SqlState};
 use tokio_postgres::replication::LogicalReplicationStream;
 use tokio_postgres::types::PgLsn;
 use tokio_postgres::SimpleQueryMessage;
 use tracing::{error, info, warn};
 
-use crate::source::{SimpleSource, SourceError, SourceTransaction, Timestamper};
-use mz_dataflow_types::postgres_source::PostgresTable;
-use mz_dataflow_types::{sources::PostgresSourceConnector, SourceErrorDetails};
+use crate::source::{SchemaChange, SimpleSource, SourceError, SourceTransaction, Timestamper};
+use mz_dataflow_types::postgres_source::{PostgresColumn, PostgresTable};
+use mz_dataflow_types::sources::{PostgresSourceConnector, SchemaChangePolicy};
+use mz_dataflow_types::SourceErrorDetails;
 use mz_expr::SourceInstanceId;
+use mz_ore::cast::CastFrom;
+use mz_ore::task;
 use mz_repr::{Datum, Row};
 
 use self::metrics::PgSourceMetrics;
@@ -44,6 +53,14 @@ lazy_static! {
     static ref PG_EPOCH: SystemTime = UNIX_EPOCH + Duration::from_secs(946_684_800);
 }
 
+/// The number of times to attempt to copy a range of the initial snapshot before abandoning the
+/// snapshot.
+const SNAPSHOT_RANGE_ATTEMPTS: usize = 3;
+
+/// The number of copied rows that may be in flight between the copies of the ranges of the
+/// initial snapshot and the dataflow.
+const SNAPSHOT_CHANNEL_CAPACITY: usize = 1024;
+
 /// Information required to sync data from Postgres
 pub struct PostgresSourceReader {
     source_id: SourceInstanceId,
@@ -51,7 +68,14 @@ pub struct PostgresSourceReader {
     /// Our cursor into the WAL
     lsn: PgLsn,
     metrics: PgSourceMetrics,
+    /// The tables this source replicates, with the columns it produces for
+    /// each.
     source_tables: HashMap<u32, PostgresTable>,
+    /// The number of upstream columns in each table as of the last time its
+    /// new columns were reported as ignored.
+    ignored_columns: HashMap<u32, usize>,
+    /// Schema changes that have not yet been reported.
+    schema_changes: Vec<SchemaChange>,
 }
 
 trait ErrorExt {
@@ -150,25 +174,31 @@ impl PostgresSourceReader {
             connector,
             lsn: 0.into(),
             metrics: PgSourceMetrics::new(metrics, source_id),
+            ignored_columns: HashMap::new(),
+            schema_changes: vec![],
         }
     }
 
-    /// Validates that all expected tables exist in the publication tables and they have the same schema
-    fn validate_tables(&self, tables: Vec<TableInfo>) -> Result<(), anyhow::Error> {
-        let pub_tables: HashMap<u32, PostgresTable> = tables
+    /// Validates that all expected tables exist in the publication tables and that their schemas
+    /// are compatible with the source's schema change policy
+    fn validate_tables(&mut self, tables: Vec<TableInfo>) -> Result<(), anyhow::Error> {
+        let mut pub_tables: HashMap<u32, PostgresTable> = tables
             .into_iter()
             .map(|t| (t.rel_id, PostgresTable::from(t)))
             .collect();
-        for (id, schema) in self.source_tables.iter() {
-            match pub_tables.get(id) {
+        let ids: Vec<u32> = self.source_tables.keys().copied().collect();
+        for id in ids {
+            let schema = &self.source_tables[&id];
+            match pub_tables.remove(&id) {
                 Some(pub_schema) => {
-                    if pub_schema != schema {
+                    if pub_schema.name != schema.name || pub_schema.namespace != schema.namespace {
                         error!(
                             "Error validating table in publication. Expected: {:?} Actual: {:?}",
                             schema, pub_schema
                         );
                         bail!("Schema for table {} differs, recreate materialized source to use new schema", schema.name)
                     }
+                    self.reconcile_columns(id, pub_schema.columns, |a, b| a == b)?;
                 }
                 None => {
                     error!("publication missing table: {} with id {}", schema.name, id);
@@ -183,14 +213,108 @@ impl PostgresSourceReader {
         Ok(())
     }
 
+    /// Reconciles the upstream columns of the table with OID `rel_id` with the columns this
+    /// source produces for it.
+    ///
+    /// Columns appended to the upstream table are handled according to the source's schema
+    /// change policy. Any other difference, as determined by `same_column`, is an error.
+    fn reconcile_columns<F>(
+        &mut self,
+        rel_id: u32,
+        upstream: Vec<PostgresColumn>,
+        same_column: F,
+    ) -> Result<(), anyhow::Error>
+    where
+        F: Fn(&PostgresColumn, &PostgresColumn) -> bool,
+    {
+        let policy = self.connector.schema_change_policy;
+        let table = self
+            .source_tables
+            .get_mut(&rel_id)
+            .expect("reconciling unknown table");
+        let object = format!("{}.{}", table.namespace, table.name);
+        let known = table.columns.len();
+        if upstream.len() < known
+            || !table
+                .columns
+                .iter()
+                .zip(&upstream)
+                .all(|(c, u)| same_column(c, u))
+        {
+            error!(
+                "alter table error: name {}, oid {}, old_schema {:?}, new_schema {:?}",
+                table.name, rel_id, table.columns, upstream
+            );
+            self.schema_changes.push(SchemaChange {
+                object,
+                change: "altered existing columns".into(),
+                action: SchemaChangePolicy::Error.as_str().into(),
+            });
+            bail!(
+                "source table {} with oid {} has been altered",
+                table.name,
+                rel_id
+            );
+        }
+        if upstream.len() == known {
+            return Ok(());
+        }
+
+        let new_columns: Vec<_> = upstream[known..].iter().map(|c| c.name.as_str()).collect();
+        let change = SchemaChange {
+            object,
+            change: format!("added columns {}", new_columns.join(", ")),
+            action: policy.as_str().into(),
+        };
+        match policy {
+            SchemaChangePolicy::Error => {
+                error!(
+                    "alter table detected on {} with id {}",
+                    table.name, table.relation_id
+                );
+                self.schema_changes.push(change);
+                bail!(
+                    "source table {} with oid {} has been altered",
+                    table.name,
+                    rel_id
+                );
+            }
+            SchemaChangePolicy::IgnoreNewColumns => {
+                // Relation messages are resent whenever replication restarts, so only report the
+                // change the first time it is seen.
+                if self.ignored_columns.insert(rel_id, upstream.len()) != Some(upstream.len()) {
+                    info!(
+                        "ignoring new columns in {}: {}",
+                        change.object, change.change
+                    );
+                    self.schema_changes.push(change);
+                }
+            }
+            SchemaChangePolicy::AddNullableColumns => {
+                info!("adding new columns in {}: {}", change.object, change.change);
+                self.schema_changes.push(change);
+                table.columns.extend(upstream.into_iter().skip(known));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports any schema changes observed since the last call.
+    fn report_schema_changes(&mut self, timestamper: &Timestamper) {
+        for change in self.schema_changes.drain(..) {
+            timestamper.report_schema_change(change);
+        }
+    }
+
     /// Creates the replication slot and produces the initial snapshot of the data
     ///
     /// After the initial snapshot has been produced it returns the name of the created slot and
-    /// the LSN at which we should start the replication stream at.
-    async fn produce_snapshot<W: AsyncWrite + Unpin>(
+    /// the LSN at which we should start the replication stream at. Every row sent to the dataflow
+    /// is recorded in `log`, so that the snapshot can be reverted if it fails.
+    async fn produce_snapshot(
         &mut self,
         snapshot_tx: &mut SourceTransaction<'_>,
-        buffer: &mut W,
+        log: &mut SnapshotLog,
     ) -> Result<(), ReplicationError> {
         let client =
             try_recoverable!(mz_postgres_util::connect_replication(&self.connector.conn).await);
@@ -212,15 +336,11 @@ impl PostgresSourceReader {
         // Validate publication tables against the state snapshot
         try_fatal!(self.validate_tables(publication_tables));
 
-        // Start a transaction and immediately create a replication slot with the USE SNAPSHOT
-        // directive. This makes the starting point of the slot and the snapshot of the transaction
-        // identical.
-        client
-            .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
-            .await?;
-
+        // Create a replication slot and export the snapshot that corresponds to its starting
+        // point, so that the ranges of the snapshot can be copied over separate connections. The
+        // exported snapshot remains valid for as long as this connection is open and idle.
         let slot_query = format!(
-            r#"CREATE_REPLICATION_SLOT {:?} LOGICAL "pgoutput" USE_SNAPSHOT"#,
+            r#"CREATE_REPLICATION_SLOT {:?} LOGICAL "pgoutput" EXPORT_SNAPSHOT"#,
             &self.connector.slot_name
         );
         let slot_row = client
@@ -245,74 +365,194 @@ impl PostgresSourceReader {
         self.lsn = try_fatal!(consistent_point
             .parse()
             .or_else(|_| Err(anyhow!("invalid lsn"))));
-        for info in self.source_tables.values() {
-            let relation_id: Datum = (i32::try_from(info.relation_id).unwrap()).into();
-            let reader = client
-                .copy_out_simple(
-                    format!(
-                        "COPY {:?}.{:?} TO STDOUT (FORMAT TEXT, DELIMITER '\t')",
-                        info.namespace, info.name
-                    )
-                    .as_str(),
-                )
-                .await?;
+        let snapshot = try_recoverable!(slot_row
+            .get("snapshot_name")
+            .ok_or_else(|| anyhow!("missing expected column: `snapshot_name`")))
+        .to_string();
 
-            pin_mut!(reader);
-            let mut mz_row = Row::default();
-            // TODO: once tokio-stream is released with https://github.com/tokio-rs/tokio/pull/4502
-            //    we can convert this into a single `timeout(...)` call on the reader CopyOutStream
-            while let Some(b) = tokio::time::timeout(Duration::from_secs(30), reader.next())
-                .await?
-                .transpose()?
-            {
-                let mut packer = mz_row.packer();
-                packer.push(relation_id);
-                // Convert raw rows from COPY into repr:Row. Each Row is a relation_id
-                // and list of string-encoded values, e.g. Row{ 16391 , ["1", "2"] }
-                let parser = mz_pgcopy::CopyTextFormatParser::new(b.as_ref(), "\t", "\\N");
-
-                let mut raw_values = parser.iter_raw(info.columns.len() as i32);
-                try_fatal!(packer.push_list_with(|rp| -> Result<(), anyhow::Error> {
-                    while let Some(raw_value) = raw_values.next() {
-                        match raw_value? {
-                            Some(value) => rp.push(Datum::String(std::str::from_utf8(value)?)),
-                            None => rp.push(Datum::Null),
-                        }
-                    }
-                    Ok(())
-                }));
-                try_recoverable!(snapshot_tx.insert(mz_row.clone()).await);
-                try_fatal!(
-                    buffer
-                        .write_all(&try_fatal!(bincode::serialize(&mz_row)))
-                        .await
-                );
+        let ranges = self.snapshot_ranges().await?;
+        info!(
+            "producing replication snapshot for source {} in {} ranges with parallelism {}",
+            self.source_id,
+            ranges.len(),
+            self.connector.snapshot_parallelism
+        );
+        let ranges = Arc::new(ranges);
+        let permits = Arc::new(Semaphore::new(self.connector.snapshot_parallelism));
+        let (event_tx, mut event_rx) = mpsc::channel(SNAPSHOT_CHANNEL_CAPACITY);
+        let source_id = self.source_id;
+        let spawn_copy = |range: usize, delay: Duration| {
+            let conn = self.connector.conn.clone();
+            let snapshot = snapshot.clone();
+            let ranges = Arc::clone(&ranges);
+            let permits = Arc::clone(&permits);
+            let event_tx = event_tx.clone();
+            task::spawn(
+                || format!("postgres_snapshot_range:{}", source_id),
+                async move {
+                    tokio::time::sleep(delay).await;
+                    let _permit = permits.acquire_owned().await.expect("never closed");
+                    let res = copy_range(&conn, &snapshot, &ranges[range], range, &event_tx).await;
+                    let _ = event_tx.send(RangeEvent::Done(range, res)).await;
+                },
+            )
+        };
+        let mut copies = RangeCopies(
+            (0..ranges.len())
+                .map(|range| spawn_copy(range, Duration::ZERO))
+                .collect(),
+        );
+        // Rows are sent to the dataflow as they are copied. If the copy of a range fails, the
+        // rows it produced are retracted before the range is copied again, so each row is
+        // recorded only once, in the same log that reverts the snapshot as a whole.
+        let mut attempts = vec![1; ranges.len()];
+        let mut remaining = ranges.len();
+        while remaining > 0 {
+            match event_rx.recv().await.expect("sender held by this task") {
+                RangeEvent::Row(range, row) => {
+                    try_fatal!(log.record(range, &row).await);
+                    try_recoverable!(snapshot_tx.insert(row).await);
+                }
+                RangeEvent::Done(_, Ok(())) => remaining -= 1,
+                RangeEvent::Done(range, Err(ReplicationError::Recoverable(e)))
+                    if attempts[range] < SNAPSHOT_RANGE_ATTEMPTS =>
+                {
+                    warn!(
+                        "copying {} for source {} failed, retrying: {}",
+                        ranges[range], source_id, e
+                    );
+                    attempts[range] += 1;
+                    try_fatal!(log.revert_range(snapshot_tx, range).await);
+                    copies.0.push(spawn_copy(range, Duration::from_secs(3)));
+                }
+                RangeEvent::Done(_, Err(e)) => return Err(e),
             }
-
-            self.metrics.tables.inc();
         }
+        self.metrics
+            .tables
+            .set(u64::cast_from(self.source_tables.len()));
         self.metrics.lsn.set(self.lsn.into());
-        client.simple_query("COMMIT;").await?;
+        // Keep the exporting connection open until every range has been copied.
+        drop(client);
         Ok(())
     }
 
-    /// Reverts a failed snapshot by deleting any processed rows from the dataflow.
-    async fn revert_snapshot<R: Read + Seek>(
-        &self,
-        snapshot_tx: &mut SourceTransaction<'_>,
-        mut reader: R,
-    ) -> Result<(), anyhow::Error> {
-        tokio::task::block_in_place(|| -> Result<(), anyhow::Error> {
-            let len = reader.seek(SeekFrom::Current(0))?;
-            reader.seek(SeekFrom::Start(0))?;
-            let mut reader = reader.take(len);
-            let handle = Handle::current();
-            while reader.limit() > 0 {
-                let row = bincode::deserialize_from(&mut reader)?;
-                handle.block_on(snapshot_tx.delete(row))?;
+    /// Splits the tables of this source into the ranges to copy during the initial snapshot.
+    ///
+    /// Tables larger than the source's `snapshot_range_size` are split into ranges of about that
+    /// size. On Postgres 14 and later, tables are split into ranges of heap pages, the final one
+    /// unbounded so that it includes any pages added after the table's size was read. On earlier
+    /// versions, tables with a single-column primary key are split into ranges of the key, at
+    /// bounds taken from the key's histogram in the table's statistics. Other tables are copied
+    /// whole.
+    async fn snapshot_ranges(&self) -> Result<Vec<SnapshotRange>, ReplicationError> {
+        let client = try_recoverable!(mz_postgres_util::connect(&self.connector.conn).await);
+        let row = client
+            .query_one(
+                "SELECT current_setting('server_version_num')::int4, \
+                 current_setting('block_size')::int8",
+                &[],
+            )
+            .await?;
+        let server_version: i32 = row.get(0);
+        let block_size: i64 = row.get(1);
+        let range_bytes = i64::try_from(self.connector.snapshot_range_bytes).unwrap_or(i64::MAX);
+
+        let mut ranges = vec![];
+        for table in self.source_tables.values() {
+            let row = client
+                .query_one("SELECT pg_relation_size($1::oid)", &[&table.relation_id])
+                .await?;
+            let size: i64 = row.get(0);
+            let count = size / range_bytes + i64::from(size % range_bytes != 0);
+            if count <= 1 {
+                ranges.push(SnapshotRange::whole(table));
+                continue;
             }
-            Ok(())
-        })
+
+            if server_version >= 140_000 {
+                let pages = size / block_size;
+                let range_pages = cmp::max(range_bytes / block_size, 1);
+                let mut start = 0;
+                while start + range_pages < pages {
+                    ranges.push(SnapshotRange {
+                        table: table.clone(),
+                        bounds: RangeBounds::Pages(start, Some(start + range_pages)),
+                    });
+                    start += range_pages;
+                }
+                ranges.push(SnapshotRange {
+                    table: table.clone(),
+                    bounds: RangeBounds::Pages(start, None),
+                });
+                continue;
+            }
+
+            // Before Postgres 14, a scan restricted to a range of pages reads the entire table,
+            // so copying a table in ranges of pages would multiply rather than divide the work.
+            // A range of the primary key, by contrast, is read through the key's index.
+            let key = match table
+                .columns
+                .iter()
+                .filter(|c| c.primary_key)
+                .collect::<Vec<_>>()[..]
+            {
+                [key] => key.name.clone(),
+                _ => {
+                    ranges.push(SnapshotRange::whole(table));
+                    continue;
+                }
+            };
+            let row = client
+                .query_opt(
+                    "SELECT histogram_bounds::text::text[] FROM pg_stats \
+                     WHERE schemaname = $1 AND tablename = $2 AND attname = $3",
+                    &[&table.namespace, &table.name, &key],
+                )
+                .await?;
+            let histogram: Vec<String> = row
+                .and_then(|row| row.get::<_, Option<Vec<String>>>(0))
+                .unwrap_or_default();
+            // Each bucket of the histogram holds about the same number of rows, so evenly spaced
+            // bounds split the table into ranges of about the same size.
+            let count = cmp::min(
+                usize::try_from(count).unwrap_or(usize::MAX),
+                histogram.len(),
+            );
+            let mut splits: Vec<_> = (1..count)
+                .map(|i| histogram[i * histogram.len() / count].clone())
+                .collect();
+            splits.dedup();
+            if splits.is_empty() {
+                info!(
+                    "copying {}.{} for source {} whole: no statistics for column {}",
+                    table.namespace, table.name, self.source_id, key
+                );
+                ranges.push(SnapshotRange::whole(table));
+                continue;
+            }
+            let mut lower = None;
+            for split in splits {
+                ranges.push(SnapshotRange {
+                    table: table.clone(),
+                    bounds: RangeBounds::Key {
+                        column: key.clone(),
+                        lower: lower.take(),
+                        upper: Some(split.clone()),
+                    },
+                });
+                lower = Some(split);
+            }
+            ranges.push(SnapshotRange {
+                table: table.clone(),
+                bounds: RangeBounds::Key {
+                    column: key,
+                    lower,
+                    upper: None,
+                },
+            });
+        }
+        Ok(ranges)
     }
 
     /// Converts a Tuple received in the replication stream into a Row instance. The logical
@@ -321,6 +561,8 @@ impl PostgresSourceReader {
     ///
     /// The `old_tuple` argument can be used as a source of data to use when encountering unchanged
     /// TOAST values.
+    ///
+    /// Values for any upstream columns that this source ignores are dropped.
     fn row_from_tuple<'a, T>(&mut self, rel_id: u32, tuple_data: T) -> Result<Row, anyhow::Error>
     where
         T: IntoIterator<Item = &'a TupleData>,
@@ -328,10 +570,11 @@ impl PostgresSourceReader {
         let mut row = Row::default();
         let mut packer = row.packer();
 
+        let n_columns = self.source_tables[&rel_id].columns.len();
         let rel_id: Datum = (rel_id as i32).into();
         packer.push(rel_id);
         packer.push_list_with(move |packer| {
-            for val in tuple_data.into_iter() {
+            for val in tuple_data.into_iter().take(n_columns) {
                 let datum = match val {
                     TupleData::Null => Datum::Null,
                     TupleData::UnchangedToast => bail!(
@@ -483,18 +726,6 @@ impl PostgresSourceReader {
                             let rel_id = relation.rel_id();
                             match self.source_tables.get(&rel_id) {
                                 Some(source_table) => {
-                                    // Start with the cheapest check first, this will catch the majority of alters
-                                    if source_table.columns.len() != relation.columns().len() {
-                                        error!(
-                                            "alter table detected on {} with id {}",
-                                            source_table.name, source_table.relation_id
-                                        );
-                                        return Err(Fatal(anyhow!(
-                                            "source table {} with oid {} has been altered",
-                                            source_table.name,
-                                            source_table.relation_id
-                                        )));
-                                    }
                                     if source_table.name.ne(relation.name().unwrap())
                                         || source_table.namespace.ne(relation.namespace().unwrap())
                                     {
@@ -506,6 +737,18 @@ impl PostgresSourceReader {
                                             relation.namespace().unwrap(),
                                             relation.name().unwrap()
                                         );
+                                        self.schema_changes.push(SchemaChange {
+                                            object: format!(
+                                                "{}.{}",
+                                                source_table.namespace, source_table.name
+                                            ),
+                                            change: format!(
+                                                "renamed to {}.{}",
+                                                relation.namespace().unwrap(),
+                                                relation.name().unwrap()
+                                            ),
+                                            action: SchemaChangePolicy::Error.as_str().into(),
+                                        });
                                         return Err(Fatal(anyhow!(
                                             "source table {} with oid {} has been altered",
                                             source_table.name,
@@ -514,20 +757,27 @@ impl PostgresSourceReader {
                                     }
                                     // Relation messages do not include nullability/primary_key data
                                     // so we check the name, type_oid, and type_mod explicitly and error if any of them differ
-                                    if !source_table.columns.iter().zip(relation.columns()).all(
-                                        |(src, rel)| {
-                                            src.name == rel.name().unwrap()
-                                                && src.type_oid == rel.type_id()
-                                                && src.type_mod == rel.type_modifier()
-                                        },
-                                    ) {
-                                        error!("alter table error: name {}, oid {}, old_schema {:?}, new_schema {:?}", source_table.name, source_table.relation_id, source_table.columns, relation.columns());
-                                        return Err(Fatal(anyhow!(
-                                            "source table {} with oid {} has been altered",
-                                            source_table.name,
-                                            source_table.relation_id
-                                        )));
-                                    }
+                                    let upstream = relation
+                                        .columns()
+                                        .iter()
+                                        .map(|c| PostgresColumn {
+                                            name: c.name().unwrap().to_string(),
+                                            type_oid: c.type_id(),
+                                            type_mod: c.type_modifier(),
+                                            nullable: true,
+                                            primary_key: false,
+                                        })
+                                        .collect();
+                                    try_fatal!(self.reconcile_columns(
+                                        rel_id,
+                                        upstream,
+                                        |src, rel| {
+                                            src.name == rel.name
+                                                && src.type_oid == rel.type_oid
+                                                && src.type_mod == rel.type_mod
+                                        }
+                                    ));
+                                    self.report_schema_changes(timestamper);
                                 }
                                 // Ignore messages for tables we do not know about
                                 None => continue,
@@ -566,6 +816,254 @@ impl PostgresSourceReader {
     }
 }
 
+/// A portion of an upstream table to copy during the initial snapshot.
+struct SnapshotRange {
+    table: PostgresTable,
+    bounds: RangeBounds,
+}
+
+/// The rows of a table that a [`SnapshotRange`] covers.
+enum RangeBounds {
+    /// The entire table.
+    All,
+    /// The heap pages from an inclusive lower bound to an optional exclusive upper bound.
+    Pages(i64, Option<i64>),
+    /// The values of the named key column from an optional inclusive lower bound to an optional
+    /// exclusive upper bound, in their text form.
+    Key {
+        column: String,
+        lower: Option<String>,
+        upper: Option<String>,
+    },
+}
+
+impl SnapshotRange {
+    fn whole(table: &PostgresTable) -> SnapshotRange {
+        SnapshotRange {
+            table: table.clone(),
+            bounds: RangeBounds::All,
+        }
+    }
+
+    /// Returns the condition that selects the rows of the range, if it does not cover the
+    /// entire table.
+    fn filter(&self) -> Option<String> {
+        match &self.bounds {
+            RangeBounds::All => None,
+            RangeBounds::Pages(start, end) => {
+                let mut filter = format!("ctid >= '({},0)'::tid", start);
+                if let Some(end) = end {
+                    write!(filter, " AND ctid < '({},0)'::tid", end).unwrap();
+                }
+                Some(filter)
+            }
+            RangeBounds::Key {
+                column,
+                lower,
+                upper,
+            } => {
+                // Untyped literals are coerced to the type of the column they are compared to.
+                let literal = |value: &str| format!("'{}'", value.replace('\'', "''"));
+                let mut conditions = vec![];
+                if let Some(lower) = lower {
+                    conditions.push(format!("{:?} >= {}", column, literal(lower)));
+                }
+                if let Some(upper) = upper {
+                    conditions.push(format!("{:?} < {}", column, literal(upper)));
+                }
+                Some(conditions.join(" AND "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for SnapshotRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.table.namespace, self.table.name)?;
+        match &self.bounds {
+            RangeBounds::All => Ok(()),
+            RangeBounds::Pages(start, None) => write!(f, " pages [{}, ..)", start),
+            RangeBounds::Pages(start, Some(end)) => write!(f, " pages [{}, {})", start, end),
+            RangeBounds::Key {
+                column,
+                lower,
+                upper,
+            } => write!(
+                f,
+                " {} [{}, {})",
+                column,
+                lower.as_deref().unwrap_or(".."),
+                upper.as_deref().unwrap_or("..")
+            ),
+        }
+    }
+}
+
+/// An event in the copy of the range of the initial snapshot with the given index.
+enum RangeEvent {
+    /// A row of the range was copied.
+    Row(usize, Row),
+    /// The copy of the range finished.
+    Done(usize, Result<(), ReplicationError>),
+}
+
+/// The in-progress copies of the ranges of a snapshot, which are aborted if the snapshot is
+/// abandoned.
+struct RangeCopies(Vec<JoinHandle<()>>);
+
+impl Drop for RangeCopies {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+/// Copies `range`, the range with index `index`, as of the exported snapshot `snapshot`, sending
+/// each row to `event_tx`.
+async fn copy_range(
+    conn: &str,
+    snapshot: &str,
+    range: &SnapshotRange,
+    index: usize,
+    event_tx: &mpsc::Sender<RangeEvent>,
+) -> Result<(), ReplicationError> {
+    let client = try_recoverable!(mz_postgres_util::connect(conn).await);
+    client
+        .simple_query("BEGIN READ ONLY ISOLATION LEVEL REPEATABLE READ;")
+        .await?;
+    // Importing the snapshot fails if the connection that exported it has gone away, which the
+    // snapshot as a whole can recover from.
+    try_recoverable!(
+        client
+            .simple_query(&format!("SET TRANSACTION SNAPSHOT '{}';", snapshot))
+            .await
+    );
+
+    let info = &range.table;
+    let relation_id: Datum = (i32::try_from(info.relation_id).unwrap()).into();
+    // Name the columns explicitly, as the upstream table may have columns that this source
+    // ignores.
+    let columns = info
+        .columns
+        .iter()
+        .map(|c| format!("{:?}", c.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let query = match range.filter() {
+        None => format!(
+            "COPY {:?}.{:?} ({}) TO STDOUT (FORMAT TEXT, DELIMITER '\t')",
+            info.namespace, info.name, columns
+        ),
+        Some(filter) => format!(
+            "COPY (SELECT {} FROM {:?}.{:?} WHERE {}) TO STDOUT (FORMAT TEXT, DELIMITER '\t')",
+            columns, info.namespace, info.name, filter
+        ),
+    };
+    let reader = client.copy_out_simple(&query).await?;
+
+    pin_mut!(reader);
+    let mut mz_row = Row::default();
+    // TODO: once tokio-stream is released with https://github.com/tokio-rs/tokio/pull/4502
+    //    we can convert this into a single `timeout(...)` call on the reader CopyOutStream
+    while let Some(b) = tokio::time::timeout(Duration::from_secs(30), reader.next())
+        .await?
+        .transpose()?
+    {
+        let mut packer = mz_row.packer();
+        packer.push(relation_id);
+        // Convert raw rows from COPY into repr:Row. Each Row is a relation_id
+        // and list of string-encoded values, e.g. Row{ 16391 , ["1", "2"] }
+        let parser = mz_pgcopy::CopyTextFormatParser::new(b.as_ref(), "\t", "\\N");
+
+        let mut raw_values = parser.iter_raw(info.columns.len() as i32);
+        try_fatal!(packer.push_list_with(|rp| -> Result<(), anyhow::Error> {
+            while let Some(raw_value) = raw_values.next() {
+                match raw_value? {
+                    Some(value) => rp.push(Datum::String(std::str::from_utf8(value)?)),
+                    None => rp.push(Datum::Null),
+                }
+            }
+            Ok(())
+        }));
+        // The receiver is only gone once the snapshot has been abandoned.
+        if event_tx
+            .send(RangeEvent::Row(index, mz_row.clone()))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+    client.simple_query("COMMIT;").await?;
+    Ok(())
+}
+
+/// The rows of the initial snapshot that have been sent to the dataflow, by range, so that they
+/// can be retracted if the copy of a range, or the snapshot as a whole, fails.
+#[derive(Default)]
+struct SnapshotLog {
+    ranges: HashMap<usize, tokio::io::BufWriter<tokio::fs::File>>,
+}
+
+impl SnapshotLog {
+    /// Records that `row` of the range with index `range` was sent to the dataflow.
+    async fn record(&mut self, range: usize, row: &Row) -> Result<(), anyhow::Error> {
+        let writer = match self.ranges.entry(range) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(tokio::io::BufWriter::new(
+                tokio::fs::File::from_std(tempfile::tempfile()?),
+            )),
+        };
+        writer.write_all(&bincode::serialize(row)?).await?;
+        Ok(())
+    }
+
+    /// Retracts the rows recorded for the range with index `range`, and forgets them.
+    async fn revert_range(
+        &mut self,
+        snapshot_tx: &mut SourceTransaction<'_>,
+        range: usize,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(mut writer) = self.ranges.remove(&range) {
+            writer.flush().await?;
+            let reader = BufReader::new(writer.into_inner().into_std().await);
+            revert_rows(snapshot_tx, reader).await?;
+        }
+        Ok(())
+    }
+
+    /// Retracts every recorded row, and forgets them.
+    async fn revert(
+        &mut self,
+        snapshot_tx: &mut SourceTransaction<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let ranges: Vec<_> = self.ranges.keys().copied().collect();
+        for range in ranges {
+            self.revert_range(snapshot_tx, range).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Deletes the rows serialized into `reader`, up to its current position, from the dataflow.
+async fn revert_rows<R: Read + Seek>(
+    snapshot_tx: &mut SourceTransaction<'_>,
+    mut reader: R,
+) -> Result<(), anyhow::Error> {
+    tokio::task::block_in_place(|| -> Result<(), anyhow::Error> {
+        let len = reader.seek(SeekFrom::Current(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut reader = reader.take(len);
+        let handle = Handle::current();
+        while reader.limit() > 0 {
+            let row = bincode::deserialize_from(&mut reader)?;
+            handle.block_on(snapshot_tx.delete(row))?;
+        }
+        Ok(())
+    })
+}
+
 #[async_trait]
 impl SimpleSource for PostgresSourceReader {
     /// The top-level control of the state machine and retry logic
@@ -575,13 +1073,10 @@ impl SimpleSource for PostgresSourceReader {
         {
             let mut snapshot_tx = timestamper.start_tx().await;
             loop {
-                let file =
-                    tokio::fs::File::from_std(tempfile::tempfile().map_err(|e| SourceError {
-                        source_id: self.source_id,
-                        error: SourceErrorDetails::FileIO(e.to_string()),
-                    })?);
-                let mut writer = tokio::io::BufWriter::new(file);
-                match self.produce_snapshot(&mut snapshot_tx, &mut writer).await {
+                let mut log = SnapshotLog::default();
+                let res = self.produce_snapshot(&mut snapshot_tx, &mut log).await;
+                self.report_schema_changes(timestamper);
+                match res {
                     Ok(_) => {
                         info!(
                             "replication snapshot for source {} succeeded",
@@ -590,16 +1085,11 @@ impl SimpleSource for PostgresSourceReader {
                         break;
                     }
                     Err(ReplicationError::Recoverable(e)) => {
-                        writer.flush().await.map_err(|e| SourceError {
-                            source_id: self.source_id,
-                            error: SourceErrorDetails::Initialization(e.to_string()),
-                        })?;
                         warn!(
                             "replication snapshot for source {} failed, retrying: {}",
                             &self.source_id, e
                         );
-                        let reader = BufReader::new(writer.into_inner().into_std().await);
-                        self.revert_snapshot(&mut snapshot_tx, reader)
+                        log.revert(&mut snapshot_tx)
                             .await
                             .map_err(|e| SourceError {
                                 source_id: self.source_id,
@@ -620,7 +1110,9 @@ impl SimpleSource for PostgresSourceReader {
         }
 
         loop {
-            match self.produce_replication(timestamper).await {
+            let res = self.produce_replication(timestamper).await;
+            self.report_schema_changes(timestamper);
+            match res {
                 Err(ReplicationError::Recoverable(e)) => {
                     warn!(
                         "replication for source {} interrupted, retrying: {}",
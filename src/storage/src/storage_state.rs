@@ -229,6 +229,11 @@ impl<'a, A: Allocate, B: StorageCapture> ActiveStorageState<'a, A, B> {
                     );
                 }
             }
+            StorageCommand::AlterSources(sources) => {
+                for (id, desc) in sources {
+                    self.storage_state.source_descriptions.insert(id, desc);
+                }
+            }
             StorageCommand::RenderSources(sources) => self.build_storage_dataflow(sources),
             StorageCommand::AllowCompaction(list) => {
                 for (id, frontier) in list {
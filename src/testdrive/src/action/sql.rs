@@ -28,9 +28,9 @@ use mz_ore::now::NOW_ZERO;
 use mz_ore::retry::Retry;
 use mz_pgrepr::{Interval, Jsonb, Numeric};
 use mz_sql_parser::ast::{
-    CreateClusterStatement, CreateDatabaseStatement, CreateSchemaStatement, CreateSecretStatement,
-    CreateSourceStatement, CreateTableStatement, CreateViewStatement, Raw, Statement,
-    ViewDefinition,
+    CreateClusterStatement, CreateDatabaseStatement, CreatePublicationStatement,
+    CreateSchemaStatement, CreateSecretStatement, CreateSourceStatement, CreateTableStatement,
+    CreateTaskStatement, CreateViewStatement, Raw, Statement, ViewDefinition,
 };
 
 use crate::action::{Action, ControlFlow, State};
@@ -114,6 +114,20 @@ impl Action for SqlAction {
                 )
                 .await
             }
+            Statement::CreatePublication(CreatePublicationStatement { name, .. }) => {
+                self.try_drop(
+                    &mut state.pgclient,
+                    &format!("DROP PUBLICATION IF EXISTS {} CASCADE", name),
+                )
+                .await
+            }
+            Statement::CreateTask(CreateTaskStatement { name, .. }) => {
+                self.try_drop(
+                    &mut state.pgclient,
+                    &format!("DROP TASK IF EXISTS {} CASCADE", name),
+                )
+                .await
+            }
             _ => Ok(()),
         }
     }
@@ -131,8 +145,8 @@ impl Action for SqlAction {
             // DDL statements should always provide the expected result on the first try
             CreateDatabase(_) | CreateSchema(_) | CreateSource(_) | CreateSink(_)
             | CreateView(_) | CreateViews(_) | CreateTable(_) | CreateIndex(_) | CreateType(_)
-            | CreateRole(_) | AlterObjectRename(_) | AlterIndex(_) | Discard(_)
-            | DropDatabase(_) | DropObjects(_) | SetVariable(_) | ShowDatabases(_)
+            | CreateRole(_) | AlterObjectRename(_) | AlterIndex(_) | Comment(_) | Discard(_)
+            | DropDatabase(_) | DropObjects(_) | Undrop(_) | SetVariable(_) | ShowDatabases(_)
             | ShowObjects(_) | ShowIndexes(_) | ShowColumns(_) | ShowCreateView(_)
             | ShowCreateSource(_) | ShowCreateTable(_) | ShowCreateSink(_) | ShowCreateIndex(_)
             | ShowVariable(_) => false,
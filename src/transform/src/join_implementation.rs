@@ -18,7 +18,9 @@
 
 use std::collections::HashMap;
 
-use mz_expr::{JoinInputMapper, MapFilterProject, MirRelationExpr, MirScalarExpr, RECURSION_LIMIT};
+use mz_expr::{
+    Id, JoinInputMapper, MapFilterProject, MirRelationExpr, MirScalarExpr, RECURSION_LIMIT,
+};
 use mz_ore::stack::{CheckedRecursion, RecursionGuard};
 
 use self::index_map::IndexMap;
@@ -180,6 +182,16 @@ impl JoinImplementation {
                 });
             }
 
+            // Estimates of the number of rows in each input break ties between
+            // otherwise equally attractive orders. An estimate cannot be
+            // compared with the lack of one, so estimates are only used if
+            // every input has one.
+            let cardinalities = inputs
+                .iter()
+                .map(|input| estimate_cardinality(input, indexes))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_else(|| vec![0; inputs.len()]);
+
             // Determine if we can perform delta queries with the existing arrangements.
             // We could defer the execution if we are sure we know we want one input,
             // but we could imagine wanting the best from each and then comparing the two.
@@ -188,12 +200,14 @@ impl JoinImplementation {
                 &input_mapper,
                 &available_arrangements,
                 &unique_keys,
+                &cardinalities,
             );
             let differential_plan = differential::plan(
                 relation,
                 &input_mapper,
                 &available_arrangements,
                 &unique_keys,
+                &cardinalities,
             );
 
             *relation = delta_query_plan
@@ -203,10 +217,29 @@ impl JoinImplementation {
     }
 }
 
+/// Estimates the number of rows in a join input from the statistics of the
+/// global collection it reads, looking through any surrounding MFPs and
+/// arrangements.
+///
+/// Filters are ignored, so the estimate is an upper bound.
+fn estimate_cardinality(input: &MirRelationExpr, indexes: &IndexMap) -> Option<u64> {
+    let (_, input) = MapFilterProject::extract_non_errors_from_expr(input);
+    let input = match input {
+        MirRelationExpr::ArrangeBy { input, .. } => &**input,
+        input => input,
+    };
+    match input {
+        MirRelationExpr::Get {
+            id: Id::Global(id), ..
+        } => indexes.cardinality(*id),
+        _ => None,
+    }
+}
+
 mod index_map {
     use std::collections::HashMap;
 
-    use mz_expr::{Id, LocalId, MirScalarExpr};
+    use mz_expr::{GlobalId, Id, LocalId, MirScalarExpr};
 
     use crate::IndexOracle;
 
@@ -251,6 +284,12 @@ mod index_map {
                 ),
             }
         }
+
+        /// Returns an estimate of the number of rows in the specified global
+        /// collection, if one is available.
+        pub fn cardinality(&self, id: GlobalId) -> Option<u64> {
+            self.global.cardinality(id)
+        }
     }
 }
 
@@ -266,6 +305,7 @@ mod delta_queries {
         input_mapper: &JoinInputMapper,
         available: &[Vec<Vec<MirScalarExpr>>],
         unique_keys: &[Vec<Vec<usize>>],
+        cardinalities: &[u64],
     ) -> Option<MirRelationExpr> {
         let mut new_join = join.clone();
 
@@ -285,7 +325,13 @@ mod delta_queries {
             }
 
             // Determine a viable order for each relation, or return `None` if none found.
-            let orders = super::optimize_orders(equivalences, available, unique_keys, input_mapper);
+            let orders = super::optimize_orders(
+                equivalences,
+                available,
+                unique_keys,
+                cardinalities,
+                input_mapper,
+            );
 
             // A viable delta query requires that, for every order,
             // there is an arrangement for every input except for
@@ -334,6 +380,7 @@ mod differential {
         input_mapper: &JoinInputMapper,
         available: &[Vec<Vec<MirScalarExpr>>],
         unique_keys: &[Vec<Vec<usize>>],
+        cardinalities: &[u64],
     ) -> Option<MirRelationExpr> {
         let mut new_join = join.clone();
 
@@ -348,8 +395,13 @@ mod differential {
             // Important, we should choose something stable under re-ordering, to converge under fixed
             // point iteration; we choose to start with the first input optimizing our criteria, which
             // should remain stable even when promoted to the first position.
-            let mut orders =
-                super::optimize_orders(equivalences, available, unique_keys, input_mapper);
+            let mut orders = super::optimize_orders(
+                equivalences,
+                available,
+                unique_keys,
+                cardinalities,
+                input_mapper,
+            );
 
             // For differential join, it is not as important for the starting
             // input to have good characteristics because the other ones
@@ -529,9 +581,16 @@ fn optimize_orders(
     equivalences: &[Vec<MirScalarExpr>],
     available: &[Vec<Vec<MirScalarExpr>>],
     unique_keys: &[Vec<Vec<usize>>],
+    cardinalities: &[u64],
     input_mapper: &JoinInputMapper,
 ) -> Vec<Vec<(Characteristics, Vec<MirScalarExpr>, usize)>> {
-    let mut orderer = Orderer::new(equivalences, available, unique_keys, input_mapper);
+    let mut orderer = Orderer::new(
+        equivalences,
+        available,
+        unique_keys,
+        cardinalities,
+        input_mapper,
+    );
     (0..available.len())
         .map(move |i| orderer.optimize_order_for(i))
         .collect::<Vec<_>>()
@@ -542,7 +601,8 @@ fn optimize_orders(
 /// A candidate is described by a collection and a key, and may have various liabilities.
 /// Primarily, the candidate may risk substantial inflation of records, which is something
 /// that concerns us greatly. Additionally the candidate may be unarranged, and we would
-/// prefer candidates that do not require additional memory. Finally, we prefer lower id
+/// prefer candidates that do not require additional memory. Between otherwise equal
+/// candidates, we prefer those estimated to have fewer records. Finally, we prefer lower id
 /// collections in the interest of consistent tie-breaking.
 #[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone)]
 pub struct Characteristics {
@@ -552,16 +612,26 @@ pub struct Characteristics {
     key_length: usize,
     // Indicates that there will be no additional in-memory footprint.
     arranged: bool,
+    // An estimate of the number of records in the collection, or zero if there are no
+    // estimates. Fewer records mean fewer lookups in the arrangements that follow.
+    estimated_rows: std::cmp::Reverse<u64>,
     // We want to prefer input earlier in the input list, for stability of ordering.
     input: std::cmp::Reverse<usize>,
 }
 
 impl Characteristics {
-    fn new(unique_key: bool, key_length: usize, arranged: bool, input: usize) -> Self {
+    fn new(
+        unique_key: bool,
+        key_length: usize,
+        arranged: bool,
+        estimated_rows: u64,
+        input: usize,
+    ) -> Self {
         Self {
             unique_key,
             key_length,
             arranged,
+            estimated_rows: std::cmp::Reverse(estimated_rows),
             input: std::cmp::Reverse(input),
         }
     }
@@ -572,6 +642,7 @@ struct Orderer<'a> {
     equivalences: &'a [Vec<MirScalarExpr>],
     arrangements: &'a [Vec<Vec<MirScalarExpr>>],
     unique_keys: &'a [Vec<Vec<usize>>],
+    cardinalities: &'a [u64],
     input_mapper: &'a JoinInputMapper,
     reverse_equivalences: Vec<Vec<(usize, usize)>>,
     unique_arrangement: Vec<Vec<bool>>,
@@ -589,6 +660,7 @@ impl<'a> Orderer<'a> {
         equivalences: &'a [Vec<MirScalarExpr>],
         arrangements: &'a [Vec<Vec<MirScalarExpr>>],
         unique_keys: &'a [Vec<Vec<usize>>],
+        cardinalities: &'a [u64],
         input_mapper: &'a JoinInputMapper,
     ) -> Self {
         let inputs = arrangements.len();
@@ -623,6 +695,7 @@ impl<'a> Orderer<'a> {
             equivalences,
             arrangements,
             unique_keys,
+            cardinalities,
             input_mapper,
             reverse_equivalences,
             unique_arrangement,
@@ -659,13 +732,13 @@ impl<'a> Orderer<'a> {
             {
                 self.arrangement_active[input].push(pos);
                 self.priority_queue.push((
-                    Characteristics::new(is_unique, 0, true, input),
+                    Characteristics::new(is_unique, 0, true, self.cardinalities[input], input),
                     vec![],
                     input,
                 ));
             } else {
                 self.priority_queue.push((
-                    Characteristics::new(is_unique, 0, false, input),
+                    Characteristics::new(is_unique, 0, false, self.cardinalities[input], input),
                     vec![],
                     input,
                 ));
@@ -690,7 +763,11 @@ impl<'a> Orderer<'a> {
 
         // calculate characteristics of an arrangement, if any on the starting input
         // by default, there is no arrangement on the starting input
-        let mut start_tuple = (Characteristics::new(false, 0, false, start), vec![], start);
+        let mut start_tuple = (
+            Characteristics::new(false, 0, false, self.cardinalities[start], start),
+            vec![],
+            start,
+        );
         // use an arrangement if there exists one that lines up with the keys of
         // the second input
         if let Some((_, key, second)) = self.order.get(0) {
@@ -712,7 +789,13 @@ impl<'a> Orderer<'a> {
                 {
                     let is_unique = self.unique_arrangement[start][pos];
                     start_tuple = (
-                        Characteristics::new(is_unique, candidate_start_key.len(), true, start),
+                        Characteristics::new(
+                            is_unique,
+                            candidate_start_key.len(),
+                            true,
+                            self.cardinalities[start],
+                            start,
+                        ),
                         candidate_start_key,
                         start,
                     );
@@ -784,6 +867,7 @@ impl<'a> Orderer<'a> {
                                                     is_unique,
                                                     keys.len(),
                                                     true,
+                                                    self.cardinalities[rel],
                                                     rel,
                                                 ),
                                                 keys.clone(),
@@ -802,6 +886,7 @@ impl<'a> Orderer<'a> {
                                         is_unique,
                                         self.bound[rel].len(),
                                         false,
+                                        self.cardinalities[rel],
                                         rel,
                                     ),
                                     self.bound[rel].clone(),
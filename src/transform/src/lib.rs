@@ -120,6 +120,15 @@ pub trait IndexOracle: fmt::Debug {
     // better somehow? Making the entire optimizer generic over this iterator
     // type doesn't presently seem worthwhile.
     fn indexes_on(&self, id: GlobalId) -> Box<dyn Iterator<Item = &[MirScalarExpr]> + '_>;
+
+    /// Returns an estimate of the number of rows in the identified collection,
+    /// if statistics about the collection have been collected.
+    ///
+    /// Estimates may be stale, and so are only suitable for choosing between
+    /// plans that are otherwise equally attractive.
+    fn cardinality(&self, _id: GlobalId) -> Option<u64> {
+        None
+    }
 }
 
 /// An [`IndexOracle`] that knows about no indexes.
@@ -63,6 +63,20 @@ def workflow_github_8021(c: Composition) -> None:
     c.kill("materialized")
 
 
+def workflow_index_compaction_window(c: Composition) -> None:
+    c.up("materialized")
+    c.wait_for_materialized("materialized")
+    c.run("testdrive", "index-compaction-window-before.td")
+    c.kill("materialized")
+
+    # Ensure the compaction windows were persisted
+    c.up("materialized")
+    c.wait_for_materialized("materialized")
+    c.run("testdrive_no_reset", "index-compaction-window-after.td")
+    c.kill("materialized")
+
+
 def workflow_default(c: Composition) -> None:
     workflow_disable_user_indexes(c)
     workflow_github_8021(c)
+    workflow_index_compaction_window(c)